@@ -5,11 +5,15 @@
 use serde::{Deserialize, Serialize};
 use tauri::State;
 
+use cowork_core::completion::DEFAULT_COMPLETION_LIMIT;
+use cowork_core::palette::PaletteItem;
 use cowork_core::prompt::{
-    AgentInfo, CommandInfo, ComponentRegistry, PluginInfo, RegistrySummary, SkillInfo,
+    AgentInfo, CommandInfo, ComponentRegistry, PluginInfo, RegistrySummary, SkillInfo, TemplateVars,
 };
-use cowork_core::provider::{catalog, create_provider_with_settings, ChatMessage};
-use cowork_core::ApprovalLevel;
+use cowork_core::provider::{
+    catalog, create_provider_with_settings, probe_provider, provider_circuits, ProviderStatus, TestReport, PROBE_TIMEOUT,
+};
+use cowork_core::{ApiKeyStorage, ApprovalLevel};
 
 use crate::state::{AppState, ProviderSettings, Settings};
 
@@ -42,10 +46,15 @@ pub async fn update_settings(
                 .to_string()
         });
 
+    // A key kept in the keychain is written there below, never to the config
+    let use_keyring = settings.provider.api_key_storage == ApiKeyStorage::Keyring;
     if let Some(provider) = config.providers.get_mut(provider_name) {
         provider.model = model.clone();
-        if let Some(key) = &settings.provider.api_key {
+        if let Some(key) = &settings.provider.api_key
+            && !use_keyring
+        {
             provider.api_key = Some(key.clone());
+            provider.api_key_storage = ApiKeyStorage::Config;
         }
         provider.base_url = settings.provider.base_url.clone();
     } else {
@@ -55,9 +64,9 @@ pub async fn update_settings(
             cowork_core::config::ProviderConfig {
                 provider_type: provider_name.clone(),
                 model,
-                api_key: settings.provider.api_key.clone(),
+                api_key: settings.provider.api_key.clone().filter(|_| !use_keyring),
                 base_url: settings.provider.base_url.clone(),
-                model_tiers: None,
+                ..cowork_core::config::ProviderConfig::for_provider(provider_name)
             },
         );
     }
@@ -79,6 +88,22 @@ pub async fn update_settings(
             .cloned();
     }
 
+    // New sessions start with the tool profile; open ones keep theirs
+    if let Some(tools) = &settings.tools {
+        config.tools.resolve(&tools.profile).map_err(|e| e.to_string())?;
+        config.tools.profile = tools.profile.clone();
+    }
+
+    if use_keyring {
+        let key = settings.provider.api_key.as_deref().filter(|key| !key.is_empty());
+        let stored = key.and_then(|_| cowork_core::credentials::read_api_key(provider_name).ok().flatten());
+        if let Some(key) = key
+            && stored.as_deref() != Some(key)
+        {
+            cm.set_keyring_api_key(provider_name, key).map_err(|e| e.to_string())?;
+        }
+    }
+
     Ok(())
 }
 
@@ -113,6 +138,7 @@ pub async fn get_provider_config(
             api_key: provider.get_api_key(),
             model: Some(provider.model.clone()),
             base_url: provider.base_url.clone(),
+            api_key_storage: provider.api_key_storage,
         })
     } else {
         // Return defaults for unconfigured provider
@@ -125,29 +151,70 @@ pub async fn get_provider_config(
                     .to_string(),
             ),
             base_url: None,
+            api_key_storage: ApiKeyStorage::default(),
         })
     }
 }
 
-/// API test result matching frontend expectations
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ApiTestResult {
-    pub success: bool,
-    pub message: String,
+/// Whether API keys can be kept in the OS keychain here
+#[tauri::command]
+pub async fn keyring_available() -> Result<bool, String> {
+    Ok(tokio::task::spawn_blocking(cowork_core::credentials::keyring_available)
+        .await
+        .unwrap_or(false))
+}
+
+/// Store a provider's API key in the OS keychain, removing any plain-text
+/// copy from the config, and save the config
+#[tauri::command]
+pub async fn set_keyring_api_key(
+    provider: String,
+    api_key: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let mut cm = state.config_manager.write();
+    cm.set_keyring_api_key(&provider, &api_key).map_err(|e| e.to_string())?;
+    cm.save().map_err(|e| e.to_string())
+}
+
+/// Delete a provider's API key from the OS keychain and save the config;
+/// returns whether there was one
+#[tauri::command]
+pub async fn remove_keyring_api_key(provider: String, state: State<'_, AppState>) -> Result<bool, String> {
+    let mut cm = state.config_manager.write();
+    let removed = cm.remove_keyring_api_key(&provider).map_err(|e| e.to_string())?;
+    cm.save().map_err(|e| e.to_string())?;
+    Ok(removed)
+}
+
+/// Move a provider's plain-text API key into the OS keychain, blanking it in
+/// the config; returns false if there was none to move
+#[tauri::command]
+pub async fn migrate_api_key_to_keyring(provider: String, state: State<'_, AppState>) -> Result<bool, String> {
+    let mut cm = state.config_manager.write();
+    let moved = cm.migrate_api_key_to_keyring(&provider).map_err(|e| e.to_string())?;
+    if moved {
+        cm.save().map_err(|e| e.to_string())?;
+    }
+    Ok(moved)
 }
 
-/// Test the API connection with current settings
+/// Probe the provider and model with the given settings
+///
+/// Checks the key, tool calling, streaming and the context limit (see
+/// `provider::probe`), so the settings can warn before a model that can't
+/// call tools is saved.
 #[tauri::command]
 pub async fn test_api_connection(
     provider_type: String,
     api_key: String,
     model: Option<String>,
-) -> Result<ApiTestResult, String> {
+) -> Result<TestReport, String> {
     // Validate provider exists in catalog
     if catalog::get(&provider_type).is_none() {
-        return Ok(ApiTestResult {
-            success: false,
-            message: format!("Unknown provider: {}", provider_type),
+        return Ok(TestReport {
+            error: Some(format!("Unknown provider: {}", provider_type)),
+            ..Default::default()
         });
     }
 
@@ -163,28 +230,35 @@ pub async fn test_api_connection(
     let provider = match create_provider_with_settings(&provider_type, &api_key, &model_id) {
         Ok(p) => p,
         Err(e) => {
-            return Ok(ApiTestResult {
-                success: false,
-                message: format!("Failed to create provider: {}", e),
+            return Ok(TestReport {
+                error: Some(format!("Failed to create provider: {}", e)),
+                ..Default::default()
             });
         }
     };
 
-    // Try a simple chat
-    let messages = vec![ChatMessage::user("Say 'hello' and nothing else.")];
-
-    match provider.chat(messages, None).await {
-        Ok(response) => Ok(ApiTestResult {
-            success: true,
-            message: response
-                .content
-                .unwrap_or_else(|| "Connected successfully".to_string()),
-        }),
-        Err(e) => Ok(ApiTestResult {
-            success: false,
-            message: e.to_string(),
-        }),
+    Ok(probe_provider(&provider, None, PROBE_TIMEOUT).await)
+}
+
+/// Get the circuit of each configured provider
+///
+/// A provider that keeps timing out or failing with server errors is marked
+/// unavailable for a cool-down (see `provider::circuit`); a successful
+/// `test_api_connection` marks it available again.
+#[tauri::command]
+pub async fn get_provider_status(state: State<'_, AppState>) -> Result<Vec<ProviderStatus>, String> {
+    let cm = state.config_manager.read();
+    let config = cm.config();
+    let circuits = provider_circuits();
+
+    let mut statuses: Vec<ProviderStatus> = config.providers.keys().map(|id| circuits.status(id)).collect();
+    for status in circuits.statuses() {
+        if !statuses.iter().any(|s| s.provider_id == status.provider_id) {
+            statuses.push(status);
+        }
     }
+    statuses.sort_by(|a, b| a.provider_id.cmp(&b.provider_id));
+    Ok(statuses)
 }
 
 /// Check if initial setup is complete
@@ -262,19 +336,23 @@ pub async fn fetch_provider_models(provider_type: String) -> Result<Vec<ModelInf
 // ================== Component Registry Commands ==================
 
 /// Get a summary of all registered components
+///
+/// Agents carry their assembled system prompts, as `/agents show` prints them.
 #[tauri::command]
 pub async fn get_component_summary(state: State<'_, AppState>) -> Result<RegistrySummary, String> {
     let registry = ComponentRegistry::for_workspace(&state.workspace_path)
         .map_err(|e| e.to_string())?;
-    Ok(registry.summary())
+    Ok(registry.summary_with_prompts(&TemplateVars::gather(&state.workspace_path)))
 }
 
 /// List all registered agents
+///
+/// Each agent's `builtin` flag separates Cowork's agents from user-defined ones.
 #[tauri::command]
 pub async fn list_agents(state: State<'_, AppState>) -> Result<Vec<AgentInfo>, String> {
     let registry = ComponentRegistry::for_workspace(&state.workspace_path)
         .map_err(|e| e.to_string())?;
-    Ok(registry.summary().agents)
+    Ok(registry.summary_with_prompts(&TemplateVars::gather(&state.workspace_path)).agents)
 }
 
 /// List all registered commands
@@ -293,6 +371,38 @@ pub async fn list_skills(state: State<'_, AppState>) -> Result<Vec<SkillInfo>, S
     Ok(registry.summary().skills)
 }
 
+/// Everything the command palette offers: skills, slash commands, tools,
+/// agents and sessions (see `cowork_core::palette`)
+///
+/// Tools and agents the session's tool profile leaves out are marked
+/// unavailable.
+#[tauri::command]
+pub async fn get_palette_items(
+    session_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<PaletteItem>, String> {
+    let session_id = session_id.unwrap_or_else(|| "default".to_string());
+    let profile = state.session_manager.tool_profile(&session_id);
+    let sessions = state.session_manager.session_summaries();
+    let cm = state.config_manager.read();
+    Ok(state.palette.items(cm.config(), &cm.policy(), &profile, &sessions))
+}
+
+/// Workspace files completing an `@` mention in the composer, best first
+/// (see `cowork_core::completion`)
+#[tauri::command]
+pub async fn complete_workspace_paths(
+    prefix: String,
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, String> {
+    let completer = state.path_completer.clone();
+    let limit = limit.unwrap_or(DEFAULT_COMPLETION_LIMIT);
+    tokio::task::spawn_blocking(move || completer.complete_paths(&prefix, limit))
+        .await
+        .map_err(|e| e.to_string())
+}
+
 /// List all registered plugins
 #[tauri::command]
 pub async fn list_plugins(state: State<'_, AppState>) -> Result<Vec<PluginInfo>, String> {
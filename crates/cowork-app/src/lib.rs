@@ -12,13 +12,18 @@ use std::time::Duration;
 use tauri::Manager;
 use parking_lot::RwLock;
 
-use cowork_core::session::{OutputReceiver, SessionManager, SessionOutput};
+use cowork_core::notifications::{Notification, NotificationKind, NotificationTracker};
+use cowork_core::session::{OutputReceiver, SequencedOutput, SessionManager};
+use cowork_core::tools::interaction::Clipboard;
 use cowork_core::ConfigManager;
 use state::AppState;
 
 const REPO_OWNER: &str = "szguoxz";
 const REPO_NAME: &str = "cowork";
 
+/// How often idle sessions are checked for suspension
+const IDLE_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
 /// True if built by GitHub CI, false for local builds.
 const IS_CI_BUILD: bool = option_env!("GITHUB_ACTIONS").is_some();
 
@@ -32,16 +37,10 @@ fn check_for_update_background() -> Option<String> {
 
     let current = env!("CARGO_PKG_VERSION");
 
-    let releases = self_update::backends::github::ReleaseList::configure()
-        .repo_owner(REPO_OWNER)
-        .repo_name(REPO_NAME)
-        .build()
-        .ok()?
-        .fetch()
-        .ok()?;
+    let releases = cowork_core::update::fetch_releases(REPO_OWNER, REPO_NAME).ok()?;
 
     let latest = releases.first()?;
-    let latest_version = latest.version.trim_start_matches('v');
+    let latest_version = latest.version();
 
     if self_update::version::bump_is_greater(current, latest_version).unwrap_or(false) {
         Some(latest_version.to_string())
@@ -56,28 +55,48 @@ fn check_for_update_background() -> Option<String> {
 pub fn init_state(
     workspace_path: std::path::PathBuf,
     config_manager: ConfigManager,
+    clipboard: Arc<dyn Clipboard>,
 ) -> (AppState, OutputReceiver) {
     // Wrap config manager in Arc<RwLock> for shared access
     let config_manager = Arc::new(RwLock::new(config_manager));
 
     // Create session manager - reads config from disk for each new session
-    let (session_manager, output_rx) = SessionManager::new(workspace_path.clone());
+    let (mut session_manager, output_rx) = SessionManager::new(workspace_path.clone());
+    session_manager.set_clipboard(clipboard);
 
     let state = AppState {
+        palette: Arc::new(cowork_core::palette::Palette::new(workspace_path.clone())),
+        path_completer: Arc::new(cowork_core::completion::PathCompleter::new(workspace_path.clone())),
         workspace_path,
         config_manager,
         session_manager: Arc::new(session_manager),
+        notified_session: Arc::new(parking_lot::Mutex::new(None)),
     };
 
     (state, output_rx)
 }
 
+/// Periodically suspend sessions idle past `[limits] idle_suspend_mins`
+fn spawn_idle_sweep(session_manager: Arc<SessionManager>) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(IDLE_SWEEP_INTERVAL);
+        loop {
+            interval.tick().await;
+            let suspended = session_manager.suspend_idle_sessions().await;
+            if !suspended.is_empty() {
+                tracing::info!("Suspended idle sessions: {}", suspended.join(", "));
+            }
+        }
+    });
+}
+
 /// Spawn the output handler that forwards session outputs to the frontend
 fn spawn_output_handler(app_handle: tauri::AppHandle, mut output_rx: OutputReceiver) {
     use tauri::Emitter;
 
     tauri::async_runtime::spawn(async move {
         tracing::info!("Session output handler started");
+        let mut notifications = NotificationTracker::new();
 
         while let Some((session_id, output)) = output_rx.recv().await {
             // Warn if session_id looks like a subagent UUID (should be forwarded with parent_id)
@@ -96,17 +115,22 @@ fn spawn_output_handler(app_handle: tauri::AppHandle, mut output_rx: OutputRecei
                 );
             }
 
+            // Number the output so a reloaded frontend can tell what it missed
+            let state = app_handle.state::<AppState>();
+            let sequenced = state.session_manager.record_output(&session_id, output);
+            let output = sequenced.output.as_ref();
+
             // Emit as a tagged event with session ID
             #[derive(serde::Serialize)]
-            struct SessionEvent {
+            struct SessionEvent<'a> {
                 session_id: String,
                 #[serde(flatten)]
-                output: SessionOutput,
+                output: &'a SequencedOutput,
             }
 
             let event = SessionEvent {
                 session_id: session_id.clone(),
-                output: output.clone(),
+                output: &sequenced,
             };
 
             // Emit to the general channel
@@ -116,15 +140,47 @@ fn spawn_output_handler(app_handle: tauri::AppHandle, mut output_rx: OutputRecei
 
             // Also emit to session-specific channel
             let channel = format!("session_output:{}", session_id);
-            if let Err(e) = app_handle.emit(&channel, &output) {
+            if let Err(e) = app_handle.emit(&channel, &sequenced) {
                 tracing::error!("Failed to emit to {}: {}", channel, e);
             }
+
+            let config = state.config_manager.read().config().notifications.clone();
+            if let Some(notification) = notifications.observe(&config, &session_id, output) {
+                show_notification(&app_handle, &notification);
+            }
         }
 
         tracing::info!("Session output handler ended");
     });
 }
 
+/// Show an OS notification and remember its session for focusing
+///
+/// Approval requests only notify while the window is in the background.
+fn show_notification(app_handle: &tauri::AppHandle, notification: &Notification) {
+    use tauri_plugin_notification::NotificationExt;
+
+    let focused = app_handle
+        .get_webview_window("main")
+        .and_then(|window| window.is_focused().ok())
+        .unwrap_or(false);
+    if notification.kind == NotificationKind::ApprovalRequired && focused {
+        return;
+    }
+
+    *app_handle.state::<AppState>().notified_session.lock() = Some(notification.session_id.clone());
+
+    if let Err(e) = app_handle
+        .notification()
+        .builder()
+        .title(format!("Cowork: {}", notification.title))
+        .body(&notification.body)
+        .show()
+    {
+        tracing::warn!("Failed to show notification: {}", e);
+    }
+}
+
 /// Run the Tauri application
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -164,6 +220,7 @@ pub fn run() {
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
         .setup(|app| {
             // Use current working directory as workspace
             let workspace_path = std::env::current_dir()
@@ -190,11 +247,42 @@ pub fn run() {
             // Initialize config manager, falling back to default if it fails
             let config_manager = ConfigManager::new().unwrap_or_default();
 
-            let (state, output_rx) = init_state(workspace_path, config_manager);
+            // Apply the saved session retention limits
+            let sessions_config = config_manager.config().sessions.clone();
+            tauri::async_runtime::spawn(async move {
+                cowork_core::session::prune_in_background(sessions_config);
+            });
+
+            // Metrics are only recorded with [telemetry] enabled; the endpoint
+            // is served on the async runtime, and the textfile rewritten as
+            // metrics come in
+            let telemetry_config = config_manager.config().telemetry.clone();
+            tauri::async_runtime::spawn(async move {
+                drop(cowork_core::telemetry::install_from_config(&telemetry_config));
+            });
+
+            // Report (or kill) the processes a crashed earlier run left behind
+            let kill_orphans = config_manager.config().general.kill_orphaned_processes;
+            std::thread::spawn(move || {
+                let orphans = cowork_core::processes::scan_orphans(&cowork_core::processes::default_dir());
+                if orphans.is_empty() {
+                    return;
+                }
+                if kill_orphans {
+                    tracing::info!("{}", orphans.kill().summary());
+                } else {
+                    tracing::warn!("{} (set general.kill_orphaned_processes to kill them at startup)", orphans.summary());
+                }
+            });
+
+            let clipboard = Arc::new(simple_commands::AppClipboard(app.handle().clone()));
+            let (state, output_rx) = init_state(workspace_path, config_manager, clipboard);
+            let session_manager = state.session_manager.clone();
             app.manage(state);
 
             // Spawn output handler to forward session outputs to frontend
             spawn_output_handler(app.handle().clone(), output_rx);
+            spawn_idle_sweep(session_manager);
 
             // Background update check using same approach as CLI (no private key needed)
             tauri::async_runtime::spawn(async move {
@@ -221,25 +309,80 @@ pub fn run() {
 
             Ok(())
         })
+        .on_window_event(|window, event| {
+            use tauri::Emitter;
+
+            // Clicking a notification focuses the window; switch to the session it was about
+            if let tauri::WindowEvent::Focused(true) = event {
+                let session_id = window.state::<AppState>().notified_session.lock().take();
+                if let Some(session_id) = session_id
+                    && let Err(e) = window.emit("focus_session", &session_id)
+                {
+                    tracing::error!("Failed to emit focus_session: {}", e);
+                }
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             // Session commands (unified architecture)
             simple_commands::start_loop,
             simple_commands::send_message,
             simple_commands::send_message_with_images,
+            simple_commands::send_message_with_attachments,
             simple_commands::stop_loop,
             simple_commands::cancel_session,
+            simple_commands::cancel_tool,
+            simple_commands::explain_tool,
+            simple_commands::clear_queue,
+            simple_commands::regenerate_last,
+            simple_commands::edit_user_message,
+            simple_commands::run_recipe,
+            simple_commands::compact_session,
+            simple_commands::set_generation_params,
+            simple_commands::list_plans,
+            simple_commands::open_plan,
+            simple_commands::approve_plan,
             simple_commands::is_loop_running,
             simple_commands::approve_tool,
             simple_commands::reject_tool,
+            simple_commands::approve_delegated,
+            simple_commands::reject_delegated,
             simple_commands::answer_question,
             simple_commands::list_sessions,
+            simple_commands::set_session_keep_alive,
+            simple_commands::get_session_stats,
+            simple_commands::get_memory_stats,
+            simple_commands::get_workspace_locks,
+            simple_commands::get_session_transcript,
             simple_commands::create_session,
+            simple_commands::fork_session,
+            simple_commands::list_session_templates,
+            simple_commands::create_session_from_template,
+            simple_commands::get_workspace_roots,
+            simple_commands::set_additional_roots,
+            simple_commands::open_in_editor,
+            simple_commands::get_worktree_summary,
+            simple_commands::merge_worktree,
             simple_commands::clear_session,
+            // Selection and clipboard commands
+            simple_commands::set_context_selection,
+            simple_commands::write_clipboard,
             // Saved session commands
             simple_commands::list_saved_sessions,
             simple_commands::load_saved_session,
             simple_commands::delete_saved_session,
+            simple_commands::prune_saved_sessions,
+            simple_commands::import_sessions,
+            simple_commands::set_session_pinned,
+            simple_commands::pin_message,
+            simple_commands::rename_session,
+            simple_commands::set_tool_profile,
+            simple_commands::get_capabilities,
+            simple_commands::send_session_input,
+            simple_commands::get_audit_log,
             simple_commands::open_sessions_folder,
+            // Usage commands
+            simple_commands::get_usage_report,
+            simple_commands::get_tool_usage_report,
             // Config commands
             simple_commands::get_config_path,
             simple_commands::open_config_folder,
@@ -259,6 +402,11 @@ pub fn run() {
             commands::check_api_key,
             commands::get_provider_config,
             commands::test_api_connection,
+            commands::get_provider_status,
+            commands::keyring_available,
+            commands::set_keyring_api_key,
+            commands::remove_keyring_api_key,
+            commands::migrate_api_key_to_keyring,
             commands::is_setup_complete,
             commands::fetch_provider_models,
             // Component registry commands
@@ -266,6 +414,8 @@ pub fn run() {
             commands::list_agents,
             commands::list_commands,
             commands::list_skills,
+            commands::get_palette_items,
+            commands::complete_workspace_paths,
             commands::list_plugins,
         ])
         .run(tauri::generate_context!())
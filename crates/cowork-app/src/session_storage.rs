@@ -33,6 +33,15 @@ pub struct SessionMetadata {
     pub updated_at: DateTime<Utc>,
     pub file_path: PathBuf,
     pub file_size: u64,
+    /// Restored from an autosave: the session ended without being saved
+    #[serde(default)]
+    pub recovered: bool,
+    /// Kept when saved sessions are pruned
+    #[serde(default)]
+    pub pinned: bool,
+    /// Session template the session was started from
+    #[serde(default)]
+    pub template: Option<String>,
 }
 
 /// Session storage manager
@@ -156,11 +165,25 @@ impl SessionStorage {
             updated_at: session.updated_at,
             file_path: path.clone(),
             file_size,
+            recovered: false,
+            pinned: false,
+            template: None,
         })
     }
 
-    /// Delete a session by ID
-    pub fn delete(&self, id: &str) -> std::io::Result<()> {
+    /// Delete a session by ID, with its attachments and scratch directory
+    ///
+    /// The session's audit log is only removed when `with_audit_log` is set.
+    pub fn delete(&self, id: &str, with_audit_log: bool) -> std::io::Result<()> {
+        // The session's own file, autosave, attachments and scratch directory
+        let saved = match cowork_core::session::delete_saved_session(id, with_audit_log) {
+            Ok(found) => found,
+            Err(e) => {
+                tracing::warn!("Failed to remove saved data of session {}: {}", id, e);
+                false
+            }
+        };
+
         let entries = std::fs::read_dir(&self.sessions_dir)?;
 
         for entry in entries.flatten() {
@@ -171,6 +194,9 @@ impl SessionStorage {
                         return std::fs::remove_file(&path);
                     }
         }
+        if saved {
+            return Ok(());
+        }
 
         Err(std::io::Error::new(
             std::io::ErrorKind::NotFound,
@@ -317,7 +343,7 @@ mod tests {
         storage.save(&session).unwrap();
         assert_eq!(storage.list().unwrap().len(), 1);
 
-        storage.delete("delete-me-123").unwrap();
+        storage.delete("delete-me-123", false).unwrap();
         assert_eq!(storage.list().unwrap().len(), 0);
     }
 }
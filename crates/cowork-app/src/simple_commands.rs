@@ -2,23 +2,54 @@
 //!
 //! Commands for interacting with the SessionManager:
 //! - start_loop: Initialize the session manager output handler
-//! - send_message: Send a message to a session
+//! - send_message: Send a message to a session, optionally with per-turn overrides (returns its queue position if busy)
+//! - send_message_with_attachments: Send a message with checked image attachments
+//! - clear_queue: Drop messages waiting behind the current turn
+//! - set_generation_params: Change temperature, max_tokens, top_p, and stop sequences
+//! - list_plans / open_plan / approve_plan: Browse plans and approve one into a session
 //! - stop_loop: Stop a session
 //! - approve_tool / reject_tool: Handle tool approval
+//! - explain_tool: Ask why a tool call waiting for approval is wanted
+//! - approve_delegated / reject_delegated: Settle a tool call delegated to a reviewer
 //! - list_sessions: List active sessions
+//! - get_session_stats: Output queue depth, dropped-event counts and history memory for a session
+//! - get_memory_stats: Memory each open session holds, for finding the one that grows large
+//! - get_workspace_locks: Files sessions are writing right now, for debugging conflicts between sessions
+//! - fork_session: Branch a session into a new one from an earlier message
+//! - list_session_templates / create_session_from_template: Start a session pre-configured for a kind of task
+//! - get_worktree_summary / merge_worktree: Review and merge back a session isolated in a git worktree
+//! - run_recipe: Run one of the workspace's recipes as a turn of a session
+//! - compact_session: Compact a session's history now, as `/compact` does
 //! - answer_question: Send an answer to a question
 //! - add_mcp_server / remove_mcp_server / list_mcp_servers / list_mcp_tools: MCP management
 //! - install_skill / remove_skill / list_installed_skills: Skill management
 //! - clear_session: Clear conversation history
 //! - open_sessions_folder: Open sessions folder in file manager
+//! - open_in_editor: Open a file of the workspace in the user's editor, at a line
+//! - prune_saved_sessions / set_session_pinned: Apply the retention limits, and pin sessions to keep them
+//! - pin_message: Keep a message of an open session verbatim when its history is compacted
+//! - rename_session: Give a session a title of the user's own
+//! - set_tool_profile: Switch the tools a session offers the model
+//! - set_context_selection: Send the text selected in the app with the session's next message
+//! - write_clipboard: Put text on the system clipboard (also how the ClipboardWrite tool writes)
+//! - get_usage_report: Token usage and estimated cost across sessions
+//! - get_tool_usage_report: Tool calls per tool across saved sessions
 
 use std::collections::HashMap;
-use std::path::Path;
-use tauri::State;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, State};
 
 use cowork_core::config::McpServerConfig;
-use cowork_core::session::{ImageAttachment, SessionInput, SessionOutput};
+use cowork_core::context::CompactionMode;
+use cowork_core::provider::{parse_window, GenerationParams, UsageGroupBy, UsageReport, UsageStore};
+use cowork_core::session::{
+    Attachment, Capabilities, FileLockInfo, ImageAttachment, ImportReport, ImportSource, MemoryStats, PruneReport, SequencedOutput, SessionInput, SessionOutput, SessionStats,
+    SessionSummary, TitleSource, ToolUsageReport, TranscriptSync, TurnApproval, TurnOverrides, WorktreeSummary,
+};
 use cowork_core::skills::installer::{InstallLocation, SkillInstaller};
+use cowork_core::templates::{list_templates, load_template, SessionTemplate};
+use cowork_core::tools::interaction::{Clipboard, MAX_CLIPBOARD_CHARS};
+use cowork_core::tools::planning::{PlanRecord, PlanStep, PlanStore};
 
 use crate::state::AppState;
 
@@ -56,9 +87,16 @@ fn open_folder_in_file_manager(path: &Path) -> Result<(), String> {
 /// The output handler is automatically started during app setup.
 /// This command just emits initial ready/idle events for the default session.
 #[tauri::command]
-pub async fn start_loop(app: tauri::AppHandle) -> Result<(), String> {
+pub async fn start_loop(app: tauri::AppHandle, state: State<'_, AppState>) -> Result<(), String> {
     use tauri::Emitter;
 
+    // After a reload the default session may be mid-turn; the frontend
+    // catches up with get_session_transcript instead
+    if state.session_manager.transcript("default", 0).is_some() {
+        tracing::info!("start_loop called again - the frontend catches up on its own");
+        return Ok(());
+    }
+
     tracing::info!("start_loop called - emitting initial ready/idle");
 
     // Emit initial ready/idle for the default session
@@ -69,13 +107,13 @@ pub async fn start_loop(app: tauri::AppHandle) -> Result<(), String> {
     struct SessionEvent {
         session_id: String,
         #[serde(flatten)]
-        output: SessionOutput,
+        output: SequencedOutput,
     }
 
     for output in [ready, idle] {
         let event = SessionEvent {
             session_id: "default".to_string(),
-            output,
+            output: state.session_manager.record_output("default", output),
         };
         if let Err(e) = app.emit("loop_output", &event) {
             tracing::error!("Failed to emit loop_output: {}", e);
@@ -94,21 +132,50 @@ pub struct ImageData {
     pub media_type: String,
 }
 
+/// Settings the frontend picked for a single message (all optional)
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct MessageOverrides {
+    /// Model ID, or haiku, sonnet or opus for the provider's tiers
+    pub model: Option<String>,
+    /// "all" or an approval level
+    pub approval: Option<TurnApproval>,
+    /// Tool profile name (a preset or one from the config)
+    pub tool_profile: Option<String>,
+}
+
+impl MessageOverrides {
+    /// The turn overrides these name, resolving the tool profile in `state`'s config
+    fn resolve(self, state: &AppState) -> Result<TurnOverrides, String> {
+        let tool_profile = match self.tool_profile {
+            Some(name) => Some(state.config_manager.read().config().tools.resolve(&name).map_err(|e| e.to_string())?),
+            None => None,
+        };
+        Ok(TurnOverrides { model: self.model, approval_level: self.approval, tool_profile })
+    }
+}
+
 /// Send a message to a session
 ///
+/// Returns the queue position when the session is busy with another turn
+/// (1 = next to run), or null when the message starts right away.
 /// If session_id is not provided, uses "default".
 #[tauri::command]
 pub async fn send_message(
     content: String,
     session_id: Option<String>,
+    overrides: Option<MessageOverrides>,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<Option<usize>, String> {
     let session_id = session_id.unwrap_or_else(|| "default".to_string());
     tracing::info!("send_message to session '{}': {} chars", session_id, content.len());
 
+    let mut message = SessionInput::user_message(content);
+    if let Some(overrides) = overrides {
+        message = message.with_overrides(overrides.resolve(&state)?);
+    }
     state
         .session_manager
-        .push_message(&session_id, SessionInput::user_message(content))
+        .send_message(&session_id, message)
         .await
         .map_err(|e| e.to_string())
 }
@@ -116,6 +183,7 @@ pub async fn send_message(
 /// Send a message with image attachments to a session
 ///
 /// Images should be base64-encoded with their MIME types.
+/// Returns the queue position like `send_message`.
 /// If session_id is not provided, uses "default".
 #[tauri::command]
 pub async fn send_message_with_images(
@@ -123,7 +191,7 @@ pub async fn send_message_with_images(
     images: Vec<ImageData>,
     session_id: Option<String>,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<Option<usize>, String> {
     let session_id = session_id.unwrap_or_else(|| "default".to_string());
     tracing::info!(
         "send_message_with_images to session '{}': {} chars, {} images",
@@ -140,7 +208,7 @@ pub async fn send_message_with_images(
 
     state
         .session_manager
-        .push_message(
+        .send_message(
             &session_id,
             SessionInput::user_message_with_images(content, attachments),
         )
@@ -148,6 +216,38 @@ pub async fn send_message_with_images(
         .map_err(|e| e.to_string())
 }
 
+/// Send a message with attachments (pasted or dropped images)
+///
+/// Each attachment is loaded and checked first; a bad one fails the command
+/// with its position and the problem, and nothing is sent.
+#[tauri::command]
+pub async fn send_message_with_attachments(
+    content: String,
+    attachments: Vec<Attachment>,
+    session_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Option<usize>, String> {
+    let session_id = session_id.unwrap_or_else(|| "default".to_string());
+    tracing::info!(
+        "send_message_with_attachments to session '{}': {} chars, {} attachments",
+        session_id,
+        content.len(),
+        attachments.len()
+    );
+
+    let images = attachments
+        .into_iter()
+        .enumerate()
+        .map(|(i, attachment)| attachment.into_image().map_err(|e| format!("Attachment {}: {}", i + 1, e)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    state
+        .session_manager
+        .send_message(&session_id, SessionInput::user_message_with_images(content, images))
+        .await
+        .map_err(|e| e.to_string())
+}
+
 /// Stop a session (or all sessions if no ID provided)
 #[tauri::command]
 pub async fn stop_loop(
@@ -214,6 +314,41 @@ pub async fn reject_tool(
         .map_err(|e| e.to_string())
 }
 
+/// Approve a tool call delegated to a reviewer, as `approver`
+#[tauri::command]
+pub async fn approve_delegated(
+    tool_id: String,
+    approver: String,
+    session_id: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    tracing::info!("{} approving delegated tool {} in session {}", approver, tool_id, session_id);
+
+    state
+        .session_manager
+        .approve_delegated(&session_id, &tool_id, &approver)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Refuse a tool call delegated to a reviewer, as `approver`
+#[tauri::command]
+pub async fn reject_delegated(
+    tool_id: String,
+    approver: String,
+    reason: Option<String>,
+    session_id: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    tracing::info!("{} rejecting delegated tool {} in session {}: {:?}", approver, tool_id, session_id, reason);
+
+    state
+        .session_manager
+        .reject_delegated(&session_id, &tool_id, &approver, reason)
+        .await
+        .map_err(|e| e.to_string())
+}
+
 /// Answer a question from ask_user_question tool
 #[tauri::command]
 pub async fn answer_question(
@@ -251,10 +386,287 @@ pub async fn cancel_session(
         .map_err(|e| e.to_string())
 }
 
-/// List active sessions
+/// Cancel one tool call in a session, letting the rest of the turn continue
+///
+/// A no-op if the tool has already finished.
+#[tauri::command]
+pub async fn cancel_tool(
+    tool_call_id: String,
+    session_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let session_id = session_id.unwrap_or_else(|| "default".to_string());
+    tracing::info!("Cancelling tool {} in session {}", tool_call_id, session_id);
+
+    state
+        .session_manager
+        .push_message(&session_id, SessionInput::cancel_tool(tool_call_id))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Ask the fast model why a tool call waiting for approval is wanted
+///
+/// The answer arrives as a `tool_explanation` output; the call stays
+/// pending. Only one explanation is given per call.
+#[tauri::command]
+pub async fn explain_tool(
+    tool_call_id: String,
+    session_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let session_id = session_id.unwrap_or_else(|| "default".to_string());
+    tracing::info!("Explaining tool {} in session {}", tool_call_id, session_id);
+
+    state
+        .session_manager
+        .push_message(&session_id, SessionInput::explain_tool(tool_call_id))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Drop messages queued behind the current turn in a session
+#[tauri::command]
+pub async fn clear_queue(
+    session_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let session_id = session_id.unwrap_or_else(|| "default".to_string());
+    tracing::info!("Clearing message queue for session {}", session_id);
+
+    state
+        .session_manager
+        .push_message(&session_id, SessionInput::clear_queue())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Discard the last reply in a session and generate it again
+///
+/// `temperature` applies to the new reply only. Refused while a turn is in
+/// flight; the session emits `history_truncated` before the new reply.
+#[tauri::command]
+pub async fn regenerate_last(
+    session_id: Option<String>,
+    temperature: Option<f32>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let session_id = session_id.unwrap_or_else(|| "default".to_string());
+    tracing::info!("Regenerating last reply in session {}", session_id);
+
+    state
+        .session_manager
+        .push_message(&session_id, SessionInput::regenerate_last(temperature))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Run a recipe from the workspace's `.cowork/recipes` as a turn of the session
+///
+/// `args` is what would follow the name in `/recipe run`. The session reports
+/// progress with `recipe_started`, `recipe_step_started`,
+/// `recipe_step_finished` and `recipe_finished`; a recipe that doesn't load
+/// or validate fails the turn with an `error` before any step runs.
+#[tauri::command]
+pub async fn run_recipe(
+    session_id: Option<String>,
+    name: String,
+    args: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let session_id = session_id.unwrap_or_else(|| "default".to_string());
+    tracing::info!("Running recipe {} in session {}", name, session_id);
+
+    state
+        .session_manager
+        .push_message(&session_id, SessionInput::run_recipe(name, args.unwrap_or_default()))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Compact the session's history between turns
+///
+/// Unset options come from `[summarizer]`; `focus` is what the summary
+/// should pay attention to. The session reports the result with
+/// `compacted`.
+#[tauri::command]
+pub async fn compact_session(
+    session_id: Option<String>,
+    mode: Option<CompactionMode>,
+    model: Option<String>,
+    focus: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let session_id = session_id.unwrap_or_else(|| "default".to_string());
+    tracing::info!("Compacting session {}", session_id);
+
+    state
+        .session_manager
+        .push_message(&session_id, SessionInput::compact(mode, model, focus))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Rewrite an earlier user message and re-run the conversation from it
+///
+/// Everything after the message is discarded. Refused while a turn is in
+/// flight; the session emits `history_truncated` before the new reply.
+#[tauri::command]
+pub async fn edit_user_message(
+    session_id: Option<String>,
+    message_id: String,
+    new_content: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let session_id = session_id.unwrap_or_else(|| "default".to_string());
+    tracing::info!("Editing message {} in session {}", message_id, session_id);
+
+    state
+        .session_manager
+        .push_message(&session_id, SessionInput::edit_user_message(message_id, new_content))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Change the generation parameters for a session's later LLM requests
+///
+/// Out-of-range values are clamped; the session reports the applied values
+/// with a `generation_params_changed` event.
+#[tauri::command]
+pub async fn set_generation_params(
+    session_id: Option<String>,
+    params: GenerationParams,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let session_id = session_id.unwrap_or_else(|| "default".to_string());
+    tracing::info!("Setting generation params for session {}: {}", session_id, params);
+
+    state
+        .session_manager
+        .push_message(&session_id, SessionInput::set_generation_params(params))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// List all plans, newest first
+#[tauri::command]
+pub async fn list_plans() -> Result<Vec<PlanRecord>, String> {
+    PlanStore::default().list().map_err(|e| e.to_string())
+}
+
+/// A plan with its content and steps
+#[derive(serde::Serialize)]
+pub struct PlanDetails {
+    #[serde(flatten)]
+    pub record: PlanRecord,
+    pub path: String,
+    pub content: String,
+    pub steps: Vec<PlanStep>,
+    /// Free-form plan without a checklist
+    pub read_only: bool,
+}
+
+/// Open a plan
+#[tauri::command]
+pub async fn open_plan(name: String) -> Result<PlanDetails, String> {
+    let plan = PlanStore::default()
+        .open(&name)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Plan not found: {}", name))?;
+    Ok(PlanDetails {
+        read_only: plan.is_read_only(),
+        path: plan.path.display().to_string(),
+        record: plan.record,
+        content: plan.content,
+        steps: plan.steps,
+    })
+}
+
+/// Approve a plan (default: the session's current plan)
+///
+/// The session reports the change with a `plan_status_changed` event and
+/// injects the plan into its next turn.
+#[tauri::command]
+pub async fn approve_plan(
+    session_id: Option<String>,
+    name: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let session_id = session_id.unwrap_or_else(|| "default".to_string());
+    tracing::info!("Approving plan {:?} for session {}", name, session_id);
+
+    state
+        .session_manager
+        .push_message(&session_id, SessionInput::approve_plan(name))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// List open sessions, each marked active or suspended
+#[tauri::command]
+pub async fn list_sessions(state: State<'_, AppState>) -> Result<Vec<SessionSummary>, String> {
+    Ok(state.session_manager.session_summaries())
+}
+
+/// Keep a session from being suspended when idle (or allow it again)
+#[tauri::command]
+pub async fn set_session_keep_alive(
+    session_id: String,
+    keep_alive: bool,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    state.session_manager.set_keep_alive(&session_id, keep_alive);
+    Ok(())
+}
+
+/// Output channel statistics for a session (None if it isn't running)
+///
+/// Ephemeral events (thinking, tool activity) are dropped instead of stalling
+/// the session when the frontend falls behind; `events_dropped` counts them.
+#[tauri::command]
+pub async fn get_session_stats(
+    session_id: String,
+    state: State<'_, AppState>,
+) -> Result<Option<SessionStats>, String> {
+    Ok(state.session_manager.session_stats(&session_id))
+}
+
+/// Memory each open session holds: its history, the outputs kept for the
+/// frontend, and how much of both is interned or spilled to disk
+#[tauri::command]
+pub async fn get_memory_stats(state: State<'_, AppState>) -> Result<HashMap<String, MemoryStats>, String> {
+    Ok(state.session_manager.memory_stats())
+}
+
+/// Files sessions are writing right now, and which session holds each
+///
+/// A session's write to a file another session holds fails with an error
+/// naming that session; this shows the locks behind such errors.
+#[tauri::command]
+pub async fn get_workspace_locks(state: State<'_, AppState>) -> Result<Vec<FileLockInfo>, String> {
+    Ok(state.session_manager.workspace_locks())
+}
+
+/// A session's outputs after `since_seq`, for a frontend catching up
+///
+/// The frontend calls this after a reload, or when an event's `seq` skips
+/// one; `pending` holds the approvals and questions still waiting, even
+/// when they are older than the outputs kept.
 #[tauri::command]
-pub async fn list_sessions(state: State<'_, AppState>) -> Result<Vec<String>, String> {
-    Ok(state.session_manager.list_sessions())
+pub async fn get_session_transcript(
+    session_id: String,
+    since_seq: u64,
+    state: State<'_, AppState>,
+) -> Result<TranscriptSync, String> {
+    Ok(state
+        .session_manager
+        .transcript(&session_id, since_seq)
+        .unwrap_or_else(|| TranscriptSync {
+            events: Vec::new(),
+            last_seq: 0,
+            complete: true,
+            pending: Vec::new(),
+        }))
 }
 
 /// Create a new session
@@ -274,11 +686,178 @@ pub async fn create_session(
     Ok(())
 }
 
+/// A session created by `fork_session`, with the link back to its parent
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ForkedSession {
+    pub session_id: String,
+    pub parent_id: String,
+    pub fork_point: usize,
+}
+
+/// Fork a session into a new one whose history ends at `at_message` (inclusive)
+///
+/// The source keeps running unchanged; the fork copies its config and
+/// approval settings and starts idle under a new session ID.
+#[tauri::command]
+pub async fn fork_session(
+    session_id: String,
+    at_message: usize,
+    state: State<'_, AppState>,
+) -> Result<ForkedSession, String> {
+    tracing::info!("Forking session {} at message {}", session_id, at_message);
+    let fork_id = state
+        .session_manager
+        .fork_session(&session_id, at_message)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(ForkedSession {
+        session_id: fork_id,
+        parent_id: session_id,
+        fork_point: at_message,
+    })
+}
+
+/// A session template for the new session dialog, or why it can't be used
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TemplateEntry {
+    pub name: String,
+    pub template: Option<SessionTemplate>,
+    /// Why the template can't be used (it doesn't parse, or names unknown settings)
+    pub error: Option<String>,
+}
+
+/// List the session templates: the workspace's `.cowork/templates` and the
+/// built-ins it doesn't replace
+#[tauri::command]
+pub async fn list_session_templates(state: State<'_, AppState>) -> Result<Vec<TemplateEntry>, String> {
+    let workspace = state.workspace_path.clone();
+    let config = state.config();
+    tokio::task::spawn_blocking(move || {
+        let (tools, _) = cowork_core::recipes::builtin_tools_and_skills(&workspace);
+        list_templates(&workspace)
+            .into_iter()
+            .map(|(name, template)| {
+                let checked = template.and_then(|template| template.check(&config, &tools).map(|()| template));
+                match checked {
+                    Ok(template) => TemplateEntry { name, template: Some(template), error: None },
+                    Err(e) => TemplateEntry { name, template: None, error: Some(e.to_string()) },
+                }
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Start a session from a session template
+///
+/// The template's settings override the config's for this session, and its
+/// opening message, with `args` filled in, is sent as the first message. The
+/// session's saved metadata records the template's name.
+#[tauri::command]
+pub async fn create_session_from_template(
+    session_id: String,
+    template: String,
+    args: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    tracing::info!("Creating session {} from template {}", session_id, template);
+    let workspace = state.workspace_path.clone();
+    let config = state.config();
+    let template = tokio::task::spawn_blocking(move || load_template(&workspace, &template, &config))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())?;
+    state
+        .session_manager
+        .create_session_from_template(&session_id, &template, args.as_deref().unwrap_or_default())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// The workspace roots: the workspace first, then the other roots
+#[tauri::command]
+pub async fn get_workspace_roots(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    Ok(std::iter::once(state.workspace_path.clone())
+        .chain(state.session_manager.additional_roots())
+        .map(|path| path.display().to_string())
+        .collect())
+}
+
+/// Set the workspace roots besides the workspace, for sessions created from now on
+///
+/// Each must be an existing directory; the file tools of new sessions may
+/// use them as well as the workspace. Returns the roots as `get_workspace_roots` does.
+#[tauri::command]
+pub async fn set_additional_roots(roots: Vec<String>, state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    let roots = roots
+        .iter()
+        .map(|root| match dunce::canonicalize(root) {
+            Ok(path) if path.is_dir() => Ok(path),
+            _ => Err(format!("{} is not a directory", root)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    tracing::info!("Setting additional workspace roots: {:?}", roots);
+    state.session_manager.set_additional_roots(roots);
+    get_workspace_roots(state).await
+}
+
+/// Open a file of the workspace in the user's editor (`[general] editor`, or
+/// a detected one), at `line` and `column` if given
+///
+/// Files in the worktree of an isolated `session_id` may be opened as well.
+/// Returns the command that was started.
+#[tauri::command]
+pub async fn open_in_editor(
+    path: String,
+    line: Option<u32>,
+    column: Option<u32>,
+    session_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, String> {
+    let template = state.config_manager.read().config().general.editor.clone();
+    let mut roots: Vec<PathBuf> = std::iter::once(state.workspace_path.clone())
+        .chain(state.session_manager.additional_roots())
+        .collect();
+    if let Some(worktree) = session_id.and_then(|id| state.session_manager.worktree(&id)) {
+        roots.insert(0, worktree.workspace());
+    }
+    cowork_core::editor::open_in_editor(template.as_deref(), &roots, &path, line, column).map_err(|e| e.to_string())
+}
+
 /// List saved sessions from disk
+///
+/// Sessions saved by the agent loop are included with their `pinned` flag;
+/// ones that ended without being saved (a crash) come from their autosave,
+/// marked `recovered`.
 #[tauri::command]
 pub async fn list_saved_sessions() -> Result<Vec<crate::session_storage::SessionMetadata>, String> {
     let storage = crate::session_storage::SessionStorage::new();
-    storage.list().map_err(|e| e.to_string())
+    let mut sessions = storage.list().map_err(|e| e.to_string())?;
+
+    let sessions_dir = cowork_core::session::get_sessions_dir().map_err(|e| e.to_string())?;
+    let autosave_dir = cowork_core::session::get_autosave_dir().map_err(|e| e.to_string())?;
+    for saved in cowork_core::session::list_saved_sessions().map_err(|e| e.to_string())? {
+        sessions.retain(|s| s.id != saved.id);
+        let dir = if saved.recovered { &autosave_dir } else { &sessions_dir };
+        let file_path = dir.join(format!("{}.json", saved.id));
+        sessions.push(crate::session_storage::SessionMetadata {
+            file_size: std::fs::metadata(&file_path).map(|m| m.len()).unwrap_or(0),
+            id: saved.id,
+            title: Some(saved.name),
+            message_count: saved.messages.len(),
+            provider_type: saved.provider.unwrap_or_default(),
+            created_at: saved.created_at,
+            updated_at: saved.updated_at,
+            file_path,
+            recovered: saved.recovered,
+            pinned: saved.pinned,
+            template: saved.template,
+        });
+    }
+    sessions.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+    Ok(sessions)
 }
 
 /// Load a saved session by ID
@@ -289,10 +868,263 @@ pub async fn load_saved_session(session_id: String) -> Result<crate::session_sto
 }
 
 /// Delete a saved session by ID
+///
+/// Its audit log is kept unless `delete_audit_log` is set. The git worktree
+/// of an isolated session is removed with it, discarding unmerged work.
 #[tauri::command]
-pub async fn delete_saved_session(session_id: String) -> Result<(), String> {
+pub async fn delete_saved_session(
+    session_id: String,
+    delete_audit_log: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    state
+        .session_manager
+        .delete_worktree(&session_id)
+        .await
+        .map_err(|e| e.to_string())?;
     let storage = crate::session_storage::SessionStorage::new();
-    storage.delete(&session_id).map_err(|e| e.to_string())
+    storage
+        .delete(&session_id, delete_audit_log.unwrap_or(false))
+        .map_err(|e| e.to_string())
+}
+
+/// Delete the saved sessions the `[sessions]` retention limits exclude
+///
+/// With `dry_run` nothing is deleted and the report lists what would be.
+/// Audit logs are kept unless `delete_audit_log` is set.
+#[tauri::command]
+pub async fn prune_saved_sessions(
+    dry_run: Option<bool>,
+    delete_audit_log: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<PruneReport, String> {
+    let config = state.config_manager.read().config().sessions.clone();
+    let (dry_run, with_audit_log) = (dry_run.unwrap_or(false), delete_audit_log.unwrap_or(false));
+    tokio::task::spawn_blocking(move || cowork_core::session::prune_saved_sessions(&config, dry_run, with_audit_log))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// Import sessions from another assistant into the saved sessions
+///
+/// `source` is `claude-code`; `paths` are its session files or directories
+/// holding them. With `dry_run` nothing is written and the report lists what
+/// would be. Files that can't be imported are reported, not fatal.
+#[tauri::command]
+pub async fn import_sessions(
+    source: String,
+    paths: Vec<String>,
+    dry_run: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<ImportReport, String> {
+    let source = source.parse::<ImportSource>().map_err(|e| e.to_string())?;
+    let paths: Vec<std::path::PathBuf> = paths.into_iter().map(Into::into).collect();
+    let workspace = state.workspace_path.clone();
+    tokio::task::spawn_blocking(move || {
+        let (tools, _) = cowork_core::recipes::builtin_tools_and_skills(&workspace);
+        cowork_core::session::import_sessions(source, &paths, &tools, dry_run.unwrap_or(false))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())
+}
+
+/// Pin or unpin a session, exempting it from pruning
+///
+/// An open session is told directly, so its later saves keep the flag;
+/// otherwise its saved files are updated.
+#[tauri::command]
+pub async fn set_session_pinned(
+    session_id: String,
+    pinned: bool,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    if state.session_manager.has_session(&session_id) {
+        return state
+            .session_manager
+            .push_message(&session_id, SessionInput::set_pinned(pinned))
+            .await
+            .map_err(|e| e.to_string());
+    }
+    cowork_core::session::set_session_pinned(&session_id, pinned).map_err(|e| e.to_string())
+}
+
+/// Pin or unpin a message of an open session, by the ID its output carried
+///
+/// Without an ID, the latest reply. Compaction keeps pinned messages.
+#[tauri::command]
+pub async fn pin_message(
+    session_id: String,
+    message_id: Option<String>,
+    pinned: bool,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    state
+        .session_manager
+        .push_message(&session_id, SessionInput::pin_message(message_id, pinned))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Give a session a title of the user's own, which is never replaced by a generated one
+///
+/// An open session is told directly and reports `TitleChanged`; otherwise
+/// its saved files are updated.
+#[tauri::command]
+pub async fn rename_session(
+    session_id: String,
+    title: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+    if title.is_empty() {
+        return Err("A session title can't be empty".to_string());
+    }
+    if state.session_manager.has_session(&session_id) {
+        return state
+            .session_manager
+            .push_message(&session_id, SessionInput::set_title(title))
+            .await
+            .map_err(|e| e.to_string());
+    }
+    cowork_core::session::set_session_title(&session_id, &title, TitleSource::User).map_err(|e| e.to_string())
+}
+
+/// Switch the tools a session offers the model, from its next turn
+///
+/// `profile` is a preset or a profile from the config's `[tools.profiles]`;
+/// the session reports the tools it offers with a `tool_profile_changed`
+/// event when the turn starts.
+#[tauri::command]
+pub async fn set_tool_profile(
+    session_id: Option<String>,
+    profile: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let session_id = session_id.unwrap_or_else(|| "default".to_string());
+    let profile = state.config_manager.read().config().tools.resolve(&profile).map_err(|e| e.to_string())?;
+    tracing::info!("Setting tool profile for session {}: {}", session_id, profile);
+
+    state
+        .session_manager
+        .push_message(&session_id, SessionInput::set_tool_profile(profile))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// What sessions support: input and output types, tools, features and
+/// limits, for the frontend to check before offering them
+#[tauri::command]
+pub async fn get_capabilities(state: State<'_, AppState>) -> Result<Capabilities, String> {
+    Ok(state.session_manager.capabilities())
+}
+
+/// Send a session input given as JSON, like `{"type": "cancel"}`
+///
+/// An input type this version doesn't know is answered with an
+/// `unsupported_input` event instead of failing here.
+#[tauri::command]
+pub async fn send_session_input(
+    session_id: Option<String>,
+    input: serde_json::Value,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let session_id = session_id.unwrap_or_else(|| "default".to_string());
+    let input = SessionInput::from_json(input).map_err(|e| format!("Invalid session input: {}", e))?;
+    state
+        .session_manager
+        .push_message(&session_id, input)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Set the text selected in the app, for the session's next message
+///
+/// The message takes it in place of `${SELECTION}`, or as a reminder ahead
+/// of it, and clears it. Empty text clears it sooner. Selections longer than
+/// `MAX_SELECTION_CHARS` are cut.
+#[tauri::command]
+pub async fn set_context_selection(
+    session_id: Option<String>,
+    text: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let session_id = session_id.unwrap_or_else(|| "default".to_string());
+    // Clearing the selection of a session that isn't open has nothing to do
+    if text.is_empty() && !state.session_manager.has_session(&session_id) {
+        return Ok(());
+    }
+    state
+        .session_manager
+        .push_message(&session_id, SessionInput::set_selection(text))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Put text on the system clipboard, returning the characters written
+#[tauri::command]
+pub async fn write_clipboard(text: String, app: AppHandle) -> Result<usize, String> {
+    let chars = text.chars().count();
+    if chars > MAX_CLIPBOARD_CHARS {
+        return Err(format!(
+            "Text is {} characters, more than the {} that can be copied at once",
+            chars, MAX_CLIPBOARD_CHARS
+        ));
+    }
+    write_clipboard_text(&app, &text)?;
+    Ok(chars)
+}
+
+fn write_clipboard_text(app: &AppHandle, text: &str) -> Result<(), String> {
+    use tauri_plugin_clipboard_manager::ClipboardExt;
+
+    app.clipboard().write_text(text).map_err(|e| e.to_string())
+}
+
+/// The system clipboard for the ClipboardWrite tool, written like `write_clipboard`
+pub struct AppClipboard(pub AppHandle);
+
+impl Clipboard for AppClipboard {
+    fn write_text(&self, text: &str) -> Result<(), String> {
+        write_clipboard_text(&self.0, text)
+    }
+}
+
+/// What merging an isolated session's worktree would bring into the repository
+#[tauri::command]
+pub async fn get_worktree_summary(
+    session_id: String,
+    state: State<'_, AppState>,
+) -> Result<WorktreeSummary, String> {
+    state
+        .session_manager
+        .worktree_summary(&session_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Merge an isolated session's worktree into the repository's branch
+///
+/// The session is stopped and its worktree removed. A conflict aborts the
+/// merge and leaves the session and worktree as they were.
+#[tauri::command]
+pub async fn merge_worktree(
+    session_id: String,
+    state: State<'_, AppState>,
+) -> Result<WorktreeSummary, String> {
+    tracing::info!("Merging the worktree of session {}", session_id);
+    state
+        .session_manager
+        .merge_worktree(&session_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Get a session's audit log of approvals and executed mutations, oldest first
+#[tauri::command]
+pub async fn get_audit_log(session_id: String) -> Result<Vec<cowork_core::session::AuditRecord>, String> {
+    cowork_core::session::load_audit_log(&session_id).map_err(|e| e.to_string())
 }
 
 // ────────────────────────────────────────────────────────────────────────────────
@@ -307,6 +1139,8 @@ pub struct McpServerInfo {
     pub enabled: bool,
     pub status: String,
     pub tool_count: usize,
+    /// Whether the server is configured to run in the sandbox
+    pub sandboxed: bool,
     pub error: Option<String>,
 }
 
@@ -393,6 +1227,7 @@ pub async fn list_mcp_servers(state: State<'_, AppState>) -> Result<Vec<McpServe
                 enabled: cfg.enabled,
                 status: "stopped".to_string(), // Servers start on-demand
                 tool_count: 0,
+                sandboxed: cfg.sandbox.is_some() && !cfg.is_http(),
                 error: None,
             }
         })
@@ -549,6 +1384,37 @@ pub async fn open_sessions_folder() -> Result<String, String> {
     Ok(path_str)
 }
 
+/// Aggregate recorded token usage and estimated cost
+///
+/// `since` is a recent window like "7d" (None = all recorded usage);
+/// `group_by` is "model" (default), "day", or "session".
+#[tauri::command]
+pub async fn get_usage_report(
+    since: Option<String>,
+    group_by: Option<String>,
+) -> Result<UsageReport, String> {
+    let window = since.as_deref().map(parse_window).transpose().map_err(|e| e.to_string())?;
+    let group_by: UsageGroupBy = group_by.as_deref().unwrap_or("model").parse()?;
+
+    tokio::task::spawn_blocking(move || UsageStore::open_default().report(window, group_by))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// Add up tool calls per tool across saved sessions
+///
+/// `since` is a recent window like "7d" (None = all saved sessions).
+#[tauri::command]
+pub async fn get_tool_usage_report(since: Option<String>) -> Result<ToolUsageReport, String> {
+    let window = since.as_deref().map(parse_window).transpose().map_err(|e| e.to_string())?;
+
+    tokio::task::spawn_blocking(move || cowork_core::session::tool_usage_report(window))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
 /// Get the config file path
 #[tauri::command]
 pub async fn get_config_path() -> Result<String, String> {
@@ -2,11 +2,13 @@
 
 use std::path::PathBuf;
 use std::sync::Arc;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 
+use cowork_core::completion::PathCompleter;
+use cowork_core::palette::Palette;
 use cowork_core::provider::catalog;
 use cowork_core::session::SessionManager;
-use cowork_core::{Config, ConfigManager};
+use cowork_core::{ApiKeyStorage, Config, ConfigManager};
 
 /// Global application state
 pub struct AppState {
@@ -16,6 +18,12 @@ pub struct AppState {
     pub config_manager: Arc<RwLock<ConfigManager>>,
     /// Session manager for the unified agent loop
     pub session_manager: Arc<SessionManager>,
+    /// Session of the last notification shown, focused when the window regains focus
+    pub notified_session: Arc<Mutex<Option<String>>>,
+    /// Command palette items, kept until the files they come from change
+    pub palette: Arc<Palette>,
+    /// Workspace files for `@` mention completion, kept until they change
+    pub path_completer: Arc<PathCompleter>,
 }
 
 impl AppState {
@@ -71,6 +79,9 @@ pub struct Settings {
     /// Web search configuration (optional)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub web_search: Option<WebSearchSettings>,
+    /// Tool profile new sessions start with (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tools: Option<ToolsSettings>,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -79,6 +90,9 @@ pub struct ProviderSettings {
     pub api_key: Option<String>,
     pub model: Option<String>,
     pub base_url: Option<String>,
+    /// Where `api_key` is kept when saved
+    #[serde(default)]
+    pub api_key_storage: ApiKeyStorage,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -99,16 +113,25 @@ pub struct WebSearchSettings {
     pub api_key: Option<String>,
 }
 
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ToolsSettings {
+    pub profile: String,
+    /// Presets and custom profiles to choose from (ignored when updating)
+    #[serde(default)]
+    pub available: Vec<String>,
+}
+
 impl From<&Config> for Settings {
     fn from(config: &Config) -> Self {
         // Get the default provider settings
-        let (provider_type, api_key, model, base_url) =
+        let (provider_type, api_key, model, base_url, api_key_storage) =
             if let Some(provider) = config.get_default_provider() {
                 (
                     provider.provider_type.clone(),
                     provider.get_api_key(),
                     provider.model.clone(),
                     provider.base_url.clone(),
+                    provider.api_key_storage,
                 )
             } else {
                 // Fallback to defaults (must match config.rs defaults)
@@ -117,6 +140,7 @@ impl From<&Config> for Settings {
                     None,
                     catalog::default_model("anthropic").unwrap_or("").to_string(),
                     None,
+                    ApiKeyStorage::Config,
                 )
             };
 
@@ -135,6 +159,7 @@ impl From<&Config> for Settings {
                 api_key,
                 model: Some(model),
                 base_url,
+                api_key_storage,
             },
             approval: ApprovalSettings {
                 auto_approve_level: config.approval.auto_approve_level.clone(),
@@ -146,6 +171,10 @@ impl From<&Config> for Settings {
                 show_tool_calls: true,
             },
             web_search,
+            tools: Some(ToolsSettings {
+                profile: config.tools.profile.clone(),
+                available: config.tools.profile_names(),
+            }),
         }
     }
 }
@@ -158,6 +187,7 @@ impl Default for Settings {
                 api_key: None,
                 model: Some(catalog::default_model("anthropic").unwrap_or("").to_string()),
                 base_url: None,
+                api_key_storage: ApiKeyStorage::Config,
             },
             approval: ApprovalSettings {
                 auto_approve_level: "low".to_string(),
@@ -169,6 +199,7 @@ impl Default for Settings {
                 show_tool_calls: true,
             },
             web_search: None,
+            tools: None,
         }
     }
 }
@@ -0,0 +1,175 @@
+//! Slash-command aliases from the `[cli.aliases]` config section
+//!
+//! An alias expands to a command with optional default arguments
+//! (`"/t" = "/test --all"`); arguments typed after the alias are appended.
+//! Aliases may point at other aliases. Entries are checked when loaded, so a
+//! typo'd target or a loop is reported at startup instead of when used.
+
+use std::collections::{BTreeMap, HashSet};
+
+/// Validated slash-command aliases
+#[derive(Debug, Clone, Default)]
+pub struct Aliases {
+    /// Alias (with its leading `/`) to expansion
+    entries: BTreeMap<String, String>,
+}
+
+impl Aliases {
+    /// Load aliases, keeping only those that end at one of `commands`
+    ///
+    /// `commands` are the built-in commands and skills, with their leading
+    /// `/`. Returns a warning for each alias that was dropped: an invalid
+    /// name, a name that would hide a command, an unknown target, or a cycle.
+    pub fn load(config: &BTreeMap<String, String>, commands: &[String]) -> (Self, Vec<String>) {
+        let mut warnings = Vec::new();
+        let mut raw = BTreeMap::new();
+        for (alias, expansion) in config {
+            let alias = normalize(alias);
+            let expansion = expansion.trim();
+            if alias.len() < 2 || alias.contains(char::is_whitespace) {
+                warnings.push(format!("Alias {:?} is not a single /word; ignored", alias));
+            } else if commands.contains(&alias) {
+                warnings.push(format!("Alias {} would hide the {} command; ignored", alias, alias));
+            } else if !expansion.starts_with('/') {
+                warnings.push(format!("Alias {} must expand to a /command, not {:?}; ignored", alias, expansion));
+            } else {
+                raw.insert(alias, expansion.to_string());
+            }
+        }
+
+        let mut entries = BTreeMap::new();
+        for (alias, expansion) in &raw {
+            match resolve_name(&raw, alias) {
+                Ok(target) if commands.contains(&target) => {
+                    entries.insert(alias.clone(), expansion.clone());
+                }
+                Ok(target) => warnings.push(format!("Alias {} points to unknown command {}; ignored", alias, target)),
+                Err(cycle) => warnings.push(format!("Alias {} is part of a cycle ({}); ignored", alias, cycle)),
+            }
+        }
+        (Self { entries }, warnings)
+    }
+
+    /// Expand an alias at the start of `input`, following aliases of aliases
+    ///
+    /// Input that doesn't start with an alias is returned unchanged.
+    pub fn resolve(&self, input: &str) -> Result<String, String> {
+        let mut resolved = input.trim().to_string();
+        let mut seen = HashSet::new();
+        loop {
+            let (name, args) = split_command(&resolved);
+            let Some(expansion) = self.entries.get(name) else {
+                return Ok(resolved);
+            };
+            if !seen.insert(name.to_string()) {
+                return Err(format!("Alias {} expands to itself", name));
+            }
+            resolved = if args.is_empty() {
+                expansion.clone()
+            } else {
+                format!("{} {}", expansion, args)
+            };
+        }
+    }
+
+    /// What an alias expands to
+    pub fn expansion(&self, alias: &str) -> Option<&str> {
+        self.entries.get(alias).map(String::as_str)
+    }
+
+    /// Aliases and their expansions, sorted by alias
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().map(|(alias, expansion)| (alias.as_str(), expansion.as_str()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// An alias name with its leading `/`
+fn normalize(alias: &str) -> String {
+    let alias = alias.trim();
+    if alias.starts_with('/') {
+        alias.to_string()
+    } else {
+        format!("/{}", alias)
+    }
+}
+
+/// Split `/name args` into the command name and its arguments
+fn split_command(input: &str) -> (&str, &str) {
+    match input.split_once(char::is_whitespace) {
+        Some((name, args)) => (name, args.trim()),
+        None => (input, ""),
+    }
+}
+
+/// Follow an alias through other aliases to the command it runs
+///
+/// Returns the chain as `/a -> /b -> /a` if it loops.
+fn resolve_name(raw: &BTreeMap<String, String>, alias: &str) -> Result<String, String> {
+    let mut chain = vec![alias.to_string()];
+    let mut name = alias.to_string();
+    while let Some(expansion) = raw.get(&name) {
+        name = split_command(expansion).0.to_string();
+        let looped = chain.contains(&name);
+        chain.push(name.clone());
+        if looped {
+            return Err(chain.join(" -> "));
+        }
+    }
+    Ok(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commands() -> Vec<String> {
+        ["/help", "/commit", "/test"].iter().map(|c| c.to_string()).collect()
+    }
+
+    fn config(entries: &[(&str, &str)]) -> BTreeMap<String, String> {
+        entries.iter().map(|(a, e)| (a.to_string(), e.to_string())).collect()
+    }
+
+    #[test]
+    fn test_resolve_appends_arguments() {
+        let (aliases, warnings) = Aliases::load(
+            &config(&[("/c", "/commit"), ("t", "/test --all"), ("/ta", "/t")]),
+            &commands(),
+        );
+        assert!(warnings.is_empty(), "{:?}", warnings);
+
+        assert_eq!(aliases.resolve("/c").unwrap(), "/commit");
+        assert_eq!(aliases.resolve("/c fix typo").unwrap(), "/commit fix typo");
+        assert_eq!(aliases.resolve("/t").unwrap(), "/test --all");
+        assert_eq!(aliases.resolve("/ta unit").unwrap(), "/test --all unit");
+        assert_eq!(aliases.resolve("/commit").unwrap(), "/commit");
+        assert_eq!(aliases.resolve("hello /c").unwrap(), "hello /c");
+        assert_eq!(aliases.expansion("/t"), Some("/test --all"));
+    }
+
+    #[test]
+    fn test_invalid_aliases_reported() {
+        let (aliases, warnings) = Aliases::load(
+            &config(&[
+                ("/x", "/y"),
+                ("/y", "/x --flag"),
+                ("/d", "/deploy"),
+                ("/help", "/commit"),
+                ("/n", "commit"),
+                ("/ok", "/commit"),
+            ]),
+            &commands(),
+        );
+
+        assert_eq!(aliases.iter().collect::<Vec<_>>(), vec![("/ok", "/commit")]);
+        assert_eq!(warnings.len(), 5, "{:?}", warnings);
+        assert!(warnings.iter().any(|w| w.contains("/d points to unknown command /deploy")));
+        assert!(warnings.iter().any(|w| w.contains("/x is part of a cycle (/x -> /y -> /x)")));
+        assert!(warnings.iter().any(|w| w.contains("would hide the /help command")));
+        assert!(warnings.iter().any(|w| w.contains("must expand to a /command")));
+    }
+}
@@ -0,0 +1,45 @@
+//! System clipboard for the ClipboardWrite tool
+//!
+//! Only built with the `clipboard` feature; without it the CLI has no
+//! ClipboardWrite tool.
+
+use std::sync::Arc;
+
+use cowork_core::tools::interaction::Clipboard;
+
+/// The system clipboard, if this build can write to it
+#[cfg(feature = "clipboard")]
+pub fn system_clipboard() -> Option<Arc<dyn Clipboard>> {
+    Some(Arc::new(ArboardClipboard::default()))
+}
+
+/// The system clipboard, if this build can write to it
+#[cfg(not(feature = "clipboard"))]
+pub fn system_clipboard() -> Option<Arc<dyn Clipboard>> {
+    None
+}
+
+/// Clipboard written through arboard
+///
+/// The handle is opened on first use and kept: on X11 the copied text is
+/// served by this process, and goes away with the handle.
+#[cfg(feature = "clipboard")]
+#[derive(Default)]
+struct ArboardClipboard {
+    handle: std::sync::Mutex<Option<arboard::Clipboard>>,
+}
+
+#[cfg(feature = "clipboard")]
+impl Clipboard for ArboardClipboard {
+    fn write_text(&self, text: &str) -> Result<(), String> {
+        let mut handle = self.handle.lock().map_err(|e| e.to_string())?;
+        if handle.is_none() {
+            *handle = Some(arboard::Clipboard::new().map_err(|e| e.to_string())?);
+        }
+        handle
+            .as_mut()
+            .expect("clipboard handle was just opened")
+            .set_text(text)
+            .map_err(|e| e.to_string())
+    }
+}
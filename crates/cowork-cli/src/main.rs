@@ -3,23 +3,61 @@
 //! This CLI uses the unified session architecture from cowork-core,
 //! sharing the same agent loop logic with the UI application.
 
+mod aliases;
+mod clipboard;
+mod notify;
 mod onboarding;
 mod tui;
 mod update;
 
+use std::io::IsTerminal;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
+use chrono::{Local, Utc};
 use clap::{Parser, Subcommand};
 use console::style;
+use aliases::Aliases;
+use notify::Notifier;
 use onboarding::OnboardingWizard;
 
-use cowork_core::config::ConfigManager;
-use cowork_core::provider::{catalog, has_api_key_configured};
+use cowork_core::config::{Config, ConfigManager, NotificationsConfig, PromptSystemConfig, RemoteTarget, TelemetryConfig};
+use cowork_core::context::{ContextGatherer, WorkspaceIndex, INDEX_FILE};
+use cowork_core::completion::PathCompleter;
+use cowork_core::credentials;
+use cowork_core::formatting::{format_approval_args, format_file_diff, format_tool_diff, use_color, PathDisplay};
+use cowork_core::i18n::{Localizer, Text};
+use cowork_core::provider::{
+    catalog, has_api_key_configured, parse_window, UsageGroupBy, UsageRow, UsageStore,
+};
 use cowork_core::orchestration::SystemPrompt;
-use cowork_core::prompt::{ComponentRegistry, TemplateVars, substitute_commands};
-use cowork_core::session::{SessionConfig, SessionInput, SessionManager, SessionOutput, ImageAttachment};
-use cowork_core::skills::SkillRegistry;
-use cowork_core::ToolApprovalConfig;
+use cowork_core::palette::Palette;
+use cowork_core::processes::{self, ProcessTracker};
+use cowork_core::telemetry;
+use cowork_core::prompt::{ComponentRegistry, PromptPipeline, TemplateVars, substitute_commands};
+use cowork_core::recipes::{self, RecipeRunner};
+use cowork_core::schedule::{RunHistory, RunRecord, RunStatus, RunningJobs, Schedule, ScheduleTimezone, ScheduledJob, Timetable};
+use cowork_core::templates::{load_template, SessionTemplate};
+use cowork_core::session::{
+    autosave_interval, delete_saved_session, fork_point_before_user_turn, import_sessions, load_audit_log, load_session,
+    prune_in_background, prune_saved_sessions, tool_usage_report, AuditEvent, AuditRecord, AuditSummary, ImageAttachment, ImportOutcome, ImportSource,
+    Isolation, SavedSession, SessionConfig, SessionInput, SessionManager, SessionOutput, SessionReplay, ToolCounts, ToolDoneStatus,
+};
+use cowork_core::skills::fork::FORK_TURNS_BACK_KEY;
+use cowork_core::skills::merge::MERGE_WORKTREE_KEY;
+use cowork_core::skills::compact::COMPACT_KEY;
+use cowork_core::skills::context::{TOOL_PROFILE_KEY, TOOL_USAGE_KEY};
+use cowork_core::skills::pin::{PIN_REPLY_KEY, PIN_SESSION_KEY};
+use cowork_core::skills::title::SESSION_TITLE_KEY;
+use cowork_core::skills::plans::APPROVE_PLAN_KEY;
+use cowork_core::skills::recipe::RUN_RECIPE_KEY;
+use cowork_core::skills::retry::{EDIT_LAST_CONTENT_KEY, RETRY_TEMPERATURE_KEY};
+use cowork_core::skills::settings::GENERATION_PARAMS_KEY;
+use cowork_core::skills::{SkillContext, SkillRegistry, FOLLOW_UP_PROMPT_KEY};
+use cowork_core::tools::filesystem::WorkspaceRoots;
+use cowork_core::tools::remote::RemoteWorkspace;
+use cowork_core::mcp_server::ToolServer;
+use cowork_core::{RuleDecision, ToolApprovalConfig, ToolProfile, ToolRegistryBuilder, ToolRule, TurnOverrides};
 // Import for ! prefix bash mode
 use cowork_core::tools::shell::ExecuteCommand;
 use cowork_core::tools::Tool;
@@ -31,8 +69,8 @@ use crossterm::{
 };
 use ratatui::prelude::*;
 use tui::{
-    App, Event, EventHandler, KeyAction, Message, Modal,
-    handle_key_approval, handle_key_normal, handle_key_question,
+    App, Binding, Event, EventHandler, KeyAction, Keymap, Message, Modal,
+    handle_key_approval, handle_key_question, handle_key_result,
 };
 
 #[derive(Parser)]
@@ -44,9 +82,9 @@ struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
 
-    /// Workspace directory
+    /// Workspace directory; repeat to add more roots (relative paths resolve in the first)
     #[arg(short, long, default_value = ".")]
-    workspace: PathBuf,
+    workspace: Vec<PathBuf>,
 
     /// Verbose output
     #[arg(short, long)]
@@ -64,16 +102,52 @@ struct Cli {
     #[arg(long)]
     auto_approve: bool,
 
+    /// Work in an isolated git worktree, merged back with /merge
+    /// (overrides `[general] isolation`)
+    #[arg(long)]
+    worktree: bool,
+
+    /// Tools offered to the model: minimal, coding, research, full, or a
+    /// profile from `[tools.profiles]` (overrides `[tools] profile`)
+    #[arg(long = "tools", value_name = "PROFILE")]
+    tool_profile: Option<String>,
+
     /// Execute a single prompt and exit (non-interactive mode)
     #[arg(long)]
     one_shot: Option<String>,
+
+    /// With --one-shot: tools that run without asking, comma-separated; every
+    /// other tool call is rejected. Bash can be scoped by command: `Bash:cargo*`
+    #[arg(long, value_delimiter = ',', requires = "one_shot")]
+    approve_tools: Vec<ToolRule>,
+
+    /// With --one-shot: tools rejected without asking, comma-separated (same
+    /// syntax as --approve-tools)
+    #[arg(long, value_delimiter = ',', requires = "one_shot")]
+    deny_tools: Vec<ToolRule>,
 }
 
+/// Exit code of a one-shot run in which a tool call was rejected without asking
+const EXIT_TOOLS_REJECTED: i32 = 3;
+
 #[derive(Subcommand)]
 enum Commands {
     /// Interactive chat mode
     Chat,
 
+    /// Start a chat from a session template (.cowork/templates, or a built-in:
+    /// code-review, refactor, research); --provider, --model and --tools
+    /// override the template's settings
+    New {
+        /// Template name
+        #[arg(long)]
+        template: String,
+
+        /// Arguments filled into the template's opening message
+        #[arg(trailing_var_arg = true)]
+        args: Vec<String>,
+    },
+
     /// Show available tools
     Tools,
 
@@ -94,6 +168,113 @@ enum Commands {
     /// List prompt system components (agents, commands, skills)
     #[command(subcommand)]
     Components(ComponentCommands),
+
+    /// List and check the workspace's recipes (.cowork/recipes)
+    #[command(subcommand)]
+    Recipe(RecipeCommands),
+
+    /// Show token usage and estimated cost across sessions
+    Usage {
+        /// Only include usage from this recent window (e.g. 24h, 7d, 2w)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Group rows by model, day, session, or purpose; or show tool calls with `tool`
+        #[arg(long, default_value = "model")]
+        by: UsageBy,
+    },
+
+    /// Replay a saved session against another model and compare the results
+    Replay {
+        /// Saved session file, or the ID of a session in the sessions directory
+        session: String,
+
+        /// Model to replay with
+        #[arg(long)]
+        model: String,
+
+        /// Provider to replay with (defaults to the configured provider)
+        #[arg(long)]
+        provider: Option<String>,
+
+        /// Report path without extension; `.md` and `.json` are written
+        /// (default: replay-<session id>)
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Build or update the workspace index used by CodebaseSearch
+    Index {
+        /// Discard the existing index and read every file again
+        #[arg(long)]
+        rebuild: bool,
+    },
+
+    /// Delete or prune saved sessions
+    #[command(subcommand)]
+    Sessions(SessionsCommands),
+
+    /// Import sessions from another assistant into the saved sessions
+    #[command(subcommand)]
+    Import(ImportCommands),
+
+    /// Keep provider API keys in the OS keychain
+    #[command(subcommand)]
+    Auth(AuthCommands),
+
+    /// Show a session's audit log of approvals and executed mutations
+    Audit {
+        /// ID of the session
+        session_id: String,
+
+        /// Print the raw JSONL records
+        #[arg(long, conflicts_with = "summary")]
+        json: bool,
+
+        /// Print counts by event and by who decided approvals
+        #[arg(long)]
+        summary: bool,
+    },
+
+    /// List what the desktop app's command palette offers: skills, slash
+    /// commands, tools, agents and recent sessions
+    Palette {
+        /// Print the items as a JSON array
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show the language servers, MCP servers and background shells cowork
+    /// runs, and any a crashed run left behind
+    Doctor {
+        /// Kill the processes crashed runs left behind
+        #[arg(long)]
+        kill_orphans: bool,
+    },
+
+    /// Serve the workspace's tools to MCP clients (e.g. Claude Desktop)
+    McpServe {
+        /// Serve on stdin/stdout (the default)
+        #[arg(long, conflicts_with = "port")]
+        stdio: bool,
+
+        /// Serve newline-delimited JSON-RPC on this local TCP port, one client at a time
+        #[arg(long)]
+        port: Option<u16>,
+
+        /// Run tools that would need approval instead of refusing them
+        /// (tools the organization policy requires approval for are still refused)
+        #[arg(long)]
+        yolo: bool,
+    },
+
+    /// Run session templates unattended on a cron schedule
+    #[command(subcommand)]
+    Schedule(ScheduleCommands),
+
+    /// Inspect the metrics recorded with [telemetry] enabled
+    #[command(subcommand)]
+    Metrics(MetricsCommands),
 }
 
 #[derive(Subcommand)]
@@ -135,6 +316,150 @@ enum ComponentCommands {
     All,
 }
 
+#[derive(Subcommand)]
+enum RecipeCommands {
+    /// List the workspace's recipes
+    List,
+
+    /// Check a recipe for unknown tools and unbound variables without running it
+    Validate {
+        /// Recipe name
+        name: String,
+
+        /// Print the steps as they would run with --args
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Arguments to bind for a dry run, as given to /recipe run
+        #[arg(long, default_value = "", requires = "dry_run")]
+        args: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum SessionsCommands {
+    /// Delete the saved sessions the [sessions] retention limits exclude
+    Prune {
+        /// List what would be deleted without deleting it
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Also delete the pruned sessions' audit logs
+        #[arg(long)]
+        with_audit_log: bool,
+    },
+
+    /// Delete a saved session with its attachments and scratch directory
+    Delete {
+        /// ID of the session
+        session_id: String,
+
+        /// Also delete the session's audit log
+        #[arg(long)]
+        with_audit_log: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum MetricsCommands {
+    /// Print the current counters and histogram totals
+    Dump,
+}
+
+#[derive(Subcommand)]
+enum ScheduleCommands {
+    /// Schedule a template, e.g. `cowork schedule add "0 3 * * *" --template deps`
+    Add {
+        /// Cron expression: minute, hour, day of month, month and day of
+        /// week, in `[schedule] timezone`
+        cron: String,
+
+        /// Session template the runs start from
+        #[arg(long)]
+        template: String,
+
+        /// Arguments filled into the template's opening message, or the
+        /// message itself when the template has none
+        #[arg(long)]
+        prompt: Option<String>,
+
+        /// Workspace the runs work in (defaults to -w)
+        #[arg(long = "workspace", value_name = "PATH")]
+        job_workspace: Option<PathBuf>,
+
+        /// Name of the job (defaults to <template>-<n>)
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Tools that run without asking, comma-separated (as with
+        /// --one-shot); every other call that needs approval is rejected
+        #[arg(long, value_delimiter = ',')]
+        approve_tools: Vec<ToolRule>,
+
+        /// Tools rejected without asking, comma-separated
+        #[arg(long, value_delimiter = ',')]
+        deny_tools: Vec<ToolRule>,
+    },
+
+    /// List the scheduled jobs and when they run next
+    List,
+
+    /// Remove a scheduled job
+    Remove {
+        /// Name of the job
+        id: String,
+    },
+
+    /// Run the scheduled jobs as they come due, until interrupted
+    Daemon,
+
+    /// Show how past runs ended
+    History {
+        /// Only this job's runs
+        id: Option<String>,
+
+        /// Most recent runs to show
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+}
+
+#[derive(Subcommand)]
+enum ImportCommands {
+    /// Import Claude Code sessions: .jsonl files, or directories holding them
+    /// (such as ~/.claude/projects)
+    ClaudeCode {
+        /// Session files or directories
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+
+        /// List the sessions that would be imported without writing them
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum AuthCommands {
+    /// Store a provider's API key in the OS keychain (prompts for it)
+    Set {
+        /// Provider ID, e.g. anthropic
+        provider: String,
+    },
+
+    /// Delete a provider's API key from the OS keychain
+    Remove {
+        /// Provider ID, e.g. anthropic
+        provider: String,
+    },
+
+    /// Offer to move API keys kept in plain text in the config into the OS keychain
+    Migrate {
+        /// Only this provider (default: every provider with a plain-text key)
+        provider: Option<String>,
+    },
+}
+
 /// Setup logging with stderr output and file logging for errors
 ///
 /// Logs are written to:
@@ -203,18 +528,10 @@ async fn main() -> anyhow::Result<()> {
     // Setup logging with both stderr and file output
     setup_logging(cli.verbose);
 
-    // Use dunce::canonicalize to avoid UNC path prefix on Windows (\\?\)
-    // If canonicalize fails, ensure we at least have an absolute path
-    let workspace = dunce::canonicalize(&cli.workspace).unwrap_or_else(|_| {
-        if cli.workspace.is_absolute() {
-            cli.workspace.clone()
-        } else {
-            // Make relative path absolute using current directory
-            std::env::current_dir()
-                .map(|cwd| cwd.join(&cli.workspace))
-                .unwrap_or(cli.workspace.clone())
-        }
-    });
+    // The first -w is the workspace; any others are more roots for the file tools
+    let mut paths = cli.workspace.iter().map(|path| absolute_workspace(path));
+    let workspace = paths.next().unwrap_or_else(|| absolute_workspace(Path::new(".")));
+    let roots = WorkspaceRoots::new(workspace.clone()).with_additional(paths);
 
     // Load config to get default provider
     let config_manager = ConfigManager::new().ok();
@@ -230,43 +547,140 @@ async fn main() -> anyhow::Result<()> {
     // Validate provider ID
     let provider_id = validate_provider_id(&provider_str);
 
+    // Apply the saved session retention limits, unless pruning explicitly
+    if !matches!(cli.command, Some(Commands::Sessions(_)) | Some(Commands::McpServe { .. }))
+        && let Some(cm) = &config_manager
+    {
+        prune_in_background(cm.config().sessions.clone());
+    }
+
+    // Metrics are only recorded with [telemetry] enabled; written out on exit
+    let telemetry_config = config_manager.as_ref().map(|cm| cm.config().telemetry.clone()).unwrap_or_default();
+    let _telemetry = telemetry::install_from_config(&telemetry_config);
+
     // Handle one-shot mode
     if let Some(prompt) = cli.one_shot {
-        return run_one_shot(&workspace, provider_id, cli.model.as_deref(), &prompt, cli.auto_approve).await;
+        let rules = (cli.approve_tools, cli.deny_tools);
+        return run_one_shot(
+            &roots,
+            provider_id,
+            cli.model.as_deref(),
+            &prompt,
+            cli.auto_approve,
+            rules,
+            cli.tool_profile.as_deref(),
+        )
+        .await;
     }
 
-    // Apply staged update if available (skip if user is running `update`)
-    if !matches!(cli.command, Some(Commands::Update { .. })) {
+    // Apply staged update if available (skip if user is running `update`,
+    // or serving MCP, where nothing may touch stdout)
+    let skip_update = matches!(cli.command, Some(Commands::Update { .. } | Commands::McpServe { .. }));
+    if !skip_update {
         // apply_staged_update prints its own status messages
         let _ = update::apply_staged_update();
     }
 
     // Background version check: downloads eligible updates to staging
-    let _version_check = if !matches!(cli.command, Some(Commands::Update { .. })) {
+    let _version_check = if !skip_update {
         Some(update::spawn_startup_check())
     } else {
         None
     };
 
     match cli.command {
-        Some(Commands::Chat) => run_chat(&workspace, provider_id, cli.model.as_deref(), cli.auto_approve).await?,
+        Some(Commands::Chat) => run_chat(&roots, provider_id, cli.model.as_deref(), cli.auto_approve, cli.worktree, cli.tool_profile.as_deref(), None).await?,
+        Some(Commands::New { template, args }) => {
+            let config = config_manager.as_ref().map(|cm| cm.config().clone()).unwrap_or_default();
+            let mut template = load_template(&workspace, &template, &config)?;
+            // Flags on the command line win over the template's settings
+            if let Some(provider) = &cli.provider {
+                template.provider = Some(provider.clone());
+                template.model = None;
+            }
+            if let Some(model) = &cli.model {
+                template.model = Some(model.clone());
+            }
+            if let Some(profile) = &cli.tool_profile {
+                template.tool_profile = Some(profile.clone());
+            }
+            let provider_id = template.provider.as_deref().map(validate_provider_id).unwrap_or(provider_id);
+            let args = args.join(" ");
+            run_chat(
+                &roots,
+                provider_id,
+                template.model.as_deref(),
+                cli.auto_approve,
+                cli.worktree,
+                template.tool_profile.as_deref(),
+                Some((&template, &args)),
+            )
+            .await?
+        }
         Some(Commands::Tools) => show_tools(),
         Some(Commands::Config) => show_config(&workspace),
         Some(Commands::Update { check }) => update::run_update(check).await?,
         Some(Commands::Plugin(cmd)) => handle_plugin_command(&workspace, cmd)?,
         Some(Commands::Components(cmd)) => handle_component_command(&workspace, cmd)?,
-        None => run_chat(&workspace, provider_id, cli.model.as_deref(), cli.auto_approve).await?,
+        Some(Commands::Recipe(cmd)) => handle_recipe_command(&workspace, cmd)?,
+        Some(Commands::Usage { since, by: UsageBy::Tokens(by) }) => show_usage(since.as_deref(), by)?,
+        Some(Commands::Usage { since, by: UsageBy::Tool }) => show_tool_usage(since.as_deref())?,
+        Some(Commands::Replay { session, model, provider, output }) => {
+            let provider_id = provider.as_deref().map(validate_provider_id).unwrap_or(provider_id);
+            run_replay(&workspace, &session, provider_id, &model, output).await?
+        }
+        Some(Commands::Index { rebuild }) => build_index(&workspace, rebuild)?,
+        Some(Commands::Sessions(cmd)) => handle_sessions_command(config_manager.as_ref(), cmd)?,
+        Some(Commands::Import(cmd)) => handle_import_command(&workspace, cmd)?,
+        Some(Commands::Auth(cmd)) => handle_auth_command(cmd)?,
+        Some(Commands::Audit { session_id, json, summary }) => show_audit(&session_id, json, summary)?,
+        Some(Commands::Doctor { kill_orphans }) => show_doctor(kill_orphans),
+        Some(Commands::Palette { json }) => show_palette(&workspace, json)?,
+        Some(Commands::McpServe { stdio: _, port, yolo }) => run_mcp_serve(&workspace, port, yolo).await?,
+        Some(Commands::Schedule(cmd)) => handle_schedule_command(&workspace, cmd).await?,
+        Some(Commands::Metrics(MetricsCommands::Dump)) => dump_metrics(&telemetry_config)?,
+        None => run_chat(&roots, provider_id, cli.model.as_deref(), cli.auto_approve, cli.worktree, cli.tool_profile.as_deref(), None).await?,
     }
 
     Ok(())
 }
 
+/// Make a `-w` path absolute
+///
+/// Uses dunce::canonicalize to avoid UNC path prefix on Windows (\\?\).
+/// If canonicalize fails, ensure we at least have an absolute path.
+fn absolute_workspace(path: &Path) -> PathBuf {
+    dunce::canonicalize(path).unwrap_or_else(|_| {
+        if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            // Make relative path absolute using current directory
+            std::env::current_dir()
+                .map(|cwd| cwd.join(path))
+                .unwrap_or(path.to_path_buf())
+        }
+    })
+}
+
 /// Build the system prompt with all template variables properly substituted
-fn build_system_prompt(workspace: &Path, model_info: Option<&str>) -> String {
-    let mut vars = TemplateVars {
-        working_directory: workspace.display().to_string(),
-        is_git_repo: workspace.join(".git").exists(),
-        ..Default::default()
+/// and the `[prompt]` overrides applied (a remote workspace is described by
+/// its location only)
+fn build_system_prompt(
+    roots: &WorkspaceRoots,
+    model_info: Option<&str>,
+    prompt_config: &PromptSystemConfig,
+    remote: Option<&RemoteTarget>,
+) -> String {
+    let workspace = roots.primary();
+    let mut vars = match remote {
+        Some(target) => TemplateVars {
+            working_directory: target.to_string(),
+            ..Default::default()
+        },
+        None => TemplateVars {
+            additional_roots: roots.additional().to_vec(),
+            ..TemplateVars::gather(workspace)
+        },
     };
 
     // Get git status if in a repo
@@ -302,45 +716,169 @@ fn build_system_prompt(workspace: &Path, model_info: Option<&str>) -> String {
         vars.model_info = info.to_string();
     }
 
-    SystemPrompt::new()
-        .with_template_vars(vars)
-        .build()
+    let mut prompt = SystemPrompt::new()
+        .with_prompt_config(prompt_config, workspace)
+        .with_template_vars(vars);
+    if remote.is_none() {
+        prompt = prompt.with_memory(&ContextGatherer::new(workspace).memory_hierarchy());
+    }
+    prompt.build()
+}
+
+/// Load agents, commands, skills and hooks for a workspace
+///
+/// Files that fail to load are logged here (and listed by `components`),
+/// so a broken agent definition shows up before anything tries to use it.
+fn load_components(workspace: &Path) -> ComponentRegistry {
+    ComponentRegistry::for_workspace(workspace).unwrap_or_else(|e| {
+        tracing::warn!("Failed to load components: {}", e);
+        ComponentRegistry::with_builtins()
+    })
 }
 
 /// Run a single prompt non-interactively (for scripting/testing)
+///
+/// `rules` are the `--approve-tools` and `--deny-tools` rules. Nothing can
+/// answer an approval prompt, so a call that asks is rejected; without a
+/// terminal the run refuses to start if any tool could ask. The process exits
+/// with `EXIT_TOOLS_REJECTED` if any call was rejected.
 async fn run_one_shot(
-    workspace: &Path,
+    roots: &WorkspaceRoots,
     provider_id: &str,
     model: Option<&str>,
     prompt: &str,
     auto_approve: bool,
+    rules: (Vec<ToolRule>, Vec<ToolRule>),
+    tool_profile: Option<&str>,
 ) -> anyhow::Result<()> {
     // Load config
     let config_manager = ConfigManager::new()?;
-    let api_key = cowork_core::provider::get_api_key(&config_manager, provider_id);
-    let base_url = config_manager
-        .config()
-        .get_provider(provider_id)
-        .and_then(|p| p.base_url.clone());
-
-    // Create session config
-    let workspace = workspace.to_path_buf();
-    let model = model.map(|s| s.to_string());
-    let approval_config = if auto_approve {
+    let tool_profile = resolve_tool_profile(&config_manager, tool_profile)?;
+    let workspace = roots.primary().to_path_buf();
+    let mut approval_config = if auto_approve {
         ToolApprovalConfig::trust_all()
     } else {
         ToolApprovalConfig::default()
+    }
+    .with_trusted_hosts(&config_manager.config().approval.trusted_hosts);
+    approval_config.apply_policy(&config_manager.policy());
+    let (approve_tools, deny_tools) = rules;
+    let approval_config = approval_config.with_rules(approve_tools, deny_tools);
+
+    // A prompt nobody can answer would only end in a rejection, so a script
+    // has to settle every tool up front
+    if !std::io::stdin().is_terminal() {
+        let registry = ToolRegistryBuilder::new(workspace.clone())
+            .with_provider(provider_id)
+            .with_web_search_config(config_manager.config().web_search.clone())
+            .with_policy(config_manager.policy())
+            .with_tool_profile(tool_profile.clone())
+            .build();
+        let mut asking: Vec<String> = registry
+            .list()
+            .into_iter()
+            .map(|tool| tool.name)
+            .filter(|name| approval_config.could_prompt(name))
+            .collect();
+        if !asking.is_empty() {
+            asking.sort();
+            anyhow::bail!(
+                "These tools could ask for approval, which can't be given without a terminal: {}\n\
+                 Settle them with --approve-tools and --deny-tools, or pass --auto-approve",
+                asking.join(", ")
+            );
+        }
+    }
+
+    let run = OneShot {
+        roots,
+        provider_id,
+        model,
+        prompt,
+        auto_approve,
+        approval_config,
+        tool_profile,
+        template: None,
+        session_id: ONE_SHOT_SESSION_ID.to_string(),
     };
+    let outcome = execute_one_shot(&config_manager, run).await?;
+    if outcome.rejected > 0 {
+        eprintln!("{} tool call(s) rejected without asking", outcome.rejected);
+        std::process::exit(EXIT_TOOLS_REJECTED);
+    }
+    Ok(())
+}
+
+/// ID of the one-shot session
+const ONE_SHOT_SESSION_ID: &str = "cli-oneshot";
+
+/// A single prompt run to the end of its turn, with nobody to ask
+struct OneShot<'a> {
+    roots: &'a WorkspaceRoots,
+    provider_id: &'a str,
+    model: Option<&'a str>,
+    prompt: &'a str,
+    auto_approve: bool,
+    approval_config: ToolApprovalConfig,
+    tool_profile: ToolProfile,
+    /// Template whose settings apply on top of the config's
+    template: Option<&'a SessionTemplate>,
+    session_id: String,
+}
+
+/// How a one-shot turn ended
+struct OneShotOutcome {
+    /// Tool calls rejected without asking
+    rejected: usize,
+    /// Errors the session reported
+    errors: Vec<String>,
+}
+
+/// Run a one-shot prompt, printing its progress
+///
+/// Calls that need approval are settled by the approval config's rules and
+/// rejected otherwise (unless `auto_approve`); questions are answered with
+/// their first option.
+async fn execute_one_shot(config_manager: &ConfigManager, run: OneShot<'_>) -> anyhow::Result<OneShotOutcome> {
+    let OneShot {
+        roots,
+        provider_id,
+        model,
+        prompt,
+        auto_approve,
+        approval_config,
+        tool_profile,
+        template,
+        session_id,
+    } = run;
+    let workspace = roots.primary().to_path_buf();
+    let model = model.map(|s| s.to_string());
+    let api_key = cowork_core::provider::get_api_key(config_manager, provider_id);
+    let base_url = config_manager
+        .config()
+        .get_provider(provider_id)
+        .and_then(|p| p.base_url.clone());
 
     // Build system prompt with template variables
-    let system_prompt = build_system_prompt(&workspace, model.as_deref());
+    let system_prompt = build_system_prompt(
+        roots,
+        model.as_deref(),
+        &config_manager.config().prompt,
+        config_manager.config().remote.as_ref(),
+    );
 
     // Create session config
     let mut session_config = SessionConfig::new(workspace.clone())
         .with_provider(provider_id)
         .with_approval_config(approval_config.clone())
         .with_system_prompt(system_prompt)
-        .with_web_search_config(config_manager.config().web_search.clone());
+        .with_web_search_config(config_manager.config().web_search.clone())
+        .with_limits(config_manager.config().limits.clone())
+        .with_env_policy(config_manager.config().env.policy_for(&workspace))
+        .with_network_policy(config_manager.config().web.network_policy())
+        .with_policy(config_manager.policy())
+        .with_tool_profile(tool_profile)
+        .with_usage_store(Arc::new(UsageStore::open_default()));
     if let Some(ref m) = model {
         session_config = session_config.with_model(m.clone());
     }
@@ -350,11 +888,33 @@ async fn run_one_shot(
     if let Some(ref url) = base_url {
         session_config = session_config.with_base_url(url.clone());
     }
+    if let Some(provider) = config_manager.config().get_provider(provider_id) {
+        session_config = session_config.with_request_timeout(provider.request_timeout(false));
+    }
+    session_config = session_config.with_component_registry(Arc::new(load_components(&workspace)));
+    if let Some(target) = config_manager.config().remote.clone() {
+        session_config = session_config.with_remote(Arc::new(RemoteWorkspace::new(target)));
+    } else {
+        session_config = session_config.with_additional_roots(roots.additional().to_vec());
+    }
+    if let Some(clipboard) = clipboard::system_clipboard() {
+        session_config = session_config.with_clipboard(clipboard);
+    }
+    if let Some(template) = template {
+        template.apply(&mut session_config, config_manager.config())?;
+    }
 
     // Create session manager
     let (session_manager, mut output_rx) = SessionManager::with_config(session_config);
-
-    let session_id = "cli-oneshot";
+    let session_id = session_id.as_str();
+
+    // Approvals and questions are answered automatically here, so only a
+    // long-running turn is worth an alert
+    let mut notifier = Notifier::new(NotificationsConfig {
+        approval_required: false,
+        question_asked: false,
+        ..config_manager.config().notifications.clone()
+    });
 
     // Parse @path image attachments from the prompt
     let (cleaned_prompt, images) = ImageAttachment::parse_from_text(prompt, &workspace);
@@ -371,8 +931,14 @@ async fn run_one_shot(
             .await?;
     }
 
+    // Tool calls rejected without asking, for the exit code
+    let mut rejected = 0;
+    let mut errors = Vec::new();
+    let paths = PathDisplay::new(workspace.clone());
+
     // Process outputs until idle
-    while let Some((_, output)) = output_rx.recv().await {
+    while let Some((sid, output)) = output_rx.recv().await {
+        notifier.observe(&sid, &output);
         match output {
             SessionOutput::AssistantMessage { content, .. } => {
                 // Content already has token usage appended by core (when available)
@@ -381,6 +947,18 @@ async fn run_one_shot(
             SessionOutput::ToolStart { name, .. } => {
                 println!("  {} {}", style("[Executing:").dim(), style(&name).yellow());
             }
+            SessionOutput::ToolCall { diff: Some(diff), .. } => {
+                for line in format_file_diff(&diff, use_color()) {
+                    println!("    {}", line);
+                }
+            }
+            SessionOutput::ToolDone { name, status: ToolDoneStatus::Cancelled, .. } => {
+                println!("  {} {}", style("✗").yellow(), style(format!("{} cancelled", name)).dim());
+            }
+            SessionOutput::ToolDone { status: ToolDoneStatus::Rejected, output, .. } => {
+                rejected += 1;
+                println!("  {} {}", style("✗").red(), style(output).dim());
+            }
             SessionOutput::ToolDone { name, success, .. } => {
                 // Only show status, not the full result
                 if success {
@@ -389,16 +967,59 @@ async fn run_one_shot(
                     println!("  {} {}", style("✗").red(), style(format!("{} failed", name)).dim());
                 }
             }
-            SessionOutput::ToolPending { id, name, arguments, .. } => {
-                // In one-shot mode with auto_approve=false, we need to handle approval
-                if auto_approve {
+            SessionOutput::ToolPending { id, name, arguments, policy_required, security_warning, generated_files, .. } => {
+                // In one-shot mode with auto_approve=false, we need to handle approval.
+                // The rules settle subagents' calls here; the session's own never get this far.
+                let decision = approval_config.decide(&name, &arguments);
+                if let RuleDecision::Deny(reason) = decision {
+                    rejected += 1;
+                    println!("{}: {}", style("Tool rejected").yellow(), reason);
+                    session_manager
+                        .push_message(session_id, SessionInput::reject_tool(&id, Some(reason)))
+                        .await?;
+                } else if policy_required {
+                    rejected += 1;
+                    // --auto-approve can't stand in for the person the policy asks for
+                    println!("{}: {} (requires approval by organization policy)", style("Tool rejected").yellow(), name);
+                    session_manager
+                        .push_message(
+                            session_id,
+                            SessionInput::reject_tool(&id, Some("Organization policy requires interactive approval".to_string())),
+                        )
+                        .await?;
+                } else if let Some(warning) = security_warning {
+                    rejected += 1;
+                    // Nobody is here to look at it, and --auto-approve doesn't cover it
+                    println!("{}: {} ({})", style("Tool rejected").red(), name, warning);
+                    session_manager
+                        .push_message(
+                            session_id,
+                            SessionInput::reject_tool(&id, Some(format!("Needs interactive approval: {}", warning))),
+                        )
+                        .await?;
+                } else if let Some(file) = generated_files.first() {
+                    rejected += 1;
+                    // Changing a generated file is never auto-approved either
+                    println!("{}: {} ({})", style("Tool rejected").yellow(), name, file);
+                    session_manager
+                        .push_message(
+                            session_id,
+                            SessionInput::reject_tool(&id, Some(format!("Needs interactive approval: {}", file))),
+                        )
+                        .await?;
+                } else if auto_approve || matches!(decision, RuleDecision::Approve(_)) {
                     session_manager
                         .push_message(session_id, SessionInput::approve_tool(&id))
                         .await?;
                 } else {
                     // Show tool and auto-reject in non-interactive one-shot mode
+                    rejected += 1;
                     println!("{}: {} (auto-rejected in one-shot mode)", style("Tool pending").yellow(), name);
-                    println!("  Args: {}", serde_json::to_string_pretty(&arguments).unwrap_or_default());
+                    let details = format_tool_diff(&name, &arguments, use_color(), &paths)
+                        .unwrap_or_else(|| format_approval_args(&name, &arguments, &paths));
+                    for line in details {
+                        println!("  {}", line);
+                    }
                     session_manager
                         .push_message(session_id, SessionInput::reject_tool(&id, Some("Non-interactive mode".to_string())))
                         .await?;
@@ -428,10 +1049,31 @@ async fn run_one_shot(
                     .push_message(session_id, SessionInput::answer_question(&request_id, answers))
                     .await?;
             }
-            SessionOutput::Error { message } => {
+            SessionOutput::Error { message, .. } => {
                 println!("{}", style(format!("Error: {}", message)).red());
+                errors.push(message);
+            }
+            SessionOutput::Warning { message, .. } => {
+                println!("{}", style(format!("Warning: {}", message)).yellow());
+            }
+            SessionOutput::Refusal { reason, message, .. } => {
+                println!("{}", style(format!("Response refused ({})", reason)).red());
+                if let Some(message) = message {
+                    println!("{}", message);
+                }
+                errors.push(format!("Response refused ({})", reason));
+            }
+            SessionOutput::SelfReview { findings, .. } => {
+                if findings.is_empty() {
+                    println!("{}", style("Self-review: no gaps found").dim());
+                } else {
+                    println!("{}", style(format!("Self-review found {} gap(s):", findings.len())).yellow());
+                    for finding in &findings {
+                        println!("  - {}", finding);
+                    }
+                }
             }
-            SessionOutput::Idle => {
+            SessionOutput::Idle { .. } => {
                 // Done processing
                 break;
             }
@@ -442,14 +1084,17 @@ async fn run_one_shot(
     // Stop the session
     session_manager.stop_session(session_id)?;
 
-    Ok(())
+    Ok(OneShotOutcome { rejected, errors })
 }
 
 async fn run_chat(
-    workspace: &Path,
+    roots: &WorkspaceRoots,
     cli_provider_id: &str,
     model: Option<&str>,
     auto_approve: bool,
+    worktree: bool,
+    tool_profile: Option<&str>,
+    template: Option<(&SessionTemplate, &str)>,
 ) -> anyhow::Result<()> {
     // Load config
     let mut config_manager = ConfigManager::new()?;
@@ -473,7 +1118,7 @@ async fn run_chat(
 
     // Check if API key is configured - show setup instructions if not
     if !has_api_key_configured(&config_manager, provider_id) {
-        show_setup_instructions(provider_id);
+        show_setup_instructions(provider_id, Localizer::new(config_manager.config().general.locale()));
         return Ok(());
     }
 
@@ -485,23 +1130,48 @@ async fn run_chat(
         .and_then(|p| p.base_url.clone());
 
     // Create session config
-    let workspace_path = workspace.to_path_buf();
+    let workspace_path = roots.primary().to_path_buf();
     let model = model.map(|s| s.to_string());
-    let approval_config = if auto_approve {
+    let mut approval_config = if auto_approve {
         ToolApprovalConfig::trust_all()
     } else {
         ToolApprovalConfig::default()
-    };
+    }
+    .with_trusted_hosts(&config_manager.config().approval.trusted_hosts);
+    approval_config.apply_policy(&config_manager.policy());
+    let isolation = if worktree { Isolation::Worktree } else { config_manager.config().general.isolation };
+    let tool_profile = resolve_tool_profile(&config_manager, tool_profile)?;
 
     // Build system prompt with template variables
-    let system_prompt = build_system_prompt(&workspace_path, model.as_deref());
+    let system_prompt = build_system_prompt(
+        roots,
+        model.as_deref(),
+        &config_manager.config().prompt,
+        config_manager.config().remote.as_ref(),
+    );
 
     // Create session config
     let mut session_config = SessionConfig::new(workspace_path.clone())
         .with_provider(provider_id)
         .with_approval_config(approval_config.clone())
         .with_system_prompt(system_prompt)
-        .with_web_search_config(config_manager.config().web_search.clone());
+        .with_web_search_config(config_manager.config().web_search.clone())
+        .with_limits(config_manager.config().limits.clone())
+        .with_env_policy(config_manager.config().env.policy_for(&workspace_path))
+        .with_network_policy(config_manager.config().web.network_policy())
+        .with_policy(config_manager.policy())
+        .with_auto_memory(config_manager.config().general.auto_memory)
+        .with_self_review(config_manager.config().general.self_review)
+        .with_self_review_threshold(config_manager.config().general.self_review_threshold)
+        .with_strict_tools(config_manager.config().general.strict_tools)
+        .with_keep_scratch(config_manager.config().general.keep_scratch)
+        .with_autosave_interval(autosave_interval(config_manager.config().general.autosave_secs))
+        .with_isolation(isolation)
+        .with_tool_profile(tool_profile)
+        .with_compact_tool_descriptions(config_manager.config().tools.compact_descriptions)
+        .with_prompt_pipeline(PromptPipeline::from_config(&config_manager.config().prompt.input))
+        .with_summarizer(config_manager.config().summarizer.clone(), config_manager.config().summarizer_provider())
+        .with_usage_store(Arc::new(UsageStore::open_default()));
     if let Some(ref m) = model {
         session_config = session_config.with_model(m.clone());
     }
@@ -511,28 +1181,77 @@ async fn run_chat(
     if let Some(ref url) = base_url {
         session_config = session_config.with_base_url(url.clone());
     }
+    if let Some(provider) = config_manager.config().get_provider(provider_id) {
+        session_config = session_config.with_request_timeout(provider.request_timeout(false));
+    }
+    session_config = session_config.with_component_registry(Arc::new(load_components(&workspace_path)));
+    if let Some(target) = config_manager.config().remote.clone() {
+        session_config = session_config.with_remote(Arc::new(RemoteWorkspace::new(target)));
+    } else {
+        session_config = session_config.with_additional_roots(roots.additional().to_vec());
+    }
+    if let Some(clipboard) = clipboard::system_clipboard() {
+        session_config = session_config.with_clipboard(clipboard);
+    }
+    // A template's settings apply on top of the ones above, and its opening message starts the chat
+    let opening = match template {
+        Some((template, args)) => {
+            template.apply(&mut session_config, config_manager.config())?;
+            template.opening_message(args)
+        }
+        None => None,
+    };
 
     // Create session manager
     let (session_manager, output_rx) = SessionManager::with_config(session_config);
+    // An isolated session starts now, so a workspace that can't have a worktree is refused up front
+    if isolation == Isolation::Worktree {
+        session_manager
+            .create_session(CLI_SESSION_ID)
+            .await
+            .map_err(|e| anyhow::anyhow!("Can't start an isolated session: {}", e))?;
+    }
 
     // Run the TUI
     run_chat_tui(
-        &workspace_path,
+        roots.paths(),
         session_manager,
         output_rx,
+        config_manager.config(),
         provider_id,
         auto_approve,
+        opening,
     ).await
 }
 
+/// The `--tools` profile, or the config's `[tools] profile` without one
+fn resolve_tool_profile(config_manager: &ConfigManager, name: Option<&str>) -> anyhow::Result<ToolProfile> {
+    let tools = &config_manager.config().tools;
+    match name {
+        Some(name) => Ok(tools.resolve(name)?),
+        None => Ok(tools.default_profile()),
+    }
+}
+
+/// ID of the interactive chat session
+const CLI_SESSION_ID: &str = "cli-session";
+
+/// Commands handled by the TUI itself rather than by a skill
+const BUILTIN_COMMANDS: &[&str] = &["/exit", "/quit", "/q", "/help", "/?", "/plan", "/tools", "/clear", "/vim", "/processes"];
+
 /// Run the TUI-based chat interface
 async fn run_chat_tui(
-    workspace: &Path,
+    roots: &[PathBuf],
     session_manager: SessionManager,
     output_rx: cowork_core::session::OutputReceiver,
+    config: &Config,
     provider_id: &str,
     auto_approve: bool,
+    opening: Option<String>,
 ) -> anyhow::Result<()> {
+    let mut notifier = Notifier::new(config.notifications.clone());
+    let cli_config = &config.cli;
+
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = std::io::stdout();
@@ -543,25 +1262,69 @@ async fn run_chat_tui(
     // Create app state
     let provider_info = provider_id.to_string();
     let version = env!("CARGO_PKG_VERSION").to_string();
-    let mut app = App::new(provider_info, version);
+    let mut app = App::new(provider_info, version, Localizer::new(config.general.locale()));
+    let workspace = roots[0].as_path();
+    app.paths = PathDisplay::new(workspace.to_path_buf());
+    app.editor = config.general.editor.clone();
+    app.roots = roots.to_vec();
+    app.path_completer = Some(PathCompleter::new(workspace));
+
+    let skill_registry = SkillRegistry::with_builtins(workspace.to_path_buf());
+    app.slash_commands = BUILTIN_COMMANDS.iter().map(|c| c.to_string()).collect();
+    app.slash_commands
+        .extend(skill_registry.list_user_invocable().into_iter().map(|s| format!("/{}", s.name)));
+    app.slash_commands.sort();
+    app.slash_commands.dedup();
+    let (aliases, alias_warnings) = Aliases::load(&cli_config.aliases, &app.slash_commands);
+    let (keymap, key_warnings) = Keymap::load(&cli_config.keybindings);
+    app.aliases = aliases;
+    app.keymap = keymap;
+    app.set_vim_mode(cli_config.vim_mode);
+    for warning in alias_warnings.into_iter().chain(key_warnings) {
+        app.add_message(Message::error(format!("Config: {}", warning)));
+    }
 
     if auto_approve {
         app.approve_all_session = true;
         app.add_message(Message::system("Auto-approve mode is ON"));
     }
 
+    let orphans = processes::scan_orphans(&processes::default_dir());
+    if !orphans.is_empty() {
+        if config.general.kill_orphaned_processes {
+            app.add_message(Message::system(orphans.kill().summary()));
+        } else {
+            app.add_message(Message::system(format!(
+                "{}. Stop them with `cowork doctor --kill-orphans`, or set general.kill_orphaned_processes",
+                orphans.summary()
+            )));
+        }
+    }
+
+    if let Some(message) = opening {
+        app.start_turn();
+        app.add_message(Message::user(&message));
+        app.status = "Sending...".to_string();
+        if let Err(e) = session_manager
+            .push_message(CLI_SESSION_ID, SessionInput::user_message(message))
+            .await
+        {
+            app.status.clear();
+            app.add_message(Message::error(format!("Failed to send the template's opening message: {}", e)));
+        }
+    }
+
     // Create event handler
     let mut events = EventHandler::new(output_rx);
 
-    let session_id = "cli-session";
-
     // Main event loop
     let result = run_event_loop(
         &mut terminal,
         &mut app,
         &mut events,
+        &mut notifier,
         &session_manager,
-        session_id,
+        CLI_SESSION_ID,
         workspace,
     ).await;
 
@@ -573,6 +1336,9 @@ async fn run_chat_tui(
 
     // Stop session
     let _ = session_manager.stop_all();
+    if let Some(branch) = &app.worktree {
+        println!("The session's work stays on branch {}; run cowork --worktree and /merge to merge it", branch);
+    }
 
     result
 }
@@ -582,11 +1348,16 @@ async fn run_event_loop(
     terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
     app: &mut App,
     events: &mut EventHandler,
+    notifier: &mut Notifier,
     session_manager: &SessionManager,
-    session_id: &str,
+    initial_session_id: &str,
     workspace: &Path,
 ) -> anyhow::Result<()> {
+    // `/fork` switches the conversation to a new session
+    let mut active_session = initial_session_id.to_string();
     loop {
+        let session_id = active_session.as_str();
+
         // Check quit flag at start of loop to exit immediately after /exit
         if app.should_quit {
             break;
@@ -606,24 +1377,37 @@ async fn run_event_loop(
                     let action = if let Some(ref mut modal) = app.modal {
                         // Modal is showing — route keys to modal handler
                         match modal {
-                            Modal::Approval(approval) => handle_key_approval(key, approval),
+                            Modal::Approval(approval) => handle_key_approval(key, approval, &app.keymap),
                             Modal::Question(question) => handle_key_question(key, question),
                         }
+                    } else if app.selected_result.is_some()
+                        && let Some(action) = handle_key_result(key, &app.keymap)
+                    {
+                        // A tool result is highlighted — move through results or open one
+                        action
                     } else if !app.status.is_empty() {
                         // Processing — allow typing but don't submit, ESC to cancel
                         match key.code {
+                            // In vim mode Esc leaves insert mode first
+                            crossterm::event::KeyCode::Esc if app.input_wants_escape() => app.handle_input_key(key),
+                            _ if app.keymap.is(Binding::Cancel, &key) => KeyAction::Cancel,
                             crossterm::event::KeyCode::Char('c')
                                 if key.modifiers.contains(crossterm::event::KeyModifiers::CONTROL) =>
                             {
                                 KeyAction::Quit
                             }
-                            crossterm::event::KeyCode::Esc => KeyAction::Cancel,
                             crossterm::event::KeyCode::Enter => KeyAction::None,
-                            _ => handle_key_normal(key, &mut app.input),
+                            crossterm::event::KeyCode::Tab if app.mention_hint().is_none() => KeyAction::HighlightNextTool,
+                            crossterm::event::KeyCode::Char('x')
+                                if key.modifiers.contains(crossterm::event::KeyModifiers::CONTROL) =>
+                            {
+                                KeyAction::CancelTool
+                            }
+                            _ => app.handle_input_key(key),
                         }
                     } else {
                         // Idle — normal input
-                        handle_key_normal(key, &mut app.input)
+                        app.handle_input_key(key)
                     };
 
                     match action {
@@ -637,22 +1421,70 @@ async fn run_event_loop(
                                 .push_message(session_id, SessionInput::cancel())
                                 .await?;
                         }
+                        KeyAction::HighlightNextTool => app.highlight_next_tool(),
+                        KeyAction::SelectPreviousResult => {
+                            if !app.select_previous_result() {
+                                app.add_message(Message::system("No tool result with a file to open yet"));
+                            }
+                        }
+                        KeyAction::SelectNextResult => app.select_next_result(),
+                        KeyAction::ClearResultSelection => app.clear_result_selection(),
+                        KeyAction::OpenResult => {
+                            if let Some((path, line)) = app.selected_file().cloned() {
+                                // An isolated session's files are in its worktree
+                                let roots = match session_manager.worktree(session_id) {
+                                    Some(worktree) => vec![worktree.workspace()],
+                                    None => app.roots.clone(),
+                                };
+                                let message = match cowork_core::editor::open_in_editor(
+                                    app.editor.as_deref(),
+                                    &roots,
+                                    &path,
+                                    line,
+                                    None,
+                                ) {
+                                    Ok(_) => Message::system(format!("Opened {}", app.paths.display(&path))),
+                                    Err(e) => Message::error(format!("Couldn't open {}: {}", path, e)),
+                                };
+                                app.clear_result_selection();
+                                app.add_message(message);
+                            }
+                        }
+                        KeyAction::CancelTool => {
+                            if let Some((id, name)) = app.highlighted_tool().cloned() {
+                                app.add_message(Message::system(format!("Cancelling {}...", name)));
+                                session_manager
+                                    .push_message(session_id, SessionInput::cancel_tool(id))
+                                    .await?;
+                            }
+                        }
                         KeyAction::Submit(input) => {
                             app.start_turn();
                             app.push_history(input.clone());
-                            handle_user_input(app, session_manager, session_id, workspace, &input).await?;
+                            handle_user_input(app, session_manager, &mut active_session, workspace, &input).await?;
                         }
                         KeyAction::ApproveTool => {
                             if let Some(Modal::Approval(approval)) = app.modal.take() {
-                                app.add_message(Message::system(format!("Approved: {}", approval.name)));
+                                app.add_message(Message::system(app.localizer.format(Text::ToolApproved, &[("tool", &approval.name)])));
                                 session_manager
                                     .push_message(session_id, SessionInput::approve_tool(&approval.id))
                                     .await?;
                             }
                         }
+                        KeyAction::ExplainTool => {
+                            // The dialog stays open until the call is approved or rejected
+                            if let Some(Modal::Approval(approval)) = &mut app.modal
+                                && !approval.explain_requested
+                            {
+                                approval.explain_requested = true;
+                                session_manager
+                                    .push_message(session_id, SessionInput::explain_tool(&approval.id))
+                                    .await?;
+                            }
+                        }
                         KeyAction::RejectTool => {
                             if let Some(Modal::Approval(approval)) = app.modal.take() {
-                                app.add_message(Message::system(format!("Rejected: {}", approval.name)));
+                                app.add_message(Message::system(app.localizer.format(Text::ToolRejected, &[("tool", &approval.name)])));
                                 session_manager
                                     .push_message(session_id, SessionInput::reject_tool(&approval.id, None))
                                     .await?;
@@ -661,10 +1493,9 @@ async fn run_event_loop(
                         KeyAction::ApproveToolSession => {
                             if let Some(Modal::Approval(approval)) = app.modal.take() {
                                 app.session_approved_tools.insert(approval.name.clone());
-                                app.add_message(Message::system(format!(
-                                    "Approved '{}' for session",
-                                    approval.name
-                                )));
+                                app.add_message(Message::system(
+                                    app.localizer.format(Text::ToolApprovedForSession, &[("tool", &approval.name)]),
+                                ));
                                 session_manager
                                     .push_message(session_id, SessionInput::approve_tool(&approval.id))
                                     .await?;
@@ -673,7 +1504,7 @@ async fn run_event_loop(
                         KeyAction::ApproveAllSession => {
                             if let Some(Modal::Approval(approval)) = app.modal.take() {
                                 app.approve_all_session = true;
-                                app.add_message(Message::system("All tools approved for session"));
+                                app.add_message(Message::system(app.localizer.text(Text::AllToolsApproved)));
                                 session_manager
                                     .push_message(session_id, SessionInput::approve_tool(&approval.id))
                                     .await?;
@@ -738,27 +1569,33 @@ async fn run_event_loop(
                         }
                         KeyAction::HistoryPrev => app.history_prev(),
                         KeyAction::HistoryNext => app.history_next(),
+                        KeyAction::ToggleTodos => app.todos_collapsed = !app.todos_collapsed,
+                        KeyAction::SearchHistory => app.search_history(),
                         KeyAction::None => {}
                     }
                 }
                 Event::Terminal(crossterm::event::Event::Resize(_, _)) => {
                     // Terminal will redraw on next iteration
                 }
-                Event::Session(sid, output) => {
-                    if sid == session_id {
-                        // Check for auto-approval before handling
-                        if let SessionOutput::ToolPending { ref id, ref name, .. } = output
-                            && app.should_auto_approve(name) {
-                                app.add_message(Message::system(format!("Auto-approved: {}", name)));
-                                session_manager
-                                    .push_message(session_id, SessionInput::approve_tool(id))
-                                    .await?;
-                                // Don't show the approval modal
-                                continue;
-                            }
-                        app.handle_session_output(output);
+                Event::Session(sid, output) if sid == session_id => {
+                    // Check for auto-approval before handling
+                    match *output {
+                        SessionOutput::ToolPending { ref id, ref name, policy_required: false, ref security_warning, ref generated_files, .. }
+                            if security_warning.is_none() && generated_files.is_empty() && app.should_auto_approve(name) =>
+                        {
+                            app.add_message(Message::system(format!("Auto-approved: {}", name)));
+                            session_manager
+                                .push_message(session_id, SessionInput::approve_tool(id))
+                                .await?;
+                            // Don't show the approval modal
+                            continue;
+                        }
+                        _ => {}
                     }
+                    notifier.observe(&sid, &output);
+                    app.handle_session_output(*output);
                 }
+                Event::Session(..) => {}
                 Event::Tick => {
                     app.tick();
                 }
@@ -778,34 +1615,119 @@ async fn run_event_loop(
 async fn handle_user_input(
     app: &mut App,
     session_manager: &SessionManager,
-    session_id: &str,
+    session_id: &mut String,
     workspace: &Path,
     input: &str,
 ) -> anyhow::Result<()> {
-    let input = input.trim();
+    let input = match app.aliases.resolve(input) {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            app.add_message(Message::error(e));
+            return Ok(());
+        }
+    };
+    let input = input.as_str();
+    // An isolated session's commands run in its worktree
+    let worktree = session_manager.worktree(session_id).map(|w| w.workspace());
+    let workspace = worktree.as_deref().unwrap_or(workspace);
 
     match input {
         "/exit" | "/quit" | "/q" => {
             app.should_quit = true;
         }
         "/help" | "/?" => {
-            app.add_message(Message::system("Commands: /exit, /quit, /clear, /tools, /plan, /help"));
-            app.add_message(Message::system("Use ! prefix for direct shell commands (e.g., ! ls -la)"));
-            app.add_message(Message::system("Shortcuts: Ctrl+C to quit, Shift+Up/Down to scroll"));
+            let commands = "/exit, /quit, /clear, /tools, /plan, /debug last-request, /settings model, /plans, /memory, /agents, /fork, /merge, /recipe, /template, /provider, /pin, /title, /retry, /edit-last, /with, /vim, /processes, /help";
+            app.add_message(Message::system(app.localizer.format(Text::HelpCommands, &[("commands", &commands)])));
+            app.add_message(Message::system(app.localizer.text(Text::HelpShell)));
+            if !app.aliases.is_empty() {
+                let aliases: Vec<String> = app
+                    .aliases
+                    .iter()
+                    .map(|(alias, expansion)| format!("{} → {}", alias, expansion))
+                    .collect();
+                let aliases = aliases.join(", ");
+                app.add_message(Message::system(app.localizer.format(Text::HelpAliases, &[("aliases", &aliases)])));
+            }
+            let shortcuts = app.localizer.format(
+                Text::HelpShortcuts,
+                &[
+                    ("todos", &app.keymap.key(Binding::ToggleTodos)),
+                    ("search", &app.keymap.key(Binding::Search)),
+                    ("cancel", &app.keymap.key(Binding::Cancel)),
+                ],
+            );
+            app.add_message(Message::system(shortcuts));
         }
         "/plan" => {
-            // Toggle plan mode
-            let new_mode = !app.plan_mode;
-            session_manager
-                .push_message(session_id, SessionInput::set_plan_mode(new_mode))
-                .await?;
+            // Toggle plan mode, where sessions support it
+            if !app.plan_mode && !session_manager.capabilities().features.plan_mode {
+                app.add_message(Message::error("Plan mode isn't available with this tool profile or policy".to_string()));
+            } else {
+                let new_mode = !app.plan_mode;
+                session_manager
+                    .push_message(session_id, SessionInput::set_plan_mode(new_mode))
+                    .await?;
+            }
         }
         "/tools" => {
-            app.add_message(Message::system("Available tools: read_file, write_file, edit, glob, grep, execute_command, web_fetch, task, and more"));
+            let profile = session_manager.tool_profile(session_id);
+            let names = ConfigManager::new().unwrap_or_default().config().tools.profile_names();
+            let offered = match &app.offered_tools {
+                Some((name, tools, tokens)) if *name == profile.name => {
+                    format!(" - {} tools, ~{} tokens per request: {}", tools.len(), tokens, tools.join(", "))
+                }
+                _ => String::new(),
+            };
+            app.add_message(Message::system(format!("Tool profile: {}{}", profile, offered)));
+            app.add_message(Message::system(format!("Switch with /tools <profile>: {}", names.join(", "))));
+        }
+        cmd if cmd.starts_with("/tools ") => {
+            let name = cmd["/tools ".len()..].trim();
+            match ConfigManager::new().unwrap_or_default().config().tools.resolve(name) {
+                Ok(profile) => {
+                    session_manager
+                        .push_message(session_id, SessionInput::set_tool_profile(profile))
+                        .await?;
+                    app.add_message(Message::system(format!("Tool profile {} applies from the next message", name)));
+                }
+                Err(e) => app.add_message(Message::error(e.to_string())),
+            }
+        }
+        cmd if cmd.starts_with("/with ") => match parse_turn_overrides(&cmd["/with ".len()..]) {
+            Ok((overrides, message)) if !message.is_empty() => {
+                send_user_message(app, session_manager, session_id, workspace, message, Some(overrides)).await?;
+            }
+            Ok(_) => app.add_message(Message::system(
+                "Usage: /with [model=<model>] [approval=<all|level>] [tools=<profile>] <message>",
+            )),
+            Err(e) => app.add_message(Message::error(e)),
+        },
+        "/vim" => {
+            let on = app.vim.is_none();
+            app.set_vim_mode(on);
+            let saved = ConfigManager::new().and_then(|mut manager| {
+                manager.config_mut().cli.vim_mode = on;
+                manager.save()
+            });
+            let state = if on { "on: Esc for normal mode, i to type again" } else { "off" };
+            match saved {
+                Ok(()) => app.add_message(Message::system(format!("Vim mode {}", state))),
+                Err(e) => app.add_message(Message::error(format!("Vim mode {} until you quit; saving it failed: {}", state, e))),
+            }
+        }
+        "/processes" => {
+            let children = ProcessTracker::global().children();
+            if children.is_empty() {
+                app.add_message(Message::system("No language servers, MCP servers or background shells running"));
+            }
+            for child in children {
+                app.add_message(Message::system(child.to_string()));
+            }
         }
         "/clear" => {
             let _ = session_manager.stop_session(session_id);
             app.messages.clear();
+            app.generation_params = Default::default();
             app.add_message(Message::system("Conversation cleared"));
         }
         cmd if cmd.starts_with('!') => {
@@ -829,7 +1751,185 @@ async fn handle_user_input(
             let skill_name = parts[0];
             let args = parts.get(1).copied().unwrap_or("");
 
-            if let Some(skill) = skill_registry.get(skill_name) {
+            if let Some(skill) = skill_registry.get(skill_name)
+                && skill.runs_locally()
+            {
+                // Local skill: show the output directly without an LLM turn
+                app.add_message(Message::user(cmd));
+                let mut data = std::collections::HashMap::new();
+                if let Ok(params) = serde_json::to_value(&app.generation_params) {
+                    data.insert(GENERATION_PARAMS_KEY.to_string(), params);
+                }
+                let profile = session_manager.tool_profile(session_id).name;
+                let tool_profile = match &app.offered_tools {
+                    Some((name, tools, tokens)) if *name == profile => {
+                        serde_json::json!({ "profile": profile, "tools": tools, "definition_tokens": tokens })
+                    }
+                    _ => serde_json::json!({ "profile": profile }),
+                };
+                data.insert(TOOL_PROFILE_KEY.to_string(), tool_profile);
+                if let Some(stats) = session_manager.session_stats(session_id)
+                    && let Ok(usage) = serde_json::to_value(&stats.tool_usage)
+                {
+                    data.insert(TOOL_USAGE_KEY.to_string(), usage);
+                }
+                let ctx = SkillContext {
+                    workspace: workspace.to_path_buf(),
+                    args: args.to_string(),
+                    data,
+                };
+                let result = skill.execute(ctx).await;
+                if result.success {
+                    // `/settings model` returns updated parameters for the session
+                    if let Some(params) = result
+                        .data
+                        .as_ref()
+                        .and_then(|d| d.get(GENERATION_PARAMS_KEY))
+                        .and_then(|v| serde_json::from_value(v.clone()).ok())
+                    {
+                        session_manager
+                            .push_message(session_id, SessionInput::set_generation_params(params))
+                            .await?;
+                    }
+                    // `/plans approve` names the plan to approve (null for the current one)
+                    if let Some(plan) = result.data.as_ref().and_then(|d| d.get(APPROVE_PLAN_KEY)) {
+                        let name = plan.as_str().map(String::from);
+                        session_manager
+                            .push_message(session_id, SessionInput::approve_plan(name))
+                            .await?;
+                    }
+                    // `/pin` pins or unpins the session
+                    if let Some(pinned) = result.data.as_ref().and_then(|d| d.get(PIN_SESSION_KEY)).and_then(|v| v.as_bool()) {
+                        session_manager
+                            .push_message(session_id, SessionInput::set_pinned(pinned))
+                            .await?;
+                    }
+                    // `/pin reply` pins or unpins the latest reply
+                    if let Some(pinned) = result.data.as_ref().and_then(|d| d.get(PIN_REPLY_KEY)).and_then(|v| v.as_bool()) {
+                        session_manager
+                            .push_message(session_id, SessionInput::pin_message(None, pinned))
+                            .await?;
+                    }
+                    // `/title` names the session
+                    if let Some(title) = result.data.as_ref().and_then(|d| d.get(SESSION_TITLE_KEY)).and_then(|v| v.as_str()) {
+                        session_manager
+                            .push_message(session_id, SessionInput::set_title(title))
+                            .await?;
+                    }
+                    // `/fork` names how many user messages to go back
+                    if let Some(turns_back) = result
+                        .data
+                        .as_ref()
+                        .and_then(|d| d.get(FORK_TURNS_BACK_KEY))
+                        .and_then(|v| v.as_u64())
+                    {
+                        match fork_cli_session(session_manager, session_id, turns_back as usize).await {
+                            Ok(fork_id) => {
+                                app.add_message(Message::system(format!(
+                                    "Forked {} into session {}; messages from your last {} onward are not part of this branch",
+                                    session_id, fork_id, turns_back
+                                )));
+                                *session_id = fork_id;
+                            }
+                            Err(e) => app.add_message(Message::error(e.to_string())),
+                        }
+                        return Ok(());
+                    }
+                    // `/merge` reviews the worktree's changes, and merges them once confirmed
+                    if let Some(confirmed) = result
+                        .data
+                        .as_ref()
+                        .and_then(|d| d.get(MERGE_WORKTREE_KEY))
+                        .and_then(|v| v.as_bool())
+                    {
+                        if !confirmed {
+                            match session_manager.worktree_summary(session_id).await {
+                                Ok(summary) if summary.is_empty() => app.add_message(Message::system(summary.to_string())),
+                                Ok(summary) => app.add_message(Message::system(format!(
+                                    "{}\n\nRun /merge confirm to merge these changes and end the session",
+                                    summary
+                                ))),
+                                Err(e) => app.add_message(Message::error(e.to_string())),
+                            }
+                            return Ok(());
+                        }
+                        match session_manager.merge_worktree(session_id).await {
+                            Ok(summary) => {
+                                app.worktree = None;
+                                app.add_message(Message::system(format!(
+                                    "Merged {} into {} and removed its worktree; your next message starts a new session",
+                                    summary.branch, summary.target
+                                )));
+                            }
+                            Err(e) => app.add_message(Message::error(e.to_string())),
+                        }
+                        return Ok(());
+                    }
+                    // `/retry` and `/edit-last` re-run the last turn in place
+                    if let Some(temperature) = result.data.as_ref().and_then(|d| d.get(RETRY_TEMPERATURE_KEY)) {
+                        let temperature = temperature.as_f64().map(|t| t as f32);
+                        app.status = "Regenerating...".to_string();
+                        app.start_turn();
+                        session_manager
+                            .push_message(session_id, SessionInput::regenerate_last(temperature))
+                            .await?;
+                        return Ok(());
+                    }
+                    if let Some(content) = result
+                        .data
+                        .as_ref()
+                        .and_then(|d| d.get(EDIT_LAST_CONTENT_KEY))
+                        .and_then(|v| v.as_str())
+                    {
+                        let Some(message_id) = app.last_user_message_id.clone() else {
+                            app.add_message(Message::error("No message to edit yet"));
+                            return Ok(());
+                        };
+                        app.status = "Re-running...".to_string();
+                        app.start_turn();
+                        session_manager
+                            .push_message(session_id, SessionInput::edit_user_message(message_id, content))
+                            .await?;
+                        return Ok(());
+                    }
+                    // `/recipe run` runs the recipe as a turn of the session
+                    if let Some(recipe) = result.data.as_ref().and_then(|d| d.get(RUN_RECIPE_KEY)) {
+                        let name = recipe["name"].as_str().unwrap_or_default();
+                        app.status = format!("Running recipe {name}...");
+                        app.start_turn();
+                        session_manager
+                            .push_message(session_id, SessionInput::run_recipe(name, recipe["args"].as_str().unwrap_or_default()))
+                            .await?;
+                        return Ok(());
+                    }
+                    // `/compact` compacts the history between turns
+                    if let Some(compact) = result.data.as_ref().and_then(|d| d.get(COMPACT_KEY)) {
+                        let text = |key: &str| compact[key].as_str().map(str::to_string);
+                        let mode = compact["mode"].as_str().and_then(|mode| mode.parse().ok());
+                        app.status = "Compacting...".to_string();
+                        app.start_turn();
+                        session_manager
+                            .push_message(session_id, SessionInput::compact(mode, text("model"), text("focus")))
+                            .await?;
+                        return Ok(());
+                    }
+                    app.add_message(Message::system(result.response));
+                    // `/agents test` hands a prompt to the LLM for a new turn
+                    if let Some(prompt) = result
+                        .data
+                        .as_ref()
+                        .and_then(|d| d.get(FOLLOW_UP_PROMPT_KEY))
+                        .and_then(|v| v.as_str())
+                    {
+                        app.status = format!("Running /{skill_name}...");
+                        session_manager
+                            .push_message(session_id, SessionInput::user_message(prompt))
+                            .await?;
+                    }
+                } else {
+                    app.add_message(Message::error(result.error.unwrap_or_default()));
+                }
+            } else if let Some(skill) = skill_registry.get(skill_name) {
                 app.add_message(Message::user(cmd));
                 app.status = format!("Running /{skill_name}...");
 
@@ -854,29 +1954,78 @@ async fn handle_user_input(
             }
         }
         _ => {
-            // Regular message to AI - parse @path image attachments
-            let (cleaned_input, images) = ImageAttachment::parse_from_text(input, workspace);
-
-            if images.is_empty() {
-                app.add_message(Message::user(input));
-                app.status = "Sending...".to_string();
-                session_manager
-                    .push_message(session_id, SessionInput::user_message(input))
-                    .await?;
-            } else {
-                // Show user message with image count
-                app.add_message(Message::user(format!("{} [{} image(s)]", &cleaned_input, images.len())));
-                app.status = "Sending...".to_string();
-                session_manager
-                    .push_message(session_id, SessionInput::user_message_with_images(&cleaned_input, images))
-                    .await?;
-            }
+            send_user_message(app, session_manager, session_id, workspace, input, None).await?;
         }
     }
 
     Ok(())
 }
 
+/// Send a regular message to the AI, whose turn runs with `overrides` if any
+async fn send_user_message(
+    app: &mut App,
+    session_manager: &SessionManager,
+    session_id: &str,
+    workspace: &Path,
+    input: &str,
+    overrides: Option<TurnOverrides>,
+) -> anyhow::Result<()> {
+    // Parse @path image attachments
+    let (cleaned_input, images) = ImageAttachment::parse_from_text(input, workspace);
+
+    let message = if images.is_empty() {
+        app.add_message(Message::user(input));
+        SessionInput::user_message(input)
+    } else {
+        // Show user message with image count
+        app.add_message(Message::user(format!("{} [{} image(s)]", &cleaned_input, images.len())));
+        SessionInput::user_message_with_images(&cleaned_input, images)
+    };
+    app.status = "Sending...".to_string();
+    let message = match overrides {
+        Some(overrides) => message.with_overrides(overrides),
+        None => message,
+    };
+    session_manager.push_message(session_id, message).await?;
+    Ok(())
+}
+
+/// Split `/with` arguments into the overrides their leading `key=value`
+/// words set and the message that follows them
+fn parse_turn_overrides(args: &str) -> Result<(TurnOverrides, &str), String> {
+    let mut overrides = TurnOverrides::default();
+    let mut rest = args.trim_start();
+    loop {
+        let (word, tail) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        let Some((key, value)) = word.split_once('=') else {
+            break;
+        };
+        overrides = match key {
+            "model" => overrides.with_model(value),
+            "approval" => overrides.with_approval(value.parse()?),
+            "tools" => overrides
+                .with_tool_profile(ConfigManager::new().unwrap_or_default().config().tools.resolve(value).map_err(|e| e.to_string())?),
+            _ => return Err(format!("Unknown override: {}. Valid overrides: model, approval, tools", key)),
+        };
+        rest = tail.trim_start();
+    }
+    Ok((overrides, rest.trim_end()))
+}
+
+/// Fork a session before its `turns_back`-th last user message, returning the fork's ID
+async fn fork_cli_session(
+    session_manager: &SessionManager,
+    session_id: &str,
+    turns_back: usize,
+) -> anyhow::Result<String> {
+    let history = session_manager
+        .session_history(session_id)
+        .ok_or_else(|| anyhow::anyhow!("Nothing to fork yet"))?;
+    let at_message = fork_point_before_user_turn(&history.messages, turns_back)
+        .ok_or_else(|| anyhow::anyhow!("There is nothing before your last {} message(s) to fork from", turns_back))?;
+    Ok(session_manager.fork_session(session_id, at_message).await?)
+}
+
 /// Run a bash command and return output as string (for TUI mode)
 async fn run_bash_command_quiet(workspace: &Path, command: &str) -> anyhow::Result<String> {
     let tool = ExecuteCommand::new(workspace.to_path_buf());
@@ -932,8 +2081,9 @@ fn show_tools() {
         ("edit", "Surgical string replacement", "High"),
         ("glob", "Find files by pattern", "None"),
         ("grep", "Search file contents", "None"),
+        ("codebase_search", "Look up symbols and files in the workspace index", "None"),
         ("list_directory", "List directory contents", "None"),
-        ("search_files", "Search for files", "None"),
+        ("search_files", "Find files by name, path and content", "None"),
         ("delete_file", "Delete a file", "High"),
         ("move_file", "Move or rename files", "Low"),
         // Shell
@@ -999,6 +2149,779 @@ fn show_config(workspace: &Path) {
     }
 }
 
+/// Print the command palette's items for the workspace
+fn show_palette(workspace: &Path, json: bool) -> anyhow::Result<()> {
+    let config_manager = ConfigManager::new().unwrap_or_default();
+    let config = config_manager.config();
+    let profile = config.tools.default_profile();
+    let items = Palette::new(workspace).items(config, &config_manager.policy(), &profile, &[]);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&items)?);
+        return Ok(());
+    }
+    for item in &items {
+        let line = format!("  {:<8} {:<28} {}", format!("{:?}", item.kind).to_lowercase(), item.title, item.description);
+        match &item.unavailable {
+            Some(reason) => println!("{} {}", style(line).dim(), style(format!("({})", reason)).dim()),
+            None => println!("{}", line),
+        }
+    }
+    Ok(())
+}
+
+/// Print the children of running instances and the orphans of crashed ones
+fn show_doctor(kill_orphans: bool) {
+    let dir = processes::default_dir();
+    println!("{}", style("Child processes:").bold());
+    let instances = processes::running_instances(&dir);
+    if instances.iter().all(|(_, children)| children.is_empty()) {
+        println!("  {}", style("None running").dim());
+    }
+    for (owner, children) in instances.iter().filter(|(_, children)| !children.is_empty()) {
+        println!("  cowork (PID {}):", owner);
+        for child in children {
+            println!("    {}", child);
+        }
+    }
+    println!();
+
+    let orphans = processes::scan_orphans(&dir);
+    if orphans.is_empty() {
+        println!("{}", style("No processes left behind by crashed runs").green());
+        return;
+    }
+    println!("{}", style("Left behind by crashed runs:").yellow().bold());
+    for orphan in &orphans.orphans {
+        println!("  {}", orphan);
+    }
+    if kill_orphans {
+        let report = orphans.kill();
+        println!();
+        println!("{}", report.summary());
+    } else {
+        println!();
+        println!("Stop them with {}", style("cowork doctor --kill-orphans").cyan());
+    }
+}
+
+/// Replay a saved session with dry-run tools and write the comparison report
+async fn run_replay(
+    workspace: &Path,
+    session: &str,
+    provider_id: &str,
+    model: &str,
+    output: Option<PathBuf>,
+) -> anyhow::Result<()> {
+    let saved = if Path::new(session).is_file() {
+        let mut saved: SavedSession = serde_json::from_str(&std::fs::read_to_string(session)?)?;
+        saved.repair_history();
+        saved
+    } else {
+        load_session(session)?.ok_or_else(|| anyhow::anyhow!("No saved session file or ID '{}'", session))?
+    };
+
+    let config_manager = ConfigManager::new()?;
+    let config = config_manager.config();
+    let mut session_config = SessionConfig::new(workspace.to_path_buf())
+        .with_provider(provider_id)
+        .with_model(model)
+        .with_system_prompt(build_system_prompt(&WorkspaceRoots::new(workspace.to_path_buf()), Some(model), &config.prompt, None))
+        .with_web_search_config(config.web_search.clone())
+        .with_limits(config.limits.clone())
+        .with_env_policy(config.env.policy_for(workspace))
+        .with_network_policy(config.web.network_policy())
+        .with_component_registry(Arc::new(load_components(workspace)));
+    if let Some(key) = cowork_core::provider::get_api_key(&config_manager, provider_id) {
+        session_config = session_config.with_api_key(key);
+    }
+    if let Some(provider) = config.get_provider(provider_id) {
+        if let Some(url) = provider.base_url.clone() {
+            session_config = session_config.with_base_url(url);
+        }
+        session_config = session_config.with_request_timeout(provider.request_timeout(false));
+    }
+
+    println!(
+        "Replaying {} ({} messages) on {}/{} with dry-run tools...",
+        saved.id,
+        saved.messages.len(),
+        provider_id,
+        model
+    );
+    let report = SessionReplay::new(saved, session_config)
+        .with_original_usage(Arc::new(UsageStore::open_default()))
+        .run()
+        .await?;
+
+    let base = output.unwrap_or_else(|| PathBuf::from(format!("replay-{}", report.session_id)));
+    let markdown_path = base.with_extension("md");
+    let json_path = base.with_extension("json");
+    std::fs::write(&markdown_path, report.to_markdown())?;
+    std::fs::write(&json_path, report.to_json()?)?;
+
+    for warning in &report.warnings {
+        println!("  {} {}", style("Warning:").yellow(), warning);
+    }
+    let same = report.turns.iter().filter(|t| t.same_tools).count();
+    println!(
+        "{} turns replayed, same tool calls in {}. Report: {} and {}",
+        report.turns.len(),
+        same,
+        markdown_path.display(),
+        json_path.display()
+    );
+    Ok(())
+}
+
+/// Serve the workspace's tools over MCP until the client disconnects (stdio)
+/// or the process is stopped (TCP)
+async fn run_mcp_serve(workspace: &Path, port: Option<u16>, yolo: bool) -> anyhow::Result<()> {
+    let config_manager = ConfigManager::new()?;
+    let config = config_manager.config();
+    let mut approval_config = if yolo {
+        ToolApprovalConfig::trust_all()
+    } else {
+        ToolApprovalConfig::default()
+    }
+    .with_trusted_hosts(&config_manager.config().approval.trusted_hosts);
+    approval_config.apply_policy(&config_manager.policy());
+
+    let registry = ToolRegistryBuilder::new(workspace.to_path_buf())
+        .with_task(false)
+        .with_web_search_config(config.web_search.clone())
+        .with_env_policy(config.env.policy_for(workspace))
+        .with_network_policy(config.web.network_policy())
+        .with_policy(config_manager.policy())
+        .build();
+    let server = ToolServer::new(workspace, registry, approval_config).into_server();
+
+    match port {
+        None => server.serve_stdio().await?,
+        Some(port) => {
+            let listener = tokio::net::TcpListener::bind(("127.0.0.1", port)).await?;
+            eprintln!("Serving MCP for {} on {}", workspace.display(), listener.local_addr()?);
+            loop {
+                let (stream, peer) = listener.accept().await?;
+                tracing::info!("MCP client connected from {}", peer);
+                let (reader, writer) = stream.into_split();
+                if let Err(e) = server.serve(tokio::io::BufReader::new(reader), writer).await {
+                    tracing::warn!("MCP connection from {} failed: {}", peer, e);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn build_index(workspace: &Path, rebuild: bool) -> anyhow::Result<()> {
+    let started = std::time::Instant::now();
+    let (index, stats) = if rebuild {
+        let mut index = WorkspaceIndex::default();
+        let stats = index.refresh(workspace);
+        index.save(workspace)?;
+        (index, stats)
+    } else {
+        WorkspaceIndex::update(workspace)
+    };
+
+    let symbols: usize = index.files.values().map(|f| f.symbols.len()).sum();
+    println!(
+        "Indexed {} files ({} symbols) in {:.1}s: {} added, {} updated, {} removed, {} unchanged",
+        index.files.len(),
+        symbols,
+        started.elapsed().as_secs_f64(),
+        stats.added,
+        stats.updated,
+        stats.removed,
+        stats.unchanged
+    );
+    if index.truncated {
+        println!(
+            "{}",
+            style(format!(
+                "The workspace has more than {} files; the rest were left out",
+                cowork_core::context::index::MAX_INDEXED_FILES
+            ))
+            .yellow()
+        );
+    }
+    println!("{}", style(workspace.join(INDEX_FILE).display()).dim());
+    Ok(())
+}
+
+/// Handle keychain commands for API keys
+fn handle_auth_command(cmd: AuthCommands) -> anyhow::Result<()> {
+    let mut config_manager = ConfigManager::new()?;
+    match cmd {
+        AuthCommands::Set { provider } => {
+            if !credentials::keyring_available() {
+                anyhow::bail!(
+                    "No OS keychain is available here; set {} or api_key in the config instead",
+                    catalog::api_key_env(&provider).unwrap_or("the provider's API key environment variable")
+                );
+            }
+            let key: String = dialoguer::Password::new()
+                .with_prompt(format!("API key for {}", provider))
+                .interact()?;
+            config_manager.set_keyring_api_key(&provider, &key)?;
+            config_manager.save()?;
+            println!("Stored the API key for {} in the OS keychain", provider);
+        }
+        AuthCommands::Remove { provider } => {
+            let removed = config_manager.remove_keyring_api_key(&provider)?;
+            config_manager.save()?;
+            if removed {
+                println!("Removed the API key for {} from the OS keychain", provider);
+            } else {
+                println!("No API key for {} in the OS keychain", provider);
+            }
+        }
+        AuthCommands::Migrate { provider } => {
+            let providers = match provider {
+                Some(provider) => vec![provider],
+                None => config_manager.plaintext_api_key_providers(),
+            };
+            if providers.is_empty() {
+                println!("No API keys are kept in plain text in {}", config_manager.config_path().display());
+                return Ok(());
+            }
+            if !credentials::keyring_available() {
+                anyhow::bail!("No OS keychain is available here; the keys stay in the config");
+            }
+            for provider in providers {
+                let confirmed = dialoguer::Confirm::new()
+                    .with_prompt(format!("Move the API key for {} into the OS keychain?", provider))
+                    .default(true)
+                    .interact()?;
+                if !confirmed {
+                    continue;
+                }
+                if config_manager.migrate_api_key_to_keyring(&provider)? {
+                    config_manager.save()?;
+                    println!("Moved the API key for {} into the OS keychain", provider);
+                } else {
+                    println!("No plain-text API key for {}", provider);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Handle saved session commands
+fn handle_sessions_command(config_manager: Option<&ConfigManager>, cmd: SessionsCommands) -> anyhow::Result<()> {
+    match cmd {
+        SessionsCommands::Prune { dry_run, with_audit_log } => {
+            let config = config_manager.map(|cm| cm.config().sessions.clone()).unwrap_or_default();
+            if !config.is_enabled() {
+                println!("No retention limits set; add max_count or max_age_days under [sessions] in the config");
+                return Ok(());
+            }
+            let report = prune_saved_sessions(&config, dry_run, with_audit_log)?;
+            println!("{}", report);
+            if !report.errors.is_empty() {
+                anyhow::bail!("{} sessions could not be deleted", report.errors.len());
+            }
+        }
+        SessionsCommands::Delete { session_id, with_audit_log } => {
+            if !delete_saved_session(&session_id, with_audit_log)? {
+                anyhow::bail!("No saved session {}", session_id);
+            }
+            println!("Deleted session {}", session_id);
+        }
+    }
+    Ok(())
+}
+
+async fn handle_schedule_command(workspace: &Path, cmd: ScheduleCommands) -> anyhow::Result<()> {
+    let path = Schedule::default_path()?;
+    match cmd {
+        ScheduleCommands::Add { cron, template, prompt, job_workspace, name, approve_tools, deny_tools } => {
+            let workspace = job_workspace.as_deref().map(absolute_workspace).unwrap_or_else(|| workspace.to_path_buf());
+            // A job that can't start would only fail every night
+            let config = ConfigManager::new()?.config().clone();
+            let loaded = load_template(&workspace, &template, &config)?;
+            scheduled_prompt(&loaded, prompt.as_deref())?;
+            let mut schedule = Schedule::load(&path)?;
+            let job = schedule.add(ScheduledJob {
+                id: name.unwrap_or_default(),
+                cron,
+                template,
+                prompt,
+                workspace,
+                approve_tools: approve_tools.iter().map(ToString::to_string).collect(),
+                deny_tools: deny_tools.iter().map(ToString::to_string).collect(),
+                created_at: Utc::now(),
+            })?;
+            let id = job.id.clone();
+            let next = job.next_run(&schedule_timezone(&config)?, Utc::now())?;
+            schedule.save(&path)?;
+            match next {
+                Some(next) => println!("Scheduled {}; next run {}", id, next.with_timezone(&Local).format("%Y-%m-%d %H:%M")),
+                None => println!("Scheduled {}; it has no future runs", id),
+            }
+        }
+        ScheduleCommands::List => {
+            let schedule = Schedule::load(&path)?;
+            if schedule.jobs.is_empty() {
+                println!("No scheduled jobs; add one with `cowork schedule add`");
+                return Ok(());
+            }
+            let config = ConfigManager::new()?.config().clone();
+            let timezone = schedule_timezone(&config)?;
+            let history = RunHistory::open_default().load(None)?;
+            println!("{} (times in {})", style("Scheduled jobs:").bold(), timezone);
+            println!();
+            for job in &schedule.jobs {
+                let next = match job.next_run(&timezone, Utc::now()) {
+                    Ok(Some(next)) => next.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string(),
+                    Ok(None) => "never".to_string(),
+                    Err(e) => format!("invalid ({})", e),
+                };
+                println!("  {}  {}  template {}", style(&job.id).cyan(), job.cron, job.template);
+                println!("    workspace: {}", job.workspace.display());
+                if let Some(prompt) = &job.prompt {
+                    println!("    prompt: {}", prompt);
+                }
+                println!("    next run: {}", next);
+                if let Some(last) = history.iter().rev().find(|run| run.job_id == job.id) {
+                    println!("    last run: {} ({})", last.started_at.with_timezone(&Local).format("%Y-%m-%d %H:%M"), last.status);
+                }
+            }
+        }
+        ScheduleCommands::Remove { id } => {
+            let mut schedule = Schedule::load(&path)?;
+            schedule.remove(&id)?;
+            schedule.save(&path)?;
+            println!("Removed {}", id);
+        }
+        ScheduleCommands::Daemon => run_schedule_daemon(&path).await?,
+        ScheduleCommands::History { id, limit } => {
+            let runs = RunHistory::open_default().load(id.as_deref())?;
+            if runs.is_empty() {
+                println!("No recorded runs");
+                return Ok(());
+            }
+            for run in runs.iter().skip(runs.len().saturating_sub(limit)) {
+                let status = match run.status {
+                    RunStatus::Success => style(run.status.to_string()).green(),
+                    RunStatus::Partial | RunStatus::Skipped => style(run.status.to_string()).yellow(),
+                    RunStatus::Failed => style(run.status.to_string()).red(),
+                };
+                print!("{}  {}  {}", run.started_at.with_timezone(&Local).format("%Y-%m-%d %H:%M"), run.job_id, status);
+                if run.rejected_tools > 0 {
+                    print!(" ({} tool call(s) rejected)", run.rejected_tools);
+                }
+                println!();
+                if let Some(session_id) = &run.session_id {
+                    println!("    session: {}", session_id);
+                }
+                if let Some(message) = &run.message {
+                    println!("    {}", style(message).dim());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// How often the daemon reads the schedule again, so added and removed jobs
+/// take effect
+const SCHEDULE_RELOAD_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// The time zone in `[schedule]`
+fn schedule_timezone(config: &Config) -> anyhow::Result<ScheduleTimezone> {
+    Ok(ScheduleTimezone::parse(config.schedule.timezone.as_deref())?)
+}
+
+/// A scheduled run's opening message: the template's, filled in with the
+/// job's prompt, or the prompt when the template has none
+fn scheduled_prompt(template: &SessionTemplate, prompt: Option<&str>) -> anyhow::Result<String> {
+    template
+        .opening_message(prompt.unwrap_or_default())
+        .or_else(|| prompt.map(str::trim).filter(|p| !p.is_empty()).map(str::to_string))
+        .ok_or_else(|| anyhow::anyhow!("Template {} has no opening message; give the run one with --prompt", template.name))
+}
+
+/// Run the scheduled jobs as they come due until Ctrl+C
+///
+/// A job whose previous run is still going is skipped, and the skip
+/// recorded in the run history.
+async fn run_schedule_daemon(path: &Path) -> anyhow::Result<()> {
+    let history = Arc::new(RunHistory::open_default());
+    let running = RunningJobs::new();
+    let mut timetable = Timetable::new();
+    let mut jobs = Vec::new();
+    println!("Running the jobs in {} (Ctrl+C to stop)", path.display());
+    loop {
+        let config = ConfigManager::new()?.config().clone();
+        let timezone = schedule_timezone(&config)?;
+        // A schedule being edited by hand keeps the jobs it had
+        match Schedule::load(path) {
+            Ok(schedule) => jobs = schedule.jobs,
+            Err(e) => tracing::warn!("Keeping the previous schedule: {}", e),
+        }
+
+        let now = Utc::now();
+        for job in timetable.due(&jobs, &timezone, now) {
+            let Some(guard) = running.try_start(&job.id) else {
+                tracing::warn!("Skipping scheduled job {}: its previous run is still going", job.id);
+                println!("{} {}: its previous run is still going", style("Skipped").yellow(), job.id);
+                if let Err(e) = history.append(&RunRecord::skipped(&job.id, now)) {
+                    tracing::warn!("Failed to record the skipped run of {}: {}", job.id, e);
+                }
+                continue;
+            };
+            let history = history.clone();
+            tokio::spawn(async move {
+                let _guard = guard;
+                println!("{} {}", style("Running").bold(), job.id);
+                let record = run_scheduled_job(&job).await;
+                println!("{} {}: {}", style("Finished").bold(), job.id, record.status);
+                if let Err(e) = history.append(&record) {
+                    tracing::warn!("Failed to record the run of {}: {}", job.id, e);
+                }
+            });
+        }
+
+        let wait = match timetable.next_due() {
+            Some(next) => (next - Utc::now()).to_std().unwrap_or_default().min(SCHEDULE_RELOAD_INTERVAL),
+            None => SCHEDULE_RELOAD_INTERVAL,
+        };
+        tokio::select! {
+            _ = tokio::time::sleep(wait) => {}
+            _ = tokio::signal::ctrl_c() => {
+                println!("Stopped");
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Run a job through the one-shot path, with its transcript saved
+async fn run_scheduled_job(job: &ScheduledJob) -> RunRecord {
+    let started_at = Utc::now();
+    let session_id = job.session_id(started_at);
+    let (status, rejected_tools, message) = match execute_scheduled_job(job, &session_id).await {
+        Ok(outcome) if !outcome.errors.is_empty() => (RunStatus::Failed, outcome.rejected, outcome.errors.last().cloned()),
+        Ok(outcome) if outcome.rejected > 0 => (RunStatus::Partial, outcome.rejected, None),
+        Ok(_) => (RunStatus::Success, 0, None),
+        Err(e) => (RunStatus::Failed, 0, Some(e.to_string())),
+    };
+    RunRecord {
+        job_id: job.id.clone(),
+        started_at,
+        finished_at: Some(Utc::now()),
+        status,
+        session_id: Some(session_id),
+        rejected_tools,
+        message,
+    }
+}
+
+async fn execute_scheduled_job(job: &ScheduledJob, session_id: &str) -> anyhow::Result<OneShotOutcome> {
+    // Read now, so the run sees config changes made since the daemon started
+    let config_manager = ConfigManager::new()?;
+    let template = load_template(&job.workspace, &job.template, config_manager.config())?;
+    let prompt = scheduled_prompt(&template, job.prompt.as_deref())?;
+    let (approve_tools, deny_tools) = job.rules()?;
+    let mut approval_config =
+        ToolApprovalConfig::default().with_trusted_hosts(&config_manager.config().approval.trusted_hosts);
+    approval_config.apply_policy(&config_manager.policy());
+    let run = OneShot {
+        roots: &WorkspaceRoots::new(job.workspace.clone()),
+        provider_id: validate_provider_id(config_manager.default_provider()),
+        model: None,
+        prompt: &prompt,
+        auto_approve: false,
+        approval_config: approval_config.with_rules(approve_tools, deny_tools),
+        tool_profile: resolve_tool_profile(&config_manager, None)?,
+        template: Some(&template),
+        session_id: session_id.to_string(),
+    };
+    execute_one_shot(&config_manager, run).await
+}
+
+fn handle_import_command(workspace: &Path, cmd: ImportCommands) -> anyhow::Result<()> {
+    let ImportCommands::ClaudeCode { paths, dry_run } = cmd;
+    // Calls to tools this build doesn't have are kept as text
+    let (tools, _) = recipes::builtin_tools_and_skills(workspace);
+    let report = import_sessions(ImportSource::ClaudeCode, &paths, &tools, dry_run)?;
+    println!("{}", report);
+    if !dry_run && report.count(ImportOutcome::Imported) > 0 {
+        println!("Open one from the app's Sessions page to continue it");
+    }
+    if report.count(ImportOutcome::Failed) > 0 {
+        anyhow::bail!("{} files could not be imported", report.count(ImportOutcome::Failed));
+    }
+    Ok(())
+}
+
+fn show_audit(session_id: &str, json: bool, summary: bool) -> anyhow::Result<()> {
+    let records = load_audit_log(session_id)?;
+
+    if json {
+        for record in &records {
+            println!("{}", serde_json::to_string(record)?);
+        }
+        return Ok(());
+    }
+
+    println!("{}", style(format!("Audit log of session {}:", session_id)).bold());
+    println!();
+    if records.is_empty() {
+        println!("  {}", style("No events recorded").dim());
+        return Ok(());
+    }
+
+    if summary {
+        let summary = AuditSummary::from_records(&records);
+        if let (Some(first), Some(last)) = (summary.first, summary.last) {
+            println!(
+                "  {} events from {} to {}",
+                summary.records,
+                first.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S"),
+                last.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S")
+            );
+            println!();
+        }
+        for (event, count) in &summary.events {
+            println!("  {:<20} {:>6}", event, count);
+        }
+        for (label, by) in [("approved by", &summary.approved_by), ("rejected by", &summary.rejected_by)] {
+            if !by.is_empty() {
+                let counts: Vec<String> = by.iter().map(|(decider, n)| format!("{} {}", decider, n)).collect();
+                println!("  {:<20} {}", label, counts.join(", "));
+            }
+        }
+        if summary.failed_tools > 0 {
+            println!("  {:<20} {:>6}", "failed tools", summary.failed_tools);
+        }
+        return Ok(());
+    }
+
+    for record in &records {
+        print_audit_record(record);
+    }
+    Ok(())
+}
+
+fn print_audit_record(record: &AuditRecord) {
+    let time = record.timestamp.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S");
+    let detail = match &record.event {
+        AuditEvent::ApprovalRequested { tool, arguments, policy_required, delegated_to, findings, .. } => {
+            let mut policy = if *policy_required { " (required by policy)".to_string() } else { String::new() };
+            for finding in findings {
+                policy.push_str(&format!(" [{}]", finding));
+            }
+            match delegated_to {
+                Some(channel) => format!("{}{} (delegated to {}) {}", tool, policy, channel, arguments),
+                None => format!("{}{} {}", tool, policy, arguments),
+            }
+        }
+        AuditEvent::Approved { tool, by, rule, approver, .. } => match (rule, approver) {
+            (Some(rule), _) => format!("{} by {} '{}'", tool, by, rule),
+            (None, Some(approver)) => format!("{} by {} {}", tool, by, approver),
+            (None, None) => format!("{} by {}", tool, by),
+        },
+        AuditEvent::Rejected { tool, by, reason, approver, .. } => {
+            let by = match approver {
+                Some(approver) => format!("{} {}", by, approver),
+                None => by.to_string(),
+            };
+            match reason {
+                Some(reason) => format!("{} by {}: {}", tool, by, reason),
+                None => format!("{} by {}", tool, by),
+            }
+        }
+        AuditEvent::ToolExecuted { tool, arguments, success, cancelled, .. } => {
+            let outcome = if *cancelled { "cancelled" } else if *success { "ok" } else { "failed" };
+            format!("{} [{}] {}", tool, outcome, arguments)
+        }
+        AuditEvent::CommandRun { command, success, .. } => {
+            format!("[{}] {}", if *success { "ok" } else { "failed" }, command)
+        }
+        AuditEvent::SessionCompacted { messages_summarized, chars_before, chars_after } => {
+            format!("{} messages summarized ({} -> {} chars)", messages_summarized, chars_before, chars_after)
+        }
+    };
+    println!("  {}  {:<18}  {}", style(time).dim(), record.event.name(), detail);
+}
+
+/// Print the metrics in the telemetry textfile, without histogram buckets
+fn dump_metrics(config: &TelemetryConfig) -> anyhow::Result<()> {
+    let path = config.textfile_path();
+    println!("{}", style(format!("Metrics ({}):", path.display())).bold());
+    println!();
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let hint = if config.enabled { "No metrics recorded yet" } else { "No metrics recorded ([telemetry] is disabled)" };
+            println!("  {}", style(hint).dim());
+            return Ok(());
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let samples: Vec<_> = telemetry::parse_exposition(&text)
+        .into_iter()
+        .filter(|sample| !sample.series.split('{').next().unwrap_or_default().ends_with("_bucket"))
+        .collect();
+    let width = samples.iter().map(|sample| sample.series.chars().count()).max().unwrap_or(0);
+    for sample in samples {
+        println!("  {:<width$}  {}", sample.series, sample.value);
+    }
+    Ok(())
+}
+
+/// What `cowork usage` reports
+#[derive(Debug, Clone, Copy)]
+enum UsageBy {
+    /// Tokens and cost, grouped
+    Tokens(UsageGroupBy),
+    /// Tool calls, from the saved sessions
+    Tool,
+}
+
+impl std::str::FromStr for UsageBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("tool") {
+            return Ok(Self::Tool);
+        }
+        s.parse()
+            .map(Self::Tokens)
+            .map_err(|_| format!("Unknown grouping '{}' (expected model, day, session, purpose, or tool)", s))
+    }
+}
+
+fn show_usage(since: Option<&str>, by: UsageGroupBy) -> anyhow::Result<()> {
+    let window = since.map(parse_window).transpose()?;
+    let report = UsageStore::open_default().report(window, by)?;
+
+    let heading = match since {
+        Some(since) => format!("Usage (last {}):", since),
+        None => "Usage (all recorded):".to_string(),
+    };
+    println!("{}", style(heading).bold());
+    println!();
+
+    if report.rows.is_empty() {
+        println!("  {}", style("No usage recorded").dim());
+        return Ok(());
+    }
+
+    let key_width = report
+        .rows
+        .iter()
+        .map(|row| row.key.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(5);
+    println!(
+        "  {}",
+        style(format!(
+            "{:<key_width$}  {:>8}  {:>12}  {:>12}  {:>12}  {:>10}",
+            "", "requests", "input", "cached", "output", "cost"
+        ))
+        .dim()
+    );
+    for row in &report.rows {
+        print_usage_row(row, key_width);
+    }
+    println!();
+    print_usage_row(&report.total, key_width);
+
+    if report.total.unpriced_requests > 0 {
+        println!();
+        println!(
+            "  {}",
+            style(format!(
+                "{} request(s) used models without known pricing and are not included in cost",
+                report.total.unpriced_requests
+            ))
+            .dim()
+        );
+    }
+    Ok(())
+}
+
+fn show_tool_usage(since: Option<&str>) -> anyhow::Result<()> {
+    let window = since.map(parse_window).transpose()?;
+    let report = tool_usage_report(window)?;
+
+    let heading = match since {
+        Some(since) => format!("Tool calls (sessions updated in the last {}):", since),
+        None => "Tool calls (all saved sessions):".to_string(),
+    };
+    println!("{}", style(heading).bold());
+    println!();
+
+    if report.usage.is_empty() {
+        println!("  {}", style("No tool calls recorded").dim());
+        return Ok(());
+    }
+
+    let rows = report.usage.top(usize::MAX);
+    let key_width = rows.iter().map(|(tool, _)| tool.chars().count()).max().unwrap_or(0).max(5);
+    println!(
+        "  {}",
+        style(format!(
+            "{:<key_width$}  {:>8}  {:>8}  {:>8}  {:>8}  {:>10}  {:>10}",
+            "", "calls", "ok", "failed", "rejected", "time", "to model"
+        ))
+        .dim()
+    );
+    for (tool, counts) in rows {
+        print_tool_usage_row(tool, counts, key_width);
+    }
+    println!();
+    print_tool_usage_row("total", &report.usage.total(), key_width);
+    println!();
+    println!("  {}", style(format!("{} session(s)", report.sessions)).dim());
+    Ok(())
+}
+
+fn print_tool_usage_row(tool: &str, counts: &ToolCounts, key_width: usize) {
+    println!(
+        "  {:<key_width$}  {:>8}  {:>8}  {:>8}  {:>8}  {:>9.1}s  {:>7} KB",
+        tool,
+        format_count(counts.invocations),
+        format_count(counts.successes),
+        format_count(counts.failures),
+        format_count(counts.rejections),
+        counts.duration_ms as f64 / 1000.0,
+        format_count(counts.result_bytes.div_ceil(1024)),
+    );
+}
+
+fn print_usage_row(row: &UsageRow, key_width: usize) {
+    let cost = row.cost.map(|c| format!("${:.2}", c)).unwrap_or_else(|| "-".to_string());
+    println!(
+        "  {:<key_width$}  {:>8}  {:>12}  {:>12}  {:>12}  {:>10}",
+        row.key,
+        format_count(row.requests),
+        format_count(row.input_tokens),
+        format_count(row.cached_tokens),
+        format_count(row.output_tokens),
+        cost
+    );
+}
+
+/// Format a count with thousands separators
+fn format_count(n: u64) -> String {
+    let digits = n.to_string();
+    let mut out = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
 /// Handle plugin management commands
 fn handle_plugin_command(workspace: &Path, cmd: PluginCommands) -> anyhow::Result<()> {
     // Use core's convenience constructor
@@ -1110,6 +3033,46 @@ fn handle_plugin_command(workspace: &Path, cmd: PluginCommands) -> anyhow::Resul
 }
 
 /// Handle component listing commands
+fn handle_recipe_command(workspace: &Path, cmd: RecipeCommands) -> anyhow::Result<()> {
+    match cmd {
+        RecipeCommands::List => {
+            let recipes = recipes::list_recipes(workspace);
+            if recipes.is_empty() {
+                println!("No recipes in {}", recipes::recipes_dir(workspace).display());
+                return Ok(());
+            }
+            println!("{}", style("Recipes:").bold());
+            println!();
+            for (name, recipe) in recipes {
+                match recipe {
+                    Ok(recipe) => {
+                        println!("  {}", style(recipe.usage()).cyan());
+                        if let Some(line) = recipe.description.lines().next() {
+                            println!("    {}", line);
+                        }
+                    }
+                    Err(e) => println!("  {} {}", style(name).red(), style(format!("({})", e)).dim()),
+                }
+            }
+        }
+        RecipeCommands::Validate { name, dry_run, args } => {
+            let recipe = recipes::load_recipe(workspace, &name)?;
+            // MCP tools are only known inside a session
+            let (tools, skills) = recipes::builtin_tools_and_skills(workspace);
+            let problems = recipe.validate(&tools, &skills);
+            if !problems.is_empty() {
+                anyhow::bail!("{} is not valid:\n- {}", name, problems.join("\n- "));
+            }
+            if dry_run {
+                println!("{}", RecipeRunner::new(recipe, &args, &tools, &skills)?.format_plan()?);
+            } else {
+                println!("{} is valid ({} steps)", name, recipe.steps.len());
+            }
+        }
+    }
+    Ok(())
+}
+
 fn handle_component_command(workspace: &Path, cmd: ComponentCommands) -> anyhow::Result<()> {
     // Use core's convenience constructor
     let registry = ComponentRegistry::for_workspace(workspace)
@@ -1130,11 +3093,17 @@ fn handle_component_command(workspace: &Path, cmd: ComponentCommands) -> anyhow:
             agents.sort_by_key(|a| a.name());
 
             for agent in agents {
+                let origin = if agent.scope == cowork_core::prompt::Scope::Builtin {
+                    style("[built-in]").dim()
+                } else {
+                    style("[custom]").green()
+                };
                 println!(
-                    "  {} {} - {}",
+                    "  {} {} - {} {}",
                     style("•").cyan(),
                     style(agent.name()).bold(),
-                    agent.description()
+                    agent.description(),
+                    origin
                 );
                 let model = agent.model();
                 if !matches!(model, cowork_core::ModelPreference::Inherit) {
@@ -1229,30 +3198,39 @@ fn handle_component_command(workspace: &Path, cmd: ComponentCommands) -> anyhow:
         }
     }
 
+    let errors = registry.load_errors();
+    if !errors.is_empty() {
+        println!();
+        println!("{}", style("Failed to load:").bold().yellow());
+        for error in errors {
+            println!("  {} {}", style(error.path.display()).yellow(), error.message);
+        }
+    }
+
     Ok(())
 }
 
 /// Show setup instructions when no API key is configured
-fn show_setup_instructions(provider_id: &str) {
-    println!("{}", style("Welcome to Cowork!").bold().cyan());
+fn show_setup_instructions(provider_id: &str, localizer: Localizer) {
+    println!("{}", style(localizer.text(Text::SetupWelcome)).bold().cyan());
     println!();
-    println!("{}", style("Setup Required").bold().yellow());
-    println!("No API key configured. Please set one up before using Cowork.");
+    println!("{}", style(localizer.text(Text::SetupRequired)).bold().yellow());
+    println!("{}", localizer.text(Text::SetupNoApiKey));
     println!();
 
     let env_var = catalog::api_key_env(provider_id).unwrap_or("API_KEY");
 
-    println!("{}", style("Option 1: Environment Variable (Quick)").bold());
+    println!("{}", style(localizer.text(Text::SetupEnvOption)).bold());
     println!("  export {}=\"your-api-key-here\"", style(env_var).cyan());
     println!();
 
-    println!("{}", style("Option 2: Config File (Persistent)").bold());
+    println!("{}", style(localizer.text(Text::SetupConfigOption)).bold());
     let config_path = ConfigManager::default_config_path()
         .map(|p| p.display().to_string())
         .unwrap_or_else(|_| "~/.config/cowork/config.toml".to_string());
-    println!("  Edit: {}", style(&config_path).cyan());
+    println!("  {}", localizer.format(Text::SetupEditConfig, &[("path", &style(&config_path).cyan())]));
     println!();
-    println!("  Example config:");
+    println!("  {}", localizer.text(Text::SetupExampleConfig));
     println!("  {}", style("─".repeat(50)).dim());
     println!(r#"  default_provider = "anthropic"
 
@@ -1266,13 +3244,13 @@ fn show_setup_instructions(provider_id: &str) {
     println!("  {}", style("─".repeat(50)).dim());
     println!();
 
-    println!("{}", style("Get your API key:").bold());
+    println!("{}", style(localizer.text(Text::SetupGetApiKey)).bold());
     println!("  Anthropic (Claude): {}", style("https://console.anthropic.com/").cyan());
     println!("  OpenAI (GPT-4):     {}", style("https://platform.openai.com/").cyan());
     println!();
 
-    println!("{}", style("After configuring, run 'cowork' again to start.").dim());
+    println!("{}", style(localizer.text(Text::SetupRunAgain)).dim());
     println!();
-    println!("For more help: {}", style("cowork --help").cyan());
+    println!("{}", localizer.format(Text::SetupMoreHelp, &[("command", &style("cowork --help").cyan())]));
 }
 
@@ -0,0 +1,87 @@
+//! Terminal bell and desktop notifications
+//!
+//! Alerts when a long turn finishes or a session is waiting on the user,
+//! per the `[notifications]` config section. Desktop notifications shell out
+//! to `notify-send` (Linux) or `osascript` (macOS); failures are ignored.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use cowork_core::config::NotificationsConfig;
+use cowork_core::notifications::{Notification, NotificationTracker};
+use cowork_core::session::SessionOutput;
+
+/// Turns session output into bells and desktop notifications
+pub struct Notifier {
+    config: NotificationsConfig,
+    tracker: NotificationTracker,
+}
+
+impl Notifier {
+    pub fn new(config: NotificationsConfig) -> Self {
+        Self {
+            config,
+            tracker: NotificationTracker::new(),
+        }
+    }
+
+    /// Record a session output, alerting the user if it needs attention
+    pub fn observe(&mut self, session_id: &str, output: &SessionOutput) {
+        if let Some(notification) = self.tracker.observe(&self.config, session_id, output) {
+            self.alert(&notification);
+        }
+    }
+
+    fn alert(&self, notification: &Notification) {
+        if self.config.terminal_bell {
+            let mut stdout = std::io::stdout();
+            let _ = stdout.write_all(b"\x07");
+            let _ = stdout.flush();
+        }
+        if self.config.desktop
+            && let Some(mut command) = desktop_command(notification)
+        {
+            // Reap the child off-thread so a slow notifier never blocks the UI
+            std::thread::spawn(move || {
+                let _ = command
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .status();
+            });
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn desktop_command(notification: &Notification) -> Option<Command> {
+    let script = format!(
+        "display notification {} with title \"Cowork\" subtitle {}",
+        applescript_string(&notification.body),
+        applescript_string(&notification.title)
+    );
+    let mut command = Command::new("osascript");
+    command.arg("-e").arg(script);
+    Some(command)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn desktop_command(notification: &Notification) -> Option<Command> {
+    let mut command = Command::new("notify-send");
+    command
+        .arg("--app-name=Cowork")
+        .arg(format!("Cowork: {}", notification.title))
+        .arg(&notification.body);
+    Some(command)
+}
+
+#[cfg(not(unix))]
+fn desktop_command(_notification: &Notification) -> Option<Command> {
+    None
+}
+
+/// Quote text as an AppleScript string literal
+#[cfg(target_os = "macos")]
+fn applescript_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
@@ -1,13 +1,15 @@
 //! Onboarding wizard for first-run setup
 //!
 //! Guides new users through provider selection, API key configuration,
-//! and initial setup.
+//! and initial setup (optional web search and remote workspace).
 
 use console::style;
-use dialoguer::{theme::ColorfulTheme, Confirm, Password, Select};
+use dialoguer::{theme::ColorfulTheme, Confirm, Input, Password, Select};
 
-use cowork_core::config::{ConfigManager, ProviderConfig, WebSearchConfig};
-use cowork_core::provider::{catalog, GenAIProvider};
+use cowork_core::credentials;
+use cowork_core::config::{ConfigManager, ProviderConfig, RemoteTarget, WebSearchConfig};
+use cowork_core::provider::{catalog, probe_provider, GenAIProvider, TestReport, PROBE_TIMEOUT};
+use cowork_core::tools::remote::{RemoteFileKind, RemoteWorkspace};
 use cowork_core::tools::web::supports_native_search;
 
 /// Provider information for configuration and display
@@ -214,6 +216,9 @@ impl OnboardingWizard {
                 self.offer_serpapi_setup()?;
             }
 
+            // Optional: work on a remote machine over SSH
+            self.offer_remote_setup().await?;
+
             // Show completion
             self.show_completion(&provider_info);
 
@@ -286,6 +291,96 @@ impl OnboardingWizard {
         Ok(())
     }
 
+    /// Offer to set up a remote workspace reached over SSH
+    async fn offer_remote_setup(&mut self) -> anyhow::Result<()> {
+        println!();
+        println!(
+            "{} {}",
+            style("Optional:").bold().yellow(),
+            style("Remote Workspace").bold()
+        );
+        println!();
+        println!(
+            "  {}",
+            style("Cowork can work on a directory on another machine over SSH.").dim()
+        );
+        println!();
+
+        let setup = Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt("Would you like to work on a remote machine?")
+            .default(false)
+            .interact()?;
+
+        if !setup {
+            return Ok(());
+        }
+
+        let destination: String = Input::with_theme(&ColorfulTheme::default())
+            .with_prompt("SSH host (user@host or ~/.ssh/config alias)")
+            .interact_text()?;
+        let (user, host) = match destination.trim().split_once('@') {
+            Some((user, host)) => (Some(user.to_string()), host.to_string()),
+            None => (None, destination.trim().to_string()),
+        };
+        let port: u16 = Input::with_theme(&ColorfulTheme::default())
+            .with_prompt("SSH port")
+            .default(22)
+            .interact_text()?;
+        let identity_file: String = Input::with_theme(&ColorfulTheme::default())
+            .with_prompt("Identity file (empty for ssh's default keys or agent)")
+            .allow_empty(true)
+            .interact_text()?;
+        let root: String = Input::with_theme(&ColorfulTheme::default())
+            .with_prompt("Workspace path on the remote host")
+            .validate_with(|path: &String| {
+                if path.starts_with('/') {
+                    Ok(())
+                } else {
+                    Err("Use an absolute path")
+                }
+            })
+            .interact_text()?;
+
+        let target = RemoteTarget {
+            host,
+            user,
+            port: (port != 22).then_some(port),
+            identity_file: (!identity_file.trim().is_empty()).then(|| identity_file.trim().into()),
+            root,
+            ssh_options: Vec::new(),
+        };
+
+        println!();
+        println!("  {}", style(format!("Connecting to {}...", target)).dim());
+        let remote = RemoteWorkspace::new(target.clone());
+        match remote.kind(remote.root()).await {
+            Ok(Some(RemoteFileKind::Directory)) => println!(
+                "  {} {}",
+                style("✓").green().bold(),
+                style("Remote workspace reachable!").green()
+            ),
+            Ok(_) => println!(
+                "  {} {}",
+                style("!").yellow().bold(),
+                style(format!("{} is not a directory on the remote host", target.root)).yellow()
+            ),
+            Err(e) => println!(
+                "  {} {}",
+                style("!").yellow().bold(),
+                style(format!("Could not connect: {}", e)).yellow()
+            ),
+        }
+
+        self.config_manager.config_mut().remote = Some(target);
+        self.config_manager.save()?;
+        println!(
+            "  {}",
+            style("Saved to [remote] in the config file; remove it to work locally again.").dim()
+        );
+
+        Ok(())
+    }
+
     /// Consume the wizard and return the config manager
     pub fn into_config_manager(self) -> ConfigManager {
         self.config_manager
@@ -407,7 +502,7 @@ impl OnboardingWizard {
                 .template("{spinner:.blue} {msg}")
                 .unwrap(),
         );
-        spinner.set_message("Connecting to API...");
+        spinner.set_message("Checking the key, tool calling and streaming...");
         spinner.enable_steady_tick(std::time::Duration::from_millis(100));
 
         // Create provider and make test call
@@ -419,23 +514,27 @@ impl OnboardingWizard {
             }
         };
 
-        let test_messages = vec![cowork_core::provider::ChatMessage::user("Say 'hello' in one word.")];
-
-        let result = provider.chat(test_messages, None).await;
+        let report = probe_provider(&provider, None, PROBE_TIMEOUT).await;
 
         spinner.finish_and_clear();
 
-        match result {
-            Ok(_) => {
-                println!(
-                    "  {} {}",
-                    style("✓").green().bold(),
-                    style("Connection successful!").green()
-                );
+        match report.error {
+            None => {
+                print_report(&report);
+                if report.tools_ok {
+                    return Ok(true);
+                }
+
+                // Without tool calling the agent can't do its job, so don't
+                // make this model the default unless the user insists
+                let keep = Confirm::with_theme(&ColorfulTheme::default())
+                    .with_prompt(format!("{} failed the tool-calling test. Use it as the default anyway?", model))
+                    .default(false)
+                    .interact()?;
                 println!();
-                Ok(true)
+                Ok(keep)
             }
-            Err(e) => {
+            Some(ref e) => {
                 println!(
                     "  {} {}",
                     style("✗").red().bold(),
@@ -493,9 +592,6 @@ impl OnboardingWizard {
             .unwrap_or_else(|| ProviderConfig::for_provider(provider_name));
 
         provider_config.model = provider_info.default_model.to_string();
-        if let Some(key) = api_key {
-            provider_config.api_key = Some(key.to_string());
-        }
         // base_url defaults to None (uses the provider's default endpoint).
         // Pro users can set a custom base_url in the config file.
 
@@ -504,6 +600,24 @@ impl OnboardingWizard {
             .set_provider(provider_name, provider_config);
         self.config_manager.set_default_provider(provider_name);
 
+        // New keys go into the OS keychain where there is one
+        if let Some(key) = api_key {
+            let stored = credentials::keyring_available()
+                && match self.config_manager.set_keyring_api_key(provider_name, key) {
+                    Ok(()) => {
+                        println!("  {}", style("API key saved in the OS keychain").dim());
+                        true
+                    }
+                    Err(e) => {
+                        println!("  {}", style(format!("{}; saving the key in the config file", e)).yellow());
+                        false
+                    }
+                };
+            if !stored {
+                self.config_manager.set_api_key_for(provider_name, key.to_string());
+            }
+        }
+
         // Save to disk (ConfigManager adds sample config comments for new files)
         self.config_manager.save()?;
 
@@ -601,6 +715,25 @@ impl OnboardingWizard {
     }
 }
 
+/// Print the probe results, one line per capability
+fn print_report(report: &TestReport) {
+    let mark = |ok: bool| if ok { style("✓").green().bold() } else { style("✗").red().bold() };
+    println!(
+        "  {} {}",
+        mark(true),
+        style(format!("Connection successful ({} ms)", report.latency_ms)).green()
+    );
+    println!("  {} Tool calling", mark(report.tools_ok));
+    println!("  {} Streaming", mark(report.streaming_ok));
+    if let Some(limit) = report.context_limit {
+        println!("  {} Context window: {} tokens", mark(true), limit);
+    }
+    for warning in &report.warnings {
+        println!("  {}", style(format!("Warning: {}", warning)).yellow());
+    }
+    println!();
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -612,3 +745,4 @@ mod tests {
         assert_eq!(info.env_var, "ANTHROPIC_API_KEY");
     }
 }
+
@@ -1,13 +1,26 @@
 //! Application state and types for the TUI
 
-use cowork_core::formatting::{format_ephemeral, truncate_str};
+use cowork_core::completion::PathCompleter;
+use cowork_core::formatting::{
+    format_ephemeral, format_tool_progress, tool_call_diff, truncate_str, FileDiff, PathDisplay,
+};
 pub use cowork_core::DiffLine;
 use std::time::Instant;
-use cowork_core::session::SessionOutput;
+use cowork_core::i18n::{Localizer, Text};
+use cowork_core::provider::GenerationParams;
+use cowork_core::session::{SessionOutput, ToolDoneStatus};
+use cowork_core::recipes::StepStatus;
+use cowork_core::tools::task::TodoItem;
+use cowork_core::tools::Artifact;
 use cowork_core::QuestionInfo;
 use std::collections::{HashMap, HashSet};
 use tui_input::Input;
 
+use super::events::{handle_key_normal, handle_key_vim, KeyAction};
+use super::keymap::Keymap;
+use super::vim::VimState;
+use crate::aliases::Aliases;
+
 /// Message types for display in the output area
 #[derive(Debug, Clone)]
 pub enum MessageType {
@@ -26,7 +39,11 @@ pub enum MessageType {
         success: bool,
         elapsed_secs: f32,
         diff: Option<Vec<DiffLine>>,
+        /// Structured result, shown as text under the summary
+        artifacts: Vec<Artifact>,
         expanded: bool,
+        /// The file the tool worked on and the line to open it at
+        file: Option<(String, Option<u32>)>,
     },
 }
 
@@ -76,6 +93,7 @@ impl Message {
         success: bool,
         elapsed_secs: f32,
         diff: Option<Vec<DiffLine>>,
+        artifacts: Vec<Artifact>,
     ) -> Self {
         Self {
             message_type: MessageType::ToolResult {
@@ -83,11 +101,29 @@ impl Message {
                 success,
                 elapsed_secs,
                 diff,
+                artifacts,
                 expanded: false,
+                file: None,
             },
             content: String::new(),
         }
     }
+
+    /// Set the file a tool result is about, for opening it in the editor
+    pub fn with_file(mut self, path: Option<String>, line: Option<u32>) -> Self {
+        if let MessageType::ToolResult { file, .. } = &mut self.message_type {
+            *file = path.map(|path| (path, line));
+        }
+        self
+    }
+
+    /// The file a tool result is about, with the line to open it at
+    pub fn file(&self) -> Option<&(String, Option<u32>)> {
+        match &self.message_type {
+            MessageType::ToolResult { file, .. } => file.as_ref(),
+            _ => None,
+        }
+    }
 }
 
 /// Pending tool approval request
@@ -97,27 +133,41 @@ pub struct PendingApproval {
     pub name: String,
     pub arguments: serde_json::Value,
     pub description: Option<String>,
+    /// Diff the call would make (Edit/Write), computed once when the request arrives
+    pub diff: Option<FileDiff>,
     pub selected_option: usize,
+    /// Whether an explanation was asked for (only one is given per call)
+    pub explain_requested: bool,
+    /// Why the model wants the call, once it's explained
+    pub explanation: Option<String>,
+    /// Why the call looks like secret exfiltration, if it does
+    pub security_warning: Option<String>,
 }
 
 impl PendingApproval {
-    pub fn new(id: String, name: String, arguments: serde_json::Value, description: Option<String>) -> Self {
+    pub fn new(
+        id: String,
+        name: String,
+        arguments: serde_json::Value,
+        description: Option<String>,
+        paths: &PathDisplay,
+    ) -> Self {
+        let diff = tool_call_diff(&name, &arguments, paths);
         Self {
             id,
             name,
             arguments,
             description,
+            diff,
             selected_option: 0,
+            explain_requested: false,
+            explanation: None,
+            security_warning: None,
         }
     }
 
-    pub fn options(&self) -> &[&str] {
-        &[
-            "Yes - approve this call",
-            "No - reject this call",
-            "Always - auto-approve for session",
-            "Approve all - auto-approve everything",
-        ]
+    pub fn options(&self, localizer: &Localizer) -> [&'static str; 4] {
+        [Text::ApprovalYes, Text::ApprovalNo, Text::ApprovalAlways, Text::ApprovalAll].map(|text| localizer.text(text))
     }
 
     pub fn select_next(&mut self) {
@@ -236,16 +286,59 @@ pub struct App {
     pub turn_start: Option<Instant>,
     /// Whether plan mode is active
     pub plan_mode: bool,
+    /// Generation parameters of the session (passed to `/settings model`)
+    pub generation_params: GenerationParams,
+    /// ID the session gave the last user message (the one `/edit-last` rewrites)
+    pub last_user_message_id: Option<String>,
+    /// Todo checklist maintained by the TodoWrite tool
+    pub todos: Vec<TodoItem>,
+    /// Whether the todo panel shows only its summary line
+    pub todos_collapsed: bool,
+    /// Tool calls of the current turn still waiting for a result: (id, name)
+    pub running_tools: Vec<(String, String)>,
+    /// Index into `running_tools` of the tool Ctrl+X cancels
+    pub highlighted_tool: usize,
+    /// Index into `messages` of the tool result highlighted for opening its file
+    pub selected_result: Option<usize>,
+    /// Keys for the remappable actions
+    pub keymap: Keymap,
+    /// Modal editing state of the input (None = vim mode off)
+    pub vim: Option<VimState>,
+    /// Slash-command aliases
+    pub aliases: Aliases,
+    /// Built-in commands and skills offered as completions, with their `/`
+    pub slash_commands: Vec<String>,
+    /// Branch of the git worktree the session is isolated in
+    pub worktree: Option<String>,
+    /// The session's title, shown over the messages
+    pub title: Option<String>,
+    /// Last tool profile the session reported: name, tools offered and
+    /// rough tokens of their definitions
+    pub offered_tools: Option<(String, Vec<String>, u64)>,
+    /// Shows paths relative to the workspace
+    pub paths: PathDisplay,
+    /// Editor command template from `[general] editor` (None = detect one)
+    pub editor: Option<String>,
+    /// Roots files may be opened from: the workspace and any additional roots
+    pub roots: Vec<std::path::PathBuf>,
+    /// Completes `@` mentions of workspace files (None = no completion)
+    pub path_completer: Option<PathCompleter>,
+    /// Completions of the `@` mention last typed: the mention (without its
+    /// `@`) and the paths
+    mention_completions: Option<(String, Vec<String>)>,
+    /// Catalog of the text shown in the UI
+    pub localizer: Localizer,
 }
 
+/// How many completions of an `@` mention are shown
+const MENTION_COMPLETIONS: usize = 8;
+
 const SPINNER: &[char] = &['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
 
 impl App {
-    pub fn new(provider_info: String, version: String) -> Self {
+    pub fn new(provider_info: String, version: String, localizer: Localizer) -> Self {
         Self {
-            messages: vec![
-                Message::system("Welcome to Cowork. Type your message and press Enter. Ctrl+C to quit."),
-            ],
+            messages: vec![Message::system(localizer.text(Text::Welcome))],
             ephemeral: None,
             status: String::new(),
             tick: 0,
@@ -262,6 +355,26 @@ impl App {
             approve_all_session: false,
             turn_start: None,
             plan_mode: false,
+            generation_params: GenerationParams::default(),
+            last_user_message_id: None,
+            todos: Vec::new(),
+            todos_collapsed: false,
+            running_tools: Vec::new(),
+            highlighted_tool: 0,
+            selected_result: None,
+            keymap: Keymap::default(),
+            vim: None,
+            aliases: Aliases::default(),
+            slash_commands: Vec::new(),
+            worktree: None,
+            title: None,
+            offered_tools: None,
+            paths: PathDisplay::default(),
+            editor: None,
+            roots: Vec::new(),
+            path_completer: None,
+            mention_completions: None,
+            localizer,
         }
     }
 
@@ -298,6 +411,57 @@ impl App {
         self.scroll_offset = usize::MAX;
     }
 
+    /// The running tool Ctrl+X would cancel
+    pub fn highlighted_tool(&self) -> Option<&(String, String)> {
+        self.running_tools.get(self.highlighted_tool)
+    }
+
+    /// Highlight the next running tool
+    pub fn highlight_next_tool(&mut self) {
+        if !self.running_tools.is_empty() {
+            self.highlighted_tool = (self.highlighted_tool + 1) % self.running_tools.len();
+        }
+    }
+
+    /// Forget a tool call that has its result
+    fn tool_finished(&mut self, id: &str) {
+        self.running_tools.retain(|(tool_id, _)| tool_id != id);
+        if self.highlighted_tool >= self.running_tools.len() {
+            self.highlighted_tool = 0;
+        }
+    }
+
+    /// Highlight the tool result with a file before the highlighted one, or
+    /// the latest if none is; false if there is none
+    pub fn select_previous_result(&mut self) -> bool {
+        let end = self.selected_result.unwrap_or(self.messages.len());
+        match self.messages[..end].iter().rposition(|m| m.file().is_some()) {
+            Some(index) => {
+                self.selected_result = Some(index);
+                true
+            }
+            None => self.selected_result.is_some(),
+        }
+    }
+
+    /// Highlight the next tool result with a file, if there is one
+    pub fn select_next_result(&mut self) {
+        let Some(current) = self.selected_result else { return };
+        if let Some(offset) = self.messages[current + 1..].iter().position(|m| m.file().is_some()) {
+            self.selected_result = Some(current + 1 + offset);
+        }
+    }
+
+    /// The highlighted tool result's file and line
+    pub fn selected_file(&self) -> Option<&(String, Option<u32>)> {
+        self.messages.get(self.selected_result?)?.file()
+    }
+
+    /// Stop highlighting a tool result
+    pub fn clear_result_selection(&mut self) {
+        self.selected_result = None;
+    }
+
     /// Scroll up by one line
     pub fn scroll_up(&mut self) {
         if self.scroll_offset > 0 {
@@ -348,6 +512,122 @@ impl App {
         }
     }
 
+    /// Handle a key typed at the input, with vim keys when vim mode is on
+    pub fn handle_input_key(&mut self, key: crossterm::event::KeyEvent) -> KeyAction {
+        self.selected_result = None;
+        if key.code == crossterm::event::KeyCode::Tab && key.modifiers.is_empty() && self.accept_mention() {
+            return KeyAction::None;
+        }
+        let action = match &mut self.vim {
+            Some(vim) => handle_key_vim(key, &mut self.input, vim, &self.keymap),
+            None => handle_key_normal(key, &mut self.input, &self.keymap),
+        };
+        self.complete_mention();
+        action
+    }
+
+    /// Whether Esc goes to vim mode (leaving insert mode, dropping a
+    /// half-typed command) rather than cancelling the turn
+    pub fn input_wants_escape(&self) -> bool {
+        self.vim.as_ref().is_some_and(VimState::wants_escape)
+    }
+
+    /// Turn vim mode on or off
+    pub fn set_vim_mode(&mut self, on: bool) {
+        self.vim = on.then(VimState::new);
+    }
+
+    /// Replace the input with the most recent history entry containing the
+    /// text typed so far; repeating searches further back
+    pub fn search_history(&mut self) {
+        let (query, end) = match self.history_index {
+            Some(i) => (self.history_draft.clone(), i),
+            None => (self.input.value().to_string(), self.history.len()),
+        };
+        let Some(found) = self.history[..end].iter().rposition(|entry| entry.contains(&query)) else {
+            return;
+        };
+        self.history_draft = query;
+        self.history_index = Some(found);
+        self.input = Input::new(self.history[found].clone());
+    }
+
+    /// Completions for a partly typed slash command, aliases shown with
+    /// their expansion: `/commit  /compact  /c → /commit`
+    pub fn slash_hint(&self) -> Option<String> {
+        let typed = self.input.value();
+        if !typed.starts_with('/') || typed.contains(char::is_whitespace) {
+            return None;
+        }
+        let mut hints: Vec<String> = self
+            .slash_commands
+            .iter()
+            .filter(|c| c.starts_with(typed))
+            .cloned()
+            .collect();
+        hints.extend(
+            self.aliases
+                .iter()
+                .filter(|(alias, _)| alias.starts_with(typed))
+                .map(|(alias, expansion)| format!("{} → {}", alias, expansion)),
+        );
+        // An exact alias goes first so its expansion is always visible
+        if let Some(expansion) = self.aliases.expansion(typed) {
+            let exact = format!("{} → {}", typed, expansion);
+            hints.retain(|h| *h != exact);
+            hints.insert(0, exact);
+        }
+        (!hints.is_empty()).then(|| hints.join("  "))
+    }
+
+    /// The `@` mention the input ends with (the cursor at its end), without its `@`
+    fn typed_mention(&self) -> Option<&str> {
+        let typed = self.input.value();
+        if self.input.cursor() != typed.chars().count() {
+            return None;
+        }
+        let token = typed.rsplit(char::is_whitespace).next()?;
+        token.strip_prefix('@').filter(|path| !path.contains(['@', '"', '`']))
+    }
+
+    /// Look up the completions of the `@` mention being typed, if it changed
+    fn complete_mention(&mut self) {
+        let (Some(completer), Some(prefix)) = (&self.path_completer, self.typed_mention()) else {
+            self.mention_completions = None;
+            return;
+        };
+        if self.mention_completions.as_ref().is_some_and(|(cached, _)| cached == prefix) {
+            return;
+        }
+        let paths = completer.complete_paths(prefix, MENTION_COMPLETIONS);
+        self.mention_completions = Some((prefix.to_string(), paths));
+    }
+
+    /// Workspace files completing the `@` mention being typed, best first:
+    /// `@src/main.rs  @src/map.rs`
+    pub fn mention_hint(&self) -> Option<String> {
+        let (prefix, paths) = self.mention_completions.as_ref()?;
+        if self.typed_mention() != Some(prefix.as_str()) || paths.is_empty() {
+            return None;
+        }
+        Some(paths.iter().map(|path| format!("@{}", path)).collect::<Vec<_>>().join("  "))
+    }
+
+    /// Replace the `@` mention being typed with its best completion (Tab);
+    /// false when there is none
+    fn accept_mention(&mut self) -> bool {
+        let Some((prefix, paths)) = self.mention_completions.take() else {
+            return false;
+        };
+        let Some(path) = paths.first().filter(|_| self.typed_mention() == Some(prefix.as_str())) else {
+            return false;
+        };
+        let typed = self.input.value();
+        let path = if path.contains(char::is_whitespace) { format!("\"{}\"", path) } else { path.clone() };
+        self.input = Input::new(format!("{}{} ", &typed[..typed.len() - prefix.len()], path));
+        true
+    }
+
     /// Check if a tool should be auto-approved
     pub fn should_auto_approve(&self, tool_name: &str) -> bool {
         self.approve_all_session || self.session_approved_tools.contains(tool_name)
@@ -359,12 +639,18 @@ impl App {
             SessionOutput::Ready => {
                 self.status = "Ready".to_string();
             }
-            SessionOutput::Idle => {
+            SessionOutput::Idle { review_warning, .. } => {
                 self.status.clear();
                 self.ephemeral = None;
+                self.running_tools.clear();
+                if review_warning {
+                    self.status = "Self-review flagged this turn".to_string();
+                }
+            }
+            SessionOutput::UserMessage { id, .. } => {
+                self.last_user_message_id = Some(id);
             }
-            SessionOutput::UserMessage { .. } => {}
-            SessionOutput::Thinking { content } => {
+            SessionOutput::Thinking { content, .. } => {
                 if content.is_empty() {
                     self.status = "Processing...".to_string();
                 } else {
@@ -390,10 +676,33 @@ impl App {
             }
             SessionOutput::ToolStart { name, arguments, .. } => {
                 self.status = "Processing...".to_string();
-                self.ephemeral = Some(format_ephemeral(&name, &arguments));
+                self.ephemeral = Some(format_ephemeral(&name, &arguments, &self.paths));
+            }
+            SessionOutput::ToolPending { id, name, arguments, description, security_warning, .. } => {
+                let mut approval = PendingApproval::new(id, name, arguments, description, &self.paths);
+                approval.security_warning = security_warning;
+                self.modal = Some(Modal::Approval(approval));
+            }
+            SessionOutput::ApprovalDelegated { tool, reason, channel, timeout_secs, .. } => {
+                // Only a reviewer can approve it; Esc still cancels the turn
+                self.add_message(Message::system(format!(
+                    "{} waits for a reviewer on {} ({}); refused after {}s without an answer",
+                    tool, channel, reason, timeout_secs
+                )));
             }
-            SessionOutput::ToolPending { id, name, arguments, description, .. } => {
-                self.modal = Some(Modal::Approval(PendingApproval::new(id, name, arguments, description)));
+            SessionOutput::ToolExplanation { id, text, .. } => match &mut self.modal {
+                Some(Modal::Approval(approval)) if approval.id == id => approval.explanation = Some(text),
+                // Answered after the dialog closed
+                _ => self.add_message(Message::system(text)),
+            },
+            SessionOutput::ToolProgress { id, elapsed_ms, output_bytes, last_line, .. } => {
+                // Only while the call is still running
+                if let Some((_, name)) = self.running_tools.iter().find(|(tool_id, _)| *tool_id == id) {
+                    self.ephemeral = Some(format_tool_progress(name, elapsed_ms, output_bytes, last_line.as_deref()));
+                }
+            }
+            SessionOutput::ToolDone { name, status: ToolDoneStatus::Cancelled, .. } => {
+                self.ephemeral = Some(format!("{}: cancelled", name));
             }
             SessionOutput::ToolDone { name, success, output, .. } => {
                 if success {
@@ -403,33 +712,46 @@ impl App {
                     self.ephemeral = Some(format!("{}: {}", name, err));
                 }
             }
-            SessionOutput::ToolCall { formatted, .. } => {
+            SessionOutput::ToolCall { id, name, formatted, .. } => {
                 // Add tool call as a persistent message with elapsed time
                 let elapsed = self.elapsed_secs();
                 self.add_message(Message::tool_call(&formatted, elapsed));
+                self.running_tools.push((id, name));
             }
-            SessionOutput::ToolResult { summary, success, diff_preview, .. } => {
+            SessionOutput::ToolResult { id, summary, success, diff_preview, artifacts, absolute_path, line, .. } => {
+                self.tool_finished(&id);
                 // Add tool result as a persistent message with elapsed time
                 let elapsed = self.elapsed_secs();
-                self.add_message(Message::tool_result(&summary, success, elapsed, diff_preview));
+                self.add_message(
+                    Message::tool_result(&summary, success, elapsed, diff_preview, artifacts).with_file(absolute_path, line),
+                );
                 // Clear ephemeral since we have the result
                 self.ephemeral = None;
             }
             SessionOutput::Question { request_id, questions, .. } => {
                 self.modal = Some(Modal::Question(PendingQuestion::new(request_id, questions)));
             }
-            SessionOutput::Error { message } => {
+            SessionOutput::Error { message, .. } => {
                 self.add_message(Message::error(message));
                 self.status.clear();
                 self.ephemeral = None;
             }
+            SessionOutput::Warning { message, .. } => {
+                self.add_message(Message::system(format!("Warning: {}", message)));
+            }
+            SessionOutput::Refusal { reason, message, .. } => {
+                self.add_message(Message::error(match message {
+                    Some(message) => format!("Response refused ({}): {}", reason, message),
+                    None => format!("Response refused ({})", reason),
+                }));
+            }
             SessionOutput::Cancelled => {
-                self.add_message(Message::system("Cancelled".to_string()));
+                self.add_message(Message::system(self.localizer.text(Text::Cancelled)));
                 self.status.clear();
                 self.ephemeral = None;
                 self.modal = None;
             }
-            SessionOutput::PlanModeChanged { active, plan_file } => {
+            SessionOutput::PlanModeChanged { active, plan_file, .. } => {
                 self.plan_mode = active;
                 if active {
                     if let Some(ref path) = plan_file {
@@ -443,6 +765,113 @@ impl App {
                     self.add_message(Message::system("Plan mode disabled."));
                 }
             }
+            SessionOutput::HistoryTruncated { .. } => {
+                // The transcript stays as it was; the session no longer sees what follows
+                self.add_message(Message::system(
+                    "Discarded the last turn; re-running it",
+                ));
+            }
+            SessionOutput::Suspended => {
+                self.add_message(Message::system("Session suspended while idle; your next message resumes it"));
+            }
+            SessionOutput::Resumed { suspended_secs, .. } => {
+                self.add_message(Message::system(format!(
+                    "Session resumed after {}m suspended", suspended_secs / 60
+                )));
+            }
+            SessionOutput::Worktree { path, branch, .. } => {
+                self.add_message(Message::system(format!(
+                    "Working in an isolated worktree at {} (branch {}); /merge reviews and merges its changes",
+                    path, branch
+                )));
+                self.worktree = Some(branch);
+            }
+            SessionOutput::MessageQueued { position, .. } => {
+                self.add_message(Message::system(format!("Queued ({})", position)));
+            }
+            SessionOutput::FilesChangedExternally { paths, .. } => {
+                self.add_message(Message::system(format!(
+                    "Changed outside this session: {}", paths.join(", ")
+                )));
+            }
+            SessionOutput::GenerationParamsChanged { params, .. } => {
+                self.generation_params = params;
+            }
+            SessionOutput::PlanStatusChanged { name, status, .. } => {
+                self.add_message(Message::system(format!("Plan {}: {}", name, status)));
+            }
+            SessionOutput::TodosUpdated { items, .. } => {
+                self.todos = items;
+            }
+            SessionOutput::TitleChanged { title, .. } => {
+                self.title = Some(title);
+            }
+            SessionOutput::ToolProfileChanged { profile, tools, definition_tokens, .. } => {
+                self.add_message(Message::system(format!(
+                    "Tool profile {}: {} tools (~{} tokens per request)",
+                    profile,
+                    tools.len(),
+                    definition_tokens
+                )));
+                self.offered_tools = Some((profile, tools, definition_tokens));
+            }
+            SessionOutput::TurnOverridden { model, approval, tool_profile, .. } => {
+                let mut overrides = Vec::new();
+                if let Some(model) = model {
+                    overrides.push(format!("model {}", model));
+                }
+                if let Some(approval) = approval {
+                    overrides.push(format!("approval {}", approval));
+                }
+                if let Some(profile) = tool_profile {
+                    overrides.push(format!("tool profile {}", profile));
+                }
+                if !overrides.is_empty() {
+                    self.add_message(Message::system(format!("This turn runs with {}", overrides.join(", "))));
+                }
+            }
+            SessionOutput::RecipeStarted { name, steps, .. } => {
+                let list: Vec<String> = steps.iter().map(|s| format!("  - {}", s.label)).collect();
+                self.add_message(Message::system(format!("Recipe {}:\n{}", name, list.join("\n"))));
+            }
+            SessionOutput::RecipeStepStarted { name, step_id, .. } => {
+                self.status = format!("Recipe {}: {}...", name, step_id);
+            }
+            SessionOutput::RecipeStepFinished { step_id, status, output, .. } => {
+                let line = match status {
+                    StepStatus::Succeeded => format!("✓ {}", step_id),
+                    StepStatus::Failed => format!("✗ {}: {}", step_id, truncate_str(output.lines().next().unwrap_or_default(), 80)),
+                    StepStatus::Skipped => format!("- {} (skipped)", step_id),
+                };
+                self.add_message(Message::system(line));
+            }
+            SessionOutput::RecipeFinished { summary, success, .. } => {
+                let first = summary.lines().next().unwrap_or_default().to_string();
+                self.add_message(if success { Message::system(first) } else { Message::error(first) });
+            }
+            SessionOutput::UnsupportedInput { input_type, protocol_version, .. } => {
+                self.add_message(Message::error(format!(
+                    "This version of cowork doesn't support `{}` (protocol {})",
+                    input_type, protocol_version
+                )));
+            }
+            SessionOutput::Compacted { mode, model, messages_summarized, tokens_before, tokens_after, .. } => {
+                let by = model.map(|model| format!(" by {}", model)).unwrap_or_default();
+                self.add_message(Message::system(format!(
+                    "Compacted {} messages ({}{}): ~{} -> ~{} tokens",
+                    messages_summarized, mode, by, tokens_before, tokens_after
+                )));
+            }
+            SessionOutput::SelfReview { findings, .. } => {
+                self.add_message(Message::system(if findings.is_empty() {
+                    "Self-review: no gaps found".to_string()
+                } else {
+                    let lines: Vec<String> = findings.iter().map(|finding| format!("- {}", finding)).collect();
+                    format!("Self-review found {} gap(s):\n{}", findings.len(), lines.join("\n"))
+                }));
+            }
+            // Outputs from a newer core than this TUI knows about
+            _ => {}
         }
     }
 }
@@ -455,7 +884,7 @@ mod tests {
 
     #[test]
     fn test_assistant_message_displays_content_as_is() {
-        let mut app = App::new("test".to_string(), "0.1.0".to_string());
+        let mut app = App::new("test".to_string(), "0.1.0".to_string(), Localizer::default());
 
         // Content comes pre-formatted from core - TUI just displays it
         app.handle_session_output(SessionOutput::assistant_message(
@@ -471,7 +900,7 @@ mod tests {
 
     #[test]
     fn test_assistant_message_without_token_info() {
-        let mut app = App::new("test".to_string(), "0.1.0".to_string());
+        let mut app = App::new("test".to_string(), "0.1.0".to_string(), Localizer::default());
 
         app.handle_session_output(SessionOutput::assistant_message("msg1", "Hello world"));
 
@@ -482,7 +911,7 @@ mod tests {
 
     #[test]
     fn test_tool_pending_with_description() {
-        let mut app = App::new("test".to_string(), "0.1.0".to_string());
+        let mut app = App::new("test".to_string(), "0.1.0".to_string(), Localizer::default());
 
         let description = Some("Enter plan mode to design an implementation approach.".to_string());
         app.handle_session_output(SessionOutput::tool_pending(
@@ -502,9 +931,110 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_tool_explanation_shown_in_its_dialog() {
+        let mut app = App::new("test".to_string(), "0.1.0".to_string(), Localizer::default());
+        app.handle_session_output(SessionOutput::tool_pending("tool-1", "Bash", serde_json::json!({"command": "rm -rf target"}), None));
+
+        // Another call's explanation doesn't land in this dialog
+        app.handle_session_output(SessionOutput::tool_explanation("tool-0", "Lists files.\nRisk: none"));
+        app.handle_session_output(SessionOutput::tool_explanation("tool-1", "Cleans the build.\nRisk: deletes target/"));
+        let Some(Modal::Approval(approval)) = &app.modal else {
+            panic!("Expected Approval modal");
+        };
+        assert_eq!(approval.explanation.as_deref(), Some("Cleans the build.\nRisk: deletes target/"));
+        assert_eq!(app.messages.last().unwrap().content, "Lists files.\nRisk: none");
+    }
+
+    #[test]
+    fn test_tool_progress_shown_while_running() {
+        let mut app = App::new("test".to_string(), "0.1.0".to_string(), Localizer::default());
+        let progress = |id: &str| {
+            SessionOutput::tool_progress(cowork_core::session::ToolProgress {
+                tool_call_id: id.to_string(),
+                elapsed_ms: 65_000,
+                output_bytes: 512,
+                last_line: Some("running 12 tests".to_string()),
+            })
+        };
+
+        app.handle_session_output(SessionOutput::tool_call("tool-1", "Bash", serde_json::json!({}), "Bash(cargo test)", None));
+        app.handle_session_output(progress("tool-1"));
+        assert_eq!(app.ephemeral.as_deref(), Some("Bash: 1m 5s, 512 B output\n  running 12 tests"));
+
+        // A late heartbeat doesn't overwrite the result
+        app.handle_session_output(SessionOutput::tool_result("tool-1", "Bash", true, "ok", "Ran cargo test", None, Vec::new()));
+        app.handle_session_output(progress("tool-1"));
+        assert!(app.ephemeral.is_none());
+    }
+
+    #[test]
+    fn test_search_history_repeats_further_back() {
+        let mut app = App::new("test".to_string(), "0.1.0".to_string(), Localizer::default());
+        for entry in ["cargo build", "ls", "cargo test", "pwd"] {
+            app.push_history(entry.to_string());
+        }
+        app.input = Input::new("cargo".to_string());
+
+        app.search_history();
+        assert_eq!(app.input.value(), "cargo test");
+        app.search_history();
+        assert_eq!(app.input.value(), "cargo build");
+        // Nothing older matches; the input stays put
+        app.search_history();
+        assert_eq!(app.input.value(), "cargo build");
+        // Leaving history restores the search text
+        app.history_next();
+        app.history_next();
+        app.history_next();
+        app.history_next();
+        assert_eq!(app.input.value(), "cargo");
+    }
+
+    #[test]
+    fn test_slash_hint_shows_alias_expansion() {
+        let mut app = App::new("test".to_string(), "0.1.0".to_string(), Localizer::default());
+        app.slash_commands = vec!["/clear".to_string(), "/commit".to_string(), "/help".to_string()];
+        let config = [("/c".to_string(), "/commit".to_string())].into();
+        app.aliases = Aliases::load(&config, &app.slash_commands).0;
+
+        app.input = Input::new("/c".to_string());
+        assert_eq!(app.slash_hint().unwrap(), "/c → /commit  /clear  /commit");
+        app.input = Input::new("/co".to_string());
+        assert_eq!(app.slash_hint().unwrap(), "/commit");
+        app.input = Input::new("/commit now".to_string());
+        assert!(app.slash_hint().is_none());
+        app.input = Input::new("/zz".to_string());
+        assert!(app.slash_hint().is_none());
+    }
+
+    #[test]
+    fn test_mention_completed_with_tab() {
+        let workspace = tempfile::tempdir().unwrap();
+        std::fs::create_dir(workspace.path().join("src")).unwrap();
+        std::fs::write(workspace.path().join("src/main.rs"), "").unwrap();
+        std::fs::write(workspace.path().join("src/map.rs"), "").unwrap();
+        let mut app = App::new("test".to_string(), "0.1.0".to_string(), Localizer::default());
+        app.path_completer = Some(PathCompleter::new(workspace.path()));
+        let key = |code| crossterm::event::KeyEvent::new(code, crossterm::event::KeyModifiers::NONE);
+
+        for c in "see @src/mai".chars() {
+            app.handle_input_key(key(crossterm::event::KeyCode::Char(c)));
+        }
+        assert_eq!(app.mention_hint().unwrap(), "@src/main.rs");
+        app.handle_input_key(key(crossterm::event::KeyCode::Tab));
+        assert_eq!(app.input.value(), "see @src/main.rs ");
+        assert!(app.mention_hint().is_none());
+
+        // Not a mention: an email address
+        app.input = Input::new("mail dev@src".to_string());
+        app.handle_input_key(key(crossterm::event::KeyCode::End));
+        assert!(app.mention_hint().is_none());
+    }
+
     #[test]
     fn test_tool_pending_without_description() {
-        let mut app = App::new("test".to_string(), "0.1.0".to_string());
+        let mut app = App::new("test".to_string(), "0.1.0".to_string(), Localizer::default());
 
         app.handle_session_output(SessionOutput::tool_pending(
             "tool-1",
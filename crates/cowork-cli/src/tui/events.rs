@@ -6,13 +6,16 @@ use tokio::sync::mpsc;
 
 use cowork_core::session::SessionOutput;
 
+use super::keymap::{Binding, Keymap};
+use super::vim::{Buffer, VimMode, VimOutcome, VimState};
+
 /// Events that can occur in the TUI
 #[derive(Debug)]
 pub enum Event {
     /// Terminal event (key press, resize, etc.)
     Terminal(CrosstermEvent),
-    /// Session output from the agent loop
-    Session(String, SessionOutput),
+    /// Session output from the agent loop (boxed: some outputs are large)
+    Session(String, Box<SessionOutput>),
     /// Tick for UI refresh
     Tick,
 }
@@ -51,7 +54,7 @@ impl EventHandler {
         // Spawn session output forwarding task
         tokio::spawn(async move {
             while let Some((session_id, output)) = output_rx.recv().await {
-                if tx_clone.send(Event::Session(session_id, output)).is_err() {
+                if tx_clone.send(Event::Session(session_id, Box::new(output))).is_err() {
                     break;
                 }
             }
@@ -85,6 +88,8 @@ pub enum KeyAction {
     ApproveToolSession,
     /// Approve all tools for session
     ApproveAllSession,
+    /// Ask why the pending tool is wanted
+    ExplainTool,
     /// Answer question and move to next
     AnswerQuestion,
     /// Scroll up
@@ -99,10 +104,103 @@ pub enum KeyAction {
     HistoryPrev,
     /// History next
     HistoryNext,
+    /// Collapse or expand the todo panel
+    ToggleTodos,
+    /// Search input history for the text typed so far
+    SearchHistory,
+    /// Highlight the next running tool
+    HighlightNextTool,
+    /// Cancel the highlighted running tool
+    CancelTool,
+    /// Highlight the tool result before the highlighted one (or the latest)
+    SelectPreviousResult,
+    /// Highlight the tool result after the highlighted one
+    SelectNextResult,
+    /// Open the highlighted tool result's file in the editor
+    OpenResult,
+    /// Stop highlighting a tool result
+    ClearResultSelection,
 }
 
 /// Handle a key event in normal mode
-pub fn handle_key_normal(key: KeyEvent, input: &mut tui_input::Input) -> KeyAction {
+pub fn handle_key_normal(key: KeyEvent, input: &mut tui_input::Input, keymap: &Keymap) -> KeyAction {
+    if keymap.is(Binding::ToggleTodos, &key) {
+        return KeyAction::ToggleTodos;
+    }
+    if keymap.is(Binding::Search, &key) {
+        return KeyAction::SearchHistory;
+    }
+    if keymap.is(Binding::SelectResult, &key) {
+        return KeyAction::SelectPreviousResult;
+    }
+    handle_typing(key, input)
+}
+
+/// Handle a key while a tool result is highlighted
+///
+/// Returns None for keys that don't act on the selection; they clear it and
+/// go to the input as usual.
+pub fn handle_key_result(key: KeyEvent, keymap: &Keymap) -> Option<KeyAction> {
+    if keymap.is(Binding::OpenFile, &key) {
+        return Some(KeyAction::OpenResult);
+    }
+    if keymap.is(Binding::SelectResult, &key) {
+        return Some(KeyAction::SelectPreviousResult);
+    }
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') if key.modifiers.is_empty() => Some(KeyAction::SelectPreviousResult),
+        KeyCode::Down | KeyCode::Char('j') if key.modifiers.is_empty() => Some(KeyAction::SelectNextResult),
+        KeyCode::Esc => Some(KeyAction::ClearResultSelection),
+        _ => None,
+    }
+}
+
+/// Handle a key at the input in vim mode
+///
+/// The remappable shortcuts apply outside insert mode only; in insert mode
+/// keys type as they do without vim mode.
+pub fn handle_key_vim(key: KeyEvent, input: &mut tui_input::Input, vim: &mut VimState, keymap: &Keymap) -> KeyAction {
+    let mut buffer = Buffer::from_input(input);
+    match vim.handle(&key, &mut buffer) {
+        VimOutcome::Handled => {
+            *input = buffer.to_input();
+            KeyAction::None
+        }
+        VimOutcome::Submit => {
+            let action = handle_typing(key, input);
+            if matches!(action, KeyAction::Submit(_)) {
+                vim.reset();
+            }
+            action
+        }
+        VimOutcome::HistoryPrev => KeyAction::HistoryPrev,
+        VimOutcome::HistoryNext => KeyAction::HistoryNext,
+        VimOutcome::Unhandled => {
+            if vim.mode() != VimMode::Insert {
+                if keymap.is(Binding::ToggleTodos, &key) {
+                    return KeyAction::ToggleTodos;
+                }
+                if keymap.is(Binding::Search, &key) {
+                    return KeyAction::SearchHistory;
+                }
+                if keymap.is(Binding::SelectResult, &key) {
+                    return KeyAction::SelectPreviousResult;
+                }
+            }
+            match key.code {
+                // Control keys quit or do nothing rather than type their letter
+                KeyCode::Char('c' | 'd') if key.modifiers.contains(KeyModifiers::CONTROL) => KeyAction::Quit,
+                KeyCode::Char(_) if key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+                    KeyAction::None
+                }
+                _ => handle_typing(key, input),
+            }
+        }
+    }
+}
+
+/// Edit the input with a key, or submit it
+fn handle_typing(key: KeyEvent, input: &mut tui_input::Input) -> KeyAction {
     match key.code {
         KeyCode::Enter => {
             let value = input.value().to_string();
@@ -154,7 +252,16 @@ pub fn handle_key_normal(key: KeyEvent, input: &mut tui_input::Input) -> KeyActi
 }
 
 /// Handle a key event in tool approval mode
-pub fn handle_key_approval(key: KeyEvent, approval: &mut super::PendingApproval) -> KeyAction {
+pub fn handle_key_approval(key: KeyEvent, approval: &mut super::PendingApproval, keymap: &Keymap) -> KeyAction {
+    if keymap.is(Binding::Approve, &key) {
+        return KeyAction::ApproveTool;
+    }
+    if keymap.is(Binding::Reject, &key) {
+        return KeyAction::RejectTool;
+    }
+    if keymap.is(Binding::Explain, &key) {
+        return KeyAction::ExplainTool;
+    }
     match key.code {
         KeyCode::Up | KeyCode::Char('k') => {
             approval.select_prev();
@@ -173,8 +280,6 @@ pub fn handle_key_approval(key: KeyEvent, approval: &mut super::PendingApproval)
                 _ => KeyAction::RejectTool,
             }
         }
-        KeyCode::Char('y') | KeyCode::Char('Y') => KeyAction::ApproveTool,
-        KeyCode::Char('n') | KeyCode::Char('N') => KeyAction::RejectTool,
         KeyCode::Esc => KeyAction::RejectTool,
         KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => KeyAction::Quit,
         _ => KeyAction::None,
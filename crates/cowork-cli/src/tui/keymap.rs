@@ -0,0 +1,354 @@
+//! Remappable TUI keys from the `[cli.keybindings]` config section
+//!
+//! Keys are written as a key name or character with optional modifiers:
+//! `y`, `esc`, `ctrl+t`, `alt+s`, `f2`. Bindings are checked when loaded;
+//! an invalid or conflicting one is reported and its default kept.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// A key with its modifiers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyChord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    const fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self { code, modifiers }
+    }
+
+    /// Whether a key press is this chord
+    ///
+    /// Letters match in either case, so `y` also matches Shift+Y.
+    pub fn matches(&self, key: &KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Char(c) => {
+                KeyCode::Char(c.to_ascii_lowercase()) == self.code
+                    && key.modifiers - KeyModifiers::SHIFT == self.modifiers
+            }
+            code => code == self.code && key.modifiers == self.modifiers,
+        }
+    }
+
+    /// A key that moves through the approval dialog: j, k or Space
+    fn is_dialog_navigation(&self) -> bool {
+        self.modifiers.is_empty() && matches!(self.code, KeyCode::Char('j' | 'k' | ' '))
+    }
+
+    /// A key the text input needs: a plain character, Enter or Backspace
+    fn is_typing(&self) -> bool {
+        let plain = self.modifiers.is_empty() || self.modifiers == KeyModifiers::SHIFT;
+        plain && matches!(self.code, KeyCode::Char(_) | KeyCode::Enter | KeyCode::Backspace)
+    }
+}
+
+impl FromStr for KeyChord {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.trim().split('+').collect();
+        let (key, mods) = match parts.split_last() {
+            // "ctrl++" binds the plus key
+            Some((&"", rest)) if rest.last() == Some(&"") => ("+", &rest[..rest.len() - 1]),
+            Some((key, mods)) => (*key, mods),
+            None => return Err("empty key".to_string()),
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        for modifier in mods {
+            modifiers |= match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "option" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                other => return Err(format!("unknown modifier {:?}", other)),
+            };
+        }
+
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c.to_ascii_lowercase()),
+            _ => match key.to_ascii_lowercase().as_str() {
+                "esc" | "escape" => KeyCode::Esc,
+                "enter" | "return" => KeyCode::Enter,
+                "tab" => KeyCode::Tab,
+                "space" => KeyCode::Char(' '),
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                name => match name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                    Some(n @ 1..=12) => KeyCode::F(n),
+                    _ => return Err(format!("unknown key {:?}", key)),
+                },
+            },
+        };
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "Ctrl+"),
+            (KeyModifiers::ALT, "Alt+"),
+            (KeyModifiers::SHIFT, "Shift+"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                f.write_str(name)?;
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("Space"),
+            KeyCode::Char(c) if self.modifiers.is_empty() => write!(f, "{}", c),
+            KeyCode::Char(c) => write!(f, "{}", c.to_ascii_uppercase()),
+            KeyCode::Esc => f.write_str("Esc"),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+/// Actions that can be bound to a key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Binding {
+    /// Approve the tool in the approval dialog
+    Approve,
+    /// Reject the tool in the approval dialog
+    Reject,
+    /// Ask why the tool in the approval dialog is wanted
+    Explain,
+    /// Cancel the running turn
+    Cancel,
+    /// Collapse or expand the todo panel
+    ToggleTodos,
+    /// Search input history for the text typed so far
+    Search,
+    /// Highlight the latest tool result that has a file
+    SelectResult,
+    /// Open the highlighted tool result's file in the editor
+    OpenFile,
+}
+
+impl Binding {
+    const ALL: [Binding; 8] = [
+        Binding::Approve,
+        Binding::Reject,
+        Binding::Explain,
+        Binding::Cancel,
+        Binding::ToggleTodos,
+        Binding::Search,
+        Binding::SelectResult,
+        Binding::OpenFile,
+    ];
+
+    /// The action's name in `[cli.keybindings]`
+    pub fn name(self) -> &'static str {
+        match self {
+            Binding::Approve => "approve",
+            Binding::Reject => "reject",
+            Binding::Explain => "explain",
+            Binding::Cancel => "cancel",
+            Binding::ToggleTodos => "toggle_todos",
+            Binding::Search => "search",
+            Binding::SelectResult => "select_result",
+            Binding::OpenFile => "open_file",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim().replace('-', "_");
+        Self::ALL.into_iter().find(|b| b.name() == name)
+    }
+
+    /// Whether the action is used in the approval dialog or on a highlighted
+    /// tool result rather than at the input
+    fn in_dialog(self) -> bool {
+        matches!(self, Binding::Approve | Binding::Reject | Binding::Explain | Binding::OpenFile)
+    }
+
+    fn default_key(self) -> KeyChord {
+        match self {
+            Binding::Approve => KeyChord::new(KeyCode::Char('y'), KeyModifiers::NONE),
+            Binding::Reject => KeyChord::new(KeyCode::Char('n'), KeyModifiers::NONE),
+            Binding::Explain => KeyChord::new(KeyCode::Char('e'), KeyModifiers::NONE),
+            Binding::Cancel => KeyChord::new(KeyCode::Esc, KeyModifiers::NONE),
+            Binding::ToggleTodos => KeyChord::new(KeyCode::Char('t'), KeyModifiers::CONTROL),
+            Binding::Search => KeyChord::new(KeyCode::Char('r'), KeyModifiers::CONTROL),
+            Binding::SelectResult => KeyChord::new(KeyCode::Char('o'), KeyModifiers::CONTROL),
+            Binding::OpenFile => KeyChord::new(KeyCode::Char('o'), KeyModifiers::NONE),
+        }
+    }
+}
+
+/// Keys with a fixed meaning: quit anywhere, and navigating the dialog
+const RESERVED: [(KeyCode, KeyModifiers, &str); 7] = [
+    (KeyCode::Char('c'), KeyModifiers::CONTROL, "quit"),
+    (KeyCode::Char('d'), KeyModifiers::CONTROL, "quit"),
+    (KeyCode::Char('x'), KeyModifiers::CONTROL, "cancel one tool"),
+    (KeyCode::Tab, KeyModifiers::NONE, "next tool"),
+    (KeyCode::Up, KeyModifiers::NONE, "history and dialog navigation"),
+    (KeyCode::Down, KeyModifiers::NONE, "history and dialog navigation"),
+    (KeyCode::Enter, KeyModifiers::NONE, "submit"),
+];
+
+/// The key for each remappable action
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    keys: [KeyChord; 8],
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self {
+            keys: Binding::ALL.map(Binding::default_key),
+        }
+    }
+}
+
+impl Keymap {
+    /// Apply `[cli.keybindings]` to the defaults
+    ///
+    /// Returns a warning for each binding that was ignored: an unknown
+    /// action or key, a key the text input or dialog needs, or a key already
+    /// bound to another action.
+    pub fn load(config: &BTreeMap<String, String>) -> (Self, Vec<String>) {
+        let mut keymap = Self::default();
+        let mut warnings = Vec::new();
+        let mut configured = Vec::new();
+
+        for (action, key) in config {
+            let Some(binding) = Binding::from_name(action) else {
+                let names: Vec<&str> = Binding::ALL.iter().map(|b| b.name()).collect();
+                warnings.push(format!("Unknown key binding action {:?} (expected one of {})", action, names.join(", ")));
+                continue;
+            };
+            let chord = match key.parse::<KeyChord>() {
+                Ok(chord) => chord,
+                Err(e) => {
+                    warnings.push(format!("Key binding {} = {:?}: {}", binding.name(), key, e));
+                    continue;
+                }
+            };
+            if let Some((_, _, used_for)) = RESERVED.iter().find(|(code, mods, _)| chord == KeyChord::new(*code, *mods)) {
+                warnings.push(format!("Key binding {} = {:?}: {} is reserved for {}", binding.name(), key, chord, used_for));
+                continue;
+            }
+            if binding.in_dialog() && chord.is_dialog_navigation() {
+                warnings.push(format!(
+                    "Key binding {} = {:?}: {} moves through the approval dialog",
+                    binding.name(),
+                    key,
+                    chord
+                ));
+                continue;
+            }
+            if !binding.in_dialog() && chord.is_typing() {
+                warnings.push(format!(
+                    "Key binding {} = {:?}: {} is needed for typing; use a key with ctrl or alt",
+                    binding.name(),
+                    key,
+                    chord
+                ));
+                continue;
+            }
+            configured.push(binding);
+            keymap.keys[binding as usize] = chord;
+        }
+
+        // When two actions share a key, the later binding keeps its default
+        for binding in configured.into_iter().rev() {
+            let chord = keymap.key(binding);
+            if let Some(other) = Binding::ALL.into_iter().find(|b| *b != binding && keymap.key(*b) == chord) {
+                warnings.push(format!(
+                    "Key binding {} = {} conflicts with {}; keeping {}",
+                    binding.name(),
+                    chord,
+                    other.name(),
+                    binding.default_key()
+                ));
+                keymap.keys[binding as usize] = binding.default_key();
+            }
+        }
+        (keymap, warnings)
+    }
+
+    /// The key bound to an action
+    pub fn key(&self, binding: Binding) -> KeyChord {
+        self.keys[binding as usize]
+    }
+
+    /// Whether a key press triggers an action
+    pub fn is(&self, binding: Binding, key: &KeyEvent) -> bool {
+        self.key(binding).matches(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(entries: &[(&str, &str)]) -> BTreeMap<String, String> {
+        entries.iter().map(|(a, k)| (a.to_string(), k.to_string())).collect()
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        let chord: KeyChord = "Ctrl+G".parse().unwrap();
+        assert!(chord.matches(&KeyEvent::new(KeyCode::Char('g'), KeyModifiers::CONTROL)));
+        assert_eq!(chord.to_string(), "Ctrl+G");
+        assert_eq!("esc".parse::<KeyChord>().unwrap().to_string(), "Esc");
+        assert_eq!("alt+f2".parse::<KeyChord>().unwrap().to_string(), "Alt+F2");
+        assert!("y".parse::<KeyChord>().unwrap().matches(&KeyEvent::new(KeyCode::Char('Y'), KeyModifiers::SHIFT)));
+        assert!("hyper+x".parse::<KeyChord>().is_err());
+        assert!("f13".parse::<KeyChord>().is_err());
+    }
+
+    #[test]
+    fn test_load_remaps_and_reports() {
+        let (keymap, warnings) = Keymap::load(&config(&[
+            ("approve", "a"),
+            ("cancel", "ctrl+g"),
+            ("toggle-todos", "q"),
+            ("search", "ctrl+c"),
+            ("reject", "a"),
+            ("zoom", "ctrl+z"),
+        ]));
+
+        assert_eq!(keymap.key(Binding::Approve).to_string(), "a");
+        assert_eq!(keymap.key(Binding::Cancel).to_string(), "Ctrl+G");
+        // Rejected bindings keep their defaults
+        assert_eq!(keymap.key(Binding::ToggleTodos).to_string(), "Ctrl+T");
+        assert_eq!(keymap.key(Binding::Search).to_string(), "Ctrl+R");
+        assert_eq!(keymap.key(Binding::Reject).to_string(), "n");
+
+        assert_eq!(warnings.len(), 4, "{:?}", warnings);
+        assert!(warnings.iter().any(|w| w.contains("needed for typing")));
+        assert!(warnings.iter().any(|w| w.contains("reserved for quit")));
+        assert!(warnings.iter().any(|w| w.contains("conflicts with")));
+        assert!(warnings.iter().any(|w| w.contains("\"zoom\"")));
+    }
+
+    #[test]
+    fn test_open_file_takes_a_plain_key() {
+        let (keymap, warnings) = Keymap::load(&config(&[("open_file", "enter"), ("select_result", "g")]));
+        assert_eq!(warnings.len(), 2, "{:?}", warnings);
+        assert_eq!(keymap.key(Binding::OpenFile).to_string(), "o");
+        assert_eq!(keymap.key(Binding::SelectResult).to_string(), "Ctrl+O");
+
+        let (keymap, warnings) = Keymap::load(&config(&[("open_file", "e"), ("select_result", "alt+o")]));
+        // e is explain's key too
+        assert!(warnings.iter().any(|w| w.contains("conflicts with")), "{:?}", warnings);
+        assert_eq!(keymap.key(Binding::SelectResult).to_string(), "Alt+O");
+    }
+}
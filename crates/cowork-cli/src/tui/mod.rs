@@ -6,8 +6,11 @@
 
 mod app;
 pub mod events;
+pub mod keymap;
 mod ui;
+pub mod vim;
 
 pub use app::{App, Message, MessageType, Modal, PendingApproval, PendingQuestion};
-pub use events::{Event, EventHandler, KeyAction, handle_key_approval, handle_key_normal, handle_key_question};
+pub use events::{Event, EventHandler, KeyAction, handle_key_approval, handle_key_question, handle_key_result};
+pub use keymap::{Binding, Keymap};
 pub use ui::draw;
@@ -9,37 +9,49 @@ use ratatui::{
     Frame,
 };
 
-use cowork_core::formatting::format_approval_args;
+use cowork_core::formatting::{format_approval_args, truncate_str, FileDiff, PathDisplay};
+use cowork_core::i18n::{Localizer, Text};
+use cowork_core::tools::task::{TodoPriority, TodoStatus};
+use cowork_core::tools::Artifact;
 use cowork_core::DiffLine;
 
-use super::{App, Message, MessageType, Modal, PendingApproval, PendingQuestion};
+use unicode_width::UnicodeWidthStr;
+
+use super::vim::Buffer;
+use super::{App, Binding, Keymap, Message, MessageType, Modal, PendingApproval, PendingQuestion};
 
 /// Draw the entire UI
 pub fn draw(frame: &mut Frame, app: &mut App) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Min(5),    // Messages area
-            Constraint::Length(1), // Status bar
-            Constraint::Length(3), // Input area
+            Constraint::Min(5),                       // Messages area
+            Constraint::Length(todo_panel_height(app)), // Todo panel (0 = hidden)
+            Constraint::Length(1),                    // Status bar
+            Constraint::Length(input_height(app)),    // Input area
         ])
         .split(frame.area());
 
     draw_messages(frame, app, chunks[0]);
-    draw_status_bar(frame, app, chunks[1]);
-    draw_input(frame, app, chunks[2]);
+    draw_todos(frame, app, chunks[1]);
+    draw_status_bar(frame, app, chunks[2]);
+    draw_input(frame, app, chunks[3]);
 
     // Draw modal overlay if present
     if let Some(ref modal) = app.modal {
-        draw_modal(frame, modal);
+        draw_modal(frame, modal, &app.paths, &app.localizer, &app.keymap);
     }
 }
 
 /// Draw the messages area with persistent messages + ephemeral line at bottom
 fn draw_messages(frame: &mut Frame, app: &mut App, area: Rect) {
+    let title = match &app.title {
+        Some(title) => format!(" {} ", truncate_str(title, area.width.saturating_sub(4) as usize)),
+        None => " Messages ".to_string(),
+    };
     let block = Block::default()
         .borders(Borders::ALL)
-        .title(" Messages ");
+        .title(title);
 
     let inner_area = block.inner(area);
     frame.render_widget(block, area);
@@ -50,12 +62,19 @@ fn draw_messages(frame: &mut Frame, app: &mut App, area: Rect) {
 
     let max_width = inner_area.width as usize - 2;
 
-    // Convert persistent messages to list items
-    let mut items: Vec<ListItem> = app
-        .messages
-        .iter()
-        .flat_map(|msg| message_to_lines(msg, max_width))
-        .collect();
+    // Convert persistent messages to list items, the highlighted tool result's first line reversed
+    let mut items: Vec<ListItem> = Vec::new();
+    let mut selected_line = None;
+    for (index, msg) in app.messages.iter().enumerate() {
+        let mut lines = message_to_lines(msg, max_width);
+        if app.selected_result == Some(index)
+            && let Some(first) = lines.first_mut()
+        {
+            selected_line = Some(items.len());
+            *first = first.clone().style(Style::default().add_modifier(Modifier::REVERSED));
+        }
+        items.extend(lines);
+    }
 
     // Append ephemeral activity lines (dim) if present - up to 3 lines
     if let Some(ref ephemeral) = app.ephemeral {
@@ -73,11 +92,18 @@ fn draw_messages(frame: &mut Frame, app: &mut App, area: Rect) {
     let visible_lines = inner_area.height as usize;
 
     // Handle auto-scroll
-    let scroll = if app.scroll_offset == usize::MAX {
+    let mut scroll = if app.scroll_offset == usize::MAX {
         total_lines.saturating_sub(visible_lines)
     } else {
         app.scroll_offset.min(total_lines.saturating_sub(visible_lines))
     };
+    // Keep the highlighted tool result in view
+    if let Some(line) = selected_line
+        && (line < scroll || line >= scroll + visible_lines)
+    {
+        scroll = line.saturating_sub(visible_lines / 2).min(total_lines.saturating_sub(visible_lines));
+        app.scroll_offset = scroll;
+    }
 
     if app.scroll_offset == usize::MAX && total_lines > visible_lines {
         app.scroll_offset = total_lines - visible_lines;
@@ -104,9 +130,11 @@ fn message_to_lines(msg: &Message, max_width: usize) -> Vec<ListItem<'static>> {
             // Tool calls: ● ToolName(args...) [Xs] in cyan
             tool_call_to_lines(formatted, *elapsed_secs, max_width)
         }
-        MessageType::ToolResult { summary, success, elapsed_secs, diff, expanded, .. } => {
-            // Tool results: ⎿ summary [Xs], with optional diff (red for errors)
-            tool_result_to_lines(summary, *success, *elapsed_secs, diff.as_ref(), *expanded, max_width)
+        MessageType::ToolResult { summary, success, elapsed_secs, diff, artifacts, expanded, .. } => {
+            // Tool results: ⎿ summary [Xs], with optional diff (red for errors) and artifacts
+            let mut items = tool_result_to_lines(summary, *success, *elapsed_secs, diff.as_ref(), *expanded, max_width);
+            items.extend(artifacts_to_lines(artifacts, max_width));
+            items
         }
         _ => {
             let (prefix, style) = match &msg.message_type {
@@ -343,6 +371,33 @@ fn tool_result_to_lines(
     items
 }
 
+/// Render tool result artifacts as plain text (tables as aligned columns)
+fn artifacts_to_lines(artifacts: &[Artifact], max_width: usize) -> Vec<ListItem<'static>> {
+    const MAX_LINES: usize = 10;
+    let continuation = "     ";
+    let style = Style::default().fg(Color::DarkGray);
+    let width = max_width.saturating_sub(continuation.len());
+
+    let lines: Vec<String> = artifacts.iter().flat_map(|a| a.render_text()).collect();
+    let mut items: Vec<ListItem> = lines
+        .iter()
+        .take(MAX_LINES)
+        .map(|line| {
+            ListItem::new(Line::from(vec![
+                Span::styled(continuation.to_string(), style),
+                Span::styled(truncate_str(line, width), style),
+            ]))
+        })
+        .collect();
+    if lines.len() > MAX_LINES {
+        items.push(ListItem::new(Line::from(Span::styled(
+            format!("{}... {} more lines", continuation, lines.len() - MAX_LINES),
+            style,
+        ))));
+    }
+    items
+}
+
 /// Parsed header info
 struct HeaderInfo {
     text: String,
@@ -558,6 +613,89 @@ fn wrap_text(text: &str, max_width: usize) -> Vec<String> {
     lines
 }
 
+/// Most todo rows shown before the panel scrolls to the active item
+const MAX_TODO_ROWS: usize = 8;
+
+/// Rows taken by the todo panel: nothing without todos, the title line when
+/// collapsed, otherwise the items plus borders
+fn todo_panel_height(app: &App) -> u16 {
+    if app.todos.is_empty() {
+        0
+    } else if app.todos_collapsed {
+        1
+    } else {
+        app.todos.len().min(MAX_TODO_ROWS) as u16 + 2
+    }
+}
+
+/// Draw the todo checklist kept by the TodoWrite tool
+fn draw_todos(frame: &mut Frame, app: &App, area: Rect) {
+    if app.todos.is_empty() {
+        return;
+    }
+    let completed = app.todos.iter().filter(|t| t.status == TodoStatus::Completed).count();
+    let title = format!(
+        " Tasks {}/{} ({}) ",
+        completed,
+        app.todos.len(),
+        app.keymap.key(Binding::ToggleTodos)
+    );
+
+    if app.todos_collapsed {
+        let current = app
+            .todos
+            .iter()
+            .find(|t| t.status == TodoStatus::InProgress)
+            .map(|t| format!(" {}", t.active_form))
+            .unwrap_or_default();
+        let line = Line::from(vec![
+            Span::styled(title, Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+            Span::styled(current, Style::default().fg(Color::Yellow)),
+        ]);
+        frame.render_widget(Paragraph::new(line), area);
+        return;
+    }
+
+    // Keep the item being worked on visible in long lists
+    let active = app.todos.iter().position(|t| t.status != TodoStatus::Completed).unwrap_or(0);
+    let skip = active.saturating_sub(MAX_TODO_ROWS / 2).min(app.todos.len().saturating_sub(MAX_TODO_ROWS));
+    let max_width = area.width.saturating_sub(6) as usize;
+
+    let items: Vec<ListItem> = app
+        .todos
+        .iter()
+        .skip(skip)
+        .take(MAX_TODO_ROWS)
+        .map(|todo| {
+            let (marker, style, text) = match todo.status {
+                TodoStatus::Completed => (
+                    "[x] ",
+                    Style::default().fg(Color::DarkGray).add_modifier(Modifier::CROSSED_OUT),
+                    &todo.content,
+                ),
+                TodoStatus::InProgress => (
+                    "[~] ",
+                    Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+                    &todo.active_form,
+                ),
+                TodoStatus::Pending => ("[ ] ", Style::default(), &todo.content),
+            };
+            let priority = if todo.priority == TodoPriority::High { "! " } else { "" };
+            ListItem::new(Line::from(vec![
+                Span::styled(marker, style),
+                Span::styled(priority, Style::default().fg(Color::Red)),
+                Span::styled(truncate_str(text, max_width), style),
+            ]))
+        })
+        .collect();
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::DarkGray))
+        .title(Span::styled(title, Style::default().fg(Color::Cyan)));
+    frame.render_widget(List::new(items).block(block), area);
+}
+
 /// Draw the status bar
 fn draw_status_bar(frame: &mut Frame, app: &App, area: Rect) {
     let time = Local::now().format("%H:%M").to_string();
@@ -565,17 +703,45 @@ fn draw_status_bar(frame: &mut Frame, app: &App, area: Rect) {
     // Context usage is already appended to assistant messages by core
     let right_info = format!("cowork {} | {} | {}", app.version, app.provider_info, time);
 
-    // Build left side: plan mode indicator + status
+    // Build left side: plan mode and worktree indicators + status
     let plan_indicator = if app.plan_mode { "[PLAN] " } else { "" };
-    let (left_text, bg_color) = if !app.status.is_empty() {
+    let worktree_indicator = app
+        .worktree
+        .as_ref()
+        .map(|branch| format!("[WORKTREE {}] ", branch))
+        .unwrap_or_default();
+    // With tools running, show which one Ctrl+X would cancel
+    let tool_hint = match app.highlighted_tool() {
+        Some((_, name)) if app.running_tools.len() > 1 => format!(
+            " [{} {}/{}, Tab: next, Ctrl+X: cancel]",
+            name,
+            app.highlighted_tool + 1,
+            app.running_tools.len()
+        ),
+        Some((_, name)) => format!(" [{}, Ctrl+X: cancel]", name),
+        None => String::new(),
+    };
+    // With a tool result highlighted, show its file and the key that opens it
+    let result_hint = app.selected_file().map(|(path, line)| {
+        let at = line.map(|line| format!(":{}", line)).unwrap_or_default();
+        format!(
+            "{}{}, {}: open in editor, Up/Down: other results, Esc: back",
+            app.paths.display(path),
+            at,
+            app.keymap.key(Binding::OpenFile)
+        )
+    });
+    let (left_text, bg_color) = if let Some(hint) = result_hint {
+        (format!("{}{}{}", plan_indicator, worktree_indicator, hint), Color::DarkGray)
+    } else if !app.status.is_empty() {
         (
-            format!("{}{} {}", plan_indicator, app.spinner(), app.status),
+            format!("{}{}{} {}{}", plan_indicator, worktree_indicator, app.spinner(), app.status, tool_hint),
             if app.plan_mode { Color::Magenta } else { Color::Blue },
         )
     } else if app.plan_mode {
-        (plan_indicator.trim().to_string(), Color::Magenta)
+        (format!("{}{}", plan_indicator, worktree_indicator).trim().to_string(), Color::Magenta)
     } else {
-        (String::new(), Color::DarkGray)
+        (worktree_indicator.trim().to_string(), Color::DarkGray)
     };
 
     let style = Style::default().bg(bg_color).fg(Color::White);
@@ -592,140 +758,197 @@ fn draw_status_bar(frame: &mut Frame, app: &App, area: Rect) {
     frame.render_widget(paragraph, area);
 }
 
+/// Most lines of a multi-line input shown at once
+const MAX_INPUT_ROWS: usize = 6;
+
+/// Height of the input area: it grows with the lines of a multi-line input
+fn input_height(app: &App) -> u16 {
+    app.input.value().split('\n').count().min(MAX_INPUT_ROWS) as u16 + 2
+}
+
 /// Draw the input area
 fn draw_input(frame: &mut Frame, app: &App, area: Rect) {
     let prompt = "You> ";
     let input_active = app.modal.is_none();
+    // A partly typed slash command or `@` mention shows its completions in the title
+    let title = match app.slash_hint().or_else(|| app.mention_hint()).filter(|_| input_active) {
+        Some(hint) => format!(" {} ", hint),
+        None => " Input ".to_string(),
+    };
 
-    let block = Block::default()
+    let mut block = Block::default()
         .borders(Borders::ALL)
-        .title(" Input ")
+        .title(title)
         .border_style(if input_active {
             Style::default().fg(Color::Cyan)
         } else {
             Style::default().fg(Color::DarkGray)
         });
+    // Vim mode shows its mode and the command typed so far
+    if let Some(vim) = app.vim.as_ref().filter(|_| input_active) {
+        block = block.title(Line::from(format!(" {} ", vim.indicator())).right_aligned());
+    }
+
+    if !input_active {
+        let paragraph = Paragraph::new(format!("{}(waiting...)", prompt))
+            .style(Style::default().fg(Color::DarkGray))
+            .block(block);
+        frame.render_widget(paragraph, area);
+        return;
+    }
 
     // Calculate available width for text (inside borders)
     let inner_width = area.width.saturating_sub(2) as usize;
     let prompt_len = prompt.len();
     let text_width = inner_width.saturating_sub(prompt_len);
 
-    let (display_text, cursor_offset, input_style) = if input_active {
-        let input_value = app.input.value();
-        let cursor_pos = app.input.visual_cursor();
-
-        // Calculate scroll offset to keep cursor visible
-        // Leave some margin so cursor isn't at the very edge
-        let margin = 5.min(text_width / 4);
-        let scroll_offset = if cursor_pos >= text_width.saturating_sub(margin) {
-            cursor_pos.saturating_sub(text_width.saturating_sub(margin))
-        } else {
-            0
-        };
-
-        // Get the visible portion of the input
-        let chars: Vec<char> = input_value.chars().collect();
-        let visible_start = scroll_offset.min(chars.len());
-        let visible_end = (scroll_offset + text_width).min(chars.len());
-        let visible_text: String = chars[visible_start..visible_end].iter().collect();
+    let buffer = Buffer::from_input(&app.input);
+    let cursor_row = buffer.line_of(buffer.cursor);
+    let cursor_col = buffer.chars[..buffer.cursor].iter().rev().take_while(|c| **c != '\n').count();
+    let selected = app.vim.as_ref().and_then(|vim| vim.visual_lines(&buffer));
 
-        // Add scroll indicator if text is scrolled
-        let prefix = if scroll_offset > 0 { "…" } else { "" };
-        let suffix = if visible_end < chars.len() { "…" } else { "" };
-
-        let display = format!("{}{}{}{}", prompt, prefix, visible_text, suffix);
-        let cursor_in_view = cursor_pos.saturating_sub(scroll_offset);
-
-        (display, cursor_in_view + prompt_len + prefix.len(), Style::default())
+    // Calculate scroll offset to keep cursor visible
+    // Leave some margin so cursor isn't at the very edge
+    let margin = 5.min(text_width / 4);
+    let scroll_offset = if cursor_col >= text_width.saturating_sub(margin) {
+        cursor_col.saturating_sub(text_width.saturating_sub(margin))
     } else {
-        (format!("{}(waiting...)", prompt), 0, Style::default().fg(Color::DarkGray))
+        0
     };
+    let first_row = cursor_row.saturating_sub(MAX_INPUT_ROWS - 1);
 
-    let paragraph = Paragraph::new(display_text)
-        .style(input_style)
-        .block(block);
+    let mut cursor_x = 0;
+    let lines: Vec<Line> = app
+        .input
+        .value()
+        .split('\n')
+        .enumerate()
+        .skip(first_row)
+        .take(MAX_INPUT_ROWS)
+        .map(|(row, line)| {
+            // Get the visible portion of the line
+            let chars: Vec<char> = line.chars().collect();
+            let visible_start = scroll_offset.min(chars.len());
+            let visible_end = (scroll_offset + text_width).min(chars.len());
+            let visible_text: String = chars[visible_start..visible_end].iter().collect();
+
+            // Add scroll indicator if text is scrolled
+            let prefix = if scroll_offset > 0 { "…" } else { "" };
+            let suffix = if visible_end < chars.len() { "…" } else { "" };
+            if row == cursor_row {
+                let before: String = chars[visible_start..cursor_col.clamp(visible_start, chars.len())].iter().collect();
+                cursor_x = prompt_len + prefix.chars().count() + before.width();
+            }
 
-    frame.render_widget(paragraph, area);
+            let lead = if row == 0 { prompt.to_string() } else { " ".repeat(prompt_len) };
+            let style = match selected {
+                Some((first, last)) if (first..=last).contains(&row) => Style::default().add_modifier(Modifier::REVERSED),
+                _ => Style::default(),
+            };
+            Line::from(vec![Span::raw(lead), Span::styled(format!("{}{}{}", prefix, visible_text, suffix), style)])
+        })
+        .collect();
 
-    // Set cursor position when input is active
-    if input_active {
-        let cursor_x = area.x + 1 + cursor_offset as u16;
-        let cursor_y = area.y + 1;
-        frame.set_cursor_position((cursor_x.min(area.x + area.width - 2), cursor_y));
-    }
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+
+    let cursor_x = area.x + 1 + cursor_x as u16;
+    let cursor_y = area.y + 1 + (cursor_row - first_row) as u16;
+    frame.set_cursor_position((cursor_x.min(area.x + area.width - 2), cursor_y));
 }
 
 /// Draw modal overlay (dispatches to approval or question)
-fn draw_modal(frame: &mut Frame, modal: &Modal) {
+fn draw_modal(frame: &mut Frame, modal: &Modal, paths: &PathDisplay, localizer: &Localizer, keymap: &Keymap) {
     match modal {
-        Modal::Approval(approval) => draw_approval_modal(frame, approval),
+        Modal::Approval(approval) => draw_approval_modal(frame, approval, paths, localizer, keymap),
         Modal::Question(question) => draw_question_modal(frame, question),
     }
 }
 
 /// Draw the tool approval modal
-fn draw_approval_modal(frame: &mut Frame, approval: &PendingApproval) {
+fn draw_approval_modal(
+    frame: &mut Frame,
+    approval: &PendingApproval,
+    paths: &PathDisplay,
+    localizer: &Localizer,
+    keymap: &Keymap,
+) {
     let area = centered_rect(70, 60, frame.area());
     frame.render_widget(Clear, area);
 
+    // A call flagged as possible secret exfiltration stands out
+    let border = if approval.security_warning.is_some() { Color::Red } else { Color::Yellow };
     let block = Block::default()
         .borders(Borders::ALL)
-        .title(" Tool Approval Required ")
-        .border_style(Style::default().fg(Color::Yellow));
+        .title(localizer.text(Text::ApprovalTitle))
+        .border_style(Style::default().fg(border));
 
     let inner = block.inner(area);
     frame.render_widget(block, area);
 
-    // Adjust layout based on whether we have a description
-    let has_description = approval.description.is_some();
-    let chunks = if has_description {
-        Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([
-                Constraint::Length(2), // Tool name
-                Constraint::Length(4), // Description
-                Constraint::Min(4),    // Arguments
-                Constraint::Length(6), // Options
-            ])
-            .split(inner)
-    } else {
-        Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([
-                Constraint::Length(2), // Tool name
-                Constraint::Min(8),    // Arguments (more space when no description)
-                Constraint::Length(6), // Options
-            ])
-            .split(inner)
+    // The explanation, or a note while it's on its way
+    let explanation = match (&approval.explanation, approval.explain_requested) {
+        (Some(text), _) => Some(text.as_str()),
+        (None, true) => Some(localizer.text(Text::ApprovalExplaining)),
+        (None, false) => None,
     };
 
-    let tool_text = Paragraph::new(format!("Tool: {}", approval.name))
+    // Description and explanation only take space when there are any
+    let mut constraints = vec![Constraint::Length(2)]; // Tool name
+    if approval.security_warning.is_some() {
+        constraints.push(Constraint::Length(4)); // Security warning
+    }
+    if approval.description.is_some() {
+        constraints.push(Constraint::Length(4)); // Description
+    }
+    if explanation.is_some() {
+        constraints.push(Constraint::Length(6)); // Explanation
+    }
+    constraints.push(Constraint::Min(4)); // Arguments
+    constraints.push(Constraint::Length(6)); // Options and the explain hint
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(constraints)
+        .split(inner);
+    let mut chunks = chunks.iter().copied();
+
+    let tool_text = Paragraph::new(localizer.format(Text::ApprovalTool, &[("tool", &approval.name)]))
         .style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD));
-    frame.render_widget(tool_text, chunks[0]);
+    frame.render_widget(tool_text, chunks.next().unwrap_or_default());
+
+    if let Some(warning) = &approval.security_warning {
+        let warning_text = Paragraph::new(format!("\u{26a0} {}", warning))
+            .style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
+            .wrap(Wrap { trim: false });
+        frame.render_widget(warning_text, chunks.next().unwrap_or_default());
+    }
 
-    // Show description if available, then arguments
-    let (args_chunk, options_chunk) = if has_description {
-        // Render description
-        let desc_text = Paragraph::new(approval.description.as_deref().unwrap_or(""))
+    if let Some(description) = &approval.description {
+        let desc_text = Paragraph::new(description.as_str())
             .style(Style::default().fg(Color::White))
             .wrap(Wrap { trim: false });
-        frame.render_widget(desc_text, chunks[1]);
-        (chunks[2], chunks[3])
-    } else {
-        (chunks[1], chunks[2])
-    };
+        frame.render_widget(desc_text, chunks.next().unwrap_or_default());
+    }
+
+    if let Some(explanation) = explanation {
+        let explanation_text = Paragraph::new(explanation)
+            .style(Style::default().fg(Color::Cyan))
+            .wrap(Wrap { trim: false });
+        frame.render_widget(explanation_text, chunks.next().unwrap_or_default());
+    }
 
-    // Format arguments nicely instead of raw JSON dump
-    let args_lines = format_approval_args(&approval.name, &approval.arguments);
-    let args_text = Paragraph::new(args_lines.join("\n"))
+    // Edit/Write show their diff; other tools get formatted arguments instead of raw JSON
+    let args_text = match &approval.diff {
+        Some(diff) => Paragraph::new(file_diff_to_lines(diff)),
+        None => Paragraph::new(format_approval_args(&approval.name, &approval.arguments, paths).join("\n")),
+    };
+    let args_text = args_text
         .style(Style::default().fg(Color::Gray))
         .wrap(Wrap { trim: false })
-        .block(Block::default().borders(Borders::TOP).title(" Details "));
-    frame.render_widget(args_text, args_chunk);
+        .block(Block::default().borders(Borders::TOP).title(localizer.text(Text::ApprovalDetails)));
+    frame.render_widget(args_text, chunks.next().unwrap_or_default());
 
-    let options: Vec<ListItem> = approval
-        .options()
+    let mut options: Vec<ListItem> = approval
+        .options(localizer)
         .iter()
         .enumerate()
         .map(|(i, opt)| {
@@ -740,10 +963,43 @@ fn draw_approval_modal(frame: &mut Frame, approval: &PendingApproval) {
             ListItem::new(format!("  {}  ", opt)).style(style)
         })
         .collect();
+    if !approval.explain_requested {
+        let hint = localizer.format(Text::ApprovalExplain, &[("key", &keymap.key(Binding::Explain))]);
+        options.push(ListItem::new(format!("  {}  ", hint)).style(Style::default().fg(Color::DarkGray)));
+    }
 
     let list = List::new(options)
-        .block(Block::default().borders(Borders::TOP).title(" Select action (\u{2191}/\u{2193}, Enter) "));
-    frame.render_widget(list, options_chunk);
+        .block(Block::default().borders(Borders::TOP).title(localizer.text(Text::ApprovalSelect)));
+    frame.render_widget(list, chunks.next().unwrap_or_default());
+}
+
+/// Render a file diff for the approval modal: header, then numbered +/- lines
+fn file_diff_to_lines(diff: &FileDiff) -> Vec<Line<'static>> {
+    let number_style = Style::default().fg(Color::DarkGray);
+    let header = if diff.new_file {
+        format!("File: {} (new file, {} lines)", diff.path, diff.added)
+    } else {
+        format!("File: {} (+{} \u{2212}{})", diff.path, diff.added, diff.removed)
+    };
+
+    let mut lines = vec![Line::from(header)];
+    for diff_line in &diff.lines {
+        let (marker, style) = match diff_line.line_type.as_str() {
+            _ if diff.new_file => (" ", Style::default().fg(Color::Gray)),
+            "added" => ("+", Style::default().fg(Color::Green)),
+            "removed" => ("-", Style::default().fg(Color::Red)),
+            _ => (" ", Style::default().fg(Color::DarkGray)),
+        };
+        let line_num = diff_line
+            .line_number
+            .map(|n| format!("{:>4} ", n))
+            .unwrap_or_else(|| "     ".to_string());
+        lines.push(Line::from(vec![
+            Span::styled(line_num, number_style),
+            Span::styled(format!("{} {}", marker, diff_line.content), style),
+        ]));
+    }
+    lines
 }
 
 /// Draw the question modal
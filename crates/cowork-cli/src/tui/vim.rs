@@ -0,0 +1,861 @@
+//! Vim-style modal editing of the input box (`[cli] vim_mode`, `/vim`)
+//!
+//! `VimState` turns key presses into edits of a `Buffer`, the input's text
+//! and cursor, without knowing about the terminal:
+//! - Modes: insert (where editing starts), normal and visual-line
+//! - Motions: h l w b e 0 ^ $ j k gg G, with counts (`3w`, `2j`, `5G`)
+//! - Operators: d c y with a motion, doubled for whole lines (`dd`, `2yy`)
+//! - Commands: i a I A o O x D C p P u, and V for visual-line mode
+//!
+//! j and k move between the lines of a multi-line input; past its first or
+//! last line they browse the input history instead.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use tui_input::Input;
+
+/// Editing mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VimMode {
+    Normal,
+    Insert,
+    VisualLine,
+}
+
+/// What a key press came to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VimOutcome {
+    /// Taken by vim (the buffer may have changed)
+    Handled,
+    /// Not a vim key: typing in insert mode, or a shortcut outside it
+    Unhandled,
+    /// Send the input
+    Submit,
+    /// Moved up past the first line: the previous history entry
+    HistoryPrev,
+    /// Moved down past the last line: the next history entry
+    HistoryNext,
+}
+
+/// The input's text and cursor (a char index), as vim edits them
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Buffer {
+    pub chars: Vec<char>,
+    pub cursor: usize,
+}
+
+impl Buffer {
+    pub fn new(text: &str, cursor: usize) -> Self {
+        let chars: Vec<char> = text.chars().collect();
+        let cursor = cursor.min(chars.len());
+        Self { chars, cursor }
+    }
+
+    pub fn from_input(input: &Input) -> Self {
+        Self::new(input.value(), input.cursor())
+    }
+
+    pub fn to_input(&self) -> Input {
+        Input::new(self.text()).with_cursor(self.cursor)
+    }
+
+    pub fn text(&self) -> String {
+        self.chars.iter().collect()
+    }
+
+    fn len(&self) -> usize {
+        self.chars.len()
+    }
+
+    /// Start of the line `pos` is on
+    fn line_start(&self, pos: usize) -> usize {
+        self.chars[..pos.min(self.len())]
+            .iter()
+            .rposition(|c| *c == '\n')
+            .map_or(0, |i| i + 1)
+    }
+
+    /// End of the line `pos` is on: its newline, or the end of the text
+    fn line_end(&self, pos: usize) -> usize {
+        let pos = pos.min(self.len());
+        self.chars[pos..].iter().position(|c| *c == '\n').map_or(self.len(), |i| pos + i)
+    }
+
+    /// Number of the line `pos` is on, from 0
+    pub fn line_of(&self, pos: usize) -> usize {
+        self.chars[..pos.min(self.len())].iter().filter(|c| **c == '\n').count()
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_of(self.len()) + 1
+    }
+
+    /// Start of line number `line` (the last line if there are fewer)
+    fn start_of_line(&self, line: usize) -> usize {
+        let mut start = 0;
+        for _ in 0..line {
+            let end = self.line_end(start);
+            if end == self.len() {
+                break;
+            }
+            start = end + 1;
+        }
+        start
+    }
+
+    fn first_non_blank(&self, pos: usize) -> usize {
+        let end = self.line_end(pos);
+        let start = self.line_start(pos);
+        (start..end).find(|i| !self.chars[*i].is_whitespace()).unwrap_or(start)
+    }
+
+    /// Keep the cursor on a character of its line, as normal mode does
+    fn clamp_normal(&mut self) {
+        let (start, end) = (self.line_start(self.cursor), self.line_end(self.cursor));
+        if self.cursor >= end && end > start {
+            self.cursor = end - 1;
+        }
+    }
+
+    fn insert(&mut self, pos: usize, text: &[char]) {
+        self.chars.splice(pos..pos, text.iter().copied());
+    }
+}
+
+/// Character classes words are made of
+fn class(c: char) -> u8 {
+    if c.is_whitespace() {
+        0
+    } else if c.is_alphanumeric() || c == '_' {
+        1
+    } else {
+        2
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Delete,
+    Change,
+    Yank,
+}
+
+impl Operator {
+    fn from_key(c: char) -> Option<Self> {
+        match c {
+            'd' => Some(Self::Delete),
+            'c' => Some(Self::Change),
+            'y' => Some(Self::Yank),
+            _ => None,
+        }
+    }
+
+    fn key(self) -> char {
+        match self {
+            Self::Delete => 'd',
+            Self::Change => 'c',
+            Self::Yank => 'y',
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Motion {
+    Left,
+    Right,
+    WordForward,
+    WordBackward,
+    WordEnd,
+    LineStart,
+    FirstNonBlank,
+    LineEnd,
+    Down,
+    Up,
+    FirstLine,
+    LastLine,
+}
+
+impl Motion {
+    fn from_key(key: &KeyEvent) -> Option<Self> {
+        Some(match key.code {
+            KeyCode::Char('h') | KeyCode::Left | KeyCode::Backspace => Self::Left,
+            KeyCode::Char('l') | KeyCode::Right | KeyCode::Char(' ') => Self::Right,
+            KeyCode::Char('w') => Self::WordForward,
+            KeyCode::Char('b') => Self::WordBackward,
+            KeyCode::Char('e') => Self::WordEnd,
+            KeyCode::Char('0') | KeyCode::Home => Self::LineStart,
+            KeyCode::Char('^') => Self::FirstNonBlank,
+            KeyCode::Char('$') | KeyCode::End => Self::LineEnd,
+            KeyCode::Char('j') | KeyCode::Down => Self::Down,
+            KeyCode::Char('k') | KeyCode::Up => Self::Up,
+            KeyCode::Char('G') => Self::LastLine,
+            _ => return None,
+        })
+    }
+
+    /// Whether an operator takes whole lines
+    fn linewise(self) -> bool {
+        matches!(self, Self::Down | Self::Up | Self::FirstLine | Self::LastLine)
+    }
+
+    /// Whether an operator takes the character the motion lands on
+    fn inclusive(self) -> bool {
+        matches!(self, Self::WordEnd | Self::LineEnd)
+    }
+
+    /// Where the motion takes the cursor, or None when j or k would leave
+    /// the buffer
+    fn target(self, buf: &Buffer, count: Option<usize>) -> Option<usize> {
+        let n = count.unwrap_or(1);
+        let pos = buf.cursor;
+        Some(match self {
+            Self::Left => pos.saturating_sub(n).max(buf.line_start(pos)),
+            Self::Right => (pos + n).min(buf.line_end(pos)),
+            Self::WordForward => (0..n).fold(pos, |p, _| word_forward(buf, p)),
+            Self::WordBackward => (0..n).fold(pos, |p, _| word_backward(buf, p)),
+            Self::WordEnd => (0..n).fold(pos, |p, _| word_end(buf, p)),
+            Self::LineStart => buf.line_start(pos),
+            Self::FirstNonBlank => buf.first_non_blank(pos),
+            Self::LineEnd => {
+                let end = buf.line_end(buf.start_of_line(buf.line_of(pos) + n - 1));
+                end.saturating_sub(1).max(buf.line_start(end))
+            }
+            Self::Down | Self::Up => {
+                let line = buf.line_of(pos);
+                let line = if self == Self::Down {
+                    Some(line + n).filter(|l| *l < buf.line_count())?
+                } else {
+                    line.checked_sub(n)?
+                };
+                let column = pos - buf.line_start(pos);
+                let start = buf.start_of_line(line);
+                (start + column).min(buf.line_end(start))
+            }
+            Self::FirstLine => buf.first_non_blank(buf.start_of_line(count.unwrap_or(1) - 1)),
+            Self::LastLine => {
+                let line = count.map_or(buf.line_count() - 1, |n| n - 1);
+                buf.first_non_blank(buf.start_of_line(line))
+            }
+        })
+    }
+}
+
+/// Start of the next word
+fn word_forward(buf: &Buffer, mut pos: usize) -> usize {
+    if pos >= buf.len() {
+        return pos;
+    }
+    let start = class(buf.chars[pos]);
+    if start != 0 {
+        while pos < buf.len() && class(buf.chars[pos]) == start {
+            pos += 1;
+        }
+    }
+    while pos < buf.len() && class(buf.chars[pos]) == 0 {
+        pos += 1;
+    }
+    pos
+}
+
+/// Start of this word, or of the one before when already there
+fn word_backward(buf: &Buffer, mut pos: usize) -> usize {
+    while pos > 0 && class(buf.chars[pos - 1]) == 0 {
+        pos -= 1;
+    }
+    if pos == 0 {
+        return 0;
+    }
+    let word = class(buf.chars[pos - 1]);
+    while pos > 0 && class(buf.chars[pos - 1]) == word {
+        pos -= 1;
+    }
+    pos
+}
+
+/// Last character of this word, or of the next one when already there
+fn word_end(buf: &Buffer, pos: usize) -> usize {
+    let mut pos = pos + 1;
+    while pos < buf.len() && class(buf.chars[pos]) == 0 {
+        pos += 1;
+    }
+    if pos >= buf.len() {
+        return buf.len().saturating_sub(1);
+    }
+    let word = class(buf.chars[pos]);
+    while pos + 1 < buf.len() && class(buf.chars[pos + 1]) == word {
+        pos += 1;
+    }
+    pos
+}
+
+/// Text yanked or deleted, for p and P
+#[derive(Debug, Clone, Default)]
+struct Register {
+    text: Vec<char>,
+    /// Whole lines, pasted as lines of their own
+    linewise: bool,
+}
+
+/// Modal editing state of the input (see the module docs)
+#[derive(Debug, Clone)]
+pub struct VimState {
+    mode: VimMode,
+    /// Count typed so far
+    count: Option<usize>,
+    /// Operator waiting for its motion, with the count typed before it
+    operator: Option<(Operator, Option<usize>)>,
+    /// `g` typed, waiting for the second `g`
+    pending_g: bool,
+    register: Register,
+    /// Text and cursor before each change, for u
+    undo: Vec<(Vec<char>, usize)>,
+    /// Line visual-line mode started on
+    anchor: usize,
+}
+
+impl Default for VimState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VimState {
+    /// Start in insert mode, so typing works as without vim mode
+    pub fn new() -> Self {
+        Self {
+            mode: VimMode::Insert,
+            count: None,
+            operator: None,
+            pending_g: false,
+            register: Register::default(),
+            undo: Vec::new(),
+            anchor: 0,
+        }
+    }
+
+    pub fn mode(&self) -> VimMode {
+        self.mode
+    }
+
+    /// Whether Esc belongs to vim: leaving insert or visual-line mode, or
+    /// dropping a half-typed command; otherwise it cancels the turn as usual
+    pub fn wants_escape(&self) -> bool {
+        self.mode != VimMode::Normal || self.count.is_some() || self.operator.is_some() || self.pending_g
+    }
+
+    /// Lines selected in visual-line mode, first and last
+    pub fn visual_lines(&self, buf: &Buffer) -> Option<(usize, usize)> {
+        (self.mode == VimMode::VisualLine).then(|| {
+            let line = buf.line_of(buf.cursor);
+            (self.anchor.min(line), self.anchor.max(line))
+        })
+    }
+
+    /// The mode and the command typed so far, e.g. `-- NORMAL -- 2d`
+    pub fn indicator(&self) -> String {
+        let mode = match self.mode {
+            VimMode::Normal => "-- NORMAL --",
+            VimMode::Insert => "-- INSERT --",
+            VimMode::VisualLine => "-- VISUAL LINE --",
+        };
+        let mut pending = String::new();
+        if let Some((operator, count)) = self.operator {
+            if let Some(count) = count {
+                pending.push_str(&count.to_string());
+            }
+            pending.push(operator.key());
+        }
+        if let Some(count) = self.count {
+            pending.push_str(&count.to_string());
+        }
+        if self.pending_g {
+            pending.push('g');
+        }
+        if pending.is_empty() { mode.to_string() } else { format!("{} {}", mode, pending) }
+    }
+
+    /// Back to insert mode with nothing to undo, for the next message
+    pub fn reset(&mut self) {
+        let register = std::mem::take(&mut self.register);
+        *self = Self { register, ..Self::new() };
+    }
+
+    /// Apply a key press to `buf`
+    pub fn handle(&mut self, key: &KeyEvent, buf: &mut Buffer) -> VimOutcome {
+        if key.code == KeyCode::Enter && key.modifiers.is_empty() {
+            return VimOutcome::Submit;
+        }
+        if key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) {
+            return VimOutcome::Unhandled;
+        }
+        match self.mode {
+            VimMode::Insert => {
+                if key.code != KeyCode::Esc {
+                    return VimOutcome::Unhandled;
+                }
+                self.mode = VimMode::Normal;
+                if buf.cursor > buf.line_start(buf.cursor) {
+                    buf.cursor -= 1;
+                }
+                VimOutcome::Handled
+            }
+            VimMode::Normal | VimMode::VisualLine => {
+                buf.clamp_normal();
+                let outcome = self.command(key, buf);
+                if self.mode != VimMode::Insert {
+                    buf.clamp_normal();
+                }
+                outcome
+            }
+        }
+    }
+
+    fn clear_pending(&mut self) {
+        self.count = None;
+        self.operator = None;
+        self.pending_g = false;
+    }
+
+    /// The counts typed before the operator and the motion, multiplied
+    fn take_count(&mut self) -> Option<usize> {
+        let before = self.operator.and_then(|(_, count)| count);
+        match (before, self.count.take()) {
+            (None, None) => None,
+            (a, b) => Some(a.unwrap_or(1) * b.unwrap_or(1)),
+        }
+    }
+
+    fn snapshot(&mut self, buf: &Buffer) {
+        self.undo.push((buf.chars.clone(), buf.cursor));
+    }
+
+    fn insert_mode(&mut self, buf: &Buffer, snapshot: bool) {
+        if snapshot {
+            self.snapshot(buf);
+        }
+        self.mode = VimMode::Insert;
+    }
+
+    /// A key in normal or visual-line mode
+    fn command(&mut self, key: &KeyEvent, buf: &mut Buffer) -> VimOutcome {
+        let KeyCode::Char(c) = key.code else {
+            return match key.code {
+                KeyCode::Esc => {
+                    self.clear_pending();
+                    self.mode = VimMode::Normal;
+                    VimOutcome::Handled
+                }
+                KeyCode::Delete => self.command(&KeyEvent::from(KeyCode::Char('x')), buf),
+                _ => match Motion::from_key(key) {
+                    Some(motion) => self.motion(motion, buf),
+                    None => VimOutcome::Unhandled,
+                },
+            };
+        };
+
+        if self.pending_g {
+            self.pending_g = false;
+            return if c == 'g' {
+                self.motion(Motion::FirstLine, buf)
+            } else {
+                self.clear_pending();
+                VimOutcome::Handled
+            };
+        }
+        if c.is_ascii_digit() && (c != '0' || self.count.is_some()) {
+            let digit = c.to_digit(10).unwrap_or(0) as usize;
+            self.count = Some(self.count.unwrap_or(0).saturating_mul(10).saturating_add(digit));
+            return VimOutcome::Handled;
+        }
+        if c == 'g' {
+            self.pending_g = true;
+            return VimOutcome::Handled;
+        }
+        if let Some(motion) = Motion::from_key(key) {
+            return self.motion(motion, buf);
+        }
+
+        if self.mode == VimMode::VisualLine {
+            return self.visual_command(c, buf);
+        }
+
+        if let Some(operator) = Operator::from_key(c) {
+            match self.operator {
+                // Doubled: whole lines
+                Some((pending, _)) if pending == operator => {
+                    let count = self.take_count().unwrap_or(1);
+                    self.operator = None;
+                    let first = buf.line_of(buf.cursor);
+                    let last = (first + count - 1).min(buf.line_count() - 1);
+                    self.operate_lines(operator, first, last, buf);
+                }
+                Some(_) => self.clear_pending(),
+                None => self.operator = Some((operator, self.count.take())),
+            }
+            return VimOutcome::Handled;
+        }
+        if self.operator.is_some() {
+            // Not a motion: the operator is dropped
+            self.clear_pending();
+            return VimOutcome::Handled;
+        }
+
+        let count = self.count.take();
+        let n = count.unwrap_or(1);
+        match c {
+            'i' => self.insert_mode(buf, true),
+            'a' => {
+                if buf.cursor < buf.line_end(buf.cursor) {
+                    buf.cursor += 1;
+                }
+                self.insert_mode(buf, true);
+            }
+            'I' => {
+                buf.cursor = buf.first_non_blank(buf.cursor);
+                self.insert_mode(buf, true);
+            }
+            'A' => {
+                buf.cursor = buf.line_end(buf.cursor);
+                self.insert_mode(buf, true);
+            }
+            'o' | 'O' => {
+                self.snapshot(buf);
+                let at = if c == 'o' { buf.line_end(buf.cursor) } else { buf.line_start(buf.cursor) };
+                buf.insert(at, &['\n']);
+                buf.cursor = if c == 'o' { at + 1 } else { at };
+                self.insert_mode(buf, false);
+            }
+            'x' => {
+                let to = (buf.cursor + n).min(buf.line_end(buf.cursor));
+                if to > buf.cursor {
+                    self.operate_chars(Operator::Delete, buf.cursor, to, buf);
+                }
+            }
+            'D' | 'C' => {
+                let operator = if c == 'D' { Operator::Delete } else { Operator::Change };
+                self.operator = Some((operator, None));
+                self.count = count;
+                return self.motion(Motion::LineEnd, buf);
+            }
+            'p' | 'P' => self.paste(c == 'p', n, buf),
+            'u' => {
+                for _ in 0..n {
+                    let Some((chars, cursor)) = self.undo.pop() else {
+                        break;
+                    };
+                    *buf = Buffer { chars, cursor };
+                }
+            }
+            'V' => {
+                self.anchor = buf.line_of(buf.cursor);
+                self.mode = VimMode::VisualLine;
+            }
+            _ => {}
+        }
+        VimOutcome::Handled
+    }
+
+    /// An operator key (or x, V, Esc) in visual-line mode
+    fn visual_command(&mut self, c: char, buf: &mut Buffer) -> VimOutcome {
+        self.clear_pending();
+        let Some((first, last)) = self.visual_lines(buf) else {
+            return VimOutcome::Handled;
+        };
+        let operator = match c {
+            'd' | 'x' => Operator::Delete,
+            'c' | 's' => Operator::Change,
+            'y' => Operator::Yank,
+            'V' => {
+                self.mode = VimMode::Normal;
+                return VimOutcome::Handled;
+            }
+            _ => return VimOutcome::Handled,
+        };
+        self.mode = VimMode::Normal;
+        self.operate_lines(operator, first, last, buf);
+        VimOutcome::Handled
+    }
+
+    /// Move the cursor, or apply the pending operator up to where it goes
+    fn motion(&mut self, motion: Motion, buf: &mut Buffer) -> VimOutcome {
+        let count = self.take_count();
+        let Some((operator, _)) = self.operator.take() else {
+            return match motion.target(buf, count) {
+                Some(target) => {
+                    buf.cursor = target;
+                    VimOutcome::Handled
+                }
+                None if self.mode == VimMode::VisualLine => VimOutcome::Handled,
+                None if motion == Motion::Up => VimOutcome::HistoryPrev,
+                None => VimOutcome::HistoryNext,
+            };
+        };
+
+        // cw on a word changes to its end, as ce does
+        let on_word = buf.cursor < buf.len() && class(buf.chars[buf.cursor]) != 0;
+        if operator == Operator::Change && motion == Motion::WordForward && on_word {
+            let word = class(buf.chars[buf.cursor]);
+            let end = (buf.cursor..buf.len()).take_while(|i| class(buf.chars[*i]) == word).count();
+            let to = buf.cursor + end;
+            self.operate_chars(operator, buf.cursor, to, buf);
+            return VimOutcome::Handled;
+        }
+        let Some(target) = motion.target(buf, count) else {
+            return VimOutcome::Handled;
+        };
+        if motion.linewise() {
+            let (a, b) = (buf.line_of(buf.cursor), buf.line_of(target));
+            self.operate_lines(operator, a.min(b), a.max(b), buf);
+            return VimOutcome::Handled;
+        }
+
+        let (from, mut to) = (buf.cursor.min(target), buf.cursor.max(target));
+        if motion.inclusive() && to < buf.line_end(to) {
+            to += 1;
+        }
+        // The last word of a line is taken up to the end of the line, not the next one
+        if motion == Motion::WordForward && buf.line_end(from) > from {
+            to = to.min(buf.line_end(from));
+        }
+        self.operate_chars(operator, from, to, buf);
+        VimOutcome::Handled
+    }
+
+    fn operate_chars(&mut self, operator: Operator, from: usize, to: usize, buf: &mut Buffer) {
+        self.register = Register {
+            text: buf.chars[from..to].to_vec(),
+            linewise: false,
+        };
+        if operator != Operator::Yank {
+            self.snapshot(buf);
+            buf.chars.drain(from..to);
+        }
+        buf.cursor = from;
+        if operator == Operator::Change {
+            self.insert_mode(buf, false);
+        }
+    }
+
+    fn operate_lines(&mut self, operator: Operator, first: usize, last: usize, buf: &mut Buffer) {
+        let start = buf.start_of_line(first);
+        let end = buf.line_end(buf.start_of_line(last));
+        self.register = Register {
+            text: buf.chars[start..end].to_vec(),
+            linewise: true,
+        };
+        match operator {
+            Operator::Yank => buf.cursor = start,
+            Operator::Delete => {
+                self.snapshot(buf);
+                // Take a newline along with the lines: theirs or the one before
+                let (start, end) = if end < buf.len() {
+                    (start, end + 1)
+                } else {
+                    (start.saturating_sub(1), end)
+                };
+                buf.chars.drain(start..end);
+                buf.cursor = buf.first_non_blank(start.min(buf.len()));
+            }
+            Operator::Change => {
+                self.snapshot(buf);
+                buf.chars.drain(start..end);
+                buf.cursor = start;
+                self.insert_mode(buf, false);
+            }
+        }
+    }
+
+    fn paste(&mut self, after: bool, count: usize, buf: &mut Buffer) {
+        if self.register.text.is_empty() && !self.register.linewise {
+            return;
+        }
+        self.snapshot(buf);
+        let mut text = Vec::new();
+        if self.register.linewise {
+            for _ in 0..count {
+                text.push('\n');
+                text.extend(&self.register.text);
+            }
+            if after {
+                let at = buf.line_end(buf.cursor);
+                buf.insert(at, &text);
+                buf.cursor = at + 1;
+            } else {
+                // The lines go above: their newline after them rather than before
+                text.remove(0);
+                text.push('\n');
+                let at = buf.line_start(buf.cursor);
+                buf.insert(at, &text);
+                buf.cursor = at;
+            }
+        } else {
+            for _ in 0..count {
+                text.extend(&self.register.text);
+            }
+            let at = if after && buf.cursor < buf.line_end(buf.cursor) { buf.cursor + 1 } else { buf.cursor };
+            buf.insert(at, &text);
+            buf.cursor = at + text.len() - 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Type `keys` in normal mode, starting from `text` with the cursor at `cursor`
+    fn normal(text: &str, cursor: usize, keys: &str) -> (VimState, Buffer) {
+        let mut vim = VimState::new();
+        let mut buf = Buffer::new(text, cursor);
+        vim.mode = VimMode::Normal;
+        for c in keys.chars() {
+            vim.handle(&KeyEvent::from(KeyCode::Char(c)), &mut buf);
+        }
+        (vim, buf)
+    }
+
+    fn esc(vim: &mut VimState, buf: &mut Buffer) -> VimOutcome {
+        vim.handle(&KeyEvent::from(KeyCode::Esc), buf)
+    }
+
+    #[test]
+    fn test_modes() {
+        let mut vim = VimState::new();
+        let mut buf = Buffer::new("hello", 5);
+        assert_eq!(vim.mode(), VimMode::Insert);
+        assert_eq!(vim.handle(&KeyEvent::from(KeyCode::Char('x')), &mut buf), VimOutcome::Unhandled);
+        assert!(vim.wants_escape());
+
+        assert_eq!(esc(&mut vim, &mut buf), VimOutcome::Handled);
+        assert_eq!((vim.mode(), buf.cursor), (VimMode::Normal, 4));
+        assert!(!vim.wants_escape());
+        assert_eq!(vim.indicator(), "-- NORMAL --");
+
+        vim.handle(&KeyEvent::from(KeyCode::Char('V')), &mut buf);
+        assert_eq!(vim.mode(), VimMode::VisualLine);
+        esc(&mut vim, &mut buf);
+        vim.handle(&KeyEvent::from(KeyCode::Char('A')), &mut buf);
+        assert_eq!((vim.mode(), buf.cursor), (VimMode::Insert, 5));
+
+        // Shortcuts and Enter pass through in any mode
+        let ctrl_r = KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL);
+        esc(&mut vim, &mut buf);
+        assert_eq!(vim.handle(&ctrl_r, &mut buf), VimOutcome::Unhandled);
+        assert_eq!(vim.handle(&KeyEvent::from(KeyCode::Enter), &mut buf), VimOutcome::Submit);
+    }
+
+    #[test]
+    fn test_motions() {
+        let text = "let x = foo(bar);";
+        assert_eq!(normal(text, 0, "w").1.cursor, 4);
+        assert_eq!(normal(text, 0, "3w").1.cursor, 8);
+        assert_eq!(normal(text, 0, "4w").1.cursor, 11);
+        assert_eq!(normal(text, 12, "b").1.cursor, 11);
+        assert_eq!(normal(text, 12, "2b").1.cursor, 8);
+        assert_eq!(normal(text, 0, "e").1.cursor, 2);
+        assert_eq!(normal(text, 5, "$").1.cursor, 16);
+        assert_eq!(normal(text, 5, "0").1.cursor, 0);
+        assert_eq!(normal("  indented", 8, "^").1.cursor, 2);
+        assert_eq!(normal(text, 5, "3l").1.cursor, 8);
+        assert_eq!(normal(text, 5, "10h").1.cursor, 0);
+    }
+
+    #[test]
+    fn test_lines_and_history() {
+        let text = "first line\nsecond\nthird line";
+        assert_eq!(normal(text, 8, "j").1.cursor, 16, "column kept within the shorter line");
+        assert_eq!(normal(text, 8, "2j").1.cursor, 26);
+        assert_eq!(normal(text, 25, "gg").1.cursor, 0);
+        assert_eq!(normal(text, 0, "G").1.cursor, 18);
+        assert_eq!(normal(text, 0, "2G").1.cursor, 11);
+
+        // Past the first or last line: history
+        let mut vim = VimState::new();
+        let mut buf = Buffer::new(text, 3);
+        esc(&mut vim, &mut buf);
+        assert_eq!(vim.handle(&KeyEvent::from(KeyCode::Char('k')), &mut buf), VimOutcome::HistoryPrev);
+        assert_eq!(vim.handle(&KeyEvent::from(KeyCode::Char('G')), &mut buf), VimOutcome::Handled);
+        assert_eq!(vim.handle(&KeyEvent::from(KeyCode::Char('j')), &mut buf), VimOutcome::HistoryNext);
+    }
+
+    #[test]
+    fn test_operators_and_counts() {
+        assert_eq!(normal("one two three", 0, "dw").1.text(), "two three");
+        assert_eq!(normal("one two three", 0, "d2w").1.text(), "three");
+        assert_eq!(normal("one two three", 0, "2dw").1.text(), "three");
+        assert_eq!(normal("one two", 4, "dw").1.text(), "one ");
+        assert_eq!(normal("one two three", 4, "d$").1.text(), "one ");
+        assert_eq!(normal("one two three", 4, "D").1.text(), "one ");
+        assert_eq!(normal("one two three", 4, "db").1.text(), "two three");
+        assert_eq!(normal("one two three", 1, "3x").1.text(), "otwo three");
+
+        let (vim, buf) = normal("one two three", 4, "cw");
+        assert_eq!((buf.text().as_str(), buf.cursor, vim.mode()), ("one  three", 4, VimMode::Insert));
+        assert_eq!(normal("a b", 0, "cw").1.text(), " b");
+
+        let text = "a\nb\nc\nd";
+        assert_eq!(normal(text, 2, "dd").1.text(), "a\nc\nd");
+        assert_eq!(normal(text, 2, "2dd").1.text(), "a\nd");
+        assert_eq!(normal(text, 6, "dd").1.text(), "a\nb\nc");
+        assert_eq!(normal(text, 2, "dj").1.text(), "a\nd");
+        assert_eq!(normal(text, 4, "dgg").1.text(), "d");
+        let (vim, buf) = normal(text, 2, "cc");
+        assert_eq!((buf.text().as_str(), buf.cursor, vim.mode()), ("a\n\nc\nd", 2, VimMode::Insert));
+
+        // Pending commands show, and Esc drops them
+        let (mut vim, mut buf) = normal(text, 0, "2d3");
+        assert_eq!(vim.indicator(), "-- NORMAL -- 2d3");
+        assert!(vim.wants_escape());
+        esc(&mut vim, &mut buf);
+        assert_eq!((vim.indicator().as_str(), buf.text().as_str()), ("-- NORMAL --", text));
+    }
+
+    #[test]
+    fn test_yank_paste_and_undo() {
+        assert_eq!(normal("one two", 0, "ywP").1.text(), "one one two");
+        assert_eq!(normal("ab", 0, "ylp").1.text(), "aab");
+        assert_eq!(normal("a\nb", 0, "yyp").1.text(), "a\na\nb");
+        assert_eq!(normal("a\nb", 2, "yy2P").1.text(), "a\nb\nb\nb");
+        assert_eq!(normal("a\nb", 0, "ddp").1.text(), "b\na");
+
+        let (_, buf) = normal("one two three", 0, "dwdwuu");
+        assert_eq!((buf.text().as_str(), buf.cursor), ("one two three", 0));
+        assert_eq!(normal("one two three", 0, "dwdw2u").1.text(), "one two three");
+
+        // An insert undoes as one change
+        let (mut vim, mut buf) = normal("abc", 2, "a");
+        for c in "def".chars() {
+            buf.insert(buf.cursor, &[c]);
+            buf.cursor += 1;
+        }
+        esc(&mut vim, &mut buf);
+        assert_eq!(buf.text(), "abcdef");
+        vim.handle(&KeyEvent::from(KeyCode::Char('u')), &mut buf);
+        assert_eq!(buf.text(), "abc");
+    }
+
+    #[test]
+    fn test_open_lines() {
+        let (vim, buf) = normal("a\nb", 0, "o");
+        assert_eq!((buf.text().as_str(), buf.cursor, vim.mode()), ("a\n\nb", 2, VimMode::Insert));
+        let (_, buf) = normal("a\nb", 2, "O");
+        assert_eq!((buf.text().as_str(), buf.cursor), ("a\n\nb", 2));
+    }
+
+    #[test]
+    fn test_visual_line() {
+        let text = "a\nb\nc\nd";
+        let (vim, buf) = normal(text, 2, "Vj");
+        assert_eq!(vim.visual_lines(&buf), Some((1, 2)));
+        assert_eq!(vim.indicator(), "-- VISUAL LINE --");
+
+        let (vim, buf) = normal(text, 2, "Vjd");
+        assert_eq!((buf.text().as_str(), vim.mode()), ("a\nd", VimMode::Normal));
+        assert_eq!(normal(text, 4, "Vkyjp").1.text(), "a\nb\nc\nb\nc\nd");
+        let (vim, buf) = normal(text, 0, "Vc");
+        assert_eq!((buf.text().as_str(), vim.mode()), ("\nb\nc\nd", VimMode::Insert));
+
+        // j past the last line stays in the buffer
+        let (_, buf) = normal(text, 6, "Vj");
+        assert_eq!(buf.cursor, 6);
+    }
+}
@@ -14,8 +14,8 @@ use console::style;
 use self_update::cargo_crate_version;
 
 use cowork_core::update::{
-    clear_staged_update, compute_sha256, has_auto_update_marker, read_staged_update,
-    updates_dir, write_staged_update, StagedUpdate,
+    clear_staged_update, compute_sha256, download_file, fetch_releases, has_auto_update_marker,
+    read_staged_update, updates_dir, write_staged_update, StagedUpdate,
 };
 
 const REPO_OWNER: &str = "szguoxz";
@@ -134,24 +134,17 @@ fn background_download_inner() -> Option<String> {
 
     let current = cargo_crate_version!();
 
-    let releases = self_update::backends::github::ReleaseList::configure()
-        .repo_owner(REPO_OWNER)
-        .repo_name(REPO_NAME)
-        .build()
-        .ok()?
-        .fetch()
-        .ok()?;
+    let releases = fetch_releases(REPO_OWNER, REPO_NAME).ok()?;
 
     // Find the latest release with the [auto-update] marker that is newer
     let eligible = releases.iter().find(|r| {
-        let version = r.version.trim_start_matches('v');
         let is_newer =
-            self_update::version::bump_is_greater(current, version).unwrap_or(false);
+            self_update::version::bump_is_greater(current, r.version()).unwrap_or(false);
         let has_marker = has_auto_update_marker(r.body.as_deref());
         is_newer && has_marker
     })?;
 
-    let version = eligible.version.trim_start_matches('v').to_string();
+    let version = eligible.version().to_string();
     let target = self_update::get_target();
 
     // Find the matching asset
@@ -177,7 +170,7 @@ fn background_download_inner() -> Option<String> {
 
     // Download the asset
     let archive_path = stage_dir.join(&asset.name);
-    download_asset(&asset.download_url, &archive_path).ok()?;
+    download_file(&asset.download_url, &archive_path).ok()?;
 
     // Extract the binary from the archive
     extract_binary(&archive_path, &binary_path).ok()?;
@@ -254,14 +247,10 @@ pub async fn run_update(check_only: bool) -> anyhow::Result<()> {
 fn check_for_update(current: &str) -> anyhow::Result<()> {
     println!("Checking for updates...");
 
-    let releases = self_update::backends::github::ReleaseList::configure()
-        .repo_owner(REPO_OWNER)
-        .repo_name(REPO_NAME)
-        .build()?
-        .fetch()?;
+    let releases = fetch_releases(REPO_OWNER, REPO_NAME)?;
 
     if let Some(latest) = releases.first() {
-        let latest_version = latest.version.trim_start_matches('v');
+        let latest_version = latest.version();
         if self_update::version::bump_is_greater(current, latest_version)? {
             println!(
                 "{} {} is available (you have {})",
@@ -287,46 +276,51 @@ fn check_for_update(current: &str) -> anyhow::Result<()> {
 fn perform_update(current: &str) -> anyhow::Result<()> {
     println!("Looking for updates...");
 
-    let status = self_update::backends::github::Update::configure()
-        .repo_owner(REPO_OWNER)
-        .repo_name(REPO_NAME)
-        .bin_name("cowork")
-        .show_download_progress(true)
-        .no_confirm(true)
-        .current_version(current)
-        .build()?
-        .update()?;
-
-    match status {
-        self_update::Status::UpToDate(v) => {
+    let releases = fetch_releases(REPO_OWNER, REPO_NAME)?;
+    let latest = match releases.first() {
+        Some(latest) if self_update::version::bump_is_greater(current, latest.version())? => latest,
+        _ => {
             println!(
                 "{} (version {})",
                 style("Already up to date.").green(),
-                v
-            );
-        }
-        self_update::Status::Updated(v) => {
-            println!(
-                "{} to version {}",
-                style("Successfully updated").green().bold(),
-                style(v).cyan()
+                current
             );
+            return Ok(());
         }
+    };
+
+    let target = self_update::get_target();
+    let asset_name = format!("cowork-cli-{}", target);
+    let asset = latest
+        .assets
+        .iter()
+        .find(|a| a.name.starts_with(&asset_name))
+        .ok_or_else(|| anyhow::anyhow!("Release {} has no build for {}", latest.version(), target))?;
+
+    println!("Downloading {}...", asset.name);
+    let dir = self_update::TempDir::new()?;
+    let archive_path = dir.path().join(&asset.name);
+    let binary_path = dir.path().join(binary_name());
+    download_file(&asset.download_url, &archive_path)?;
+    extract_binary(&archive_path, &binary_path)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&binary_path, fs::Permissions::from_mode(0o755))?;
     }
+    self_replace::self_replace(&binary_path)?;
+
+    println!(
+        "{} to version {}",
+        style("Successfully updated").green().bold(),
+        style(latest.version()).cyan()
+    );
 
     Ok(())
 }
 
 // ─── Helpers ─────────────────────────────────────────────────────────────────
 
-/// Download a file from a URL to a local path.
-fn download_asset(url: &str, dest: &PathBuf) -> anyhow::Result<()> {
-    let response = reqwest::blocking::get(url)?;
-    let bytes = response.bytes()?;
-    fs::write(dest, &bytes)?;
-    Ok(())
-}
-
 /// Extract the `cowork` binary from a tar.gz or zip archive.
 fn extract_binary(archive_path: &PathBuf, binary_path: &PathBuf) -> anyhow::Result<()> {
     let name = archive_path
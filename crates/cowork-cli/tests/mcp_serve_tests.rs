@@ -0,0 +1,108 @@
+//! Conformance tests for `cowork mcp-serve`
+//!
+//! Each test starts the CLI binary as an MCP server over stdio and drives it
+//! with the client from cowork-mcp.
+
+use std::path::Path;
+
+use cowork_mcp::client::{ClientInfo, McpClient, ToolCallResult};
+use cowork_mcp::transport::StdioTransport;
+use serde_json::json;
+use tempfile::TempDir;
+
+/// A workspace with one text file in it
+fn workspace() -> (TempDir, std::path::PathBuf) {
+    let dir = TempDir::new().unwrap();
+    let path = dunce::canonicalize(dir.path()).unwrap();
+    std::fs::write(path.join("notes.txt"), "hello from the workspace\n").unwrap();
+    (dir, path)
+}
+
+/// Start `cowork mcp-serve` on the workspace and initialize a client
+async fn connect(workspace: &Path, extra_args: &[&str]) -> McpClient<StdioTransport> {
+    let mut args = vec!["-w", workspace.to_str().unwrap(), "mcp-serve", "--stdio"];
+    args.extend_from_slice(extra_args);
+    let transport = StdioTransport::spawn(env!("CARGO_BIN_EXE_cowork-cli"), &args).await.unwrap();
+    let mut client = McpClient::new(transport);
+    let info = client
+        .initialize(ClientInfo {
+            name: "conformance-test".to_string(),
+            version: "0.0.0".to_string(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(info.name, "cowork");
+    client
+}
+
+fn text(result: &ToolCallResult) -> String {
+    result.content.iter().filter_map(|c| c.text.clone()).collect::<Vec<_>>().join("\n")
+}
+
+#[tokio::test]
+async fn test_mcp_serve_over_stdio() {
+    let (_dir, workspace) = workspace();
+    let client = connect(&workspace, &[]).await;
+
+    let tools = client.list_tools().await.unwrap();
+    let names: Vec<&str> = tools.iter().map(|t| t.name.as_str()).collect();
+    for expected in ["Read", "Write", "Edit", "Glob", "Grep", "Bash"] {
+        assert!(names.contains(&expected), "{} missing from {:?}", expected, names);
+    }
+    assert!(!names.contains(&"AskUserQuestion"), "session-only tools are left out");
+    assert!(!names.contains(&"Task"));
+    let read = tools.iter().find(|t| t.name == "Read").unwrap();
+    assert_eq!(read.input_schema["required"], json!(["file_path"]));
+
+    // Read doesn't need approval
+    let notes = workspace.join("notes.txt");
+    let result = client.call_tool("Read", json!({"file_path": notes})).await.unwrap();
+    assert!(!result.is_error, "{}", text(&result));
+    assert!(text(&result).contains("hello from the workspace"));
+
+    // A failing tool is reported as an error result
+    let result = client
+        .call_tool("Read", json!({"file_path": workspace.join("missing.txt")}))
+        .await
+        .unwrap();
+    assert!(result.is_error);
+
+    // Write does, and there is no one to ask
+    let created = workspace.join("created.txt");
+    let result = client
+        .call_tool("Write", json!({"file_path": created, "content": "x"}))
+        .await
+        .unwrap();
+    assert!(result.is_error);
+    assert!(text(&result).contains("needs approval"), "{}", text(&result));
+    assert!(!created.exists());
+
+    let result = client.call_tool("NoSuchTool", json!({})).await.unwrap();
+    assert!(result.is_error);
+    assert!(text(&result).contains("Unknown tool"));
+
+    // Workspace files are read-only resources
+    let resources = client.list_resources().await.unwrap();
+    let resource = resources.iter().find(|r| r.name == "notes.txt").unwrap();
+    assert!(resource.uri.starts_with("file://"));
+    let contents = client.read_resource(&resource.uri).await.unwrap();
+    assert_eq!(contents[0].text.as_deref(), Some("hello from the workspace\n"));
+
+    let outside = workspace.parent().unwrap().join("outside.txt");
+    let uri = format!("file://{}", outside.display());
+    assert!(client.read_resource(&uri).await.is_err());
+}
+
+#[tokio::test]
+async fn test_mcp_serve_yolo_runs_tools_needing_approval() {
+    let (_dir, workspace) = workspace();
+    let client = connect(&workspace, &["--yolo"]).await;
+
+    let created = workspace.join("created.txt");
+    let result = client
+        .call_tool("Write", json!({"file_path": created, "content": "written over MCP\n"}))
+        .await
+        .unwrap();
+    assert!(!result.is_error, "{}", text(&result));
+    assert_eq!(std::fs::read_to_string(&created).unwrap(), "written over MCP\n");
+}
@@ -0,0 +1,64 @@
+//! Non-interactive `cowork --one-shot` runs: the approve/deny rule flags and
+//! the refusal to start without a terminal when a tool could ask for approval
+
+use std::process::{Command, Output, Stdio};
+
+use tempfile::TempDir;
+
+/// Run the CLI on an empty workspace with stdin closed (no terminal), with
+/// its own home directory and no API key, so nothing reaches a real provider
+fn run(args: &[&str]) -> Output {
+    let workspace = TempDir::new().unwrap();
+    Command::new(env!("CARGO_BIN_EXE_cowork-cli"))
+        .arg("-w")
+        .arg(workspace.path())
+        .args(args)
+        .env("HOME", workspace.path())
+        .env_remove("XDG_CONFIG_HOME")
+        .env_remove("XDG_DATA_HOME")
+        .env_remove("ANTHROPIC_API_KEY")
+        .stdin(Stdio::null())
+        .output()
+        .unwrap()
+}
+
+fn stderr_of(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn test_refuses_without_terminal_when_tools_could_ask() {
+    let output = run(&["--one-shot", "hello"]);
+    assert!(!output.status.success());
+    let stderr = stderr_of(&output);
+    assert!(stderr.contains("could ask for approval"), "{stderr}");
+    assert!(stderr.contains("Bash"), "{stderr}");
+
+    // Deny rules alone still leave Bash able to ask
+    let output = run(&["--one-shot", "hello", "--deny-tools", "Write,Edit"]);
+    let stderr = stderr_of(&output);
+    let listed: Vec<&str> = stderr
+        .lines()
+        .next()
+        .and_then(|line| line.rsplit_once(": "))
+        .map(|(_, tools)| tools.split(", ").collect())
+        .unwrap_or_default();
+    assert!(listed.contains(&"Bash"), "{stderr}");
+    assert!(!listed.contains(&"Write") && !listed.contains(&"Edit"), "{stderr}");
+
+    // Approve rules settle every call: the run gets as far as the provider
+    let output = run(&["--one-shot", "hello", "--approve-tools", "Read,Glob,Grep,Bash:cargo*"]);
+    assert!(!stderr_of(&output).contains("could ask for approval"));
+}
+
+#[test]
+fn test_rule_flags_are_validated() {
+    let output = run(&["--one-shot", "hello", "--approve-tools", "Read,Write:src/*"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr_of(&output).contains("only Bash rules take a command pattern"));
+
+    // The rules only apply to one-shot runs
+    let output = run(&["--approve-tools", "Read", "tools"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr_of(&output).contains("--one-shot"));
+}
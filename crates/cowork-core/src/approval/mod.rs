@@ -4,11 +4,17 @@
 //! are automatically approved vs require explicit confirmation.
 
 pub mod bash_safety;
+pub mod exfiltration;
+pub mod rules;
 
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
 
-use crate::tools::interaction::ASK_QUESTION_TOOL_NAME;
+use crate::policy::{tool_matches, Policy};
+use crate::tools::interaction::{ASK_QUESTION_TOOL_NAME, CLIPBOARD_WRITE_TOOL_NAME};
+
+pub use exfiltration::SecurityFinding;
+pub use rules::ToolRule;
 
 /// Level of approval required for an operation
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Default)]
@@ -93,6 +99,19 @@ pub enum ApprovalResponse {
 // Tool Approval Configuration
 // ============================================================================
 
+/// What the approve and deny rules decide for a tool call
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleDecision {
+    /// Run it without asking, as the given rule allows
+    Approve(String),
+    /// Refuse it without asking, for the given reason
+    Deny(String),
+    /// Only a reviewer on `channel` may approve it, not the session owner
+    Delegate { channel: String, reason: String },
+    /// No rule settles it: the usual approval flow applies
+    Undecided,
+}
+
 /// Configuration for auto-approval of tools
 ///
 /// This is the canonical source for determining which tools need user approval.
@@ -109,8 +128,29 @@ pub struct ToolApprovalConfig {
     session_approved: std::collections::HashSet<String>,
     /// If true, auto-approve everything for the session
     session_approve_all: bool,
+    /// Least strict level allowed (from the organization policy)
+    min_level: ApprovalLevel,
+    /// Tool patterns that always need approval (from the organization policy)
+    policy_required: Vec<String>,
+    /// Calls run without asking; with any, every other call is refused
+    approve_rules: Vec<ToolRule>,
+    /// Calls refused without asking
+    deny_rules: Vec<ToolRule>,
+    /// Calls a reviewer must approve, with the channel reviewers watch
+    delegate_rules: Vec<ToolRule>,
+    delegate_channel: String,
+    /// How long a delegated call waits for a reviewer before it's refused
+    delegate_timeout: std::time::Duration,
+    /// Hosts shell commands may send secrets to without a warning
+    trusted_hosts: exfiltration::TrustedHosts,
 }
 
+/// Channel delegated approvals go to when none is configured
+pub const DEFAULT_DELEGATE_CHANNEL: &str = "reviewers";
+
+/// How long a delegated approval waits for a reviewer by default
+pub const DEFAULT_DELEGATE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30 * 60);
+
 impl Default for ToolApprovalConfig {
     fn default() -> Self {
         Self::new(ApprovalLevel::Low)
@@ -126,6 +166,11 @@ impl ToolApprovalConfig {
         auto_approve.insert("Read".to_string());
         auto_approve.insert("Glob".to_string());
         auto_approve.insert("Grep".to_string());
+        auto_approve.insert("CodebaseSearch".to_string());
+        auto_approve.insert("SearchFiles".to_string());
+
+        // Only checks a chunked write and tidies its whitespace
+        auto_approve.insert("FinishFile".to_string());
 
         // Web operations (read-only)
         auto_approve.insert("WebFetch".to_string());
@@ -143,13 +188,22 @@ impl ToolApprovalConfig {
         // Planning/interaction tools
         auto_approve.insert(ASK_QUESTION_TOOL_NAME.to_string());
         auto_approve.insert("ExitPlanMode".to_string());
+        auto_approve.insert("PlanStatus".to_string());
         auto_approve.insert("Skill".to_string());
 
+        // Clipboard writes only replace the clipboard (and show in the transcript)
+        auto_approve.insert(CLIPBOARD_WRITE_TOOL_NAME.to_string());
+
         // Destructive tools that always require approval
         let mut always_require = std::collections::HashSet::new();
         always_require.insert("Write".to_string());
         always_require.insert("Edit".to_string());
         always_require.insert("Bash".to_string());
+        // High risk: rewrites many files at once (approved once for the batch)
+        always_require.insert("BatchEdit".to_string());
+        always_require.insert("WriteDocx".to_string());
+        always_require.insert("WriteXlsx".to_string());
+        always_require.insert("WritePptx".to_string());
 
         Self {
             auto_approve,
@@ -157,6 +211,14 @@ impl ToolApprovalConfig {
             level,
             session_approved: std::collections::HashSet::new(),
             session_approve_all: false,
+            min_level: ApprovalLevel::None,
+            policy_required: Vec::new(),
+            approve_rules: Vec::new(),
+            deny_rules: Vec::new(),
+            delegate_rules: Vec::new(),
+            delegate_channel: DEFAULT_DELEGATE_CHANNEL.to_string(),
+            delegate_timeout: DEFAULT_DELEGATE_TIMEOUT,
+            trusted_hosts: exfiltration::TrustedHosts::default(),
         }
     }
 
@@ -168,6 +230,14 @@ impl ToolApprovalConfig {
             level: ApprovalLevel::None,
             session_approved: std::collections::HashSet::new(),
             session_approve_all: false,
+            min_level: ApprovalLevel::None,
+            policy_required: Vec::new(),
+            approve_rules: Vec::new(),
+            deny_rules: Vec::new(),
+            delegate_rules: Vec::new(),
+            delegate_channel: DEFAULT_DELEGATE_CHANNEL.to_string(),
+            delegate_timeout: DEFAULT_DELEGATE_TIMEOUT,
+            trusted_hosts: exfiltration::TrustedHosts::default(),
         }
     }
 
@@ -183,6 +253,9 @@ impl ToolApprovalConfig {
     /// For Bash tools, this parses the command to determine if it's read-only (safe).
     /// For other tools, delegates to `should_auto_approve`.
     pub fn should_auto_approve_with_args(&self, tool_name: &str, args: &serde_json::Value) -> bool {
+        if self.required_by_policy(tool_name) {
+            return false;
+        }
         if tool_name == "Bash" {
             // Session-wide approval overrides everything
             if self.session_approve_all {
@@ -203,7 +276,12 @@ impl ToolApprovalConfig {
 
     /// Check if a tool should be auto-approved
     pub fn should_auto_approve(&self, tool_name: &str) -> bool {
-        // Session-wide approval overrides everything
+        // The organization policy overrides even session approvals
+        if self.required_by_policy(tool_name) {
+            return false;
+        }
+
+        // Session-wide approval overrides everything else
         if self.session_approve_all || self.session_approved.contains(tool_name) {
             return true;
         }
@@ -276,8 +354,119 @@ impl ToolApprovalConfig {
         self.level
     }
 
-    /// Set the approval level
+    /// Set the approval level (raised to the policy's minimum, if any)
     pub fn set_level(&mut self, level: ApprovalLevel) {
-        self.level = level;
+        self.level = level.max(self.min_level);
+    }
+
+    /// Apply the organization policy's approval rules
+    ///
+    /// The level is raised to the policy's minimum (and kept there by
+    /// `set_level`), and the tools it lists always need approval.
+    pub fn apply_policy(&mut self, policy: &Policy) {
+        if let Some(min) = policy.min_approval_level() {
+            self.min_level = self.min_level.max(min);
+            self.level = self.level.max(self.min_level);
+        }
+        self.policy_required.extend(policy.approval.require.iter().cloned());
+    }
+
+    /// Whether the organization policy requires approval for the tool
+    pub fn required_by_policy(&self, tool_name: &str) -> bool {
+        self.policy_required.iter().any(|pattern| tool_matches(pattern, tool_name))
+    }
+
+    /// Settle calls without asking: those matching `deny` are refused, those
+    /// matching `approve` run, and when there are approve rules every other
+    /// call is refused too
+    ///
+    /// Tools the organization policy requires approval for still ask.
+    pub fn with_rules(mut self, approve: Vec<ToolRule>, deny: Vec<ToolRule>) -> Self {
+        self.approve_rules = approve;
+        self.deny_rules = deny;
+        self
+    }
+
+    /// Hand calls matching `rules` to a reviewer on `channel`: the session
+    /// owner can't approve them, only cancel them
+    ///
+    /// Delegation wins over approve rules and the organization policy; deny
+    /// rules still refuse first.
+    pub fn with_delegation(mut self, rules: Vec<ToolRule>, channel: impl Into<String>) -> Self {
+        self.delegate_rules = rules;
+        self.delegate_channel = channel.into();
+        self
+    }
+
+    /// Refuse delegated calls no reviewer settled within `timeout`
+    pub fn with_delegate_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.delegate_timeout = timeout;
+        self
+    }
+
+    /// How long a delegated call waits for a reviewer
+    pub fn delegate_timeout(&self) -> std::time::Duration {
+        self.delegate_timeout
+    }
+
+    /// Don't warn about secrets sent to these hosts (patterns as in
+    /// `web.allowed_hosts`)
+    pub fn with_trusted_hosts(mut self, hosts: &[String]) -> Self {
+        self.trusted_hosts = exfiltration::TrustedHosts::new(hosts);
+        self
+    }
+
+    /// Signs that a call exfiltrates secrets (see `exfiltration`)
+    ///
+    /// A call with findings always needs a person's approval: approve rules,
+    /// session approvals and frontends' auto-approval don't apply to it.
+    pub fn scan(&self, tool_name: &str, args: &serde_json::Value) -> Vec<exfiltration::SecurityFinding> {
+        exfiltration::scan_tool_call(tool_name, args, &self.trusted_hosts)
+    }
+
+    /// Whether any approve or deny rules are set
+    pub fn has_rules(&self) -> bool {
+        !self.approve_rules.is_empty() || !self.deny_rules.is_empty()
+    }
+
+    /// What the approve and deny rules decide for a call
+    pub fn decide(&self, tool_name: &str, args: &serde_json::Value) -> RuleDecision {
+        if let Some(rule) = self.deny_rules.iter().find(|rule| rule.denies(tool_name, args)) {
+            return RuleDecision::Deny(format!("{} is denied by the rule '{}'", tool_name, rule));
+        }
+        if let Some(rule) = self.delegate_rules.iter().find(|rule| rule.denies(tool_name, args)) {
+            return RuleDecision::Delegate {
+                channel: self.delegate_channel.clone(),
+                reason: format!("{} needs a reviewer's approval (rule '{}')", tool_name, rule),
+            };
+        }
+        if self.required_by_policy(tool_name) {
+            return RuleDecision::Undecided;
+        }
+        if let Some(rule) = self.approve_rules.iter().find(|rule| rule.approves(tool_name, args)) {
+            return RuleDecision::Approve(rule.to_string());
+        }
+        if self.approve_rules.is_empty() {
+            return RuleDecision::Undecided;
+        }
+        let allowed: Vec<String> = self.approve_rules.iter().map(ToString::to_string).collect();
+        RuleDecision::Deny(format!(
+            "{} is not among the approved tools ({})",
+            tool_name,
+            allowed.join(", ")
+        ))
+    }
+
+    /// Whether some call to the tool could wait for an interactive approval:
+    /// the rules don't settle every call and the tool isn't auto-approved
+    pub fn could_prompt(&self, tool_name: &str) -> bool {
+        if self.deny_rules.iter().any(|rule| rule.covers_tool(tool_name)) {
+            return false;
+        }
+        if self.required_by_policy(tool_name) || self.delegate_rules.iter().any(|rule| rule.names_tool(tool_name)) {
+            return true;
+        }
+        // With approve rules every call is approved or refused
+        self.approve_rules.is_empty() && !self.should_auto_approve(tool_name)
     }
 }
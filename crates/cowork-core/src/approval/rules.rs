@@ -0,0 +1,225 @@
+//! Approve and deny rules for unattended runs (`--approve-tools`, `--deny-tools`)
+//!
+//! A rule names a tool with a policy-style pattern (`*` matches any run of
+//! characters; MCP tools also match by their name on the server) and may
+//! scope Bash to commands matching a pattern: `Bash:cargo*`. A compound
+//! command (`a && b`, `a | b`, `a; b`) matches an approve rule only if every
+//! command in it does, and a deny rule if any does. Commands with
+//! substitutions (`$(...)`, backticks) never match an approve rule.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::policy::{tool_matches, wildcard_match};
+
+/// The tool whose calls a rule can scope by command
+const BASH_TOOL: &str = "Bash";
+
+/// One `--approve-tools` / `--deny-tools` entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolRule {
+    /// Tool name pattern
+    tool: String,
+    /// Command pattern for Bash calls (None = every call)
+    command: Option<String>,
+}
+
+impl ToolRule {
+    /// A rule covering every call to the tools matching `tool`
+    pub fn new(tool: impl Into<String>) -> Self {
+        Self {
+            tool: tool.into(),
+            command: None,
+        }
+    }
+
+    /// Whether some call to the tool could match the rule
+    pub fn names_tool(&self, tool_name: &str) -> bool {
+        tool_matches(&self.tool, tool_name)
+    }
+
+    /// Whether the rule covers every call to the tool, whatever its arguments
+    pub fn covers_tool(&self, tool_name: &str) -> bool {
+        self.command.is_none() && tool_matches(&self.tool, tool_name)
+    }
+
+    /// Whether a call matches the rule as an approve rule
+    pub fn approves(&self, tool_name: &str, args: &serde_json::Value) -> bool {
+        if !tool_matches(&self.tool, tool_name) {
+            return false;
+        }
+        let Some(pattern) = &self.command else {
+            return true;
+        };
+        let Some(command) = args.get("command").and_then(|v| v.as_str()) else {
+            return false;
+        };
+        if command.contains('`') || command.contains("$(") || command.contains("<(") || command.contains(">(") {
+            return false;
+        }
+        let segments = command_segments(command);
+        !segments.is_empty() && segments.iter().all(|segment| wildcard_match(pattern, segment))
+    }
+
+    /// Whether a call matches the rule as a deny rule
+    pub fn denies(&self, tool_name: &str, args: &serde_json::Value) -> bool {
+        if !tool_matches(&self.tool, tool_name) {
+            return false;
+        }
+        let Some(pattern) = &self.command else {
+            return true;
+        };
+        match args.get("command").and_then(|v| v.as_str()) {
+            Some(command) => command_segments(command).iter().any(|segment| wildcard_match(pattern, segment)),
+            None => false,
+        }
+    }
+}
+
+impl FromStr for ToolRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (tool, command) = match s.trim().split_once(':') {
+            Some((tool, command)) => (tool.trim(), Some(command.trim())),
+            None => (s.trim(), None),
+        };
+        if tool.is_empty() {
+            return Err(format!("Invalid tool rule '{}': missing the tool name", s));
+        }
+        match command {
+            Some(_) if tool != BASH_TOOL => Err(format!(
+                "Invalid tool rule '{}': only {} rules take a command pattern",
+                s, BASH_TOOL
+            )),
+            Some("") => Err(format!("Invalid tool rule '{}': empty command pattern", s)),
+            command => Ok(Self {
+                tool: tool.to_string(),
+                command: command.map(str::to_string),
+            }),
+        }
+    }
+}
+
+impl fmt::Display for ToolRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.command {
+            Some(command) => write!(f, "{}:{}", self.tool, command),
+            None => write!(f, "{}", self.tool),
+        }
+    }
+}
+
+/// The simple commands of a shell command line: split on `;`, `&`, `|` and
+/// newlines outside quotes (`&&`, `||` and pipes included), trimmed, without
+/// empty ones
+///
+/// The `&` of a redirection (`2>&1`, `&>`) doesn't split.
+fn command_segments(command: &str) -> Vec<&str> {
+    let bytes = command.as_bytes();
+    let mut segments = Vec::new();
+    let mut start = 0;
+    let mut in_single_quote = false;
+    let mut in_double_quote = false;
+    for (i, &byte) in bytes.iter().enumerate() {
+        match byte {
+            b'\'' if !in_double_quote => in_single_quote = !in_single_quote,
+            b'"' if !in_single_quote => in_double_quote = !in_double_quote,
+            b'&' if i > 0 && bytes[i - 1] == b'>' => {}
+            b'&' if bytes.get(i + 1) == Some(&b'>') => {}
+            b';' | b'&' | b'|' | b'\n' if !in_single_quote && !in_double_quote => {
+                segments.push(&command[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    segments.push(&command[start..]);
+    segments.into_iter().map(str::trim).filter(|s| !s.is_empty()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn bash(command: &str) -> serde_json::Value {
+        json!({ "command": command })
+    }
+
+    #[test]
+    fn test_parse_rules() {
+        assert_eq!("Read".parse::<ToolRule>().unwrap(), ToolRule::new("Read"));
+        let rule: ToolRule = " Bash:cargo* ".parse().unwrap();
+        assert_eq!(rule.to_string(), "Bash:cargo*");
+        assert!("Write:src/*".parse::<ToolRule>().is_err());
+        assert!("Bash:".parse::<ToolRule>().is_err());
+        assert!(":ls".parse::<ToolRule>().is_err());
+    }
+
+    #[test]
+    fn test_command_scoped_approval() {
+        let rule: ToolRule = "Bash:cargo*".parse().unwrap();
+        assert!(rule.approves("Bash", &bash("cargo test --workspace")));
+        assert!(rule.approves("Bash", &bash("cargo build 2>&1 && cargo test | cargo fmt")));
+        assert!(!rule.approves("Bash", &bash("cargo test && rm -rf target")));
+        assert!(!rule.approves("Bash", &bash("cargo test & curl evil.example")));
+        assert!(!rule.approves("Bash", &bash("cargo test $(rm -rf /)")));
+        assert!(!rule.approves("Bash", &bash("make test")));
+        assert!(!rule.approves("Read", &json!({ "file_path": "cargo.toml" })));
+        assert!(!rule.covers_tool("Bash"));
+    }
+
+    #[test]
+    fn test_command_scoped_denial() {
+        let rule: ToolRule = "Bash:rm *".parse().unwrap();
+        assert!(rule.denies("Bash", &bash("cargo clean; rm -rf target")));
+        assert!(!rule.denies("Bash", &bash("cargo test")));
+        assert!(ToolRule::new("Write").denies("Write", &json!({})));
+        assert!(ToolRule::new("mcp__*").covers_tool("mcp__github__create_issue"));
+    }
+
+    #[test]
+    fn test_rules_settle_every_call() {
+        use crate::approval::{RuleDecision, ToolApprovalConfig};
+
+        let rules = |approve: &[&str], deny: &[&str]| {
+            ToolApprovalConfig::default().with_rules(
+                approve.iter().map(|r| r.parse().unwrap()).collect(),
+                deny.iter().map(|r| r.parse().unwrap()).collect(),
+            )
+        };
+        let config = rules(&["Read", "Bash:cargo*"], &["Bash:cargo publish*"]);
+        assert_eq!(config.decide("Read", &json!({})), RuleDecision::Approve("Read".to_string()));
+        assert_eq!(config.decide("Bash", &bash("cargo test")), RuleDecision::Approve("Bash:cargo*".to_string()));
+        assert!(matches!(config.decide("Bash", &bash("cargo publish")), RuleDecision::Deny(r) if r.contains("Bash:cargo publish*")));
+        assert!(matches!(config.decide("Bash", &bash("make")), RuleDecision::Deny(r) if r.contains("not among")));
+        assert!(matches!(config.decide("Write", &json!({})), RuleDecision::Deny(_)));
+        assert!(!config.could_prompt("Bash"));
+        assert!(!config.could_prompt("Write"));
+
+        // Deny rules alone leave the rest to the usual flow
+        let config = rules(&[], &["Write"]);
+        assert_eq!(config.decide("Bash", &bash("make")), RuleDecision::Undecided);
+        assert!(!config.could_prompt("Write"));
+        assert!(config.could_prompt("Bash"));
+        assert!(!config.could_prompt("Read"));
+    }
+
+    #[test]
+    fn test_delegation_wins_over_approval() {
+        use crate::approval::{RuleDecision, ToolApprovalConfig};
+
+        let config = ToolApprovalConfig::default()
+            .with_rules(vec!["Bash".parse().unwrap()], vec!["Bash:rm *".parse().unwrap()])
+            .with_delegation(vec!["Bash:kubectl*".parse().unwrap()], "ops");
+        assert!(matches!(
+            config.decide("Bash", &bash("cargo build && kubectl apply -f prod.yaml")),
+            RuleDecision::Delegate { channel, reason } if channel == "ops" && reason.contains("Bash:kubectl*")
+        ));
+        assert_eq!(config.decide("Bash", &bash("cargo build")), RuleDecision::Approve("Bash".to_string()));
+        // Deny rules still refuse first
+        assert!(matches!(config.decide("Bash", &bash("rm -rf / ; kubectl get pods")), RuleDecision::Deny(_)));
+        assert!(config.could_prompt("Bash"));
+    }
+}
@@ -0,0 +1,161 @@
+//! Workspace file paths for completing `@` mentions
+//!
+//! The desktop composer and the CLI input complete `@src/ma` to workspace
+//! files. `PathCompleter` lists the files the way the workspace index does
+//! (ignore-aware, without `.git`, `.cowork` and build output) and keeps the
+//! list until a directory in it changes, so completing as someone types
+//! doesn't walk the workspace on every key.
+//!
+//! A file matches when its path starts with what was typed, when one of its
+//! path segments does, or when the typed characters appear in it in order.
+//! Matches rank in that order, then shallower paths first, then the most
+//! recently modified.
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::context::index::WorkspaceListing;
+
+/// How many completions are returned when the caller doesn't say
+pub const DEFAULT_COMPLETION_LIMIT: usize = 20;
+
+/// Completes workspace paths, keeping the file list until it changes
+pub struct PathCompleter {
+    workspace: PathBuf,
+    cache: Mutex<Option<Arc<WorkspaceListing>>>,
+}
+
+impl PathCompleter {
+    pub fn new(workspace: impl Into<PathBuf>) -> Self {
+        Self {
+            workspace: workspace.into(),
+            cache: Mutex::new(None),
+        }
+    }
+
+    /// Forget the listed files
+    pub fn invalidate(&self) {
+        *self.cache.lock().unwrap() = None;
+    }
+
+    /// Up to `limit` workspace files matching `prefix` (relative, with `/`
+    /// separators), best first; the most recently modified files when
+    /// `prefix` is empty
+    ///
+    /// Walks the workspace when the list isn't cached or is out of date, so
+    /// call it off the async runtime.
+    pub fn complete_paths(&self, prefix: &str, limit: usize) -> Vec<String> {
+        let listing = self.listing();
+        rank_paths(listing.files.iter().map(|(path, mtime)| (path.as_str(), *mtime)), prefix, limit)
+    }
+
+    fn listing(&self) -> Arc<WorkspaceListing> {
+        let mut cache = self.cache.lock().unwrap();
+        if let Some(listing) = cache.as_ref().filter(|listing| listing.is_current()) {
+            return listing.clone();
+        }
+        let listing = Arc::new(WorkspaceListing::load(&self.workspace));
+        *cache = Some(listing.clone());
+        listing
+    }
+}
+
+/// How well a path matches what was typed; lower is better
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum MatchKind {
+    /// The path starts with it
+    Prefix,
+    /// A path segment (a directory or the file name) starts with it
+    Segment,
+    /// Its characters appear in the path in order
+    Subsequence,
+}
+
+fn match_kind(path: &str, query: &str) -> Option<MatchKind> {
+    let path = path.to_lowercase();
+    if path.starts_with(query) {
+        return Some(MatchKind::Prefix);
+    }
+    if path.split('/').skip(1).any(|segment| segment.starts_with(query)) {
+        return Some(MatchKind::Segment);
+    }
+    let mut rest = path.chars();
+    query
+        .chars()
+        .all(|wanted| rest.any(|c| c == wanted))
+        .then_some(MatchKind::Subsequence)
+}
+
+/// The best `limit` of `files` ((relative path, mtime) pairs) for `query`,
+/// matched case-insensitively
+pub fn rank_paths<'a>(files: impl IntoIterator<Item = (&'a str, u64)>, query: &str, limit: usize) -> Vec<String> {
+    let query = query.trim_start_matches("./").to_lowercase();
+    let mut matches: Vec<_> = files
+        .into_iter()
+        .filter_map(|(path, mtime)| {
+            let kind = match_kind(path, &query)?;
+            Some((kind, path.matches('/').count(), std::cmp::Reverse(mtime), path))
+        })
+        .collect();
+    if query.is_empty() {
+        // Nothing typed yet: what was worked on last, wherever it is
+        matches.sort_by(|a, b| (a.2, a.3).cmp(&(b.2, b.3)));
+    } else {
+        matches.sort();
+    }
+    matches.into_iter().take(limit).map(|(_, _, _, path)| path.to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FILES: &[(&str, u64)] = &[
+        ("README.md", 10),
+        ("src/main.rs", 20),
+        ("src/map.rs", 30),
+        ("src/tools/manager.rs", 40),
+        ("tests/smoke/main_test.rs", 50),
+        ("docs/schema.md", 60),
+    ];
+
+    fn rank(query: &str, limit: usize) -> Vec<String> {
+        rank_paths(FILES.iter().copied(), query, limit)
+    }
+
+    #[test]
+    fn test_prefix_matches_rank_first_then_by_recency() {
+        assert_eq!(rank("src/ma", 10), vec!["src/map.rs", "src/main.rs", "src/tools/manager.rs"]);
+    }
+
+    #[test]
+    fn test_segment_matches_rank_shallow_first() {
+        assert_eq!(rank("ma", 10), vec!["src/map.rs", "src/main.rs", "tests/smoke/main_test.rs", "src/tools/manager.rs", "docs/schema.md"]);
+    }
+
+    #[test]
+    fn test_subsequence_matches_and_case() {
+        assert_eq!(rank("SMR", 10), vec!["src/map.rs", "src/main.rs", "tests/smoke/main_test.rs", "src/tools/manager.rs"]);
+        assert_eq!(rank("readme", 10), vec!["README.md"]);
+        assert!(rank("zzz", 10).is_empty());
+    }
+
+    #[test]
+    fn test_empty_query_lists_recent_files() {
+        assert_eq!(rank("", 2), vec!["docs/schema.md", "tests/smoke/main_test.rs"]);
+    }
+
+    #[test]
+    fn test_completer_sees_new_files() {
+        let workspace = tempfile::tempdir().unwrap();
+        std::fs::create_dir(workspace.path().join("src")).unwrap();
+        std::fs::write(workspace.path().join("src/lib.rs"), "").unwrap();
+        std::fs::write(workspace.path().join(".gitignore"), "*.log\n").unwrap();
+        std::fs::write(workspace.path().join("src/debug.log"), "").unwrap();
+
+        let completer = PathCompleter::new(workspace.path());
+        assert_eq!(completer.complete_paths("src/", 10), vec!["src/lib.rs"]);
+        std::fs::write(workspace.path().join("src/main.rs"), "").unwrap();
+        assert_eq!(completer.complete_paths("src/m", 10), vec!["src/main.rs"]);
+    }
+}
@@ -3,14 +3,23 @@
 //! Handles loading, saving, and managing application configuration
 //! including API keys and provider settings.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use cowork_sandbox::policy::{PolicyBuilder, SecurityLevel};
+use cowork_sandbox::{EnvPolicy, FilesystemPolicy, NetworkPolicy, ResourceLimits, SandboxConfig};
 use serde::{Deserialize, Serialize};
 
+use crate::context::{CompactionMode, DEFAULT_KEEP_TURNS};
+use crate::i18n::Locale;
+use crate::policy::Policy;
 use crate::prompt::ComponentPaths;
 use crate::provider::catalog;
+use crate::session::Isolation;
 
 use crate::error::{Error, Result};
+use crate::orchestration::tool_profile::{ToolProfile, FULL_PROFILE, PRESETS};
+use crate::tools::filesystem::{SyntaxVerifier, DEFAULT_VERIFY_TIMEOUT};
 
 /// Default constants used throughout the application
 pub mod defaults {
@@ -38,6 +47,9 @@ pub mod defaults {
     /// Session directory name (relative to workspace)
     pub const SESSION_DIR_NAME: &str = ".cowork";
 
+    /// Project settings file name (inside `SESSION_DIR_NAME`)
+    pub const PROJECT_SETTINGS_FILE: &str = "settings.json";
+
     /// Maximum context size in characters before truncation
     pub const MAX_CONTEXT_SIZE: usize = 100_000;
 
@@ -80,6 +92,9 @@ pub struct McpServerConfig {
     /// Whether this server is enabled (auto-starts on CLI startup)
     #[serde(default = "default_true")]
     pub enabled: bool,
+    /// Run the server (stdio transport) confined by the sandbox
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sandbox: Option<McpSandboxConfig>,
 }
 
 fn default_true() -> bool {
@@ -97,6 +112,7 @@ impl McpServerConfig {
             env: HashMap::new(),
             headers: HashMap::new(),
             enabled: true,
+            sandbox: None,
         }
     }
 
@@ -110,6 +126,7 @@ impl McpServerConfig {
             env: HashMap::new(),
             headers: HashMap::new(),
             enabled: true,
+            sandbox: None,
         }
     }
 
@@ -141,6 +158,85 @@ impl McpServerConfig {
         self.enabled = enabled;
         self
     }
+
+    /// Run the server in the sandbox
+    pub fn with_sandbox(mut self, sandbox: McpSandboxConfig) -> Self {
+        self.sandbox = Some(sandbox);
+        self
+    }
+}
+
+/// Sandbox for a stdio MCP server (`[mcp_servers.<name>.sandbox]`)
+///
+/// Policies start from the `level` preset and are replaced by the sections
+/// given here. The server gets the sandbox's environment allowlist plus its
+/// own `env`, and `root` as working directory and home.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpSandboxConfig {
+    /// Refuse to start the server when it can't be sandboxed
+    /// (false = start it unsandboxed, with a warning)
+    #[serde(default = "default_true")]
+    pub required: bool,
+    /// Preset the policies start from
+    #[serde(default = "default_mcp_sandbox_level")]
+    pub level: SecurityLevel,
+    /// Network policy (overrides the preset's)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network: Option<NetworkPolicy>,
+    /// Filesystem read/write/exec paths (overrides the preset's)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filesystem: Option<FilesystemPolicy>,
+    /// Resource limits. The presets' limits are meant for one-off commands,
+    /// so a long-running server gets none unless they are set here.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limits: Option<ResourceLimits>,
+    /// Working directory and home (default: `<temp>/cowork-mcp/<name>`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root: Option<PathBuf>,
+}
+
+fn default_mcp_sandbox_level() -> SecurityLevel {
+    SecurityLevel::Standard
+}
+
+impl Default for McpSandboxConfig {
+    fn default() -> Self {
+        Self {
+            required: true,
+            level: default_mcp_sandbox_level(),
+            network: None,
+            filesystem: None,
+            limits: None,
+            root: None,
+        }
+    }
+}
+
+impl McpSandboxConfig {
+    /// The sandbox config for server `name` with its `env`
+    pub fn to_sandbox_config(&self, name: &str, env: &HashMap<String, String>) -> SandboxConfig {
+        let root = self
+            .root
+            .clone()
+            .unwrap_or_else(|| std::env::temp_dir().join("cowork-mcp").join(name));
+        let env = env.iter().fold(EnvPolicy::default(), |policy, (k, v)| policy.with_var(k, v));
+        let limits = self.limits.clone().unwrap_or(ResourceLimits {
+            max_memory: 0,
+            max_cpu_time: 0,
+            max_processes: 0,
+            max_fds: 0,
+            max_file_size: 0,
+        });
+
+        let mut builder = PolicyBuilder::new(root).base_level(self.level).limits(limits).env(env);
+        if let Some(network) = &self.network {
+            builder = builder.network(network.clone());
+        }
+        if let Some(filesystem) = &self.filesystem {
+            builder = builder.filesystem(filesystem.clone());
+        }
+        builder.build()
+    }
 }
 
 /// Main application configuration
@@ -170,6 +266,45 @@ pub struct Config {
     /// Prompt system settings
     #[serde(default)]
     pub prompt: PromptSystemConfig,
+    /// Request and session limits shared by all sessions
+    #[serde(default)]
+    pub limits: LimitsConfig,
+    /// Notifications for long turns, approvals, and questions
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
+    /// Environment for commands run by the agent
+    #[serde(default)]
+    pub env: EnvConfig,
+    /// Remote workspace reached over SSH (None = the local workspace)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote: Option<RemoteTarget>,
+    /// Proxy, CA bundle and timeout for outbound HTTP
+    #[serde(default)]
+    pub network: NetworkConfig,
+    /// Hosts WebFetch and WebSearch may reach
+    #[serde(default)]
+    pub web: WebConfig,
+    /// Slash-command aliases and key bindings for the terminal UI
+    #[serde(default)]
+    pub cli: CliConfig,
+    /// How many saved sessions to keep, and for how long
+    #[serde(default)]
+    pub sessions: SessionsConfig,
+    /// Tool profile sessions start with, and custom profiles
+    #[serde(default)]
+    pub tools: ToolsConfig,
+    /// Syntax check of files after Edit and Write
+    #[serde(default)]
+    pub verify: VerifyConfig,
+    /// Scheduled runs (see `schedule`)
+    #[serde(default)]
+    pub schedule: ScheduleConfig,
+    /// Opt-in latency and reliability metrics (see `telemetry`)
+    #[serde(default)]
+    pub telemetry: TelemetryConfig,
+    /// How the history is compacted when the context fills up
+    #[serde(default)]
+    pub summarizer: SummarizerConfig,
 }
 
 fn default_provider_name() -> String {
@@ -195,6 +330,19 @@ impl Default for Config {
             general: GeneralConfig::default(),
             web_search: WebSearchConfig::default(),
             prompt: PromptSystemConfig::default(),
+            limits: LimitsConfig::default(),
+            notifications: NotificationsConfig::default(),
+            env: EnvConfig::default(),
+            remote: None,
+            network: NetworkConfig::default(),
+            web: WebConfig::default(),
+            cli: CliConfig::default(),
+            sessions: SessionsConfig::default(),
+            tools: ToolsConfig::default(),
+            verify: VerifyConfig::default(),
+            schedule: ScheduleConfig::default(),
+            telemetry: TelemetryConfig::default(),
+            summarizer: SummarizerConfig::default(),
         }
     }
 }
@@ -215,6 +363,16 @@ impl Config {
         self.providers.get(name)
     }
 
+    /// The provider `[summarizer] provider` names, if it is configured
+    pub fn summarizer_provider(&self) -> Option<ProviderConfig> {
+        let name = self.summarizer.provider.as_deref()?;
+        let provider = self.get_provider(name).cloned();
+        if provider.is_none() {
+            tracing::warn!("Summarizer provider '{}' is not configured; summarizing with the session's", name);
+        }
+        provider
+    }
+
     /// Get mutable provider config
     pub fn get_provider_mut(&mut self, name: &str) -> Option<&mut ProviderConfig> {
         self.providers.get_mut(name)
@@ -270,6 +428,23 @@ impl ModelTiers {
     }
 }
 
+/// Where a provider's API key is kept
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiKeyStorage {
+    /// In `api_key`, in plain text
+    #[default]
+    Config,
+    /// In the OS keychain (see `crate::credentials`)
+    Keyring,
+}
+
+impl ApiKeyStorage {
+    fn is_config(&self) -> bool {
+        *self == Self::Config
+    }
+}
+
 /// LLM Provider configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
@@ -279,6 +454,10 @@ pub struct ProviderConfig {
     /// API key
     #[serde(skip_serializing_if = "Option::is_none")]
     pub api_key: Option<String>,
+    /// Where the API key is kept; with `keyring` it's read from the OS
+    /// keychain, falling back to the environment variable
+    #[serde(skip_serializing_if = "ApiKeyStorage::is_config")]
+    pub api_key_storage: ApiKeyStorage,
     /// Model to use (default/primary model)
     pub model: String,
     /// Model tiers for subagent execution (fast/balanced/powerful)
@@ -288,6 +467,12 @@ pub struct ProviderConfig {
     /// Use this for API proxies or custom endpoints
     #[serde(skip_serializing_if = "Option::is_none")]
     pub base_url: Option<String>,
+    /// Seconds a request may take before it's abandoned (default: 120)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_timeout_secs: Option<u64>,
+    /// Seconds a streamed request may take (default: 300)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_timeout_secs: Option<u64>,
 }
 
 impl Default for ProviderConfig {
@@ -302,12 +487,25 @@ impl ProviderConfig {
         Self {
             provider_type: provider_id.to_string(),
             api_key: None,
+            api_key_storage: ApiKeyStorage::Config,
             model: catalog::default_model(provider_id).unwrap_or("").to_string(),
             model_tiers: None,
             base_url: None,
+            request_timeout_secs: None,
+            stream_timeout_secs: None,
         }
     }
 
+    /// How long a request to this provider may take, streamed or not
+    pub fn request_timeout(&self, streaming: bool) -> std::time::Duration {
+        let (configured, default) = if streaming {
+            (self.stream_timeout_secs, crate::provider::DEFAULT_STREAM_TIMEOUT)
+        } else {
+            (self.request_timeout_secs, crate::provider::DEFAULT_REQUEST_TIMEOUT)
+        };
+        configured.map(std::time::Duration::from_secs).unwrap_or(default)
+    }
+
     /// Get model tiers, falling back to provider defaults
     pub fn get_model_tiers(&self) -> ModelTiers {
         self.model_tiers
@@ -315,7 +513,8 @@ impl ProviderConfig {
             .unwrap_or_else(|| ModelTiers::for_provider(&self.provider_type))
     }
 
-    /// Get the API key, checking catalog's env var if not set directly
+    /// Get the API key, checking the keychain (with `api_key_storage =
+    /// "keyring"`) and then catalog's env var if not set directly
     pub fn get_api_key(&self) -> Option<String> {
         // First check direct API key
         if let Some(key) = &self.api_key
@@ -323,6 +522,11 @@ impl ProviderConfig {
                 return Some(key.clone());
             }
 
+        if self.api_key_storage == ApiKeyStorage::Keyring
+            && let Some(key) = crate::credentials::keyring_api_key(&self.provider_type) {
+                return Some(key);
+            }
+
         // Fall back to catalog's default env var for this provider
         if let Some(env_name) = catalog::api_key_env(&self.provider_type)
             && let Ok(key) = std::env::var(env_name)
@@ -343,6 +547,43 @@ pub struct ApprovalConfig {
     pub show_dialogs: bool,
     /// Timeout for approval requests (seconds)
     pub timeout_secs: u64,
+    /// Calls only a reviewer may approve, as `--deny-tools` rules (`Bash:kubectl*`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub delegate: Vec<String>,
+    /// Where reviewers watch for delegated approvals
+    #[serde(default = "default_delegate_channel")]
+    pub delegate_channel: String,
+    /// Seconds a delegated approval waits for a reviewer before it's refused
+    #[serde(default = "default_delegate_timeout_secs")]
+    pub delegate_timeout_secs: u64,
+    /// Hosts shell commands may send secrets to without being flagged as
+    /// possible exfiltration (`ci.corp.example`, `*.internal.example`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trusted_hosts: Vec<String>,
+}
+
+fn default_delegate_channel() -> String {
+    crate::approval::DEFAULT_DELEGATE_CHANNEL.to_string()
+}
+
+fn default_delegate_timeout_secs() -> u64 {
+    crate::approval::DEFAULT_DELEGATE_TIMEOUT.as_secs()
+}
+
+impl ApprovalConfig {
+    /// The delegation rules, skipping (with a warning) ones that don't parse
+    pub fn delegate_rules(&self) -> Vec<crate::approval::ToolRule> {
+        self.delegate
+            .iter()
+            .filter_map(|rule| match rule.parse() {
+                Ok(rule) => Some(rule),
+                Err(e) => {
+                    tracing::warn!("Ignoring approval.delegate entry: {}", e);
+                    None
+                }
+            })
+            .collect()
+    }
 }
 
 impl Default for ApprovalConfig {
@@ -351,6 +592,10 @@ impl Default for ApprovalConfig {
             auto_approve_level: "low".to_string(),
             show_dialogs: true,
             timeout_secs: 300,
+            delegate: Vec::new(),
+            delegate_channel: default_delegate_channel(),
+            delegate_timeout_secs: default_delegate_timeout_secs(),
+            trusted_hosts: Vec::new(),
         }
     }
 }
@@ -369,6 +614,87 @@ pub struct GeneralConfig {
     /// When false, responses are returned all at once (better token counting).
     #[serde(default)]
     pub stream_mode: bool,
+    /// Path of the opt-in LLM request log (JSONL)
+    /// The `COWORK_LLM_LOG` environment variable overrides this path.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub llm_log: Option<PathBuf>,
+    /// Rotate the LLM request log once it grows past this many bytes
+    #[serde(default = "default_llm_log_max_bytes")]
+    pub llm_log_max_bytes: u64,
+    /// Extra regex patterns redacted from the LLM request log
+    /// (for secrets that may appear in tool results)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub llm_log_redact: Vec<String>,
+    /// Watch files the agent reads or writes and tell it when they change
+    /// outside the session (e.g. edited in your editor) (default: false)
+    #[serde(default)]
+    pub watch_files: bool,
+    /// ripgrep binary used by the Grep tool instead of searching PATH
+    /// The `COWORK_RIPGREP` environment variable overrides this (`off` disables ripgrep).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ripgrep_path: Option<PathBuf>,
+    /// Check MCP tool arguments against the tool's schema before calling it
+    /// (turn off for servers whose schemas don't match what they accept)
+    #[serde(default = "default_true")]
+    pub validate_mcp_tool_args: bool,
+    /// Offer to save "always ..." / "never ..." / "remember ..." instructions
+    /// to CLAUDE.local.md when the user gives them (default: false)
+    #[serde(default)]
+    pub auto_memory: bool,
+    /// After a turn that changed files or ran commands, have the fast model
+    /// check the work against the request and report gaps (default: false)
+    #[serde(default)]
+    pub self_review: bool,
+    /// Self-review findings a turn may have before it ends with a warning
+    /// (default: 0, any finding warns)
+    #[serde(default)]
+    pub self_review_threshold: usize,
+    /// Adapt tool schemas to providers with a stricter dialect (OpenAI strict
+    /// mode, Gemini); turn off if a provider starts rejecting the adapted schemas
+    #[serde(default = "default_true")]
+    pub strict_tools: bool,
+    /// Keep a saved session's scratch directory after the session ends,
+    /// instead of removing it with the session's other temporary files (default: false)
+    #[serde(default)]
+    pub keep_scratch: bool,
+    /// Seconds between autosaves of a running session, which is also
+    /// autosaved after every turn, so a crash loses little (0 = only save
+    /// when the session ends)
+    #[serde(default = "default_autosave_secs")]
+    pub autosave_secs: u64,
+    /// Where sessions work: "none" (the workspace itself) or "worktree" (a
+    /// git worktree on a new branch, merged back with /merge) (default: none)
+    #[serde(default)]
+    pub isolation: Isolation,
+    /// Language of the text shown to people, e.g. "ja" (see `i18n`)
+    /// (default: the system locale; English where it isn't supported)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    /// Kill language servers, MCP servers and background shells a crashed
+    /// earlier run left behind when starting, instead of only reporting
+    /// them (default: false; `cowork doctor --kill-orphans` kills them too)
+    #[serde(default)]
+    pub kill_orphaned_processes: bool,
+    /// Command that opens a file in your editor, with `{file}`, `{line}` and
+    /// `{column}` placeholders, e.g. `code --goto {file}:{line}` (default:
+    /// VS Code, Cursor or a JetBrains IDE found on PATH, else the system's opener)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub editor: Option<String>,
+}
+
+fn default_autosave_secs() -> u64 {
+    60
+}
+
+fn default_llm_log_max_bytes() -> u64 {
+    crate::provider::DEFAULT_LLM_LOG_MAX_BYTES
+}
+
+impl GeneralConfig {
+    /// The locale text is shown in
+    pub fn locale(&self) -> Locale {
+        Locale::resolve(self.locale.as_deref())
+    }
 }
 
 impl Default for GeneralConfig {
@@ -378,10 +704,576 @@ impl Default for GeneralConfig {
             log_level: "info".to_string(),
             telemetry: false,
             stream_mode: false,
+            llm_log: None,
+            llm_log_max_bytes: default_llm_log_max_bytes(),
+            llm_log_redact: Vec::new(),
+            watch_files: false,
+            ripgrep_path: None,
+            validate_mcp_tool_args: true,
+            auto_memory: false,
+            self_review: false,
+            self_review_threshold: crate::session::DEFAULT_SELF_REVIEW_THRESHOLD,
+            strict_tools: true,
+            keep_scratch: false,
+            autosave_secs: default_autosave_secs(),
+            isolation: Isolation::None,
+            locale: None,
+            kill_orphaned_processes: false,
+            editor: None,
         }
     }
 }
 
+/// Limits shared across all sessions, so many sessions can't trip
+/// account-level provider rate limits
+///
+/// A value of 0 disables that limit.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LimitsConfig {
+    /// Maximum LLM requests in flight at once, across all sessions
+    pub max_concurrent_requests: usize,
+    /// Maximum LLM requests started per minute, per provider
+    pub requests_per_minute: usize,
+    /// Maximum number of sessions open at once
+    pub max_sessions: usize,
+    /// Answer an identical read-only tool call from its previous result once
+    /// it has been made this many times in a turn
+    pub repeated_tool_calls: usize,
+    /// Stop the turn once an identical read-only tool call has been made
+    /// this many times
+    pub repeated_tool_calls_abort: usize,
+    /// Maximum tool calls running at once, per session
+    pub max_concurrent_tools: usize,
+    /// Suspend a desktop session after this many minutes without input or
+    /// output (0 = never)
+    pub idle_suspend_mins: u64,
+    /// Outputs kept per session for a reloaded frontend to catch up on
+    /// (see `session::transcript`; 0 = none)
+    pub transcript_events: usize,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent_requests: 8,
+            requests_per_minute: 120,
+            max_sessions: 32,
+            repeated_tool_calls: 3,
+            repeated_tool_calls_abort: 6,
+            max_concurrent_tools: 8,
+            idle_suspend_mins: 30,
+            transcript_events: crate::session::DEFAULT_TRANSCRIPT_EVENTS,
+        }
+    }
+}
+
+/// Outbound HTTP settings (see `crate::http` for how they combine with the
+/// standard proxy environment variables)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkConfig {
+    /// Proxy for all HTTP and HTTPS requests, e.g. `http://proxy.corp:8080`
+    /// (overrides HTTPS_PROXY / HTTP_PROXY / ALL_PROXY)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    /// PEM file of extra CA certificates to trust, e.g. a proxy's MITM CA
+    /// (overrides SSL_CERT_FILE)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ca_bundle: Option<PathBuf>,
+    /// Request timeout, in seconds, for web tools, MCP servers and update
+    /// checks (LLM requests use their own, longer timeout)
+    pub timeout_secs: u64,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            proxy: None,
+            ca_bundle: None,
+            timeout_secs: 30,
+        }
+    }
+}
+
+/// Hosts the web tools may reach
+///
+/// Patterns match a host and its subdomains, or use `*` wildcards
+/// (`*.corp.example`). Loopback, private and link-local addresses are
+/// refused unless their host is listed in `allowed_hosts`; a bare `*` there
+/// allows every public host without opening those up.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebConfig {
+    /// Only these hosts may be fetched (empty = any public host)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub allowed_hosts: Vec<String>,
+    /// Hosts and addresses never fetched
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub blocked_hosts: Vec<String>,
+}
+
+impl WebConfig {
+    /// The policy WebFetch and WebSearch enforce
+    pub fn network_policy(&self) -> NetworkPolicy {
+        let mut policy = NetworkPolicy::allow_all();
+        policy.allowed_hosts = self.allowed_hosts.iter().map(|h| h.trim().to_ascii_lowercase()).collect();
+        policy.block_hosts(&self.blocked_hosts);
+        policy
+    }
+}
+
+/// Terminal UI settings
+///
+/// Both maps are validated when the CLI starts; invalid entries are
+/// reported and ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CliConfig {
+    /// Slash-command aliases, alias to command with optional default
+    /// arguments: `"/c" = "/commit"`, `"/t" = "/test --all"`
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub aliases: BTreeMap<String, String>,
+    /// Key for a TUI action (`approve`, `reject`, `explain`, `cancel`,
+    /// `toggle_todos`, `search`), e.g. `cancel = "ctrl+g"`
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub keybindings: BTreeMap<String, String>,
+    /// Edit the input with vim keys; `/vim` toggles it and saves the choice
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub vim_mode: bool,
+}
+
+/// Retention for saved sessions, applied when the CLI or app starts and by
+/// `cowork sessions prune`
+///
+/// Sessions past `max_age_days` are deleted, then the least valuable of the
+/// rest (fewest messages, then least recently updated) until `max_count`
+/// remain. Pinned sessions are never pruned and don't count towards
+/// `max_count`. A value of 0 disables that limit.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionsConfig {
+    /// Maximum number of unpinned saved sessions
+    pub max_count: usize,
+    /// Delete unpinned sessions not updated for this many days
+    pub max_age_days: u64,
+}
+
+impl SessionsConfig {
+    /// Whether any limit is set
+    pub fn is_enabled(&self) -> bool {
+        self.max_count > 0 || self.max_age_days > 0
+    }
+}
+
+/// Scheduled runs (the jobs themselves are in `schedules.toml`)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScheduleConfig {
+    /// IANA time zone cron expressions are read in, e.g. `Europe/Berlin`
+    /// (None or `local` = the system's)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+}
+
+/// Which tools sessions offer the model (see `orchestration::tool_profile`)
+///
+/// `profile` names a preset (`minimal`, `coding`, `research`, `full`) or a
+/// profile in `profiles`, which takes precedence over a preset of the same
+/// name.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolsConfig {
+    /// Profile new sessions start with
+    pub profile: String,
+    /// Custom profiles by name
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, CustomToolProfile>,
+    /// Describe built-in tools in a few sentences each instead of in full
+    /// (overrides in `.cowork/tool-descriptions` apply either way)
+    pub compact_descriptions: bool,
+}
+
+impl Default for ToolsConfig {
+    fn default() -> Self {
+        Self {
+            profile: FULL_PROFILE.to_string(),
+            profiles: BTreeMap::new(),
+            compact_descriptions: false,
+        }
+    }
+}
+
+/// Tool-name patterns of a custom profile: `include = ["Read", "mcp__github__*"]`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CustomToolProfile {
+    /// Tools offered (empty = every tool)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    /// Tools left out, even if included
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+}
+
+impl ToolsConfig {
+    /// The profile called `name`: a custom one, else a preset
+    pub fn resolve(&self, name: &str) -> Result<ToolProfile> {
+        if let Some(custom) = self.profiles.get(name) {
+            return Ok(ToolProfile::new(name, custom.include.clone(), custom.exclude.clone()));
+        }
+        ToolProfile::preset(name).ok_or_else(|| {
+            Error::Config(format!(
+                "Unknown tool profile '{}' (available: {})",
+                name,
+                self.profile_names().join(", ")
+            ))
+        })
+    }
+
+    /// The profile new sessions start with (`full` if `profile` is unknown)
+    pub fn default_profile(&self) -> ToolProfile {
+        self.resolve(&self.profile).unwrap_or_else(|e| {
+            tracing::warn!("{}; offering every tool", e);
+            ToolProfile::full()
+        })
+    }
+
+    /// Names of the presets, then of the custom profiles not replacing one
+    pub fn profile_names(&self) -> Vec<String> {
+        let presets = PRESETS.iter().map(|name| name.to_string());
+        let custom = self.profiles.keys().filter(|name| !PRESETS.contains(&name.as_str())).cloned();
+        presets.chain(custom).collect()
+    }
+}
+
+/// Syntax check of files after Edit and Write (see `tools::filesystem::SyntaxVerifier`)
+///
+/// Off unless `enabled`. Problems are added to the tool result so the model
+/// fixes them in the same turn; a checker that isn't installed only leaves a
+/// note.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VerifyConfig {
+    /// Check files after writing them
+    pub enabled: bool,
+    /// Languages (`rust`, `python`, `javascript`, `json`) or file extensions not checked
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub disabled: Vec<String>,
+    /// Commands by language or file extension, replacing the built-in check:
+    /// `go = "gofmt -e {file}"`
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub commands: BTreeMap<String, String>,
+    /// Time a check may take
+    pub timeout_secs: u64,
+}
+
+impl Default for VerifyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            disabled: Vec::new(),
+            commands: BTreeMap::new(),
+            timeout_secs: DEFAULT_VERIFY_TIMEOUT.as_secs(),
+        }
+    }
+}
+
+/// Metrics on LLM latency, tool failures and approval waits
+///
+/// Off unless `enabled`. Metrics carry no conversation content or file
+/// paths, only names such as the provider, model and tool.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TelemetryConfig {
+    /// Record metrics
+    pub enabled: bool,
+    /// Prometheus textfile the metrics are written to (default
+    /// `<data dir>/cowork/metrics.prom`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub textfile: Option<PathBuf>,
+    /// Address to serve the metrics on over HTTP, e.g. `127.0.0.1:9464`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub listen: Option<String>,
+}
+
+/// Context compaction settings (see `context::summarizer`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SummarizerConfig {
+    /// Summarize with a model, or condense without one
+    pub mode: CompactionMode,
+    /// Configured provider (a `[providers]` name) to summarize with (default:
+    /// the session's)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    /// Model to summarize with (default: the provider's fast tier)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Summarization prompt template replacing the built-in one, relative to
+    /// the workspace; `{transcript}` and `{focus}` in it are filled in
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt_file: Option<PathBuf>,
+    /// Turns kept verbatim at each end of the history in heuristic mode
+    pub keep_turns: usize,
+}
+
+impl Default for SummarizerConfig {
+    fn default() -> Self {
+        Self {
+            mode: CompactionMode::default(),
+            provider: None,
+            model: None,
+            prompt_file: None,
+            keep_turns: DEFAULT_KEEP_TURNS,
+        }
+    }
+}
+
+impl TelemetryConfig {
+    /// The textfile metrics are written to
+    pub fn textfile_path(&self) -> PathBuf {
+        self.textfile.clone().unwrap_or_else(|| {
+            dirs::data_dir()
+                .map(|p| p.join("cowork"))
+                .unwrap_or_else(|| PathBuf::from(".cowork"))
+                .join("metrics.prom")
+        })
+    }
+}
+
+impl VerifyConfig {
+    /// The verifier Edit and Write use, or None if checks are off
+    pub fn verifier(&self) -> Option<SyntaxVerifier> {
+        if !self.enabled {
+            return None;
+        }
+        let verifier = self
+            .commands
+            .iter()
+            .fold(SyntaxVerifier::new(), |verifier, (language, command)| verifier.with_command(language, command))
+            .with_disabled(self.disabled.clone())
+            .with_timeout(std::time::Duration::from_secs(self.timeout_secs.max(1)));
+        Some(verifier)
+    }
+}
+
+/// When to alert the user that a session needs attention
+///
+/// The desktop app shows OS notifications; the CLI rings the terminal bell
+/// and, if `desktop` is set, shells out to `notify-send` / `osascript`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationsConfig {
+    /// Notify when a turn finishes after running at least `min_turn_secs`
+    pub turn_completed: bool,
+    /// Minimum turn length, in seconds, worth a completion notification
+    pub min_turn_secs: u64,
+    /// Notify when a tool is waiting for approval
+    pub approval_required: bool,
+    /// Notify when the assistant asks a question
+    pub question_asked: bool,
+    /// CLI: ring the terminal bell
+    pub terminal_bell: bool,
+    /// CLI: also show a desktop notification via `notify-send` / `osascript`
+    pub desktop: bool,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            turn_completed: true,
+            min_turn_secs: 30,
+            approval_required: true,
+            question_asked: true,
+            terminal_bell: true,
+            desktop: false,
+        }
+    }
+}
+
+/// Environment for commands run by the agent (Bash tool and sandbox)
+///
+/// Only allowlisted parent variables reach the child; `vars` are set on
+/// top. Values of variables whose names match `secret_patterns` are replaced
+/// with `***` in command output. A project can override this in the `env`
+/// section of `.cowork/settings.json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EnvConfig {
+    /// Variables set for every command (e.g. `DATABASE_URL`)
+    pub vars: BTreeMap<String, String>,
+    /// Parent variables passed through; `*` globs allowed (e.g. `LC_*`)
+    #[serde(alias = "env_allowlist")]
+    pub allowlist: Vec<String>,
+    /// Variable name globs whose values are masked in output
+    pub secret_patterns: Vec<String>,
+}
+
+impl Default for EnvConfig {
+    fn default() -> Self {
+        let policy = EnvPolicy::default();
+        Self {
+            vars: policy.vars,
+            allowlist: policy.allowlist,
+            secret_patterns: policy.secret_patterns,
+        }
+    }
+}
+
+impl EnvConfig {
+    /// Policy for commands run in `workspace`, applying the project's
+    /// `.cowork/settings.json` overrides
+    pub fn policy_for(&self, workspace: &Path) -> EnvPolicy {
+        let mut policy = EnvPolicy {
+            vars: self.vars.clone(),
+            allowlist: self.allowlist.clone(),
+            secret_patterns: self.secret_patterns.clone(),
+        };
+
+        match ProjectSettings::load(workspace) {
+            Ok(Some(ProjectSettings { env: Some(project), .. })) => {
+                policy.vars.extend(project.vars);
+                if let Some(allowlist) = project.allowlist {
+                    policy.allowlist = allowlist;
+                }
+                if let Some(patterns) = project.secret_patterns {
+                    policy.secret_patterns = patterns;
+                }
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Ignoring project settings: {}", e),
+        }
+        policy
+    }
+}
+
+/// Per-project settings from `<workspace>/.cowork/settings.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectSettings {
+    /// Overrides for the `[env]` config section
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env: Option<ProjectEnvConfig>,
+    /// Additions to the generated-file guard of Write, Edit and BatchEdit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generated_files: Option<GeneratedFilesConfig>,
+}
+
+/// Project additions to the built-in generated-file patterns
+/// (see `tools::filesystem::GeneratedFileGuard`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GeneratedFilesConfig {
+    /// More files to treat as generated; these are checked before the built-in
+    /// patterns, so their action wins
+    #[serde(default)]
+    pub patterns: Vec<GeneratedPattern>,
+    /// Files never treated as generated, whatever pattern or marker matches
+    #[serde(default)]
+    pub allow: Vec<String>,
+}
+
+/// A project's generated-file pattern: a glob, or a glob with what to do
+/// instead of editing the files it matches
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum GeneratedPattern {
+    Glob(String),
+    WithAction { pattern: String, action: String },
+}
+
+impl GeneratedPattern {
+    /// The glob, matched like BatchEdit's include (file name without a `/`)
+    pub fn pattern(&self) -> &str {
+        match self {
+            Self::Glob(pattern) | Self::WithAction { pattern, .. } => pattern,
+        }
+    }
+
+    /// What to do instead of editing a matching file, if the project says
+    pub fn action(&self) -> Option<&str> {
+        match self {
+            Self::Glob(_) => None,
+            Self::WithAction { action, .. } => Some(action),
+        }
+    }
+}
+
+/// Project overrides for `EnvConfig`; unset lists keep the global value
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectEnvConfig {
+    /// Variables added to (and overriding) the global `vars`
+    #[serde(default)]
+    pub vars: BTreeMap<String, String>,
+    /// Replaces the global allowlist
+    #[serde(default, alias = "env_allowlist")]
+    pub allowlist: Option<Vec<String>>,
+    /// Replaces the global secret patterns
+    #[serde(default)]
+    pub secret_patterns: Option<Vec<String>>,
+}
+
+impl ProjectSettings {
+    /// Path of the project settings file for a workspace
+    pub fn path(workspace: &Path) -> PathBuf {
+        workspace
+            .join(defaults::SESSION_DIR_NAME)
+            .join(defaults::PROJECT_SETTINGS_FILE)
+    }
+
+    /// Load the project settings, or `None` if the file doesn't exist
+    pub fn load(workspace: &Path) -> Result<Option<Self>> {
+        let path = Self::path(workspace);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(&path)?;
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|e| Error::Config(format!("Invalid {}: {}", path.display(), e)))
+    }
+}
+
+/// Remote workspace on another machine, reached over SSH
+///
+/// When set, the file and shell tools operate on `root` on `host` instead of
+/// the local workspace. Authentication must work without prompts (an agent
+/// or `identity_file`), since commands run non-interactively.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemoteTarget {
+    /// Host name or `~/.ssh/config` alias
+    pub host: String,
+    /// Login user (default: ssh's choice)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    /// SSH port (default: 22 or the `~/.ssh/config` setting)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    /// Private key to authenticate with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity_file: Option<PathBuf>,
+    /// Absolute path of the workspace on the remote host
+    pub root: String,
+    /// Extra `-o` options for ssh (e.g. `StrictHostKeyChecking=accept-new`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ssh_options: Vec<String>,
+}
+
+impl RemoteTarget {
+    /// `user@host`, or just `host` without a user
+    pub fn destination(&self) -> String {
+        match &self.user {
+            Some(user) => format!("{}@{}", user, self.host),
+            None => self.host.clone(),
+        }
+    }
+}
+
+impl std::fmt::Display for RemoteTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.destination(), self.root)
+    }
+}
+
 /// Prompt system configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PromptSystemConfig {
@@ -409,6 +1301,37 @@ pub struct PromptSystemConfig {
     /// Base system prompt (if not using default)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub base_system_prompt: Option<String>,
+    /// File appended after the system prompt, with template variables
+    /// substituted (relative paths are resolved against the workspace)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub append_file: Option<PathBuf>,
+    /// File replacing the built-in system prompt entirely
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub override_file: Option<PathBuf>,
+    /// Sections of the built-in prompt to strip (e.g. "notebook", "web")
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disable_sections: Vec<String>,
+    /// Extra template variables, substituted as `${CUSTOM:key}` in the
+    /// system prompt and agent prompts
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub variables: HashMap<String, String>,
+    /// Built-in preprocessing of user messages (`[prompt.input]`)
+    #[serde(default)]
+    pub input: InputProcessingConfig,
+}
+
+/// Built-in preprocessing of user messages (see `prompt::pipeline`)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputProcessingConfig {
+    /// Replace email addresses and `scrub_hosts` with placeholders before
+    /// messages reach the model; replies are shown with the originals restored
+    pub scrub_pii: bool,
+    /// Internal host names to scrub, or globs like `*.corp.example.com`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub scrub_hosts: Vec<String>,
+    /// Ask for replies in the language of messages not written in English
+    pub match_language: bool,
 }
 
 fn default_hook_timeout_ms() -> u64 {
@@ -426,6 +1349,11 @@ impl Default for PromptSystemConfig {
             hook_timeout_ms: default_hook_timeout_ms(),
             enable_skill_auto_invoke: true,
             base_system_prompt: None,
+            append_file: None,
+            override_file: None,
+            disable_sections: Vec::new(),
+            variables: HashMap::new(),
+            input: InputProcessingConfig::default(),
         }
     }
 }
@@ -518,9 +1446,14 @@ impl WebSearchConfig {
 }
 
 /// Configuration manager for loading and saving config
+///
+/// Also holds the organization policy (see `crate::policy`), loaded before
+/// the user's config; its constraints apply where settings are used, so
+/// they never end up written to the user's file.
 pub struct ConfigManager {
     config_path: PathBuf,
     config: Config,
+    policy: Arc<Policy>,
 }
 
 impl ConfigManager {
@@ -532,13 +1465,25 @@ impl ConfigManager {
 
     /// Create a config manager with a specific path
     pub fn with_path(config_path: PathBuf) -> Result<Self> {
+        let policy = Arc::new(Policy::load()?);
         let config = if config_path.exists() {
             Self::load_from_path(&config_path)?
         } else {
             Config::default()
         };
 
-        Ok(Self { config_path, config })
+        Ok(Self { config_path, config, policy })
+    }
+
+    /// Use `policy` instead of the one loaded from the policy file
+    pub fn with_policy(mut self, policy: Policy) -> Self {
+        self.policy = Arc::new(policy);
+        self
+    }
+
+    /// The organization policy constraining this configuration
+    pub fn policy(&self) -> Arc<Policy> {
+        self.policy.clone()
     }
 
     /// Get the default config path
@@ -749,11 +1694,66 @@ impl ConfigManager {
             return false;
         }
 
-        // Check if any provider has an explicit API key in config (not from env)
-        self.config
+        // Check if any provider has an explicit API key in config or the
+        // keychain (not from env)
+        self.config.providers.values().any(|p| {
+            p.api_key.as_ref().map(|k| !k.is_empty()).unwrap_or(false)
+                || p.api_key_storage == ApiKeyStorage::Keyring
+        })
+    }
+
+    /// Store a provider's API key in the OS keychain and stop keeping it in
+    /// the config; save the config afterwards
+    pub fn set_keyring_api_key(&mut self, provider_name: &str, key: &str) -> Result<()> {
+        let provider = self
+            .config
             .providers
-            .values()
-            .any(|p| p.api_key.as_ref().map(|k| !k.is_empty()).unwrap_or(false))
+            .entry(provider_name.to_string())
+            .or_insert_with(|| ProviderConfig::for_provider(provider_name));
+        crate::credentials::store_api_key(&provider.provider_type, key)?;
+        provider.api_key = None;
+        provider.api_key_storage = ApiKeyStorage::Keyring;
+        Ok(())
+    }
+
+    /// Delete a provider's API key from the OS keychain, returning whether
+    /// there was one; save the config afterwards
+    pub fn remove_keyring_api_key(&mut self, provider_name: &str) -> Result<bool> {
+        let Some(provider) = self.config.providers.get_mut(provider_name) else {
+            return crate::credentials::delete_api_key(provider_name);
+        };
+        let removed = crate::credentials::delete_api_key(&provider.provider_type)?;
+        provider.api_key_storage = ApiKeyStorage::Config;
+        Ok(removed)
+    }
+
+    /// Move a provider's plain-text API key into the OS keychain, blanking
+    /// it in the config; returns false if there was none to move. Save the
+    /// config afterwards.
+    pub fn migrate_api_key_to_keyring(&mut self, provider_name: &str) -> Result<bool> {
+        let key = self
+            .config
+            .providers
+            .get(provider_name)
+            .and_then(|p| p.api_key.clone())
+            .filter(|key| !key.is_empty());
+        match key {
+            Some(key) => self.set_keyring_api_key(provider_name, &key).map(|()| true),
+            None => Ok(false),
+        }
+    }
+
+    /// Providers whose API key is kept in plain text in the config
+    pub fn plaintext_api_key_providers(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .config
+            .providers
+            .iter()
+            .filter(|(_, p)| p.api_key.as_ref().is_some_and(|k| !k.is_empty()))
+            .map(|(name, _)| name.clone())
+            .collect();
+        names.sort();
+        names
     }
 
     /// List all configured providers
@@ -767,6 +1767,11 @@ impl Default for ConfigManager {
         Self::new().unwrap_or_else(|_| Self {
             config_path: PathBuf::from("config.toml"),
             config: Config::default(),
+            // A policy that exists but can't be read still has to restrict something
+            policy: Arc::new(Policy::load().unwrap_or_else(|e| {
+                tracing::error!("{}; requiring approval for every tool", e);
+                Policy::unreadable(&Policy::default_path())
+            })),
         })
     }
 }
@@ -11,7 +11,16 @@
 //! | 4        | User       | `~/.claude/CLAUDE.md`, `./CLAUDE.local.md`  |
 
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+/// Directories left out of the project tree (build output, dependencies)
+pub(crate) const TREE_SKIP: &[&str] = &["node_modules", "target", "dist", "build", "out", "vendor", "__pycache__", "venv"];
+
+/// Entries listed per directory in the project tree
+const TREE_MAX_ENTRIES: usize = 40;
+
+/// Longest README summary, in characters
+const README_SUMMARY_CHARS: usize = 2000;
 
 /// Memory tier priority levels (lower = higher priority)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
@@ -69,7 +78,8 @@ impl MemoryHierarchy {
 }
 
 /// Gathered project context
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ProjectContext {
     /// Content from CLAUDE.md if found (legacy single-file support)
     pub claude_md: Option<String>,
@@ -87,6 +97,72 @@ pub struct ProjectContext {
     pub main_language: Option<String>,
     /// Key files found (package.json, Cargo.toml, etc.)
     pub key_files: Vec<String>,
+    /// Opening of the README, without badges and images
+    pub readme_summary: Option<String>,
+    /// Workspace tree two levels deep (directories end in `/`)
+    pub tree: Vec<String>,
+    /// Best guess at the command that runs the tests
+    pub test_command: Option<String>,
+    /// What the workspace's .editorconfig and rustfmt.toml ask for
+    pub file_conventions: Option<String>,
+}
+
+impl ProjectContext {
+    /// Whether nothing about the project was found
+    pub fn is_empty(&self) -> bool {
+        self.project_type.is_none() && self.key_files.is_empty() && self.readme_summary.is_none() && self.tree.is_empty()
+    }
+
+    /// The overview given to the model on a session's first turn, within
+    /// roughly `budget_tokens` (the tree and then the README are cut first)
+    pub fn render(&self, budget_tokens: usize) -> String {
+        let max_chars = budget_tokens.saturating_mul(4);
+        let mut out = String::from("Project overview, gathered when this session started:\n");
+        match (&self.project_type, &self.main_language) {
+            (Some(kind), Some(language)) => out.push_str(&format!("- Project: {} ({})\n", kind, language)),
+            (Some(kind), None) => out.push_str(&format!("- Project: {}\n", kind)),
+            (None, Some(language)) => out.push_str(&format!("- Language: {}\n", language)),
+            (None, None) => {}
+        }
+        if let Some(command) = &self.test_command {
+            out.push_str(&format!("- Tests (guessed): `{}`\n", command));
+        }
+        if !self.key_files.is_empty() {
+            out.push_str(&format!("- Key files: {}\n", self.key_files.join(", ")));
+        }
+        if let Some(conventions) = &self.file_conventions {
+            out.push_str(&format!("- File conventions: {}\n", conventions));
+        }
+
+        if !self.tree.is_empty() {
+            out.push_str("\nLayout:\n");
+            for (i, line) in self.tree.iter().enumerate() {
+                // Leave room for the "more entries" line
+                if out.chars().count() + line.chars().count() + 32 > max_chars {
+                    out.push_str(&format!("... ({} more entries)\n", self.tree.len() - i));
+                    break;
+                }
+                out.push_str(line);
+                out.push('\n');
+            }
+        }
+
+        if let Some(readme) = &self.readme_summary {
+            let heading = "\nREADME (opening):\n";
+            let room = max_chars.saturating_sub(out.chars().count() + heading.len());
+            if room > 80 {
+                out.push_str(heading);
+                if readme.chars().count() > room {
+                    out.extend(readme.chars().take(room.saturating_sub(4)));
+                    out.push_str(" ...");
+                } else {
+                    out.push_str(readme);
+                }
+                out.push('\n');
+            }
+        }
+        out.trim_end().to_string()
+    }
 }
 
 /// Gathers project context from a workspace
@@ -119,17 +195,37 @@ impl ContextGatherer {
             context.recent_commits = git_info.commits;
         }
 
-        // Detect project type and language
-        let (project_type, language) = self.detect_project_type().await;
-        context.project_type = project_type;
-        context.main_language = language;
-
-        // Find key configuration files
-        context.key_files = self.find_key_files().await;
+        // Describe the project itself
+        let overview = self.project_overview();
+        context.project_type = overview.project_type;
+        context.main_language = overview.main_language;
+        context.key_files = overview.key_files;
+        context.readme_summary = overview.readme_summary;
+        context.tree = overview.tree;
+        context.test_command = overview.test_command;
+        context.file_conventions = overview.file_conventions;
 
         context
     }
 
+    /// Gather what describes the project itself: type, layout, README and
+    /// how to test it (blocking)
+    ///
+    /// Memory files and git state are left out; the system prompt has those.
+    pub fn project_overview(&self) -> ProjectContext {
+        let (project_type, main_language) = self.detect_project_type();
+        ProjectContext {
+            project_type,
+            main_language,
+            key_files: self.find_key_files(),
+            readme_summary: self.readme_summary(),
+            tree: self.tree(),
+            test_command: self.test_command(),
+            file_conventions: crate::tools::filesystem::describe_workspace_conventions(&self.workspace),
+            ..Default::default()
+        }
+    }
+
     /// Gather the 4-tier memory hierarchy
     ///
     /// Priority order (1 = highest):
@@ -138,6 +234,15 @@ impl ContextGatherer {
     /// 3. Rules: ./.claude/rules/*.md
     /// 4. User: ~/.claude/CLAUDE.md, ./CLAUDE.local.md
     pub async fn gather_memory_hierarchy(&self) -> MemoryHierarchy {
+        let gatherer = Self::new(self.workspace.clone());
+        tokio::task::spawn_blocking(move || gatherer.memory_hierarchy())
+            .await
+            .unwrap_or_default()
+    }
+
+    /// Blocking version of [`Self::gather_memory_hierarchy`], read when a session's
+    /// system prompt is built (so memory edits apply from the next session)
+    pub fn memory_hierarchy(&self) -> MemoryHierarchy {
         let mut files = Vec::new();
 
         // Tier 1: Enterprise (platform-specific paths)
@@ -161,7 +266,7 @@ impl ContextGatherer {
         }
 
         for path in &enterprise_paths {
-            if let Ok(content) = tokio::fs::read_to_string(path).await {
+            if let Ok(content) = std::fs::read_to_string(path) {
                 let size = content.len();
                 files.push(MemoryFile {
                     path: path.clone(),
@@ -180,7 +285,7 @@ impl ContextGatherer {
         ];
 
         for path in &project_paths {
-            if let Ok(content) = tokio::fs::read_to_string(path).await {
+            if let Ok(content) = std::fs::read_to_string(path) {
                 let size = content.len();
                 files.push(MemoryFile {
                     path: path.clone(),
@@ -199,10 +304,10 @@ impl ContextGatherer {
 
         for rules_dir in &rules_dirs {
             if rules_dir.exists()
-                && let Ok(mut entries) = tokio::fs::read_dir(rules_dir).await {
+                && let Ok(entries) = std::fs::read_dir(rules_dir) {
                     let mut rule_paths = Vec::new();
 
-                    while let Ok(Some(entry)) = entries.next_entry().await {
+                    for entry in entries.flatten() {
                         let path = entry.path();
                         if path.extension().map(|e| e == "md").unwrap_or(false) {
                             rule_paths.push(path);
@@ -213,7 +318,7 @@ impl ContextGatherer {
                     rule_paths.sort();
 
                     for path in rule_paths {
-                        if let Ok(content) = tokio::fs::read_to_string(&path).await {
+                        if let Ok(content) = std::fs::read_to_string(&path) {
                             let size = content.len();
                             files.push(MemoryFile {
                                 path,
@@ -239,7 +344,7 @@ impl ContextGatherer {
         }
 
         for path in &user_paths {
-            if let Ok(content) = tokio::fs::read_to_string(path).await {
+            if let Ok(content) = std::fs::read_to_string(path) {
                 let size = content.len();
                 files.push(MemoryFile {
                     path: path.clone(),
@@ -360,7 +465,7 @@ impl ContextGatherer {
     }
 
     /// Detect project type and main language
-    async fn detect_project_type(&self) -> (Option<String>, Option<String>) {
+    pub(crate) fn detect_project_type(&self) -> (Option<String>, Option<String>) {
         let mut project_type = None;
         let mut language = None;
 
@@ -395,14 +500,18 @@ impl ContextGatherer {
     }
 
     /// Find key configuration files
-    async fn find_key_files(&self) -> Vec<String> {
+    fn find_key_files(&self) -> Vec<String> {
         let key_files = [
             "Cargo.toml",
+            "rust-toolchain.toml",
             "package.json",
             "tsconfig.json",
             "pyproject.toml",
             "requirements.txt",
             "go.mod",
+            ".nvmrc",
+            ".python-version",
+            ".tool-versions",
             "Dockerfile",
             "docker-compose.yml",
             ".env.example",
@@ -422,6 +531,105 @@ impl ContextGatherer {
 
         found
     }
+
+    /// The README's opening, skipping badge and image lines
+    fn readme_summary(&self) -> Option<String> {
+        let content = ["README.md", "README", "README.rst", "README.txt", "readme.md"]
+            .iter()
+            .find_map(|name| std::fs::read_to_string(self.workspace.join(name)).ok())?;
+        let mut summary = String::new();
+        for line in content.lines() {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("[![") || trimmed.starts_with("![") || trimmed.starts_with('<') {
+                continue;
+            }
+            if summary.len() + line.len() > README_SUMMARY_CHARS {
+                break;
+            }
+            summary.push_str(line);
+            summary.push('\n');
+        }
+        let summary = summary.trim().to_string();
+        (!summary.is_empty()).then_some(summary)
+    }
+
+    /// The workspace tree two levels deep, without hidden entries and build output
+    fn tree(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        let top = tree_entries(&self.workspace);
+        for (name, is_dir) in top.iter().take(TREE_MAX_ENTRIES) {
+            if !is_dir {
+                lines.push(name.clone());
+                continue;
+            }
+            lines.push(format!("{}/", name));
+            let children = tree_entries(&self.workspace.join(name));
+            for (child, child_is_dir) in children.iter().take(TREE_MAX_ENTRIES) {
+                lines.push(format!("  {}{}", child, if *child_is_dir { "/" } else { "" }));
+            }
+            if children.len() > TREE_MAX_ENTRIES {
+                lines.push(format!("  ... {} more", children.len() - TREE_MAX_ENTRIES));
+            }
+        }
+        if top.len() > TREE_MAX_ENTRIES {
+            lines.push(format!("... {} more", top.len() - TREE_MAX_ENTRIES));
+        }
+        lines
+    }
+
+    /// Guess the test command from the build files present
+    pub(crate) fn test_command(&self) -> Option<String> {
+        let has = |file: &str| self.workspace.join(file).exists();
+        let command = if has("Cargo.toml") {
+            "cargo test"
+        } else if let Ok(package) = std::fs::read_to_string(self.workspace.join("package.json"))
+            && serde_json::from_str::<serde_json::Value>(&package)
+                .is_ok_and(|json| json["scripts"]["test"].is_string())
+        {
+            if has("pnpm-lock.yaml") {
+                "pnpm test"
+            } else if has("yarn.lock") {
+                "yarn test"
+            } else if has("bun.lockb") {
+                "bun test"
+            } else {
+                "npm test"
+            }
+        } else if has("go.mod") {
+            "go test ./..."
+        } else if has("pyproject.toml") || has("pytest.ini") || has("tox.ini") || has("setup.py") {
+            "pytest"
+        } else if has("pom.xml") {
+            "mvn test"
+        } else if has("build.gradle") || has("build.gradle.kts") {
+            if has("gradlew") { "./gradlew test" } else { "gradle test" }
+        } else if std::fs::read_to_string(self.workspace.join("Makefile"))
+            .is_ok_and(|makefile| makefile.lines().any(|l| l.starts_with("test:")))
+        {
+            "make test"
+        } else {
+            return None;
+        };
+        Some(command.to_string())
+    }
+}
+
+/// Visible entries of `dir` for the project tree: directories first, then by name
+pub(crate) fn tree_entries(dir: &Path) -> Vec<(String, bool)> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut entries: Vec<(String, bool)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+            let skip = name.starts_with('.') || (is_dir && TREE_SKIP.contains(&name.as_str()));
+            (!skip).then_some((name, is_dir))
+        })
+        .collect();
+    entries.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    entries
 }
 
 #[derive(Debug, Default)]
@@ -0,0 +1,624 @@
+//! Workspace index for cheap code lookups
+//!
+//! Exploring a large repository by globbing and grepping repeats the same
+//! walk on every question. The index records, for each file the
+//! workspace's `.gitignore` files don't exclude: its language, line count,
+//! size and modification time, and the names of the symbols it defines
+//! (found with a per-language regex pass over definition lines, e.g. `fn`,
+//! `struct`, `class`, `def`). The `CodebaseSearch` tool answers queries by
+//! symbol, path or language from it.
+//!
+//! The index is cached in `.cowork/cache/index.json`. [`WorkspaceIndex::refresh`]
+//! walks the workspace again but only re-reads files whose size or
+//! modification time changed, and drops entries for files that are gone.
+//! At most `MAX_INDEXED_FILES` files are indexed, and only files up to
+//! `MAX_SCANNED_FILE_BYTES` are read for lines and symbols.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use globset::{GlobBuilder, GlobMatcher};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::gather::TREE_SKIP;
+use super::overview::create_cache_dir;
+
+/// Index file, relative to the workspace
+pub const INDEX_FILE: &str = ".cowork/cache/index.json";
+
+/// Most files indexed; the rest of a giant workspace is left out
+pub const MAX_INDEXED_FILES: usize = 50_000;
+
+/// Larger files are listed but not read for lines and symbols
+pub const MAX_SCANNED_FILE_BYTES: u64 = 1024 * 1024;
+
+/// Most symbols kept per file
+const MAX_SYMBOLS_PER_FILE: usize = 500;
+
+/// Bumped when the entry format or symbol extraction changes, so old indexes are rebuilt
+const INDEX_VERSION: u32 = 1;
+
+/// A definition found in a file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Symbol {
+    pub name: String,
+    /// The defining keyword, e.g. `fn`, `class`, `def`
+    pub kind: String,
+    /// 1-based line number
+    pub line: usize,
+}
+
+/// What the index knows about one file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedFile {
+    pub language: Option<String>,
+    pub lines: usize,
+    pub size: u64,
+    /// Modification time in nanoseconds since the epoch
+    pub mtime_ns: u64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub symbols: Vec<Symbol>,
+}
+
+/// Files of a workspace, keyed by path relative to it (with `/` separators)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorkspaceIndex {
+    version: u32,
+    pub files: BTreeMap<String, IndexedFile>,
+    /// Whether files were left out because of `MAX_INDEXED_FILES`
+    #[serde(default)]
+    pub truncated: bool,
+}
+
+/// What a refresh changed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IndexStats {
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+    pub unchanged: usize,
+}
+
+impl IndexStats {
+    /// Whether the refresh changed anything
+    pub fn changed(&self) -> bool {
+        self.added + self.updated + self.removed > 0
+    }
+}
+
+/// A query against the index; every field given must match
+#[derive(Debug, Clone, Default)]
+pub struct IndexQuery {
+    /// Substring of a symbol name (case-insensitive)
+    pub symbol: Option<String>,
+    /// Substring of the file path (case-insensitive)
+    pub path: Option<String>,
+    /// Language name, e.g. `rust` (case-insensitive)
+    pub language: Option<String>,
+    /// Most hits returned
+    pub limit: usize,
+}
+
+/// One search result: a symbol, or a file when no symbol was asked for
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SearchHit {
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    pub lines: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<Symbol>,
+}
+
+impl WorkspaceIndex {
+    /// The index cached for `workspace`, if there is one in the current format
+    pub fn load(workspace: &Path) -> Option<Self> {
+        let content = std::fs::read_to_string(workspace.join(INDEX_FILE)).ok()?;
+        let index: Self = serde_json::from_str(&content).ok()?;
+        (index.version == INDEX_VERSION).then_some(index)
+    }
+
+    /// Write the index to the workspace's cache
+    pub fn save(&self, workspace: &Path) -> std::io::Result<()> {
+        let path = workspace.join(INDEX_FILE);
+        create_cache_dir(path.parent().unwrap_or(workspace))?;
+        std::fs::write(path, serde_json::to_string(self)?)
+    }
+
+    /// Load the cached index (or start an empty one), bring it up to date
+    /// and save it if anything changed (blocking)
+    pub fn update(workspace: &Path) -> (Self, IndexStats) {
+        let mut index = Self::load(workspace).unwrap_or_default();
+        let stats = index.refresh(workspace);
+        if stats.changed()
+            && let Err(e) = index.save(workspace)
+        {
+            tracing::debug!("Failed to save workspace index: {}", e);
+        }
+        (index, stats)
+    }
+
+    /// Re-read files that changed since the last refresh and drop those that are gone
+    pub fn refresh(&mut self, workspace: &Path) -> IndexStats {
+        let mut walk = Walk::default();
+        walk.dir(workspace, "", &mut Vec::new());
+
+        let mut stats = IndexStats::default();
+        let mut files = BTreeMap::new();
+        for (path, size, mtime_ns) in walk.files {
+            let entry = match self.files.remove(&path) {
+                Some(old) if old.size == size && old.mtime_ns == mtime_ns => {
+                    stats.unchanged += 1;
+                    old
+                }
+                Some(_) => {
+                    stats.updated += 1;
+                    scan_file(&workspace.join(&path), &path, size, mtime_ns)
+                }
+                None => {
+                    stats.added += 1;
+                    scan_file(&workspace.join(&path), &path, size, mtime_ns)
+                }
+            };
+            files.insert(path, entry);
+        }
+        stats.removed = self.files.len();
+
+        self.version = INDEX_VERSION;
+        self.files = files;
+        self.truncated = walk.truncated;
+        stats
+    }
+
+    /// Matching symbols (or files, without a symbol query), best first
+    ///
+    /// Symbols rank exact name, then case-insensitive exact, then prefix,
+    /// then substring matches; files rank a match in the file name above
+    /// one elsewhere in the path. Ties go to shorter paths.
+    pub fn search(&self, query: &IndexQuery) -> Vec<SearchHit> {
+        let lower = |s: &Option<String>| s.as_deref().map(str::to_lowercase).filter(|s| !s.is_empty());
+        let path_query = lower(&query.path);
+        let language = lower(&query.language);
+        let symbol_query = query.symbol.as_deref().filter(|s| !s.is_empty());
+
+        let mut scored: Vec<(u32, SearchHit)> = Vec::new();
+        for (path, file) in &self.files {
+            if let Some(language) = &language
+                && file.language.as_deref() != Some(language.as_str())
+            {
+                continue;
+            }
+            let lower_path = path.to_lowercase();
+            if let Some(q) = &path_query
+                && !lower_path.contains(q.as_str())
+            {
+                continue;
+            }
+            let hit = |symbol: Option<&Symbol>| SearchHit {
+                path: path.clone(),
+                language: file.language.clone(),
+                lines: file.lines,
+                symbol: symbol.cloned(),
+            };
+
+            match symbol_query {
+                Some(q) => {
+                    for symbol in &file.symbols {
+                        if let Some(score) = symbol_score(&symbol.name, q) {
+                            scored.push((score, hit(Some(symbol))));
+                        }
+                    }
+                }
+                None => {
+                    let name = lower_path.rsplit('/').next().unwrap_or_default();
+                    let score = match &path_query {
+                        Some(q) if name.contains(q.as_str()) => 2,
+                        _ => 1,
+                    };
+                    scored.push((score, hit(None)));
+                }
+            }
+        }
+
+        scored.sort_by(|(a_score, a), (b_score, b)| {
+            b_score
+                .cmp(a_score)
+                .then_with(|| a.path.len().cmp(&b.path.len()))
+                .then_with(|| a.path.cmp(&b.path))
+                .then_with(|| a.symbol.as_ref().map(|s| s.line).cmp(&b.symbol.as_ref().map(|s| s.line)))
+        });
+        scored.into_iter().take(query.limit).map(|(_, hit)| hit).collect()
+    }
+}
+
+/// How well a symbol name matches a query (None = not at all)
+fn symbol_score(name: &str, query: &str) -> Option<u32> {
+    if name == query {
+        return Some(4);
+    }
+    let name = name.to_lowercase();
+    let query = query.to_lowercase();
+    if name == query {
+        Some(3)
+    } else if name.starts_with(&query) {
+        Some(2)
+    } else if name.contains(&query) {
+        Some(1)
+    } else {
+        None
+    }
+}
+
+/// Index entry for a file, reading it if it is small enough and not binary
+fn scan_file(path: &Path, relative: &str, size: u64, mtime_ns: u64) -> IndexedFile {
+    let language = language_for(relative);
+    let mut entry = IndexedFile {
+        language: language.map(str::to_string),
+        lines: 0,
+        size,
+        mtime_ns,
+        symbols: Vec::new(),
+    };
+    if size > MAX_SCANNED_FILE_BYTES {
+        return entry;
+    }
+    let Ok(bytes) = std::fs::read(path) else {
+        return entry;
+    };
+    if bytes.iter().take(8192).any(|&b| b == 0) {
+        entry.language = None;
+        return entry;
+    }
+    let text = String::from_utf8_lossy(&bytes);
+    entry.lines = text.lines().count();
+    if let Some(language) = language {
+        entry.symbols = extract_symbols(language, &text);
+    }
+    entry
+}
+
+/// Definitions in `text`, for languages with symbol patterns
+pub fn extract_symbols(language: &str, text: &str) -> Vec<Symbol> {
+    let Some((_, patterns)) = SYMBOL_PATTERNS.iter().find(|(name, _)| *name == language) else {
+        return Vec::new();
+    };
+    text.lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let caps = patterns.iter().find_map(|p| p.captures(line))?;
+            Some(Symbol {
+                name: caps["name"].to_string(),
+                kind: caps["kind"].trim_end_matches(['*', '!']).to_string(),
+                line: i + 1,
+            })
+        })
+        .take(MAX_SYMBOLS_PER_FILE)
+        .collect()
+}
+
+/// Definition-line patterns per language, each capturing `kind` and `name`
+static SYMBOL_PATTERNS: LazyLock<Vec<(&'static str, Vec<Regex>)>> = LazyLock::new(|| {
+    let compile = |patterns: &[&str]| patterns.iter().map(|p| Regex::new(p).expect("valid symbol pattern")).collect();
+    let js = r"^(?:export\s+)?(?:default\s+)?(?:declare\s+)?(?:abstract\s+)?(?:async\s+)?(?P<kind>function\*?|class|interface|type|enum|const|let|var)\s+(?P<name>[A-Za-z_$][\w$]*)";
+    let class_like = r"^\s*(?:(?:public|private|protected|internal|static|final|abstract|sealed|open|data|partial|override)\s+)*(?P<kind>class|interface|enum|record|object|struct|trait|fun|func|function)\s+(?P<name>[A-Za-z_]\w*)";
+    vec![
+        (
+            "rust",
+            compile(&[
+                r#"^\s*(?:pub(?:\([^)]*\))?\s+)?(?:(?:async|const|unsafe|extern(?:\s+"[^"]*")?)\s+)*(?P<kind>fn|struct|enum|trait|type|mod|const|static|union|macro_rules!)\s+(?P<name>[A-Za-z_][A-Za-z0-9_]*)"#,
+            ]),
+        ),
+        ("python", compile(&[r"^\s*(?:async\s+)?(?P<kind>def|class)\s+(?P<name>[A-Za-z_]\w*)"])),
+        ("javascript", compile(&[js])),
+        ("typescript", compile(&[js])),
+        ("go", compile(&[r"^(?P<kind>func|type|var|const)\s+(?:\([^)]*\)\s*)?(?P<name>[A-Za-z_]\w*)"])),
+        ("java", compile(&[class_like])),
+        ("kotlin", compile(&[class_like])),
+        ("csharp", compile(&[class_like])),
+        ("swift", compile(&[class_like])),
+        ("php", compile(&[class_like])),
+        ("ruby", compile(&[r"^\s*(?P<kind>def|class|module)\s+(?:self\.)?(?P<name>[A-Za-z_]\w*[?!]?)"])),
+        (
+            "c",
+            compile(&[
+                r"^(?:typedef\s+)?(?P<kind>struct|enum|union)\s+(?P<name>[A-Za-z_]\w*)",
+                r"^#\s*(?P<kind>define)\s+(?P<name>[A-Za-z_]\w*)",
+            ]),
+        ),
+        (
+            "cpp",
+            compile(&[
+                r"^(?:template\s*<[^>]*>\s*)?(?:typedef\s+)?(?P<kind>struct|class|enum|union|namespace)\s+(?P<name>[A-Za-z_]\w*)",
+                r"^#\s*(?P<kind>define)\s+(?P<name>[A-Za-z_]\w*)",
+            ]),
+        ),
+    ]
+});
+
+/// Language of a file, from its name
+pub fn language_for(path: &str) -> Option<&'static str> {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    match name {
+        "Makefile" | "makefile" | "GNUmakefile" => return Some("make"),
+        "Dockerfile" => return Some("dockerfile"),
+        _ => {}
+    }
+    let ext = name.rsplit_once('.')?.1.to_ascii_lowercase();
+    Some(match ext.as_str() {
+        "rs" => "rust",
+        "py" | "pyi" => "python",
+        "js" | "jsx" | "mjs" | "cjs" => "javascript",
+        "ts" | "tsx" | "mts" | "cts" => "typescript",
+        "go" => "go",
+        "java" => "java",
+        "kt" | "kts" => "kotlin",
+        "cs" => "csharp",
+        "swift" => "swift",
+        "php" => "php",
+        "rb" => "ruby",
+        "c" | "h" => "c",
+        "cc" | "cpp" | "cxx" | "hpp" | "hh" | "hxx" => "cpp",
+        "sh" | "bash" | "zsh" => "shell",
+        "md" | "markdown" => "markdown",
+        "toml" => "toml",
+        "json" => "json",
+        "yaml" | "yml" => "yaml",
+        "html" | "htm" => "html",
+        "css" | "scss" => "css",
+        "sql" => "sql",
+        _ => return None,
+    })
+}
+
+/// One `.gitignore` line
+struct IgnoreRule {
+    matcher: GlobMatcher,
+    /// Directory of the `.gitignore` it came from, relative to the workspace
+    base: String,
+    /// Matched against the path below `base` rather than the file name
+    anchored: bool,
+    negated: bool,
+    dir_only: bool,
+}
+
+impl IgnoreRule {
+    fn parse(line: &str, base: &str) -> Option<Self> {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (negated, pattern) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line.strip_prefix('\\').unwrap_or(line)),
+        };
+        let dir_only = pattern.ends_with('/');
+        let pattern = pattern.trim_end_matches('/');
+        let anchored = pattern.contains('/');
+        let pattern = pattern.trim_start_matches('/');
+        if pattern.is_empty() {
+            return None;
+        }
+        let matcher = GlobBuilder::new(pattern).literal_separator(true).build().ok()?.compile_matcher();
+        Some(Self {
+            matcher,
+            base: base.to_string(),
+            anchored,
+            negated,
+            dir_only,
+        })
+    }
+
+    /// Whether the rule applies to `path` (relative to the workspace)
+    fn matches(&self, path: &str, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        let below = if self.base.is_empty() {
+            path
+        } else {
+            match path.strip_prefix(self.base.as_str()).and_then(|p| p.strip_prefix('/')) {
+                Some(below) => below,
+                None => return false,
+            }
+        };
+        if self.anchored {
+            self.matcher.is_match(below)
+        } else {
+            self.matcher.is_match(below.rsplit('/').next().unwrap_or(below))
+        }
+    }
+}
+
+/// Whether the last rule matching `path` excludes it
+fn is_ignored(rules: &[IgnoreRule], path: &str, is_dir: bool) -> bool {
+    rules
+        .iter()
+        .rev()
+        .find(|rule| rule.matches(path, is_dir))
+        .is_some_and(|rule| !rule.negated)
+}
+
+/// Files in `workspace` its `.gitignore` files don't exclude, as paths
+/// relative to it (with `/` separators) and their sizes
+///
+/// The walk skips what the index skips: `.git`, `.cowork` and build output
+/// directories such as `target` and `node_modules`, and stops after
+/// `MAX_INDEXED_FILES` files.
+pub(crate) fn workspace_files(workspace: &Path) -> Vec<(String, u64)> {
+    let mut walk = Walk::default();
+    walk.dir(workspace, "", &mut Vec::new());
+    walk.files.into_iter().map(|(path, size, _)| (path, size)).collect()
+}
+
+/// The workspace files of `workspace_files` with their modification times
+/// (ns since the epoch), and what tells whether the list is still current
+pub(crate) struct WorkspaceListing {
+    pub files: Vec<(String, u64)>,
+    stamps: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+impl WorkspaceListing {
+    pub fn load(workspace: &Path) -> Self {
+        let mut walk = Walk {
+            stamps: Some(Vec::new()),
+            ..Walk::default()
+        };
+        walk.dir(workspace, "", &mut Vec::new());
+        Self {
+            files: walk.files.into_iter().map(|(path, _, mtime_ns)| (path, mtime_ns)).collect(),
+            stamps: walk.stamps.unwrap_or_default(),
+        }
+    }
+
+    /// Whether no directory walked (nor its `.gitignore`) changed since:
+    /// adding, removing or renaming a file changes its directory
+    pub fn is_current(&self) -> bool {
+        self.stamps.iter().all(|(path, stamp)| modified(path) == *stamp)
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Files found walking the workspace: (relative path, size, mtime in ns)
+#[derive(Default)]
+struct Walk {
+    files: Vec<(String, u64, u64)>,
+    truncated: bool,
+    /// Modification times of the directories walked and their
+    /// `.gitignore` files, when recorded
+    stamps: Option<Vec<(PathBuf, Option<SystemTime>)>>,
+}
+
+impl Walk {
+    /// Walk `dir` (at `relative`), with the rules of the `.gitignore` files above it
+    fn dir(&mut self, dir: &Path, relative: &str, rules: &mut Vec<IgnoreRule>) {
+        let inherited = rules.len();
+        if let Some(stamps) = &mut self.stamps {
+            let gitignore = dir.join(".gitignore");
+            stamps.push((dir.to_path_buf(), modified(dir)));
+            stamps.push((gitignore.clone(), modified(&gitignore)));
+        }
+        if let Ok(content) = std::fs::read_to_string(dir.join(".gitignore")) {
+            rules.extend(content.lines().filter_map(|line| IgnoreRule::parse(line, relative)));
+        }
+
+        let mut entries: Vec<_> = match std::fs::read_dir(dir) {
+            Ok(entries) => entries.flatten().collect(),
+            Err(_) => Vec::new(),
+        };
+        entries.sort_by_key(|e| e.file_name());
+
+        for entry in entries {
+            if self.truncated {
+                break;
+            }
+            let name = entry.file_name().to_string_lossy().to_string();
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            let is_dir = file_type.is_dir();
+            if name == ".git" || (relative.is_empty() && name == ".cowork") || (is_dir && TREE_SKIP.contains(&name.as_str())) {
+                continue;
+            }
+            let path = if relative.is_empty() { name } else { format!("{}/{}", relative, name) };
+            if is_ignored(rules, &path, is_dir) {
+                continue;
+            }
+
+            if is_dir {
+                self.dir(&entry.path(), &path, rules);
+            } else if file_type.is_file() {
+                if self.files.len() >= MAX_INDEXED_FILES {
+                    self.truncated = true;
+                    break;
+                }
+                let Ok(metadata) = entry.metadata() else {
+                    continue;
+                };
+                let mtime_ns = metadata
+                    .modified()
+                    .ok()
+                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                    .map(|d| d.as_nanos() as u64)
+                    .unwrap_or(0);
+                self.files.push((path, metadata.len(), mtime_ns));
+            }
+        }
+
+        rules.truncate(inherited);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(content: &str, base: &str) -> Vec<IgnoreRule> {
+        content.lines().filter_map(|line| IgnoreRule::parse(line, base)).collect()
+    }
+
+    #[test]
+    fn test_gitignore_rules() {
+        let rules = rules("# build output\n*.log\n/dist\ndocs/generated/\ncache/\n!keep.log\n", "");
+        assert!(is_ignored(&rules, "server.log", false));
+        assert!(is_ignored(&rules, "src/deep/server.log", false));
+        assert!(!is_ignored(&rules, "src/keep.log", false));
+        assert!(is_ignored(&rules, "dist", true));
+        assert!(!is_ignored(&rules, "src/dist", true));
+        assert!(is_ignored(&rules, "docs/generated", true));
+        assert!(is_ignored(&rules, "src/cache", true));
+        assert!(!is_ignored(&rules, "src/cache", false));
+    }
+
+    #[test]
+    fn test_nested_gitignore_applies_below_its_directory() {
+        let rules = rules("*.tmp\n/local\n", "web");
+        assert!(is_ignored(&rules, "web/a.tmp", false));
+        assert!(!is_ignored(&rules, "a.tmp", false));
+        assert!(is_ignored(&rules, "web/local", true));
+        assert!(!is_ignored(&rules, "web/src/local", true));
+    }
+
+    #[test]
+    fn test_symbol_extraction() {
+        let rust = "pub struct Index;\nimpl Index {\n    pub(crate) async fn refresh(&self) {}\n}\nconst LIMIT: usize = 3;\nmacro_rules! log {}\n";
+        let names: Vec<(String, String, usize)> =
+            extract_symbols("rust", rust).into_iter().map(|s| (s.kind, s.name, s.line)).collect();
+        assert_eq!(
+            names,
+            vec![
+                ("struct".to_string(), "Index".to_string(), 1),
+                ("fn".to_string(), "refresh".to_string(), 3),
+                ("const".to_string(), "LIMIT".to_string(), 5),
+                ("macro_rules".to_string(), "log".to_string(), 6),
+            ]
+        );
+
+        let python = "class Loader:\n    async def load(self):\n        pass\n";
+        let names: Vec<String> = extract_symbols("python", python).into_iter().map(|s| s.name).collect();
+        assert_eq!(names, vec!["Loader", "load"]);
+
+        let ts = "export default async function main() {}\nexport interface Props {}\n  const inner = 1;\n";
+        let names: Vec<String> = extract_symbols("typescript", ts).into_iter().map(|s| s.name).collect();
+        assert_eq!(names, vec!["main", "Props"]);
+
+        let go = "func (s *Server) Start() error {\ntype Config struct {\n";
+        let names: Vec<String> = extract_symbols("go", go).into_iter().map(|s| s.name).collect();
+        assert_eq!(names, vec!["Start", "Config"]);
+
+        assert!(extract_symbols("markdown", "# fn title").is_empty());
+    }
+
+    #[test]
+    fn test_language_for() {
+        assert_eq!(language_for("src/main.rs"), Some("rust"));
+        assert_eq!(language_for("web/App.TSX"), Some("typescript"));
+        assert_eq!(language_for("Makefile"), Some("make"));
+        assert_eq!(language_for("LICENSE"), None);
+    }
+}
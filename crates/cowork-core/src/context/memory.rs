@@ -0,0 +1,257 @@
+//! Project-local memory entries
+//!
+//! `CLAUDE.local.md` in the workspace holds personal notes for the project
+//! (it is part of the user tier of the memory hierarchy, see `gather`).
+//! Entries are its top-level bullets. Edits are read-modify-write: the file
+//! is re-read just before writing and the edit is redone if it changed in
+//! the meantime, so notes added by hand while an edit is in flight are kept.
+
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::error::{Error, Result};
+
+/// Memory file that `/memory add` and auto-learned notes are written to
+pub const LOCAL_MEMORY_FILE: &str = "CLAUDE.local.md";
+
+/// Heading a newly created memory file starts with
+const NEW_FILE_HEADER: &str = "# Local memory\n\nNotes for this project that aren't checked in.\n";
+
+/// Times an edit is redone when the file keeps changing under it
+const MAX_ATTEMPTS: usize = 3;
+
+/// A correction worth remembering: "always ...", "never ...", "remember (that|to) ..."
+static LEARNABLE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)^(?:(?:no|nope|ok|okay|also|and|but|please|actually)[,!]?\s+)*(?:(?P<rule>(?:always|never)\s+.+)|remember\s+(?:(?P<to>to)\s+)?(?:that\s+)?(?P<fact>.+))$",
+    )
+    .expect("learnable note regex")
+});
+
+/// The workspace's `CLAUDE.local.md`
+#[derive(Debug, Clone)]
+pub struct LocalMemory {
+    path: PathBuf,
+}
+
+impl LocalMemory {
+    pub fn for_workspace(workspace: &Path) -> Self {
+        Self {
+            path: workspace.join(LOCAL_MEMORY_FILE),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Create the file if it doesn't exist yet
+    pub fn ensure_exists(&self) -> Result<()> {
+        self.update(|content| Ok(content.to_string()))
+    }
+
+    /// Top-level bullet entries, in file order
+    pub fn entries(&self) -> Result<Vec<String>> {
+        Ok(parse_entries(&self.read()?.unwrap_or_default()))
+    }
+
+    /// Append a bullet
+    pub fn add(&self, note: &str) -> Result<()> {
+        let note = single_line(note);
+        if note.is_empty() {
+            return Err(Error::Agent("Memory note is empty".to_string()));
+        }
+        self.update(|content| {
+            let mut content = content.to_string();
+            if !content.is_empty() && !content.ends_with('\n') {
+                content.push('\n');
+            }
+            content.push_str(&format!("- {}\n", note));
+            Ok(content)
+        })
+    }
+
+    /// Append a bullet learned from the conversation, stamped with when it was saved
+    pub fn add_learned(&self, note: &str, at: chrono::DateTime<chrono::Local>) -> Result<()> {
+        self.add(&format!("{} (learned {})", single_line(note), at.format("%Y-%m-%d %H:%M")))
+    }
+
+    /// Remove entry `index` (1-based, as listed by `entries`), returning its text
+    ///
+    /// Fails rather than removing something else if that entry was edited
+    /// or moved since it was listed.
+    pub fn remove(&self, index: usize) -> Result<String> {
+        let entries = self.entries()?;
+        let Some(target) = index.checked_sub(1).and_then(|i| entries.get(i)).cloned() else {
+            return Err(Error::Agent(format!(
+                "No memory entry {} ({} entries)",
+                index,
+                entries.len()
+            )));
+        };
+
+        self.update(|content| {
+            let lines: Vec<&str> = content.split_inclusive('\n').collect();
+            let at = lines
+                .iter()
+                .position(|line| entry_text(line).as_deref() == Some(target.as_str()))
+                .ok_or_else(|| Error::Agent(format!("Memory entry {} changed; list the entries again", index)))?;
+            Ok(lines
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| *i != at)
+                .map(|(_, line)| *line)
+                .collect())
+        })?;
+        Ok(target)
+    }
+
+    fn read(&self) -> Result<Option<String>> {
+        match std::fs::read_to_string(&self.path) {
+            Ok(content) => Ok(Some(content)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Read-modify-write, redone if the file changes before the write
+    fn update(&self, edit: impl Fn(&str) -> Result<String>) -> Result<()> {
+        for _ in 0..MAX_ATTEMPTS {
+            let before = self.read()?;
+            let after = edit(before.as_deref().unwrap_or(NEW_FILE_HEADER))?;
+            if before.as_deref() == Some(after.as_str()) {
+                return Ok(());
+            }
+
+            // Someone saved the file since we read it: start over from their version
+            if self.read()? != before {
+                continue;
+            }
+            let tmp_path = self.path.with_extension("md.tmp");
+            std::fs::write(&tmp_path, &after)?;
+            std::fs::rename(&tmp_path, &self.path)?;
+            return Ok(());
+        }
+        Err(Error::Agent(format!(
+            "{} kept changing while saving; try again",
+            self.path.display()
+        )))
+    }
+}
+
+/// Text of each top-level `-` or `*` bullet
+fn parse_entries(content: &str) -> Vec<String> {
+    content.lines().filter_map(entry_text).collect()
+}
+
+fn entry_text(line: &str) -> Option<String> {
+    let line = line.trim_end();
+    line.strip_prefix("- ")
+        .or_else(|| line.strip_prefix("* "))
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+}
+
+fn single_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The note a user message asks to remember, if it reads like a standing instruction
+///
+/// Looks for a sentence starting with "always", "never" or "remember"
+/// (after fillers like "no," or "please"), e.g. "No, always use tabs here."
+/// or "Remember to run cargo fmt". Returns the note to save.
+pub fn learnable_note(message: &str) -> Option<String> {
+    message
+        .split(['.', '!', '?', '\n'])
+        .map(str::trim)
+        .find_map(|sentence| {
+            let caps = LEARNABLE_RE.captures(sentence)?;
+            if let Some(rule) = caps.name("rule") {
+                let rule = single_line(rule.as_str());
+                let lower = rule.to_lowercase();
+                if lower.starts_with("never mind") || rule.split(' ').count() < 3 {
+                    return None;
+                }
+                return Some(capitalize(&rule));
+            }
+            let fact = single_line(caps.name("fact")?.as_str());
+            if fact.split(' ').count() < 2 {
+                return None;
+            }
+            Some(if caps.name("to").is_some() {
+                format!("Always {}", fact)
+            } else {
+                capitalize(&fact)
+            })
+        })
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_add_list_remove() {
+        let dir = TempDir::new().unwrap();
+        let memory = LocalMemory::for_workspace(dir.path());
+        assert!(memory.entries().unwrap().is_empty());
+
+        memory.add("Use tabs in Makefiles").unwrap();
+        memory.add("  Run   cargo fmt\nbefore committing ").unwrap();
+        let content = std::fs::read_to_string(memory.path()).unwrap();
+        assert!(content.starts_with("# Local memory"));
+        assert_eq!(memory.entries().unwrap(), vec!["Use tabs in Makefiles", "Run cargo fmt before committing"]);
+
+        assert_eq!(memory.remove(1).unwrap(), "Use tabs in Makefiles");
+        assert_eq!(memory.entries().unwrap(), vec!["Run cargo fmt before committing"]);
+        assert!(memory.remove(5).is_err());
+        assert!(memory.add("   ").is_err());
+    }
+
+    #[test]
+    fn test_manual_edits_kept() {
+        let dir = TempDir::new().unwrap();
+        let memory = LocalMemory::for_workspace(dir.path());
+        std::fs::write(memory.path(), "# Notes\nSome prose\n  - nested, not an entry\n* Starred entry").unwrap();
+
+        memory.add("New entry").unwrap();
+        let content = std::fs::read_to_string(memory.path()).unwrap();
+        assert_eq!(content, "# Notes\nSome prose\n  - nested, not an entry\n* Starred entry\n- New entry\n");
+        assert_eq!(memory.entries().unwrap(), vec!["Starred entry", "New entry"]);
+    }
+
+    #[test]
+    fn test_learned_entry_is_stamped() {
+        let dir = TempDir::new().unwrap();
+        let memory = LocalMemory::for_workspace(dir.path());
+        let at = chrono::Local::now();
+        memory.add_learned("Never push to main", at).unwrap();
+        let entry = &memory.entries().unwrap()[0];
+        assert_eq!(entry, &format!("Never push to main (learned {})", at.format("%Y-%m-%d %H:%M")));
+    }
+
+    #[test]
+    fn test_learnable_note() {
+        assert_eq!(learnable_note("No, always use tabs here."), Some("Always use tabs here".to_string()));
+        assert_eq!(learnable_note("That's wrong. never commit generated files!"), Some("Never commit generated files".to_string()));
+        assert_eq!(learnable_note("Please remember to run cargo fmt"), Some("Always run cargo fmt".to_string()));
+        assert_eq!(learnable_note("Remember that the API is versioned"), Some("The API is versioned".to_string()));
+
+        assert_eq!(learnable_note("Never mind, use the other file"), None);
+        assert_eq!(learnable_note("Always?"), None);
+        assert_eq!(learnable_note("Do you always do this?"), None);
+        assert_eq!(learnable_note("Fix the failing test"), None);
+    }
+}
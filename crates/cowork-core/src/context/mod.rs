@@ -4,11 +4,24 @@
 //! - Token counting and context compaction (summarizer)
 //! - Context limit checking (monitor)
 //! - Project context gathering (gather)
+//! - The cached project overview for a session's first turn (overview)
+//! - The workspace index of files and symbols behind CodebaseSearch (index)
+//! - Project-local memory entries (memory)
 
 pub mod gather;
+pub mod index;
+pub mod memory;
 pub mod monitor;
+pub mod overview;
 pub mod summarizer;
 
 pub use gather::{ContextGatherer, MemoryFile, MemoryHierarchy, MemoryTier, ProjectContext};
-pub use monitor::{context_limit, should_compact, usage_stats, ContextUsage};
-pub use summarizer::{compact, CompactResult};
+pub use index::{IndexQuery, IndexStats, IndexedFile, SearchHit, Symbol, WorkspaceIndex, INDEX_FILE};
+pub use memory::{learnable_note, LocalMemory, LOCAL_MEMORY_FILE};
+pub use overview::{cached_overview, project_overview, project_overview_within, DEFAULT_OVERVIEW_BUDGET, GATHER_TIMEOUT};
+pub use monitor::{context_limit, estimate_image_tokens, should_compact, usage_stats, ContextUsage};
+pub use summarizer::{
+    collapse_tool_results, compact, compact_result, emergency_summarization_request, heuristic_compact, is_summary,
+    summarization_request, CompactResult, CompactionMode, COLLAPSED_TOOL_RESULT, COMPACTION_PURPOSE, DEFAULT_KEEP_TURNS, FOCUS_VAR,
+    TRANSCRIPT_VAR,
+};
@@ -2,6 +2,8 @@
 //!
 //! No state tracking needed - just check the LLM-reported tokens against limits.
 
+use genai::adapter::AdapterKind;
+
 use crate::provider::{catalog, model_listing::get_model_context_limit};
 
 /// Default threshold percentage for auto-compact (75%)
@@ -37,6 +39,47 @@ pub fn context_limit(provider_id: &str, model: Option<&str>) -> usize {
     limit
 }
 
+/// Estimate the input tokens an image costs, following each provider's published rules
+///
+/// - OpenAI: 85 + 170 per 512px tile, after scaling to fit 2048x2048 and
+///   then to a shortest side of at most 768
+/// - Gemini: 258 per 768px tile (258 in total when both sides are at most 384)
+/// - Anthropic and the rest: width * height / 750, after scaling the long
+///   side to at most 1568
+///
+/// Images whose size is unknown are counted at the largest Anthropic cost.
+pub fn estimate_image_tokens(provider_id: &str, dimensions: Option<(u32, u32)>) -> u64 {
+    const ANTHROPIC_MAX_TOKENS: u64 = 1600;
+    let Some((width, height)) = dimensions.filter(|(w, h)| *w > 0 && *h > 0) else {
+        return ANTHROPIC_MAX_TOKENS;
+    };
+    let (width, height) = (width as f64, height as f64);
+    let fit = |w: f64, h: f64, limit: f64| {
+        let scale = (limit / w.max(h)).min(1.0);
+        (w * scale, h * scale)
+    };
+
+    match catalog::adapter(provider_id) {
+        Some(AdapterKind::OpenAI | AdapterKind::OpenAIResp) => {
+            let (w, h) = fit(width, height, 2048.0);
+            let scale = (768.0 / w.min(h)).min(1.0);
+            let tiles = ((w * scale) / 512.0).ceil() * ((h * scale) / 512.0).ceil();
+            85 + 170 * tiles as u64
+        }
+        Some(AdapterKind::Gemini) => {
+            if width <= 384.0 && height <= 384.0 {
+                258
+            } else {
+                258 * ((width / 768.0).ceil() * (height / 768.0).ceil()) as u64
+            }
+        }
+        _ => {
+            let (w, h) = fit(width, height, 1568.0);
+            ((w * h / 750.0).ceil() as u64).min(ANTHROPIC_MAX_TOKENS)
+        }
+    }
+}
+
 /// Check if context should be compacted
 ///
 /// Returns true if usage exceeds threshold or remaining tokens too low.
@@ -107,6 +150,18 @@ mod tests {
         assert_eq!(limit, 128_000);
     }
 
+    #[test]
+    fn test_estimate_image_tokens() {
+        // 1000x1000: Anthropic 1334, OpenAI 768x768 -> 4 tiles, Gemini 4 tiles
+        assert_eq!(estimate_image_tokens("anthropic", Some((1000, 1000))), 1334);
+        assert_eq!(estimate_image_tokens("openai", Some((1000, 1000))), 85 + 170 * 4);
+        assert_eq!(estimate_image_tokens("gemini", Some((1000, 1000))), 258 * 4);
+        assert_eq!(estimate_image_tokens("gemini", Some((300, 200))), 258);
+        // Large screenshots are scaled down first
+        assert_eq!(estimate_image_tokens("anthropic", Some((3840, 2160))), 1600);
+        assert_eq!(estimate_image_tokens("anthropic", None), 1600);
+    }
+
     #[test]
     fn test_usage_stats() {
         let usage = usage_stats(50_000, 1_000, 200_000);
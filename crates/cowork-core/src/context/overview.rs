@@ -0,0 +1,127 @@
+//! Project overview given to the model on a session's first turn
+//!
+//! Without it the model starts blind and spends its first tool calls
+//! listing directories. The overview (see `ProjectContext::render`) is
+//! cached in `.cowork/cache/project-context.json`, keyed by a hash of the
+//! modification times of the workspace root and its top-level entries, so
+//! later sessions reuse it until the layout or a top-level file changes.
+//!
+//! Gathering is bounded by `GATHER_TIMEOUT`; a workspace too large to scan
+//! in time gets no overview rather than a slow first response.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use super::gather::{tree_entries, ContextGatherer, ProjectContext};
+
+/// Cache file, relative to the workspace
+pub const OVERVIEW_CACHE_FILE: &str = ".cowork/cache/project-context.json";
+
+/// Default size of the overview, in estimated tokens
+pub const DEFAULT_OVERVIEW_BUDGET: usize = 1500;
+
+/// Longest a session waits for the overview before going without it
+pub const GATHER_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Bumped when the overview's content changes, so old caches are ignored
+const CACHE_VERSION: u32 = 2;
+
+/// What the cache file holds
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedOverview {
+    fingerprint: String,
+    context: ProjectContext,
+    /// The rendered overview, as injected
+    text: String,
+}
+
+/// The overview for `workspace`, from the cache if the project hasn't
+/// changed (blocking); None if there is nothing to say about the project
+pub fn project_overview(workspace: &Path, budget_tokens: usize) -> Option<String> {
+    let cache_path = workspace.join(OVERVIEW_CACHE_FILE);
+    if let Some(cached) = read_cache(&cache_path)
+        && cached.fingerprint == fingerprint(workspace, budget_tokens)
+    {
+        return (!cached.text.is_empty()).then_some(cached.text);
+    }
+
+    let context = ContextGatherer::new(workspace).project_overview();
+    let text = if context.is_empty() { String::new() } else { context.render(budget_tokens) };
+    // The cache directory exists before the fingerprint is taken, so
+    // creating it doesn't invalidate the entry being written
+    if let Err(e) = write_cache(workspace, &cache_path, budget_tokens, context, &text) {
+        tracing::debug!("Failed to cache project overview: {}", e);
+    }
+    (!text.is_empty()).then_some(text)
+}
+
+/// [`project_overview`] on a blocking thread, given up after `timeout`
+pub async fn project_overview_within(workspace: PathBuf, budget_tokens: usize, timeout: Duration) -> Option<String> {
+    let task = tokio::task::spawn_blocking(move || project_overview(&workspace, budget_tokens));
+    match tokio::time::timeout(timeout, task).await {
+        Ok(overview) => overview.ok().flatten(),
+        Err(_) => {
+            tracing::warn!("Project overview took longer than {:?}; starting without it", timeout);
+            None
+        }
+    }
+}
+
+/// The overview last cached for `workspace`, whether or not it is current
+pub fn cached_overview(workspace: &Path) -> Option<String> {
+    read_cache(&workspace.join(OVERVIEW_CACHE_FILE)).map(|cached| cached.text)
+}
+
+fn read_cache(path: &Path) -> Option<CachedOverview> {
+    let content = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn write_cache(
+    workspace: &Path,
+    path: &Path,
+    budget_tokens: usize,
+    context: ProjectContext,
+    text: &str,
+) -> std::io::Result<()> {
+    create_cache_dir(path.parent().unwrap_or(workspace))?;
+    let cached = CachedOverview {
+        fingerprint: fingerprint(workspace, budget_tokens),
+        context,
+        text: text.to_string(),
+    };
+    std::fs::write(path, serde_json::to_string_pretty(&cached)?)
+}
+
+/// Create the workspace's cache directory, kept out of version control
+pub(crate) fn create_cache_dir(dir: &Path) -> std::io::Result<()> {
+    if !dir.exists() {
+        std::fs::create_dir_all(dir)?;
+        std::fs::write(dir.join(".gitignore"), "*\n")?;
+    }
+    Ok(())
+}
+
+/// Hash of what the overview depends on: the budget and the modification
+/// times of the workspace root, its visible entries and their children lists
+fn fingerprint(workspace: &Path, budget_tokens: usize) -> String {
+    let mtime = |path: &Path| {
+        std::fs::metadata(path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_nanos())
+    };
+
+    let mut hasher = DefaultHasher::new();
+    (CACHE_VERSION, budget_tokens).hash(&mut hasher);
+    mtime(workspace).hash(&mut hasher);
+    for (name, is_dir) in tree_entries(workspace) {
+        (&name, is_dir, mtime(&workspace.join(&name))).hash(&mut hasher);
+    }
+    format!("{:016x}", hasher.finish())
+}
@@ -1,10 +1,92 @@
 //! Conversation summarization for context compaction
+//!
+//! A history is compacted in one of two modes (`CompactionMode`):
+//!
+//! - `llm`: a model summarizes the conversation, by default the provider's
+//!   fast tier rather than the session's own model (see `SummarizerConfig`).
+//!   The prompt can be replaced by a template with `{transcript}` and
+//!   `{focus}` in it.
+//! - `heuristic`: no model call. The first and last turns are kept verbatim,
+//!   the turns between them are condensed to their text with tool results
+//!   dropped, and pinned messages are always kept. The same history always
+//!   compacts to the same result.
+//!
+//! Pinned messages (see `session::pins`) survive either mode: heuristic
+//! compaction keeps them in place, `llm` compaction after the summary.
+
+use std::fmt;
+use std::str::FromStr;
 
 use serde::{Deserialize, Serialize};
 
 use crate::error::Result;
-use crate::prompt::builtin::reminders::CONVERSATION_SUMMARIZATION;
-use crate::provider::{message_text_content, ChatMessage, ChatRole, GenAIProvider};
+use crate::prompt::builtin::reminders::{CONVERSATION_SUMMARIZATION, EMERGENCY_COMPACTION};
+use crate::provider::{
+    message_text_content, ChatMessage, ChatRole, ContentPart, GenAIProvider, MessageContent, ToolResponse,
+};
+
+/// Characters per token assumed when fitting the history to a token budget
+const CHARS_PER_TOKEN: usize = 4;
+
+/// What a tool result collapsed to fit the summarization request reads
+pub const COLLAPSED_TOOL_RESULT: &str = "[tool result omitted]";
+
+/// Share of the context window the emergency summarization request may use
+const EMERGENCY_REQUEST_SHARE: usize = 2;
+
+/// Longest emergency summary, in words
+const EMERGENCY_SUMMARY_WORDS: usize = 800;
+
+/// Turns kept verbatim at each end of the history by heuristic compaction
+pub const DEFAULT_KEEP_TURNS: usize = 2;
+
+/// Characters of each message kept when heuristic compaction condenses it
+const CONDENSED_MESSAGE_CHARS: usize = 300;
+
+/// Start of the summary a compaction leaves in the history
+const SUMMARY_OPEN: &str = "<summary>";
+
+/// `UsageRecord::purpose` of a summarization's tokens
+pub const COMPACTION_PURPOSE: &str = "compaction";
+
+/// Placeholder for the conversation in a summarization prompt template
+pub const TRANSCRIPT_VAR: &str = "{transcript}";
+
+/// Placeholder for what to pay attention to in a summarization prompt template
+pub const FOCUS_VAR: &str = "{focus}";
+
+/// How a history is compacted
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompactionMode {
+    /// A model summarizes the conversation
+    #[default]
+    Llm,
+    /// Condensed without a model call: the first and last turns kept, tool
+    /// results dropped in between
+    Heuristic,
+}
+
+impl fmt::Display for CompactionMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Llm => write!(f, "llm"),
+            Self::Heuristic => write!(f, "heuristic"),
+        }
+    }
+}
+
+impl FromStr for CompactionMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "llm" => Ok(Self::Llm),
+            "heuristic" => Ok(Self::Heuristic),
+            _ => Err(format!("Invalid compaction mode: {} (expected llm or heuristic)", s)),
+        }
+    }
+}
 
 /// Result of a compaction operation
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,6 +99,50 @@ pub struct CompactResult {
     pub chars_after: usize,
     /// Number of messages that were summarized
     pub messages_summarized: usize,
+    /// How the history was compacted
+    #[serde(default)]
+    pub mode: CompactionMode,
+    /// The model that wrote the summary (None for heuristic compaction)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Estimated tokens of the history before compaction
+    #[serde(default)]
+    pub tokens_before: usize,
+    /// Estimated tokens of the history after compaction
+    #[serde(default)]
+    pub tokens_after: usize,
+    /// The history that replaces the conversation
+    #[serde(default)]
+    pub messages: Vec<ChatMessage>,
+}
+
+impl CompactResult {
+    /// Estimated tokens the compaction saved
+    pub fn tokens_saved(&self) -> usize {
+        self.tokens_before.saturating_sub(self.tokens_after)
+    }
+
+    /// Record the model that wrote the summary
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// Keep the messages of `messages` that `is_pinned` accepts after the
+    /// summary, as plain text (a pinned reply's tool calls are left to the
+    /// summary, so the history stays valid)
+    pub fn with_pinned(mut self, messages: &[ChatMessage], is_pinned: impl Fn(&ChatMessage) -> bool) -> Self {
+        for message in messages.iter().filter(|m| is_pinned(m)) {
+            let text = message_text_content(message);
+            self.chars_after += text.len();
+            self.messages.push(match message.role {
+                ChatRole::Assistant => ChatMessage::assistant(text),
+                _ => ChatMessage::user(text),
+            });
+        }
+        self.tokens_after = self.chars_after / CHARS_PER_TOKEN;
+        self
+    }
 }
 
 /// Compact conversation history into a summary using LLM
@@ -30,16 +156,36 @@ pub async fn compact(
     let chars_before: usize = messages.iter().map(|m| message_text_content(m).len()).sum();
 
     if messages.is_empty() {
-        return Ok(CompactResult {
-            summary: "<summary>No prior context.</summary>".to_string(),
-            chars_before: 0,
-            chars_after: 0,
-            messages_summarized: 0,
-        });
+        return Ok(compact_result(0, 0, Some("<summary>No prior context.</summary>".to_string())));
     }
 
+    let response = provider.chat(summarization_request(messages, preserve_instructions, None), None).await?;
+    Ok(compact_result(chars_before, messages.len(), response.content).with_model(provider.model()))
+}
+
+/// The request asking the model to summarize `messages`
+///
+/// `template` replaces the built-in prompt: `{transcript}` in it becomes the
+/// conversation and `{focus}` what to pay attention to (empty when nothing
+/// was asked for). A template without `{transcript}` gets the conversation
+/// before it.
+pub fn summarization_request(
+    messages: &[ChatMessage],
+    preserve_instructions: Option<&str>,
+    template: Option<&str>,
+) -> Vec<ChatMessage> {
     let conversation_text = format_for_summarization(messages);
 
+    if let Some(template) = template {
+        let prompt = template.replace(FOCUS_VAR, preserve_instructions.unwrap_or_default());
+        let prompt = if prompt.contains(TRANSCRIPT_VAR) {
+            prompt.replace(TRANSCRIPT_VAR, &conversation_text)
+        } else {
+            format!("Here is the conversation history:\n\n{}\n\n{}", conversation_text, prompt)
+        };
+        return vec![ChatMessage::user(prompt)];
+    }
+
     let summary_prompt = match preserve_instructions {
         Some(instructions) => format!(
             "IMPORTANT: Pay special attention to and preserve details about: {}\n\n{}",
@@ -48,24 +194,200 @@ pub async fn compact(
         None => CONVERSATION_SUMMARIZATION.to_string(),
     };
 
-    let llm_messages = vec![ChatMessage::user(format!(
+    vec![ChatMessage::user(format!(
         "Here is the conversation history:\n\n{}\n\n{}",
         conversation_text, summary_prompt
-    ))];
-
-    let response = provider.chat(llm_messages, None).await?;
+    ))]
+}
 
-    let summary = response.content.unwrap_or_else(|| {
+/// The compaction of `messages_summarized` messages of `chars_before`
+/// characters into the model's `summary`
+pub fn compact_result(chars_before: usize, messages_summarized: usize, summary: Option<String>) -> CompactResult {
+    let summary = summary.unwrap_or_else(|| {
         "<summary>Previous conversation involved various development tasks.</summary>".to_string()
     });
-    let chars_after = summary.len();
-
-    Ok(CompactResult {
+    CompactResult {
+        chars_after: summary.len(),
+        chars_before,
+        messages_summarized,
+        mode: CompactionMode::Llm,
+        model: None,
+        tokens_before: chars_before / CHARS_PER_TOKEN,
+        tokens_after: summary.len() / CHARS_PER_TOKEN,
+        messages: vec![ChatMessage::user(&summary)],
         summary,
+    }
+}
+
+/// Whether `message` is the summary an earlier compaction left
+pub fn is_summary(message: &ChatMessage) -> bool {
+    message.role == ChatRole::User && message_text_content(message).starts_with(SUMMARY_OPEN)
+}
+
+/// Compact `messages` without a model call
+///
+/// The first and last `keep_turns` turns (each starting at a user message)
+/// are kept verbatim. The turns between them are condensed into summaries
+/// of their text, without tool calls or results. When there are no turns
+/// between them, the tool results of all but the last `keep_turns` turns are
+/// collapsed instead. Messages `is_pinned` accepts are never dropped.
+pub fn heuristic_compact(
+    messages: &[ChatMessage],
+    keep_turns: usize,
+    is_pinned: impl Fn(&ChatMessage) -> bool,
+) -> CompactResult {
+    let chars = |messages: &[ChatMessage]| -> usize { messages.iter().map(|m| message_text_content(m).len()).sum() };
+    let chars_before = chars(messages);
+    let starts: Vec<usize> = messages
+        .iter()
+        .enumerate()
+        .filter(|(_, m)| m.role == ChatRole::User)
+        .map(|(i, _)| i)
+        .collect();
+
+    let mut history = Vec::new();
+    let mut summaries = Vec::new();
+    let mut summarized = 0;
+    if starts.len() > keep_turns * 2 {
+        let head_end = if keep_turns == 0 { 0 } else { starts[keep_turns] };
+        let tail_start = if keep_turns == 0 { messages.len() } else { starts[starts.len() - keep_turns] };
+        history.extend_from_slice(&messages[..head_end]);
+
+        let mut lines = Vec::new();
+        for message in &messages[head_end..tail_start] {
+            let plain = message.role != ChatRole::Tool && message.content.tool_calls().is_empty();
+            if is_pinned(message) && plain {
+                flush_summary(&mut lines, &mut history, &mut summaries);
+                history.push(message.clone());
+                continue;
+            }
+            if let Some(line) = condensed_line(message, is_pinned(message)) {
+                lines.push(line);
+            }
+            summarized += 1;
+        }
+        flush_summary(&mut lines, &mut history, &mut summaries);
+        history.extend_from_slice(&messages[tail_start..]);
+    } else {
+        // Nothing between the kept turns: only the oldest tool results go
+        history = messages.to_vec();
+        let recent = starts.len().saturating_sub(keep_turns);
+        let recent_start = starts.get(recent).copied().unwrap_or(history.len());
+        let pinned: Vec<bool> = history.iter().map(&is_pinned).collect();
+        for (message, pinned) in history[..recent_start].iter_mut().zip(pinned) {
+            if !pinned {
+                summarized += collapse_tool_results(std::slice::from_mut(message), 0).min(1);
+            }
+        }
+    }
+
+    let chars_after = chars(&history);
+    CompactResult {
+        summary: summaries.join("\n\n"),
         chars_before,
         chars_after,
-        messages_summarized: messages.len(),
-    })
+        messages_summarized: summarized,
+        mode: CompactionMode::Heuristic,
+        model: None,
+        tokens_before: chars_before / CHARS_PER_TOKEN,
+        tokens_after: chars_after / CHARS_PER_TOKEN,
+        messages: history,
+    }
+}
+
+/// A message as a line of a heuristic summary (None for tool results)
+///
+/// Pinned messages keep their full text; others are shortened.
+fn condensed_line(message: &ChatMessage, pinned: bool) -> Option<String> {
+    let text = message_text_content(message);
+    let text = text.trim();
+    let shortened = if pinned || text.chars().count() <= CONDENSED_MESSAGE_CHARS {
+        text.to_string()
+    } else {
+        format!("{}...", text.chars().take(CONDENSED_MESSAGE_CHARS).collect::<String>())
+    };
+    match message.role {
+        ChatRole::Tool if !pinned => None,
+        ChatRole::Tool => Some(format!("Tool: {}", shortened)),
+        ChatRole::User => Some(format!("Human: {}", shortened)),
+        ChatRole::System => Some(format!("System: {}", shortened)),
+        ChatRole::Assistant => {
+            let tools: Vec<&str> = message.content.tool_calls().iter().map(|call| call.fn_name.as_str()).collect();
+            match (shortened.is_empty(), tools.is_empty()) {
+                (true, true) => None,
+                (false, true) => Some(format!("Assistant: {}", shortened)),
+                (true, false) => Some(format!("Assistant: [called {}]", tools.join(", "))),
+                (false, false) => Some(format!("Assistant: {} [called {}]", shortened, tools.join(", "))),
+            }
+        }
+    }
+}
+
+/// Add the condensed `lines` to `history` as a summary, if there are any
+fn flush_summary(lines: &mut Vec<String>, history: &mut Vec<ChatMessage>, summaries: &mut Vec<String>) {
+    if lines.is_empty() {
+        return;
+    }
+    let summary = format!(
+        "{}\nEarlier conversation, condensed (tool results omitted):\n\n{}\n</summary>",
+        SUMMARY_OPEN,
+        lines.join("\n")
+    );
+    lines.clear();
+    history.push(ChatMessage::user(&summary));
+    summaries.push(summary);
+}
+
+/// Summarization request for a history the provider rejected as too long
+///
+/// The oldest tool results are collapsed first, until the request fits in
+/// half of `context_limit` (estimated at `CHARS_PER_TOKEN`), and the model is
+/// asked for a much shorter summary than usual. Returns the request and the
+/// number of tool results collapsed.
+pub fn emergency_summarization_request(messages: &[ChatMessage], context_limit: usize) -> (Vec<ChatMessage>, usize) {
+    let instructions = EMERGENCY_COMPACTION.replace("{max_words}", &EMERGENCY_SUMMARY_WORDS.to_string());
+    let overhead = CONVERSATION_SUMMARIZATION.len() + instructions.len();
+    let budget = (context_limit / EMERGENCY_REQUEST_SHARE * CHARS_PER_TOKEN).saturating_sub(overhead);
+
+    let mut messages = messages.to_vec();
+    let collapsed = collapse_tool_results(&mut messages, budget);
+    let mut request = summarization_request(&messages, None, None);
+    if let Some(message) = request.first_mut() {
+        crate::provider::append_message_text(message, &format!("\n\n{}", instructions));
+    }
+    (request, collapsed)
+}
+
+/// Replace tool results with `COLLAPSED_TOOL_RESULT`, oldest first, until
+/// the text of `messages` is at most `max_chars`
+///
+/// Returns the number of tool results collapsed.
+pub fn collapse_tool_results(messages: &mut [ChatMessage], max_chars: usize) -> usize {
+    let mut total: usize = messages.iter().map(|m| message_text_content(m).len()).sum();
+    let mut collapsed = 0;
+    for message in messages.iter_mut() {
+        if total <= max_chars {
+            break;
+        }
+        if message.content.tool_responses().is_empty() {
+            continue;
+        }
+        let before = message_text_content(message).len();
+        let parts = std::mem::take(&mut message.content)
+            .into_parts()
+            .into_iter()
+            .map(|part| match part {
+                ContentPart::ToolResponse(response) if response.content != COLLAPSED_TOOL_RESULT => {
+                    collapsed += 1;
+                    ContentPart::ToolResponse(ToolResponse::new(response.call_id, COLLAPSED_TOOL_RESULT))
+                }
+                part => part,
+            })
+            .collect::<Vec<_>>();
+        message.content = MessageContent::from_parts(parts);
+        total = total - before + message_text_content(message).len();
+    }
+    collapsed
 }
 
 fn format_for_summarization(messages: &[ChatMessage]) -> String {
@@ -84,3 +406,139 @@ fn format_for_summarization(messages: &[ChatMessage]) -> String {
         .collect::<Vec<_>>()
         .join("\n\n")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::{assistant_with_tool_calls, tool_result_message, ToolCall};
+
+    /// A turn reading a file: the request, the call, its result and the answer
+    fn turn(n: usize) -> Vec<ChatMessage> {
+        let call = ToolCall {
+            call_id: format!("call_{}", n),
+            fn_name: "Read".to_string(),
+            fn_arguments: serde_json::json!({ "path": format!("file{}.rs", n) }),
+            thought_signatures: None,
+        };
+        vec![
+            ChatMessage::user(format!("question {}", n)),
+            assistant_with_tool_calls(None, vec![call]),
+            tool_result_message(format!("call_{}", n), "x".repeat(500)),
+            ChatMessage::assistant(format!("answer {}", n)),
+        ]
+    }
+
+    fn texts(messages: &[ChatMessage]) -> Vec<String> {
+        messages.iter().map(message_text_content).collect()
+    }
+
+    #[test]
+    fn test_oldest_tool_results_collapsed_first() {
+        let mut messages = vec![
+            ChatMessage::user("read both files"),
+            tool_result_message("call_1", "a".repeat(1000)),
+            tool_result_message("call_2", "b".repeat(1000)),
+        ];
+        assert_eq!(collapse_tool_results(&mut messages, 1500), 1);
+        assert_eq!(message_text_content(&messages[1]), COLLAPSED_TOOL_RESULT);
+        assert_eq!(message_text_content(&messages[2]).len(), 1000);
+
+        // Already small enough: nothing changes
+        assert_eq!(collapse_tool_results(&mut messages, 1500), 0);
+    }
+
+    #[test]
+    fn test_emergency_request_asks_for_a_short_summary() {
+        let messages = vec![ChatMessage::user("hello"), tool_result_message("call_1", "x".repeat(100_000))];
+        let (request, collapsed) = emergency_summarization_request(&messages, 10_000);
+        assert_eq!(collapsed, 1);
+        let text = message_text_content(&request[0]);
+        assert!(text.contains(COLLAPSED_TOOL_RESULT));
+        assert!(text.contains(&format!("at most about {} words", EMERGENCY_SUMMARY_WORDS)));
+    }
+
+    #[test]
+    fn test_heuristic_keeps_first_and_last_turns() {
+        let messages: Vec<ChatMessage> = (1..=6).flat_map(turn).collect();
+        let result = heuristic_compact(&messages, 2, is_summary);
+
+        let kept = texts(&result.messages);
+        // Two turns, the condensed middle, two turns
+        assert_eq!(kept.len(), 4 * 2 + 1 + 4 * 2);
+        assert_eq!(kept[0], "question 1");
+        assert_eq!(kept[9], "question 5");
+        assert!(kept[8].starts_with(SUMMARY_OPEN));
+        assert!(kept[8].contains("Human: question 3"));
+        assert!(kept[8].contains("Assistant: [called Read]"));
+        assert!(kept[8].contains("Assistant: answer 4"));
+        assert!(!kept[8].contains("xxx"));
+        assert_eq!(result.messages_summarized, 8);
+        assert_eq!(result.mode, CompactionMode::Heuristic);
+        assert!(result.tokens_saved() > 0);
+
+        // The same history always compacts to the same result
+        let again = heuristic_compact(&messages, 2, is_summary);
+        assert_eq!(
+            serde_json::to_value(&again.messages).unwrap(),
+            serde_json::to_value(&result.messages).unwrap()
+        );
+        assert_eq!(again.summary, result.summary);
+    }
+
+    #[test]
+    fn test_heuristic_never_removes_pinned_messages() {
+        let mut messages: Vec<ChatMessage> = (1..=2).flat_map(turn).collect();
+        messages.push(ChatMessage::user("<summary>\nThe build uses cargo-make\n</summary>"));
+        messages.extend((3..=6).flat_map(turn));
+
+        let result = heuristic_compact(&messages, 1, is_summary);
+        let kept = texts(&result.messages);
+        assert!(kept.contains(&"<summary>\nThe build uses cargo-make\n</summary>".to_string()));
+
+        // Compacting again keeps both the old summary and the new ones
+        let twice = heuristic_compact(&result.messages, 1, is_summary);
+        for summary in kept.iter().filter(|text| text.starts_with(SUMMARY_OPEN)) {
+            assert!(texts(&twice.messages).contains(summary));
+        }
+
+        // Pinned tool results survive collapsing too
+        let messages = turn(1).into_iter().chain(turn(2)).collect::<Vec<_>>();
+        let pinned_results = heuristic_compact(&messages, 1, |m| m.role == ChatRole::Tool);
+        assert_eq!(texts(&pinned_results.messages)[2].len(), 500);
+        let collapsed = heuristic_compact(&messages, 1, is_summary);
+        assert_eq!(texts(&collapsed.messages)[2], COLLAPSED_TOOL_RESULT);
+        assert_eq!(texts(&collapsed.messages)[6].len(), 500);
+    }
+
+    #[test]
+    fn test_llm_compaction_keeps_pinned_messages() {
+        let mut messages: Vec<ChatMessage> = (1..=3).flat_map(turn).collect();
+        messages.insert(4, ChatMessage::user("Never touch the generated bindings"));
+        let pinned = |m: &ChatMessage| message_text_content(m).starts_with("Never") || message_text_content(m) == "answer 2";
+
+        let result = compact_result(1000, messages.len(), Some("<summary>done</summary>".to_string()))
+            .with_pinned(&messages, pinned);
+        assert_eq!(
+            texts(&result.messages),
+            vec!["<summary>done</summary>", "Never touch the generated bindings", "answer 2"]
+        );
+        assert_eq!(result.messages[2].role, ChatRole::Assistant);
+        assert!(result.messages[2].content.tool_calls().is_empty());
+        assert_eq!(result.chars_after, "<summary>done</summary>".len() + 34 + 8);
+    }
+
+    #[test]
+    fn test_summarization_template_variables() {
+        let messages = vec![ChatMessage::user("fix the parser")];
+        let request = summarization_request(&messages, Some("error handling"), Some("Focus: {focus}\n\n{transcript}"));
+        let text = message_text_content(&request[0]);
+        assert!(text.starts_with("Focus: error handling\n\n"));
+        assert!(text.contains("fix the parser"));
+
+        // Without {transcript} the conversation comes first
+        let request = summarization_request(&messages, None, Some("Summarize. {focus}"));
+        let text = message_text_content(&request[0]);
+        assert!(text.contains("fix the parser"));
+        assert!(text.ends_with("Summarize. "));
+    }
+}
@@ -0,0 +1,83 @@
+//! API keys in the OS keychain
+//!
+//! A provider with `api_key_storage = "keyring"` keeps its key in the
+//! platform's credential store (macOS Keychain, Windows Credential Manager,
+//! or the Secret Service on Linux) rather than in plain text in
+//! `config.toml`. Entries live under the `cowork` service with the provider
+//! name as the account, so providers of the same type share a key.
+//!
+//! Where there is no keychain (a headless CI machine, say), reading a key
+//! logs a warning once and `ProviderConfig::get_api_key` falls back to the
+//! provider's environment variable; writing one fails with an error.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use keyring::Entry;
+
+use crate::error::{Error, Result};
+
+/// Keychain service the API keys are stored under
+pub const KEYRING_SERVICE: &str = "cowork";
+
+/// Whether the missing keychain has been warned about
+static UNAVAILABLE_WARNED: AtomicBool = AtomicBool::new(false);
+
+fn entry(provider: &str) -> Result<Entry> {
+    Entry::new(KEYRING_SERVICE, provider).map_err(keyring_error)
+}
+
+fn keyring_error(e: keyring::Error) -> Error {
+    Error::Config(format!("OS keychain: {}", e))
+}
+
+/// Whether an OS keychain can be used here
+pub fn keyring_available() -> bool {
+    // Looking up an entry that isn't there only succeeds with a working store
+    let probe = entry("cowork-probe").and_then(|entry| match entry.get_password() {
+        Ok(_) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(keyring_error(e)),
+    });
+    probe.is_ok()
+}
+
+/// A provider's API key from the keychain, None if there is none
+pub fn read_api_key(provider: &str) -> Result<Option<String>> {
+    match entry(provider)?.get_password() {
+        Ok(key) => Ok(Some(key)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(keyring_error(e)),
+    }
+}
+
+/// Store a provider's API key in the keychain, replacing any there
+pub fn store_api_key(provider: &str, key: &str) -> Result<()> {
+    let key = key.trim();
+    if key.is_empty() {
+        return Err(Error::Config(format!("Empty API key for {}", provider)));
+    }
+    entry(provider)?.set_password(key).map_err(keyring_error)
+}
+
+/// Delete a provider's API key from the keychain, returning whether there
+/// was one
+pub fn delete_api_key(provider: &str) -> Result<bool> {
+    match entry(provider)?.delete_credential() {
+        Ok(()) => Ok(true),
+        Err(keyring::Error::NoEntry) => Ok(false),
+        Err(e) => Err(keyring_error(e)),
+    }
+}
+
+/// A provider's API key from the keychain for `ProviderConfig::get_api_key`;
+/// a keychain that can't be reached is warned about once and treated as empty
+pub(crate) fn keyring_api_key(provider: &str) -> Option<String> {
+    match read_api_key(provider) {
+        Ok(key) => key.filter(|key| !key.is_empty()),
+        Err(e) => {
+            if !UNAVAILABLE_WARNED.swap(true, Ordering::Relaxed) {
+                tracing::warn!("{}; using API keys from environment variables instead", e);
+            }
+            None
+        }
+    }
+}
@@ -0,0 +1,223 @@
+//! Opening files in the user's editor
+//!
+//! Frontends call `open_in_editor` when someone asks to see a file a tool
+//! worked on. The command comes from `[general] editor`, a template such as
+//! `code --goto {file}:{line}`; without one, a known editor found on PATH
+//! (VS Code, Cursor, a JetBrains IDE) is used, and failing that the system's
+//! default opener. Only files inside the workspace roots are opened. The
+//! editor runs detached: nothing waits for it, and it stays open after cowork exits.
+
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
+use crate::tools::filesystem::validate_path_in_roots;
+
+/// Editors looked for on PATH, most preferred first, with their command templates
+const KNOWN_EDITORS: &[(&str, &str)] = &[
+    ("code", "code --goto {file}:{line}:{column}"),
+    ("cursor", "cursor --goto {file}:{line}:{column}"),
+    ("idea", "idea --line {line} --column {column} {file}"),
+    ("rustrover", "rustrover --line {line} --column {column} {file}"),
+    ("pycharm", "pycharm --line {line} --column {column} {file}"),
+    ("webstorm", "webstorm --line {line} --column {column} {file}"),
+    ("goland", "goland --line {line} --column {column} {file}"),
+    ("clion", "clion --line {line} --column {column} {file}"),
+];
+
+/// The command for a template: split like a shell would, with `{file}`,
+/// `{line}` and `{column}` replaced in each argument
+///
+/// A missing line or column is 1. A template without `{file}` gets the file
+/// as its last argument.
+pub fn expand_template(template: &str, file: &Path, line: Option<u32>, column: Option<u32>) -> Result<Vec<String>> {
+    let words = shlex::split(template)
+        .filter(|words| !words.is_empty())
+        .ok_or_else(|| Error::Config(format!("Editor command {:?} is empty or has unbalanced quotes", template)))?;
+    let file = file.to_string_lossy();
+    let (line, column) = (line.unwrap_or(1).to_string(), column.unwrap_or(1).to_string());
+    let mut command: Vec<String> = words
+        .iter()
+        .map(|word| word.replace("{file}", &file).replace("{line}", &line).replace("{column}", &column))
+        .collect();
+    if !words.iter().any(|word| word.contains("{file}")) {
+        command.push(file.to_string());
+    }
+    Ok(command)
+}
+
+/// Find an executable on PATH
+fn find_on_path(name: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    let names: Vec<String> = if cfg!(windows) {
+        vec![format!("{}.cmd", name), format!("{}.exe", name)]
+    } else {
+        vec![name.to_string()]
+    };
+    std::env::split_paths(&path)
+        .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Template of the first known editor on PATH
+pub fn detect_editor() -> Option<&'static str> {
+    KNOWN_EDITORS
+        .iter()
+        .find(|(launcher, _)| find_on_path(launcher).is_some())
+        .map(|(_, template)| *template)
+}
+
+/// The system's default opener for a file (no line support)
+fn system_opener(file: &Path) -> Vec<String> {
+    let file = file.to_string_lossy().to_string();
+    if cfg!(target_os = "macos") {
+        vec!["open".to_string(), file]
+    } else if cfg!(windows) {
+        vec!["cmd".to_string(), "/C".to_string(), "start".to_string(), String::new(), file]
+    } else {
+        vec!["xdg-open".to_string(), file]
+    }
+}
+
+/// The command that opens `file`: the configured template, else a detected
+/// editor, else the system's opener
+pub fn editor_command(template: Option<&str>, file: &Path, line: Option<u32>, column: Option<u32>) -> Result<Vec<String>> {
+    match template.filter(|t| !t.trim().is_empty()).or_else(|| detect_editor()) {
+        Some(template) => expand_template(template, file, line, column),
+        None => Ok(system_opener(file)),
+    }
+}
+
+/// The file at `path` if it is inside one of `roots` (relative paths resolve
+/// against the first root)
+pub fn resolve_in_roots(path: &str, roots: &[PathBuf]) -> Result<PathBuf> {
+    let primary = roots.first().ok_or_else(|| Error::Workspace("No workspace to open files in".to_string()))?;
+    let file = validate_path_in_roots(&primary.join(path), roots)?;
+    if !file.is_file() {
+        return Err(Error::Workspace(format!("{} is not a file", file.display())));
+    }
+    Ok(file)
+}
+
+/// Start the editor detached, without waiting for it
+fn launch(command: &[String]) -> std::io::Result<()> {
+    let (program, args) = command.split_first().ok_or_else(|| std::io::Error::other("empty editor command"))?;
+    let mut process = std::process::Command::new(program);
+    process
+        .args(args)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null());
+    // Its own process group, so signals meant for cowork don't reach it
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut process, 0);
+    let mut child = process.spawn()?;
+    // Reap it whenever it exits (launchers like `code` exit at once)
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+/// Open a file in the workspace in the user's editor; returns the command run
+pub fn open_in_editor(
+    template: Option<&str>,
+    roots: &[PathBuf],
+    path: &str,
+    line: Option<u32>,
+    column: Option<u32>,
+) -> Result<Vec<String>> {
+    open_in_editor_with(template, roots, path, line, column, launch)
+}
+
+/// `open_in_editor` with the process start left to `launch`
+pub fn open_in_editor_with(
+    template: Option<&str>,
+    roots: &[PathBuf],
+    path: &str,
+    line: Option<u32>,
+    column: Option<u32>,
+    launch: impl FnOnce(&[String]) -> std::io::Result<()>,
+) -> Result<Vec<String>> {
+    let file = resolve_in_roots(path, roots)?;
+    let command = editor_command(template, &file, line, column)?;
+    launch(&command).map_err(|e| Error::Config(format!("Couldn't start editor {}: {}", command[0], e)))?;
+    Ok(command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_template() {
+        let file = Path::new("/work/app/src/my file.rs");
+        assert_eq!(
+            expand_template("code --goto {file}:{line}:{column}", file, Some(12), Some(4)).unwrap(),
+            ["code", "--goto", "/work/app/src/my file.rs:12:4"]
+        );
+        // Missing positions are 1; quoted words stay whole
+        assert_eq!(
+            expand_template("\"/opt/My IDE/bin/idea\" --line {line} {file}", file, None, None).unwrap(),
+            ["/opt/My IDE/bin/idea", "--line", "1", "/work/app/src/my file.rs"]
+        );
+        // Without {file}, the file goes last
+        assert_eq!(expand_template("subl -n", file, Some(3), None).unwrap(), ["subl", "-n", "/work/app/src/my file.rs"]);
+
+        assert!(expand_template("code \"--goto {file}", file, None, None).is_err());
+        assert!(expand_template("  ", file, None, None).is_err());
+    }
+
+    #[test]
+    fn test_only_files_in_the_workspace_are_opened() {
+        let workspace = tempfile::tempdir().unwrap();
+        let other = tempfile::tempdir().unwrap();
+        std::fs::create_dir(workspace.path().join("src")).unwrap();
+        std::fs::write(workspace.path().join("src/lib.rs"), "").unwrap();
+        std::fs::write(other.path().join("secret.txt"), "").unwrap();
+        let roots = [dunce::canonicalize(workspace.path()).unwrap()];
+
+        let file = resolve_in_roots("src/lib.rs", &roots).unwrap();
+        assert_eq!(file, roots[0].join("src/lib.rs"));
+        let absolute = roots[0].join("src/lib.rs");
+        assert_eq!(resolve_in_roots(&absolute.to_string_lossy(), &roots).unwrap(), file);
+
+        let outside = other.path().join("secret.txt");
+        assert!(matches!(
+            resolve_in_roots(&outside.to_string_lossy(), &roots),
+            Err(Error::Tool(crate::error::ToolError::PermissionDenied(_)))
+        ));
+        let escape = format!("../{}/secret.txt", other.path().file_name().unwrap().to_string_lossy());
+        assert!(resolve_in_roots(&escape, &roots).is_err());
+        assert!(resolve_in_roots("src", &roots).is_err());
+        assert!(resolve_in_roots("missing.rs", &roots).is_err());
+    }
+
+    #[test]
+    fn test_open_runs_the_configured_command() {
+        let workspace = tempfile::tempdir().unwrap();
+        std::fs::write(workspace.path().join("main.rs"), "").unwrap();
+        let roots = [dunce::canonicalize(workspace.path()).unwrap()];
+
+        let mut launched = Vec::new();
+        let command = open_in_editor_with(Some("myedit +{line} {file}"), &roots, "main.rs", Some(7), None, |command| {
+            launched = command.to_vec();
+            Ok(())
+        })
+        .unwrap();
+        let file = roots[0].join("main.rs").to_string_lossy().to_string();
+        assert_eq!(launched, ["myedit", "+7", file.as_str()]);
+        assert_eq!(command, launched);
+
+        let err = open_in_editor_with(Some("missing-editor {file}"), &roots, "main.rs", None, None, |_| {
+            Err(std::io::Error::from(std::io::ErrorKind::NotFound))
+        })
+        .unwrap_err();
+        assert!(err.to_string().contains("missing-editor"), "{}", err);
+
+        let mut called = false;
+        let outside = open_in_editor_with(Some("myedit {file}"), &roots, "/etc/hosts", None, None, |_| {
+            called = true;
+            Ok(())
+        });
+        assert!(outside.is_err());
+        assert!(!called);
+    }
+}
@@ -0,0 +1,432 @@
+//! Embedding API - run the agent loop inside another application
+//!
+//! A small, stable facade over the session machinery for hosts that are
+//! neither the CLI nor the desktop app. `AgentBuilder` takes the workspace,
+//! the provider, an optional `Approver`, the tool profile and whether hooks
+//! run; `start` returns an `Agent` handle to talk to the session through.
+//!
+//! Everything this module exports is covered by semver: removing or
+//! changing an item is a breaking change, adding one is not. That holds for
+//! outputs and tool requests too: `SessionOutput`, its variants and
+//! `ToolRequest` are `#[non_exhaustive]`, so new outputs and fields can be
+//! added without breaking a host's matches. The rest of the crate may change
+//! between releases without notice, so hosts should reach the agent loop
+//! only through here. `tests/embed_api_tests.rs` snapshots the module's
+//! public items and `SessionOutput`'s variants and fields, so a change to
+//! them fails the build until the snapshot is updated on purpose. Items
+//! behind the `test-util` feature are for tests and not covered.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use cowork_core::embed::{AgentBuilder, Approver, BoxFuture, Decision, ProviderSettings, SessionOutput, ToolRequest};
+//!
+//! /// Lets the model read, and refuses everything else
+//! struct ReadOnly;
+//!
+//! impl Approver for ReadOnly {
+//!     fn decide<'a>(&'a self, request: &'a ToolRequest) -> BoxFuture<'a, Decision> {
+//!         Box::pin(async move {
+//!             match request.tool.as_str() {
+//!                 "Read" | "Glob" | "Grep" => Decision::Approve,
+//!                 _ => Decision::Reject { reason: Some("This host is read-only".to_string()) },
+//!             }
+//!         })
+//!     }
+//! }
+//!
+//! # async fn run() -> cowork_core::Result<()> {
+//! let mut agent = AgentBuilder::new("/path/to/project")
+//!     .with_provider(ProviderSettings::new("anthropic").with_model("claude-sonnet-4-5-20250929"))
+//!     .with_approver(ReadOnly)
+//!     .with_hooks(false)
+//!     .start()
+//!     .await?;
+//!
+//! agent.send_message("Summarize the README").await?;
+//! while let Some(output) = agent.next_output().await {
+//!     match output {
+//!         SessionOutput::AssistantMessage { content, .. } => println!("{}", content),
+//!         SessionOutput::Idle { .. } => break,
+//!         _ => {}
+//!     }
+//! }
+//! agent.stop().await
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+
+use crate::error::{Error, Result};
+use crate::orchestration::SystemPrompt;
+use crate::prompt::TemplateVars;
+use crate::session::{OutputReceiver, SessionConfig, SessionInput, SessionManager};
+
+pub use crate::orchestration::ToolProfile;
+pub use crate::session::{QuestionInfo, QuestionOption, SessionOutput, ToolDoneStatus};
+
+/// ID of the one session an `Agent` runs
+const EMBEDDED_SESSION: &str = "embedded";
+
+/// A boxed future, as `Approver::decide` returns
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// The model provider a session talks to
+#[derive(Clone)]
+pub struct ProviderSettings {
+    provider_id: String,
+    model: Option<String>,
+    api_key: Option<String>,
+    base_url: Option<String>,
+}
+
+impl ProviderSettings {
+    /// A provider by its ID (`anthropic`, `openai`, `gemini`, ...), with its
+    /// default model and the API key from its usual environment variable
+    pub fn new(provider_id: impl Into<String>) -> Self {
+        Self {
+            provider_id: provider_id.into(),
+            model: None,
+            api_key: None,
+            base_url: None,
+        }
+    }
+
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// An OpenAI-compatible endpoint or a gateway in front of the provider
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    pub fn provider_id(&self) -> &str {
+        &self.provider_id
+    }
+
+    /// The model, when one is set (None = the provider's default)
+    pub fn model(&self) -> Option<&str> {
+        self.model.as_deref()
+    }
+
+    pub fn base_url(&self) -> Option<&str> {
+        self.base_url.as_deref()
+    }
+}
+
+impl Default for ProviderSettings {
+    fn default() -> Self {
+        Self::new(SessionConfig::default().provider_id)
+    }
+}
+
+// The API key stays out of logs
+impl std::fmt::Debug for ProviderSettings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProviderSettings")
+            .field("provider_id", &self.provider_id)
+            .field("model", &self.model)
+            .field("api_key", &self.api_key.as_ref().map(|_| "[set]"))
+            .field("base_url", &self.base_url)
+            .finish()
+    }
+}
+
+/// A tool call waiting for approval
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct ToolRequest {
+    /// Tool call ID, as `Agent::approve` and `Agent::reject` take it
+    pub id: String,
+    /// Tool name (`Bash`, `Edit`, ...)
+    pub tool: String,
+    pub arguments: serde_json::Value,
+    /// What the call is for, when the tool says
+    pub description: Option<String>,
+    /// Organization policy requires a person to approve the call
+    pub policy_required: bool,
+    /// The call looks like it exfiltrates secrets; a person should approve it
+    pub security_warning: Option<String>,
+    /// Generated files (lockfiles, build output) the call changes on purpose;
+    /// a person should approve it
+    pub generated_files: Vec<String>,
+}
+
+/// An `Approver`'s answer to a tool call
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    /// Run the call
+    Approve,
+    /// Don't run it; the reason is passed to the model
+    Reject { reason: Option<String> },
+    /// Leave it to the host: `Agent::next_output` returns the
+    /// `SessionOutput::ToolPending` for `Agent::approve` or `Agent::reject`
+    Defer,
+}
+
+/// Decides tool calls that need approval, implemented by the host
+///
+/// Calls the session's approval rules settle never reach the approver.
+pub trait Approver: Send + Sync {
+    fn decide<'a>(&'a self, request: &'a ToolRequest) -> BoxFuture<'a, Decision>;
+}
+
+/// What an `Agent` was started with
+#[derive(Debug, Clone)]
+pub struct AgentSettings {
+    workspace: PathBuf,
+    provider: ProviderSettings,
+    tool_profile: ToolProfile,
+    hooks: bool,
+    system_prompt: Option<String>,
+}
+
+impl AgentSettings {
+    pub fn workspace(&self) -> &Path {
+        &self.workspace
+    }
+
+    pub fn provider(&self) -> &ProviderSettings {
+        &self.provider
+    }
+
+    pub fn tool_profile(&self) -> &ToolProfile {
+        &self.tool_profile
+    }
+
+    /// Whether hooks from the workspace and user settings run
+    pub fn hooks(&self) -> bool {
+        self.hooks
+    }
+
+    /// The host's system prompt (None = the built-in one)
+    pub fn system_prompt(&self) -> Option<&str> {
+        self.system_prompt.as_deref()
+    }
+}
+
+/// Configures and starts an `Agent`
+pub struct AgentBuilder {
+    settings: AgentSettings,
+    approver: Option<Arc<dyn Approver>>,
+    #[cfg(feature = "test-util")]
+    mock_provider: Option<Arc<crate::provider::MockProvider>>,
+}
+
+impl AgentBuilder {
+    /// An agent working in `workspace`, with the default provider, every
+    /// tool, hooks on and no approver
+    pub fn new(workspace: impl Into<PathBuf>) -> Self {
+        Self {
+            settings: AgentSettings {
+                workspace: workspace.into(),
+                provider: ProviderSettings::default(),
+                tool_profile: ToolProfile::full(),
+                hooks: true,
+                system_prompt: None,
+            },
+            approver: None,
+            #[cfg(feature = "test-util")]
+            mock_provider: None,
+        }
+    }
+
+    pub fn with_provider(mut self, provider: ProviderSettings) -> Self {
+        self.settings.provider = provider;
+        self
+    }
+
+    /// Decide tool calls that need approval in the host (without one, every
+    /// such call comes out of `Agent::next_output` as `ToolPending`)
+    pub fn with_approver(mut self, approver: impl Approver + 'static) -> Self {
+        self.approver = Some(Arc::new(approver));
+        self
+    }
+
+    /// The tools offered to the model
+    pub fn with_tool_profile(mut self, profile: ToolProfile) -> Self {
+        self.settings.tool_profile = profile;
+        self
+    }
+
+    /// Run hooks from the workspace and user settings
+    pub fn with_hooks(mut self, enabled: bool) -> Self {
+        self.settings.hooks = enabled;
+        self
+    }
+
+    /// Replace the built-in system prompt
+    pub fn with_system_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.settings.system_prompt = Some(prompt.into());
+        self
+    }
+
+    /// Drive the session with scripted responses instead of a provider
+    #[cfg(feature = "test-util")]
+    pub fn with_mock_provider(mut self, mock: Arc<crate::provider::MockProvider>) -> Self {
+        self.mock_provider = Some(mock);
+        self
+    }
+
+    /// Start the session
+    ///
+    /// Fails if the workspace isn't a directory or the provider can't be
+    /// set up (e.g. it has no API key).
+    pub async fn start(self) -> Result<Agent> {
+        let settings = self.settings;
+        if !settings.workspace.is_dir() {
+            return Err(Error::Workspace(format!("{} is not a directory", settings.workspace.display())));
+        }
+
+        let system_prompt = settings.system_prompt.clone().unwrap_or_else(|| {
+            SystemPrompt::new()
+                .with_template_vars(TemplateVars::gather(&settings.workspace))
+                .build()
+        });
+        let provider = &settings.provider;
+        let mut config = SessionConfig::new(settings.workspace.clone())
+            .with_provider(provider.provider_id.clone())
+            .with_system_prompt(system_prompt)
+            .with_tool_profile(settings.tool_profile.clone())
+            .with_enable_hooks(settings.hooks)
+            .with_save_session(false);
+        if let Some(model) = &provider.model {
+            config = config.with_model(model.clone());
+        }
+        if let Some(api_key) = &provider.api_key {
+            config = config.with_api_key(api_key.clone());
+        }
+        if let Some(base_url) = &provider.base_url {
+            config = config.with_base_url(base_url.clone());
+        }
+        #[cfg(feature = "test-util")]
+        if let Some(mock) = self.mock_provider {
+            config = config.with_mock_provider(mock);
+        }
+
+        let (manager, outputs) = SessionManager::with_config(config);
+        manager.create_session(EMBEDDED_SESSION).await?;
+        Ok(Agent {
+            manager,
+            outputs,
+            settings,
+            approver: self.approver,
+            routes: HashMap::new(),
+        })
+    }
+}
+
+/// A running session
+///
+/// Outputs queue up until `next_output` takes them, so a host should keep
+/// reading while a turn runs.
+pub struct Agent {
+    manager: SessionManager,
+    outputs: OutputReceiver,
+    settings: AgentSettings,
+    approver: Option<Arc<dyn Approver>>,
+    /// Session to answer each pending approval or question in (a subagent's
+    /// calls are answered in the subagent)
+    routes: HashMap<String, String>,
+}
+
+impl Agent {
+    pub fn settings(&self) -> &AgentSettings {
+        &self.settings
+    }
+
+    /// Send a user message; it waits behind the turn in flight, if any
+    pub async fn send_message(&self, text: impl Into<String>) -> Result<()> {
+        self.push(EMBEDDED_SESSION, SessionInput::user_message(text)).await
+    }
+
+    /// The session's next output (None once the session has stopped)
+    ///
+    /// Tool calls the approver settles are answered here and not returned.
+    pub async fn next_output(&mut self) -> Option<SessionOutput> {
+        loop {
+            let (session_id, output) = self.outputs.recv().await?;
+            if session_id != EMBEDDED_SESSION {
+                continue;
+            }
+            match &output {
+                SessionOutput::ToolPending { id, name, arguments, description, subagent_id, policy_required, security_warning, generated_files } => {
+                    let target = subagent_id.clone().unwrap_or_else(|| EMBEDDED_SESSION.to_string());
+                    let request = ToolRequest {
+                        id: id.clone(),
+                        tool: name.clone(),
+                        arguments: arguments.clone(),
+                        description: description.clone(),
+                        policy_required: *policy_required,
+                        security_warning: security_warning.clone(),
+                        generated_files: generated_files.iter().map(|file| file.path.clone()).collect(),
+                    };
+                    let decision = match &self.approver {
+                        Some(approver) => approver.decide(&request).await,
+                        None => Decision::Defer,
+                    };
+                    let input = match decision {
+                        Decision::Approve => SessionInput::approve_tool(&request.id),
+                        Decision::Reject { reason } => SessionInput::reject_tool(&request.id, reason),
+                        Decision::Defer => {
+                            self.routes.insert(request.id, target);
+                            return Some(output);
+                        }
+                    };
+                    if let Err(e) = self.push(&target, input).await {
+                        tracing::warn!("Approver's decision on {} not delivered: {}", id, e);
+                    }
+                }
+                SessionOutput::Question { request_id, subagent_id: Some(subagent), .. } => {
+                    self.routes.insert(request_id.clone(), subagent.clone());
+                    return Some(output);
+                }
+                _ => return Some(output),
+            }
+        }
+    }
+
+    /// Approve a tool call `next_output` returned as `ToolPending`
+    pub async fn approve(&mut self, tool_call_id: &str) -> Result<()> {
+        let target = self.route(tool_call_id);
+        self.push(&target, SessionInput::approve_tool(tool_call_id)).await
+    }
+
+    /// Reject a tool call `next_output` returned as `ToolPending`
+    pub async fn reject(&mut self, tool_call_id: &str, reason: Option<String>) -> Result<()> {
+        let target = self.route(tool_call_id);
+        self.push(&target, SessionInput::reject_tool(tool_call_id, reason)).await
+    }
+
+    /// Answer a `SessionOutput::Question`: each question's text to the
+    /// chosen option's label (or the host's own answer)
+    pub async fn answer(&mut self, request_id: &str, answers: HashMap<String, String>) -> Result<()> {
+        let target = self.route(request_id);
+        self.push(&target, SessionInput::answer_question(request_id, answers)).await
+    }
+
+    /// Stop the session, rejecting anything still waiting for an answer
+    pub async fn stop(self) -> Result<()> {
+        self.manager.stop_all()
+    }
+
+    fn route(&mut self, id: &str) -> String {
+        self.routes.remove(id).unwrap_or_else(|| EMBEDDED_SESSION.to_string())
+    }
+
+    async fn push(&self, session_id: &str, input: SessionInput) -> Result<()> {
+        self.manager.push_message(session_id, input).await
+    }
+}
@@ -43,6 +43,25 @@ pub enum Error {
 
     #[error("Operation cancelled")]
     Cancelled,
+
+    #[error("Session limit reached: at most {0} sessions can be open at once")]
+    SessionLimit(usize),
+
+    #[error("Recipe error: {0}")]
+    Recipe(String),
+
+    #[error("Schedule error: {0}")]
+    Schedule(String),
+
+    #[error(
+        "Context overflow: the conversation{} is too long for the model's context window{} even after compacting it; start over with /clear, or start a session with a narrower task",
+        .tokens.map(|t| format!(" ({} tokens)", t)).unwrap_or_default(),
+        .limit.map(|l| format!(" ({} tokens)", l)).unwrap_or_default()
+    )]
+    ContextOverflow { tokens: Option<u64>, limit: Option<u64> },
+
+    #[error("Merging {0} conflicts in {files}; the merge was aborted and the worktree kept for resolving by hand", files = .1.join(", "))]
+    MergeConflict(String, Vec<String>),
 }
 
 /// Tool-specific errors
@@ -60,12 +79,27 @@ pub enum ToolError {
     #[error("Permission denied: {0}")]
     PermissionDenied(String),
 
+    #[error("Policy violation: {0}")]
+    PolicyViolation(String),
+
     #[error("Resource not found: {0}")]
     ResourceNotFound(String),
 
     #[error("Rejected by user: {0}")]
     Rejected(String),
 
+    #[error("Connection to remote workspace lost: {0}")]
+    ConnectionLost(String),
+
+    #[error("Cancelled by user")]
+    Cancelled,
+
+    #[error("{path} is being modified by session '{session}', retry or coordinate with it")]
+    FileLocked { path: String, session: String },
+
+    #[error("{path} is {reason}: {action}. Only if it really must be changed by hand, retry with allow_generated: true, which a person has to approve")]
+    GeneratedFile { path: String, reason: String, action: String },
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 }
@@ -3,6 +3,8 @@
 //! This module provides consistent formatting of tool calls and results
 //! for both UI display and LLM consumption.
 
+use std::path::{Path, PathBuf};
+
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
@@ -38,6 +40,93 @@ pub fn format_size(bytes: u64) -> String {
     }
 }
 
+// ============================================================================
+// Path display
+// ============================================================================
+
+/// Renders paths for people: relative to the workspace when inside it, else
+/// `~/`-relative inside the home directory, else absolute
+///
+/// Works on the path text, so Unix and Windows paths are handled alike on
+/// any platform. Relative results use `/` separators; paths that are already
+/// relative are shown as given. The default renders every path as given.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PathDisplay {
+    workspace: Option<PathBuf>,
+    home: Option<PathBuf>,
+}
+
+impl PathDisplay {
+    /// Display paths relative to `workspace`, or to the home directory
+    pub fn new(workspace: impl Into<PathBuf>) -> Self {
+        Self {
+            workspace: Some(workspace.into()),
+            home: dirs::home_dir(),
+        }
+    }
+
+    /// Use `home` as the home directory (None = don't shorten to `~`)
+    pub fn with_home(mut self, home: Option<PathBuf>) -> Self {
+        self.home = home;
+        self
+    }
+
+    /// The path as shown to people
+    pub fn display(&self, path: &str) -> String {
+        if let Some(workspace) = &self.workspace
+            && let Some(relative) = relative_to(path, workspace)
+        {
+            return if relative.is_empty() { ".".to_string() } else { relative };
+        }
+        if let Some(home) = &self.home
+            && let Some(relative) = relative_to(path, home)
+        {
+            return if relative.is_empty() { "~".to_string() } else { format!("~/{}", relative) };
+        }
+        path.to_string()
+    }
+
+    /// The file a tool argument names: relative paths resolve against the workspace
+    pub fn absolute(&self, path: &str) -> PathBuf {
+        match &self.workspace {
+            Some(workspace) if !is_absolute_text(path) => workspace.join(path),
+            _ => PathBuf::from(path),
+        }
+    }
+}
+
+/// Whether `path` is absolute as a Unix (`/x`) or Windows (`C:\x`, `\\server`) path
+fn is_absolute_text(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    path.starts_with('/')
+        || path.starts_with('\\')
+        || (bytes.len() >= 3 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' && matches!(bytes[2], b'/' | b'\\'))
+}
+
+/// `path` relative to `base` with `/` separators, if `path` is absolute and inside `base`
+///
+/// Windows paths (a drive letter) compare case-insensitively.
+fn relative_to(path: &str, base: &Path) -> Option<String> {
+    if !is_absolute_text(path) {
+        return None;
+    }
+    let normalize = |p: &str| p.replace('\\', "/").trim_end_matches('/').to_string();
+    let path = normalize(path);
+    let base = normalize(&base.to_string_lossy());
+    if base.is_empty() || !path.is_char_boundary(base.len()) {
+        return None;
+    }
+    let (head, rest) = path.split_at(base.len());
+    let windows = base.as_bytes().get(1) == Some(&b':');
+    let same = if windows { head.eq_ignore_ascii_case(&base) } else { head == base };
+    match rest.strip_prefix('/') {
+        _ if !same => None,
+        Some(relative) => Some(relative.to_string()),
+        None if rest.is_empty() => Some(String::new()),
+        None => None,
+    }
+}
+
 /// Truncate a tool result to prevent context overflow
 ///
 /// Large tool outputs (e.g., listing 3000+ files) can exceed the model's
@@ -181,15 +270,13 @@ fn truncate_at_line_boundary(result: &str, max_size: usize) -> String {
 // ============================================================================
 
 /// Format tool arguments into a concise single-line summary
-pub fn format_tool_summary(tool_name: &str, args: &Value) -> String {
+pub fn format_tool_summary(tool_name: &str, args: &Value, paths: &PathDisplay) -> String {
     match tool_name {
-        "Read" => args["file_path"].as_str().unwrap_or("?").to_string(),
-        "Write" => args["file_path"].as_str().unwrap_or("?").to_string(),
-        "Edit" => args["file_path"].as_str().unwrap_or("?").to_string(),
+        "Read" | "Write" | "Edit" => paths.display(args["file_path"].as_str().unwrap_or("?")),
         "Glob" => args["pattern"].as_str().unwrap_or("?").to_string(),
         "Grep" => {
             let pattern = args["pattern"].as_str().unwrap_or("?");
-            let path = args["path"].as_str().unwrap_or(".");
+            let path = paths.display(args["path"].as_str().unwrap_or("."));
             format!("{} in {}", pattern, path)
         }
         "Bash" => {
@@ -205,28 +292,52 @@ pub fn format_tool_summary(tool_name: &str, args: &Value) -> String {
         "WebSearch" => args["query"].as_str().unwrap_or("?").to_string(),
         "LSP" => {
             let op = args["operation"].as_str().unwrap_or("?");
-            let file = args["filePath"].as_str().unwrap_or("?");
+            let file = paths.display(args["filePath"].as_str().unwrap_or("?"));
             format!("{} {}", op, file)
         }
         _ => serde_json::to_string(args).unwrap_or_default(),
     }
 }
 
+/// Format a long-running tool's heartbeat, e.g. "Bash: 1m 23s, 45.2 KB output"
+/// with the latest output line beneath it
+pub fn format_tool_progress(tool_name: &str, elapsed_ms: u64, output_bytes: u64, last_line: Option<&str>) -> String {
+    let secs = elapsed_ms / 1000;
+    let elapsed = if secs >= 60 {
+        format!("{}m {}s", secs / 60, secs % 60)
+    } else {
+        format!("{}s", secs)
+    };
+    let mut progress = if output_bytes > 0 {
+        format!("{}: {}, {} output", tool_name, elapsed, format_size(output_bytes))
+    } else {
+        format!("{}: {}, no output yet", tool_name, elapsed)
+    };
+    if let Some(line) = last_line {
+        // Output lines may be any text, so cut on a character boundary
+        let shown: String = line.chars().take(77).collect();
+        let ellipsis = if shown.len() < line.len() { "..." } else { "" };
+        progress.push_str(&format!("\n  {}{}", shown, ellipsis));
+    }
+    progress
+}
+
 /// Format ephemeral display for tool execution (up to 3 lines)
-pub fn format_ephemeral(tool_name: &str, args: &Value) -> String {
+pub fn format_ephemeral(tool_name: &str, args: &Value, paths: &PathDisplay) -> String {
     let mut lines = Vec::new();
 
     match tool_name {
         "Read" | "Glob" => {
             let path = args["file_path"]
                 .as_str()
-                .or_else(|| args["pattern"].as_str())
-                .unwrap_or("?");
-            lines.push(format!("{}: {}", tool_name, truncate_str(path, 60)));
+                .map(|path| paths.display(path))
+                .or_else(|| args["pattern"].as_str().map(str::to_string))
+                .unwrap_or_else(|| "?".to_string());
+            lines.push(format!("{}: {}", tool_name, truncate_str(&path, 60)));
         }
         "Write" => {
             if let Some(path) = args["file_path"].as_str() {
-                lines.push(format!("Write: {}", truncate_str(path, 60)));
+                lines.push(format!("Write: {}", truncate_str(&paths.display(path), 60)));
             }
             if let Some(content) = args["content"].as_str() {
                 let line_count = content.lines().count();
@@ -235,7 +346,7 @@ pub fn format_ephemeral(tool_name: &str, args: &Value) -> String {
         }
         "Edit" => {
             if let Some(path) = args["file_path"].as_str() {
-                lines.push(format!("Edit: {}", truncate_str(path, 60)));
+                lines.push(format!("Edit: {}", truncate_str(&paths.display(path), 60)));
             }
             if let Some(old) = args["old_string"].as_str() {
                 let preview = old.lines().next().unwrap_or("");
@@ -248,11 +359,11 @@ pub fn format_ephemeral(tool_name: &str, args: &Value) -> String {
         }
         "Grep" => {
             let pattern = args["pattern"].as_str().unwrap_or("?");
-            let path = args["path"].as_str().unwrap_or(".");
+            let path = paths.display(args["path"].as_str().unwrap_or("."));
             lines.push(format!(
                 "Grep: {} in {}",
                 truncate_str(pattern, 30),
-                truncate_str(path, 30)
+                truncate_str(&path, 30)
             ));
         }
         "Bash" => {
@@ -272,7 +383,7 @@ pub fn format_ephemeral(tool_name: &str, args: &Value) -> String {
             lines.push(format!("Task [{}]: {}", agent, truncate_str(desc, 50)));
         }
         _ => {
-            let summary = format_tool_summary(tool_name, args);
+            let summary = format_tool_summary(tool_name, args, paths);
             lines.push(format!("{}: {}", tool_name, truncate_str(&summary, 60)));
         }
     }
@@ -282,14 +393,14 @@ pub fn format_ephemeral(tool_name: &str, args: &Value) -> String {
 }
 
 /// Format a tool call in Claude Code style: `ToolName(param: value, ...)`
-pub fn format_tool_call(tool_name: &str, args: &Value) -> String {
+pub fn format_tool_call(tool_name: &str, args: &Value, paths: &PathDisplay) -> String {
     match tool_name {
         "Read" => {
-            let path = args["file_path"].as_str().unwrap_or("?");
+            let path = paths.display(args["file_path"].as_str().unwrap_or("?"));
             format!("Read({})", path)
         }
         "Write" => {
-            let path = args["file_path"].as_str().unwrap_or("?");
+            let path = paths.display(args["file_path"].as_str().unwrap_or("?"));
             let lines = args["content"]
                 .as_str()
                 .map(|c| c.lines().count())
@@ -297,20 +408,20 @@ pub fn format_tool_call(tool_name: &str, args: &Value) -> String {
             format!("Write({}, {} lines)", path, lines)
         }
         "Edit" => {
-            let path = args["file_path"].as_str().unwrap_or("?");
+            let path = paths.display(args["file_path"].as_str().unwrap_or("?"));
             format!("Edit({})", path)
         }
         "Glob" => {
             let pattern = args["pattern"].as_str().unwrap_or("?");
             if let Some(path) = args["path"].as_str() {
-                format!("Glob(pattern: \"{}\", path: \"{}\")", pattern, path)
+                format!("Glob(pattern: \"{}\", path: \"{}\")", pattern, paths.display(path))
             } else {
                 format!("Glob(\"{}\")", pattern)
             }
         }
         "Grep" => {
             let pattern = args["pattern"].as_str().unwrap_or("?");
-            let path = args["path"].as_str().unwrap_or(".");
+            let path = paths.display(args["path"].as_str().unwrap_or("."));
             format!("Grep(pattern: \"{}\", path: \"{}\")", truncate_str(pattern, 30), path)
         }
         "Bash" => {
@@ -333,9 +444,13 @@ pub fn format_tool_call(tool_name: &str, args: &Value) -> String {
         }
         "LSP" => {
             let op = args["operation"].as_str().unwrap_or("?");
-            let file = args["filePath"].as_str().unwrap_or("?");
+            let file = paths.display(args["filePath"].as_str().unwrap_or("?"));
             format!("LSP({}: {})", op, file)
         }
+        "ClipboardWrite" => {
+            let text = args["text"].as_str().unwrap_or_default();
+            format!("ClipboardWrite({} chars)", text.chars().count())
+        }
         "TodoWrite" => {
             if let Some(todos) = args["todos"].as_array() {
                 format!("TodoWrite({} items)", todos.len())
@@ -343,10 +458,23 @@ pub fn format_tool_call(tool_name: &str, args: &Value) -> String {
                 "TodoWrite(...)".to_string()
             }
         }
-        "ExportDocument" => {
+        "BatchEdit" => {
+            let find = args["find"].as_str().unwrap_or("?");
+            let replace = args["replace"].as_str().unwrap_or("?");
+            let include = args["include"].as_str().unwrap_or("*");
+            let mode = if args["dry_run"].as_bool().unwrap_or(false) { ", dry run" } else { "" };
+            format!(
+                "BatchEdit(\"{}\" -> \"{}\" in {}{})",
+                truncate_str(find, 30),
+                truncate_str(replace, 30),
+                include,
+                mode
+            )
+        }
+        "ExportDocument" | "WriteDocx" | "WriteXlsx" | "WritePptx" => {
             let path = args["file_path"].as_str().unwrap_or("?");
             let filename = path.rsplit('/').next().unwrap_or(path);
-            format!("ExportDocument({})", filename)
+            format!("{}({})", tool_name, filename)
         }
         _ => {
             if let Some(obj) = args.as_object() {
@@ -355,6 +483,7 @@ pub fn format_tool_call(tool_name: &str, args: &Value) -> String {
                     .take(2)
                     .map(|(k, v)| {
                         let val = match v {
+                            Value::String(s) if is_path_key(k) => truncate_str(&paths.display(s), 20),
                             Value::String(s) => truncate_str(s, 20),
                             Value::Number(n) => n.to_string(),
                             Value::Bool(b) => b.to_string(),
@@ -378,47 +507,15 @@ pub fn format_tool_call(tool_name: &str, args: &Value) -> String {
 }
 
 /// Format tool arguments for approval modal display (multi-line, readable)
-pub fn format_approval_args(tool_name: &str, args: &Value) -> Vec<String> {
+pub fn format_approval_args(tool_name: &str, args: &Value, paths: &PathDisplay) -> Vec<String> {
     let mut lines = Vec::new();
 
     match tool_name {
-        "Write" => {
-            if let Some(path) = args["file_path"].as_str() {
-                lines.push(format!("File: {}", path));
-            }
-            if let Some(content) = args["content"].as_str() {
-                let preview = content.lines().take(5).collect::<Vec<_>>().join("\n");
-                let total_lines = content.lines().count();
-                lines.push(format!("Content ({} lines):", total_lines));
-                for line in preview.lines().take(5) {
-                    lines.push(format!("  {}", truncate_str(line, 60)));
-                }
-                if total_lines > 5 {
-                    lines.push(format!("  ... ({} more lines)", total_lines - 5));
-                }
-            }
-        }
-        "Edit" => {
-            if let Some(path) = args["file_path"].as_str() {
-                lines.push(format!("File: {}", path));
-            }
-            if let Some(old) = args["old_string"].as_str() {
-                lines.push("Old:".to_string());
-                for line in old.lines().take(3) {
-                    lines.push(format!("  - {}", truncate_str(line, 50)));
-                }
-                if old.lines().count() > 3 {
-                    lines.push(format!("  ... ({} more lines)", old.lines().count() - 3));
-                }
-            }
-            if let Some(new) = args["new_string"].as_str() {
-                lines.push("New:".to_string());
-                for line in new.lines().take(3) {
-                    lines.push(format!("  + {}", truncate_str(line, 50)));
-                }
-                if new.lines().count() > 3 {
-                    lines.push(format!("  ... ({} more lines)", new.lines().count() - 3));
-                }
+        "Write" | "Edit" => {
+            if let Some(diff) = tool_call_diff(tool_name, args, paths) {
+                lines.extend(format_file_diff(&diff, false));
+            } else if let Some(path) = args["file_path"].as_str() {
+                lines.push(format!("File: {}", paths.display(path)));
             }
         }
         "Bash" => {
@@ -439,6 +536,7 @@ pub fn format_approval_args(tool_name: &str, args: &Value) -> Vec<String> {
             if let Some(obj) = args.as_object() {
                 for (key, value) in obj.iter().take(6) {
                     let val_str = match value {
+                        Value::String(s) if is_path_key(key) => truncate_str(&paths.display(s), 50),
                         Value::String(s) => truncate_str(s, 50),
                         Value::Null => "null".to_string(),
                         Value::Bool(b) => b.to_string(),
@@ -457,6 +555,11 @@ pub fn format_approval_args(tool_name: &str, args: &Value) -> Vec<String> {
     lines
 }
 
+/// Whether a tool argument called `key` holds a path
+fn is_path_key(key: &str) -> bool {
+    key == "path" || key.ends_with("_path") || key.ends_with("Path")
+}
+
 // ============================================================================
 // Tool result formatting (for LLM consumption)
 // ============================================================================
@@ -465,14 +568,18 @@ pub fn format_approval_args(tool_name: &str, args: &Value) -> Vec<String> {
 ///
 /// Routes to the appropriate formatter based on tool name, or auto-detects
 /// the format based on JSON structure.
-pub fn format_tool_result(tool_name: &str, result: &str) -> String {
+pub fn format_tool_result(tool_name: &str, result: &str, paths: &PathDisplay) -> String {
     if let Ok(json) = serde_json::from_str::<Value>(result) {
         match tool_name {
             "list_directory" => format_directory_result(&json),
             "Glob" | "glob" | "find_files" => format_glob_result(&json),
             "Grep" | "grep" | "search_code" | "ripgrep" => format_grep_result(&json),
+            "SearchFiles" | "search_files" => format_search_result(&json),
             "Read" | "read_file" | "read_pdf" | "read_office_doc" => format_file_content(&json, result),
             "Bash" | "execute_command" | "shell" | "bash" => format_command_result(&json),
+            "Write" | "write_file" | "Edit" | "edit_file" | "edit" if json.get("lines_added").is_some() => {
+                format_file_change_result(tool_name, &json, paths)
+            }
             "Write" | "write_file" | "Edit" | "edit_file" | "delete_file" | "move_file" | "edit" => {
                 format_status_result(&json)
             }
@@ -585,6 +692,36 @@ pub fn format_grep_result(json: &Value) -> String {
     }
 }
 
+/// Format SearchFiles results: each file with what matched, best first
+pub fn format_search_result(json: &Value) -> String {
+    if let Some(results) = json.get("results").and_then(|r| r.as_array()) {
+        let total = json.get("total").and_then(|t| t.as_u64()).unwrap_or(results.len() as u64);
+        let mut lines = vec![format!("{} files found:", total)];
+
+        for result in results.iter().take(15) {
+            let path = result.get("path").and_then(|v| v.as_str()).unwrap_or("?");
+            let matches: Vec<&str> = result
+                .get("matches")
+                .and_then(|m| m.as_array())
+                .map(|m| m.iter().filter_map(|v| v.as_str()).collect())
+                .unwrap_or_default();
+            match result.get("line_number").and_then(|v| v.as_u64()) {
+                Some(line) => lines.push(format!("  🔍 {}:{} ({})", path, line, matches.join(", "))),
+                None => lines.push(format!("  🔍 {} ({})", path, matches.join(", "))),
+            }
+        }
+
+        let shown = results.len().min(15) as u64;
+        if total > shown {
+            lines.push(format!("  ... and {} more", total - shown));
+        }
+
+        lines.join("\n")
+    } else {
+        truncate_str(&json.to_string(), 500)
+    }
+}
+
 /// Format file content results
 pub fn format_file_content(json: &Value, raw: &str) -> String {
     if let Some(content) = json.get("content").and_then(|c| c.as_str()) {
@@ -644,6 +781,33 @@ pub fn format_status_result(json: &Value) -> String {
     }
 }
 
+/// Format a compact Edit/Write result, e.g. "edited src/main.rs: +12 −3",
+/// followed by any syntax problems found after the write
+pub fn format_file_change_result(tool_name: &str, json: &Value, paths: &PathDisplay) -> String {
+    let path = paths.display(json.get("path").and_then(|p| p.as_str()).unwrap_or("?"));
+    let added = json.get("lines_added").and_then(|n| n.as_u64()).unwrap_or(0);
+    let removed = json.get("lines_removed").and_then(|n| n.as_u64()).unwrap_or(0);
+
+    let summary = if json.get("created").and_then(|c| c.as_bool()).unwrap_or(false) {
+        format!("created {}: {} lines", path, added)
+    } else {
+        let verb = match tool_name {
+            "Write" | "write_file" => "wrote",
+            _ => "edited",
+        };
+        format!("{} {}: +{} \u{2212}{}", verb, path, added, removed)
+    };
+    let problems = json.pointer("/verification/problems").and_then(|p| p.as_array());
+    match problems.map(|p| p.as_slice()) {
+        Some([first, rest @ ..]) => {
+            let at = first["line"].as_u64().map(|line| format!(" at line {}", line)).unwrap_or_default();
+            let more = if rest.is_empty() { String::new() } else { format!(" (+{} more)", rest.len()) };
+            format!("{}; syntax problem{}{}", summary, at, more)
+        }
+        _ => summary,
+    }
+}
+
 /// Auto-detect and format JSON based on structure
 pub fn format_generic_json(json: &Value, raw: &str) -> String {
     if json.get("entries").is_some() {
@@ -703,12 +867,28 @@ impl DiffLine {
     }
 }
 
+/// The line a tool result is about, for opening its file there: the first
+/// changed line of an edit (from its diff preview), or where a read started
+pub fn tool_result_line(tool_name: &str, args: &Value, diff_preview: Option<&[DiffLine]>) -> Option<u32> {
+    match tool_name {
+        "Edit" => {
+            let lines = diff_preview?;
+            // A removed line has no number of its own: use the next line's
+            let changed = lines.iter().position(|l| l.line_type != "context").unwrap_or(0);
+            lines[changed..].iter().chain(&lines[..changed]).find_map(|l| l.line_number)
+        }
+        "Read" => args["offset"].as_u64().filter(|offset| *offset > 0).map(|offset| offset as u32),
+        _ => None,
+    }
+}
+
 /// Format a tool result summary: short, one-line description of what happened
 pub fn format_tool_result_summary(
     tool_name: &str,
     success: bool,
     output: &str,
     args: &Value,
+    paths: &PathDisplay,
 ) -> (String, Option<Vec<DiffLine>>) {
     if !success {
         let err_preview = output.lines().next().unwrap_or("Error");
@@ -720,33 +900,25 @@ pub fn format_tool_result_summary(
             let line_count = output.lines().count();
             (format!("Read {} lines", line_count), None)
         }
-        "Write" => {
-            let line_count = args["content"]
-                .as_str()
-                .map(|c| c.lines().count())
-                .unwrap_or(0);
-            (format!("Wrote {} lines", line_count), None)
-        }
-        "Edit" => {
-            let old_str = args["old_string"].as_str().unwrap_or("");
-            let new_str = args["new_string"].as_str().unwrap_or("");
-            let diff = generate_edit_diff(old_str, new_str);
-
-            let old_lines = old_str.lines().count();
-            let new_lines = new_str.lines().count();
-            let summary = if new_lines > old_lines {
-                format!("Added {} lines", new_lines - old_lines)
-            } else if old_lines > new_lines {
-                format!("Removed {} lines", old_lines - new_lines)
-            } else {
-                format!("Changed {} lines", old_lines)
+        "Write" | "Edit" => {
+            let summary = match serde_json::from_str::<Value>(output) {
+                Ok(json) if json.get("lines_added").is_some() => format_file_change_result(tool_name, &json, paths),
+                _ => format!("{} {}", tool_name, paths.display(args["file_path"].as_str().unwrap_or("?"))),
             };
-            (summary, Some(diff))
+            let diff = (tool_name == "Edit").then(|| edit_result_diff(args, paths));
+            (summary, diff)
         }
         "Glob" => {
             let match_count = output.lines().filter(|l| !l.is_empty()).count();
             (format!("Found {} files", match_count), None)
         }
+        "SearchFiles" => {
+            let total = serde_json::from_str::<Value>(output)
+                .ok()
+                .and_then(|json| json["total"].as_u64())
+                .unwrap_or(0);
+            (format!("Found {} files", total), None)
+        }
         "Grep" => {
             let match_count = output.lines().filter(|l| !l.is_empty()).count();
             if match_count == 0 {
@@ -774,6 +946,20 @@ pub fn format_tool_result_summary(
             let preview = output.lines().next().unwrap_or("Completed");
             (truncate_str(preview, 60), None)
         }
+        "BatchEdit" => {
+            let summary = serde_json::from_str::<Value>(output)
+                .ok()
+                .and_then(|json| json["summary"].as_str().map(|s| s.lines().next().unwrap_or_default().to_string()))
+                .unwrap_or_else(|| "Completed".to_string());
+            (truncate_str(&summary, 80), None)
+        }
+        "ClipboardWrite" => {
+            let chars = serde_json::from_str::<Value>(output)
+                .ok()
+                .and_then(|json| json["chars_written"].as_u64())
+                .unwrap_or(0);
+            (format!("Copied {} characters to the clipboard", chars), None)
+        }
         "TodoWrite" => {
             if let Some(todos) = args["todos"].as_array() {
                 let completed = todos
@@ -792,7 +978,7 @@ pub fn format_tool_result_summary(
                 ("Updated todos".to_string(), None)
             }
         }
-        "ExportDocument" => {
+        "ExportDocument" | "WriteDocx" | "WriteXlsx" | "WritePptx" => {
             // Parse the output JSON to get format and path
             if let Ok(json) = serde_json::from_str::<Value>(output) {
                 let format = json.get("format").and_then(|f| f.as_str()).unwrap_or("document");
@@ -803,6 +989,7 @@ pub fn format_tool_result_summary(
                     "pdf" => "PDF",
                     "docx" => "Word",
                     "xlsx" => "Excel",
+                    "pptx" => "PowerPoint",
                     "html_slides" => "HTML Slides",
                     _ => format,
                 };
@@ -824,49 +1011,304 @@ pub fn format_tool_result_summary(
     }
 }
 
-/// Generate diff lines for an Edit tool operation
-fn generate_edit_diff(old_str: &str, new_str: &str) -> Vec<DiffLine> {
-    let mut diff_lines = Vec::new();
-    let old_lines: Vec<&str> = old_str.lines().collect();
-    let new_lines: Vec<&str> = new_str.lines().collect();
+// ============================================================================
+// File diffs (Edit / Write)
+// ============================================================================
+
+/// Unchanged lines kept around each change
+const DIFF_CONTEXT: usize = 3;
 
-    let max_lines = 10;
-    let mut count = 0;
+/// Above this many line comparisons, skip matching and show a full replacement
+const MAX_DIFF_CELLS: usize = 4_000_000;
 
-    // Show removed lines (max 5)
-    for (i, line) in old_lines.iter().take(5).enumerate() {
-        if count >= max_lines {
-            break;
+/// Diff lines rendered before the rest is summarized
+const MAX_RENDERED_DIFF_LINES: usize = 80;
+
+/// Lines of a new file shown in its preview
+const NEW_FILE_PREVIEW_LINES: usize = 20;
+
+const ANSI_GREEN: &str = "\x1b[32m";
+const ANSI_RED: &str = "\x1b[31m";
+const ANSI_DIM: &str = "\x1b[2m";
+const ANSI_RESET: &str = "\x1b[0m";
+
+/// Line diff of the change an Edit or Write call makes to a file
+///
+/// The TUI renders `lines` with its own styling; `format_file_diff` renders
+/// them as text for the terminal and approval prompts.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FileDiff {
+    /// The file as shown to people (see `PathDisplay`)
+    pub path: String,
+    /// The file on disk, for opening it
+    #[serde(default)]
+    pub absolute_path: String,
+    /// The file doesn't exist yet, so `lines` is a preview of its content
+    pub new_file: bool,
+    pub lines: Vec<DiffLine>,
+    pub added: usize,
+    pub removed: usize,
+}
+
+/// Whether terminal output should be colored (stdout is a TTY and NO_COLOR is unset)
+pub fn use_color() -> bool {
+    use std::io::IsTerminal;
+    std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()) && std::io::stdout().is_terminal()
+}
+
+/// Compute the diff an Edit or Write call would make, reading the target file
+/// for context (real line numbers for Edit, the old content for Write)
+pub fn tool_call_diff(tool_name: &str, args: &Value, paths: &PathDisplay) -> Option<FileDiff> {
+    let file_path = args["file_path"].as_str()?;
+    let path = paths.absolute(file_path);
+    let (display, absolute) = (paths.display(file_path), path.to_string_lossy().to_string());
+
+    match tool_name {
+        "Edit" => {
+            let old_str = args["old_string"].as_str()?.replace("\r\n", "\n");
+            let new_str = args["new_string"].as_str()?.replace("\r\n", "\n");
+            let first_line = std::fs::read_to_string(&path)
+                .ok()
+                .and_then(|content| line_of(&content.replace("\r\n", "\n"), &old_str))
+                .unwrap_or(1);
+            Some(file_diff(display, absolute, &old_str, &new_str, first_line))
         }
-        diff_lines.push(DiffLine::removed(line.to_string()));
-        count += 1;
-        if i == 4 && old_lines.len() > 5 {
-            diff_lines.push(DiffLine {
-                line_number: None,
-                line_type: "context".to_string(),
-                content: format!("... {} more removed", old_lines.len() - 5),
-            });
-            count += 1;
+        "Write" => {
+            let content = args["content"].as_str()?.replace("\r\n", "\n");
+            match std::fs::read_to_string(&path) {
+                Ok(existing) => Some(file_diff(display, absolute, &existing.replace("\r\n", "\n"), &content, 1)),
+                Err(_) if !path.exists() => {
+                    let lines: Vec<DiffLine> = content
+                        .lines()
+                        .enumerate()
+                        .map(|(i, line)| DiffLine::added((i + 1) as u32, line))
+                        .collect();
+                    Some(FileDiff {
+                        path: display,
+                        absolute_path: absolute,
+                        new_file: true,
+                        added: lines.len(),
+                        removed: 0,
+                        lines,
+                    })
+                }
+                // Unreadable (e.g. binary) files have no line diff
+                Err(_) => None,
+            }
         }
+        _ => None,
     }
+}
 
-    // Show added lines (max 5)
-    for (i, line) in new_lines.iter().take(5).enumerate() {
-        if count >= max_lines {
-            break;
+/// Render a file diff as text: a header, then `NNNN ± content` lines
+///
+/// New files get a numbered content preview instead of `+` lines.
+pub fn format_file_diff(diff: &FileDiff, color: bool) -> Vec<String> {
+    let paint = |code: &str, text: String| {
+        if color {
+            format!("{}{}{}", code, text, ANSI_RESET)
+        } else {
+            text
+        }
+    };
+
+    let mut out = Vec::new();
+    if diff.new_file {
+        out.push(format!("File: {} (new file, {} lines)", diff.path, diff.added));
+        for line in diff.lines.iter().take(NEW_FILE_PREVIEW_LINES) {
+            let number = format!("{:>4}", line.line_number.unwrap_or(0));
+            out.push(format!("{}  {}", paint(ANSI_DIM, number), line.content));
+        }
+        if diff.lines.len() > NEW_FILE_PREVIEW_LINES {
+            out.push(format!("  ... ({} more lines)", diff.lines.len() - NEW_FILE_PREVIEW_LINES));
         }
-        diff_lines.push(DiffLine::added((i + 1) as u32, line.to_string()));
-        count += 1;
-        if i == 4 && new_lines.len() > 5 {
-            diff_lines.push(DiffLine {
-                line_number: None,
-                line_type: "context".to_string(),
-                content: format!("... {} more added", new_lines.len() - 5),
+        return out;
+    }
+
+    out.push(format!("File: {} (+{} \u{2212}{})", diff.path, diff.added, diff.removed));
+    for line in diff.lines.iter().take(MAX_RENDERED_DIFF_LINES) {
+        let number = line
+            .line_number
+            .map(|n| format!("{:>4}", n))
+            .unwrap_or_else(|| "    ".to_string());
+        let body = match line.line_type.as_str() {
+            "added" => paint(ANSI_GREEN, format!("+ {}", line.content)),
+            "removed" => paint(ANSI_RED, format!("- {}", line.content)),
+            _ => paint(ANSI_DIM, format!("  {}", line.content)),
+        };
+        out.push(format!("{} {}", paint(ANSI_DIM, number), body));
+    }
+    if diff.lines.len() > MAX_RENDERED_DIFF_LINES {
+        out.push(format!("  ... ({} more lines)", diff.lines.len() - MAX_RENDERED_DIFF_LINES));
+    }
+    out
+}
+
+/// Render an Edit/Write call's diff as text, or `None` for other tools
+pub fn format_tool_diff(tool_name: &str, args: &Value, color: bool, paths: &PathDisplay) -> Option<Vec<String>> {
+    tool_call_diff(tool_name, args, paths).map(|diff| format_file_diff(&diff, color))
+}
+
+/// Count lines added and removed going from `old` to `new`
+pub fn diff_stats(old: &str, new: &str) -> (usize, usize) {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    diff_ops(&old_lines, &new_lines)
+        .iter()
+        .fold((0, 0), |(added, removed), op| match op {
+            DiffOp::Insert(_) => (added + 1, removed),
+            DiffOp::Delete(_) => (added, removed + 1),
+            DiffOp::Equal(..) => (added, removed),
+        })
+}
+
+/// Diff lines for a completed Edit, numbered from where the new text landed
+fn edit_result_diff(args: &Value, paths: &PathDisplay) -> Vec<DiffLine> {
+    let old_str = args["old_string"].as_str().unwrap_or("").replace("\r\n", "\n");
+    let new_str = args["new_string"].as_str().unwrap_or("").replace("\r\n", "\n");
+    let first_line = args["file_path"]
+        .as_str()
+        .and_then(|path| std::fs::read_to_string(paths.absolute(path)).ok())
+        .and_then(|content| line_of(&content.replace("\r\n", "\n"), &new_str))
+        .unwrap_or(1);
+    diff_lines(&old_str, &new_str, first_line)
+}
+
+/// 1-based line where `needle` first occurs in `content`
+fn line_of(content: &str, needle: &str) -> Option<u32> {
+    if needle.is_empty() {
+        return None;
+    }
+    let offset = content.find(needle)?;
+    Some(content[..offset].matches('\n').count() as u32 + 1)
+}
+
+fn file_diff(path: String, absolute_path: String, old: &str, new: &str, first_line: u32) -> FileDiff {
+    let lines = diff_lines(old, new, first_line);
+    let (added, removed) = diff_stats(old, new);
+    FileDiff {
+        path,
+        absolute_path,
+        new_file: false,
+        lines,
+        added,
+        removed,
+    }
+}
+
+/// Line diff of `old` → `new` with unchanged runs trimmed to `DIFF_CONTEXT`
+///
+/// Added and context lines are numbered in the new text, starting at `first_line`.
+pub(crate) fn diff_lines(old: &str, new: &str, first_line: u32) -> Vec<DiffLine> {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let ops = diff_ops(&old_lines, &new_lines);
+
+    let number = |j: usize| first_line + j as u32;
+    let is_change = |op: &DiffOp| !matches!(op, DiffOp::Equal(..));
+    let mut lines = Vec::new();
+    let mut i = 0;
+    while i < ops.len() {
+        if is_change(&ops[i]) {
+            lines.push(match ops[i] {
+                DiffOp::Delete(k) => DiffLine::removed(old_lines[k]),
+                DiffOp::Insert(j) => DiffLine::added(number(j), new_lines[j]),
+                DiffOp::Equal(..) => unreachable!(),
             });
+            i += 1;
+            continue;
+        }
+
+        // A run of unchanged lines: keep the edges next to changes
+        let start = i;
+        while i < ops.len() && !is_change(&ops[i]) {
+            i += 1;
+        }
+        let run = &ops[start..i];
+        let keep_head = if start == 0 { 0 } else { DIFF_CONTEXT };
+        let keep_tail = if i == ops.len() { 0 } else { DIFF_CONTEXT };
+
+        let context = |op: &DiffOp| match *op {
+            DiffOp::Equal(_, j) => DiffLine::context(number(j), new_lines[j]),
+            _ => unreachable!(),
+        };
+        if run.len() <= keep_head + keep_tail {
+            lines.extend(run.iter().map(context));
+        } else {
+            lines.extend(run[..keep_head].iter().map(context));
+            if keep_head > 0 && keep_tail > 0 {
+                lines.push(DiffLine {
+                    line_number: None,
+                    line_type: "context".to_string(),
+                    content: format!("... {} unchanged lines", run.len() - keep_head - keep_tail),
+                });
+            }
+            lines.extend(run[run.len() - keep_tail..].iter().map(context));
+        }
+    }
+
+    lines
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DiffOp {
+    /// Line `old` of the old text equals line `new` of the new text
+    Equal(usize, usize),
+    Delete(usize),
+    Insert(usize),
+}
+
+/// Longest-common-subsequence line diff
+fn diff_ops(old: &[&str], new: &[&str]) -> Vec<DiffOp> {
+    // Common prefix and suffix don't need the table
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    let mut ops: Vec<DiffOp> = (0..prefix).map(|k| DiffOp::Equal(k, k)).collect();
+
+    let (n, m) = (old_mid.len(), new_mid.len());
+    if n.saturating_mul(m) > MAX_DIFF_CELLS {
+        ops.extend((0..n).map(|k| DiffOp::Delete(prefix + k)));
+        ops.extend((0..m).map(|k| DiffOp::Insert(prefix + k)));
+    } else {
+        // lcs[a][b] = LCS length of old_mid[a..] and new_mid[b..]
+        let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+        for a in (0..n).rev() {
+            for b in (0..m).rev() {
+                lcs[a][b] = if old_mid[a] == new_mid[b] {
+                    lcs[a + 1][b + 1] + 1
+                } else {
+                    lcs[a + 1][b].max(lcs[a][b + 1])
+                };
+            }
+        }
+
+        let (mut a, mut b) = (0, 0);
+        while a < n || b < m {
+            if a < n && b < m && old_mid[a] == new_mid[b] {
+                ops.push(DiffOp::Equal(prefix + a, prefix + b));
+                a += 1;
+                b += 1;
+            } else if b < m && (a == n || lcs[a][b + 1] > lcs[a + 1][b]) {
+                ops.push(DiffOp::Insert(prefix + b));
+                b += 1;
+            } else {
+                ops.push(DiffOp::Delete(prefix + a));
+                a += 1;
+            }
         }
     }
 
-    diff_lines
+    let (old_tail, new_tail) = (old.len() - suffix, new.len() - suffix);
+    ops.extend((0..suffix).map(|k| DiffOp::Equal(old_tail + k, new_tail + k)));
+    ops
 }
 
 #[cfg(test)]
@@ -888,21 +1330,97 @@ mod tests {
         assert_eq!(format_size(1048576), "1.0 MB");
     }
 
+    #[test]
+    fn test_format_tool_progress() {
+        assert_eq!(format_tool_progress("Bash", 4_200, 0, None), "Bash: 4s, no output yet");
+        assert_eq!(
+            format_tool_progress("Bash", 83_000, 2048, Some("test result: ok")),
+            "Bash: 1m 23s, 2.0 KB output\n  test result: ok"
+        );
+    }
+
     #[test]
     fn test_format_tool_summary() {
         let args = json!({"file_path": "/foo/bar.rs"});
-        assert_eq!(format_tool_summary("Read", &args), "/foo/bar.rs");
+        assert_eq!(format_tool_summary("Read", &args, &PathDisplay::default()), "/foo/bar.rs");
     }
 
     #[test]
     fn test_format_tool_call() {
         let args = json!({"file_path": "/foo/bar.rs"});
-        assert_eq!(format_tool_call("Read", &args), "Read(/foo/bar.rs)");
+        assert_eq!(format_tool_call("Read", &args, &PathDisplay::default()), "Read(/foo/bar.rs)");
+        let paths = PathDisplay::new("/foo").with_home(None);
+        assert_eq!(format_tool_call("Read", &args, &paths), "Read(bar.rs)");
+        let args = json!({"pattern": "todo", "path": "/foo/src"});
+        assert_eq!(format_tool_call("Grep", &args, &paths), "Grep(pattern: \"todo\", path: \"src\")");
+        let args = json!({"notebook_path": "/foo/a.ipynb"});
+        assert_eq!(format_tool_call("NotebookEdit", &args, &paths), "NotebookEdit(notebook_path: a.ipynb)");
+        let args = json!({"find": "old_name", "replace": "new_name", "include": "*.rs", "dry_run": true});
+        assert_eq!(format_tool_call("BatchEdit", &args, &paths), "BatchEdit(\"old_name\" -> \"new_name\" in *.rs, dry run)");
+    }
+
+    #[test]
+    fn test_path_display_unix() {
+        let paths = PathDisplay::new("/home/me/work/app").with_home(Some(PathBuf::from("/home/me")));
+        assert_eq!(paths.display("/home/me/work/app/src/lib.rs"), "src/lib.rs");
+        assert_eq!(paths.display("/home/me/work/app"), ".");
+        assert_eq!(paths.display("/home/me/work/app-old/x.rs"), "~/work/app-old/x.rs");
+        assert_eq!(paths.display("/home/me"), "~");
+        assert_eq!(paths.display("/etc/hosts"), "/etc/hosts");
+        assert_eq!(paths.display("src/lib.rs"), "src/lib.rs");
+        assert_eq!(paths.absolute("src/lib.rs"), PathBuf::from("/home/me/work/app").join("src/lib.rs"));
+        assert_eq!(paths.absolute("/etc/hosts"), PathBuf::from("/etc/hosts"));
+    }
+
+    #[test]
+    fn test_path_display_windows() {
+        let paths = PathDisplay::new(r"C:\Users\me\work\app").with_home(Some(PathBuf::from(r"C:\Users\me")));
+        assert_eq!(paths.display(r"C:\Users\me\work\app\src\lib.rs"), "src/lib.rs");
+        assert_eq!(paths.display("c:/users/me/work/app/src/lib.rs"), "src/lib.rs");
+        assert_eq!(paths.display(r"C:\Users\me\Downloads\a.txt"), "~/Downloads/a.txt");
+        assert_eq!(paths.display(r"D:\data\a.txt"), r"D:\data\a.txt");
+        assert_eq!(paths.display(r"\\server\share\a.txt"), r"\\server\share\a.txt");
+        assert_eq!(paths.display(r"src\lib.rs"), r"src\lib.rs");
+    }
+
+    #[test]
+    fn test_tool_result_line() {
+        let edit = [
+            DiffLine::context(9, "fn main() {"),
+            DiffLine::removed("    old();"),
+            DiffLine::context(10, "}"),
+        ];
+        assert_eq!(tool_result_line("Edit", &json!({}), Some(&edit)), Some(10));
+        let edit = [DiffLine::context(4, "a"), DiffLine::removed("b"), DiffLine::added(5, "c")];
+        assert_eq!(tool_result_line("Edit", &json!({}), Some(&edit)), Some(5));
+        assert_eq!(tool_result_line("Read", &json!({"offset": 120}), None), Some(120));
+        assert_eq!(tool_result_line("Read", &json!({}), None), None);
+        assert_eq!(tool_result_line("Write", &json!({}), None), None);
+    }
+
+    #[test]
+    fn test_format_search_result() {
+        let result = json!({
+            "query": "parser",
+            "results": [
+                {"path": "src/parser.rs", "score": 1040, "matches": ["name", "content"], "line_number": 3, "line": "pub struct Parser;"},
+                {"path": "src/parser/mod.rs", "score": 200, "matches": ["path"]}
+            ],
+            "total": 17,
+            "next_cursor": "2"
+        });
+        assert_eq!(
+            format_tool_result("SearchFiles", &result.to_string(), &PathDisplay::default()),
+            "17 files found:\n  🔍 src/parser.rs:3 (name, content)\n  🔍 src/parser/mod.rs (path)\n  ... and 15 more"
+        );
+        let (summary, _) =
+            format_tool_result_summary("SearchFiles", true, &result.to_string(), &json!({}), &PathDisplay::default());
+        assert_eq!(summary, "Found 17 files");
     }
 
     #[test]
     fn test_format_tool_result_summary() {
-        let (summary, diff) = format_tool_result_summary("Read", true, "line1\nline2", &json!({}));
+        let (summary, diff) = format_tool_result_summary("Read", true, "line1\nline2", &json!({}), &PathDisplay::default());
         assert_eq!(summary, "Read 2 lines");
         assert!(diff.is_none());
     }
@@ -976,6 +1494,111 @@ mod tests {
         assert!(!content.ends_with("lin"), "Should not cut mid-word");
     }
 
+    #[test]
+    fn test_diff_stats() {
+        assert_eq!(diff_stats("a\nb\nc", "a\nB\nc\nd"), (2, 1));
+        assert_eq!(diff_stats("", "x\ny"), (2, 0));
+        assert_eq!(diff_stats("same", "same"), (0, 0));
+    }
+
+    #[test]
+    fn test_diff_lines_numbers_and_context() {
+        let old: String = (1..=20).map(|i| format!("line {}\n", i)).collect();
+        let new = old.replace("line 3\n", "line three\n").replace("line 17\n", "");
+        let lines = diff_lines(&old, &new, 10);
+
+        let kinds: Vec<&str> = lines.iter().map(|l| l.line_type.as_str()).collect();
+        assert_eq!(kinds.iter().filter(|k| **k == "added").count(), 1);
+        assert_eq!(kinds.iter().filter(|k| **k == "removed").count(), 2);
+
+        let added = lines.iter().find(|l| l.line_type == "added").unwrap();
+        assert_eq!(added.content, "line three");
+        assert_eq!(added.line_number, Some(12));
+        // Unchanged lines between the two changes collapse to a marker
+        assert!(lines.iter().any(|l| l.content == "... 7 unchanged lines"));
+        // Leading context is trimmed to DIFF_CONTEXT lines
+        assert_eq!(lines[0].content, "line 1");
+        assert_eq!(lines.last().unwrap().content, "line 20");
+    }
+
+    #[test]
+    fn test_tool_call_diff_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.txt");
+        let path_str = path.to_str().unwrap();
+        let paths = PathDisplay::new(dir.path());
+
+        let args = json!({"file_path": path_str, "content": "one\ntwo\n"});
+        let diff = tool_call_diff("Write", &args, &paths).unwrap();
+        assert!(diff.new_file);
+        assert_eq!(diff.added, 2);
+        assert_eq!(diff.path, "notes.txt");
+        assert_eq!(diff.absolute_path, path_str);
+        let rendered = format_file_diff(&diff, false);
+        assert_eq!(rendered[0], "File: notes.txt (new file, 2 lines)");
+        assert_eq!(rendered[1], "   1  one");
+
+        // Relative paths are read from the workspace
+        std::fs::write(&path, "one\n2\n").unwrap();
+        let args = json!({"file_path": "notes.txt", "content": "one\ntwo\n"});
+        let diff = tool_call_diff("Write", &args, &paths).unwrap();
+        assert!(!diff.new_file);
+        assert_eq!((diff.added, diff.removed), (1, 1));
+    }
+
+    #[test]
+    fn test_tool_call_diff_edit_line_numbers() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("main.rs");
+        std::fs::write(&path, "fn main() {\n    old();\n}\n").unwrap();
+
+        let args = json!({
+            "file_path": path.to_str().unwrap(),
+            "old_string": "    old();",
+            "new_string": "    new();\n    more();",
+        });
+        let diff = tool_call_diff("Edit", &args, &PathDisplay::default()).unwrap();
+        assert_eq!((diff.added, diff.removed), (2, 1));
+        assert_eq!(diff.lines[1].line_number, Some(2));
+
+        let plain = format_file_diff(&diff, false);
+        assert_eq!(plain[1], "     -     old();");
+        assert_eq!(plain[2], "   2 +     new();");
+        assert!(!plain.concat().contains('\x1b'));
+
+        let colored = format_file_diff(&diff, true);
+        assert!(colored[2].contains(ANSI_GREEN));
+        assert!(colored[1].contains(ANSI_RED));
+    }
+
+    #[test]
+    fn test_format_file_change_result() {
+        let edited = json!({"path": "src/main.rs", "lines_added": 12, "lines_removed": 3});
+        assert_eq!(format_tool_result("Edit", &edited.to_string(), &PathDisplay::default()), "edited src/main.rs: +12 \u{2212}3");
+
+        let created = json!({"path": "new.rs", "created": true, "lines_added": 4, "lines_removed": 0});
+        assert_eq!(format_tool_result("Write", &created.to_string(), &PathDisplay::default()), "created new.rs: 4 lines");
+
+        let broken = json!({
+            "path": "src/lib.rs", "lines_added": 1, "lines_removed": 1,
+            "verification": {"language": "rust", "status": "problems", "problems": [
+                {"file": "src/lib.rs", "line": 7, "message": "unclosed delimiter"},
+                {"file": "src/lib.rs", "message": "expected item"}
+            ]}
+        });
+        assert_eq!(
+            format_tool_result("Edit", &broken.to_string(), &PathDisplay::default()),
+            "edited src/lib.rs: +1 \u{2212}1; syntax problem at line 7 (+1 more)"
+        );
+    }
+
+    #[test]
+    fn test_format_file_change_result_relative() {
+        let paths = PathDisplay::new("/work/app").with_home(None);
+        let edited = json!({"path": "/work/app/src/main.rs", "lines_added": 1, "lines_removed": 0});
+        assert_eq!(format_tool_result("Edit", &edited.to_string(), &paths), "edited src/main.rs: +1 \u{2212}0");
+    }
+
     #[test]
     fn test_truncate_tool_result_small_input() {
         let small = "small result";
@@ -0,0 +1,295 @@
+//! Outbound HTTP client construction
+//!
+//! Every HTTP client cowork uses - LLM providers, WebFetch and WebSearch,
+//! MCP HTTP servers, skill downloads, and update checks - is built from
+//! [`HttpSettings`], so they all go through the same proxy, trust the same
+//! extra CA certificates, and share one timeout.
+//!
+//! # Precedence
+//!
+//! Proxy, for each request:
+//! 1. `[network] proxy` in the config file (used for both http and https)
+//! 2. `HTTPS_PROXY` for https URLs, `HTTP_PROXY` for http URLs
+//! 3. `ALL_PROXY`
+//!
+//! Each variable may also be lowercase (`https_proxy`); the uppercase one
+//! wins. Hosts listed in `NO_PROXY` / `no_proxy` are reached directly,
+//! whichever proxy was chosen.
+//!
+//! CA certificates: `[network] ca_bundle`, else `SSL_CERT_FILE`. The bundle
+//! is added to the built-in roots, not used instead of them.
+//!
+//! Settings are read once per process; changes to the config file take
+//! effect on restart.
+
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use reqwest::{Certificate, NoProxy, Proxy};
+
+use crate::config::{ConfigManager, NetworkConfig};
+use crate::error::{Error, Result};
+
+/// User agent sent unless a caller sets its own
+const USER_AGENT: &str = concat!("cowork/", env!("CARGO_PKG_VERSION"));
+
+/// Resolved settings for building HTTP clients
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpSettings {
+    /// Proxy for http URLs (None = direct)
+    pub http_proxy: Option<String>,
+    /// Proxy for https URLs (None = direct)
+    pub https_proxy: Option<String>,
+    /// Comma-separated hosts that bypass the proxy
+    pub no_proxy: Option<String>,
+    /// PEM file of extra CA certificates
+    pub ca_bundle: Option<PathBuf>,
+    /// Request timeout
+    pub timeout: Duration,
+}
+
+impl Default for HttpSettings {
+    fn default() -> Self {
+        Self::from_config(&NetworkConfig::default())
+    }
+}
+
+impl HttpSettings {
+    /// Combine config with the process environment
+    pub fn from_config(network: &NetworkConfig) -> Self {
+        Self::resolve(network, |name| std::env::var(name).ok())
+    }
+
+    /// Read the config file and environment
+    pub fn load() -> Self {
+        match ConfigManager::new() {
+            Ok(manager) => Self::from_config(&manager.config().network),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Combine config with variables looked up through `env`
+    pub fn resolve(network: &NetworkConfig, env: impl Fn(&str) -> Option<String>) -> Self {
+        let var = |name: &str| {
+            env(name)
+                .or_else(|| env(&name.to_lowercase()))
+                .filter(|value| !value.trim().is_empty())
+        };
+        let all_proxy = var("ALL_PROXY");
+        let (http_proxy, https_proxy) = match network.proxy.as_ref().filter(|p| !p.trim().is_empty()) {
+            Some(proxy) => (Some(proxy.clone()), Some(proxy.clone())),
+            None => (
+                var("HTTP_PROXY").or_else(|| all_proxy.clone()),
+                var("HTTPS_PROXY").or(all_proxy),
+            ),
+        };
+
+        Self {
+            http_proxy,
+            https_proxy,
+            no_proxy: var("NO_PROXY"),
+            ca_bundle: network.ca_bundle.clone().or_else(|| var("SSL_CERT_FILE").map(PathBuf::from)),
+            timeout: Duration::from_secs(network.timeout_secs),
+        }
+    }
+
+    /// Async client builder with proxy, certificates and timeout applied
+    ///
+    /// Callers may override the timeout or user agent before building.
+    pub fn client_builder(&self) -> Result<reqwest::ClientBuilder> {
+        let mut builder = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .timeout(self.timeout)
+            .no_proxy();
+        for proxy in self.proxies()? {
+            builder = builder.proxy(proxy);
+        }
+        if let Some(path) = &self.ca_bundle {
+            builder = builder.tls_certs_merge(load_certificates(path)?);
+        }
+        Ok(builder)
+    }
+
+    /// Blocking client builder with proxy, certificates and timeout applied
+    ///
+    /// Like any blocking client, must not be built or dropped on an async
+    /// runtime thread.
+    pub fn blocking_client_builder(&self) -> Result<reqwest::blocking::ClientBuilder> {
+        let mut builder = reqwest::blocking::Client::builder()
+            .user_agent(USER_AGENT)
+            .timeout(self.timeout)
+            .no_proxy();
+        for proxy in self.proxies()? {
+            builder = builder.proxy(proxy);
+        }
+        if let Some(path) = &self.ca_bundle {
+            builder = builder.tls_certs_merge(load_certificates(path)?);
+        }
+        Ok(builder)
+    }
+
+    /// Build an async client
+    pub fn client(&self) -> Result<reqwest::Client> {
+        self.client_builder()?.build().map_err(client_error)
+    }
+
+    /// Build a blocking client
+    pub fn blocking_client(&self) -> Result<reqwest::blocking::Client> {
+        self.blocking_client_builder()?.build().map_err(client_error)
+    }
+
+    /// Proxies to install, replacing the environment lookup reqwest does
+    /// on its own
+    fn proxies(&self) -> Result<Vec<Proxy>> {
+        let no_proxy = self.no_proxy.as_deref().and_then(NoProxy::from_string);
+        let mut proxies = Vec::new();
+        if let Some(url) = &self.http_proxy {
+            proxies.push(Proxy::http(url).map_err(|e| proxy_error(url, e))?.no_proxy(no_proxy.clone()));
+        }
+        if let Some(url) = &self.https_proxy {
+            proxies.push(Proxy::https(url).map_err(|e| proxy_error(url, e))?.no_proxy(no_proxy));
+        }
+        Ok(proxies)
+    }
+}
+
+/// Settings for this process, read on first use
+pub fn settings() -> &'static HttpSettings {
+    static SETTINGS: OnceLock<HttpSettings> = OnceLock::new();
+    SETTINGS.get_or_init(HttpSettings::load)
+}
+
+/// Async client with this process's settings
+pub fn client() -> Result<reqwest::Client> {
+    settings().client()
+}
+
+/// Blocking client with this process's settings
+pub fn blocking_client() -> Result<reqwest::blocking::Client> {
+    settings().blocking_client()
+}
+
+/// Read every certificate in a PEM bundle
+fn load_certificates(path: &Path) -> Result<Vec<Certificate>> {
+    let pem = std::fs::read(path).map_err(|e| {
+        Error::Config(format!(
+            "Failed to read CA bundle {}: {} (check [network] ca_bundle or SSL_CERT_FILE)",
+            path.display(),
+            e
+        ))
+    })?;
+    let certificates = Certificate::from_pem_bundle(&pem)
+        .map_err(|e| Error::Config(format!("Invalid certificate in CA bundle {}: {}", path.display(), e)))?;
+    if certificates.is_empty() {
+        return Err(Error::Config(format!(
+            "No certificates in CA bundle {}: expected PEM \"BEGIN CERTIFICATE\" blocks",
+            path.display()
+        )));
+    }
+    Ok(certificates)
+}
+
+fn proxy_error(url: &str, e: reqwest::Error) -> Error {
+    Error::Config(format!("Invalid proxy URL '{}': {}", url, e))
+}
+
+fn client_error(e: reqwest::Error) -> Error {
+    Error::Config(format!("Failed to create HTTP client: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn resolve(network: &NetworkConfig, vars: &[(&str, &str)]) -> HttpSettings {
+        let vars: HashMap<String, String> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        HttpSettings::resolve(network, |name| vars.get(name).cloned())
+    }
+
+    #[test]
+    fn test_proxy_precedence() {
+        let env = [
+            ("HTTPS_PROXY", "http://https-proxy:3128"),
+            ("http_proxy", "http://http-proxy:3128"),
+            ("ALL_PROXY", "http://all-proxy:3128"),
+            ("no_proxy", "localhost,.internal"),
+            ("SSL_CERT_FILE", "/etc/ssl/corp.pem"),
+        ];
+        let settings = resolve(&NetworkConfig::default(), &env);
+        assert_eq!(settings.https_proxy.as_deref(), Some("http://https-proxy:3128"));
+        assert_eq!(settings.http_proxy.as_deref(), Some("http://http-proxy:3128"));
+        assert_eq!(settings.no_proxy.as_deref(), Some("localhost,.internal"));
+        assert_eq!(settings.ca_bundle, Some(PathBuf::from("/etc/ssl/corp.pem")));
+        assert_eq!(settings.timeout, Duration::from_secs(30));
+
+        let settings = resolve(&NetworkConfig::default(), &[("all_proxy", "http://all-proxy:3128")]);
+        assert_eq!(settings.http_proxy.as_deref(), Some("http://all-proxy:3128"));
+        assert_eq!(settings.https_proxy.as_deref(), Some("http://all-proxy:3128"));
+
+        // Config wins over every variable
+        let network = NetworkConfig {
+            proxy: Some("http://config-proxy:8080".to_string()),
+            ca_bundle: Some(PathBuf::from("/opt/ca.pem")),
+            timeout_secs: 5,
+        };
+        let settings = resolve(&network, &env);
+        assert_eq!(settings.http_proxy.as_deref(), Some("http://config-proxy:8080"));
+        assert_eq!(settings.https_proxy.as_deref(), Some("http://config-proxy:8080"));
+        assert_eq!(settings.no_proxy.as_deref(), Some("localhost,.internal"));
+        assert_eq!(settings.ca_bundle, Some(PathBuf::from("/opt/ca.pem")));
+        assert_eq!(settings.timeout, Duration::from_secs(5));
+
+        assert_eq!(resolve(&NetworkConfig::default(), &[("HTTPS_PROXY", " ")]).https_proxy, None);
+    }
+
+    #[test]
+    fn test_ca_bundle_errors_name_the_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let missing = dir.path().join("missing.pem");
+        let settings = HttpSettings { ca_bundle: Some(missing.clone()), ..resolve(&NetworkConfig::default(), &[]) };
+        let err = settings.client().unwrap_err().to_string();
+        assert!(err.contains(&missing.display().to_string()), "{}", err);
+
+        let garbage = dir.path().join("garbage.pem");
+        std::fs::write(&garbage, "not a certificate\n").unwrap();
+        let settings = HttpSettings { ca_bundle: Some(garbage.clone()), ..settings };
+        let err = settings.client().unwrap_err().to_string();
+        assert!(err.contains(&garbage.display().to_string()), "{}", err);
+
+        let settings = HttpSettings { ca_bundle: None, https_proxy: Some("not a url".to_string()), ..settings };
+        assert!(settings.client().unwrap_err().to_string().contains("Invalid proxy URL 'not a url'"));
+    }
+
+    #[tokio::test]
+    async fn test_requests_go_through_proxy() {
+        // A one-shot HTTP proxy that answers every request itself
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy_url = format!("http://{}", listener.local_addr().unwrap());
+        let proxy = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 7\r\nConnection: close\r\n\r\nproxied")
+                .await
+                .unwrap();
+            String::from_utf8_lossy(&request).into_owned()
+        });
+
+        let network = NetworkConfig { proxy: Some(proxy_url), ..Default::default() };
+        let client = resolve(&network, &[]).client().unwrap();
+        let body = client.get("http://example.invalid/status").send().await.unwrap().text().await.unwrap();
+        assert_eq!(body, "proxied");
+
+        let request = proxy.await.unwrap();
+        assert!(request.starts_with("GET http://example.invalid/status HTTP/1.1"), "{}", request);
+        assert!(request.contains(USER_AGENT));
+    }
+}
@@ -0,0 +1,69 @@
+//! English catalog, the fallback for every other locale
+
+use super::Text;
+
+pub(super) fn text(text: Text) -> &'static str {
+    match text {
+        Text::Thinking => "Thinking...",
+        Text::WaitingForRequestSlot => "Waiting for available request slot...",
+        Text::Retrying => "{error}, retrying...",
+        Text::Compacting => "Context at {percent}% - compacting conversation history...",
+        Text::Compacted => "Compacted {messages} messages into summary ({before} -> {after} chars)",
+        Text::CompactingOnRequest => "Compacting conversation history...",
+        Text::CompactingOverflow => "The conversation exceeded the model's context window - summarizing it...",
+        Text::ContextRecovered => {
+            "The conversation no longer fit the model's context window: summarized {messages} messages (dropping {collapsed} older tool results first) and retried"
+        }
+        Text::MaxIterations => "Max iteration limit reached",
+        Text::ResponseCutOff => "The response was cut off by the output token limit",
+        Text::MalformedToolCalls => "The model kept producing tool calls that could not be parsed",
+        Text::RepeatedToolCall => {
+            "The model keeps calling {tool} with the same arguments; answering from the previous result and asking it to change approach"
+        }
+        Text::DelegatedApproval => "This tool call waits for a reviewer's approval; it can only be cancelled here",
+        Text::SessionInterrupted => "Session interrupted",
+        Text::NoPlanToApprove => "No plan to approve",
+        Text::PlanApprovalFailed => "Failed to approve plan: {error}",
+        Text::EmptyTitle => "A session title can't be empty",
+        Text::NoMessageToPin => "There's no such message to pin",
+        Text::MemorySaveFailed => "Failed to save memory: {error}",
+        Text::ExplainNotPending => "That tool call isn't waiting for approval, so there's nothing to explain",
+        Text::ExplainAlreadyRequested => "That tool call has already been explained",
+        Text::ExplainUnavailable => "Tool calls can't be explained in this session",
+        Text::ExplainFailed => "Couldn't explain the tool call: {error}",
+        Text::UnresolvedMentions => "No such file in the workspace, so not passed on: {paths}",
+
+        Text::Welcome => "Welcome to Cowork. Type your message and press Enter. Ctrl+C to quit.",
+        Text::Cancelled => "Cancelled",
+        Text::HelpCommands => "Commands: {commands}",
+        Text::HelpShell => "Use ! prefix for direct shell commands (e.g., ! ls -la)",
+        Text::HelpAliases => "Aliases: {aliases}",
+        Text::HelpShortcuts => {
+            "Shortcuts: Ctrl+C to quit, Shift+Up/Down to scroll, {todos} to show/hide tasks, {search} to search history, {cancel} to cancel the turn, Tab/Ctrl+X to pick and cancel one running tool"
+        }
+        Text::ApprovalTitle => " Tool Approval Required ",
+        Text::ApprovalTool => "Tool: {tool}",
+        Text::ApprovalDetails => " Details ",
+        Text::ApprovalSelect => " Select action (\u{2191}/\u{2193}, Enter) ",
+        Text::ApprovalYes => "Yes - approve this call",
+        Text::ApprovalNo => "No - reject this call",
+        Text::ApprovalAlways => "Always - auto-approve for session",
+        Text::ApprovalAll => "Approve all - auto-approve everything",
+        Text::ApprovalExplain => "{key} - explain why the call is wanted",
+        Text::ApprovalExplaining => "Asking the model to explain the call...",
+        Text::ToolApproved => "Approved: {tool}",
+        Text::ToolRejected => "Rejected: {tool}",
+        Text::ToolApprovedForSession => "Approved '{tool}' for session",
+        Text::AllToolsApproved => "All tools approved for session",
+        Text::SetupWelcome => "Welcome to Cowork!",
+        Text::SetupRequired => "Setup Required",
+        Text::SetupNoApiKey => "No API key configured. Please set one up before using Cowork.",
+        Text::SetupEnvOption => "Option 1: Environment Variable (Quick)",
+        Text::SetupConfigOption => "Option 2: Config File (Persistent)",
+        Text::SetupEditConfig => "Edit: {path}",
+        Text::SetupExampleConfig => "Example config:",
+        Text::SetupGetApiKey => "Get your API key:",
+        Text::SetupRunAgain => "After configuring, run 'cowork' again to start.",
+        Text::SetupMoreHelp => "For more help: {command}",
+    }
+}
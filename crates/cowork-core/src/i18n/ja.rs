@@ -0,0 +1,70 @@
+//! Japanese catalog
+
+use super::Text;
+
+pub(super) fn text(text: Text) -> Option<&'static str> {
+    let translated = match text {
+        Text::Thinking => "考え中...",
+        Text::WaitingForRequestSlot => "リクエストの空きを待っています...",
+        Text::Retrying => "{error}、再試行しています...",
+        Text::Compacting => "コンテキストが {percent}% に達しました - 会話履歴を圧縮しています...",
+        Text::Compacted => "{messages} 件のメッセージを要約に圧縮しました ({before} -> {after} 文字)",
+        Text::CompactingOnRequest => "会話履歴を圧縮しています...",
+        Text::CompactingOverflow => "会話がモデルのコンテキストウィンドウを超えました - 要約しています...",
+        Text::ContextRecovered => {
+            "会話がモデルのコンテキストウィンドウに収まらなくなったため、{messages} 件のメッセージを要約して再試行しました (先に古いツール結果 {collapsed} 件を省略)"
+        }
+        Text::MaxIterations => "繰り返し回数の上限に達しました",
+        Text::ResponseCutOff => "出力トークンの上限で応答が途中で切れました",
+        Text::MalformedToolCalls => "モデルが解析できないツール呼び出しを繰り返しました",
+        Text::RepeatedToolCall => {
+            "モデルが同じ引数で {tool} を繰り返し呼び出しています。前回の結果で応答し、方針を変えるよう求めます"
+        }
+        Text::DelegatedApproval => "このツール呼び出しはレビュアーの承認待ちです。ここではキャンセルのみできます",
+        Text::SessionInterrupted => "セッションが中断されました",
+        Text::NoPlanToApprove => "承認するプランがありません",
+        Text::PlanApprovalFailed => "プランを承認できませんでした: {error}",
+        Text::EmptyTitle => "セッションのタイトルは空にできません",
+        Text::NoMessageToPin => "ピン留めするメッセージが見つかりません",
+        Text::MemorySaveFailed => "メモリに保存できませんでした: {error}",
+        Text::ExplainNotPending => "このツール呼び出しは承認待ちではないため、説明できません",
+        Text::ExplainAlreadyRequested => "このツール呼び出しはすでに説明済みです",
+        Text::ExplainUnavailable => "このセッションではツール呼び出しを説明できません",
+        Text::ExplainFailed => "ツール呼び出しを説明できませんでした: {error}",
+        Text::UnresolvedMentions => "ワークスペースに見つからないため渡していません: {paths}",
+
+        Text::Welcome => "Cowork へようこそ。メッセージを入力して Enter を押してください。Ctrl+C で終了します。",
+        Text::Cancelled => "キャンセルしました",
+        Text::HelpCommands => "コマンド: {commands}",
+        Text::HelpShell => "! を先頭に付けるとシェルコマンドを直接実行します (例: ! ls -la)",
+        Text::HelpAliases => "エイリアス: {aliases}",
+        Text::HelpShortcuts => {
+            "ショートカット: Ctrl+C で終了、Shift+Up/Down でスクロール、{todos} でタスクの表示切替、{search} で履歴検索、{cancel} でターンをキャンセル、Tab/Ctrl+X で実行中のツールを選んでキャンセル"
+        }
+        Text::ApprovalTitle => " ツールの承認が必要です ",
+        Text::ApprovalTool => "ツール: {tool}",
+        Text::ApprovalDetails => " 詳細 ",
+        Text::ApprovalSelect => " 操作を選択 (\u{2191}/\u{2193}, Enter) ",
+        Text::ApprovalYes => "はい - この呼び出しを承認",
+        Text::ApprovalNo => "いいえ - この呼び出しを拒否",
+        Text::ApprovalAlways => "常に - このセッションでは自動承認",
+        Text::ApprovalAll => "すべて承認 - すべてを自動承認",
+        Text::ApprovalExplain => "{key} - この呼び出しの理由を説明",
+        Text::ApprovalExplaining => "呼び出しの説明をモデルに問い合わせています...",
+        Text::ToolApproved => "承認しました: {tool}",
+        Text::ToolRejected => "拒否しました: {tool}",
+        Text::ToolApprovedForSession => "このセッションでは '{tool}' を承認しました",
+        Text::AllToolsApproved => "このセッションではすべてのツールを承認しました",
+        Text::SetupWelcome => "Cowork へようこそ!",
+        Text::SetupRequired => "セットアップが必要です",
+        Text::SetupNoApiKey => "API キーが設定されていません。Cowork を使う前に設定してください。",
+        Text::SetupEnvOption => "方法 1: 環境変数 (手軽)",
+        Text::SetupConfigOption => "方法 2: 設定ファイル (永続)",
+        Text::SetupEditConfig => "編集: {path}",
+        Text::SetupExampleConfig => "設定例:",
+        Text::SetupGetApiKey => "API キーの取得:",
+        Text::SetupRunAgain => "設定後、もう一度 'cowork' を実行してください。",
+        Text::SetupMoreHelp => "詳しいヘルプ: {command}",
+    };
+    Some(translated)
+}
@@ -0,0 +1,315 @@
+//! Localized user-facing text
+//!
+//! Text shown to people (status lines, warnings and errors in
+//! `SessionOutput`, the CLI's help, approval dialog and setup instructions)
+//! comes from a keyed catalog instead of string literals. Each `Text` has an
+//! English entry (the `en` catalog is an exhaustive match, so a key without
+//! one doesn't build) and may have one in another locale; a missing
+//! translation falls back to English.
+//!
+//! The locale is `locale` in `[general]`, or the system locale (`LC_ALL`,
+//! `LC_MESSAGES`, `LANG`) when that isn't set. The agent loop owns a
+//! `Localizer` and sends its outputs already localized, so every frontend
+//! shows the session's language.
+//!
+//! Text sent to the model (prompts, reminders, tool results) is never
+//! localized.
+
+mod en;
+mod ja;
+
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+/// A language text is shown in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    En,
+    Ja,
+}
+
+impl Locale {
+    /// Every supported locale
+    pub const ALL: &'static [Locale] = &[Locale::En, Locale::Ja];
+
+    /// The locale for a language tag such as `ja`, `ja-JP` or `ja_JP.UTF-8`
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let language = tag
+            .split(['_', '-', '.', '@'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match language.as_str() {
+            "en" | "c" | "posix" => Some(Locale::En),
+            "ja" => Some(Locale::Ja),
+            _ => None,
+        }
+    }
+
+    /// The system locale, from the first of `LC_ALL`, `LC_MESSAGES` and
+    /// `LANG` that is set (English when it isn't supported)
+    pub fn system() -> Self {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .into_iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| Self::from_tag(&value))
+            .unwrap_or_default()
+    }
+
+    /// The configured locale, or the system locale when none is configured
+    ///
+    /// An unsupported configured locale falls back to English.
+    pub fn resolve(configured: Option<&str>) -> Self {
+        match configured.map(str::trim).filter(|tag| !tag.is_empty()) {
+            Some(tag) => Self::from_tag(tag).unwrap_or_else(|| {
+                tracing::warn!("Unsupported locale '{}', using English", tag);
+                Locale::En
+            }),
+            None => Self::system(),
+        }
+    }
+
+    /// The locale's language tag
+    pub fn tag(&self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::Ja => "ja",
+        }
+    }
+}
+
+impl std::fmt::Display for Locale {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.tag())
+    }
+}
+
+macro_rules! texts {
+    ($($(#[$doc:meta])* $name:ident => $key:literal,)*) => {
+        /// A user-facing text, by its catalog key
+        ///
+        /// Parameters are written `{name}` in the catalogs.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum Text {
+            $($(#[$doc])* $name,)*
+        }
+
+        impl Text {
+            /// Every text in the catalog
+            pub const ALL: &'static [Text] = &[$(Text::$name,)*];
+
+            /// The text's catalog key
+            pub fn key(self) -> &'static str {
+                match self {
+                    $(Text::$name => $key,)*
+                }
+            }
+        }
+    };
+}
+
+texts! {
+    /// Status while waiting for the model
+    Thinking => "loop.thinking",
+    /// Status while waiting under the request limits
+    WaitingForRequestSlot => "loop.waiting_for_request_slot",
+    /// Status after a failed LLM request (`error`)
+    Retrying => "loop.retrying",
+    /// Status before compacting the history (`percent`)
+    Compacting => "loop.compacting",
+    /// Status after compacting the history (`messages`, `before`, `after`)
+    Compacted => "loop.compacted",
+    /// Status before compacting the history at the user's request
+    CompactingOnRequest => "loop.compacting_on_request",
+    /// Status while summarizing a history that overflowed the context window
+    CompactingOverflow => "loop.compacting_overflow",
+    /// Warning: an overflowing history was summarized (`messages`, `collapsed`)
+    ContextRecovered => "loop.context_recovered",
+    /// A turn made too many LLM requests
+    MaxIterations => "loop.max_iterations",
+    /// Warning: the reply hit the output token limit
+    ResponseCutOff => "loop.response_cut_off",
+    /// Warning: the model's tool calls could not be parsed
+    MalformedToolCalls => "loop.malformed_tool_calls",
+    /// Warning: the model repeats a tool call (`tool`)
+    RepeatedToolCall => "loop.repeated_tool_call",
+    /// Warning: the user tried to approve a delegated call
+    DelegatedApproval => "loop.delegated_approval",
+    /// The session's control channel closed mid-turn
+    SessionInterrupted => "loop.session_interrupted",
+    /// Error: plan approval without a plan
+    NoPlanToApprove => "loop.no_plan_to_approve",
+    /// Error: the plan's status could not be saved (`error`)
+    PlanApprovalFailed => "loop.plan_approval_failed",
+    /// Error: an empty session title
+    EmptyTitle => "loop.empty_title",
+    /// Error: a pin named no message of the session
+    NoMessageToPin => "loop.no_message_to_pin",
+    /// Error: an instruction could not be saved to memory (`error`)
+    MemorySaveFailed => "loop.memory_save_failed",
+    /// Warning: an explanation asked for a call not waiting for approval
+    ExplainNotPending => "loop.explain_not_pending",
+    /// Warning: a second explanation asked for the same call
+    ExplainAlreadyRequested => "loop.explain_already_requested",
+    /// Warning: the session has no model to explain calls with
+    ExplainUnavailable => "loop.explain_unavailable",
+    /// Warning: the explanation request failed (`error`)
+    ExplainFailed => "loop.explain_failed",
+    /// Warning: `@` mentions that name no workspace path (`paths`)
+    UnresolvedMentions => "loop.unresolved_mentions",
+
+    /// The TUI's first message
+    Welcome => "cli.welcome",
+    /// The turn was cancelled
+    Cancelled => "cli.cancelled",
+    /// /help: the commands (`commands`)
+    HelpCommands => "cli.help.commands",
+    /// /help: shell commands
+    HelpShell => "cli.help.shell",
+    /// /help: the aliases (`aliases`)
+    HelpAliases => "cli.help.aliases",
+    /// /help: the shortcuts (`todos`, `search`, `cancel`)
+    HelpShortcuts => "cli.help.shortcuts",
+    /// Approval dialog title
+    ApprovalTitle => "cli.approval.title",
+    /// Approval dialog: the tool (`tool`)
+    ApprovalTool => "cli.approval.tool",
+    /// Approval dialog: the arguments' heading
+    ApprovalDetails => "cli.approval.details",
+    /// Approval dialog: the options' heading
+    ApprovalSelect => "cli.approval.select",
+    /// Approval option: approve this call
+    ApprovalYes => "cli.approval.yes",
+    /// Approval option: reject this call
+    ApprovalNo => "cli.approval.no",
+    /// Approval option: approve the tool for the session
+    ApprovalAlways => "cli.approval.always",
+    /// Approval option: approve every tool for the session
+    ApprovalAll => "cli.approval.all",
+    /// Approval dialog: the key that explains the call (`key`)
+    ApprovalExplain => "cli.approval.explain",
+    /// Approval dialog: waiting for the explanation
+    ApprovalExplaining => "cli.approval.explaining",
+    /// A call was approved (`tool`)
+    ToolApproved => "cli.approval.approved",
+    /// A call was rejected (`tool`)
+    ToolRejected => "cli.approval.rejected",
+    /// A tool was approved for the session (`tool`)
+    ToolApprovedForSession => "cli.approval.approved_for_session",
+    /// Every tool was approved for the session
+    AllToolsApproved => "cli.approval.all_approved",
+    /// Setup: greeting
+    SetupWelcome => "cli.setup.welcome",
+    /// Setup: heading
+    SetupRequired => "cli.setup.required",
+    /// Setup: no API key
+    SetupNoApiKey => "cli.setup.no_api_key",
+    /// Setup: the environment variable option
+    SetupEnvOption => "cli.setup.env_option",
+    /// Setup: the config file option
+    SetupConfigOption => "cli.setup.config_option",
+    /// Setup: the config file (`path`)
+    SetupEditConfig => "cli.setup.edit_config",
+    /// Setup: heading of the example config
+    SetupExampleConfig => "cli.setup.example_config",
+    /// Setup: heading of the API key links
+    SetupGetApiKey => "cli.setup.get_api_key",
+    /// Setup: what to do next
+    SetupRunAgain => "cli.setup.run_again",
+    /// Setup: where to find help (`command`)
+    SetupMoreHelp => "cli.setup.more_help",
+}
+
+/// Looks texts up in one locale's catalog
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Localizer {
+    locale: Locale,
+}
+
+impl Localizer {
+    pub fn new(locale: Locale) -> Self {
+        Self { locale }
+    }
+
+    pub fn locale(&self) -> Locale {
+        self.locale
+    }
+
+    /// The text in this locale, or in English when it has no translation
+    pub fn text(&self, text: Text) -> &'static str {
+        let translated = match self.locale {
+            Locale::En => None,
+            Locale::Ja => ja::text(text),
+        };
+        translated.unwrap_or_else(|| en::text(text))
+    }
+
+    /// The text with its `{name}` parameters filled in
+    pub fn format(&self, text: Text, params: &[(&str, &(dyn Display + Sync))]) -> String {
+        let mut formatted = self.text(text).to_string();
+        for (name, value) in params {
+            formatted = formatted.replace(&format!("{{{}}}", name), &value.to_string());
+        }
+        formatted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    fn params(text: &str) -> BTreeSet<&str> {
+        text.split('{').skip(1).filter_map(|rest| rest.split_once('}')).map(|(name, _)| name).collect()
+    }
+
+    #[test]
+    fn test_every_text_has_a_unique_key_and_english_entry() {
+        let keys: BTreeSet<_> = Text::ALL.iter().map(|text| text.key()).collect();
+        assert_eq!(keys.len(), Text::ALL.len());
+        for text in Text::ALL {
+            assert!(!en::text(*text).is_empty(), "{} has no English text", text.key());
+        }
+    }
+
+    #[test]
+    fn test_translations_keep_the_english_parameters() {
+        for locale in Locale::ALL {
+            let localizer = Localizer::new(*locale);
+            for text in Text::ALL {
+                assert_eq!(
+                    params(localizer.text(*text)),
+                    params(en::text(*text)),
+                    "{} in {}",
+                    text.key(),
+                    locale
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_format_and_fallback() {
+        let ja = Localizer::new(Locale::Ja);
+        assert_eq!(ja.text(Text::Thinking), "考え中...");
+        assert_eq!(
+            Localizer::default().format(Text::ToolApproved, &[("tool", &"Bash")]),
+            "Approved: Bash"
+        );
+        assert_eq!(ja.format(Text::ToolApproved, &[("tool", &"Bash")]), "承認しました: Bash");
+    }
+
+    #[test]
+    fn test_locale_from_tag() {
+        assert_eq!(Locale::from_tag("ja_JP.UTF-8"), Some(Locale::Ja));
+        assert_eq!(Locale::from_tag("en-US"), Some(Locale::En));
+        assert_eq!(Locale::from_tag("C"), Some(Locale::En));
+        assert_eq!(Locale::from_tag("fr_FR"), None);
+        assert_eq!(Locale::resolve(Some("ja")), Locale::Ja);
+        assert_eq!(Locale::resolve(Some("klingon")), Locale::En);
+    }
+}
@@ -6,26 +6,51 @@
 //! - Task planning and execution
 //! - Human-in-the-loop approval system
 //! - Context management
+//!
+//! Applications embedding the agent should use the `embed` module, the
+//! crate's stable API.
 
 pub mod approval;
+pub mod completion;
 pub mod config;
 pub mod context;
+pub mod credentials;
+pub mod editor;
+pub mod embed;
 pub mod error;
 pub mod formatting;
+pub mod http;
+pub mod i18n;
 pub mod mcp_manager;
+#[cfg(feature = "mcp")]
+pub mod mcp_server;
+pub mod notifications;
 pub mod orchestration;
+pub mod palette;
+pub mod policy;
+pub mod processes;
 pub mod prompt;
 pub mod provider;
+pub mod recipes;
+pub mod schedule;
 pub mod session;
 pub mod skills;
+pub mod templates;
+pub mod telemetry;
 pub mod tools;
 pub mod update;
 
-pub use approval::{ApprovalLevel, ApprovalRequest, ToolApprovalConfig};
-pub use config::{defaults, Config, ConfigManager, McpServerConfig, ModelTiers, ProviderConfig};
+pub use approval::{ApprovalLevel, ApprovalRequest, RuleDecision, ToolApprovalConfig, ToolRule};
+pub use config::{
+    defaults, ApiKeyStorage, CliConfig, Config, ConfigManager, EnvConfig, InputProcessingConfig, LimitsConfig, McpSandboxConfig,
+    McpServerConfig, ModelTiers, NetworkConfig, NotificationsConfig, ProjectSettings, ProviderConfig,
+    RemoteTarget, SessionsConfig,
+};
 // Context exports moved to context module
 pub use mcp_manager::{McpServerInfo, McpServerManager, McpServerStatus, McpToolInfo};
 pub use error::{Error, Result};
+pub use notifications::{Notification, NotificationKind, NotificationTracker};
+pub use policy::Policy;
 pub use provider::{
     create_provider_from_config, create_provider_from_provider_config, create_provider_with_settings,
     get_api_key, get_model_tiers, has_api_key_configured, ChatRole,
@@ -43,19 +68,22 @@ pub use prompt::{
 // Orchestration exports
 pub use orchestration::{
     create_standard_tool_registry, format_tool_result_for_llm,
-    SystemPrompt, ToolRegistryBuilder,
+    SystemPrompt, ToolProfile, ToolRegistryBuilder,
 };
 
 // Session exports (unified agent loop architecture)
 pub use session::{
-    AgentLoop, ChatSession, ImageAttachment, QuestionInfo, QuestionOption, SessionConfig,
+    AgentLoop, ChatSession, ImageAttachment, QuestionInfo, QuestionOption, QueuePolicy, SessionConfig,
     SessionId, SessionInput, SessionManager, SessionOutput, SessionRegistry, ToolCallStatus,
+    ToolDoneStatus, TurnApproval, TurnOverrides,
 };
 
 // Formatting exports (consolidated)
 pub use formatting::{
-    format_approval_args, format_command_result, format_directory_result, format_ephemeral,
-    format_file_content, format_generic_json, format_glob_result, format_grep_result,
-    format_size, format_status_result, format_tool_call, format_tool_result,
-    format_tool_result_summary, format_tool_summary, truncate_str, DiffLine,
+    diff_stats, format_approval_args, format_command_result, format_directory_result,
+    format_ephemeral, format_file_change_result, format_file_content, format_file_diff,
+    format_generic_json, format_glob_result, format_grep_result, format_size,
+    format_status_result, format_tool_call, format_tool_diff, format_tool_progress, format_tool_result,
+    format_tool_result_summary, format_tool_summary, tool_call_diff, truncate_str, use_color,
+    DiffLine, FileDiff,
 };
@@ -2,16 +2,25 @@
 //!
 //! Manages the lifecycle of MCP servers: starting, stopping, and discovering tools.
 //! Supports both stdio (local process) and HTTP (remote server) transports.
+//!
+//! Stdio servers with a `sandbox` section are launched through the sandbox's
+//! process backend. Their stderr is scanned for denials, and a server the
+//! sandbox stops is marked failed with the violation responsible.
 
 use std::collections::HashMap;
 use std::io::{BufRead, BufReader, Write};
-use std::process::{Child, Command, Stdio};
+use std::process::{Child, ChildStderr, Command, ExitStatus, Stdio};
 use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 
+use cowork_sandbox::{PolicyViolationRecord, SandboxConfig, ViolationOutcome};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use crate::config::McpServerConfig;
+use crate::processes::{ProcessGuard, ProcessKind, ProcessTracker};
+use crate::tools::process_utils::std_own_process_group;
 use crate::error::{Error, Result, ToolError};
 
 /// Status of an MCP server
@@ -32,14 +41,7 @@ enum McpConnection {
     /// Stdio connection to local process
     Stdio(Child),
     /// HTTP connection to remote server
-    Http {
-        /// Base URL of the server
-        url: String,
-        /// HTTP client
-        client: reqwest::blocking::Client,
-        /// Headers to include in requests
-        headers: HashMap<String, String>,
-    },
+    Http(RemoteServer),
 }
 
 /// Information about a running MCP server
@@ -55,13 +57,284 @@ pub struct McpServerInstance {
     connection: Option<McpConnection>,
     /// Tools provided by this server
     pub tools: Vec<McpToolInfo>,
+    /// What the sandbox reports about the running process (None = unsandboxed)
+    sandbox: Option<SandboxWatch>,
+    /// Registration with the process tracker while the process runs
+    tracked: Option<ProcessGuard>,
+}
+
+/// How long to wait for a server whose pipe closed to exit
+const EXIT_GRACE: Duration = Duration::from_secs(1);
+
+/// Denials reported by a sandboxed server, read from its stderr
+#[derive(Debug)]
+struct SandboxWatch {
+    config: SandboxConfig,
+    command_line: String,
+    violations: Arc<Mutex<Vec<PolicyViolationRecord>>>,
+    stderr_reader: Option<JoinHandle<()>>,
+}
+
+impl SandboxWatch {
+    /// Scan `stderr` for denials on a background thread (which also keeps
+    /// the pipe from filling up)
+    fn new(config: SandboxConfig, command_line: String, stderr: Option<ChildStderr>) -> Self {
+        let violations = Arc::new(Mutex::new(Vec::new()));
+        let stderr_reader = stderr.map(|stderr| {
+            let (config, command_line, violations) = (config.clone(), command_line.clone(), violations.clone());
+            std::thread::spawn(move || {
+                for line in BufReader::new(stderr).lines().map_while(std::result::Result::ok) {
+                    let records = cowork_sandbox::audit::scan_stderr(&config, &command_line, &line);
+                    for record in &records {
+                        tracing::warn!("Sandbox denied {} to `{}` ({})", record.target, command_line, record.rule);
+                    }
+                    violations.lock().unwrap().extend(records);
+                }
+            })
+        });
+        Self { config, command_line, violations, stderr_reader }
+    }
+
+    /// The violation that stopped the process, if the sandbox did
+    fn stopping_violation(&mut self, status: &ExitStatus) -> Option<PolicyViolationRecord> {
+        if let Some(record) = cowork_sandbox::audit::exit_violation(&self.config, &self.command_line, status) {
+            return Some(record);
+        }
+        // Let the reader catch up with what the process wrote before exiting
+        let deadline = Instant::now() + EXIT_GRACE;
+        while self.stderr_reader.as_ref().is_some_and(|r| !r.is_finished()) && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        self.violations
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|r| r.outcome == ViolationOutcome::Denied)
+            .cloned()
+    }
+
+    fn violations(&self) -> Vec<PolicyViolationRecord> {
+        self.violations.lock().unwrap().clone()
+    }
+}
+
+/// Why a stdio server's process exited, or None while it is running
+///
+/// With `grace`, waits briefly for a process whose pipe just closed.
+fn exit_reason(child: &mut Child, sandbox: Option<&mut SandboxWatch>, grace: bool) -> Option<String> {
+    let deadline = Instant::now() + if grace { EXIT_GRACE } else { Duration::ZERO };
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(20)),
+            _ => return None,
+        }
+    };
+    match sandbox.and_then(|watch| watch.stopping_violation(&status)) {
+        Some(record) => Some(format!("Stopped by the sandbox: {} ({})", record.target, record.rule)),
+        None => Some(format!("Server exited ({})", status)),
+    }
+}
+
+/// Spawn a stdio server, confined when its config has a `sandbox` section
+///
+/// Returns the sandbox config the child runs under (None = unsandboxed).
+/// A server that can't be sandboxed is only started without it when
+/// `sandbox.required` is false.
+fn spawn_stdio(name: &str, config: &McpServerConfig) -> Result<(Child, Option<SandboxConfig>)> {
+    if let Some(sandbox) = &config.sandbox {
+        let sandbox_config = sandbox.to_sandbox_config(name, &config.env);
+        match spawn_sandboxed(&sandbox_config, &config.command, &config.args) {
+            Ok(child) => return Ok((child, Some(sandbox_config))),
+            Err(e) if sandbox.required => {
+                return Err(mcp_error(format!(
+                    "Refusing to start MCP server '{}' unsandboxed: {} (set sandbox.required = false to allow it)",
+                    name, e
+                )));
+            }
+            Err(e) => {
+                tracing::warn!("Starting MCP server '{}' without its sandbox: {}", name, e);
+            }
+        }
+    }
+
+    let mut cmd = Command::new(&config.command);
+    std_own_process_group(&mut cmd);
+    let child = cmd
+        .args(&config.args)
+        .envs(&config.env)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| mcp_error(format!("Failed to start MCP server '{}': {}", name, e)))?;
+    Ok((child, None))
+}
+
+fn spawn_sandboxed(config: &SandboxConfig, command: &str, args: &[String]) -> std::result::Result<Child, String> {
+    std::fs::create_dir_all(&config.root)
+        .map_err(|e| format!("couldn't create sandbox root {}: {}", config.root.display(), e))?;
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    cowork_sandbox::process::spawn_sandboxed(config, command, &args).map_err(|e| e.to_string())
+}
+
+/// How long the event stream to a remote server may stay open before it is
+/// reopened (which the transport does without losing requests)
+#[cfg(feature = "mcp")]
+const EVENT_STREAM_TIMEOUT: Duration = Duration::from_secs(3600);
+
+/// A request for the remote server's worker thread, with where to send the result
+#[cfg(feature = "mcp")]
+type RemoteRequest = (String, Option<Value>, std::sync::mpsc::Sender<std::result::Result<Value, String>>);
+
+/// Connection to a remote (http) server
+///
+/// The server is driven through cowork-mcp's HTTP/SSE transport on a
+/// thread of its own, so the manager's blocking API can wait on it from
+/// anywhere.
+#[cfg(feature = "mcp")]
+struct RemoteServer {
+    url: String,
+    requests: std::sync::mpsc::Sender<RemoteRequest>,
+}
+
+#[cfg(feature = "mcp")]
+impl RemoteServer {
+    /// Open the event stream and run the initialize handshake
+    fn connect(name: &str, config: &McpServerConfig) -> Result<Self> {
+        use cowork_mcp::client::{ClientInfo, McpClient};
+        use cowork_mcp::transport::HttpSseTransport;
+
+        let url = config.url.clone().ok_or_else(|| mcp_error("HTTP transport requires a URL"))?;
+        let settings = crate::http::settings();
+        let client = settings
+            .client_builder()?
+            .timeout(EVENT_STREAM_TIMEOUT)
+            .build()
+            .map_err(|e| mcp_error(format!("Failed to create HTTP client: {}", e)))?;
+        let transport = HttpSseTransport::with_client(url.clone(), client)
+            .with_headers(config.headers.clone())
+            .with_timeout(settings.timeout);
+
+        let (requests, incoming) = std::sync::mpsc::channel::<RemoteRequest>();
+        let (ready_tx, ready) = std::sync::mpsc::channel();
+        std::thread::Builder::new()
+            .name(format!("mcp-{}", name))
+            .spawn(move || {
+                let runtime = match tokio::runtime::Builder::new_multi_thread()
+                    .worker_threads(1)
+                    .enable_all()
+                    .build()
+                {
+                    Ok(runtime) => runtime,
+                    Err(e) => {
+                        let _ = ready_tx.send(Err(e.to_string()));
+                        return;
+                    }
+                };
+                let mut client = McpClient::new(transport);
+                let info = ClientInfo {
+                    name: "cowork".to_string(),
+                    version: env!("CARGO_PKG_VERSION").to_string(),
+                };
+                let initialized = runtime.block_on(client.initialize(info)).map(|_| ()).map_err(|e| e.to_string());
+                let failed = initialized.is_err();
+                let _ = ready_tx.send(initialized);
+                if failed {
+                    return;
+                }
+                // Runs until the connection is dropped
+                while let Ok((method, params, reply)) = incoming.recv() {
+                    let result = runtime.block_on(client.request(&method, params)).map_err(|e| e.to_string());
+                    let _ = reply.send(result);
+                }
+            })
+            .map_err(|e| mcp_error(format!("Failed to start MCP client thread: {}", e)))?;
+
+        match ready.recv() {
+            Ok(Ok(())) => Ok(Self { url, requests }),
+            Ok(Err(e)) => Err(mcp_error(format!("Failed to connect to MCP server '{}': {}", name, e))),
+            Err(_) => Err(mcp_error(format!("MCP client thread for '{}' exited", name))),
+        }
+    }
+
+    /// Send a request and wait for its result
+    fn request(&self, method: &str, params: Option<Value>) -> std::result::Result<Value, String> {
+        let (reply, result) = std::sync::mpsc::channel();
+        self.requests
+            .send((method.to_string(), params, reply))
+            .map_err(|_| "connection closed".to_string())?;
+        result.recv().map_err(|_| "connection closed".to_string())?
+    }
+}
+
+/// Connection to a remote (http) server
+///
+/// Without the `mcp` feature each request is a plain POST answered in the
+/// response body.
+#[cfg(not(feature = "mcp"))]
+struct RemoteServer {
+    url: String,
+    client: reqwest::blocking::Client,
+    headers: HashMap<String, String>,
+    request_id: std::sync::atomic::AtomicU64,
+}
+
+#[cfg(not(feature = "mcp"))]
+impl RemoteServer {
+    /// Run the initialize handshake
+    fn connect(name: &str, config: &McpServerConfig) -> Result<Self> {
+        let url = config.url.clone().ok_or_else(|| mcp_error("HTTP transport requires a URL"))?;
+        let remote = Self {
+            url,
+            client: crate::http::blocking_client()?,
+            headers: config.headers.clone(),
+            request_id: std::sync::atomic::AtomicU64::new(1),
+        };
+        let params = serde_json::json!({
+            "protocolVersion": "2024-11-05",
+            "capabilities": {},
+            "clientInfo": {
+                "name": "cowork",
+                "version": env!("CARGO_PKG_VERSION")
+            }
+        });
+        remote
+            .request("initialize", Some(params))
+            .map_err(|e| mcp_error(format!("Failed to connect to MCP server '{}': {}", name, e)))?;
+        Ok(remote)
+    }
+
+    /// Send a request and wait for its result
+    fn request(&self, method: &str, params: Option<Value>) -> std::result::Result<Value, String> {
+        let request = McpRequest {
+            jsonrpc: "2.0",
+            id: self.request_id.fetch_add(1, std::sync::atomic::Ordering::SeqCst),
+            method: method.to_string(),
+            params,
+        };
+        let mut builder = self.client.post(&self.url);
+        for (key, value) in &self.headers {
+            builder = builder.header(key, value);
+        }
+        let response = builder.json(&request).send().map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("HTTP {}", response.status()));
+        }
+        let response: McpResponse = response.json().map_err(|e| format!("invalid response: {}", e))?;
+        match response.error {
+            Some(err) => Err(format!("{} ({})", err.message, err.code)),
+            None => Ok(response.result.unwrap_or(Value::Null)),
+        }
+    }
 }
 
 impl std::fmt::Debug for McpConnection {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             McpConnection::Stdio(_) => write!(f, "Stdio(...)"),
-            McpConnection::Http { url, .. } => write!(f, "Http({})", url),
+            McpConnection::Http(remote) => write!(f, "Http({})", remote.url),
         }
     }
 }
@@ -143,6 +416,8 @@ impl McpServerManager {
                 status: McpServerStatus::Stopped,
                 connection: None,
                 tools: Vec::new(),
+                sandbox: None,
+                tracked: None,
             });
         }
         drop(servers);
@@ -159,6 +434,8 @@ impl McpServerManager {
             status: McpServerStatus::Stopped,
             connection: None,
             tools: Vec::new(),
+            sandbox: None,
+            tracked: None,
         });
     }
 
@@ -174,6 +451,16 @@ impl McpServerManager {
 
     /// Start an MCP server by name
     pub fn start_server(&self, name: &str) -> Result<()> {
+        let result = self.start(name);
+        crate::telemetry::counter(
+            crate::telemetry::MCP_SERVER_STARTS,
+            1,
+            &[("server", name), ("outcome", crate::telemetry::outcome(&result))],
+        );
+        result
+    }
+
+    fn start(&self, name: &str) -> Result<()> {
         let mut servers = self.servers.lock().unwrap();
 
         let instance = servers.get_mut(name)
@@ -187,63 +474,36 @@ impl McpServerManager {
 
         // Check if this is HTTP or stdio transport
         if instance.config.is_http() {
-            // HTTP transport
-            let url = instance.config.url.clone()
-                .ok_or_else(|| mcp_error("HTTP transport requires a URL"))?;
-
-            let client = reqwest::blocking::Client::new();
-            let headers = instance.config.headers.clone();
-
-            // Test connection with initialize request
-            let init_request = McpRequest {
-                jsonrpc: "2.0",
-                id: self.next_request_id(),
-                method: "initialize".to_string(),
-                params: Some(serde_json::json!({
-                    "protocolVersion": "2024-11-05",
-                    "capabilities": {},
-                    "clientInfo": {
-                        "name": "cowork",
-                        "version": "0.1.0"
-                    }
-                })),
+            // HTTP transport: connecting runs the initialize handshake
+            let remote = match RemoteServer::connect(name, &instance.config) {
+                Ok(remote) => remote,
+                Err(e) => {
+                    instance.status = McpServerStatus::Failed(e.to_string());
+                    return Err(e);
+                }
             };
-
-            let mut request = client.post(&url);
-            for (key, value) in &headers {
-                request = request.header(key, value);
-            }
-            request = request.header("Content-Type", "application/json");
-
-            let response = request.json(&init_request).send()
-                .map_err(|e| mcp_error(format!("Failed to connect to MCP server '{}': {}", name, e)))?;
-
-            if !response.status().is_success() {
-                instance.status = McpServerStatus::Failed(format!("HTTP {}", response.status()));
-                return Err(mcp_error(format!("MCP server returned HTTP {}", response.status())));
-            }
-
-            let mcp_response: McpResponse = response.json()
-                .map_err(|e| mcp_error(format!("Invalid init response: {}", e)))?;
-
-            if let Some(err) = mcp_response.error {
-                instance.status = McpServerStatus::Failed(err.message.clone());
-                return Err(mcp_error(format!("MCP init failed ({}): {}", err.code, err.message)));
-            }
-
-            instance.connection = Some(McpConnection::Http { url, client, headers });
+            instance.connection = Some(McpConnection::Http(remote));
             instance.status = McpServerStatus::Running;
         } else {
             // Stdio transport
-            let mut cmd = Command::new(&instance.config.command);
-            cmd.args(&instance.config.args)
-                .envs(&instance.config.env)
-                .stdin(Stdio::piped())
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped());
-
-            let mut child = cmd.spawn()
-                .map_err(|e| mcp_error(format!("Failed to start MCP server '{}': {}", name, e)))?;
+            let (mut child, sandbox_config) = match spawn_stdio(name, &instance.config) {
+                Ok(spawned) => spawned,
+                Err(e) => {
+                    instance.status = McpServerStatus::Failed(e.to_string());
+                    return Err(e);
+                }
+            };
+            // Unsandboxed servers lead a process group of their own; the guard
+            // also stops a server that fails to initialize
+            let group = cfg!(unix) && sandbox_config.is_none();
+            let tracked = ProcessTracker::global().track(child.id(), ProcessKind::Mcp, name, group);
+            instance.sandbox = sandbox_config.map(|config| {
+                let command_line = std::iter::once(instance.config.command.as_str())
+                    .chain(instance.config.args.iter().map(String::as_str))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                SandboxWatch::new(config, command_line, child.stderr.take())
+            });
 
             // Send initialize request
             let init_request = McpRequest {
@@ -276,8 +536,10 @@ impl McpServerManager {
 
                 match reader.read_line(&mut line) {
                     Ok(0) => {
-                        instance.status = McpServerStatus::Failed("Server closed connection".to_string());
-                        return Err(mcp_error("MCP server closed connection during init"));
+                        let reason = exit_reason(&mut child, instance.sandbox.as_mut(), true)
+                            .unwrap_or_else(|| "Server closed connection".to_string());
+                        instance.status = McpServerStatus::Failed(reason.clone());
+                        return Err(mcp_error(format!("MCP server closed connection during init: {}", reason)));
                     }
                     Ok(_) => {
                         let response: McpResponse = serde_json::from_str(&line)
@@ -311,6 +573,7 @@ impl McpServerManager {
             }
 
             instance.connection = Some(McpConnection::Stdio(child));
+            instance.tracked = Some(tracked);
             instance.status = McpServerStatus::Running;
         }
 
@@ -335,16 +598,17 @@ impl McpServerManager {
                     let _ = process.kill();
                     let _ = process.wait();
                 }
-                McpConnection::Http { .. } => {
-                    // HTTP connections don't need explicit shutdown
-                    // Just drop the client
+                McpConnection::Http(_) => {
+                    // Dropping the connection closes the event stream
                 }
             }
         }
 
         instance.connection = None;
+        instance.tracked = None;
         instance.status = McpServerStatus::Stopped;
         instance.tools.clear();
+        instance.sandbox = None;
 
         Ok(())
     }
@@ -384,10 +648,18 @@ impl McpServerManager {
     }
 
     /// List all servers and their status
+    ///
+    /// Running stdio servers whose process has exited are marked failed.
     pub fn list_servers(&self) -> Vec<McpServerInfo> {
-        let servers = self.servers.lock().unwrap();
-        servers.values()
+        let mut servers = self.servers.lock().unwrap();
+        servers.values_mut()
             .map(|s| {
+                if s.status == McpServerStatus::Running
+                    && let Some(McpConnection::Stdio(process)) = &mut s.connection
+                    && let Some(reason) = exit_reason(process, s.sandbox.as_mut(), false)
+                {
+                    s.status = McpServerStatus::Failed(reason);
+                }
                 // Use URL for HTTP servers, command for stdio
                 let command = if s.config.is_http() {
                     s.config.url.clone().unwrap_or_default()
@@ -400,6 +672,8 @@ impl McpServerManager {
                     enabled: s.config.enabled,
                     status: s.status.clone(),
                     tool_count: s.tools.len(),
+                    sandboxed: s.sandbox.is_some(),
+                    violations: s.sandbox.as_ref().map(SandboxWatch::violations).unwrap_or_default(),
                 }
             })
             .collect()
@@ -439,26 +713,11 @@ impl McpServerManager {
         };
 
         let response_result: Option<Value> = match &mut instance.connection {
-            Some(McpConnection::Http { url, client, headers }) => {
-                // HTTP transport
-                let mut request = client.post(url.as_str());
-                for (key, value) in headers.iter() {
-                    request = request.header(key, value);
-                }
-                request = request.header("Content-Type", "application/json");
-
-                let response = request.json(&tools_request).send()
-                    .map_err(|e| mcp_error(format!("Failed to send tools request: {}", e)))?;
-
-                let mcp_response: McpResponse = response.json()
-                    .map_err(|e| mcp_error(format!("Invalid tools response: {}", e)))?;
-
-                if let Some(err) = mcp_response.error {
-                    return Err(mcp_error(format!("Tools list failed: {}", err.message)));
-                }
-
-                mcp_response.result
-            }
+            Some(McpConnection::Http(remote)) => Some(
+                remote
+                    .request("tools/list", None)
+                    .map_err(|e| mcp_error(format!("Tools list failed: {}", e)))?,
+            ),
             Some(McpConnection::Stdio(process)) => {
                 // Stdio transport
                 if let Some(ref mut stdin) = process.stdin {
@@ -479,6 +738,10 @@ impl McpServerManager {
 
                     match reader.read_line(&mut line) {
                         Ok(0) => {
+                            if let Some(reason) = exit_reason(process, instance.sandbox.as_mut(), true) {
+                                instance.status = McpServerStatus::Failed(reason.clone());
+                                return Err(mcp_error(format!("MCP server closed connection: {}", reason)));
+                            }
                             return Err(mcp_error("MCP server closed connection"));
                         }
                         Ok(_) => {
@@ -529,6 +792,17 @@ impl McpServerManager {
 
     /// Execute a tool call on an MCP server (lazy-starts if needed)
     pub fn call_tool(&self, server_name: &str, tool_name: &str, arguments: Value) -> Result<Value> {
+        let started = std::time::Instant::now();
+        let result = self.call(server_name, tool_name, arguments);
+        crate::telemetry::histogram(
+            crate::telemetry::MCP_CALL_SECONDS,
+            started.elapsed().as_secs_f64(),
+            &[("server", server_name), ("outcome", crate::telemetry::outcome(&result))],
+        );
+        result
+    }
+
+    fn call(&self, server_name: &str, tool_name: &str, arguments: Value) -> Result<Value> {
         // Check if server needs to be started (lazy start)
         {
             let servers = self.servers.lock().unwrap();
@@ -560,35 +834,21 @@ impl McpServerManager {
         };
 
         match &mut instance.connection {
-            Some(McpConnection::Http { url, client, headers }) => {
-                // HTTP transport - synchronous call and response
-                let mut request = client.post(url.as_str());
-                for (key, value) in headers.iter() {
-                    request = request.header(key, value);
-                }
-                request = request.header("Content-Type", "application/json");
-
-                let response = request.json(&call_request).send()
-                    .map_err(|e| mcp_error(format!("Failed to send tool call: {}", e)))?;
-
-                let mcp_response: McpResponse = response.json()
-                    .map_err(|e| mcp_error(format!("Invalid tool response: {}", e)))?;
-
-                if let Some(err) = mcp_response.error {
-                    return Err(mcp_error(format!("Tool call failed: {}", err.message)));
-                }
-
-                Ok(mcp_response.result.unwrap_or(Value::Null))
-            }
+            Some(McpConnection::Http(remote)) => remote
+                .request("tools/call", call_request.params)
+                .map_err(|e| mcp_error(format!("Tool call failed: {}", e))),
             Some(McpConnection::Stdio(process)) => {
                 // Stdio transport
                 if let Some(ref mut stdin) = process.stdin {
                     let msg = serde_json::to_string(&call_request)
                         .map_err(|e| mcp_error(format!("Failed to serialize call request: {}", e)))?;
-                    writeln!(stdin, "{}", msg)
-                        .map_err(|e| mcp_error(format!("Failed to write to MCP server: {}", e)))?;
-                    stdin.flush()
-                        .map_err(|e| mcp_error(format!("Failed to flush to MCP server: {}", e)))?;
+                    if let Err(e) = writeln!(stdin, "{}", msg).and_then(|_| stdin.flush()) {
+                        if let Some(reason) = exit_reason(process, instance.sandbox.as_mut(), true) {
+                            instance.status = McpServerStatus::Failed(reason.clone());
+                            return Err(mcp_error(format!("MCP server '{}' is not running: {}", server_name, reason)));
+                        }
+                        return Err(mcp_error(format!("Failed to write to MCP server: {}", e)));
+                    }
                 } else {
                     return Err(mcp_error("MCP server stdin not available"));
                 }
@@ -628,6 +888,10 @@ pub struct McpServerInfo {
     pub status: McpServerStatus,
     /// Number of tools provided
     pub tool_count: usize,
+    /// Whether the running process is confined by the sandbox
+    pub sandboxed: bool,
+    /// Denials the sandbox reported for the running process
+    pub violations: Vec<PolicyViolationRecord>,
 }
 
 impl Drop for McpServerManager {
@@ -0,0 +1,206 @@
+//! MCP server mode: cowork's own tools for external MCP clients
+//!
+//! `ToolServer` implements `McpHandler` over a `ToolRegistry`, so a client
+//! such as Claude Desktop can use the workspace's file, shell and LSP tools.
+//! Calls go through the same `ToolApprovalConfig` as a session, but an MCP
+//! client has no way to answer an approval request: a call that would need
+//! one is refused, unless the config approves everything for the session
+//! (`cowork mcp-serve --yolo`). The organization policy's required approvals
+//! and calls flagged as possible secret exfiltration are refused even then.
+//!
+//! Workspace files are exposed read-only as `file://` resources.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use cowork_mcp::client::ToolCallResult;
+use cowork_mcp::server::{McpHandler, McpServer, PromptContent, ResourceContent};
+use cowork_mcp::{McpPrompt, McpResource, McpTool, ResourcesCapability, ServerCapabilities, ToolsCapability};
+use serde_json::Value;
+
+use crate::approval::ToolApprovalConfig;
+use crate::context::gather::TREE_SKIP;
+use crate::tools::filesystem::validate_path;
+use crate::tools::interaction::ASK_QUESTION_TOOL_NAME;
+use crate::tools::{ToolExecutionContext, ToolOutput, ToolRegistry};
+
+/// Tools left out because they only make sense inside a session
+const SESSION_TOOLS: &[&str] = &[ASK_QUESTION_TOOL_NAME, "EnterPlanMode", "ExitPlanMode", "PlanStatus", "TodoWrite"];
+
+/// Most files listed by `resources/list`
+const MAX_RESOURCES: usize = 1000;
+
+/// Largest file `resources/read` returns
+const MAX_RESOURCE_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Serves a tool registry and the workspace's files over MCP
+pub struct ToolServer {
+    workspace: PathBuf,
+    registry: ToolRegistry,
+    approval: ToolApprovalConfig,
+    next_call: AtomicU64,
+}
+
+impl ToolServer {
+    /// Serve `registry`'s tools, minus the session-only ones, gated by `approval`
+    pub fn new(workspace: impl Into<PathBuf>, mut registry: ToolRegistry, approval: ToolApprovalConfig) -> Self {
+        registry.retain(|name| !SESSION_TOOLS.contains(&name));
+        Self {
+            workspace: workspace.into(),
+            registry,
+            approval,
+            next_call: AtomicU64::new(1),
+        }
+    }
+
+    /// An MCP server answering with this handler, advertising tools and resources
+    pub fn into_server(self) -> McpServer<ToolServer> {
+        McpServer::new(Arc::new(self))
+            .with_name("cowork")
+            .with_version(env!("CARGO_PKG_VERSION"))
+            .with_capabilities(ServerCapabilities {
+                tools: Some(ToolsCapability { list_changed: false }),
+                resources: Some(ResourcesCapability {
+                    subscribe: false,
+                    list_changed: false,
+                }),
+                prompts: None,
+            })
+    }
+
+    /// The workspace file a `file://` URI names, if it is inside the workspace
+    fn resource_path(&self, uri: &str) -> Result<PathBuf, String> {
+        let url = url::Url::parse(uri).map_err(|e| format!("Invalid URI {}: {}", uri, e))?;
+        if url.scheme() != "file" {
+            return Err(format!("Unsupported URI scheme '{}': only file:// is served", url.scheme()));
+        }
+        let path = url.to_file_path().map_err(|_| format!("Invalid file URI: {}", uri))?;
+        validate_path(&path, &self.workspace).map_err(|e| e.to_string())
+    }
+}
+
+impl McpHandler for ToolServer {
+    async fn list_tools(&self) -> Vec<McpTool> {
+        let mut tools: Vec<McpTool> = self
+            .registry
+            .all()
+            .iter()
+            .map(|tool| McpTool {
+                name: tool.name().to_string(),
+                description: tool.description().to_string(),
+                input_schema: tool.parameters_schema(),
+            })
+            .collect();
+        tools.sort_by(|a, b| a.name.cmp(&b.name));
+        tools
+    }
+
+    async fn call_tool(&self, name: &str, mut arguments: Value) -> Result<ToolCallResult, String> {
+        let tool = self.registry.get(name).ok_or_else(|| format!("Unknown tool: {}", name))?;
+        if let Err(invalid) = self.registry.check_arguments(name, &mut arguments) {
+            return Err(invalid.to_tool_result());
+        }
+        let findings = self.approval.scan(name, &arguments);
+        if !findings.is_empty() {
+            return Err(format!(
+                "{} needs a person's approval, which can't be given over MCP; the call was not run. {}",
+                name,
+                crate::approval::exfiltration::warning(&findings)
+            ));
+        }
+        if !self.approval.should_auto_approve_with_args(name, &arguments) {
+            return Err(format!(
+                "{} needs approval, which can't be given over MCP; the call was not run",
+                name
+            ));
+        }
+
+        let call_id = format!("mcp-{}", self.next_call.fetch_add(1, Ordering::Relaxed));
+        let ctx = ToolExecutionContext::standalone(call_id, name).approved();
+        match tool.execute(arguments, ctx).await {
+            Ok(output) => Ok(tool_call_result(&output)),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    async fn list_resources(&self) -> Vec<McpResource> {
+        let workspace = self.workspace.clone();
+        tokio::task::spawn_blocking(move || workspace_resources(&workspace))
+            .await
+            .unwrap_or_default()
+    }
+
+    async fn read_resource(&self, uri: &str) -> Result<ResourceContent, String> {
+        let path = self.resource_path(uri)?;
+        let metadata = std::fs::metadata(&path).map_err(|e| e.to_string())?;
+        if !metadata.is_file() {
+            return Err(format!("{} is not a file", path.display()));
+        }
+        if metadata.len() > MAX_RESOURCE_BYTES {
+            return Err(format!("{} is larger than {} bytes", path.display(), MAX_RESOURCE_BYTES));
+        }
+        let bytes = tokio::fs::read(&path).await.map_err(|e| e.to_string())?;
+        let text = String::from_utf8(bytes).map_err(|_| format!("{} is not a text file", path.display()))?;
+        Ok(ResourceContent {
+            uri: uri.to_string(),
+            mime_type: Some("text/plain".to_string()),
+            text: Some(text),
+            blob: None,
+        })
+    }
+
+    async fn list_prompts(&self) -> Vec<McpPrompt> {
+        Vec::new()
+    }
+
+    async fn get_prompt(&self, name: &str, _arguments: HashMap<String, String>) -> Result<PromptContent, String> {
+        Err(format!("Unknown prompt: {}", name))
+    }
+}
+
+/// A tool's output as MCP content: its text, with the error first on failure
+fn tool_call_result(output: &ToolOutput) -> ToolCallResult {
+    let content = match &output.content {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        other => serde_json::to_string_pretty(other).unwrap_or_default(),
+    };
+    let text = match &output.error {
+        Some(error) if content.is_empty() => error.clone(),
+        Some(error) => format!("{}\n{}", error, content),
+        None => content,
+    };
+    ToolCallResult::text(text, !output.success)
+}
+
+/// Files in the workspace as resources, skipping hidden entries and
+/// dependency and build directories
+fn workspace_resources(workspace: &Path) -> Vec<McpResource> {
+    walkdir::WalkDir::new(workspace)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| {
+            if entry.depth() == 0 {
+                return true;
+            }
+            let name = entry.file_name().to_string_lossy();
+            let skip = name.starts_with('.') || (entry.file_type().is_dir() && TREE_SKIP.contains(&name.as_ref()));
+            !skip
+        })
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| {
+            let uri = url::Url::from_file_path(entry.path()).ok()?;
+            let name = entry.path().strip_prefix(workspace).ok()?.to_string_lossy().replace('\\', "/");
+            Some(McpResource {
+                uri: uri.to_string(),
+                name,
+                description: None,
+                mime_type: None,
+            })
+        })
+        .take(MAX_RESOURCES)
+        .collect()
+}
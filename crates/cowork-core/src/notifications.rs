@@ -0,0 +1,244 @@
+//! Attention notifications derived from session output
+//!
+//! Frontends feed every `SessionOutput` through a `NotificationTracker`,
+//! which decides when the user should be alerted: a long turn finished, a
+//! tool is waiting for approval, or the assistant asked a question. How the
+//! alert is shown (OS notification, terminal bell) is up to the frontend.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::config::NotificationsConfig;
+use crate::session::SessionOutput;
+
+/// Longest session title shown in a notification
+const MAX_TITLE_CHARS: usize = 50;
+
+/// Why the user is being notified
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationKind {
+    TurnCompleted,
+    ApprovalRequired,
+    QuestionAsked,
+}
+
+/// An alert for the user, ready to display
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    pub kind: NotificationKind,
+    /// Session that needs attention (to focus it when the alert is clicked)
+    pub session_id: String,
+    /// Session title, from its first user message
+    pub title: String,
+    pub body: String,
+}
+
+#[derive(Debug, Default)]
+struct SessionActivity {
+    title: Option<String>,
+    turn_started: Option<Instant>,
+}
+
+/// Tracks turn timing and titles per session to produce notifications
+#[derive(Debug, Default)]
+pub struct NotificationTracker {
+    sessions: HashMap<String, SessionActivity>,
+}
+
+impl NotificationTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record an output and return a notification if it warrants one
+    pub fn observe(
+        &mut self,
+        config: &NotificationsConfig,
+        session_id: &str,
+        output: &SessionOutput,
+    ) -> Option<Notification> {
+        self.observe_at(config, session_id, output, Instant::now())
+    }
+
+    fn observe_at(
+        &mut self,
+        config: &NotificationsConfig,
+        session_id: &str,
+        output: &SessionOutput,
+        now: Instant,
+    ) -> Option<Notification> {
+        let activity = self.sessions.entry(session_id.to_string()).or_default();
+
+        let (kind, body) = match output {
+            SessionOutput::Ready => return None,
+            SessionOutput::Idle { .. } => {
+                let started = activity.turn_started.take()?;
+                let elapsed = now.saturating_duration_since(started);
+                if !config.turn_completed || elapsed < Duration::from_secs(config.min_turn_secs) {
+                    return None;
+                }
+                (
+                    NotificationKind::TurnCompleted,
+                    format!("Finished after {}", format_elapsed(elapsed)),
+                )
+            }
+            SessionOutput::UserMessage { content, .. } => {
+                if activity.title.is_none() {
+                    activity.title = title_from_message(content);
+                }
+                activity.turn_started.get_or_insert(now);
+                return None;
+            }
+            SessionOutput::ToolPending { name, .. } => {
+                activity.turn_started.get_or_insert(now);
+                if !config.approval_required {
+                    return None;
+                }
+                (NotificationKind::ApprovalRequired, format!("Approval required: {}", name))
+            }
+            SessionOutput::Question { questions, .. } => {
+                activity.turn_started.get_or_insert(now);
+                if !config.question_asked {
+                    return None;
+                }
+                let body = questions
+                    .first()
+                    .map(|q| q.question.clone())
+                    .unwrap_or_else(|| "The assistant has a question".to_string());
+                (NotificationKind::QuestionAsked, body)
+            }
+            _ => {
+                activity.turn_started.get_or_insert(now);
+                return None;
+            }
+        };
+
+        Some(Notification {
+            kind,
+            session_id: session_id.to_string(),
+            title: activity.title.clone().unwrap_or_else(|| session_id.to_string()),
+            body,
+        })
+    }
+
+    /// Forget a closed session
+    pub fn remove(&mut self, session_id: &str) {
+        self.sessions.remove(session_id);
+    }
+}
+
+/// First line of a message, trimmed to `MAX_TITLE_CHARS`
+fn title_from_message(content: &str) -> Option<String> {
+    let line = content.lines().map(str::trim).find(|l| !l.is_empty())?;
+    if line.chars().count() > MAX_TITLE_CHARS {
+        let truncated: String = line.chars().take(MAX_TITLE_CHARS - 3).collect();
+        Some(format!("{}...", truncated))
+    } else {
+        Some(line.to_string())
+    }
+}
+
+fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    if secs >= 60 {
+        format!("{}m {}s", secs / 60, secs % 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::{QuestionInfo, QuestionOption};
+
+    fn pending(name: &str) -> SessionOutput {
+        SessionOutput::tool_pending("call_1", name, serde_json::json!({}), None)
+    }
+
+    #[test]
+    fn test_long_turn_notifies_on_idle() {
+        let config = NotificationsConfig::default();
+        let mut tracker = NotificationTracker::new();
+        let start = Instant::now();
+
+        let msg = SessionOutput::user_message("m1", "Refactor the parser\nand add tests");
+        assert!(tracker.observe_at(&config, "s1", &msg, start).is_none());
+        let done = tracker
+            .observe_at(&config, "s1", &SessionOutput::idle(), start + Duration::from_secs(95))
+            .unwrap();
+        assert_eq!(done.kind, NotificationKind::TurnCompleted);
+        assert_eq!(done.title, "Refactor the parser");
+        assert_eq!(done.body, "Finished after 1m 35s");
+
+        // A second idle without activity doesn't notify again
+        assert!(tracker
+            .observe_at(&config, "s1", &SessionOutput::idle(), start + Duration::from_secs(200))
+            .is_none());
+    }
+
+    #[test]
+    fn test_short_turn_is_quiet() {
+        let config = NotificationsConfig::default();
+        let mut tracker = NotificationTracker::new();
+        let start = Instant::now();
+
+        tracker.observe_at(&config, "s1", &SessionOutput::user_message("m1", "hi"), start);
+        assert!(tracker
+            .observe_at(&config, "s1", &SessionOutput::idle(), start + Duration::from_secs(3))
+            .is_none());
+    }
+
+    #[test]
+    fn test_approval_and_question() {
+        let config = NotificationsConfig::default();
+        let mut tracker = NotificationTracker::new();
+
+        let approval = tracker.observe(&config, "s1", &pending("Bash")).unwrap();
+        assert_eq!(approval.kind, NotificationKind::ApprovalRequired);
+        assert_eq!(approval.body, "Approval required: Bash");
+        // No user message seen yet, so the session ID stands in for the title
+        assert_eq!(approval.title, "s1");
+
+        let question = SessionOutput::Question {
+            request_id: "q1".to_string(),
+            questions: vec![QuestionInfo {
+                question: "Which database?".to_string(),
+                header: None,
+                options: vec![QuestionOption {
+                    label: "SQLite".to_string(),
+                    description: None,
+                }],
+                multi_select: false,
+            }],
+            subagent_id: None,
+        };
+        let asked = tracker.observe(&config, "s1", &question).unwrap();
+        assert_eq!(asked.kind, NotificationKind::QuestionAsked);
+        assert_eq!(asked.body, "Which database?");
+    }
+
+    #[test]
+    fn test_disabled_kinds() {
+        let config = NotificationsConfig {
+            turn_completed: false,
+            approval_required: false,
+            ..Default::default()
+        };
+        let mut tracker = NotificationTracker::new();
+        let start = Instant::now();
+
+        assert!(tracker.observe_at(&config, "s1", &pending("Bash"), start).is_none());
+        assert!(tracker
+            .observe_at(&config, "s1", &SessionOutput::idle(), start + Duration::from_secs(600))
+            .is_none());
+    }
+
+    #[test]
+    fn test_title_truncated() {
+        let long = "é".repeat(80);
+        let title = title_from_message(&long).unwrap();
+        assert_eq!(title.chars().count(), MAX_TITLE_CHARS);
+        assert!(title.ends_with("..."));
+    }
+}
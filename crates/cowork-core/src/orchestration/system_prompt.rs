@@ -4,10 +4,12 @@
 //! This module bridges the legacy static prompt approach with the new dynamic
 //! prompt builder system.
 
+use std::collections::HashMap;
 use std::path::Path;
 use std::sync::Arc;
 
 use crate::config::PromptSystemConfig;
+use crate::context::MemoryHierarchy;
 use crate::prompt::{
     builtin, AssembledPrompt, ComponentRegistry, HooksConfig,
     PromptBuilder, TemplateVars,
@@ -33,6 +35,14 @@ pub struct SystemPrompt {
     registry: Option<Arc<ComponentRegistry>>,
     /// Hooks configuration
     hooks: Option<HooksConfig>,
+    /// Appended after the base prompt (from `[prompt] append_file`)
+    append: Option<String>,
+    /// Marked sections to strip from the base prompt
+    disabled_sections: Vec<String>,
+    /// `${CUSTOM:key}` variables from `[prompt] variables`
+    custom_vars: HashMap<String, String>,
+    /// Memory files (CLAUDE.md and friends), added after the base prompt
+    memory: Option<String>,
 }
 
 impl Default for SystemPrompt {
@@ -50,6 +60,10 @@ impl SystemPrompt {
             template_vars: None,
             registry: None,
             hooks: None,
+            append: None,
+            disabled_sections: Vec::new(),
+            custom_vars: HashMap::new(),
+            memory: None,
         }
     }
 
@@ -61,6 +75,10 @@ impl SystemPrompt {
             template_vars: None,
             registry: None,
             hooks: None,
+            append: None,
+            disabled_sections: Vec::new(),
+            custom_vars: HashMap::new(),
+            memory: None,
         }
     }
 
@@ -95,7 +113,56 @@ impl SystemPrompt {
             template_vars: None,
             registry: Some(Arc::new(registry)),
             hooks: Some(hooks),
-        })
+            append: None,
+            disabled_sections: Vec::new(),
+            custom_vars: HashMap::new(),
+            memory: None,
+        }
+        .with_prompt_config(config, workspace_path))
+    }
+
+    /// Apply the `[prompt]` file, section and variable options
+    ///
+    /// `override_file` replaces the base prompt, `append_file` is added after
+    /// it, `disable_sections` strips marked sections, and `variables` are
+    /// substituted as `${CUSTOM:key}`. Relative paths are resolved against
+    /// the workspace; unreadable files are logged and skipped.
+    pub fn with_prompt_config(mut self, config: &PromptSystemConfig, workspace_path: &Path) -> Self {
+        if let Some(path) = &config.override_file {
+            let path = workspace_path.join(path);
+            match std::fs::read_to_string(&path) {
+                Ok(base) => {
+                    tracing::warn!(
+                        "SYSTEM PROMPT OVERRIDDEN: using {} instead of the built-in prompt",
+                        path.display()
+                    );
+                    self.base = base;
+                }
+                Err(e) => tracing::warn!(
+                    "Failed to read prompt override_file {}: {} (using the built-in prompt)",
+                    path.display(),
+                    e
+                ),
+            }
+        }
+
+        if let Some(path) = &config.append_file {
+            let path = workspace_path.join(path);
+            match std::fs::read_to_string(&path) {
+                Ok(append) => self.append = Some(append),
+                Err(e) => tracing::warn!("Failed to read prompt append_file {}: {}", path.display(), e),
+            }
+        }
+
+        let known = builtin::section_ids(&self.base);
+        for id in &config.disable_sections {
+            if !known.contains(&id.as_str()) {
+                tracing::warn!("Unknown prompt section '{}' in disable_sections (known: {})", id, known.join(", "));
+            }
+        }
+        self.disabled_sections = config.disable_sections.clone();
+        self.custom_vars = config.variables.clone();
+        self
     }
 
     /// Add workspace context to the prompt
@@ -115,6 +182,28 @@ impl SystemPrompt {
         self
     }
 
+    /// Add the memory hierarchy's files to the prompt
+    ///
+    /// Their content is added as-is, without template substitution.
+    pub fn with_memory(mut self, memory: &MemoryHierarchy) -> Self {
+        if memory.is_empty() {
+            return self;
+        }
+        let mut section = String::from(
+            "# Memory\nInstructions from the user's memory files. Follow them; they take precedence over the defaults above.",
+        );
+        for file in &memory.files {
+            section.push_str(&format!(
+                "\n\nContents of {} ({}):\n\n{}",
+                file.path.display(),
+                file.tier,
+                file.content.trim_end()
+            ));
+        }
+        self.memory = Some(section);
+        self
+    }
+
     /// Add template variables for substitution
     pub fn with_template_vars(mut self, vars: TemplateVars) -> Self {
         self.template_vars = Some(vars);
@@ -143,14 +232,40 @@ impl SystemPrompt {
         self
     }
 
-    /// Build the final system prompt string (legacy interface)
-    pub fn build(&self) -> String {
-        let mut prompt = self.base.clone();
+    /// Base prompt with sections stripped, the append file added, and
+    /// template variables substituted
+    fn render_base(&self) -> String {
+        let mut prompt = builtin::render_sections(&self.base, &self.disabled_sections);
+        if let Some(append) = &self.append {
+            if !prompt.ends_with('\n') {
+                prompt.push('\n');
+            }
+            prompt.push('\n');
+            prompt.push_str(append.trim_end());
+        }
 
-        // Apply template variable substitution if available
-        if let Some(vars) = &self.template_vars {
-            prompt = vars.substitute(&prompt);
+        let mut prompt = match &self.template_vars {
+            // Variables set on the template vars win over the config's
+            Some(vars) if !self.custom_vars.is_empty() => {
+                let mut vars = vars.clone();
+                for (key, value) in &self.custom_vars {
+                    vars.custom.entry(key.clone()).or_insert_with(|| value.clone());
+                }
+                vars.substitute(&prompt)
+            }
+            Some(vars) => vars.substitute(&prompt),
+            None => prompt,
+        };
+        if let Some(memory) = &self.memory {
+            prompt.push_str("\n\n");
+            prompt.push_str(memory);
         }
+        prompt
+    }
+
+    /// Build the final system prompt string (legacy interface)
+    pub fn build(&self) -> String {
+        let mut prompt = self.render_base();
 
         // Append context if present
         if let Some(ctx) = &self.context {
@@ -165,13 +280,7 @@ impl SystemPrompt {
     /// This is the preferred method for new code, as it returns
     /// the full assembled prompt with tool restrictions and metadata.
     pub fn build_assembled(&self) -> AssembledPrompt {
-        let base = if let Some(vars) = &self.template_vars {
-            vars.substitute(&self.base)
-        } else {
-            self.base.clone()
-        };
-
-        let mut builder = PromptBuilder::new(base);
+        let mut builder = PromptBuilder::new(self.render_base());
 
         // Add context if present
         if let Some(ctx) = &self.context {
@@ -215,13 +324,7 @@ impl SystemPrompt {
     ///
     /// This allows further customization before building the final prompt.
     pub fn to_builder(&self) -> PromptBuilder {
-        let base = if let Some(vars) = &self.template_vars {
-            vars.substitute(&self.base)
-        } else {
-            self.base.clone()
-        };
-
-        let mut builder = PromptBuilder::new(base);
+        let mut builder = PromptBuilder::new(self.render_base());
 
         if let Some(ctx) = &self.context {
             builder = builder.with_hook_context(ctx.clone());
@@ -313,6 +416,53 @@ mod tests {
         assert!(!built.contains("${WORKING_DIRECTORY}"));
     }
 
+    #[test]
+    fn test_prompt_config_variables() {
+        let config = PromptSystemConfig {
+            variables: HashMap::from([
+                ("team".to_string(), "Platform".to_string()),
+                ("ticket".to_string(), "from config".to_string()),
+            ]),
+            ..Default::default()
+        };
+        let vars = TemplateVars {
+            custom: HashMap::from([("ticket".to_string(), "ABC-1".to_string())]),
+            ..Default::default()
+        };
+        let built = SystemPrompt::with_base("${CUSTOM:team} ${CUSTOM:ticket}")
+            .with_prompt_config(&config, Path::new("."))
+            .with_template_vars(vars)
+            .build();
+        assert_eq!(built, "Platform ABC-1");
+    }
+
+    #[test]
+    fn test_with_memory() {
+        use crate::context::{MemoryFile, MemoryTier};
+
+        let content = "- Use ${TABS} for indentation\n".to_string();
+        let memory = MemoryHierarchy {
+            files: vec![MemoryFile {
+                path: PathBuf::from("/my/project/CLAUDE.local.md"),
+                size: content.len(),
+                content,
+                tier: MemoryTier::User,
+            }],
+            ..Default::default()
+        };
+        let built = SystemPrompt::with_base("Base")
+            .with_template_vars(TemplateVars::default())
+            .with_memory(&memory)
+            .build();
+        assert!(built.starts_with("Base\n\n# Memory"));
+        assert!(built.contains("Contents of /my/project/CLAUDE.local.md (user):"));
+        // Memory is not a template
+        assert!(built.ends_with("- Use ${TABS} for indentation"));
+
+        let built = SystemPrompt::with_base("Base").with_memory(&MemoryHierarchy::default()).build();
+        assert_eq!(built, "Base");
+    }
+
     #[test]
     fn test_build_assembled() {
         let prompt = SystemPrompt::new();
@@ -337,6 +487,74 @@ mod tests {
         assert_eq!(DEFAULT_SYSTEM_PROMPT, builtin::SYSTEM_PROMPT);
     }
 
+    #[test]
+    fn test_prompt_config_combinations() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("append.md"), "Project rule in ${WORKING_DIRECTORY}\n").unwrap();
+        std::fs::write(
+            dir.path().join("override.md"),
+            "Custom base\n\n<!-- section: web -->\n## Web\nWeb guidance\n<!-- /section -->\n\nTail\n",
+        )
+        .unwrap();
+        let vars = TemplateVars {
+            working_directory: "/my/project".to_string(),
+            skills_xml: "- commit: Create a git commit".to_string(),
+            ..Default::default()
+        };
+        let build = |append: bool, override_base: bool, disable: &[&str]| {
+            let config = PromptSystemConfig {
+                append_file: append.then(|| PathBuf::from("append.md")),
+                override_file: override_base.then(|| PathBuf::from("override.md")),
+                disable_sections: disable.iter().map(|s| s.to_string()).collect(),
+                ..Default::default()
+            };
+            SystemPrompt::new()
+                .with_prompt_config(&config, dir.path())
+                .with_template_vars(vars.clone())
+                .build()
+        };
+        let builtin = SystemPrompt::new().with_template_vars(vars.clone()).build();
+
+        // No options: the built-in prompt without section markers
+        let plain = build(false, false, &[]);
+        assert_eq!(plain, builtin);
+        assert!(plain.contains("## Notebooks") && plain.contains("## Web"));
+        assert!(!plain.contains("<!--"));
+
+        // Append: after the built-in prompt, with variables substituted
+        let appended = build(true, false, &[]);
+        assert!(appended.starts_with(builtin.trim_end()));
+        assert!(appended.ends_with("Project rule in /my/project"));
+
+        // Disabled sections are stripped, the rest is kept
+        let stripped = build(false, false, &["notebook", "web", "browser"]);
+        assert!(!stripped.contains("## Notebooks") && !stripped.contains("## Web"));
+        assert!(stripped.contains("## Tool Usage Policy") && stripped.contains("## Code References"));
+        assert!(stripped.len() < builtin.len());
+
+        // Both: stripped prompt followed by the append file
+        let both = build(true, false, &["web"]);
+        assert!(!both.contains("## Web") && both.contains("## Notebooks"));
+        assert!(both.ends_with("Project rule in /my/project"));
+
+        // Override replaces the base entirely
+        assert_eq!(build(false, true, &[]), "Custom base\n\n## Web\nWeb guidance\n\nTail\n");
+        assert_eq!(build(false, true, &["web"]), "Custom base\n\nTail\n");
+        assert_eq!(build(true, true, &["web"]), "Custom base\n\nTail\n\nProject rule in /my/project");
+
+        // Missing files are skipped
+        let config = PromptSystemConfig {
+            append_file: Some(PathBuf::from("missing.md")),
+            override_file: Some(PathBuf::from("missing.md")),
+            ..Default::default()
+        };
+        let missing = SystemPrompt::new()
+            .with_prompt_config(&config, dir.path())
+            .with_template_vars(vars.clone())
+            .build();
+        assert_eq!(missing, builtin);
+    }
+
     #[test]
     fn test_registry_access() {
         let prompt = SystemPrompt::new();
@@ -0,0 +1,163 @@
+//! Tool profiles: which of a session's tools the model is offered
+//!
+//! Every tool definition is sent with every LLM request, so a session that
+//! only needs a few tools saves tokens on each turn by leaving the rest out.
+//! Tools a profile leaves out can't be called either.
+//!
+//! A profile is a list of tool-name patterns to include (empty = every
+//! tool) and to exclude, matched like the policy's disabled tools: `*` is a
+//! wildcard, and MCP tools also match by their name on the server. The
+//! presets:
+//!
+//! - `minimal`: the filesystem read tools and Bash
+//! - `coding`: the filesystem tools, shell, LSP, Task and TodoWrite
+//! - `research`: the web tools and the filesystem read tools (Read also
+//!   reads PDF and Office documents)
+//! - `full`: every tool (the default)
+//!
+//! Custom profiles are defined in the config's `[tools.profiles]`.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::policy::tool_matches;
+use crate::tools::ToolDefinition;
+
+/// Name of the profile offering every tool
+pub const FULL_PROFILE: &str = "full";
+
+/// Names of the built-in profiles, smallest first
+pub const PRESETS: &[&str] = &["minimal", "coding", "research", FULL_PROFILE];
+
+const MINIMAL_TOOLS: &[&str] = &["Read", "Glob", "Grep", "Bash"];
+
+const CODING_TOOLS: &[&str] = &[
+    "Read",
+    "Write",
+    "FinishFile",
+    "Edit",
+    "BatchEdit",
+    "Glob",
+    "Grep",
+    "CodebaseSearch",
+    "SearchFiles",
+    "Bash",
+    "KillShell",
+    "LSP",
+    "Task",
+    "TaskOutput",
+    "TodoWrite",
+];
+
+const RESEARCH_TOOLS: &[&str] = &["Read", "Glob", "Grep", "SearchFiles", "WebFetch", "WebSearch"];
+
+/// A named selection of tools
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolProfile {
+    pub name: String,
+    /// Patterns of the tools offered (empty = every tool)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    /// Patterns of tools left out, even if included
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+}
+
+impl ToolProfile {
+    pub fn new(name: impl Into<String>, include: Vec<String>, exclude: Vec<String>) -> Self {
+        Self {
+            name: name.into(),
+            include,
+            exclude,
+        }
+    }
+
+    /// The profile offering every tool
+    pub fn full() -> Self {
+        Self::new(FULL_PROFILE, Vec::new(), Vec::new())
+    }
+
+    /// The built-in profile called `name`, if there is one
+    pub fn preset(name: &str) -> Option<Self> {
+        let tools = match name {
+            "minimal" => MINIMAL_TOOLS,
+            "coding" => CODING_TOOLS,
+            "research" => RESEARCH_TOOLS,
+            FULL_PROFILE => return Some(Self::full()),
+            _ => return None,
+        };
+        Some(Self::new(name, tools.iter().map(|t| t.to_string()).collect(), Vec::new()))
+    }
+
+    /// Whether the profile offers every tool
+    pub fn is_full(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// Whether the tool called `tool` is offered
+    pub fn allows(&self, tool: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|pattern| tool_matches(pattern, tool)))
+            && !self.exclude.iter().any(|pattern| tool_matches(pattern, tool))
+    }
+}
+
+impl Default for ToolProfile {
+    fn default() -> Self {
+        Self::full()
+    }
+}
+
+impl fmt::Display for ToolProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+/// Rough token count of the tool definitions sent with each request
+/// (~4 characters per token)
+pub fn definition_tokens(definitions: &[ToolDefinition]) -> u64 {
+    let chars: usize = definitions
+        .iter()
+        .map(|def| {
+            def.name.len()
+                + def.description.as_ref().map_or(0, |d| d.len())
+                + def.schema.as_ref().map_or(0, |s| s.to_string().len())
+        })
+        .sum();
+    (chars / 4) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_patterns() {
+        let profile = ToolProfile::new(
+            "docs",
+            vec!["Read".into(), "Write*".into(), "mcp__github__*".into()],
+            vec!["WritePptx".into()],
+        );
+        assert!(profile.allows("Read"));
+        assert!(profile.allows("WriteDocx"));
+        assert!(profile.allows("mcp__github__create_issue"));
+        assert!(!profile.allows("WritePptx"));
+        assert!(!profile.allows("Bash"));
+
+        let no_shell = ToolProfile::new("no-shell", Vec::new(), vec!["Bash".into(), "KillShell".into()]);
+        assert!(no_shell.allows("Read"));
+        assert!(!no_shell.allows("Bash"));
+        assert!(!no_shell.is_full());
+    }
+
+    #[test]
+    fn test_presets() {
+        for name in PRESETS {
+            assert_eq!(ToolProfile::preset(name).unwrap().name, *name);
+        }
+        assert!(ToolProfile::preset("bogus").is_none());
+        assert!(ToolProfile::preset(FULL_PROFILE).unwrap().allows("mcp__any__tool"));
+        assert!(!ToolProfile::preset("minimal").unwrap().allows("mcp__any__tool"));
+    }
+}
@@ -6,24 +6,34 @@
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use tokio::sync::mpsc;
 
 use crate::config::{ModelTiers, WebSearchConfig};
+use crate::error::ToolError;
 use crate::mcp_manager::McpServerManager;
-use crate::session::{SessionOutput, SessionRegistry};
-use crate::tools::filesystem::{EditFile, ExportDocument, GlobFiles, GrepFiles, ReadFile, WriteFile};
-use crate::tools::interaction::AskUserQuestion;
+use crate::policy::Policy;
+use crate::prompt::{ComponentRegistry, ToolRestrictions};
+use crate::provider::UsageStore;
+use crate::session::{AuditLog, OutputSender, RequestLimiter, SessionRegistry};
+use crate::tools::filesystem::{
+    BatchEdit, CodebaseSearch, EditFile, ExportDocument, FinishFile, GeneratedFileGuard, GlobFiles, GrepFiles, ReadFile, SearchFiles,
+    SyntaxVerifier, WriteDocx, WriteFile, WritePptx, WriteXlsx,
+};
+use crate::tools::interaction::{AskUserQuestion, Clipboard, ClipboardWrite};
 use crate::tools::lsp::LspTool;
 use crate::tools::mcp::create_mcp_tools;
 use crate::tools::notebook::NotebookEdit;
-use crate::tools::planning::{EnterPlanMode, ExitPlanMode, PlanModeState};
-use crate::tools::shell::{ExecuteCommand, KillShell, ShellProcessRegistry};
+use crate::tools::remote::RemoteWorkspace;
+use crate::tools::planning::{EnterPlanMode, ExitPlanMode, PlanModeState, PlanStatusTool};
+use crate::tools::shell::{EnvPolicy, ExecuteCommand, KillShell, ShellProcessRegistry};
 use crate::tools::skill::SkillTool;
-use crate::tools::task::{AgentInstanceRegistry, TaskOutputTool, TaskTool, TodoWrite};
-use crate::tools::web::{supports_native_search, WebFetch, WebSearch};
-use crate::tools::ToolRegistry;
+use crate::tools::task::{AgentInstanceRegistry, TaskOutputTool, TaskTool, TodoList, TodoWrite};
+use crate::tools::web::{supports_native_search, NetworkPolicy, WebFetch, WebSearch};
+use crate::tools::descriptions::describe_tools;
+use crate::tools::{BoxFuture, Tool, ToolExecutionContext, ToolOutput, ToolRegistry};
 use crate::skills::SkillRegistry;
 
+use super::tool_profile::ToolProfile;
+
 /// Defines which subset of tools a subagent should have access to
 #[derive(Debug, Clone)]
 pub enum ToolScope {
@@ -35,6 +45,8 @@ pub enum ToolScope {
     Plan,
     /// Everything except TaskTool and AskUserQuestion
     GeneralPurpose,
+    /// GeneralPurpose narrowed to a custom agent's `tools` list
+    Custom(ToolRestrictions),
 }
 
 /// Builder for creating a tool registry with customizable options
@@ -51,14 +63,46 @@ pub struct ToolRegistryBuilder {
     tool_scope: Option<ToolScope>,
     skill_registry: Option<Arc<SkillRegistry>>,
     plan_mode_state: Option<Arc<tokio::sync::RwLock<PlanModeState>>>,
-    /// Parent output channel for subagent progress forwarding
-    progress_tx: Option<mpsc::Sender<(String, SessionOutput)>>,
-    /// Parent session ID for progress forwarding
-    progress_session_id: Option<String>,
+    /// Todo list shared with the agent loop (TodoWrite and PlanStatus)
+    todo_list: Option<TodoList>,
+    /// Parent session's outputs for subagent progress forwarding
+    progress_tx: Option<OutputSender>,
     /// Shared session registry for subagent approval routing
     session_registry: Option<SessionRegistry>,
     /// MCP server manager for external tool integration
     mcp_manager: Option<Arc<McpServerManager>>,
+    /// Whether MCP tool arguments are validated against their schemas
+    mcp_validation: bool,
+    /// Replace tools that have side effects with dry-run stand-ins
+    dry_run: bool,
+    /// Component registry for custom agent definitions (used by TaskTool)
+    component_registry: Option<Arc<ComponentRegistry>>,
+    /// Shared request limiter passed on to subagents (used by TaskTool)
+    request_limiter: Option<Arc<RequestLimiter>>,
+    /// Usage store subagents record their LLM calls to (used by TaskTool)
+    usage_store: Option<Arc<UsageStore>>,
+    /// Audit log subagents append to (used by TaskTool)
+    audit_log: Option<Arc<AuditLog>>,
+    /// Environment policy for the Bash tool, passed on to subagents
+    env_policy: Option<EnvPolicy>,
+    /// Remote workspace the file and shell tools operate on, passed on to subagents
+    remote: Option<Arc<RemoteWorkspace>>,
+    /// Organization policy: disabled tools and blocked hosts, passed on to subagents
+    policy: Option<Arc<Policy>>,
+    /// Hosts WebFetch and WebSearch may reach, passed on to subagents
+    network_policy: Option<NetworkPolicy>,
+    /// Session scratch directory the file tools may use outside the workspace
+    scratch_dir: Option<PathBuf>,
+    /// Roots the file tools may use besides the workspace, passed on to subagents
+    additional_roots: Vec<PathBuf>,
+    /// Syntax check after Edit and Write, passed on to subagents
+    verifier: Option<Arc<SyntaxVerifier>>,
+    /// System clipboard for ClipboardWrite (not passed on to subagents)
+    clipboard: Option<Arc<dyn Clipboard>>,
+    /// Tools offered (None = every tool; not passed on to subagents)
+    tool_profile: Option<ToolProfile>,
+    /// Offer built-in tools' compact descriptions (not passed on to subagents)
+    compact_descriptions: bool,
 }
 
 impl ToolRegistryBuilder {
@@ -74,10 +118,26 @@ impl ToolRegistryBuilder {
             tool_scope: None,
             skill_registry: None,
             plan_mode_state: None,
+            todo_list: None,
             progress_tx: None,
-            progress_session_id: None,
             session_registry: None,
             mcp_manager: None,
+            mcp_validation: true,
+            dry_run: false,
+            component_registry: None,
+            request_limiter: None,
+            usage_store: None,
+            audit_log: None,
+            env_policy: None,
+            remote: None,
+            policy: None,
+            network_policy: None,
+            scratch_dir: None,
+            additional_roots: Vec::new(),
+            verifier: None,
+            clipboard: None,
+            tool_profile: None,
+            compact_descriptions: false,
         }
     }
 
@@ -87,6 +147,97 @@ impl ToolRegistryBuilder {
         self
     }
 
+    /// Set the shared request limiter for subagent LLM calls
+    pub fn with_request_limiter(mut self, limiter: Arc<RequestLimiter>) -> Self {
+        self.request_limiter = Some(limiter);
+        self
+    }
+
+    /// Set the usage store for subagent LLM calls
+    pub fn with_usage_store(mut self, store: Arc<UsageStore>) -> Self {
+        self.usage_store = Some(store);
+        self
+    }
+
+    /// Set the audit log subagents append to
+    pub fn with_audit_log(mut self, log: Arc<AuditLog>) -> Self {
+        self.audit_log = Some(log);
+        self
+    }
+
+    /// Set the environment policy for shell commands
+    pub fn with_env_policy(mut self, policy: EnvPolicy) -> Self {
+        self.env_policy = Some(policy);
+        self
+    }
+
+    /// Apply an organization policy: its disabled tools are left out and
+    /// WebFetch refuses its blocked hosts
+    pub fn with_policy(mut self, policy: Arc<Policy>) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    /// Set the hosts WebFetch and WebSearch may reach (default: any public
+    /// host); the organization policy's blocked hosts are added to it
+    pub fn with_network_policy(mut self, network: NetworkPolicy) -> Self {
+        self.network_policy = Some(network);
+        self
+    }
+
+    /// Operate on a remote workspace instead of the local one
+    ///
+    /// The file and shell tools use the remote backend; tools that only work
+    /// on local files (ExportDocument, the Office writers, NotebookEdit, LSP)
+    /// are left out.
+    pub fn with_remote(mut self, remote: Arc<RemoteWorkspace>) -> Self {
+        self.remote = Some(remote);
+        self
+    }
+
+    /// Let Read, Write and Edit use the session's scratch directory as well as
+    /// the workspace
+    ///
+    /// Glob and Grep search the workspace unless given a path, so the scratch
+    /// directory stays out of their results unless asked for.
+    pub fn with_scratch_dir(mut self, dir: PathBuf) -> Self {
+        self.scratch_dir = Some(dir);
+        self
+    }
+
+    /// Let the file tools use these directories as well as the workspace
+    ///
+    /// Read, Write and Edit accept paths in any root (relative paths still
+    /// resolve against the workspace), and Glob and Grep take a `root`
+    /// parameter (see `WorkspaceRoots`). Ignored for a remote workspace.
+    pub fn with_additional_roots(mut self, roots: Vec<PathBuf>) -> Self {
+        self.additional_roots = roots;
+        self
+    }
+
+    /// Check the syntax of files Edit and Write change (local workspaces only)
+    pub fn with_verifier(mut self, verifier: Arc<SyntaxVerifier>) -> Self {
+        self.verifier = Some(verifier);
+        self
+    }
+
+    /// Register ClipboardWrite, writing to `clipboard`
+    ///
+    /// Only the session's own registry gets it; subagents can't reach the
+    /// user's clipboard.
+    pub fn with_clipboard(mut self, clipboard: Arc<dyn Clipboard>) -> Self {
+        self.clipboard = Some(clipboard);
+        self
+    }
+
+    /// Register only the tools `profile` offers
+    ///
+    /// Subagents get the tools of their scope whatever the profile.
+    pub fn with_tool_profile(mut self, profile: ToolProfile) -> Self {
+        self.tool_profile = Some(profile);
+        self
+    }
+
     /// Set a shared PlanModeState — used by the agent loop to share state
     /// between the planning tools and the tool filtering logic
     pub fn with_plan_mode_state(mut self, state: Arc<tokio::sync::RwLock<PlanModeState>>) -> Self {
@@ -94,6 +245,13 @@ impl ToolRegistryBuilder {
         self
     }
 
+    /// Set a shared todo list — used by the agent loop to report and persist
+    /// the checklist TodoWrite maintains
+    pub fn with_todo_list(mut self, todos: TodoList) -> Self {
+        self.todo_list = Some(todos);
+        self
+    }
+
     /// Set a tool scope — when set, `build()` will use scoped tool registration
     pub fn with_tool_scope(mut self, scope: ToolScope) -> Self {
         self.tool_scope = Some(scope);
@@ -101,13 +259,8 @@ impl ToolRegistryBuilder {
     }
 
     /// Set the progress channel for subagent activity forwarding
-    pub fn with_progress_channel(
-        mut self,
-        tx: mpsc::Sender<(String, SessionOutput)>,
-        session_id: String,
-    ) -> Self {
+    pub fn with_progress_channel(mut self, tx: OutputSender) -> Self {
         self.progress_tx = Some(tx);
-        self.progress_session_id = Some(session_id);
         self
     }
 
@@ -153,8 +306,59 @@ impl ToolRegistryBuilder {
         self
     }
 
+    /// Set whether MCP tool arguments are validated (default: true)
+    pub fn with_mcp_validation(mut self, enabled: bool) -> Self {
+        self.mcp_validation = enabled;
+        self
+    }
+
+    /// Offer the built-in tools' compact descriptions instead of the full ones
+    ///
+    /// Overrides in `.cowork/tool-descriptions` apply either way (see
+    /// [`crate::tools::descriptions`]).
+    pub fn with_compact_descriptions(mut self, enabled: bool) -> Self {
+        self.compact_descriptions = enabled;
+        self
+    }
+
+    /// Answer calls to tools with side effects without running them
+    pub fn with_dry_run(mut self, enabled: bool) -> Self {
+        self.dry_run = enabled;
+        self
+    }
+
+    /// Set the component registry so TaskTool can launch custom agents
+    pub fn with_component_registry(mut self, registry: Arc<ComponentRegistry>) -> Self {
+        self.component_registry = Some(registry);
+        self
+    }
+
     /// Build the tool registry with the configured options
     pub fn build(self) -> ToolRegistry {
+        let dry_run = self.dry_run;
+        let policy = self.policy.clone();
+        let profile = self.tool_profile.clone();
+        let workspace = self.workspace.clone();
+        let compact = self.compact_descriptions;
+        let mut registry = self.build_registry();
+        describe_tools(&mut registry, &workspace, compact);
+        if let Some(profile) = profile {
+            registry.retain(|name| profile.allows(name));
+        }
+        if let Some(policy) = policy {
+            registry.retain(|name| !policy.is_tool_disabled(name));
+            registry.map_tools(|tool| {
+                if policy.requires_approval(tool.name()) {
+                    Arc::new(PolicyApprovedTool { inner: tool })
+                } else {
+                    tool
+                }
+            });
+        }
+        if dry_run { registry.into_dry_run() } else { registry }
+    }
+
+    fn build_registry(self) -> ToolRegistry {
         if let Some(scope) = self.tool_scope.clone() {
             return self.build_scoped(scope);
         }
@@ -162,23 +366,69 @@ impl ToolRegistryBuilder {
         let mut registry = ToolRegistry::new();
 
         // Filesystem tools
-        registry.register(Arc::new(ReadFile::new(self.workspace.clone())));
-        registry.register(Arc::new(WriteFile::new(self.workspace.clone())));
-        registry.register(Arc::new(EditFile::new(self.workspace.clone())));
-        registry.register(Arc::new(GlobFiles::new(self.workspace.clone())));
-        registry.register(Arc::new(GrepFiles::new(self.workspace.clone())));
-        registry.register(Arc::new(ExportDocument::new(self.workspace.clone())));
+        let remote = self.remote.clone();
+        let scratch = self.scratch_dir.clone();
+        let roots = &self.additional_roots;
+        let generated = Arc::new(GeneratedFileGuard::load(&self.workspace));
+        registry.register(Arc::new(
+            ReadFile::new(self.workspace.clone())
+                .with_remote(remote.clone())
+                .with_additional_roots(roots)
+                .with_scratch_dir(scratch.clone()),
+        ));
+        registry.register(Arc::new(
+            WriteFile::new(self.workspace.clone())
+                .with_remote(remote.clone())
+                .with_additional_roots(roots)
+                .with_scratch_dir(scratch.clone())
+                .with_verifier(self.verifier.clone())
+                .with_generated_guard(generated.clone()),
+        ));
+        registry.register(Arc::new(
+            FinishFile::new(self.workspace.clone())
+                .with_remote(remote.clone())
+                .with_additional_roots(roots)
+                .with_scratch_dir(scratch.clone())
+                .with_verifier(self.verifier.clone()),
+        ));
+        registry.register(Arc::new(
+            EditFile::new(self.workspace.clone())
+                .with_remote(remote.clone())
+                .with_additional_roots(roots)
+                .with_scratch_dir(scratch)
+                .with_verifier(self.verifier.clone())
+                .with_generated_guard(generated.clone()),
+        ));
+        registry.register(Arc::new(
+            GlobFiles::new(self.workspace.clone()).with_remote(remote.clone()).with_additional_roots(roots),
+        ));
+        registry.register(Arc::new(
+            GrepFiles::new(self.workspace.clone()).with_remote(remote.clone()).with_additional_roots(roots),
+        ));
+        if remote.is_none() {
+            registry.register(Arc::new(BatchEdit::new(self.workspace.clone()).with_generated_guard(generated)));
+            registry.register(Arc::new(CodebaseSearch::new(self.workspace.clone())));
+            registry.register(Arc::new(SearchFiles::new(self.workspace.clone()).with_additional_roots(roots)));
+            registry.register(Arc::new(ExportDocument::new(self.workspace.clone())));
+            registry.register(Arc::new(WriteDocx::new(self.workspace.clone())));
+            registry.register(Arc::new(WriteXlsx::new(self.workspace.clone())));
+            registry.register(Arc::new(WritePptx::new(self.workspace.clone())));
+        }
 
         // Shell tools with shared process registry
         let shell_registry = Arc::new(ShellProcessRegistry::new());
-        registry.register(Arc::new(
-            ExecuteCommand::new(self.workspace.clone())
-                .with_registry(shell_registry.clone())
-        ));
+        let mut execute = ExecuteCommand::new(self.workspace.clone())
+            .with_registry(shell_registry.clone())
+            .with_remote(remote.clone());
+        if let Some(policy) = self.env_policy.clone() {
+            execute = execute.with_env_policy(policy);
+        }
+        registry.register(Arc::new(execute));
         registry.register(Arc::new(KillShell::new(shell_registry)));
 
         // Web tools
-        registry.register(Arc::new(WebFetch::new()));
+        let network = self.web_network();
+        registry.register(Arc::new(WebFetch::new().with_network_policy(network.clone())));
 
         // Check if provider has built-in web search
         let provider_has_native = self
@@ -212,7 +462,7 @@ impl ToolRegistryBuilder {
                 } else {
                     WebSearch::new()
                 };
-                registry.register(Arc::new(web_search));
+                registry.register(Arc::new(web_search.with_network_policy(network)));
             } else {
                 tracing::debug!("WebSearch not registered: SerpAPI not configured");
             }
@@ -221,23 +471,33 @@ impl ToolRegistryBuilder {
         }
 
         // Notebook tools
-        registry.register(Arc::new(NotebookEdit::new(self.workspace.clone())));
+        if remote.is_none() {
+            registry.register(Arc::new(NotebookEdit::new(self.workspace.clone())));
+        }
 
         // Task management tools (TodoWrite is always available)
-        registry.register(Arc::new(TodoWrite::new()));
+        let todo_write = self.todo_write();
+        let todos = todo_write.get_list();
+        registry.register(Arc::new(todo_write));
 
         // Code intelligence tools
-        registry.register(Arc::new(LspTool::new(self.workspace.clone())));
+        if remote.is_none() {
+            registry.register(Arc::new(LspTool::new(self.workspace.clone())));
+        }
 
         // Interaction tools
         registry.register(Arc::new(AskUserQuestion::new()));
+        if let Some(clipboard) = self.clipboard.clone() {
+            registry.register(Arc::new(ClipboardWrite::new(clipboard)));
+        }
 
         // Planning tools with shared state
         let plan_mode_state = self.plan_mode_state.clone().unwrap_or_else(||
             Arc::new(tokio::sync::RwLock::new(PlanModeState::default()))
         );
         registry.register(Arc::new(EnterPlanMode::new(plan_mode_state.clone())));
-        registry.register(Arc::new(ExitPlanMode::new(plan_mode_state)));
+        registry.register(Arc::new(ExitPlanMode::new(plan_mode_state.clone())));
+        registry.register(Arc::new(PlanStatusTool::new(plan_mode_state, todos)));
 
         // Agent/Task tools - require provider_id for full functionality
         if self.include_task
@@ -253,15 +513,49 @@ impl ToolRegistryBuilder {
                 if let Some(tiers) = self.model_tiers {
                     task_tool = task_tool.with_model_tiers(tiers);
                 }
-                if let (Some(tx), Some(sid)) = (self.progress_tx, self.progress_session_id) {
-                    task_tool = task_tool.with_progress_channel(tx, sid);
+                if let Some(tx) = self.progress_tx {
+                    task_tool = task_tool.with_progress_channel(tx);
                 }
                 if let Some(reg) = self.session_registry {
                     task_tool = task_tool.with_session_registry(reg);
                 }
+                if let Some(components) = self.component_registry {
+                    task_tool = task_tool.with_component_registry(components);
+                }
+                if let Some(limiter) = self.request_limiter {
+                    task_tool = task_tool.with_request_limiter(limiter);
+                }
+                if let Some(policy) = self.env_policy.clone() {
+                    task_tool = task_tool.with_env_policy(policy);
+                }
+                if let Some(policy) = self.policy.clone() {
+                    task_tool = task_tool.with_policy(policy);
+                }
+                if let Some(network) = self.network_policy.clone() {
+                    task_tool = task_tool.with_network_policy(network);
+                }
+                if let Some(verifier) = self.verifier.clone() {
+                    task_tool = task_tool.with_verifier(verifier);
+                }
+                if !self.additional_roots.is_empty() {
+                    task_tool = task_tool.with_additional_roots(self.additional_roots.clone());
+                }
+                if let Some(store) = self.usage_store {
+                    task_tool = task_tool.with_usage_store(store);
+                }
+                if let Some(log) = self.audit_log {
+                    task_tool = task_tool.with_audit_log(log);
+                }
+                if let Some(remote) = remote {
+                    task_tool = task_tool.with_remote(remote);
+                }
 
                 registry.register(Arc::new(task_tool));
-                registry.register(Arc::new(TaskOutputTool::new(agent_registry)));
+                let mut task_output = TaskOutputTool::new(agent_registry);
+                if let Some(policy) = self.env_policy.clone() {
+                    task_output = task_output.with_env_policy(policy);
+                }
+                registry.register(Arc::new(task_output));
             }
 
         // Skill tool - when a skill registry is provided
@@ -280,6 +574,7 @@ impl ToolRegistryBuilder {
                 "Registered MCP tools from server manager"
             );
         }
+        registry.set_mcp_validation(self.mcp_validation);
 
         registry
     }
@@ -290,75 +585,262 @@ impl ToolRegistryBuilder {
     /// replacing `create_agent_tool_registry()` in executor.rs.
     fn build_scoped(self, scope: ToolScope) -> ToolRegistry {
         let mut registry = ToolRegistry::new();
-        let workspace = self.workspace;
+        let workspace = self.workspace.clone();
+        let env_policy = self.env_policy.as_ref();
+        let network = self.web_network();
+        let remote = self.remote.as_ref();
+        let roots = &self.additional_roots;
 
         match scope {
             ToolScope::Bash => {
-                let shell_registry = Arc::new(ShellProcessRegistry::new());
-                registry.register(Arc::new(
-                    ExecuteCommand::new(workspace).with_registry(shell_registry),
-                ));
+                registry.register(Arc::new(execute_command(workspace, env_policy, remote)));
             }
             ToolScope::Explore => {
                 // CC's Explore has all tools except Task, ExitPlanMode, Edit, Write, NotebookEdit
-                registry.register(Arc::new(ReadFile::new(workspace.clone())));
-                registry.register(Arc::new(GlobFiles::new(workspace.clone())));
-                registry.register(Arc::new(GrepFiles::new(workspace.clone())));
-                let shell_registry = Arc::new(ShellProcessRegistry::new());
                 registry.register(Arc::new(
-                    ExecuteCommand::new(workspace.clone()).with_registry(shell_registry),
+                    ReadFile::new(workspace.clone()).with_remote(remote.cloned()).with_additional_roots(roots),
+                ));
+                registry.register(Arc::new(
+                    GlobFiles::new(workspace.clone()).with_remote(remote.cloned()).with_additional_roots(roots),
+                ));
+                registry.register(Arc::new(
+                    GrepFiles::new(workspace.clone()).with_remote(remote.cloned()).with_additional_roots(roots),
                 ));
-                registry.register(Arc::new(WebFetch::new()));
+                registry.register(Arc::new(execute_command(workspace.clone(), env_policy, remote)));
+                registry.register(Arc::new(WebFetch::new().with_network_policy(network.clone())));
                 // Include WebSearch if SerpAPI is configured
                 if let Some(config) = self.web_search_config.as_ref()
                     && config.is_configured() {
-                        registry.register(Arc::new(WebSearch::with_config(config.clone())));
+                        registry.register(Arc::new(WebSearch::with_config(config.clone()).with_network_policy(network)));
                     }
-                registry.register(Arc::new(LspTool::new(workspace)));
-                registry.register(Arc::new(TodoWrite::new()));
+                if remote.is_none() {
+                    registry.register(Arc::new(CodebaseSearch::new(workspace.clone())));
+                    registry.register(Arc::new(SearchFiles::new(workspace.clone()).with_additional_roots(roots)));
+                    registry.register(Arc::new(LspTool::new(workspace)));
+                }
+                registry.register(Arc::new(self.todo_write()));
             }
             ToolScope::Plan => {
                 // CC's Plan has all tools except Task, ExitPlanMode, Edit, Write, NotebookEdit
-                registry.register(Arc::new(ReadFile::new(workspace.clone())));
-                registry.register(Arc::new(GlobFiles::new(workspace.clone())));
-                registry.register(Arc::new(GrepFiles::new(workspace.clone())));
-                let shell_registry = Arc::new(ShellProcessRegistry::new());
                 registry.register(Arc::new(
-                    ExecuteCommand::new(workspace.clone()).with_registry(shell_registry),
+                    ReadFile::new(workspace.clone()).with_remote(remote.cloned()).with_additional_roots(roots),
+                ));
+                registry.register(Arc::new(
+                    GlobFiles::new(workspace.clone()).with_remote(remote.cloned()).with_additional_roots(roots),
                 ));
-                registry.register(Arc::new(WebFetch::new()));
+                registry.register(Arc::new(
+                    GrepFiles::new(workspace.clone()).with_remote(remote.cloned()).with_additional_roots(roots),
+                ));
+                registry.register(Arc::new(execute_command(workspace.clone(), env_policy, remote)));
+                registry.register(Arc::new(WebFetch::new().with_network_policy(network.clone())));
                 // Include WebSearch if SerpAPI is configured
                 if let Some(config) = self.web_search_config.as_ref()
                     && config.is_configured() {
-                        registry.register(Arc::new(WebSearch::with_config(config.clone())));
+                        registry.register(Arc::new(WebSearch::with_config(config.clone()).with_network_policy(network)));
                     }
-                registry.register(Arc::new(LspTool::new(workspace)));
-                registry.register(Arc::new(TodoWrite::new()));
+                if remote.is_none() {
+                    registry.register(Arc::new(CodebaseSearch::new(workspace.clone())));
+                    registry.register(Arc::new(SearchFiles::new(workspace.clone()).with_additional_roots(roots)));
+                    registry.register(Arc::new(LspTool::new(workspace)));
+                }
+                registry.register(Arc::new(self.todo_write()));
             }
             ToolScope::GeneralPurpose => {
-                registry.register(Arc::new(ReadFile::new(workspace.clone())));
-                registry.register(Arc::new(WriteFile::new(workspace.clone())));
-                registry.register(Arc::new(EditFile::new(workspace.clone())));
-                registry.register(Arc::new(GlobFiles::new(workspace.clone())));
-                registry.register(Arc::new(GrepFiles::new(workspace.clone())));
-                registry.register(Arc::new(ExportDocument::new(workspace.clone())));
-                let shell_registry = Arc::new(ShellProcessRegistry::new());
-                registry.register(Arc::new(
-                    ExecuteCommand::new(workspace.clone()).with_registry(shell_registry),
-                ));
-                registry.register(Arc::new(WebFetch::new()));
-                // Include WebSearch if SerpAPI is configured
-                if let Some(config) = self.web_search_config.as_ref()
-                    && config.is_configured() {
-                        registry.register(Arc::new(WebSearch::with_config(config.clone())));
+                self.register_general_purpose(&mut registry);
+            }
+            ToolScope::Custom(restrictions) => {
+                let mut general = ToolRegistry::new();
+                self.register_general_purpose(&mut general);
+                for tool in general.all() {
+                    if restrictions.allows_tool(tool.name()) {
+                        registry.register(Arc::new(RestrictedTool {
+                            inner: tool,
+                            restrictions: restrictions.clone(),
+                        }));
                     }
-                registry.register(Arc::new(LspTool::new(workspace)));
-                registry.register(Arc::new(TodoWrite::new()));
+                }
             }
         }
 
         registry
     }
+
+    /// Register the tools available to general-purpose subagents
+    fn register_general_purpose(&self, registry: &mut ToolRegistry) {
+        let workspace = self.workspace.clone();
+        let env_policy = self.env_policy.as_ref();
+        let network = self.web_network();
+        let remote = self.remote.as_ref();
+        let verifier = self.verifier.clone();
+        let roots = &self.additional_roots;
+        let generated = Arc::new(GeneratedFileGuard::load(&workspace));
+        registry.register(Arc::new(
+            ReadFile::new(workspace.clone()).with_remote(remote.cloned()).with_additional_roots(roots),
+        ));
+        registry.register(Arc::new(
+            WriteFile::new(workspace.clone())
+                .with_remote(remote.cloned())
+                .with_additional_roots(roots)
+                .with_verifier(verifier.clone())
+                .with_generated_guard(generated.clone()),
+        ));
+        registry.register(Arc::new(
+            FinishFile::new(workspace.clone())
+                .with_remote(remote.cloned())
+                .with_additional_roots(roots)
+                .with_verifier(verifier.clone()),
+        ));
+        registry.register(Arc::new(
+            EditFile::new(workspace.clone())
+                .with_remote(remote.cloned())
+                .with_additional_roots(roots)
+                .with_verifier(verifier)
+                .with_generated_guard(generated.clone()),
+        ));
+        registry.register(Arc::new(
+            GlobFiles::new(workspace.clone()).with_remote(remote.cloned()).with_additional_roots(roots),
+        ));
+        registry.register(Arc::new(
+            GrepFiles::new(workspace.clone()).with_remote(remote.cloned()).with_additional_roots(roots),
+        ));
+        if remote.is_none() {
+            registry.register(Arc::new(BatchEdit::new(workspace.clone()).with_generated_guard(generated)));
+            registry.register(Arc::new(CodebaseSearch::new(workspace.clone())));
+            registry.register(Arc::new(SearchFiles::new(workspace.clone()).with_additional_roots(roots)));
+            registry.register(Arc::new(ExportDocument::new(workspace.clone())));
+            registry.register(Arc::new(WriteDocx::new(workspace.clone())));
+            registry.register(Arc::new(WriteXlsx::new(workspace.clone())));
+            registry.register(Arc::new(WritePptx::new(workspace.clone())));
+        }
+        registry.register(Arc::new(execute_command(workspace.clone(), env_policy, remote)));
+        registry.register(Arc::new(WebFetch::new().with_network_policy(network.clone())));
+        // Include WebSearch if SerpAPI is configured
+        if let Some(config) = self.web_search_config.as_ref()
+            && config.is_configured() {
+                registry.register(Arc::new(WebSearch::with_config(config.clone()).with_network_policy(network)));
+            }
+        if remote.is_none() {
+            registry.register(Arc::new(LspTool::new(workspace)));
+        }
+        registry.register(Arc::new(self.todo_write()));
+    }
+
+    /// The web tools' network policy, with the organization's blocked hosts
+    fn web_network(&self) -> NetworkPolicy {
+        let mut network = self.network_policy.clone().unwrap_or_else(NetworkPolicy::allow_all);
+        if let Some(policy) = &self.policy {
+            network.block_hosts(policy.blocked_hosts());
+        }
+        network
+    }
+
+    /// TodoWrite on the shared list if one was set, else on its own
+    fn todo_write(&self) -> TodoWrite {
+        match &self.todo_list {
+            Some(todos) => TodoWrite::with_shared_list(todos.clone()),
+            None => TodoWrite::new(),
+        }
+    }
+}
+
+/// Bash tool with its own process registry and the session's env policy
+fn execute_command(
+    workspace: PathBuf,
+    env_policy: Option<&EnvPolicy>,
+    remote: Option<&Arc<RemoteWorkspace>>,
+) -> ExecuteCommand {
+    let shell_registry = Arc::new(ShellProcessRegistry::new());
+    let execute = ExecuteCommand::new(workspace)
+        .with_registry(shell_registry)
+        .with_remote(remote.cloned());
+    match env_policy {
+        Some(policy) => execute.with_env_policy(policy.clone()),
+        None => execute,
+    }
+}
+
+/// Tool wrapper enforcing argument patterns like `Bash(git:*)` from an agent's `tools` list
+struct RestrictedTool {
+    inner: Arc<dyn Tool>,
+    restrictions: ToolRestrictions,
+}
+
+impl Tool for RestrictedTool {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn description(&self) -> &str {
+        self.inner.description()
+    }
+
+    fn compact_description(&self) -> &str {
+        self.inner.compact_description()
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        self.inner.parameters_schema()
+    }
+
+    fn execute(&self, params: serde_json::Value, ctx: ToolExecutionContext) -> BoxFuture<'_, Result<ToolOutput, ToolError>> {
+        if !self.restrictions.is_allowed(self.inner.name(), &params) {
+            let allowed: Vec<String> = self.restrictions.allowed.iter().map(|s| s.to_string()).collect();
+            let message = format!(
+                "This call to {} is not allowed for this agent. Allowed tools: {}",
+                self.inner.name(),
+                allowed.join(", ")
+            );
+            return Box::pin(async move { Err(ToolError::PermissionDenied(message)) });
+        }
+        self.inner.execute(params, ctx)
+    }
+
+    fn supports_progress(&self) -> bool {
+        self.inner.supports_progress()
+    }
+}
+
+/// Wrapper that asks for approval before every call, as the policy requires
+///
+/// The call then runs pre-approved, so tools that ask on their own (Bash)
+/// don't ask twice.
+struct PolicyApprovedTool {
+    inner: Arc<dyn Tool>,
+}
+
+impl Tool for PolicyApprovedTool {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn description(&self) -> &str {
+        self.inner.description()
+    }
+
+    fn compact_description(&self) -> &str {
+        self.inner.compact_description()
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        self.inner.parameters_schema()
+    }
+
+    fn execute(&self, params: serde_json::Value, ctx: ToolExecutionContext) -> BoxFuture<'_, Result<ToolOutput, ToolError>> {
+        Box::pin(async move {
+            ctx.request_approval(params.clone(), Some("Approval required by organization policy".to_string()))
+                .await
+                .map_err(ToolError::Rejected)?;
+            self.inner.execute(params, ctx.approved()).await
+        })
+    }
+
+    fn is_idempotent(&self) -> bool {
+        self.inner.is_idempotent()
+    }
+
+    fn supports_progress(&self) -> bool {
+        self.inner.supports_progress()
+    }
 }
 
 /// Convenience function to create a standard tool registry with all tools enabled
@@ -412,6 +894,9 @@ mod tests {
         assert!(registry.get("Glob").is_some());
         assert!(registry.get("Grep").is_some());
         assert!(registry.get("ExportDocument").is_some());
+        assert!(registry.get("WriteDocx").is_some());
+        assert!(registry.get("WriteXlsx").is_some());
+        assert!(registry.get("WritePptx").is_some());
 
         // Should have shell tools
         assert!(registry.get("Bash").is_some());
@@ -498,6 +983,54 @@ mod tests {
         assert!(registry.get("TaskOutput").is_some());
     }
 
+    #[tokio::test]
+    async fn test_custom_scope_enforces_patterns() {
+        use crate::prompt::ToolSpec;
+
+        let temp_dir = tempdir().unwrap();
+        let restrictions = ToolRestrictions::allow_only(vec![
+            ToolSpec::parse("Read"),
+            ToolSpec::parse("Bash(git:*)"),
+        ]);
+        let registry = ToolRegistryBuilder::new(temp_dir.path().to_path_buf())
+            .with_tool_scope(ToolScope::Custom(restrictions))
+            .build();
+
+        assert!(registry.get("Read").is_some());
+        assert!(registry.get("Bash").is_some());
+        assert!(registry.get("Write").is_none());
+        assert!(registry.get("Task").is_none());
+
+        let bash = registry.get("Bash").unwrap();
+        let result = bash
+            .execute(
+                serde_json::json!({"command": "rm -rf build"}),
+                ToolExecutionContext::standalone("test", "Bash"),
+            )
+            .await;
+        assert!(matches!(result, Err(ToolError::PermissionDenied(_))));
+    }
+
+    #[test]
+    fn test_policy_disables_tools() {
+        let temp_dir = tempdir().unwrap();
+        let policy = Policy::parse("[tools]\ndisabled = [\"browser_*\", \"NotebookEdit\"]").unwrap();
+        let registry = ToolRegistryBuilder::new(temp_dir.path().to_path_buf())
+            .with_policy(Arc::new(policy))
+            .build();
+
+        assert!(registry.get("NotebookEdit").is_none());
+        assert!(registry.get("Read").is_some());
+        assert!(registry.get("WebFetch").is_some());
+
+        let mut registry = ToolRegistryBuilder::new(temp_dir.path().to_path_buf())
+            .with_policy(Arc::new(Policy::default()))
+            .build();
+        assert!(registry.get("NotebookEdit").is_some());
+        registry.retain(|name| name != "Read");
+        assert!(registry.get("Read").is_none());
+    }
+
     #[test]
     fn test_registry_without_provider_has_no_task_tools() {
         let temp_dir = tempdir().unwrap();
@@ -507,4 +1040,82 @@ mod tests {
         assert!(registry.get("Task").is_none());
         assert!(registry.get("TaskOutput").is_none());
     }
+
+    #[test]
+    fn test_clipboard_write_only_with_clipboard() {
+        struct NoopClipboard;
+        impl Clipboard for NoopClipboard {
+            fn write_text(&self, _text: &str) -> Result<(), String> {
+                Ok(())
+            }
+        }
+
+        let temp_dir = tempdir().unwrap();
+        let registry = ToolRegistryBuilder::new(temp_dir.path().to_path_buf()).build();
+        assert!(registry.get("ClipboardWrite").is_none());
+
+        let registry = ToolRegistryBuilder::new(temp_dir.path().to_path_buf())
+            .with_clipboard(Arc::new(NoopClipboard))
+            .build();
+        assert!(registry.get("ClipboardWrite").is_some());
+    }
+
+    /// Every tool a session can have (but MCP tools and ClipboardWrite),
+    /// filtered by `profile`
+    fn profile_tools(profile: &str) -> Vec<String> {
+        let temp_dir = tempdir().unwrap();
+        let ws_config = WebSearchConfig {
+            api_key: Some("test-api-key".to_string()),
+            ..Default::default()
+        };
+        let profile = ToolProfile::preset(profile).unwrap();
+        // A provider without native search, so WebSearch is registered
+        let registry = ToolRegistryBuilder::new(temp_dir.path().to_path_buf())
+            .with_provider("deepseek")
+            .with_api_key("test-key".to_string())
+            .with_web_search_config(ws_config)
+            .with_skill_registry(Arc::new(SkillRegistry::new()))
+            .with_tool_profile(profile)
+            .build();
+        let mut names: Vec<String> = registry.list().into_iter().map(|t| t.name).collect();
+        names.sort();
+        names
+    }
+
+    // Tools added to a preset should be a decision, not an accident: update
+    // these lists (and docs/configuration.md) on purpose.
+    #[test]
+    fn test_preset_tool_sets() {
+        assert_eq!(profile_tools("minimal"), ["Bash", "Glob", "Grep", "Read"]);
+        assert_eq!(
+            profile_tools("coding"),
+            [
+                "Bash", "BatchEdit", "CodebaseSearch", "Edit", "FinishFile", "Glob", "Grep", "KillShell", "LSP",
+                "Read", "SearchFiles", "Task", "TaskOutput", "TodoWrite", "Write",
+            ]
+        );
+        assert_eq!(profile_tools("research"), ["Glob", "Grep", "Read", "SearchFiles", "WebFetch", "WebSearch"]);
+        assert_eq!(
+            profile_tools("full"),
+            [
+                "AskUserQuestion", "Bash", "BatchEdit", "CodebaseSearch", "Edit", "EnterPlanMode", "ExitPlanMode",
+                "ExportDocument", "FinishFile", "Glob", "Grep", "KillShell", "LSP", "NotebookEdit", "PlanStatus", "Read", "SearchFiles", "Skill",
+                "Task", "TaskOutput", "TodoWrite", "WebFetch", "WebSearch", "Write", "WriteDocx", "WritePptx",
+                "WriteXlsx",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_filtered_registry_shares_tools() {
+        let temp_dir = tempdir().unwrap();
+        let registry = ToolRegistryBuilder::new(temp_dir.path().to_path_buf()).build();
+        let profile = ToolProfile::preset("minimal").unwrap();
+        let minimal = registry.filtered(|name| profile.allows(name));
+
+        assert_eq!(minimal.list().len(), 4);
+        assert!(Arc::ptr_eq(&minimal.get("Bash").unwrap(), &registry.get("Bash").unwrap()));
+        assert!(minimal.get("Write").is_none());
+        assert!(registry.get("Write").is_some());
+    }
 }
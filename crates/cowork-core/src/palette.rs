@@ -0,0 +1,477 @@
+//! Command palette entries: one list of everything a person can invoke
+//!
+//! The desktop app's ⌘K palette and `cowork palette --json` show skills,
+//! slash commands, tools, agents and recent sessions as `PaletteItem`s of
+//! the same shape, with keywords to fuzzy-match on and an invocation saying
+//! what picking the item does. Items that can't be used right now (a tool
+//! the policy disables, one the tool profile leaves out) are included with
+//! the reason, so the palette can gray them out.
+//!
+//! Loading the registries and the saved sessions is slow, so `Palette`
+//! keeps what it loaded until the files it came from change (or
+//! `invalidate` is called); the config-dependent parts are filled in on
+//! each call.
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::approval::{ApprovalLevel, ToolApprovalConfig};
+use crate::config::Config;
+use crate::orchestration::ToolProfile;
+use crate::policy::Policy;
+use crate::prompt::registry::ComponentPaths;
+use crate::prompt::ComponentRegistry;
+use crate::session::{SessionState, SessionSummary};
+use crate::skills::SkillRegistry;
+use crate::tools::standard_tool_definitions;
+
+/// How many saved sessions the palette offers, most recent first
+const RECENT_SESSIONS: usize = 20;
+
+/// What a palette item is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PaletteKind {
+    Skill,
+    Command,
+    Tool,
+    Agent,
+    Session,
+}
+
+impl PaletteKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Skill => "skill",
+            Self::Command => "command",
+            Self::Tool => "tool",
+            Self::Agent => "agent",
+            Self::Session => "session",
+        }
+    }
+}
+
+/// What picking an item does
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PaletteInvocation {
+    /// Send `text` as a message (put it in the message box first when the
+    /// item takes arguments)
+    SlashCommand { text: String },
+    /// Put `text` in the message box for the person to finish
+    Prefill { text: String },
+    /// Call the Tauri command `command` with `args`
+    TauriCommand { command: String, args: serde_json::Value },
+    /// Switch to the open session
+    OpenSession { session_id: String },
+}
+
+/// An entry of the command palette
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PaletteItem {
+    /// `<kind>:<name>`, unique across the list
+    pub id: String,
+    pub kind: PaletteKind,
+    pub title: String,
+    pub description: String,
+    /// Lowercase words to fuzzy-match on besides the title
+    pub keywords: Vec<String>,
+    /// Argument hints, e.g. `<file>` or `--force`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    pub invocation: PaletteInvocation,
+    /// Why the item can't be used now (None = it can)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unavailable: Option<String>,
+    /// Tools: whether calling it asks for approval at the configured level
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub needs_approval: Option<bool>,
+    /// Tools: the tool profiles offering it
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub profiles: Vec<String>,
+}
+
+impl PaletteItem {
+    fn new(kind: PaletteKind, name: &str, title: String, description: String, invocation: PaletteInvocation) -> Self {
+        let mut item = Self {
+            id: format!("{}:{}", kind.as_str(), name),
+            kind,
+            keywords: Vec::new(),
+            title,
+            description,
+            args: Vec::new(),
+            invocation,
+            unavailable: None,
+            needs_approval: None,
+            profiles: Vec::new(),
+        };
+        item.add_keywords(words(name));
+        item.add_keywords([kind.as_str().to_string()]);
+        item
+    }
+
+    /// Add keywords it doesn't have yet
+    fn add_keywords(&mut self, keywords: impl IntoIterator<Item = String>) {
+        for keyword in keywords {
+            if !self.keywords.contains(&keyword) {
+                self.keywords.push(keyword);
+            }
+        }
+    }
+}
+
+/// Modification times of the files palette items are loaded from
+type Fingerprint = Vec<Option<SystemTime>>;
+
+/// Palette items of a workspace, loaded once and kept until their files change
+pub struct Palette {
+    workspace: PathBuf,
+    cache: Mutex<Option<(Fingerprint, Vec<PaletteItem>)>>,
+}
+
+impl Palette {
+    pub fn new(workspace: impl Into<PathBuf>) -> Self {
+        Self {
+            workspace: workspace.into(),
+            cache: Mutex::new(None),
+        }
+    }
+
+    /// Forget the loaded items (after installing a skill, say)
+    pub fn invalidate(&self) {
+        *self.cache.lock().unwrap() = None;
+    }
+
+    /// Every item: skills, slash commands, tools, agents, then the open
+    /// sessions and the recent saved ones
+    ///
+    /// `profile` is the tool profile of the session the palette is opened
+    /// in.
+    pub fn items(&self, config: &Config, policy: &Policy, profile: &ToolProfile, open_sessions: &[SessionSummary]) -> Vec<PaletteItem> {
+        let loaded = {
+            let fingerprint = fingerprint(&self.workspace);
+            let mut cache = self.cache.lock().unwrap();
+            match cache.as_ref() {
+                Some((cached, items)) if *cached == fingerprint => items.clone(),
+                _ => {
+                    let items = load(&self.workspace);
+                    *cache = Some((fingerprint, items.clone()));
+                    items
+                }
+            }
+        };
+
+        let level = config.approval.auto_approve_level.parse().unwrap_or(ApprovalLevel::Low);
+        let mut approval = ToolApprovalConfig::default();
+        approval.apply_policy(policy);
+        approval.set_level(level);
+        let profiles: Vec<(String, ToolProfile)> = config
+            .tools
+            .profile_names()
+            .into_iter()
+            .filter_map(|name| Some((name.clone(), config.tools.resolve(&name).ok()?)))
+            .collect();
+
+        let mut items: Vec<PaletteItem> = loaded
+            .into_iter()
+            .filter(|item| match (item.kind, &item.invocation) {
+                // Open sessions are listed below, from `open_sessions`
+                (PaletteKind::Session, PaletteInvocation::TauriCommand { args, .. }) => {
+                    let id = args["sessionId"].as_str().unwrap_or_default();
+                    !open_sessions.iter().any(|s| s.session_id == id)
+                }
+                _ => true,
+            })
+            .map(|mut item| {
+                let name = item.id.split_once(':').map_or("", |(_, name)| name).to_string();
+                match item.kind {
+                    PaletteKind::Tool => {
+                        item.needs_approval = Some(approval.needs_approval(&name));
+                        item.profiles = profiles
+                            .iter()
+                            .filter(|(_, p)| p.allows(&name))
+                            .map(|(profile_name, _)| profile_name.clone())
+                            .collect();
+                        if policy.is_tool_disabled(&name) {
+                            item.unavailable = Some("Disabled by the organization policy".to_string());
+                        } else if !profile.allows(&name) {
+                            item.unavailable = Some(format!("Not offered by the tool profile '{}'", profile));
+                        }
+                    }
+                    PaletteKind::Agent if !profile.allows("Task") || policy.is_tool_disabled("Task") => {
+                        item.unavailable = Some("Agents need the Task tool, which isn't offered".to_string());
+                    }
+                    _ => {}
+                }
+                item
+            })
+            .collect();
+
+        let open = open_sessions.iter().map(|session| {
+            let title = session.title.clone().unwrap_or_else(|| session.session_id.clone());
+            let state = match session.state {
+                SessionState::Active => "Open",
+                SessionState::Suspended => "Open, suspended while idle",
+            };
+            let mut item = PaletteItem::new(
+                PaletteKind::Session,
+                &session.session_id,
+                title.clone(),
+                format!("{} (tools: {})", state, session.tool_profile),
+                PaletteInvocation::OpenSession {
+                    session_id: session.session_id.clone(),
+                },
+            );
+            item.add_keywords(words(&title));
+            item
+        });
+        let first_saved = items.iter().position(|item| item.kind == PaletteKind::Session).unwrap_or(items.len());
+        items.splice(first_saved..first_saved, open);
+        items
+    }
+}
+
+/// Load the items that only change with files on disk
+fn load(workspace: &Path) -> Vec<PaletteItem> {
+    let mut items = Vec::new();
+
+    let mut skills = SkillRegistry::with_builtins(workspace.to_path_buf()).list();
+    skills.sort_by(|a, b| a.name.cmp(&b.name));
+    for skill in &skills {
+        let text = format!("/{}", skill.name);
+        let mut item = PaletteItem::new(
+            PaletteKind::Skill,
+            &skill.name,
+            text.clone(),
+            skill.description.clone(),
+            PaletteInvocation::SlashCommand { text },
+        );
+        item.add_keywords(words(&skill.display_name));
+        // Usage other than the bare command names the arguments
+        if let Some(args) = skill.usage.strip_prefix("Usage: ").unwrap_or(&skill.usage).strip_prefix(&format!("/{} ", skill.name)) {
+            item.args = vec![args.trim().to_string()];
+        }
+        if !skill.user_invocable {
+            item.unavailable = Some("Only the model uses this skill".to_string());
+        }
+        items.push(item);
+    }
+
+    let registry = ComponentRegistry::for_workspace(workspace).unwrap_or_else(|e| {
+        tracing::warn!("Palette: couldn't load components: {}", e);
+        ComponentRegistry::with_builtins()
+    });
+    let mut commands: Vec<_> = registry
+        .command_names()
+        .filter_map(|name| registry.get_command(name))
+        // A command a skill of the same name runs is listed once, as the skill
+        .filter(|command| !skills.iter().any(|skill| skill.name == command.name()))
+        .collect();
+    commands.sort_by(|a, b| a.name().cmp(b.name()));
+    for command in commands {
+        let text = command.invocation();
+        let mut item = PaletteItem::new(
+            PaletteKind::Command,
+            command.name(),
+            text.clone(),
+            command.description().to_string(),
+            PaletteInvocation::SlashCommand { text },
+        );
+        item.add_keywords([format!("{:?}", command.scope).to_lowercase()]);
+        item.args = command.argument_hints().to_vec();
+        items.push(item);
+    }
+
+    let mut tools = standard_tool_definitions(workspace);
+    tools.sort_by(|a, b| a.name.cmp(&b.name));
+    for tool in tools {
+        let description = tool.description.as_deref().and_then(|d| d.lines().next()).unwrap_or_default();
+        let description = description.trim_start_matches("- ");
+        items.push(PaletteItem::new(
+            PaletteKind::Tool,
+            &tool.name,
+            tool.name.clone(),
+            description.to_string(),
+            PaletteInvocation::Prefill {
+                text: format!("Use {} to ", tool.name),
+            },
+        ));
+    }
+
+    for agent in registry.summary().agents {
+        let mut item = PaletteItem::new(
+            PaletteKind::Agent,
+            &agent.name,
+            agent.name.clone(),
+            agent.description.clone(),
+            PaletteInvocation::Prefill {
+                text: format!("Use the {} agent to ", agent.name),
+            },
+        );
+        item.add_keywords([agent.scope.clone()]);
+        items.push(item);
+    }
+
+    let mut saved = crate::session::list_saved_sessions().unwrap_or_else(|e| {
+        tracing::warn!("Palette: couldn't list saved sessions: {}", e);
+        Vec::new()
+    });
+    saved.sort_by_key(|session| std::cmp::Reverse(session.updated_at));
+    for session in saved.into_iter().take(RECENT_SESSIONS) {
+        let mut item = PaletteItem::new(
+            PaletteKind::Session,
+            &session.id,
+            session.name.clone(),
+            format!(
+                "Saved {}, {} messages",
+                session.updated_at.format("%Y-%m-%d %H:%M"),
+                session.messages.len()
+            ),
+            PaletteInvocation::TauriCommand {
+                command: "load_saved_session".to_string(),
+                args: json!({ "sessionId": session.id }),
+            },
+        );
+        item.add_keywords(words(&session.name));
+        items.push(item);
+    }
+
+    items
+}
+
+/// Modification times of what the items are loaded from: the component
+/// and skill directories (two levels deep, as skills are directories) and
+/// the saved sessions
+fn fingerprint(workspace: &Path) -> Fingerprint {
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    for (base, _) in ComponentPaths::for_project(workspace).iter_by_priority() {
+        dirs.push(ComponentPaths::agents_dir(base));
+        dirs.push(ComponentPaths::skills_dir(base));
+        dirs.push(ComponentPaths::commands_dir(base));
+    }
+    dirs.push(workspace.join(".cowork").join("skills"));
+    dirs.extend(crate::session::get_sessions_dir().ok());
+    dirs.extend(crate::session::get_autosave_dir().ok());
+    dirs.iter().map(|dir| latest_change(dir, 2)).collect()
+}
+
+/// Latest modification time of `path` and what it holds, `depth` levels down
+fn latest_change(path: &Path, depth: usize) -> Option<SystemTime> {
+    let mut latest = std::fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    if depth > 0
+        && let Ok(entries) = std::fs::read_dir(path)
+    {
+        for entry in entries.flatten() {
+            if let Some(modified) = latest_change(&entry.path(), depth - 1) {
+                latest = latest.max(modified);
+            }
+        }
+    }
+    Some(latest)
+}
+
+/// Lowercase words of `text`, split at separators and camel case humps
+/// (`WriteDocx` → write, docx)
+fn words(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut previous_lower = false;
+    for c in text.chars() {
+        if (!c.is_alphanumeric() || (c.is_uppercase() && previous_lower)) && !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+        if c.is_alphanumeric() {
+            word.extend(c.to_lowercase());
+        }
+        previous_lower = c.is_lowercase();
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add_skill(workspace: &Path, name: &str) {
+        let dir = workspace.join(".cowork").join("skills").join(name);
+        std::fs::create_dir_all(&dir).unwrap();
+        let content = format!("---\nname: {}\ndescription: Test skill\n---\nDo it", name);
+        std::fs::write(dir.join("SKILL.md"), content).unwrap();
+    }
+
+    fn palette_items(palette: &Palette, profile: &ToolProfile) -> Vec<PaletteItem> {
+        palette.items(&Config::default(), &Policy::default(), profile, &[])
+    }
+
+    #[test]
+    fn test_every_skill_and_tool_listed_once() {
+        let workspace = tempfile::tempdir().unwrap();
+        add_skill(workspace.path(), "deploy-preview");
+        let items = palette_items(&Palette::new(workspace.path()), &ToolProfile::full());
+
+        let count = |kind: PaletteKind, name: &str| {
+            let id = format!("{}:{}", kind.as_str(), name);
+            items.iter().filter(|item| item.id == id).count()
+        };
+        let skills = SkillRegistry::with_builtins(workspace.path().to_path_buf()).list();
+        assert!(skills.iter().any(|skill| skill.name == "deploy-preview"));
+        for skill in skills {
+            assert_eq!(count(PaletteKind::Skill, &skill.name), 1, "skill {}", skill.name);
+        }
+        let tools = standard_tool_definitions(workspace.path());
+        assert!(!tools.is_empty());
+        for tool in tools {
+            assert_eq!(count(PaletteKind::Tool, &tool.name), 1, "tool {}", tool.name);
+        }
+        // Commands a skill runs are listed as the skill
+        assert_eq!(count(PaletteKind::Command, "commit"), 0);
+
+        let mut ids: Vec<&str> = items.iter().map(|item| item.id.as_str()).collect();
+        ids.sort();
+        let listed = ids.len();
+        ids.dedup();
+        assert_eq!(ids.len(), listed);
+    }
+
+    #[test]
+    fn test_tools_outside_the_profile_are_unavailable() {
+        let workspace = tempfile::tempdir().unwrap();
+        let minimal = ToolProfile::preset("minimal").unwrap();
+        let items = palette_items(&Palette::new(workspace.path()), &minimal);
+        let item = |id: &str| items.iter().find(|item| item.id == id).unwrap();
+
+        let write = item("tool:Write");
+        assert_eq!(write.unavailable.as_deref(), Some("Not offered by the tool profile 'minimal'"));
+        assert_eq!(write.needs_approval, Some(true));
+        assert_eq!(write.profiles, vec!["coding", "full"]);
+        let read = item("tool:Read");
+        assert_eq!(read.unavailable, None);
+        assert_eq!(read.needs_approval, Some(false));
+        assert!(item("agent:Explore").unavailable.is_some());
+    }
+
+    #[test]
+    fn test_new_skills_invalidate_the_cache() {
+        let workspace = tempfile::tempdir().unwrap();
+        let palette = Palette::new(workspace.path());
+        let listed = |palette: &Palette| palette_items(palette, &ToolProfile::full()).iter().any(|item| item.id == "skill:lint-all");
+        assert!(!listed(&palette));
+        add_skill(workspace.path(), "lint-all");
+        assert!(listed(&palette));
+    }
+
+    #[test]
+    fn test_words() {
+        assert_eq!(words("WriteDocx"), vec!["write", "docx"]);
+        assert_eq!(words("commit-push-pr"), vec!["commit", "push", "pr"]);
+        assert_eq!(words("clean_gone"), vec!["clean", "gone"]);
+        assert_eq!(words("LSP"), vec!["lsp"]);
+    }
+}
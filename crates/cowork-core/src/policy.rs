@@ -0,0 +1,356 @@
+//! Organization policy - managed settings users can't override
+//!
+//! An administrator deploys a policy file (`/etc/cowork/policy.toml`, or
+//! `%ProgramData%\cowork\policy.toml` on Windows; the `COWORK_POLICY_FILE`
+//! environment variable overrides the path). `ConfigManager` loads it before
+//! the user's config, and its rules constrain what that config can do:
+//!
+//! - `[approval] min_level` - `auto_approve_level` is raised to at least this
+//! - `[approval] require` - tools that always ask, even when auto-approved
+//!   or approved for the session
+//! - `[tools] disabled` - tools left out of every registry
+//! - `[network] blocked_hosts` - hosts WebFetch refuses (subdomains included)
+//! - `[provider] allowed` / `base_url` - the providers sessions may use, and
+//!   the endpoint they must go through
+//!
+//! Tool names may use `*` wildcards, and MCP tools also match by their name
+//! on the server (`browser_*` matches `mcp__playwright__browser_click`).
+//! Without a policy file nothing is constrained.
+//!
+//! ```toml
+//! [approval]
+//! min_level = "medium"
+//! require = ["Bash"]
+//!
+//! [tools]
+//! disabled = ["browser_*"]
+//!
+//! [network]
+//! blocked_hosts = ["pastebin.com"]
+//!
+//! [provider]
+//! allowed = ["anthropic"]
+//! base_url = "https://llm-gateway.example.com/anthropic"
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::approval::ApprovalLevel;
+use crate::error::{Error, Result};
+use crate::tools::mcp::MCP_TOOL_PREFIX;
+
+/// Environment variable overriding the policy file path
+pub const POLICY_FILE_ENV: &str = "COWORK_POLICY_FILE";
+
+/// Constraints from the organization policy file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Policy {
+    pub approval: ApprovalRules,
+    pub tools: ToolRules,
+    pub network: NetworkRules,
+    pub provider: ProviderRules,
+    /// File the policy was loaded from
+    #[serde(skip)]
+    pub source: Option<PathBuf>,
+}
+
+/// `[approval]` rules
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApprovalRules {
+    /// Least strict `auto_approve_level` allowed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_level: Option<String>,
+    /// Tools that always need approval
+    pub require: Vec<String>,
+}
+
+/// `[tools]` rules
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ToolRules {
+    /// Tools no session may use
+    pub disabled: Vec<String>,
+}
+
+/// `[network]` rules
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetworkRules {
+    /// Hosts the web tools may not reach
+    pub blocked_hosts: Vec<String>,
+}
+
+/// `[provider]` rules
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProviderRules {
+    /// Providers sessions may use (empty = any)
+    pub allowed: Vec<String>,
+    /// Endpoint every provider request must go through
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+}
+
+impl Policy {
+    /// Where the policy file is looked for
+    pub fn default_path() -> PathBuf {
+        if let Some(path) = std::env::var_os(POLICY_FILE_ENV) {
+            return PathBuf::from(path);
+        }
+        #[cfg(windows)]
+        {
+            let program_data = std::env::var_os("ProgramData").unwrap_or_else(|| "C:\\ProgramData".into());
+            PathBuf::from(program_data).join("cowork").join("policy.toml")
+        }
+        #[cfg(not(windows))]
+        {
+            PathBuf::from("/etc/cowork/policy.toml")
+        }
+    }
+
+    /// Load the policy from its default path
+    pub fn load() -> Result<Self> {
+        Self::load_from(&Self::default_path())
+    }
+
+    /// Load the policy from `path`; no file means no constraints
+    pub fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| Error::Config(format!("Failed to read policy {}: {}", path.display(), e)))?;
+        let mut policy = Self::parse(&content)
+            .map_err(|e| Error::Config(format!("Invalid policy {}: {}", path.display(), e)))?;
+        policy.source = Some(path.to_path_buf());
+        Ok(policy)
+    }
+
+    /// Parse a policy file's content
+    pub fn parse(content: &str) -> std::result::Result<Self, String> {
+        let policy: Self = toml::from_str(content).map_err(|e| e.to_string())?;
+        if let Some(level) = &policy.approval.min_level {
+            level.parse::<ApprovalLevel>()?;
+        }
+        Ok(policy)
+    }
+
+    /// The policy to enforce when the policy file exists but can't be read:
+    /// every tool call needs approval
+    pub fn unreadable(path: &Path) -> Self {
+        Self {
+            approval: ApprovalRules {
+                min_level: Some(ApprovalLevel::Critical.to_string()),
+                require: vec!["*".to_string()],
+            },
+            source: Some(path.to_path_buf()),
+            ..Default::default()
+        }
+    }
+
+    /// Whether the policy constrains nothing
+    pub fn is_empty(&self) -> bool {
+        Self { source: None, ..self.clone() } == Self::default()
+    }
+
+    /// "the organization policy (<path>)", for messages
+    pub fn describe(&self) -> String {
+        match &self.source {
+            Some(path) => format!("the organization policy ({})", path.display()),
+            None => "the organization policy".to_string(),
+        }
+    }
+
+    /// The least strict approval level allowed
+    pub fn min_approval_level(&self) -> Option<ApprovalLevel> {
+        self.approval.min_level.as_deref().and_then(|level| level.parse().ok())
+    }
+
+    /// Raise `level` to the policy's minimum strictness
+    pub fn clamp_approval_level(&self, level: ApprovalLevel) -> ApprovalLevel {
+        self.min_approval_level().map_or(level, |min| level.max(min))
+    }
+
+    /// Whether calls to `tool` always need approval
+    pub fn requires_approval(&self, tool: &str) -> bool {
+        self.approval.require.iter().any(|pattern| tool_matches(pattern, tool))
+    }
+
+    /// Whether `tool` is disabled
+    pub fn is_tool_disabled(&self, tool: &str) -> bool {
+        self.tools.disabled.iter().any(|pattern| tool_matches(pattern, tool))
+    }
+
+    /// Hosts the web tools may not reach
+    pub fn blocked_hosts(&self) -> &[String] {
+        &self.network.blocked_hosts
+    }
+
+    /// Whether sessions may use `provider_id`
+    pub fn allows_provider(&self, provider_id: &str) -> bool {
+        self.provider.allowed.is_empty() || self.provider.allowed.iter().any(|p| p == provider_id)
+    }
+
+    /// Check provider settings, returning the base URL to use
+    ///
+    /// Fails for a provider outside the allowed list, or a configured base
+    /// URL other than the one the policy requires.
+    pub fn check_provider(&self, provider_id: &str, base_url: Option<&str>) -> Result<Option<String>> {
+        if !self.allows_provider(provider_id) {
+            return Err(Error::Config(format!(
+                "Provider '{}' is not allowed by {}. Allowed providers: {}",
+                provider_id,
+                self.describe(),
+                self.provider.allowed.join(", ")
+            )));
+        }
+        let Some(required) = &self.provider.base_url else {
+            return Ok(base_url.map(String::from));
+        };
+        match base_url {
+            Some(url) if url.trim_end_matches('/') != required.trim_end_matches('/') => Err(Error::Config(format!(
+                "base_url {} for provider '{}' is not allowed by {}; requests must go through {}",
+                url,
+                provider_id,
+                self.describe(),
+                required
+            ))),
+            _ => Ok(Some(required.clone())),
+        }
+    }
+
+    /// Settings of the `Config` tool the policy constrains
+    pub fn locked_settings(&self) -> Vec<&'static str> {
+        let mut locked = Vec::new();
+        if self.approval.min_level.is_some() {
+            locked.push("auto_approve_level");
+        }
+        if !self.provider.allowed.is_empty() {
+            locked.extend(["default_provider", "provider"]);
+        }
+        locked
+    }
+}
+
+/// Whether a tool name matches a policy pattern (`*` matches any run of characters)
+///
+/// MCP tools (`mcp__<server>__<tool>`) also match by their name on the server.
+pub fn tool_matches(pattern: &str, tool: &str) -> bool {
+    let server_name = tool
+        .strip_prefix(MCP_TOOL_PREFIX)
+        .and_then(|rest| rest.split_once("__"))
+        .map(|(_, name)| name);
+    wildcard_match(pattern, tool) || server_name.is_some_and(|name| wildcard_match(pattern, name))
+}
+
+pub(crate) fn wildcard_match(pattern: &str, text: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == text,
+        Some((prefix, rest)) => {
+            let Some(text) = text.strip_prefix(prefix) else {
+                return false;
+            };
+            (0..=text.len())
+                .filter(|&i| text.is_char_boundary(i))
+                .any(|i| wildcard_match(rest, &text[i..]))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POLICY: &str = r#"
+[approval]
+min_level = "medium"
+require = ["Bash"]
+
+[tools]
+disabled = ["browser_*", "NotebookEdit"]
+
+[network]
+blocked_hosts = ["pastebin.com"]
+
+[provider]
+allowed = ["anthropic"]
+base_url = "https://gateway.example.com/anthropic"
+"#;
+
+    #[test]
+    fn test_missing_file_changes_nothing() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let policy = Policy::load_from(&dir.path().join("policy.toml")).unwrap();
+        assert!(policy.is_empty());
+        assert_eq!(policy.clamp_approval_level(ApprovalLevel::None), ApprovalLevel::None);
+        assert!(!policy.requires_approval("Bash"));
+        assert!(!policy.is_tool_disabled("mcp__playwright__browser_click"));
+        assert_eq!(policy.check_provider("openai", Some("http://localhost:8080")).unwrap().as_deref(), Some("http://localhost:8080"));
+        assert!(policy.locked_settings().is_empty());
+    }
+
+    #[test]
+    fn test_load_and_reject_invalid() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("policy.toml");
+        std::fs::write(&path, POLICY).unwrap();
+        let policy = Policy::load_from(&path).unwrap();
+        assert_eq!(policy.source.as_deref(), Some(path.as_path()));
+        assert!(!policy.is_empty());
+
+        std::fs::write(&path, "[approval]\nmin_level = \"lenient\"\n").unwrap();
+        assert!(Policy::load_from(&path).unwrap_err().to_string().contains("lenient"));
+        // Typos are errors rather than silently unenforced rules
+        std::fs::write(&path, "[tools]\ndisable = [\"Bash\"]\n").unwrap();
+        assert!(Policy::load_from(&path).is_err());
+    }
+
+    #[test]
+    fn test_approval_level_clamped() {
+        let policy = Policy::parse(POLICY).unwrap();
+        assert_eq!(policy.clamp_approval_level(ApprovalLevel::Low), ApprovalLevel::Medium);
+        assert_eq!(policy.clamp_approval_level(ApprovalLevel::High), ApprovalLevel::High);
+        assert!(policy.requires_approval("Bash"));
+        assert!(!policy.requires_approval("Read"));
+    }
+
+    #[test]
+    fn test_disabled_tool_patterns() {
+        let policy = Policy::parse(POLICY).unwrap();
+        assert!(policy.is_tool_disabled("NotebookEdit"));
+        assert!(policy.is_tool_disabled("browser_navigate"));
+        assert!(policy.is_tool_disabled("mcp__playwright__browser_click"));
+        assert!(!policy.is_tool_disabled("mcp__github__create_issue"));
+        assert!(!policy.is_tool_disabled("Read"));
+
+        assert!(tool_matches("*", "Anything"));
+        assert!(tool_matches("mcp__*__search", "mcp__docs__search"));
+        assert!(!tool_matches("Bash", "BashOutput"));
+    }
+
+    #[test]
+    fn test_provider_rules() {
+        let policy = Policy::parse(POLICY).unwrap();
+        let gateway = "https://gateway.example.com/anthropic";
+        assert_eq!(policy.check_provider("anthropic", None).unwrap().as_deref(), Some(gateway));
+        assert_eq!(policy.check_provider("anthropic", Some("https://gateway.example.com/anthropic/")).unwrap().as_deref(), Some(gateway));
+
+        let err = policy.check_provider("openai", None).unwrap_err().to_string();
+        assert!(err.contains("'openai' is not allowed") && err.contains("anthropic"), "{}", err);
+        let err = policy.check_provider("anthropic", Some("https://api.anthropic.com")).unwrap_err().to_string();
+        assert!(err.contains("must go through"), "{}", err);
+
+        assert_eq!(policy.locked_settings(), vec!["auto_approve_level", "default_provider", "provider"]);
+    }
+
+    #[test]
+    fn test_unreadable_policy_asks_for_everything() {
+        let policy = Policy::unreadable(Path::new("/etc/cowork/policy.toml"));
+        assert!(policy.requires_approval("Read"));
+        assert_eq!(policy.clamp_approval_level(ApprovalLevel::None), ApprovalLevel::Critical);
+    }
+}
@@ -0,0 +1,561 @@
+//! Tracking of long-lived child processes, so none outlive cowork
+//!
+//! Language servers, MCP servers and background shells are registered with
+//! the `ProcessTracker` when they are spawned:
+//! - Each instance of cowork records its children (PID, kind, label and a
+//!   signature telling the process apart from a later one given the same
+//!   PID) in `<data dir>/cowork/processes/<instance PID>.json`.
+//! - The `ProcessGuard` returned for a child unregisters it when dropped.
+//!   On Unix, children started in a process group of their own have the
+//!   whole group killed, so what they started goes too.
+//! - On Windows, children are assigned to a Job Object that kills whatever
+//!   it holds once its last handle closes. cowork keeps that handle until it
+//!   exits, so the children and what they started go with it however it
+//!   ends, crashes included.
+//! - When an instance dies without dropping its guards (killed, crashed),
+//!   its file stays. `scan_orphans` at the next startup finds the children
+//!   it lists that still run, and `OrphanScan::kill` stops them.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// What a tracked child is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProcessKind {
+    Lsp,
+    Mcp,
+    Shell,
+}
+
+impl std::fmt::Display for ProcessKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Lsp => write!(f, "language server"),
+            Self::Mcp => write!(f, "MCP server"),
+            Self::Shell => write!(f, "background shell"),
+        }
+    }
+}
+
+/// A child process as recorded in the runtime file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrackedProcess {
+    pub pid: u32,
+    pub kind: ProcessKind,
+    /// The command, or the server's name
+    pub label: String,
+    pub started_at: DateTime<Utc>,
+    /// Tells the process apart from a later one reusing its PID (None when
+    /// the platform can't say)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    /// Whether it leads a process group of its own (killed as a whole)
+    #[serde(default)]
+    pub group: bool,
+}
+
+impl TrackedProcess {
+    /// Whether the process still runs (and is the one recorded)
+    pub fn is_running(&self) -> bool {
+        match (process_signature(self.pid), &self.signature) {
+            (None, _) => false,
+            (Some(current), Some(recorded)) => current == *recorded,
+            (Some(_), None) => true,
+        }
+    }
+}
+
+impl std::fmt::Display for TrackedProcess {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} (PID {}, started {})", self.kind, self.label, self.pid, self.started_at.format("%Y-%m-%d %H:%M"))
+    }
+}
+
+/// Contents of an instance's runtime file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RuntimeFile {
+    /// The cowork process that spawned the children
+    owner: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    owner_signature: Option<String>,
+    children: Vec<TrackedProcess>,
+}
+
+impl RuntimeFile {
+    fn owner_running(&self) -> bool {
+        match (process_signature(self.owner), &self.owner_signature) {
+            (None, _) => false,
+            (Some(current), Some(recorded)) => current == *recorded,
+            (Some(_), None) => true,
+        }
+    }
+}
+
+/// Registry of this instance's children (see the module docs)
+#[derive(Debug)]
+pub struct ProcessTracker {
+    dir: PathBuf,
+    children: Mutex<Vec<TrackedProcess>>,
+}
+
+impl ProcessTracker {
+    /// A tracker keeping its runtime file in `dir`
+    pub fn new(dir: impl Into<PathBuf>) -> Arc<Self> {
+        Arc::new(Self {
+            dir: dir.into(),
+            children: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// The tracker of this process, keeping its file in `default_dir()`
+    pub fn global() -> &'static Arc<Self> {
+        static TRACKER: OnceLock<Arc<ProcessTracker>> = OnceLock::new();
+        TRACKER.get_or_init(|| Self::new(default_dir()))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Register a child just spawned; `group` when it leads a process group
+    /// of its own
+    pub fn track(self: &Arc<Self>, pid: u32, kind: ProcessKind, label: impl Into<String>, group: bool) -> ProcessGuard {
+        let process = TrackedProcess {
+            pid,
+            kind,
+            label: label.into(),
+            started_at: Utc::now(),
+            signature: process_signature(pid),
+            group,
+        };
+        tracing::debug!("Tracking {}", process);
+        #[cfg(windows)]
+        if let Err(e) = job::assign(pid) {
+            tracing::warn!("Couldn't tie {} to cowork's lifetime: {}", process, e);
+        }
+        let mut children = self.children.lock().unwrap();
+        children.push(process);
+        self.save(&children);
+        ProcessGuard {
+            tracker: self.clone(),
+            pid,
+            group,
+        }
+    }
+
+    /// Children registered and still running
+    pub fn children(&self) -> Vec<TrackedProcess> {
+        let children = self.children.lock().unwrap();
+        children.iter().filter(|c| c.is_running()).cloned().collect()
+    }
+
+    fn untrack(&self, pid: u32) {
+        let mut children = self.children.lock().unwrap();
+        children.retain(|c| c.pid != pid);
+        self.save(&children);
+    }
+
+    fn file(&self) -> PathBuf {
+        self.dir.join(format!("{}.json", std::process::id()))
+    }
+
+    /// Write the runtime file, or remove it once no children are left
+    fn save(&self, children: &[TrackedProcess]) {
+        let path = self.file();
+        if children.is_empty() {
+            let _ = std::fs::remove_file(&path);
+            return;
+        }
+        let file = RuntimeFile {
+            owner: std::process::id(),
+            owner_signature: process_signature(std::process::id()),
+            children: children.to_vec(),
+        };
+        let written = std::fs::create_dir_all(&self.dir).and_then(|_| {
+            let tmp = path.with_extension("json.tmp");
+            std::fs::write(&tmp, serde_json::to_vec_pretty(&file)?)?;
+            std::fs::rename(&tmp, &path)
+        });
+        if let Err(e) = written {
+            tracing::warn!("Couldn't record child processes in {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// A registered child; dropping it unregisters the child and, for a group
+/// leader on Unix, kills its process group
+#[derive(Debug)]
+pub struct ProcessGuard {
+    tracker: Arc<ProcessTracker>,
+    pid: u32,
+    group: bool,
+}
+
+impl ProcessGuard {
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+}
+
+impl Drop for ProcessGuard {
+    fn drop(&mut self) {
+        if self.group {
+            let _ = kill_process(self.pid, true);
+        }
+        self.tracker.untrack(self.pid);
+    }
+}
+
+/// `<data dir>/cowork/processes`
+pub fn default_dir() -> PathBuf {
+    dirs::data_dir()
+        .map(|p| p.join("cowork"))
+        .unwrap_or_else(|| PathBuf::from(".cowork"))
+        .join("processes")
+}
+
+/// Children of the cowork instances running now, by instance PID
+pub fn running_instances(dir: &Path) -> Vec<(u32, Vec<TrackedProcess>)> {
+    runtime_files(dir)
+        .into_iter()
+        .filter(|(_, file)| file.owner_running())
+        .map(|(_, file)| {
+            let children = file.children.into_iter().filter(TrackedProcess::is_running).collect();
+            (file.owner, children)
+        })
+        .collect()
+}
+
+/// Children earlier instances left running (see `scan_orphans`)
+#[derive(Debug, Default)]
+pub struct OrphanScan {
+    pub orphans: Vec<TrackedProcess>,
+    /// Runtime files of the instances that left them
+    files: Vec<PathBuf>,
+}
+
+/// What became of the orphans `OrphanScan::kill` was given
+#[derive(Debug, Default)]
+pub struct OrphanReport {
+    pub killed: Vec<TrackedProcess>,
+    pub failed: Vec<(TrackedProcess, String)>,
+}
+
+/// Find the children of instances that exited without cleaning up and are
+/// still running
+///
+/// Runtime files of such instances whose children have all exited are
+/// removed.
+pub fn scan_orphans(dir: &Path) -> OrphanScan {
+    let mut scan = OrphanScan::default();
+    for (path, file) in runtime_files(dir) {
+        if file.owner_running() {
+            continue;
+        }
+        let orphans: Vec<TrackedProcess> = file.children.into_iter().filter(TrackedProcess::is_running).collect();
+        if orphans.is_empty() {
+            let _ = std::fs::remove_file(&path);
+        } else {
+            scan.orphans.extend(orphans);
+            scan.files.push(path);
+        }
+    }
+    scan
+}
+
+impl OrphanScan {
+    pub fn is_empty(&self) -> bool {
+        self.orphans.is_empty()
+    }
+
+    /// One line naming the orphans, for a startup message
+    pub fn summary(&self) -> String {
+        let orphans: Vec<String> = self.orphans.iter().map(ToString::to_string).collect();
+        format!("{} process(es) an earlier run left behind still running: {}", orphans.len(), orphans.join(", "))
+    }
+
+    /// Kill the orphans (whole process groups for group leaders) and forget
+    /// the instances that left them
+    pub fn kill(self) -> OrphanReport {
+        let mut report = OrphanReport::default();
+        for orphan in self.orphans {
+            // Checked again: it may have exited or its PID been reused since the scan
+            if !orphan.is_running() {
+                continue;
+            }
+            match kill_process(orphan.pid, orphan.group) {
+                Ok(()) => {
+                    tracing::info!("Killed orphaned {}", orphan);
+                    report.killed.push(orphan);
+                }
+                Err(e) => {
+                    tracing::warn!("Couldn't kill orphaned {}: {}", orphan, e);
+                    report.failed.push((orphan, e));
+                }
+            }
+        }
+        if report.failed.is_empty() {
+            for path in self.files {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+        report
+    }
+}
+
+impl OrphanReport {
+    /// One line saying what was killed and what wasn't
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.killed.is_empty() {
+            let killed: Vec<String> = self.killed.iter().map(ToString::to_string).collect();
+            parts.push(format!("Killed {} process(es) an earlier run left behind: {}", killed.len(), killed.join(", ")));
+        }
+        for (orphan, error) in &self.failed {
+            parts.push(format!("Couldn't kill {}: {}", orphan, error));
+        }
+        if parts.is_empty() {
+            parts.push("The processes an earlier run left behind had already exited".to_string());
+        }
+        parts.join("; ")
+    }
+}
+
+fn runtime_files(dir: &Path) -> Vec<(PathBuf, RuntimeFile)> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| {
+            let file = std::fs::read(&path).ok().and_then(|bytes| serde_json::from_slice(&bytes).ok())?;
+            Some((path, file))
+        })
+        .collect()
+}
+
+/// The Job Object children are assigned to on Windows
+#[cfg(windows)]
+mod job {
+    use std::sync::OnceLock;
+
+    use windows_sys::Win32::Foundation::CloseHandle;
+    use windows_sys::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, JobObjectExtendedLimitInformation, SetInformationJobObject,
+        JOBOBJECT_EXTENDED_LIMIT_INFORMATION, JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+    };
+    use windows_sys::Win32::System::Threading::{OpenProcess, PROCESS_SET_QUOTA, PROCESS_TERMINATE};
+
+    /// This process's kill-on-close job, created on first use and never
+    /// closed: the OS closes it when cowork exits, killing what it holds
+    pub(super) fn handle() -> Result<usize, String> {
+        static JOB: OnceLock<Result<usize, String>> = OnceLock::new();
+        JOB.get_or_init(|| unsafe {
+            let job = CreateJobObjectW(std::ptr::null(), std::ptr::null());
+            if job.is_null() {
+                return Err(std::io::Error::last_os_error().to_string());
+            }
+            let mut limits: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = std::mem::zeroed();
+            limits.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+            let set = SetInformationJobObject(
+                job,
+                JobObjectExtendedLimitInformation,
+                &limits as *const _ as *const std::ffi::c_void,
+                std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+            );
+            if set == 0 {
+                let e = std::io::Error::last_os_error().to_string();
+                CloseHandle(job);
+                return Err(e);
+            }
+            Ok(job as usize)
+        })
+        .clone()
+    }
+
+    /// Put a child in the job; what it starts afterwards joins it too
+    pub(super) fn assign(pid: u32) -> Result<(), String> {
+        let job = handle()?;
+        unsafe {
+            let process = OpenProcess(PROCESS_SET_QUOTA | PROCESS_TERMINATE, 0, pid);
+            if process.is_null() {
+                return Err(std::io::Error::last_os_error().to_string());
+            }
+            let assigned = AssignProcessToJobObject(job as _, process);
+            let result = if assigned == 0 { Err(std::io::Error::last_os_error().to_string()) } else { Ok(()) };
+            CloseHandle(process);
+            result
+        }
+    }
+}
+
+/// Kill a process, or the process group it leads
+fn kill_process(pid: u32, group: bool) -> Result<(), String> {
+    #[cfg(unix)]
+    {
+        use nix::sys::signal::{kill, killpg, Signal};
+        use nix::unistd::Pid;
+        let pid = Pid::from_raw(i32::try_from(pid).map_err(|e| e.to_string())?);
+        let result = if group { killpg(pid, Signal::SIGKILL) } else { kill(pid, Signal::SIGKILL) };
+        match result {
+            Ok(()) | Err(nix::errno::Errno::ESRCH) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+    #[cfg(windows)]
+    {
+        let _ = group;
+        let mut cmd = std::process::Command::new("taskkill");
+        cmd.args(["/F", "/T", "/PID", &pid.to_string()]);
+        {
+            use std::os::windows::process::CommandExt;
+            cmd.creation_flags(0x08000000);
+        }
+        let output = cmd.output().map_err(|e| e.to_string())?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+        }
+    }
+}
+
+/// What identifies a running process beyond its PID: its name and start
+/// time, or None when no such process runs
+fn process_signature(pid: u32) -> Option<String> {
+    #[cfg(target_os = "linux")]
+    {
+        let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+        // `pid (comm) state ... starttime`: comm may hold spaces and parentheses
+        let (head, rest) = stat.rsplit_once(')')?;
+        let comm = head.split_once('(')?.1;
+        let fields: Vec<&str> = rest.split_whitespace().collect();
+        // Exited, waiting to be reaped
+        if fields.first() == Some(&"Z") {
+            return None;
+        }
+        let start_time = fields.get(19)?;
+        Some(format!("{}@{}", comm, start_time))
+    }
+    #[cfg(all(unix, not(target_os = "linux")))]
+    {
+        let output = std::process::Command::new("ps")
+            .args(["-o", "stat=,lstart=,comm=", "-p", &pid.to_string()])
+            .output()
+            .ok()?;
+        let line = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        let (state, signature) = line.split_once(char::is_whitespace)?;
+        (!state.starts_with('Z')).then(|| signature.trim().to_string())
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        let output = std::process::Command::new("tasklist")
+            .args(["/FI", &format!("PID eq {}", pid), "/FO", "CSV", "/NH"])
+            .creation_flags(0x08000000)
+            .output()
+            .ok()?;
+        let line = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        // `"name.exe","1234",...`, or an INFO line when no process matches
+        let name = line.strip_prefix('"')?.split('"').next()?;
+        Some(name.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signature_of_this_process() {
+        let signature = process_signature(std::process::id());
+        assert!(signature.is_some());
+        assert_eq!(signature, process_signature(std::process::id()));
+        assert_eq!(process_signature(u32::MAX / 2), None);
+    }
+
+    #[test]
+    fn test_runtime_file_follows_children() {
+        let dir = tempfile::tempdir().unwrap();
+        let tracker = ProcessTracker::new(dir.path());
+        let file = dir.path().join(format!("{}.json", std::process::id()));
+
+        // This process stands in for a child: it's running
+        let guard = tracker.track(std::process::id(), ProcessKind::Mcp, "github", false);
+        assert!(file.exists());
+        assert_eq!(tracker.children().len(), 1);
+        assert_eq!(running_instances(dir.path()), vec![(std::process::id(), tracker.children())]);
+        // Its owner runs, so it's no orphan
+        assert!(scan_orphans(dir.path()).is_empty());
+
+        drop(guard);
+        assert!(!file.exists());
+        assert!(tracker.children().is_empty());
+    }
+
+    #[test]
+    fn test_dead_owner_leaves_orphans() {
+        let dir = tempfile::tempdir().unwrap();
+        let orphan = TrackedProcess {
+            pid: std::process::id(),
+            kind: ProcessKind::Lsp,
+            label: "rust-analyzer".to_string(),
+            started_at: Utc::now(),
+            signature: process_signature(std::process::id()),
+            group: false,
+        };
+        let exited = TrackedProcess {
+            pid: u32::MAX / 2,
+            signature: Some("gone@1".to_string()),
+            ..orphan.clone()
+        };
+        let write = |name: &str, children: Vec<TrackedProcess>| {
+            let file = RuntimeFile {
+                owner: u32::MAX / 2,
+                owner_signature: Some("cowork@1".to_string()),
+                children,
+            };
+            std::fs::write(dir.path().join(name), serde_json::to_vec(&file).unwrap()).unwrap();
+        };
+        write("1.json", vec![orphan.clone(), exited.clone()]);
+        write("2.json", vec![exited]);
+
+        let scan = scan_orphans(dir.path());
+        assert_eq!(scan.orphans, vec![orphan]);
+        // Nothing left running: forgotten right away
+        assert!(!dir.path().join("2.json").exists());
+        assert!(dir.path().join("1.json").exists());
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_tracked_child_joins_the_job() {
+        use std::os::windows::io::AsRawHandle;
+        use windows_sys::Win32::System::JobObjects::IsProcessInJob;
+
+        let dir = tempfile::tempdir().unwrap();
+        let tracker = ProcessTracker::new(dir.path());
+        let mut child = std::process::Command::new("ping")
+            .args(["-n", "30", "127.0.0.1"])
+            .stdout(std::process::Stdio::null())
+            .spawn()
+            .unwrap();
+        let guard = tracker.track(child.id(), ProcessKind::Shell, "ping", false);
+
+        let job = job::handle().unwrap();
+        let mut in_job = 0;
+        let checked = unsafe { IsProcessInJob(child.as_raw_handle(), job as _, &mut in_job) };
+
+        drop(guard);
+        child.kill().unwrap();
+        child.wait().unwrap();
+        assert_ne!(checked, 0, "{}", std::io::Error::last_os_error());
+        assert_ne!(in_job, 0);
+    }
+}
@@ -28,8 +28,10 @@ use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
 
+use crate::prompt::builder::PromptBuilder;
 use crate::prompt::parser::{parse_frontmatter, parse_tool_list, ParseError, ParsedDocument};
 use crate::prompt::types::{ModelPreference, Scope, ToolRestrictions, ToolSpec};
+use crate::prompt::TemplateVars;
 
 /// Context mode for agents - how they receive conversation context
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
@@ -248,6 +250,18 @@ impl AgentDefinition {
     pub fn is_tool_allowed(&self, tool_name: &str, args: &serde_json::Value) -> bool {
         self.tool_restrictions().is_allowed(tool_name, args)
     }
+
+    /// The system prompt the agent runs with, after template substitution
+    ///
+    /// Depends only on the definition and `vars`, so the same inputs always
+    /// give the same prompt.
+    pub fn assembled_prompt(&self, vars: &TemplateVars) -> String {
+        PromptBuilder::empty()
+            .with_agent(self.clone())
+            .with_environment(vars)
+            .build()
+            .system_prompt
+    }
 }
 
 /// Error type for agent parsing and loading
@@ -259,6 +273,9 @@ pub enum AgentError {
     #[error("Missing required field: {0}")]
     MissingField(String),
 
+    #[error("Invalid field '{field}': {reason}")]
+    InvalidField { field: String, reason: String },
+
     #[error("Failed to read agent file: {0}")]
     IoError(#[from] std::io::Error),
 
@@ -296,17 +313,33 @@ fn parse_agent_from_document(
         .get_string("name")
         .ok_or_else(|| AgentError::MissingField("name".to_string()))?
         .to_string();
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(invalid_field(
+            "name",
+            format!("'{}' must be letters, digits, '-' or '_'", name),
+        ));
+    }
 
-    let description = doc
-        .get_string("description")
-        .unwrap_or("No description")
-        .to_string();
+    let description = match doc.metadata.get("description") {
+        None => "No description".to_string(),
+        Some(value) => value
+            .as_str()
+            .ok_or_else(|| invalid_field("description", "must be a string"))?
+            .to_string(),
+    };
 
     // Extract optional fields
-    let model = doc
-        .get_string("model")
-        .map(ModelPreference::parse)
-        .unwrap_or_default();
+    let model = match doc.metadata.get("model") {
+        None => ModelPreference::default(),
+        Some(value) => value
+            .as_str()
+            .map(ModelPreference::parse)
+            .ok_or_else(|| invalid_field("model", "must be a string"))?,
+    };
 
     let color = doc
         .get_string("color")
@@ -324,7 +357,17 @@ fn parse_agent_from_document(
         .map(ContextMode::parse)
         .unwrap_or_default();
 
-    let max_turns = doc.get_i64("max_turns").map(|v| v as usize);
+    let max_turns = match doc.metadata.get("max_turns") {
+        None => None,
+        Some(value) => match value.as_i64() {
+            Some(turns) if turns > 0 => Some(turns as usize),
+            _ => return Err(invalid_field("max_turns", "must be a positive integer")),
+        },
+    };
+
+    if doc.content.trim().is_empty() {
+        return Err(AgentError::MissingField("system prompt (body after frontmatter)".to_string()));
+    }
 
     let metadata = AgentMetadata {
         name,
@@ -344,6 +387,13 @@ fn parse_agent_from_document(
     })
 }
 
+fn invalid_field(field: &str, reason: impl Into<String>) -> AgentError {
+    AgentError::InvalidField {
+        field: field.to_string(),
+        reason: reason.into(),
+    }
+}
+
 /// Load an agent from a file path
 pub fn load_agent_from_file(path: &Path, scope: Scope) -> Result<AgentDefinition, AgentError> {
     let content = std::fs::read_to_string(path)?;
@@ -558,6 +608,29 @@ Content
             assert!(matches!(result, Err(AgentError::MissingField(_))));
         }
 
+        #[test]
+        fn test_parse_invalid_fields() {
+            let invalid = [
+                ("name: \"my agent\"", "name"),
+                ("name: Ok\nmax_turns: zero", "max_turns"),
+                ("name: Ok\nmax_turns: 0", "max_turns"),
+                ("name: Ok\nmodel: [haiku]", "model"),
+            ];
+            for (frontmatter, field) in invalid {
+                let content = format!("---\n{}\n---\n\nPrompt\n", frontmatter);
+                match parse_agent(&content, None, Scope::Project) {
+                    Err(AgentError::InvalidField { field: f, .. }) => assert_eq!(f, field),
+                    other => panic!("expected invalid {} for {:?}, got {:?}", field, frontmatter, other),
+                }
+            }
+        }
+
+        #[test]
+        fn test_parse_empty_prompt() {
+            let result = parse_agent("---\nname: Empty\n---\n\n", None, Scope::Project);
+            assert!(matches!(result, Err(AgentError::MissingField(_))));
+        }
+
         #[test]
         fn test_parse_explore_agent() {
             let agent = parse_agent(builtin::agents::EXPLORE, None, Scope::Builtin).unwrap();
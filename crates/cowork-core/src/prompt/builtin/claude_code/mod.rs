@@ -43,6 +43,13 @@
 //! - `${MODEL_INFO}` - Model name and ID
 //! - `${ASSISTANT_NAME}` - Assistant name
 //! - `${SECURITY_POLICY}` - Security policy content
+//! - `${DATE_TIME}` - Local date and time with time zone
+//! - `${LOCALE}` - User's locale
+//! - `${USER_NAME}`, `${HOSTNAME}`, `${SHELL}` - User, machine and shell
+//! - `${WORKSPACE_NAME}` - Last component of the working directory
+//! - `${PRIMARY_LANGUAGE}`, `${TEST_COMMAND}` - Detected from the project's build files
+//! - `${SCRATCH_DIR}` - The session's scratch directory (filled in when the session starts)
+//! - `${CUSTOM:key}` - Variable `key` from `[prompt] variables`
 
 /// Main system prompt (pre-expanded from Claude Code)
 pub const SYSTEM_PROMPT: &str = include_str!("system/main.md");
@@ -52,47 +59,104 @@ pub mod tools {
     /// Bash tool - execute shell commands
     pub const BASH: &str = include_str!("tools/bash.md");
 
+    /// Bash tool, compact form
+    pub const BASH_COMPACT: &str = "Runs a shell command in the working directory and returns its output. Quote paths with spaces, prefer the dedicated tools (Read, Grep, Glob, Edit) over cat, grep, find or sed, and set run_in_background for long-running commands. Ask before committing, pushing or running anything destructive.";
+
     /// Read tool - read file contents
     pub const READ: &str = include_str!("tools/read.md");
 
+    /// Read tool, compact form
+    pub const READ_COMPACT: &str = "Reads a file from the workspace, given an absolute path, with line numbers. Use offset and limit for part of a large file. Also reads images, PDFs and notebooks.";
+
     /// Write tool - write files
     pub const WRITE: &str = include_str!("tools/write.md");
 
+    /// Write tool, compact form
+    pub const WRITE_COMPACT: &str = "Writes a file, replacing any existing content; Read an existing file first. Prefer Edit for changes to existing files, and don't create documentation files unless asked.";
+
     /// Edit tool - edit files with replacements
     pub const EDIT: &str = include_str!("tools/edit.md");
 
+    /// Edit tool, compact form
+    pub const EDIT_COMPACT: &str = "Replaces old_string with new_string in a file you have Read. old_string must match exactly, indentation included, and be unique unless replace_all is set.";
+
     /// Glob tool - find files by pattern
     pub const GLOB: &str = include_str!("tools/glob.md");
 
+    /// Glob tool, compact form
+    pub const GLOB_COMPACT: &str = "Finds files whose path matches a glob pattern such as \"src/**/*.rs\", newest first.";
+
     /// Grep tool - search file contents
     pub const GREP: &str = include_str!("tools/grep.md");
 
+    /// Grep tool, compact form
+    pub const GREP_COMPACT: &str = "Searches file contents with a regular expression (ripgrep syntax). Filter with glob or type; output_mode picks matching lines, file paths or counts.";
+
+    /// CodebaseSearch tool - look up symbols and files in the workspace index
+    pub const CODEBASE_SEARCH: &str = include_str!("tools/codebasesearch.md");
+
+    /// CodebaseSearch tool, compact form
+    pub const CODEBASE_SEARCH_COMPACT: &str = "Looks up symbols (functions, types, modules) and files by name in the workspace index; faster than Grep for finding where something is defined.";
+
+    /// SearchFiles tool - find files by name, path and content at once
+    pub const SEARCH_FILES: &str = include_str!("tools/searchfiles.md");
+
+    /// SearchFiles tool, compact form
+    pub const SEARCH_FILES_COMPACT: &str = "Finds files by name, path and content in one call, ranked by relevance.";
+
     /// Task tool - launch subagents
     pub const TASK: &str = include_str!("tools/task.md");
 
+    /// Task tool, compact form
+    pub const TASK_COMPACT: &str = "Launches a subagent for a self-contained task, such as exploring the codebase or planning, and returns its final report. Give it a complete prompt: it doesn't see this conversation.";
+
     /// TodoWrite tool - task management
     pub const TODOWRITE: &str = include_str!("tools/todowrite.md");
 
+    /// TodoWrite tool, compact form
+    pub const TODOWRITE_COMPACT: &str = "Replaces the session's todo list. Use it to plan and track multi-step work: keep exactly one item in_progress and mark items completed as soon as they're done.";
+
     /// AskUserQuestion tool - ask user questions
     pub const ASK_USER_QUESTION: &str = include_str!("tools/askuserquestion.md");
 
+    /// AskUserQuestion tool, compact form
+    pub const ASK_USER_QUESTION_COMPACT: &str = "Asks the user one or more multiple-choice questions and returns their answers. Use it when a decision is theirs to make.";
+
     /// WebFetch tool - fetch web content
     pub const WEBFETCH: &str = include_str!("tools/webfetch.md");
 
+    /// WebFetch tool, compact form
+    pub const WEBFETCH_COMPACT: &str = "Fetches a URL, converts the page to markdown and answers the prompt about it.";
+
     /// WebSearch tool - search the web
     pub const WEBSEARCH: &str = include_str!("tools/websearch.md");
 
+    /// WebSearch tool, compact form
+    pub const WEBSEARCH_COMPACT: &str = "Searches the web and returns results with titles and links. Cite the sources you use.";
+
     /// EnterPlanMode tool - enter planning mode
     pub const ENTER_PLAN_MODE: &str = include_str!("tools/enterplanmode.md");
 
+    /// EnterPlanMode tool, compact form
+    pub const ENTER_PLAN_MODE_COMPACT: &str = "Switches to plan mode, where you explore and design an approach without changing files, for tasks that need planning before implementation.";
+
     /// ExitPlanMode tool - exit planning mode
     pub const EXIT_PLAN_MODE: &str = include_str!("tools/exitplanmode.md");
 
+    /// ExitPlanMode tool, compact form
+    pub const EXIT_PLAN_MODE_COMPACT: &str = "Presents the finished plan to the user for approval and leaves plan mode. Only for planning implementation work.";
+
     /// LSP tool - code intelligence via Language Server Protocol
     pub const LSP: &str = include_str!("tools/lsp.md");
 
+    /// LSP tool, compact form
+    pub const LSP_COMPACT: &str = "Code intelligence from a language server: go to definition, find references, hover, and document or workspace symbols at a file position.";
+
     /// Skill tool - execute skills/slash commands
     pub const SKILL: &str = include_str!("tools/skill.md");
+
+    /// Skill tool, compact form
+    pub const SKILL_COMPACT: &str = "Runs a skill or slash command by name, with optional arguments. Only use skills listed as available.";
 }
 
 /// Agent definitions
@@ -120,6 +184,9 @@ pub mod reminders {
 
     /// Git commit guidelines
     pub const GIT_COMMIT: &str = include_str!("reminders/git_commit.md");
+
+    /// The session's scratch directory (`${SCRATCH_DIR}` is its path)
+    pub const SCRATCH_DIR: &str = include_str!("reminders/scratch_dir.md");
 }
 
 #[cfg(test)]
@@ -141,6 +208,15 @@ mod tests {
         "${ASSISTANT_NAME}",
         "${SECURITY_POLICY}",
         "${SKILLS_XML}",
+        "${DATE_TIME}",
+        "${LOCALE}",
+        "${USER_NAME}",
+        "${HOSTNAME}",
+        "${SHELL}",
+        "${WORKSPACE_NAME}",
+        "${PRIMARY_LANGUAGE}",
+        "${TEST_COMMAND}",
+        "${SCRATCH_DIR}",
     ];
 
     /// Check if a variable pattern is a runtime variable
@@ -183,6 +259,8 @@ mod tests {
         assert!(!tools::EDIT.is_empty());
         assert!(!tools::GLOB.is_empty());
         assert!(!tools::GREP.is_empty());
+        assert!(!tools::CODEBASE_SEARCH.is_empty());
+        assert!(!tools::SEARCH_FILES.is_empty());
         assert!(!tools::TASK.is_empty());
         assert!(!tools::TODOWRITE.is_empty());
         assert!(!tools::ASK_USER_QUESTION.is_empty());
@@ -194,6 +272,33 @@ mod tests {
         assert!(!tools::SKILL.is_empty());
     }
 
+    #[test]
+    fn test_compact_tool_descriptions_are_shorter() {
+        let pairs = [
+            (tools::BASH, tools::BASH_COMPACT),
+            (tools::READ, tools::READ_COMPACT),
+            (tools::WRITE, tools::WRITE_COMPACT),
+            (tools::EDIT, tools::EDIT_COMPACT),
+            (tools::GLOB, tools::GLOB_COMPACT),
+            (tools::GREP, tools::GREP_COMPACT),
+            (tools::CODEBASE_SEARCH, tools::CODEBASE_SEARCH_COMPACT),
+            (tools::SEARCH_FILES, tools::SEARCH_FILES_COMPACT),
+            (tools::TASK, tools::TASK_COMPACT),
+            (tools::TODOWRITE, tools::TODOWRITE_COMPACT),
+            (tools::ASK_USER_QUESTION, tools::ASK_USER_QUESTION_COMPACT),
+            (tools::WEBFETCH, tools::WEBFETCH_COMPACT),
+            (tools::WEBSEARCH, tools::WEBSEARCH_COMPACT),
+            (tools::ENTER_PLAN_MODE, tools::ENTER_PLAN_MODE_COMPACT),
+            (tools::EXIT_PLAN_MODE, tools::EXIT_PLAN_MODE_COMPACT),
+            (tools::LSP, tools::LSP_COMPACT),
+            (tools::SKILL, tools::SKILL_COMPACT),
+        ];
+        for (full, compact) in pairs {
+            assert!(!compact.is_empty());
+            assert!(compact.len() * 2 < full.len(), "not compact: {}", compact);
+        }
+    }
+
     #[test]
     fn test_agents_load() {
         assert!(!agents::EXPLORE.is_empty());
@@ -213,6 +318,7 @@ mod tests {
         assert!(!reminders::SECURITY_POLICY.is_empty());
         assert!(!reminders::PLAN_MODE_ACTIVE.is_empty());
         assert!(!reminders::GIT_COMMIT.is_empty());
+        assert!(reminders::SCRATCH_DIR.contains("${SCRATCH_DIR}"));
     }
 
     #[test]
@@ -225,6 +331,8 @@ mod tests {
             ("tools::EDIT", tools::EDIT),
             ("tools::GLOB", tools::GLOB),
             ("tools::GREP", tools::GREP),
+            ("tools::CODEBASE_SEARCH", tools::CODEBASE_SEARCH),
+            ("tools::SEARCH_FILES", tools::SEARCH_FILES),
             ("tools::TASK", tools::TASK),
             ("tools::TODOWRITE", tools::TODOWRITE),
             ("tools::ASK_USER_QUESTION", tools::ASK_USER_QUESTION),
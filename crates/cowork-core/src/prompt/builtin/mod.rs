@@ -60,6 +60,50 @@ pub fn strip_markdown_header(content: &str) -> &str {
     content
 }
 
+/// Opening marker of an optional system prompt section (`<!-- section: id -->`)
+const SECTION_START: &str = "<!-- section:";
+/// Closing marker of an optional system prompt section
+const SECTION_END: &str = "<!-- /section -->";
+
+/// Ids of the optional sections marked in a prompt
+pub fn section_ids(prompt: &str) -> Vec<&str> {
+    prompt.lines().filter_map(section_start).collect()
+}
+
+/// Remove section markers, dropping the sections whose id is in `disabled`
+///
+/// Tool guidance in the system prompt is wrapped in `<!-- section: id -->`
+/// ... `<!-- /section -->` so it can be stripped when the tool isn't
+/// registered. The markers themselves never reach the model.
+pub fn render_sections(prompt: &str, disabled: &[String]) -> String {
+    let mut out = String::with_capacity(prompt.len());
+    let mut skipping = false;
+    // Drop the blank line that separated a stripped section from the next one
+    let mut skip_blank = false;
+    for line in prompt.split_inclusive('\n') {
+        if let Some(id) = section_start(line) {
+            skipping = disabled.iter().any(|d| d == id);
+            continue;
+        }
+        if line.trim() == SECTION_END {
+            skip_blank = skipping;
+            skipping = false;
+            continue;
+        }
+        if skipping || (skip_blank && line.trim().is_empty()) {
+            continue;
+        }
+        skip_blank = false;
+        out.push_str(line);
+    }
+    out
+}
+
+fn section_start(line: &str) -> Option<&str> {
+    let id = line.trim().strip_prefix(SECTION_START)?.strip_suffix("-->")?;
+    Some(id.trim())
+}
+
 /// Agent definitions
 pub mod agents {
     /// Explore agent - fast codebase searching
@@ -110,6 +154,15 @@ pub mod reminders {
     pub const SECURITY_POLICY: &str = include_str!("reminders/security_policy.md");
     /// Conversation summarization instructions
     pub const CONVERSATION_SUMMARIZATION: &str = include_str!("reminders/conversation_summarization.md");
+    /// Extra summarization instructions when the provider rejected the history as too long
+    /// (`{max_words}` is the summary's length)
+    pub const EMERGENCY_COMPACTION: &str = include_str!("reminders/emergency_compaction.md");
+    /// Session title instructions (for the fast model naming a session)
+    pub const SESSION_TITLE: &str = include_str!("reminders/session_title.md");
+    /// Tool call explanation instructions (for the fast model explaining a pending call)
+    pub const TOOL_EXPLANATION: &str = include_str!("reminders/tool_explanation.md");
+    /// End-of-turn self-review instructions (for the fast model checking a turn's changes)
+    pub const SELF_REVIEW: &str = include_str!("reminders/self_review.md");
 }
 
 /// Built-in commands (slash commands) — official Claude Code plugin commands
@@ -177,6 +230,19 @@ mod tests {
             &stripped[..stripped.len().min(50)]);
     }
 
+    #[test]
+    fn test_render_sections() {
+        let prompt = "Intro\n\n<!-- section: web -->\n## Web\n\nUse WebFetch.\n<!-- /section -->\n\n## Next\n";
+        assert_eq!(section_ids(prompt), vec!["web"]);
+        assert_eq!(render_sections(prompt, &[]), "Intro\n\n## Web\n\nUse WebFetch.\n\n## Next\n");
+        assert_eq!(render_sections(prompt, &["web".to_string()]), "Intro\n\n## Next\n");
+
+        // The built-in prompt marks its tool guidance
+        let ids = section_ids(SYSTEM_PROMPT);
+        assert!(ids.contains(&"notebook") && ids.contains(&"web"));
+        assert!(!render_sections(SYSTEM_PROMPT, &[]).contains("<!--"));
+    }
+
     #[test]
     fn test_agents_load() {
         assert!(!agents::EXPLORE.is_empty());
@@ -202,6 +268,10 @@ mod tests {
         assert!(!reminders::PLAN_MODE_ACTIVE.is_empty());
         assert!(!reminders::SECURITY_POLICY.is_empty());
         assert!(!reminders::CONVERSATION_SUMMARIZATION.is_empty());
+        assert!(reminders::EMERGENCY_COMPACTION.contains("{max_words}"));
+        assert!(!reminders::SESSION_TITLE.is_empty());
+        assert!(!reminders::TOOL_EXPLANATION.is_empty());
+        assert!(reminders::SELF_REVIEW.contains("NONE"));
     }
 
     #[test]
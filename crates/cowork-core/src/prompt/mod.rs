@@ -54,11 +54,14 @@ pub mod commands;
 pub mod hook_executor;
 pub mod hooks;
 pub mod parser;
+pub mod pipeline;
 pub mod plugins;
 pub mod registry;
 pub mod substitution;
 pub mod types;
 
+use std::collections::HashMap;
+
 // Re-export commonly used types
 pub use parser::{parse_frontmatter, parse_tool_list, ParsedDocument, ParseError};
 pub use substitution::{substitute_commands, extract_commands, has_substitutions};
@@ -88,23 +91,36 @@ pub use builder::{AssembledPrompt, AssemblyMetadata, PromptBuilder, SkillDefinit
 
 // Re-export registry types
 pub use registry::{
-    AgentInfo, CommandInfo, ComponentPaths, ComponentRegistry, LoadResult, PluginInfo,
+    AgentInfo, CommandInfo, ComponentKind, ComponentLoadError, ComponentPaths, ComponentRegistry, LoadResult, PluginInfo,
     RegistryCounts, RegistryError, RegistrySummary, SkillInfo,
 };
 
+// Re-export input preprocessing types
+pub use pipeline::{InputProcessor, LanguageDetector, PiiScrubber, ProcessedInput, PromptPipeline};
+
 // Re-export plugin types
 pub use plugins::{
     DiscoverResult, Plugin, PluginError, PluginManifest, PluginRegistry,
 };
 
+/// The session's scratch directory, only known once the session starts:
+/// `TemplateVars::substitute` leaves it for the agent loop to fill in
+pub const SCRATCH_DIR_VAR: &str = "${SCRATCH_DIR}";
+
+/// Variables filled in after `TemplateVars::substitute`, so not reported as unresolved
+const DEFERRED_VARS: &[&str] = &["SCRATCH_DIR"];
+
 /// Template variables that can be substituted in prompts
 ///
 /// These variables are substituted at runtime in prompt templates using
-/// `${VARIABLE_NAME}` syntax.
+/// `${VARIABLE_NAME}` syntax; entries of `custom` are `${CUSTOM:key}`.
 #[derive(Debug, Clone)]
 pub struct TemplateVars {
     /// Current working directory
     pub working_directory: String,
+    /// Other workspace roots, listed with the working directory in
+    /// `${WORKING_DIRECTORY}` (see `tools::filesystem::WorkspaceRoots`)
+    pub additional_roots: Vec<std::path::PathBuf>,
     /// Whether the directory is a git repo
     pub is_git_repo: bool,
     /// Platform name (linux, macos, windows)
@@ -131,12 +147,33 @@ pub struct TemplateVars {
     pub recent_commits: String,
     /// Available skills listing for the Skill tool
     pub skills_xml: String,
+    /// Login name of the user running cowork
+    pub user_name: String,
+    /// Host name of the machine
+    pub hostname: String,
+    /// The user's shell (`$SHELL`, or `%ComSpec%` on Windows)
+    pub shell: String,
+    /// Last component of the working directory
+    pub workspace_name: String,
+    /// Local date and time with UTC offset and, when known, the time zone
+    /// name (e.g. "2025-01-31 14:05 +01:00 (Europe/Berlin)")
+    pub date_time: String,
+    /// The user's locale (e.g. "en_US"), from `LC_ALL`/`LC_MESSAGES`/`LANG`
+    pub locale: String,
+    /// Main language of the project, if detected
+    pub primary_language: String,
+    /// Likely command to run the project's tests, if detected
+    pub test_command: String,
+    /// Variables defined outside the code (e.g. `[prompt] variables`),
+    /// substituted as `${CUSTOM:key}`
+    pub custom: HashMap<String, String>,
 }
 
 impl Default for TemplateVars {
     fn default() -> Self {
         Self {
             working_directory: String::new(),
+            additional_roots: Vec::new(),
             is_git_repo: false,
             platform: std::env::consts::OS.to_string(),
             os_version: get_os_version(),
@@ -150,10 +187,66 @@ impl Default for TemplateVars {
             main_branch: "main".to_string(),
             recent_commits: String::new(),
             skills_xml: String::new(),
+            user_name: env_var(&["USER", "USERNAME", "LOGNAME"]).unwrap_or_default(),
+            hostname: get_hostname(),
+            shell: env_var(&["SHELL", "ComSpec"]).unwrap_or_default(),
+            workspace_name: String::new(),
+            date_time: get_date_time(),
+            locale: get_locale(),
+            primary_language: String::new(),
+            test_command: String::new(),
+            custom: HashMap::new(),
         }
     }
 }
 
+/// The first of the environment variables that is set and not empty
+fn env_var(names: &[&str]) -> Option<String> {
+    names
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.trim().is_empty())
+}
+
+/// The machine's host name, without spawning a process where possible
+fn get_hostname() -> String {
+    env_var(&["HOSTNAME", "COMPUTERNAME"])
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .or_else(|| {
+            std::process::Command::new("hostname")
+                .output()
+                .ok()
+                .and_then(|o| String::from_utf8(o.stdout).ok())
+        })
+        .map(|name| name.trim().to_string())
+        .unwrap_or_default()
+}
+
+/// Local date and time with offset, plus the zone name from `TZ` or the
+/// `/etc/localtime` link when there is one
+fn get_date_time() -> String {
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M %:z").to_string();
+    let zone = env_var(&["TZ"])
+        .map(|tz| tz.trim_start_matches(':').to_string())
+        .or_else(|| {
+            let target = std::fs::read_link("/etc/localtime").ok()?;
+            let target = target.to_string_lossy();
+            target.split_once("zoneinfo/").map(|(_, zone)| zone.to_string())
+        });
+    match zone {
+        Some(zone) => format!("{} ({})", now, zone),
+        None => now,
+    }
+}
+
+/// The locale name without its encoding (`en_US.UTF-8` -> `en_US`)
+fn get_locale() -> String {
+    env_var(&["LC_ALL", "LC_MESSAGES", "LANG"])
+        .map(|locale| locale.split(['.', '@']).next().unwrap_or_default().to_string())
+        .filter(|locale| locale != "C" && locale != "POSIX")
+        .unwrap_or_default()
+}
+
 /// Get the OS version string (cross-platform)
 fn get_os_version() -> String {
     #[cfg(target_os = "linux")]
@@ -198,13 +291,43 @@ fn get_os_version() -> String {
 }
 
 impl TemplateVars {
+    /// Variables for a local workspace, with everything else at its default
+    pub fn for_workspace(workspace: &std::path::Path) -> Self {
+        Self {
+            working_directory: workspace.display().to_string(),
+            is_git_repo: workspace.join(".git").exists(),
+            workspace_name: workspace
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+
+    /// Variables for a local workspace, plus what a look at its top-level
+    /// build files tells about the project (language, test command)
+    pub fn gather(workspace: &std::path::Path) -> Self {
+        let gatherer = crate::context::ContextGatherer::new(workspace);
+        let (_, language) = gatherer.detect_project_type();
+        Self {
+            primary_language: language.filter(|l| l != "Unknown").unwrap_or_default(),
+            test_command: gatherer.test_command().unwrap_or_default(),
+            ..Self::for_workspace(workspace)
+        }
+    }
+
     /// Substitute template variables in a string
     ///
-    /// Replaces all `${VARIABLE_NAME}` patterns with their corresponding values.
-    /// If `skills_xml` is empty and `working_directory` is set, auto-populates
-    /// the available skills listing from the SkillRegistry.
+    /// Replaces all `${VARIABLE_NAME}` patterns with their corresponding values
+    /// in one pass, so a value containing `${...}` is left as it is. Patterns
+    /// that name no variable are kept and logged, since they are usually a
+    /// typo in a user's prompt or skill. If `skills_xml` is empty and
+    /// `working_directory` is set, auto-populates the available skills
+    /// listing from the SkillRegistry.
     pub fn substitute(&self, template: &str) -> String {
-        let skills_xml = if self.skills_xml.is_empty() && !self.working_directory.is_empty() {
+        let skills_xml = if !template.contains("${SKILLS_XML}") {
+            String::new()
+        } else if self.skills_xml.is_empty() && !self.working_directory.is_empty() {
             let registry = crate::skills::SkillRegistry::with_builtins(
                 std::path::PathBuf::from(&self.working_directory),
             );
@@ -217,23 +340,95 @@ impl TemplateVars {
         } else {
             self.skills_xml.clone()
         };
+        let working_directory = if self.additional_roots.is_empty() {
+            self.working_directory.clone()
+        } else {
+            crate::tools::filesystem::WorkspaceRoots::new(self.working_directory.clone().into())
+                .with_additional(self.additional_roots.iter().cloned())
+                .describe()
+        };
+
+        let mut out = String::with_capacity(template.len());
+        let mut unresolved: Vec<&str> = Vec::new();
+        let mut rest = template;
+        while let Some(start) = rest.find("${") {
+            out.push_str(&rest[..start]);
+            let after = &rest[start + 2..];
+            let name = after.find('}').map(|end| &after[..end]).filter(|name| is_variable_name(name));
+            let Some(name) = name else {
+                out.push_str("${");
+                rest = after;
+                continue;
+            };
+            match self.value(name, &skills_xml, &working_directory) {
+                Some(value) => out.push_str(value),
+                None => {
+                    out.push_str(&rest[start..start + name.len() + 3]);
+                    if !unresolved.contains(&name) && !DEFERRED_VARS.contains(&name) {
+                        unresolved.push(name);
+                    }
+                }
+            }
+            rest = &after[name.len() + 1..];
+        }
+        out.push_str(rest);
 
-        template
-            .replace("${WORKING_DIRECTORY}", &self.working_directory)
-            .replace("${IS_GIT_REPO}", if self.is_git_repo { "Yes" } else { "No" })
-            .replace("${PLATFORM}", &self.platform)
-            .replace("${OS_VERSION}", &self.os_version)
-            .replace("${CURRENT_DATE}", &self.current_date)
-            .replace("${CURRENT_YEAR}", &self.current_year)
-            .replace("${MODEL_INFO}", &self.model_info)
-            .replace("${GIT_STATUS}", &self.git_status)
-            .replace("${ASSISTANT_NAME}", &self.assistant_name)
-            .replace("${SECURITY_POLICY}", &self.security_policy)
-            .replace("${CURRENT_BRANCH}", &self.current_branch)
-            .replace("${MAIN_BRANCH}", &self.main_branch)
-            .replace("${RECENT_COMMITS}", &self.recent_commits)
-            .replace("${SKILLS_XML}", &skills_xml)
+        if !unresolved.is_empty() {
+            tracing::warn!(
+                "Unresolved template variables left in prompt: ${{{}}}",
+                unresolved.join("}, ${")
+            );
+        }
+        out
     }
+
+    /// The value of the variable `name` (without `${}`), if there is one
+    fn value<'a>(&'a self, name: &str, skills_xml: &'a str, working_directory: &'a str) -> Option<&'a str> {
+        if let Some(key) = name.strip_prefix("CUSTOM:") {
+            return self.custom.get(key).map(String::as_str);
+        }
+        Some(match name {
+            "WORKING_DIRECTORY" => working_directory,
+            "IS_GIT_REPO" => if self.is_git_repo { "Yes" } else { "No" },
+            "PLATFORM" => &self.platform,
+            "OS_VERSION" => &self.os_version,
+            "CURRENT_DATE" => &self.current_date,
+            "CURRENT_YEAR" => &self.current_year,
+            "DATE_TIME" => &self.date_time,
+            "LOCALE" => &self.locale,
+            "MODEL_INFO" => &self.model_info,
+            "GIT_STATUS" => &self.git_status,
+            "ASSISTANT_NAME" => &self.assistant_name,
+            "SECURITY_POLICY" => &self.security_policy,
+            "CURRENT_BRANCH" => &self.current_branch,
+            "MAIN_BRANCH" => &self.main_branch,
+            "RECENT_COMMITS" => &self.recent_commits,
+            "SKILLS_XML" => skills_xml,
+            "USER_NAME" => &self.user_name,
+            "HOSTNAME" => &self.hostname,
+            "SHELL" => &self.shell,
+            "WORKSPACE_NAME" => &self.workspace_name,
+            "PRIMARY_LANGUAGE" => &self.primary_language,
+            "TEST_COMMAND" => &self.test_command,
+            _ => return None,
+        })
+    }
+}
+
+/// Whether `${name}` looks like a template variable rather than other text
+/// that happens to contain `${` (shell parameter expansions like `${x:-y}`,
+/// code)
+fn is_variable_name(name: &str) -> bool {
+    let (name, key) = match name.split_once(':') {
+        Some(("CUSTOM", key)) => ("CUSTOM", Some(key)),
+        Some(_) => return false,
+        None => (name, None),
+    };
+    let valid_name = !name.is_empty() && name.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_');
+    let valid_key = key.is_none_or(|key| {
+        !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+    });
+    valid_name && valid_key
 }
 
 #[cfg(test)]
@@ -254,4 +449,49 @@ mod tests {
         assert!(result.contains("/home/user/project"));
         assert!(result.contains("Yes"));
     }
+
+    #[test]
+    fn test_working_directory_lists_roots() {
+        let vars = TemplateVars {
+            working_directory: "/work/backend".to_string(),
+            additional_roots: vec!["/work/frontend".into()],
+            ..Default::default()
+        };
+        let result = vars.substitute("Working directory: ${WORKING_DIRECTORY}");
+        assert!(result.starts_with("Working directory: /work/backend (root \"backend\""), "{}", result);
+        assert!(result.ends_with("- \"frontend\": /work/frontend"), "{}", result);
+    }
+
+    #[test]
+    fn test_substitution_is_single_pass() {
+        let vars = TemplateVars {
+            git_status: "M notes about ${PLATFORM}".to_string(),
+            platform: "linux".to_string(),
+            custom: HashMap::from([("team".to_string(), "Core".to_string())]),
+            ..Default::default()
+        };
+
+        let result = vars.substitute("${GIT_STATUS} on ${PLATFORM} for ${CUSTOM:team}");
+        assert_eq!(result, "M notes about ${PLATFORM} on linux for Core");
+        // Unknown variables and other `${` text are kept
+        assert_eq!(
+            vars.substitute("${CUSTOM:missing} ${TYPO_VAR} ${x:-default} ${"),
+            "${CUSTOM:missing} ${TYPO_VAR} ${x:-default} ${"
+        );
+        // The scratch directory is filled in later, by the agent loop
+        assert_eq!(vars.substitute(SCRATCH_DIR_VAR), SCRATCH_DIR_VAR);
+    }
+
+    #[test]
+    fn test_gather_detects_project() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let workspace = dir.path().join("my-crate");
+        std::fs::create_dir(&workspace).unwrap();
+        std::fs::write(workspace.join("Cargo.toml"), "[package]\nname = \"my-crate\"\n").unwrap();
+
+        let vars = TemplateVars::gather(&workspace);
+        let result = vars.substitute("${WORKSPACE_NAME}: ${PRIMARY_LANGUAGE}, `${TEST_COMMAND}`");
+        assert_eq!(result, "my-crate: Rust, `cargo test`");
+        assert!(vars.date_time.starts_with(&vars.current_date));
+    }
 }
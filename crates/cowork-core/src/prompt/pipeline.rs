@@ -0,0 +1,386 @@
+//! Preprocessing of user messages before they reach the model
+//!
+//! A [`PromptPipeline`] runs its [`InputProcessor`]s in order on every user
+//! message. Two are built in and enabled from `[prompt.input]`:
+//!
+//! - [`PiiScrubber`] replaces email addresses and internal host names with
+//!   placeholders like `[EMAIL_1]`. The pipeline keeps the mapping for the
+//!   session, so a value gets the same placeholder every time and replies can
+//!   be restored with [`PromptPipeline::unscrub`] before they are shown.
+//! - [`LanguageDetector`] notices messages that aren't in English and asks
+//!   for a reply in the same language.
+//!
+//! Processors can fail, but the pipeline never does: a failing processor is
+//! logged and the message passes through it unchanged.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, LazyLock};
+
+use regex::Regex;
+
+use crate::config::InputProcessingConfig;
+use crate::error::Result;
+use crate::policy::wildcard_match;
+
+/// A user message on its way through the pipeline
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProcessedInput {
+    /// The message as the model will see it
+    pub content: String,
+    /// The message as the user wrote it
+    pub original: String,
+    /// Reminders sent to the model ahead of the message
+    pub reminders: Vec<String>,
+    /// Placeholders standing in for scrubbed text (placeholder -> original),
+    /// including those from earlier messages of the session
+    pub substitutions: BTreeMap<String, String>,
+    /// Notes left by processors, e.g. `language` = `French`
+    pub metadata: BTreeMap<String, String>,
+}
+
+impl ProcessedInput {
+    pub fn new(content: impl Into<String>) -> Self {
+        let content = content.into();
+        Self {
+            original: content.clone(),
+            content,
+            ..Default::default()
+        }
+    }
+
+    /// The message to send: reminders first, then the content
+    pub fn message(&self) -> String {
+        if self.reminders.is_empty() {
+            return self.content.clone();
+        }
+        format!(
+            "<system-reminder>\n{}\n</system-reminder>\n\n{}",
+            self.reminders.join("\n"),
+            self.content
+        )
+    }
+}
+
+/// One preprocessing step
+pub trait InputProcessor: Send + Sync {
+    /// Name used in logs
+    fn name(&self) -> &str;
+
+    /// Return the input with this step applied
+    fn process(&self, input: &ProcessedInput) -> Result<ProcessedInput>;
+}
+
+/// Ordered preprocessing steps, plus the session's scrubbing map
+#[derive(Clone, Default)]
+pub struct PromptPipeline {
+    processors: Vec<Arc<dyn InputProcessor>>,
+    substitutions: BTreeMap<String, String>,
+}
+
+impl std::fmt::Debug for PromptPipeline {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PromptPipeline")
+            .field("processors", &self.processors.iter().map(|p| p.name()).collect::<Vec<_>>())
+            .field("substitutions", &self.substitutions.len())
+            .finish()
+    }
+}
+
+impl PromptPipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The built-in processors enabled in `config`
+    pub fn from_config(config: &InputProcessingConfig) -> Self {
+        let mut pipeline = Self::new();
+        if config.scrub_pii {
+            pipeline.register(Arc::new(PiiScrubber::new(config.scrub_hosts.clone())));
+        }
+        if config.match_language {
+            pipeline.register(Arc::new(LanguageDetector));
+        }
+        pipeline
+    }
+
+    /// Add a processor, run after those already registered
+    pub fn register(&mut self, processor: Arc<dyn InputProcessor>) {
+        self.processors.push(processor);
+    }
+
+    /// Add a processor, run after those already registered
+    pub fn with_processor(mut self, processor: impl InputProcessor + 'static) -> Self {
+        self.register(Arc::new(processor));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.processors.is_empty()
+    }
+
+    /// Run every processor on a user message
+    pub fn process(&mut self, content: &str) -> ProcessedInput {
+        let mut input = ProcessedInput::new(content);
+        input.substitutions = std::mem::take(&mut self.substitutions);
+        for processor in &self.processors {
+            match processor.process(&input) {
+                Ok(output) => input = output,
+                Err(e) => tracing::warn!(
+                    "Input processor '{}' failed, passing the message through: {}",
+                    processor.name(),
+                    e
+                ),
+            }
+        }
+        self.substitutions = input.substitutions.clone();
+        input
+    }
+
+    /// Placeholders used so far in the session (placeholder -> original)
+    pub fn substitutions(&self) -> &BTreeMap<String, String> {
+        &self.substitutions
+    }
+
+    /// Put scrubbed values back into text from the model
+    pub fn unscrub(&self, text: &str) -> String {
+        self.substitutions
+            .iter()
+            .fold(text.to_string(), |text, (placeholder, original)| text.replace(placeholder, original))
+    }
+}
+
+static EMAIL_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}").unwrap());
+
+static HOST_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b[A-Za-z0-9](?:[A-Za-z0-9-]*[A-Za-z0-9])?(?:\.[A-Za-z0-9](?:[A-Za-z0-9-]*[A-Za-z0-9])?)*\b").unwrap());
+
+/// Replaces email addresses and internal host names with placeholders
+#[derive(Debug, Clone, Default)]
+pub struct PiiScrubber {
+    /// Host names or `*` globs (e.g. `*.corp.example.com`), matched ignoring case
+    hosts: Vec<String>,
+}
+
+impl PiiScrubber {
+    pub fn new(hosts: Vec<String>) -> Self {
+        Self {
+            hosts: hosts.into_iter().map(|h| h.trim().to_ascii_lowercase()).collect(),
+        }
+    }
+
+    fn is_internal_host(&self, host: &str) -> bool {
+        let host = host.to_ascii_lowercase();
+        self.hosts.iter().any(|pattern| wildcard_match(pattern, &host))
+    }
+}
+
+/// The placeholder for `value`, reusing the one it already has
+fn placeholder(substitutions: &mut BTreeMap<String, String>, kind: &str, value: &str) -> String {
+    if let Some((placeholder, _)) = substitutions.iter().find(|(_, original)| *original == value) {
+        return placeholder.clone();
+    }
+    let prefix = format!("[{}_", kind);
+    let n = substitutions.keys().filter(|p| p.starts_with(&prefix)).count() + 1;
+    let placeholder = format!("{}{}]", prefix, n);
+    substitutions.insert(placeholder.clone(), value.to_string());
+    placeholder
+}
+
+impl InputProcessor for PiiScrubber {
+    fn name(&self) -> &str {
+        "pii-scrubber"
+    }
+
+    fn process(&self, input: &ProcessedInput) -> Result<ProcessedInput> {
+        let mut output = input.clone();
+        let substitutions = &mut output.substitutions;
+        let mut scrubbed = 0;
+
+        // Emails first, so their domains aren't taken for hosts
+        let content = EMAIL_RE
+            .replace_all(&input.content, |caps: &regex::Captures| {
+                scrubbed += 1;
+                placeholder(substitutions, "EMAIL", &caps[0])
+            })
+            .into_owned();
+        let content = if self.hosts.is_empty() {
+            content
+        } else {
+            HOST_RE
+                .replace_all(&content, |caps: &regex::Captures| {
+                    if self.is_internal_host(&caps[0]) {
+                        scrubbed += 1;
+                        placeholder(substitutions, "HOST", &caps[0])
+                    } else {
+                        caps[0].to_string()
+                    }
+                })
+                .into_owned()
+        };
+
+        if scrubbed > 0 {
+            output.metadata.insert("scrubbed".to_string(), scrubbed.to_string());
+        }
+        output.content = content;
+        Ok(output)
+    }
+}
+
+/// Asks for replies in the language of messages that aren't in English
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LanguageDetector;
+
+impl InputProcessor for LanguageDetector {
+    fn name(&self) -> &str {
+        "language-detector"
+    }
+
+    fn process(&self, input: &ProcessedInput) -> Result<ProcessedInput> {
+        let mut output = input.clone();
+        if let Some(language) = detect_language(&input.content) {
+            output.metadata.insert("language".to_string(), language.to_string());
+            output.reminders.push(format!(
+                "The user is writing in {}. Respond in the same language they write in.",
+                language
+            ));
+        }
+        Ok(output)
+    }
+}
+
+/// Common short words of the Latin-script languages told apart from English
+const STOPWORDS: &[(&str, &[&str])] = &[
+    ("English", &["the", "and", "is", "are", "to", "of", "it", "that", "this", "with", "for", "you", "what", "how", "please", "can", "my"]),
+    ("Spanish", &["el", "los", "las", "que", "y", "es", "por", "para", "con", "una", "cómo", "qué", "está", "del", "mi", "puedes"]),
+    ("French", &["le", "les", "des", "est", "et", "une", "pour", "avec", "dans", "je", "vous", "pas", "ce", "du", "mon", "peux"]),
+    ("German", &["der", "die", "das", "und", "ist", "nicht", "ich", "mit", "ein", "eine", "zu", "für", "wie", "auf", "den", "bitte"]),
+    ("Portuguese", &["os", "que", "e", "é", "um", "uma", "para", "com", "não", "do", "da", "em", "você", "meu", "como"]),
+    ("Italian", &["il", "lo", "che", "di", "è", "un", "una", "per", "con", "non", "sono", "della", "come", "mio", "puoi"]),
+];
+
+/// The language of `text` when it clearly isn't English
+///
+/// Non-Latin scripts are recognized by their characters; Latin-script
+/// languages by their most common words.
+pub fn detect_language(text: &str) -> Option<&'static str> {
+    let mut scripts: BTreeMap<&'static str, usize> = BTreeMap::new();
+    let mut letters = 0;
+    for c in text.chars().filter(|c| c.is_alphabetic()) {
+        letters += 1;
+        let script = match c {
+            '\u{3040}'..='\u{30FF}' => "Japanese",
+            '\u{AC00}'..='\u{D7AF}' | '\u{1100}'..='\u{11FF}' => "Korean",
+            '\u{4E00}'..='\u{9FFF}' => "Chinese",
+            '\u{0400}'..='\u{04FF}' => "Russian",
+            '\u{0600}'..='\u{06FF}' => "Arabic",
+            '\u{0590}'..='\u{05FF}' => "Hebrew",
+            '\u{0370}'..='\u{03FF}' => "Greek",
+            '\u{0900}'..='\u{097F}' => "Hindi",
+            '\u{0E00}'..='\u{0E7F}' => "Thai",
+            _ => continue,
+        };
+        *scripts.entry(script).or_default() += 1;
+    }
+    let non_latin: usize = scripts.values().sum();
+    if letters > 0 && non_latin * 10 >= letters * 3 {
+        // Japanese mixes kanji with kana
+        if scripts.contains_key("Japanese") {
+            return Some("Japanese");
+        }
+        return scripts.into_iter().max_by_key(|(_, n)| *n).map(|(script, _)| script);
+    }
+
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    if words.len() < 3 {
+        return None;
+    }
+    let score = |stopwords: &[&str]| words.iter().filter(|w| stopwords.contains(&w.as_str())).count();
+    let english = score(STOPWORDS[0].1);
+    STOPWORDS[1..]
+        .iter()
+        .map(|(language, stopwords)| (*language, score(stopwords)))
+        .filter(|(_, n)| *n >= 2 && *n > english)
+        .max_by_key(|(_, n)| *n)
+        .map(|(language, _)| language)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scrubbing_pipeline() -> PromptPipeline {
+        PromptPipeline::from_config(&InputProcessingConfig {
+            scrub_pii: true,
+            scrub_hosts: vec!["*.corp.example.com".to_string(), "build01".to_string()],
+            match_language: true,
+        })
+    }
+
+    #[test]
+    fn test_scrubs_emails_and_internal_hosts() {
+        let mut pipeline = scrubbing_pipeline();
+        let input = pipeline.process("Email ana@example.com about db1.corp.example.com and build01, not github.com");
+        assert_eq!(input.content, "Email [EMAIL_1] about [HOST_1] and [HOST_2], not github.com");
+        assert_eq!(input.original, "Email ana@example.com about db1.corp.example.com and build01, not github.com");
+        assert_eq!(input.metadata.get("scrubbed").map(String::as_str), Some("3"));
+
+        // The mapping lasts for the session
+        let input = pipeline.process("Also cc bo@example.com and ana@example.com");
+        assert_eq!(input.content, "Also cc [EMAIL_2] and [EMAIL_1]");
+        assert_eq!(pipeline.substitutions().len(), 4);
+    }
+
+    #[test]
+    fn test_unscrub_round_trip() {
+        let mut pipeline = scrubbing_pipeline();
+        let message = "Ask ana@example.com to restart DB1.corp.example.com";
+        let scrubbed = pipeline.process(message).content;
+        assert!(!scrubbed.contains("ana@example.com"));
+        assert_eq!(pipeline.unscrub(&scrubbed), message);
+
+        let reply = "I'll email [EMAIL_1] once [HOST_1] is back up.";
+        assert_eq!(pipeline.unscrub(reply), "I'll email ana@example.com once DB1.corp.example.com is back up.");
+    }
+
+    #[test]
+    fn test_detects_non_english_messages() {
+        assert_eq!(detect_language("¿Puedes revisar por qué el test falla con una lista vacía?"), Some("Spanish"));
+        assert_eq!(detect_language("Peux-tu corriger le bug dans les tests et la doc?"), Some("French"));
+        assert_eq!(detect_language("修复这个函数的错误"), Some("Chinese"));
+        assert_eq!(detect_language("この関数のバグを直して"), Some("Japanese"));
+        assert_eq!(detect_language("Почини тесты пожалуйста"), Some("Russian"));
+        assert_eq!(detect_language("Can you fix the failing test in parser.rs?"), None);
+        assert_eq!(detect_language("fix it"), None);
+
+        let mut pipeline = scrubbing_pipeline();
+        let input = pipeline.process("Peux-tu corriger le bug dans les tests et la doc?");
+        assert_eq!(input.metadata.get("language").map(String::as_str), Some("French"));
+        assert!(input.message().starts_with("<system-reminder>\nThe user is writing in French."));
+        assert!(input.message().ends_with("Peux-tu corriger le bug dans les tests et la doc?"));
+    }
+
+    struct Failing;
+
+    impl InputProcessor for Failing {
+        fn name(&self) -> &str {
+            "failing"
+        }
+
+        fn process(&self, _input: &ProcessedInput) -> Result<ProcessedInput> {
+            Err(crate::error::Error::Agent("boom".to_string()))
+        }
+    }
+
+    #[test]
+    fn test_failing_processor_passes_input_through() {
+        let mut pipeline = PromptPipeline::new().with_processor(Failing).with_processor(PiiScrubber::default());
+        let input = pipeline.process("ping ana@example.com");
+        assert_eq!(input.content, "ping [EMAIL_1]");
+        assert_eq!(PromptPipeline::new().process("hi").message(), "hi");
+    }
+}
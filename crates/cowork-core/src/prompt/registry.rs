@@ -45,6 +45,7 @@ use crate::prompt::hook_executor::load_hooks_config;
 use crate::prompt::hooks::HooksConfig;
 use crate::prompt::plugins::{DiscoverResult, PluginRegistry};
 use crate::prompt::types::Scope;
+use crate::prompt::TemplateVars;
 use crate::skills::loader::{DynamicSkill, SkillSource};
 
 // ================== Serializable Info Structs ==================
@@ -56,7 +57,18 @@ pub struct AgentInfo {
     pub description: String,
     pub scope: String,
     pub model: Option<String>,
+    /// Allowed tools (empty = all tools)
     pub tools: Vec<String>,
+    /// Whether the agent ships with Cowork (false = user, project, or plugin defined)
+    pub builtin: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_turns: Option<usize>,
+    /// File the agent was loaded from (None for built-in agents)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_path: Option<PathBuf>,
+    /// Assembled system prompt, filled in by `ComponentRegistry::summary_with_prompts`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
 }
 
 impl From<&AgentDefinition> for AgentInfo {
@@ -74,9 +86,13 @@ impl From<&AgentDefinition> for AgentInfo {
             scope: format!("{:?}", agent.scope).to_lowercase(),
             model: match agent.model() {
                 crate::ModelPreference::Inherit => None,
-                other => Some(format!("{:?}", other).to_lowercase()),
+                other => Some(other.to_string()),
             },
             tools,
+            builtin: agent.scope == Scope::Builtin,
+            max_turns: agent.max_turns(),
+            source_path: agent.source_path.clone(),
+            prompt: None,
         }
     }
 }
@@ -160,6 +176,33 @@ pub struct RegistrySummary {
     pub skills: Vec<SkillInfo>,
     pub plugins: Vec<PluginInfo>,
     pub counts: RegistryCounts,
+    /// Component files that failed to load
+    #[serde(default)]
+    pub errors: Vec<ComponentLoadError>,
+}
+
+impl RegistrySummary {
+    /// Agent files that failed to load
+    pub fn agent_errors(&self) -> impl Iterator<Item = &ComponentLoadError> {
+        self.errors.iter().filter(|e| e.kind == ComponentKind::Agent)
+    }
+}
+
+/// Type of a component file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ComponentKind {
+    Agent,
+    Command,
+    Skill,
+}
+
+/// A component file that was found but could not be loaded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComponentLoadError {
+    pub kind: ComponentKind,
+    pub path: PathBuf,
+    pub message: String,
 }
 
 /// Component counts
@@ -325,6 +368,9 @@ pub struct ComponentRegistry {
 
     /// Plugin registry
     plugins: PluginRegistry,
+
+    /// Component files that failed to load (reported at load time)
+    load_errors: Vec<ComponentLoadError>,
 }
 
 impl ComponentRegistry {
@@ -386,7 +432,19 @@ impl ComponentRegistry {
             commands,
             skills,
             plugins,
+            errors: self.load_errors.clone(),
+        }
+    }
+
+    /// Summary with each agent's assembled system prompt
+    ///
+    /// This is what `/agents` and the desktop app show.
+    pub fn summary_with_prompts(&self, vars: &TemplateVars) -> RegistrySummary {
+        let mut summary = self.summary();
+        for info in &mut summary.agents {
+            info.prompt = self.agents.get(&info.name).map(|agent| agent.assembled_prompt(vars));
         }
+        summary
     }
 
     /// Load built-in components (agents and commands)
@@ -538,6 +596,7 @@ impl ComponentRegistry {
                 }
                 Err(e) => {
                     tracing::warn!("Failed to load agent from {}: {}", path.display(), e);
+                    self.record_load_error(ComponentKind::Agent, &path, e);
                 }
             }
         }
@@ -581,6 +640,7 @@ impl ComponentRegistry {
                 }
                 Err(e) => {
                     tracing::warn!("Failed to load skill from {}: {}", path.display(), e);
+                    self.record_load_error(ComponentKind::Skill, &path, e);
                 }
             }
         }
@@ -618,6 +678,7 @@ impl ComponentRegistry {
                 }
                 Err(e) => {
                     tracing::warn!("Failed to load command from {}: {}", path.display(), e);
+                    self.record_load_error(ComponentKind::Command, &path, e);
                 }
             }
         }
@@ -625,6 +686,14 @@ impl ComponentRegistry {
         Ok(loaded)
     }
 
+    fn record_load_error(&mut self, kind: ComponentKind, path: &Path, error: impl std::fmt::Display) {
+        self.load_errors.push(ComponentLoadError {
+            kind,
+            path: path.to_path_buf(),
+            message: error.to_string(),
+        });
+    }
+
     /// Check if a new agent should override an existing one
     fn should_override_agent(&self, name: &str, new_scope: Scope) -> bool {
         match self.agents.get(name) {
@@ -668,6 +737,11 @@ impl ComponentRegistry {
         self.skills.get(name)
     }
 
+    /// Component files that failed to load, in discovery order
+    pub fn load_errors(&self) -> &[ComponentLoadError] {
+        &self.load_errors
+    }
+
     /// Get a command by name
     pub fn get_command(&self, name: &str) -> Option<&CommandDefinition> {
         // Support both with and without leading slash
@@ -1219,6 +1293,30 @@ mod tests {
             assert!(registry.get_agent("test-agent").is_some());
         }
 
+        #[test]
+        fn test_invalid_agent_reported_at_load() {
+            let temp = TempDir::new().unwrap();
+            let agents_dir = temp.path().join("agents");
+            create_agent_file(&agents_dir, "good", "Good agent");
+            std::fs::write(agents_dir.join("bad.md"), "---\nname: bad\nmax_turns: many\n---\n\nPrompt").unwrap();
+
+            let mut registry = ComponentRegistry::with_builtins();
+            let loaded = registry.load_agents_from_dir(&agents_dir, Scope::Project).unwrap();
+
+            assert_eq!(loaded, 1);
+            assert!(registry.get_agent("bad").is_none());
+            assert_eq!(registry.load_errors().len(), 1);
+            assert!(registry.load_errors()[0].path.ends_with("bad.md"));
+            assert!(registry.load_errors()[0].message.contains("max_turns"));
+
+            let summary = registry.summary();
+            assert_eq!(summary.errors.len(), 1);
+            assert_eq!(summary.agent_errors().count(), 1);
+            let builtin = |name: &str| summary.agents.iter().find(|a| a.name == name).unwrap().builtin;
+            assert!(builtin("Explore"));
+            assert!(!builtin("good"));
+        }
+
         #[test]
         fn test_load_commands_from_dir() {
             let temp = TempDir::new().unwrap();
@@ -199,6 +199,30 @@ impl ToolRestrictions {
         false
     }
 
+    /// Check if any invocation of a tool could be allowed
+    ///
+    /// Unlike [`is_allowed`](Self::is_allowed), this ignores arguments: a
+    /// pattern spec like `Bash(git:*)` makes `Bash` available even though only
+    /// some commands will pass. Used to decide which tool definitions to offer.
+    pub fn allows_tool(&self, tool_name: &str) -> bool {
+        let names_tool = |spec: &ToolSpec| match spec {
+            ToolSpec::All => true,
+            ToolSpec::Name(name) => name == tool_name,
+            ToolSpec::Pattern { tool, .. } => tool == tool_name,
+        };
+
+        // Only unconditional denials hide the tool entirely
+        if self
+            .denied
+            .iter()
+            .any(|spec| !matches!(spec, ToolSpec::Pattern { .. }) && names_tool(spec))
+        {
+            return false;
+        }
+
+        self.allowed.is_empty() || self.allowed.iter().any(names_tool)
+    }
+
     /// Compute the intersection of two restrictions
     ///
     /// The result is the most restrictive combination:
@@ -575,6 +599,23 @@ mod tests {
             assert!(!restrictions.is_allowed("Bash", &json!({"command": "rm -rf /"})));
         }
 
+        #[test]
+        fn test_allows_tool_ignores_patterns() {
+            let restrictions = ToolRestrictions {
+                allowed: vec![ToolSpec::parse("Read"), ToolSpec::parse("Bash(git:*)")],
+                denied: vec![ToolSpec::parse("Write(src/*:*)")],
+            };
+
+            assert!(restrictions.allows_tool("Read"));
+            assert!(restrictions.allows_tool("Bash"));
+            assert!(!restrictions.allows_tool("Write"));
+            assert!(!restrictions.allows_tool("Grep"));
+
+            let deny_all_bash = ToolRestrictions::deny(vec![ToolSpec::parse("Bash")]);
+            assert!(!deny_all_bash.allows_tool("Bash"));
+            assert!(deny_all_bash.allows_tool("Read"));
+        }
+
         #[test]
         fn test_intersect_denied() {
             let r1 = ToolRestrictions::deny(vec![ToolSpec::Name("A".to_string())]);
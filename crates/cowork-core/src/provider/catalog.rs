@@ -27,6 +27,31 @@ pub struct Model {
     /// Maximum output tokens for this model
     #[serde(default = "default_max_output")]
     pub max_output: usize,
+    /// Token prices, when known
+    #[serde(default)]
+    pub pricing: Option<ModelPricing>,
+}
+
+/// Token prices in USD per million tokens
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct ModelPricing {
+    pub input: f64,
+    pub output: f64,
+    /// Price of input tokens read from the prompt cache (defaults to `input`)
+    #[serde(default)]
+    pub cached_input: Option<f64>,
+}
+
+impl ModelPricing {
+    /// Cost in USD of a request; `cached_tokens` are counted within `input_tokens`
+    pub fn cost(&self, input_tokens: u64, output_tokens: u64, cached_tokens: u64) -> f64 {
+        let cached = cached_tokens.min(input_tokens);
+        let uncached = input_tokens - cached;
+        let per_token = |price: f64| price / 1_000_000.0;
+        uncached as f64 * per_token(self.input)
+            + cached as f64 * per_token(self.cached_input.unwrap_or(self.input))
+            + output_tokens as f64 * per_token(self.output)
+    }
 }
 
 fn default_max_output() -> usize {
@@ -42,6 +67,8 @@ pub struct Provider {
     pub base_url: String,
     pub api_key_env: Option<String>,
     pub native_web_search: bool,
+    /// Whether the provider's models accept images
+    pub vision: bool,
     pub models: HashMap<ModelTier, Model>,
 }
 
@@ -81,6 +108,8 @@ struct RawProvider {
     base_url: String,
     api_key_env: Option<String>,
     native_web_search: bool,
+    #[serde(default)]
+    vision: bool,
     models: RawModels,
 }
 
@@ -111,6 +140,7 @@ static CATALOG: LazyLock<HashMap<String, Provider>> = LazyLock::new(|| {
                 base_url: raw.base_url,
                 api_key_env: raw.api_key_env,
                 native_web_search: raw.native_web_search,
+                vision: raw.vision,
                 models,
             };
             (id, provider)
@@ -140,6 +170,18 @@ pub fn has_native_search(provider_id: &str) -> bool {
     get(provider_id).map(|p| p.native_web_search).unwrap_or(false)
 }
 
+/// Check if a provider's models accept images
+pub fn supports_vision(provider_id: &str) -> bool {
+    get(provider_id).map(|p| p.vision).unwrap_or(false)
+}
+
+/// IDs of the providers whose models accept images, sorted
+pub fn vision_providers() -> Vec<&'static str> {
+    let mut ids: Vec<_> = all().filter(|p| p.vision).map(|p| p.id.as_str()).collect();
+    ids.sort_unstable();
+    ids
+}
+
 /// Get the genai AdapterKind for a provider
 pub fn adapter(provider_id: &str) -> Option<AdapterKind> {
     get(provider_id).map(|p| p.adapter)
@@ -192,6 +234,23 @@ pub fn model_tiers(provider_id: &str) -> Option<(&'static str, &'static str, &'s
     })
 }
 
+/// Get token pricing for a model by ID
+///
+/// Looks in the given provider first, then in any provider that lists the
+/// model (e.g. a model served through a proxy provider).
+pub fn pricing(provider_id: &str, model_id: &str) -> Option<ModelPricing> {
+    let find = |provider: &Provider| {
+        provider
+            .models
+            .values()
+            .find(|m| m.id == model_id)
+            .and_then(|m| m.pricing)
+    };
+    get(provider_id)
+        .and_then(find)
+        .or_else(|| all().find_map(find))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -211,6 +270,13 @@ mod tests {
         assert!(!has_native_search("ollama"));
     }
 
+    #[test]
+    fn test_vision() {
+        assert!(supports_vision("Anthropic"));
+        assert!(!supports_vision("deepseek"));
+        assert_eq!(vision_providers(), vec!["anthropic", "gemini", "openai"]);
+    }
+
     #[test]
     fn test_base_url() {
         assert_eq!(base_url("anthropic"), Some("https://api.anthropic.com"));
@@ -247,4 +313,16 @@ mod tests {
         assert_eq!(context_window("ANTHROPIC"), context_window("anthropic"));
         assert_eq!(context_window("OpenAI"), context_window("openai"));
     }
+
+    #[test]
+    fn test_pricing() {
+        let sonnet = pricing("anthropic", "claude-sonnet-4-5-20250929").unwrap();
+        // 1M uncached input + 1M cached input + 1M output
+        let cost = sonnet.cost(2_000_000, 1_000_000, 1_000_000);
+        assert!((cost - (3.0 + 0.3 + 15.0)).abs() < 1e-9);
+
+        // Found through another provider when the model isn't in this one
+        assert!(pricing("openrouter", "gpt-5").is_some());
+        assert!(pricing("ollama", "llama3.2").is_none());
+    }
 }
@@ -0,0 +1,354 @@
+//! Request timeouts and circuit breaking, per provider
+//!
+//! A provider that stops answering would otherwise leave every session on
+//! "Thinking..." until cancelled. Each LLM call is bounded by a timeout, and
+//! timeouts and server errors (HTTP 5xx) count against the provider's circuit:
+//! after `CIRCUIT_FAILURES` of them within `CIRCUIT_WINDOW` the circuit opens,
+//! and for `CIRCUIT_COOLDOWN` requests to that provider fail at once instead of
+//! hanging again. After the cool-down one request goes through as a probe
+//! (half-open) while the others are still turned away: success closes the
+//! circuit, another failure reopens it. A probe that never reports back
+//! (cancelled, say) stops blocking the others after another cool-down.
+//!
+//! One `ProviderCircuits` is shared by all sessions (see
+//! `factory::provider_circuits`).
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use parking_lot::Mutex;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+
+use crate::error::{Error, Result};
+
+/// How long a request may take before it's abandoned
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// How long a streamed request may take (a long answer streams for a while)
+pub const DEFAULT_STREAM_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Timeouts or server errors within the window that open a circuit
+pub const CIRCUIT_FAILURES: usize = 3;
+
+/// Window in which failures count towards opening a circuit
+pub const CIRCUIT_WINDOW: Duration = Duration::from_secs(5 * 60);
+
+/// How long an open circuit turns requests away
+pub const CIRCUIT_COOLDOWN: Duration = Duration::from_secs(2 * 60);
+
+/// Matches the status in a provider error for a server-side failure
+static SERVER_ERROR_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\bHTTP 5\d\d\b").expect("server error pattern is valid"));
+
+/// Whether requests to a provider are going through
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Requests go through
+    Closed,
+    /// Requests fail at once until the cool-down ends
+    Open,
+    /// The cool-down has ended; one probe request's result decides
+    HalfOpen,
+}
+
+/// A provider's circuit, for `/provider` and the app's settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProviderStatus {
+    pub provider_id: String,
+    pub state: CircuitState,
+    /// Timeouts and server errors within the window
+    pub recent_failures: usize,
+    /// When an open circuit lets requests through again
+    pub open_until: Option<DateTime<Local>>,
+    /// The most recent timeout or server error
+    pub last_error: Option<String>,
+}
+
+/// One provider's failure history
+#[derive(Debug, Default)]
+struct Circuit {
+    failures: VecDeque<Instant>,
+    open_until: Option<Instant>,
+    /// While half-open, until when the request let through as the probe
+    /// holds the circuit for its result
+    probe_until: Option<Instant>,
+    last_error: Option<String>,
+}
+
+impl Circuit {
+    fn state(&self, now: Instant) -> CircuitState {
+        match self.open_until {
+            None => CircuitState::Closed,
+            Some(until) if now < until => CircuitState::Open,
+            Some(_) => CircuitState::HalfOpen,
+        }
+    }
+}
+
+/// Circuit breakers for every provider, keyed by provider ID
+#[derive(Debug)]
+pub struct ProviderCircuits {
+    failure_threshold: usize,
+    window: Duration,
+    cooldown: Duration,
+    circuits: Mutex<HashMap<String, Circuit>>,
+}
+
+impl Default for ProviderCircuits {
+    fn default() -> Self {
+        Self::new(CIRCUIT_FAILURES, CIRCUIT_WINDOW, CIRCUIT_COOLDOWN)
+    }
+}
+
+impl ProviderCircuits {
+    /// Open after `failure_threshold` failures within `window`, for `cooldown`
+    pub fn new(failure_threshold: usize, window: Duration, cooldown: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            window,
+            cooldown,
+            circuits: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Fail if the provider's circuit is open, or half-open with another
+    /// request already probing it; otherwise the caller may send its
+    /// request (as the probe, when half-open) and must record the result
+    pub fn check(&self, provider_id: &str) -> Result<()> {
+        let now = Instant::now();
+        let mut circuits = self.circuits.lock();
+        let Some(circuit) = circuits.get_mut(provider_id) else {
+            return Ok(());
+        };
+        match circuit.state(now) {
+            CircuitState::Closed => Ok(()),
+            CircuitState::Open => {
+                let until = circuit.open_until.map(|until| wall_clock(until, now)).unwrap_or_else(Local::now);
+                Err(Error::Provider(format!(
+                    "Provider {} marked unavailable until {}, use /provider to switch",
+                    provider_id,
+                    until.format("%H:%M")
+                )))
+            }
+            CircuitState::HalfOpen if circuit.probe_until.is_some_and(|until| now < until) => Err(Error::Provider(format!(
+                "Provider {} is being checked after recent failures, try again shortly or use /provider to switch",
+                provider_id
+            ))),
+            CircuitState::HalfOpen => {
+                circuit.probe_until = Some(now + self.cooldown);
+                Ok(())
+            }
+        }
+    }
+
+    /// The probe of a half-open circuit got an answer that says nothing
+    /// about the provider's health (a rejected request, say): let the next
+    /// request probe it instead
+    pub fn end_probe(&self, provider_id: &str) {
+        if let Some(circuit) = self.circuits.lock().get_mut(provider_id) {
+            circuit.probe_until = None;
+        }
+    }
+
+    /// A request got an answer: close the circuit and forget earlier failures
+    pub fn record_success(&self, provider_id: &str) {
+        let removed = self.circuits.lock().remove(provider_id);
+        if removed.is_some_and(|circuit| circuit.open_until.is_some()) {
+            crate::telemetry::counter(
+                crate::telemetry::PROVIDER_CIRCUIT_TRANSITIONS,
+                1,
+                &[("provider", provider_id), ("state", "closed")],
+            );
+        }
+    }
+
+    /// A request timed out or hit a server error; opens the circuit once
+    /// there have been enough of them, or at once if it was half-open
+    pub fn record_failure(&self, provider_id: &str, error: &str) {
+        let now = Instant::now();
+        let mut circuits = self.circuits.lock();
+        let circuit = circuits.entry(provider_id.to_string()).or_default();
+        circuit.last_error = Some(error.to_string());
+
+        let half_open = circuit.state(now) == CircuitState::HalfOpen;
+        while circuit
+            .failures
+            .front()
+            .is_some_and(|failure| now.duration_since(*failure) >= self.window)
+        {
+            circuit.failures.pop_front();
+        }
+        circuit.failures.push_back(now);
+
+        if half_open || circuit.failures.len() >= self.failure_threshold {
+            tracing::warn!(provider = %provider_id, error = %error, "Provider circuit opened");
+            circuit.open_until = Some(now + self.cooldown);
+            circuit.probe_until = None;
+            circuit.failures.clear();
+            crate::telemetry::counter(
+                crate::telemetry::PROVIDER_CIRCUIT_TRANSITIONS,
+                1,
+                &[("provider", provider_id), ("state", "open")],
+            );
+        }
+    }
+
+    /// Close the provider's circuit, as after a successful probe
+    pub fn reset(&self, provider_id: &str) {
+        self.record_success(provider_id);
+    }
+
+    /// The provider's circuit (closed if nothing has failed)
+    pub fn status(&self, provider_id: &str) -> ProviderStatus {
+        let now = Instant::now();
+        let circuits = self.circuits.lock();
+        match circuits.get(provider_id) {
+            Some(circuit) => self.describe(provider_id, circuit, now),
+            None => ProviderStatus {
+                provider_id: provider_id.to_string(),
+                state: CircuitState::Closed,
+                recent_failures: 0,
+                open_until: None,
+                last_error: None,
+            },
+        }
+    }
+
+    /// Every provider with a recent failure, by provider ID
+    pub fn statuses(&self) -> Vec<ProviderStatus> {
+        let now = Instant::now();
+        let circuits = self.circuits.lock();
+        let mut statuses: Vec<_> = circuits
+            .iter()
+            .map(|(provider_id, circuit)| self.describe(provider_id, circuit, now))
+            .collect();
+        statuses.sort_by(|a, b| a.provider_id.cmp(&b.provider_id));
+        statuses
+    }
+
+    fn describe(&self, provider_id: &str, circuit: &Circuit, now: Instant) -> ProviderStatus {
+        let state = circuit.state(now);
+        ProviderStatus {
+            provider_id: provider_id.to_string(),
+            state,
+            recent_failures: circuit
+                .failures
+                .iter()
+                .filter(|failure| now.duration_since(**failure) < self.window)
+                .count(),
+            open_until: (state == CircuitState::Open)
+                .then(|| circuit.open_until.map(|until| wall_clock(until, now)))
+                .flatten(),
+            last_error: circuit.last_error.clone(),
+        }
+    }
+}
+
+/// Whether a provider error is a server-side failure (HTTP 5xx)
+pub fn is_server_error(message: &str) -> bool {
+    SERVER_ERROR_RE.is_match(message)
+}
+
+/// Local time at which `instant` falls
+fn wall_clock(instant: Instant, now: Instant) -> DateTime<Local> {
+    let remaining = chrono::Duration::from_std(instant.saturating_duration_since(now)).unwrap_or_default();
+    Local::now() + remaining
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_opens_after_repeated_failures() {
+        let circuits = ProviderCircuits::default();
+        for _ in 0..CIRCUIT_FAILURES - 1 {
+            circuits.record_failure("anthropic", "No response within 120s");
+        }
+        assert!(circuits.check("anthropic").is_ok());
+        assert_eq!(circuits.status("anthropic").recent_failures, CIRCUIT_FAILURES - 1);
+
+        circuits.record_failure("anthropic", "GenAI error (HTTP 503)");
+        let err = circuits.check("anthropic").unwrap_err().to_string();
+        assert!(err.contains("anthropic marked unavailable until"), "{}", err);
+        assert!(err.contains("/provider"), "{}", err);
+        let status = circuits.status("anthropic");
+        assert_eq!(status.state, CircuitState::Open);
+        assert_eq!(status.last_error.as_deref(), Some("GenAI error (HTTP 503)"));
+        assert!(status.open_until.is_some());
+
+        // Other providers are unaffected
+        assert!(circuits.check("openai").is_ok());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_failures_outside_window_forgotten() {
+        let circuits = ProviderCircuits::default();
+        circuits.record_failure("anthropic", "timeout");
+        circuits.record_failure("anthropic", "timeout");
+        tokio::time::advance(CIRCUIT_WINDOW).await;
+        circuits.record_failure("anthropic", "timeout");
+        assert!(circuits.check("anthropic").is_ok());
+        assert_eq!(circuits.status("anthropic").recent_failures, 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_half_open_after_cooldown() {
+        let circuits = ProviderCircuits::new(1, CIRCUIT_WINDOW, CIRCUIT_COOLDOWN);
+        circuits.record_failure("anthropic", "timeout");
+        assert!(circuits.check("anthropic").is_err());
+
+        tokio::time::advance(CIRCUIT_COOLDOWN).await;
+        assert!(circuits.check("anthropic").is_ok());
+        assert_eq!(circuits.status("anthropic").state, CircuitState::HalfOpen);
+
+        // A failure while half-open reopens it straight away
+        circuits.record_failure("anthropic", "timeout");
+        assert_eq!(circuits.status("anthropic").state, CircuitState::Open);
+
+        tokio::time::advance(CIRCUIT_COOLDOWN).await;
+        assert!(circuits.check("anthropic").is_ok());
+        circuits.record_success("anthropic");
+        assert_eq!(circuits.status("anthropic").state, CircuitState::Closed);
+        assert!(circuits.statuses().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_half_open_lets_one_probe_through() {
+        let circuits = Arc::new(ProviderCircuits::new(1, CIRCUIT_WINDOW, CIRCUIT_COOLDOWN));
+        circuits.record_failure("anthropic", "timeout");
+        tokio::time::advance(CIRCUIT_COOLDOWN).await;
+
+        let check = |circuits: Arc<ProviderCircuits>| tokio::spawn(async move { circuits.check("anthropic").is_ok() });
+        let (first, second) = tokio::join!(check(circuits.clone()), check(circuits.clone()));
+        assert_eq!([first.unwrap(), second.unwrap()].iter().filter(|&&ok| ok).count(), 1);
+        let err = circuits.check("anthropic").unwrap_err().to_string();
+        assert!(err.contains("anthropic is being checked"), "{}", err);
+
+        // An answer that says nothing about health hands the probe on
+        circuits.end_probe("anthropic");
+        assert!(circuits.check("anthropic").is_ok());
+        assert!(circuits.check("anthropic").is_err());
+
+        // A probe that never reports back stops blocking after a cool-down
+        tokio::time::advance(CIRCUIT_COOLDOWN).await;
+        assert!(circuits.check("anthropic").is_ok());
+        circuits.record_success("anthropic");
+        assert!(circuits.check("anthropic").is_ok());
+        assert!(circuits.check("anthropic").is_ok());
+    }
+
+    #[test]
+    fn test_is_server_error() {
+        assert!(is_server_error("GenAI error (HTTP 502): bad gateway"));
+        assert!(!is_server_error("GenAI error (HTTP 429): rate limited"));
+        assert!(!is_server_error("HTTP 5000 things"));
+    }
+}
@@ -0,0 +1,106 @@
+//! Recognizing context overflow errors
+//!
+//! A request whose history no longer fits the model's context window is
+//! rejected, and every retry of the same history is rejected again. Each
+//! provider family words the error differently; the patterns below cover
+//! them, reading the request's token count and the model's limit where the
+//! message gives them:
+//!
+//! - Anthropic: `prompt is too long: 215000 tokens > 200000 maximum`
+//! - OpenAI and the OpenAI-compatible providers: `This model's maximum
+//!   context length is 128000 tokens. However, your messages resulted in
+//!   130000 tokens`, with the code `context_length_exceeded`
+//! - Gemini: `The input token count (1200000) exceeds the maximum number of
+//!   tokens allowed (1048576)`
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+/// A provider's report that the request exceeded the context window
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ContextOverflow {
+    /// Tokens in the rejected request, when reported
+    pub tokens: Option<u64>,
+    /// The model's context window, when reported
+    pub limit: Option<u64>,
+}
+
+/// Patterns reporting tokens then limit
+static TOKENS_THEN_LIMIT: LazyLock<Vec<Regex>> = LazyLock::new(|| {
+    [
+        // Anthropic
+        r"(?i)prompt is too long: (\d+) tokens > (\d+) maximum",
+        // Gemini
+        r"(?i)input token count \(?(\d+)\)? exceeds the maximum number of tokens allowed \(?(\d+)\)?",
+    ]
+    .into_iter()
+    .map(|pattern| Regex::new(pattern).expect("context overflow pattern is valid"))
+    .collect()
+});
+
+/// Patterns reporting limit then tokens
+static LIMIT_THEN_TOKENS: LazyLock<Vec<Regex>> = LazyLock::new(|| {
+    [
+        // OpenAI and compatible
+        r"(?i)maximum context length is (\d+) tokens.*?(?:resulted in|requested) (\d+) tokens",
+    ]
+    .into_iter()
+    .map(|pattern| Regex::new(pattern).expect("context overflow pattern is valid"))
+    .collect()
+});
+
+/// Wording of an overflow whose counts aren't given
+static OVERFLOW_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)context_length_exceeded|prompt is too long|maximum context length|exceeds the context window|context window (?:is )?exceeded|input token count .* exceeds the maximum",
+    )
+    .expect("context overflow pattern is valid")
+});
+
+/// The overflow a provider error reports, if it reports one
+pub fn context_overflow(message: &str) -> Option<ContextOverflow> {
+    let count = |captures: &regex::Captures, group: usize| captures.get(group)?.as_str().parse().ok();
+    if let Some(captures) = TOKENS_THEN_LIMIT.iter().find_map(|re| re.captures(message)) {
+        return Some(ContextOverflow { tokens: count(&captures, 1), limit: count(&captures, 2) });
+    }
+    if let Some(captures) = LIMIT_THEN_TOKENS.iter().find_map(|re| re.captures(message)) {
+        return Some(ContextOverflow { tokens: count(&captures, 2), limit: count(&captures, 1) });
+    }
+    OVERFLOW_RE.is_match(message).then(ContextOverflow::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provider_messages() {
+        let anthropic = r#"GenAI error (HTTP 400): {"type":"error","error":{"type":"invalid_request_error","message":"prompt is too long: 215000 tokens > 200000 maximum"}}"#;
+        assert_eq!(
+            context_overflow(anthropic),
+            Some(ContextOverflow { tokens: Some(215_000), limit: Some(200_000) })
+        );
+
+        let openai = "This model's maximum context length is 128000 tokens. However, your messages resulted in 130512 tokens. Please reduce the length of the messages. (code: context_length_exceeded)";
+        assert_eq!(
+            context_overflow(openai),
+            Some(ContextOverflow { tokens: Some(130_512), limit: Some(128_000) })
+        );
+
+        let gemini = "The input token count (1200000) exceeds the maximum number of tokens allowed (1048576).";
+        assert_eq!(
+            context_overflow(gemini),
+            Some(ContextOverflow { tokens: Some(1_200_000), limit: Some(1_048_576) })
+        );
+
+        assert_eq!(context_overflow(r#"{"code":"context_length_exceeded"}"#), Some(ContextOverflow::default()));
+    }
+
+    #[test]
+    fn test_other_errors() {
+        assert_eq!(context_overflow("GenAI error (HTTP 503): overloaded"), None);
+        // OpenAI's per-minute token limit is a rate limit, not an overflow
+        assert_eq!(context_overflow("Request too large for gpt-4o: Limit 30000, Requested 45000 tokens per min"), None);
+    }
+}
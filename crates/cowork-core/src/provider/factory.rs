@@ -1,11 +1,15 @@
 //! Provider Factory Module
 //!
 //! Shared provider creation and configuration utilities for both CLI and UI.
-//! Centralizes API key retrieval, model tier configuration, and provider instantiation.
+//! Centralizes API key retrieval, model tier configuration, and provider instantiation,
+//! and holds the circuit breakers shared by every session's requests.
+
+use std::sync::OnceLock;
 
 use crate::config::{ConfigManager, ModelTiers};
 use crate::error::{Error, Result};
 use super::catalog;
+use super::circuit::ProviderCircuits;
 use super::genai_provider::GenAIProvider;
 
 /// Get API key for a provider, checking config then environment variables
@@ -175,6 +179,15 @@ pub fn create_provider_from_provider_config(
     )
 }
 
+/// Circuit breakers for every provider, shared across sessions
+///
+/// Timeouts and server errors seen by one session mark the provider
+/// unavailable for all of them; a successful probe resets its circuit.
+pub fn provider_circuits() -> &'static ProviderCircuits {
+    static CIRCUITS: OnceLock<ProviderCircuits> = OnceLock::new();
+    CIRCUITS.get_or_init(ProviderCircuits::default)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
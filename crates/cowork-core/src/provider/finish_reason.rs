@@ -0,0 +1,106 @@
+//! Why the model stopped generating
+//!
+//! genai doesn't expose the provider's stop reason, so it is read from the
+//! raw response body instead. Each provider family names it differently:
+//! Anthropic's `stop_reason`, OpenAI's `choices[0].finish_reason` (also used
+//! by the OpenAI-compatible providers), and Gemini's
+//! `candidates[0].finishReason` or `promptFeedback.blockReason`.
+
+use serde_json::Value;
+
+/// Why the model stopped generating
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FinishReason {
+    /// The model finished its turn (or hit a stop sequence)
+    Stop,
+    /// The model stopped to call tools
+    ToolUse,
+    /// Output hit the token limit mid-response
+    Length,
+    /// The provider refused or filtered the response (holds the reason as reported)
+    Refusal(String),
+    /// The model produced a tool call the provider couldn't parse
+    MalformedToolCall,
+    /// Anything else, as reported
+    Other(String),
+}
+
+impl FinishReason {
+    /// Map a provider's reason string onto a `FinishReason`
+    pub fn parse(reason: &str) -> Self {
+        match reason.to_ascii_lowercase().as_str() {
+            "stop" | "end_turn" | "stop_sequence" | "complete" | "finish_reason_unspecified" => Self::Stop,
+            "tool_use" | "tool_calls" | "function_call" => Self::ToolUse,
+            "length" | "max_tokens" => Self::Length,
+            "refusal" | "content_filter" | "safety" | "recitation" | "blocklist" | "prohibited_content"
+            | "spii" | "image_safety" | "error_toxic" => Self::Refusal(reason.to_string()),
+            "malformed_function_call" => Self::MalformedToolCall,
+            _ => Self::Other(reason.to_string()),
+        }
+    }
+
+    /// Read the finish reason from a raw response body
+    pub fn from_raw_body(body: &Value) -> Option<Self> {
+        let reason = body
+            .get("stop_reason")
+            .or_else(|| body.pointer("/choices/0/finish_reason"))
+            .or_else(|| body.pointer("/candidates/0/finishReason"))
+            .or_else(|| body.pointer("/promptFeedback/blockReason"))
+            .or_else(|| body.get("finish_reason"))
+            .and_then(Value::as_str)?;
+        Some(Self::parse(reason))
+    }
+
+    /// Whether the provider refused or filtered the response
+    pub fn is_refusal(&self) -> bool {
+        matches!(self, Self::Refusal(_))
+    }
+}
+
+/// The explanation an OpenAI model gives when it refuses (`message.refusal`)
+///
+/// genai only reads `message.content`, which is empty for a refusal.
+pub fn refusal_message(body: &Value) -> Option<String> {
+    body.pointer("/choices/0/message/refusal")
+        .and_then(Value::as_str)
+        .filter(|s| !s.trim().is_empty())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_reads_each_provider_family() {
+        let anthropic = json!({"stop_reason": "max_tokens", "content": []});
+        assert_eq!(FinishReason::from_raw_body(&anthropic), Some(FinishReason::Length));
+
+        let openai = json!({"choices": [{"finish_reason": "tool_calls", "message": {}}]});
+        assert_eq!(FinishReason::from_raw_body(&openai), Some(FinishReason::ToolUse));
+
+        let gemini = json!({"candidates": [{"finishReason": "MALFORMED_FUNCTION_CALL"}]});
+        assert_eq!(FinishReason::from_raw_body(&gemini), Some(FinishReason::MalformedToolCall));
+
+        let blocked = json!({"promptFeedback": {"blockReason": "SAFETY"}});
+        assert_eq!(FinishReason::from_raw_body(&blocked), Some(FinishReason::Refusal("SAFETY".to_string())));
+
+        assert_eq!(FinishReason::from_raw_body(&json!({"id": "x"})), None);
+    }
+
+    #[test]
+    fn test_refusals_keep_the_reported_reason() {
+        assert_eq!(FinishReason::parse("refusal"), FinishReason::Refusal("refusal".to_string()));
+        assert_eq!(FinishReason::parse("content_filter"), FinishReason::Refusal("content_filter".to_string()));
+        assert_eq!(FinishReason::parse("end_turn"), FinishReason::Stop);
+        assert_eq!(FinishReason::parse("pause_turn"), FinishReason::Other("pause_turn".to_string()));
+    }
+
+    #[test]
+    fn test_refusal_message() {
+        let body = json!({"choices": [{"message": {"content": null, "refusal": "I can't help with that."}}]});
+        assert_eq!(refusal_message(&body).as_deref(), Some("I can't help with that."));
+        assert_eq!(refusal_message(&json!({"choices": [{"message": {"refusal": ""}}]})), None);
+    }
+}
@@ -5,22 +5,21 @@
 //!
 //! ## LLM Request/Response Logging
 //!
-//! Set the `LLM_LOG_FILE` environment variable to enable detailed logging of all
-//! LLM requests and responses to a JSON file. This is useful for debugging context
-//! issues, token usage, and model behavior.
+//! Set `[general] llm_log = "path"` in config or the `COWORK_LLM_LOG` environment
+//! variable to append a redacted JSONL record of every request and response.
+//! This is useful for debugging context issues, token usage, and model behavior.
+//! See [`super::logging`] for details.
 //!
-//! Example: `LLM_LOG_FILE=/tmp/llm.log cowork`
+//! Example: `COWORK_LLM_LOG=/tmp/llm.jsonl cowork`
 
 use genai::adapter::AdapterKind;
 use genai::chat::{ChatMessage, ChatOptions, ChatRequest, ChatStreamEvent, ToolCall, ToolResponse};
 use genai::resolver::{AuthData, AuthResolver, Endpoint};
 use genai::ModelIden;
 use genai::ServiceTarget;
-use genai::WebConfig;
 use genai::Client;
-use futures::StreamExt;
+use futures::{SinkExt, StreamExt};
 use std::time::Duration;
-use tokio::sync::mpsc;
 use tracing::{debug, error, warn};
 
 /// Retry configuration for different error types
@@ -48,14 +47,27 @@ impl Default for RetryConfig {
 
 /// Check if an error indicates rate limiting (HTTP 429)
 fn is_rate_limit_error(e: &genai::Error) -> bool {
+    http_status(e) == Some(429)
+}
+
+/// HTTP status of a failed response, if the error carries one
+fn http_status(e: &genai::Error) -> Option<u16> {
     match e {
         genai::Error::WebModelCall { webc_error, .. }
-        | genai::Error::WebAdapterCall { webc_error, .. } => matches!(
-            webc_error,
-            genai::webc::Error::ResponseFailedStatus { status, .. }
-                if status.as_u16() == 429
-        ),
-        _ => false,
+        | genai::Error::WebAdapterCall { webc_error, .. } => match webc_error {
+            genai::webc::Error::ResponseFailedStatus { status, .. } => Some(status.as_u16()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Prefix for a provider error message, with the HTTP status when there is
+/// one (the circuit breaker counts 5xx responses against the provider)
+fn error_prefix(label: &str, e: &genai::Error) -> String {
+    match http_status(e) {
+        Some(status) => format!("{} (HTTP {})", label, status),
+        None => label.to_string(),
     }
 }
 
@@ -127,7 +139,10 @@ fn extract_webc_body(e: &genai::webc::Error) -> Option<String> {
 use crate::error::{Error, Result};
 use crate::tools::ToolDefinition;
 use super::catalog;
-use super::logging::{log_llm_interaction, LogConfig};
+use super::logging::{LlmLogger, LogConfig};
+use super::generation::GenerationParams;
+use super::finish_reason::{refusal_message, FinishReason};
+use super::llm_provider::{BoxFuture, EventStream, LlmProvider, StreamAssembler, StreamEvent};
 
 /// Response from completion that may contain both content and tool calls
 #[derive(Debug, Clone, Default)]
@@ -140,6 +155,10 @@ pub struct CompletionResult {
     pub input_tokens: Option<u64>,
     /// Output tokens used for this response (from provider)
     pub output_tokens: Option<u64>,
+    /// Input tokens read from the prompt cache (counted within `input_tokens`)
+    pub cached_tokens: Option<u64>,
+    /// Why the model stopped (`None` when the provider didn't say, e.g. when streaming)
+    pub finish_reason: Option<FinishReason>,
 }
 
 impl CompletionResult {
@@ -163,17 +182,22 @@ pub struct GenAIProvider {
     adapter: AdapterKind,
     model: String,
     system_prompt: Option<String>,
+    /// Opt-in request logger (None = logging disabled)
+    llm_logger: Option<LlmLogger>,
 }
 
 impl GenAIProvider {
     /// Default timeout for LLM API requests (5 minutes)
     const DEFAULT_TIMEOUT: Duration = Duration::from_secs(300);
 
-    /// Create WebConfig with appropriate timeouts for LLM requests
-    fn default_web_config() -> WebConfig {
-        WebConfig::default()
-            .with_timeout(Self::DEFAULT_TIMEOUT)
-            .with_connect_timeout(Duration::from_secs(30))
+    /// HTTP client with the shared network settings and timeouts suited to LLM requests
+    fn http_client() -> Result<reqwest::Client> {
+        crate::http::settings()
+            .client_builder()?
+            .timeout(Self::DEFAULT_TIMEOUT)
+            .connect_timeout(Duration::from_secs(30))
+            .build()
+            .map_err(|e| Error::Config(format!("Failed to create HTTP client: {}", e)))
     }
 
     /// Create a new provider with default settings (uses environment variables for auth)
@@ -186,18 +210,26 @@ impl GenAIProvider {
 
         // Use model mapper to force the correct adapter for this provider
         let client = Client::builder()
-            .with_web_config(Self::default_web_config())
+            .with_reqwest(Self::http_client()?)
             .with_model_mapper_fn(move |model_iden: ModelIden| -> std::result::Result<ModelIden, genai::resolver::Error> {
                 Ok(ModelIden::new(adapter, model_iden.model_name.clone()))
             })
             .build();
 
+        // Redact the key genai will pick up from the environment
+        let env_key = catalog::api_key_env(provider_id).and_then(|name| std::env::var(name).ok());
+        let llm_logger = LlmLogger::load().map(|l| match env_key {
+            Some(key) => l.with_secret(key),
+            None => l,
+        });
+
         Ok(Self {
             client,
             provider_id: provider_id.to_string(),
             adapter: provider.adapter,
             model: model.unwrap_or(&provider.default_model().id).to_string(),
             system_prompt: None,
+            llm_logger,
         })
     }
 
@@ -207,6 +239,7 @@ impl GenAIProvider {
             .ok_or_else(|| Error::Config(format!("Unknown provider: {}", provider_id)))?;
         let adapter = provider.adapter;
 
+        let llm_logger = LlmLogger::load().map(|l| l.with_secret(api_key));
         let api_key = api_key.to_string();
         let auth_resolver = AuthResolver::from_resolver_fn(
             move |_model_iden| -> std::result::Result<Option<AuthData>, genai::resolver::Error> {
@@ -216,7 +249,7 @@ impl GenAIProvider {
 
         // Use model mapper to force the correct adapter for this provider
         let client = Client::builder()
-            .with_web_config(Self::default_web_config())
+            .with_reqwest(Self::http_client()?)
             .with_auth_resolver(auth_resolver)
             .with_model_mapper_fn(move |model_iden: ModelIden| -> std::result::Result<ModelIden, genai::resolver::Error> {
                 Ok(ModelIden::new(adapter, model_iden.model_name.clone()))
@@ -229,6 +262,7 @@ impl GenAIProvider {
             adapter: provider.adapter,
             model: model.unwrap_or(&provider.default_model().id).to_string(),
             system_prompt: None,
+            llm_logger,
         })
     }
 
@@ -249,6 +283,7 @@ impl GenAIProvider {
             .ok_or_else(|| Error::Config(format!("Unknown provider: {}", provider_id)))?;
         let adapter = provider.adapter;
 
+        let llm_logger = LlmLogger::load().map(|l| l.with_secret(api_key));
         let api_key_owned = api_key.to_string();
         let auth_resolver = AuthResolver::from_resolver_fn(
             move |_model_iden| -> std::result::Result<Option<AuthData>, genai::resolver::Error> {
@@ -268,7 +303,7 @@ impl GenAIProvider {
 
         // Use model mapper to force the correct adapter for this provider
         let client = Client::builder()
-            .with_web_config(Self::default_web_config())
+            .with_reqwest(Self::http_client()?)
             .with_auth_resolver(auth_resolver)
             .with_model_mapper_fn(move |model_iden: ModelIden| -> std::result::Result<ModelIden, genai::resolver::Error> {
                 Ok(ModelIden::new(adapter, model_iden.model_name.clone()))
@@ -285,6 +320,7 @@ impl GenAIProvider {
             adapter: provider.adapter,
             model: model.unwrap_or(&provider.default_model().id).to_string(),
             system_prompt: None,
+            llm_logger,
         })
     }
 
@@ -294,6 +330,36 @@ impl GenAIProvider {
         self
     }
 
+    /// The same provider, connection and system prompt, answering with `model`
+    pub fn for_model(&self, model: impl Into<String>) -> Self {
+        Self {
+            client: self.client.clone(),
+            provider_id: self.provider_id.clone(),
+            adapter: self.adapter,
+            model: model.into(),
+            system_prompt: self.system_prompt.clone(),
+            llm_logger: self.llm_logger.clone(),
+        }
+    }
+
+    /// Replace the request logger (None disables logging)
+    pub fn with_llm_logger(mut self, logger: Option<LlmLogger>) -> Self {
+        self.llm_logger = logger;
+        self
+    }
+
+    /// Get the request logger, if logging is enabled
+    pub fn llm_logger(&self) -> Option<&LlmLogger> {
+        self.llm_logger.as_ref()
+    }
+
+    /// Append an interaction to the request log if logging is enabled
+    fn log_interaction(&self, config: LogConfig<'_>) {
+        if let Some(logger) = &self.llm_logger {
+            logger.log(config);
+        }
+    }
+
     /// Get the provider ID (e.g., "anthropic", "together")
     pub fn provider_id(&self) -> &str {
         &self.provider_id
@@ -314,6 +380,16 @@ impl GenAIProvider {
         &self,
         messages: Vec<ChatMessage>,
         tools: Option<Vec<ToolDefinition>>,
+    ) -> Result<CompletionResult> {
+        self.chat_with_params(messages, tools, &GenerationParams::default()).await
+    }
+
+    /// Like `chat`, overriding the provider's sampling defaults with `params`
+    pub async fn chat_with_params(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<Vec<ToolDefinition>>,
+        params: &GenerationParams,
     ) -> Result<CompletionResult> {
         // Keep copies for logging
         let messages_for_log = messages.clone();
@@ -347,13 +423,15 @@ impl GenAIProvider {
         );
 
         // Configure chat options
-        // Note: We don't set max_tokens because newer OpenAI models (gpt-5.x) require
-        // max_completion_tokens instead, and genai doesn't support that yet.
-        // APIs have sensible defaults so this is fine.
-        let capture_raw = std::env::var("LLM_LOG_FILE").is_ok();
-        let chat_options = ChatOptions::default()
-            .with_capture_usage(true)
-            .with_capture_raw_body(capture_raw);
+        // Note: max_tokens is only sent when the session sets it, because newer
+        // OpenAI models (gpt-5.x) require max_completion_tokens instead, and
+        // genai doesn't support that yet. APIs have sensible defaults otherwise.
+        // The raw body is always captured: it's the only place the finish reason appears.
+        let chat_options = params.apply(
+            ChatOptions::default()
+                .with_capture_usage(true)
+                .with_capture_raw_body(true),
+        );
 
         // Retry configuration
         let retry_config = RetryConfig::default();
@@ -374,19 +452,30 @@ impl GenAIProvider {
                     // Extract token usage and raw body BEFORE consuming response
                     let input_tokens = response.usage.prompt_tokens.map(|t| t as u64);
                     let output_tokens = response.usage.completion_tokens.map(|t| t as u64);
+                    let cached_tokens = usage_cached_tokens(&response.usage);
+                    let finish_reason = response.captured_raw_body.as_ref().and_then(FinishReason::from_raw_body);
                     // Convert JSON Value to pretty-printed string for logging
                     let captured_raw_body = response.captured_raw_body
                         .as_ref()
+                        .filter(|_| self.llm_logger.is_some())
                         .and_then(|v| serde_json::to_string_pretty(v).ok());
 
-                    // Extract content
-                    let content = response.first_text().map(|s| s.to_string());
+                    // Extract content (a refusal's explanation may live outside it)
+                    let content = response
+                        .first_text()
+                        .map(|s| s.to_string())
+                        .or_else(|| response.captured_raw_body.as_ref().and_then(refusal_message));
 
                     // Check for empty response - retry if configured
                     let is_empty = content.as_ref().map(|c| c.trim().is_empty()).unwrap_or(true);
                     let has_tool_calls = !response.tool_calls().is_empty();
+                    // A refusal or malformed call will be handled by the caller, asking again won't help
+                    let explained = matches!(
+                        finish_reason,
+                        Some(FinishReason::Refusal(_) | FinishReason::MalformedToolCall)
+                    );
 
-                    if is_empty && !has_tool_calls && empty_retries < retry_config.max_retries {
+                    if is_empty && !has_tool_calls && !explained && empty_retries < retry_config.max_retries {
                         empty_retries += 1;
                         warn!(
                             model = %self.model,
@@ -417,17 +506,23 @@ impl GenAIProvider {
                         })
                         .collect();
 
+                    if let Some(reason) = finish_reason.as_ref().filter(|r| **r != FinishReason::Stop && **r != FinishReason::ToolUse) {
+                        warn!(model = %self.model, finish_reason = ?reason, "Response did not finish normally");
+                    }
+
                     let result = CompletionResult {
                         content,
                         tool_calls,
                         input_tokens,
                         output_tokens,
+                        cached_tokens,
+                        finish_reason,
                     };
 
                     // Log successful interaction with raw HTTP body if captured
-                    log_llm_interaction(LogConfig {
+                    self.log_interaction(LogConfig {
                         model: &self.model,
-                        provider: Some("genai"),
+                        provider: Some(&self.provider_id),
                         system_prompt: self.system_prompt.as_deref(),
                         messages: &messages_for_log,
                         tools: tools_for_log.as_deref(),
@@ -462,9 +557,9 @@ impl GenAIProvider {
                         let (error_details, error_debug) = extract_genai_error_details(&e);
 
                         // Log to LLM log file so we have full context
-                        log_llm_interaction(LogConfig {
+                        self.log_interaction(LogConfig {
                             model: &self.model,
-                            provider: Some("genai"),
+                            provider: Some(&self.provider_id),
                             system_prompt: self.system_prompt.as_deref(),
                             messages: &messages_for_log,
                             tools: tools_for_log.as_deref(),
@@ -490,12 +585,12 @@ impl GenAIProvider {
 
                     // No retry - extract detailed error information
                     let (error_details, raw_body) = extract_genai_error_details(&e);
-                    let error_msg = format!("GenAI error: {}", error_details);
+                    let error_msg = format!("{}: {}", error_prefix("GenAI error", &e), error_details);
 
                     // Log failed interaction with request context and raw response
-                    log_llm_interaction(LogConfig {
+                    self.log_interaction(LogConfig {
                         model: &self.model,
-                        provider: Some("genai"),
+                        provider: Some(&self.provider_id),
                         system_prompt: self.system_prompt.as_deref(),
                         messages: &messages_for_log,
                         tools: tools_for_log.as_deref(),
@@ -549,10 +644,9 @@ impl GenAIProvider {
         }
 
         // Configure chat options (no max_tokens - see note in chat method)
-        let capture_raw = std::env::var("LLM_LOG_FILE").is_ok();
         let chat_options = ChatOptions::default()
             .with_capture_usage(true)
-            .with_capture_raw_body(capture_raw);
+            .with_capture_raw_body(true);
 
         // Execute the chat again (non-streaming)
         // Note: The client's model_mapper will ensure the correct adapter is used
@@ -568,9 +662,14 @@ impl GenAIProvider {
         // Extract token usage before consuming response
         let input_tokens = response.usage.prompt_tokens.map(|t| t as u64);
         let output_tokens = response.usage.completion_tokens.map(|t| t as u64);
+        let cached_tokens = usage_cached_tokens(&response.usage);
+        let finish_reason = response.captured_raw_body.as_ref().and_then(FinishReason::from_raw_body);
 
         // Extract content
-        let content = response.first_text().map(|s| s.to_string());
+        let content = response
+            .first_text()
+            .map(|s| s.to_string())
+            .or_else(|| response.captured_raw_body.as_ref().and_then(refusal_message));
 
         // Extract tool calls
         let tool_calls: Vec<ToolCall> = response
@@ -596,22 +695,57 @@ impl GenAIProvider {
             tool_calls,
             input_tokens,
             output_tokens,
+            cached_tokens,
+            finish_reason,
         })
     }
 
-    /// Execute a streaming chat completion
+    /// Execute a streaming chat completion (see `LlmProvider::stream`)
     ///
-    /// Sends text chunks to the provided channel as they arrive.
-    /// Returns the final CompletionResult with tool calls and token usage.
-    pub async fn chat_stream(
+    /// The request runs as the stream is polled; dropping the stream
+    /// cancels it.
+    pub fn chat_stream(
         &self,
         messages: Vec<ChatMessage>,
         tools: Option<Vec<ToolDefinition>>,
-        chunk_tx: mpsc::Sender<String>,
-    ) -> Result<CompletionResult> {
+        params: &GenerationParams,
+    ) -> EventStream<'_> {
+        let params = params.clone();
+        let (tx, rx) = futures::channel::mpsc::channel(32);
+        let request = async move {
+            self.run_stream(messages, tools, &params, EventSender { tx, sent: StreamAssembler::new() })
+                .await
+        };
+        // Drive the request while passing its events on; the stream ends
+        // once the request is done and its events are drained
+        let request = futures::stream::once(request).filter_map(|()| std::future::ready(None));
+        Box::pin(futures::stream::select(rx, request))
+    }
+
+    /// Send a streaming request, passing its events to `events`
+    async fn run_stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<Vec<ToolDefinition>>,
+        params: &GenerationParams,
+        mut events: EventSender,
+    ) {
         // Keep copies for logging
         let messages_for_log = messages.clone();
         let tools_for_log = tools.clone();
+        let log = |result: Option<&CompletionResult>, error: Option<&str>| {
+            self.log_interaction(LogConfig {
+                model: &self.model,
+                provider: Some(&self.provider_id),
+                streaming: true,
+                system_prompt: self.system_prompt.as_deref(),
+                messages: &messages_for_log,
+                tools: tools_for_log.as_deref(),
+                result,
+                error,
+                ..Default::default()
+            });
+        };
 
         let mut chat_req = ChatRequest::default();
 
@@ -640,150 +774,229 @@ impl GenAIProvider {
             "Sending streaming LLM request"
         );
 
-        // Configure chat options - capture usage, content, and tool calls at the end
-        let chat_options = ChatOptions::default()
-            .with_capture_usage(true)
-            .with_capture_content(true)
-            .with_capture_tool_calls(true);
+        // Configure chat options - capture usage and tool calls at the end
+        let chat_options = params.apply(
+            ChatOptions::default()
+                .with_capture_usage(true)
+                .with_capture_tool_calls(true),
+        );
 
         // Execute streaming request
-        let stream_response = self
+        let stream_response = match self
             .client
             .exec_chat_stream(&self.model, chat_req, Some(&chat_options))
             .await
-            .map_err(|e| {
+        {
+            Ok(response) => response,
+            Err(e) => {
                 let (error_details, _) = extract_genai_error_details(&e);
                 error!(error = %error_details, model = %self.model, "Streaming request failed");
-                Error::Provider(format!("GenAI streaming error: {}", error_details))
-            })?;
+                let error_msg = format!("{}: {}", error_prefix("GenAI streaming error", &e), error_details);
+                log(None, Some(&error_msg));
+                events.fail(error_msg).await;
+                return;
+            }
+        };
 
         let mut stream = stream_response.stream;
-        let mut content_buffer = String::new();
+        let mut tool_calls = StreamedToolCalls::default();
 
         // Process stream events
         while let Some(event_result) = stream.next().await {
-            match event_result {
-                Ok(event) => match event {
-                    ChatStreamEvent::Start => {
-                        debug!("Stream started");
+            let event = match event_result {
+                Ok(event) => event,
+                Err(e) => {
+                    let error_msg = format!("Stream error: {:?}", e);
+                    error!(error = %error_msg, model = %self.model, "Stream error");
+                    log(None, Some(&error_msg));
+                    events.fail(error_msg).await;
+                    return;
+                }
+            };
+            let sent = match event {
+                ChatStreamEvent::Start => {
+                    debug!("Stream started");
+                    true
+                }
+                ChatStreamEvent::Chunk(chunk) => events.send(StreamEvent::TextDelta(chunk.content)).await,
+                ChatStreamEvent::ReasoningChunk(_) | ChatStreamEvent::ThoughtSignatureChunk(_) => {
+                    // Skip reasoning/thought chunks for now
+                    true
+                }
+                ChatStreamEvent::ToolCallChunk(tool_chunk) => {
+                    debug!(tool_name = %tool_chunk.tool_call.fn_name, "Received tool call chunk");
+                    match tool_calls.delta(&tool_chunk.tool_call) {
+                        Some(delta) => events.send(delta).await,
+                        None => true,
                     }
-                    ChatStreamEvent::Chunk(chunk) => {
-                        // Send text chunk to caller
-                        content_buffer.push_str(&chunk.content);
-                        if chunk_tx.send(chunk.content).await.is_err() {
-                            debug!("Chunk receiver dropped, stream cancelled");
+                }
+                ChatStreamEvent::End(end_event) => {
+                    let usage = end_event.captured_usage.as_ref();
+                    let input_tokens = usage.and_then(|u| u.prompt_tokens).map(|t| t as u64);
+                    let output_tokens = usage.and_then(|u| u.completion_tokens).map(|t| t as u64);
+                    let cached_tokens = usage.and_then(usage_cached_tokens);
+
+                    // Calls no chunk announced (a provider may only report them here)
+                    for call in end_event.captured_into_tool_calls().unwrap_or_default() {
+                        if !tool_calls.seen(&call)
+                            && let Some(delta) = tool_calls.delta(&call)
+                            && !events.send(delta).await
+                        {
                             break;
                         }
                     }
-                    ChatStreamEvent::ReasoningChunk(_) | ChatStreamEvent::ThoughtSignatureChunk(_) => {
-                        // Skip reasoning/thought chunks for now
-                    }
-                    ChatStreamEvent::ToolCallChunk(tool_chunk) => {
-                        debug!(
-                            tool_name = %tool_chunk.tool_call.fn_name,
-                            "Received tool call chunk"
-                        );
-                    }
-                    ChatStreamEvent::End(end_event) => {
-                        // Extract usage first (before consuming end_event)
-                        let input_tokens = end_event.captured_usage
-                            .as_ref()
-                            .and_then(|u| u.prompt_tokens)
-                            .map(|t| t as u64);
-                        let output_tokens = end_event.captured_usage
-                            .as_ref()
-                            .and_then(|u| u.completion_tokens)
-                            .map(|t| t as u64);
-
-                        // Get captured text (non-consuming)
-                        let captured_text = end_event.captured_first_text().map(|s| s.to_string());
-
-                        // Check if we have tool calls before consuming
-                        let raw_tool_calls = end_event.captured_tool_calls();
-                        let tool_call_count = raw_tool_calls.as_ref().map(|v| v.len()).unwrap_or(0);
-
-                        debug!(
-                            input_tokens = ?input_tokens,
-                            output_tokens = ?output_tokens,
-                            captured_text_len = captured_text.as_ref().map(|s| s.len()).unwrap_or(0),
-                            tool_call_count = tool_call_count,
-                            "Stream ended"
-                        );
 
-                        // Get tool calls from captured content (consumes end_event)
-                        let tool_calls: Vec<ToolCall> = end_event
-                            .captured_into_tool_calls()
-                            .unwrap_or_default()
-                            .into_iter()
-                            .filter(|tc| {
-                                if tc.fn_name.is_empty() {
-                                    warn!("Received tool call with empty name, skipping");
-                                    return false;
-                                }
-                                debug!(
-                                    tool_name = %tc.fn_name,
-                                    call_id = %tc.call_id,
-                                    "Received tool call from stream"
-                                );
-                                true
+                    debug!(
+                        input_tokens = ?input_tokens,
+                        output_tokens = ?output_tokens,
+                        tool_call_count = tool_calls.len(),
+                        "Stream ended"
+                    );
+
+                    // The stream doesn't carry the stop reason; only a full max_tokens budget gives it away
+                    let finish_reason = params
+                        .max_tokens
+                        .zip(output_tokens)
+                        .filter(|(max, used)| *used >= u64::from(*max))
+                        .map(|_| FinishReason::Length);
+
+                    if input_tokens.is_some() || output_tokens.is_some() {
+                        events
+                            .send(StreamEvent::UsageFinal {
+                                input: input_tokens,
+                                output: output_tokens,
+                                cached: cached_tokens,
                             })
-                            .collect();
-
-                        // Use captured text or our buffer
-                        let content = captured_text
-                            .or_else(|| if content_buffer.is_empty() { None } else { Some(content_buffer) });
-
-                        let result = CompletionResult {
-                            content,
-                            tool_calls,
-                            input_tokens,
-                            output_tokens,
-                        };
-
-                        // Log successful interaction
-                        log_llm_interaction(LogConfig {
-                            model: &self.model,
-                            provider: Some("genai-stream"),
-                            system_prompt: self.system_prompt.as_deref(),
-                            messages: &messages_for_log,
-                            tools: tools_for_log.as_deref(),
-                            result: Some(&result),
-                            ..Default::default()
-                        });
-
-                        return Ok(result);
+                            .await;
                     }
-                },
-                Err(e) => {
-                    let error_msg = format!("Stream error: {:?}", e);
-                    error!(error = %error_msg, model = %self.model, "Stream error");
+                    events.send(StreamEvent::Done { finish_reason }).await;
 
-                    log_llm_interaction(LogConfig {
-                        model: &self.model,
-                        provider: Some("genai-stream"),
-                        system_prompt: self.system_prompt.as_deref(),
-                        messages: &messages_for_log,
-                        tools: tools_for_log.as_deref(),
-                        error: Some(&error_msg),
-                        ..Default::default()
-                    });
-
-                    return Err(Error::Provider(error_msg));
+                    // Log successful interaction
+                    log(Some(&events.sent.finish()), None);
+                    return;
                 }
+            };
+            if !sent {
+                debug!("Event receiver dropped, stream cancelled");
+                return;
             }
         }
 
         // Stream ended without End event (shouldn't happen normally)
         warn!("Stream ended unexpectedly without End event");
-        Ok(CompletionResult {
-            content: if content_buffer.is_empty() { None } else { Some(content_buffer) },
-            tool_calls: vec![],
-            input_tokens: None,
-            output_tokens: None,
+        events.send(StreamEvent::Done { finish_reason: None }).await;
+    }
+}
+
+impl LlmProvider for GenAIProvider {
+    fn provider_id(&self) -> &str {
+        &self.provider_id
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn complete(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<Vec<ToolDefinition>>,
+        params: &GenerationParams,
+    ) -> BoxFuture<'_, Result<CompletionResult>> {
+        let params = params.clone();
+        Box::pin(async move { self.chat_with_params(messages, tools, &params).await })
+    }
+
+    fn stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<Vec<ToolDefinition>>,
+        params: &GenerationParams,
+    ) -> EventStream<'_> {
+        self.chat_stream(messages, tools, params)
+    }
+}
+
+/// Passes a stream's events on, keeping what was sent for the request log
+struct EventSender {
+    tx: futures::channel::mpsc::Sender<Result<StreamEvent>>,
+    sent: StreamAssembler,
+}
+
+impl EventSender {
+    /// Send `event`; false once the receiver is gone
+    async fn send(&mut self, event: StreamEvent) -> bool {
+        self.sent.push(event.clone());
+        self.tx.send(Ok(event)).await.is_ok()
+    }
+
+    /// End the stream with an error
+    async fn fail(&mut self, message: String) {
+        let _ = self.tx.send(Err(Error::Provider(message))).await;
+    }
+}
+
+/// The tool calls a stream has passed on, in order, with how much of
+/// their arguments was sent
+#[derive(Default)]
+struct StreamedToolCalls {
+    calls: Vec<(String, usize)>,
+}
+
+impl StreamedToolCalls {
+    fn len(&self) -> usize {
+        self.calls.len()
+    }
+
+    fn seen(&self, call: &ToolCall) -> bool {
+        self.calls.iter().any(|(id, _)| *id == call.call_id)
+    }
+
+    /// The part of `call` not sent yet, if any
+    ///
+    /// genai hands on OpenAI-style arguments as the text so far, and other
+    /// providers' as the whole value at once.
+    fn delta(&mut self, call: &ToolCall) -> Option<StreamEvent> {
+        if call.fn_name.is_empty() {
+            warn!("Received tool call with empty name, skipping");
+            return None;
+        }
+        let arguments = match &call.fn_arguments {
+            serde_json::Value::String(text) => text.clone(),
+            serde_json::Value::Null => String::new(),
+            value => value.to_string(),
+        };
+        let (index, first) = match self.calls.iter().position(|(id, _)| *id == call.call_id) {
+            Some(index) => (index, false),
+            None => {
+                self.calls.push((call.call_id.clone(), 0));
+                (self.calls.len() - 1, true)
+            }
+        };
+        let sent = &mut self.calls[index].1;
+        let partial_json = arguments.get(*sent..).unwrap_or_default().to_string();
+        if partial_json.is_empty() && !first {
+            return None;
+        }
+        *sent = arguments.len();
+        Some(StreamEvent::ToolCallDelta {
+            index,
+            id: first.then(|| call.call_id.clone()),
+            name: first.then(|| call.fn_name.clone()),
+            partial_json,
         })
     }
 }
 
+/// Prompt-cache hits reported in a response's usage
+fn usage_cached_tokens(usage: &genai::chat::Usage) -> Option<u64> {
+    usage
+        .prompt_tokens_details
+        .as_ref()
+        .and_then(|details| details.cached_tokens)
+        .map(|t| t as u64)
+}
+
 impl GenAIProvider {
     /// Get the provider name/ID
     pub fn name(&self) -> &str {
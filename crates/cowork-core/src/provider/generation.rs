@@ -0,0 +1,152 @@
+//! Per-session generation parameters
+//!
+//! `GenerationParams` overrides the provider's sampling defaults for a
+//! session's LLM requests. Unset fields keep the provider default.
+//! Out-of-range values are clamped (with a warning) rather than rejected.
+
+use genai::chat::ChatOptions;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+/// Valid temperature range across supported providers
+pub const TEMPERATURE_RANGE: (f32, f32) = (0.0, 2.0);
+
+/// Valid nucleus sampling range
+pub const TOP_P_RANGE: (f32, f32) = (0.0, 1.0);
+
+/// Sampling and length parameters sent with each LLM request
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GenerationParams {
+    /// Sampling temperature (0.0 - 2.0)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    /// Upper bound on generated tokens
+    ///
+    /// genai sends this as `max_tokens`, which some newer OpenAI models
+    /// reject in favour of `max_completion_tokens`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    /// Nucleus sampling probability mass (0.0 - 1.0)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    /// Sequences that end generation when produced
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub stop_sequences: Vec<String>,
+}
+
+impl GenerationParams {
+    /// Whether every parameter is left to the provider default
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Clamp values into their valid ranges, warning about each change
+    pub fn clamped(mut self) -> Self {
+        self.temperature = clamp_f32("temperature", self.temperature, TEMPERATURE_RANGE);
+        self.top_p = clamp_f32("top_p", self.top_p, TOP_P_RANGE);
+        if self.max_tokens == Some(0) {
+            warn!("max_tokens must be at least 1, using 1");
+            self.max_tokens = Some(1);
+        }
+        let before = self.stop_sequences.len();
+        self.stop_sequences.retain(|s| !s.is_empty());
+        if self.stop_sequences.len() != before {
+            warn!("Ignoring empty stop sequences");
+        }
+        self
+    }
+
+    /// Set these parameters on genai chat options
+    pub fn apply(&self, mut options: ChatOptions) -> ChatOptions {
+        if let Some(temperature) = self.temperature {
+            options = options.with_temperature(temperature as f64);
+        }
+        if let Some(max_tokens) = self.max_tokens {
+            options = options.with_max_tokens(max_tokens);
+        }
+        if let Some(top_p) = self.top_p {
+            options = options.with_top_p(top_p as f64);
+        }
+        if !self.stop_sequences.is_empty() {
+            options = options.with_stop_sequences(self.stop_sequences.clone());
+        }
+        options
+    }
+}
+
+impl std::fmt::Display for GenerationParams {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fn or_default<T: ToString>(value: Option<T>) -> String {
+            value.map(|v| v.to_string()).unwrap_or_else(|| "default".to_string())
+        }
+        write!(
+            f,
+            "temperature={} max_tokens={} top_p={} stop={}",
+            or_default(self.temperature),
+            or_default(self.max_tokens),
+            or_default(self.top_p),
+            if self.stop_sequences.is_empty() {
+                "none".to_string()
+            } else {
+                format!("{:?}", self.stop_sequences)
+            }
+        )
+    }
+}
+
+fn clamp_f32(name: &str, value: Option<f32>, (min, max): (f32, f32)) -> Option<f32> {
+    let value = value?;
+    if value.is_nan() {
+        warn!("{} is not a number, using the provider default", name);
+        return None;
+    }
+    let clamped = value.clamp(min, max);
+    if clamped != value {
+        warn!("{} {} is outside {}-{}, using {}", name, value, min, max, clamped);
+    }
+    Some(clamped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clamps_out_of_range_values() {
+        let params = GenerationParams {
+            temperature: Some(3.5),
+            max_tokens: Some(0),
+            top_p: Some(-0.2),
+            stop_sequences: vec!["".to_string(), "###".to_string()],
+        }
+        .clamped();
+
+        assert_eq!(params.temperature, Some(2.0));
+        assert_eq!(params.max_tokens, Some(1));
+        assert_eq!(params.top_p, Some(0.0));
+        assert_eq!(params.stop_sequences, vec!["###"]);
+    }
+
+    #[test]
+    fn test_nan_falls_back_to_default() {
+        let params = GenerationParams { temperature: Some(f32::NAN), ..Default::default() }.clamped();
+        assert_eq!(params.temperature, None);
+        assert!(params.is_default());
+    }
+
+    #[test]
+    fn test_apply_sets_chat_options() {
+        let params = GenerationParams {
+            temperature: Some(0.2),
+            max_tokens: Some(512),
+            top_p: None,
+            stop_sequences: vec!["END".to_string()],
+        };
+        let options = params.apply(ChatOptions::default());
+        assert_eq!(options.temperature, Some(0.2f32 as f64));
+        assert_eq!(options.max_tokens, Some(512));
+        assert_eq!(options.top_p, None);
+        assert_eq!(options.stop_sequences, vec!["END"]);
+    }
+}
@@ -0,0 +1,369 @@
+//! The interface the agent loop talks to a model through
+//!
+//! `LlmProvider::complete` answers a request in one piece; `stream` answers
+//! it as a sequence of `StreamEvent`s, so a caller can show text as it
+//! arrives. Providers without a streaming API get a `stream` that sends the
+//! completed response as a single chunk. `StreamAssembler` puts the events
+//! back together into a `CompletionResult`, including the tool calls whose
+//! arguments arrive as JSON fragments.
+
+use std::future::Future;
+use std::pin::Pin;
+
+use futures::{Stream, StreamExt};
+use serde_json::Value;
+use tracing::warn;
+
+use super::{ChatMessage, CompletionResult, FinishReason, GenerationParams, ToolCall};
+use crate::error::Result;
+use crate::tools::ToolDefinition;
+
+/// A boxed future, as `LlmProvider::complete` returns
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// The events of a streamed response, as `LlmProvider::stream` returns
+pub type EventStream<'a> = Pin<Box<dyn Stream<Item = Result<StreamEvent>> + Send + 'a>>;
+
+/// One piece of a streamed response
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamEvent {
+    /// More of the response text
+    TextDelta(String),
+    /// More of the arguments of the `index`th tool call, as JSON text
+    ///
+    /// The call's ID and name come with its first fragment (a later one
+    /// may repeat them). Fragments can end anywhere, even mid-string.
+    ToolCallDelta {
+        index: usize,
+        id: Option<String>,
+        name: Option<String>,
+        partial_json: String,
+    },
+    /// Token usage for the whole request
+    UsageFinal {
+        input: Option<u64>,
+        output: Option<u64>,
+        /// Input tokens read from the prompt cache
+        cached: Option<u64>,
+    },
+    /// The response is complete
+    Done { finish_reason: Option<FinishReason> },
+}
+
+/// A model backend
+pub trait LlmProvider: Send + Sync {
+    /// Provider ID (e.g., "anthropic", "together")
+    fn provider_id(&self) -> &str;
+
+    fn model(&self) -> &str;
+
+    /// Answer `messages`, offering `tools`, in one piece
+    fn complete(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<Vec<ToolDefinition>>,
+        params: &GenerationParams,
+    ) -> BoxFuture<'_, Result<CompletionResult>>;
+
+    /// Answer `messages` as a stream of events, ending with `Done`
+    ///
+    /// By default the response from `complete` arrives as one chunk.
+    /// A request that fails yields a single error.
+    fn stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<Vec<ToolDefinition>>,
+        params: &GenerationParams,
+    ) -> EventStream<'_> {
+        let request = self.complete(messages, tools, params);
+        Box::pin(futures::stream::once(request).flat_map(|result| {
+            let events = match result {
+                Ok(result) => completion_events(result).into_iter().map(Ok).collect(),
+                Err(e) => vec![Err(e)],
+            };
+            futures::stream::iter(events)
+        }))
+    }
+}
+
+/// A complete response as stream events: its text, each tool call in one
+/// fragment, its usage and `Done`
+pub fn completion_events(result: CompletionResult) -> Vec<StreamEvent> {
+    let mut events = Vec::new();
+    if let Some(text) = result.content.filter(|text| !text.is_empty()) {
+        events.push(StreamEvent::TextDelta(text));
+    }
+    for (index, call) in result.tool_calls.into_iter().enumerate() {
+        events.push(StreamEvent::ToolCallDelta {
+            index,
+            id: Some(call.call_id),
+            name: Some(call.fn_name),
+            partial_json: call.fn_arguments.to_string(),
+        });
+    }
+    if result.input_tokens.is_some() || result.output_tokens.is_some() {
+        events.push(StreamEvent::UsageFinal {
+            input: result.input_tokens,
+            output: result.output_tokens,
+            cached: result.cached_tokens,
+        });
+    }
+    events.push(StreamEvent::Done { finish_reason: result.finish_reason });
+    events
+}
+
+/// Read `events` to the end into a `CompletionResult`, handing each piece
+/// of text to `on_text` as it arrives
+pub async fn collect_stream(mut events: EventStream<'_>, mut on_text: impl FnMut(&str)) -> Result<CompletionResult> {
+    let mut assembler = StreamAssembler::new();
+    while let Some(event) = events.next().await {
+        let event = event?;
+        if let StreamEvent::TextDelta(text) = &event {
+            on_text(text);
+        }
+        assembler.push(event);
+    }
+    Ok(assembler.finish())
+}
+
+/// A tool call being streamed
+#[derive(Debug, Default)]
+struct PartialToolCall {
+    id: Option<String>,
+    name: Option<String>,
+    /// Argument JSON so far, as bytes: a fragment read off the wire can end
+    /// partway through a character
+    json: Vec<u8>,
+}
+
+/// Builds a `CompletionResult` from stream events
+#[derive(Debug, Default)]
+pub struct StreamAssembler {
+    text: String,
+    tool_calls: Vec<PartialToolCall>,
+    input_tokens: Option<u64>,
+    output_tokens: Option<u64>,
+    cached_tokens: Option<u64>,
+    finish_reason: Option<FinishReason>,
+}
+
+impl StreamAssembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the next event
+    pub fn push(&mut self, event: StreamEvent) {
+        match event {
+            StreamEvent::TextDelta(text) => self.text.push_str(&text),
+            StreamEvent::ToolCallDelta { index, id, name, partial_json } => {
+                let call = self.tool_call(index);
+                if id.is_some() {
+                    call.id = id;
+                }
+                if name.is_some() {
+                    call.name = name;
+                }
+                call.json.extend_from_slice(partial_json.as_bytes());
+            }
+            StreamEvent::UsageFinal { input, output, cached } => {
+                self.input_tokens = input;
+                self.output_tokens = output;
+                self.cached_tokens = cached;
+            }
+            StreamEvent::Done { finish_reason } => self.finish_reason = finish_reason,
+        }
+    }
+
+    /// Add raw bytes of the `index`th tool call's arguments, for a
+    /// provider that reads them off the wire undecoded
+    pub fn push_tool_json_bytes(&mut self, index: usize, bytes: &[u8]) {
+        self.tool_call(index).json.extend_from_slice(bytes);
+    }
+
+    fn tool_call(&mut self, index: usize) -> &mut PartialToolCall {
+        if self.tool_calls.len() <= index {
+            self.tool_calls.resize_with(index + 1, PartialToolCall::default);
+        }
+        &mut self.tool_calls[index]
+    }
+
+    /// The assembled response
+    ///
+    /// Calls without a name are dropped. Arguments that aren't valid JSON
+    /// are kept as a string, so the tool reports them to the model.
+    pub fn finish(self) -> CompletionResult {
+        let tool_calls = self
+            .tool_calls
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, call)| {
+                let Some(name) = call.name.filter(|name| !name.is_empty()) else {
+                    warn!(index, "Streamed tool call has no name, skipping");
+                    return None;
+                };
+                let arguments = if call.json.iter().all(u8::is_ascii_whitespace) {
+                    Value::Object(Default::default())
+                } else {
+                    serde_json::from_slice(&call.json).unwrap_or_else(|e| {
+                        warn!(tool_name = %name, error = %e, "Streamed tool call arguments are not valid JSON");
+                        Value::String(String::from_utf8_lossy(&call.json).into_owned())
+                    })
+                };
+                Some(ToolCall {
+                    call_id: call.id.unwrap_or_else(|| format!("call_{}", index)),
+                    fn_name: name,
+                    fn_arguments: arguments,
+                    thought_signatures: None,
+                })
+            })
+            .collect();
+
+        CompletionResult {
+            content: if self.text.is_empty() { None } else { Some(self.text) },
+            tool_calls,
+            input_tokens: self.input_tokens,
+            output_tokens: self.output_tokens,
+            cached_tokens: self.cached_tokens,
+            finish_reason: self.finish_reason,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn fragment(index: usize, partial_json: &str) -> StreamEvent {
+        StreamEvent::ToolCallDelta { index, id: None, name: None, partial_json: partial_json.to_string() }
+    }
+
+    fn start(index: usize, id: &str, name: &str) -> StreamEvent {
+        StreamEvent::ToolCallDelta {
+            index,
+            id: Some(id.to_string()),
+            name: Some(name.to_string()),
+            partial_json: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_reassembles_tool_calls_split_across_chunks() {
+        let mut assembler = StreamAssembler::new();
+        assembler.push(StreamEvent::TextDelta("Reading ".to_string()));
+        assembler.push(start(0, "call_a", "Read"));
+        assembler.push(start(1, "call_b", "Grep"));
+        assembler.push(StreamEvent::TextDelta("both".to_string()));
+        // Interleaved, split mid-key, mid-string and inside an escape
+        for event in [
+            fragment(0, "{\"file_"),
+            fragment(1, "{\"pattern\": \"a\\"),
+            fragment(0, "path\": \"src/ma"),
+            fragment(1, "\"b\"}"),
+            fragment(0, "in.rs\"}"),
+        ] {
+            assembler.push(event);
+        }
+        assembler.push(StreamEvent::UsageFinal { input: Some(10), output: Some(4), cached: None });
+        assembler.push(StreamEvent::Done { finish_reason: Some(FinishReason::ToolUse) });
+
+        let result = assembler.finish();
+        assert_eq!(result.content.as_deref(), Some("Reading both"));
+        assert_eq!(result.tool_calls.len(), 2);
+        assert_eq!(result.tool_calls[0].call_id, "call_a");
+        assert_eq!(result.tool_calls[0].fn_arguments, json!({"file_path": "src/main.rs"}));
+        assert_eq!(result.tool_calls[1].fn_name, "Grep");
+        assert_eq!(result.tool_calls[1].fn_arguments, json!({"pattern": "a\"b"}));
+        assert_eq!((result.input_tokens, result.output_tokens), (Some(10), Some(4)));
+        assert_eq!(result.finish_reason, Some(FinishReason::ToolUse));
+    }
+
+    #[test]
+    fn test_reassembles_arguments_split_inside_a_character() {
+        let json = "{\"content\": \"caf\u{e9} \u{1f600}\"}".as_bytes();
+        let e_acute = json.iter().position(|&b| b == 0xc3).unwrap();
+        let emoji = json.iter().position(|&b| b == 0xf0).unwrap();
+
+        let mut assembler = StreamAssembler::new();
+        assembler.push(start(0, "call_1", "Write"));
+        // Split between the two bytes of "é" and inside the four of the emoji
+        assembler.push_tool_json_bytes(0, &json[..e_acute + 1]);
+        assembler.push_tool_json_bytes(0, &json[e_acute + 1..emoji + 2]);
+        assembler.push_tool_json_bytes(0, &json[emoji + 2..]);
+        // And an escaped surrogate pair split between its halves
+        assembler.push(start(1, "call_2", "Write"));
+        assembler.push(fragment(1, "{\"content\": \"\\ud83d"));
+        assembler.push(fragment(1, "\\ude00\"}"));
+
+        let result = assembler.finish();
+        assert_eq!(result.tool_calls[0].fn_arguments, json!({"content": "caf\u{e9} \u{1f600}"}));
+        assert_eq!(result.tool_calls[1].fn_arguments, json!({"content": "\u{1f600}"}));
+    }
+
+    #[test]
+    fn test_missing_or_broken_arguments() {
+        let mut assembler = StreamAssembler::new();
+        assembler.push(start(0, "call_1", "TodoWrite"));
+        assembler.push(start(1, "call_2", "Bash"));
+        assembler.push(fragment(1, "{\"command\": \"ls"));
+        assembler.push(fragment(2, "{}"));
+
+        let result = assembler.finish();
+        assert_eq!(result.tool_calls.len(), 2, "the call without a name is dropped");
+        assert_eq!(result.tool_calls[0].fn_arguments, json!({}));
+        assert_eq!(result.tool_calls[1].fn_arguments, json!("{\"command\": \"ls"));
+    }
+
+    struct Fixed(CompletionResult);
+
+    impl LlmProvider for Fixed {
+        fn provider_id(&self) -> &str {
+            "fixed"
+        }
+
+        fn model(&self) -> &str {
+            "fixed"
+        }
+
+        fn complete(
+            &self,
+            _messages: Vec<ChatMessage>,
+            _tools: Option<Vec<ToolDefinition>>,
+            _params: &GenerationParams,
+        ) -> BoxFuture<'_, Result<CompletionResult>> {
+            let result = self.0.clone();
+            Box::pin(async move { Ok(result) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_default_stream_is_the_completion_in_one_chunk() {
+        let provider = Fixed(CompletionResult {
+            content: Some("Let me look".to_string()),
+            tool_calls: vec![ToolCall {
+                call_id: "call_1".to_string(),
+                fn_name: "Read".to_string(),
+                fn_arguments: json!({"file_path": "a.txt"}),
+                thought_signatures: None,
+            }],
+            input_tokens: Some(7),
+            output_tokens: Some(3),
+            ..Default::default()
+        });
+        let events: Vec<_> = provider
+            .stream(vec![], None, &GenerationParams::default())
+            .map(|event| event.unwrap())
+            .collect()
+            .await;
+        assert_eq!(events.len(), 4);
+        assert_eq!(events[0], StreamEvent::TextDelta("Let me look".to_string()));
+        assert_eq!(events[3], StreamEvent::Done { finish_reason: None });
+
+        let mut assembler = StreamAssembler::new();
+        events.into_iter().for_each(|event| assembler.push(event));
+        let result = assembler.finish();
+        assert_eq!(result.tool_calls[0].fn_arguments, json!({"file_path": "a.txt"}));
+        assert_eq!(result.input_tokens, Some(7));
+    }
+}
@@ -1,34 +1,64 @@
 //! LLM request/response logging utilities
 //!
-//! Provides shared logging functionality for all provider implementations.
-//! Set the `LLM_LOG_FILE` environment variable to enable detailed logging
-//! of all LLM requests and responses to a JSON file.
+//! Provides opt-in request logging for all provider implementations. When
+//! enabled, every LLM call appends one JSON record (JSONL) containing the
+//! full message array, tool definitions, and the parsed response with token
+//! usage.
 //!
-//! Example: `LLM_LOG_FILE=/tmp/llm.log cowork`
+//! Logging is enabled by either:
+//! - `[general] llm_log = "path"` in the config file
+//! - The `COWORK_LLM_LOG` environment variable (overrides the config path)
+//!
+//! The legacy `LLM_LOG_FILE` variable is still honored.
+//!
+//! API keys, Authorization headers, and any `llm_log_redact` patterns from
+//! config are replaced with `[REDACTED]` before a record is written. The log
+//! is rotated to `<path>.1` once it grows past `llm_log_max_bytes`.
+//!
+//! Example: `COWORK_LLM_LOG=/tmp/llm.jsonl cowork`
 
-use serde_json::json;
-use std::io::Write;
+use regex::Regex;
+use serde_json::{json, Value};
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
 use tracing::{debug, warn};
 
+use crate::config::{ConfigManager, GeneralConfig};
 use crate::tools::ToolDefinition;
 use super::genai_provider::CompletionResult;
 use super::ChatMessage;
 
-/// Convert ChatMessage to JSON for logging
-fn message_to_json(msg: &ChatMessage) -> serde_json::Value {
-    json!({
-        "role": format!("{:?}", msg.role),
-        "content": super::message_text_content(msg)
-    })
-}
+/// Environment variable that enables request logging (overrides config)
+pub const LLM_LOG_ENV: &str = "COWORK_LLM_LOG";
+
+/// Legacy environment variable, kept for backwards compatibility
+const LEGACY_LLM_LOG_ENV: &str = "LLM_LOG_FILE";
+
+/// Default size at which the log file is rotated (10 MiB)
+pub const DEFAULT_LLM_LOG_MAX_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Replacement text for redacted values
+const REDACTED: &str = "[REDACTED]";
+
+/// Object keys whose values are always redacted
+const SENSITIVE_KEYS: &[&str] = &["authorization", "x-api-key", "api-key", "api_key", "x-goog-api-key"];
+
+/// Matches credential headers embedded in free text (e.g., raw HTTP error bodies)
+static CREDENTIAL_HEADER_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)((?:authorization|x-api-key|api-key|x-goog-api-key)"?\s*[:=]\s*"?)(?:bearer\s+)?[^"\s,}]+"#)
+        .expect("valid credential header regex")
+});
 
 /// Configuration for what to include in the log entry
 #[derive(Default)]
 pub struct LogConfig<'a> {
     /// The model used for this request
     pub model: &'a str,
-    /// Provider name (e.g., "genai", "rig")
+    /// Provider ID (e.g., "anthropic", "openai")
     pub provider: Option<&'a str>,
+    /// Whether the request used the streaming API
+    pub streaming: bool,
     /// System prompt if available
     pub system_prompt: Option<&'a str>,
     /// Messages in the request
@@ -43,36 +73,202 @@ pub struct LogConfig<'a> {
     pub error: Option<&'a str>,
 }
 
-/// Log an LLM request/response interaction to file if LLM_LOG_FILE is set
-///
-/// This is the unified logging function used by all providers. It writes
-/// a JSON object for each interaction, appending to the log file.
-///
-/// # Arguments
-/// * `config` - Configuration containing all data to log
-pub fn log_llm_interaction(config: LogConfig<'_>) {
-    let log_file = match std::env::var("LLM_LOG_FILE") {
-        Ok(path) => path,
-        Err(_) => return, // No logging if env var not set
-    };
-
-    let messages_json: Vec<serde_json::Value> = config.messages.iter()
-        .map(message_to_json)
-        .collect();
-
-    let entry = json!({
+/// Appends redacted LLM request/response records to a JSONL file
+#[derive(Debug, Clone)]
+pub struct LlmLogger {
+    path: PathBuf,
+    max_bytes: u64,
+    redactions: Vec<Regex>,
+    secrets: Vec<String>,
+}
+
+impl LlmLogger {
+    /// Create a logger writing to the given path with default settings
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            max_bytes: DEFAULT_LLM_LOG_MAX_BYTES,
+            redactions: Vec::new(),
+            secrets: Vec::new(),
+        }
+    }
+
+    /// Set the size at which the log is rotated
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Add regex patterns whose matches are redacted
+    ///
+    /// Invalid patterns are skipped with a warning.
+    pub fn with_redactions(mut self, patterns: &[String]) -> Self {
+        for pattern in patterns {
+            match Regex::new(pattern) {
+                Ok(re) => self.redactions.push(re),
+                Err(e) => warn!("Ignoring invalid llm_log_redact pattern '{}': {}", pattern, e),
+            }
+        }
+        self
+    }
+
+    /// Add a literal secret (e.g., the provider API key) that must never be logged
+    pub fn with_secret(mut self, secret: impl Into<String>) -> Self {
+        let secret = secret.into();
+        if !secret.is_empty() {
+            self.secrets.push(secret);
+        }
+        self
+    }
+
+    /// Get the log file path
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Build a logger from general config, with the environment overriding the path
+    ///
+    /// Returns None when logging is not enabled.
+    pub fn from_config(general: &GeneralConfig) -> Option<Self> {
+        let path = log_path_from_env().or_else(|| general.llm_log.clone())?;
+        Some(
+            Self::new(path)
+                .with_max_bytes(general.llm_log_max_bytes)
+                .with_redactions(&general.llm_log_redact),
+        )
+    }
+
+    /// Build a logger from the environment and the config file on disk
+    pub fn load() -> Option<Self> {
+        match ConfigManager::new() {
+            Ok(manager) => Self::from_config(&manager.config().general),
+            Err(_) => log_path_from_env().map(Self::new),
+        }
+    }
+
+    /// Log an LLM request/response interaction
+    ///
+    /// Writes one JSON object per line, appending to the log file.
+    pub fn log(&self, config: LogConfig<'_>) {
+        let mut entry = build_entry(&config);
+        self.redact(&mut entry);
+
+        if let Err(e) = self.rotate_if_needed() {
+            warn!("Failed to rotate LLM log file {}: {}", self.path.display(), e);
+        }
+
+        if let Some(parent) = self.path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            let _ = std::fs::create_dir_all(parent);
+        }
+
+        match std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+        {
+            Ok(mut file) => {
+                let line = serde_json::to_string(&entry).unwrap_or_default();
+                if let Err(e) = writeln!(file, "{}", line) {
+                    warn!("Failed to write to LLM log file: {}", e);
+                }
+            }
+            Err(e) => {
+                warn!("Failed to open LLM log file {}: {}", self.path.display(), e);
+            }
+        }
+
+        debug!("Logged LLM interaction to {}", self.path.display());
+    }
+
+    /// Move the current log aside once it exceeds the size limit
+    fn rotate_if_needed(&self) -> std::io::Result<()> {
+        match std::fs::metadata(&self.path) {
+            Ok(meta) if self.max_bytes > 0 && meta.len() >= self.max_bytes => {
+                std::fs::rename(&self.path, rotated_path(&self.path))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Redact secrets from every string in a JSON value
+    fn redact(&self, value: &mut Value) {
+        match value {
+            Value::String(s) => *s = self.redact_str(s),
+            Value::Array(items) => items.iter_mut().for_each(|v| self.redact(v)),
+            Value::Object(map) => {
+                for (key, v) in map.iter_mut() {
+                    if SENSITIVE_KEYS.iter().any(|k| key.eq_ignore_ascii_case(k)) && !v.is_null() {
+                        *v = Value::String(REDACTED.to_string());
+                    } else {
+                        self.redact(v);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Redact secrets, credential headers, and configured patterns from text
+    fn redact_str(&self, text: &str) -> String {
+        let mut out = text.to_string();
+        for secret in &self.secrets {
+            out = out.replace(secret.as_str(), REDACTED);
+        }
+        out = CREDENTIAL_HEADER_RE
+            .replace_all(&out, format!("${{1}}{}", REDACTED))
+            .into_owned();
+        for re in &self.redactions {
+            out = re.replace_all(&out, REDACTED).into_owned();
+        }
+        out
+    }
+}
+
+/// Log path from `COWORK_LLM_LOG` (or the legacy `LLM_LOG_FILE`)
+fn log_path_from_env() -> Option<PathBuf> {
+    [LLM_LOG_ENV, LEGACY_LLM_LOG_ENV]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|v| !v.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Path the log is rotated to
+fn rotated_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(".1");
+    PathBuf::from(name)
+}
+
+/// Build the JSON record for an interaction
+fn build_entry(config: &LogConfig<'_>) -> Value {
+    let tools = config.tools.map(|t| {
+        t.iter()
+            .map(|tool| json!({
+                "name": tool.name,
+                "description": tool.description,
+                "schema": tool.schema
+            }))
+            .collect::<Vec<_>>()
+    });
+
+    // Prefer structured raw bodies so the record stays valid JSON throughout
+    let raw = config.raw_response.map(|raw| {
+        serde_json::from_str::<Value>(raw).unwrap_or_else(|_| Value::String(raw.to_string()))
+    });
+
+    json!({
         "timestamp": chrono::Utc::now().to_rfc3339(),
-        "model": config.model,
         "provider": config.provider,
+        "model": config.model,
+        "streaming": config.streaming,
         "request": {
             "system_prompt": config.system_prompt,
-            "messages": messages_json,
+            "messages": serde_json::to_value(config.messages).unwrap_or(Value::Null),
             "message_count": config.messages.len(),
-            "tools": config.tools.map(|t| t.iter().map(|tool| json!({
-                "name": tool.name,
-                "description": tool.description,
-                "schema": tool.schema
-            })).collect::<Vec<_>>()),
+            "tools": tools,
             "tool_count": config.tools.map(|t| t.len()).unwrap_or(0),
         },
         "response": {
@@ -83,36 +279,33 @@ pub fn log_llm_interaction(config: LogConfig<'_>) {
                     "name": c.fn_name,
                     "call_id": c.call_id,
                     "arguments": c.fn_arguments
-                })).collect::<Vec<_>>()
+                })).collect::<Vec<_>>(),
+                "usage": {
+                    "input_tokens": r.input_tokens,
+                    "output_tokens": r.output_tokens,
+                },
             })),
-            "raw": config.raw_response,
+            "raw": raw,
         },
         "error": config.error,
-    });
-
-    // Append to log file
-    match std::fs::OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(&log_file)
-    {
-        Ok(mut file) => {
-            // Use pretty printing if raw_response is present (Rig provider), compact otherwise
-            let json_str = if config.raw_response.is_some() {
-                serde_json::to_string_pretty(&entry).unwrap_or_default()
-            } else {
-                serde_json::to_string(&entry).unwrap_or_default()
-            };
-            if let Err(e) = writeln!(file, "{}", json_str) {
-                warn!("Failed to write to LLM log file: {}", e);
-            }
-        }
-        Err(e) => {
-            warn!("Failed to open LLM log file {}: {}", log_file, e);
-        }
-    }
+    })
+}
 
-    debug!("Logged LLM interaction to {}", log_file);
+/// Read the most recent record from a request log
+///
+/// Falls back to the rotated file when the current log is missing or empty.
+pub fn read_last_record(path: &Path) -> Option<Value> {
+    [path.to_path_buf(), rotated_path(path)]
+        .iter()
+        .find_map(|p| {
+            let file = std::fs::File::open(p).ok()?;
+            std::io::BufReader::new(file)
+                .lines()
+                .map_while(|l| l.ok())
+                .filter(|l| !l.trim().is_empty())
+                .last()
+                .and_then(|l| serde_json::from_str(&l).ok())
+        })
 }
 
 #[cfg(test)]
@@ -130,4 +323,67 @@ mod tests {
         assert!(config.provider.is_none());
         assert!(config.tools.is_none());
     }
+
+    #[test]
+    fn test_log_writes_full_messages() {
+        let dir = tempfile::tempdir().unwrap();
+        let logger = LlmLogger::new(dir.path().join("llm.jsonl"));
+        let messages = vec![ChatMessage::user("first"), ChatMessage::assistant("second")];
+        let result = CompletionResult {
+            content: Some("done".to_string()),
+            input_tokens: Some(12),
+            output_tokens: Some(3),
+            ..Default::default()
+        };
+
+        logger.log(LogConfig {
+            model: "m",
+            provider: Some("anthropic"),
+            messages: &messages,
+            result: Some(&result),
+            ..Default::default()
+        });
+
+        let record = read_last_record(logger.path()).unwrap();
+        assert_eq!(record["provider"], "anthropic");
+        assert_eq!(record["request"]["message_count"], 2);
+        assert!(record["request"]["messages"].to_string().contains("second"));
+        assert_eq!(record["response"]["parsed"]["usage"]["input_tokens"], 12);
+    }
+
+    #[test]
+    fn test_redacts_secrets_and_headers() {
+        let dir = tempfile::tempdir().unwrap();
+        let logger = LlmLogger::new(dir.path().join("llm.jsonl"))
+            .with_secret("sk-secret-123")
+            .with_redactions(&["ghp_[A-Za-z0-9]+".to_string()]);
+        let messages = vec![ChatMessage::user("key sk-secret-123 and token ghp_abcDEF42")];
+
+        logger.log(LogConfig {
+            model: "m",
+            messages: &messages,
+            raw_response: Some("HTTP 401 - Headers: {\"authorization\": \"Bearer sk-other\"}"),
+            ..Default::default()
+        });
+
+        let line = std::fs::read_to_string(logger.path()).unwrap();
+        assert!(!line.contains("sk-secret-123"));
+        assert!(!line.contains("ghp_abcDEF42"));
+        assert!(!line.contains("sk-other"));
+        assert!(line.contains(REDACTED));
+    }
+
+    #[test]
+    fn test_rotation_by_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("llm.jsonl");
+        let logger = LlmLogger::new(&path).with_max_bytes(10);
+
+        logger.log(LogConfig { model: "one", ..Default::default() });
+        logger.log(LogConfig { model: "two", ..Default::default() });
+
+        assert!(rotated_path(&path).exists());
+        let record = read_last_record(&path).unwrap();
+        assert_eq!(record["model"], "two");
+    }
 }
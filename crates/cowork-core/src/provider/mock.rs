@@ -0,0 +1,404 @@
+//! Scriptable provider for end-to-end agent loop tests
+//!
+//! `MockProvider` replays a queue of scripted responses (text, tool calls,
+//! token usage, finish reasons, errors, or hangs) in place of a real LLM, and records every request
+//! it receives so tests can assert on what the agent loop sent. A response
+//! can also be scripted as the stream events it arrives in, with a delay
+//! between them.
+//!
+//! ```ignore
+//! let mock = MockProvider::builder()
+//!     .tool_call("call_1", "Read", json!({"file_path": "README.md"}))
+//!     .text("Done")
+//!     .with_usage(120, 8)
+//!     .build();
+//! let config = SessionConfig::new(workspace).with_mock_provider(mock.clone());
+//! ```
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::StreamExt;
+use parking_lot::Mutex;
+
+use super::llm_provider::{completion_events, BoxFuture, EventStream, LlmProvider, StreamAssembler, StreamEvent};
+use super::{message_text_content, ChatMessage, CompletionResult, FinishReason, GenerationParams, ToolCall};
+use crate::error::{Error, Result};
+use crate::tools::ToolDefinition;
+
+/// Provider ID reported by the mock (used for request limits)
+pub const MOCK_PROVIDER_ID: &str = "mock";
+
+/// Model ID reported by the mock (used for usage records)
+pub const MOCK_MODEL_ID: &str = "mock-model";
+
+/// How long a hanging request sleeps before giving up (longer than any request timeout)
+const HANG_DURATION: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// One scripted step: the response to the next request
+#[derive(Debug, Clone)]
+enum MockStep {
+    Respond(CompletionResult),
+    /// Events streamed in order, `delay` apart
+    Stream { events: Vec<StreamEvent>, delay: Duration },
+    Fail(String),
+    Hang,
+}
+
+/// A request the mock received
+#[derive(Debug, Clone)]
+pub struct MockRequest {
+    /// Messages sent, in order (system prompt not included)
+    pub messages: Vec<ChatMessage>,
+    /// Names of the tools offered to the model
+    pub tools: Vec<String>,
+    /// Generation parameters sent with the request
+    pub params: GenerationParams,
+    /// Model the request was sent to (`MOCK_MODEL_ID` unless a turn overrode it)
+    pub model: String,
+}
+
+impl MockRequest {
+    /// Text of every tool result in the request, in order
+    pub fn tool_results(&self) -> Vec<String> {
+        self.messages
+            .iter()
+            .filter(|m| !m.content.tool_responses().is_empty())
+            .map(message_text_content)
+            .collect()
+    }
+
+    /// Text of the last message in the request
+    pub fn last_message_text(&self) -> String {
+        self.messages.last().map(message_text_content).unwrap_or_default()
+    }
+}
+
+/// Builds a `MockProvider` from a sequence of scripted steps
+#[derive(Debug, Default)]
+pub struct MockProviderBuilder {
+    steps: Vec<MockStep>,
+    provider_id: Option<String>,
+}
+
+impl MockProviderBuilder {
+    /// Respond with plain text (ends the agentic turn)
+    pub fn text(self, content: impl Into<String>) -> Self {
+        self.respond(CompletionResult {
+            content: Some(content.into()),
+            ..Default::default()
+        })
+    }
+
+    /// Respond with a single tool call
+    pub fn tool_call(self, id: impl Into<String>, name: impl Into<String>, arguments: serde_json::Value) -> Self {
+        self.tool_calls(vec![mock_tool_call(id, name, arguments)])
+    }
+
+    /// Respond with several tool calls at once
+    pub fn tool_calls(self, tool_calls: Vec<ToolCall>) -> Self {
+        self.respond(CompletionResult {
+            tool_calls,
+            ..Default::default()
+        })
+    }
+
+    /// Respond with a fully specified result
+    pub fn respond(mut self, result: CompletionResult) -> Self {
+        self.steps.push(MockStep::Respond(result));
+        self
+    }
+
+    /// Respond with `events`, as a provider would stream them (a
+    /// non-streaming request gets them assembled)
+    pub fn stream(mut self, events: impl IntoIterator<Item = StreamEvent>) -> Self {
+        self.steps.push(MockStep::Stream {
+            events: events.into_iter().collect(),
+            delay: Duration::ZERO,
+        });
+        self
+    }
+
+    /// Wait `delay` before each event of the most recently added stream
+    pub fn with_chunk_delay(mut self, delay: Duration) -> Self {
+        if let Some(MockStep::Stream { delay: step_delay, .. }) = self.steps.last_mut() {
+            *step_delay = delay;
+        }
+        self
+    }
+
+    /// Fail the next request with a provider error
+    pub fn fail(mut self, message: impl Into<String>) -> Self {
+        self.steps.push(MockStep::Fail(message.into()));
+        self
+    }
+
+    /// Never answer the next request (sleeps far past any request timeout;
+    /// use with `tokio::time::pause` to skip the wait)
+    pub fn hang(mut self) -> Self {
+        self.steps.push(MockStep::Hang);
+        self
+    }
+
+    /// Report `provider_id` instead of `MOCK_PROVIDER_ID`, so a test's
+    /// provider-wide state (such as its circuit) is its own
+    pub fn with_provider_id(mut self, provider_id: impl Into<String>) -> Self {
+        self.provider_id = Some(provider_id.into());
+        self
+    }
+
+    /// Set token usage on the most recently added response
+    pub fn with_usage(mut self, input_tokens: u64, output_tokens: u64) -> Self {
+        if let Some(MockStep::Respond(result)) = self.steps.last_mut() {
+            result.input_tokens = Some(input_tokens);
+            result.output_tokens = Some(output_tokens);
+        }
+        self
+    }
+
+    /// Set why the model stopped on the most recently added response
+    pub fn with_finish_reason(mut self, reason: FinishReason) -> Self {
+        if let Some(MockStep::Respond(result)) = self.steps.last_mut() {
+            result.finish_reason = Some(reason);
+        }
+        self
+    }
+
+    pub fn build(self) -> Arc<MockProvider> {
+        Arc::new(MockProvider {
+            provider_id: self.provider_id.unwrap_or_else(|| MOCK_PROVIDER_ID.to_string()),
+            model: MOCK_MODEL_ID.to_string(),
+            script: Arc::new(Mutex::new(self.steps.into())),
+            requests: Arc::new(Mutex::new(Vec::new())),
+        })
+    }
+}
+
+/// Build a tool call for scripting
+pub fn mock_tool_call(id: impl Into<String>, name: impl Into<String>, arguments: serde_json::Value) -> ToolCall {
+    ToolCall {
+        call_id: id.into(),
+        fn_name: name.into(),
+        fn_arguments: arguments,
+        thought_signatures: None,
+    }
+}
+
+/// Test double that replays scripted responses and records requests
+#[derive(Debug)]
+pub struct MockProvider {
+    provider_id: String,
+    model: String,
+    /// Shared with the mock's copies for other models
+    script: Arc<Mutex<VecDeque<MockStep>>>,
+    requests: Arc<Mutex<Vec<MockRequest>>>,
+}
+
+impl MockProvider {
+    pub fn builder() -> MockProviderBuilder {
+        MockProviderBuilder::default()
+    }
+
+    /// Requests received so far, oldest first
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.requests.lock().clone()
+    }
+
+    /// Number of scripted steps not yet consumed
+    pub fn remaining(&self) -> usize {
+        self.script.lock().len()
+    }
+
+    pub fn provider_id(&self) -> &str {
+        &self.provider_id
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// The same mock reporting `model`: it plays the same script and
+    /// records into the same requests
+    pub fn for_model(&self, model: impl Into<String>) -> Arc<MockProvider> {
+        Arc::new(MockProvider {
+            provider_id: self.provider_id.clone(),
+            model: model.into(),
+            script: self.script.clone(),
+            requests: self.requests.clone(),
+        })
+    }
+
+    /// Record the request and return the next scripted step
+    ///
+    /// Errors once the script runs out, so an unexpected extra request fails the test.
+    pub async fn chat(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<Vec<ToolDefinition>>,
+    ) -> Result<CompletionResult> {
+        self.chat_with_params(messages, tools, &GenerationParams::default()).await
+    }
+
+    /// Like `chat`, recording the generation parameters
+    pub async fn chat_with_params(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<Vec<ToolDefinition>>,
+        params: &GenerationParams,
+    ) -> Result<CompletionResult> {
+        match self.next_step(messages, tools, params) {
+            Some(MockStep::Respond(result)) => Ok(result),
+            Some(MockStep::Stream { events, .. }) => {
+                let mut assembler = StreamAssembler::new();
+                events.into_iter().for_each(|event| assembler.push(event));
+                Ok(assembler.finish())
+            }
+            Some(MockStep::Fail(message)) => Err(Error::Provider(message)),
+            Some(MockStep::Hang) => {
+                tokio::time::sleep(HANG_DURATION).await;
+                Err(Error::Provider("MockProvider hang ended".to_string()))
+            }
+            None => Err(Error::Provider("MockProvider script exhausted".to_string())),
+        }
+    }
+
+    /// Like `chat_with_params`, streaming the response: a scripted stream
+    /// as scripted, any other response as a single chunk
+    pub fn chat_stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<Vec<ToolDefinition>>,
+        params: &GenerationParams,
+    ) -> EventStream<'_> {
+        let (events, delay) = match self.next_step(messages, tools, params) {
+            Some(MockStep::Respond(result)) => (completion_events(result), Duration::ZERO),
+            Some(MockStep::Stream { events, delay }) => (events, delay),
+            Some(MockStep::Fail(message)) => {
+                return Box::pin(futures::stream::iter([Err(Error::Provider(message))]));
+            }
+            Some(MockStep::Hang) => {
+                return Box::pin(futures::stream::once(async {
+                    tokio::time::sleep(HANG_DURATION).await;
+                    Err(Error::Provider("MockProvider hang ended".to_string()))
+                }));
+            }
+            None => {
+                let exhausted = Error::Provider("MockProvider script exhausted".to_string());
+                return Box::pin(futures::stream::iter([Err(exhausted)]));
+            }
+        };
+        Box::pin(futures::stream::iter(events).then(move |event| async move {
+            if !delay.is_zero() {
+                tokio::time::sleep(delay).await;
+            }
+            Ok(event)
+        }))
+    }
+
+    /// Record the request and take the next scripted step
+    fn next_step(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<Vec<ToolDefinition>>,
+        params: &GenerationParams,
+    ) -> Option<MockStep> {
+        self.requests.lock().push(MockRequest {
+            messages,
+            tools: tools.unwrap_or_default().into_iter().map(|t| t.name).collect(),
+            params: params.clone(),
+            model: self.model.clone(),
+        });
+        self.script.lock().pop_front()
+    }
+}
+
+impl LlmProvider for MockProvider {
+    fn provider_id(&self) -> &str {
+        &self.provider_id
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn complete(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<Vec<ToolDefinition>>,
+        params: &GenerationParams,
+    ) -> BoxFuture<'_, Result<CompletionResult>> {
+        let params = params.clone();
+        Box::pin(async move { self.chat_with_params(messages, tools, &params).await })
+    }
+
+    fn stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<Vec<ToolDefinition>>,
+        params: &GenerationParams,
+    ) -> EventStream<'_> {
+        self.chat_stream(messages, tools, params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_replays_script_and_records_requests() {
+        let mock = MockProvider::builder()
+            .tool_call("call_1", "Read", json!({"file_path": "a.txt"}))
+            .with_usage(10, 2)
+            .fail("rate limited")
+            .text("done")
+            .build();
+
+        let first = mock.chat(vec![ChatMessage::user("hi")], None).await.unwrap();
+        assert_eq!(first.tool_calls[0].fn_name, "Read");
+        assert_eq!(first.input_tokens, Some(10));
+
+        let err = mock.chat(vec![], None).await.unwrap_err();
+        assert!(err.to_string().contains("rate limited"));
+
+        let third = mock.chat(vec![], None).await.unwrap();
+        assert_eq!(third.content.as_deref(), Some("done"));
+
+        assert!(mock.chat(vec![], None).await.is_err());
+        assert_eq!(mock.remaining(), 0);
+        assert_eq!(mock.requests().len(), 4);
+        assert_eq!(mock.requests()[0].last_message_text(), "hi");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_scripted_stream() {
+        let events = vec![
+            StreamEvent::TextDelta("Hel".to_string()),
+            StreamEvent::TextDelta("lo".to_string()),
+            StreamEvent::Done { finish_reason: Some(FinishReason::Stop) },
+        ];
+        let mock = MockProvider::builder()
+            .stream(events.clone())
+            .with_chunk_delay(Duration::from_millis(100))
+            .stream(events.clone())
+            .text("plain")
+            .build();
+        let params = GenerationParams::default();
+
+        let started = tokio::time::Instant::now();
+        let streamed: Vec<_> = mock.stream(vec![], None, &params).map(|event| event.unwrap()).collect().await;
+        assert_eq!(streamed, events);
+        assert_eq!(started.elapsed(), Duration::from_millis(300));
+
+        // Asked for in one piece, a scripted stream arrives assembled
+        let result = mock.chat(vec![], None).await.unwrap();
+        assert_eq!(result.content.as_deref(), Some("Hello"));
+        assert_eq!(result.finish_reason, Some(FinishReason::Stop));
+
+        // A plain response streams as one chunk
+        let streamed: Vec<_> = mock.stream(vec![], None, &params).map(|event| event.unwrap()).collect().await;
+        assert_eq!(streamed[0], StreamEvent::TextDelta("plain".to_string()));
+    }
+}
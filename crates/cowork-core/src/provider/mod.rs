@@ -12,21 +12,50 @@
 //! - Ollama (local)
 
 pub mod catalog;
+pub mod circuit;
+pub mod context_overflow;
 pub mod factory;
+pub mod finish_reason;
 mod genai_provider;
-mod logging;
+pub mod generation;
+pub mod llm_provider;
+pub mod logging;
+#[cfg(feature = "mock-provider")]
+pub mod mock;
 pub mod model_listing;
+pub mod probe;
+pub mod tool_schema;
+pub mod usage;
 
+pub use circuit::{
+    is_server_error, CircuitState, ProviderCircuits, ProviderStatus, CIRCUIT_COOLDOWN, DEFAULT_REQUEST_TIMEOUT,
+    DEFAULT_STREAM_TIMEOUT,
+};
+pub use context_overflow::{context_overflow, ContextOverflow};
 pub use factory::{
     create_provider_from_config, create_provider_from_provider_config,
-    create_provider_with_settings, get_api_key, get_model_tiers, has_api_key_configured,
+    create_provider_with_settings, get_api_key, get_model_tiers, has_api_key_configured, provider_circuits,
 };
 pub use genai_provider::{
     create_provider, CompletionResult, GenAIProvider,
 };
+pub use finish_reason::FinishReason;
+pub use generation::GenerationParams;
+pub use llm_provider::{collect_stream, completion_events, EventStream, LlmProvider, StreamAssembler, StreamEvent};
+
+pub use logging::{LlmLogger, DEFAULT_LLM_LOG_MAX_BYTES, LLM_LOG_ENV};
+
+#[cfg(feature = "mock-provider")]
+pub use mock::{mock_tool_call, MockProvider, MockProviderBuilder, MockRequest, MOCK_MODEL_ID};
 
 pub use model_listing::{get_known_models, get_model_context_limit, ModelInfo};
 
+pub use probe::{probe_provider, TestReport, PROBE_TIMEOUT};
+
+pub use tool_schema::SchemaDialect;
+
+pub use usage::{parse_window, UsageGroupBy, UsageRecord, UsageReport, UsageRow, UsageStore};
+
 // Re-export ChatRole from genai as our Role type
 pub use genai::chat::ChatRole;
 
@@ -0,0 +1,239 @@
+//! Capability probe for a provider and model
+//!
+//! Run before a model is saved as the default, so users learn up front that
+//! a key is wrong or a model can't call tools, rather than an hour into a
+//! session. The probe makes three requests:
+//!
+//! 1. A trivial completion (checks the key, and times the round trip)
+//! 2. A prompt that can only be answered by calling a dummy tool. The tool
+//!    only exists in that request; nothing registers it.
+//! 3. The same trivial completion, streamed
+//!
+//! The context limit comes from the catalog or, for Ollama, from the server.
+//! A provider that answers the first request has its circuit reset, so a
+//! probe brings back a provider marked unavailable.
+
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::catalog;
+use super::genai_provider::{CompletionResult, GenAIProvider};
+use super::llm_provider::{collect_stream, LlmProvider};
+use super::GenerationParams;
+use crate::provider::ChatMessage;
+use crate::tools::ToolDefinition;
+
+/// How long each probe request may take
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Name of the dummy tool the model is asked to call
+const PROBE_TOOL: &str = "lookup_probe_value";
+
+/// What the probe found out about a provider and model
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TestReport {
+    /// The provider accepted the key and answered
+    pub auth_ok: bool,
+    /// The model answered a tool-only question with a structured tool call
+    pub tools_ok: bool,
+    /// A streamed response came back
+    pub streaming_ok: bool,
+    /// Context window in tokens (None = unknown)
+    pub context_limit: Option<usize>,
+    /// Round trip of the trivial completion
+    pub latency_ms: u64,
+    /// Problems worth showing before the settings are saved
+    pub warnings: Vec<String>,
+    /// Why the auth check failed
+    pub error: Option<String>,
+}
+
+/// Probe `provider`, giving each request up to `timeout`
+///
+/// `base_url` is the provider's configured endpoint, used to ask an Ollama
+/// server for the model's context length. Steps after a failed auth check
+/// are skipped. If auth succeeds, the provider's circuit is reset.
+pub async fn probe_provider(provider: &GenAIProvider, base_url: Option<&str>, timeout: Duration) -> TestReport {
+    let mut report = TestReport::default();
+    let model = provider.model().to_string();
+
+    let started = Instant::now();
+    match tokio::time::timeout(timeout, provider.chat(vec![hello()], None)).await {
+        Ok(Ok(_)) => {
+            report.auth_ok = true;
+            super::factory::provider_circuits().reset(provider.provider_id());
+        }
+        Ok(Err(e)) => report.error = Some(e.to_string()),
+        Err(_) => report.error = Some(format!("No response within {}s", timeout.as_secs())),
+    }
+    report.latency_ms = started.elapsed().as_millis() as u64;
+    if !report.auth_ok {
+        return report;
+    }
+
+    let question = ChatMessage::user(format!(
+        "What value is stored under the key \"cowork\"? Look it up with the {} tool; don't answer from memory.",
+        PROBE_TOOL
+    ));
+    match tokio::time::timeout(timeout, provider.chat(vec![question], Some(vec![probe_tool()]))).await {
+        Ok(Ok(result)) if called_probe_tool(&result) => report.tools_ok = true,
+        Ok(Ok(_)) => report.warnings.push(format!(
+            "{} answered without calling the test tool. It may not support tool calling, so turns that need tools will degrade.",
+            model
+        )),
+        Ok(Err(e)) => report.warnings.push(format!("Tool calling failed: {}", e)),
+        Err(_) => report.warnings.push(format!("The tool-calling test got no response within {}s", timeout.as_secs())),
+    }
+
+    let mut chunks = 0;
+    let events = provider.stream(vec![hello()], None, &GenerationParams::default());
+    let stream = tokio::time::timeout(timeout, collect_stream(events, |_| chunks += 1)).await;
+    match stream {
+        Ok(Ok(result)) if chunks > 0 || result.has_content() => report.streaming_ok = true,
+        Ok(Ok(_)) => report.warnings.push("The streamed response was empty; turn stream mode off for this model".to_string()),
+        Ok(Err(e)) => report.warnings.push(format!("Streaming failed ({}); turn stream mode off for this model", e)),
+        Err(_) => report.warnings.push(format!("The streaming test got no response within {}s", timeout.as_secs())),
+    }
+
+    let fetched = match provider.provider_id() {
+        "ollama" => ollama_context_length(base_url.or(catalog::base_url("ollama")), &model, timeout).await,
+        _ => None,
+    };
+    let (limit, warning) = match fetched {
+        Some(limit) => (Some(limit), None),
+        None => catalog_context_limit(provider.provider_id(), &model),
+    };
+    report.context_limit = limit;
+    report.warnings.extend(warning);
+
+    report
+}
+
+fn hello() -> ChatMessage {
+    ChatMessage::user("Say 'hello' and nothing else.")
+}
+
+/// The dummy tool, defined for the probe request only
+fn probe_tool() -> ToolDefinition {
+    ToolDefinition::new(PROBE_TOOL)
+        .with_description("Look up the value stored under a key. This is the only way to learn a stored value.")
+        .with_schema(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "key": { "type": "string", "description": "Key to look up" }
+            },
+            "required": ["key"]
+        }))
+}
+
+/// Whether the response calls the dummy tool with structured arguments
+fn called_probe_tool(result: &CompletionResult) -> bool {
+    result
+        .tool_calls
+        .iter()
+        .any(|call| call.fn_name == PROBE_TOOL && call.fn_arguments.get("key").is_some_and(Value::is_string))
+}
+
+/// The catalog's context window for `model`, warning when the model isn't
+/// listed and the provider's default is assumed
+fn catalog_context_limit(provider_id: &str, model: &str) -> (Option<usize>, Option<String>) {
+    let Some(provider) = catalog::get(provider_id) else {
+        return (None, Some(format!("No context limit is known for provider {}", provider_id)));
+    };
+    let listed = [catalog::ModelTier::Fast, catalog::ModelTier::Balanced, catalog::ModelTier::Powerful]
+        .into_iter()
+        .filter_map(|tier| provider.model(tier))
+        .find(|m| m.id == model);
+    match listed {
+        Some(m) => (Some(m.context), None),
+        None => {
+            let assumed = provider.default_model().context;
+            (
+                Some(assumed),
+                Some(format!("{} isn't in the catalog; assuming a {}-token context window", model, assumed)),
+            )
+        }
+    }
+}
+
+/// Ask an Ollama server for the model's context length (`/api/show`)
+async fn ollama_context_length(base_url: Option<&str>, model: &str, timeout: Duration) -> Option<usize> {
+    let base = base_url?.trim_end_matches('/').trim_end_matches("/v1");
+    let response = crate::http::client()
+        .ok()?
+        .post(format!("{}/api/show", base))
+        .json(&serde_json::json!({ "model": model }))
+        .timeout(timeout)
+        .send()
+        .await
+        .ok()?;
+    let body: Value = response.error_for_status().ok()?.json().await.ok()?;
+    parse_ollama_context_length(&body)
+}
+
+/// `model_info.<architecture>.context_length` from an `/api/show` response
+fn parse_ollama_context_length(body: &Value) -> Option<usize> {
+    body.get("model_info")?
+        .as_object()?
+        .iter()
+        .find(|(key, _)| key.ends_with(".context_length"))
+        .and_then(|(_, value)| value.as_u64())
+        .map(|n| n as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::ToolCall;
+    use serde_json::json;
+
+    fn call(name: &str, arguments: Value) -> CompletionResult {
+        CompletionResult {
+            tool_calls: vec![ToolCall {
+                call_id: "call-1".to_string(),
+                fn_name: name.to_string(),
+                fn_arguments: arguments,
+                thought_signatures: None,
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_probe_tool_call_must_be_structured() {
+        assert!(called_probe_tool(&call(PROBE_TOOL, json!({"key": "cowork"}))));
+        assert!(!called_probe_tool(&call(PROBE_TOOL, json!("{\"key\": \"cowork\"}"))));
+        assert!(!called_probe_tool(&call("other_tool", json!({"key": "cowork"}))));
+        let text_only = CompletionResult {
+            content: Some("The value is 42.".to_string()),
+            ..Default::default()
+        };
+        assert!(!called_probe_tool(&text_only));
+    }
+
+    #[test]
+    fn test_catalog_context_limit() {
+        let listed = catalog::default_model("anthropic").unwrap();
+        let (limit, warning) = catalog_context_limit("anthropic", listed);
+        assert_eq!(limit, catalog::context_window("anthropic"));
+        assert!(warning.is_none());
+
+        let (limit, warning) = catalog_context_limit("ollama", "qwen2.5-coder:7b");
+        assert_eq!(limit, catalog::context_window("ollama"));
+        assert!(warning.unwrap().contains("qwen2.5-coder:7b isn't in the catalog"));
+
+        assert_eq!(catalog_context_limit("nope", "x").0, None);
+    }
+
+    #[test]
+    fn test_parse_ollama_context_length() {
+        let body = json!({
+            "details": {"family": "qwen2"},
+            "model_info": {"general.architecture": "qwen2", "qwen2.context_length": 32768}
+        });
+        assert_eq!(parse_ollama_context_length(&body), Some(32768));
+        assert_eq!(parse_ollama_context_length(&json!({"model_info": {}})), None);
+    }
+}
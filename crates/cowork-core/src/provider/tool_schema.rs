@@ -0,0 +1,438 @@
+//! Tool schema dialects
+//!
+//! Tools describe their parameters in plain JSON Schema, which Anthropic
+//! accepts as-is. Other providers only take a subset:
+//!
+//! - OpenAI strict function calling wants every object closed
+//!   (`additionalProperties: false`) with every property listed in
+//!   `required`. Optional properties become nullable instead, and keywords
+//!   strict mode rejects are dropped (`oneOf` becomes `anyOf`). Schemas that
+//!   can't be expressed that way, such as free-form objects, are sent
+//!   unchanged and the tool stays non-strict.
+//! - Gemini takes an OpenAPI-style subset: no `$ref`, a single `type` per
+//!   schema (`nullable` for null), no `additionalProperties` and no empty
+//!   `properties`.
+//!
+//! Adaptation is pure and only changes what is sent to the provider; calls
+//! are still validated against the tool's own schema, after
+//! [`strip_optional_nulls`] removes the nulls strict mode sends for omitted
+//! properties. `strict_tools = false` in `[general]` turns it off.
+
+use genai::adapter::AdapterKind;
+use serde_json::{json, Map, Value};
+
+use crate::provider::catalog;
+use crate::tools::ToolDefinition;
+
+/// Keywords OpenAI strict mode rejects; dropped from adapted schemas
+const OPENAI_UNSUPPORTED: &[&str] = &[
+    "$schema",
+    "default",
+    "examples",
+    "format",
+    "minLength",
+    "maxLength",
+    "pattern",
+    "minimum",
+    "maximum",
+    "exclusiveMinimum",
+    "exclusiveMaximum",
+    "multipleOf",
+    "minItems",
+    "maxItems",
+    "uniqueItems",
+    "minProperties",
+    "maxProperties",
+];
+
+/// Keywords with no strict equivalent; a schema using them stays non-strict
+const OPENAI_INCOMPATIBLE: &[&str] = &["allOf", "not", "if", "then", "else", "patternProperties"];
+
+/// Keywords outside Gemini's schema subset
+const GEMINI_UNSUPPORTED: &[&str] = &[
+    "$schema",
+    "$id",
+    "$defs",
+    "definitions",
+    "additionalProperties",
+    "patternProperties",
+    "default",
+    "examples",
+    "exclusiveMinimum",
+    "exclusiveMaximum",
+];
+
+/// The schema dialect a provider's tool definitions are sent in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SchemaDialect {
+    /// Plain JSON Schema, sent unchanged
+    #[default]
+    Standard,
+    /// OpenAI strict function calling
+    OpenAiStrict,
+    /// Gemini function declarations
+    Gemini,
+}
+
+impl SchemaDialect {
+    /// The dialect for a genai adapter (`Standard` when `strict_tools` is off)
+    pub fn for_adapter(adapter: AdapterKind, strict_tools: bool) -> Self {
+        if !strict_tools {
+            return Self::Standard;
+        }
+        match adapter {
+            AdapterKind::OpenAI | AdapterKind::OpenAIResp => Self::OpenAiStrict,
+            AdapterKind::Gemini => Self::Gemini,
+            _ => Self::Standard,
+        }
+    }
+
+    /// The dialect for a catalog provider (`Standard` for unknown providers)
+    pub fn for_provider(provider_id: &str, strict_tools: bool) -> Self {
+        catalog::adapter(provider_id)
+            .map(|adapter| Self::for_adapter(adapter, strict_tools))
+            .unwrap_or_default()
+    }
+}
+
+/// Adapt tool definitions to a dialect
+pub fn adapt_definitions(definitions: Vec<ToolDefinition>, dialect: SchemaDialect) -> Vec<ToolDefinition> {
+    definitions.into_iter().map(|def| adapt_definition(def, dialect)).collect()
+}
+
+/// Adapt one tool definition to a dialect
+///
+/// Strict OpenAI definitions are marked with `{"strict": true}` in their
+/// config. genai's OpenAI adapter doesn't forward the flag yet (it always
+/// sends `strict: false`), so for now the marker only records which tools
+/// can go strict; the closed schemas are sent either way.
+pub fn adapt_definition(mut definition: ToolDefinition, dialect: SchemaDialect) -> ToolDefinition {
+    let Some(schema) = definition.schema.as_ref() else {
+        return definition;
+    };
+    match dialect {
+        SchemaDialect::Standard => definition,
+        SchemaDialect::OpenAiStrict => match openai_strict_schema(schema) {
+            Some(strict) => {
+                definition.schema = Some(strict);
+                definition.config = Some(json!({ "strict": true }));
+                definition
+            }
+            None => definition,
+        },
+        SchemaDialect::Gemini => {
+            // Gemini rejects object parameters without properties; send none instead
+            let schema = gemini_schema(schema);
+            definition.schema = schema.get("properties").is_some().then_some(schema);
+            definition
+        }
+    }
+}
+
+/// A schema in OpenAI strict form, or `None` if strict mode can't express it
+pub fn openai_strict_schema(schema: &Value) -> Option<Value> {
+    let map = schema.as_object()?;
+    if OPENAI_INCOMPATIBLE.iter().any(|k| map.contains_key(*k)) {
+        return None;
+    }
+
+    let mut out = Map::new();
+    for (key, value) in map {
+        if OPENAI_UNSUPPORTED.contains(&key.as_str()) {
+            continue;
+        }
+        let value = match key.as_str() {
+            "properties" | "additionalProperties" | "required" => continue,
+            "items" => openai_strict_schema(value)?,
+            "anyOf" | "oneOf" => Value::Array(
+                value
+                    .as_array()?
+                    .iter()
+                    .map(openai_strict_schema)
+                    .collect::<Option<Vec<_>>>()?,
+            ),
+            "$defs" | "definitions" => Value::Object(
+                value
+                    .as_object()?
+                    .iter()
+                    .map(|(name, def)| Some((name.clone(), openai_strict_schema(def)?)))
+                    .collect::<Option<Map<_, _>>>()?,
+            ),
+            _ => value.clone(),
+        };
+        let key = if key == "oneOf" { "anyOf" } else { key.as_str() };
+        out.insert(key.to_string(), value);
+    }
+
+    if is_object_schema(map) {
+        // Free-form objects can't be closed without losing what they accept
+        if map.get("additionalProperties").is_some_and(|a| a != &Value::Bool(false)) {
+            return None;
+        }
+        let properties = map.get("properties")?.as_object()?;
+        let required = required_names(map);
+
+        let mut strict_properties = Map::new();
+        for (name, property) in properties {
+            let mut property = openai_strict_schema(property)?;
+            if !required.contains(&name.as_str()) {
+                property = nullable(property)?;
+            }
+            strict_properties.insert(name.clone(), property);
+        }
+        out.insert(
+            "required".to_string(),
+            Value::Array(properties.keys().map(|k| Value::String(k.clone())).collect()),
+        );
+        out.insert("properties".to_string(), Value::Object(strict_properties));
+        out.insert("additionalProperties".to_string(), Value::Bool(false));
+    }
+    Some(Value::Object(out))
+}
+
+/// A schema in Gemini's dialect
+pub fn gemini_schema(schema: &Value) -> Value {
+    let defs = schema
+        .get("$defs")
+        .or_else(|| schema.get("definitions"))
+        .and_then(Value::as_object)
+        .cloned()
+        .unwrap_or_default();
+    gemini_node(schema, &defs, 0)
+}
+
+/// Remove nulls sent for properties the tool's schema doesn't require
+///
+/// Strict mode makes the model send every property, using `null` for the
+/// ones it would have left out.
+pub fn strip_optional_nulls(schema: &Value, arguments: &mut Value) {
+    match arguments {
+        Value::Object(fields) => {
+            let Some(map) = schema.as_object() else {
+                return;
+            };
+            let required = required_names(map);
+            fields.retain(|name, value| !value.is_null() || required.contains(&name.as_str()));
+            if let Some(properties) = map.get("properties").and_then(Value::as_object) {
+                for (name, value) in fields.iter_mut() {
+                    if let Some(property) = properties.get(name) {
+                        strip_optional_nulls(property, value);
+                    }
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for item in items {
+                    strip_optional_nulls(item_schema, item);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn is_object_schema(map: &Map<String, Value>) -> bool {
+    map.get("type").and_then(Value::as_str) == Some("object") || map.contains_key("properties")
+}
+
+fn required_names(map: &Map<String, Value>) -> Vec<&str> {
+    map.get("required")
+        .and_then(Value::as_array)
+        .map(|names| names.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default()
+}
+
+/// Allow `null` as well (None for untyped schemas)
+fn nullable(mut schema: Value) -> Option<Value> {
+    let map = schema.as_object_mut()?;
+    if let Some(any_of) = map.get_mut("anyOf").and_then(Value::as_array_mut) {
+        if !any_of.iter().any(|s| s.get("type") == Some(&json!("null"))) {
+            any_of.push(json!({ "type": "null" }));
+        }
+        return Some(schema);
+    }
+    match map.get_mut("type")? {
+        Value::String(t) => {
+            let t = t.clone();
+            map.insert("type".to_string(), json!([t, "null"]));
+        }
+        Value::Array(types) => {
+            if !types.contains(&json!("null")) {
+                types.push(json!("null"));
+            }
+        }
+        _ => return None,
+    }
+    if let Some(values) = map.get_mut("enum").and_then(Value::as_array_mut)
+        && !values.contains(&Value::Null)
+    {
+        values.push(Value::Null);
+    }
+    Some(schema)
+}
+
+/// How deep `$ref`s are inlined before giving up on a recursive definition
+const MAX_REF_DEPTH: usize = 8;
+
+fn gemini_node(schema: &Value, defs: &Map<String, Value>, depth: usize) -> Value {
+    let Some(map) = schema.as_object() else {
+        return schema.clone();
+    };
+
+    if let Some(target) = map.get("$ref").and_then(Value::as_str) {
+        let name = target.rsplit('/').next().unwrap_or_default();
+        return match defs.get(name) {
+            Some(def) if depth < MAX_REF_DEPTH => gemini_node(def, defs, depth + 1),
+            _ => json!({ "type": "object" }),
+        };
+    }
+
+    let mut out = Map::new();
+    for (key, value) in map {
+        if GEMINI_UNSUPPORTED.contains(&key.as_str()) {
+            continue;
+        }
+        let (key, value) = match key.as_str() {
+            "type" => match value {
+                Value::Array(types) => {
+                    let mut types: Vec<&str> = types.iter().filter_map(Value::as_str).collect();
+                    if types.contains(&"null") {
+                        out.insert("nullable".to_string(), Value::Bool(true));
+                        types.retain(|t| *t != "null");
+                    }
+                    ("type", json!(types.first().copied().unwrap_or("string")))
+                }
+                _ => ("type", value.clone()),
+            },
+            "const" => ("enum", json!([value])),
+            "properties" => (
+                "properties",
+                Value::Object(
+                    value
+                        .as_object()
+                        .map(|props| {
+                            props
+                                .iter()
+                                .map(|(name, prop)| (name.clone(), gemini_node(prop, defs, depth)))
+                                .collect()
+                        })
+                        .unwrap_or_default(),
+                ),
+            ),
+            "items" => ("items", gemini_node(value, defs, depth)),
+            "anyOf" | "oneOf" => (
+                "anyOf",
+                Value::Array(
+                    value
+                        .as_array()
+                        .map(|schemas| schemas.iter().map(|s| gemini_node(s, defs, depth)).collect())
+                        .unwrap_or_default(),
+                ),
+            ),
+            other => (other, value.clone()),
+        };
+        // Empty `properties` is rejected as well; an object without them is not
+        if key == "properties" && value.as_object().is_some_and(Map::is_empty) {
+            continue;
+        }
+        out.insert(key.to_string(), value);
+    }
+    Value::Object(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": { "type": "string", "minLength": 1 },
+                "limit": { "type": "integer", "default": 10 },
+                "mode": { "type": "string", "enum": ["fast", "full"] },
+                "items": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": { "id": { "type": "string" } },
+                        "required": ["id"]
+                    }
+                },
+                "target": { "oneOf": [{ "type": "string" }, { "type": "integer" }] }
+            },
+            "required": ["path"]
+        })
+    }
+
+    #[test]
+    fn test_openai_strict_closes_objects() {
+        let strict = openai_strict_schema(&todo_schema()).unwrap();
+        assert_eq!(strict["additionalProperties"], false);
+        assert_eq!(strict["required"], json!(["items", "limit", "mode", "path", "target"]));
+        assert_eq!(strict["properties"]["path"], json!({ "type": "string" }));
+        assert_eq!(strict["properties"]["limit"], json!({ "type": ["integer", "null"] }));
+        assert_eq!(strict["properties"]["mode"]["enum"], json!(["fast", "full", null]));
+        assert_eq!(strict["properties"]["items"]["items"]["additionalProperties"], false);
+        assert_eq!(
+            strict["properties"]["target"],
+            json!({ "anyOf": [{ "type": "string" }, { "type": "integer" }, { "type": "null" }] })
+        );
+    }
+
+    #[test]
+    fn test_openai_strict_leaves_free_form_objects() {
+        let schema = json!({
+            "type": "object",
+            "properties": { "env": { "type": "object", "additionalProperties": { "type": "string" } } }
+        });
+        assert!(openai_strict_schema(&schema).is_none());
+
+        let def = ToolDefinition::new("Env").with_schema(schema.clone());
+        let adapted = adapt_definition(def, SchemaDialect::OpenAiStrict);
+        assert_eq!(adapted.schema, Some(schema));
+        assert!(adapted.config.is_none());
+    }
+
+    #[test]
+    fn test_gemini_inlines_refs_and_single_types() {
+        let schema = json!({
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "type": "object",
+            "additionalProperties": false,
+            "properties": {
+                "note": { "type": ["string", "null"] },
+                "item": { "$ref": "#/$defs/Item" },
+                "kind": { "const": "todo" }
+            },
+            "$defs": { "Item": { "type": "object", "properties": { "id": { "type": "string" } } } }
+        });
+        assert_eq!(
+            gemini_schema(&schema),
+            json!({
+                "type": "object",
+                "properties": {
+                    "note": { "type": "string", "nullable": true },
+                    "item": { "type": "object", "properties": { "id": { "type": "string" } } },
+                    "kind": { "enum": ["todo"] }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_strip_optional_nulls() {
+        let mut args = json!({ "path": "a", "limit": null, "items": [{ "id": "1" }], "target": null });
+        strip_optional_nulls(&todo_schema(), &mut args);
+        assert_eq!(args, json!({ "path": "a", "items": [{ "id": "1" }] }));
+    }
+
+    #[test]
+    fn test_dialect_for_adapter() {
+        assert_eq!(SchemaDialect::for_adapter(AdapterKind::OpenAI, true), SchemaDialect::OpenAiStrict);
+        assert_eq!(SchemaDialect::for_adapter(AdapterKind::Gemini, true), SchemaDialect::Gemini);
+        assert_eq!(SchemaDialect::for_adapter(AdapterKind::Anthropic, true), SchemaDialect::Standard);
+        assert_eq!(SchemaDialect::for_adapter(AdapterKind::OpenAI, false), SchemaDialect::Standard);
+    }
+}
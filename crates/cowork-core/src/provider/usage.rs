@@ -0,0 +1,399 @@
+//! Persistent token usage accounting
+//!
+//! Every completed LLM call appends one `UsageRecord` (JSONL) to the usage
+//! store under the data directory. `UsageReport` aggregates the records by
+//! model, day, session or purpose and estimates cost from the catalog's
+//! pricing; models without pricing are reported with token counts only.
+//!
+//! The store is rotated to `<path>.1` once it grows past its size limit, so
+//! reports cover the current file plus one previous generation.
+
+use std::collections::BTreeMap;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, Local, Utc};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use super::catalog;
+use crate::error::{Error, Result};
+
+/// Default size at which the usage store is rotated (5 MiB, roughly 25k records)
+pub const DEFAULT_USAGE_MAX_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Report key of the calls the session's turns made (records without a purpose)
+const TURN_PURPOSE: &str = "turn";
+
+/// Token usage of one completed LLM call
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageRecord {
+    pub timestamp: DateTime<Utc>,
+    pub session_id: String,
+    pub provider: String,
+    pub model: String,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// Input tokens read from the prompt cache (counted within `input_tokens`)
+    #[serde(default)]
+    pub cached_tokens: u64,
+    /// What a call made outside the session's turns was for (e.g. `explain`);
+    /// None for the turns themselves
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub purpose: Option<String>,
+}
+
+impl UsageRecord {
+    /// Estimated cost in USD, if the model has known pricing
+    pub fn cost(&self) -> Option<f64> {
+        catalog::pricing(&self.provider, &self.model)
+            .map(|p| p.cost(self.input_tokens, self.output_tokens, self.cached_tokens))
+    }
+}
+
+/// Append-only JSONL store of usage records
+#[derive(Debug)]
+pub struct UsageStore {
+    path: PathBuf,
+    max_bytes: u64,
+    /// Serializes appends and rotation across sessions
+    write_lock: Mutex<()>,
+    /// Records handed to `record` that haven't been written yet
+    pending: AtomicUsize,
+}
+
+impl UsageStore {
+    /// Create a store at the given path with the default size limit
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            max_bytes: DEFAULT_USAGE_MAX_BYTES,
+            write_lock: Mutex::new(()),
+            pending: AtomicUsize::new(0),
+        }
+    }
+
+    /// Set the size at which the store is rotated (0 = never)
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// The store in the cowork data directory
+    pub fn open_default() -> Self {
+        Self::new(default_usage_path())
+    }
+
+    /// Get the store file path
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append a record, rotating the file first if it is over the size limit
+    pub fn append(&self, record: &UsageRecord) -> Result<()> {
+        let _guard = self.write_lock.lock();
+
+        if let Ok(meta) = std::fs::metadata(&self.path)
+            && self.max_bytes > 0
+            && meta.len() >= self.max_bytes
+        {
+            std::fs::rename(&self.path, rotated_path(&self.path))?;
+        }
+
+        if let Some(parent) = self.path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(record)?)?;
+        Ok(())
+    }
+
+    /// Append a record on a blocking thread without waiting for it
+    ///
+    /// Failures are logged, never returned, so accounting can't stall or
+    /// break the agent loop.
+    pub fn record(self: &Arc<Self>, record: UsageRecord) {
+        let store = self.clone();
+        store.pending.fetch_add(1, Ordering::AcqRel);
+        tokio::task::spawn_blocking(move || {
+            if let Err(e) = store.append(&record) {
+                warn!("Failed to write usage record to {}: {}", store.path.display(), e);
+            }
+            store.pending.fetch_sub(1, Ordering::AcqRel);
+        });
+    }
+
+    /// Wait until every record handed to `record` so far has been written
+    pub async fn flush(&self) {
+        while self.pending.load(Ordering::Acquire) > 0 {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+    }
+
+    /// Load records newer than `since` (all records if None), oldest first
+    ///
+    /// Malformed lines are skipped.
+    pub fn load(&self, since: Option<DateTime<Utc>>) -> Result<Vec<UsageRecord>> {
+        let mut records = Vec::new();
+        for path in [rotated_path(&self.path), self.path.clone()] {
+            let file = match std::fs::File::open(&path) {
+                Ok(file) => file,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+            for line in std::io::BufReader::new(file).lines() {
+                let Ok(record) = serde_json::from_str::<UsageRecord>(&line?) else {
+                    continue;
+                };
+                if since.is_none_or(|since| record.timestamp >= since) {
+                    records.push(record);
+                }
+            }
+        }
+        Ok(records)
+    }
+
+    /// Load and aggregate records from the last `window` (everything if None)
+    pub fn report(&self, window: Option<Duration>, group_by: UsageGroupBy) -> Result<UsageReport> {
+        let since = window.map(|w| Utc::now() - w);
+        let records = self.load(since)?;
+        Ok(UsageReport::build(&records, group_by, since))
+    }
+}
+
+/// Default usage store path: `<data dir>/cowork/usage.jsonl`
+pub fn default_usage_path() -> PathBuf {
+    dirs::data_dir()
+        .map(|p| p.join("cowork"))
+        .unwrap_or_else(|| PathBuf::from(".cowork"))
+        .join("usage.jsonl")
+}
+
+/// Path the store is rotated to
+fn rotated_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(".1");
+    PathBuf::from(name)
+}
+
+/// Parse a report window like `30m`, `24h`, `7d`, or `2w`
+pub fn parse_window(text: &str) -> Result<Duration> {
+    let text = text.trim();
+    let invalid = || Error::Config(format!("Invalid time window '{}' (expected e.g. 24h, 7d, 2w)", text));
+    let split = text.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+    let (amount, unit) = text.split_at(split);
+    let amount: i64 = amount.parse().map_err(|_| invalid())?;
+    match unit {
+        "m" => Ok(Duration::minutes(amount)),
+        "h" => Ok(Duration::hours(amount)),
+        "d" => Ok(Duration::days(amount)),
+        "w" => Ok(Duration::weeks(amount)),
+        _ => Err(invalid()),
+    }
+}
+
+/// How report rows are grouped
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageGroupBy {
+    #[default]
+    Model,
+    /// Local calendar day
+    Day,
+    Session,
+    /// What the calls were for: the session's turns, or a side call such as `explain`
+    Purpose,
+}
+
+impl std::str::FromStr for UsageGroupBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "model" => Ok(Self::Model),
+            "day" => Ok(Self::Day),
+            "session" => Ok(Self::Session),
+            "purpose" => Ok(Self::Purpose),
+            _ => Err(format!("Unknown grouping '{}' (expected model, day, session, or purpose)", s)),
+        }
+    }
+}
+
+/// Aggregated usage for one group (or the total)
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct UsageRow {
+    pub key: String,
+    pub requests: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cached_tokens: u64,
+    /// Estimated cost in USD of the priced requests (None if none were priced)
+    pub cost: Option<f64>,
+    /// Requests whose model has no known pricing
+    pub unpriced_requests: u64,
+}
+
+impl UsageRow {
+    fn add(&mut self, record: &UsageRecord) {
+        self.requests += 1;
+        self.input_tokens += record.input_tokens;
+        self.output_tokens += record.output_tokens;
+        self.cached_tokens += record.cached_tokens;
+        match record.cost() {
+            Some(cost) => *self.cost.get_or_insert(0.0) += cost,
+            None => self.unpriced_requests += 1,
+        }
+    }
+}
+
+/// Usage totals over a time window, grouped for display
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UsageReport {
+    /// Start of the window (None = all recorded usage)
+    pub since: Option<DateTime<Utc>>,
+    pub group_by: UsageGroupBy,
+    /// Days in order; models and sessions by descending cost, then tokens
+    pub rows: Vec<UsageRow>,
+    pub total: UsageRow,
+}
+
+impl UsageReport {
+    /// Aggregate records into rows
+    pub fn build(records: &[UsageRecord], group_by: UsageGroupBy, since: Option<DateTime<Utc>>) -> Self {
+        let mut groups: BTreeMap<String, UsageRow> = BTreeMap::new();
+        let mut total = UsageRow {
+            key: "total".to_string(),
+            ..Default::default()
+        };
+
+        for record in records {
+            let key = match group_by {
+                UsageGroupBy::Model => format!("{}/{}", record.provider, record.model),
+                UsageGroupBy::Day => record.timestamp.with_timezone(&Local).format("%Y-%m-%d").to_string(),
+                UsageGroupBy::Session => record.session_id.clone(),
+                UsageGroupBy::Purpose => record.purpose.clone().unwrap_or_else(|| TURN_PURPOSE.to_string()),
+            };
+            groups
+                .entry(key.clone())
+                .or_insert_with(|| UsageRow { key, ..Default::default() })
+                .add(record);
+            total.add(record);
+        }
+
+        let mut rows: Vec<UsageRow> = groups.into_values().collect();
+        if group_by != UsageGroupBy::Day {
+            rows.sort_by(|a, b| {
+                b.cost
+                    .unwrap_or(0.0)
+                    .total_cmp(&a.cost.unwrap_or(0.0))
+                    .then((b.input_tokens + b.output_tokens).cmp(&(a.input_tokens + a.output_tokens)))
+            });
+        }
+
+        Self { since, group_by, rows, total }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(session: &str, provider: &str, model: &str, input: u64, output: u64) -> UsageRecord {
+        UsageRecord {
+            timestamp: Utc::now(),
+            session_id: session.to_string(),
+            provider: provider.to_string(),
+            model: model.to_string(),
+            input_tokens: input,
+            output_tokens: output,
+            cached_tokens: 0,
+            purpose: None,
+        }
+    }
+
+    #[test]
+    fn test_append_load_and_rotate() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = UsageStore::new(dir.path().join("usage.jsonl")).with_max_bytes(1);
+
+        let first = record("s1", "anthropic", "claude-sonnet-4-5-20250929", 100, 10);
+        let second = record("s2", "openai", "gpt-5", 200, 20);
+        store.append(&first).unwrap();
+        // Over the limit, so the first record moves to usage.jsonl.1
+        store.append(&second).unwrap();
+        assert!(dir.path().join("usage.jsonl.1").exists());
+
+        // Garbage lines are skipped
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(store.path())
+            .unwrap()
+            .write_all(b"not json\n")
+            .unwrap();
+
+        assert_eq!(store.load(None).unwrap(), vec![first, second]);
+        let future = Utc::now() + Duration::hours(1);
+        assert!(store.load(Some(future)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_report_groups_and_tolerates_missing_pricing() {
+        let records = vec![
+            record("s1", "anthropic", "claude-sonnet-4-5-20250929", 1_000_000, 0),
+            record("s1", "anthropic", "claude-sonnet-4-5-20250929", 0, 1_000_000),
+            record("s2", "ollama", "llama3.2", 500, 50),
+        ];
+
+        let report = UsageReport::build(&records, UsageGroupBy::Model, None);
+        assert_eq!(report.rows.len(), 2);
+        assert_eq!(report.rows[0].key, "anthropic/claude-sonnet-4-5-20250929");
+        assert_eq!(report.rows[0].requests, 2);
+        assert!((report.rows[0].cost.unwrap() - 18.0).abs() < 1e-9);
+        assert_eq!(report.rows[1].cost, None);
+        assert_eq!(report.rows[1].unpriced_requests, 1);
+
+        assert_eq!(report.total.requests, 3);
+        assert_eq!(report.total.input_tokens, 1_000_500);
+        assert_eq!(report.total.unpriced_requests, 1);
+
+        let by_session = UsageReport::build(&records, UsageGroupBy::Session, None);
+        assert_eq!(by_session.rows.iter().map(|r| r.key.as_str()).collect::<Vec<_>>(), ["s1", "s2"]);
+    }
+
+    #[test]
+    fn test_report_by_purpose() {
+        let explain = UsageRecord {
+            purpose: Some("explain".to_string()),
+            ..record("s1", "anthropic", "claude-haiku-4-5", 300, 40)
+        };
+        let records = vec![record("s1", "anthropic", "claude-sonnet-4-5-20250929", 5_000, 500), explain.clone()];
+
+        let report = UsageReport::build(&records, UsageGroupBy::Purpose, None);
+        assert_eq!(report.rows.iter().map(|r| r.key.as_str()).collect::<Vec<_>>(), ["turn", "explain"]);
+        assert_eq!(report.rows[1].input_tokens, 300);
+
+        // Records without a purpose are written as before
+        let line = serde_json::to_string(&records[0]).unwrap();
+        assert!(!line.contains("purpose"));
+        assert_eq!(serde_json::from_str::<UsageRecord>(&serde_json::to_string(&explain).unwrap()).unwrap(), explain);
+    }
+
+    #[test]
+    fn test_parse_window() {
+        assert_eq!(parse_window("7d").unwrap(), Duration::days(7));
+        assert_eq!(parse_window("24h").unwrap(), Duration::hours(24));
+        assert_eq!(parse_window("2w").unwrap(), Duration::weeks(2));
+        assert!(parse_window("7").is_err());
+        assert!(parse_window("d").is_err());
+        assert!(parse_window("3y").is_err());
+    }
+}
@@ -0,0 +1,707 @@
+//! Recipes: scripted multi-step workflows
+//!
+//! A recipe is an ordered list of steps kept in `.cowork/recipes/<name>.yaml`
+//! (or `.yml`, or `.md` with the recipe in its YAML frontmatter and its
+//! description as the body). Each step runs one of:
+//!
+//! - `tool`: a tool with templated arguments (`with`)
+//! - `skill`: a skill, with optional `args`
+//! - `shell`: a shell command, run by the Bash tool
+//! - `prompt`: a prompt for the LLM, which answers in a turn of its own
+//!
+//! ```yaml
+//! description: Release a new version
+//! args:
+//!   - name: version
+//! steps:
+//!   - id: test
+//!     shell: cargo test
+//!     on_failure: ask
+//!   - id: notes
+//!     prompt: Write release notes for ${args.version}
+//!     output: notes
+//!   - id: tag
+//!     shell: git tag -a v${args.version} -m "${notes}"
+//! ```
+//!
+//! Strings are templated with `${args.NAME}`, `${steps.ID.output}`,
+//! `${steps.ID.exit_code}`, `${steps.ID.success}` and `${NAME}` for a step's
+//! `output` variable; `$${...}` is a literal `${...}` (e.g. for shell
+//! variables). Steps run through the session like the model's own tool
+//! calls, so mutating ones still need approval.
+
+mod runner;
+
+pub(crate) use runner::event_output;
+pub use runner::{RecipeReport, RecipeRunner, RecipeStepInfo, StepExecutor, StepOutcome, StepReport, StepStatus};
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::{Error, Result};
+
+/// Recipe directory, relative to the workspace
+pub const RECIPES_DIR: &str = ".cowork/recipes";
+
+/// File extensions recipes are read from, in order of precedence
+const RECIPE_EXTENSIONS: &[&str] = &["yaml", "yml", "md"];
+
+/// Tool shell steps run with
+pub const SHELL_TOOL: &str = "Bash";
+
+/// Tool skill steps run with
+pub const SKILL_TOOL: &str = "Skill";
+
+/// A scripted workflow
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Recipe {
+    /// Name the recipe is run by (its file name without the extension)
+    #[serde(skip)]
+    pub name: String,
+    /// File the recipe was loaded from
+    #[serde(skip)]
+    pub path: PathBuf,
+    #[serde(default)]
+    pub description: String,
+    /// Arguments, bound in order from `/recipe run <name> [args]`
+    #[serde(default)]
+    pub args: Vec<RecipeArg>,
+    pub steps: Vec<RecipeStep>,
+}
+
+/// An argument a recipe takes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecipeArg {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Value when the argument isn't given (required if absent)
+    #[serde(default)]
+    pub default: Option<String>,
+}
+
+/// One step of a recipe
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecipeStep {
+    /// Name later steps refer to the step's results by
+    #[serde(default)]
+    pub id: String,
+    #[serde(flatten)]
+    pub action: StepAction,
+    /// Tool arguments (`tool` steps)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub with: Option<Value>,
+    /// Skill arguments (`skill` steps)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub args: Option<String>,
+    /// Variable the step's output is stored in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    #[serde(default)]
+    pub on_failure: OnFailure,
+}
+
+/// What a step does
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StepAction {
+    /// Run a tool
+    Tool(String),
+    /// Run a skill
+    Skill(String),
+    /// Run a shell command
+    Shell(String),
+    /// Ask the LLM
+    Prompt(String),
+}
+
+impl StepAction {
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Tool(_) => "tool",
+            Self::Skill(_) => "skill",
+            Self::Shell(_) => "shell",
+            Self::Prompt(_) => "prompt",
+        }
+    }
+}
+
+/// What happens when a step fails
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnFailure {
+    /// Stop the recipe
+    #[default]
+    Abort,
+    /// Carry on with the next step
+    Continue,
+    /// Ask the user whether to carry on
+    Ask,
+}
+
+/// A `${...}` reference in a template
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum VarRef {
+    Arg(String),
+    Step(String, StepField),
+    Output(String),
+}
+
+/// A step result a template can refer to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StepField {
+    Output,
+    ExitCode,
+    Success,
+}
+
+impl VarRef {
+    fn parse(reference: &str) -> Option<Self> {
+        let parts: Vec<&str> = reference.trim().split('.').collect();
+        let is_name = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+        match parts.as_slice() {
+            ["args", name] if is_name(name) => Some(Self::Arg(name.to_string())),
+            ["steps", id, field] if is_name(id) => {
+                let field = match *field {
+                    "output" => StepField::Output,
+                    "exit_code" => StepField::ExitCode,
+                    "success" => StepField::Success,
+                    _ => return None,
+                };
+                Some(Self::Step(id.to_string(), field))
+            }
+            [name] if is_name(name) && *name != "args" && *name != "steps" => Some(Self::Output(name.to_string())),
+            _ => None,
+        }
+    }
+}
+
+/// Values templates are filled in from as a recipe runs
+#[derive(Debug, Clone, Default)]
+pub struct Variables {
+    args: HashMap<String, String>,
+    steps: HashMap<String, StepOutcome>,
+    outputs: HashMap<String, String>,
+}
+
+impl Variables {
+    /// Record a finished step's results
+    pub fn record(&mut self, step: &RecipeStep, outcome: &StepOutcome) {
+        if let Some(name) = &step.output {
+            self.outputs.insert(name.clone(), outcome.output.clone());
+        }
+        self.steps.insert(step.id.clone(), outcome.clone());
+    }
+
+    fn lookup(&self, reference: &VarRef) -> Option<String> {
+        match reference {
+            VarRef::Arg(name) => self.args.get(name).cloned(),
+            VarRef::Output(name) => self.outputs.get(name).cloned(),
+            VarRef::Step(id, field) => self.steps.get(id).map(|outcome| match field {
+                StepField::Output => outcome.output.clone(),
+                StepField::ExitCode => outcome.exit_code.to_string(),
+                StepField::Success => outcome.success.to_string(),
+            }),
+        }
+    }
+
+    /// Fill in `template`; unbound references are an error, or left as they
+    /// are with `keep_unbound` (for showing a plan before the steps have run)
+    pub fn render(&self, template: &str, keep_unbound: bool) -> Result<String> {
+        let mut unbound = None;
+        let rendered = template_pattern().replace_all(template, |caps: &regex::Captures| {
+            if caps.get(1).is_some() {
+                return format!("${{{}}}", &caps[2]);
+            }
+            match VarRef::parse(&caps[2]).and_then(|r| self.lookup(&r)) {
+                Some(value) => value,
+                None => {
+                    unbound.get_or_insert_with(|| caps[2].to_string());
+                    caps[0].to_string()
+                }
+            }
+        });
+        match unbound {
+            Some(reference) if !keep_unbound => Err(Error::Recipe(format!("${{{}}} is not bound", reference))),
+            _ => Ok(rendered.into_owned()),
+        }
+    }
+
+    /// Fill in every string in `value`
+    pub fn render_value(&self, value: &Value, keep_unbound: bool) -> Result<Value> {
+        Ok(match value {
+            Value::String(s) => Value::String(self.render(s, keep_unbound)?),
+            Value::Array(items) => Value::Array(
+                items.iter().map(|v| self.render_value(v, keep_unbound)).collect::<Result<_>>()?,
+            ),
+            Value::Object(map) => Value::Object(
+                map.iter()
+                    .map(|(k, v)| Ok((k.clone(), self.render_value(v, keep_unbound)?)))
+                    .collect::<Result<_>>()?,
+            ),
+            other => other.clone(),
+        })
+    }
+}
+
+/// `${reference}`, or `$${literal}` for a literal `${literal}`
+fn template_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\$(\$)?\{([^}]*)\}").expect("template pattern is valid"))
+}
+
+/// The references in `template`, skipping escaped ones
+fn references(template: &str) -> Vec<String> {
+    template_pattern()
+        .captures_iter(template)
+        .filter(|caps| caps.get(1).is_none())
+        .map(|caps| caps[2].to_string())
+        .collect()
+}
+
+fn value_references(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(s) => references(s),
+        Value::Array(items) => items.iter().flat_map(value_references).collect(),
+        Value::Object(map) => map.values().flat_map(value_references).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// A step ready to run, with its templates filled in
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedStep {
+    pub id: String,
+    pub action: ResolvedAction,
+    pub on_failure: OnFailure,
+}
+
+/// What a resolved step runs
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResolvedAction {
+    Tool { name: String, arguments: Value },
+    Skill { name: String, args: String },
+    Shell { command: String },
+    Prompt { prompt: String },
+}
+
+impl std::fmt::Display for ResolvedStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.action {
+            ResolvedAction::Tool { name, arguments } => write!(f, "{}: {}({})", self.id, name, arguments),
+            ResolvedAction::Skill { name, args } if args.is_empty() => write!(f, "{}: /{}", self.id, name),
+            ResolvedAction::Skill { name, args } => write!(f, "{}: /{} {}", self.id, name, args),
+            ResolvedAction::Shell { command } => write!(f, "{}: $ {}", self.id, command),
+            ResolvedAction::Prompt { prompt } => write!(f, "{}: ask \"{}\"", self.id, prompt),
+        }
+    }
+}
+
+impl RecipeStep {
+    /// Fill in the step's templates from `vars`
+    pub fn resolve(&self, vars: &Variables, keep_unbound: bool) -> Result<ResolvedStep> {
+        let render = |s: &str| vars.render(s, keep_unbound).map_err(|e| in_step(&self.id, e));
+        let action = match &self.action {
+            StepAction::Tool(name) => ResolvedAction::Tool {
+                name: name.clone(),
+                arguments: vars
+                    .render_value(self.with.as_ref().unwrap_or(&Value::Object(Default::default())), keep_unbound)
+                    .map_err(|e| in_step(&self.id, e))?,
+            },
+            StepAction::Skill(name) => ResolvedAction::Skill {
+                name: name.clone(),
+                args: render(self.args.as_deref().unwrap_or(""))?,
+            },
+            StepAction::Shell(command) => ResolvedAction::Shell { command: render(command)? },
+            StepAction::Prompt(prompt) => ResolvedAction::Prompt { prompt: render(prompt)? },
+        };
+        Ok(ResolvedStep {
+            id: self.id.clone(),
+            action,
+            on_failure: self.on_failure,
+        })
+    }
+
+    /// Every template reference in the step
+    fn references(&self) -> Vec<String> {
+        let mut refs = match &self.action {
+            StepAction::Shell(s) | StepAction::Prompt(s) => references(s),
+            StepAction::Tool(_) | StepAction::Skill(_) => Vec::new(),
+        };
+        if let Some(with) = &self.with {
+            refs.extend(value_references(with));
+        }
+        if let Some(args) = &self.args {
+            refs.extend(references(args));
+        }
+        refs
+    }
+}
+
+/// Name the step a templating error happened in
+fn in_step(id: &str, error: Error) -> Error {
+    match error {
+        Error::Recipe(message) => Error::Recipe(format!("Step {}: {}", id, message)),
+        other => other,
+    }
+}
+
+impl Recipe {
+    /// Parse a recipe file's contents; `path` decides the format and the name
+    pub fn parse(path: &Path, content: &str) -> Result<Self> {
+        let invalid = |e: &dyn std::fmt::Display| Error::Recipe(format!("{} is not a valid recipe: {}", path.display(), e));
+        let is_markdown = path.extension().is_some_and(|ext| ext == "md");
+        let mut recipe: Recipe = if is_markdown {
+            let doc = crate::prompt::parse_frontmatter(content).map_err(|e| invalid(&e))?;
+            let mut recipe: Recipe =
+                serde_json::from_value(Value::Object(doc.metadata.into_iter().collect())).map_err(|e| invalid(&e))?;
+            if recipe.description.is_empty() {
+                recipe.description = doc.content.trim().to_string();
+            }
+            recipe
+        } else {
+            serde_yml::from_str(content).map_err(|e| invalid(&e))?
+        };
+        recipe.name = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        recipe.path = path.to_path_buf();
+        Ok(recipe)
+    }
+
+    /// Load a recipe file
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Self::parse(path, &content)
+    }
+
+    /// Problems that would stop the recipe running: duplicate or missing
+    /// step IDs, unknown tools or skills, and references to arguments,
+    /// steps or variables that aren't bound by the time they're used
+    pub fn validate(&self, tools: &[String], skills: &[String]) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.steps.is_empty() {
+            problems.push("the recipe has no steps".to_string());
+        }
+        let mut args: Vec<&str> = Vec::new();
+        for arg in &self.args {
+            if args.contains(&arg.name.as_str()) {
+                problems.push(format!("argument {} is declared twice", arg.name));
+            }
+            args.push(&arg.name);
+        }
+
+        let mut steps_so_far: Vec<&str> = Vec::new();
+        let mut outputs_so_far: Vec<&str> = Vec::new();
+        for (i, step) in self.steps.iter().enumerate() {
+            let label = if step.id.is_empty() { format!("step {}", i + 1) } else { format!("step {}", step.id) };
+            if step.id.is_empty() {
+                problems.push(format!("{} has no id", label));
+            } else if steps_so_far.contains(&step.id.as_str()) {
+                problems.push(format!("step id {} is used twice", step.id));
+            }
+
+            match &step.action {
+                StepAction::Tool(name) if !tools.contains(name) => {
+                    problems.push(format!("{} uses unknown tool {}", label, name));
+                }
+                StepAction::Skill(name) if !skills.contains(name) => {
+                    problems.push(format!("{} uses unknown skill {}", label, name));
+                }
+                StepAction::Skill(_) if !tools.iter().any(|t| t == SKILL_TOOL) => {
+                    problems.push(format!("{} needs the {} tool, which isn't available", label, SKILL_TOOL));
+                }
+                StepAction::Shell(_) if !tools.iter().any(|t| t == SHELL_TOOL) => {
+                    problems.push(format!("{} needs the {} tool, which isn't available", label, SHELL_TOOL));
+                }
+                _ => {}
+            }
+            if step.with.is_some() && !matches!(step.action, StepAction::Tool(_)) {
+                problems.push(format!("{}: `with` only applies to tool steps", label));
+            }
+            if step.args.is_some() && !matches!(step.action, StepAction::Skill(_)) {
+                problems.push(format!("{}: `args` only applies to skill steps", label));
+            }
+
+            for reference in step.references() {
+                let bound = match VarRef::parse(&reference) {
+                    None => {
+                        problems.push(format!("{}: ${{{}}} is not a valid reference", label, reference));
+                        continue;
+                    }
+                    Some(VarRef::Arg(name)) => args.contains(&name.as_str()),
+                    Some(VarRef::Step(id, _)) => steps_so_far.contains(&id.as_str()),
+                    Some(VarRef::Output(name)) => outputs_so_far.contains(&name.as_str()),
+                };
+                if !bound {
+                    problems.push(format!("{}: ${{{}}} is not bound before the step runs", label, reference));
+                }
+            }
+
+            steps_so_far.push(&step.id);
+            if let Some(output) = &step.output {
+                outputs_so_far.push(output);
+            }
+        }
+        problems
+    }
+
+    /// Bind the arguments of `/recipe run <name> [args]`: positional values
+    /// in declaration order, or `name=value` for any argument
+    pub fn bind_args(&self, args: &str) -> Result<Variables> {
+        let words = shlex::split(args)
+            .ok_or_else(|| Error::Recipe(format!("Can't parse the arguments to {}: unbalanced quotes", self.name)))?;
+        let mut bound: HashMap<String, String> = HashMap::new();
+        let mut positional = self.args.iter();
+        for word in words {
+            if let Some((name, value)) = word.split_once('=')
+                && self.args.iter().any(|a| a.name == name)
+            {
+                bound.insert(name.to_string(), value.to_string());
+                continue;
+            }
+            let arg = positional
+                .find(|a| !bound.contains_key(&a.name))
+                .ok_or_else(|| Error::Recipe(format!("{} takes {} argument(s); got too many", self.name, self.args.len())))?;
+            bound.insert(arg.name.clone(), word);
+        }
+        for arg in &self.args {
+            if !bound.contains_key(&arg.name) {
+                let value = arg.default.clone().ok_or_else(|| {
+                    Error::Recipe(format!("{} needs argument {}: {}", self.name, arg.name, self.usage()))
+                })?;
+                bound.insert(arg.name.clone(), value);
+            }
+        }
+        Ok(Variables {
+            args: bound,
+            ..Default::default()
+        })
+    }
+
+    /// `/recipe run <name> <arg> [optional]`
+    pub fn usage(&self) -> String {
+        let mut usage = format!("/recipe run {}", self.name);
+        for arg in &self.args {
+            if arg.default.is_some() {
+                usage.push_str(&format!(" [{}]", arg.name));
+            } else {
+                usage.push_str(&format!(" <{}>", arg.name));
+            }
+        }
+        usage
+    }
+
+    /// The steps with the arguments filled in, for a dry run; references to
+    /// earlier steps' results are left as they are
+    pub fn plan(&self, vars: &Variables) -> Result<Vec<ResolvedStep>> {
+        self.steps.iter().map(|step| step.resolve(vars, true)).collect()
+    }
+
+    /// A dry run's plan, one numbered line per step
+    pub fn format_plan(&self, vars: &Variables) -> Result<String> {
+        let mut lines = vec![format!("Recipe {} ({} steps):", self.name, self.steps.len())];
+        for (i, step) in self.plan(vars)?.iter().enumerate() {
+            let on_failure = match step.on_failure {
+                OnFailure::Abort => "",
+                OnFailure::Continue => " [on failure: continue]",
+                OnFailure::Ask => " [on failure: ask]",
+            };
+            lines.push(format!("{}. {}{}", i + 1, step, on_failure));
+        }
+        Ok(lines.join("\n"))
+    }
+
+    /// One line describing a step, for progress checklists
+    pub fn step_label(step: &RecipeStep) -> String {
+        let detail = match &step.action {
+            StepAction::Tool(name) | StepAction::Skill(name) => name.clone(),
+            StepAction::Shell(command) => command.lines().next().unwrap_or("").to_string(),
+            StepAction::Prompt(prompt) => prompt.lines().next().unwrap_or("").to_string(),
+        };
+        format!("{} ({} {})", step.id, step.action.kind(), detail)
+    }
+}
+
+/// Where a workspace's recipes are kept
+pub fn recipes_dir(workspace: &Path) -> PathBuf {
+    workspace.join(RECIPES_DIR)
+}
+
+/// The workspace's recipes by name, each loaded or the reason it couldn't be
+pub fn list_recipes(workspace: &Path) -> Vec<(String, Result<Recipe>)> {
+    let Ok(entries) = std::fs::read_dir(recipes_dir(workspace)) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| RECIPE_EXTENSIONS.iter().any(|r| ext == *r)))
+        .collect();
+    // A name in several formats is read from the first extension only
+    paths.sort_by_key(|p| {
+        let rank = RECIPE_EXTENSIONS
+            .iter()
+            .position(|ext| p.extension().is_some_and(|e| e == *ext))
+            .unwrap_or(usize::MAX);
+        (p.file_stem().map(|s| s.to_os_string()), rank)
+    });
+    paths.dedup_by_key(|p| p.file_stem().map(|s| s.to_os_string()));
+    paths
+        .into_iter()
+        .map(|path| {
+            let name = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+            (name, Recipe::load(&path))
+        })
+        .collect()
+}
+
+/// Load the workspace's recipe called `name`
+pub fn load_recipe(workspace: &Path, name: &str) -> Result<Recipe> {
+    let dir = recipes_dir(workspace);
+    RECIPE_EXTENSIONS
+        .iter()
+        .map(|ext| dir.join(format!("{}.{}", name, ext)))
+        .find(|path| path.is_file())
+        .map(|path| Recipe::load(&path))
+        .unwrap_or_else(|| Err(Error::Recipe(format!("No recipe named {} in {}", name, dir.display()))))
+}
+
+/// Names of the built-in tools and the skills available in `workspace`,
+/// for validating recipes outside a session (which may add MCP tools)
+pub fn builtin_tools_and_skills(workspace: &Path) -> (Vec<String>, Vec<String>) {
+    let tools = crate::orchestration::ToolRegistryBuilder::new(workspace.to_path_buf())
+        .build()
+        .list()
+        .into_iter()
+        .map(|t| t.name)
+        .collect();
+    let skills = crate::skills::SkillRegistry::with_builtins(workspace.to_path_buf())
+        .list()
+        .into_iter()
+        .map(|s| s.name)
+        .collect();
+    (tools, skills)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RELEASE: &str = r#"
+description: Release a version
+args:
+  - name: version
+  - name: remote
+    default: origin
+steps:
+  - id: test
+    shell: cargo test
+    on_failure: ask
+  - id: notes
+    prompt: "Write notes for ${args.version} (tests exited ${steps.test.exit_code})"
+    output: notes
+  - id: bump
+    tool: Edit
+    with:
+      file_path: Cargo.toml
+      new_string: 'version = "${args.version}"'
+  - id: push
+    shell: git tag v${args.version} -m "${notes}" && git push ${args.remote} $${HOME}
+"#;
+
+    fn release() -> Recipe {
+        Recipe::parse(Path::new(".cowork/recipes/release.yaml"), RELEASE).unwrap()
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_yaml() {
+        let recipe = release();
+        assert_eq!(recipe.name, "release");
+        assert_eq!(recipe.steps.len(), 4);
+        assert_eq!(recipe.steps[0].action, StepAction::Shell("cargo test".to_string()));
+        assert_eq!(recipe.steps[0].on_failure, OnFailure::Ask);
+        assert_eq!(recipe.steps[1].output.as_deref(), Some("notes"));
+        assert_eq!(recipe.steps[2].action, StepAction::Tool("Edit".to_string()));
+        assert_eq!(recipe.steps[3].on_failure, OnFailure::Abort);
+    }
+
+    #[test]
+    fn test_parse_markdown() {
+        let content = "---\nsteps:\n  - id: hi\n    shell: echo hi\n---\n\nSays hi.\n";
+        let recipe = Recipe::parse(Path::new("hello.md"), content).unwrap();
+        assert_eq!(recipe.name, "hello");
+        assert_eq!(recipe.description, "Says hi.");
+        assert_eq!(recipe.steps[0].action, StepAction::Shell("echo hi".to_string()));
+    }
+
+    #[test]
+    fn test_validate() {
+        let recipe = release();
+        assert!(recipe.validate(&names(&["Bash", "Edit"]), &[]).is_empty());
+
+        let problems = recipe.validate(&names(&["Bash"]), &[]);
+        assert_eq!(problems, vec!["step bump uses unknown tool Edit"]);
+
+        let content = "steps:\n  - id: a\n    shell: echo ${steps.b.output} ${args.x} ${later}\n  - id: b\n    prompt: hi\n    output: later\n  - id: a\n    skill: nope\n";
+        let recipe = Recipe::parse(Path::new("bad.yaml"), content).unwrap();
+        let problems = recipe.validate(&names(&["Bash", "Skill"]), &names(&["commit"]));
+        assert_eq!(
+            problems,
+            vec![
+                "step a: ${steps.b.output} is not bound before the step runs",
+                "step a: ${args.x} is not bound before the step runs",
+                "step a: ${later} is not bound before the step runs",
+                "step id a is used twice",
+                "step a uses unknown skill nope",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bind_args() {
+        let recipe = release();
+        let vars = recipe.bind_args("1.2.0").unwrap();
+        assert_eq!(vars.render("${args.version} to ${args.remote}", false).unwrap(), "1.2.0 to origin");
+
+        let vars = recipe.bind_args("remote=upstream '2.0 beta'").unwrap();
+        assert_eq!(vars.render("${args.version} to ${args.remote}", false).unwrap(), "2.0 beta to upstream");
+
+        let err = recipe.bind_args("").unwrap_err().to_string();
+        assert!(err.contains("needs argument version: /recipe run release <version> [remote]"), "{}", err);
+        assert!(recipe.bind_args("1 2 3").is_err());
+    }
+
+    #[test]
+    fn test_render_after_steps() {
+        let recipe = release();
+        let mut vars = recipe.bind_args("1.2.0").unwrap();
+        assert!(vars.render("${steps.test.exit_code}", false).is_err());
+
+        vars.record(&recipe.steps[0], &StepOutcome::new(false, "2 failed", 101));
+        vars.record(&recipe.steps[1], &StepOutcome::new(true, "Fixes", 0));
+        let step = recipe.steps[3].resolve(&vars, false).unwrap();
+        assert_eq!(
+            step.action,
+            ResolvedAction::Shell {
+                command: "git tag v1.2.0 -m \"Fixes\" && git push origin ${HOME}".to_string()
+            }
+        );
+        assert_eq!(vars.render("${steps.test.exit_code} ${steps.test.success}", false).unwrap(), "101 false");
+    }
+
+    #[test]
+    fn test_format_plan_keeps_step_references() {
+        let recipe = release();
+        let plan = recipe.format_plan(&recipe.bind_args("1.2.0").unwrap()).unwrap();
+        assert!(plan.starts_with("Recipe release (4 steps):\n1. test: $ cargo test [on failure: ask]\n"), "{}", plan);
+        assert!(plan.contains("2. notes: ask \"Write notes for 1.2.0 (tests exited ${steps.test.exit_code})\""), "{}", plan);
+        assert!(plan.contains(r#"3. bump: Edit({"file_path":"Cargo.toml","new_string":"version = \"1.2.0\""})"#), "{}", plan);
+    }
+}
@@ -0,0 +1,410 @@
+//! Running a recipe's steps in order
+//!
+//! The runner decides what runs next and what a failure means; a
+//! `StepExecutor` (the agent loop, in a session) does the running, so tool
+//! steps go through the same approval flow as the model's own calls.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::{OnFailure, Recipe, ResolvedAction, Variables, SHELL_TOOL};
+use crate::error::{Error, Result};
+use crate::session::SessionOutput;
+use crate::skills::BoxFuture;
+
+/// Longest step output carried in `RecipeStepFinished`
+const MAX_EVENT_OUTPUT_CHARS: usize = 2000;
+
+/// How a step ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepOutcome {
+    pub success: bool,
+    /// What later steps see as `${steps.ID.output}`: a shell step's stdout,
+    /// a prompt or skill step's final answer, a tool's result
+    pub output: String,
+    /// A shell step's exit code; 0 or 1 for other steps
+    pub exit_code: i64,
+}
+
+impl StepOutcome {
+    pub fn new(success: bool, output: impl Into<String>, exit_code: i64) -> Self {
+        Self {
+            success,
+            output: output.into(),
+            exit_code,
+        }
+    }
+
+    /// A step that finished with `output`
+    pub fn succeeded(output: impl Into<String>) -> Self {
+        Self::new(true, output, 0)
+    }
+
+    /// A step that failed with `error`
+    pub fn failed(error: impl Into<String>) -> Self {
+        Self::new(false, error, 1)
+    }
+
+    /// A shell step's outcome from the Bash tool's result
+    fn from_shell(tool: StepOutcome) -> Self {
+        let Ok(result) = serde_json::from_str::<Value>(&tool.output) else {
+            return tool;
+        };
+        let exit_code = result["exit_code"].as_i64().unwrap_or(if tool.success { 0 } else { 1 });
+        let success = tool.success && result["success"].as_bool().unwrap_or(exit_code == 0);
+        let stdout = result["stdout"].as_str().unwrap_or("").trim_end();
+        let stderr = result["stderr"].as_str().unwrap_or("").trim_end();
+        let output = if success || stderr.is_empty() {
+            stdout.to_string()
+        } else if stdout.is_empty() {
+            stderr.to_string()
+        } else {
+            format!("{}\n{}", stdout, stderr)
+        };
+        Self::new(success, output, exit_code)
+    }
+}
+
+/// Where a step ended up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Succeeded,
+    Failed,
+    /// Not run: an earlier step stopped the recipe
+    Skipped,
+}
+
+/// A step as listed in `RecipeStarted`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecipeStepInfo {
+    pub id: String,
+    /// e.g. "test (shell cargo test)"
+    pub label: String,
+}
+
+/// What happened to one step
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StepReport {
+    pub id: String,
+    pub status: StepStatus,
+    pub output: String,
+}
+
+/// What happened when a recipe ran
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecipeReport {
+    pub name: String,
+    pub steps: Vec<StepReport>,
+    /// No step stopped the recipe (failures set to `continue` don't)
+    pub success: bool,
+    /// The user cancelled a step, stopping the recipe
+    pub cancelled: bool,
+}
+
+impl std::fmt::Display for RecipeReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let outcome = if self.cancelled {
+            "was cancelled"
+        } else if self.success {
+            "finished"
+        } else {
+            "stopped"
+        };
+        write!(f, "Recipe {} {}", self.name, outcome)?;
+        for step in &self.steps {
+            let mark = match step.status {
+                StepStatus::Succeeded => "✓",
+                StepStatus::Failed => "✗",
+                StepStatus::Skipped => "-",
+            };
+            write!(f, "\n  {} {}", mark, step.id)?;
+            if step.status == StepStatus::Failed
+                && let Some(line) = step.output.lines().find(|l| !l.trim().is_empty())
+            {
+                write!(f, ": {}", line.trim())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Runs a recipe's steps for the `RecipeRunner`
+///
+/// Each `run_*` returns `None` if the user cancelled the step, which stops
+/// the recipe.
+pub trait StepExecutor: Send {
+    /// Run a tool like one the model called, approvals included
+    fn run_tool(&mut self, name: String, arguments: Value) -> BoxFuture<'_, Option<StepOutcome>>;
+
+    /// Run a skill; its output is the LLM's final answer after following it
+    fn run_skill(&mut self, name: String, args: String) -> BoxFuture<'_, Option<StepOutcome>>;
+
+    /// Have the LLM answer a prompt in a turn of its own; its output is the final answer
+    fn run_prompt(&mut self, prompt: String) -> BoxFuture<'_, Option<StepOutcome>>;
+
+    /// Ask the user whether to carry on after a step with `on_failure: ask` failed
+    fn confirm_continue(&mut self, step_id: String, error: String) -> BoxFuture<'_, bool>;
+
+    /// Report progress
+    fn report(&mut self, output: SessionOutput) -> BoxFuture<'_, ()>;
+}
+
+/// Runs a recipe's steps in order, passing results between them
+pub struct RecipeRunner {
+    recipe: Recipe,
+    vars: Variables,
+}
+
+impl RecipeRunner {
+    /// Prepare `recipe` to run with `args` (as given to `/recipe run`)
+    ///
+    /// Fails if the arguments don't bind or the recipe doesn't validate
+    /// against the session's `tools` and `skills`, before anything runs.
+    pub fn new(recipe: Recipe, args: &str, tools: &[String], skills: &[String]) -> Result<Self> {
+        let problems = recipe.validate(tools, skills);
+        if !problems.is_empty() {
+            return Err(Error::Recipe(format!("{} is not valid:\n- {}", recipe.name, problems.join("\n- "))));
+        }
+        let vars = recipe.bind_args(args)?;
+        Ok(Self { recipe, vars })
+    }
+
+    pub fn recipe(&self) -> &Recipe {
+        &self.recipe
+    }
+
+    /// The steps as they would run, for a dry run
+    pub fn format_plan(&self) -> Result<String> {
+        self.recipe.format_plan(&self.vars)
+    }
+
+    /// Run every step, stopping at a failure unless the step says otherwise
+    pub async fn run(mut self, executor: &mut dyn StepExecutor) -> RecipeReport {
+        let name = self.recipe.name.clone();
+        let infos = self
+            .recipe
+            .steps
+            .iter()
+            .map(|step| RecipeStepInfo {
+                id: step.id.clone(),
+                label: Recipe::step_label(step),
+            })
+            .collect();
+        executor.report(SessionOutput::recipe_started(&name, infos)).await;
+
+        let mut reports = Vec::with_capacity(self.recipe.steps.len());
+        let mut stopped = false;
+        let mut cancelled = false;
+        for step in &self.recipe.steps {
+            if stopped {
+                let report = StepReport {
+                    id: step.id.clone(),
+                    status: StepStatus::Skipped,
+                    output: String::new(),
+                };
+                executor.report(SessionOutput::recipe_step_finished(&name, &report)).await;
+                reports.push(report);
+                continue;
+            }
+
+            executor.report(SessionOutput::recipe_step_started(&name, &step.id)).await;
+            let outcome = match step.resolve(&self.vars, false) {
+                Err(e) => Some(StepOutcome::failed(e.to_string())),
+                Ok(resolved) => match resolved.action {
+                    ResolvedAction::Tool { name, arguments } => executor.run_tool(name, arguments).await,
+                    ResolvedAction::Shell { command } => executor
+                        .run_tool(SHELL_TOOL.to_string(), json!({ "command": command }))
+                        .await
+                        .map(StepOutcome::from_shell),
+                    ResolvedAction::Skill { name, args } => executor.run_skill(name, args).await,
+                    ResolvedAction::Prompt { prompt } => executor.run_prompt(prompt).await,
+                },
+            };
+            let outcome = outcome.unwrap_or_else(|| {
+                cancelled = true;
+                StepOutcome::failed("Cancelled by user")
+            });
+            self.vars.record(step, &outcome);
+
+            let report = StepReport {
+                id: step.id.clone(),
+                status: if outcome.success { StepStatus::Succeeded } else { StepStatus::Failed },
+                output: outcome.output.clone(),
+            };
+            executor.report(SessionOutput::recipe_step_finished(&name, &report)).await;
+            reports.push(report);
+
+            if !outcome.success {
+                stopped = cancelled
+                    || match step.on_failure {
+                        OnFailure::Abort => true,
+                        OnFailure::Continue => false,
+                        OnFailure::Ask => !executor.confirm_continue(step.id.clone(), outcome.output.clone()).await,
+                    };
+            }
+        }
+
+        let report = RecipeReport {
+            name,
+            steps: reports,
+            success: !stopped,
+            cancelled,
+        };
+        executor
+            .report(SessionOutput::recipe_finished(&report.name, report.success, report.to_string()))
+            .await;
+        report
+    }
+}
+
+/// A step's output as carried in `RecipeStepFinished`
+pub(crate) fn event_output(output: &str) -> String {
+    match output.char_indices().nth(MAX_EVENT_OUTPUT_CHARS) {
+        Some((end, _)) => format!("{}…", &output[..end]),
+        None => output.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+    use std::path::Path;
+
+    /// Answers steps from a script and records what it was asked to run
+    #[derive(Default)]
+    struct ScriptedExecutor {
+        outcomes: VecDeque<Option<StepOutcome>>,
+        continue_after_failure: bool,
+        ran: Vec<String>,
+        events: Vec<SessionOutput>,
+    }
+
+    impl ScriptedExecutor {
+        fn next(&mut self, ran: String) -> BoxFuture<'_, Option<StepOutcome>> {
+            self.ran.push(ran);
+            let outcome = self.outcomes.pop_front().expect("a scripted outcome");
+            Box::pin(async move { outcome })
+        }
+    }
+
+    impl StepExecutor for ScriptedExecutor {
+        fn run_tool(&mut self, name: String, arguments: Value) -> BoxFuture<'_, Option<StepOutcome>> {
+            self.next(format!("{} {}", name, arguments))
+        }
+
+        fn run_skill(&mut self, name: String, args: String) -> BoxFuture<'_, Option<StepOutcome>> {
+            self.next(format!("/{} {}", name, args))
+        }
+
+        fn run_prompt(&mut self, prompt: String) -> BoxFuture<'_, Option<StepOutcome>> {
+            self.next(prompt)
+        }
+
+        fn confirm_continue(&mut self, step_id: String, _error: String) -> BoxFuture<'_, bool> {
+            self.ran.push(format!("ask {}", step_id));
+            let answer = self.continue_after_failure;
+            Box::pin(async move { answer })
+        }
+
+        fn report(&mut self, output: SessionOutput) -> BoxFuture<'_, ()> {
+            self.events.push(output);
+            Box::pin(async {})
+        }
+    }
+
+    const RECIPE: &str = r#"
+args:
+  - name: version
+steps:
+  - id: test
+    shell: cargo test
+    on_failure: ask
+  - id: notes
+    prompt: "Notes for ${args.version}; tests exited ${steps.test.exit_code}"
+    output: notes
+  - id: commit
+    skill: commit
+    args: "${notes}"
+"#;
+
+    fn runner() -> RecipeRunner {
+        let recipe = Recipe::parse(Path::new("release.yaml"), RECIPE).unwrap();
+        let tools = vec!["Bash".to_string(), "Skill".to_string()];
+        RecipeRunner::new(recipe, "1.0", &tools, &["commit".to_string()]).unwrap()
+    }
+
+    fn shell_result(exit_code: i64, stdout: &str) -> Option<StepOutcome> {
+        let result = json!({"exit_code": exit_code, "stdout": stdout, "stderr": "", "success": exit_code == 0});
+        Some(StepOutcome::succeeded(result.to_string()))
+    }
+
+    #[tokio::test]
+    async fn test_variables_flow_between_steps() {
+        let mut executor = ScriptedExecutor {
+            outcomes: VecDeque::from([
+                shell_result(0, "ok\n"),
+                Some(StepOutcome::succeeded("Fixed it")),
+                Some(StepOutcome::succeeded("Committed")),
+            ]),
+            ..Default::default()
+        };
+        let report = runner().run(&mut executor).await;
+
+        assert!(report.success);
+        assert_eq!(
+            executor.ran,
+            vec![
+                r#"Bash {"command":"cargo test"}"#,
+                "Notes for 1.0; tests exited 0",
+                "/commit Fixed it",
+            ]
+        );
+        assert!(matches!(&executor.events[0], SessionOutput::RecipeStarted { steps, .. } if steps.len() == 3));
+        assert!(matches!(executor.events.last(), Some(SessionOutput::RecipeFinished { success: true, .. })));
+    }
+
+    #[tokio::test]
+    async fn test_failure_asks_and_stops() {
+        let mut executor = ScriptedExecutor {
+            outcomes: VecDeque::from([shell_result(101, "2 failed")]),
+            ..Default::default()
+        };
+        let report = runner().run(&mut executor).await;
+
+        assert!(!report.success);
+        assert_eq!(executor.ran, vec![r#"Bash {"command":"cargo test"}"#, "ask test"]);
+        let statuses: Vec<StepStatus> = report.steps.iter().map(|s| s.status).collect();
+        assert_eq!(statuses, vec![StepStatus::Failed, StepStatus::Skipped, StepStatus::Skipped]);
+        assert_eq!(report.to_string(), "Recipe release stopped\n  ✗ test: 2 failed\n  - notes\n  - commit");
+    }
+
+    #[tokio::test]
+    async fn test_failure_continues_when_confirmed() {
+        let mut executor = ScriptedExecutor {
+            outcomes: VecDeque::from([
+                shell_result(101, ""),
+                Some(StepOutcome::succeeded("Notes")),
+                None,
+            ]),
+            continue_after_failure: true,
+            ..Default::default()
+        };
+        let report = runner().run(&mut executor).await;
+
+        assert_eq!(executor.ran[2], "Notes for 1.0; tests exited 101");
+        assert!(report.cancelled);
+        assert!(!report.success);
+    }
+
+    #[test]
+    fn test_invalid_recipe_is_refused() {
+        let recipe = Recipe::parse(Path::new("release.yaml"), RECIPE).unwrap();
+        let err = RecipeRunner::new(recipe, "1.0", &["Bash".to_string()], &[]).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Recipe error: release is not valid:\n- step commit uses unknown skill commit"
+        );
+    }
+}
@@ -0,0 +1,570 @@
+//! Scheduled runs: session templates run unattended on a cron schedule
+//!
+//! Jobs are kept in `schedules.toml` next to the config file:
+//!
+//! ```toml
+//! [[job]]
+//! id = "deps-1"
+//! cron = "0 3 * * *"
+//! template = "deps"
+//! prompt = "Update dependencies with patch bumps, run the tests and open a PR if they pass"
+//! workspace = "/home/me/project"
+//! approve_tools = ["Read", "Edit", "Bash:cargo*"]
+//! ```
+//!
+//! `cron` is a standard five-field expression (minute, hour, day of month,
+//! month, day of week with 0 or 7 for Sunday), or six or seven fields
+//! starting with seconds. It is read in `[schedule] timezone`, the system's
+//! time zone when that isn't set.
+//!
+//! Runs never ask anyone: `approve_tools` and `deny_tools` settle calls like
+//! one-shot's `--approve-tools` and `--deny-tools`, and any other call that
+//! needs approval is rejected, which makes the run `partial`. A job still
+//! running when it comes due again is skipped. Every run (and skip) is
+//! recorded in the run history, `schedule-history.jsonl` in the data
+//! directory.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Local, Utc};
+use chrono_tz::Tz;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::approval::ToolRule;
+use crate::config::ConfigManager;
+use crate::error::{Error, Result};
+
+/// Jobs file, in the config directory
+pub const SCHEDULE_FILE: &str = "schedules.toml";
+
+/// Run history file, in the data directory
+pub const HISTORY_FILE: &str = "schedule-history.jsonl";
+
+/// Prefix of the saved sessions scheduled runs write their transcripts to
+pub const SESSION_PREFIX: &str = "schedule";
+
+/// A template run on a cron schedule
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduledJob {
+    /// Name the job is listed and removed by
+    pub id: String,
+    /// When the job runs
+    pub cron: String,
+    /// Session template the run starts from
+    pub template: String,
+    /// Arguments filled into the template's opening message, or the opening
+    /// message when the template has none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    /// Workspace the run works in
+    pub workspace: PathBuf,
+    /// Tools that run without asking (`--approve-tools` syntax)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub approve_tools: Vec<String>,
+    /// Tools rejected without asking (`--deny-tools` syntax)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deny_tools: Vec<String>,
+    /// When the job was added
+    pub created_at: DateTime<Utc>,
+}
+
+impl ScheduledJob {
+    /// The job's approve and deny rules
+    pub fn rules(&self) -> Result<(Vec<ToolRule>, Vec<ToolRule>)> {
+        let parse = |rules: &[String]| {
+            rules
+                .iter()
+                .map(|rule| rule.parse().map_err(|e| Error::Schedule(format!("Job {}: {}", self.id, e))))
+                .collect::<Result<Vec<ToolRule>>>()
+        };
+        Ok((parse(&self.approve_tools)?, parse(&self.deny_tools)?))
+    }
+
+    /// The job's next run after `after`
+    pub fn next_run(&self, timezone: &ScheduleTimezone, after: DateTime<Utc>) -> Result<Option<DateTime<Utc>>> {
+        Ok(timezone.next_occurrence(&parse_cron(&self.cron)?, after))
+    }
+
+    /// ID of the saved session a run starting at `started_at` writes its
+    /// transcript to
+    pub fn session_id(&self, started_at: DateTime<Utc>) -> String {
+        format!("{}-{}-{}", SESSION_PREFIX, self.id, started_at.format("%Y%m%d-%H%M%S"))
+    }
+}
+
+/// The scheduled jobs
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Schedule {
+    #[serde(default, rename = "job")]
+    pub jobs: Vec<ScheduledJob>,
+}
+
+impl Schedule {
+    /// `schedules.toml` in the config directory
+    pub fn default_path() -> Result<PathBuf> {
+        let config_path = ConfigManager::default_config_path()?;
+        Ok(config_path.with_file_name(SCHEDULE_FILE))
+    }
+
+    /// The jobs in `path` (none when it doesn't exist)
+    pub fn load(path: &Path) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => toml::from_str(&content)
+                .map_err(|e| Error::Schedule(format!("Failed to parse {}: {}", path.display(), e))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let content =
+            toml::to_string_pretty(self).map_err(|e| Error::Schedule(format!("Failed to write the schedule: {}", e)))?;
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+
+    pub fn get(&self, id: &str) -> Option<&ScheduledJob> {
+        self.jobs.iter().find(|job| job.id == id)
+    }
+
+    /// Add a job, checking its cron expression and rules; an empty `id` is
+    /// replaced with `<template>-<n>`
+    pub fn add(&mut self, mut job: ScheduledJob) -> Result<&ScheduledJob> {
+        parse_cron(&job.cron)?;
+        job.rules()?;
+        if job.id.is_empty() {
+            job.id = (1..)
+                .map(|n| format!("{}-{}", job.template, n))
+                .find(|id| self.get(id).is_none())
+                .unwrap_or_default();
+        } else if self.get(&job.id).is_some() {
+            return Err(Error::Schedule(format!("A job named {} already exists", job.id)));
+        }
+        self.jobs.push(job);
+        Ok(self.jobs.last().unwrap())
+    }
+
+    pub fn remove(&mut self, id: &str) -> Result<ScheduledJob> {
+        let index = self
+            .jobs
+            .iter()
+            .position(|job| job.id == id)
+            .ok_or_else(|| Error::Schedule(format!("No job named {}", id)))?;
+        Ok(self.jobs.remove(index))
+    }
+}
+
+/// Parse a cron expression: five standard fields, or six or seven starting
+/// with seconds
+pub fn parse_cron(expr: &str) -> Result<cron::Schedule> {
+    let fields: Vec<&str> = expr.split_whitespace().collect();
+    let expr = match fields.len() {
+        // Standard cron numbers Sunday 0 (or 7), the cron crate 1
+        5 => format!("0 {} {}", fields[..4].join(" "), standard_days_of_week(fields[4])),
+        _ => fields.join(" "),
+    };
+    cron::Schedule::from_str(&expr).map_err(|e| Error::Schedule(format!("Invalid cron expression '{}': {}", expr, e)))
+}
+
+/// A standard day-of-week field with its numbers replaced by names
+fn standard_days_of_week(field: &str) -> String {
+    const DAYS: [&str; 8] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT", "SUN"];
+    // Numbers after a `/` are steps, not days
+    let day = Regex::new(r"(^|[,\-])(\d+)").unwrap();
+    day.replace_all(field, |caps: &regex::Captures| {
+        let name = caps[2].parse::<usize>().ok().and_then(|n| DAYS.get(n)).copied();
+        format!("{}{}", &caps[1], name.unwrap_or(&caps[2]))
+    })
+    .into_owned()
+}
+
+/// The time zone cron expressions are read in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScheduleTimezone {
+    /// The system's
+    Local,
+    Named(Tz),
+}
+
+impl ScheduleTimezone {
+    /// An IANA time zone name (`Europe/Berlin`, `UTC`), or the system's
+    /// time zone for `local` or None
+    pub fn parse(name: Option<&str>) -> Result<Self> {
+        match name.map(str::trim).filter(|name| !name.is_empty()) {
+            None => Ok(Self::Local),
+            Some(name) if name.eq_ignore_ascii_case("local") => Ok(Self::Local),
+            Some(name) => name
+                .parse()
+                .map(Self::Named)
+                .map_err(|_| Error::Schedule(format!("Unknown time zone '{}'", name))),
+        }
+    }
+
+    /// The first time `schedule` fires after `after`
+    pub fn next_occurrence(&self, schedule: &cron::Schedule, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            Self::Local => schedule.after(&after.with_timezone(&Local)).next().map(|t| t.with_timezone(&Utc)),
+            Self::Named(tz) => schedule.after(&after.with_timezone(tz)).next().map(|t| t.with_timezone(&Utc)),
+        }
+    }
+}
+
+impl fmt::Display for ScheduleTimezone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Local => f.write_str("local"),
+            Self::Named(tz) => f.write_str(tz.name()),
+        }
+    }
+}
+
+/// When each job runs next, for a loop that wakes up to run them
+#[derive(Debug, Default)]
+pub struct Timetable {
+    /// Job ID to its cron expression and next run
+    next: HashMap<String, (String, DateTime<Utc>)>,
+}
+
+impl Timetable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The jobs due at `now`, each once however many of its runs were
+    /// missed; jobs not seen before (or whose expression changed) are due
+    /// from `now` on
+    ///
+    /// Jobs no longer in `jobs` are forgotten. A job whose expression
+    /// doesn't parse is left out with a warning.
+    pub fn due(&mut self, jobs: &[ScheduledJob], timezone: &ScheduleTimezone, now: DateTime<Utc>) -> Vec<ScheduledJob> {
+        self.next.retain(|id, _| jobs.iter().any(|job| &job.id == id));
+        let mut due = Vec::new();
+        for job in jobs {
+            if let Some((cron, next)) = self.next.get(&job.id)
+                && *cron == job.cron
+            {
+                if *next > now {
+                    continue;
+                }
+                due.push(job.clone());
+            }
+            match job.next_run(timezone, now) {
+                Ok(Some(next)) => {
+                    self.next.insert(job.id.clone(), (job.cron.clone(), next));
+                }
+                Ok(None) => {
+                    self.next.remove(&job.id);
+                }
+                Err(e) => {
+                    tracing::warn!("Skipping scheduled job {}: {}", job.id, e);
+                    self.next.remove(&job.id);
+                }
+            }
+        }
+        due
+    }
+
+    /// When the next job is due (None when none will be)
+    pub fn next_due(&self) -> Option<DateTime<Utc>> {
+        self.next.values().map(|(_, next)| *next).min()
+    }
+
+    /// When the job runs next, as of the last `due`
+    pub fn next_run(&self, id: &str) -> Option<DateTime<Utc>> {
+        self.next.get(id).map(|(_, next)| *next)
+    }
+}
+
+/// Jobs being run, so a job is never run twice at once
+#[derive(Debug, Clone, Default)]
+pub struct RunningJobs {
+    running: Arc<Mutex<HashSet<String>>>,
+}
+
+impl RunningJobs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mark the job running until the guard is dropped (None when it
+    /// already is, and this run should be skipped)
+    pub fn try_start(&self, id: &str) -> Option<RunGuard> {
+        let inserted = self.running.lock().unwrap().insert(id.to_string());
+        inserted.then(|| RunGuard {
+            id: id.to_string(),
+            running: self.running.clone(),
+        })
+    }
+
+    pub fn is_running(&self, id: &str) -> bool {
+        self.running.lock().unwrap().contains(id)
+    }
+}
+
+/// A job's run in progress (see `RunningJobs::try_start`)
+#[derive(Debug)]
+pub struct RunGuard {
+    id: String,
+    running: Arc<Mutex<HashSet<String>>>,
+}
+
+impl Drop for RunGuard {
+    fn drop(&mut self) {
+        self.running.lock().unwrap().remove(&self.id);
+    }
+}
+
+/// How a scheduled run ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    /// The turn finished without errors or rejected tool calls
+    Success,
+    /// The turn finished, but tool calls were rejected
+    Partial,
+    /// The run couldn't start, or the turn ended in an error
+    Failed,
+    /// The job was still running from its previous occurrence
+    Skipped,
+}
+
+impl fmt::Display for RunStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Success => "success",
+            Self::Partial => "partial",
+            Self::Failed => "failed",
+            Self::Skipped => "skipped",
+        })
+    }
+}
+
+/// One run of a job, in the run history
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub job_id: String,
+    pub started_at: DateTime<Utc>,
+    /// None for a skipped run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<DateTime<Utc>>,
+    pub status: RunStatus,
+    /// Saved session with the run's transcript
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    /// Tool calls rejected without asking
+    #[serde(default)]
+    pub rejected_tools: usize,
+    /// The error a failed run ended with, or why a run was skipped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl RunRecord {
+    /// A run that didn't happen because the job was still running
+    pub fn skipped(job_id: impl Into<String>, at: DateTime<Utc>) -> Self {
+        Self {
+            job_id: job_id.into(),
+            started_at: at,
+            finished_at: None,
+            status: RunStatus::Skipped,
+            session_id: None,
+            rejected_tools: 0,
+            message: Some("The previous run was still going".to_string()),
+        }
+    }
+}
+
+/// The run history, one JSON record per line
+#[derive(Debug, Clone)]
+pub struct RunHistory {
+    path: PathBuf,
+}
+
+impl RunHistory {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// `<data dir>/cowork/schedule-history.jsonl`
+    pub fn open_default() -> Self {
+        Self::new(
+            dirs::data_dir()
+                .map(|p| p.join("cowork"))
+                .unwrap_or_else(|| PathBuf::from(".cowork"))
+                .join(HISTORY_FILE),
+        )
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn append(&self, record: &RunRecord) -> Result<()> {
+        if let Some(parent) = self.path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = std::fs::OpenOptions::new().create(true).append(true).open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(record)?)?;
+        Ok(())
+    }
+
+    /// The recorded runs, oldest first, of one job or all of them
+    ///
+    /// Lines that don't parse are skipped.
+    pub fn load(&self, job_id: Option<&str>) -> Result<Vec<RunRecord>> {
+        let content = match std::fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(content
+            .lines()
+            .filter_map(|line| serde_json::from_str::<RunRecord>(line).ok())
+            .filter(|record| job_id.is_none_or(|id| record.job_id == id))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use tempfile::TempDir;
+
+    fn job(id: &str, cron: &str) -> ScheduledJob {
+        ScheduledJob {
+            id: id.to_string(),
+            cron: cron.to_string(),
+            template: "deps".to_string(),
+            prompt: None,
+            workspace: PathBuf::from("/tmp/project"),
+            approve_tools: vec!["Bash:cargo*".to_string()],
+            deny_tools: Vec::new(),
+            created_at: Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap(),
+        }
+    }
+
+    fn utc(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, mo, d, h, mi, 0).unwrap()
+    }
+
+    #[test]
+    fn test_next_occurrence() {
+        let utc_zone = ScheduleTimezone::parse(Some("UTC")).unwrap();
+        let nightly = job("nightly", "0 3 * * *");
+        // Friday 2026-03-06
+        assert_eq!(nightly.next_run(&utc_zone, utc(2026, 3, 6, 2, 59)).unwrap(), Some(utc(2026, 3, 6, 3, 0)));
+        assert_eq!(nightly.next_run(&utc_zone, utc(2026, 3, 6, 3, 0)).unwrap(), Some(utc(2026, 3, 7, 3, 0)));
+
+        // Standard day-of-week numbers: 1-5 is Monday to Friday
+        let weekdays = job("weekdays", "30 9 * * 1-5");
+        assert_eq!(weekdays.next_run(&utc_zone, utc(2026, 3, 6, 10, 0)).unwrap(), Some(utc(2026, 3, 9, 9, 30)));
+        let sundays = job("sundays", "0 12 * * 0");
+        assert_eq!(sundays.next_run(&utc_zone, utc(2026, 3, 6, 0, 0)).unwrap(), Some(utc(2026, 3, 8, 12, 0)));
+        assert_eq!(standard_days_of_week("*/2"), "*/2");
+
+        // Read in the configured zone: 03:00 in Berlin is 02:00 UTC in winter, 01:00 in summer
+        let berlin = ScheduleTimezone::parse(Some("Europe/Berlin")).unwrap();
+        assert_eq!(nightly.next_run(&berlin, utc(2026, 1, 10, 12, 0)).unwrap(), Some(utc(2026, 1, 11, 2, 0)));
+        assert_eq!(nightly.next_run(&berlin, utc(2026, 7, 10, 12, 0)).unwrap(), Some(utc(2026, 7, 11, 1, 0)));
+
+        // Six fields start with seconds
+        let every_half_minute = job("fast", "*/30 * * * * *");
+        assert_eq!(
+            every_half_minute.next_run(&utc_zone, utc(2026, 3, 6, 0, 0)).unwrap(),
+            Some(Utc.with_ymd_and_hms(2026, 3, 6, 0, 0, 30).unwrap())
+        );
+
+        assert!(parse_cron("not a schedule").is_err());
+        assert!(ScheduleTimezone::parse(Some("Mars/Olympus")).is_err());
+        assert_eq!(ScheduleTimezone::parse(Some("local")).unwrap(), ScheduleTimezone::Local);
+    }
+
+    #[test]
+    fn test_timetable_runs_each_occurrence_once() {
+        let zone = ScheduleTimezone::parse(Some("UTC")).unwrap();
+        let jobs = vec![job("hourly", "0 * * * *")];
+        let mut timetable = Timetable::new();
+        // A new job waits for its next occurrence
+        assert!(timetable.due(&jobs, &zone, utc(2026, 3, 6, 9, 30)).is_empty());
+        assert_eq!(timetable.next_due(), Some(utc(2026, 3, 6, 10, 0)));
+        assert!(timetable.due(&jobs, &zone, utc(2026, 3, 6, 9, 59)).is_empty());
+        assert_eq!(timetable.due(&jobs, &zone, utc(2026, 3, 6, 10, 0)).len(), 1);
+        assert!(timetable.due(&jobs, &zone, utc(2026, 3, 6, 10, 0)).is_empty());
+        // Missed occurrences run once
+        assert_eq!(timetable.due(&jobs, &zone, utc(2026, 3, 6, 14, 5)).len(), 1);
+        assert_eq!(timetable.next_run("hourly"), Some(utc(2026, 3, 6, 15, 0)));
+        // Removed jobs are forgotten
+        timetable.due(&[], &zone, utc(2026, 3, 6, 14, 6));
+        assert_eq!(timetable.next_due(), None);
+    }
+
+    #[test]
+    fn test_overlapping_run_is_skipped() {
+        let running = RunningJobs::new();
+        let first = running.try_start("nightly").unwrap();
+        assert!(running.is_running("nightly"));
+        // Another job can run alongside
+        let other = running.try_start("weekly");
+        assert!(other.is_some());
+        // The same job can't until its run finishes
+        assert!(running.try_start("nightly").is_none());
+        drop(first);
+        assert!(!running.is_running("nightly"));
+        assert!(running.try_start("nightly").is_some());
+    }
+
+    #[test]
+    fn test_schedule_and_history_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(SCHEDULE_FILE);
+        let mut schedule = Schedule::load(&path).unwrap();
+        assert!(schedule.jobs.is_empty());
+
+        let added = schedule.add(job("", "0 3 * * *")).unwrap().id.clone();
+        assert_eq!(added, "deps-1");
+        assert_eq!(schedule.add(job("", "0 4 * * *")).unwrap().id, "deps-2");
+        assert!(schedule.add(job("deps-1", "0 5 * * *")).is_err());
+        assert!(schedule.add(job("bad", "61 * * * *")).is_err());
+        let mut bad_rule = job("bad", "0 3 * * *");
+        bad_rule.deny_tools = vec!["Bash:".to_string()];
+        assert!(schedule.add(bad_rule).is_err());
+        schedule.save(&path).unwrap();
+
+        let mut loaded = Schedule::load(&path).unwrap();
+        assert_eq!(loaded, schedule);
+        assert_eq!(loaded.remove("deps-2").unwrap().cron, "0 4 * * *");
+        assert!(loaded.remove("deps-2").is_err());
+
+        let history = RunHistory::new(dir.path().join(HISTORY_FILE));
+        assert!(history.load(None).unwrap().is_empty());
+        let started = utc(2026, 3, 6, 3, 0);
+        history
+            .append(&RunRecord {
+                job_id: "deps-1".to_string(),
+                started_at: started,
+                finished_at: Some(utc(2026, 3, 6, 3, 4)),
+                status: RunStatus::Partial,
+                session_id: Some(job("deps-1", "0 3 * * *").session_id(started)),
+                rejected_tools: 2,
+                message: None,
+            })
+            .unwrap();
+        history.append(&RunRecord::skipped("deps-2", started)).unwrap();
+        let runs = history.load(Some("deps-1")).unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].session_id.as_deref(), Some("schedule-deps-1-20260306-030000"));
+        assert_eq!(history.load(None).unwrap()[1].status, RunStatus::Skipped);
+    }
+}
@@ -8,26 +8,63 @@
 //! - Automatic context window management
 //! - Saving session state on close
 
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use tokio::sync::mpsc;
-use tokio::task::JoinSet;
+use tokio::task::{AbortHandle, JoinSet};
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, warn};
 
+use super::attachments::{image_dimensions, validate_image};
+use super::file_watcher::{external_change_reminder, WorkspaceWatcher};
+use super::fork::SessionSnapshot;
+use super::limits::{RequestLimiter, RequestPermit};
+use super::workspace_locks::{FileLock, WorkspaceLocks};
+use super::repeat_calls::{RepeatDetector, RepeatVerdict};
+use super::tool_scheduler::{resolve_path, ToolScheduler};
+use super::mentions::{extract_mentions, mention_reminder, resolve_mentions};
+use super::selection::{capped_selection, with_selection};
+use super::pins::MessagePins;
 use super::approval::{
-    approval_channel, ApprovalReceiver, ApprovalRequest, ApprovalResponse,
-    ApprovalSender, QuestionResponse, ToolExecutionContext,
+    approval_channel, progress_channel, ApprovalReceiver, ApprovalRequest, ApprovalResponse,
+    ApprovalSender, Delegation, FileWrites, QuestionResponse, ToolExecutionContext,
 };
-use super::types::{SessionConfig, SessionId, SessionInput, SessionOutput};
+use super::output::OutputSender;
+use super::queue::{InputQueue, QueuedInput, Rerun};
+use super::overrides::{resolve_model, TurnApproval, TurnOverrides};
+use super::tool_usage::ToolUsageStats;
+use super::types::{QuestionInfo, QuestionOption, SessionConfig, SessionId, SessionInput, SessionOutput, ToolDoneStatus};
+use super::explain::{clean_explanation, explanation_request, EXPLAIN_PURPOSE, EXPLAIN_TAIL_MESSAGES};
+use super::self_review::{parse_findings, review_request, SELF_REVIEW_PURPOSE};
+use super::turn_changes::TurnChangeSet;
 use super::ChatSession;
-use crate::context::{compact, context_limit, usage_stats};
+use crate::approval::{exfiltration, RuleDecision};
+use crate::context::{
+    compact_result, context_limit, emergency_summarization_request, estimate_image_tokens, heuristic_compact,
+    is_summary, learnable_note, project_overview_within, summarization_request, usage_stats, CompactResult,
+    CompactionMode, LocalMemory, COMPACTION_PURPOSE, GATHER_TIMEOUT, LOCAL_MEMORY_FILE,
+};
 use crate::error::Result;
-use crate::formatting::{format_tool_call, format_tool_result_summary, truncate_tool_result};
-use crate::orchestration::ToolRegistryBuilder;
-use crate::prompt::{HookContext, HookEvent, HookExecutor, HooksConfig};
-use crate::provider::{ChatMessage, GenAIProvider, ToolCall};
-use crate::skills::SkillRegistry;
+use crate::formatting::{
+    format_tool_call, format_tool_result_summary, tool_call_diff, tool_result_line, truncate_tool_result, PathDisplay,
+};
+use crate::i18n::{Localizer, Text};
+use crate::orchestration::tool_profile::definition_tokens;
+use crate::orchestration::{ToolProfile, ToolRegistryBuilder};
+use crate::prompt::builtin::claude_code::reminders;
+use crate::prompt::{HookContext, HookEvent, HookExecutor, HooksConfig, ToolRestrictions, ToolSpec, SCRATCH_DIR_VAR};
+use crate::provider::{
+    catalog, collect_stream, context_overflow, is_server_error, message_text_content, ContextOverflow, provider_circuits, tool_schema, ChatMessage, ChatRole, CompletionResult, EventStream, FinishReason, GenAIProvider, GenerationParams,
+    LlmProvider, SchemaDialect, ToolCall, UsageRecord, UsageStore, DEFAULT_REQUEST_TIMEOUT, DEFAULT_STREAM_TIMEOUT,
+};
+use crate::recipes::{load_recipe, RecipeRunner, StepExecutor, StepOutcome};
+use crate::skills::{BoxFuture, SkillRegistry};
+use crate::telemetry;
+use crate::tools::filesystem::{normalize_path, path_to_display};
 use crate::tools::interaction::ASK_QUESTION_TOOL_NAME;
-use crate::tools::planning::PlanModeState;
+use crate::tools::planning::{PlanModeState, PlanStatus, PlanStore};
+use crate::tools::dry_run::runs_in_dry_run;
+use crate::tools::task::TodoList;
 use crate::tools::{ToolDefinition, ToolRegistry};
 
 /// Maximum number of agentic turns per user message
@@ -38,6 +75,245 @@ const MAX_ITERATIONS: usize = 100;
 /// ~30k chars ≈ ~10k tokens, leaving room for conversation history
 const MAX_TOOL_RESULT_SIZE: usize = 30_000;
 
+/// Times a response cut off by the output token limit is continued per turn
+const MAX_LENGTH_CONTINUATIONS: usize = 3;
+
+/// Times the model is asked to redo a tool call the provider couldn't parse per turn
+const MAX_MALFORMED_CALL_RETRIES: usize = 2;
+
+/// Times a request that timed out is sent again before the turn fails
+const MAX_TIMEOUT_RETRIES: usize = 1;
+
+/// How long the request naming a session may take before it's abandoned
+const TITLE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// How long the request explaining a pending tool call may take before it's abandoned
+const EXPLAIN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// How long the end-of-turn self-review may take before it's abandoned
+const SELF_REVIEW_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Shortest gap between two progress reports forwarded for the same tool call
+const TOOL_PROGRESS_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// How long a cancelled tool gets to stop on its own before its task is aborted
+const TOOL_CANCEL_GRACE: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Sent after a response hit the output token limit
+const LENGTH_CONTINUATION: &str = "Your last response was cut off by the output token limit. Continue exactly where it stopped, without repeating what you already wrote.";
+
+/// Sent after a tool call the provider couldn't parse
+const MALFORMED_TOOL_CALL: &str = "Your last tool call could not be parsed, so nothing was run. Make the call again with arguments that are valid JSON matching the tool's schema.";
+
+/// Result recorded for a tool call the user cancelled
+const TOOL_CANCELLED: &str = "Cancelled by user";
+
+/// Answer that saves an offered memory note
+const MEMORY_SAVE: &str = "Save";
+/// Answer that dismisses an offered memory note
+const MEMORY_SKIP: &str = "Don't save";
+
+/// Answer that carries on with a recipe after a failed step
+const RECIPE_CONTINUE: &str = "Continue";
+/// Answer that stops a recipe after a failed step
+const RECIPE_STOP: &str = "Stop";
+
+/// The model backend an agent loop talks to
+enum LlmClient {
+    GenAI(GenAIProvider),
+    /// Scripted responses for tests
+    #[cfg(feature = "mock-provider")]
+    Mock(Arc<crate::provider::MockProvider>),
+}
+
+impl LlmClient {
+    /// Create the session's client: the scripted mock if one is configured, else genai
+    fn from_config(config: &SessionConfig) -> Result<Self> {
+        #[cfg(feature = "mock-provider")]
+        if let Some(mock) = config.mock_provider.clone() {
+            // The policy still applies, though the mock sends nothing
+            if let Some(policy) = &config.policy {
+                policy.check_provider(&config.provider_id, config.base_url.as_deref())?;
+            }
+            return Ok(Self::Mock(mock));
+        }
+
+        let provider = Self::connect(config, config.model.as_deref())?;
+        Ok(Self::GenAI(match config.system_prompt.as_deref() {
+            Some(prompt) => provider.with_system_prompt(prompt),
+            None => provider,
+        }))
+    }
+
+    /// A genai provider for the session's provider and `model`, without a system prompt
+    fn connect(config: &SessionConfig, model: Option<&str>) -> Result<GenAIProvider> {
+        Self::connect_to(config, &config.provider_id, config.api_key.clone(), config.base_url.as_deref(), model)
+    }
+
+    /// A genai provider for `provider_id` and `model`, under the session's policy
+    fn connect_to(
+        config: &SessionConfig,
+        provider_id: &str,
+        api_key: Option<String>,
+        base_url: Option<&str>,
+        model: Option<&str>,
+    ) -> Result<GenAIProvider> {
+        // The policy's gateway replaces any configured base URL, so requests
+        // can't go around it even with the key taken from the environment
+        let base_url = match &config.policy {
+            Some(policy) => policy.check_provider(provider_id, base_url)?,
+            None => base_url.map(str::to_string),
+        };
+        let api_key = match (api_key, &config.policy) {
+            (Some(key), _) => Some(key),
+            (None, Some(policy)) if policy.provider.base_url.is_some() => Some(
+                catalog::api_key_env(provider_id)
+                    .and_then(|name| std::env::var(name).ok())
+                    .unwrap_or_default(),
+            ),
+            (None, _) => None,
+        };
+
+        match api_key.as_deref() {
+            Some(key) => GenAIProvider::with_config(provider_id, key, model, base_url.as_deref()),
+            None => GenAIProvider::new(provider_id, model),
+        }
+    }
+
+    /// The session provider's fast model (the `fast` tier), or its own
+    /// model when the provider has no tiers
+    fn fast_model(config: &SessionConfig) -> Option<String> {
+        config
+            .model_tiers
+            .as_ref()
+            .map(|tiers| tiers.fast.clone())
+            .or_else(|| catalog::model_tiers(&config.provider_id).map(|(fast, _, _)| fast.to_string()))
+            .or_else(|| config.model.clone())
+    }
+
+    /// A client for compaction summaries: the `[summarizer]` provider and
+    /// model, by default the session provider's fast model; the same script
+    /// for the mock
+    fn summarizer(&self, config: &SessionConfig) -> Result<Self> {
+        #[cfg(feature = "mock-provider")]
+        if let Self::Mock(_) = self {
+            return self.side_client(config);
+        }
+        let Some(provider) = &config.summarizer_provider else {
+            let model = config.summarizer.model.clone().or_else(|| Self::fast_model(config));
+            return Ok(Self::GenAI(Self::connect(config, model.as_deref())?));
+        };
+        let model = config
+            .summarizer
+            .model
+            .clone()
+            .or_else(|| provider.model_tiers.as_ref().map(|tiers| tiers.fast.clone()))
+            .or_else(|| catalog::model_tiers(&provider.provider_type).map(|(fast, _, _)| fast.to_string()))
+            .unwrap_or_else(|| provider.model.clone());
+        Ok(Self::GenAI(Self::connect_to(
+            config,
+            &provider.provider_type,
+            provider.get_api_key(),
+            provider.base_url.as_deref(),
+            Some(&model),
+        )?))
+    }
+
+    /// The same client answering with `model` (for a turn's overrides)
+    fn for_model(&self, model: &str) -> Self {
+        match self {
+            Self::GenAI(provider) => Self::GenAI(provider.for_model(model)),
+            #[cfg(feature = "mock-provider")]
+            Self::Mock(mock) => Self::Mock(mock.for_model(model)),
+        }
+    }
+
+    /// A client for side requests made outside the session's turns: the
+    /// fast model for genai, the same script for the mock
+    fn side_client(&self, config: &SessionConfig) -> Result<Self> {
+        match self {
+            Self::GenAI(_) => Ok(Self::GenAI(Self::connect(config, Self::fast_model(config).as_deref())?)),
+            #[cfg(feature = "mock-provider")]
+            Self::Mock(mock) => Ok(Self::Mock(mock.clone())),
+        }
+    }
+
+    /// The usage record of a completed call, unless the provider reported no tokens
+    fn usage_record(
+        &self,
+        session_id: &str,
+        input_tokens: Option<u64>,
+        output_tokens: Option<u64>,
+        cached_tokens: Option<u64>,
+        purpose: Option<&str>,
+    ) -> Option<UsageRecord> {
+        if input_tokens.is_none() && output_tokens.is_none() {
+            return None;
+        }
+        Some(UsageRecord {
+            timestamp: chrono::Utc::now(),
+            session_id: session_id.to_string(),
+            provider: self.provider_id().to_string(),
+            model: self.model().to_string(),
+            input_tokens: input_tokens.unwrap_or(0),
+            output_tokens: output_tokens.unwrap_or(0),
+            cached_tokens: cached_tokens.unwrap_or(0),
+            purpose: purpose.map(str::to_string),
+        })
+    }
+
+    /// The backend behind the client
+    fn provider(&self) -> &dyn LlmProvider {
+        match self {
+            Self::GenAI(provider) => provider,
+            #[cfg(feature = "mock-provider")]
+            Self::Mock(mock) => mock.as_ref(),
+        }
+    }
+
+    fn provider_id(&self) -> &str {
+        self.provider().provider_id()
+    }
+
+    /// Whether the model accepts images (see `catalog::supports_vision`)
+    fn supports_images(&self) -> bool {
+        match self {
+            Self::GenAI(provider) => catalog::supports_vision(provider.provider_id()),
+            #[cfg(feature = "mock-provider")]
+            Self::Mock(_) => true,
+        }
+    }
+
+    fn model(&self) -> &str {
+        self.provider().model()
+    }
+
+    async fn chat(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<Vec<ToolDefinition>>,
+        params: &GenerationParams,
+    ) -> Result<CompletionResult> {
+        self.provider().complete(messages, tools, params).await
+    }
+
+    fn stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<Vec<ToolDefinition>>,
+        params: &GenerationParams,
+    ) -> EventStream<'_> {
+        self.provider().stream(messages, tools, params)
+    }
+}
+
+/// Session settings a turn's overrides replaced, put back when the turn ends
+#[derive(Default)]
+struct OverriddenSettings {
+    provider: Option<LlmClient>,
+    /// The session profile's tools and their definitions
+    tools: Option<(ToolRegistry, Vec<ToolDefinition>)>,
+}
 
 /// Result from an LLM call
 struct LlmCallResult {
@@ -47,6 +323,10 @@ struct LlmCallResult {
     input_tokens: Option<u64>,
     /// Output tokens for this response (from provider)
     output_tokens: Option<u64>,
+    /// Input tokens read from the prompt cache
+    cached_tokens: Option<u64>,
+    /// Why the model stopped, when the provider said
+    finish_reason: Option<FinishReason>,
 }
 
 /// Info for spawning a subagent from a skill with `context: fork`
@@ -68,10 +348,80 @@ struct SpawnedToolResult {
     arguments: serde_json::Value,
     success: bool,
     output: String,
+    /// Structured result for the frontend, already compacted
+    artifacts: Vec<crate::tools::Artifact>,
     /// For skill injection: (content, skill_name)
     inject_info: Option<(String, Option<String>)>,
+    /// Tools the injected skill is restricted to (from `allowed-tools`)
+    allowed_tools: Option<Vec<String>>,
     /// For skill subagent spawning (context: fork)
     subagent_info: Option<SubagentSpawnInfo>,
+    /// The user cancelled the call while it waited for approval
+    cancelled: bool,
+    /// Refused by the user or a reviewer, or cancelled while waiting for approval
+    rejected: bool,
+    /// Time from the task starting to the tool returning
+    elapsed: std::time::Duration,
+}
+
+/// A file a call writes, locked against other sessions while the tool runs
+struct FileLockRequest {
+    locks: Arc<WorkspaceLocks>,
+    path: std::path::PathBuf,
+    /// The path as the conflict error names it
+    shown: String,
+    session_id: SessionId,
+    title: Arc<SessionTitle>,
+    output: OutputSender,
+}
+
+impl FileLockRequest {
+    /// Take the lock, or tell the user which session holds it and fail
+    async fn acquire(self) -> std::result::Result<FileLock, crate::error::ToolError> {
+        let lock = self.locks.try_lock(&self.path, &self.shown, &self.session_id, Some(self.title));
+        if let Err(e) = &lock {
+            self.output.emit_critical(SessionOutput::warning(e.to_string())).await;
+        }
+        lock
+    }
+}
+
+/// Locks and snapshots the files a call picks as it runs (BatchEdit), as
+/// the agent loop does for a file tool's path argument
+struct TurnWrites {
+    workspace: std::path::PathBuf,
+    locks: Option<Arc<WorkspaceLocks>>,
+    changes: Arc<parking_lot::Mutex<TurnChangeSet>>,
+    path_display: PathDisplay,
+    session_id: SessionId,
+    title: Arc<SessionTitle>,
+    output: OutputSender,
+}
+
+impl FileWrites for TurnWrites {
+    fn prepare<'a>(&'a self, paths: &'a [std::path::PathBuf]) -> BoxFuture<'a, std::result::Result<Vec<FileLock>, crate::error::ToolError>> {
+        Box::pin(async move {
+            let paths: Vec<_> = paths.iter().map(|path| resolve_path(&self.workspace, path)).collect();
+            let mut held = Vec::new();
+            if let Some(locks) = &self.locks {
+                for path in &paths {
+                    let shown = self.path_display.display(&path.to_string_lossy());
+                    match locks.try_lock(path, &shown, &self.session_id, Some(self.title.clone())) {
+                        Ok(lock) => held.push(lock),
+                        Err(e) => {
+                            self.output.emit_critical(SessionOutput::warning(e.to_string())).await;
+                            return Err(e);
+                        }
+                    }
+                }
+            }
+            let mut changes = self.changes.lock();
+            for path in &paths {
+                changes.snapshot(path);
+            }
+            Ok(held)
+        })
+    }
 }
 
 /// Execute a tool and build the result
@@ -81,8 +431,39 @@ async fn execute_tool_task(
     name: String,
     arguments: serde_json::Value,
     ctx: ToolExecutionContext,
+    file_lock: Option<FileLockRequest>,
 ) -> SpawnedToolResult {
-    match tool.execute(arguments.clone(), ctx).await {
+    let started = std::time::Instant::now();
+    // A delegated or flagged call waits for approval before the tool runs at all
+    let ctx = if ctx.delegation().is_some() || !ctx.findings().is_empty() {
+        ctx.request_approval(arguments.clone(), None)
+            .await
+            .map(|()| ctx.approved())
+            .map_err(crate::error::ToolError::Rejected)
+    } else {
+        Ok(ctx)
+    };
+    // The file is locked only once the call may run, so approvals don't hold it
+    let result = match ctx {
+        Ok(ctx) => match file_lock {
+            Some(request) => match request.acquire().await {
+                Ok(_lock) => tool.execute(arguments.clone(), ctx).await,
+                Err(e) => Err(e),
+            },
+            None => tool.execute(arguments.clone(), ctx).await,
+        },
+        Err(e) => Err(e),
+    };
+    let outcome = match &result {
+        Err(crate::error::ToolError::Rejected(_)) => "rejected",
+        result => telemetry::outcome(result),
+    };
+    telemetry::histogram(
+        telemetry::TOOL_SECONDS,
+        started.elapsed().as_secs_f64(),
+        &[("tool", &name), ("outcome", outcome)],
+    );
+    match result {
         Ok(output) => {
             let output_str = output.content.to_string();
             let skill_name = output.metadata.get(crate::tools::skill::SKILL_NAME_KEY)
@@ -117,23 +498,102 @@ async fn execute_tool_task(
                 (inject_info, None)
             };
 
-            SpawnedToolResult { id, name, arguments, success: true, output: output_str, inject_info, subagent_info }
+            let allowed_tools = output.metadata.get(crate::tools::skill::ALLOWED_TOOLS)
+                .and_then(|v| v.as_array())
+                .map(|tools| tools.iter().filter_map(|t| t.as_str().map(String::from)).collect());
+
+            let artifacts = output.artifacts.into_iter().filter_map(|a| a.compact()).collect();
+
+            SpawnedToolResult {
+                id, name, arguments, success: true, output: output_str, artifacts,
+                inject_info, allowed_tools, subagent_info, cancelled: false,
+                rejected: false, elapsed: started.elapsed(),
+            }
         }
         Err(e) => SpawnedToolResult {
             id, name, arguments, success: false,
+            rejected: matches!(e, crate::error::ToolError::Rejected(_)),
             output: format!("Error: {}", e),
-            inject_info: None, subagent_info: None,
+            artifacts: Vec::new(),
+            inject_info: None, allowed_tools: None, subagent_info: None, cancelled: false,
+            elapsed: started.elapsed(),
+        }
+    }
+}
+
+/// Tool restriction declared by an inline skill via `allowed-tools`
+///
+/// Active from the moment the skill is injected until the current turn ends,
+/// so it applies across every LLM iteration the skill drives.
+struct SkillToolRestriction {
+    skill_name: String,
+    restrictions: ToolRestrictions,
+}
+
+impl SkillToolRestriction {
+    fn new(skill_name: impl Into<String>, allowed_tools: &[String]) -> Self {
+        Self {
+            skill_name: skill_name.into(),
+            restrictions: ToolRestrictions::allow_only(
+                allowed_tools.iter().map(|t| ToolSpec::parse(t)).collect(),
+            ),
         }
     }
+
+    /// Keep only the tool definitions the skill may use
+    fn filter_definitions(&self, definitions: Vec<ToolDefinition>) -> Vec<ToolDefinition> {
+        definitions
+            .into_iter()
+            .filter(|td| self.restrictions.allows_tool(&td.name))
+            .collect()
+    }
+
+    /// Error message for a tool call outside the allowed list, if any
+    fn rejection(&self, tool_call: &ToolCall) -> Option<String> {
+        if self.restrictions.is_allowed(&tool_call.fn_name, &tool_call.fn_arguments) {
+            return None;
+        }
+        let allowed: Vec<String> = self.restrictions.allowed.iter().map(|s| s.to_string()).collect();
+        Some(format!(
+            "Tool '{}' is not allowed while running skill '/{}'. Allowed tools: {}",
+            tool_call.fn_name,
+            self.skill_name,
+            allowed.join(", ")
+        ))
+    }
+}
+
+/// Approvals waiting on the frontend: tool name and responder by tool call ID
+type PendingApprovals = std::collections::HashMap<String, (String, tokio::sync::oneshot::Sender<ApprovalResponse>)>;
+
+/// Record how long a call waited for someone to decide on it
+fn record_approval_wait(
+    asked_at: &mut std::collections::HashMap<String, std::time::Instant>,
+    tool_call_id: &str,
+    tool: &str,
+    outcome: &str,
+    decider: &str,
+) {
+    if let Some(asked) = asked_at.remove(tool_call_id) {
+        telemetry::histogram(
+            telemetry::APPROVAL_WAIT_SECONDS,
+            asked.elapsed().as_secs_f64(),
+            &[("tool", tool), ("outcome", outcome), ("decider", decider)],
+        );
+    }
 }
 
 /// Reject all pending approval and question requests
 fn reject_all_pending(
-    approvals: &mut std::collections::HashMap<String, tokio::sync::oneshot::Sender<ApprovalResponse>>,
+    approvals: &mut PendingApprovals,
     questions: &mut std::collections::HashMap<String, tokio::sync::oneshot::Sender<QuestionResponse>>,
     reason: &str,
+    audit: Option<&AuditLog>,
 ) {
-    for (_, tx) in approvals.drain() {
+    for (id, (name, tx)) in approvals.drain() {
+        if let Some(audit) = audit {
+            audit.rejected(&id, &name, Decider::System, Some(reason.to_string()));
+        }
         let _ = tx.send(ApprovalResponse::Rejected { reason: Some(reason.to_string()) });
     }
     for (_, tx) in questions.drain() {
@@ -141,35 +601,94 @@ fn reject_all_pending(
     }
 }
 
-use super::persistence::{get_sessions_dir, SavedSession};
-
-/// User input with optional image attachments
-type UserInput = (String, Vec<super::ImageAttachment>);
+use super::audit::{AuditLog, Decider};
+use super::persistence::{
+    get_scratch_dir, remove_autosave, remove_scratch_dir, write_autosave, write_session, SavedSession,
+    SESSION_FORMAT_VERSION,
+};
+use super::retention::{set_session_pinned, set_session_title};
+use super::title::{fallback_title, generate_title, SessionTitle, TitleSource};
 
 /// The unified agent loop
 pub struct AgentLoop {
     /// Session identifier
     session_id: SessionId,
-    /// Message receiver (user text messages with optional images)
-    message_rx: mpsc::UnboundedReceiver<UserInput>,
+    /// Queue of user messages (text with optional images) waiting to run
+    input_queue: Arc<InputQueue>,
     /// Control receiver (approvals, rejections, question answers, cancel)
     control_rx: mpsc::UnboundedReceiver<SessionInput>,
-    /// Output sender
-    output_tx: mpsc::Sender<(SessionId, SessionOutput)>,
+    /// Output sender (blocks only for essential outputs)
+    output: OutputSender,
     /// Approval channel sender (passed to tools and subagents)
     approval_tx: ApprovalSender,
     /// Approval channel receiver (for handling approval requests from tools)
     approval_rx: ApprovalReceiver,
     /// LLM provider
-    provider: GenAIProvider,
+    provider: LlmClient,
     /// Chat session with message history
     session: ChatSession,
-    /// Tool registry
+    /// Every tool the session has, whatever its tool profile
+    all_tools: ToolRegistry,
+    /// The tools the session's tool profile offers
     tool_registry: ToolRegistry,
-    /// Tool definitions for LLM
+    /// Tool definitions for LLM, in the provider's schema dialect
     tool_definitions: Vec<ToolDefinition>,
+    /// Tool profile to switch to when the next turn starts (set by SetToolProfile)
+    tool_profile: Arc<parking_lot::Mutex<Option<ToolProfile>>>,
+    schema_dialect: SchemaDialect,
     /// Plan mode state (shared with EnterPlanMode/ExitPlanMode tools and /plan command)
     plan_mode_state: Arc<tokio::sync::RwLock<PlanModeState>>,
+    /// TodoWrite checklist (shared with the tool, reported to the frontend and saved)
+    todo_list: TodoList,
+    /// Generation parameters for LLM requests (updated by SetGenerationParams)
+    generation_params: Arc<tokio::sync::RwLock<GenerationParams>>,
+    /// Whether the session is exempt from pruning (updated by SetPinned)
+    pinned: Arc<AtomicBool>,
+    /// Messages compaction keeps (updated by PinMessage)
+    pins: Arc<parking_lot::Mutex<MessagePins>>,
+    /// Text selected in the app, until the next message takes it (updated by SetSelection)
+    selection: Arc<parking_lot::Mutex<Option<String>>>,
+    /// The session's title (shared with the manager; updated by SetTitle)
+    title: Arc<SessionTitle>,
+    /// Fast model that names the session after its first turn, until it has tried
+    title_provider: Option<GenAIProvider>,
+    /// Fast model that explains pending tool calls and reviews turns (None = it couldn't be set up)
+    explain_client: Option<Arc<LlmClient>>,
+    /// Review each turn's changes against its request (see `session::self_review`)
+    self_review: bool,
+    /// Findings above which a reviewed turn's `Idle` warns
+    self_review_threshold: usize,
+    /// Files written and commands run by the current turn
+    turn_changes: Arc<parking_lot::Mutex<TurnChangeSet>>,
+    /// The message the current turn answers (None = not a message, or cancelled), for the self-review
+    turn_request: Option<String>,
+    /// How the history is compacted when the context fills up
+    summarizer: crate::config::SummarizerConfig,
+    /// Model that writes compaction summaries (None = it couldn't be set up)
+    summarizer_client: Option<LlmClient>,
+    /// Summarization prompt from `summarizer.prompt_file` (None = the built-in one)
+    summary_template: Option<String>,
+    /// Tool restriction from an injected skill (cleared when the next user message arrives)
+    skill_restriction: Option<SkillToolRestriction>,
+    /// Identical tool calls made this turn (reset when the next user message arrives)
+    repeat_detector: RepeatDetector,
+    /// Id of the turn being run (tools get it in their context)
+    turn_id: String,
+    /// Model tiers of the session's provider (a turn's overrides may name a tier)
+    model_tiers: crate::config::ModelTiers,
+    /// What the running turn's overrides replaced (None = it has none)
+    overridden: Option<OverriddenSettings>,
+    /// Approval of the running turn, when its overrides loosen the session's
+    turn_approval: Option<(TurnApproval, crate::approval::ToolApprovalConfig)>,
+    /// Whether the user approved a Write to each path this turn: appending
+    /// the next chunk of the file follows the same decision
+    chunk_decisions: std::collections::HashMap<std::path::PathBuf, bool>,
+    /// Workspace the session's tools operate in (where its recipes are found)
+    workspace: std::path::PathBuf,
+    /// Shows paths in tool calls and results relative to the workspace
+    path_display: PathDisplay,
+    /// Orders calls on the same file and caps how many tools run at once
+    tool_scheduler: ToolScheduler,
     /// Context limit for this provider/model
     context_limit: usize,
     /// Last input tokens from LLM response
@@ -184,10 +703,73 @@ pub struct AgentLoop {
     hooks_enabled: bool,
     /// Whether to persist the session on exit
     save_session: bool,
+    /// Autosaves while the session runs (None = only saved on exit)
+    autosave: Option<Autosave>,
     /// When the session was created
     created_at: chrono::DateTime<chrono::Utc>,
+    /// Session this one was forked from, and the parent message it ends at
+    parent: Option<(String, usize)>,
+    /// Session template the session was started from
+    template: Option<String>,
+    /// The session's tool calls, by tool (kept across resumes)
+    tool_usage: ToolUsageStats,
+    /// Catalog of the text shown to people (not the model)
+    localizer: Localizer,
+    /// History snapshot refreshed after each turn (for forking)
+    snapshot: Option<Arc<SessionSnapshot>>,
     /// Whether to use streaming mode for LLM responses
     stream_mode: bool,
+    /// How long an LLM request may take before it's abandoned
+    request_timeout: std::time::Duration,
+    /// Watcher for external edits to files the session has touched (opt-in)
+    file_watcher: Option<WorkspaceWatcher>,
+    /// Limiter shared across sessions, held around each LLM request
+    request_limiter: Option<Arc<RequestLimiter>>,
+    /// Locks on the files sessions are writing, shared across sessions
+    workspace_locks: Option<Arc<WorkspaceLocks>>,
+    /// Store each completed LLM call's token usage is appended to
+    usage_store: Option<Arc<UsageStore>>,
+    /// Audit log approvals and mutations are appended to (None = not audited)
+    audit_log: Option<Arc<AuditLog>>,
+    /// Memory file standing instructions are offered to (None = auto-memory off)
+    auto_memory: Option<LocalMemory>,
+    /// Organization policy (marks approvals it requires so they aren't auto-approved)
+    policy: Option<Arc<crate::policy::Policy>>,
+    /// Approve and deny rules settling tool calls before they run
+    approval_config: crate::approval::ToolApprovalConfig,
+    /// Workspace and budget for the project overview, until the first message takes it
+    project_context: Option<(std::path::PathBuf, usize)>,
+    /// Scratch directory for temporary files (None = no scratch dir)
+    scratch_dir: Option<std::path::PathBuf>,
+    /// Keep the scratch directory when a saved session ends
+    keep_scratch: bool,
+    /// Reminder naming the scratch directory, until the first message takes it
+    scratch_reminder: Option<String>,
+    /// Preprocessing of user messages, and the session's scrubbing map
+    prompt_pipeline: crate::prompt::PromptPipeline,
+    /// User turns that can be re-run, oldest first (cleared by compaction)
+    turns: Vec<TurnRecord>,
+    /// Temperature for the current turn only (from `RegenerateLast`)
+    turn_temperature: Option<f32>,
+}
+
+/// Schedule of a running session's autosaves
+struct Autosave {
+    interval: std::time::Duration,
+    last: std::time::Instant,
+    /// Write in progress on a blocking thread
+    writer: Option<tokio::task::JoinHandle<()>>,
+}
+
+/// Where a user turn starts in the history, so it can be regenerated or edited
+struct TurnRecord {
+    /// ID sent with the turn's `SessionOutput::UserMessage`
+    message_id: String,
+    /// Index of the user message in the session history
+    start: usize,
+    /// The message as the user sent it, before preprocessing and hooks
+    content: String,
+    images: Vec<super::ImageAttachment>,
 }
 
 impl AgentLoop {
@@ -196,15 +778,21 @@ impl AgentLoop {
         session_id: SessionId,
         mut input_rx: mpsc::Receiver<SessionInput>,
         output_tx: mpsc::Sender<(SessionId, SessionOutput)>,
-        config: SessionConfig,
+        mut config: SessionConfig,
     ) -> Result<Self> {
         // Create internal channels for dispatching
-        let (message_tx, message_rx) = mpsc::unbounded_channel();
         let (control_tx, control_rx) = mpsc::unbounded_channel();
+        let input_queue = Arc::new(InputQueue::new(
+            session_id.clone(),
+            config.queue_policy,
+            control_tx.clone(),
+            output_tx.clone(),
+        ));
+        let queue_for_dispatcher = input_queue.clone();
 
         // Create approval channel for tools to request approval
         // Subagents use parent's channel; main sessions create their own
-        let (approval_tx, approval_rx) = if let Some(parent_tx) = config.parent_approval_channel {
+        let (approval_tx, approval_rx) = if let Some(parent_tx) = config.parent_approval_channel.clone() {
             // Subagent: use parent's channel for tool requests
             // Create a dummy local receiver (won't receive - parent handles approvals)
             let (_dummy_tx, dummy_rx) = approval_channel();
@@ -215,10 +803,38 @@ impl AgentLoop {
         };
 
         // Create shared plan mode state
-        let plan_mode_state = Arc::new(tokio::sync::RwLock::new(PlanModeState::default()));
+        let plan_mode_state = Arc::new(tokio::sync::RwLock::new(PlanModeState {
+            session_id: Some(session_id.clone()),
+            ..Default::default()
+        }));
         let plan_mode_for_dispatcher = plan_mode_state.clone();
+        let generation_params = Arc::new(tokio::sync::RwLock::new(config.generation.clone().clamped()));
+        let generation_for_dispatcher = generation_params.clone();
+        let pinned = Arc::new(AtomicBool::new(config.resume.as_ref().is_some_and(|saved| saved.pinned)));
+        let pinned_for_dispatcher = pinned.clone();
+        let pins = Arc::new(parking_lot::Mutex::new(MessagePins::from_keys(
+            config.resume.iter().flat_map(|saved| saved.pinned_messages.clone()),
+        )));
+        let pins_for_dispatcher = pins.clone();
+        let selection = Arc::new(parking_lot::Mutex::new(None));
+        let selection_for_dispatcher = selection.clone();
+        let title = config.title.clone().unwrap_or_default();
+        if let Some(saved) = &config.resume {
+            title.set(saved.name.clone(), saved.title_source);
+        }
+        let title_for_dispatcher = title.clone();
+        // The profile is reported with the first turn, so the tokens its
+        // tool definitions take are known from the start
+        let tool_profile = Arc::new(parking_lot::Mutex::new(Some(config.tool_profile.clone())));
+        let tool_profile_for_dispatcher = tool_profile.clone();
+        let output = OutputSender::new(
+            session_id.clone(),
+            output_tx.clone(),
+            config.output_counters.clone().unwrap_or_default(),
+        );
         let output_for_dispatcher = output_tx.clone();
         let sid_for_dispatcher = session_id.clone();
+        let localizer_for_dispatcher = Localizer::new(config.locale);
 
         // Spawn Dispatcher Task
         // This task reads from the main input channel and routes messages to the correct internal channel
@@ -227,17 +843,29 @@ impl AgentLoop {
             info!("Dispatcher started for session: {}", sid);
             while let Some(input) = input_rx.recv().await {
                 match input {
-                    SessionInput::UserMessage { content } => {
-                        if let Err(e) = message_tx.send((content, vec![])) {
-                            error!("Dispatcher: failed to send user message (receiver dropped?): {}", e);
-                            break;
-                        }
+                    SessionInput::UserMessage { content, overrides } => {
+                        queue_for_dispatcher.submit((content, vec![]), overrides).await;
                     }
-                    SessionInput::UserMessageWithImages { content, images } => {
-                        if let Err(e) = message_tx.send((content, images)) {
-                            error!("Dispatcher: failed to send user message with images (receiver dropped?): {}", e);
-                            break;
-                        }
+                    SessionInput::UserMessageWithImages { content, images, overrides } => {
+                        queue_for_dispatcher.submit((content, images), overrides).await;
+                    }
+                    SessionInput::Cancel => {
+                        queue_for_dispatcher.cancel();
+                    }
+                    SessionInput::ClearQueue => {
+                        queue_for_dispatcher.clear();
+                    }
+                    SessionInput::RegenerateLast { temperature } => {
+                        queue_for_dispatcher.submit_rerun(Rerun::Regenerate { temperature }).await;
+                    }
+                    SessionInput::EditUserMessage { message_id, new_content } => {
+                        queue_for_dispatcher.submit_rerun(Rerun::Edit { message_id, new_content }).await;
+                    }
+                    SessionInput::RunRecipe { name, args } => {
+                        queue_for_dispatcher.submit_recipe(name, args).await;
+                    }
+                    SessionInput::Compact { mode, model, focus } => {
+                        queue_for_dispatcher.submit_compact(mode, model, focus).await;
                     }
                     SessionInput::SetPlanMode { active } => {
                         // Update plan mode state
@@ -263,6 +891,97 @@ impl AgentLoop {
                         )).await;
                         debug!("Plan mode set to {} for session {}", active, sid_for_dispatcher);
                     }
+                    SessionInput::ApprovePlan { name } => {
+                        // The plan is injected when the next turn starts
+                        let output = {
+                            let mut state = plan_mode_for_dispatcher.write().await;
+                            match name.or_else(|| state.current_plan.clone()) {
+                                None => SessionOutput::error(localizer_for_dispatcher.text(Text::NoPlanToApprove)),
+                                Some(name) => match PlanStore::default().set_status(&name, PlanStatus::Approved) {
+                                    Ok(_) => {
+                                        state.current_plan = Some(name.clone());
+                                        state.approved_plan = Some(name.clone());
+                                        SessionOutput::plan_status_changed(name, PlanStatus::Approved)
+                                    }
+                                    Err(e) => SessionOutput::error(
+                                        localizer_for_dispatcher.format(Text::PlanApprovalFailed, &[("error", &e)]),
+                                    ),
+                                },
+                            }
+                        };
+                        let _ = output_for_dispatcher.send((sid_for_dispatcher.clone(), output)).await;
+                    }
+                    SessionInput::SetGenerationParams { params } => {
+                        // Takes effect from the next LLM request
+                        let params = params.clamped();
+                        *generation_for_dispatcher.write().await = params.clone();
+                        debug!("Generation params set to {} for session {}", params, sid_for_dispatcher);
+                        let _ = output_for_dispatcher.send((
+                            sid_for_dispatcher.clone(),
+                            SessionOutput::generation_params_changed(params),
+                        )).await;
+                    }
+                    SessionInput::SetPinned { pinned } => {
+                        // Later saves carry the flag; files already written are updated now
+                        pinned_for_dispatcher.store(pinned, Ordering::Relaxed);
+                        let session_id = sid_for_dispatcher.clone();
+                        let updated = tokio::task::spawn_blocking(move || set_session_pinned(&session_id, pinned)).await;
+                        if let Ok(Err(e)) = updated {
+                            debug!("Saved files of session {} not updated: {}", sid_for_dispatcher, e);
+                        }
+                    }
+                    SessionInput::PinMessage { message_id, pinned } => {
+                        // Compaction from now on keeps it; later saves carry the pin
+                        if !pins_for_dispatcher.lock().set_pinned(message_id.as_deref(), pinned) {
+                            let _ = output_for_dispatcher.send((
+                                sid_for_dispatcher.clone(),
+                                SessionOutput::error(localizer_for_dispatcher.text(Text::NoMessageToPin)),
+                            )).await;
+                        }
+                    }
+                    SessionInput::SetSelection { text } => {
+                        *selection_for_dispatcher.lock() = capped_selection(&text);
+                    }
+                    SessionInput::SetTitle { title } => {
+                        // Later saves carry the title; files already written are updated now
+                        let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+                        let output = if title.is_empty() {
+                            SessionOutput::error(localizer_for_dispatcher.text(Text::EmptyTitle))
+                        } else {
+                            title_for_dispatcher.set(title.clone(), TitleSource::User);
+                            let (session_id, saved_title) = (sid_for_dispatcher.clone(), title.clone());
+                            let updated = tokio::task::spawn_blocking(move || {
+                                set_session_title(&session_id, &saved_title, TitleSource::User)
+                            })
+                            .await;
+                            if let Ok(Err(e)) = updated {
+                                debug!("Saved files of session {} not updated: {}", sid_for_dispatcher, e);
+                            }
+                            SessionOutput::title_changed(title)
+                        };
+                        let _ = output_for_dispatcher.send((sid_for_dispatcher.clone(), output)).await;
+                    }
+                    SessionInput::ExplainTool { id } if queue_for_dispatcher.is_idle() => {
+                        // Approvals only wait inside a turn
+                        debug!("Nothing to explain for tool {} in idle session {}", id, sid_for_dispatcher);
+                        let _ = output_for_dispatcher.send((
+                            sid_for_dispatcher.clone(),
+                            SessionOutput::warning(localizer_for_dispatcher.text(Text::ExplainNotPending)),
+                        )).await;
+                    }
+                    SessionInput::SetToolProfile { profile } => {
+                        // Applied, and reported, when the next turn starts
+                        debug!("Tool profile set to {} for session {}", profile, sid_for_dispatcher);
+                        *tool_profile_for_dispatcher.lock() = Some(profile);
+                    }
+                    SessionInput::Unsupported { input_type } => {
+                        // From a newer frontend: say so rather than drop it
+                        warn!("Session {} got unsupported input: {}", sid_for_dispatcher, input_type);
+                        let _ = output_for_dispatcher.send((
+                            sid_for_dispatcher.clone(),
+                            SessionOutput::unsupported_input(input_type),
+                        )).await;
+                    }
                     // All other inputs are control messages (approvals, answers, cancel)
                     input => {
                         if let Err(e) = control_tx.send(input) {
@@ -272,9 +991,32 @@ impl AgentLoop {
                     }
                 }
             }
+            queue_for_dispatcher.close();
             info!("Dispatcher ended for session: {} (input channel closed)", sid);
         });
 
+        // The scratch directory is best-effort: without one the file tools
+        // keep to the workspace. A remote session has no local scratch.
+        let scratch_dir = if config.scratch && config.remote.is_none() {
+            let created = get_scratch_dir(&session_id).and_then(|dir| {
+                std::fs::create_dir_all(&dir)?;
+                Ok(dir)
+            });
+            match created {
+                Ok(dir) => Some(dir),
+                Err(e) => {
+                    warn!("Scratch directory unavailable for session {}: {}", session_id, e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        let scratch_display = scratch_dir.as_deref().map(path_to_display).unwrap_or_default();
+        if let Some(prompt) = config.system_prompt.as_mut() {
+            *prompt = prompt.replace(SCRATCH_DIR_VAR, &scratch_display);
+        }
+
         // Create the provider
         debug!(
             "AgentLoop config: provider={:?}, model={:?}, base_url={:?}, api_key_set={}, system_prompt_len={}",
@@ -284,25 +1026,51 @@ impl AgentLoop {
             config.api_key.is_some(),
             config.system_prompt.as_ref().map(|s| s.len()).unwrap_or(0),
         );
-        let provider = match config.api_key.as_deref() {
-            Some(key) => GenAIProvider::with_config(
-                &config.provider_id,
-                key,
-                config.model.as_deref(),
-                config.base_url.as_deref(),
-            )?,
-            None => GenAIProvider::new(&config.provider_id, config.model.as_deref())?,
-        };
-        let provider = match config.system_prompt.as_deref() {
-            Some(prompt) => provider.with_system_prompt(prompt),
-            None => provider,
+        let provider = LlmClient::from_config(&config)?;
+
+        // Saved sessions are named by the provider's fast model (the session's
+        // own when the provider has no tiers), unless they already have a name
+        let title_provider = match &provider {
+            LlmClient::GenAI(_) if config.save_session && title.source().is_fallback() => {
+                LlmClient::connect(&config, LlmClient::fast_model(&config).as_deref())
+                    .inspect_err(|e| warn!("Session {} won't be given a title: {}", session_id, e))
+                    .ok()
+            }
+            _ => None,
         };
+        // Pending tool calls are explained by the fast model too
+        let explain_client = provider
+            .side_client(&config)
+            .inspect_err(|e| warn!("Tool calls in session {} can't be explained: {}", session_id, e))
+            .ok()
+            .map(Arc::new);
+        let summarizer_client = provider
+            .summarizer(&config)
+            .inspect_err(|e| warn!("Session {} can't be summarized by a model: {}", session_id, e))
+            .ok();
+        let summary_template = config.summarizer.prompt_file.as_ref().and_then(|path| {
+            let path = config.workspace_path.join(path);
+            std::fs::read_to_string(&path)
+                .inspect_err(|e| warn!("Using the built-in summarization prompt, {} can't be read: {}", path.display(), e))
+                .ok()
+        });
 
-        // Create chat session
-        let session = match &config.system_prompt {
+        // Create chat session, continuing a saved one if given
+        let mut session = match &config.system_prompt {
             Some(prompt) => ChatSession::with_system_prompt(prompt),
             None => ChatSession::new(),
         };
+        let (todos, created_at, parent, template, tool_usage) = match config.resume.clone() {
+            Some(saved) => {
+                info!("Resuming session {} ({} messages, {} todos)", saved.id, saved.messages.len(), saved.todos.len());
+                session.restore_messages(saved.messages);
+                let parent = saved.parent_id.zip(saved.fork_point);
+                (saved.todos, saved.created_at, parent, saved.template, saved.tool_usage)
+            }
+            None => (Vec::new(), chrono::Utc::now(), None, None, ToolUsageStats::default()),
+        };
+        let template = config.template.clone().or(template);
+        let todo_list: TodoList = Arc::new(tokio::sync::RwLock::new(todos));
 
         // Create skill registry
         let skill_registry = Arc::new(SkillRegistry::with_builtins(config.workspace_path.clone()));
@@ -311,7 +1079,8 @@ impl AgentLoop {
         let mut tool_builder = ToolRegistryBuilder::new(config.workspace_path.clone())
             .with_provider(&config.provider_id)
             .with_skill_registry(skill_registry)
-            .with_plan_mode_state(plan_mode_state.clone());
+            .with_plan_mode_state(plan_mode_state.clone())
+            .with_todo_list(todo_list.clone());
 
         if let Some(ref key) = config.api_key {
             tool_builder = tool_builder.with_api_key(key.clone());
@@ -328,7 +1097,7 @@ impl AgentLoop {
         }
 
         // Wire progress channel so subagent activity is forwarded to TUI
-        tool_builder = tool_builder.with_progress_channel(output_tx.clone(), session_id.clone());
+        tool_builder = tool_builder.with_progress_channel(output.clone());
 
         // Share session registry so subagents can register for approval routing
         if let Some(reg) = config.session_registry.clone() {
@@ -339,10 +1108,67 @@ impl AgentLoop {
         if let Some(mcp_manager) = config.mcp_manager.clone() {
             tool_builder = tool_builder.with_mcp_manager(mcp_manager);
         }
+        tool_builder = tool_builder.with_mcp_validation(config.validate_mcp_tool_args);
+        tool_builder = tool_builder.with_dry_run(config.dry_run);
+
+        // Custom agent definitions for the Task tool
+        if let Some(components) = config.component_registry.clone() {
+            tool_builder = tool_builder.with_component_registry(components);
+        }
+
+        // Subagents draw from the same request limits as their parent
+        if let Some(limiter) = config.request_limiter.clone() {
+            tool_builder = tool_builder.with_request_limiter(limiter);
+        }
+        if let Some(store) = config.usage_store.clone() {
+            tool_builder = tool_builder.with_usage_store(store);
+        }
+
+        // A dry run executes nothing and asks for no approvals, so there is nothing to audit
+        let audit_log = config.audit_log.clone().or_else(|| {
+            (config.audit && !config.dry_run).then(|| {
+                let masker = config.env_policy.clone().unwrap_or_default().masker();
+                AuditLog::for_session(&session_id)
+                    .map(|log| Arc::new(log.with_masker(masker)))
+                    .inspect_err(|e| warn!("Audit log unavailable for session {}: {}", session_id, e))
+                    .ok()
+            })?
+        });
+        if let Some(log) = audit_log.clone() {
+            tool_builder = tool_builder.with_audit_log(log);
+        }
+        if let Some(policy) = config.env_policy.clone() {
+            tool_builder = tool_builder.with_env_policy(policy);
+        }
+        if let Some(remote) = config.remote.clone() {
+            tool_builder = tool_builder.with_remote(remote);
+        }
+        if !config.additional_roots.is_empty() {
+            tool_builder = tool_builder.with_additional_roots(config.additional_roots.clone());
+        }
+        if let Some(policy) = config.policy.clone() {
+            tool_builder = tool_builder.with_policy(policy);
+        }
+        if let Some(network) = config.network_policy.clone() {
+            tool_builder = tool_builder.with_network_policy(network);
+        }
+        if let Some(verifier) = config.verifier.clone() {
+            tool_builder = tool_builder.with_verifier(verifier);
+        }
+
+        if let Some(dir) = scratch_dir.clone() {
+            tool_builder = tool_builder.with_scratch_dir(dir);
+        }
+        if let Some(clipboard) = config.clipboard.clone() {
+            tool_builder = tool_builder.with_clipboard(clipboard);
+        }
+        tool_builder = tool_builder.with_compact_descriptions(config.compact_tool_descriptions);
 
-        let tool_registry = tool_builder.build();
+        let all_tools = tool_builder.build();
+        let tool_registry = all_tools.filtered(|name| config.tool_profile.allows(name));
 
-        let tool_definitions = tool_registry.list();
+        let schema_dialect = SchemaDialect::for_provider(&config.provider_id, config.strict_tools);
+        let tool_definitions = tool_schema::adapt_definitions(tool_registry.list(), schema_dialect);
 
         // Get context limit for this provider/model
         let ctx_limit = context_limit(&config.provider_id, config.model.as_deref());
@@ -362,18 +1188,63 @@ impl AgentLoop {
             .unwrap_or_default();
         let hooks_enabled = config.enable_hooks.unwrap_or(config.prompt_config.enable_hooks);
 
+        // Watching is best-effort: a failure here only disables the reminders.
+        // Remote workspaces can't be watched from here.
+        let file_watcher = if config.watch_files && config.remote.is_none() {
+            match WorkspaceWatcher::new(config.workspace_path.clone()) {
+                Ok(watcher) => Some(watcher),
+                Err(e) => {
+                    warn!("File watcher unavailable, external edits won't be reported: {}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
         Ok(Self {
             session_id,
-            message_rx,
+            input_queue,
             control_rx,
-            output_tx,
+            output,
             approval_tx,
             approval_rx,
             provider,
             session,
+            all_tools,
             tool_registry,
             tool_definitions,
+            tool_profile,
+            schema_dialect,
             plan_mode_state,
+            todo_list,
+            generation_params,
+            pinned,
+            pins,
+            selection,
+            title,
+            title_provider,
+            explain_client,
+            self_review: config.self_review,
+            self_review_threshold: config.self_review_threshold,
+            turn_changes: Arc::default(),
+            turn_request: None,
+            summarizer: config.summarizer.clone(),
+            summarizer_client,
+            summary_template,
+            skill_restriction: None,
+            repeat_detector: RepeatDetector::new(&config.limits),
+            turn_id: String::new(),
+            model_tiers: config
+                .model_tiers
+                .clone()
+                .unwrap_or_else(|| crate::config::ModelTiers::for_provider(&config.provider_id)),
+            overridden: None,
+            turn_approval: None,
+            chunk_decisions: std::collections::HashMap::new(),
+            workspace: config.workspace_path.clone(),
+            path_display: PathDisplay::new(config.workspace_path.clone()),
+            tool_scheduler: ToolScheduler::new(config.workspace_path.clone(), config.limits.max_concurrent_tools),
             context_limit: ctx_limit,
             last_input_tokens: 0,
             last_output_tokens: 0,
@@ -381,14 +1252,53 @@ impl AgentLoop {
             hooks_config,
             hooks_enabled,
             save_session: config.save_session,
-            created_at: chrono::Utc::now(),
+            autosave: config
+                .autosave_interval
+                .filter(|_| config.save_session)
+                .map(|interval| Autosave { interval, last: std::time::Instant::now(), writer: None }),
+            created_at,
+            parent,
+            template,
+            tool_usage,
+            localizer: Localizer::new(config.locale),
+            snapshot: config.snapshot,
             stream_mode: config.stream_mode,
+            request_timeout: config.request_timeout.unwrap_or(if config.stream_mode {
+                DEFAULT_STREAM_TIMEOUT
+            } else {
+                DEFAULT_REQUEST_TIMEOUT
+            }),
+            file_watcher,
+            request_limiter: config.request_limiter,
+            workspace_locks: config.workspace_locks,
+            usage_store: config.usage_store,
+            audit_log,
+            auto_memory: (config.auto_memory && config.remote.is_none())
+                .then(|| LocalMemory::for_workspace(&config.workspace_path)),
+            policy: config.policy,
+            approval_config: config.approval_config,
+            // A resumed session already has its overview in the history
+            project_context: (config.project_context && config.resume.is_none() && config.remote.is_none())
+                .then(|| (config.workspace_path.clone(), config.project_context_budget)),
+            scratch_reminder: (scratch_dir.is_some() && config.resume.is_none())
+                .then(|| reminders::SCRATCH_DIR.replace(SCRATCH_DIR_VAR, &scratch_display)),
+            scratch_dir,
+            keep_scratch: config.keep_scratch,
+            prompt_pipeline: config.prompt_pipeline,
+            turns: Vec::new(),
+            turn_temperature: None,
         })
     }
 
+    /// Get the session's input queue (shared with the dispatcher)
+    pub(crate) fn input_queue(&self) -> Arc<InputQueue> {
+        self.input_queue.clone()
+    }
+
     /// Run the agent loop until Stop is received or channel closes
     pub async fn run(mut self) {
         info!("Agent loop starting for session: {}", self.session_id);
+        let started = std::time::Instant::now();
 
         // Execute SessionStart hooks
         if self.hooks_enabled {
@@ -400,43 +1310,190 @@ impl AgentLoop {
             }
         }
 
+        // A resumed session's checklist is shown before the first turn
+        let todos = self.todo_list.read().await.clone();
+        if !todos.is_empty() {
+            self.emit(SessionOutput::todos_updated(todos)).await;
+        }
+
         // Main Loop: Only cares about Questions (UserMessages)
         // The Agentic Loop (inside handle_user_message) handles Answers (Approvals)
-        while let Some((content, images)) = self.message_rx.recv().await {
-            if let Err(e) = self.handle_user_message(content, images).await {
-                self.emit(SessionOutput::error(e.to_string())).await;
-            }
+        while let Some(input) = self.input_queue.next().await {
+            self.apply_tool_profile().await;
+            *self.turn_changes.lock() = TurnChangeSet::new();
+            self.turn_request = None;
+            let result = match input {
+                QueuedInput::Message((content, images), overrides) => {
+                    if let Some(overrides) = overrides {
+                        self.override_turn(overrides).await;
+                    }
+                    self.handle_user_message(content, images).await
+                }
+                QueuedInput::Rerun(rerun) => self.rerun_turn(rerun).await,
+                QueuedInput::Recipe { name, args } => self.run_recipe(&name, &args).await,
+                QueuedInput::Compact { mode, model, focus } => self.compact_on_request(mode, model, focus).await,
+            };
+            let review_warning = match result {
+                Ok(()) => self.review_turn().await,
+                Err(e) => {
+                    self.emit(SessionOutput::error(e.to_string())).await;
+                    false
+                }
+            };
+            // However the turn ended, the next one runs with the session's settings
+            self.turn_temperature = None;
+            self.restore_overrides();
+            self.input_queue.finish_turn();
+            self.update_snapshot().await;
+            self.autosave(true).await;
+            self.generate_title();
             // Emit Idle when the turn is complete
-            self.emit(SessionOutput::idle()).await;
+            self.emit(if review_warning { SessionOutput::idle_with_review_warning() } else { SessionOutput::idle() })
+                .await;
         }
 
         // Channel closed - this happens when the session is stopped or the dispatcher exits
         info!("Message channel closed for session: {}", self.session_id);
+        telemetry::histogram(
+            telemetry::SESSION_SECONDS,
+            started.elapsed().as_secs_f64(),
+            &[("provider", self.provider.provider_id())],
+        );
 
-        // Save session before exiting (if enabled)
+        // Release the watcher's OS handles now rather than whenever the loop is dropped
+        self.file_watcher = None;
+
+        // Save session before exiting (if enabled); the autosave is no longer needed
         if self.save_session {
             info!("Saving session {} before exit", self.session_id);
-            if let Err(e) = self.save_session().await {
-                error!("Failed to save session {}: {}", self.session_id, e);
+            if let Some(writer) = self.autosave.as_mut().and_then(|a| a.writer.take()) {
+                let _ = writer.await;
+            }
+            match self.save_session().await {
+                Ok(()) => {
+                    if let Err(e) = remove_autosave(&self.session_id) {
+                        warn!("Failed to remove autosave of session {}: {}", self.session_id, e);
+                    }
+                }
+                Err(e) => error!("Failed to save session {}: {}", self.session_id, e),
             }
         }
 
+        // Scratch files go with the session, unless a saved session keeps them
+        // (a suspended one always does, since it will be resumed)
+        let keep_scratch = self.keep_scratch || self.input_queue.is_suspended();
+        if self.scratch_dir.is_some()
+            && !(self.save_session && keep_scratch)
+            && let Err(e) = remove_scratch_dir(&self.session_id)
+        {
+            warn!("Failed to remove scratch directory of session {}: {}", self.session_id, e);
+        }
+
         info!("Agent loop ended for session: {}", self.session_id);
     }
 
+    /// Switch to the tool profile set since the last turn, if any, and report it
+    async fn apply_tool_profile(&mut self) {
+        let Some(profile) = self.tool_profile.lock().take() else {
+            return;
+        };
+        self.tool_registry = self.all_tools.filtered(|name| profile.allows(name));
+        self.tool_definitions = tool_schema::adapt_definitions(self.tool_registry.list(), self.schema_dialect);
+        let mut tools: Vec<String> = self.tool_definitions.iter().map(|t| t.name.clone()).collect();
+        tools.sort();
+        let tokens = definition_tokens(&self.tool_definitions);
+        info!(
+            "Session {} offers {} tools (profile {}, ~{} tokens)",
+            self.session_id,
+            tools.len(),
+            profile,
+            tokens
+        );
+        self.emit(SessionOutput::tool_profile_changed(profile.name, tools, tokens)).await;
+    }
+
+    /// Put a turn's overrides in place of the session's settings, and say so
+    ///
+    /// What they replace is kept for `restore_overrides`, which the main loop
+    /// calls when the turn ends.
+    async fn override_turn(&mut self, overrides: TurnOverrides) {
+        let mut saved = OverriddenSettings::default();
+        let model = overrides
+            .model
+            .as_deref()
+            .map(|model| resolve_model(model, &self.model_tiers))
+            .filter(|model| model != self.provider.model());
+        if let Some(model) = &model {
+            let provider = self.provider.for_model(model);
+            saved.provider = Some(std::mem::replace(&mut self.provider, provider));
+        }
+        if let Some(profile) = &overrides.tool_profile {
+            let registry = self.all_tools.filtered(|name| profile.allows(name));
+            let definitions = tool_schema::adapt_definitions(registry.list(), self.schema_dialect);
+            saved.tools = Some((
+                std::mem::replace(&mut self.tool_registry, registry),
+                std::mem::replace(&mut self.tool_definitions, definitions),
+            ));
+        }
+        self.turn_approval = overrides
+            .approval_level
+            .map(|approval| (approval, approval.apply(&self.approval_config)));
+        self.overridden = Some(saved);
+        info!("Session {} runs its next turn with {}", self.session_id, overrides);
+        self.emit(SessionOutput::turn_overridden(
+            model,
+            overrides.approval_level,
+            overrides.tool_profile.map(|profile| profile.name),
+        ))
+        .await;
+    }
+
+    /// Put back the session settings a turn's overrides replaced, if any
+    fn restore_overrides(&mut self) {
+        let Some(saved) = self.overridden.take() else {
+            return;
+        };
+        if let Some(provider) = saved.provider {
+            self.provider = provider;
+        }
+        if let Some((registry, definitions)) = saved.tools {
+            self.tool_registry = registry;
+            self.tool_definitions = definitions;
+        }
+        self.turn_approval = None;
+    }
+
     /// Handle a user message - run the agentic loop
     async fn handle_user_message(
         &mut self,
         content: String,
         images: Vec<super::ImageAttachment>,
     ) -> Result<()> {
+        // Images are checked before anything else so a rejected one doesn't start a turn
+        let image_tokens = self.check_images(&images)?;
+
+        // The app's selection goes with this message only; like preprocessing
+        // (scrubbing, reminders) it only changes what the model sees
+        let selection = self.selection.lock().take();
+        let for_model = match &selection {
+            Some(selection) => with_selection(&content, selection),
+            None => content.clone(),
+        };
+        let mut content_with_hooks = self.prompt_pipeline.process(&for_model).message();
+
+        // `@path` mentions are listed for the model to read; ones naming no
+        // workspace path are reported once the message is echoed
+        let (mentioned, unresolved) = resolve_mentions(&extract_mentions(&content), &self.workspace);
+        if !mentioned.is_empty() {
+            content_with_hooks = format!("{}\n\n{}", content_with_hooks, mention_reminder(&mentioned));
+        }
+
         // Execute UserPromptSubmit hooks
-        let mut content_with_hooks = content.clone();
         if self.hooks_enabled {
             match self.run_user_prompt_hook(&content) {
                 Ok(Some(additional_context)) => {
                     // Append hook context to the message
-                    content_with_hooks = format!("{}\n\n<user-prompt-submit-hook>\n{}\n</user-prompt-submit-hook>", content, additional_context);
+                    content_with_hooks = format!("{}\n\n<user-prompt-submit-hook>\n{}\n</user-prompt-submit-hook>", content_with_hooks, additional_context);
                 }
                 Err(block_reason) => {
                     // Hook blocked the message
@@ -446,6 +1503,17 @@ impl AgentLoop {
             }
         }
 
+        // A skill's tool restriction only lasts for the turn that injected it
+        self.skill_restriction = None;
+        self.repeat_detector.reset();
+        self.turn_request = Some(content.clone());
+
+        // Control inputs left over from the previous turn (e.g. a Cancel that
+        // arrived after it finished) must not affect this one
+        while let Ok(stale) = self.control_rx.try_recv() {
+            debug!("Dropping stale control input: {:?}", stale);
+        }
+
         // Generate message ID
         let msg_id = uuid::Uuid::new_v4().to_string();
 
@@ -457,6 +1525,40 @@ impl AgentLoop {
         };
         self.emit(SessionOutput::user_message(&msg_id, &display_content))
             .await;
+        if !unresolved.is_empty() {
+            let paths = unresolved.iter().map(|path| format!("@{}", path)).collect::<Vec<_>>().join(", ");
+            self.emit(SessionOutput::warning(self.localizer.format(Text::UnresolvedMentions, &[("paths", &paths)])))
+                .await;
+        }
+
+        // Until a title is generated, the session is named after its first message
+        if self.title.get().is_none()
+            && let Some(title) = fallback_title(&content)
+        {
+            self.title.set(title.clone(), TitleSource::Fallback);
+            self.emit(SessionOutput::title_changed(title)).await;
+        }
+
+        // The context check before the next request only knows the last
+        // reported usage, so count the images' estimated cost now
+        self.last_input_tokens += image_tokens;
+
+        // The first message is preceded by an overview of the project
+        if let Some((workspace, budget)) = self.project_context.take()
+            && let Some(overview) = project_overview_within(workspace, budget, GATHER_TIMEOUT).await
+        {
+            self.session.add_user_message(format!("<system-reminder>\n{}\n</system-reminder>", overview));
+        }
+        if let Some(reminder) = self.scratch_reminder.take() {
+            self.session.add_user_message(format!("<system-reminder>\n{}\n</system-reminder>", reminder));
+        }
+
+        self.turns.push(TurnRecord {
+            message_id: msg_id.clone(),
+            start: self.session.message_count(),
+            content: content.clone(),
+            images: images.clone(),
+        });
 
         // Add to session (with hook context if any, and images)
         if images.is_empty() {
@@ -465,33 +1567,216 @@ impl AgentLoop {
             self.session
                 .add_user_message_with_images(&content_with_hooks, images);
         }
+        if let Some(message) = self.session.messages.last() {
+            self.pins.lock().record(&msg_id, message);
+        }
+        self.inject_approved_plan().await;
 
         // Run the agentic loop
-        self.run_agentic_loop().await
+        self.run_agentic_loop().await?;
+
+        // Once the turn has acted on a standing instruction, offer to remember it
+        if self.auto_memory.is_some()
+            && let Some(note) = learnable_note(&content)
+        {
+            self.offer_memory(note).await;
+        }
+        Ok(())
+    }
+
+    /// Re-run an earlier turn
+    ///
+    /// The turn's user message and everything after it leave the history
+    /// (frontends learn which from `HistoryTruncated`), then its message, or
+    /// the edited text, runs as a new turn. Turns from before a compaction
+    /// or resume can't be re-run: their place in the history is unknown.
+    async fn rerun_turn(&mut self, rerun: Rerun) -> Result<()> {
+        let (index, content) = match rerun {
+            Rerun::Regenerate { temperature } => {
+                let index = self.turns.len().checked_sub(1).ok_or_else(|| {
+                    crate::error::Error::Agent("Nothing to regenerate: no message has been answered since the session started or was compacted".to_string())
+                })?;
+                self.turn_temperature = GenerationParams { temperature, ..Default::default() }.clamped().temperature;
+                (index, self.turns[index].content.clone())
+            }
+            Rerun::Edit { message_id, new_content } => {
+                let index = self.turns.iter().position(|t| t.message_id == message_id).ok_or_else(|| {
+                    crate::error::Error::Agent(format!(
+                        "Message {} can't be edited: it is not in this session, or it came before a resume or compaction",
+                        message_id
+                    ))
+                })?;
+                (index, new_content)
+            }
+        };
+
+        let turn = self.turns.drain(index..).next().expect("index is in range");
+        let repair = self.session.truncate(turn.start);
+        self.pins.lock().retain(&self.session.messages);
+        if !repair.is_empty() {
+            warn!("Repaired history after truncating session {}: {:?}", self.session_id, repair);
+        }
+        debug!("Re-running turn {} of session {}", turn.message_id, self.session_id);
+        self.emit(SessionOutput::history_truncated(&turn.message_id)).await;
+
+        self.handle_user_message(content, turn.images).await
+    }
+
+    /// Check images attached to a user message, returning their estimated token cost
+    ///
+    /// Fails if the provider can't read images or an image isn't valid.
+    fn check_images(&self, images: &[super::ImageAttachment]) -> Result<u64> {
+        use base64::{engine::general_purpose::STANDARD, Engine};
+
+        if images.is_empty() {
+            return Ok(0);
+        }
+        if !self.provider.supports_images() {
+            return Err(crate::error::Error::Agent(format!(
+                "Provider '{}' doesn't accept images. Providers that do: {}",
+                self.provider.provider_id(),
+                catalog::vision_providers().join(", ")
+            )));
+        }
+
+        let mut tokens = 0;
+        for (i, image) in images.iter().enumerate() {
+            let bytes = STANDARD
+                .decode(&image.data)
+                .map_err(|e| crate::error::Error::Agent(format!("Image {} is not valid base64: {}", i + 1, e)))?;
+            validate_image(&bytes, &image.media_type)
+                .map_err(|e| crate::error::Error::Agent(format!("Image {}: {}", i + 1, e)))?;
+            tokens += estimate_image_tokens(self.provider.provider_id(), image_dimensions(&bytes));
+        }
+        Ok(tokens)
+    }
+
+    /// Ask whether to save a note to the memory file, and save it if accepted
+    ///
+    /// Waits for the answer like a question from AskUserQuestion; a Cancel
+    /// dismisses it.
+    async fn offer_memory(&mut self, note: String) {
+        let Some(memory) = self.auto_memory.clone() else {
+            return;
+        };
+        let request_id = format!("memory-{}", uuid::Uuid::new_v4());
+        self.emit(SessionOutput::Question {
+            request_id: request_id.clone(),
+            questions: vec![QuestionInfo {
+                question: format!("Save this to memory for future sessions?\n\"{}\"", note),
+                header: Some("Memory".to_string()),
+                options: vec![
+                    QuestionOption {
+                        label: MEMORY_SAVE.to_string(),
+                        description: Some(format!("Add it to {}", LOCAL_MEMORY_FILE)),
+                    },
+                    QuestionOption {
+                        label: MEMORY_SKIP.to_string(),
+                        description: None,
+                    },
+                ],
+                multi_select: false,
+            }],
+            subagent_id: None,
+        })
+        .await;
+
+        let answers = loop {
+            match self.control_rx.recv().await {
+                Some(SessionInput::AnswerQuestion { request_id: id, answers }) if id == request_id => break answers,
+                Some(SessionInput::Cancel) | None => return,
+                Some(other) => debug!("Ignoring {:?} while waiting for the memory question", other),
+            }
+        };
+        if !answers.values().any(|a| a == MEMORY_SAVE) {
+            return;
+        }
+        match memory.add_learned(&note, chrono::Local::now()) {
+            Ok(()) => info!("Saved memory to {}: {}", memory.path().display(), note),
+            Err(e) => {
+                self.emit(SessionOutput::error(self.localizer.format(Text::MemorySaveFailed, &[("error", &e)]))).await
+            }
+        }
+    }
+
+    /// Run a recipe from the workspace as a turn of its own
+    ///
+    /// Its steps go through the same paths as the model's tool calls and
+    /// turns, so approvals, hooks and the audit log apply to them as usual.
+    async fn run_recipe(&mut self, name: &str, args: &str) -> Result<()> {
+        let recipe = load_recipe(&self.workspace, name)?;
+        let tools: Vec<String> = self.tool_registry.list().into_iter().map(|t| t.name).collect();
+        let skills: Vec<String> = SkillRegistry::with_builtins(self.workspace.clone())
+            .list()
+            .into_iter()
+            .map(|s| s.name)
+            .collect();
+        let runner = RecipeRunner::new(recipe, args, &tools, &skills)?;
+
+        self.skill_restriction = None;
+        self.repeat_detector.reset();
+        while let Ok(stale) = self.control_rx.try_recv() {
+            debug!("Dropping stale control input: {:?}", stale);
+        }
+
+        info!("Running recipe {} in session {}", name, self.session_id);
+        let report = runner.run(self).await;
+        self.skill_restriction = None;
+
+        // Later turns should know what the recipe did
+        self.session.add_user_message(format!("<system-reminder>\n{}\n</system-reminder>", report));
+        Ok(())
+    }
+
+    /// Let the model act on the last user message, for a recipe step
+    ///
+    /// The step's output is the model's final answer; None if the turn was
+    /// cut short without one (cancelled, or the session stopped).
+    async fn run_prompt_turn(&mut self) -> Option<StepOutcome> {
+        if let Err(e) = self.run_agentic_loop().await {
+            return Some(StepOutcome::failed(e.to_string()));
+        }
+        self.session.final_assistant_text().map(StepOutcome::succeeded)
     }
 
     /// Run the agentic loop until no more tool calls
     async fn run_agentic_loop(&mut self) -> Result<()> {
+        self.turn_id = uuid::Uuid::new_v4().to_string();
+        self.chunk_decisions.clear();
         let mut iteration = 0;
+        let mut length_continuations = 0;
+        let mut malformed_retries = 0;
 
         loop {
             iteration += 1;
             if iteration > MAX_ITERATIONS {
                 return Err(crate::error::Error::Agent(
-                    "Max iteration limit reached".to_string(),
+                    self.localizer.text(Text::MaxIterations).to_string(),
                 ));
             }
 
+            // Keep a long turn's progress if the app dies mid-turn
+            self.autosave(false).await;
+
             // Check and compact context if needed before calling LLM
             if let Err(e) = self.check_and_compact_context().await {
                 warn!("Context compaction failed: {}, continuing anyway", e);
             }
 
+            // Tell the model about files edited outside the session since it last saw them
+            self.report_external_changes().await;
+
             // Call LLM
-            self.emit(SessionOutput::thinking("Thinking...".to_string()))
-                .await;
+            self.emit_ephemeral(SessionOutput::thinking(self.localizer.text(Text::Thinking).to_string()));
+
+            // An unanswered tool call makes the provider reject the whole conversation
+            self.session.repair_history();
 
-            let response = self.call_llm().await?;
+            let permit = self.acquire_request_permit().await;
+            let response = self.call_llm_recovering().await;
+            drop(permit);
+            let response = response?;
+            self.record_usage(&response);
 
             // Store token counts from LLM response
             if let Some(input) = response.input_tokens {
@@ -504,8 +1789,18 @@ impl AgentLoop {
             // Generate message ID
             let msg_id = uuid::Uuid::new_v4().to_string();
 
-            // Emit assistant message with token usage appended to content
             let content = response.content.clone().unwrap_or_default();
+
+            // A refused or filtered response ends the turn; nothing it asked for runs
+            if let Some(FinishReason::Refusal(reason)) = &response.finish_reason {
+                warn!("Provider refused the response: {}", reason);
+                self.session.add_assistant_message(&content, Vec::new());
+                let message = (!content.trim().is_empty()).then(|| self.prompt_pipeline.unscrub(&content));
+                self.emit(SessionOutput::refusal(reason, message)).await;
+                return Ok(());
+            }
+
+            // Emit assistant message with token usage appended to content
             if !content.is_empty() {
                 debug!(
                     input_tokens = ?response.input_tokens,
@@ -514,9 +1809,10 @@ impl AgentLoop {
                     "Emitting assistant message with tokens"
                 );
 
+                // Scrubbed values are restored for display only
                 self.emit(SessionOutput::assistant_message_with_tokens(
                     &msg_id,
-                    &content,
+                    self.prompt_pipeline.unscrub(&content),
                     response.input_tokens,
                     response.output_tokens,
                     Some(self.context_limit),
@@ -527,152 +1823,666 @@ impl AgentLoop {
             // Add assistant message with tool calls
             let tool_calls = response.tool_calls.clone();
             self.session.add_assistant_message(&content, tool_calls.clone());
+            if !content.is_empty()
+                && let Some(message) = self.session.messages.last()
+            {
+                self.pins.lock().record(&msg_id, message);
+            }
 
-            // If no tool calls, we're done
+            // If no tool calls, we're done - unless the response stopped short
             if tool_calls.is_empty() {
-                return Ok(());
+                let retry = match response.finish_reason {
+                    Some(FinishReason::Length) if length_continuations < MAX_LENGTH_CONTINUATIONS => {
+                        length_continuations += 1;
+                        Some(LENGTH_CONTINUATION)
+                    }
+                    Some(FinishReason::MalformedToolCall) if malformed_retries < MAX_MALFORMED_CALL_RETRIES => {
+                        malformed_retries += 1;
+                        Some(MALFORMED_TOOL_CALL)
+                    }
+                    Some(FinishReason::Length) => {
+                        self.emit(SessionOutput::warning(self.localizer.text(Text::ResponseCutOff))).await;
+                        None
+                    }
+                    Some(FinishReason::MalformedToolCall) => {
+                        self.emit(SessionOutput::warning(self.localizer.text(Text::MalformedToolCalls))).await;
+                        None
+                    }
+                    _ => None,
+                };
+                let Some(note) = retry else {
+                    return Ok(());
+                };
+                info!("Response stopped short ({:?}), asking the model to carry on", response.finish_reason);
+                self.session.add_user_message(format!("<system-reminder>\n{}\n</system-reminder>", note));
+                continue;
             }
 
-            // Spawn ALL tools in parallel
-            let mut join_set: JoinSet<SpawnedToolResult> = JoinSet::new();
+            // Look for the model repeating itself before anything runs
+            let mut verdicts = Vec::with_capacity(tool_calls.len());
             for tool_call in &tool_calls {
-                // Log warning if tool call has empty or null arguments
-                if tool_call.fn_arguments.is_null() ||
-                   (tool_call.fn_arguments.is_object() && tool_call.fn_arguments.as_object().map(|o| o.is_empty()).unwrap_or(false)) {
-                    warn!(
-                        tool_name = %tool_call.fn_name,
-                        tool_id = %tool_call.call_id,
-                        arguments = ?tool_call.fn_arguments,
-                        "Tool call received with empty or null arguments"
-                    );
+                let idempotent = self.tool_registry.get(&tool_call.fn_name).is_some_and(|t| t.is_idempotent());
+                match self.repeat_detector.observe(&tool_call.fn_name, &tool_call.fn_arguments, idempotent) {
+                    RepeatVerdict::Abort(message) => {
+                        warn!("{}", message);
+                        return Err(crate::error::Error::Agent(message));
+                    }
+                    verdict => verdicts.push(verdict),
                 }
+            }
 
-                // Emit tool_start (ephemeral) and tool_call (persistent) before spawning
-                self.emit_tool_execution_start(tool_call).await;
-
-                if let Some(tool) = self.tool_registry.get(&tool_call.fn_name) {
-                    let id = tool_call.call_id.clone();
-                    let name = tool_call.fn_name.clone();
-                    let arguments = tool_call.fn_arguments.clone();
-                    let ctx = ToolExecutionContext::new(
-                        self.approval_tx.clone(),
-                        id.clone(),
-                        name.clone(),
-                    );
-                    join_set.spawn(execute_tool_task(tool, id, name, arguments, ctx));
-                } else {
-                    // Tool not found - handle immediately
-                    let error_msg = format!("Unknown tool: {}", tool_call.fn_name);
-                    self.session.add_tool_result(&tool_call.call_id, &error_msg, true);
-                    self.emit(SessionOutput::tool_done(&tool_call.call_id, &tool_call.fn_name, false, error_msg)).await;
-                }
+            if !self.execute_tool_calls(&tool_calls, verdicts).await? {
+                return Ok(());
             }
+        }
+    }
 
-            // Track pending approval/question requests by ID
-            let mut pending_approvals: std::collections::HashMap<String, tokio::sync::oneshot::Sender<ApprovalResponse>> = std::collections::HashMap::new();
-            let mut pending_questions: std::collections::HashMap<String, tokio::sync::oneshot::Sender<QuestionResponse>> = std::collections::HashMap::new();
+    /// Run a batch of tool calls from one assistant message, answering the
+    /// approvals and questions they raise, and record their results
+    ///
+    /// Returns false if the turn ended first (cancelled, or the session
+    /// stopped).
+    async fn execute_tool_calls(&mut self, tool_calls: &[ToolCall], verdicts: Vec<RepeatVerdict>) -> Result<bool> {
+        // Track completed tool IDs for cancel cleanup
+        let mut completed_tool_ids: std::collections::HashSet<String> = std::collections::HashSet::new();
+
+        // Spawn ALL tools in parallel (the scheduler orders calls on the same
+        // file), keeping each one's handle for CancelTool
+        self.tool_scheduler.reset();
+        let mut join_set: JoinSet<SpawnedToolResult> = JoinSet::new();
+        let mut running: std::collections::HashMap<String, (AbortHandle, CancellationToken)> = std::collections::HashMap::new();
+        // Cancelled by SessionInput::Cancel; each call gets a child token CancelTool cancels on its own
+        let batch_cancel = CancellationToken::new();
+        // Calls cancelled while waiting for approval (reported as cancelled, not failed)
+        let mut cancelled_ids: std::collections::HashSet<String> = std::collections::HashSet::new();
+        // Heartbeats from tools that report progress, and when each call last reported
+        let (progress_tx, mut progress_rx) = progress_channel();
+        let mut last_progress: std::collections::HashMap<String, std::time::Instant> = std::collections::HashMap::new();
+        for (tool_call, verdict) in tool_calls.iter().zip(verdicts) {
+            // Log warning if tool call has empty or null arguments
+            if tool_call.fn_arguments.is_null() ||
+               (tool_call.fn_arguments.is_object() && tool_call.fn_arguments.as_object().map(|o| o.is_empty()).unwrap_or(false)) {
+                warn!(
+                    tool_name = %tool_call.fn_name,
+                    tool_id = %tool_call.call_id,
+                    arguments = ?tool_call.fn_arguments,
+                    "Tool call received with empty or null arguments"
+                );
+            }
 
-            // Track completed tool IDs for cancel cleanup
-            let mut completed_tool_ids: std::collections::HashSet<String> = std::collections::HashSet::new();
+            // Emit tool_start (ephemeral) and tool_call (persistent) before spawning
+            self.emit_tool_execution_start(tool_call).await;
+
+            if let Some(error_msg) = self.skill_restriction.as_ref().and_then(|r| r.rejection(tool_call)) {
+                // Outside the active skill's allowed tools - refuse without executing
+                self.tool_usage.record(&tool_call.fn_name, ToolDoneStatus::Rejected, std::time::Duration::ZERO, error_msg.len());
+                self.session.add_tool_result(&tool_call.call_id, &error_msg, true);
+                self.emit(SessionOutput::tool_done(&tool_call.call_id, &tool_call.fn_name, false, error_msg)).await;
+            } else if let RepeatVerdict::Reuse { output, success, first } = verdict {
+                // Same call, same answer - don't run it again
+                if first {
+                    self.emit(SessionOutput::warning(
+                        self.localizer.format(Text::RepeatedToolCall, &[("tool", &tool_call.fn_name)]),
+                    ))
+                    .await;
+                }
+                completed_tool_ids.insert(tool_call.call_id.clone());
+                self.finalize_spawned_tool(SpawnedToolResult {
+                    id: tool_call.call_id.clone(),
+                    name: tool_call.fn_name.clone(),
+                    arguments: tool_call.fn_arguments.clone(),
+                    success,
+                    output,
+                    artifacts: Vec::new(),
+                    inject_info: None,
+                    allowed_tools: None,
+                    subagent_info: None,
+                    cancelled: false,
+                    rejected: false,
+                    elapsed: std::time::Duration::ZERO,
+                })
+                .await;
+            } else if let Some(tool) = self.tool_registry.get(&tool_call.fn_name) {
+                let id = tool_call.call_id.clone();
+                let name = tool_call.fn_name.clone();
+                let mut arguments = tool_call.fn_arguments.clone();
+                if self.schema_dialect == SchemaDialect::OpenAiStrict {
+                    tool_schema::strip_optional_nulls(&tool.parameters_schema(), &mut arguments);
+                }
+                if let Err(invalid) = self.tool_registry.check_arguments(&name, &mut arguments) {
+                    // Don't run it - tell the model what to fix instead
+                    debug!("{}", invalid);
+                    let error_msg = invalid.to_tool_result();
+                    self.tool_usage.record(&name, ToolDoneStatus::Failed, std::time::Duration::ZERO, error_msg.len());
+                    completed_tool_ids.insert(id.clone());
+                    self.session.add_tool_result(&id, &error_msg, true);
+                    self.emit(SessionOutput::tool_done(&id, &name, false, error_msg)).await;
+                    continue;
+                }
+                let cancel = batch_cancel.child_token();
+                let ctx = ToolExecutionContext::new(
+                    self.approval_tx.clone(),
+                    id.clone(),
+                    name.clone(),
+                )
+                .with_session(self.session_id.clone(), self.turn_id.clone())
+                .with_workspace(self.workspace.clone())
+                .with_cancellation(cancel.clone())
+                .with_file_writes(self.turn_writes());
+                // Possible secret exfiltration: a person approves it, whatever
+                // the rules and the turn's approval say (deny rules still refuse)
+                let findings = self.approval_config.scan(&name, &arguments);
+                let decision = match self.approval_config.decide(&name, &arguments) {
+                    RuleDecision::Approve(_) | RuleDecision::Undecided if !findings.is_empty() => {
+                        warn!("Asking for approval of {}: {}", name, exfiltration::warning(&findings));
+                        RuleDecision::Undecided
+                    }
+                    decision => decision,
+                };
+                let ctx = ctx.flagged(findings.clone());
+                // The next chunk of a file the user decided on earlier in the turn
+                let earlier = match decision {
+                    RuleDecision::Undecided | RuleDecision::Delegate { .. }
+                        if findings.is_empty() && name == "Write" && arguments["mode"] == "append" =>
+                    {
+                        self.chunk_path(&arguments).and_then(|path| self.chunk_decisions.get(&path).copied())
+                    }
+                    _ => None,
+                };
+                let ctx = match decision {
+                    _ if earlier == Some(true) => {
+                        if let Some(audit) = &self.audit_log {
+                            audit.approved(&id, &name, Decider::User, Some("an earlier chunk of the file".to_string()));
+                        }
+                        ctx.approved()
+                    }
+                    _ if earlier == Some(false) => {
+                        let reason = "The user rejected an earlier chunk of this file".to_string();
+                        if let Some(audit) = &self.audit_log {
+                            audit.rejected(&id, &name, Decider::User, Some(reason.clone()));
+                        }
+                        let error_msg = format!("Error: Rejected by user: {}", reason);
+                        self.tool_usage.record(&name, ToolDoneStatus::Rejected, std::time::Duration::ZERO, error_msg.len());
+                        completed_tool_ids.insert(id.clone());
+                        self.session.add_tool_result(&id, &error_msg, true);
+                        self.emit(SessionOutput::tool_rejected(&id, &name, error_msg)).await;
+                        continue;
+                    }
+                    RuleDecision::Approve(rule) => {
+                        if let Some(audit) = &self.audit_log {
+                            audit.approved(&id, &name, Decider::Rule, Some(rule));
+                        }
+                        ctx.approved()
+                    }
+                    RuleDecision::Deny(reason) => {
+                        info!("Refusing {} without asking: {}", name, reason);
+                        if let Some(audit) = &self.audit_log {
+                            audit.rejected(&id, &name, Decider::Rule, Some(reason.clone()));
+                        }
+                        let error_msg = format!("Error: Rejected without asking: {}", reason);
+                        self.tool_usage.record(&name, ToolDoneStatus::Rejected, std::time::Duration::ZERO, error_msg.len());
+                        completed_tool_ids.insert(id.clone());
+                        self.session.add_tool_result(&id, &error_msg, true);
+                        self.emit(SessionOutput::tool_rejected(&id, &name, error_msg)).await;
+                        continue;
+                    }
+                    RuleDecision::Delegate { channel, reason } => {
+                        info!("Delegating approval of {} to {}: {}", name, channel, reason);
+                        ctx.delegated(Delegation { channel, reason })
+                    }
+                    RuleDecision::Undecided => match &self.turn_approval {
+                        // The user loosened approval for this turn
+                        Some((approval, config))
+                            if findings.is_empty() && config.should_auto_approve_with_args(&name, &arguments) =>
+                        {
+                            if let Some(audit) = &self.audit_log {
+                                audit.approved(&id, &name, Decider::User, Some(format!("approval={} for the turn", approval)));
+                            }
+                            ctx.approved()
+                        }
+                        _ => ctx,
+                    },
+                };
+                let ctx = if tool.supports_progress() { ctx.with_progress(progress_tx.clone()) } else { ctx };
+                let slot = self.tool_scheduler.schedule(&name, &arguments);
+                let file_lock = self.file_lock_request(&name, &arguments);
+                // Before the first write of the turn, remember what the file held
+                if let Some(path) = self.tool_scheduler.written_file(&name, &arguments) {
+                    self.turn_changes.lock().snapshot(&path);
+                }
+                let task = async move {
+                    let _guard = slot.acquire().await;
+                    execute_tool_task(tool, id, name, arguments, ctx, file_lock).await
+                };
+                running.insert(tool_call.call_id.clone(), (join_set.spawn(task), cancel));
+            } else {
+                // Tool not found - handle immediately
+                let error_msg = format!("Unknown tool: {}", tool_call.fn_name);
+                self.tool_usage.record(&tool_call.fn_name, ToolDoneStatus::Failed, std::time::Duration::ZERO, error_msg.len());
+                self.session.add_tool_result(&tool_call.call_id, &error_msg, true);
+                self.emit(SessionOutput::tool_done(&tool_call.call_id, &tool_call.fn_name, false, error_msg)).await;
+            }
+        }
 
-            // Process tools: single select! loop handles everything
-            loop {
-                tokio::select! {
-                    // Handle tool completion
-                    result = join_set.join_next() => {
-                        match result {
-                            Some(Ok(res)) => {
-                                completed_tool_ids.insert(res.id.clone());
+        // Track pending approval/question requests by ID
+        let mut pending_approvals = PendingApprovals::new();
+        // Delegated approvals among them, by when a reviewer must answer
+        let mut delegated: std::collections::HashMap<String, tokio::time::Instant> = std::collections::HashMap::new();
+        let mut pending_questions: std::collections::HashMap<String, tokio::sync::oneshot::Sender<QuestionResponse>> = std::collections::HashMap::new();
+        // Pending calls already explained (at most once each)
+        let mut explained: std::collections::HashSet<String> = std::collections::HashSet::new();
+        // When each pending approval was asked for, for the wait-time metric
+        let mut asked_at: std::collections::HashMap<String, std::time::Instant> = std::collections::HashMap::new();
+
+        // Process tools: single select! loop handles everything
+        loop {
+            tokio::select! {
+                // Handle tool completion
+                result = join_set.join_next() => {
+                    match result {
+                        Some(Ok(mut res)) => {
+                            running.remove(&res.id);
+                            // A cancelled call may finish before the abort lands; its result is already recorded
+                            if completed_tool_ids.insert(res.id.clone()) {
+                                res.cancelled = cancelled_ids.remove(&res.id);
                                 self.finalize_spawned_tool(res).await;
-                            }
-                            Some(Err(e)) => {
-                                error!("Tool task failed: {:?}", e);
-                            }
-                            None => {
-                                // JoinSet is empty - all tools completed
-                                break;
+                            } else {
+                                debug!("Dropping result of cancelled tool {}", res.id);
                             }
                         }
+                        Some(Err(e)) if e.is_cancelled() => {
+                            debug!("Tool task aborted: {:?}", e);
+                        }
+                        Some(Err(e)) => {
+                            error!("Tool task failed: {:?}", e);
+                        }
+                        None => {
+                            // JoinSet is empty - all tools completed
+                            break;
+                        }
+                    }
+                }
+
+                // Forward heartbeats from long-running tools, at most one a second per call
+                Some(progress) = progress_rx.recv() => {
+                    let now = std::time::Instant::now();
+                    let due = last_progress
+                        .get(&progress.tool_call_id)
+                        .is_none_or(|last| now.duration_since(*last) >= TOOL_PROGRESS_INTERVAL);
+                    if due && !completed_tool_ids.contains(&progress.tool_call_id) {
+                        last_progress.insert(progress.tool_call_id.clone(), now);
+                        self.emit_ephemeral(SessionOutput::tool_progress(progress));
                     }
+                }
 
-                    // Handle approval/question requests from tools
-                    request = self.approval_rx.recv() => {
-                        match request {
-                            Some(ApprovalRequest::ToolApproval { tool_call_id, tool_name, arguments, description, response_tx }) => {
-                                // Store oneshot and emit pending event
-                                pending_approvals.insert(tool_call_id.clone(), response_tx);
-                                self.emit(SessionOutput::tool_pending(&tool_call_id, &tool_name, arguments, description)).await;
+                // Refuse delegated calls no reviewer answered in time
+                _ = tokio::time::sleep_until(delegated.values().min().copied().unwrap_or_else(tokio::time::Instant::now)), if !delegated.is_empty() => {
+                    let now = tokio::time::Instant::now();
+                    let expired: Vec<String> = delegated.iter().filter(|(_, deadline)| **deadline <= now).map(|(id, _)| id.clone()).collect();
+                    for id in expired {
+                        delegated.remove(&id);
+                        if let Some((name, tx)) = pending_approvals.remove(&id) {
+                            let reason = format!("No reviewer answered within {:?}", self.approval_config.delegate_timeout());
+                            info!("Refusing delegated {} ({}): {}", name, id, reason);
+                            if let Some(audit) = &self.audit_log {
+                                audit.rejected(&id, &name, Decider::System, Some(reason.clone()));
                             }
-                            Some(ApprovalRequest::Question { request_id, questions, response_tx }) => {
-                                // Store oneshot and emit question event
-                                pending_questions.insert(request_id.clone(), response_tx);
-                                self.emit(SessionOutput::Question {
-                                    request_id,
-                                    questions,
-                                    subagent_id: None,
-                                }).await;
+                            let _ = tx.send(ApprovalResponse::Rejected { reason: Some(reason) });
+                        }
+                    }
+                }
+
+                // Handle approval/question requests from tools
+                request = self.approval_rx.recv() => {
+                    match request {
+                        Some(ApprovalRequest::ToolApproval { tool_call_id, tool_name, arguments, delegation: Some(delegation), findings, response_tx, .. }) => {
+                            // Only a reviewer may settle it; the user sees it and may cancel it
+                            let timeout = self.approval_config.delegate_timeout();
+                            pending_approvals.insert(tool_call_id.clone(), (tool_name.clone(), response_tx));
+                            asked_at.insert(tool_call_id.clone(), std::time::Instant::now());
+                            delegated.insert(tool_call_id.clone(), tokio::time::Instant::now() + timeout);
+                            if let Some(audit) = &self.audit_log {
+                                audit.approval_delegated(&tool_call_id, &tool_name, &arguments, &delegation.channel, &findings);
                             }
-                            None => {
-                                error!("Approval channel closed unexpectedly");
+                            self.emit(SessionOutput::approval_delegated(tool_call_id, tool_name, arguments, &delegation, timeout)).await;
+                        }
+                        Some(ApprovalRequest::ToolApproval { tool_call_id, tool_name, arguments, description, findings, generated, response_tx, .. }) => {
+                            // Store oneshot and emit pending event
+                            pending_approvals.insert(tool_call_id.clone(), (tool_name.clone(), response_tx));
+                            asked_at.insert(tool_call_id.clone(), std::time::Instant::now());
+                            let policy_required = self.policy.as_ref().is_some_and(|p| p.requires_approval(&tool_name));
+                            if let Some(audit) = &self.audit_log {
+                                audit.approval_requested(&tool_call_id, &tool_name, &arguments, policy_required, &findings);
                             }
+                            self.emit(SessionOutput::ToolPending {
+                                id: tool_call_id,
+                                name: tool_name,
+                                arguments,
+                                description,
+                                subagent_id: None,
+                                policy_required,
+                                security_warning: (!findings.is_empty()).then(|| exfiltration::warning(&findings)),
+                                generated_files: generated,
+                            }).await;
+                        }
+                        Some(ApprovalRequest::Question { request_id, questions, response_tx }) => {
+                            // Store oneshot and emit question event
+                            pending_questions.insert(request_id.clone(), response_tx);
+                            self.emit(SessionOutput::Question {
+                                request_id,
+                                questions,
+                                subagent_id: None,
+                            }).await;
+                        }
+                        None => {
+                            error!("Approval channel closed unexpectedly");
                         }
                     }
+                }
 
-                    // Handle control messages (approvals, answers, cancel)
-                    input = self.control_rx.recv() => {
-                        match input {
-                            Some(SessionInput::ApproveTool { tool_call_id }) => {
-                                if let Some(tx) = pending_approvals.remove(&tool_call_id) {
-                                    let _ = tx.send(ApprovalResponse::Approved);
-                                } else {
-                                    warn!("Received approval for unknown tool_call_id: {}", tool_call_id);
+                // Handle control messages (approvals, answers, cancel)
+                input = self.control_rx.recv() => {
+                    match input {
+                        Some(SessionInput::ApproveTool { tool_call_id }) if delegated.contains_key(&tool_call_id) => {
+                            warn!("Refusing the user's approval of delegated tool call {}", tool_call_id);
+                            self.emit(SessionOutput::warning(self.localizer.text(Text::DelegatedApproval))).await;
+                        }
+                        Some(SessionInput::ApproveTool { tool_call_id }) => {
+                            if let Some((name, tx)) = pending_approvals.remove(&tool_call_id) {
+                                if let Some(audit) = &self.audit_log {
+                                    audit.approved(&tool_call_id, &name, Decider::User, None);
                                 }
+                                record_approval_wait(&mut asked_at, &tool_call_id, &name, "approved", "user");
+                                self.record_chunk_decision(tool_calls, &tool_call_id, true);
+                                let _ = tx.send(ApprovalResponse::Approved);
+                            } else {
+                                warn!("Received approval for unknown tool_call_id: {}", tool_call_id);
                             }
-                            Some(SessionInput::RejectTool { tool_call_id, reason }) => {
-                                if let Some(tx) = pending_approvals.remove(&tool_call_id) {
-                                    let _ = tx.send(ApprovalResponse::Rejected { reason });
-                                } else {
-                                    warn!("Received rejection for unknown tool_call_id: {}", tool_call_id);
+                        }
+                        Some(SessionInput::RejectTool { tool_call_id, reason }) => {
+                            delegated.remove(&tool_call_id);
+                            if let Some((name, tx)) = pending_approvals.remove(&tool_call_id) {
+                                if let Some(audit) = &self.audit_log {
+                                    audit.rejected(&tool_call_id, &name, Decider::User, reason.clone());
                                 }
+                                record_approval_wait(&mut asked_at, &tool_call_id, &name, "rejected", "user");
+                                self.record_chunk_decision(tool_calls, &tool_call_id, false);
+                                let _ = tx.send(ApprovalResponse::Rejected { reason });
+                            } else {
+                                warn!("Received rejection for unknown tool_call_id: {}", tool_call_id);
                             }
-                            Some(SessionInput::AnswerQuestion { request_id, answers }) => {
-                                if let Some(tx) = pending_questions.remove(&request_id) {
-                                    let _ = tx.send(QuestionResponse { answers });
-                                } else {
-                                    warn!("Received answer for unknown request_id: {}", request_id);
+                        }
+                        Some(SessionInput::ApproveDelegated { tool_call_id, approver }) => {
+                            if delegated.remove(&tool_call_id).is_some()
+                                && let Some((name, tx)) = pending_approvals.remove(&tool_call_id)
+                            {
+                                info!("{} approved delegated {} ({})", approver, name, tool_call_id);
+                                if let Some(audit) = &self.audit_log {
+                                    audit.approved_by_reviewer(&tool_call_id, &name, &approver);
                                 }
+                                record_approval_wait(&mut asked_at, &tool_call_id, &name, "approved", "reviewer");
+                                let _ = tx.send(ApprovalResponse::Approved);
+                            } else {
+                                warn!("Received delegated approval for unknown tool_call_id: {}", tool_call_id);
                             }
-                            Some(SessionInput::Cancel) => {
-                                reject_all_pending(&mut pending_approvals, &mut pending_questions, "Cancelled by user");
-                                self.handle_cancel_cleanup(&tool_calls, &mut completed_tool_ids, &mut join_set).await;
-                                self.emit(SessionOutput::cancelled()).await;
-                                return Ok(());
+                        }
+                        Some(SessionInput::RejectDelegated { tool_call_id, approver, reason }) => {
+                            if delegated.remove(&tool_call_id).is_some()
+                                && let Some((name, tx)) = pending_approvals.remove(&tool_call_id)
+                            {
+                                info!("{} rejected delegated {} ({})", approver, name, tool_call_id);
+                                if let Some(audit) = &self.audit_log {
+                                    audit.rejected_by_reviewer(&tool_call_id, &name, &approver, reason.clone());
+                                }
+                                record_approval_wait(&mut asked_at, &tool_call_id, &name, "rejected", "reviewer");
+                                let reason = reason.unwrap_or_else(|| format!("Rejected by reviewer {}", approver));
+                                let _ = tx.send(ApprovalResponse::Rejected { reason: Some(reason) });
+                            } else {
+                                warn!("Received delegated rejection for unknown tool_call_id: {}", tool_call_id);
                             }
-                            Some(other) => {
-                                debug!("Unexpected control input: {:?}", other);
+                        }
+                        Some(SessionInput::AnswerQuestion { request_id, answers }) => {
+                            if let Some(tx) = pending_questions.remove(&request_id) {
+                                let _ = tx.send(QuestionResponse { answers });
+                            } else {
+                                warn!("Received answer for unknown request_id: {}", request_id);
                             }
-                            None => {
-                                reject_all_pending(&mut pending_approvals, &mut pending_questions, "Session ended");
-                                self.emit(SessionOutput::error("Session interrupted".to_string())).await;
-                                return Ok(());
+                        }
+                        Some(SessionInput::CancelTool { id }) => {
+                            delegated.remove(&id);
+                            if let Some((name, tx)) = pending_approvals.remove(&id) {
+                                // Not started yet: the same as a rejection
+                                if let Some(audit) = &self.audit_log {
+                                    audit.rejected(&id, &name, Decider::User, Some(TOOL_CANCELLED.to_string()));
+                                }
+                                cancelled_ids.insert(id);
+                                let _ = tx.send(ApprovalResponse::Rejected { reason: Some(TOOL_CANCELLED.to_string()) });
+                            } else if let Some((handle, cancel)) = running.remove(&id)
+                                && completed_tool_ids.insert(id.clone())
+                            {
+                                // Let the tool stop its own work, then abort it if it hasn't
+                                cancel.cancel();
+                                tokio::spawn(async move {
+                                    tokio::time::sleep(TOOL_CANCEL_GRACE).await;
+                                    handle.abort();
+                                });
+                                let call = tool_calls.iter().find(|tc| tc.call_id == id);
+                                let name = call.map(|tc| tc.fn_name.clone()).unwrap_or_default();
+                                info!("Cancelled running tool {} ({})", id, name);
+                                // It may have changed things before the abort landed
+                                if let (Some(audit), Some(call)) = (&self.audit_log, call)
+                                    && self.tool_registry.get(&name).is_some_and(|tool| !runs_in_dry_run(tool.as_ref()))
+                                {
+                                    audit.tool_executed(&id, &name, &call.fn_arguments, false, true);
+                                }
+                                self.record_cancelled_tool(&id, &name).await;
+                            } else {
+                                debug!("Nothing to cancel for tool {}: already finished", id);
+                            }
+                        }
+                        Some(SessionInput::ExplainTool { id }) => {
+                            // One explanation per call, and only while it waits for approval
+                            let call = tool_calls.iter().find(|tc| tc.call_id == id);
+                            match call {
+                                Some(call) if pending_approvals.contains_key(&id) => {
+                                    if explained.insert(id.clone()) {
+                                        self.explain_tool_call(id, &call.fn_name, &call.fn_arguments).await;
+                                    } else {
+                                        self.emit(SessionOutput::warning(self.localizer.text(Text::ExplainAlreadyRequested))).await;
+                                    }
+                                }
+                                _ => {
+                                    debug!("Nothing to explain for tool {}: not waiting for approval", id);
+                                    self.emit(SessionOutput::warning(self.localizer.text(Text::ExplainNotPending))).await;
+                                }
                             }
                         }
+                        Some(SessionInput::Cancel) => {
+                            reject_all_pending(&mut pending_approvals, &mut pending_questions, "Cancelled by user", self.audit_log.as_deref());
+                            self.handle_cancel_cleanup(tool_calls, &mut completed_tool_ids, &mut join_set, &batch_cancel).await;
+                            // A turn the user stopped isn't reviewed
+                            self.turn_request = None;
+                            self.emit(SessionOutput::cancelled()).await;
+                            return Ok(false);
+                        }
+                        Some(other) => {
+                            debug!("Unexpected control input: {:?}", other);
+                        }
+                        None => {
+                            reject_all_pending(&mut pending_approvals, &mut pending_questions, "Session ended", self.audit_log.as_deref());
+                            self.emit(SessionOutput::error(self.localizer.text(Text::SessionInterrupted).to_string())).await;
+                            return Ok(false);
+                        }
                     }
                 }
             }
         }
+        Ok(true)
+    }
+
+    /// Path a Write call writes, as chunk decisions are keyed
+    fn chunk_path(&self, arguments: &serde_json::Value) -> Option<std::path::PathBuf> {
+        arguments["file_path"].as_str().map(|path| normalize_path(&self.workspace.join(path)))
+    }
+
+    /// Remember the user's decision on a Write, for the file's later chunks
+    fn record_chunk_decision(&mut self, tool_calls: &[ToolCall], tool_call_id: &str, approved: bool) {
+        let Some(call) = tool_calls.iter().find(|tc| tc.call_id == tool_call_id && tc.fn_name == "Write") else {
+            return;
+        };
+        if let Some(path) = self.chunk_path(&call.fn_arguments) {
+            self.chunk_decisions.insert(path, approved);
+        }
     }
 
     /// Tools allowed when plan mode is active
     /// Note: Write is allowed for writing the plan file to ~/.claude/plans/
     const PLAN_MODE_TOOLS: &'static [&'static str] = &[
-        "Read", "Glob", "Grep", "LSP", "WebFetch", "WebSearch", "Write",
+        "Read", "Glob", "Grep", "CodebaseSearch", "SearchFiles", "LSP", "WebFetch", "WebSearch", "Write",
         ASK_QUESTION_TOOL_NAME, "ExitPlanMode", "TodoWrite",
     ];
 
+    /// Wait for a slot under the shared request limits, if any are configured
+    async fn acquire_request_permit(&self) -> Option<RequestPermit> {
+        let limiter = self.request_limiter.as_ref()?;
+        let provider_id = self.provider.provider_id();
+        if let Some(permit) = limiter.try_acquire(provider_id) {
+            return Some(permit);
+        }
+        self.emit_ephemeral(SessionOutput::thinking(
+            self.localizer.text(Text::WaitingForRequestSlot).to_string(),
+        ));
+        Some(limiter.acquire(provider_id).await)
+    }
+
+    /// Append a completed call's token usage to the usage store (fire-and-forget)
+    fn record_usage(&self, response: &LlmCallResult) {
+        let Some(store) = &self.usage_store else {
+            return;
+        };
+        if let Some(record) = self.provider.usage_record(
+            &self.session_id,
+            response.input_tokens,
+            response.output_tokens,
+            response.cached_tokens,
+            None,
+        ) {
+            store.record(record);
+        }
+    }
+
+    /// Ask the fast model why a call waiting for approval is wanted
+    ///
+    /// The request carries the end of the conversation and the call, and
+    /// runs on its own so the call stays pending meanwhile; its answer comes
+    /// as `SessionOutput::ToolExplanation`. Neither the request nor the
+    /// reply enter the history, and its tokens are recorded with
+    /// `EXPLAIN_PURPOSE`.
+    async fn explain_tool_call(&self, id: String, tool_name: &str, arguments: &serde_json::Value) {
+        let Some(client) = self.explain_client.clone() else {
+            self.emit(SessionOutput::warning(self.localizer.text(Text::ExplainUnavailable))).await;
+            return;
+        };
+        let messages = &self.session.messages;
+        let mut tail = messages[messages.len().saturating_sub(EXPLAIN_TAIL_MESSAGES)..].to_vec();
+        self.session.arena().resolve_messages(&mut tail);
+        let request = explanation_request(&tail, tool_name, arguments);
+
+        let (output, usage_store, session_id, localizer) =
+            (self.output.clone(), self.usage_store.clone(), self.session_id.clone(), self.localizer);
+        tokio::spawn(async move {
+            let params = GenerationParams::default();
+            let reply = match tokio::time::timeout(EXPLAIN_TIMEOUT, client.chat(request, None, &params)).await {
+                Ok(Ok(response)) => {
+                    // Counted apart from the session's turns
+                    if let Some(store) = &usage_store
+                        && let Some(record) = client.usage_record(
+                            &session_id,
+                            response.input_tokens,
+                            response.output_tokens,
+                            response.cached_tokens,
+                            Some(EXPLAIN_PURPOSE),
+                        )
+                    {
+                        store.record(record);
+                    }
+                    response.content.as_deref().and_then(clean_explanation).ok_or_else(|| "empty reply".to_string())
+                }
+                Ok(Err(e)) => Err(e.to_string()),
+                Err(_) => Err(format!("no reply within {}s", EXPLAIN_TIMEOUT.as_secs())),
+            };
+            let output_event = match reply {
+                Ok(text) => SessionOutput::tool_explanation(id, text),
+                Err(error) => {
+                    debug!("Tool call {} in session {} not explained: {}", id, session_id, error);
+                    SessionOutput::warning(localizer.format(Text::ExplainFailed, &[("error", &error)]))
+                }
+            };
+            output.emit_critical(output_event).await;
+        });
+    }
+
+    /// Review the turn's changes against the message it answered
+    ///
+    /// Only with `self_review` on, for a turn that wrote files or ran
+    /// commands. The fast model's findings come as `SessionOutput::SelfReview`
+    /// and nothing acts on them; returns whether there are more than
+    /// `self_review_threshold`, for the turn's `Idle`. A review that fails is
+    /// only logged. Its tokens are recorded with `SELF_REVIEW_PURPOSE`.
+    async fn review_turn(&mut self) -> bool {
+        let changes = std::mem::take(&mut *self.turn_changes.lock());
+        let Some(request) = self.turn_request.take().filter(|_| self.self_review) else {
+            return false;
+        };
+        let files = changes.changes();
+        if files.is_empty() && changes.commands().is_empty() {
+            return false;
+        }
+        let Some(client) = self.explain_client.clone() else {
+            warn!("Turn of session {} not reviewed: no model to review it", self.session_id);
+            return false;
+        };
+
+        let messages = review_request(&request, &files, changes.commands(), &self.path_display);
+        let params = GenerationParams::default();
+        let findings = match tokio::time::timeout(SELF_REVIEW_TIMEOUT, client.chat(messages, None, &params)).await {
+            Ok(Ok(response)) => {
+                // Counted apart from the session's turns
+                if let Some(store) = &self.usage_store
+                    && let Some(record) = client.usage_record(
+                        &self.session_id,
+                        response.input_tokens,
+                        response.output_tokens,
+                        response.cached_tokens,
+                        Some(SELF_REVIEW_PURPOSE),
+                    )
+                {
+                    store.record(record);
+                }
+                parse_findings(response.content.as_deref().unwrap_or_default())
+            }
+            Ok(Err(e)) => {
+                warn!("Turn of session {} not reviewed: {}", self.session_id, e);
+                return false;
+            }
+            Err(_) => {
+                warn!("Turn of session {} not reviewed: no reply within {}s", self.session_id, SELF_REVIEW_TIMEOUT.as_secs());
+                return false;
+            }
+        };
+        debug!("Self-review of session {} found {} gap(s)", self.session_id, findings.len());
+        let warning = findings.len() > self.self_review_threshold;
+        self.emit(SessionOutput::self_review(findings)).await;
+        warning
+    }
+
     /// Call the LLM and get a response
+    ///
+    /// Fails at once while the provider's circuit is open. A request that
+    /// times out is sent again (up to `MAX_TIMEOUT_RETRIES`); timeouts and
+    /// server errors count against the provider's circuit.
     async fn call_llm(&self) -> Result<LlmCallResult> {
-        let mut llm_messages = self.session.get_messages().to_vec();
+        let mut llm_messages = self.session.resolved_messages();
 
         // Check plan mode state — filter tools and inject reminder
         let plan_state = self.plan_mode_state.read().await;
@@ -680,6 +2490,11 @@ impl AgentLoop {
         let plan_file = plan_state.plan_file.clone();
         drop(plan_state); // Release the lock
 
+        let mut params = self.generation_params.read().await.clone();
+        if let Some(temperature) = self.turn_temperature {
+            params.temperature = Some(temperature);
+        }
+
         let tools = if self.tool_definitions.is_empty() {
             None
         } else if plan_active {
@@ -693,9 +2508,18 @@ impl AgentLoop {
             Some(self.tool_definitions.clone())
         };
 
+        // Further narrow to the active skill's allowed tools
+        let tools = match (&self.skill_restriction, tools) {
+            (Some(restriction), Some(tools)) => {
+                let filtered = restriction.filter_definitions(tools);
+                if filtered.is_empty() { None } else { Some(filtered) }
+            }
+            (_, tools) => tools,
+        };
+
         // Inject plan mode reminder into the messages
         if plan_active {
-            let base_reminder = crate::prompt::builtin::claude_code::reminders::PLAN_MODE_ACTIVE;
+            let base_reminder = reminders::PLAN_MODE_ACTIVE;
             // Add plan file path to the reminder
             let reminder = if let Some(ref pf) = plan_file {
                 format!("{}\n\nA plan file exists from plan mode at: {}\n\nWrite your plan to this file using the Write tool.",
@@ -712,27 +2536,170 @@ impl AgentLoop {
             }
         }
 
+        let circuits = provider_circuits();
+        let provider_id = self.provider.provider_id();
+        let labels = [("provider", provider_id), ("model", self.provider.model())];
+        let mut timeouts = 0;
+        loop {
+            circuits.check(provider_id)?;
+            let request = self.request_llm(llm_messages.clone(), tools.clone(), &params);
+            let started = std::time::Instant::now();
+            let result = tokio::time::timeout(self.request_timeout, request).await;
+            let outcome = match &result {
+                Ok(result) => telemetry::outcome(result),
+                Err(_) => "timeout",
+            };
+            telemetry::histogram(
+                telemetry::LLM_CALL_SECONDS,
+                started.elapsed().as_secs_f64(),
+                &[labels[0], labels[1], ("outcome", outcome)],
+            );
+            match result {
+                Ok(Ok(result)) => {
+                    circuits.record_success(provider_id);
+                    for (kind, tokens) in [
+                        ("input", result.input_tokens),
+                        ("output", result.output_tokens),
+                        ("cached", result.cached_tokens),
+                    ] {
+                        if let Some(tokens) = tokens {
+                            telemetry::counter(telemetry::LLM_TOKENS, tokens, &[labels[0], labels[1], ("kind", kind)]);
+                        }
+                    }
+                    return Ok(result);
+                }
+                Ok(Err(e)) => {
+                    if is_server_error(&e.to_string()) {
+                        circuits.record_failure(provider_id, &e.to_string());
+                    } else {
+                        circuits.end_probe(provider_id);
+                    }
+                    return Err(e);
+                }
+                Err(_) => {
+                    let error = format!(
+                        "No response from {} within {}s",
+                        provider_id,
+                        self.request_timeout.as_secs()
+                    );
+                    warn!(provider = %provider_id, attempt = timeouts + 1, "LLM request timed out");
+                    circuits.record_failure(provider_id, &error);
+                    if timeouts >= MAX_TIMEOUT_RETRIES {
+                        return Err(crate::error::Error::Provider(error));
+                    }
+                    timeouts += 1;
+                    telemetry::counter(telemetry::LLM_RETRIES, 1, &labels);
+                    self.emit_ephemeral(SessionOutput::thinking(self.localizer.format(Text::Retrying, &[("error", &error)])));
+                }
+            }
+        }
+    }
+
+    /// Call the LLM, compacting the history and retrying once if the
+    /// provider rejects it as longer than the context window
+    ///
+    /// The context monitor compacts ahead of time from the last reported
+    /// token count, but that count doesn't include what was added since
+    /// (tool results, memory files), so a history can still overflow.
+    async fn call_llm_recovering(&mut self) -> Result<LlmCallResult> {
+        let overflow = match self.call_llm().await {
+            Err(crate::error::Error::Provider(message)) => match context_overflow(&message) {
+                Some(overflow) => overflow,
+                None => return Err(crate::error::Error::Provider(message)),
+            },
+            result => return result,
+        };
+        warn!(tokens = ?overflow.tokens, limit = ?overflow.limit, "Provider rejected the history as too long");
+        self.recalibrate_context(&overflow);
+
+        let overflowed = |overflow: ContextOverflow| crate::error::Error::ContextOverflow {
+            tokens: overflow.tokens,
+            limit: overflow.limit,
+        };
+        if let Err(e) = self.emergency_compact().await {
+            warn!("Emergency compaction failed: {}", e);
+            return Err(overflowed(overflow));
+        }
+        match self.call_llm().await {
+            Err(crate::error::Error::Provider(message)) => match context_overflow(&message) {
+                Some(again) => Err(overflowed(again)),
+                None => Err(crate::error::Error::Provider(message)),
+            },
+            result => result,
+        }
+    }
+
+    /// Correct the context monitor's view from an overflow the provider reported
+    ///
+    /// The reported token count replaces the last known usage, and a reported
+    /// limit replaces the catalog's context window when they differ.
+    fn recalibrate_context(&mut self, overflow: &ContextOverflow) {
+        if let Some(tokens) = overflow.tokens {
+            self.last_input_tokens = tokens;
+            self.last_output_tokens = 0;
+        }
+        if let Some(limit) = overflow.limit.and_then(|limit| usize::try_from(limit).ok())
+            && limit > 0
+            && limit != self.context_limit
+        {
+            info!("Context limit recalibrated from {} to {} tokens", self.context_limit, limit);
+            self.context_limit = limit;
+        }
+    }
+
+    /// Summarize the history into a short summary after it overflowed the
+    /// context window, collapsing the oldest tool results first so the
+    /// summarization request itself fits
+    async fn emergency_compact(&mut self) -> Result<()> {
+        self.emit_ephemeral(SessionOutput::thinking(self.localizer.text(Text::CompactingOverflow).to_string()));
+
+        let messages = self.session.resolved_messages();
+        let pins = self.pins.lock().clone();
+        let keep = |m: &ChatMessage| is_summary(m) || pins.is_pinned(m);
+        let (result, collapsed) = match self.summarizer.mode {
+            CompactionMode::Heuristic => (heuristic_compact(&messages, self.summarizer.keep_turns, keep), 0),
+            CompactionMode::Llm => {
+                let chars_before: usize = messages.iter().map(|m| message_text_content(m).len()).sum();
+                let (request, collapsed) = emergency_summarization_request(&messages, self.context_limit);
+                let response = self.provider.chat(request, None, &GenerationParams::default()).await?;
+                let result = compact_result(chars_before, messages.len(), response.content)
+                    .with_model(self.provider.model())
+                    .with_pinned(&messages, |m| pins.is_pinned(m));
+                (result, collapsed)
+            }
+        };
+        info!(
+            "Emergency compaction complete: {} -> {} chars ({} messages summarized, {} tool results collapsed)",
+            result.chars_before, result.chars_after, result.messages_summarized, collapsed
+        );
+
+        self.finish_compaction(&result, "emergency").await;
+
+        self.emit(SessionOutput::warning(self.localizer.format(
+            Text::ContextRecovered,
+            &[("messages", &result.messages_summarized), ("collapsed", &collapsed)],
+        )))
+        .await;
+        Ok(())
+    }
+
+    /// Send one request to the LLM, streamed or not per the session's config
+    async fn request_llm(
+        &self,
+        llm_messages: Vec<ChatMessage>,
+        tools: Option<Vec<ToolDefinition>>,
+        params: &GenerationParams,
+    ) -> Result<LlmCallResult> {
         // Use streaming or non-streaming based on config
         if self.stream_mode {
             // Streaming mode: emit text deltas as they arrive
-            let (chunk_tx, mut chunk_rx) = tokio::sync::mpsc::channel::<String>(32);
-            let output_tx = self.output_tx.clone();
-            let session_id = self.session_id.clone();
             // Generate a unique ID for this streaming response
             let stream_msg_id = format!("stream-{}", uuid::Uuid::new_v4());
-
-            // Spawn task to forward chunks to output
-            let chunk_forwarder = tokio::spawn(async move {
-                while let Some(chunk) = chunk_rx.recv().await {
-                    let _ = output_tx.send((session_id.clone(), SessionOutput::text_delta(&stream_msg_id, chunk))).await;
-                }
-            });
-
-            // Execute streaming request
-            let result = self.provider.chat_stream(llm_messages, tools, chunk_tx).await;
-
-            // Wait for chunk forwarder to finish
-            let _ = chunk_forwarder.await;
+            let events = self.provider.stream(llm_messages, tools, params);
+            let result = collect_stream(events, |chunk| {
+                self.output.emit_ephemeral(SessionOutput::text_delta(&stream_msg_id, chunk));
+            })
+            .await;
 
             match result {
                 Ok(result) => Ok(LlmCallResult {
@@ -740,17 +2707,21 @@ impl AgentLoop {
                     tool_calls: result.tool_calls,
                     input_tokens: result.input_tokens,
                     output_tokens: result.output_tokens,
+                    cached_tokens: result.cached_tokens,
+                    finish_reason: result.finish_reason,
                 }),
                 Err(e) => Err(crate::error::Error::Provider(e.to_string())),
             }
         } else {
             // Non-streaming mode: get complete response at once
-            match self.provider.chat(llm_messages, tools).await {
+            match self.provider.chat(llm_messages, tools, params).await {
                 Ok(result) => Ok(LlmCallResult {
                     content: result.content,
                     tool_calls: result.tool_calls,
                     input_tokens: result.input_tokens,
                     output_tokens: result.output_tokens,
+                    cached_tokens: result.cached_tokens,
+                    finish_reason: result.finish_reason,
                 }),
                 Err(e) => Err(crate::error::Error::Provider(e.to_string())),
             }
@@ -769,6 +2740,7 @@ impl AgentLoop {
                 info.skill_name, info.agent_type
             );
 
+            self.tool_usage.record(&res.name, ToolDoneStatus::Success, res.elapsed, brief_result.len());
             self.session.add_tool_result(&res.id, &brief_result, false);
             self.emit(SessionOutput::tool_done(&res.id, &res.name, true, brief_result.clone())).await;
 
@@ -790,15 +2762,25 @@ impl AgentLoop {
             let name = skill_name.as_deref().unwrap_or("unknown");
             let brief_result = format!("Skill '{}' loaded. Follow the instructions below.", name);
 
+            self.tool_usage.record(&res.name, ToolDoneStatus::Success, res.elapsed, brief_result.len());
             self.session.add_tool_result(&res.id, &brief_result, false);
             self.emit(SessionOutput::tool_done(&res.id, &res.name, true, brief_result)).await;
 
+            // Restrict tools for the rest of this turn if the skill declares allowed-tools
+            if let Some(allowed_tools) = res.allowed_tools {
+                debug!("Skill '{}' restricts tools to: {:?}", name, allowed_tools);
+                self.skill_restriction = Some(SkillToolRestriction::new(name, &allowed_tools));
+            }
+
             // Inject skill content as a user message with command-name tag
             let injected = format!("<command-name>/{}</command-name>\n\n{}", name, content);
             self.session.add_user_message(&injected);
             return;
         }
 
+        // Keep the result in case the model asks for it again
+        self.repeat_detector.record(&res.name, &res.arguments, &res.output, res.success);
+
         // Run post-tool hooks (if enabled)
         let mut final_output = res.output;
         if self.hooks_enabled
@@ -819,15 +2801,48 @@ impl AgentLoop {
             res.success,
             &truncated,
             &res.arguments,
+            &self.path_display,
         );
 
+        // Record what the session now knows of the file, so its own writes aren't reported
+        if res.success
+            && let Some(watcher) = self.file_watcher.as_mut()
+        {
+            watcher.track_tool_call(&res.name, &res.arguments);
+        }
+
+        // Record mutations in the audit log
+        if let Some(audit) = &self.audit_log
+            && self.tool_registry.get(&res.name).is_some_and(|tool| !runs_in_dry_run(tool.as_ref()))
+        {
+            audit.tool_executed(&res.id, &res.name, &res.arguments, res.success, res.cancelled);
+        }
+
         // Update session and emit
+        let status = match (res.success, res.cancelled, res.rejected) {
+            (true, _, _) => ToolDoneStatus::Success,
+            (false, true, _) => ToolDoneStatus::Cancelled,
+            (false, false, true) => ToolDoneStatus::Rejected,
+            (false, false, false) => ToolDoneStatus::Failed,
+        };
+        self.tool_usage.record(&res.name, status, res.elapsed, truncated.len());
+        if res.name == "Bash"
+            && !res.rejected
+            && let Some(command) = res.arguments.get("command").and_then(serde_json::Value::as_str)
+        {
+            self.turn_changes.lock().record_command(command);
+        }
         self.session.add_tool_result(&res.id, &truncated, !res.success);
 
-        // Emit tool done (ephemeral)
-        self.emit(SessionOutput::tool_done(&res.id, &res.name, res.success, &truncated)).await;
+        // Emit tool done (the only completion event on some paths, so never dropped)
+        if res.cancelled {
+            self.emit(SessionOutput::tool_cancelled(&res.id, &res.name, &truncated)).await;
+        } else {
+            self.emit(SessionOutput::tool_done(&res.id, &res.name, res.success, &truncated)).await;
+        }
 
         // Emit tool result (persistent message)
+        let line = tool_result_line(&res.name, &res.arguments, diff_preview.as_deref());
         self.emit(SessionOutput::tool_result(
             &res.id,
             &res.name,
@@ -835,8 +2850,64 @@ impl AgentLoop {
             &truncated,
             summary,
             diff_preview,
-        ))
+            res.artifacts,
+        )
+        .with_absolute_path(self.target_file(&res.arguments))
+        .with_line(line))
         .await;
+
+        if res.name == "TodoWrite" && res.success {
+            let todos = self.todo_list.read().await.clone();
+            self.emit(SessionOutput::todos_updated(todos)).await;
+        }
+    }
+
+    /// Add a plan approved since the last turn as this turn's task list
+    async fn inject_approved_plan(&mut self) {
+        let Some(name) = self.plan_mode_state.write().await.approved_plan.take() else {
+            return;
+        };
+        let store = PlanStore::default();
+        let plan = match store.open(&name) {
+            Ok(Some(plan)) => plan,
+            Ok(None) => {
+                warn!("Approved plan {} no longer exists", name);
+                return;
+            }
+            Err(e) => {
+                warn!("Failed to read approved plan {}: {}", name, e);
+                return;
+            }
+        };
+
+        info!("Injecting approved plan {} into session {}", name, self.session_id);
+        self.session.add_user_message(format!("<system-reminder>\n{}\n</system-reminder>", plan.approval_reminder()));
+
+        // Free-form plans have no steps to track, so they stay approved
+        if !plan.is_read_only() {
+            match store.set_status(&name, PlanStatus::InProgress) {
+                Ok(_) => self.emit(SessionOutput::plan_status_changed(name, PlanStatus::InProgress)).await,
+                Err(e) => warn!("Failed to update plan {}: {}", name, e),
+            }
+        }
+    }
+
+    /// Add a system reminder for tracked files changed outside the session and notify the UI
+    async fn report_external_changes(&mut self) {
+        let Some(watcher) = self.file_watcher.as_ref() else {
+            return;
+        };
+        let changes = watcher.take_changes();
+        if changes.is_empty() {
+            return;
+        }
+
+        let reminder = external_change_reminder(&changes, watcher);
+        let paths: Vec<String> = changes.iter().map(|c| watcher.display_path(&c.path)).collect();
+        info!("Files changed outside session {}: {:?}", self.session_id, paths);
+
+        self.session.add_user_message(format!("<system-reminder>\n{}\n</system-reminder>", reminder));
+        self.emit(SessionOutput::files_changed_externally(paths)).await;
     }
 
     // ========================================================================
@@ -873,50 +2944,121 @@ impl AgentLoop {
         );
 
         // Emit compaction notification
-        self.emit(SessionOutput::thinking(format!(
-            "Context at {:.0}% - compacting conversation history...",
-            usage.used_percentage * 100.0
-        )))
-        .await;
+        let percent = format!("{:.0}", usage.used_percentage * 100.0);
+        self.emit_ephemeral(SessionOutput::thinking(
+            self.localizer.format(Text::Compacting, &[("percent", &percent)]),
+        ));
 
-        // Perform compaction using LLM
-        let result = compact(&self.session.messages, None, &self.provider).await?;
+        let result = self.compact_history(self.summarizer.mode, None, None).await?;
+        self.finish_compaction(&result, "auto").await;
 
-        info!(
-            "Compaction complete: {} -> {} chars ({} messages summarized)",
-            result.chars_before,
-            result.chars_after,
-            result.messages_summarized
-        );
+        // Emit completion notification
+        self.emit_ephemeral(SessionOutput::thinking(self.localizer.format(
+            Text::Compacted,
+            &[
+                ("messages", &result.messages_summarized),
+                ("before", &result.chars_before),
+                ("after", &result.chars_after),
+            ],
+        )));
 
-        // Replace session messages with compacted version
-        self.apply_compaction_result(&result);
+        Ok(())
+    }
 
-        // Reset token counts - next LLM response will update
-        self.last_input_tokens = 0;
-        self.last_output_tokens = 0;
+    /// Compact the history at the user's request (`SessionInput::Compact`),
+    /// with `mode` and `model` in place of the summarizer's
+    async fn compact_on_request(
+        &mut self,
+        mode: Option<CompactionMode>,
+        model: Option<String>,
+        focus: Option<String>,
+    ) -> Result<()> {
+        self.emit_ephemeral(SessionOutput::thinking(self.localizer.text(Text::CompactingOnRequest).to_string()));
+        let mode = mode.unwrap_or(self.summarizer.mode);
+        let result = self.compact_history(mode, model.as_deref(), focus.as_deref()).await?;
+        self.finish_compaction(&result, "manual").await;
+        Ok(())
+    }
 
-        // Emit completion notification
-        self.emit(SessionOutput::thinking(format!(
-            "Compacted {} messages into summary ({} -> {} chars)",
-            result.messages_summarized, result.chars_before, result.chars_after
-        )))
-        .await;
+    /// Compact the history without applying the result
+    ///
+    /// `llm` compaction asks the summarizer's model, or `model` on the
+    /// summarizer's provider, and records its tokens with
+    /// `COMPACTION_PURPOSE`. `heuristic` compaction never removes earlier
+    /// summaries. Pinned messages are kept either way (see `session::pins`).
+    async fn compact_history(
+        &self,
+        mode: CompactionMode,
+        model: Option<&str>,
+        focus: Option<&str>,
+    ) -> Result<CompactResult> {
+        let messages = self.session.resolved_messages();
+        let pins = self.pins.lock().clone();
+        let result = match mode {
+            CompactionMode::Heuristic => {
+                heuristic_compact(&messages, self.summarizer.keep_turns, |m| is_summary(m) || pins.is_pinned(m))
+            }
+            CompactionMode::Llm => {
+                let summarizer = self.summarizer_client.as_ref().ok_or_else(|| {
+                    crate::error::Error::Provider("No model is available to summarize this session".to_string())
+                })?;
+                let overridden;
+                let client = match model {
+                    Some(model) => {
+                        overridden = summarizer.for_model(model);
+                        &overridden
+                    }
+                    None => summarizer,
+                };
+                let chars_before: usize = messages.iter().map(|m| message_text_content(m).len()).sum();
+                let request = summarization_request(&messages, focus, self.summary_template.as_deref());
+                let response = client.chat(request, None, &GenerationParams::default()).await?;
+                if let Some(store) = &self.usage_store
+                    && let Some(record) = client.usage_record(
+                        &self.session_id,
+                        response.input_tokens,
+                        response.output_tokens,
+                        response.cached_tokens,
+                        Some(COMPACTION_PURPOSE),
+                    )
+                {
+                    store.record(record);
+                }
+                compact_result(chars_before, messages.len(), response.content)
+                    .with_model(client.model())
+                    .with_pinned(&messages, |m| pins.is_pinned(m))
+            }
+        };
 
-        Ok(())
+        info!(
+            "Compaction ({}) complete: {} -> {} chars ({} messages summarized)",
+            result.mode, result.chars_before, result.chars_after, result.messages_summarized
+        );
+        Ok(result)
     }
 
-    /// Apply compaction result to the session
+    /// Replace the history with a compaction's and report it
     ///
-    /// Following Anthropic SDK approach: replace entire conversation with
-    /// a single USER message containing the summary wrapped in <summary> tags.
-    fn apply_compaction_result(&mut self, result: &crate::context::CompactResult) {
-        // Clear existing messages
+    /// The history becomes the result's messages: with `llm` compaction a
+    /// single USER message holding the summary in <summary> tags (following
+    /// the Anthropic SDK) and then the pinned messages, with `heuristic`
+    /// compaction the kept turns around the condensed ones.
+    async fn finish_compaction(&mut self, result: &CompactResult, kind: &str) {
         self.session.clear();
+        self.turns.clear();
+        self.session.restore_messages(result.messages.clone());
+        self.pins.lock().retain(&self.session.messages);
 
-        // Add the summary as a single USER message (following Anthropic SDK)
-        // The summary contains <summary>...</summary> tags
-        self.session.messages.push(ChatMessage::user(&result.summary));
+        telemetry::counter(telemetry::COMPACTIONS, 1, &[("kind", kind)]);
+        if let Some(audit) = &self.audit_log {
+            audit.session_compacted(result.messages_summarized, result.chars_before, result.chars_after);
+        }
+
+        // Reset token counts - next LLM response will update
+        self.last_input_tokens = 0;
+        self.last_output_tokens = 0;
+
+        self.emit(SessionOutput::compacted(result)).await;
     }
 
     // ========================================================================
@@ -977,73 +3119,269 @@ impl AgentLoop {
 
     /// Handle cancellation cleanup
     ///
-    /// Collects any completed results, aborts remaining tasks, and adds "Cancelled"
-    /// results for all tools that didn't complete.
+    /// Collects any completed results, cancels the rest and gives them a
+    /// moment to stop their work, aborts whatever is still running, and adds
+    /// "Cancelled" results for all tools that didn't complete.
     async fn handle_cancel_cleanup(
         &mut self,
         all_tool_calls: &[ToolCall],
         completed_tool_ids: &mut std::collections::HashSet<String>,
         join_set: &mut JoinSet<SpawnedToolResult>,
+        cancel: &CancellationToken,
     ) {
         // Collect any completed results from the JoinSet
         while let Some(result) = join_set.try_join_next() {
-            if let Ok(res) = result {
-                completed_tool_ids.insert(res.id.clone());
+            if let Ok(res) = result
+                && completed_tool_ids.insert(res.id.clone())
+            {
                 self.finalize_spawned_tool(res).await;
             }
         }
 
-        // Abort remaining spawned tasks
+        // Ask the rest to stop; what they return now is reported as cancelled
+        cancel.cancel();
+        let drained = tokio::time::timeout(TOOL_CANCEL_GRACE, async {
+            while join_set.join_next().await.is_some() {}
+        })
+        .await;
+        if drained.is_err() {
+            debug!("Aborting tools that didn't stop within {:?}", TOOL_CANCEL_GRACE);
+        }
         join_set.abort_all();
 
         // Add "Cancelled" results for all tools that didn't complete
         for tc in all_tool_calls {
             if !completed_tool_ids.contains(&tc.call_id) {
-                let cancel_msg = "Cancelled by user";
-                self.session.add_tool_result(&tc.call_id, cancel_msg, true);
-                self.emit(SessionOutput::tool_result(
-                    &tc.call_id,
-                    &tc.fn_name,
-                    false,
-                    cancel_msg.to_string(),
-                    "Cancelled".to_string(),
-                    None,
-                )).await;
+                self.record_cancelled_tool(&tc.call_id, &tc.fn_name).await;
             }
         }
+
+        // Results that finished after an abort may have landed out of order
+        self.session.repair_history();
+    }
+
+    /// Record a synthetic result for a tool call aborted before it finished,
+    /// so every tool call in the history keeps exactly one result
+    async fn record_cancelled_tool(&mut self, id: &str, name: &str) {
+        self.tool_usage.record(name, ToolDoneStatus::Cancelled, std::time::Duration::ZERO, TOOL_CANCELLED.len());
+        self.session.add_tool_result(id, TOOL_CANCELLED, true);
+        self.emit(SessionOutput::tool_cancelled(id, name, TOOL_CANCELLED)).await;
+        self.emit(SessionOutput::tool_result(
+            id,
+            name,
+            false,
+            TOOL_CANCELLED.to_string(),
+            "Cancelled".to_string(),
+            None,
+            Vec::new(),
+        )).await;
     }
 
-    /// Emit an output
+    /// Emit an output, blocking only if it is essential
     async fn emit(&self, output: SessionOutput) {
-        if let Err(e) = self
-            .output_tx
-            .send((self.session_id.clone(), output))
-            .await
-        {
-            error!("Failed to emit output: {}", e);
+        if output.is_ephemeral() {
+            self.emit_ephemeral(output);
+        } else {
+            self.emit_critical(output).await;
         }
     }
 
+    /// Emit an output the frontend must see, waiting for room in the channel
+    async fn emit_critical(&self, output: SessionOutput) {
+        self.output.emit_critical(output).await;
+    }
+
+    /// Emit an output the frontend may miss, without ever waiting
+    fn emit_ephemeral(&self, output: SessionOutput) {
+        self.output.emit_ephemeral(output);
+    }
+
+    /// The lock a call must take before it runs, if it writes a file other
+    /// sessions might be writing too
+    fn file_lock_request(&self, name: &str, arguments: &serde_json::Value) -> Option<FileLockRequest> {
+        let locks = self.workspace_locks.clone()?;
+        let path = self.tool_scheduler.written_file(name, arguments)?;
+        Some(FileLockRequest {
+            locks,
+            shown: self.path_display.display(&path.to_string_lossy()),
+            path,
+            session_id: self.session_id.clone(),
+            title: self.title.clone(),
+            output: self.output.clone(),
+        })
+    }
+
+    /// Locking and snapshotting for the files a call picks as it runs
+    fn turn_writes(&self) -> Arc<dyn FileWrites> {
+        Arc::new(TurnWrites {
+            workspace: self.workspace.clone(),
+            locks: self.workspace_locks.clone(),
+            changes: self.turn_changes.clone(),
+            path_display: self.path_display.clone(),
+            session_id: self.session_id.clone(),
+            title: self.title.clone(),
+            output: self.output.clone(),
+        })
+    }
+
     /// Emit tool execution start events (both ephemeral tool_start and persistent tool_call)
     async fn emit_tool_execution_start(&self, tool_call: &ToolCall) {
-        let formatted = format_tool_call(&tool_call.fn_name, &tool_call.fn_arguments);
+        let formatted = format_tool_call(&tool_call.fn_name, &tool_call.fn_arguments, &self.path_display);
+        // Diff now, while the file still holds its old content
+        let diff = tool_call_diff(&tool_call.fn_name, &tool_call.fn_arguments, &self.path_display);
 
-        self.emit(SessionOutput::tool_start(
+        self.emit_ephemeral(SessionOutput::tool_start(
             &tool_call.call_id,
             &tool_call.fn_name,
             tool_call.fn_arguments.clone(),
-        ))
-        .await;
+        ));
 
         self.emit(SessionOutput::tool_call(
             &tool_call.call_id,
             &tool_call.fn_name,
             tool_call.fn_arguments.clone(),
             formatted,
-        ))
+            diff,
+        )
+        .with_absolute_path(self.target_file(&tool_call.fn_arguments)))
         .await;
     }
 
+    /// The file a tool call works on, as an absolute path, for frontends to open
+    fn target_file(&self, arguments: &serde_json::Value) -> Option<String> {
+        let path = ["file_path", "notebook_path", "filePath"]
+            .iter()
+            .find_map(|key| arguments[*key].as_str())?;
+        Some(self.path_display.absolute(path).to_string_lossy().to_string())
+    }
+
+    /// The session's current state in its saved form
+    async fn to_saved_session(&self) -> SavedSession {
+        self.saved_session_with(self.session.resolved_messages()).await
+    }
+
+    /// The session's saved form, with these messages
+    async fn saved_session_with(&self, messages: Vec<ChatMessage>) -> SavedSession {
+        let (parent_id, fork_point) = self.parent.clone().unzip();
+        SavedSession {
+            id: self.session_id.clone(),
+            name: self.title.get().unwrap_or_else(|| format!("Session {}", self.session_id)),
+            title_source: self.title.source(),
+            format_version: SESSION_FORMAT_VERSION,
+            provider: Some(self.provider.provider_id().to_string()),
+            model: Some(self.provider.model().to_string()),
+            messages,
+            todos: self.todo_list.read().await.clone(),
+            created_at: self.created_at,
+            updated_at: chrono::Utc::now(),
+            parent_id,
+            fork_point,
+            recovered: false,
+            pinned: self.pinned.load(Ordering::Relaxed),
+            pinned_messages: self.pins.lock().keys(),
+            template: self.template.clone(),
+            tool_usage: self.tool_usage.clone(),
+        }
+    }
+
+    /// Name the session from its first turn, without holding up the next one
+    ///
+    /// The fast model sees only the first message, as it is in the history,
+    /// and the reply; neither the request nor its tokens count toward the
+    /// session. If it fails the session keeps its fallback title.
+    fn generate_title(&mut self) {
+        if !self.title.source().is_fallback() {
+            return;
+        }
+        let Some(reply) = self.session.final_assistant_text() else {
+            return;
+        };
+        let Some(message) = self.turns.first().and_then(|turn| self.session.messages.get(turn.start)) else {
+            return;
+        };
+        let Some(provider) = self.title_provider.take() else {
+            return;
+        };
+        let message = message_text_content(message);
+        let (title, output, session_id) = (self.title.clone(), self.output.clone(), self.session_id.clone());
+        tokio::spawn(async move {
+            let generated = match tokio::time::timeout(TITLE_TIMEOUT, generate_title(&provider, &message, &reply)).await {
+                Ok(Ok(generated)) => generated,
+                Ok(Err(e)) => {
+                    debug!("Title for session {} not generated: {}", session_id, e);
+                    None
+                }
+                Err(_) => {
+                    debug!("Title for session {} timed out", session_id);
+                    None
+                }
+            };
+            // The user may have named the session in the meantime
+            let Some(generated) = generated.filter(|generated| title.set(generated.clone(), TitleSource::Generated))
+            else {
+                return;
+            };
+            output.emit_critical(SessionOutput::title_changed(generated.clone())).await;
+            let id = session_id.clone();
+            let updated =
+                tokio::task::spawn_blocking(move || set_session_title(&id, &generated, TitleSource::Generated)).await;
+            if let Ok(Err(e)) = updated {
+                debug!("Saved files of session {} not updated: {}", session_id, e);
+            }
+        });
+    }
+
+    /// Record the history as of the end of this turn for the manager to fork from
+    async fn update_snapshot(&self) {
+        if let Some(snapshot) = &self.snapshot {
+            // Large tool results stay shared with the history until a fork needs them
+            let saved = self.saved_session_with(self.session.messages.clone()).await;
+            snapshot.update_interned(saved, self.session.arena().clone());
+            snapshot.set_memory_stats(self.session.memory_stats());
+            snapshot.set_tool_usage(self.tool_usage.clone());
+        }
+    }
+
+    /// Autosave the session if its history changed, once the interval has
+    /// passed (or at once with `force`)
+    ///
+    /// The history is copied here and written on a blocking thread, so the
+    /// turn isn't held up. Unless forced, a save is skipped while the
+    /// previous one is still being written.
+    async fn autosave(&mut self, force: bool) {
+        let Some(autosave) = self.autosave.as_mut() else {
+            return;
+        };
+        if !self.session.is_dirty() || self.session.messages.is_empty() {
+            return;
+        }
+        if !force && autosave.last.elapsed() < autosave.interval {
+            return;
+        }
+        match autosave.writer.take() {
+            Some(writer) if force => {
+                let _ = writer.await;
+            }
+            Some(writer) if !writer.is_finished() => {
+                autosave.writer = Some(writer);
+                return;
+            }
+            _ => {}
+        }
+
+        let saved = self.to_saved_session().await;
+        self.session.mark_saved();
+        let session_id = self.session_id.clone();
+        let writer = tokio::task::spawn_blocking(move || match write_autosave(&saved) {
+            Ok(path) => debug!("Autosaved session {} to {:?}", session_id, path),
+            Err(e) => warn!("Failed to autosave session {}: {}", session_id, e),
+        });
+        if let Some(autosave) = self.autosave.as_mut() {
+            autosave.last = std::time::Instant::now();
+            autosave.writer = Some(writer);
+        }
+    }
+
     /// Save session to disk
     async fn save_session(&self) -> Result<()> {
         // Don't save empty sessions
@@ -1052,31 +3390,192 @@ impl AgentLoop {
             return Ok(());
         }
 
-        // Get sessions directory
-        let sessions_dir = get_sessions_dir()?;
-        std::fs::create_dir_all(&sessions_dir)?;
-
-        let saved = SavedSession {
-            id: self.session_id.clone(),
-            name: format!("Session {}", self.session_id),
-            messages: self.session.messages.clone(),
-            created_at: self.created_at,
-            updated_at: chrono::Utc::now(),
-        };
-
-        // Write to file
-        let path = sessions_dir.join(format!("{}.json", self.session_id));
-        let json = serde_json::to_string_pretty(&saved)?;
-        std::fs::write(&path, json)?;
+        let saved = self.to_saved_session().await;
+        let path = write_session(&saved)?;
 
         info!("Saved session {} to {:?}", self.session_id, path);
         Ok(())
     }
 }
 
+/// A recipe's steps, run as if the model had asked for them
+impl StepExecutor for AgentLoop {
+    fn run_tool(&mut self, name: String, arguments: serde_json::Value) -> BoxFuture<'_, Option<StepOutcome>> {
+        Box::pin(async move {
+            let call = ToolCall {
+                call_id: format!("recipe-{}", uuid::Uuid::new_v4()),
+                fn_name: name,
+                fn_arguments: arguments,
+                thought_signatures: None,
+            };
+            self.session.add_assistant_message("", vec![call.clone()]);
+            match self.execute_tool_calls(std::slice::from_ref(&call), vec![RepeatVerdict::Run]).await {
+                Ok(true) => Some(match self.session.tool_result(&call.call_id) {
+                    Some((output, failed)) => StepOutcome::new(!failed, output, if failed { 1 } else { 0 }),
+                    None => StepOutcome::failed("The tool returned no result"),
+                }),
+                Ok(false) => None,
+                Err(e) => Some(StepOutcome::failed(e.to_string())),
+            }
+        })
+    }
+
+    fn run_skill(&mut self, name: String, args: String) -> BoxFuture<'_, Option<StepOutcome>> {
+        Box::pin(async move {
+            let arguments = serde_json::json!({ "skill": name, "args": args });
+            let outcome = self.run_tool(crate::recipes::SKILL_TOOL.to_string(), arguments).await?;
+            // A prompt skill leaves its instructions for the model to follow
+            let injected = self.session.get_messages().last().is_some_and(|m| m.role == ChatRole::User);
+            if !outcome.success || !injected {
+                return Some(outcome);
+            }
+            let outcome = self.run_prompt_turn().await;
+            self.skill_restriction = None;
+            outcome
+        })
+    }
+
+    fn run_prompt(&mut self, prompt: String) -> BoxFuture<'_, Option<StepOutcome>> {
+        Box::pin(async move {
+            let msg_id = uuid::Uuid::new_v4().to_string();
+            self.emit(SessionOutput::user_message(&msg_id, &prompt)).await;
+            self.session.add_user_message(&prompt);
+            self.run_prompt_turn().await
+        })
+    }
+
+    fn confirm_continue(&mut self, step_id: String, error: String) -> BoxFuture<'_, bool> {
+        Box::pin(async move {
+            let request_id = format!("recipe-{}", uuid::Uuid::new_v4());
+            let detail = error.lines().next().unwrap_or_default();
+            self.emit(SessionOutput::Question {
+                request_id: request_id.clone(),
+                questions: vec![QuestionInfo {
+                    question: format!("Recipe step {} failed: {}\nContinue with the next step?", step_id, detail),
+                    header: Some("Recipe".to_string()),
+                    options: vec![
+                        QuestionOption {
+                            label: RECIPE_CONTINUE.to_string(),
+                            description: None,
+                        },
+                        QuestionOption {
+                            label: RECIPE_STOP.to_string(),
+                            description: Some("Skip the remaining steps".to_string()),
+                        },
+                    ],
+                    multi_select: false,
+                }],
+                subagent_id: None,
+            })
+            .await;
+
+            loop {
+                match self.control_rx.recv().await {
+                    Some(SessionInput::AnswerQuestion { request_id: id, answers }) if id == request_id => {
+                        return answers.values().any(|a| a == RECIPE_CONTINUE);
+                    }
+                    Some(SessionInput::Cancel) | None => return false,
+                    Some(other) => debug!("Ignoring {:?} while waiting for the recipe question", other),
+                }
+            }
+        })
+    }
+
+    fn report(&mut self, output: SessionOutput) -> BoxFuture<'_, ()> {
+        Box::pin(async move { self.emit(output).await })
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
     use crate::approval::ToolApprovalConfig;
+    use crate::skills::{BoxFuture as SkillFuture, Skill, SkillContext, SkillInfo, SkillResult};
+    use crate::tools::skill::SkillTool;
+
+    /// Inline skill that may only read and search
+    struct ReadOnlySkill;
+
+    impl Skill for ReadOnlySkill {
+        fn info(&self) -> SkillInfo {
+            SkillInfo {
+                name: "inspect".to_string(),
+                display_name: "Inspect".to_string(),
+                description: "Read-only inspection".to_string(),
+                usage: "/inspect".to_string(),
+                user_invocable: true,
+            }
+        }
+
+        fn execute(&self, _ctx: SkillContext) -> SkillFuture<'_, SkillResult> {
+            Box::pin(async { SkillResult::success("Inspect the code") })
+        }
+
+        fn prompt_template(&self) -> &str {
+            "Inspect the code without modifying anything."
+        }
+
+        fn allowed_tools(&self) -> Option<Vec<&str>> {
+            Some(vec!["Read", "Grep"])
+        }
+    }
+
+    fn tool_call(name: &str, arguments: serde_json::Value) -> ToolCall {
+        ToolCall {
+            call_id: format!("call-{}", name),
+            fn_name: name.to_string(),
+            fn_arguments: arguments,
+            thought_signatures: None,
+        }
+    }
+
+    async fn invoke_read_only_skill() -> SpawnedToolResult {
+        let mut registry = SkillRegistry::new();
+        registry.register(Arc::new(ReadOnlySkill));
+        let tool = Arc::new(SkillTool::new(Arc::new(registry), std::path::PathBuf::from(".")));
+        let (approval_tx, _approval_rx) = approval_channel();
+        let ctx = ToolExecutionContext::new(approval_tx, "call-skill".to_string(), "Skill".to_string());
+        execute_tool_task(tool, "call-skill".to_string(), "Skill".to_string(), serde_json::json!({"skill": "inspect"}), ctx, None).await
+    }
+
+    #[tokio::test]
+    async fn test_injected_skill_carries_allowed_tools() {
+        let res = invoke_read_only_skill().await;
+        assert!(res.success, "{}", res.output);
+        assert!(res.inject_info.is_some());
+        assert_eq!(res.allowed_tools, Some(vec!["Read".to_string(), "Grep".to_string()]));
+    }
+
+    #[tokio::test]
+    async fn test_skill_restriction_refuses_bash() {
+        let res = invoke_read_only_skill().await;
+        let restriction = SkillToolRestriction::new("inspect", &res.allowed_tools.unwrap());
+
+        let bash = tool_call("Bash", serde_json::json!({"command": "rm -rf target"}));
+        let error = restriction.rejection(&bash).expect("Bash should be refused");
+        assert!(error.contains("'Bash' is not allowed"));
+        assert!(error.contains("/inspect"));
+        assert!(error.contains("Read, Grep"));
+
+        assert!(restriction.rejection(&tool_call("Read", serde_json::json!({"file_path": "src/lib.rs"}))).is_none());
+        assert!(restriction.rejection(&tool_call("Grep", serde_json::json!({"pattern": "fn"}))).is_none());
+    }
+
+    #[test]
+    fn test_skill_restriction_filters_definitions() {
+        let restriction = SkillToolRestriction::new("inspect", &["Read".to_string(), "Grep".to_string()]);
+        let definitions = ["Read", "Write", "Bash", "Grep", "Glob"]
+            .into_iter()
+            .map(ToolDefinition::new)
+            .collect();
+
+        let names: Vec<String> = restriction
+            .filter_definitions(definitions)
+            .into_iter()
+            .map(|td| td.name.to_string())
+            .collect();
+        assert_eq!(names, vec!["Read", "Grep"]);
+    }
 
     #[test]
     fn test_tool_categorization() {
@@ -14,11 +14,45 @@
 //! Serialization is achieved naturally: the mpsc channel queues requests,
 //! the agent loop handles one at a time, and each tool waits on its own
 //! oneshot receiver for the response.
+//!
+//! A call a delegation rule matches (see `ToolApprovalConfig::with_delegation`)
+//! carries a `Delegation`: the agent loop asks for its approval before the
+//! tool runs, and only a reviewer can give it.
+//!
+//! Tools that run for a long time (see `Tool::supports_progress`) also get a
+//! progress sender and report heartbeats through it; the agent loop forwards
+//! them as `SessionOutput::ToolProgress`.
+//!
+//! Cancelling a call (`SessionInput::CancelTool`, or `Cancel` for the whole
+//! turn) cancels the token in its context first; tools that await for long
+//! select on it and stop their work before the agent loop aborts the task.
+//!
+//! The agent loop locks a file tool's file and snapshots it for the turn
+//! before the call runs. A tool that picks the files it writes as it runs
+//! (BatchEdit) does the same through `ToolExecutionContext::prepare_writes`
+//! once it knows them.
 
 use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use tokio::sync::{mpsc, oneshot};
+use tokio_util::sync::CancellationToken;
 
 use super::types::QuestionInfo;
+use super::workspace_locks::FileLock;
+use crate::approval::SecurityFinding;
+use crate::error::ToolError;
+use crate::tools::filesystem::GeneratedFile;
+use crate::tools::BoxFuture;
+
+/// Readies the files a call is about to write (see
+/// `ToolExecutionContext::prepare_writes`)
+pub(crate) trait FileWrites: Send + Sync {
+    /// Lock `paths` (absolute) against other sessions and snapshot them
+    /// for the turn; the locks are held until the guards are dropped
+    fn prepare<'a>(&'a self, paths: &'a [PathBuf]) -> BoxFuture<'a, Result<Vec<FileLock>, ToolError>>;
+}
 
 /// Request sent through the approval channel
 #[derive(Debug)]
@@ -29,6 +63,13 @@ pub enum ApprovalRequest {
         tool_name: String,
         arguments: serde_json::Value,
         description: Option<String>,
+        /// Set when only a reviewer may approve the call
+        delegation: Option<Delegation>,
+        /// Signs of secret exfiltration: a person must approve the call
+        findings: Vec<SecurityFinding>,
+        /// Generated files the call changes with `allow_generated`: a person
+        /// must approve the call
+        generated: Vec<GeneratedFile>,
         response_tx: oneshot::Sender<ApprovalResponse>,
     },
     /// Request answer to a question
@@ -39,6 +80,15 @@ pub enum ApprovalRequest {
     },
 }
 
+/// Why and to whom a call's approval is delegated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Delegation {
+    /// Where reviewers watch for delegated approvals
+    pub channel: String,
+    /// The delegation rule that matched, for the reviewer
+    pub reason: String,
+}
+
 /// Response to a tool approval request
 #[derive(Debug, Clone)]
 pub enum ApprovalResponse {
@@ -63,10 +113,34 @@ pub fn approval_channel() -> (ApprovalSender, ApprovalReceiver) {
     mpsc::unbounded_channel()
 }
 
+/// Heartbeat from a long-running tool call
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolProgress {
+    pub tool_call_id: String,
+    /// Time since the tool started running
+    pub elapsed_ms: u64,
+    /// Bytes of output captured so far
+    pub output_bytes: u64,
+    /// The most recent non-empty output line
+    pub last_line: Option<String>,
+}
+
+/// Sender half of the progress channel
+pub type ProgressSender = mpsc::UnboundedSender<ToolProgress>;
+
+/// Receiver half of the progress channel
+pub type ProgressReceiver = mpsc::UnboundedReceiver<ToolProgress>;
+
+/// Create a new progress channel
+pub fn progress_channel() -> (ProgressSender, ProgressReceiver) {
+    mpsc::unbounded_channel()
+}
+
 /// Context passed to tools during execution
 ///
 /// This provides tools with the ability to request approval and access
-/// to their execution context (like the tool call ID).
+/// to their execution context: the call, the session and turn it belongs
+/// to, the workspace, and a token cancelled when the user stops the call.
 #[derive(Clone)]
 pub struct ToolExecutionContext {
     /// Channel to request approval
@@ -75,6 +149,24 @@ pub struct ToolExecutionContext {
     pub tool_call_id: String,
     /// Tool name
     pub tool_name: String,
+    /// Session the call belongs to (empty outside a session)
+    session_id: String,
+    /// Turn the call belongs to (empty outside a session)
+    turn_id: String,
+    /// Workspace root of the session
+    workspace: Option<PathBuf>,
+    /// Cancelled by `SessionInput::Cancel` or `CancelTool`
+    cancel: CancellationToken,
+    /// The call was already approved; further requests succeed at once
+    approved: bool,
+    /// Where heartbeats go (None = nobody is listening)
+    progress_tx: Option<ProgressSender>,
+    /// Only a reviewer may approve the call
+    delegation: Option<Delegation>,
+    /// Signs of secret exfiltration found before the call ran
+    findings: Vec<SecurityFinding>,
+    /// Locks and snapshots the files the call writes (None = outside a session)
+    file_writes: Option<Arc<dyn FileWrites>>,
 }
 
 impl ToolExecutionContext {
@@ -88,6 +180,152 @@ impl ToolExecutionContext {
             approval_tx,
             tool_call_id,
             tool_name,
+            session_id: String::new(),
+            turn_id: String::new(),
+            workspace: None,
+            cancel: CancellationToken::new(),
+            approved: false,
+            progress_tx: None,
+            delegation: None,
+            findings: Vec::new(),
+            file_writes: None,
+        }
+    }
+
+    /// Tie the call to a session and one of its turns
+    pub fn with_session(mut self, session_id: impl Into<String>, turn_id: impl Into<String>) -> Self {
+        self.session_id = session_id.into();
+        self.turn_id = turn_id.into();
+        self
+    }
+
+    /// Set the workspace root the call runs in
+    pub fn with_workspace(mut self, workspace: impl Into<PathBuf>) -> Self {
+        self.workspace = Some(workspace.into());
+        self
+    }
+
+    /// Stop the call when `cancel` is cancelled
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Session the call belongs to (empty outside a session)
+    pub fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
+    /// Turn the call belongs to (empty outside a session)
+    pub fn turn_id(&self) -> &str {
+        &self.turn_id
+    }
+
+    /// Workspace root of the session, if the call runs in one
+    pub fn workspace(&self) -> Option<&Path> {
+        self.workspace.as_deref()
+    }
+
+    /// The call's cancellation token
+    pub fn cancellation(&self) -> &CancellationToken {
+        &self.cancel
+    }
+
+    /// Whether the user has stopped the call
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    /// Resolves once the user stops the call
+    pub async fn cancelled(&self) {
+        self.cancel.cancelled().await
+    }
+
+    /// Run `future` unless the call is stopped first
+    ///
+    /// Dropping the future is what stops the work, so wrap the awaits that
+    /// take long (a request, a child process, a subagent) rather than the
+    /// whole tool.
+    pub async fn cancellable<T>(&self, future: impl Future<Output = T>) -> Result<T, ToolError> {
+        tokio::select! {
+            biased;
+            _ = self.cancel.cancelled() => Err(ToolError::Cancelled),
+            output = future => Ok(output),
+        }
+    }
+
+    /// Mark the call as approved so the tool doesn't ask again
+    pub fn approved(mut self) -> Self {
+        self.approved = true;
+        self
+    }
+
+    /// Leave the call's approval to a reviewer
+    pub fn delegated(mut self, delegation: Delegation) -> Self {
+        self.delegation = Some(delegation);
+        self
+    }
+
+    /// The call's delegation, if only a reviewer may approve it
+    pub fn delegation(&self) -> Option<&Delegation> {
+        self.delegation.as_ref()
+    }
+
+    /// Make a person approve the call, warned about these findings, even if
+    /// it would otherwise run without asking
+    pub fn flagged(mut self, findings: Vec<SecurityFinding>) -> Self {
+        self.findings = findings;
+        self
+    }
+
+    /// Signs of secret exfiltration in the call (empty for most calls)
+    pub fn findings(&self) -> &[SecurityFinding] {
+        &self.findings
+    }
+
+    /// Lock and snapshot the files the call writes through `file_writes`
+    pub(crate) fn with_file_writes(mut self, file_writes: Arc<dyn FileWrites>) -> Self {
+        self.file_writes = Some(file_writes);
+        self
+    }
+
+    /// Lock the files the call is about to write against other sessions and
+    /// remember what they hold for the turn's change set
+    ///
+    /// For tools that only know which files they write once they run; the
+    /// agent loop does this itself for a file tool's path argument. Keep the
+    /// locks until the files are written. Outside a session there is
+    /// nothing to lock and no locks are returned.
+    pub async fn prepare_writes(&self, paths: &[PathBuf]) -> Result<Vec<FileLock>, ToolError> {
+        match &self.file_writes {
+            Some(file_writes) => file_writes.prepare(paths).await,
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Send the tool's heartbeats to `progress_tx`
+    pub fn with_progress(mut self, progress_tx: ProgressSender) -> Self {
+        self.progress_tx = Some(progress_tx);
+        self
+    }
+
+    /// Whether anyone is listening for heartbeats
+    pub fn wants_progress(&self) -> bool {
+        self.progress_tx.is_some()
+    }
+
+    /// Report how a long-running call is getting on
+    ///
+    /// Does nothing without a progress sender. Call it as often as is
+    /// convenient; the agent loop forwards at most one report a second.
+    pub fn report_progress(&self, elapsed: std::time::Duration, output_bytes: u64, last_line: Option<String>) {
+        if let Some(tx) = &self.progress_tx {
+            let _ = tx.send(ToolProgress {
+                tool_call_id: self.tool_call_id.clone(),
+                elapsed_ms: elapsed.as_millis() as u64,
+                output_bytes,
+                last_line,
+            });
         }
     }
 
@@ -98,11 +336,7 @@ impl ToolExecutionContext {
     /// outside of an agent loop (e.g., CLI commands).
     pub fn standalone(tool_call_id: impl Into<String>, tool_name: impl Into<String>) -> Self {
         let (tx, _rx) = approval_channel();
-        Self {
-            approval_tx: tx,
-            tool_call_id: tool_call_id.into(),
-            tool_name: tool_name.into(),
-        }
+        Self::new(tx, tool_call_id.into(), tool_name.into())
     }
 
     /// Create a test context that auto-approves all requests
@@ -128,11 +362,7 @@ impl ToolExecutionContext {
             }
         });
 
-        Self {
-            approval_tx: tx,
-            tool_call_id: tool_call_id.into(),
-            tool_name: tool_name.into(),
-        }
+        Self::new(tx, tool_call_id.into(), tool_name.into())
     }
 
     /// Request approval for a tool execution
@@ -145,6 +375,32 @@ impl ToolExecutionContext {
         &self,
         arguments: serde_json::Value,
         description: Option<String>,
+    ) -> Result<(), String> {
+        if self.approved {
+            return Ok(());
+        }
+        self.ask(arguments, description, Vec::new()).await
+    }
+
+    /// Ask a person to let the call change generated files
+    ///
+    /// Unlike `request_approval` this asks even if the call was approved
+    /// already: neither the rules nor the turn's approval cover overriding
+    /// the generated-file guard, and frontends don't auto-approve it.
+    pub async fn request_generated_approval(
+        &self,
+        arguments: serde_json::Value,
+        description: Option<String>,
+        generated: Vec<GeneratedFile>,
+    ) -> Result<(), String> {
+        self.ask(arguments, description, generated).await
+    }
+
+    async fn ask(
+        &self,
+        arguments: serde_json::Value,
+        description: Option<String>,
+        generated: Vec<GeneratedFile>,
     ) -> Result<(), String> {
         let (response_tx, response_rx) = oneshot::channel();
 
@@ -153,6 +409,9 @@ impl ToolExecutionContext {
             tool_name: self.tool_name.clone(),
             arguments,
             description,
+            delegation: self.delegation.clone(),
+            findings: self.findings.clone(),
+            generated,
             response_tx,
         };
 
@@ -217,6 +476,9 @@ mod tests {
             tool_name: "Bash".to_string(),
             arguments: serde_json::json!({"command": "ls"}),
             description: None,
+            delegation: None,
+            findings: Vec::new(),
+            generated: Vec::new(),
             response_tx,
         }).unwrap();
 
@@ -252,6 +514,36 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_approved_context_does_not_ask_again() {
+        let (tx, mut rx) = approval_channel();
+        let ctx = ToolExecutionContext::new(tx, "call-1".to_string(), "Bash".to_string()).approved();
+
+        assert!(ctx.request_approval(serde_json::json!({}), None).await.is_ok());
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_generated_approval_asks_even_when_approved() {
+        let (tx, mut rx) = approval_channel();
+        let ctx = ToolExecutionContext::new(tx, "call-1".to_string(), "Edit".to_string()).approved();
+        let lock = GeneratedFile {
+            path: "Cargo.lock".to_string(),
+            reason: "Cargo's lockfile".to_string(),
+            action: "run cargo update".to_string(),
+        };
+
+        tokio::spawn(async move {
+            if let Some(ApprovalRequest::ToolApproval { generated, response_tx, .. }) = rx.recv().await {
+                assert_eq!(generated.len(), 1);
+                response_tx.send(ApprovalResponse::Rejected { reason: None }).unwrap();
+            }
+        });
+
+        let result = ctx.request_generated_approval(serde_json::json!({}), None, vec![lock]).await;
+        assert_eq!(result.unwrap_err(), "Rejected by user");
+    }
+
     #[tokio::test]
     async fn test_context_request_rejected() {
         let (tx, mut rx) = approval_channel();
@@ -0,0 +1,384 @@
+//! Tool result arena: large tool results held once per session
+//!
+//! Long sessions repeat big tool results (the same file read again, the same
+//! search run twice), and every copy used to stay resident for the life of
+//! the session. `ChatSession` keeps each tool result of at least
+//! `INTERN_MIN_BYTES` here instead, once per distinct content (keyed by its
+//! hash), and the history's tool message holds a short reference in its
+//! place. The session's transcript does the same for the tool outputs it
+//! keeps for the frontend (see `session::transcript`).
+//!
+//! Results of at least `SPILL_MIN_BYTES` are spilled to a file in the temp
+//! directory, like images from tool output (see `tools::artifact`), and read
+//! back when resolved. Entries are `Arc`s, so clones of the history (the
+//! fork snapshot, a cloned `ChatSession`) share them too; a spilled file is
+//! removed with the last entry holding it.
+//!
+//! `ChatSession::resolved_messages` puts the content back for the model, for
+//! compaction and for saving: saved sessions never contain references.
+//! Entries no message refers to any more are dropped whenever the history
+//! shrinks (truncation, clearing, compaction).
+
+use std::borrow::Cow;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::provider::{ChatMessage, ContentPart, MessageContent, ToolResponse};
+
+/// Smallest tool result worth interning, in bytes
+pub const INTERN_MIN_BYTES: usize = 4 * 1024;
+
+/// Smallest tool result spilled to disk rather than held in memory, in bytes
+pub const SPILL_MIN_BYTES: usize = 16 * 1024;
+
+/// Directory under the temp directory spilled results are written to
+const SPILL_DIR: &str = "cowork-results";
+
+/// Start of a reference in a tool message (never produced by a tool: it
+/// begins with a NUL)
+const REFERENCE_PREFIX: &str = "\u{0}cowork-arena:";
+
+/// Memory held by a session's history (and, from
+/// `SessionManager::memory_stats`, the outputs its transcript keeps)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryStats {
+    /// Bytes of message content the history holds, references at their own size
+    pub history_bytes: usize,
+    /// Bytes of the interned tool results held in memory
+    pub interned_bytes: usize,
+    /// Distinct tool results interned, spilled ones included
+    pub interned_count: usize,
+    /// Interned tool results spilled to disk
+    pub spilled_count: usize,
+    /// Bytes of the spilled tool results
+    pub spilled_bytes: usize,
+    /// Tool messages holding a reference
+    pub references: usize,
+    /// Bytes the history would hold without interning
+    pub resolved_bytes: usize,
+}
+
+impl MemoryStats {
+    /// Bytes held in memory in all: the history and the interned results
+    /// (spilled ones aren't)
+    pub fn resident_bytes(&self) -> usize {
+        self.history_bytes + self.interned_bytes
+    }
+}
+
+impl std::ops::AddAssign for MemoryStats {
+    fn add_assign(&mut self, other: Self) {
+        self.history_bytes += other.history_bytes;
+        self.interned_bytes += other.interned_bytes;
+        self.interned_count += other.interned_count;
+        self.spilled_count += other.spilled_count;
+        self.spilled_bytes += other.spilled_bytes;
+        self.references += other.references;
+        self.resolved_bytes += other.resolved_bytes;
+    }
+}
+
+/// Large tool results of one session, by content hash
+#[derive(Debug, Clone, Default)]
+pub struct ResultArena {
+    entries: HashMap<u64, Entry>,
+}
+
+/// An interned tool result
+#[derive(Debug, Clone)]
+enum Entry {
+    Resident(Arc<str>),
+    Spilled(Arc<SpillFile>),
+}
+
+/// A tool result written to disk, removed when dropped
+#[derive(Debug)]
+struct SpillFile {
+    path: PathBuf,
+    len: usize,
+}
+
+impl Entry {
+    /// Hold `content` in memory, or on disk if it is large enough and can
+    /// be written
+    fn new(content: &str) -> Self {
+        if content.len() >= SPILL_MIN_BYTES {
+            match SpillFile::write(content) {
+                Ok(file) => return Self::Spilled(Arc::new(file)),
+                Err(e) => warn!("Tool result kept in memory, it could not be spilled to disk: {}", e),
+            }
+        }
+        Self::Resident(Arc::from(content))
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Self::Resident(content) => content.len(),
+            Self::Spilled(file) => file.len,
+        }
+    }
+
+    fn content(&self) -> Cow<'_, str> {
+        match self {
+            Self::Resident(content) => Cow::Borrowed(content),
+            Self::Spilled(file) => match std::fs::read_to_string(&file.path) {
+                Ok(content) => Cow::Owned(content),
+                Err(e) => {
+                    warn!("Spilled tool result {} could not be read: {}", file.path.display(), e);
+                    Cow::Owned(format!("[Tool result no longer available: {}]", e))
+                }
+            },
+        }
+    }
+}
+
+impl SpillFile {
+    fn write(content: &str) -> std::io::Result<Self> {
+        let dir = std::env::temp_dir().join(SPILL_DIR);
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join(format!("{}.txt", uuid::Uuid::new_v4()));
+        std::fs::write(&path, content)?;
+        Ok(Self { path, len: content.len() })
+    }
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            debug!("Spilled tool result {} not removed: {}", self.path.display(), e);
+        }
+    }
+}
+
+impl ResultArena {
+    /// The reference to keep in place of `content`, interning it
+    ///
+    /// `None` when the content is too small to be worth it, or when another
+    /// content already has its hash (it is then kept inline).
+    pub fn intern(&mut self, content: &str) -> Option<String> {
+        if content.len() < INTERN_MIN_BYTES {
+            return None;
+        }
+        let key = content_hash(content);
+        let entry = self.entries.entry(key).or_insert_with(|| Entry::new(content));
+        (entry.len() == content.len() && entry.content() == content).then(|| format!("{}{:016x}", REFERENCE_PREFIX, key))
+    }
+
+    /// The content `text` stands for: the interned result for a reference,
+    /// `text` itself otherwise
+    pub fn resolve<'a>(&'a self, text: &'a str) -> Cow<'a, str> {
+        parse_reference(text)
+            .and_then(|key| self.entries.get(&key))
+            .map_or(Cow::Borrowed(text), Entry::content)
+    }
+
+    /// Put the interned content back into `messages`
+    pub fn resolve_messages(&self, messages: &mut [ChatMessage]) {
+        for message in messages.iter_mut() {
+            if !message.content.tool_responses().iter().any(|r| is_reference(&r.content)) {
+                continue;
+            }
+            let parts = std::mem::take(&mut message.content)
+                .into_parts()
+                .into_iter()
+                .map(|part| match part {
+                    ContentPart::ToolResponse(response) if is_reference(&response.content) => {
+                        let content = self.resolve(&response.content).into_owned();
+                        ContentPart::ToolResponse(ToolResponse::new(response.call_id, content))
+                    }
+                    part => part,
+                })
+                .collect::<Vec<_>>();
+            message.content = MessageContent::from_parts(parts);
+        }
+    }
+
+    /// Drop the entries none of `messages` refers to
+    pub fn retain_referenced(&mut self, messages: &[ChatMessage]) {
+        self.retain(messages.iter().flat_map(|m| m.content.tool_responses()).map(|r| r.content.as_str()));
+    }
+
+    /// Drop the entries none of `texts` is a reference to
+    pub fn retain<'a>(&mut self, texts: impl IntoIterator<Item = &'a str>) {
+        if self.entries.is_empty() {
+            return;
+        }
+        let referenced: HashSet<u64> = texts.into_iter().filter_map(parse_reference).collect();
+        self.entries.retain(|key, _| referenced.contains(key));
+    }
+
+    /// Drop every entry
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Number of interned results
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Bytes of the interned results, spilled ones included
+    pub fn bytes(&self) -> usize {
+        self.entries.values().map(Entry::len).sum()
+    }
+
+    /// Number of interned results spilled to disk
+    pub fn spilled(&self) -> usize {
+        self.entries.values().filter(|entry| matches!(entry, Entry::Spilled(_))).count()
+    }
+
+    /// What `messages`, whose references point into this arena, hold
+    pub fn memory_stats(&self, messages: &[ChatMessage]) -> MemoryStats {
+        let mut stats = self.entry_stats();
+        for message in messages {
+            let size = message.content.size();
+            stats.history_bytes += size;
+            stats.resolved_bytes += size;
+            for response in message.content.tool_responses() {
+                self.count_reference(&mut stats, &response.content);
+            }
+        }
+        stats
+    }
+
+    /// What `texts`, some of them references into this arena, hold
+    pub fn text_stats<'a>(&self, texts: impl IntoIterator<Item = &'a str>) -> MemoryStats {
+        let mut stats = self.entry_stats();
+        for text in texts {
+            stats.history_bytes += text.len();
+            stats.resolved_bytes += text.len();
+            self.count_reference(&mut stats, text);
+        }
+        stats
+    }
+
+    fn entry_stats(&self) -> MemoryStats {
+        let mut stats = MemoryStats {
+            interned_count: self.len(),
+            ..MemoryStats::default()
+        };
+        for entry in self.entries.values() {
+            match entry {
+                Entry::Resident(content) => stats.interned_bytes += content.len(),
+                Entry::Spilled(file) => {
+                    stats.spilled_count += 1;
+                    stats.spilled_bytes += file.len;
+                }
+            }
+        }
+        stats
+    }
+
+    /// Count `text` if it is a reference, at the size it stands for
+    fn count_reference(&self, stats: &mut MemoryStats, text: &str) {
+        if let Some(entry) = parse_reference(text).and_then(|key| self.entries.get(&key)) {
+            stats.references += 1;
+            stats.resolved_bytes += entry.len() - text.len();
+        }
+    }
+}
+
+fn content_hash(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Whether `text` is a reference into an arena
+pub(crate) fn is_reference(text: &str) -> bool {
+    parse_reference(text).is_some()
+}
+
+fn parse_reference(text: &str) -> Option<u64> {
+    let hex = text.strip_prefix(REFERENCE_PREFIX)?;
+    if hex.len() != 16 {
+        return None;
+    }
+    u64::from_str_radix(hex, 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::tool_result_message;
+
+    #[test]
+    fn test_small_results_stay_inline() {
+        let mut arena = ResultArena::default();
+        assert_eq!(arena.intern("ok"), None);
+        assert!(arena.is_empty());
+    }
+
+    #[test]
+    fn test_identical_results_are_held_once() {
+        let mut arena = ResultArena::default();
+        let content = "x".repeat(INTERN_MIN_BYTES * 2);
+        let first = arena.intern(&content).unwrap();
+        let second = arena.intern(&content).unwrap();
+        assert_eq!(first, second);
+        assert_eq!((arena.len(), arena.bytes(), arena.spilled()), (1, content.len(), 0));
+        assert_eq!(arena.resolve(&first), content);
+        assert_eq!(arena.resolve("plain text"), "plain text");
+    }
+
+    #[test]
+    fn test_messages_resolved_and_unreferenced_entries_dropped() {
+        let mut arena = ResultArena::default();
+        let big = "line\n".repeat(INTERN_MIN_BYTES / 2);
+        let other = "other\n".repeat(INTERN_MIN_BYTES / 2);
+        let mut messages = vec![
+            tool_result_message("call_1", arena.intern(&big).unwrap()),
+            tool_result_message("call_2", "small"),
+        ];
+        arena.intern(&other).unwrap();
+
+        let stats = arena.memory_stats(&messages);
+        assert_eq!((stats.interned_count, stats.references), (2, 1));
+        assert!(stats.history_bytes < 100);
+        assert_eq!(stats.resolved_bytes, "call_1".len() + big.len() + "call_2".len() + "small".len());
+
+        arena.retain_referenced(&messages);
+        assert_eq!(arena.len(), 1);
+
+        arena.resolve_messages(&mut messages);
+        assert_eq!(messages[0].content.tool_responses()[0].content, big);
+        assert_eq!(messages[1].content.tool_responses()[0].content, "small");
+    }
+
+    #[test]
+    fn test_large_results_spilled_and_removed_with_the_last_entry() {
+        let mut arena = ResultArena::default();
+        let content = "spilled\n".repeat(SPILL_MIN_BYTES);
+        let reference = arena.intern(&content).unwrap();
+        assert_eq!(arena.intern(&content).unwrap(), reference);
+
+        let Some(Entry::Spilled(file)) = arena.entries.values().next() else {
+            panic!("expected a spilled entry");
+        };
+        let path = file.path.clone();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), content);
+        assert_eq!(arena.resolve(&reference), content);
+
+        let stats = arena.text_stats([reference.as_str(), "small"]);
+        assert_eq!((stats.spilled_count, stats.spilled_bytes, stats.interned_bytes), (1, content.len(), 0));
+        assert_eq!(stats.resolved_bytes, content.len() + "small".len());
+
+        // A clone shares the file; it goes with the last entry holding it
+        let clone = arena.clone();
+        arena.retain([]);
+        assert!(path.exists());
+        assert_eq!(clone.resolve(&reference), content);
+        drop(clone);
+        assert!(!path.exists());
+    }
+}
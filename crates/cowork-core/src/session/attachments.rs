@@ -0,0 +1,262 @@
+//! Attachments sent with user messages
+//!
+//! Frontends send an `Attachment` (base64 data or a file path); it is checked
+//! and turned into the `ImageAttachment` carried by
+//! `SessionInput::UserMessageWithImages`. The checks are the ones providers
+//! apply, so a bad drop fails here with a clear message instead of as an
+//! API error mid-turn:
+//!
+//! - the type must be PNG, JPEG, GIF or WebP, and the bytes must match it
+//! - the image must be at most `MAX_IMAGE_BYTES`
+
+use std::path::PathBuf;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+
+use super::types::ImageAttachment;
+
+/// Largest image accepted, in bytes (Anthropic's per-image limit)
+pub const MAX_IMAGE_BYTES: usize = 5 * 1024 * 1024;
+
+/// Image types every vision provider accepts
+pub const IMAGE_MIME_TYPES: &[&str] = &["image/png", "image/jpeg", "image/gif", "image/webp"];
+
+/// What an attachment is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttachmentKind {
+    Image,
+}
+
+/// Where an attachment's bytes come from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttachmentSource {
+    /// Base64-encoded content (e.g. a pasted screenshot)
+    Base64(String),
+    /// A file to read (e.g. a dropped file)
+    Path(PathBuf),
+}
+
+/// A file attached to a user message by a frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Attachment {
+    pub kind: AttachmentKind,
+    pub source: AttachmentSource,
+    /// MIME type; inferred from the file extension for paths if omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+}
+
+impl Attachment {
+    /// An image given as base64 data
+    pub fn image_base64(data: impl Into<String>, mime_type: impl Into<String>) -> Self {
+        Self {
+            kind: AttachmentKind::Image,
+            source: AttachmentSource::Base64(data.into()),
+            mime_type: Some(mime_type.into()),
+        }
+    }
+
+    /// An image read from a file
+    pub fn image_file(path: impl Into<PathBuf>) -> Self {
+        Self {
+            kind: AttachmentKind::Image,
+            source: AttachmentSource::Path(path.into()),
+            mime_type: None,
+        }
+    }
+
+    /// Load and check the attachment, returning it ready to send
+    pub fn into_image(self) -> Result<ImageAttachment, String> {
+        match self.source {
+            AttachmentSource::Base64(data) => {
+                let mime_type = self.mime_type.ok_or("Attachment is missing its mime_type")?;
+                let bytes = STANDARD
+                    .decode(data.trim())
+                    .map_err(|e| format!("Attachment is not valid base64: {}", e))?;
+                validate_image(&bytes, &mime_type)?;
+                Ok(ImageAttachment::new(data.trim(), mime_type))
+            }
+            AttachmentSource::Path(path) => {
+                let mut image = ImageAttachment::from_file(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+                if let Some(mime_type) = self.mime_type {
+                    let bytes = STANDARD.decode(&image.data).map_err(|e| e.to_string())?;
+                    validate_image(&bytes, &mime_type).map_err(|e| format!("{}: {}", path.display(), e))?;
+                    image.media_type = mime_type;
+                }
+                Ok(image)
+            }
+        }
+    }
+}
+
+/// The image MIME type for a file extension (case-insensitive)
+pub fn image_mime_type(extension: &str) -> Option<&'static str> {
+    match extension.to_ascii_lowercase().as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        _ => None,
+    }
+}
+
+/// Check that `bytes` are an accepted image of type `mime_type`
+pub fn validate_image(bytes: &[u8], mime_type: &str) -> Result<(), String> {
+    if !IMAGE_MIME_TYPES.contains(&mime_type) {
+        return Err(format!(
+            "Unsupported image type {} (supported: {})",
+            mime_type,
+            IMAGE_MIME_TYPES.join(", ")
+        ));
+    }
+    if bytes.len() > MAX_IMAGE_BYTES {
+        return Err(format!(
+            "Image is {:.1} MB; the limit is {} MB",
+            bytes.len() as f64 / (1024.0 * 1024.0),
+            MAX_IMAGE_BYTES / (1024 * 1024)
+        ));
+    }
+    match sniff_image_type(bytes) {
+        Some(actual) if actual == mime_type => Ok(()),
+        Some(actual) => Err(format!("Image is declared as {} but its content is {}", mime_type, actual)),
+        None => Err(format!("Content is not a {} image", mime_type)),
+    }
+}
+
+/// The image type of `bytes`, from their signature
+pub fn sniff_image_type(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else {
+        None
+    }
+}
+
+/// Width and height of a PNG, JPEG, GIF or WebP image, read from its header
+pub fn image_dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    let be16 = |i: usize| Some(u16::from_be_bytes(bytes.get(i..i + 2)?.try_into().ok()?) as u32);
+    let le16 = |i: usize| Some(u16::from_le_bytes(bytes.get(i..i + 2)?.try_into().ok()?) as u32);
+    let le24 = |i: usize| {
+        let b = bytes.get(i..i + 3)?;
+        Some(b[0] as u32 | (b[1] as u32) << 8 | (b[2] as u32) << 16)
+    };
+
+    match sniff_image_type(bytes)? {
+        "image/png" => {
+            let w = u32::from_be_bytes(bytes.get(16..20)?.try_into().ok()?);
+            let h = u32::from_be_bytes(bytes.get(20..24)?.try_into().ok()?);
+            Some((w, h))
+        }
+        "image/gif" => Some((le16(6)?, le16(8)?)),
+        "image/jpeg" => {
+            // Walk the segments to the first start-of-frame marker
+            let mut i = 2;
+            while i + 9 < bytes.len() {
+                if bytes[i] != 0xFF {
+                    return None;
+                }
+                let marker = bytes[i + 1];
+                if marker == 0xFF {
+                    i += 1;
+                    continue;
+                }
+                let is_sof = (0xC0..=0xCF).contains(&marker) && !matches!(marker, 0xC4 | 0xC8 | 0xCC);
+                if is_sof {
+                    return Some((be16(i + 7)?, be16(i + 5)?));
+                }
+                i += 2 + be16(i + 2)? as usize;
+            }
+            None
+        }
+        "image/webp" => match bytes.get(12..16)? {
+            b"VP8 " => Some((le16(26)? & 0x3FFF, le16(28)? & 0x3FFF)),
+            b"VP8L" => {
+                let b = bytes.get(21..25)?;
+                let w = 1 + ((b[1] as u32 & 0x3F) << 8 | b[0] as u32);
+                let h = 1 + ((b[3] as u32 & 0x0F) << 10 | (b[2] as u32) << 2 | (b[1] as u32 & 0xC0) >> 6);
+                Some((w, h))
+            }
+            b"VP8X" => Some((1 + le24(24)?, 1 + le24(27)?)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A PNG header for a `w` x `h` image (enough for sniffing and dimensions)
+    fn png(w: u32, h: u32) -> Vec<u8> {
+        let mut bytes = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        bytes.extend_from_slice(&w.to_be_bytes());
+        bytes.extend_from_slice(&h.to_be_bytes());
+        bytes.extend_from_slice(&[8, 6, 0, 0, 0]);
+        bytes
+    }
+
+    #[test]
+    fn test_image_dimensions() {
+        assert_eq!(image_dimensions(&png(1920, 1080)), Some((1920, 1080)));
+
+        let gif = [b"GIF89a".as_slice(), &[0x40, 0x01, 0xF0, 0x00]].concat();
+        assert_eq!(image_dimensions(&gif), Some((320, 240)));
+
+        // SOI, an APP0 segment, then SOF0 with height 480 and width 640
+        let jpeg = [
+            &[0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x04, 0x00, 0x00][..],
+            &[0xFF, 0xC0, 0x00, 0x11, 0x08, 0x01, 0xE0, 0x02, 0x80, 0x03],
+        ]
+        .concat();
+        assert_eq!(image_dimensions(&jpeg), Some((640, 480)));
+
+        let mut webp = b"RIFF\0\0\0\0WEBPVP8X\x0a\0\0\0\0\0\0\0".to_vec();
+        webp.extend_from_slice(&[0xFF, 0x03, 0x00, 0x57, 0x02, 0x00]);
+        assert_eq!(image_dimensions(&webp), Some((1024, 600)));
+
+        assert_eq!(image_dimensions(b"not an image"), None);
+    }
+
+    #[test]
+    fn test_validate_image() {
+        let bytes = png(10, 10);
+        assert!(validate_image(&bytes, "image/png").is_ok());
+        assert!(validate_image(&bytes, "image/jpeg").unwrap_err().contains("content is image/png"));
+        assert!(validate_image(&bytes, "image/svg+xml").unwrap_err().contains("Unsupported"));
+        assert!(validate_image(b"plain text", "image/png").is_err());
+
+        let mut large = png(10, 10);
+        large.resize(MAX_IMAGE_BYTES + 1, 0);
+        assert!(validate_image(&large, "image/png").unwrap_err().contains("limit"));
+    }
+
+    #[test]
+    fn test_attachment_into_image() {
+        let data = STANDARD.encode(png(4, 4));
+        let image = Attachment::image_base64(&data, "image/png").into_image().unwrap();
+        assert_eq!(image.media_type, "image/png");
+        assert_eq!(image.data, data);
+        assert!(Attachment::image_base64("%%%", "image/png").into_image().is_err());
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("shot.PNG");
+        std::fs::write(&path, png(4, 4)).unwrap();
+        assert_eq!(Attachment::image_file(&path).into_image().unwrap().media_type, "image/png");
+        std::fs::write(dir.path().join("fake.png"), b"text").unwrap();
+        assert!(Attachment::image_file(dir.path().join("fake.png")).into_image().is_err());
+
+        let json = serde_json::json!({"kind": "image", "source": {"path": path}});
+        let attachment: Attachment = serde_json::from_value(json).unwrap();
+        assert!(matches!(attachment.source, AttachmentSource::Path(_)));
+    }
+}
@@ -0,0 +1,540 @@
+//! Session audit log
+//!
+//! Every approval decision and every executed mutation of a session is
+//! appended (JSONL, flushed per event) to `<session id>/audit.log` in the
+//! sessions directory. The log lives beside the session rather than in its
+//! history, so compaction never summarizes it away, and deleting a saved
+//! session leaves it in place unless its removal is asked for explicitly.
+//!
+//! Logged arguments and commands go through the session's secret masker
+//! (see `EnvPolicy::masker`), and long strings are cut short.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::warn;
+
+use super::persistence::get_sessions_dir;
+use crate::approval::SecurityFinding;
+use crate::error::{Error, Result};
+use crate::tools::shell::SecretMasker;
+
+/// File name of the audit log in a session's directory
+pub const AUDIT_LOG_FILE: &str = "audit.log";
+
+/// Version of the audit record schema, written with every record
+///
+/// - 1: approval_requested, approved, rejected, tool_executed, command_run,
+///   session_compacted
+/// - 2: the `reviewer` decider, `delegated_to` on approval_requested and
+///   `approver` on approved and rejected
+/// - 3: `findings` on approval_requested
+pub const AUDIT_SCHEMA_VERSION: u32 = 3;
+
+/// Longest string kept in logged arguments, in characters
+const MAX_LOGGED_STRING_CHARS: usize = 2000;
+
+/// Who settled an approval
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Decider {
+    /// An `--approve-tools`/`--deny-tools` rule
+    Rule,
+    /// The frontend's answer to a pending approval
+    User,
+    /// A reviewer's answer to a delegated approval
+    Reviewer,
+    /// The session, on cancellation or shutdown
+    System,
+}
+
+impl std::fmt::Display for Decider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Rule => write!(f, "rule"),
+            Self::User => write!(f, "user"),
+            Self::Reviewer => write!(f, "reviewer"),
+            Self::System => write!(f, "system"),
+        }
+    }
+}
+
+/// One audited event
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum AuditEvent {
+    /// A tool call is waiting for the frontend's approval
+    ApprovalRequested {
+        tool_call_id: String,
+        tool: String,
+        arguments: Value,
+        /// The organization policy requires the approval
+        #[serde(default)]
+        policy_required: bool,
+        /// The channel of the reviewers who must approve it, if delegated
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        delegated_to: Option<String>,
+        /// Signs of secret exfiltration that made the call need approval
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        findings: Vec<SecurityFinding>,
+    },
+    /// A tool call was approved
+    Approved {
+        tool_call_id: String,
+        tool: String,
+        by: Decider,
+        /// The approve rule that matched, when `by` is `rule`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        rule: Option<String>,
+        /// Who approved it, when `by` is `reviewer`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        approver: Option<String>,
+    },
+    /// A tool call was refused
+    Rejected {
+        tool_call_id: String,
+        tool: String,
+        by: Decider,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        reason: Option<String>,
+        /// Who refused it, when `by` is `reviewer`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        approver: Option<String>,
+    },
+    /// A tool with side effects finished running
+    ToolExecuted {
+        tool_call_id: String,
+        tool: String,
+        arguments: Value,
+        success: bool,
+        #[serde(default)]
+        cancelled: bool,
+    },
+    /// A shell command finished running
+    CommandRun {
+        tool_call_id: String,
+        command: String,
+        success: bool,
+    },
+    /// The history was summarized to free context
+    SessionCompacted {
+        messages_summarized: usize,
+        chars_before: usize,
+        chars_after: usize,
+    },
+}
+
+impl AuditEvent {
+    /// The event's name as written to the log
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ApprovalRequested { .. } => "approval_requested",
+            Self::Approved { .. } => "approved",
+            Self::Rejected { .. } => "rejected",
+            Self::ToolExecuted { .. } => "tool_executed",
+            Self::CommandRun { .. } => "command_run",
+            Self::SessionCompacted { .. } => "session_compacted",
+        }
+    }
+}
+
+/// One line of the audit log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    /// Schema version (see `AUDIT_SCHEMA_VERSION`)
+    pub v: u32,
+    pub timestamp: DateTime<Utc>,
+    pub session_id: String,
+    #[serde(flatten)]
+    pub event: AuditEvent,
+}
+
+/// Counts of a log's records, by event and by decider
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuditSummary {
+    pub records: usize,
+    pub events: BTreeMap<&'static str, usize>,
+    pub approved_by: BTreeMap<Decider, usize>,
+    pub rejected_by: BTreeMap<Decider, usize>,
+    pub failed_tools: usize,
+    pub first: Option<DateTime<Utc>>,
+    pub last: Option<DateTime<Utc>>,
+}
+
+impl AuditSummary {
+    pub fn from_records(records: &[AuditRecord]) -> Self {
+        let mut summary = Self {
+            records: records.len(),
+            first: records.first().map(|r| r.timestamp),
+            last: records.last().map(|r| r.timestamp),
+            ..Self::default()
+        };
+        for record in records {
+            *summary.events.entry(record.event.name()).or_default() += 1;
+            match &record.event {
+                AuditEvent::Approved { by, .. } => *summary.approved_by.entry(*by).or_default() += 1,
+                AuditEvent::Rejected { by, .. } => *summary.rejected_by.entry(*by).or_default() += 1,
+                AuditEvent::ToolExecuted { success: false, .. } => summary.failed_tools += 1,
+                _ => {}
+            }
+        }
+        summary
+    }
+}
+
+/// Append-only audit log of one session
+///
+/// Shared (as `Arc<AuditLog>`) with the session's subagents, so their
+/// approvals and mutations land in the parent's log.
+#[derive(Debug)]
+pub struct AuditLog {
+    session_id: String,
+    path: PathBuf,
+    masker: SecretMasker,
+    /// Opened on the first event
+    file: Mutex<Option<File>>,
+}
+
+impl AuditLog {
+    /// A log at the given path
+    pub fn new(session_id: impl Into<String>, path: impl Into<PathBuf>) -> Self {
+        Self {
+            session_id: session_id.into(),
+            path: path.into(),
+            masker: SecretMasker::default(),
+            file: Mutex::new(None),
+        }
+    }
+
+    /// The log of a session in the sessions directory
+    pub fn for_session(session_id: &str) -> Result<Self> {
+        Ok(Self::new(session_id, get_audit_log_path(session_id)?))
+    }
+
+    /// Mask these secrets in logged arguments and commands
+    pub fn with_masker(mut self, masker: SecretMasker) -> Self {
+        self.masker = masker;
+        self
+    }
+
+    /// Get the log file path
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Arguments as they are logged: secrets masked and long strings cut short
+    pub fn redact(&self, arguments: &Value) -> Value {
+        match arguments {
+            Value::String(s) => Value::String(shorten(&self.masker.mask(s))),
+            Value::Array(items) => Value::Array(items.iter().map(|v| self.redact(v)).collect()),
+            Value::Object(map) => Value::Object(map.iter().map(|(k, v)| (k.clone(), self.redact(v))).collect()),
+            other => other.clone(),
+        }
+    }
+
+    /// A tool call is waiting for approval
+    pub fn approval_requested(
+        &self,
+        tool_call_id: &str,
+        tool: &str,
+        arguments: &Value,
+        policy_required: bool,
+        findings: &[SecurityFinding],
+    ) {
+        self.record(AuditEvent::ApprovalRequested {
+            tool_call_id: tool_call_id.to_string(),
+            tool: tool.to_string(),
+            arguments: self.redact(arguments),
+            policy_required,
+            delegated_to: None,
+            findings: self.redact_findings(findings),
+        });
+    }
+
+    /// A tool call is waiting for a reviewer on `channel`
+    pub fn approval_delegated(
+        &self,
+        tool_call_id: &str,
+        tool: &str,
+        arguments: &Value,
+        channel: &str,
+        findings: &[SecurityFinding],
+    ) {
+        self.record(AuditEvent::ApprovalRequested {
+            tool_call_id: tool_call_id.to_string(),
+            tool: tool.to_string(),
+            arguments: self.redact(arguments),
+            policy_required: false,
+            delegated_to: Some(channel.to_string()),
+            findings: self.redact_findings(findings),
+        });
+    }
+
+    /// Findings with secrets in their details masked
+    fn redact_findings(&self, findings: &[SecurityFinding]) -> Vec<SecurityFinding> {
+        findings
+            .iter()
+            .map(|finding| SecurityFinding {
+                pattern: finding.pattern.clone(),
+                detail: self.masker.mask(&finding.detail),
+            })
+            .collect()
+    }
+
+    /// A tool call was approved
+    pub fn approved(&self, tool_call_id: &str, tool: &str, by: Decider, rule: Option<String>) {
+        self.record(AuditEvent::Approved {
+            tool_call_id: tool_call_id.to_string(),
+            tool: tool.to_string(),
+            by,
+            rule,
+            approver: None,
+        });
+    }
+
+    /// A reviewer approved a delegated tool call
+    pub fn approved_by_reviewer(&self, tool_call_id: &str, tool: &str, approver: &str) {
+        self.record(AuditEvent::Approved {
+            tool_call_id: tool_call_id.to_string(),
+            tool: tool.to_string(),
+            by: Decider::Reviewer,
+            rule: None,
+            approver: Some(approver.to_string()),
+        });
+    }
+
+    /// A tool call was refused
+    pub fn rejected(&self, tool_call_id: &str, tool: &str, by: Decider, reason: Option<String>) {
+        self.record(AuditEvent::Rejected {
+            tool_call_id: tool_call_id.to_string(),
+            tool: tool.to_string(),
+            by,
+            reason: reason.map(|r| self.masker.mask(&r)),
+            approver: None,
+        });
+    }
+
+    /// A reviewer refused a delegated tool call
+    pub fn rejected_by_reviewer(&self, tool_call_id: &str, tool: &str, approver: &str, reason: Option<String>) {
+        self.record(AuditEvent::Rejected {
+            tool_call_id: tool_call_id.to_string(),
+            tool: tool.to_string(),
+            by: Decider::Reviewer,
+            reason: reason.map(|r| self.masker.mask(&r)),
+            approver: Some(approver.to_string()),
+        });
+    }
+
+    /// A tool with side effects finished; shell commands are also logged as `command_run`
+    pub fn tool_executed(&self, tool_call_id: &str, tool: &str, arguments: &Value, success: bool, cancelled: bool) {
+        self.record(AuditEvent::ToolExecuted {
+            tool_call_id: tool_call_id.to_string(),
+            tool: tool.to_string(),
+            arguments: self.redact(arguments),
+            success,
+            cancelled,
+        });
+        if tool == "Bash"
+            && let Some(command) = arguments.get("command").and_then(Value::as_str)
+        {
+            self.record(AuditEvent::CommandRun {
+                tool_call_id: tool_call_id.to_string(),
+                command: self.masker.mask(command),
+                success,
+            });
+        }
+    }
+
+    /// The history was compacted
+    pub fn session_compacted(&self, messages_summarized: usize, chars_before: usize, chars_after: usize) {
+        self.record(AuditEvent::SessionCompacted { messages_summarized, chars_before, chars_after });
+    }
+
+    /// Append an event and flush it
+    ///
+    /// Failures are logged, never returned, so auditing can't break the
+    /// agent loop.
+    pub fn record(&self, event: AuditEvent) {
+        let record = AuditRecord {
+            v: AUDIT_SCHEMA_VERSION,
+            timestamp: Utc::now(),
+            session_id: self.session_id.clone(),
+            event,
+        };
+        if let Err(e) = self.append(&record) {
+            warn!("Failed to write audit record to {}: {}", self.path.display(), e);
+        }
+    }
+
+    fn append(&self, record: &AuditRecord) -> Result<()> {
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+
+        let mut file = self.file.lock();
+        if file.is_none() {
+            if let Some(parent) = self.path.parent()
+                && !parent.as_os_str().is_empty()
+            {
+                std::fs::create_dir_all(parent)?;
+            }
+            *file = Some(std::fs::OpenOptions::new().create(true).append(true).open(&self.path)?);
+        }
+        let Some(file) = file.as_mut() else {
+            return Ok(());
+        };
+        file.write_all(line.as_bytes())?;
+        file.flush()?;
+        Ok(())
+    }
+}
+
+/// Get the path of a session's audit log: `<session id>/audit.log` in the
+/// sessions directory
+pub fn get_audit_log_path(session_id: &str) -> Result<PathBuf> {
+    let is_file_name = Path::new(session_id).file_name().is_some_and(|name| name == session_id);
+    if !is_file_name {
+        return Err(Error::Workspace(format!("Invalid session id for an audit log: {}", session_id)));
+    }
+    Ok(get_sessions_dir()?.join(session_id).join(AUDIT_LOG_FILE))
+}
+
+/// Read an audit log, oldest first
+///
+/// Malformed lines are skipped.
+pub fn read_audit_log(path: &Path) -> Result<Vec<AuditRecord>> {
+    let file = File::open(path)?;
+    let mut records = Vec::new();
+    for line in std::io::BufReader::new(file).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(record) => records.push(record),
+            Err(e) => warn!("Skipping malformed audit record in {}: {}", path.display(), e),
+        }
+    }
+    Ok(records)
+}
+
+/// Load a session's audit log from the sessions directory
+pub fn load_audit_log(session_id: &str) -> Result<Vec<AuditRecord>> {
+    let path = get_audit_log_path(session_id)?;
+    if !path.exists() {
+        return Err(Error::Workspace(format!("No audit log for session {}", session_id)));
+    }
+    read_audit_log(&path)
+}
+
+/// Remove a session's audit log, and the session's own directory once
+/// nothing else is left in it
+pub fn remove_audit_log(session_id: &str) -> Result<()> {
+    let path = get_audit_log_path(session_id)?;
+    match std::fs::remove_file(&path) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e.into()),
+    }
+    if let Some(session_dir) = path.parent() {
+        // Fails, and is left alone, while it holds anything else
+        let _ = std::fs::remove_dir(session_dir);
+    }
+    Ok(())
+}
+
+/// Cut a string to `MAX_LOGGED_STRING_CHARS`, noting its full length
+fn shorten(s: &str) -> String {
+    let len = s.chars().count();
+    if len <= MAX_LOGGED_STRING_CHARS {
+        return s.to_string();
+    }
+    let kept: String = s.chars().take(MAX_LOGGED_STRING_CHARS).collect();
+    format!("{}… [{} chars]", kept, len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_records_are_appended_with_the_schema_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = AuditLog::new("s1", dir.path().join("s1").join(AUDIT_LOG_FILE));
+
+        log.approval_requested("c1", "Bash", &json!({"command": "rm -rf build"}), false, &[]);
+        log.approved("c1", "Bash", Decider::User, None);
+        log.tool_executed("c1", "Bash", &json!({"command": "rm -rf build"}), true, false);
+        log.session_compacted(12, 40_000, 2_000);
+
+        let text = std::fs::read_to_string(log.path()).unwrap();
+        let first: Value = serde_json::from_str(text.lines().next().unwrap()).unwrap();
+        assert_eq!(first["v"], AUDIT_SCHEMA_VERSION);
+        assert_eq!(first["event"], "approval_requested");
+        assert_eq!(first["session_id"], "s1");
+
+        let records = read_audit_log(log.path()).unwrap();
+        let events: Vec<&str> = records.iter().map(|r| r.event.name()).collect();
+        assert_eq!(
+            events,
+            ["approval_requested", "approved", "tool_executed", "command_run", "session_compacted"]
+        );
+    }
+
+    #[test]
+    fn test_secrets_are_masked_and_long_strings_shortened() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = AuditLog::new("s1", dir.path().join(AUDIT_LOG_FILE))
+            .with_masker(SecretMasker::new(["sk-live-0123456789".to_string()]));
+
+        let arguments = json!({
+            "command": "curl -H 'Authorization: sk-live-0123456789' example.com",
+            "content": "x".repeat(MAX_LOGGED_STRING_CHARS + 10),
+        });
+        log.tool_executed("c1", "Bash", &arguments, true, false);
+
+        let text = std::fs::read_to_string(log.path()).unwrap();
+        assert!(!text.contains("sk-live-0123456789"));
+        let records = read_audit_log(log.path()).unwrap();
+        let AuditEvent::ToolExecuted { arguments, .. } = &records[0].event else {
+            panic!("expected tool_executed, got {:?}", records[0].event);
+        };
+        assert!(arguments["content"].as_str().unwrap().ends_with(&format!("[{} chars]", MAX_LOGGED_STRING_CHARS + 10)));
+        let AuditEvent::CommandRun { command, .. } = &records[1].event else {
+            panic!("expected command_run, got {:?}", records[1].event);
+        };
+        assert_eq!(command, "curl -H 'Authorization: ***' example.com");
+    }
+
+    #[test]
+    fn test_summary_counts_events_and_deciders() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = AuditLog::new("s1", dir.path().join(AUDIT_LOG_FILE));
+        log.approved("c1", "Write", Decider::Rule, Some("Write".to_string()));
+        log.approved("c2", "Bash", Decider::User, None);
+        log.rejected("c3", "Bash", Decider::System, Some("Session ended".to_string()));
+        log.tool_executed("c1", "Write", &json!({"file_path": "a.txt"}), false, false);
+
+        // Garbage lines are skipped
+        std::fs::OpenOptions::new().append(true).open(log.path()).unwrap().write_all(b"not json\n").unwrap();
+
+        let summary = AuditSummary::from_records(&read_audit_log(log.path()).unwrap());
+        assert_eq!(summary.records, 4);
+        assert_eq!(summary.events["approved"], 2);
+        assert_eq!(summary.approved_by[&Decider::Rule], 1);
+        assert_eq!(summary.rejected_by[&Decider::System], 1);
+        assert_eq!(summary.failed_tools, 1);
+    }
+
+    #[test]
+    fn test_invalid_session_id_is_refused() {
+        assert!(get_audit_log_path("../escape").is_err());
+        assert!(get_audit_log_path("session-1").unwrap().ends_with("session-1/audit.log"));
+    }
+}
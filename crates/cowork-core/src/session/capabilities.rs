@@ -0,0 +1,240 @@
+//! Capabilities: what this core supports, for frontends to feature-detect
+//!
+//! A frontend built against another version of the core asks for the
+//! `Capabilities` first (`SessionManager::capabilities`, the app's
+//! `get_capabilities` command) and only offers what the core supports: the
+//! `SessionInput` and `SessionOutput` types it knows, the tools sessions
+//! offer, optional features and limits.
+//!
+//! An input whose `type` this core doesn't know, parsed with
+//! `SessionInput::from_json`, becomes `SessionInput::Unsupported`, which the
+//! session answers with `SessionOutput::UnsupportedInput` rather than
+//! dropping it.
+
+use serde::{Deserialize, Serialize};
+
+use super::attachments::MAX_IMAGE_BYTES;
+use super::types::SessionConfig;
+use crate::orchestration::ToolRegistryBuilder;
+
+/// Version of the session protocol, `SessionInput` and `SessionOutput`
+///
+/// Bumped when a type changes shape or is removed; new types are found
+/// in `Capabilities::inputs` and `Capabilities::outputs` instead.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// `type` of every `SessionInput` (keep in step with the enum)
+pub const SESSION_INPUT_TYPES: &[&str] = &[
+    "user_message",
+    "user_message_with_images",
+    "approve_tool",
+    "reject_tool",
+    "approve_delegated",
+    "reject_delegated",
+    "answer_question",
+    "cancel",
+    "cancel_tool",
+    "explain_tool",
+    "set_plan_mode",
+    "clear_queue",
+    "set_generation_params",
+    "approve_plan",
+    "regenerate_last",
+    "edit_user_message",
+    "run_recipe",
+    "set_pinned",
+    "pin_message",
+    "set_selection",
+    "set_title",
+    "set_tool_profile",
+    "compact",
+];
+
+/// `type` of every `SessionOutput` (keep in step with the enum)
+pub const SESSION_OUTPUT_TYPES: &[&str] = &[
+    "ready",
+    "idle",
+    "user_message",
+    "thinking",
+    "text_delta",
+    "assistant_message",
+    "tool_start",
+    "tool_pending",
+    "approval_delegated",
+    "tool_explanation",
+    "tool_progress",
+    "tool_done",
+    "tool_call",
+    "tool_result",
+    "question",
+    "error",
+    "warning",
+    "refusal",
+    "cancelled",
+    "plan_mode_changed",
+    "message_queued",
+    "files_changed_externally",
+    "generation_params_changed",
+    "plan_status_changed",
+    "todos_updated",
+    "title_changed",
+    "tool_profile_changed",
+    "turn_overridden",
+    "history_truncated",
+    "suspended",
+    "resumed",
+    "worktree",
+    "recipe_started",
+    "recipe_step_started",
+    "recipe_step_finished",
+    "recipe_finished",
+    "unsupported_input",
+    "compacted",
+    "self_review",
+];
+
+/// What the core supports
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Capabilities {
+    /// See `PROTOCOL_VERSION`
+    pub protocol_version: u32,
+    /// Version of cowork-core
+    pub core_version: String,
+    /// `SessionInput` types sessions accept
+    pub inputs: Vec<String>,
+    /// `SessionOutput` types sessions send
+    pub outputs: Vec<String>,
+    /// Built-in tools new sessions offer, after the tool profile (MCP tools
+    /// are listed by their servers)
+    pub tools: Vec<String>,
+    pub features: Features,
+    pub limits: CapabilityLimits,
+}
+
+/// Optional features of new sessions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Features {
+    /// Replies arrive as `text_delta` outputs while they are generated
+    pub streaming: bool,
+    /// Messages can carry images (`user_message_with_images`)
+    pub attachments: bool,
+    /// `set_plan_mode` and the plan mode tools
+    pub plan_mode: bool,
+    /// The Task tool runs subagents
+    pub subagents: bool,
+    /// User messages can carry `overrides` for their turn
+    pub turn_overrides: bool,
+    /// Turns that change files end with a `self_review`
+    pub self_review: bool,
+}
+
+/// Limits frontends should check before sending
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapabilityLimits {
+    /// Largest image a message can carry, in bytes
+    pub max_attachment_bytes: usize,
+    /// Most sessions open at once (0 = unlimited)
+    pub max_sessions: usize,
+}
+
+impl Capabilities {
+    /// The capabilities of sessions built from `config`
+    pub fn for_config(config: &SessionConfig, max_sessions: usize) -> Self {
+        let mut builder = ToolRegistryBuilder::new(config.workspace_path.clone())
+            .with_provider(&config.provider_id)
+            .with_tool_profile(config.tool_profile.clone())
+            .with_compact_descriptions(config.compact_tool_descriptions);
+        if let Some(key) = config.api_key.clone() {
+            builder = builder.with_api_key(key);
+        }
+        if let Some(web_search) = config.web_search_config.clone() {
+            builder = builder.with_web_search_config(web_search);
+        }
+        if let Some(policy) = config.policy.clone() {
+            builder = builder.with_policy(policy);
+        }
+        let mut tools: Vec<String> = builder.build().list().into_iter().map(|tool| tool.name).collect();
+        tools.sort();
+        let offers = |name: &str| tools.iter().any(|tool| tool == name);
+
+        Self {
+            protocol_version: PROTOCOL_VERSION,
+            core_version: env!("CARGO_PKG_VERSION").to_string(),
+            inputs: SESSION_INPUT_TYPES.iter().map(|t| t.to_string()).collect(),
+            outputs: SESSION_OUTPUT_TYPES.iter().map(|t| t.to_string()).collect(),
+            features: Features {
+                streaming: config.stream_mode,
+                attachments: true,
+                plan_mode: offers("EnterPlanMode"),
+                subagents: offers("Task"),
+                turn_overrides: true,
+                self_review: config.self_review,
+            },
+            limits: CapabilityLimits {
+                max_attachment_bytes: MAX_IMAGE_BYTES,
+                max_sessions,
+            },
+            tools,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::{SessionInput, SessionOutput};
+    use serde::de::DeserializeOwned;
+
+    /// The `type` tags serde accepts, read from its unknown variant error
+    fn serde_types<T: DeserializeOwned + std::fmt::Debug>() -> Vec<String> {
+        let err = serde_json::from_value::<T>(serde_json::json!({ "type": "no_such_type" })).unwrap_err();
+        let message = err.to_string();
+        let expected = message.split("expected one of ").nth(1).expect(&message);
+        expected
+            .split(", ")
+            .map(|tag| tag.trim().trim_matches('`').to_string())
+            .filter(|tag| !tag.is_empty())
+            .collect()
+    }
+
+    #[test]
+    fn test_input_types_match_the_enum() {
+        // A new SessionInput must be listed in SESSION_INPUT_TYPES
+        assert_eq!(serde_types::<SessionInput>(), SESSION_INPUT_TYPES);
+    }
+
+    #[test]
+    fn test_output_types_match_the_enum() {
+        // A new SessionOutput must be listed in SESSION_OUTPUT_TYPES
+        assert_eq!(serde_types::<SessionOutput>(), SESSION_OUTPUT_TYPES);
+    }
+
+    #[test]
+    fn test_unknown_input_types_are_unsupported() {
+        let input = SessionInput::from_json(serde_json::json!({ "type": "set_mood", "mood": "calm" })).unwrap();
+        assert!(matches!(input, SessionInput::Unsupported { ref input_type } if input_type == "set_mood"));
+
+        let input = SessionInput::from_json(serde_json::json!({ "type": "set_title", "title": "Renamed" })).unwrap();
+        assert!(matches!(input, SessionInput::SetTitle { ref title } if title == "Renamed"));
+
+        // A known type with the wrong fields is an error, not an unsupported input
+        assert!(SessionInput::from_json(serde_json::json!({ "type": "set_title" })).is_err());
+        assert!(SessionInput::from_json(serde_json::json!({ "content": "no type" })).is_err());
+    }
+
+    #[test]
+    fn test_features_follow_the_tool_profile() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = SessionConfig::new(dir.path().to_path_buf());
+        let full = Capabilities::for_config(&config, 4);
+        assert!(full.features.plan_mode && full.features.subagents);
+        assert!(full.tools.iter().any(|tool| tool == "Read"));
+        assert_eq!(full.limits.max_sessions, 4);
+
+        let config = config.with_tool_profile(crate::ToolProfile::preset("research").unwrap());
+        let research = Capabilities::for_config(&config, 0);
+        assert!(research.tools.iter().any(|tool| tool == "Read"));
+        assert!(!research.tools.iter().any(|tool| tool == "Write"));
+        assert!(!research.features.plan_mode && !research.features.subagents);
+    }
+}
@@ -2,14 +2,16 @@
 //!
 //! Provides shared session state for both CLI and UI.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use serde::{Deserialize, Serialize};
 
 use crate::orchestration::system_prompt::DEFAULT_SYSTEM_PROMPT;
 use crate::provider::{
-    ChatMessage, ContentPart, MessageContent, ToolCall, tool_result_message, assistant_with_tool_calls,
+    ChatMessage, ChatRole, ContentPart, MessageContent, ToolCall, tool_result_message, assistant_with_tool_calls,
 };
+use super::arena::{MemoryStats, ResultArena};
 use super::ImageAttachment;
+use super::history::{repair_history, HistoryRepair};
 
 /// Status of a tool call
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -34,11 +36,18 @@ pub struct ChatSession {
     /// Unique session ID
     pub id: String,
     /// Conversation messages (using genai's ChatMessage directly)
+    ///
+    /// Large tool results hold a reference into the session's arena (see
+    /// `session::arena`); `resolved_messages` has their content.
     pub messages: Vec<ChatMessage>,
     /// System prompt for this session
     pub system_prompt: String,
     /// Tool call status tracking (keyed by call_id)
     pub tool_status: HashMap<String, ToolCallStatus>,
+    /// Whether the history changed since it was last saved
+    dirty: bool,
+    /// Large tool results the messages refer to
+    arena: ResultArena,
 }
 
 impl ChatSession {
@@ -49,6 +58,8 @@ impl ChatSession {
             messages: Vec::new(),
             system_prompt: DEFAULT_SYSTEM_PROMPT.to_string(),
             tool_status: HashMap::new(),
+            dirty: false,
+            arena: ResultArena::default(),
         }
     }
 
@@ -59,12 +70,15 @@ impl ChatSession {
             messages: Vec::new(),
             system_prompt: system_prompt.into(),
             tool_status: HashMap::new(),
+            dirty: false,
+            arena: ResultArena::default(),
         }
     }
 
     /// Add a user message
     pub fn add_user_message(&mut self, content: impl Into<String>) {
         self.messages.push(ChatMessage::user(content.into()));
+        self.dirty = true;
     }
 
     /// Add a user message with image attachments
@@ -79,6 +93,7 @@ impl ChatSession {
             parts.push(ContentPart::from_binary_base64(&img.media_type, img.data, None));
         }
         self.messages.push(ChatMessage::user(MessageContent::from_parts(parts)));
+        self.dirty = true;
     }
 
     /// Add an assistant message
@@ -94,6 +109,7 @@ impl ChatSession {
             let content_opt = if content_str.is_empty() { None } else { Some(content_str) };
             self.messages.push(assistant_with_tool_calls(content_opt, tool_calls));
         }
+        self.dirty = true;
     }
 
     /// Add a tool result
@@ -102,8 +118,11 @@ impl ChatSession {
         let status = if is_error { ToolCallStatus::Failed } else { ToolCallStatus::Completed };
         self.tool_status.insert(call_id.to_string(), status);
 
-        // Add tool result message
-        self.messages.push(tool_result_message(call_id, result.into()));
+        // Add tool result message, large results held once in the arena
+        let result = result.into();
+        let content = self.arena.intern(&result).unwrap_or(result);
+        self.messages.push(tool_result_message(call_id, content));
+        self.dirty = true;
     }
 
     /// Add multiple tool results
@@ -142,21 +161,126 @@ impl ChatSession {
         self.tool_status.get(call_id).copied()
     }
 
-    /// Get messages (already in LLM format)
+    /// Pair every tool call with exactly one result
+    ///
+    /// Calls that got a synthesized result are marked as failed.
+    pub fn repair_history(&mut self) -> HistoryRepair {
+        let repair = repair_history(&mut self.messages);
+        for call_id in &repair.synthesized {
+            self.tool_status.insert(call_id.clone(), ToolCallStatus::Failed);
+        }
+        self.dirty |= !repair.is_empty();
+        repair
+    }
+
+    /// Drop the messages from index `len` on, with their tool call statuses
+    ///
+    /// The history is repaired afterwards in case the cut separated tool
+    /// calls from their results.
+    pub fn truncate(&mut self, len: usize) -> HistoryRepair {
+        self.dirty |= len < self.messages.len();
+        self.messages.truncate(len);
+        let kept: HashSet<&str> = self
+            .messages
+            .iter()
+            .flat_map(|m| m.content.tool_calls())
+            .map(|tc| tc.call_id.as_str())
+            .collect();
+        self.tool_status.retain(|id, _| kept.contains(id.as_str()));
+        self.arena.retain_referenced(&self.messages);
+        self.repair_history()
+    }
+
+    /// Get messages (already in LLM format), large tool results as references
     pub fn get_messages(&self) -> &[ChatMessage] {
         &self.messages
     }
 
+    /// The messages with the content of large tool results, for the model
+    /// and for saving
+    pub fn resolved_messages(&self) -> Vec<ChatMessage> {
+        let mut messages = self.messages.clone();
+        self.arena.resolve_messages(&mut messages);
+        messages
+    }
+
+    /// Continue a saved history, interning its large tool results
+    ///
+    /// Like the history it was saved from, it doesn't count as a change.
+    pub fn restore_messages(&mut self, messages: Vec<ChatMessage>) {
+        self.arena.clear();
+        self.messages = messages
+            .into_iter()
+            .map(|message| {
+                let [ContentPart::ToolResponse(response)] = message.content.parts().as_slice() else {
+                    return message;
+                };
+                match self.arena.intern(&response.content) {
+                    Some(reference) => ChatMessage {
+                        content: tool_result_message(response.call_id.clone(), reference).content,
+                        ..message
+                    },
+                    None => message,
+                }
+            })
+            .collect();
+    }
+
+    /// The session's arena of large tool results
+    pub fn arena(&self) -> &ResultArena {
+        &self.arena
+    }
+
+    /// Memory the history holds, for diagnosing sessions that grow large
+    pub fn memory_stats(&self) -> MemoryStats {
+        self.arena.memory_stats(&self.messages)
+    }
+
     /// Clear conversation history
     pub fn clear(&mut self) {
         self.messages.clear();
         self.tool_status.clear();
+        self.arena.clear();
+        self.dirty = true;
+    }
+
+    /// Whether the history changed since `mark_saved`
+    ///
+    /// Changes made through `messages` directly aren't tracked.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Record that the history as it is now has been saved
+    pub fn mark_saved(&mut self) {
+        self.dirty = false;
     }
 
     /// Get message count
     pub fn message_count(&self) -> usize {
         self.messages.len()
     }
+
+    /// The result recorded for a tool call, and whether the call failed
+    pub fn tool_result(&self, call_id: &str) -> Option<(String, bool)> {
+        let response = self
+            .messages
+            .iter()
+            .rev()
+            .flat_map(|m| m.content.tool_responses())
+            .find(|r| r.call_id == call_id)?;
+        let failed = matches!(self.get_tool_status(call_id), Some(ToolCallStatus::Failed | ToolCallStatus::Rejected));
+        Some((self.arena.resolve(&response.content).into_owned(), failed))
+    }
+
+    /// Text of the last message, if it is the assistant's answer rather than
+    /// a tool call or anything else
+    pub fn final_assistant_text(&self) -> Option<String> {
+        self.messages
+            .last()
+            .filter(|m| m.role == ChatRole::Assistant && m.content.tool_calls().is_empty())
+            .map(|m| m.content.joined_texts().unwrap_or_default())
+    }
 }
 
 impl Default for ChatSession {
@@ -184,6 +308,26 @@ mod tests {
         assert_eq!(session.message_count(), 2);
     }
 
+    #[test]
+    fn test_dirty_until_saved() {
+        let mut session = ChatSession::new();
+        assert!(!session.is_dirty());
+
+        session.add_user_message("Hello");
+        assert!(session.is_dirty());
+        session.mark_saved();
+        assert!(!session.is_dirty());
+
+        session.truncate(5);
+        session.repair_history();
+        assert!(!session.is_dirty(), "nothing was removed or repaired");
+
+        session.add_assistant_message("Hi there", vec![]);
+        session.mark_saved();
+        session.truncate(1);
+        assert!(session.is_dirty());
+    }
+
     #[test]
     fn test_tool_status_tracking() {
         let mut session = ChatSession::new();
@@ -212,4 +356,66 @@ mod tests {
         session.reject_tool("call_123");
         assert_eq!(session.get_tool_status("call_123"), Some(ToolCallStatus::Rejected));
     }
+
+    #[test]
+    fn test_truncate_drops_removed_tool_calls() {
+        let call = |id: &str| ToolCall {
+            call_id: id.to_string(),
+            fn_name: "read_file".to_string(),
+            fn_arguments: serde_json::json!({"path": "/test.txt"}),
+            thought_signatures: None,
+        };
+        let mut session = ChatSession::new();
+        session.add_user_message("first");
+        session.add_assistant_message("", vec![call("call_1")]);
+        session.add_tool_result("call_1", "contents", false);
+        session.add_user_message("second");
+        session.add_assistant_message("", vec![call("call_2")]);
+        session.add_tool_result("call_2", "contents", false);
+
+        // Cutting between a call and its result gets the result synthesized
+        let repair = session.truncate(5);
+        assert_eq!(repair.synthesized, vec!["call_2".to_string()]);
+        assert_eq!(session.message_count(), 6);
+
+        assert!(session.truncate(3).is_empty());
+        assert_eq!(session.message_count(), 3);
+        assert_eq!(session.get_tool_status("call_1"), Some(ToolCallStatus::Completed));
+        assert_eq!(session.get_tool_status("call_2"), None);
+    }
+
+    #[test]
+    fn test_repeated_large_results_held_once() {
+        let contents = "0123456789abcdef".repeat(64 * 1024); // 1MB
+        let mut session = ChatSession::new();
+        for i in 0..100 {
+            let call_id = format!("call_{}", i);
+            session.add_assistant_message("", vec![ToolCall {
+                call_id: call_id.clone(),
+                fn_name: "read_file".to_string(),
+                fn_arguments: serde_json::json!({"path": "/big.txt"}),
+                thought_signatures: None,
+            }]);
+            session.add_tool_result(&call_id, contents.clone(), false);
+        }
+
+        let stats = session.memory_stats();
+        assert_eq!((stats.interned_count, stats.spilled_count, stats.references), (1, 1, 100));
+        assert!(stats.resident_bytes() < 64 * 1024);
+        assert!(stats.resolved_bytes > 100 * contents.len());
+
+        // The model, saving and result lookups see the full content
+        let resolved = session.resolved_messages();
+        assert_eq!(resolved[199].content.tool_responses()[0].content, contents);
+        assert_eq!(session.tool_result("call_42"), Some((contents.clone(), false)));
+
+        // A resumed history is interned again
+        let mut resumed = ChatSession::new();
+        resumed.restore_messages(resolved);
+        assert!(!resumed.is_dirty());
+        assert_eq!(resumed.memory_stats(), stats);
+
+        session.truncate(1);
+        assert!(session.arena().is_empty());
+    }
 }
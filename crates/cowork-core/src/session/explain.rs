@@ -0,0 +1,102 @@
+//! Explaining a pending tool call
+//!
+//! While a call waits for approval, `SessionInput::ExplainTool` asks the
+//! provider's fast model why the assistant wants it and what it could
+//! change. The request is made outside the session: it sees only the end of
+//! the conversation and the call, and isn't added to the history. Its tokens
+//! are recorded with `EXPLAIN_PURPOSE`, apart from the session's turns.
+
+use crate::prompt::builtin::reminders::TOOL_EXPLANATION;
+use crate::provider::{message_text_content, ChatMessage, ChatRole};
+
+/// `UsageRecord::purpose` of an explanation's tokens
+pub const EXPLAIN_PURPOSE: &str = "explain";
+
+/// Messages from the end of the conversation sent with the call
+pub const EXPLAIN_TAIL_MESSAGES: usize = 6;
+
+/// Most characters of each message sent
+const MAX_MESSAGE_CHARS: usize = 1_500;
+
+/// Most characters of the call's arguments sent
+const MAX_ARGUMENT_CHARS: usize = 4_000;
+
+/// Most characters of an explanation shown
+const MAX_EXPLANATION_CHARS: usize = 1_200;
+
+/// The request asking why the assistant wants to run `tool_name` with
+/// `arguments`, given the conversation's last messages in `tail`
+pub fn explanation_request(tail: &[ChatMessage], tool_name: &str, arguments: &serde_json::Value) -> Vec<ChatMessage> {
+    let conversation = tail
+        .iter()
+        .filter_map(|message| {
+            let role = match message.role {
+                ChatRole::User => "user",
+                ChatRole::Assistant => "assistant",
+                ChatRole::Tool => "tool",
+                ChatRole::System => return None,
+            };
+            let text = message_text_content(message);
+            let text = text.trim();
+            (!text.is_empty()).then(|| format!("<{role}>\n{}\n</{role}>", excerpt(text, MAX_MESSAGE_CHARS)))
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let arguments = serde_json::to_string_pretty(arguments).unwrap_or_else(|_| arguments.to_string());
+    vec![ChatMessage::user(format!(
+        "Here is the end of the conversation:\n\n{}\n\nThe assistant now wants to run:\n\n<tool_call name=\"{}\">\n{}\n</tool_call>\n\n{}",
+        conversation,
+        tool_name,
+        excerpt(&arguments, MAX_ARGUMENT_CHARS),
+        TOOL_EXPLANATION
+    ))]
+}
+
+/// The explanation from the model's reply, trimmed and cut to
+/// `MAX_EXPLANATION_CHARS` (None when it's empty)
+pub fn clean_explanation(reply: &str) -> Option<String> {
+    let reply = reply.trim();
+    (!reply.is_empty()).then(|| excerpt(reply, MAX_EXPLANATION_CHARS))
+}
+
+/// `text` cut to `max_chars` characters, marked with … when cut
+pub(super) fn excerpt(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let kept: String = text.chars().take(max_chars).collect();
+    format!("{}…", kept.trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::tool_result_message;
+    use serde_json::json;
+
+    #[test]
+    fn test_explanation_request() {
+        let tail = vec![
+            ChatMessage::system("System prompt text"),
+            ChatMessage::user("clean up the build output"),
+            tool_result_message("call_1", "x".repeat(5_000)),
+        ];
+        let request = explanation_request(&tail, "Bash", &json!({"command": "rm -rf target"}));
+        assert_eq!(request.len(), 1);
+        let text = message_text_content(&request[0]);
+        assert!(!text.contains("System prompt text"));
+        assert!(text.contains("<user>\nclean up the build output\n</user>"));
+        assert!(text.contains("rm -rf target"));
+        assert!(text.contains(TOOL_EXPLANATION));
+        // Long messages are cut
+        assert!(!text.contains(&"x".repeat(MAX_MESSAGE_CHARS + 1)));
+    }
+
+    #[test]
+    fn test_clean_explanation() {
+        assert_eq!(clean_explanation("  \n"), None);
+        assert_eq!(clean_explanation("\nDeletes the build.\nRisk: low\n").as_deref(), Some("Deletes the build.\nRisk: low"));
+        let long = clean_explanation(&"word ".repeat(1_000)).unwrap();
+        assert!(long.ends_with('…'));
+    }
+}
@@ -0,0 +1,306 @@
+//! Workspace file watcher for detecting external edits
+//!
+//! Tracks the files a session has read or written and watches their parent
+//! directories (non-recursively, so large trees don't exhaust watch handles).
+//! Before each LLM call the agent loop asks for files that changed on disk
+//! since the session last saw them, so the model can re-read before editing.
+//!
+//! Changes are detected by comparing a (size, mtime) fingerprint against the
+//! one recorded when the session last touched the file, which filters out the
+//! session's own writes. The watcher is dropped with the agent loop, closing
+//! its OS handles.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use tracing::{debug, warn};
+
+/// Changes must be quiet this long before they're reported (editors often
+/// write a file several times in quick succession)
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Directories whose contents are never reported
+const IGNORED_DIRS: &[&str] = &[".git", ".trash", ".snapshots"];
+
+/// Tools that read or write a file, and the argument holding its path
+const FILE_TOOLS: &[(&str, &str)] = &[
+    ("Read", "file_path"),
+    ("Write", "file_path"),
+    ("FinishFile", "file_path"),
+    ("Edit", "file_path"),
+    ("NotebookEdit", "notebook_path"),
+];
+
+/// What the session last saw of a file (`None` = it didn't exist)
+type Fingerprint = Option<(u64, Option<SystemTime>)>;
+
+fn fingerprint(path: &Path) -> Fingerprint {
+    std::fs::metadata(path)
+        .ok()
+        .map(|m| (m.len(), m.modified().ok()))
+}
+
+/// A tracked file that changed outside the session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExternalChange {
+    pub path: PathBuf,
+    pub deleted: bool,
+}
+
+#[derive(Default)]
+struct WatchState {
+    /// Files the session has touched, with their last seen fingerprint
+    tracked: HashMap<PathBuf, Fingerprint>,
+    /// Tracked files with filesystem events, and when the last event arrived
+    pending: HashMap<PathBuf, Instant>,
+}
+
+/// Watches files the session has touched for changes made by someone else
+pub struct WorkspaceWatcher {
+    workspace: PathBuf,
+    watcher: RecommendedWatcher,
+    watched_dirs: HashSet<PathBuf>,
+    state: Arc<Mutex<WatchState>>,
+}
+
+impl WorkspaceWatcher {
+    /// Create a watcher for a workspace (nothing is watched until files are tracked)
+    pub fn new(workspace: impl Into<PathBuf>) -> notify::Result<Self> {
+        let state = Arc::new(Mutex::new(WatchState::default()));
+        let state_for_events = state.clone();
+
+        let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            let event = match event {
+                Ok(event) => event,
+                Err(e) => {
+                    warn!("File watcher error: {}", e);
+                    return;
+                }
+            };
+            if event.kind.is_access() {
+                return;
+            }
+
+            let mut state = state_for_events.lock().unwrap();
+            let now = Instant::now();
+            for path in event.paths {
+                if state.tracked.contains_key(&path) {
+                    state.pending.insert(path, now);
+                }
+            }
+        })?;
+
+        Ok(Self {
+            workspace: workspace.into(),
+            watcher,
+            watched_dirs: HashSet::new(),
+            state,
+        })
+    }
+
+    /// Track the file touched by a successful tool call, if it's a file tool
+    pub fn track_tool_call(&mut self, tool_name: &str, arguments: &serde_json::Value) {
+        let Some((_, key)) = FILE_TOOLS.iter().find(|(name, _)| *name == tool_name) else {
+            return;
+        };
+        if let Some(path) = arguments[key].as_str() {
+            self.track(&self.workspace.join(path));
+        }
+    }
+
+    /// Record the current state of a file as seen by the session and watch it
+    pub fn track(&mut self, path: &Path) {
+        let Some(path) = self.resolve(path) else {
+            return;
+        };
+        if is_ignored(&path) {
+            return;
+        }
+
+        if let Some(dir) = path.parent()
+            && !self.watched_dirs.contains(dir)
+        {
+            match self.watcher.watch(dir, RecursiveMode::NonRecursive) {
+                Ok(()) => {
+                    self.watched_dirs.insert(dir.to_path_buf());
+                }
+                Err(e) => {
+                    debug!("Not watching {}: {}", dir.display(), e);
+                    return;
+                }
+            }
+        }
+
+        let mut state = self.state.lock().unwrap();
+        state.pending.remove(&path);
+        state.tracked.insert(path.clone(), fingerprint(&path));
+    }
+
+    /// Take tracked files that changed on disk since the session last saw them
+    ///
+    /// Changes still inside the debounce window are kept for the next call.
+    /// Reported files are re-baselined so each change is reported once.
+    pub fn take_changes(&self) -> Vec<ExternalChange> {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+
+        let settled: Vec<PathBuf> = state
+            .pending
+            .iter()
+            .filter(|(_, at)| now.duration_since(**at) >= DEBOUNCE)
+            .map(|(path, _)| path.clone())
+            .collect();
+
+        let mut changes = Vec::new();
+        for path in settled {
+            state.pending.remove(&path);
+            let current = fingerprint(&path);
+            if let Some(seen) = state.tracked.get_mut(&path)
+                && *seen != current
+            {
+                *seen = current;
+                changes.push(ExternalChange { deleted: current.is_none(), path });
+            }
+        }
+        changes.sort_by(|a, b| a.path.cmp(&b.path));
+        changes
+    }
+
+    /// Path relative to the workspace for display
+    pub fn display_path(&self, path: &Path) -> String {
+        let workspace = dunce::canonicalize(&self.workspace).unwrap_or_else(|_| self.workspace.clone());
+        path.strip_prefix(&workspace)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .to_string()
+    }
+
+    /// Canonical path of a file whose parent exists (the file itself may not)
+    fn resolve(&self, path: &Path) -> Option<PathBuf> {
+        let parent = dunce::canonicalize(path.parent()?).ok()?;
+        Some(parent.join(path.file_name()?))
+    }
+}
+
+/// Build the system reminder telling the model about external changes
+pub fn external_change_reminder(changes: &[ExternalChange], watcher: &WorkspaceWatcher) -> String {
+    changes
+        .iter()
+        .map(|change| {
+            let path = watcher.display_path(&change.path);
+            if change.deleted {
+                format!("NOTE: {} was deleted outside this session since you last read it.", path)
+            } else {
+                format!(
+                    "NOTE: {} was modified outside this session since you last read it — re-read before editing.",
+                    path
+                )
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn is_ignored(path: &Path) -> bool {
+    path.components().any(|c| match c {
+        Component::Normal(name) => IGNORED_DIRS.iter().any(|d| name == *d),
+        _ => false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Poll until the watcher reports changes (events arrive asynchronously)
+    fn wait_for_changes(watcher: &WorkspaceWatcher) -> Vec<ExternalChange> {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            let changes = watcher.take_changes();
+            if !changes.is_empty() || Instant::now() > deadline {
+                return changes;
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+    }
+
+    #[test]
+    fn test_reports_external_modification_once() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/lib.rs"), "fn a() {}").unwrap();
+
+        let mut watcher = WorkspaceWatcher::new(dir.path()).unwrap();
+        watcher.track_tool_call("Read", &serde_json::json!({"file_path": "src/lib.rs"}));
+
+        std::fs::write(dir.path().join("src/lib.rs"), "fn a() {}\nfn b() {}").unwrap();
+
+        let changes = wait_for_changes(&watcher);
+        assert_eq!(changes.len(), 1);
+        assert!(!changes[0].deleted);
+        assert_eq!(watcher.display_path(&changes[0].path), Path::new("src").join("lib.rs").to_string_lossy());
+
+        let reminder = external_change_reminder(&changes, &watcher);
+        assert!(reminder.contains("lib.rs was modified outside this session"));
+        assert!(reminder.contains("re-read before editing"));
+
+        // Already reported
+        std::thread::sleep(DEBOUNCE);
+        assert!(watcher.take_changes().is_empty());
+    }
+
+    #[test]
+    fn test_own_writes_are_not_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("notes.txt");
+        std::fs::write(&file, "v1").unwrap();
+
+        let mut watcher = WorkspaceWatcher::new(dir.path()).unwrap();
+        watcher.track(&file);
+
+        // The session writes, then records the new state (as finalize does)
+        std::fs::write(&file, "version two").unwrap();
+        watcher.track_tool_call("Write", &serde_json::json!({"file_path": file.to_string_lossy()}));
+
+        std::thread::sleep(DEBOUNCE + Duration::from_millis(200));
+        assert!(watcher.take_changes().is_empty());
+    }
+
+    #[test]
+    fn test_reports_deletion() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("gone.txt");
+        std::fs::write(&file, "bye").unwrap();
+
+        let mut watcher = WorkspaceWatcher::new(dir.path()).unwrap();
+        watcher.track(&file);
+        std::fs::remove_file(&file).unwrap();
+
+        let changes = wait_for_changes(&watcher);
+        assert_eq!(changes.len(), 1);
+        assert!(changes[0].deleted);
+        assert!(external_change_reminder(&changes, &watcher).contains("gone.txt was deleted"));
+    }
+
+    #[test]
+    fn test_untracked_and_ignored_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join(".git")).unwrap();
+        let head = dir.path().join(".git/HEAD");
+        std::fs::write(&head, "ref").unwrap();
+        std::fs::write(dir.path().join("other.txt"), "x").unwrap();
+
+        let mut watcher = WorkspaceWatcher::new(dir.path()).unwrap();
+        watcher.track(&head);
+        watcher.track_tool_call("Bash", &serde_json::json!({"command": "cat other.txt"}));
+        assert!(watcher.watched_dirs.is_empty());
+
+        std::fs::write(&head, "ref: refs/heads/main").unwrap();
+        std::fs::write(dir.path().join("other.txt"), "y").unwrap();
+        std::thread::sleep(DEBOUNCE + Duration::from_millis(200));
+        assert!(watcher.take_changes().is_empty());
+    }
+}
@@ -0,0 +1,129 @@
+//! Session branching - start a new session from a point in another's history
+//!
+//! A fork copies the source history up to and including a message, repairs
+//! tool calls the cut left unanswered, and records where it came from
+//! (`parent_id`, `fork_point`) so frontends can show a branch indicator.
+
+use parking_lot::RwLock;
+
+use crate::error::{Error, Result};
+use crate::provider::{ChatMessage, ChatRole};
+use super::arena::{MemoryStats, ResultArena};
+use super::persistence::{SavedSession, SESSION_FORMAT_VERSION};
+use super::pins::MessagePins;
+use super::tool_usage::ToolUsageStats;
+
+/// A running session's history as of its last finished turn
+///
+/// Shared between the agent loop (which updates it) and the manager (which
+/// forks from it and reports its memory use and tool calls), like
+/// `OutputCounters`.
+#[derive(Debug, Default)]
+pub struct SessionSnapshot {
+    /// The history, large tool results as references into the arena
+    saved: RwLock<Option<(SavedSession, ResultArena)>>,
+    memory: RwLock<MemoryStats>,
+    tool_usage: RwLock<ToolUsageStats>,
+}
+
+impl SessionSnapshot {
+    /// Replace the snapshot
+    pub fn update(&self, saved: SavedSession) {
+        self.update_interned(saved, ResultArena::default());
+    }
+
+    /// Replace the snapshot with a history whose large tool results are
+    /// references into `arena`
+    pub fn update_interned(&self, saved: SavedSession, arena: ResultArena) {
+        *self.saved.write() = Some((saved, arena));
+    }
+
+    /// The latest snapshot, if the session has recorded one
+    pub fn get(&self) -> Option<SavedSession> {
+        let (mut saved, arena) = self.saved.read().clone()?;
+        arena.resolve_messages(&mut saved.messages);
+        Some(saved)
+    }
+
+    /// Record what the session's history holds
+    pub fn set_memory_stats(&self, stats: MemoryStats) {
+        *self.memory.write() = stats;
+    }
+
+    /// What the session's history held at the end of its last turn
+    pub fn memory_stats(&self) -> MemoryStats {
+        *self.memory.read()
+    }
+
+    /// Record the session's tool calls
+    pub fn set_tool_usage(&self, usage: ToolUsageStats) {
+        *self.tool_usage.write() = usage;
+    }
+
+    /// The session's tool calls as of the end of its last turn
+    pub fn tool_usage(&self) -> ToolUsageStats {
+        self.tool_usage.read().clone()
+    }
+}
+
+/// Build a fork of `source` that ends with message `at_message` (inclusive)
+///
+/// Tool calls left without results by the cut get synthesized results, so
+/// the fork can be sent to the provider as is.
+pub fn fork_saved_session(source: &SavedSession, at_message: usize, new_id: &str) -> Result<SavedSession> {
+    if at_message >= source.messages.len() {
+        return Err(Error::Agent(format!(
+            "Session {} has {} messages; cannot fork at message {}",
+            source.id,
+            source.messages.len(),
+            at_message
+        )));
+    }
+
+    let now = chrono::Utc::now();
+    let mut fork = SavedSession {
+        id: new_id.to_string(),
+        name: format!("Fork of {}", source.name),
+        // A fallback title is still replaced once the fork completes a turn
+        title_source: source.title_source,
+        format_version: SESSION_FORMAT_VERSION,
+        provider: source.provider.clone(),
+        model: source.model.clone(),
+        messages: source.messages[..=at_message].to_vec(),
+        todos: source.todos.clone(),
+        created_at: now,
+        updated_at: now,
+        parent_id: Some(source.id.clone()),
+        fork_point: Some(at_message),
+        recovered: false,
+        pinned: false,
+        pinned_messages: Vec::new(),
+        template: source.template.clone(),
+        // Counts start with the fork's own calls
+        tool_usage: Default::default(),
+    };
+    fork.repair_history();
+    // Pins of the messages the fork copied carry over
+    let mut pins = MessagePins::from_keys(source.pinned_messages.iter().cloned());
+    pins.retain(&fork.messages);
+    fork.pinned_messages = pins.keys();
+    Ok(fork)
+}
+
+/// The fork point that drops the last `turns_back` user messages
+///
+/// Returns the index of the message just before the `turns_back`-th user
+/// message from the end, or `None` if there aren't that many user messages
+/// or nothing comes before the one found.
+pub fn fork_point_before_user_turn(messages: &[ChatMessage], turns_back: usize) -> Option<usize> {
+    if turns_back == 0 {
+        return messages.len().checked_sub(1);
+    }
+    let (index, _) = messages
+        .iter()
+        .enumerate()
+        .rev()
+        .filter(|(_, m)| m.role == ChatRole::User)
+        .nth(turns_back - 1)?;
+    index.checked_sub(1)
+}
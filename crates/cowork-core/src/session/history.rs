@@ -0,0 +1,199 @@
+//! Conversation history repair
+//!
+//! Providers reject a conversation whose assistant `tool_use` blocks are not
+//! answered by matching `tool_result` blocks (and vice versa). A crash,
+//! a cancellation bug, or an old saved session can leave such gaps, which
+//! would otherwise brick the session. `repair_history` closes them:
+//!
+//! - tool calls with no result get a synthesized one right after the
+//!   results that were recorded
+//! - results whose id matches no tool call of the preceding assistant
+//!   message (or that answer a call twice) are dropped
+
+use std::collections::HashSet;
+use tracing::warn;
+
+use crate::provider::{tool_result_message, ChatMessage, ChatRole, ContentPart, MessageContent};
+
+/// Result content synthesized for a tool call that never got one
+pub const INTERRUPTED_TOOL_RESULT: &str = "(no result recorded — session was interrupted)";
+
+/// What `repair_history` changed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HistoryRepair {
+    /// Call ids that got a synthesized result
+    pub synthesized: Vec<String>,
+    /// Call ids whose orphaned results were dropped
+    pub dropped: Vec<String>,
+}
+
+impl HistoryRepair {
+    /// Whether the history was already valid
+    pub fn is_empty(&self) -> bool {
+        self.synthesized.is_empty() && self.dropped.is_empty()
+    }
+}
+
+/// Pair every tool call with exactly one result, logging each fix
+pub fn repair_history(messages: &mut Vec<ChatMessage>) -> HistoryRepair {
+    let mut repair = HistoryRepair::default();
+
+    // Fast path: nothing to do when every call is answered once, in place
+    if is_consistent(messages) {
+        return repair;
+    }
+
+    let mut repaired = Vec::with_capacity(messages.len());
+    // Calls of the latest assistant message and which of them were answered
+    let mut open: Vec<String> = Vec::new();
+    let mut answered: HashSet<String> = HashSet::new();
+
+    for msg in messages.drain(..) {
+        if msg.role == ChatRole::Tool {
+            let ChatMessage { role, content, options } = msg;
+            let had_parts = !content.is_empty();
+            let parts: Vec<ContentPart> = content
+                .into_parts()
+                .into_iter()
+                .filter(|part| match part {
+                    ContentPart::ToolResponse(response) => {
+                        let valid = open.contains(&response.call_id) && answered.insert(response.call_id.clone());
+                        if !valid {
+                            warn!("Dropping tool result {} with no matching tool call", response.call_id);
+                            repair.dropped.push(response.call_id.clone());
+                        }
+                        valid
+                    }
+                    _ => true,
+                })
+                .collect();
+            if had_parts && parts.is_empty() {
+                continue;
+            }
+            repaired.push(ChatMessage { role, content: MessageContent::from_parts(parts), options });
+            continue;
+        }
+
+        close_open_calls(&mut repaired, &open, &answered, &mut repair);
+        open = if msg.role == ChatRole::Assistant {
+            msg.content.tool_calls().into_iter().map(|tc| tc.call_id.clone()).collect()
+        } else {
+            Vec::new()
+        };
+        answered.clear();
+        repaired.push(msg);
+    }
+    close_open_calls(&mut repaired, &open, &answered, &mut repair);
+
+    *messages = repaired;
+    repair
+}
+
+/// Synthesize results for the open calls that were not answered
+fn close_open_calls(
+    repaired: &mut Vec<ChatMessage>,
+    open: &[String],
+    answered: &HashSet<String>,
+    repair: &mut HistoryRepair,
+) {
+    for call_id in open.iter().filter(|id| !answered.contains(*id)) {
+        warn!("Synthesizing result for tool call {} with no recorded result", call_id);
+        repaired.push(tool_result_message(call_id, INTERRUPTED_TOOL_RESULT));
+        repair.synthesized.push(call_id.clone());
+    }
+}
+
+/// Whether every tool call is answered exactly once by the tool messages
+/// that directly follow it, with no stray results
+fn is_consistent(messages: &[ChatMessage]) -> bool {
+    let mut open: HashSet<&str> = HashSet::new();
+    for msg in messages {
+        if msg.role == ChatRole::Tool {
+            for response in msg.content.tool_responses() {
+                if !open.remove(response.call_id.as_str()) {
+                    return false;
+                }
+            }
+            continue;
+        }
+        if !open.is_empty() {
+            return false;
+        }
+        if msg.role == ChatRole::Assistant {
+            open = msg.content.tool_calls().into_iter().map(|tc| tc.call_id.as_str()).collect();
+        }
+    }
+    open.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::{assistant_with_tool_calls, message_text_content, ToolCall};
+
+    fn call(id: &str) -> ToolCall {
+        ToolCall {
+            call_id: id.to_string(),
+            fn_name: "Read".to_string(),
+            fn_arguments: serde_json::json!({"file_path": "a.txt"}),
+            thought_signatures: None,
+        }
+    }
+
+    fn result_ids(messages: &[ChatMessage]) -> Vec<String> {
+        messages
+            .iter()
+            .flat_map(|m| m.content.tool_responses())
+            .map(|r| r.call_id.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_history_untouched() {
+        let mut messages = vec![
+            ChatMessage::user("hi"),
+            assistant_with_tool_calls(None, vec![call("a"), call("b")]),
+            tool_result_message("a", "one"),
+            tool_result_message("b", "two"),
+            ChatMessage::assistant("done"),
+        ];
+        let before = messages.len();
+        assert!(repair_history(&mut messages).is_empty());
+        assert_eq!(messages.len(), before);
+    }
+
+    #[test]
+    fn test_synthesizes_missing_results_in_place() {
+        let mut messages = vec![
+            ChatMessage::user("hi"),
+            assistant_with_tool_calls(None, vec![call("a"), call("b")]),
+            tool_result_message("a", "one"),
+            ChatMessage::user("next"),
+        ];
+        let repair = repair_history(&mut messages);
+
+        assert_eq!(repair.synthesized, vec!["b"]);
+        assert_eq!(result_ids(&messages), vec!["a", "b"]);
+        // The synthesized result sits before the next user message
+        assert_eq!(messages[3].role, ChatRole::Tool);
+        assert_eq!(message_text_content(&messages[3]), INTERRUPTED_TOOL_RESULT);
+        assert_eq!(messages[4].role, ChatRole::User);
+    }
+
+    #[test]
+    fn test_drops_orphaned_and_duplicate_results() {
+        let mut messages = vec![
+            ChatMessage::user("hi"),
+            tool_result_message("ghost", "boo"),
+            assistant_with_tool_calls(None, vec![call("a")]),
+            tool_result_message("a", "one"),
+            tool_result_message("a", "again"),
+        ];
+        let repair = repair_history(&mut messages);
+
+        assert_eq!(repair.dropped, vec!["ghost", "a"]);
+        assert!(repair.synthesized.is_empty());
+        assert_eq!(messages.len(), 3);
+        assert_eq!(result_ids(&messages), vec!["a"]);
+    }
+}
@@ -0,0 +1,618 @@
+//! Importing sessions from other assistants
+//!
+//! Claude Code keeps each session as a JSONL file (one entry per line,
+//! usually under `~/.claude/projects/<project>/`). `convert_claude_code_session`
+//! turns one into a `SavedSession`: user and assistant messages become chat
+//! messages, `tool_use` and `tool_result` blocks become tool calls and
+//! results, and the session's summary, if it has one, becomes its title.
+//!
+//! Tools with a different name here are renamed (`TOOL_RENAMES`). Calls to
+//! tools Cowork doesn't have are kept as text, with their results, so the
+//! history can still be resumed. Entries that can't be used - lines that
+//! aren't JSON, subagent and meta messages, thinking blocks - are left out
+//! and listed in the file's report rather than failing the import.
+//!
+//! `import_sessions` writes the converted sessions to the sessions
+//! directory, where `list_saved_sessions` finds them, as
+//! `claude-code-<session id>`. A session imported before is not written again.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use genai::chat::Binary;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::{Error, Result};
+use crate::provider::{ChatMessage, ContentPart, MessageContent, ToolCall, ToolResponse};
+use super::history::repair_history;
+use super::persistence::{get_sessions_dir, write_session_to, SavedSession, SESSION_FORMAT_VERSION};
+use super::title::{fallback_title, TitleSource};
+
+/// Prefix of the IDs of sessions imported from Claude Code
+pub const CLAUDE_CODE_ID_PREFIX: &str = "claude-code-";
+
+/// Arguments renamed along with a tool, old name first
+type ArgRenames = &'static [(&'static str, &'static str)];
+
+/// Claude Code tools with another name here, and their arguments to rename
+const TOOL_RENAMES: &[(&str, &str, ArgRenames)] = &[
+    ("KillBash", "KillShell", &[]),
+    ("NotebookRead", "Read", &[("notebook_path", "file_path")]),
+];
+
+/// Line numbers listed for each kind of skipped entry before the rest are counted
+const MAX_LISTED_LINES: usize = 5;
+
+/// An assistant whose sessions can be imported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ImportSource {
+    ClaudeCode,
+}
+
+impl FromStr for ImportSource {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "claude-code" => Ok(Self::ClaudeCode),
+            _ => Err(Error::Config(format!("Unknown import source {}; expected claude-code", s))),
+        }
+    }
+}
+
+impl fmt::Display for ImportSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ClaudeCode => write!(f, "Claude Code"),
+        }
+    }
+}
+
+/// A converted session and the entries left out of it
+#[derive(Debug, Clone)]
+pub struct ConvertedSession {
+    pub session: SavedSession,
+    /// Entries left out, by kind, with their line numbers
+    pub skipped: Vec<String>,
+}
+
+/// What happened to one imported file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportOutcome {
+    /// Written to the sessions directory (or would be, in a dry run)
+    Imported,
+    /// A session with its ID is already saved
+    AlreadyImported,
+    /// Couldn't be read or had no messages
+    Failed,
+}
+
+/// The import of one file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileImport {
+    pub path: PathBuf,
+    pub outcome: ImportOutcome,
+    /// ID of the saved session, unless the file failed
+    pub session_id: Option<String>,
+    pub name: String,
+    pub message_count: usize,
+    pub updated_at: Option<DateTime<Utc>>,
+    /// Entries left out, by kind, with their line numbers
+    pub skipped: Vec<String>,
+    /// Why the file failed
+    pub error: Option<String>,
+}
+
+impl FileImport {
+    fn failed(path: &Path, error: impl fmt::Display) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            outcome: ImportOutcome::Failed,
+            session_id: None,
+            name: String::new(),
+            message_count: 0,
+            updated_at: None,
+            skipped: Vec::new(),
+            error: Some(error.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for FileImport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let id = self.session_id.as_deref().unwrap_or_default();
+        match self.outcome {
+            ImportOutcome::Imported => write!(
+                f,
+                "{} \"{}\" - {} messages, updated {} (from {})",
+                id,
+                self.name,
+                self.message_count,
+                self.updated_at.map(|t| t.format("%Y-%m-%d").to_string()).unwrap_or_default(),
+                self.path.display()
+            )?,
+            ImportOutcome::AlreadyImported => write!(f, "{}: already imported as {}", self.path.display(), id)?,
+            ImportOutcome::Failed => write!(
+                f,
+                "{}: failed: {}",
+                self.path.display(),
+                self.error.as_deref().unwrap_or_default()
+            )?,
+        }
+        for skipped in &self.skipped {
+            write!(f, "\n    skipped {}", skipped)?;
+        }
+        Ok(())
+    }
+}
+
+/// What an import did, or would do in a dry run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub source: ImportSource,
+    pub dry_run: bool,
+    /// Every file found, in path order
+    pub files: Vec<FileImport>,
+}
+
+impl ImportReport {
+    /// Files with this outcome
+    pub fn count(&self, outcome: ImportOutcome) -> usize {
+        self.files.iter().filter(|file| file.outcome == outcome).count()
+    }
+}
+
+impl fmt::Display for ImportReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verb = if self.dry_run { "Would import" } else { "Imported" };
+        write!(
+            f,
+            "{} {} {} sessions ({} already imported, {} failed)",
+            verb,
+            self.count(ImportOutcome::Imported),
+            self.source,
+            self.count(ImportOutcome::AlreadyImported),
+            self.count(ImportOutcome::Failed)
+        )?;
+        for file in &self.files {
+            write!(f, "\n  {}", file)?;
+        }
+        Ok(())
+    }
+}
+
+/// Import sessions into the sessions directory
+///
+/// `paths` are session files or directories searched for them (`.jsonl`
+/// files for Claude Code). `known_tools` are the tools calls may keep
+/// (see `convert_claude_code_session`). With `dry_run` nothing is written;
+/// the report lists what would be. A file that can't be imported is
+/// reported as failed without stopping the others.
+pub fn import_sessions(
+    source: ImportSource,
+    paths: &[PathBuf],
+    known_tools: &[String],
+    dry_run: bool,
+) -> Result<ImportReport> {
+    import_sessions_to(source, paths, known_tools, dry_run, &get_sessions_dir()?)
+}
+
+/// `import_sessions` into `dir`
+pub fn import_sessions_to(
+    source: ImportSource,
+    paths: &[PathBuf],
+    known_tools: &[String],
+    dry_run: bool,
+    dir: &Path,
+) -> Result<ImportReport> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            find_session_files(path, &mut files)?;
+        } else {
+            files.push(path.clone());
+        }
+    }
+    files.sort();
+    files.dedup();
+
+    let mut report = ImportReport { source, dry_run, files: Vec::new() };
+    for path in files {
+        report.files.push(import_file(source, &path, known_tools, dry_run, dir));
+    }
+    Ok(report)
+}
+
+fn import_file(source: ImportSource, path: &Path, known_tools: &[String], dry_run: bool, dir: &Path) -> FileImport {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => return FileImport::failed(path, e),
+    };
+    let converted = match source {
+        ImportSource::ClaudeCode => convert_claude_code_session(&content, known_tools),
+    };
+    let ConvertedSession { mut session, skipped } = match converted {
+        Ok(converted) => converted,
+        Err(e) => return FileImport::failed(path, e),
+    };
+    // Sessions without a recorded ID are named after their file
+    if session.id == CLAUDE_CODE_ID_PREFIX {
+        let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        session.id = format!("{}{}", CLAUDE_CODE_ID_PREFIX, sanitize_id(&stem));
+    }
+
+    let mut import = FileImport {
+        path: path.to_path_buf(),
+        outcome: ImportOutcome::Imported,
+        session_id: Some(session.id.clone()),
+        name: session.name.clone(),
+        message_count: session.messages.len(),
+        updated_at: Some(session.updated_at),
+        skipped,
+        error: None,
+    };
+    if dir.join(format!("{}.json", session.id)).exists() {
+        import.outcome = ImportOutcome::AlreadyImported;
+    } else if !dry_run && let Err(e) = write_session_to(&session, dir) {
+        return FileImport::failed(path, e);
+    }
+    import
+}
+
+/// Every `.jsonl` file under `dir`
+fn find_session_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            find_session_files(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "jsonl") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Keep the characters allowed in a session ID
+fn sanitize_id(id: &str) -> String {
+    id.chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .collect()
+}
+
+/// Entries left out of a conversion, by kind
+#[derive(Default)]
+struct Skipped(BTreeMap<String, Vec<usize>>);
+
+impl Skipped {
+    fn add(&mut self, kind: impl Into<String>, line: usize) {
+        self.0.entry(kind.into()).or_default().push(line);
+    }
+
+    /// Each kind with its line numbers, e.g. `thinking blocks (lines 4, 9)`
+    fn into_notes(self) -> Vec<String> {
+        self.0
+            .into_iter()
+            .map(|(kind, lines)| {
+                let mut listed: Vec<String> = lines.iter().take(MAX_LISTED_LINES).map(|n| n.to_string()).collect();
+                if lines.len() > MAX_LISTED_LINES {
+                    listed.push(format!("… {} in all", lines.len()));
+                }
+                let label = if lines.len() == 1 { "line" } else { "lines" };
+                format!("{} ({} {})", kind, label, listed.join(", "))
+            })
+            .collect()
+    }
+}
+
+/// Convert a Claude Code session file (JSONL) to a saved session
+///
+/// Calls to tools not in `known_tools`, after renaming, are kept as text
+/// along with their results. Fails only if the file has no messages.
+pub fn convert_claude_code_session(jsonl: &str, known_tools: &[String]) -> Result<ConvertedSession> {
+    let mut converter = ClaudeCodeConverter::new(known_tools);
+    for (index, line) in jsonl.lines().enumerate() {
+        if !line.trim().is_empty() {
+            converter.entry(line, index + 1);
+        }
+    }
+    converter.finish()
+}
+
+struct ClaudeCodeConverter<'a> {
+    known_tools: &'a [String],
+    messages: Vec<ChatMessage>,
+    /// The assistant message being assembled: its API message ID and parts
+    /// (Claude Code writes each content block of a message on its own line)
+    assistant: Option<(String, Vec<ContentPart>)>,
+    /// Calls kept as text, by call ID, with the tool's name
+    text_calls: BTreeMap<String, String>,
+    skipped: Skipped,
+    session_id: Option<String>,
+    summary: Option<String>,
+    first_message: Option<String>,
+    model: Option<String>,
+    created_at: Option<DateTime<Utc>>,
+    updated_at: Option<DateTime<Utc>>,
+}
+
+impl<'a> ClaudeCodeConverter<'a> {
+    fn new(known_tools: &'a [String]) -> Self {
+        Self {
+            known_tools,
+            messages: Vec::new(),
+            assistant: None,
+            text_calls: BTreeMap::new(),
+            skipped: Skipped::default(),
+            session_id: None,
+            summary: None,
+            first_message: None,
+            model: None,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    fn entry(&mut self, line: &str, n: usize) {
+        let entry: Value = match serde_json::from_str(line) {
+            Ok(entry) => entry,
+            Err(_) => return self.skipped.add("malformed JSON", n),
+        };
+        match entry["type"].as_str() {
+            Some("summary") => {
+                if let Some(summary) = entry["summary"].as_str().filter(|s| !s.trim().is_empty()) {
+                    self.summary = Some(summary.trim().to_string());
+                }
+                return;
+            }
+            Some("user" | "assistant") => {}
+            Some(kind) => return self.skipped.add(format!("{} entries", kind), n),
+            None => return self.skipped.add("entries without a type", n),
+        }
+        if entry["isSidechain"].as_bool() == Some(true) {
+            return self.skipped.add("subagent messages", n);
+        }
+        if entry["isMeta"].as_bool() == Some(true) {
+            return self.skipped.add("meta messages", n);
+        }
+        let message = &entry["message"];
+        if !message.is_object() {
+            return self.skipped.add("entries without a message", n);
+        }
+
+        if self.session_id.is_none() {
+            self.session_id = entry["sessionId"].as_str().map(sanitize_id);
+        }
+        if let Some(time) = entry["timestamp"]
+            .as_str()
+            .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+            .map(|t| t.with_timezone(&Utc))
+        {
+            self.created_at.get_or_insert(time);
+            self.updated_at = Some(time);
+        }
+
+        if entry["type"] == "assistant" {
+            self.assistant_message(message, n);
+        } else {
+            self.user_message(message, n);
+        }
+    }
+
+    fn user_message(&mut self, message: &Value, n: usize) {
+        self.flush_assistant();
+        let blocks = match &message["content"] {
+            Value::String(text) => vec![serde_json::json!({ "type": "text", "text": text })],
+            Value::Array(blocks) => blocks.clone(),
+            _ => return self.skipped.add("entries without a message", n),
+        };
+
+        let mut parts = Vec::new();
+        let mut results = Vec::new();
+        for block in &blocks {
+            match block["type"].as_str().unwrap_or_default() {
+                "text" => {
+                    let text = block["text"].as_str().unwrap_or_default();
+                    if is_local_command(text) {
+                        self.skipped.add("local command messages", n);
+                    } else if !text.trim().is_empty() {
+                        if self.first_message.is_none() {
+                            self.first_message = Some(text.to_string());
+                        }
+                        parts.push(ContentPart::from_text(text));
+                    }
+                }
+                "image" => match image_part(block) {
+                    Some(image) => parts.push(image),
+                    None => self.skipped.add("images that aren't inline data", n),
+                },
+                "tool_result" => {
+                    let call_id = block["tool_use_id"].as_str().unwrap_or_default().to_string();
+                    let mut content = self.result_text(&block["content"], n);
+                    if block["is_error"].as_bool() == Some(true) {
+                        content = format!("Error: {}", content);
+                    }
+                    match self.text_calls.get(&call_id) {
+                        Some(tool) => parts.push(ContentPart::from_text(format!(
+                            "[Result of the Claude Code tool {}]\n{}",
+                            tool, content
+                        ))),
+                        None => results.push(ToolResponse::new(call_id, content)),
+                    }
+                }
+                other => self.skipped.add(format!("{} blocks", other), n),
+            }
+        }
+
+        // Results answer the preceding assistant message, so they come first
+        self.messages.extend(results.into_iter().map(ChatMessage::from));
+        if !parts.is_empty() {
+            self.messages.push(ChatMessage::user(MessageContent::from_parts(parts)));
+        }
+    }
+
+    fn assistant_message(&mut self, message: &Value, n: usize) {
+        if let Some(model) = message["model"].as_str().filter(|m| !m.starts_with('<')) {
+            self.model = Some(model.to_string());
+        }
+        let id = message["id"].as_str().unwrap_or_default().to_string();
+        if id.is_empty() || self.assistant.as_ref().is_none_or(|(current, _)| *current != id) {
+            self.flush_assistant();
+            self.assistant = Some((id, Vec::new()));
+        }
+
+        let blocks = match &message["content"] {
+            Value::String(text) => vec![serde_json::json!({ "type": "text", "text": text })],
+            Value::Array(blocks) => blocks.clone(),
+            _ => return self.skipped.add("entries without a message", n),
+        };
+        let mut parts = Vec::new();
+        for block in &blocks {
+            match block["type"].as_str().unwrap_or_default() {
+                "text" => {
+                    let text = block["text"].as_str().unwrap_or_default();
+                    if !text.trim().is_empty() {
+                        parts.push(ContentPart::from_text(text));
+                    }
+                }
+                "thinking" | "redacted_thinking" => self.skipped.add("thinking blocks", n),
+                "tool_use" => parts.push(self.tool_call(block)),
+                other => self.skipped.add(format!("{} blocks", other), n),
+            }
+        }
+        if let Some((_, current)) = &mut self.assistant {
+            current.extend(parts);
+        }
+    }
+
+    /// A tool call, renamed for this tool set, or as text if there's no such tool here
+    fn tool_call(&mut self, block: &Value) -> ContentPart {
+        let call_id = block["id"].as_str().unwrap_or_default().to_string();
+        let name = block["name"].as_str().unwrap_or_default();
+        let mut arguments = block["input"].clone();
+
+        let mut fn_name = name.to_string();
+        if let Some((_, renamed, args)) = TOOL_RENAMES.iter().find(|(from, _, _)| *from == name) {
+            fn_name = renamed.to_string();
+            if let Some(object) = arguments.as_object_mut() {
+                for (from, to) in args.iter() {
+                    if let Some(value) = object.remove(*from) {
+                        object.insert(to.to_string(), value);
+                    }
+                }
+            }
+        }
+
+        if !call_id.is_empty() && self.known_tools.contains(&fn_name) {
+            return ContentPart::ToolCall(ToolCall {
+                call_id,
+                fn_name,
+                fn_arguments: arguments,
+                thought_signatures: None,
+            });
+        }
+        self.text_calls.insert(call_id, name.to_string());
+        ContentPart::from_text(format!("[Called the Claude Code tool {} with {}]", name, arguments))
+    }
+
+    /// A tool result's content as text; images can't be kept in a result
+    fn result_text(&mut self, content: &Value, n: usize) -> String {
+        match content {
+            Value::String(text) => text.clone(),
+            Value::Array(blocks) => blocks
+                .iter()
+                .filter_map(|block| match block["type"].as_str() {
+                    Some("text") => block["text"].as_str().map(str::to_string),
+                    Some(other) => {
+                        self.skipped.add(format!("{} blocks in tool results", other), n);
+                        None
+                    }
+                    None => None,
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Value::Null => String::new(),
+            other => other.to_string(),
+        }
+    }
+
+    fn flush_assistant(&mut self) {
+        if let Some((_, parts)) = self.assistant.take()
+            && !parts.is_empty()
+        {
+            self.messages.push(ChatMessage::assistant(MessageContent::from_parts(parts)));
+        }
+    }
+
+    fn finish(mut self) -> Result<ConvertedSession> {
+        self.flush_assistant();
+        if self.messages.is_empty() {
+            return Err(Error::Workspace("No messages to import".to_string()));
+        }
+
+        let repair = repair_history(&mut self.messages);
+        let mut skipped = self.skipped.into_notes();
+        if !repair.dropped.is_empty() {
+            skipped.push(format!("{} tool results without a call", repair.dropped.len()));
+        }
+        if !repair.synthesized.is_empty() {
+            skipped.push(format!("{} tool calls without a result, marked interrupted", repair.synthesized.len()));
+        }
+
+        let (name, title_source) = match self.summary {
+            Some(summary) => (summary, TitleSource::Generated),
+            None => (
+                self.first_message
+                    .as_deref()
+                    .and_then(fallback_title)
+                    .unwrap_or_else(|| "Imported Claude Code session".to_string()),
+                TitleSource::Fallback,
+            ),
+        };
+        let now = Utc::now();
+        let session = SavedSession {
+            id: format!("{}{}", CLAUDE_CODE_ID_PREFIX, self.session_id.unwrap_or_default()),
+            name,
+            title_source,
+            format_version: SESSION_FORMAT_VERSION,
+            provider: self.model.as_ref().map(|_| "anthropic".to_string()),
+            model: self.model,
+            messages: self.messages,
+            todos: Vec::new(),
+            created_at: self.created_at.unwrap_or(now),
+            updated_at: self.updated_at.unwrap_or(now),
+            parent_id: None,
+            fork_point: None,
+            recovered: false,
+            pinned: false,
+            pinned_messages: Vec::new(),
+            template: None,
+            tool_usage: Default::default(),
+        };
+        Ok(ConvertedSession { session, skipped })
+    }
+}
+
+/// Messages Claude Code records for its own slash commands
+fn is_local_command(text: &str) -> bool {
+    let text = text.trim_start();
+    ["<command-name>", "<command-message>", "<local-command-stdout>", "<local-command-stderr>"]
+        .iter()
+        .any(|tag| text.starts_with(tag))
+}
+
+/// An inline (base64) image block as a content part
+fn image_part(block: &Value) -> Option<ContentPart> {
+    let source = &block["source"];
+    if source["type"] != "base64" {
+        return None;
+    }
+    let media_type = source["media_type"].as_str()?;
+    let data = source["data"].as_str()?;
+    Some(ContentPart::Binary(Binary::from_base64(media_type, data, None)))
+}
@@ -0,0 +1,179 @@
+//! Request limiting shared by all sessions
+//!
+//! One `RequestLimiter` is created per `SessionManager` and handed to every
+//! agent loop (and its subagents). Each LLM call holds a `RequestPermit`,
+//! which caps how many requests are in flight at once and how many start per
+//! minute for each provider.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::Instant;
+
+use crate::config::LimitsConfig;
+
+/// Window for the per-provider request rate
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Caps concurrent LLM requests and per-provider requests per minute
+#[derive(Debug)]
+pub struct RequestLimiter {
+    /// Request slots (`None` = no concurrency limit)
+    slots: Option<Arc<Semaphore>>,
+    /// Requests allowed per provider within the window (0 = unlimited)
+    requests_per_minute: usize,
+    /// Start times of recent requests, by provider
+    recent: Mutex<HashMap<String, VecDeque<Instant>>>,
+}
+
+/// Held for the duration of an LLM request
+#[derive(Debug)]
+pub struct RequestPermit {
+    _slot: Option<OwnedSemaphorePermit>,
+}
+
+impl RequestLimiter {
+    /// Create a limiter from the `[limits]` config section
+    pub fn new(limits: &LimitsConfig) -> Self {
+        Self {
+            slots: (limits.max_concurrent_requests > 0)
+                .then(|| Arc::new(Semaphore::new(limits.max_concurrent_requests))),
+            requests_per_minute: limits.requests_per_minute,
+            recent: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Get a permit right away, or `None` if the caller would have to wait
+    pub fn try_acquire(&self, provider_id: &str) -> Option<RequestPermit> {
+        let slot = match &self.slots {
+            Some(slots) => Some(slots.clone().try_acquire_owned().ok()?),
+            None => None,
+        };
+        self.record_request(provider_id).ok()?;
+        Some(RequestPermit { _slot: slot })
+    }
+
+    /// Wait for a request slot and for the provider's rate to allow another request
+    ///
+    /// The slot is given back while waiting for the rate, so a throttled
+    /// provider doesn't hold up requests to the others.
+    pub async fn acquire(&self, provider_id: &str) -> RequestPermit {
+        loop {
+            let slot = match &self.slots {
+                Some(slots) => Some(
+                    slots
+                        .clone()
+                        .acquire_owned()
+                        .await
+                        .expect("request semaphore is never closed"),
+                ),
+                None => None,
+            };
+            match self.record_request(provider_id) {
+                Ok(()) => return RequestPermit { _slot: slot },
+                Err(wait) => {
+                    drop(slot);
+                    tokio::time::sleep(wait).await;
+                }
+            }
+        }
+    }
+
+    /// Record a request start if the provider is under its rate, else return
+    /// how long until the oldest request leaves the window
+    fn record_request(&self, provider_id: &str) -> Result<(), Duration> {
+        if self.requests_per_minute == 0 {
+            return Ok(());
+        }
+
+        let now = Instant::now();
+        let mut recent = self.recent.lock();
+        let starts = recent.entry(provider_id.to_string()).or_default();
+        while starts
+            .front()
+            .is_some_and(|start| now.duration_since(*start) >= RATE_WINDOW)
+        {
+            starts.pop_front();
+        }
+
+        if starts.len() < self.requests_per_minute {
+            starts.push_back(now);
+            Ok(())
+        } else {
+            Err(RATE_WINDOW - now.duration_since(starts[0]))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(max_concurrent_requests: usize, requests_per_minute: usize) -> LimitsConfig {
+        LimitsConfig {
+            max_concurrent_requests,
+            requests_per_minute,
+            ..LimitsConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_cap() {
+        let limiter = RequestLimiter::new(&limits(2, 0));
+
+        let first = limiter.try_acquire("anthropic").unwrap();
+        let _second = limiter.try_acquire("openai").unwrap();
+        assert!(limiter.try_acquire("anthropic").is_none());
+
+        drop(first);
+        assert!(limiter.try_acquire("anthropic").is_some());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_per_provider() {
+        let limiter = RequestLimiter::new(&limits(0, 2));
+
+        assert!(limiter.try_acquire("anthropic").is_some());
+        assert!(limiter.try_acquire("anthropic").is_some());
+        assert!(limiter.try_acquire("anthropic").is_none());
+        // Other providers have their own budget
+        assert!(limiter.try_acquire("openai").is_some());
+
+        // Waits until the oldest request leaves the window
+        let start = Instant::now();
+        let _permit = limiter.acquire("anthropic").await;
+        assert!(start.elapsed() >= RATE_WINDOW);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_throttled_provider_leaves_slots_to_others() {
+        let limiter = Arc::new(RequestLimiter::new(&limits(1, 1)));
+        drop(limiter.acquire("anthropic").await);
+
+        // Waits out anthropic's rate...
+        let throttled = tokio::spawn({
+            let limiter = limiter.clone();
+            async move {
+                let _permit = limiter.acquire("anthropic").await;
+            }
+        });
+        tokio::task::yield_now().await;
+
+        // ...without holding the only slot
+        let permit = tokio::time::timeout(Duration::from_secs(1), limiter.acquire("openai")).await;
+        assert!(permit.is_ok());
+        assert!(!throttled.is_finished());
+        drop(permit);
+        throttled.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_zero_disables_limits() {
+        let limiter = RequestLimiter::new(&limits(0, 0));
+        let permits: Vec<_> = (0..100).map(|_| limiter.try_acquire("anthropic").unwrap()).collect();
+        assert_eq!(permits.len(), 100);
+    }
+}
@@ -1,20 +1,50 @@
 //! Session Manager for multi-session orchestration
 //!
 //! Manages multiple concurrent agent sessions, routing inputs and collecting outputs.
+//!
+//! Sessions left without input or output for the idle timeout can be
+//! suspended (see [`SessionManager::suspend_idle_sessions`]): the agent loop
+//! saves the session and exits, releasing its tools, language servers, MCP
+//! servers and background shells. The next message resumes it from the saved
+//! state.
+//!
+//! A session with `isolation = "worktree"` works in its own git worktree
+//! (see [`Worktree`]); [`SessionManager::merge_worktree`] merges it back.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc;
-use tracing::info;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
 
 use super::agent_loop::AgentLoop;
+use super::arena::MemoryStats;
+use super::capabilities::Capabilities;
+use super::fork::{fork_saved_session, SessionSnapshot};
+use super::limits::RequestLimiter;
+use super::output::{self, OutputCounters, SessionStats, OUTPUT_CHANNEL_CAPACITY};
+use super::persistence::{load_session, SavedSession};
+use super::queue::{InputQueue, Submission};
 use super::types::{SessionConfig, SessionId, SessionInput, SessionOutput};
-use crate::error::Result;
+use super::title::SessionTitle;
+use super::transcript::{SequencedOutput, Transcript, TranscriptSync};
+use super::workspace_locks::{FileLockInfo, WorkspaceLocks};
+use super::worktree::{Isolation, Worktree, WorktreeSummary};
+use crate::config::{PromptSystemConfig, RemoteTarget};
+use crate::context::ContextGatherer;
+use crate::error::{Error, Result};
 use crate::mcp_manager::McpServerManager;
-use crate::orchestration::SystemPrompt;
-use crate::prompt::TemplateVars;
+use crate::orchestration::tool_profile::FULL_PROFILE;
+use crate::orchestration::{SystemPrompt, ToolProfile};
+use crate::prompt::{ComponentRegistry, PromptPipeline, TemplateVars};
+use crate::provider::UsageStore;
+use crate::templates::SessionTemplate;
+use crate::tools::remote::RemoteWorkspace;
+use crate::tools::interaction::Clipboard;
 use crate::ConfigManager;
 
 /// Type alias for the output receiver
@@ -28,16 +58,91 @@ enum ConfigSource {
     Fixed(Box<SessionConfig>),
 }
 
+/// Whether a session is running or suspended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionState {
+    Active,
+    /// Saved and shut down for being idle; the next message resumes it
+    Suspended,
+}
+
+/// A session this manager created, for listing
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionSummary {
+    pub session_id: SessionId,
+    pub state: SessionState,
+    /// Never suspended for being idle
+    pub keep_alive: bool,
+    /// The session's title, once it has one (see `session::title`)
+    pub title: Option<String>,
+    /// Name of the tool profile the session offers the model
+    pub tool_profile: String,
+}
+
+/// When a running session was last active
+struct Activity {
+    /// Last time input arrived, output was sent or a turn was in flight
+    last_active: Instant,
+    /// Outputs sent as of `last_active`
+    events_sent: u64,
+}
+
+/// A session suspended for being idle
+struct Suspension {
+    since: Instant,
+    /// The agent loop, which saves the session before it exits
+    task: Option<JoinHandle<()>>,
+}
+
 /// Manages multiple concurrent agent sessions
 pub struct SessionManager {
     /// Map of session ID to input sender
     sessions: super::types::SessionRegistry,
+    /// Map of session ID to its user message queue
+    queues: RwLock<HashMap<SessionId, Arc<InputQueue>>>,
     /// Channel for all session outputs (session_id, output)
     output_tx: mpsc::Sender<(SessionId, SessionOutput)>,
     /// Workspace path for building session config
     workspace_path: PathBuf,
+    /// Other workspace roots given to new local sessions' file tools
+    additional_roots: RwLock<Vec<PathBuf>>,
     /// Config source - from disk or fixed
     config_source: ConfigSource,
+    /// Request limits shared by every session's LLM calls
+    request_limiter: Arc<RequestLimiter>,
+    /// Locks on the files sessions are writing, shared by every session
+    workspace_locks: Arc<WorkspaceLocks>,
+    /// Maximum number of open sessions (0 = unlimited)
+    max_sessions: usize,
+    /// Usage store shared by sessions built from disk config
+    usage_store: Option<Arc<UsageStore>>,
+    /// Map of session ID to its output counts
+    output_counters: RwLock<HashMap<SessionId, Arc<OutputCounters>>>,
+    /// Map of session ID to its recent outputs (kept while the session is suspended)
+    transcripts: RwLock<HashMap<SessionId, Arc<Transcript>>>,
+    /// Outputs each transcript keeps
+    transcript_events: usize,
+    /// Map of session ID to its title (kept while the session is suspended)
+    titles: RwLock<HashMap<SessionId, Arc<SessionTitle>>>,
+    /// Map of session ID to its tool profile (kept while the session is suspended)
+    tool_profiles: RwLock<HashMap<SessionId, ToolProfile>>,
+    /// Map of session ID to the config it was created with (copied by forks)
+    configs: RwLock<HashMap<SessionId, SessionConfig>>,
+    /// Suspend sessions idle for this long (None = never)
+    idle_timeout: Option<Duration>,
+    /// Map of session ID to when it was last active
+    activity: RwLock<HashMap<SessionId, Activity>>,
+    /// Map of session ID to its agent loop task
+    tasks: RwLock<HashMap<SessionId, JoinHandle<()>>>,
+    /// Map of suspended session ID to its suspension
+    suspended: RwLock<HashMap<SessionId, Suspension>>,
+    /// Sessions that are never suspended for being idle
+    keep_alive: RwLock<HashSet<SessionId>>,
+    /// Map of isolated session ID to its worktree
+    worktrees: RwLock<HashMap<SessionId, Worktree>>,
+    /// System clipboard given to sessions built from disk config
+    clipboard: Option<Arc<dyn Clipboard>>,
 }
 
 impl SessionManager {
@@ -46,14 +151,35 @@ impl SessionManager {
     /// Config is read fresh from disk when each session is created.
     /// Use this for Tauri where settings can change between sessions.
     pub fn new(workspace_path: PathBuf) -> (Self, OutputReceiver) {
-        let (output_tx, output_rx) = mpsc::channel(256);
+        let (output_tx, output_rx) = mpsc::channel(OUTPUT_CHANNEL_CAPACITY);
         let sessions = Arc::new(RwLock::new(HashMap::new()));
+        // Limits are shared state, so they are fixed when the manager is created
+        let limits = ConfigManager::new().unwrap_or_default().config().limits.clone();
 
         let manager = Self {
             sessions,
+            queues: RwLock::new(HashMap::new()),
             output_tx,
             workspace_path,
+            additional_roots: RwLock::new(Vec::new()),
             config_source: ConfigSource::FromDisk,
+            request_limiter: Arc::new(RequestLimiter::new(&limits)),
+            workspace_locks: Arc::new(WorkspaceLocks::default()),
+            max_sessions: limits.max_sessions,
+            usage_store: Some(Arc::new(UsageStore::open_default())),
+            output_counters: RwLock::new(HashMap::new()),
+            transcripts: RwLock::new(HashMap::new()),
+            transcript_events: limits.transcript_events,
+            titles: RwLock::new(HashMap::new()),
+            tool_profiles: RwLock::new(HashMap::new()),
+            configs: RwLock::new(HashMap::new()),
+            idle_timeout: idle_timeout(limits.idle_suspend_mins),
+            activity: RwLock::new(HashMap::new()),
+            tasks: RwLock::new(HashMap::new()),
+            suspended: RwLock::new(HashMap::new()),
+            keep_alive: RwLock::new(HashSet::new()),
+            worktrees: RwLock::new(HashMap::new()),
+            clipboard: None,
         };
 
         (manager, output_rx)
@@ -63,32 +189,162 @@ impl SessionManager {
     ///
     /// Use this for CLI where config is set at startup and doesn't change.
     pub fn with_config(config: SessionConfig) -> (Self, OutputReceiver) {
-        let (output_tx, output_rx) = mpsc::channel(256);
+        let (output_tx, output_rx) = mpsc::channel(OUTPUT_CHANNEL_CAPACITY);
         let sessions = Arc::new(RwLock::new(HashMap::new()));
         let workspace_path = config.workspace_path.clone();
+        let request_limiter = Arc::new(RequestLimiter::new(&config.limits));
+        let max_sessions = config.limits.max_sessions;
+        let idle_timeout = idle_timeout(config.limits.idle_suspend_mins);
+        let transcript_events = config.limits.transcript_events;
 
         let manager = Self {
             sessions,
+            queues: RwLock::new(HashMap::new()),
             output_tx,
             workspace_path,
+            additional_roots: RwLock::new(Vec::new()),
             config_source: ConfigSource::Fixed(Box::new(config)),
+            request_limiter,
+            workspace_locks: Arc::new(WorkspaceLocks::default()),
+            max_sessions,
+            // A fixed config carries its own usage store, if any
+            usage_store: None,
+            output_counters: RwLock::new(HashMap::new()),
+            transcripts: RwLock::new(HashMap::new()),
+            transcript_events,
+            titles: RwLock::new(HashMap::new()),
+            tool_profiles: RwLock::new(HashMap::new()),
+            configs: RwLock::new(HashMap::new()),
+            idle_timeout,
+            activity: RwLock::new(HashMap::new()),
+            tasks: RwLock::new(HashMap::new()),
+            suspended: RwLock::new(HashMap::new()),
+            keep_alive: RwLock::new(HashSet::new()),
+            worktrees: RwLock::new(HashMap::new()),
+            clipboard: None,
         };
 
         (manager, output_rx)
     }
 
+    /// Override the idle timeout from `[limits] idle_suspend_mins` (None = never)
+    pub fn set_idle_timeout(&mut self, timeout: Option<Duration>) {
+        self.idle_timeout = timeout;
+    }
+
+    /// Give sessions the ClipboardWrite tool, writing to `clipboard`
+    ///
+    /// A fixed config carries its own clipboard, if any.
+    pub fn set_clipboard(&mut self, clipboard: Arc<dyn Clipboard>) {
+        self.clipboard = Some(clipboard);
+    }
+
+    /// Set the workspace roots besides the workspace path
+    ///
+    /// Applies to sessions created afterwards from disk config; a remote
+    /// workspace has no other roots.
+    pub fn set_additional_roots(&self, roots: Vec<PathBuf>) {
+        *self.additional_roots.write() = roots;
+    }
+
+    /// The workspace roots besides the workspace path
+    pub fn additional_roots(&self) -> Vec<PathBuf> {
+        self.additional_roots.read().clone()
+    }
+
     /// Push a message to a session
     ///
     /// If the session doesn't exist, it will be created automatically.
     /// Returns an error if the message couldn't be sent.
     pub async fn push_message(&self, session_id: &str, input: SessionInput) -> Result<()> {
-         self.get_or_create_session(session_id).await?
-            .send(input)
+        self.send_message(session_id, input).await.map(|_| ())
+    }
+
+    /// Push a message to a session, reporting where it landed in the queue
+    ///
+    /// Returns `Some(position)` when a user message has to wait behind the turn
+    /// in flight (1 = next to run), `None` when it runs right away or the input
+    /// isn't a user message. Returns an error if the session's queue policy
+    /// rejects messages while busy.
+    pub async fn send_message(&self, session_id: &str, input: SessionInput) -> Result<Option<usize>> {
+        let tx = self.get_or_create_session(session_id).await?;
+        self.touch(session_id);
+
+        let (user_input, overrides) = match input {
+            SessionInput::UserMessage { content, overrides } => ((content, vec![]), overrides),
+            SessionInput::UserMessageWithImages { content, images, overrides } => ((content, images), overrides),
+            input => {
+                if let SessionInput::SetToolProfile { profile } = &input {
+                    self.tool_profiles.write().insert(session_id.to_string(), profile.clone());
+                }
+                if let Some(transcript) = self.transcripts.read().get(session_id) {
+                    transcript.observe_input(&input);
+                }
+                tx.send(input)
+                    .await
+                    .map_err(|e| Error::Agent(format!("Failed to send input: {}", e)))?;
+                return Ok(None);
+            }
+        };
+
+        let Some(queue) = self.queues.read().get(session_id).cloned() else {
+            return Err(Error::Agent(format!("Session {} has stopped", session_id)));
+        };
+        match queue.submit(user_input, overrides).await {
+            Submission::Rejected => Err(Error::Agent(
+                "Agent is busy; message rejected".to_string(),
+            )),
+            submission => Ok(submission.position()),
+        }
+    }
+
+    /// Approve a call waiting for a reviewer (`SessionOutput::ApprovalDelegated`)
+    ///
+    /// `approver` identifies the reviewer in the session's audit log. Unlike
+    /// `push_message`, this never starts a session.
+    pub async fn approve_delegated(&self, session_id: &str, tool_call_id: &str, approver: &str) -> Result<()> {
+        self.send_reviewer_input(session_id, approver, SessionInput::approve_delegated(tool_call_id, approver.trim()))
             .await
-            .map_err(|e| crate::error::Error::Agent(format!("Failed to send input: {}", e)))
     }
 
-    /// Create a new session with the given ID
+    /// Refuse a call waiting for a reviewer; see `approve_delegated`
+    pub async fn reject_delegated(
+        &self,
+        session_id: &str,
+        tool_call_id: &str,
+        approver: &str,
+        reason: Option<String>,
+    ) -> Result<()> {
+        self.send_reviewer_input(
+            session_id,
+            approver,
+            SessionInput::reject_delegated(tool_call_id, approver.trim(), reason),
+        )
+        .await
+    }
+
+    async fn send_reviewer_input(&self, session_id: &str, approver: &str, input: SessionInput) -> Result<()> {
+        if approver.trim().is_empty() {
+            return Err(Error::Agent("A reviewer must identify themselves to settle a delegated approval".to_string()));
+        }
+        if !self.has_session(session_id) {
+            return Err(Error::Agent(format!("Session {} is not running", session_id)));
+        }
+        self.push_message(session_id, input).await
+    }
+
+    /// Create a session with the given ID, if it isn't already running
+    ///
+    /// Returns `Error::SessionLimit` if the manager already has the maximum
+    /// number of sessions open.
+    pub async fn create_session(&self, session_id: &str) -> Result<()> {
+        self.get_or_create_session(session_id).await.map(|_| ())
+    }
+
+    /// Get a session's input sender, creating the session if needed
+    ///
+    /// A suspended session is resumed from its saved state, with a
+    /// `Resumed` notice after `Ready`.
     async fn get_or_create_session(
         &self,
         session_id: &str
@@ -98,17 +354,76 @@ impl SessionManager {
             return Ok(tx.clone());
         }
 
-        info!("Creating new session: {}", session_id);
-
-        // Create input channel for this session
-        let (input_tx, input_rx) = mpsc::channel(256);
-
         // Get config based on source
         let mut config = match &self.config_source {
             ConfigSource::FromDisk => self.build_session_config(),
             ConfigSource::Fixed(c) => (**c).clone(),
         };
+
+        let suspension = self.suspended.write().remove(session_id);
+        let Some(suspension) = suspension else {
+            return self.spawn_session(session_id, config).await;
+        };
+
+        // The loop saves the session as it exits
+        if let Some(task) = suspension.task {
+            let _ = task.await;
+        }
+        let suspended_secs = suspension.since.elapsed().as_secs();
+        info!("Resuming session {} after {}s suspended", session_id, suspended_secs);
+        config.resume = load_session(session_id)?;
+        if let Some(profile) = self.tool_profiles.read().get(session_id) {
+            config.tool_profile = profile.clone();
+        }
+        let tx = self.spawn_session(session_id, config).await?;
+        let _ = self
+            .output_tx
+            .send((session_id.to_string(), SessionOutput::resumed(suspended_secs)))
+            .await;
+        Ok(tx)
+    }
+
+    /// Start an agent loop for a session with the given config
+    async fn spawn_session(
+        &self,
+        session_id: &str,
+        mut config: SessionConfig,
+    ) -> Result<mpsc::Sender<SessionInput>> {
+        // Subagents share the registry, so count the sessions this manager created
+        if self.max_sessions > 0 && self.queues.read().len() >= self.max_sessions {
+            return Err(Error::SessionLimit(self.max_sessions));
+        }
+
+        info!("Creating new session: {}", session_id);
+
+        // Create input channel for this session
+        let (input_tx, input_rx) = mpsc::channel(256);
+
         config.session_registry = Some(self.sessions.clone());
+        config.request_limiter = Some(self.request_limiter.clone());
+        config.workspace_locks = Some(self.workspace_locks.clone());
+        if let Some(store) = &self.usage_store {
+            config.usage_store = Some(store.clone());
+        }
+        if let Some(clipboard) = &self.clipboard {
+            config.clipboard = Some(clipboard.clone());
+        }
+        let counters = Arc::new(OutputCounters::default());
+        config.output_counters = Some(counters.clone());
+        let title = Arc::new(SessionTitle::default());
+        config.title = Some(title.clone());
+        // A resumed (or forked) session can be forked before its first turn
+        let snapshot = Arc::new(SessionSnapshot::default());
+        if let Some(saved) = &config.resume {
+            snapshot.update(saved.clone());
+        }
+        config.snapshot = Some(snapshot);
+        // Forks copy the original workspace and get a worktree of their own
+        let stored_config = config.clone();
+        let worktree = match config.isolation {
+            Isolation::None => None,
+            Isolation::Worktree => Some(isolate(session_id, &mut config).await?),
+        };
 
         let agent_loop = AgentLoop::new(
             session_id.to_string(),
@@ -118,8 +433,28 @@ impl SessionManager {
         )
         .await?;
 
-        // Spawn the agent loop
-        tokio::spawn(agent_loop.run());
+        self.queues
+            .write()
+            .insert(session_id.to_string(), agent_loop.input_queue());
+        self.output_counters.write().insert(session_id.to_string(), counters);
+        self.titles.write().insert(session_id.to_string(), title);
+        self.tool_profiles.write().insert(session_id.to_string(), stored_config.tool_profile.clone());
+        self.configs.write().insert(session_id.to_string(), stored_config);
+        self.activity.write().insert(
+            session_id.to_string(),
+            Activity {
+                last_active: Instant::now(),
+                events_sent: 0,
+            },
+        );
+
+        // Spawn the agent loop; whatever it still holds is released when it exits
+        let (locks, id) = (self.workspace_locks.clone(), session_id.to_string());
+        let task = tokio::spawn(async move {
+            agent_loop.run().await;
+            locks.release_session(&id);
+        });
+        self.tasks.write().insert(session_id.to_string(), task);
 
         // Register the session
         self.sessions
@@ -131,21 +466,403 @@ impl SessionManager {
             .output_tx
             .send((session_id.to_string(), SessionOutput::ready()))
             .await;
+        if let Some(worktree) = worktree {
+            let path = worktree.workspace().display().to_string();
+            let _ = self
+                .output_tx
+                .send((session_id.to_string(), SessionOutput::worktree(path, &worktree.branch)))
+                .await;
+            self.worktrees.write().insert(session_id.to_string(), worktree);
+        }
 
         Ok(input_tx)
     }
 
+    /// Create a session from a template, and send it the template's opening
+    /// message with `args` filled in
+    ///
+    /// The template's settings override the ones the session would otherwise
+    /// start with. Returns an error if the session already exists or the
+    /// template's settings can't be applied.
+    pub async fn create_session_from_template(
+        &self,
+        session_id: &str,
+        template: &SessionTemplate,
+        args: &str,
+    ) -> Result<()> {
+        if self.has_session(session_id) || self.is_suspended(session_id) {
+            return Err(Error::Agent(format!("Session {} already exists", session_id)));
+        }
+        let mut config = match &self.config_source {
+            ConfigSource::FromDisk => self.build_session_config(),
+            ConfigSource::Fixed(c) => (**c).clone(),
+        };
+        template.apply(&mut config, ConfigManager::new().unwrap_or_default().config())?;
+        info!("Starting session {} from template {}", session_id, template.name);
+        self.spawn_session(session_id, config).await?;
+
+        if let Some(message) = template.opening_message(args) {
+            self.send_message(session_id, SessionInput::user_message(message)).await?;
+        }
+        Ok(())
+    }
+
+    /// Fork a session: start a new one whose history is the source's up to
+    /// and including message `at_message`
+    ///
+    /// A running source is forked from its history as of its last finished
+    /// turn, and its config (including approval settings) is copied; a
+    /// stopped one is loaded from disk and gets a fresh config. The source
+    /// is left untouched. Returns the new session's ID.
+    pub async fn fork_session(&self, source_id: &str, at_message: usize) -> Result<SessionId> {
+        let source = match self.session_history(source_id) {
+            Some(saved) => saved,
+            None => load_session(source_id)?
+                .ok_or_else(|| Error::Agent(format!("Session {} not found", source_id)))?,
+        };
+
+        let fork_id = uuid::Uuid::new_v4().to_string();
+        let fork = fork_saved_session(&source, at_message, &fork_id)?;
+        info!(
+            "Forking session {} at message {} into {} ({} messages)",
+            source_id,
+            at_message,
+            fork_id,
+            fork.messages.len()
+        );
+
+        let mut config = match self.configs.read().get(source_id) {
+            Some(config) => config.clone(),
+            None => match &self.config_source {
+                ConfigSource::FromDisk => self.build_session_config(),
+                ConfigSource::Fixed(c) => (**c).clone(),
+            },
+        };
+        if let Some(profile) = self.tool_profiles.read().get(source_id) {
+            config.tool_profile = profile.clone();
+        }
+        config.resume = Some(fork);
+        self.spawn_session(&fork_id, config).await?;
+        Ok(fork_id)
+    }
+
+    /// A running session's history as of its last finished turn
+    pub fn session_history(&self, session_id: &str) -> Option<SavedSession> {
+        self.configs.read().get(session_id)?.snapshot.as_ref()?.get()
+    }
+
+    /// The tool profile a session offers, or would start with if it isn't running yet
+    pub fn tool_profile(&self, session_id: &str) -> ToolProfile {
+        if let Some(profile) = self.tool_profiles.read().get(session_id) {
+            return profile.clone();
+        }
+        match &self.config_source {
+            ConfigSource::FromDisk => ConfigManager::new().unwrap_or_default().config().tools.default_profile(),
+            ConfigSource::Fixed(c) => c.tool_profile.clone(),
+        }
+    }
+
+    /// What new sessions support, for frontends to feature-detect (see
+    /// `session::capabilities`)
+    ///
+    /// Reads the configuration like a new session would, without starting
+    /// its MCP servers or building its prompt.
+    pub fn capabilities(&self) -> Capabilities {
+        match &self.config_source {
+            ConfigSource::FromDisk => {
+                let config_manager = ConfigManager::new().unwrap_or_default();
+                let config = config_manager.config();
+                let mut session_config = SessionConfig::new(self.workspace_path.clone())
+                    .with_web_search_config(config.web_search.clone())
+                    .with_policy(config_manager.policy())
+                    .with_tool_profile(config.tools.default_profile())
+                    .with_stream_mode(config.general.stream_mode);
+                if let Some(provider_config) = config.get_default_provider() {
+                    session_config = session_config.with_provider(&provider_config.provider_type);
+                    if let Some(api_key) = provider_config.get_api_key() {
+                        session_config = session_config.with_api_key(api_key);
+                    }
+                }
+                Capabilities::for_config(&session_config, self.max_sessions)
+            }
+            ConfigSource::Fixed(c) => Capabilities::for_config(c, self.max_sessions),
+        }
+    }
+
     /// Get a clone of the output sender (for testing or special cases)
     pub fn output_sender(&self) -> mpsc::Sender<(SessionId, SessionOutput)> {
         self.output_tx.clone()
     }
 
+    /// Outputs waiting in the output channel (all sessions)
+    pub fn output_queue_depth(&self) -> usize {
+        output::queue_depth(&self.output_tx)
+    }
+
+    /// Output channel, memory and tool usage statistics for a session
+    pub fn session_stats(&self, session_id: &str) -> Option<SessionStats> {
+        let counters = self.output_counters.read().get(session_id)?.clone();
+        let snapshot = self.configs.read().get(session_id).and_then(|config| config.snapshot.clone());
+        let memory = self.session_memory(session_id);
+        let tool_usage = snapshot.as_ref().map(|snapshot| snapshot.tool_usage()).unwrap_or_default();
+        Some(SessionStats {
+            session_id: session_id.to_string(),
+            queue_depth: self.output_queue_depth(),
+            queue_capacity: self.output_tx.max_capacity(),
+            events_sent: counters.sent(),
+            events_dropped: counters.dropped(),
+            memory,
+            tool_usage,
+        })
+    }
+
+    /// Memory each of the manager's sessions holds, for finding the one that
+    /// grows large
+    ///
+    /// Each session's history as of its last finished turn, and the
+    /// outputs its transcript keeps.
+    pub fn memory_stats(&self) -> HashMap<SessionId, MemoryStats> {
+        let mut ids: HashSet<SessionId> = self.configs.read().keys().cloned().collect();
+        ids.extend(self.transcripts.read().keys().cloned());
+        ids.into_iter()
+            .map(|session_id| {
+                let memory = self.session_memory(&session_id);
+                (session_id, memory)
+            })
+            .collect()
+    }
+
+    /// Memory a session's history and transcript hold
+    fn session_memory(&self, session_id: &str) -> MemoryStats {
+        let snapshot = self.configs.read().get(session_id).and_then(|config| config.snapshot.clone());
+        let mut memory = snapshot.map(|snapshot| snapshot.memory_stats()).unwrap_or_default();
+        if let Some(transcript) = self.transcripts.read().get(session_id) {
+            memory += transcript.memory_stats();
+        }
+        memory
+    }
+
+    /// Number an output as the frontend receives it, keeping it in the
+    /// session's transcript
+    ///
+    /// The consumer of the output receiver calls this for each output it
+    /// delivers and passes the sequence number on (see `session::transcript`).
+    pub fn record_output(&self, session_id: &str, output: SessionOutput) -> SequencedOutput {
+        let transcript = self.transcripts.read().get(session_id).cloned();
+        let transcript = transcript.unwrap_or_else(|| {
+            self.transcripts
+                .write()
+                .entry(session_id.to_string())
+                .or_insert_with(|| Arc::new(Transcript::new(self.transcript_events)))
+                .clone()
+        });
+        transcript.record(output)
+    }
+
+    /// A session's recorded outputs after `since_seq`, with the approvals
+    /// and questions it is still waiting on
+    ///
+    /// None if no output of the session was recorded.
+    pub fn transcript(&self, session_id: &str, since_seq: u64) -> Option<TranscriptSync> {
+        Some(self.transcripts.read().get(session_id)?.since(since_seq))
+    }
+
     /// List active session IDs
     pub fn list_sessions(&self) -> Vec<SessionId> {
         let sessions = self.sessions.read();
         sessions.keys().cloned().collect()
     }
 
+    /// The sessions this manager created, running or suspended, sorted by ID
+    pub fn session_summaries(&self) -> Vec<SessionSummary> {
+        let keep_alive = self.keep_alive.read();
+        let titles = self.titles.read();
+        let tool_profiles = self.tool_profiles.read();
+        let summary = |session_id: &SessionId, state| SessionSummary {
+            session_id: session_id.clone(),
+            state,
+            keep_alive: keep_alive.contains(session_id),
+            title: titles.get(session_id).and_then(|title| title.get()),
+            tool_profile: tool_profiles
+                .get(session_id)
+                .map_or_else(|| FULL_PROFILE.to_string(), |profile| profile.name.clone()),
+        };
+        let mut summaries: Vec<SessionSummary> = self
+            .queues
+            .read()
+            .keys()
+            .map(|id| summary(id, SessionState::Active))
+            .chain(self.suspended.read().keys().map(|id| summary(id, SessionState::Suspended)))
+            .collect();
+        summaries.sort_by(|a, b| a.session_id.cmp(&b.session_id));
+        summaries
+    }
+
+    /// Whether a session is suspended
+    pub fn is_suspended(&self, session_id: &str) -> bool {
+        self.suspended.read().contains_key(session_id)
+    }
+
+    /// Exempt a session from (or return it to) idle suspension
+    pub fn set_keep_alive(&self, session_id: &str, keep_alive: bool) {
+        if keep_alive {
+            self.keep_alive.write().insert(session_id.to_string());
+        } else {
+            self.keep_alive.write().remove(session_id);
+        }
+    }
+
+    /// Suspend every session idle for longer than the idle timeout
+    ///
+    /// A session counts as active while it sends output or has a turn in
+    /// flight (which covers pending approvals and questions), so this only
+    /// needs calling periodically. Returns the IDs of the suspended sessions.
+    pub async fn suspend_idle_sessions(&self) -> Vec<SessionId> {
+        let Some(timeout) = self.idle_timeout else {
+            return Vec::new();
+        };
+
+        let idle: Vec<SessionId> = {
+            let queues = self.queues.read();
+            let counters = self.output_counters.read();
+            let keep_alive = self.keep_alive.read();
+            let mut activity = self.activity.write();
+            let now = Instant::now();
+            activity
+                .iter_mut()
+                .filter_map(|(session_id, activity)| {
+                    let events_sent = counters.get(session_id).map_or(0, |c| c.sent());
+                    let busy = queues.get(session_id).is_some_and(|q| !q.is_idle());
+                    if busy || events_sent != activity.events_sent {
+                        activity.last_active = now;
+                        activity.events_sent = events_sent;
+                        return None;
+                    }
+                    let expired = now.duration_since(activity.last_active) >= timeout;
+                    (expired && !keep_alive.contains(session_id)).then(|| session_id.clone())
+                })
+                .collect()
+        };
+
+        let mut suspended = Vec::new();
+        for session_id in idle {
+            if self.suspend_session(&session_id).await {
+                suspended.push(session_id);
+            }
+        }
+        suspended
+    }
+
+    /// Save and shut down an idle session until its next message
+    ///
+    /// Returns false, leaving the session running, if a turn is in flight
+    /// (including one waiting on an approval or question), the session is
+    /// kept alive, or it isn't saved (and so couldn't be resumed).
+    pub async fn suspend_session(&self, session_id: &str) -> bool {
+        let Some(queue) = self.queues.read().get(session_id).cloned() else {
+            return false;
+        };
+        let saved = self.configs.read().get(session_id).is_some_and(|c| c.save_session);
+        if !saved || !queue.is_idle() || self.keep_alive.read().contains(session_id) {
+            return false;
+        }
+
+        info!("Suspending idle session: {}", session_id);
+        queue.suspend();
+        // Dropping the config releases its MCP servers; the loop releases the rest
+        self.remove_session(session_id);
+        let task = self.tasks.write().remove(session_id);
+        self.suspended.write().insert(
+            session_id.to_string(),
+            Suspension {
+                since: Instant::now(),
+                task,
+            },
+        );
+        let _ = self
+            .output_tx
+            .send((session_id.to_string(), SessionOutput::Suspended))
+            .await;
+        true
+    }
+
+    /// The files sessions are writing right now, for debugging conflicts
+    pub fn workspace_locks(&self) -> Vec<FileLockInfo> {
+        self.workspace_locks.list()
+    }
+
+    /// The worktree an isolated session is working in
+    pub fn worktree(&self, session_id: &str) -> Option<Worktree> {
+        self.worktrees.read().get(session_id).cloned()
+    }
+
+    /// What merging an isolated session's worktree would bring into the repository
+    pub async fn worktree_summary(&self, session_id: &str) -> Result<WorktreeSummary> {
+        let worktree = self.require_worktree(session_id)?;
+        run_git(move || worktree.summary()).await
+    }
+
+    /// Merge an isolated session's worktree back and end the session
+    ///
+    /// Fails if a turn is in flight. Uncommitted changes are committed to
+    /// the worktree's branch, which is merged into the branch checked out in
+    /// the repository; the session is then stopped (and saved) and the
+    /// worktree removed. A conflict aborts the merge
+    /// with `Error::MergeConflict`, leaving the session and its worktree as
+    /// they were. Returns what was merged.
+    pub async fn merge_worktree(&self, session_id: &str) -> Result<WorktreeSummary> {
+        let worktree = self.require_worktree(session_id)?;
+        if self.queues.read().get(session_id).is_some_and(|q| !q.is_idle()) {
+            return Err(Error::Agent("Wait for the current turn to finish before merging".to_string()));
+        }
+
+        info!("Merging worktree {} of session {}", worktree.branch, session_id);
+        let summary = run_git(move || worktree.merge()).await?;
+        self.end_isolated_session(session_id).await;
+        Ok(summary)
+    }
+
+    /// Remove a session's worktree and branch, discarding unmerged work
+    ///
+    /// For deleting a session: a running one is stopped first. The worktree
+    /// is found from the workspace if the session isn't running. Returns
+    /// false if the session has no worktree.
+    pub async fn delete_worktree(&self, session_id: &str) -> Result<bool> {
+        let worktree = match self.worktree(session_id) {
+            Some(worktree) => worktree,
+            None => match Worktree::for_session(&self.workspace_path, session_id) {
+                Ok(worktree) if worktree.exists() => worktree,
+                _ => return Ok(false),
+            },
+        };
+        self.end_isolated_session(session_id).await;
+        info!("Removing worktree {} of session {}", worktree.branch, session_id);
+        run_git(move || worktree.remove()).await?;
+        Ok(true)
+    }
+
+    fn require_worktree(&self, session_id: &str) -> Result<Worktree> {
+        self.worktree(session_id)
+            .ok_or_else(|| Error::Workspace(format!("Session {} isn't running in a worktree", session_id)))
+    }
+
+    /// Stop a session and wait for its loop to save it, so its worktree can go
+    async fn end_isolated_session(&self, session_id: &str) {
+        let task = self.tasks.write().remove(session_id);
+        let _ = self.stop_session(session_id);
+        if let Some(task) = task {
+            let _ = task.await;
+        }
+        self.worktrees.write().remove(session_id);
+    }
+
+    /// Mark a session active now
+    fn touch(&self, session_id: &str) {
+        if let Some(activity) = self.activity.write().get_mut(session_id) {
+            activity.last_active = Instant::now();
+        }
+    }
+
     /// Check if a session exists
     pub fn has_session(&self, session_id: &str) -> bool {
         let sessions = self.sessions.read();
@@ -157,7 +874,13 @@ impl SessionManager {
     /// Simply removes the session from the registry, which drops the input sender.
     /// The agent loop will detect the closed channel and save the session before exiting.
     pub fn stop_session(&self, session_id: &str) -> Result<()> {
-        if self.sessions.write().remove(session_id).is_some() {
+        self.tasks.write().remove(session_id);
+        self.suspended.write().remove(session_id);
+        self.keep_alive.write().remove(session_id);
+        self.titles.write().remove(session_id);
+        self.tool_profiles.write().remove(session_id);
+        self.transcripts.write().remove(session_id);
+        if self.remove_session(session_id) {
             info!("Stopped session: {}", session_id);
         }
         Ok(())
@@ -166,9 +889,30 @@ impl SessionManager {
     /// Stop all sessions
     pub fn stop_all(&self) -> Result<()> {
         self.sessions.write().clear();
+        self.queues.write().clear();
+        self.output_counters.write().clear();
+        self.transcripts.write().clear();
+        self.titles.write().clear();
+        self.tool_profiles.write().clear();
+        self.configs.write().clear();
+        self.activity.write().clear();
+        self.tasks.write().clear();
+        self.suspended.write().clear();
+        self.keep_alive.write().clear();
+        self.worktrees.write().clear();
         Ok(())
     }
 
+    /// Drop a running session's state and input sender, returning whether it was running
+    fn remove_session(&self, session_id: &str) -> bool {
+        self.workspace_locks.release_session(session_id);
+        self.queues.write().remove(session_id);
+        self.output_counters.write().remove(session_id);
+        self.configs.write().remove(session_id);
+        self.activity.write().remove(session_id);
+        self.sessions.write().remove(session_id).is_some()
+    }
+
     /// Get the number of active sessions
     pub fn session_count(&self) -> usize {
         let sessions = self.sessions.read();
@@ -188,16 +932,36 @@ impl SessionManager {
             .parse()
             .unwrap_or(crate::ApprovalLevel::Low);
 
+        let policy = config_manager.policy();
         let mut tool_approval_config = crate::ToolApprovalConfig::default();
+        tool_approval_config.apply_policy(&policy);
         tool_approval_config.set_level(approval_level);
+        let tool_approval_config = tool_approval_config
+            .with_delegation(config.approval.delegate_rules(), config.approval.delegate_channel.clone())
+            .with_delegate_timeout(Duration::from_secs(config.approval.delegate_timeout_secs))
+            .with_trusted_hosts(&config.approval.trusted_hosts);
 
         // Build system prompt with workspace context and git info
-        let system_prompt = self.build_system_prompt(default_provider.as_ref().map(|p| p.model.as_str()));
+        let system_prompt = self.build_system_prompt(
+            default_provider.as_ref().map(|p| p.model.as_str()),
+            &config.prompt,
+            config.remote.as_ref(),
+        );
 
         let mut session_config = SessionConfig::new(self.workspace_path.clone())
             .with_approval_config(tool_approval_config)
             .with_web_search_config(config.web_search.clone())
+            .with_limits(config.limits.clone())
+            .with_env_policy(config.env.policy_for(&self.workspace_path))
+            .with_network_policy(config.web.network_policy())
+            .with_policy(policy)
+            .with_tool_profile(config.tools.default_profile())
+            .with_compact_tool_descriptions(config.tools.compact_descriptions)
+            .with_summarizer(config.summarizer.clone(), config.summarizer_provider())
             .with_system_prompt(system_prompt);
+        if let Some(verifier) = config.verify.verifier() {
+            session_config = session_config.with_verifier(Arc::new(verifier));
+        }
 
         if let Some(provider_config) = default_provider {
             session_config = session_config.with_provider(&provider_config.provider_type);
@@ -208,6 +972,11 @@ impl SessionManager {
             if let Some(ref base_url) = provider_config.base_url {
                 session_config = session_config.with_base_url(base_url.clone());
             }
+            if let Some(tiers) = provider_config.model_tiers.clone() {
+                session_config = session_config.with_model_tiers(tiers);
+            }
+            session_config = session_config
+                .with_request_timeout(provider_config.request_timeout(config.general.stream_mode));
         }
 
         // Create MCP server manager from config if servers are configured
@@ -226,18 +995,57 @@ impl SessionManager {
             session_config = session_config.with_mcp_manager(mcp_manager);
         }
 
+        // Load agents, commands, skills and hooks (invalid files are logged here, not when used)
+        let components = ComponentRegistry::for_workspace(&self.workspace_path).unwrap_or_else(|e| {
+            warn!("Failed to load components: {}", e);
+            ComponentRegistry::with_builtins()
+        });
+        session_config = session_config.with_component_registry(Arc::new(components));
+
+        // File and shell tools operate on the remote workspace when one is configured
+        if let Some(target) = config.remote.clone() {
+            info!("Using remote workspace {}", target);
+            session_config = session_config.with_remote(Arc::new(RemoteWorkspace::new(target)));
+        } else {
+            session_config = session_config.with_additional_roots(self.additional_roots());
+        }
+
         // Set streaming mode from config
         session_config = session_config.with_stream_mode(config.general.stream_mode);
+        session_config = session_config.with_watch_files(config.general.watch_files);
+        session_config = session_config.with_mcp_tool_validation(config.general.validate_mcp_tool_args);
+        session_config = session_config.with_auto_memory(config.general.auto_memory);
+        session_config = session_config.with_self_review(config.general.self_review);
+        session_config = session_config.with_self_review_threshold(config.general.self_review_threshold);
+        session_config = session_config.with_strict_tools(config.general.strict_tools);
+        session_config = session_config.with_locale(config.general.locale());
+        session_config = session_config.with_keep_scratch(config.general.keep_scratch);
+        session_config = session_config.with_autosave_interval(autosave_interval(config.general.autosave_secs));
+        session_config = session_config.with_isolation(config.general.isolation);
+        session_config = session_config.with_prompt_pipeline(PromptPipeline::from_config(&config.prompt.input));
 
         session_config
     }
 
-    /// Build system prompt with workspace context and git info
-    fn build_system_prompt(&self, model_info: Option<&str>) -> String {
-        let mut vars = TemplateVars {
-            working_directory: self.workspace_path.display().to_string(),
-            is_git_repo: self.workspace_path.join(".git").exists(),
-            ..Default::default()
+    /// Build system prompt with workspace context, git info, and the `[prompt]` overrides
+    ///
+    /// A remote workspace is described by its location only; its git state
+    /// is for the agent to inspect.
+    fn build_system_prompt(
+        &self,
+        model_info: Option<&str>,
+        prompt_config: &PromptSystemConfig,
+        remote: Option<&RemoteTarget>,
+    ) -> String {
+        let mut vars = match remote {
+            Some(target) => TemplateVars {
+                working_directory: target.to_string(),
+                ..Default::default()
+            },
+            None => TemplateVars {
+                additional_roots: self.additional_roots(),
+                ..TemplateVars::gather(&self.workspace_path)
+            },
         };
 
         // Get git status and branch info if in a repo
@@ -271,12 +1079,58 @@ impl SessionManager {
             vars.model_info = info.to_string();
         }
 
-        SystemPrompt::new()
-            .with_template_vars(vars)
-            .build()
+        let mut prompt = SystemPrompt::new()
+            .with_prompt_config(prompt_config, &self.workspace_path)
+            .with_template_vars(vars);
+        if remote.is_none() {
+            prompt = prompt.with_memory(&ContextGatherer::new(&self.workspace_path).memory_hierarchy());
+        }
+        prompt.build()
     }
 }
 
+/// Run worktree git commands (which block until git finishes) off the async runtime
+async fn run_git<T: Send + 'static>(op: impl FnOnce() -> Result<T> + Send + 'static) -> Result<T> {
+    tokio::task::spawn_blocking(op)
+        .await
+        .map_err(|e| Error::Workspace(format!("Worktree operation failed: {}", e)))?
+}
+
+/// Point a session's config at its worktree, creating the worktree if needed
+///
+/// The system prompt's workspace paths are rewritten to the worktree's and
+/// the model is told where it is working.
+async fn isolate(session_id: &str, config: &mut SessionConfig) -> Result<Worktree> {
+    if config.remote.is_some() {
+        return Err(Error::Config("Worktree isolation needs a local workspace, not a remote one".to_string()));
+    }
+    let (repo_workspace, id) = (config.workspace_path.clone(), session_id.to_string());
+    let worktree = run_git(move || Worktree::open_or_create(&repo_workspace, &id)).await?;
+    let workspace = worktree.workspace();
+    info!("Session {} works in worktree {} ({})", session_id, workspace.display(), worktree.branch);
+
+    if let Some(prompt) = config.system_prompt.as_mut() {
+        *prompt = prompt.replace(&config.workspace_path.display().to_string(), &workspace.display().to_string());
+        prompt.push_str(&format!(
+            "\n\nYou are working in an isolated git worktree on branch {}. Your changes reach the user's \
+             branch only when they review and merge it with /merge, so work freely; commit when a piece of work is done.",
+            worktree.branch
+        ));
+    }
+    config.workspace_path = workspace;
+    Ok(worktree)
+}
+
+/// The autosave interval for `autosave_secs` (0 = only save on exit)
+pub fn autosave_interval(autosave_secs: u64) -> Option<Duration> {
+    (autosave_secs > 0).then(|| Duration::from_secs(autosave_secs))
+}
+
+/// The idle timeout for `idle_suspend_mins` (0 = never)
+fn idle_timeout(idle_suspend_mins: u64) -> Option<Duration> {
+    (idle_suspend_mins > 0).then(|| Duration::from_secs(idle_suspend_mins * 60))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -324,4 +1178,35 @@ mod tests {
         let _sender = manager.output_sender();
         // Just verify we can get a clone of the sender
     }
+
+    #[tokio::test]
+    async fn test_output_queue_depth_and_stats() {
+        let (manager, mut output_rx) = SessionManager::new(test_workspace());
+        assert!(manager.session_stats("missing").is_none());
+
+        manager.output_sender().send(("s1".to_string(), SessionOutput::idle())).await.unwrap();
+        assert_eq!(manager.output_queue_depth(), 1);
+        output_rx.recv().await.unwrap();
+        assert_eq!(manager.output_queue_depth(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_isolate_points_config_at_worktree() {
+        let repo = tempfile::TempDir::new().unwrap();
+        let git = |args: &[&str]| {
+            let status = std::process::Command::new("git").arg("-C").arg(repo.path()).args(args).status().unwrap();
+            assert!(status.success(), "git {:?}", args);
+        };
+        git(&["init", "--quiet"]);
+        git(&["-c", "user.name=Test", "-c", "user.email=test@test.com", "commit", "--quiet", "--allow-empty", "-m", "Initial"]);
+        let workspace = PathBuf::from(repo.path());
+        let mut config = SessionConfig::new(&workspace).with_system_prompt(format!("Work in {}", workspace.display()));
+
+        let worktree = isolate("isolated", &mut config).await.unwrap();
+        assert_eq!(config.workspace_path, worktree.workspace());
+        let prompt = config.system_prompt.unwrap();
+        assert!(prompt.starts_with(&format!("Work in {}", worktree.workspace().display())), "{}", prompt);
+        assert!(prompt.contains("branch cowork/isolated"));
+        worktree.remove().unwrap();
+    }
 }
@@ -0,0 +1,140 @@
+//! `@path` mentions of workspace files in a user's message
+//!
+//! Someone can point the agent at files by mentioning them: `look at
+//! @src/main.rs`, or `@"docs/Release Notes.md"` for paths with spaces. Before
+//! the turn starts, each mention is checked against the workspace: the ones
+//! found are listed to the model in a reminder asking it to read them (their
+//! content isn't attached), and the ones that aren't are reported back.
+//!
+//! A mention starts at an `@` at the start of the message or after
+//! whitespace or an opening bracket, so email addresses and `user@host`
+//! aren't mentions; `\@` and anything in backticks (inline code or fenced
+//! blocks) aren't either.
+
+use std::path::Path;
+
+/// Paths mentioned with `@` in `message`, in order, without duplicates
+pub fn extract_mentions(message: &str) -> Vec<String> {
+    let mut mentions: Vec<String> = Vec::new();
+    let mut in_fence = false;
+    for line in message.lines() {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        for mention in line_mentions(line) {
+            if !mentions.contains(&mention) {
+                mentions.push(mention);
+            }
+        }
+    }
+    mentions
+}
+
+fn line_mentions(line: &str) -> Vec<String> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut mentions = Vec::new();
+    let mut in_code = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '`' {
+            in_code = !in_code;
+            i += 1;
+            continue;
+        }
+        let starts_mention = c == '@'
+            && !in_code
+            && (i == 0 || chars[i - 1].is_whitespace() || matches!(chars[i - 1], '(' | '[' | '{' | '"' | '\''));
+        if !starts_mention {
+            i += 1;
+            continue;
+        }
+
+        i += 1;
+        if chars.get(i) == Some(&'"') {
+            let start = i + 1;
+            let Some(len) = chars[start..].iter().position(|&c| c == '"') else {
+                break;
+            };
+            let path: String = chars[start..start + len].iter().collect();
+            if !path.trim().is_empty() {
+                mentions.push(path);
+            }
+            i = start + len + 1;
+            continue;
+        }
+
+        let start = i;
+        while i < chars.len() && !chars[i].is_whitespace() && !matches!(chars[i], '`' | '"' | '\'' | '@') {
+            i += 1;
+        }
+        let token: String = chars[start..i].iter().collect();
+        let path = token.trim_end_matches(['.', ',', ';', ':', '!', '?', ')', ']', '}']);
+        if !path.is_empty() {
+            mentions.push(path.to_string());
+        }
+    }
+    mentions
+}
+
+/// The mentioned paths that exist in `workspace` (directories included)
+/// and those that don't
+pub fn resolve_mentions(mentions: &[String], workspace: &Path) -> (Vec<String>, Vec<String>) {
+    mentions.iter().cloned().partition(|mention| {
+        let relative = Path::new(mention);
+        !relative.is_absolute() && !mention.split(['/', '\\']).any(|part| part == "..") && workspace.join(relative).exists()
+    })
+}
+
+/// The reminder appended to a message mentioning `paths`
+pub fn mention_reminder(paths: &[String]) -> String {
+    let list: Vec<String> = paths.iter().map(|path| format!("- {}", path)).collect();
+    format!(
+        "<system-reminder>\nThe user mentioned these workspace paths. Read them before answering if they bear on the request:\n{}\n</system-reminder>",
+        list.join("\n")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_mentions() {
+        assert_eq!(extract_mentions("look at @src/main.rs and @Cargo.toml."), vec!["src/main.rs", "Cargo.toml"]);
+        assert_eq!(extract_mentions("(see @docs/a.md), then @docs/a.md again"), vec!["docs/a.md"]);
+        assert_eq!(extract_mentions("open @\"docs/Release Notes.md\" please"), vec!["docs/Release Notes.md"]);
+        assert!(extract_mentions("@").is_empty());
+    }
+
+    #[test]
+    fn test_emails_and_escaped_at_are_not_mentions() {
+        assert!(extract_mentions("mail dev@example.com about it").is_empty());
+        assert!(extract_mentions("write \\@decorator literally").is_empty());
+        assert!(extract_mentions("git push origin HEAD@{1}").is_empty());
+    }
+
+    #[test]
+    fn test_code_is_not_scanned() {
+        assert_eq!(extract_mentions("run `npm i @types/node` then edit @package.json"), vec!["package.json"]);
+        let fenced = "```python\n@app.route(\"/\")\ndef index(): ...\n```\nsee @app.py";
+        assert_eq!(extract_mentions(fenced), vec!["app.py"]);
+    }
+
+    #[test]
+    fn test_resolve_mentions() {
+        let workspace = tempfile::tempdir().unwrap();
+        std::fs::create_dir(workspace.path().join("src")).unwrap();
+        std::fs::write(workspace.path().join("src/lib.rs"), "").unwrap();
+        let mentions: Vec<String> = ["src/lib.rs", "src", "missing.rs", "../outside"].map(String::from).to_vec();
+
+        let (found, missing) = resolve_mentions(&mentions, workspace.path());
+        assert_eq!(found, vec!["src/lib.rs", "src"]);
+        assert_eq!(missing, vec!["missing.rs", "../outside"]);
+        assert!(mention_reminder(&found).contains("- src/lib.rs\n- src\n"));
+    }
+}
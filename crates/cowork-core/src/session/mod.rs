@@ -51,20 +51,88 @@
 
 mod agent_loop;
 pub mod approval;
+mod arena;
+mod attachments;
+mod audit;
+mod capabilities;
 mod chat_session;
+mod explain;
+mod file_watcher;
+mod fork;
+mod history;
+mod import;
+mod limits;
 mod manager;
+mod mentions;
+mod output;
+mod overrides;
 mod persistence;
+mod pins;
+mod queue;
+mod repeat_calls;
+mod replay;
+mod retention;
+mod selection;
+mod self_review;
+mod title;
+mod tool_scheduler;
+mod tool_usage;
+mod transcript;
+mod turn_changes;
 mod types;
+mod workspace_locks;
+mod worktree;
 
 pub use agent_loop::AgentLoop;
-pub use persistence::{get_sessions_dir, list_saved_sessions, load_session, SavedSession};
+pub use attachments::{
+    image_dimensions, sniff_image_type, validate_image, Attachment, AttachmentKind, AttachmentSource, IMAGE_MIME_TYPES,
+    MAX_IMAGE_BYTES,
+};
+pub use audit::{
+    get_audit_log_path, load_audit_log, read_audit_log, remove_audit_log, AuditEvent, AuditLog, AuditRecord,
+    AuditSummary, Decider, AUDIT_LOG_FILE, AUDIT_SCHEMA_VERSION,
+};
+pub use capabilities::{Capabilities, CapabilityLimits, Features, PROTOCOL_VERSION, SESSION_INPUT_TYPES, SESSION_OUTPUT_TYPES};
+pub use explain::{clean_explanation, explanation_request, EXPLAIN_PURPOSE, EXPLAIN_TAIL_MESSAGES};
+pub use fork::{fork_point_before_user_turn, fork_saved_session, SessionSnapshot};
+pub use persistence::{
+    get_attachments_dir, get_autosave_dir, get_scratch_dir, get_sessions_dir, list_saved_sessions, load_session,
+    remove_autosave, remove_scratch_dir, write_autosave, write_session, SavedSession, SESSION_FORMAT_VERSION,
+};
+pub use retention::{
+    delete_saved_session, plan_prune, prune_in_background, prune_saved_sessions, set_session_pinned, set_session_title,
+    PruneReason, PruneReport, PrunedSession,
+};
+pub use self_review::{parse_findings, review_request, DEFAULT_SELF_REVIEW_THRESHOLD, SELF_REVIEW_PURPOSE};
+pub use mentions::{extract_mentions, mention_reminder, resolve_mentions};
+pub use selection::{capped_selection, with_selection, MAX_SELECTION_CHARS, SELECTION_VAR};
+pub use title::{
+    clean_title, fallback_title, generate_title, SessionTitle, TitleSource, FALLBACK_TITLE_CHARS, MAX_TITLE_WORDS,
+};
+pub use replay::{recorded_turns, RecordedTurn, ReplayReport, SessionReplay, TurnComparison, TurnSummary, UsageTotals};
 pub use approval::{
-    approval_channel, ApprovalReceiver, ApprovalRequest, ApprovalResponse,
-    ApprovalSender, QuestionResponse, ToolExecutionContext,
+    approval_channel, progress_channel, ApprovalReceiver, ApprovalRequest, ApprovalResponse,
+    ApprovalSender, Delegation, ProgressReceiver, ProgressSender, QuestionResponse, ToolExecutionContext,
+    ToolProgress,
 };
+pub use arena::{MemoryStats, ResultArena, INTERN_MIN_BYTES, SPILL_MIN_BYTES};
 pub use chat_session::{ChatSession, ToolCallStatus};
-pub use manager::{OutputReceiver, SessionManager};
+pub use history::{repair_history, HistoryRepair, INTERRUPTED_TOOL_RESULT};
+pub use import::{
+    convert_claude_code_session, import_sessions, import_sessions_to, ConvertedSession, FileImport, ImportOutcome,
+    ImportReport, ImportSource, CLAUDE_CODE_ID_PREFIX,
+};
+pub use limits::{RequestLimiter, RequestPermit};
+pub use tool_usage::{tool_usage_report, ToolCounts, ToolUsageReport, ToolUsageStats};
+pub use turn_changes::{FileChange, TurnChangeSet};
+pub use transcript::{SequencedOutput, Transcript, TranscriptSync, DEFAULT_TRANSCRIPT_EVENTS};
+pub use workspace_locks::{FileLock, FileLockInfo, WorkspaceLocks, DEFAULT_LOCK_STALE_AFTER};
+pub use manager::{autosave_interval, OutputReceiver, SessionManager, SessionState, SessionSummary};
+pub use pins::MessagePins;
+pub use output::{OutputCounters, OutputSender, SessionStats, OUTPUT_CHANNEL_CAPACITY};
+pub use overrides::{resolve_model, TurnApproval, TurnOverrides};
 pub use types::{
-    ImageAttachment, QuestionInfo, QuestionOption, SessionConfig, SessionId, SessionInput,
-    SessionOutput, SessionRegistry,
+    ImageAttachment, QuestionInfo, QuestionOption, QueuePolicy, SessionConfig, SessionId,
+    SessionInput, SessionOutput, SessionRegistry, ToolDoneStatus,
 };
+pub use worktree::{Isolation, Worktree, WorktreeSummary, WORKTREES_DIR, WORKTREE_BRANCH_PREFIX};
@@ -0,0 +1,289 @@
+//! Output channel backpressure
+//!
+//! Every session's outputs share one bounded channel to the frontend. When
+//! the frontend stalls (a busy webview, a slow terminal), essential events -
+//! approvals, questions, messages, idle, errors - wait for room, but ephemeral
+//! ones (thinking, text deltas, tool activity, subagent progress) never block
+//! the agent loop: they are sent with `try_send`, held in a short backlog while
+//! the channel is full, and the oldest are dropped when the backlog overflows.
+//! Drops are counted per session and reported in the log at most every
+//! `DROP_REPORT_INTERVAL`.
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TrySendError;
+use tracing::{debug, warn};
+
+use super::arena::MemoryStats;
+use super::tool_usage::ToolUsageStats;
+use super::types::{SessionId, SessionOutput};
+
+/// Capacity of the output channel shared by a manager's sessions
+pub const OUTPUT_CHANNEL_CAPACITY: usize = 1024;
+
+/// Ephemeral outputs held per session while the channel is full
+const EPHEMERAL_BACKLOG: usize = 64;
+
+/// Minimum time between "dropped outputs" log lines for a session
+const DROP_REPORT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Output counts for one session
+#[derive(Debug, Default)]
+pub struct OutputCounters {
+    sent: AtomicU64,
+    dropped: AtomicU64,
+}
+
+impl OutputCounters {
+    /// Outputs delivered to the channel
+    pub fn sent(&self) -> u64 {
+        self.sent.load(Ordering::Relaxed)
+    }
+
+    /// Ephemeral outputs dropped because the frontend fell behind
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// Output channel statistics for a session
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionStats {
+    pub session_id: SessionId,
+    /// Outputs waiting in the shared channel (all sessions)
+    pub queue_depth: usize,
+    pub queue_capacity: usize,
+    /// Outputs this session delivered to the channel
+    pub events_sent: u64,
+    /// Ephemeral outputs this session dropped
+    pub events_dropped: u64,
+    /// What the session's history holds, as of its last finished turn, and
+    /// the outputs its transcript keeps (see `SessionManager::memory_stats`)
+    pub memory: MemoryStats,
+    /// The session's tool calls, as of its last finished turn
+    pub tool_usage: ToolUsageStats,
+}
+
+/// Number of outputs waiting in a channel
+pub fn queue_depth(tx: &mpsc::Sender<(SessionId, SessionOutput)>) -> usize {
+    tx.max_capacity() - tx.capacity()
+}
+
+/// Sends one session's outputs with the backpressure policy
+#[derive(Clone)]
+pub struct OutputSender {
+    session_id: SessionId,
+    tx: mpsc::Sender<(SessionId, SessionOutput)>,
+    counters: Arc<OutputCounters>,
+    backlog: Arc<Mutex<Backlog>>,
+}
+
+#[derive(Default)]
+struct Backlog {
+    events: VecDeque<SessionOutput>,
+    last_report: Option<Instant>,
+    /// Drop count at the last report
+    reported: u64,
+}
+
+impl OutputSender {
+    pub fn new(
+        session_id: impl Into<SessionId>,
+        tx: mpsc::Sender<(SessionId, SessionOutput)>,
+        counters: Arc<OutputCounters>,
+    ) -> Self {
+        Self {
+            session_id: session_id.into(),
+            tx,
+            counters,
+            backlog: Arc::new(Mutex::new(Backlog::default())),
+        }
+    }
+
+    pub fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
+    pub fn counters(&self) -> &Arc<OutputCounters> {
+        &self.counters
+    }
+
+    /// Send an output, blocking only if it is essential
+    pub async fn emit(&self, output: SessionOutput) {
+        if output.is_ephemeral() {
+            self.emit_ephemeral(output);
+        } else {
+            self.emit_critical(output).await;
+        }
+    }
+
+    /// Send an output, waiting for room in the channel
+    ///
+    /// Ephemeral outputs still held back are older than this one; whatever
+    /// doesn't fit now is dropped rather than delivered out of order.
+    pub async fn emit_critical(&self, output: SessionOutput) {
+        {
+            let mut backlog = self.backlog.lock();
+            self.flush(&mut backlog, true);
+        }
+        match self.tx.send((self.session_id.clone(), output)).await {
+            Ok(()) => {
+                self.counters.sent.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(e) => debug!("Output channel closed, dropping {:?}", e.0 .1),
+        }
+    }
+
+    /// Send an output without waiting, dropping the oldest held-back
+    /// ephemeral output if the backlog is full
+    pub fn emit_ephemeral(&self, output: SessionOutput) {
+        let mut backlog = self.backlog.lock();
+        self.flush(&mut backlog, false);
+
+        if backlog.events.is_empty() {
+            match self.tx.try_send((self.session_id.clone(), output)) {
+                Ok(()) => {
+                    self.counters.sent.fetch_add(1, Ordering::Relaxed);
+                    return;
+                }
+                Err(TrySendError::Full((_, output))) => backlog.events.push_back(output),
+                Err(TrySendError::Closed(_)) => return,
+            }
+        } else {
+            backlog.events.push_back(output);
+        }
+
+        if backlog.events.len() > EPHEMERAL_BACKLOG {
+            backlog.events.pop_front();
+            self.record_drops(&mut backlog, 1);
+        }
+    }
+
+    /// Move held-back outputs into the channel while there is room
+    fn flush(&self, backlog: &mut Backlog, discard_rest: bool) {
+        while let Some(output) = backlog.events.pop_front() {
+            match self.tx.try_send((self.session_id.clone(), output)) {
+                Ok(()) => {
+                    self.counters.sent.fetch_add(1, Ordering::Relaxed);
+                }
+                Err(TrySendError::Full((_, output))) => {
+                    backlog.events.push_front(output);
+                    break;
+                }
+                Err(TrySendError::Closed(_)) => {
+                    backlog.events.clear();
+                    return;
+                }
+            }
+        }
+        if discard_rest && !backlog.events.is_empty() {
+            let count = backlog.events.len() as u64;
+            backlog.events.clear();
+            self.record_drops(backlog, count);
+        }
+    }
+
+    fn record_drops(&self, backlog: &mut Backlog, count: u64) {
+        let total = self.counters.dropped.fetch_add(count, Ordering::Relaxed) + count;
+        if backlog.last_report.is_none_or(|at| at.elapsed() >= DROP_REPORT_INTERVAL) {
+            warn!(
+                "Session {}: frontend is falling behind, dropped {} ephemeral outputs ({} total, queue {}/{})",
+                self.session_id,
+                total - backlog.reported,
+                total,
+                queue_depth(&self.tx),
+                self.tx.max_capacity()
+            );
+            backlog.last_report = Some(Instant::now());
+            backlog.reported = total;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn thinking(n: usize) -> SessionOutput {
+        SessionOutput::thinking(format!("step {}", n))
+    }
+
+    #[tokio::test]
+    async fn test_ephemeral_never_blocks_and_drops_oldest() {
+        let (tx, mut rx) = mpsc::channel(4);
+        let sender = OutputSender::new("s1", tx, Arc::default());
+
+        // Nobody is reading: a tool streaming progress must still finish
+        let producer = sender.clone();
+        let total = 4 + EPHEMERAL_BACKLOG + 10;
+        tokio::time::timeout(Duration::from_secs(1), async move {
+            for n in 0..total {
+                producer.emit_ephemeral(thinking(n));
+            }
+        })
+        .await
+        .expect("ephemeral outputs blocked on a full channel");
+
+        assert_eq!(sender.counters().sent(), 4);
+        assert_eq!(sender.counters().dropped(), 10);
+
+        // Once the consumer catches up, the newest held-back outputs follow
+        for n in 0..4 {
+            assert!(matches!(rx.recv().await, Some((_, SessionOutput::Thinking { content })) if content == format!("step {}", n)));
+        }
+        sender.emit_ephemeral(thinking(total));
+        let (_, next) = rx.recv().await.unwrap();
+        assert!(matches!(next, SessionOutput::Thinking { content } if content == format!("step {}", 4 + 10)));
+    }
+
+    #[tokio::test]
+    async fn test_critical_waits_and_discards_stale_backlog() {
+        let (tx, mut rx) = mpsc::channel(2);
+        let sender = OutputSender::new("s1", tx, Arc::default());
+        for n in 0..5 {
+            sender.emit(thinking(n)).await;
+        }
+
+        let critical = tokio::spawn({
+            let sender = sender.clone();
+            async move { sender.emit(SessionOutput::idle()).await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!critical.is_finished(), "critical output should wait for room");
+
+        let mut received = Vec::new();
+        for _ in 0..3 {
+            received.push(rx.recv().await.unwrap().1);
+        }
+        critical.await.unwrap();
+
+        assert!(matches!(received[2], SessionOutput::Idle { .. }));
+        assert_eq!(sender.counters().dropped(), 3);
+        assert_eq!(sender.counters().sent(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_tool_done_is_never_dropped() {
+        let (tx, mut rx) = mpsc::channel(1);
+        let sender = OutputSender::new("s1", tx, Arc::default());
+        sender.emit(thinking(0)).await;
+
+        // The only completion event of a refused call: waits rather than drops
+        let done = tokio::spawn({
+            let sender = sender.clone();
+            async move { sender.emit(SessionOutput::tool_done("t1", "Nope", false, "Unknown tool: Nope")).await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!done.is_finished());
+
+        assert!(matches!(rx.recv().await, Some((_, SessionOutput::Thinking { .. }))));
+        done.await.unwrap();
+        assert!(matches!(rx.recv().await, Some((_, SessionOutput::ToolDone { id, .. })) if id == "t1"));
+        assert_eq!(sender.counters().dropped(), 0);
+    }
+}
@@ -0,0 +1,175 @@
+//! Settings that differ from the session's for a single turn
+//!
+//! A user message can carry `TurnOverrides`: another model, looser approval,
+//! or another tool profile. They apply from the moment its turn starts until
+//! it ends, however it ends (answered, failed or cancelled); the session's
+//! own settings are untouched and the next turn runs with them again. The
+//! turn starts with `SessionOutput::TurnOverridden`, so frontends can show
+//! that it ran differently.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::approval::{ApprovalLevel, ToolApprovalConfig};
+use crate::config::ModelTiers;
+use crate::orchestration::ToolProfile;
+
+/// Which calls a turn runs without asking
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum TurnApproval {
+    /// Every call (the organization policy and deny rules still apply)
+    All,
+    /// Calls this approval level lets through
+    Level(ApprovalLevel),
+}
+
+impl TurnApproval {
+    /// The session's approval config as it stands for the turn
+    pub fn apply(&self, session: &ToolApprovalConfig) -> ToolApprovalConfig {
+        let mut config = session.clone();
+        match self {
+            Self::All => config.approve_all_for_session(),
+            Self::Level(level) => config.set_level(*level),
+        }
+        config
+    }
+}
+
+impl fmt::Display for TurnApproval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::All => write!(f, "all"),
+            Self::Level(level) => write!(f, "{}", level),
+        }
+    }
+}
+
+impl FromStr for TurnApproval {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("all") {
+            return Ok(Self::All);
+        }
+        s.parse()
+            .map(Self::Level)
+            .map_err(|_| format!("Unknown approval: {}. Valid values: all, none, low, medium, high, critical", s))
+    }
+}
+
+impl TryFrom<String> for TurnApproval {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<TurnApproval> for String {
+    fn from(approval: TurnApproval) -> Self {
+        approval.to_string()
+    }
+}
+
+/// Settings for one turn in place of the session's (see the module docs)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TurnOverrides {
+    /// Model to answer with: a model ID of the session's provider, or
+    /// haiku, sonnet or opus for its fast, balanced or powerful tier
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Calls the turn runs without asking, besides those the session does
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approval_level: Option<TurnApproval>,
+    /// Tools offered during the turn
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_profile: Option<ToolProfile>,
+}
+
+impl TurnOverrides {
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    pub fn with_approval(mut self, approval: TurnApproval) -> Self {
+        self.approval_level = Some(approval);
+        self
+    }
+
+    pub fn with_tool_profile(mut self, profile: ToolProfile) -> Self {
+        self.tool_profile = Some(profile);
+        self
+    }
+
+    /// Whether nothing is overridden
+    pub fn is_empty(&self) -> bool {
+        self.model.is_none() && self.approval_level.is_none() && self.tool_profile.is_none()
+    }
+}
+
+impl fmt::Display for TurnOverrides {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(model) = &self.model {
+            parts.push(format!("model={}", model));
+        }
+        if let Some(approval) = &self.approval_level {
+            parts.push(format!("approval={}", approval));
+        }
+        if let Some(profile) = &self.tool_profile {
+            parts.push(format!("tools={}", profile.name));
+        }
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+/// The model ID `model` names: a tier's model for haiku, sonnet or opus,
+/// `model` itself otherwise
+pub fn resolve_model(model: &str, tiers: &ModelTiers) -> String {
+    match model.to_lowercase().as_str() {
+        "haiku" | "fast" => tiers.fast.clone(),
+        "sonnet" | "balanced" => tiers.balanced.clone(),
+        "opus" | "powerful" => tiers.powerful.clone(),
+        _ => model.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_approval_round_trips_as_a_string() {
+        for (text, approval) in [("all", TurnApproval::All), ("high", TurnApproval::Level(ApprovalLevel::High))] {
+            assert_eq!(text.parse::<TurnApproval>().unwrap(), approval);
+            assert_eq!(serde_json::to_value(approval).unwrap(), serde_json::json!(text));
+        }
+        assert!("everything".parse::<TurnApproval>().is_err());
+        assert!(serde_json::from_value::<TurnApproval>(serde_json::json!("everything")).is_err());
+    }
+
+    #[test]
+    fn test_approval_applies_to_a_copy() {
+        let session = ToolApprovalConfig::default();
+        let turn = TurnApproval::All.apply(&session);
+        let args = serde_json::json!({"file_path": "notes.txt", "content": "hi"});
+        assert!(turn.should_auto_approve_with_args("Write", &args));
+        assert!(!session.should_auto_approve_with_args("Write", &args));
+    }
+
+    #[test]
+    fn test_resolve_model() {
+        let tiers = ModelTiers {
+            fast: "small".to_string(),
+            balanced: "medium".to_string(),
+            powerful: "large".to_string(),
+        };
+        assert_eq!(resolve_model("opus", &tiers), "large");
+        assert_eq!(resolve_model("Haiku", &tiers), "small");
+        assert_eq!(resolve_model("gpt-5", &tiers), "gpt-5");
+    }
+}
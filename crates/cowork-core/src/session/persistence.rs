@@ -1,23 +1,99 @@
 //! Session persistence - save and load session state
 //!
 //! Handles saving agent sessions to disk and loading them back.
+//!
+//! Images in the history are not embedded in the session file: each is
+//! written once under `attachments/<session id>/` in the sessions directory
+//! (named by its SHA-256), and the message keeps a `cowork-attachment:<file>`
+//! reference that `load_session` resolves again.
+//!
+//! A running session also has a scratch directory, `<session id>/scratch`,
+//! for temporary files outside the workspace (see `get_scratch_dir`).
+//!
+//! While it runs, a session is autosaved to `autosave/<session id>.json`,
+//! which is removed once the session is saved on exit. An autosave newer than
+//! the session's own file means the session ended without saving (a crash,
+//! or it is still running); `load_session` and `list_saved_sessions` then
+//! return the autosave, marked `recovered`. Files are written to a temporary
+//! name and renamed, so a crash mid-write leaves the previous save intact.
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use genai::chat::{Binary, BinarySource};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use tracing::warn;
 
-use crate::error::Result;
-use crate::provider::ChatMessage;
+use crate::error::{Error, Result};
+use crate::provider::{ChatMessage, ContentPart, MessageContent};
+use crate::tools::task::TodoItem;
+use super::history::{repair_history, HistoryRepair};
+use super::title::TitleSource;
+use super::tool_usage::ToolUsageStats;
+
+/// URL prefix of an image reference in a saved message
+const ATTACHMENT_REF_PREFIX: &str = "cowork-attachment:";
+
+/// Current version of the saved session format
+///
+/// - 0: files written before the format was versioned
+/// - 1: records `format_version`, `provider` and `model`
+pub const SESSION_FORMAT_VERSION: u32 = 1;
 
 /// Saved session state for persistence
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SavedSession {
     pub id: String,
+    /// The session's title (see `session::title`)
     pub name: String,
+    /// Where the title came from; a user's title isn't replaced by a generated one
+    #[serde(default, skip_serializing_if = "TitleSource::is_fallback")]
+    pub title_source: TitleSource,
+    /// Format the file was written in (see `SESSION_FORMAT_VERSION`)
+    #[serde(default)]
+    pub format_version: u32,
+    /// Provider the session ran on, when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    /// Model the session ran on, when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
     /// Messages stored using genai's ChatMessage directly
     pub messages: Vec<ChatMessage>,
+    /// TodoWrite checklist at the time of saving
+    #[serde(default)]
+    pub todos: Vec<TodoItem>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
+    /// Session this one was forked from (see `SessionManager::fork_session`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<String>,
+    /// Index of the parent's last message copied into this session
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fork_point: Option<usize>,
+    /// Loaded from an autosave newer than the last save on exit
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub recovered: bool,
+    /// Kept when saved sessions are pruned (see `prune_saved_sessions`)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+    /// Keys of the messages compaction keeps (see `session::pins`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pinned_messages: Vec<String>,
+    /// Session template the session was started from (see `templates`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    /// The session's tool calls, by tool (see `session::tool_usage`)
+    #[serde(default, skip_serializing_if = "ToolUsageStats::is_empty")]
+    pub tool_usage: ToolUsageStats,
+}
+
+impl SavedSession {
+    /// Pair every tool call with exactly one result, so an interrupted
+    /// session can be sent to the provider again
+    pub fn repair_history(&mut self) -> HistoryRepair {
+        repair_history(&mut self.messages)
+    }
 }
 
 /// Get the sessions directory path
@@ -28,27 +104,238 @@ pub fn get_sessions_dir() -> Result<PathBuf> {
     Ok(base.join("sessions"))
 }
 
+/// Get the directory running sessions are autosaved to
+pub fn get_autosave_dir() -> Result<PathBuf> {
+    Ok(get_sessions_dir()?.join("autosave"))
+}
+
+/// Get the directory a saved session's images are written to
+pub fn get_attachments_dir(session_id: &str) -> Result<PathBuf> {
+    Ok(get_sessions_dir()?.join("attachments").join(session_id))
+}
+
+/// Get a session's scratch directory, for temporary files the model writes
+/// outside the workspace: `<session id>/scratch` in the sessions directory
+pub fn get_scratch_dir(session_id: &str) -> Result<PathBuf> {
+    let is_file_name = Path::new(session_id).file_name().is_some_and(|name| name == session_id);
+    if !is_file_name {
+        return Err(Error::Workspace(format!("Invalid session id for a scratch directory: {}", session_id)));
+    }
+    Ok(get_sessions_dir()?.join(session_id).join("scratch"))
+}
+
+/// Remove a session's scratch directory, and the session's own directory
+/// once nothing else is left in it
+pub fn remove_scratch_dir(session_id: &str) -> Result<()> {
+    let scratch = get_scratch_dir(session_id)?;
+    match std::fs::remove_dir_all(&scratch) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e.into()),
+    }
+    if let Some(session_dir) = scratch.parent() {
+        // Fails, and is left alone, while it holds anything else
+        let _ = std::fs::remove_dir(session_dir);
+    }
+    Ok(())
+}
+
+/// Write a session to the sessions directory, returning the file's path
+///
+/// Images are written to the session's attachments directory and referenced
+/// from the file rather than embedded.
+pub fn write_session(saved: &SavedSession) -> Result<PathBuf> {
+    write_session_to(saved, &get_sessions_dir()?)
+}
+
+/// Autosave a running session, returning the file's path
+pub fn write_autosave(saved: &SavedSession) -> Result<PathBuf> {
+    write_session_to(saved, &get_autosave_dir()?)
+}
+
+/// Remove a session's autosave, once it has been saved or isn't wanted
+pub fn remove_autosave(session_id: &str) -> Result<()> {
+    match std::fs::remove_file(get_autosave_dir()?.join(format!("{}.json", session_id))) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+pub(super) fn write_session_to(saved: &SavedSession, dir: &Path) -> Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+
+    let attachments_dir = get_attachments_dir(&saved.id)?;
+    let mut stored = saved.clone();
+    stored.recovered = false;
+    for message in &mut stored.messages {
+        map_binaries(message, |binary| store_attachment(binary, &attachments_dir))?;
+    }
+
+    let path = dir.join(format!("{}.json", saved.id));
+    write_atomic(&path, serde_json::to_string_pretty(&stored)?.as_bytes())?;
+    Ok(path)
+}
+
+/// Write to a temporary file next to `path`, then rename it over `path`
+pub(super) fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    let temp = PathBuf::from(temp);
+    std::fs::write(&temp, contents)?;
+    if let Err(e) = std::fs::rename(&temp, path) {
+        let _ = std::fs::remove_file(&temp);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+/// Read a session file, None if it doesn't exist
+pub(super) fn read_session_file(path: &Path) -> Result<Option<SavedSession>> {
+    match std::fs::read_to_string(path) {
+        Ok(json) => Ok(Some(serde_json::from_str(&json)?)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// The autosave, marked recovered, if it is newer than the saved session
+fn newer_of(saved: Option<SavedSession>, autosave: Option<SavedSession>) -> Option<SavedSession> {
+    match (saved, autosave) {
+        (saved, Some(mut autosave)) if saved.as_ref().is_none_or(|s| autosave.updated_at > s.updated_at) => {
+            autosave.recovered = true;
+            Some(autosave)
+        }
+        (saved, _) => saved,
+    }
+}
+
 /// Load a saved session by ID
+///
+/// A newer autosave is returned instead, marked `recovered`. Image
+/// references are resolved from the attachments directory; an image whose
+/// file is gone is replaced by a note saying so.
 pub fn load_session(session_id: &str) -> Result<Option<SavedSession>> {
-    let path = get_sessions_dir()?.join(format!("{}.json", session_id));
-    if !path.exists() {
+    let file_name = format!("{}.json", session_id);
+    let saved = read_session_file(&get_sessions_dir()?.join(&file_name))?;
+    let autosave = read_session_file(&get_autosave_dir()?.join(&file_name)).unwrap_or_else(|e| {
+        warn!("Ignoring unreadable autosave of session {}: {}", session_id, e);
+        None
+    });
+    let Some(mut saved) = newer_of(saved, autosave) else {
         return Ok(None);
+    };
+    let attachments_dir = get_attachments_dir(session_id)?;
+    for message in &mut saved.messages {
+        map_binaries(message, |binary| Ok(load_attachment(binary, &attachments_dir)))?;
+    }
+    let repair = saved.repair_history();
+    if !repair.is_empty() {
+        warn!(
+            "Repaired session {}: {} tool results synthesized, {} dropped",
+            session_id,
+            repair.synthesized.len(),
+            repair.dropped.len()
+        );
     }
-
-    let json = std::fs::read_to_string(&path)?;
-    let saved: SavedSession = serde_json::from_str(&json)?;
     Ok(Some(saved))
 }
 
+/// Replace each binary part of `message` with `f`'s result
+fn map_binaries(
+    message: &mut ChatMessage,
+    mut f: impl FnMut(Binary) -> Result<ContentPart>,
+) -> Result<()> {
+    if message.content.binaries().is_empty() {
+        return Ok(());
+    }
+    let parts = std::mem::take(&mut message.content)
+        .into_parts()
+        .into_iter()
+        .map(|part| match part {
+            ContentPart::Binary(binary) => f(binary),
+            part => Ok(part),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    message.content = MessageContent::from_parts(parts);
+    Ok(())
+}
+
+/// Write an embedded image to `dir` and return a reference to it
+fn store_attachment(binary: Binary, dir: &Path) -> Result<ContentPart> {
+    let BinarySource::Base64(data) = &binary.source else {
+        return Ok(ContentPart::Binary(binary));
+    };
+    let Ok(bytes) = STANDARD.decode(data.as_bytes()) else {
+        // Kept inline rather than losing it
+        return Ok(ContentPart::Binary(binary));
+    };
+
+    let extension = binary.content_type.strip_prefix("image/").unwrap_or("bin");
+    let file_name = format!("{:x}.{}", Sha256::digest(&bytes), extension);
+    let path = dir.join(&file_name);
+    if !path.exists() {
+        std::fs::create_dir_all(dir)?;
+        std::fs::write(&path, &bytes)?;
+    }
+    Ok(ContentPart::Binary(Binary::new(
+        binary.content_type,
+        BinarySource::Url(format!("{}{}", ATTACHMENT_REF_PREFIX, file_name)),
+        binary.name,
+    )))
+}
+
+/// Resolve an image reference written by `store_attachment`
+fn load_attachment(binary: Binary, dir: &Path) -> ContentPart {
+    let file_name = match &binary.source {
+        BinarySource::Url(url) => match url.strip_prefix(ATTACHMENT_REF_PREFIX) {
+            Some(name) => name.to_string(),
+            None => return ContentPart::Binary(binary),
+        },
+        BinarySource::Base64(_) => return ContentPart::Binary(binary),
+    };
+    match std::fs::read(dir.join(&file_name)) {
+        Ok(bytes) => ContentPart::Binary(Binary::new(
+            binary.content_type,
+            BinarySource::Base64(STANDARD.encode(bytes).into()),
+            binary.name,
+        )),
+        Err(e) => {
+            warn!("Missing session attachment {}: {}", file_name, e);
+            ContentPart::Text(format!("[image {} is no longer available]", file_name))
+        }
+    }
+}
+
 /// List all saved sessions
+///
+/// A session with a newer autosave is listed from the autosave, marked
+/// `recovered`.
 pub fn list_saved_sessions() -> Result<Vec<SavedSession>> {
-    let sessions_dir = get_sessions_dir()?;
-    if !sessions_dir.exists() {
+    let mut sessions = read_session_dir(&get_sessions_dir()?)?;
+    for autosave in read_session_dir(&get_autosave_dir()?)? {
+        match sessions.iter().position(|s| s.id == autosave.id) {
+            Some(i) => {
+                if let Some(newer) = newer_of(Some(sessions[i].clone()), Some(autosave)) {
+                    sessions[i] = newer;
+                }
+            }
+            None => sessions.extend(newer_of(None, Some(autosave))),
+        }
+    }
+
+    // Sort by updated_at descending (most recent first)
+    sessions.sort_by_key(|s| std::cmp::Reverse(s.updated_at));
+    Ok(sessions)
+}
+
+/// Every session file in `dir`, skipping ones that can't be read
+fn read_session_dir(dir: &Path) -> Result<Vec<SavedSession>> {
+    if !dir.exists() {
         return Ok(Vec::new());
     }
 
     let mut sessions = Vec::new();
-    for entry in std::fs::read_dir(&sessions_dir)? {
+    for entry in std::fs::read_dir(dir)? {
         let entry = entry?;
         let path = entry.path();
         if path.extension().is_some_and(|ext| ext == "json") {
@@ -61,8 +348,33 @@ pub fn list_saved_sessions() -> Result<Vec<SavedSession>> {
             }
         }
     }
-
-    // Sort by updated_at descending (most recent first)
-    sessions.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
     Ok(sessions)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attachments_stored_and_restored() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let data = STANDARD.encode(b"\x89PNG\r\n\x1a\nbytes");
+        let image = ContentPart::from_binary_base64("image/png", data.clone(), None);
+        let mut message = ChatMessage::user(MessageContent::from_parts(vec![ContentPart::from_text("look"), image]));
+
+        map_binaries(&mut message, |b| store_attachment(b, dir.path())).unwrap();
+        let stored = &message.content.binaries()[0].source;
+        let BinarySource::Url(url) = stored else { panic!("image still embedded: {:?}", stored) };
+        let file_name = url.strip_prefix(ATTACHMENT_REF_PREFIX).unwrap();
+        assert!(dir.path().join(file_name).exists());
+
+        let mut restored = message.clone();
+        map_binaries(&mut restored, |b| Ok(load_attachment(b, dir.path()))).unwrap();
+        assert!(matches!(&restored.content.binaries()[0].source, BinarySource::Base64(d) if **d == *data));
+
+        std::fs::remove_file(dir.path().join(file_name)).unwrap();
+        map_binaries(&mut message, |b| Ok(load_attachment(b, dir.path()))).unwrap();
+        assert!(message.content.binaries().is_empty());
+        assert!(message.content.joined_texts().unwrap().contains("no longer available"));
+    }
+}
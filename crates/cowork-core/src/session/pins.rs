@@ -0,0 +1,153 @@
+//! Pinned messages: kept verbatim whenever the history is compacted
+//!
+//! The user pins a message by the ID its `SessionOutput::UserMessage` or
+//! `SessionOutput::AssistantMessage` carried (`SessionInput::PinMessage`),
+//! or the latest reply with `/pin reply`. genai's `ChatMessage` has no room
+//! for a flag, so a pin is held as the message's key, a hash of its role and
+//! text:
+//! - Compaction keeps every message whose key is pinned (see
+//!   `context::summarizer`), and a kept message keeps its key.
+//! - Pins of messages no longer in the history are dropped after compaction
+//!   and truncation.
+//! - Saved sessions carry the keys in `SavedSession::pinned_messages`. IDs
+//!   are only known for messages sent while the session runs.
+
+use std::collections::{BTreeSet, HashMap};
+
+use sha2::{Digest, Sha256};
+
+use crate::provider::{message_text_content, ChatMessage, ChatRole};
+
+/// The pins of one session
+#[derive(Debug, Clone, Default)]
+pub struct MessagePins {
+    /// Keys of the pinned messages
+    pinned: BTreeSet<String>,
+    /// Key of each message sent to the frontend, by its output ID
+    by_id: HashMap<String, String>,
+    /// Key of the latest assistant reply
+    last_reply: Option<String>,
+}
+
+impl MessagePins {
+    /// Pins restored from a saved session
+    pub fn from_keys(keys: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            pinned: keys.into_iter().collect(),
+            ..Self::default()
+        }
+    }
+
+    /// Remember the message sent to the frontend with `id`, so it can be
+    /// pinned by that ID; only user messages and replies can be pinned
+    pub fn record(&mut self, id: impl Into<String>, message: &ChatMessage) {
+        let Some(key) = pin_key(message) else {
+            return;
+        };
+        if message.role == ChatRole::Assistant {
+            self.last_reply = Some(key.clone());
+        }
+        self.by_id.insert(id.into(), key);
+    }
+
+    /// Pin or unpin the message sent with `id`, or the latest reply with
+    /// None; false when there is no such message
+    pub fn set_pinned(&mut self, id: Option<&str>, pinned: bool) -> bool {
+        let key = match id {
+            Some(id) => self.by_id.get(id),
+            None => self.last_reply.as_ref(),
+        };
+        let Some(key) = key.cloned() else {
+            return false;
+        };
+        if pinned {
+            self.pinned.insert(key);
+        } else {
+            self.pinned.remove(&key);
+        }
+        true
+    }
+
+    /// Whether `message` is pinned
+    pub fn is_pinned(&self, message: &ChatMessage) -> bool {
+        !self.pinned.is_empty() && pin_key(message).is_some_and(|key| self.pinned.contains(&key))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pinned.is_empty()
+    }
+
+    /// Keys of the pinned messages, for saving
+    pub fn keys(&self) -> Vec<String> {
+        self.pinned.iter().cloned().collect()
+    }
+
+    /// Drop the pins of messages no longer in `messages`
+    pub fn retain(&mut self, messages: &[ChatMessage]) {
+        if self.pinned.is_empty() {
+            return;
+        }
+        let present: BTreeSet<String> = messages.iter().filter_map(pin_key).collect();
+        self.pinned.retain(|key| present.contains(key));
+    }
+}
+
+/// A message's pin key: a hash of its role and text (None for messages that
+/// can't be pinned)
+fn pin_key(message: &ChatMessage) -> Option<String> {
+    let role = match message.role {
+        ChatRole::User => "user",
+        ChatRole::Assistant => "assistant",
+        _ => return None,
+    };
+    let mut hasher = Sha256::new();
+    hasher.update(role);
+    hasher.update([0]);
+    hasher.update(message_text_content(message));
+    Some(format!("{:x}", hasher.finalize())[..32].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pin_by_id_and_latest_reply() {
+        let mut pins = MessagePins::default();
+        let question = ChatMessage::user("Always use tabs");
+        let reply = ChatMessage::assistant("Noted");
+        pins.record("u1", &question);
+        pins.record("a1", &reply);
+
+        assert!(pins.set_pinned(Some("u1"), true));
+        assert!(!pins.set_pinned(Some("unknown"), true));
+        assert!(pins.is_pinned(&question));
+        assert!(!pins.is_pinned(&reply));
+        // The same text from the other role is another message
+        assert!(!pins.is_pinned(&ChatMessage::assistant("Always use tabs")));
+
+        assert!(pins.set_pinned(None, true));
+        assert!(pins.is_pinned(&reply));
+        assert!(pins.set_pinned(Some("u1"), false));
+        assert!(!pins.is_pinned(&question));
+
+        // Saved and restored by key
+        let restored = MessagePins::from_keys(pins.keys());
+        assert!(restored.is_pinned(&reply));
+    }
+
+    #[test]
+    fn test_pins_of_dropped_messages_are_forgotten() {
+        let mut pins = MessagePins::default();
+        let kept = ChatMessage::user("kept");
+        let dropped = ChatMessage::user("dropped");
+        pins.record("1", &kept);
+        pins.record("2", &dropped);
+        pins.set_pinned(Some("1"), true);
+        pins.set_pinned(Some("2"), true);
+
+        pins.retain(std::slice::from_ref(&kept));
+        assert_eq!(pins.keys().len(), 1);
+        assert!(pins.is_pinned(&kept));
+    }
+}
@@ -0,0 +1,476 @@
+//! Per-session input queue
+//!
+//! User messages that arrive while a turn is in flight wait here instead of
+//! firing as soon as the turn ends. The session's [`QueuePolicy`] decides
+//! whether a mid-turn message is queued, rejected, or interrupts the turn.
+//!
+//! Both the dispatcher (messages sent on the session's input channel) and the
+//! [`SessionManager`](super::SessionManager) (which needs the queue position
+//! synchronously) submit through [`InputQueue::submit`].
+//!
+//! Re-runs of an earlier turn (regenerate, edit) go through the queue too,
+//! but only when nothing is in flight: they rewrite the history a running
+//! turn is working on. Recipe runs and compactions wait their turn like
+//! messages.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use tokio::sync::{mpsc, Notify};
+use tracing::debug;
+
+use super::overrides::TurnOverrides;
+use crate::context::CompactionMode;
+use super::types::{ImageAttachment, QueuePolicy, SessionId, SessionInput, SessionOutput};
+
+/// User input with optional image attachments
+pub(crate) type UserInput = (String, Vec<ImageAttachment>);
+
+/// A re-run of an earlier turn
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Rerun {
+    /// `SessionInput::RegenerateLast`
+    Regenerate { temperature: Option<f32> },
+    /// `SessionInput::EditUserMessage`
+    Edit { message_id: String, new_content: String },
+}
+
+/// Work waiting for the agent loop
+#[derive(Debug, Clone)]
+pub(crate) enum QueuedInput {
+    /// A user message, with the settings its turn runs with instead of the session's
+    Message(UserInput, Option<TurnOverrides>),
+    Rerun(Rerun),
+    /// `SessionInput::RunRecipe`
+    Recipe { name: String, args: String },
+    /// `SessionInput::Compact`
+    Compact {
+        mode: Option<CompactionMode>,
+        model: Option<String>,
+        focus: Option<String>,
+    },
+}
+
+/// Outcome of submitting a user message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Submission {
+    /// Nothing was in flight - the message runs right away
+    Started,
+    /// Waiting behind the current turn (1 = next to run)
+    Queued(usize),
+    /// Refused because a turn is in flight and the policy is `reject`
+    Rejected,
+    /// The current turn is being cancelled; the message runs next
+    Interrupted,
+}
+
+impl Submission {
+    /// Queue position, if the message is waiting behind a turn
+    pub fn position(&self) -> Option<usize> {
+        match self {
+            Submission::Queued(position) => Some(*position),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Default)]
+struct QueueState {
+    pending: VecDeque<QueuedInput>,
+    /// A turn is in flight
+    busy: bool,
+    /// The input channel closed - no more messages will arrive
+    closed: bool,
+    /// Closed by the session manager for being idle, to be resumed later
+    suspended: bool,
+}
+
+/// FIFO of user messages waiting for the agent loop
+pub struct InputQueue {
+    session_id: SessionId,
+    policy: QueuePolicy,
+    state: Mutex<QueueState>,
+    notify: Notify,
+    /// Control channel into the agent loop (for interrupting the current turn)
+    control_tx: mpsc::UnboundedSender<SessionInput>,
+    output_tx: mpsc::Sender<(SessionId, SessionOutput)>,
+}
+
+impl InputQueue {
+    pub(crate) fn new(
+        session_id: SessionId,
+        policy: QueuePolicy,
+        control_tx: mpsc::UnboundedSender<SessionInput>,
+        output_tx: mpsc::Sender<(SessionId, SessionOutput)>,
+    ) -> Self {
+        Self {
+            session_id,
+            policy,
+            state: Mutex::new(QueueState::default()),
+            notify: Notify::new(),
+            control_tx,
+            output_tx,
+        }
+    }
+
+    /// Submit a user message, whose turn runs with `overrides` if any, and
+    /// acknowledge it
+    ///
+    /// Emits `MessageQueued` when the message waits behind a turn, or an error
+    /// when the policy rejects it.
+    pub async fn submit(&self, input: UserInput, overrides: Option<TurnOverrides>) -> Submission {
+        self.submit_input(QueuedInput::Message(input, overrides)).await
+    }
+
+    /// Submit a recipe run, which waits behind the current turn like a message
+    pub(crate) async fn submit_recipe(&self, name: String, args: String) -> Submission {
+        self.submit_input(QueuedInput::Recipe { name, args }).await
+    }
+
+    /// Submit a compaction, which waits behind the current turn like a message
+    pub(crate) async fn submit_compact(
+        &self,
+        mode: Option<CompactionMode>,
+        model: Option<String>,
+        focus: Option<String>,
+    ) -> Submission {
+        self.submit_input(QueuedInput::Compact { mode, model, focus }).await
+    }
+
+    async fn submit_input(&self, input: QueuedInput) -> Submission {
+        let submission = self.enqueue(input);
+        match submission {
+            Submission::Queued(position) => {
+                self.emit(SessionOutput::message_queued(position)).await;
+            }
+            Submission::Rejected => {
+                self.emit(SessionOutput::error(
+                    "Agent is busy; message rejected (queue policy is 'reject')",
+                ))
+                .await;
+            }
+            Submission::Started | Submission::Interrupted => {}
+        }
+        submission
+    }
+
+    /// Apply the queue policy to a new message
+    fn enqueue(&self, input: QueuedInput) -> Submission {
+        let mut state = self.state.lock().unwrap();
+        // A message that hasn't been picked up yet counts as in flight
+        let in_flight = state.busy || !state.pending.is_empty();
+
+        if !in_flight {
+            state.pending.push_back(input);
+            drop(state);
+            self.notify.notify_one();
+            return Submission::Started;
+        }
+
+        match self.policy {
+            QueuePolicy::Queue => {
+                state.pending.push_back(input);
+                let position = state.pending.len() - usize::from(!state.busy);
+                drop(state);
+                self.notify.notify_one();
+                if position == 0 { Submission::Started } else { Submission::Queued(position) }
+            }
+            QueuePolicy::Reject => Submission::Rejected,
+            QueuePolicy::Interrupt => {
+                let dropped = state.pending.len();
+                state.pending.clear();
+                state.pending.push_back(input);
+                let busy = state.busy;
+                drop(state);
+                if dropped > 0 {
+                    debug!("Interrupt dropped {} queued message(s) for session {}", dropped, self.session_id);
+                }
+                self.notify.notify_one();
+                if busy {
+                    // Same path as a user Cancel
+                    let _ = self.control_tx.send(SessionInput::Cancel);
+                    Submission::Interrupted
+                } else {
+                    Submission::Started
+                }
+            }
+        }
+    }
+
+    /// Submit a re-run of an earlier turn, if nothing is in flight
+    ///
+    /// Whatever the policy, a re-run is refused with an error while a turn
+    /// is running or messages are waiting. Returns whether it was accepted.
+    pub async fn submit_rerun(&self, rerun: Rerun) -> bool {
+        let accepted = {
+            let mut state = self.state.lock().unwrap();
+            let idle = !state.busy && state.pending.is_empty();
+            if idle {
+                state.pending.push_back(QueuedInput::Rerun(rerun));
+            }
+            idle
+        };
+        if accepted {
+            self.notify.notify_one();
+        } else {
+            self.emit(SessionOutput::error(
+                "Agent is busy; wait for the turn to finish (or cancel it) before regenerating or editing",
+            ))
+            .await;
+        }
+        accepted
+    }
+
+    /// Cancel the current turn
+    ///
+    /// Under the `interrupt` policy this also drops queued messages, since the
+    /// user is abandoning the work in flight.
+    pub fn cancel(&self) {
+        if self.policy == QueuePolicy::Interrupt {
+            self.clear();
+        }
+        let _ = self.control_tx.send(SessionInput::Cancel);
+    }
+
+    /// Drop all queued messages, returning how many were dropped
+    pub fn clear(&self) -> usize {
+        let mut state = self.state.lock().unwrap();
+        let dropped = state.pending.len();
+        state.pending.clear();
+        if dropped > 0 {
+            debug!("Cleared {} queued message(s) for session {}", dropped, self.session_id);
+        }
+        dropped
+    }
+
+    /// Wait for the next message and mark a turn as in flight
+    ///
+    /// Returns `None` once the queue is closed and drained.
+    pub(crate) async fn next(&self) -> Option<QueuedInput> {
+        loop {
+            {
+                let mut state = self.state.lock().unwrap();
+                if let Some(input) = state.pending.pop_front() {
+                    state.busy = true;
+                    return Some(input);
+                }
+                if state.closed {
+                    return None;
+                }
+            }
+            self.notify.notified().await;
+        }
+    }
+
+    /// Whether nothing is in flight or waiting
+    ///
+    /// Approvals and questions only happen inside a turn, so an idle queue
+    /// also means none is pending.
+    pub fn is_idle(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        !state.busy && state.pending.is_empty()
+    }
+
+    /// Mark the current turn as finished
+    pub(crate) fn finish_turn(&self) {
+        self.state.lock().unwrap().busy = false;
+    }
+
+    /// Stop accepting messages; `next` returns `None` once drained
+    pub(crate) fn close(&self) {
+        self.state.lock().unwrap().closed = true;
+        self.notify.notify_one();
+    }
+
+    /// Close the queue because the session is being suspended
+    pub(crate) fn suspend(&self) {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.suspended = true;
+            state.closed = true;
+        }
+        self.notify.notify_one();
+    }
+
+    /// Whether the queue was closed by `suspend`
+    pub(crate) fn is_suspended(&self) -> bool {
+        self.state.lock().unwrap().suspended
+    }
+
+    async fn emit(&self, output: SessionOutput) {
+        let _ = self.output_tx.send((self.session_id.clone(), output)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue(
+        policy: QueuePolicy,
+    ) -> (
+        InputQueue,
+        mpsc::UnboundedReceiver<SessionInput>,
+        mpsc::Receiver<(SessionId, SessionOutput)>,
+    ) {
+        let (control_tx, control_rx) = mpsc::unbounded_channel();
+        let (output_tx, output_rx) = mpsc::channel(16);
+        (InputQueue::new("test".to_string(), policy, control_tx, output_tx), control_rx, output_rx)
+    }
+
+    fn msg(content: &str) -> UserInput {
+        (content.to_string(), vec![])
+    }
+
+    /// Text of a queued user message
+    fn content(input: Option<QueuedInput>) -> String {
+        match input {
+            Some(QueuedInput::Message((content, _), _)) => content,
+            other => panic!("expected a message, got {:?}", other),
+        }
+    }
+
+    fn pending(queue: &InputQueue) -> usize {
+        queue.state.lock().unwrap().pending.len()
+    }
+
+    #[tokio::test]
+    async fn test_messages_run_in_order() {
+        let (queue, _control_rx, mut output_rx) = queue(QueuePolicy::Queue);
+
+        assert_eq!(queue.submit(msg("first"), None).await, Submission::Started);
+        assert_eq!(content(queue.next().await), "first");
+
+        // Mid-turn messages queue up with increasing positions
+        assert_eq!(queue.submit(msg("second"), None).await, Submission::Queued(1));
+        assert_eq!(queue.submit(msg("third"), None).await, Submission::Queued(2));
+        assert!(matches!(output_rx.recv().await, Some((_, SessionOutput::MessageQueued { position: 1 }))));
+        assert!(matches!(output_rx.recv().await, Some((_, SessionOutput::MessageQueued { position: 2 }))));
+
+        queue.finish_turn();
+        assert_eq!(content(queue.next().await), "second");
+        queue.finish_turn();
+        assert_eq!(content(queue.next().await), "third");
+        queue.finish_turn();
+        assert_eq!(pending(&queue), 0);
+    }
+
+    #[tokio::test]
+    async fn test_unstarted_message_counts_as_in_flight() {
+        let (queue, _control_rx, _output_rx) = queue(QueuePolicy::Queue);
+
+        // The loop hasn't picked up "first" yet, so "second" is next after it
+        assert_eq!(queue.submit(msg("first"), None).await, Submission::Started);
+        assert_eq!(queue.submit(msg("second"), None).await, Submission::Queued(1));
+    }
+
+    #[tokio::test]
+    async fn test_next_waits_for_submit() {
+        let (queue, _control_rx, _output_rx) = queue(QueuePolicy::Queue);
+        let queue = std::sync::Arc::new(queue);
+
+        let waiter = tokio::spawn({
+            let queue = queue.clone();
+            async move { queue.next().await }
+        });
+        tokio::task::yield_now().await;
+        queue.submit(msg("hello"), None).await;
+
+        assert_eq!(content(waiter.await.unwrap()), "hello");
+    }
+
+    #[tokio::test]
+    async fn test_close_drains_then_ends() {
+        let (queue, _control_rx, _output_rx) = queue(QueuePolicy::Queue);
+        queue.submit(msg("last"), None).await;
+        queue.close();
+
+        assert_eq!(content(queue.next().await), "last");
+        queue.finish_turn();
+        assert!(queue.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_is_idle_until_turn_finishes() {
+        let (queue, _control_rx, _output_rx) = queue(QueuePolicy::Queue);
+        assert!(queue.is_idle());
+
+        queue.submit(msg("hello"), None).await;
+        assert!(!queue.is_idle());
+        queue.next().await;
+        assert!(!queue.is_idle());
+        queue.finish_turn();
+        assert!(queue.is_idle());
+    }
+
+    #[tokio::test]
+    async fn test_reject_policy() {
+        let (queue, _control_rx, mut output_rx) = queue(QueuePolicy::Reject);
+        queue.submit(msg("first"), None).await;
+        queue.next().await;
+
+        assert_eq!(queue.submit(msg("second"), None).await, Submission::Rejected);
+        assert_eq!(pending(&queue), 0);
+        assert!(matches!(output_rx.recv().await, Some((_, SessionOutput::Error { .. }))));
+    }
+
+    #[tokio::test]
+    async fn test_interrupt_cancels_current_turn() {
+        let (queue, mut control_rx, _output_rx) = queue(QueuePolicy::Interrupt);
+        queue.submit(msg("first"), None).await;
+        queue.next().await;
+
+        assert_eq!(queue.submit(msg("second"), None).await, Submission::Interrupted);
+        assert!(matches!(control_rx.try_recv(), Ok(SessionInput::Cancel)));
+
+        queue.finish_turn();
+        assert_eq!(content(queue.next().await), "second");
+    }
+
+    #[tokio::test]
+    async fn test_cancel_clears_queue_under_interrupt_policy() {
+        let (queue, mut control_rx, _output_rx) = queue(QueuePolicy::Interrupt);
+        queue.submit(msg("first"), None).await;
+        queue.next().await;
+        queue.submit(msg("second"), None).await;
+        assert_eq!(pending(&queue), 1);
+        assert!(matches!(control_rx.try_recv(), Ok(SessionInput::Cancel)));
+
+        // An explicit Cancel abandons the waiting message too
+        queue.cancel();
+        assert_eq!(pending(&queue), 0);
+        assert!(matches!(control_rx.try_recv(), Ok(SessionInput::Cancel)));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_keeps_queue_under_queue_policy() {
+        let (queue, mut control_rx, _output_rx) = queue(QueuePolicy::Queue);
+        queue.submit(msg("first"), None).await;
+        queue.next().await;
+        queue.submit(msg("second"), None).await;
+
+        queue.cancel();
+        assert_eq!(pending(&queue), 1);
+        assert!(matches!(control_rx.try_recv(), Ok(SessionInput::Cancel)));
+
+        assert_eq!(queue.clear(), 1);
+        assert_eq!(pending(&queue), 0);
+    }
+
+    #[tokio::test]
+    async fn test_rerun_only_when_idle() {
+        let (queue, _control_rx, mut output_rx) = queue(QueuePolicy::Queue);
+        let rerun = Rerun::Regenerate { temperature: None };
+
+        queue.submit(msg("first"), None).await;
+        assert!(!queue.submit_rerun(rerun.clone()).await, "message not picked up yet");
+        queue.next().await;
+        assert!(!queue.submit_rerun(rerun.clone()).await, "turn in flight");
+        assert_eq!(pending(&queue), 0);
+        assert!(matches!(output_rx.recv().await, Some((_, SessionOutput::Error { .. }))));
+
+        queue.finish_turn();
+        assert!(queue.submit_rerun(rerun.clone()).await);
+        assert!(matches!(queue.next().await, Some(QueuedInput::Rerun(r)) if r == rerun));
+    }
+}
@@ -0,0 +1,203 @@
+//! Detection of repeated identical tool calls within a turn
+//!
+//! A model stuck in a loop tends to re-issue the same call (same tool, same
+//! arguments) and get the same answer back. The agent loop reports each call
+//! here before running it. Once an idempotent call has been seen more than
+//! `suppress_after` times in the recent window, it is answered from the
+//! previous result with a reminder to change approach; past `abort_after`
+//! the turn is stopped.
+//!
+//! Only tools marked idempotent (`Tool::is_idempotent`) are tracked. Any other
+//! call may have changed what those tools would return, so it clears the
+//! window and the cached results.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+
+use serde_json::Value;
+
+use crate::config::LimitsConfig;
+
+/// Number of recent idempotent calls compared against
+const WINDOW: usize = 32;
+
+/// What to do with a tool call
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum RepeatVerdict {
+    /// Run the tool
+    Run,
+    /// Don't run it: answer with this output, which carries the previous
+    /// result and a reminder. `first` is set the first time this call is
+    /// suppressed this turn.
+    Reuse { output: String, success: bool, first: bool },
+    /// Stop the turn with this message
+    Abort(String),
+}
+
+/// Result kept for answering a repeated call
+struct CachedResult {
+    output: String,
+    success: bool,
+}
+
+/// Tracks the current turn's tool calls
+pub(crate) struct RepeatDetector {
+    /// Reuse the cached result once a call is seen more than this many times (0 = never)
+    suppress_after: usize,
+    /// Abort the turn once a call is seen more than this many times (0 = never)
+    abort_after: usize,
+    /// Keys of the most recent idempotent calls, oldest first
+    window: VecDeque<u64>,
+    results: HashMap<u64, CachedResult>,
+}
+
+impl RepeatDetector {
+    pub(crate) fn new(limits: &LimitsConfig) -> Self {
+        Self {
+            suppress_after: limits.repeated_tool_calls,
+            abort_after: limits.repeated_tool_calls_abort,
+            window: VecDeque::with_capacity(WINDOW),
+            results: HashMap::new(),
+        }
+    }
+
+    /// Forget everything seen so far (a new turn started)
+    pub(crate) fn reset(&mut self) {
+        self.window.clear();
+        self.results.clear();
+    }
+
+    /// Record a call about to be made and decide whether to run it
+    pub(crate) fn observe(&mut self, name: &str, arguments: &Value, idempotent: bool) -> RepeatVerdict {
+        if !idempotent {
+            self.reset();
+            return RepeatVerdict::Run;
+        }
+
+        let key = call_key(name, arguments);
+        if self.window.len() == WINDOW {
+            self.window.pop_front();
+        }
+        self.window.push_back(key);
+        let seen = self.window.iter().filter(|k| **k == key).count();
+
+        if self.abort_after > 0 && seen > self.abort_after {
+            return RepeatVerdict::Abort(format!(
+                "Stopped the turn: {} was called with the same arguments {} times without making progress",
+                name, seen
+            ));
+        }
+        if self.suppress_after == 0 || seen <= self.suppress_after {
+            return RepeatVerdict::Run;
+        }
+        match self.results.get(&key) {
+            Some(cached) => RepeatVerdict::Reuse {
+                output: format!(
+                    "{}\n\n<system-reminder>\nYou have already called {} with these exact arguments {} times this turn, and this is the same result as before. You already have this information: use it, or change your approach instead of repeating the call.\n</system-reminder>",
+                    cached.output, name, seen
+                ),
+                success: cached.success,
+                first: seen == self.suppress_after + 1,
+            },
+            // Still running (e.g. several identical calls in one response)
+            None => RepeatVerdict::Run,
+        }
+    }
+
+    /// Keep a finished call's result for answering repeats
+    ///
+    /// Only calls in the window are kept, and the first result stays: a
+    /// reused answer is never cached over the real one.
+    pub(crate) fn record(&mut self, name: &str, arguments: &Value, output: &str, success: bool) {
+        let key = call_key(name, arguments);
+        if self.window.contains(&key) {
+            self.results.entry(key).or_insert_with(|| CachedResult {
+                output: output.to_string(),
+                success,
+            });
+        }
+    }
+}
+
+/// Hash of a tool name and its normalized arguments
+///
+/// Object keys are compared in sorted order and null values are dropped, so
+/// `{"a": 1, "b": null}` and `{"a": 1}` count as the same call.
+fn call_key(name: &str, arguments: &Value) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    name.hash(&mut hasher);
+    normalize(arguments).to_string().hash(&mut hasher);
+    hasher.finish()
+}
+
+fn normalize(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().filter(|(_, v)| !v.is_null()).collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            Value::Object(entries.into_iter().map(|(k, v)| (k.clone(), normalize(v))).collect())
+        }
+        Value::Array(items) => Value::Array(items.iter().map(normalize).collect()),
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn detector() -> RepeatDetector {
+        RepeatDetector::new(&LimitsConfig {
+            repeated_tool_calls: 2,
+            repeated_tool_calls_abort: 4,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_repeats_are_reused_then_aborted() {
+        let mut detector = detector();
+        let args = json!({ "file_path": "src/main.rs", "offset": null });
+        for _ in 0..2 {
+            assert_eq!(detector.observe("Read", &args, true), RepeatVerdict::Run);
+            detector.record("Read", &args, "fn main() {}", true);
+        }
+
+        // Key order and nulls don't make a call different
+        let same = json!({ "file_path": "src/main.rs" });
+        match detector.observe("Read", &same, true) {
+            RepeatVerdict::Reuse { output, success, first } => {
+                assert!(output.starts_with("fn main() {}"));
+                assert!(output.contains("<system-reminder>"));
+                assert!(success);
+                assert!(first);
+            }
+            other => panic!("expected reuse, got {:?}", other),
+        }
+        assert!(matches!(detector.observe("Read", &same, true), RepeatVerdict::Reuse { first: false, .. }));
+        assert!(matches!(detector.observe("Read", &same, true), RepeatVerdict::Abort(_)));
+
+        // Other arguments are a different call
+        assert_eq!(detector.observe("Read", &json!({ "file_path": "src/lib.rs" }), true), RepeatVerdict::Run);
+    }
+
+    #[test]
+    fn test_non_idempotent_calls_are_exempt_and_reset() {
+        let mut detector = detector();
+        let command = json!({ "command": "cargo test" });
+        for _ in 0..10 {
+            assert_eq!(detector.observe("Bash", &command, false), RepeatVerdict::Run);
+        }
+
+        let args = json!({ "pattern": "TODO" });
+        for _ in 0..2 {
+            detector.observe("Grep", &args, true);
+            detector.record("Grep", &args, "[]", true);
+        }
+        // An edit may have changed what Grep finds
+        detector.observe("Edit", &json!({ "file_path": "a.rs" }), false);
+        assert_eq!(detector.observe("Grep", &args, true), RepeatVerdict::Run);
+    }
+}
@@ -0,0 +1,517 @@
+//! Replay a saved session against another model
+//!
+//! Each user message of a saved session is sent again, in order, to a fresh
+//! session on the model under test. Tools run dry (see `tools::dry_run`),
+//! every approval is granted, questions get their first option, and the
+//! temperature is forced to 0 so replays can be compared with each other.
+//! The result is a per-turn comparison of the tools called, the final
+//! assistant text, token usage and errors, as Markdown or JSON.
+//!
+//! Saved sessions don't record tool failures or per-turn usage, so those are
+//! only reported for the replay. The original session's total usage comes
+//! from the usage store, when it still has records for the session.
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Arc, LazyLock};
+
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::Serialize;
+
+use super::persistence::{SavedSession, SESSION_FORMAT_VERSION};
+use super::{SessionConfig, SessionInput, SessionManager, SessionOutput};
+use crate::approval::ToolApprovalConfig;
+use crate::error::{Error, Result};
+use crate::orchestration::ToolRegistryBuilder;
+use crate::provider::{message_text_content, ChatMessage, ChatRole, UsageRecord, UsageStore};
+use crate::skills::SkillRegistry;
+use crate::tools::mcp::MCP_TOOL_PREFIX;
+
+/// Session ID the replay runs under
+const REPLAY_SESSION: &str = "replay";
+
+/// User messages the agent loop adds on its own; they belong to the turn they appear in
+const INJECTED_PREFIXES: &[&str] = &[
+    "<session-start-hook>",
+    "<system-reminder>",
+    "<command-name>",
+    "Execute this skill in a subagent:",
+];
+
+/// Hook output appended to a user message by the agent loop
+const PROMPT_HOOK_SUFFIX: &str = "\n\n<user-prompt-submit-hook>";
+
+/// Token usage appended to assistant messages for display, e.g. ` [1k/20/200k 1%]`
+static TOKEN_SUFFIX_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r" \[\d+k?/\d+k?(?:/\d+k?(?: \d+%)?)?\]$").expect("token suffix regex"));
+
+/// Token totals over a set of LLM calls
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct UsageTotals {
+    pub calls: usize,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+impl UsageTotals {
+    fn from_records<'a>(records: impl IntoIterator<Item = &'a UsageRecord>) -> Self {
+        records.into_iter().fold(Self::default(), |total, r| Self {
+            calls: total.calls + 1,
+            input_tokens: total.input_tokens + r.input_tokens,
+            output_tokens: total.output_tokens + r.output_tokens,
+        })
+    }
+}
+
+/// What happened in one turn
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TurnSummary {
+    /// Tools called, in order
+    pub tools: Vec<String>,
+    /// The last assistant text of the turn
+    pub final_text: String,
+    /// Failed tool calls and turn errors (replay only)
+    pub errors: Vec<String>,
+    /// Tokens used by the turn (replay only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<UsageTotals>,
+}
+
+/// A turn of a saved session: a user message and what followed it
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedTurn {
+    pub user_message: String,
+    /// Images attached to the message (not replayed)
+    pub images: usize,
+    pub summary: TurnSummary,
+}
+
+/// Split a saved conversation into turns
+///
+/// A turn starts at each message the user typed; messages the agent loop
+/// injected (hook output, reminders, skill content) stay in the turn they
+/// appear in.
+pub fn recorded_turns(messages: &[ChatMessage]) -> Vec<RecordedTurn> {
+    let mut turns: Vec<RecordedTurn> = Vec::new();
+    for message in messages {
+        match message.role {
+            ChatRole::User => {
+                let text = message_text_content(message);
+                if INJECTED_PREFIXES.iter().any(|p| text.starts_with(p)) {
+                    continue;
+                }
+                let typed = text.split(PROMPT_HOOK_SUFFIX).next().unwrap_or_default();
+                turns.push(RecordedTurn {
+                    user_message: typed.to_string(),
+                    images: message.content.binaries().len(),
+                    summary: TurnSummary::default(),
+                });
+            }
+            ChatRole::Assistant => {
+                let Some(turn) = turns.last_mut() else {
+                    continue;
+                };
+                turn.summary
+                    .tools
+                    .extend(message.content.tool_calls().iter().map(|c| c.fn_name.clone()));
+                let text = message.content.joined_texts().unwrap_or_default();
+                if !text.trim().is_empty() {
+                    turn.summary.final_text = text;
+                }
+            }
+            _ => {}
+        }
+    }
+    turns
+}
+
+/// One turn, before and after
+#[derive(Debug, Clone, Serialize)]
+pub struct TurnComparison {
+    /// 1-based turn number
+    pub index: usize,
+    pub user_message: String,
+    pub original: TurnSummary,
+    pub replay: TurnSummary,
+    /// Whether the same tools were called in the same order
+    pub same_tools: bool,
+}
+
+/// Result of replaying a saved session
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplayReport {
+    pub session_id: String,
+    pub session_name: String,
+    pub original_provider: Option<String>,
+    pub original_model: Option<String>,
+    pub provider: String,
+    pub model: Option<String>,
+    pub replayed_at: DateTime<Utc>,
+    /// Reasons the comparison may be skewed
+    pub warnings: Vec<String>,
+    /// The original session's usage, if the usage store still has it
+    pub original_usage: Option<UsageTotals>,
+    pub replay_usage: UsageTotals,
+    pub turns: Vec<TurnComparison>,
+}
+
+impl ReplayReport {
+    /// The report as pretty-printed JSON
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// The report as Markdown: a summary table, then each turn side by side
+    pub fn to_markdown(&self) -> String {
+        let model_name = |provider: Option<&str>, model: Option<&str>| match (provider, model) {
+            (Some(p), Some(m)) => format!("{}/{}", p, m),
+            (Some(p), None) => format!("{} (default model)", p),
+            _ => "unknown".to_string(),
+        };
+        let same = self.turns.iter().filter(|t| t.same_tools).count();
+
+        let mut out = format!("# Replay of {} ({})\n\n", self.session_name, self.session_id);
+        out.push_str(&format!(
+            "- Original: {}\n",
+            model_name(self.original_provider.as_deref(), self.original_model.as_deref())
+        ));
+        out.push_str(&format!(
+            "- Replay: {}, temperature 0, tools run dry (reads see the workspace as it is now)\n",
+            model_name(Some(&self.provider), self.model.as_deref())
+        ));
+        out.push_str(&format!("- Replayed at: {}\n", self.replayed_at.format("%Y-%m-%d %H:%M:%S UTC")));
+        out.push_str(&format!("- Turns: {}, same tool calls in {}\n", self.turns.len(), same));
+
+        if !self.warnings.is_empty() {
+            out.push_str("\n## Warnings\n\n");
+            for warning in &self.warnings {
+                out.push_str(&format!("- {}\n", warning));
+            }
+        }
+
+        out.push_str("\n## Token usage\n\n| | Calls | Input | Output |\n|---|---:|---:|---:|\n");
+        let row = |label: &str, u: &UsageTotals| format!("| {} | {} | {} | {} |\n", label, u.calls, u.input_tokens, u.output_tokens);
+        match &self.original_usage {
+            Some(original) => {
+                out.push_str(&row("Original", original));
+                out.push_str(&row("Replay", &self.replay_usage));
+                out.push_str(&format!(
+                    "| Delta | {:+} | {:+} | {:+} |\n",
+                    self.replay_usage.calls as i64 - original.calls as i64,
+                    self.replay_usage.input_tokens as i64 - original.input_tokens as i64,
+                    self.replay_usage.output_tokens as i64 - original.output_tokens as i64,
+                ));
+            }
+            None => {
+                out.push_str("| Original | - | - | - |\n");
+                out.push_str(&row("Replay", &self.replay_usage));
+            }
+        }
+
+        out.push_str("\n## Turns\n\n| # | Same tools | Original tools | Replay tools | Replay tokens (in/out) | Errors |\n");
+        out.push_str("|---:|---|---|---|---|---:|\n");
+        for turn in &self.turns {
+            let tokens = turn
+                .replay
+                .usage
+                .map(|u| format!("{}/{}", u.input_tokens, u.output_tokens))
+                .unwrap_or_else(|| "-".to_string());
+            out.push_str(&format!(
+                "| {} | {} | {} | {} | {} | {} |\n",
+                turn.index,
+                if turn.same_tools { "yes" } else { "**no**" },
+                tool_list(&turn.original.tools),
+                tool_list(&turn.replay.tools),
+                tokens,
+                turn.replay.errors.len(),
+            ));
+        }
+
+        for turn in &self.turns {
+            out.push_str(&format!("\n### Turn {}\n\n", turn.index));
+            for line in turn.user_message.lines() {
+                out.push_str(&format!("> {}\n", line));
+            }
+            for (label, summary) in [("Original", &turn.original), ("Replay", &turn.replay)] {
+                out.push_str(&format!("\n#### {}\n\nTools: {}\n\n", label, tool_list(&summary.tools)));
+                if summary.final_text.is_empty() {
+                    out.push_str("_No assistant text._\n");
+                } else {
+                    out.push_str(&format!("{}\n", summary.final_text.trim_end()));
+                }
+                if !summary.errors.is_empty() {
+                    out.push_str("\nErrors:\n");
+                    for error in &summary.errors {
+                        out.push_str(&format!("- {}\n", error));
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+fn tool_list(tools: &[String]) -> String {
+    if tools.is_empty() {
+        "none".to_string()
+    } else {
+        tools.join(", ")
+    }
+}
+
+/// Replays a saved session on the model configured in a session config
+pub struct SessionReplay {
+    saved: SavedSession,
+    config: SessionConfig,
+    original_usage: Option<Arc<UsageStore>>,
+}
+
+impl SessionReplay {
+    /// Replay `saved` with the provider, model and prompt from `config`
+    ///
+    /// Approvals, dry-run tools, temperature, hooks and persistence are
+    /// overridden for the replay.
+    pub fn new(saved: SavedSession, config: SessionConfig) -> Self {
+        Self {
+            saved,
+            config,
+            original_usage: None,
+        }
+    }
+
+    /// Look up the original session's token usage in this store
+    pub fn with_original_usage(mut self, store: Arc<UsageStore>) -> Self {
+        self.original_usage = Some(store);
+        self
+    }
+
+    /// Run every turn and compare
+    pub async fn run(self) -> Result<ReplayReport> {
+        let recorded = recorded_turns(&self.saved.messages);
+        if recorded.is_empty() {
+            return Err(Error::Agent(format!("Session {} has no user messages to replay", self.saved.id)));
+        }
+        let mut warnings = self.warnings(&recorded);
+
+        let original_usage = match &self.original_usage {
+            Some(store) => {
+                let records = store.load(None)?;
+                let records: Vec<&UsageRecord> = records.iter().filter(|r| r.session_id == self.saved.id).collect();
+                (!records.is_empty()).then(|| UsageTotals::from_records(records))
+            }
+            None => None,
+        };
+
+        let usage_path = std::env::temp_dir().join(format!("cowork-replay-{}.jsonl", uuid::Uuid::new_v4()));
+        let usage_store = Arc::new(UsageStore::new(&usage_path));
+        let mut config = self.config.clone();
+        config.approval_config = ToolApprovalConfig::trust_all();
+        config.dry_run = true;
+        config.generation.temperature = Some(0.0);
+        config.enable_hooks = Some(false);
+        config.save_session = false;
+        config.auto_memory = false;
+        config.self_review = false;
+        config.watch_files = false;
+        config.scratch = false;
+        config.mcp_manager = None;
+        config.remote = None;
+        config.resume = None;
+        config.usage_store = Some(usage_store.clone());
+
+        let (manager, mut rx) = SessionManager::with_config(config);
+        let mut replayed = Vec::with_capacity(recorded.len());
+        for turn in &recorded {
+            let started = Utc::now();
+            manager
+                .push_message(REPLAY_SESSION, SessionInput::user_message(&turn.user_message))
+                .await?;
+            let summary = run_turn(&manager, &mut rx).await?;
+            replayed.push((summary, started, Utc::now()));
+        }
+        manager.stop_session(REPLAY_SESSION)?;
+
+        usage_store.flush().await;
+        let records = usage_store.load(None)?;
+        if let Err(e) = std::fs::remove_file(&usage_path) {
+            tracing::debug!("Failed to remove {}: {}", usage_path.display(), e);
+        }
+
+        let total = recorded.len();
+        let mut turns = Vec::with_capacity(total);
+        for (index, (turn, (mut replay, started, ended))) in recorded.into_iter().zip(replayed).enumerate() {
+            replay.usage = Some(UsageTotals::from_records(
+                records.iter().filter(|r| r.timestamp >= started && r.timestamp <= ended),
+            ));
+            if replay.errors.iter().any(|e| e.starts_with("Turn failed")) && index + 1 < total {
+                warnings.push(format!("Turn {} failed in the replay; later turns continue from its partial history", index + 1));
+            }
+            turns.push(TurnComparison {
+                index: index + 1,
+                same_tools: turn.summary.tools == replay.tools,
+                user_message: turn.user_message,
+                original: turn.summary,
+                replay,
+            });
+        }
+
+        Ok(ReplayReport {
+            session_id: self.saved.id,
+            session_name: self.saved.name,
+            original_provider: self.saved.provider,
+            original_model: self.saved.model,
+            provider: self.config.provider_id,
+            model: self.config.model,
+            replayed_at: Utc::now(),
+            warnings,
+            original_usage,
+            replay_usage: UsageTotals::from_records(&records),
+            turns,
+        })
+    }
+
+    /// Reasons the original and the replay may not be comparable
+    fn warnings(&self, recorded: &[RecordedTurn]) -> Vec<String> {
+        let mut warnings = Vec::new();
+        let version = self.saved.format_version;
+        if version < SESSION_FORMAT_VERSION {
+            warnings.push(format!(
+                "The session was saved in format version {} (current is {}): it doesn't record which model it ran on, and tools or prompts may have changed since",
+                version, SESSION_FORMAT_VERSION
+            ));
+        } else if version > SESSION_FORMAT_VERSION {
+            warnings.push(format!(
+                "The session was saved by a newer version of cowork (format version {}, this build reads {}); parts of it may be ignored",
+                version, SESSION_FORMAT_VERSION
+            ));
+        }
+
+        let workspace = self.config.workspace_path.clone();
+        let mut builder = ToolRegistryBuilder::new(workspace.clone())
+            .with_provider(&self.config.provider_id)
+            .with_skill_registry(Arc::new(SkillRegistry::with_builtins(workspace)));
+        if let Some(web_search) = self.config.web_search_config.clone() {
+            builder = builder.with_web_search_config(web_search);
+        }
+        let available: BTreeSet<String> = builder.build().list().into_iter().map(|t| t.name).collect();
+        let called: BTreeSet<&str> = recorded.iter().flat_map(|t| t.summary.tools.iter().map(String::as_str)).collect();
+        let (mcp, missing): (Vec<&str>, Vec<&str>) = called
+            .into_iter()
+            .filter(|name| !available.contains(*name))
+            .partition(|name| name.starts_with(MCP_TOOL_PREFIX));
+        if !missing.is_empty() {
+            warnings.push(format!(
+                "Tools called in the original no longer exist: {}",
+                missing.join(", ")
+            ));
+        }
+        if !mcp.is_empty() {
+            warnings.push(format!(
+                "MCP servers aren't started for replays, so these tools are unavailable: {}",
+                mcp.join(", ")
+            ));
+        }
+
+        let with_images: Vec<String> = recorded
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| t.images > 0)
+            .map(|(i, _)| (i + 1).to_string())
+            .collect();
+        if !with_images.is_empty() {
+            warnings.push(format!(
+                "Images attached in turn(s) {} are not replayed; only the text is sent",
+                with_images.join(", ")
+            ));
+        }
+        warnings
+    }
+}
+
+/// Drive one turn to Idle, granting approvals and answering questions
+async fn run_turn(manager: &SessionManager, rx: &mut super::OutputReceiver) -> Result<TurnSummary> {
+    let mut summary = TurnSummary::default();
+    loop {
+        let Some((session_id, output)) = rx.recv().await else {
+            return Err(Error::Agent("Replay session ended unexpectedly".to_string()));
+        };
+        if session_id != REPLAY_SESSION {
+            continue;
+        }
+        match output {
+            SessionOutput::Idle { .. } => return Ok(summary),
+            SessionOutput::ToolCall { name, .. } => summary.tools.push(name),
+            SessionOutput::AssistantMessage { content, .. } => {
+                summary.final_text = TOKEN_SUFFIX_RE.replace(&content, "").into_owned();
+            }
+            SessionOutput::ToolDone { name, success: false, output, .. } => {
+                let first_line = output.lines().next().unwrap_or_default();
+                summary.errors.push(format!("{} failed: {}", name, first_line));
+            }
+            SessionOutput::Error { message } => summary.errors.push(format!("Turn failed: {}", message)),
+            SessionOutput::ToolPending { id, .. } => {
+                manager.push_message(REPLAY_SESSION, SessionInput::approve_tool(id)).await?;
+            }
+            SessionOutput::Question { request_id, questions, .. } => {
+                let answers: HashMap<String, String> = questions
+                    .into_iter()
+                    .map(|q| {
+                        let answer = q.options.first().map(|o| o.label.clone()).unwrap_or_default();
+                        (q.header.unwrap_or(q.question), answer)
+                    })
+                    .collect();
+                manager
+                    .push_message(REPLAY_SESSION, SessionInput::answer_question(request_id, answers))
+                    .await?;
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::{assistant_with_tool_calls, tool_result_message, ToolCall};
+
+    fn call(id: &str, name: &str) -> ToolCall {
+        ToolCall {
+            call_id: id.to_string(),
+            fn_name: name.to_string(),
+            fn_arguments: serde_json::json!({}),
+            thought_signatures: None,
+        }
+    }
+
+    #[test]
+    fn test_recorded_turns() {
+        let messages = vec![
+            ChatMessage::user("<session-start-hook>\nbranch: main\n</session-start-hook>"),
+            ChatMessage::user("Fix the test\n\n<user-prompt-submit-hook>\nctx\n</user-prompt-submit-hook>"),
+            assistant_with_tool_calls(Some("Looking".to_string()), vec![call("c1", "Read"), call("c2", "Grep")]),
+            tool_result_message("c1", "fn main() {}"),
+            tool_result_message("c2", "no matches"),
+            ChatMessage::user("<system-reminder>\nplan approved\n</system-reminder>"),
+            assistant_with_tool_calls(None, vec![call("c3", "Edit")]),
+            tool_result_message("c3", "ok"),
+            ChatMessage::assistant("Fixed it"),
+            ChatMessage::user("Thanks"),
+            ChatMessage::assistant("You're welcome"),
+        ];
+
+        let turns = recorded_turns(&messages);
+        assert_eq!(turns.len(), 2);
+        assert_eq!(turns[0].user_message, "Fix the test");
+        assert_eq!(turns[0].summary.tools, vec!["Read", "Grep", "Edit"]);
+        assert_eq!(turns[0].summary.final_text, "Fixed it");
+        assert_eq!(turns[1].user_message, "Thanks");
+        assert!(turns[1].summary.tools.is_empty());
+    }
+
+    #[test]
+    fn test_token_suffix_stripped() {
+        assert_eq!(TOKEN_SUFFIX_RE.replace("Done [1k/20/200k 1%]", ""), "Done");
+        assert_eq!(TOKEN_SUFFIX_RE.replace("Done [120/8]", ""), "Done");
+        assert_eq!(TOKEN_SUFFIX_RE.replace("See [the docs]", ""), "See [the docs]");
+    }
+}
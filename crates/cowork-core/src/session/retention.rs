@@ -0,0 +1,373 @@
+//! Retention for saved sessions - deleting them, one at a time or by policy
+//!
+//! Deleting a session removes everything kept for it in the sessions
+//! directory: its file, its autosave, its image attachments and its scratch
+//! directory. Its audit log is a record of what the agent did, so it is only
+//! removed when asked for. (Images spilled from tool output go to a shared
+//! temporary directory by content hash, not per session, and are left alone.)
+//!
+//! `prune_saved_sessions` applies `SessionsConfig`: sessions past
+//! `max_age_days` go first, then the least valuable of the rest until
+//! `max_count` remain. Pinned sessions are never pruned.
+
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+
+use crate::config::SessionsConfig;
+use crate::error::{Error, Result};
+use super::audit::remove_audit_log;
+use super::persistence::{
+    get_attachments_dir, get_autosave_dir, get_sessions_dir, list_saved_sessions, read_session_file,
+    remove_autosave, remove_scratch_dir, write_atomic, SavedSession,
+};
+use super::title::TitleSource;
+
+/// Why a session was pruned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PruneReason {
+    /// Not updated within `max_age_days`
+    Age,
+    /// Beyond `max_count`, being among the least valuable
+    Count,
+}
+
+/// A session deleted (or, in a dry run, to be deleted) by pruning
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrunedSession {
+    pub id: String,
+    pub name: String,
+    pub message_count: usize,
+    pub updated_at: DateTime<Utc>,
+    pub reason: PruneReason,
+}
+
+impl fmt::Display for PrunedSession {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self.reason {
+            PruneReason::Age => "too old",
+            PruneReason::Count => "over the count limit",
+        };
+        write!(
+            f,
+            "{} \"{}\" - {} messages, updated {} ({})",
+            self.id,
+            self.name,
+            self.message_count,
+            self.updated_at.format("%Y-%m-%d"),
+            reason
+        )
+    }
+}
+
+/// What a prune did, or would do in a dry run
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PruneReport {
+    pub dry_run: bool,
+    /// Sessions deleted, least valuable first
+    pub pruned: Vec<PrunedSession>,
+    /// Sessions left, pinned ones included
+    pub kept: usize,
+    /// Pinned sessions, which are always kept
+    pub pinned: usize,
+    /// Sessions that couldn't be deleted, with the reason
+    pub errors: Vec<String>,
+}
+
+impl fmt::Display for PruneReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verb = if self.dry_run { "Would delete" } else { "Deleted" };
+        write!(
+            f,
+            "{} {} saved sessions, keeping {} ({} pinned)",
+            verb,
+            self.pruned.len(),
+            self.kept,
+            self.pinned
+        )?;
+        for session in &self.pruned {
+            write!(f, "\n  {}", session)?;
+        }
+        for error in &self.errors {
+            write!(f, "\n  Failed: {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+/// Sessions the policy prunes, least valuable first
+///
+/// Unpinned sessions not updated since `max_age_days` before `now` are
+/// pruned for age. The remaining unpinned sessions are ranked by message
+/// count, then by how recently they were updated, and all but the first
+/// `max_count` are pruned.
+pub fn plan_prune(sessions: &[SavedSession], config: &SessionsConfig, now: DateTime<Utc>) -> Vec<PrunedSession> {
+    let pruned = |session: &SavedSession, reason| PrunedSession {
+        id: session.id.clone(),
+        name: session.name.clone(),
+        message_count: session.messages.len(),
+        updated_at: session.updated_at,
+        reason,
+    };
+
+    let cutoff = (config.max_age_days > 0)
+        .then(|| now - chrono::Duration::days(config.max_age_days.min(i64::MAX as u64) as i64));
+    let (mut too_old, mut remaining): (Vec<&SavedSession>, Vec<&SavedSession>) = sessions
+        .iter()
+        .filter(|session| !session.pinned)
+        .partition(|session| cutoff.is_some_and(|cutoff| session.updated_at < cutoff));
+
+    too_old.sort_by_key(|session| session.updated_at);
+    let mut plan: Vec<PrunedSession> = too_old.into_iter().map(|session| pruned(session, PruneReason::Age)).collect();
+
+    if config.max_count > 0 && remaining.len() > config.max_count {
+        remaining.sort_by(|a, b| {
+            b.messages
+                .len()
+                .cmp(&a.messages.len())
+                .then(b.updated_at.cmp(&a.updated_at))
+        });
+        plan.extend(
+            remaining[config.max_count..]
+                .iter()
+                .rev()
+                .map(|session| pruned(session, PruneReason::Count)),
+        );
+    }
+    plan
+}
+
+/// Delete the saved sessions `config` prunes
+///
+/// With `dry_run` nothing is deleted; the report lists what would be. Audit
+/// logs are only deleted with `with_audit_log`.
+pub fn prune_saved_sessions(config: &SessionsConfig, dry_run: bool, with_audit_log: bool) -> Result<PruneReport> {
+    let sessions = list_saved_sessions()?;
+    let plan = plan_prune(&sessions, config, Utc::now());
+    let mut report = PruneReport {
+        dry_run,
+        pinned: sessions.iter().filter(|session| session.pinned).count(),
+        ..Default::default()
+    };
+
+    for session in plan {
+        if !dry_run && let Err(e) = delete_saved_session(&session.id, with_audit_log) {
+            report.errors.push(format!("{}: {}", session.id, e));
+            continue;
+        }
+        report.pruned.push(session);
+    }
+    report.kept = sessions.len() - report.pruned.len();
+    Ok(report)
+}
+
+/// Prune saved sessions in the background, as the CLI and app do on start
+///
+/// Does nothing unless a limit is set. Must be called within a Tokio runtime.
+pub fn prune_in_background(config: SessionsConfig) {
+    if !config.is_enabled() {
+        return;
+    }
+    tokio::task::spawn_blocking(move || match prune_saved_sessions(&config, false, false) {
+        Ok(report) if report.pruned.is_empty() && report.errors.is_empty() => {
+            debug!("No saved sessions to prune");
+        }
+        Ok(report) => info!("{}", report),
+        Err(e) => warn!("Failed to prune saved sessions: {}", e),
+    });
+}
+
+/// Delete a saved session and everything kept for it
+///
+/// The audit log is only deleted with `with_audit_log`. Returns whether the
+/// session had a saved file or an autosave to delete.
+pub fn delete_saved_session(session_id: &str, with_audit_log: bool) -> Result<bool> {
+    let file_name = session_file_name(session_id)?;
+    let found = match std::fs::remove_file(get_sessions_dir()?.join(&file_name)) {
+        Ok(()) => true,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => false,
+        Err(e) => return Err(e.into()),
+    };
+    let found = found || get_autosave_dir()?.join(&file_name).exists();
+    remove_autosave(session_id)?;
+
+    match std::fs::remove_dir_all(get_attachments_dir(session_id)?) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e.into()),
+    }
+    remove_scratch_dir(session_id)?;
+    if with_audit_log {
+        remove_audit_log(session_id)?;
+    }
+    Ok(found)
+}
+
+/// Pin or unpin a saved session, updating its file and its autosave
+///
+/// Fails if the session has neither.
+pub fn set_session_pinned(session_id: &str, pinned: bool) -> Result<()> {
+    update_saved_session(session_id, |saved| {
+        let changed = saved.pinned != pinned;
+        saved.pinned = pinned;
+        changed
+    })
+}
+
+/// Retitle a saved session, updating its file and its autosave
+///
+/// A generated title doesn't replace one the user set. Fails if the
+/// session has neither file.
+pub fn set_session_title(session_id: &str, title: &str, source: TitleSource) -> Result<()> {
+    update_saved_session(session_id, |saved| {
+        if saved.title_source > source || (saved.name == title && saved.title_source == source) {
+            return false;
+        }
+        saved.name = title.to_string();
+        saved.title_source = source;
+        true
+    })
+}
+
+/// Apply `update` to a saved session's file and autosave, rewriting those it
+/// changed (`update` returns whether it did)
+fn update_saved_session(session_id: &str, mut update: impl FnMut(&mut SavedSession) -> bool) -> Result<()> {
+    let file_name = session_file_name(session_id)?;
+    let mut found = false;
+    for dir in [get_sessions_dir()?, get_autosave_dir()?] {
+        let path = dir.join(&file_name);
+        if let Some(mut saved) = read_session_file(&path)? {
+            found = true;
+            if update(&mut saved) {
+                write_atomic(&path, serde_json::to_string_pretty(&saved)?.as_bytes())?;
+            }
+        }
+    }
+    if !found {
+        return Err(Error::Workspace(format!("No saved session {}", session_id)));
+    }
+    Ok(())
+}
+
+/// `<session id>.json`, rejecting IDs that would reach outside the directory
+fn session_file_name(session_id: &str) -> Result<String> {
+    let is_file_name = std::path::Path::new(session_id)
+        .file_name()
+        .is_some_and(|name| name == session_id);
+    if !is_file_name {
+        return Err(Error::Workspace(format!("Invalid session id: {}", session_id)));
+    }
+    Ok(format!("{}.json", session_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::ChatMessage;
+
+    fn session(id: &str, messages: usize, days_old: i64, pinned: bool, now: DateTime<Utc>) -> SavedSession {
+        SavedSession {
+            id: id.to_string(),
+            name: id.to_string(),
+            title_source: TitleSource::Fallback,
+            format_version: 1,
+            provider: None,
+            model: None,
+            messages: (0..messages).map(|i| ChatMessage::user(format!("message {}", i))).collect(),
+            todos: Vec::new(),
+            created_at: now - chrono::Duration::days(days_old),
+            updated_at: now - chrono::Duration::days(days_old),
+            parent_id: None,
+            fork_point: None,
+            recovered: false,
+            pinned,
+            pinned_messages: Vec::new(),
+            template: None,
+            tool_usage: Default::default(),
+        }
+    }
+
+    fn ids(plan: &[PrunedSession]) -> Vec<(&str, PruneReason)> {
+        plan.iter().map(|pruned| (pruned.id.as_str(), pruned.reason)).collect()
+    }
+
+    #[test]
+    fn test_no_limits_prunes_nothing() {
+        let now = Utc::now();
+        let sessions = vec![session("a", 1, 400, false, now), session("b", 0, 1, false, now)];
+        assert!(plan_prune(&sessions, &SessionsConfig::default(), now).is_empty());
+    }
+
+    #[test]
+    fn test_count_and_age_limits_combined() {
+        let now = Utc::now();
+        let sessions = vec![
+            session("old-big", 50, 40, false, now),
+            session("older", 2, 60, false, now),
+            session("big", 30, 5, false, now),
+            session("small-recent", 3, 1, false, now),
+            session("small-stale", 3, 10, false, now),
+            session("tiny", 1, 2, false, now),
+        ];
+        let config = SessionsConfig { max_count: 2, max_age_days: 30 };
+        let plan = plan_prune(&sessions, &config, now);
+
+        // Age goes first regardless of size, oldest first; then the least
+        // valuable of what's left, fewest messages and least recent first
+        assert_eq!(
+            ids(&plan),
+            vec![
+                ("older", PruneReason::Age),
+                ("old-big", PruneReason::Age),
+                ("tiny", PruneReason::Count),
+                ("small-stale", PruneReason::Count),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pinned_survive_both_limits() {
+        let now = Utc::now();
+        let sessions = vec![
+            session("pinned-old", 1, 365, true, now),
+            session("pinned-tiny", 0, 1, true, now),
+            session("a", 5, 1, false, now),
+            session("b", 4, 1, false, now),
+            session("c", 3, 100, false, now),
+        ];
+        let config = SessionsConfig { max_count: 1, max_age_days: 30 };
+        let plan = plan_prune(&sessions, &config, now);
+
+        // Pinned sessions neither get pruned nor use up max_count
+        assert_eq!(ids(&plan), vec![("c", PruneReason::Age), ("b", PruneReason::Count)]);
+    }
+
+    #[test]
+    fn test_report_display() {
+        let now = Utc::now();
+        let report = PruneReport {
+            dry_run: true,
+            pruned: plan_prune(
+                &[session("a", 2, 100, false, now)],
+                &SessionsConfig { max_count: 0, max_age_days: 30 },
+                now,
+            ),
+            kept: 3,
+            pinned: 1,
+            errors: Vec::new(),
+        };
+        let text = report.to_string();
+        assert!(text.starts_with("Would delete 1 saved sessions, keeping 3 (1 pinned)"), "{}", text);
+        assert!(text.contains("a \"a\" - 2 messages"), "{}", text);
+        assert!(text.contains("(too old)"), "{}", text);
+    }
+
+    #[test]
+    fn test_rejects_path_ids() {
+        assert!(delete_saved_session("../escape", false).is_err());
+        assert!(set_session_pinned("a/b", true).is_err());
+    }
+}
@@ -0,0 +1,65 @@
+//! Text the user has selected in the app, sent with their next message
+//!
+//! The app reports the selection with `SessionInput::SetSelection` as it
+//! changes. The next message takes it: written in place of `${SELECTION}`
+//! when the message contains that, as a reminder ahead of it otherwise. It
+//! is then cleared, so each selection reaches the model at most once.
+
+/// Placeholder a message can use to put the selection in place
+pub const SELECTION_VAR: &str = "${SELECTION}";
+
+/// Most characters of a selection kept; the rest is cut with a note
+pub const MAX_SELECTION_CHARS: usize = 20_000;
+
+/// The selection as it is stored: None when empty, cut to `MAX_SELECTION_CHARS`
+pub fn capped_selection(text: &str) -> Option<String> {
+    if text.trim().is_empty() {
+        return None;
+    }
+    let chars = text.chars().count();
+    if chars <= MAX_SELECTION_CHARS {
+        return Some(text.to_string());
+    }
+    let kept: String = text.chars().take(MAX_SELECTION_CHARS).collect();
+    Some(format!(
+        "{}\n[selection cut: {} of {} characters shown]",
+        kept, MAX_SELECTION_CHARS, chars
+    ))
+}
+
+/// The message as the model sees it, with the selection
+pub fn with_selection(message: &str, selection: &str) -> String {
+    if message.contains(SELECTION_VAR) {
+        return message.replace(SELECTION_VAR, selection);
+    }
+    format!(
+        "<system-reminder>\nThe user has this text selected in the app, which their message may refer to:\n<selection>\n{}\n</selection>\n</system-reminder>\n\n{}",
+        selection, message
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_placeholder_or_reminder() {
+        assert_eq!(with_selection("Explain ${SELECTION} briefly", "fn main()"), "Explain fn main() briefly");
+
+        let message = with_selection("What does this do?", "fn main()");
+        assert!(message.starts_with("<system-reminder>"), "{}", message);
+        assert!(message.contains("<selection>\nfn main()\n</selection>"), "{}", message);
+        assert!(message.ends_with("\n\nWhat does this do?"), "{}", message);
+    }
+
+    #[test]
+    fn test_capped_selection() {
+        assert_eq!(capped_selection("  \n"), None);
+        assert_eq!(capped_selection("src/main.rs").as_deref(), Some("src/main.rs"));
+
+        let long = "é".repeat(MAX_SELECTION_CHARS + 5);
+        let capped = capped_selection(&long).unwrap();
+        assert!(capped.starts_with(&"é".repeat(MAX_SELECTION_CHARS)));
+        assert!(capped.ends_with(&format!("[selection cut: {} of {} characters shown]", MAX_SELECTION_CHARS, MAX_SELECTION_CHARS + 5)));
+    }
+}
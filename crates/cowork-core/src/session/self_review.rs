@@ -0,0 +1,154 @@
+//! Reviewing a turn's changes against the request
+//!
+//! With `SessionConfig::self_review` on, a turn that wrote files or ran
+//! commands ends with one request to the provider's fast model: the user's
+//! message, the turn's diffs (from its `TurnChangeSet`) and its commands,
+//! asking for obvious gaps such as tests that were asked for and not added,
+//! or a TODO left in place of code. The findings come as
+//! `SessionOutput::SelfReview` and nothing acts on them. Like an explanation,
+//! the request stays out of the history, and its tokens are recorded with
+//! `SELF_REVIEW_PURPOSE`.
+
+use super::explain::excerpt;
+use super::turn_changes::FileChange;
+use crate::formatting::{diff_lines, PathDisplay};
+use crate::prompt::builtin::reminders::SELF_REVIEW;
+use crate::provider::ChatMessage;
+
+/// `UsageRecord::purpose` of a self-review's tokens
+pub const SELF_REVIEW_PURPOSE: &str = "self_review";
+
+/// Findings above which the turn's `Idle` carries a warning, unless configured (any finding)
+pub const DEFAULT_SELF_REVIEW_THRESHOLD: usize = 0;
+
+/// Most characters of the user's message sent
+const MAX_REQUEST_CHARS: usize = 4_000;
+
+/// Most files whose diffs are sent (the others are only counted)
+const MAX_REVIEWED_FILES: usize = 20;
+
+/// Most diff lines sent per file
+const MAX_DIFF_LINES: usize = 60;
+
+/// Most commands sent, and characters of each
+const MAX_COMMANDS: usize = 20;
+const MAX_COMMAND_CHARS: usize = 300;
+
+/// Most findings kept, and characters of each
+const MAX_FINDINGS: usize = 10;
+const MAX_FINDING_CHARS: usize = 300;
+
+/// The request asking whether the turn's `changes` and `commands` do what
+/// the user asked in `request`
+pub fn review_request(request: &str, changes: &[FileChange], commands: &[String], paths: &PathDisplay) -> Vec<ChatMessage> {
+    let mut sections = vec![format!("The user asked:\n\n<request>\n{}\n</request>", excerpt(request.trim(), MAX_REQUEST_CHARS))];
+
+    if !changes.is_empty() {
+        let mut files = Vec::new();
+        for change in changes.iter().take(MAX_REVIEWED_FILES) {
+            let (added, removed) = change.line_counts();
+            let status = if change.created() {
+                "created"
+            } else if change.removed() {
+                "removed"
+            } else {
+                "edited"
+            };
+            let lines = diff_lines(&change.before_text(), &change.after_text(), 1);
+            let mut diff: Vec<String> = lines
+                .iter()
+                .take(MAX_DIFF_LINES)
+                .map(|line| match line.line_type.as_str() {
+                    "added" => format!("+ {}", line.content),
+                    "removed" => format!("- {}", line.content),
+                    _ => format!("  {}", line.content),
+                })
+                .collect();
+            if lines.len() > MAX_DIFF_LINES {
+                diff.push(format!("  ... ({} more lines)", lines.len() - MAX_DIFF_LINES));
+            }
+            files.push(format!(
+                "<file path=\"{}\" status=\"{}\" added=\"{}\" removed=\"{}\">\n{}\n</file>",
+                paths.display(&change.path.to_string_lossy()),
+                status,
+                added,
+                removed,
+                diff.join("\n")
+            ));
+        }
+        if changes.len() > MAX_REVIEWED_FILES {
+            files.push(format!("({} more files changed)", changes.len() - MAX_REVIEWED_FILES));
+        }
+        sections.push(format!("Files the assistant changed:\n\n{}", files.join("\n\n")));
+    }
+
+    if !commands.is_empty() {
+        let mut listed: Vec<String> = commands
+            .iter()
+            .take(MAX_COMMANDS)
+            .map(|command| format!("- {}", excerpt(command.trim(), MAX_COMMAND_CHARS)))
+            .collect();
+        if commands.len() > MAX_COMMANDS {
+            listed.push(format!("({} more commands)", commands.len() - MAX_COMMANDS));
+        }
+        sections.push(format!("Commands the assistant ran:\n\n{}", listed.join("\n")));
+    }
+
+    sections.push(SELF_REVIEW.to_string());
+    vec![ChatMessage::user(sections.join("\n\n"))]
+}
+
+/// The findings in the model's reply, one per line (none for "NONE")
+pub fn parse_findings(reply: &str) -> Vec<String> {
+    reply
+        .lines()
+        .map(|line| line.trim().trim_start_matches(['-', '*', '•']).trim())
+        .filter(|line| !line.is_empty() && !line.trim_end_matches('.').eq_ignore_ascii_case("none"))
+        .take(MAX_FINDINGS)
+        .map(|line| excerpt(line, MAX_FINDING_CHARS))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::message_text_content;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_review_request() {
+        let changes = vec![
+            FileChange {
+                path: PathBuf::from("/work/src/lib.rs"),
+                before: Some(b"fn a() {}\n".to_vec()),
+                after: Some(b"fn a() {}\nfn b() { todo!() }\n".to_vec()),
+            },
+            FileChange {
+                path: PathBuf::from("/work/src/new.rs"),
+                before: None,
+                after: Some("x\n".repeat(100).into_bytes()),
+            },
+        ];
+        let commands = vec!["cargo build".to_string()];
+        let request = review_request("Add b() with tests", &changes, &commands, &PathDisplay::new("/work"));
+        let text = message_text_content(&request[0]);
+        assert!(text.contains("<request>\nAdd b() with tests\n</request>"));
+        assert!(text.contains("<file path=\"src/lib.rs\" status=\"edited\" added=\"1\" removed=\"0\">"));
+        assert!(text.contains("+ fn b() { todo!() }"));
+        assert!(text.contains("status=\"created\" added=\"100\""));
+        assert!(text.contains("... (40 more lines)"));
+        assert!(text.contains("- cargo build"));
+        assert!(text.ends_with(SELF_REVIEW));
+    }
+
+    #[test]
+    fn test_parse_findings() {
+        assert!(parse_findings("NONE").is_empty());
+        assert!(parse_findings("  None.\n").is_empty());
+        assert_eq!(
+            parse_findings("- No tests were added\n\n* src/lib.rs: b() is left as todo!()\n"),
+            ["No tests were added", "src/lib.rs: b() is left as todo!()"]
+        );
+        assert_eq!(parse_findings(&"- finding\n".repeat(20)).len(), MAX_FINDINGS);
+    }
+}
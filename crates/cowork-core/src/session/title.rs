@@ -0,0 +1,166 @@
+//! Session titles
+//!
+//! A session is named after the start of its first message until its first
+//! turn completes. Then a request to the provider's fast model (the `fast`
+//! tier of `ModelTiers`) names it from that message and the reply. The
+//! request is made outside the session: it isn't added to the history and
+//! its tokens aren't counted against the session.
+//!
+//! A title the user sets with `SessionInput::SetTitle` is final; nothing
+//! generated replaces it.
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::prompt::builtin::reminders::SESSION_TITLE;
+use crate::provider::{ChatMessage, GenAIProvider};
+
+/// Most words kept of a generated title
+pub const MAX_TITLE_WORDS: usize = 8;
+
+/// Characters of the first message used as the title until one is generated
+pub const FALLBACK_TITLE_CHARS: usize = 50;
+
+/// Most characters of the message and reply sent to name the session
+const MAX_EXCERPT_CHARS: usize = 2_000;
+
+/// Where a session's title came from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TitleSource {
+    /// The start of the first message, until a title is generated
+    #[default]
+    Fallback,
+    /// Generated after the first turn
+    Generated,
+    /// Set by the user
+    User,
+}
+
+impl TitleSource {
+    pub fn is_fallback(&self) -> bool {
+        *self == Self::Fallback
+    }
+}
+
+/// A session's title, shared by its agent loop and the manager
+#[derive(Debug, Default)]
+pub struct SessionTitle(Mutex<Option<(String, TitleSource)>>);
+
+impl SessionTitle {
+    pub fn new(text: impl Into<String>, source: TitleSource) -> Self {
+        Self(Mutex::new(Some((text.into(), source))))
+    }
+
+    /// The title, if the session has one yet
+    pub fn get(&self) -> Option<String> {
+        self.0.lock().as_ref().map(|(text, _)| text.clone())
+    }
+
+    pub fn source(&self) -> TitleSource {
+        self.0.lock().as_ref().map_or(TitleSource::Fallback, |(_, source)| *source)
+    }
+
+    /// Set the title unless one from a later source is already set (a
+    /// generated title doesn't replace the user's); returns whether it was set
+    pub fn set(&self, text: impl Into<String>, source: TitleSource) -> bool {
+        let mut title = self.0.lock();
+        if title.as_ref().is_some_and(|(_, current)| *current > source) {
+            return false;
+        }
+        *title = Some((text.into(), source));
+        true
+    }
+}
+
+/// The first message's opening words, as a title (None when it has no text)
+pub fn fallback_title(message: &str) -> Option<String> {
+    let words = message.split_whitespace().collect::<Vec<_>>().join(" ");
+    if words.is_empty() {
+        return None;
+    }
+    if words.chars().count() <= FALLBACK_TITLE_CHARS {
+        return Some(words);
+    }
+    let kept: String = words.chars().take(FALLBACK_TITLE_CHARS).collect();
+    Some(format!("{}…", kept.trim_end()))
+}
+
+/// A title from the model's reply: its first line, without quotes, a
+/// `Title:` prefix or a trailing period, cut to `MAX_TITLE_WORDS` words
+pub fn clean_title(reply: &str) -> Option<String> {
+    let line = reply.lines().map(str::trim).find(|line| !line.is_empty())?;
+    let line = match line.get(..6) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("title:") => &line[6..],
+        _ => line,
+    };
+    let line = line
+        .trim_matches(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '`' | '*' | '#' | '“' | '”'))
+        .trim_end_matches('.');
+    let title = line.split_whitespace().take(MAX_TITLE_WORDS).collect::<Vec<_>>().join(" ");
+    (!title.is_empty()).then_some(title)
+}
+
+/// Name a session from its first message and the reply to it
+///
+/// `provider` should be the fast model, without the session's system prompt.
+/// Returns None when the model gives nothing usable.
+pub async fn generate_title(provider: &GenAIProvider, message: &str, reply: &str) -> Result<Option<String>> {
+    let request = ChatMessage::user(format!(
+        "Here is the start of the conversation:\n\n<user>\n{}\n</user>\n\n<assistant>\n{}\n</assistant>\n\n{}",
+        excerpt(message),
+        excerpt(reply),
+        SESSION_TITLE
+    ));
+    let response = provider.chat(vec![request], None).await?;
+    Ok(response.content.as_deref().and_then(clean_title))
+}
+
+fn excerpt(text: &str) -> String {
+    text.trim().chars().take(MAX_EXCERPT_CHARS).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fallback_title() {
+        assert_eq!(fallback_title("  \n"), None);
+        assert_eq!(fallback_title("Fix the\n  login   test").as_deref(), Some("Fix the login test"));
+
+        let long = format!("{} tail", "word ".repeat(20));
+        let title = fallback_title(&long).unwrap();
+        assert!(title.ends_with('…'), "{}", title);
+        assert_eq!(title.chars().count(), FALLBACK_TITLE_CHARS);
+    }
+
+    #[test]
+    fn test_clean_title() {
+        assert_eq!(clean_title("\n\"Fix flaky login test.\"\n").as_deref(), Some("Fix flaky login test"));
+        assert_eq!(clean_title("Title: **Session retention**").as_deref(), Some("Session retention"));
+        assert_eq!(
+            clean_title("one two three four five six seven eight nine ten").as_deref(),
+            Some("one two three four five six seven eight")
+        );
+        assert_eq!(clean_title("  \"\"  "), None);
+        assert_eq!(clean_title(""), None);
+    }
+
+    #[test]
+    fn test_user_title_is_final() {
+        let title = SessionTitle::default();
+        assert_eq!(title.get(), None);
+        assert!(title.source().is_fallback());
+
+        assert!(title.set("Fix the login", TitleSource::Fallback));
+        assert!(title.set("Login test fix", TitleSource::Generated));
+        assert!(!title.set("Fix the login", TitleSource::Fallback));
+        assert!(title.set("My title", TitleSource::User));
+        assert!(!title.set("Login test fix", TitleSource::Generated));
+        assert!(title.set("Renamed", TitleSource::User));
+        assert_eq!(title.get().as_deref(), Some("Renamed"));
+        assert_eq!(title.source(), TitleSource::User);
+    }
+}
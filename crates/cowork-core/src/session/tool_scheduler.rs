@@ -0,0 +1,292 @@
+//! Ordering and concurrency limits for a response's tool calls
+//!
+//! The agent loop runs the tool calls of one response in parallel. Calls that
+//! touch the same file can't be left to race: two Edits would both apply to
+//! the original content and one would be lost, and a Read racing a Write
+//! could return either version. Before spawning each call, in the order the
+//! model emitted them, the agent loop takes a [`ToolSlot`] from the
+//! scheduler, and the spawned task waits on it before running the tool:
+//!
+//! - A call that writes a file waits for every earlier call on that file
+//! - A call that reads a file waits for earlier writes to it, but not for
+//!   other reads
+//! - A call that writes files it only picks as it runs (BatchEdit) is a
+//!   barrier: it waits for every earlier call, and every later call waits
+//!   for it
+//! - At most `max_concurrent_tools` calls run at once
+//!
+//! A slot only waits on earlier calls, so the waits can't deadlock. Files are
+//! keyed by the tool's path argument, resolved against the workspace with the
+//! parent directory canonicalized (so `./a.rs`, `a.rs` and a symlinked
+//! directory name the same file). Tools without a path argument are only
+//! subject to the cap and to barriers.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use futures::future::{FutureExt, Shared};
+use serde_json::Value;
+use tokio::sync::{oneshot, OwnedSemaphorePermit, Semaphore};
+
+use crate::tools::filesystem::normalize_path;
+
+/// How a file tool uses its file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Access {
+    Read,
+    Write,
+}
+
+/// Tools that read or write a single file, the argument holding its path,
+/// and how they use it
+const FILE_TOOLS: &[(&str, &str, Access)] = &[
+    ("Read", "file_path", Access::Read),
+    ("Write", "file_path", Access::Write),
+    ("FinishFile", "file_path", Access::Write),
+    ("Edit", "file_path", Access::Write),
+    ("NotebookEdit", "notebook_path", Access::Write),
+    ("ExportDocument", "file_path", Access::Write),
+    ("WriteDocx", "file_path", Access::Write),
+    ("WriteXlsx", "file_path", Access::Write),
+    ("WritePptx", "file_path", Access::Write),
+];
+
+/// Tools writing files they pick as they run, scheduled as barriers
+const BARRIER_TOOLS: &[&str] = &["BatchEdit"];
+
+/// Resolves once a call has finished (or was dropped without running)
+type Finished = Shared<oneshot::Receiver<()>>;
+
+/// Calls scheduled on one file that later calls may have to wait for
+#[derive(Default)]
+struct FileCalls {
+    /// The most recent call writing the file
+    write: Option<Finished>,
+    /// Calls reading the file since that write
+    reads: Vec<Finished>,
+}
+
+/// Hands out slots for a response's tool calls
+pub(crate) struct ToolScheduler {
+    workspace: PathBuf,
+    /// Execution slots (`None` = no limit)
+    slots: Option<Arc<Semaphore>>,
+    files: HashMap<PathBuf, FileCalls>,
+    /// The most recent barrier call
+    barrier: Option<Finished>,
+    /// Calls scheduled since that barrier
+    since_barrier: Vec<Finished>,
+}
+
+/// A call's place in the schedule
+pub(crate) struct ToolSlot {
+    /// Earlier calls this one has to wait for
+    after: Vec<Finished>,
+    slots: Option<Arc<Semaphore>>,
+    finished: oneshot::Sender<()>,
+}
+
+/// Held while a tool runs; dropping it lets the calls waiting on it go ahead
+pub(crate) struct ToolGuard {
+    _slot: Option<OwnedSemaphorePermit>,
+    _finished: oneshot::Sender<()>,
+}
+
+impl ToolScheduler {
+    /// Create a scheduler allowing `max_concurrent` calls at once (0 = unlimited)
+    pub(crate) fn new(workspace: impl Into<PathBuf>, max_concurrent: usize) -> Self {
+        Self {
+            workspace: workspace.into(),
+            slots: (max_concurrent > 0).then(|| Arc::new(Semaphore::new(max_concurrent))),
+            files: HashMap::new(),
+            barrier: None,
+            since_barrier: Vec::new(),
+        }
+    }
+
+    /// Forget the previous response's calls (they have all finished)
+    pub(crate) fn reset(&mut self) {
+        self.files.clear();
+        self.barrier = None;
+        self.since_barrier.clear();
+    }
+
+    /// Schedule the next call; calls must be scheduled in emission order
+    pub(crate) fn schedule(&mut self, tool_name: &str, arguments: &Value) -> ToolSlot {
+        let (finished, rx) = oneshot::channel();
+        let rx = rx.shared();
+        let mut after: Vec<Finished> = self.barrier.iter().cloned().collect();
+
+        if BARRIER_TOOLS.contains(&tool_name) {
+            // Everything earlier is behind it now, so later calls need only wait for it
+            after.append(&mut self.since_barrier);
+            self.files.clear();
+            self.barrier = Some(rx);
+        } else if let Some((path, access)) = self.file_access(tool_name, arguments) {
+            self.since_barrier.push(rx.clone());
+            let calls = self.files.entry(path).or_default();
+            after.extend(calls.write.clone());
+            match access {
+                Access::Read => calls.reads.push(rx),
+                Access::Write => {
+                    after.append(&mut calls.reads);
+                    calls.write = Some(rx);
+                }
+            }
+        } else {
+            self.since_barrier.push(rx);
+        }
+
+        ToolSlot {
+            after,
+            slots: self.slots.clone(),
+            finished,
+        }
+    }
+
+    /// The file a call writes, if it's a file tool that writes
+    pub(crate) fn written_file(&self, tool_name: &str, arguments: &Value) -> Option<PathBuf> {
+        self.file_access(tool_name, arguments)
+            .filter(|(_, access)| *access == Access::Write)
+            .map(|(path, _)| path)
+    }
+
+    /// The file a call uses, if it's a file tool with a path argument
+    fn file_access(&self, tool_name: &str, arguments: &Value) -> Option<(PathBuf, Access)> {
+        let (_, key, access) = FILE_TOOLS.iter().find(|(name, _, _)| *name == tool_name)?;
+        let path = arguments[key].as_str()?;
+        Some((self.resolve(Path::new(path)), *access))
+    }
+
+    fn resolve(&self, path: &Path) -> PathBuf {
+        resolve_path(&self.workspace, path)
+    }
+}
+
+/// `path` resolved against `workspace`, with its parent directory
+/// canonicalized, as calls on files are keyed
+pub(crate) fn resolve_path(workspace: &Path, path: &Path) -> PathBuf {
+    let path = normalize_path(&workspace.join(path));
+    let canonical = path
+        .parent()
+        .and_then(|parent| dunce::canonicalize(parent).ok())
+        .zip(path.file_name())
+        .map(|(parent, name)| parent.join(name));
+    canonical.unwrap_or(path)
+}
+
+impl ToolSlot {
+    /// Wait for the earlier calls on the same file, then for an execution slot
+    pub(crate) async fn acquire(self) -> ToolGuard {
+        for earlier in self.after {
+            // An error only means the earlier call was dropped; either way it's done
+            let _ = earlier.await;
+        }
+        let slot = match self.slots {
+            Some(slots) => Some(slots.acquire_owned().await.expect("tool slots are never closed")),
+            None => None,
+        };
+        ToolGuard {
+            _slot: slot,
+            _finished: self.finished,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::time::Duration;
+
+    fn edit(path: &str) -> Value {
+        json!({ "file_path": path, "old_string": "a", "new_string": "b" })
+    }
+
+    /// Whether the slot can be acquired without waiting
+    async fn ready(slot: ToolSlot) -> Option<ToolGuard> {
+        tokio::time::timeout(Duration::from_millis(50), slot.acquire()).await.ok()
+    }
+
+    #[tokio::test]
+    async fn test_writes_wait_for_earlier_calls_on_the_file() {
+        let workspace = tempfile::tempdir().unwrap();
+        let mut scheduler = ToolScheduler::new(workspace.path(), 0);
+        let abs = workspace.path().join("a.txt");
+
+        let read = scheduler.schedule("Read", &json!({ "file_path": "a.txt" }));
+        let second_read = scheduler.schedule("Read", &json!({ "file_path": abs.to_str().unwrap() }));
+        let write = scheduler.schedule("Edit", &edit("./a.txt"));
+        let other_file = scheduler.schedule("Write", &json!({ "file_path": "b.txt", "content": "" }));
+        let last_read = scheduler.schedule("Read", &json!({ "file_path": "a.txt" }));
+
+        // Reads of one file run together; other files and other tools aren't held up
+        let read = ready(read).await.unwrap();
+        let second_read = ready(second_read).await.unwrap();
+        assert!(ready(other_file).await.is_some());
+        assert!(ready(scheduler.schedule("Bash", &json!({ "command": "ls" }))).await.is_some());
+
+        let (tx, mut rx) = oneshot::channel();
+        tokio::spawn(async move {
+            let _guard = write.acquire().await;
+            let _ = tx.send(());
+        });
+        drop(read);
+        let early = tokio::time::timeout(Duration::from_millis(50), &mut rx).await;
+        assert!(early.is_err(), "the write ran before every earlier read finished");
+        drop(second_read);
+        rx.await.unwrap();
+
+        // The write has finished (its guard was dropped in the task)
+        assert!(ready(last_read).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_batch_edit_is_a_barrier() {
+        let workspace = tempfile::tempdir().unwrap();
+        let mut scheduler = ToolScheduler::new(workspace.path(), 0);
+        let batch_edit = json!({ "find": "a", "replace": "b" });
+
+        let read = ready(scheduler.schedule("Read", &json!({ "file_path": "a.txt" }))).await.unwrap();
+        let shell = ready(scheduler.schedule("Bash", &json!({ "command": "ls" }))).await.unwrap();
+        let batch = scheduler.schedule("BatchEdit", &batch_edit);
+        let after_file = scheduler.schedule("Write", &json!({ "file_path": "b.txt", "content": "" }));
+        let after_other = scheduler.schedule("Grep", &json!({ "pattern": "a" }));
+
+        let (tx, mut rx) = oneshot::channel();
+        let (release, released) = oneshot::channel::<()>();
+        tokio::spawn(async move {
+            let _guard = batch.acquire().await;
+            let _ = tx.send(());
+            let _ = released.await;
+        });
+        drop(read);
+        let early = tokio::time::timeout(Duration::from_millis(50), &mut rx).await;
+        assert!(early.is_err(), "the batch edit ran before every earlier call finished");
+        drop(shell);
+        rx.await.unwrap();
+
+        // Later calls wait for the batch edit, on any file or none
+        let (after_file, after_other) = (after_file.acquire(), after_other.acquire());
+        tokio::pin!(after_file, after_other);
+        assert!(tokio::time::timeout(Duration::from_millis(50), &mut after_file).await.is_err());
+        assert!(tokio::time::timeout(Duration::from_millis(50), &mut after_other).await.is_err());
+        let _ = release.send(());
+        after_file.await;
+        after_other.await;
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_cap() {
+        let workspace = tempfile::tempdir().unwrap();
+        let mut scheduler = ToolScheduler::new(workspace.path(), 2);
+        let first = ready(scheduler.schedule("Bash", &json!({}))).await.unwrap();
+        let _second = ready(scheduler.schedule("Grep", &json!({}))).await.unwrap();
+        let third = scheduler.schedule("Glob", &json!({}));
+        let fourth = scheduler.schedule("Glob", &json!({}));
+        assert!(ready(third).await.is_none());
+        drop(first);
+        assert!(ready(fourth).await.is_some());
+    }
+}
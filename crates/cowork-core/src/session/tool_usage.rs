@@ -0,0 +1,163 @@
+//! Per-tool usage counters
+//!
+//! The agent loop counts, for each tool, the calls the model made and how
+//! they ended, how long they ran and how much of their results went back to
+//! the model. Forty greps and two edits tell a different story than fifteen
+//! edits and three test runs, which helps tune prompts and tool
+//! descriptions.
+//!
+//! A session's counters are reported in `SessionStats` (as of its last
+//! finished turn) and kept in `SavedSession`, so `tool_usage_report` can add
+//! them up across saved sessions (`cowork usage --by tool`).
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use super::persistence::{list_saved_sessions, SavedSession};
+use super::types::ToolDoneStatus;
+
+/// What one tool's calls came to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolCounts {
+    /// Calls the model made, however they ended
+    pub invocations: u64,
+    pub successes: u64,
+    /// Calls that failed, including invalid arguments and cancelled calls
+    pub failures: u64,
+    /// Calls refused without running: by the user, a rule or a reviewer
+    pub rejections: u64,
+    /// Time spent running, approval waits included
+    pub duration_ms: u64,
+    /// Bytes of results sent back to the model, after truncation
+    pub result_bytes: u64,
+}
+
+impl ToolCounts {
+    /// Add another tool's (or session's) counts to these
+    fn add(&mut self, other: &ToolCounts) {
+        self.invocations += other.invocations;
+        self.successes += other.successes;
+        self.failures += other.failures;
+        self.rejections += other.rejections;
+        self.duration_ms += other.duration_ms;
+        self.result_bytes += other.result_bytes;
+    }
+}
+
+/// A session's tool calls, by tool name
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ToolUsageStats {
+    pub tools: BTreeMap<String, ToolCounts>,
+}
+
+impl ToolUsageStats {
+    /// Count a call to `tool` that ended with `status`, after `duration`,
+    /// sending `result_bytes` back to the model
+    pub fn record(&mut self, tool: &str, status: ToolDoneStatus, duration: Duration, result_bytes: usize) {
+        let counts = self.tools.entry(tool.to_string()).or_default();
+        counts.invocations += 1;
+        match status {
+            ToolDoneStatus::Success => counts.successes += 1,
+            ToolDoneStatus::Failed | ToolDoneStatus::Cancelled => counts.failures += 1,
+            ToolDoneStatus::Rejected => counts.rejections += 1,
+        }
+        counts.duration_ms += duration.as_millis() as u64;
+        counts.result_bytes += result_bytes as u64;
+    }
+
+    /// The counts of one tool, if the session called it
+    pub fn get(&self, tool: &str) -> Option<&ToolCounts> {
+        self.tools.get(tool)
+    }
+
+    /// Whether no tool was called
+    pub fn is_empty(&self) -> bool {
+        self.tools.is_empty()
+    }
+
+    /// Add another session's counts to these
+    pub fn merge(&mut self, other: &ToolUsageStats) {
+        for (tool, counts) in &other.tools {
+            self.tools.entry(tool.clone()).or_default().add(counts);
+        }
+    }
+
+    /// The `limit` most called tools, most called first (ties by name)
+    pub fn top(&self, limit: usize) -> Vec<(&str, &ToolCounts)> {
+        let mut tools: Vec<(&str, &ToolCounts)> = self.tools.iter().map(|(tool, counts)| (tool.as_str(), counts)).collect();
+        tools.sort_by(|a, b| b.1.invocations.cmp(&a.1.invocations).then(a.0.cmp(b.0)));
+        tools.truncate(limit);
+        tools
+    }
+
+    /// All tools' counts added up
+    pub fn total(&self) -> ToolCounts {
+        let mut total = ToolCounts::default();
+        for counts in self.tools.values() {
+            total.add(counts);
+        }
+        total
+    }
+}
+
+/// Tool calls added up across saved sessions
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ToolUsageReport {
+    /// Only sessions updated since then were counted (None = all)
+    pub since: Option<DateTime<Utc>>,
+    /// Sessions counted
+    pub sessions: usize,
+    pub usage: ToolUsageStats,
+}
+
+impl ToolUsageReport {
+    /// Add up the tool usage of `sessions` updated since `since`
+    pub fn build(sessions: &[SavedSession], since: Option<DateTime<Utc>>) -> Self {
+        let mut report = Self {
+            since,
+            ..Self::default()
+        };
+        for session in sessions.iter().filter(|session| since.is_none_or(|since| session.updated_at >= since)) {
+            report.sessions += 1;
+            report.usage.merge(&session.tool_usage);
+        }
+        report
+    }
+}
+
+/// Tool usage across the saved sessions updated within `window` (None = all)
+pub fn tool_usage_report(window: Option<chrono::Duration>) -> Result<ToolUsageReport> {
+    let since = window.map(|window| Utc::now() - window);
+    Ok(ToolUsageReport::build(&list_saved_sessions()?, since))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_by_outcome() {
+        let mut usage = ToolUsageStats::default();
+        usage.record("Grep", ToolDoneStatus::Success, Duration::from_millis(20), 100);
+        usage.record("Grep", ToolDoneStatus::Success, Duration::from_millis(30), 50);
+        usage.record("Edit", ToolDoneStatus::Rejected, Duration::ZERO, 40);
+        usage.record("Bash", ToolDoneStatus::Cancelled, Duration::from_millis(5), 0);
+
+        let grep = usage.get("Grep").unwrap();
+        assert_eq!((grep.invocations, grep.successes, grep.duration_ms, grep.result_bytes), (2, 2, 50, 150));
+        assert_eq!(usage.get("Edit").unwrap().rejections, 1);
+        assert_eq!(usage.get("Bash").unwrap().failures, 1);
+        assert_eq!(usage.top(2).iter().map(|(tool, _)| *tool).collect::<Vec<_>>(), ["Grep", "Bash"]);
+        assert_eq!(usage.total().invocations, 4);
+
+        let mut merged = usage.clone();
+        merged.merge(&usage);
+        assert_eq!(merged.get("Grep").unwrap().invocations, 4);
+        assert_eq!(merged.total().result_bytes, 2 * usage.total().result_bytes);
+    }
+}
@@ -0,0 +1,324 @@
+//! Numbered session outputs, for a frontend catching up after a reload
+//!
+//! When the webview reloads (or a listener attaches late) it misses whatever
+//! the output handler emitted in the meantime, including approvals and
+//! questions the session is still waiting on. The output handler records
+//! each output with `SessionManager::record_output` as it delivers it, which
+//! numbers it: sequence numbers are per session and increase by one, so a
+//! consumer that sees one skipped knows it missed something.
+//! `SessionManager::transcript` then returns the outputs after the last one
+//! it saw.
+//!
+//! A `Transcript` keeps the last `capacity` outputs of a session. Ephemeral
+//! outputs (text deltas, tool activity) are numbered but not kept: the
+//! messages they lead up to are. Outputs are kept as `Arc`s shared with the
+//! delivered event, except tool outputs of at least `INTERN_MIN_BYTES`: the
+//! transcript keeps those once in an arena of its own (see `session::arena`)
+//! and puts them back when returning the output. Approvals and questions
+//! still unanswered are tracked separately and always returned, even once
+//! they have left the buffer.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use serde::{Serialize, Serializer};
+
+use super::arena::{is_reference, MemoryStats, ResultArena};
+use super::types::{SessionInput, SessionOutput};
+
+/// Outputs kept per session by default
+pub const DEFAULT_TRANSCRIPT_EVENTS: usize = 500;
+
+/// An output with its place in the session's transcript
+///
+/// Serializes as the output with a `seq` field added.
+#[derive(Debug, Clone)]
+pub struct SequencedOutput {
+    /// Position among the session's outputs, from 1
+    pub seq: u64,
+    pub output: Arc<SessionOutput>,
+}
+
+impl Serialize for SequencedOutput {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Flat<'a> {
+            seq: u64,
+            #[serde(flatten)]
+            output: &'a SessionOutput,
+        }
+
+        Flat {
+            seq: self.seq,
+            output: &self.output,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// A session's outputs since a sequence number
+#[derive(Debug, Clone, Serialize)]
+pub struct TranscriptSync {
+    /// Outputs after the requested sequence number, in order
+    pub events: Vec<SequencedOutput>,
+    /// The last sequence number assigned (0 before any output)
+    pub last_seq: u64,
+    /// Whether `events` holds every kept output after the requested one;
+    /// false when older outputs have already left the buffer
+    pub complete: bool,
+    /// Approvals and questions the session is still waiting on, oldest first
+    pub pending: Vec<SequencedOutput>,
+}
+
+#[derive(Debug, Default)]
+struct Buffer {
+    events: VecDeque<SequencedOutput>,
+    pending: Vec<SequencedOutput>,
+    last_seq: u64,
+    /// Highest sequence number of an output that left the buffer
+    evicted_seq: u64,
+    /// Large tool outputs of the kept events
+    arena: ResultArena,
+}
+
+/// The recent outputs of one session
+#[derive(Debug)]
+pub struct Transcript {
+    buffer: Mutex<Buffer>,
+    capacity: usize,
+}
+
+impl Transcript {
+    /// Create a transcript keeping the last `capacity` outputs (0 = none)
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            buffer: Mutex::new(Buffer::default()),
+            capacity,
+        }
+    }
+
+    /// Number an output and keep it
+    pub fn record(&self, output: SessionOutput) -> SequencedOutput {
+        let mut buffer = self.buffer.lock();
+        buffer.last_seq += 1;
+        let event = SequencedOutput {
+            seq: buffer.last_seq,
+            output: Arc::new(output),
+        };
+
+        match event.output.as_ref() {
+            SessionOutput::ToolPending { .. } | SessionOutput::ApprovalDelegated { .. } | SessionOutput::Question { .. } => {
+                buffer.pending.push(event.clone())
+            }
+            SessionOutput::ToolCall { id, .. } | SessionOutput::ToolDone { id, .. } | SessionOutput::ToolResult { id, .. } => {
+                buffer.settle(id)
+            }
+            // The turn is over: nothing it asked for is waited on any more
+            SessionOutput::Idle { .. } | SessionOutput::Cancelled | SessionOutput::Suspended => buffer.pending.clear(),
+            _ => {}
+        }
+
+        if !event.output.is_ephemeral() {
+            let kept = tool_output(&event.output)
+                .and_then(|output| buffer.arena.intern(output))
+                .and_then(|reference| with_tool_output(&event.output, reference))
+                .map_or_else(|| event.clone(), |output| SequencedOutput { seq: event.seq, output: Arc::new(output) });
+            buffer.events.push_back(kept);
+            let mut evicted = false;
+            while buffer.events.len() > self.capacity {
+                if let Some(event) = buffer.events.pop_front() {
+                    buffer.evicted_seq = event.seq;
+                    evicted = true;
+                }
+            }
+            if evicted {
+                let Buffer { events, arena, .. } = &mut *buffer;
+                arena.retain(events.iter().filter_map(|event| tool_output(&event.output)));
+            }
+        }
+        event
+    }
+
+    /// Note an input that answers an approval or question
+    pub fn observe_input(&self, input: &SessionInput) {
+        let mut buffer = self.buffer.lock();
+        match input {
+            SessionInput::ApproveTool { tool_call_id }
+            | SessionInput::RejectTool { tool_call_id, .. }
+            | SessionInput::ApproveDelegated { tool_call_id, .. }
+            | SessionInput::RejectDelegated { tool_call_id, .. } => buffer.settle(tool_call_id),
+            SessionInput::AnswerQuestion { request_id, .. } => buffer.settle(request_id),
+            SessionInput::CancelTool { id } => buffer.settle(id),
+            SessionInput::Cancel => buffer.pending.clear(),
+            _ => {}
+        }
+    }
+
+    /// The outputs after `since_seq` (0 for all kept outputs)
+    pub fn since(&self, since_seq: u64) -> TranscriptSync {
+        let buffer = self.buffer.lock();
+        TranscriptSync {
+            events: buffer
+                .events
+                .iter()
+                .filter(|event| event.seq > since_seq)
+                .map(|event| buffer.resolve(event))
+                .collect(),
+            last_seq: buffer.last_seq,
+            complete: since_seq >= buffer.evicted_seq,
+            pending: buffer.pending.clone(),
+        }
+    }
+
+    /// The last sequence number assigned
+    pub fn last_seq(&self) -> u64 {
+        self.buffer.lock().last_seq
+    }
+
+    /// What the tool outputs of the kept events hold (`history_bytes` are
+    /// theirs, references at their own size)
+    pub fn memory_stats(&self) -> MemoryStats {
+        let buffer = self.buffer.lock();
+        buffer.arena.text_stats(buffer.events.iter().filter_map(|event| tool_output(&event.output)))
+    }
+}
+
+impl Buffer {
+    /// Forget the approval or question with this ID
+    fn settle(&mut self, id: &str) {
+        self.pending.retain(|event| pending_id(&event.output) != Some(id));
+    }
+
+    /// A kept event with its tool output put back
+    fn resolve(&self, event: &SequencedOutput) -> SequencedOutput {
+        match tool_output(&event.output).filter(|output| is_reference(output)) {
+            Some(reference) => {
+                let output = self.arena.resolve(reference).into_owned();
+                SequencedOutput {
+                    seq: event.seq,
+                    output: Arc::new(with_tool_output(&event.output, output).expect("a tool output was found")),
+                }
+            }
+            None => event.clone(),
+        }
+    }
+}
+
+/// Output of a finished tool call
+fn tool_output(output: &SessionOutput) -> Option<&str> {
+    match output {
+        SessionOutput::ToolDone { output, .. } | SessionOutput::ToolResult { output, .. } => Some(output),
+        _ => None,
+    }
+}
+
+/// A copy of a finished tool call's output with `text` as the tool output
+fn with_tool_output(output: &SessionOutput, text: String) -> Option<SessionOutput> {
+    let mut output = output.clone();
+    match &mut output {
+        SessionOutput::ToolDone { output, .. } | SessionOutput::ToolResult { output, .. } => *output = text,
+        _ => return None,
+    }
+    Some(output)
+}
+
+/// ID of the call or request an approval or question is about
+fn pending_id(output: &SessionOutput) -> Option<&str> {
+    match output {
+        SessionOutput::ToolPending { id, .. } | SessionOutput::ApprovalDelegated { id, .. } => Some(id),
+        SessionOutput::Question { request_id, .. } => Some(request_id),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::INTERN_MIN_BYTES;
+    use serde_json::json;
+
+    fn pending(id: &str) -> SessionOutput {
+        SessionOutput::ToolPending {
+            id: id.to_string(),
+            name: "Write".to_string(),
+            arguments: json!({}),
+            description: None,
+            subagent_id: None,
+            policy_required: false,
+            security_warning: None,
+            generated_files: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_keeps_the_last_outputs_in_order() {
+        let transcript = Transcript::new(3);
+        for i in 1..=5 {
+            let event = transcript.record(SessionOutput::user_message(format!("m{}", i), "hi"));
+            assert_eq!(event.seq, i);
+        }
+
+        let sync = transcript.since(3);
+        assert_eq!(sync.events.iter().map(|e| e.seq).collect::<Vec<_>>(), vec![4, 5]);
+        assert!(sync.complete);
+        // Outputs 1 and 2 have left the buffer
+        let sync = transcript.since(1);
+        assert_eq!(sync.events.iter().map(|e| e.seq).collect::<Vec<_>>(), vec![3, 4, 5]);
+        assert!(!sync.complete);
+        assert_eq!(sync.last_seq, 5);
+    }
+
+    #[test]
+    fn test_ephemeral_outputs_are_numbered_not_kept() {
+        let transcript = Transcript::new(10);
+        transcript.record(SessionOutput::user_message("m1", "hi"));
+        assert_eq!(transcript.record(SessionOutput::text_delta("a1", "He")).seq, 2);
+        transcript.record(SessionOutput::assistant_message("a1", "Hello"));
+
+        let sync = transcript.since(0);
+        assert_eq!(sync.events.iter().map(|e| e.seq).collect::<Vec<_>>(), vec![1, 3]);
+        assert!(sync.complete);
+        assert!(transcript.since(2).complete);
+    }
+
+    #[test]
+    fn test_large_tool_outputs_kept_once() {
+        let transcript = Transcript::new(4);
+        let big = "match\n".repeat(INTERN_MIN_BYTES);
+        // The live event and the stored one; the same file read twice
+        for id in ["t1", "t2"] {
+            let event = transcript.record(SessionOutput::tool_done(id, "Grep", true, &big));
+            assert!(matches!(event.output.as_ref(), SessionOutput::ToolDone { output, .. } if *output == big));
+        }
+
+        let stats = transcript.memory_stats();
+        assert_eq!((stats.interned_count, stats.references), (1, 2));
+        assert!(stats.history_bytes < 100);
+        assert_eq!(stats.resolved_bytes, 2 * big.len());
+        let sync = transcript.since(0);
+        assert!(sync.events.iter().all(|e| matches!(e.output.as_ref(), SessionOutput::ToolDone { output, .. } if *output == big)));
+
+        // Dropped once no kept event refers to it
+        for i in 0..4 {
+            transcript.record(SessionOutput::user_message(format!("m{}", i), "hi"));
+        }
+        assert_eq!(transcript.memory_stats(), MemoryStats::default());
+    }
+
+    #[test]
+    fn test_pending_approvals_outlive_the_buffer_until_answered() {
+        let transcript = Transcript::new(1);
+        transcript.record(pending("call-1"));
+        transcript.record(pending("call-2"));
+        transcript.record(SessionOutput::user_message("m1", "hi"));
+
+        let sync = transcript.since(0);
+        assert_eq!(sync.pending.iter().map(|e| e.seq).collect::<Vec<_>>(), vec![1, 2]);
+
+        transcript.observe_input(&SessionInput::approve_tool("call-1"));
+        assert_eq!(transcript.since(0).pending.iter().map(|e| e.seq).collect::<Vec<_>>(), vec![2]);
+        transcript.record(SessionOutput::idle());
+        assert!(transcript.since(0).pending.is_empty());
+    }
+}
@@ -0,0 +1,194 @@
+//! What a turn changed in the workspace
+//!
+//! The agent loop starts a `TurnChangeSet` with each turn. Before a call that
+//! writes a file runs, the set snapshots the file, the first time the turn
+//! writes it, so it knows what every written file held when the turn began:
+//! enough to diff the turn's changes (for the end-of-turn self-review) or to
+//! put the files back as they were. Shell commands are listed as they run;
+//! what they did on disk isn't tracked.
+
+use std::path::{Path, PathBuf};
+
+use crate::error::Result;
+use crate::formatting::diff_stats;
+
+/// A file whose content the turn changed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileChange {
+    pub path: PathBuf,
+    /// Content when the turn began (None = the file didn't exist)
+    pub before: Option<Vec<u8>>,
+    /// Content now (None = the file is gone)
+    pub after: Option<Vec<u8>>,
+}
+
+impl FileChange {
+    /// Whether the turn created the file
+    pub fn created(&self) -> bool {
+        self.before.is_none()
+    }
+
+    /// Whether the turn removed the file
+    pub fn removed(&self) -> bool {
+        self.after.is_none()
+    }
+
+    /// The content before, as text (empty for a new file)
+    pub fn before_text(&self) -> String {
+        text(self.before.as_deref())
+    }
+
+    /// The content now, as text (empty for a removed file)
+    pub fn after_text(&self) -> String {
+        text(self.after.as_deref())
+    }
+
+    /// Lines added and removed by the turn
+    pub fn line_counts(&self) -> (usize, usize) {
+        diff_stats(&self.before_text(), &self.after_text())
+    }
+}
+
+fn text(content: Option<&[u8]>) -> String {
+    content.map(|bytes| String::from_utf8_lossy(bytes).into_owned()).unwrap_or_default()
+}
+
+/// Files written and commands run during one turn
+#[derive(Debug, Clone, Default)]
+pub struct TurnChangeSet {
+    /// Each file the turn wrote, with its content when the turn began, in
+    /// the order they were first written
+    originals: Vec<(PathBuf, Option<Vec<u8>>)>,
+    commands: Vec<String>,
+}
+
+impl TurnChangeSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remember what `path` holds before the turn writes it
+    ///
+    /// Only the first call for a path in a turn snapshots it, so later
+    /// writes still compare against the content the turn began with.
+    pub fn snapshot(&mut self, path: &Path) {
+        if self.originals.iter().any(|(known, _)| known == path) {
+            return;
+        }
+        self.originals.push((path.to_path_buf(), std::fs::read(path).ok()));
+    }
+
+    /// Note a shell command the turn ran
+    pub fn record_command(&mut self, command: impl Into<String>) {
+        self.commands.push(command.into());
+    }
+
+    /// Commands run, in order
+    pub fn commands(&self) -> &[String] {
+        &self.commands
+    }
+
+    /// Whether the turn wrote no file and ran no command
+    pub fn is_empty(&self) -> bool {
+        self.originals.is_empty() && self.commands.is_empty()
+    }
+
+    /// The written files whose content now differs from when the turn began
+    ///
+    /// A write that failed, was rejected or put back the same content is
+    /// not a change.
+    pub fn changes(&self) -> Vec<FileChange> {
+        self.originals
+            .iter()
+            .filter_map(|(path, before)| {
+                let after = std::fs::read(path).ok();
+                (after != *before).then(|| FileChange {
+                    path: path.clone(),
+                    before: before.clone(),
+                    after,
+                })
+            })
+            .collect()
+    }
+
+    /// Put the changed files back as they were when the turn began,
+    /// removing the ones it created; returns the files restored
+    pub fn restore(&self) -> Result<Vec<PathBuf>> {
+        let mut restored = Vec::new();
+        for change in self.changes() {
+            match &change.before {
+                Some(content) => {
+                    if let Some(parent) = change.path.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                    std::fs::write(&change.path, content)?;
+                }
+                None => std::fs::remove_file(&change.path)?,
+            }
+            restored.push(change.path);
+        }
+        Ok(restored)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_changes_compare_with_the_turn_start() {
+        let dir = TempDir::new().unwrap();
+        let edited = dir.path().join("lib.rs");
+        let untouched = dir.path().join("main.rs");
+        let created = dir.path().join("new.rs");
+        std::fs::write(&edited, "fn a() {}\n").unwrap();
+        std::fs::write(&untouched, "fn main() {}\n").unwrap();
+
+        let mut changes = TurnChangeSet::new();
+        assert!(changes.is_empty());
+        changes.snapshot(&edited);
+        std::fs::write(&edited, "fn a() {}\nfn b() {}\n").unwrap();
+        // A second write in the turn keeps the first snapshot
+        changes.snapshot(&edited);
+        std::fs::write(&edited, "fn a() {}\nfn b() {}\nfn c() {}\n").unwrap();
+        // Snapshotted but never changed (e.g. the write was rejected)
+        changes.snapshot(&untouched);
+        changes.snapshot(&created);
+        std::fs::write(&created, "pub mod x;\n").unwrap();
+        changes.record_command("cargo test");
+
+        let changed = changes.changes();
+        assert_eq!(changed.iter().map(|c| c.path.clone()).collect::<Vec<_>>(), [edited.clone(), created.clone()]);
+        assert_eq!(changed[0].line_counts(), (2, 0));
+        assert!(!changed[0].created());
+        assert!(changed[1].created());
+        assert_eq!(changed[1].after_text(), "pub mod x;\n");
+        assert_eq!(changes.commands(), ["cargo test"]);
+    }
+
+    #[test]
+    fn test_restore_puts_files_back() {
+        let dir = TempDir::new().unwrap();
+        let edited = dir.path().join("lib.rs");
+        let created = dir.path().join("new.rs");
+        let removed = dir.path().join("old.rs");
+        std::fs::write(&edited, "before\n").unwrap();
+        std::fs::write(&removed, "old\n").unwrap();
+
+        let mut changes = TurnChangeSet::new();
+        for path in [&edited, &created, &removed] {
+            changes.snapshot(path);
+        }
+        std::fs::write(&edited, "after\n").unwrap();
+        std::fs::write(&created, "new\n").unwrap();
+        std::fs::remove_file(&removed).unwrap();
+        assert!(changes.changes()[2].removed());
+
+        assert_eq!(changes.restore().unwrap().len(), 3);
+        assert_eq!(std::fs::read_to_string(&edited).unwrap(), "before\n");
+        assert!(!created.exists());
+        assert_eq!(std::fs::read_to_string(&removed).unwrap(), "old\n");
+        assert!(changes.changes().is_empty());
+    }
+}
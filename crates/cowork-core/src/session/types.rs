@@ -9,12 +9,19 @@ use std::sync::Arc;
 use tokio::sync::mpsc;
 
 use crate::config::PromptSystemConfig;
-use crate::formatting::DiffLine;
+use crate::formatting::{DiffLine, FileDiff};
 use crate::mcp_manager::McpServerManager;
-use crate::orchestration::ToolScope;
+use crate::provider::GenerationParams;
+use crate::orchestration::{ToolProfile, ToolScope};
+use crate::tools::filesystem::GeneratedFile;
+use crate::tools::planning::PlanStatus;
+use crate::tools::task::TodoItem;
 use crate::prompt::ComponentRegistry;
+use crate::recipes::{RecipeStepInfo, StepReport, StepStatus};
 
-use super::approval::ApprovalSender;
+use super::approval::{ApprovalSender, ToolProgress};
+use super::attachments::{image_mime_type, validate_image, IMAGE_MIME_TYPES};
+use super::overrides::{TurnApproval, TurnOverrides};
 
 /// Unique identifier for a session
 pub type SessionId = String;
@@ -55,27 +62,28 @@ impl ImageAttachment {
     }
 
     /// Load an image from a file path
+    ///
+    /// The type comes from the extension, and the file must pass
+    /// `validate_image` (supported type, size cap, matching content).
     pub fn from_file(path: &std::path::Path) -> std::io::Result<Self> {
         use base64::{engine::general_purpose::STANDARD, Engine};
-        use std::io::Read;
-
-        let mut file = std::fs::File::open(path)?;
-        let mut buffer = Vec::new();
-        file.read_to_end(&mut buffer)?;
-
-        let data = STANDARD.encode(&buffer);
-
-        let media_type = match path.extension().and_then(|e| e.to_str()) {
-            Some("png") => "image/png",
-            Some("jpg") | Some("jpeg") => "image/jpeg",
-            Some("gif") => "image/gif",
-            Some("webp") => "image/webp",
-            Some("svg") => "image/svg+xml",
-            _ => "application/octet-stream",
-        };
+        use std::io::{Error, ErrorKind};
+
+        let media_type = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .and_then(image_mime_type)
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("not a supported image (supported: {})", IMAGE_MIME_TYPES.join(", ")),
+                )
+            })?;
+        let buffer = std::fs::read(path)?;
+        validate_image(&buffer, media_type).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
 
         Ok(Self {
-            data,
+            data: STANDARD.encode(&buffer),
             media_type: media_type.to_string(),
         })
     }
@@ -88,7 +96,7 @@ impl ImageAttachment {
     /// - @../parent/path.gif
     /// - @~/home/relative/path.png
     ///
-    /// Only image files (png, jpg, jpeg, gif, webp, svg) are extracted.
+    /// Only image files (png, jpg, jpeg, gif, webp) are extracted.
     /// Non-image @paths are left in the text.
     pub fn parse_from_text(input: &str, workspace: &std::path::Path) -> (String, Vec<Self>) {
         use std::path::PathBuf;
@@ -131,15 +139,11 @@ impl ImageAttachment {
             };
 
             // Check if it's a supported image file
-            let ext = expanded_path
+            let is_image = expanded_path
                 .extension()
                 .and_then(|e| e.to_str())
-                .map(|e: &str| e.to_lowercase());
-
-            let is_image = matches!(
-                ext.as_deref(),
-                Some("png" | "jpg" | "jpeg" | "gif" | "webp" | "svg")
-            );
+                .and_then(image_mime_type)
+                .is_some();
 
             if is_image {
                 // Add the text before this @path
@@ -174,11 +178,19 @@ impl ImageAttachment {
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum SessionInput {
     /// User sends a text-only message
-    UserMessage { content: String },
+    UserMessage {
+        content: String,
+        /// Settings for this message's turn only (see `session::overrides`)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        overrides: Option<TurnOverrides>,
+    },
     /// User sends a message with image attachments
     UserMessageWithImages {
         content: String,
         images: Vec<ImageAttachment>,
+        /// Settings for this message's turn only
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        overrides: Option<TurnOverrides>,
     },
     /// User approves a tool execution
     ApproveTool { tool_call_id: String },
@@ -187,6 +199,15 @@ pub enum SessionInput {
         tool_call_id: String,
         reason: Option<String>,
     },
+    /// A reviewer approves a delegated tool call (see
+    /// `SessionOutput::ApprovalDelegated`); `approver` goes to the audit log
+    ApproveDelegated { tool_call_id: String, approver: String },
+    /// A reviewer refuses a delegated tool call
+    RejectDelegated {
+        tool_call_id: String,
+        approver: String,
+        reason: Option<String>,
+    },
     /// User answers a question from ask_user_question tool
     AnswerQuestion {
         request_id: String,
@@ -194,8 +215,84 @@ pub enum SessionInput {
     },
     /// User cancels the current turn
     Cancel,
+    /// User cancels one tool call, letting the rest of the turn continue
+    CancelTool { id: String },
+    /// User asks why a call waiting for approval is wanted (answered with
+    /// `SessionOutput::ToolExplanation`; the call stays pending)
+    ExplainTool { id: String },
     /// User toggles plan mode
     SetPlanMode { active: bool },
+    /// User drops all messages waiting behind the current turn
+    ClearQueue,
+    /// User changes the generation parameters for later LLM requests
+    SetGenerationParams { params: GenerationParams },
+    /// User approves a plan; it becomes the task list of the next turn
+    ApprovePlan {
+        /// Plan name; the session's current plan if omitted
+        name: Option<String>,
+    },
+    /// User asks for a new answer to their last message: the last turn is
+    /// dropped from the history and its message runs again
+    ///
+    /// Refused while a turn is in flight.
+    RegenerateLast {
+        /// Temperature for the new answer only (the session's otherwise)
+        #[serde(default)]
+        temperature: Option<f32>,
+    },
+    /// User rewrites an earlier message: it and everything after it are
+    /// dropped from the history and the new text runs in its place
+    ///
+    /// Refused while a turn is in flight.
+    EditUserMessage {
+        /// ID from the message's `SessionOutput::UserMessage`
+        message_id: String,
+        new_content: String,
+    },
+    /// User runs a recipe from the workspace's `.cowork/recipes`, as a turn
+    /// of its own (see `crate::recipes`)
+    RunRecipe {
+        name: String,
+        /// Arguments as given to `/recipe run <name> [args]`
+        #[serde(default)]
+        args: String,
+    },
+    /// User pins or unpins the session, exempting it from pruning of saved
+    /// sessions; its saved files are updated at once
+    SetPinned { pinned: bool },
+    /// User pins or unpins a message, by the ID its `UserMessage` or
+    /// `AssistantMessage` carried, or the latest reply without one; pinned
+    /// messages survive compaction (see `session::pins`)
+    PinMessage {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        message_id: Option<String>,
+        pinned: bool,
+    },
+    /// User selects text in the app; the next message carries it to the
+    /// model (see `session::selection`). Empty text clears the selection.
+    SetSelection { text: String },
+    /// User renames the session; no title is generated for it afterwards,
+    /// and its saved files are updated at once
+    SetTitle { title: String },
+    /// User switches the tools offered to the model, from the next turn
+    /// (see `orchestration::tool_profile`)
+    SetToolProfile { profile: ToolProfile },
+    /// User compacts the history now, between turns (see `context::summarizer`);
+    /// unset fields come from `[summarizer]`
+    Compact {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        mode: Option<crate::context::CompactionMode>,
+        /// Model to summarize with instead of the summarizer's
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        model: Option<String>,
+        /// What the summary should pay attention to
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        focus: Option<String>,
+    },
+    /// An input whose `type` this version doesn't know, from a newer
+    /// frontend (see `from_json`); answered with `SessionOutput::UnsupportedInput`
+    #[serde(skip)]
+    Unsupported { input_type: String },
 }
 
 impl SessionInput {
@@ -203,6 +300,7 @@ impl SessionInput {
     pub fn user_message(content: impl Into<String>) -> Self {
         Self::UserMessage {
             content: content.into(),
+            overrides: None,
         }
     }
 
@@ -211,9 +309,18 @@ impl SessionInput {
         Self::UserMessageWithImages {
             content: content.into(),
             images,
+            overrides: None,
         }
     }
 
+    /// Run a user message's turn with `overrides` (other inputs are unchanged)
+    pub fn with_overrides(mut self, turn_overrides: TurnOverrides) -> Self {
+        if let Self::UserMessage { overrides, .. } | Self::UserMessageWithImages { overrides, .. } = &mut self {
+            *overrides = (!turn_overrides.is_empty()).then_some(turn_overrides);
+        }
+        self
+    }
+
     /// Create an approve tool input
     pub fn approve_tool(tool_call_id: impl Into<String>) -> Self {
         Self::ApproveTool {
@@ -229,6 +336,23 @@ impl SessionInput {
         }
     }
 
+    /// Create a reviewer's approval of a delegated call
+    pub fn approve_delegated(tool_call_id: impl Into<String>, approver: impl Into<String>) -> Self {
+        Self::ApproveDelegated {
+            tool_call_id: tool_call_id.into(),
+            approver: approver.into(),
+        }
+    }
+
+    /// Create a reviewer's refusal of a delegated call
+    pub fn reject_delegated(tool_call_id: impl Into<String>, approver: impl Into<String>, reason: Option<String>) -> Self {
+        Self::RejectDelegated {
+            tool_call_id: tool_call_id.into(),
+            approver: approver.into(),
+            reason,
+        }
+    }
+
     /// Create an answer question input
     pub fn answer_question(request_id: impl Into<String>, answers: HashMap<String, String>) -> Self {
         Self::AnswerQuestion {
@@ -242,25 +366,146 @@ impl SessionInput {
         Self::Cancel
     }
 
+    /// Create a cancel tool input
+    pub fn cancel_tool(id: impl Into<String>) -> Self {
+        Self::CancelTool { id: id.into() }
+    }
+
+    /// Create an explain tool input
+    pub fn explain_tool(id: impl Into<String>) -> Self {
+        Self::ExplainTool { id: id.into() }
+    }
+
     /// Create a set plan mode input
     pub fn set_plan_mode(active: bool) -> Self {
         Self::SetPlanMode { active }
     }
+
+    /// Create a clear queue input
+    pub fn clear_queue() -> Self {
+        Self::ClearQueue
+    }
+
+    /// Create a set generation params input
+    pub fn set_generation_params(params: GenerationParams) -> Self {
+        Self::SetGenerationParams { params }
+    }
+
+    /// Create an approve plan input
+    pub fn approve_plan(name: Option<String>) -> Self {
+        Self::ApprovePlan { name }
+    }
+
+    /// Create a regenerate last input
+    pub fn regenerate_last(temperature: Option<f32>) -> Self {
+        Self::RegenerateLast { temperature }
+    }
+
+    /// Create an edit user message input
+    pub fn edit_user_message(message_id: impl Into<String>, new_content: impl Into<String>) -> Self {
+        Self::EditUserMessage {
+            message_id: message_id.into(),
+            new_content: new_content.into(),
+        }
+    }
+
+    /// Create a run recipe input
+    pub fn run_recipe(name: impl Into<String>, args: impl Into<String>) -> Self {
+        Self::RunRecipe {
+            name: name.into(),
+            args: args.into(),
+        }
+    }
+
+    /// Create a set pinned input
+    pub fn set_pinned(pinned: bool) -> Self {
+        Self::SetPinned { pinned }
+    }
+
+    /// Create a pin message input (None pins the latest reply)
+    pub fn pin_message(message_id: Option<String>, pinned: bool) -> Self {
+        Self::PinMessage { message_id, pinned }
+    }
+
+    /// Create a set selection input
+    pub fn set_selection(text: impl Into<String>) -> Self {
+        Self::SetSelection { text: text.into() }
+    }
+
+    /// Create a set title input
+    pub fn set_title(title: impl Into<String>) -> Self {
+        Self::SetTitle { title: title.into() }
+    }
+
+    /// Create a set tool profile input
+    pub fn set_tool_profile(profile: ToolProfile) -> Self {
+        Self::SetToolProfile { profile }
+    }
+
+    /// Create a compact input
+    pub fn compact(mode: Option<crate::context::CompactionMode>, model: Option<String>, focus: Option<String>) -> Self {
+        Self::Compact { mode, model, focus }
+    }
+
+    /// Parse an input sent as JSON
+    ///
+    /// A `type` this version doesn't know becomes `Unsupported` instead of an
+    /// error, so the session can tell the frontend; a known type with the
+    /// wrong fields is still an error.
+    pub fn from_json(value: serde_json::Value) -> Result<Self, serde_json::Error> {
+        let input_type = value.get("type").and_then(|t| t.as_str()).map(str::to_string);
+        match serde_json::from_value(value) {
+            Ok(input) => Ok(input),
+            Err(err) => match input_type {
+                Some(input_type) if !super::capabilities::SESSION_INPUT_TYPES.contains(&input_type.as_str()) => {
+                    Ok(Self::Unsupported { input_type })
+                }
+                _ => Err(err),
+            },
+        }
+    }
+}
+
+/// How a tool call ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolDoneStatus {
+    Success,
+    Failed,
+    /// Stopped by the user (`SessionInput::CancelTool` or `Cancel`)
+    Cancelled,
+    /// Refused without running by the approve and deny rules
+    /// (see `ToolApprovalConfig::with_rules`)
+    Rejected,
 }
 
 /// Output messages sent FROM an agent session
+///
+/// Outputs and their fields are added over time, so the enum and its
+/// variants are `#[non_exhaustive]`: match with a wildcard arm and `..`, and
+/// build outputs with the constructors below.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
+#[non_exhaustive]
 pub enum SessionOutput {
     /// Session is ready to receive input
     Ready,
     /// Session is idle, waiting for input
-    Idle,
+    #[non_exhaustive]
+    Idle {
+        /// The turn's self-review found more gaps than
+        /// `SessionConfig::self_review_threshold` (see `SelfReview`)
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        review_warning: bool,
+    },
     /// Echo of user message (for UI display)
+    #[non_exhaustive]
     UserMessage { id: String, content: String },
     /// Assistant is thinking (streaming indicator)
+    #[non_exhaustive]
     Thinking { content: String },
     /// Streaming text delta from assistant (real-time token output)
+    #[non_exhaustive]
     TextDelta {
         /// Message ID this delta belongs to
         id: String,
@@ -269,17 +514,20 @@ pub enum SessionOutput {
     },
     /// Assistant message (complete)
     /// Token usage info is already formatted and appended to content by core.
+    #[non_exhaustive]
     AssistantMessage {
         id: String,
         content: String,
     },
     /// Tool execution starting (auto-approved or approved by user)
+    #[non_exhaustive]
     ToolStart {
         id: String,
         name: String,
         arguments: serde_json::Value,
     },
     /// Tool needs user approval
+    #[non_exhaustive]
     ToolPending {
         id: String,
         name: String,
@@ -288,41 +536,106 @@ pub enum SessionOutput {
         /// If this came from a subagent, the subagent's session ID for routing approvals
         #[serde(skip_serializing_if = "Option::is_none")]
         subagent_id: Option<String>,
+        /// Organization policy requires a person to approve this call, so
+        /// frontends must not auto-approve it
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        policy_required: bool,
+        /// The call looks like it exfiltrates secrets (see
+        /// `approval::exfiltration`): frontends show this prominently and
+        /// must not auto-approve the call
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        security_warning: Option<String>,
+        /// Generated files the call changes with `allow_generated` (see
+        /// `tools::filesystem::GeneratedFileGuard`): frontends show them and
+        /// must not auto-approve the call
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        generated_files: Vec<GeneratedFile>,
+    },
+    /// Tool call waits for a reviewer instead of the user: only
+    /// `SessionInput::ApproveDelegated`/`RejectDelegated` settle it, the user
+    /// can only cancel it. Refused when `timeout_secs` pass without an answer.
+    #[non_exhaustive]
+    ApprovalDelegated {
+        id: String,
+        tool: String,
+        args: serde_json::Value,
+        /// Why it needs a reviewer
+        reason: String,
+        /// Where reviewers watch for it
+        channel: String,
+        timeout_secs: u64,
+    },
+    /// Why the assistant wants a pending tool call, and what it could change
+    /// (the answer to `SessionInput::ExplainTool`)
+    #[non_exhaustive]
+    ToolExplanation { id: String, text: String },
+    /// Heartbeat from a long-running tool, at most one a second per call
+    #[non_exhaustive]
+    ToolProgress {
+        id: String,
+        /// Time since the tool started running
+        elapsed_ms: u64,
+        /// Bytes of output captured so far
+        output_bytes: u64,
+        /// The most recent output line
+        last_line: Option<String>,
     },
     /// Tool execution completed
+    #[non_exhaustive]
     ToolDone {
         id: String,
         name: String,
         success: bool,
+        /// Whether the tool succeeded, failed, was cancelled by the user or refused by the rules
+        status: ToolDoneStatus,
         output: String,
     },
     /// Tool call message (persistent, Claude Code style)
     ///
     /// Emitted when a tool is about to be executed, formatted for display.
     /// Unlike ToolStart (ephemeral), this is meant to be stored as a message.
+    #[non_exhaustive]
     ToolCall {
         id: String,
         name: String,
         arguments: serde_json::Value,
-        /// Formatted display string, e.g. "Read(/path/to/file.rs)"
+        /// Formatted display string, e.g. "Read(src/main.rs)", with paths
+        /// relative to the workspace (see `formatting::PathDisplay`)
         formatted: String,
+        /// For Edit/Write: the change, diffed before the tool runs
+        diff: Option<FileDiff>,
+        /// The file the tool works on, for opening it
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        absolute_path: Option<String>,
     },
     /// Tool result message (persistent, Claude Code style)
     ///
     /// Emitted after tool execution, with a summary and optional diff.
-    /// Unlike ToolDone (ephemeral), this is meant to be stored as a message.
+    /// Unlike ToolDone, this is meant to be stored as a message.
+    #[non_exhaustive]
     ToolResult {
         id: String,
         name: String,
         success: bool,
         /// The full tool output
         output: String,
-        /// Short summary like "Read 20 lines" or "Added 5 lines"
+        /// Short summary like "Read 20 lines" or "edited src/main.rs: +12 −3"
         summary: String,
         /// For Edit tool: diff preview with +/- lines
         diff_preview: Option<Vec<DiffLine>>,
+        /// Structured result (files, images, tables, links) for rich rendering
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        artifacts: Vec<crate::tools::Artifact>,
+        /// The file the tool worked on, for opening it
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        absolute_path: Option<String>,
+        /// Line of that file to open it at: the first changed line of an
+        /// edit, or where a read started
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        line: Option<u32>,
     },
     /// Question for the user (from ask_user_question tool)
+    #[non_exhaustive]
     Question {
         request_id: String,
         questions: Vec<QuestionInfo>,
@@ -331,18 +644,155 @@ pub enum SessionOutput {
         subagent_id: Option<String>,
     },
     /// Error occurred
+    #[non_exhaustive]
     Error { message: String },
+    /// Something the user should know about that didn't stop the turn
+    #[non_exhaustive]
+    Warning { message: String },
+    /// The provider refused or filtered the model's response, ending the turn
+    #[non_exhaustive]
+    Refusal {
+        /// The reason as the provider reported it (e.g. `refusal`, `content_filter`, `SAFETY`)
+        reason: String,
+        /// The model's explanation, when it gave one
+        message: Option<String>,
+    },
     /// Turn was cancelled by user
     Cancelled,
     /// Plan mode state changed
+    #[non_exhaustive]
     PlanModeChanged {
         active: bool,
         /// Path to the plan file (when entering plan mode)
         plan_file: Option<String>,
     },
+    /// User message arrived mid-turn and is waiting (1 = next to run)
+    #[non_exhaustive]
+    MessageQueued { position: usize },
+    /// Files the session has read or written were changed by someone else
+    #[non_exhaustive]
+    FilesChangedExternally {
+        /// Paths relative to the workspace
+        paths: Vec<String>,
+    },
+    /// Generation parameters changed (after clamping to valid ranges)
+    #[non_exhaustive]
+    GenerationParamsChanged { params: GenerationParams },
+    /// A plan's lifecycle status changed (approved, in progress, ...)
+    #[non_exhaustive]
+    PlanStatusChanged { name: String, status: PlanStatus },
+    /// The TodoWrite checklist changed (or was restored with the session)
+    #[non_exhaustive]
+    TodosUpdated { items: Vec<TodoItem> },
+    /// The session's title changed: set from the first message, generated
+    /// after the first turn, or set by the user
+    #[non_exhaustive]
+    TitleChanged { title: String },
+    /// The session switched tool profiles; these tools are offered from
+    /// this turn on (see `orchestration::tool_profile`)
+    #[non_exhaustive]
+    ToolProfileChanged {
+        profile: String,
+        /// Names of the tools offered, sorted
+        tools: Vec<String>,
+        /// Rough tokens their definitions add to every request
+        definition_tokens: u64,
+    },
+    /// The turn that follows runs with these settings in place of the
+    /// session's (from the message's `overrides`); the next turn doesn't
+    #[non_exhaustive]
+    TurnOverridden {
+        /// Model ID answering the turn, if not the session's
+        #[serde(skip_serializing_if = "Option::is_none")]
+        model: Option<String>,
+        /// Calls the turn runs without asking, besides the session's
+        #[serde(skip_serializing_if = "Option::is_none")]
+        approval: Option<TurnApproval>,
+        /// Tool profile of the turn, if not the session's
+        #[serde(skip_serializing_if = "Option::is_none")]
+        tool_profile: Option<String>,
+    },
+    /// The user message with this ID and everything after it were removed
+    /// from the history (by `RegenerateLast` or `EditUserMessage`); the
+    /// replacement turn follows, starting with a new `UserMessage`
+    #[non_exhaustive]
+    HistoryTruncated { from_message_id: String },
+    /// The session was idle too long and was saved and shut down; the next
+    /// message resumes it
+    Suspended,
+    /// A suspended session was resumed from its saved state
+    #[non_exhaustive]
+    Resumed {
+        /// How long the session was suspended
+        suspended_secs: u64,
+    },
+    /// The session works in an isolated git worktree (follows `Ready`)
+    #[non_exhaustive]
+    Worktree {
+        /// The worktree's directory
+        path: String,
+        /// The branch checked out in the worktree
+        branch: String,
+    },
+    /// A recipe started running; its steps make up a progress checklist
+    #[non_exhaustive]
+    RecipeStarted { name: String, steps: Vec<RecipeStepInfo> },
+    /// A recipe step started
+    #[non_exhaustive]
+    RecipeStepStarted { name: String, step_id: String },
+    /// A recipe step finished, or was skipped because an earlier one stopped the recipe
+    #[non_exhaustive]
+    RecipeStepFinished {
+        name: String,
+        step_id: String,
+        status: StepStatus,
+        /// The step's output, shortened
+        output: String,
+    },
+    /// A recipe finished running
+    #[non_exhaustive]
+    RecipeFinished {
+        name: String,
+        /// No step stopped the recipe
+        success: bool,
+        /// A line per step, like `✓ test`
+        summary: String,
+    },
+    /// The session got an input of a type it doesn't support (see
+    /// `session::capabilities`); the input was ignored
+    #[non_exhaustive]
+    UnsupportedInput { input_type: String, protocol_version: u32 },
+    /// The history was compacted, automatically or on request
+    #[non_exhaustive]
+    Compacted {
+        mode: crate::context::CompactionMode,
+        /// The model that wrote the summary (None for heuristic compaction)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        model: Option<String>,
+        messages_summarized: usize,
+        /// Estimated tokens of the history before and after
+        tokens_before: usize,
+        tokens_after: usize,
+    },
+    /// Gaps the end-of-turn self-review found between the turn's changes
+    /// and the user's request (see `session::self_review`); only reported,
+    /// never acted on
+    #[non_exhaustive]
+    SelfReview { findings: Vec<String> },
 }
 
 impl SessionOutput {
+    /// Whether the frontend can miss this output without losing state
+    ///
+    /// Ephemeral outputs are dropped rather than block the agent loop when
+    /// the frontend falls behind (see `session::output`).
+    pub fn is_ephemeral(&self) -> bool {
+        matches!(
+            self,
+            Self::Thinking { .. } | Self::TextDelta { .. } | Self::ToolStart { .. } | Self::ToolProgress { .. }
+        )
+    }
+
     /// Create a ready output
     pub fn ready() -> Self {
         Self::Ready
@@ -350,7 +800,12 @@ impl SessionOutput {
 
     /// Create an idle output
     pub fn idle() -> Self {
-        Self::Idle
+        Self::Idle { review_warning: false }
+    }
+
+    /// Create an idle output ending a turn whose self-review warned
+    pub fn idle_with_review_warning() -> Self {
+        Self::Idle { review_warning: true }
     }
 
     /// Create a user message echo
@@ -460,6 +915,9 @@ impl SessionOutput {
             arguments,
             description,
             subagent_id: None,
+            policy_required: false,
+            security_warning: None,
+            generated_files: Vec::new(),
         }
     }
 
@@ -477,6 +935,35 @@ impl SessionOutput {
             arguments,
             description,
             subagent_id: Some(subagent_id.into()),
+            policy_required: false,
+            security_warning: None,
+            generated_files: Vec::new(),
+        }
+    }
+
+    /// Create a delegated approval notification
+    pub fn approval_delegated(
+        id: impl Into<String>,
+        tool: impl Into<String>,
+        args: serde_json::Value,
+        delegation: &super::approval::Delegation,
+        timeout: std::time::Duration,
+    ) -> Self {
+        Self::ApprovalDelegated {
+            id: id.into(),
+            tool: tool.into(),
+            args,
+            reason: delegation.reason.clone(),
+            channel: delegation.channel.clone(),
+            timeout_secs: timeout.as_secs(),
+        }
+    }
+
+    /// Create a tool explanation
+    pub fn tool_explanation(id: impl Into<String>, text: impl Into<String>) -> Self {
+        Self::ToolExplanation {
+            id: id.into(),
+            text: text.into(),
         }
     }
 
@@ -491,10 +978,52 @@ impl SessionOutput {
             id: id.into(),
             name: name.into(),
             success,
+            status: if success { ToolDoneStatus::Success } else { ToolDoneStatus::Failed },
+            output: output.into(),
+        }
+    }
+
+    /// Create a tool progress heartbeat
+    pub fn tool_progress(progress: ToolProgress) -> Self {
+        Self::ToolProgress {
+            id: progress.tool_call_id,
+            elapsed_ms: progress.elapsed_ms,
+            output_bytes: progress.output_bytes,
+            last_line: progress.last_line,
+        }
+    }
+
+    /// Create a tool done notification for a call the user cancelled
+    pub fn tool_cancelled(id: impl Into<String>, name: impl Into<String>, output: impl Into<String>) -> Self {
+        Self::ToolDone {
+            id: id.into(),
+            name: name.into(),
+            success: false,
+            status: ToolDoneStatus::Cancelled,
+            output: output.into(),
+        }
+    }
+
+    /// Create a tool done output for a call the approve and deny rules refused
+    pub fn tool_rejected(id: impl Into<String>, name: impl Into<String>, output: impl Into<String>) -> Self {
+        Self::ToolDone {
+            id: id.into(),
+            name: name.into(),
+            success: false,
+            status: ToolDoneStatus::Rejected,
             output: output.into(),
         }
     }
 
+    /// Create a question for the user, asked by the session itself
+    pub fn question(request_id: impl Into<String>, questions: Vec<QuestionInfo>) -> Self {
+        Self::Question {
+            request_id: request_id.into(),
+            questions,
+            subagent_id: None,
+        }
+    }
+
     /// Create an error output
     pub fn error(message: impl Into<String>) -> Self {
         Self::Error {
@@ -502,11 +1031,149 @@ impl SessionOutput {
         }
     }
 
+    /// Create a warning output
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self::Warning {
+            message: message.into(),
+        }
+    }
+
+    /// Create a refusal output
+    pub fn refusal(reason: impl Into<String>, message: Option<String>) -> Self {
+        Self::Refusal {
+            reason: reason.into(),
+            message,
+        }
+    }
+
     /// Create a cancelled output
     pub fn cancelled() -> Self {
         Self::Cancelled
     }
 
+    /// Create a message queued acknowledgment
+    pub fn message_queued(position: usize) -> Self {
+        Self::MessageQueued { position }
+    }
+
+    /// Create an external file changes notification
+    pub fn files_changed_externally(paths: Vec<String>) -> Self {
+        Self::FilesChangedExternally { paths }
+    }
+
+    /// Create a generation params changed output
+    pub fn generation_params_changed(params: GenerationParams) -> Self {
+        Self::GenerationParamsChanged { params }
+    }
+
+    /// Create a title changed output
+    pub fn title_changed(title: impl Into<String>) -> Self {
+        Self::TitleChanged { title: title.into() }
+    }
+
+    /// Create a tool profile changed output
+    pub fn tool_profile_changed(profile: impl Into<String>, tools: Vec<String>, definition_tokens: u64) -> Self {
+        Self::ToolProfileChanged {
+            profile: profile.into(),
+            tools,
+            definition_tokens,
+        }
+    }
+
+    /// Create a turn overridden output
+    pub fn turn_overridden(model: Option<String>, approval: Option<TurnApproval>, tool_profile: Option<String>) -> Self {
+        Self::TurnOverridden { model, approval, tool_profile }
+    }
+
+    /// Create a plan status changed output
+    pub fn plan_status_changed(name: impl Into<String>, status: PlanStatus) -> Self {
+        Self::PlanStatusChanged { name: name.into(), status }
+    }
+
+    /// Create a todos updated output
+    pub fn todos_updated(items: Vec<TodoItem>) -> Self {
+        Self::TodosUpdated { items }
+    }
+
+    /// Create a history truncated output
+    pub fn history_truncated(from_message_id: impl Into<String>) -> Self {
+        Self::HistoryTruncated {
+            from_message_id: from_message_id.into(),
+        }
+    }
+
+    /// Create a resumed output
+    pub fn resumed(suspended_secs: u64) -> Self {
+        Self::Resumed { suspended_secs }
+    }
+
+    /// Create a compacted output reporting `result`
+    pub fn compacted(result: &crate::context::CompactResult) -> Self {
+        Self::Compacted {
+            mode: result.mode,
+            model: result.model.clone(),
+            messages_summarized: result.messages_summarized,
+            tokens_before: result.tokens_before,
+            tokens_after: result.tokens_after,
+        }
+    }
+
+    /// Create a self-review output
+    pub fn self_review(findings: Vec<String>) -> Self {
+        Self::SelfReview { findings }
+    }
+
+    /// Create a worktree output
+    pub fn worktree(path: impl Into<String>, branch: impl Into<String>) -> Self {
+        Self::Worktree {
+            path: path.into(),
+            branch: branch.into(),
+        }
+    }
+
+    /// Create a recipe started output
+    pub fn recipe_started(name: impl Into<String>, steps: Vec<RecipeStepInfo>) -> Self {
+        Self::RecipeStarted {
+            name: name.into(),
+            steps,
+        }
+    }
+
+    /// Create a recipe step started output
+    pub fn recipe_step_started(name: impl Into<String>, step_id: impl Into<String>) -> Self {
+        Self::RecipeStepStarted {
+            name: name.into(),
+            step_id: step_id.into(),
+        }
+    }
+
+    /// Create a recipe step finished output
+    pub fn recipe_step_finished(name: impl Into<String>, report: &StepReport) -> Self {
+        Self::RecipeStepFinished {
+            name: name.into(),
+            step_id: report.id.clone(),
+            status: report.status,
+            output: crate::recipes::event_output(&report.output),
+        }
+    }
+
+    /// Create a recipe finished output
+    pub fn recipe_finished(name: impl Into<String>, success: bool, summary: impl Into<String>) -> Self {
+        Self::RecipeFinished {
+            name: name.into(),
+            success,
+            summary: summary.into(),
+        }
+    }
+
+    /// Create an unsupported input output
+    pub fn unsupported_input(input_type: impl Into<String>) -> Self {
+        Self::UnsupportedInput {
+            input_type: input_type.into(),
+            protocol_version: super::capabilities::PROTOCOL_VERSION,
+        }
+    }
+
     /// Create a plan mode changed output
     pub fn plan_mode_changed(active: bool, plan_file: Option<String>) -> Self {
         Self::PlanModeChanged { active, plan_file }
@@ -518,12 +1185,15 @@ impl SessionOutput {
         name: impl Into<String>,
         arguments: serde_json::Value,
         formatted: impl Into<String>,
+        diff: Option<FileDiff>,
     ) -> Self {
         Self::ToolCall {
             id: id.into(),
             name: name.into(),
             arguments,
             formatted: formatted.into(),
+            diff,
+            absolute_path: None,
         }
     }
 
@@ -535,6 +1205,7 @@ impl SessionOutput {
         output: impl Into<String>,
         summary: impl Into<String>,
         diff_preview: Option<Vec<DiffLine>>,
+        artifacts: Vec<crate::tools::Artifact>,
     ) -> Self {
         Self::ToolResult {
             id: id.into(),
@@ -543,8 +1214,27 @@ impl SessionOutput {
             output: output.into(),
             summary: summary.into(),
             diff_preview,
+            artifacts,
+            absolute_path: None,
+            line: None,
         }
     }
+
+    /// Set the file a tool call or result is about (other outputs are unchanged)
+    pub fn with_absolute_path(mut self, path: Option<String>) -> Self {
+        if let Self::ToolCall { absolute_path, .. } | Self::ToolResult { absolute_path, .. } = &mut self {
+            *absolute_path = path;
+        }
+        self
+    }
+
+    /// Set the line a tool result is about (other outputs are unchanged)
+    pub fn with_line(mut self, at: Option<u32>) -> Self {
+        if let Self::ToolResult { line, .. } = &mut self {
+            *line = at;
+        }
+        self
+    }
 }
 
 /// Information about a question option
@@ -563,6 +1253,32 @@ pub struct QuestionInfo {
     pub multi_select: bool,
 }
 
+/// What to do with a user message that arrives while a turn is in flight
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueuePolicy {
+    /// Run it after the current turn (and anything already queued)
+    #[default]
+    Queue,
+    /// Refuse it with an error
+    Reject,
+    /// Cancel the current turn, drop the queue, and run it next
+    Interrupt,
+}
+
+impl std::str::FromStr for QueuePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "queue" => Ok(Self::Queue),
+            "reject" => Ok(Self::Reject),
+            "interrupt" => Ok(Self::Interrupt),
+            _ => Err(format!("Invalid queue policy: {} (expected queue, reject, or interrupt)", s)),
+        }
+    }
+}
+
 /// Configuration for creating a session
 #[derive(Clone)]
 pub struct SessionConfig {
@@ -580,6 +1296,8 @@ pub struct SessionConfig {
     pub api_key: Option<String>,
     /// Optional base URL for the provider API (for self-hosted or custom endpoints)
     pub base_url: Option<String>,
+    /// The provider's model tiers; the fast one names the session (None = catalog defaults)
+    pub model_tiers: Option<crate::config::ModelTiers>,
     /// Web search configuration
     pub web_search_config: Option<crate::config::WebSearchConfig>,
     /// Prompt system configuration
@@ -588,6 +1306,11 @@ pub struct SessionConfig {
     pub component_registry: Option<Arc<ComponentRegistry>>,
     /// Tool scope — restricts which tools are registered (for subagents)
     pub tool_scope: Option<ToolScope>,
+    /// Tools offered to the model (default: every tool; changed by `SetToolProfile`)
+    pub tool_profile: ToolProfile,
+    /// Offer the built-in tools' compact descriptions (see `tools::descriptions`)
+    /// (default: false)
+    pub compact_tool_descriptions: bool,
     /// Override whether hooks are enabled (None = use prompt_config default)
     pub enable_hooks: Option<bool>,
     /// Whether to persist the session to disk on exit (default: true)
@@ -600,6 +1323,87 @@ pub struct SessionConfig {
     pub parent_approval_channel: Option<ApprovalSender>,
     /// Use streaming mode for LLM responses (default: false)
     pub stream_mode: bool,
+    /// How long an LLM request may take (None = the provider default for the stream mode)
+    pub request_timeout: Option<std::time::Duration>,
+    /// How to handle messages sent while a turn is in flight (default: queue)
+    pub queue_policy: QueuePolicy,
+    /// Watch files the session touches and report external edits (default: false)
+    pub watch_files: bool,
+    /// Validate MCP tool arguments against their schemas (default: true)
+    pub validate_mcp_tool_args: bool,
+    /// Offer to save standing instructions from user messages to memory (default: false)
+    pub auto_memory: bool,
+    /// Have the fast model check each turn that changed files or ran
+    /// commands against the request (see `session::self_review`) (default: false)
+    pub self_review: bool,
+    /// Findings above which a reviewed turn's `Idle` carries a warning (default: 0)
+    pub self_review_threshold: usize,
+    /// Answer calls to tools with side effects without running them (default: false)
+    pub dry_run: bool,
+    /// Send tool schemas in the provider's dialect (see `provider::tool_schema`) (default: true)
+    pub strict_tools: bool,
+    /// Request and session limits (used when the manager builds its limiter)
+    pub limits: crate::config::LimitsConfig,
+    /// Shared limiter held around each LLM request (None = unlimited)
+    pub request_limiter: Option<Arc<super::limits::RequestLimiter>>,
+    /// Locks on the files other sessions are writing (None = not coordinated)
+    pub workspace_locks: Option<Arc<super::workspace_locks::WorkspaceLocks>>,
+    /// Store each completed LLM call's token usage is recorded to (None = not recorded)
+    pub usage_store: Option<Arc<crate::provider::UsageStore>>,
+    /// Keep an audit log of approvals and mutations (see `session::AuditLog`) (default: true)
+    pub audit: bool,
+    /// Audit log to append to instead of the session's own (subagents share their parent's)
+    pub audit_log: Option<Arc<super::audit::AuditLog>>,
+    /// Environment policy for shell commands (None = default allowlist)
+    pub env_policy: Option<crate::tools::shell::EnvPolicy>,
+    /// Organization policy constraining tools, approval and provider (None = no policy)
+    pub policy: Option<Arc<crate::policy::Policy>>,
+    /// Hosts WebFetch and WebSearch may reach (None = any public host)
+    pub network_policy: Option<crate::tools::web::NetworkPolicy>,
+    /// Syntax check after Edit and Write (None = not checked)
+    pub verifier: Option<Arc<crate::tools::filesystem::SyntaxVerifier>>,
+    /// Remote workspace the file and shell tools operate on (None = local)
+    pub remote: Option<Arc<crate::tools::remote::RemoteWorkspace>>,
+    /// Directories the file tools may use besides `workspace_path` (see
+    /// `tools::filesystem::WorkspaceRoots`; ignored for a remote workspace)
+    pub additional_roots: Vec<std::path::PathBuf>,
+    /// System clipboard the ClipboardWrite tool writes to (None = no such tool)
+    pub clipboard: Option<Arc<dyn crate::tools::interaction::Clipboard>>,
+    /// Output counts reported by the manager's session stats (None = not reported)
+    pub output_counters: Option<Arc<super::output::OutputCounters>>,
+    /// Title the manager lists the session under (None = not listed)
+    pub title: Option<Arc<super::title::SessionTitle>>,
+    /// History snapshot the manager forks from (None = not recorded)
+    pub snapshot: Option<Arc<super::fork::SessionSnapshot>>,
+    /// Give the model a project overview with the first message (see `context::overview`)
+    pub project_context: bool,
+    /// Size of the project overview, in estimated tokens
+    pub project_context_budget: usize,
+    /// Give the session a scratch directory for temporary files (default: true)
+    pub scratch: bool,
+    /// Keep a saved session's scratch directory when it ends (default: false)
+    pub keep_scratch: bool,
+    /// Autosave a saved session this often and after every turn (None = only on exit)
+    pub autosave_interval: Option<std::time::Duration>,
+    /// Work in a git worktree of the workspace instead of the workspace itself (default: none)
+    pub isolation: super::worktree::Isolation,
+    /// Sampling and length overrides for LLM requests (default = provider defaults)
+    pub generation: GenerationParams,
+    /// Preprocessing run on every user message (default: none)
+    pub prompt_pipeline: crate::prompt::PromptPipeline,
+    /// Saved session to continue: its messages and todo list are restored
+    pub resume: Option<super::persistence::SavedSession>,
+    /// Session template the session was started from (see `templates`)
+    pub template: Option<String>,
+    /// Language of the text the session shows people (see `i18n`) (default: English)
+    pub locale: crate::i18n::Locale,
+    /// How the history is compacted when the context fills up
+    pub summarizer: crate::config::SummarizerConfig,
+    /// The provider `summarizer.provider` names, resolved (None = the session's)
+    pub summarizer_provider: Option<crate::config::ProviderConfig>,
+    /// Scripted provider used instead of a real LLM (tests only)
+    #[cfg(feature = "mock-provider")]
+    pub mock_provider: Option<Arc<crate::provider::MockProvider>>,
 }
 
 impl Default for SessionConfig {
@@ -612,16 +1416,59 @@ impl Default for SessionConfig {
             model: None,
             api_key: None,
             base_url: None,
+            model_tiers: None,
             web_search_config: None,
             prompt_config: PromptSystemConfig::default(),
             component_registry: None,
             tool_scope: None,
+            tool_profile: ToolProfile::full(),
+            compact_tool_descriptions: false,
             enable_hooks: None,
             save_session: true,
             session_registry: None,
             mcp_manager: None,
             parent_approval_channel: None,
             stream_mode: false,
+            request_timeout: None,
+            queue_policy: QueuePolicy::default(),
+            watch_files: false,
+            validate_mcp_tool_args: true,
+            auto_memory: false,
+            self_review: false,
+            self_review_threshold: super::self_review::DEFAULT_SELF_REVIEW_THRESHOLD,
+            dry_run: false,
+            strict_tools: true,
+            limits: crate::config::LimitsConfig::default(),
+            request_limiter: None,
+            workspace_locks: None,
+            usage_store: None,
+            audit: true,
+            audit_log: None,
+            env_policy: None,
+            policy: None,
+            network_policy: None,
+            verifier: None,
+            remote: None,
+            additional_roots: Vec::new(),
+            clipboard: None,
+            output_counters: None,
+            title: None,
+            snapshot: None,
+            project_context: true,
+            project_context_budget: crate::context::DEFAULT_OVERVIEW_BUDGET,
+            scratch: true,
+            keep_scratch: false,
+            autosave_interval: None,
+            isolation: Default::default(),
+            generation: GenerationParams::default(),
+            prompt_pipeline: crate::prompt::PromptPipeline::default(),
+            resume: None,
+            template: None,
+            locale: crate::i18n::Locale::default(),
+            summarizer: crate::config::SummarizerConfig::default(),
+            summarizer_provider: None,
+            #[cfg(feature = "mock-provider")]
+            mock_provider: None,
         }
     }
 }
@@ -659,6 +1506,12 @@ impl SessionConfig {
         self
     }
 
+    /// Set the provider's model tiers (the fast one names the session)
+    pub fn with_model_tiers(mut self, tiers: crate::config::ModelTiers) -> Self {
+        self.model_tiers = Some(tiers);
+        self
+    }
+
     /// Set the system prompt
     pub fn with_system_prompt(mut self, prompt: impl Into<String>) -> Self {
         self.system_prompt = Some(prompt.into());
@@ -730,6 +1583,254 @@ impl SessionConfig {
         self.stream_mode = stream;
         self
     }
+
+    /// Set how long an LLM request may take before it's abandoned and retried
+    pub fn with_request_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the policy for messages sent while a turn is in flight
+    pub fn with_queue_policy(mut self, policy: QueuePolicy) -> Self {
+        self.queue_policy = policy;
+        self
+    }
+
+    /// Set whether to watch touched files for external edits
+    pub fn with_watch_files(mut self, watch: bool) -> Self {
+        self.watch_files = watch;
+        self
+    }
+
+    /// Set whether MCP tool arguments are validated against their schemas
+    pub fn with_mcp_tool_validation(mut self, enabled: bool) -> Self {
+        self.validate_mcp_tool_args = enabled;
+        self
+    }
+
+    /// Set whether to offer saving standing instructions to memory
+    pub fn with_auto_memory(mut self, enabled: bool) -> Self {
+        self.auto_memory = enabled;
+        self
+    }
+
+    /// Set whether each turn's changes are reviewed against its request
+    pub fn with_self_review(mut self, enabled: bool) -> Self {
+        self.self_review = enabled;
+        self
+    }
+
+    /// Set how many self-review findings a turn may have before its `Idle` warns
+    pub fn with_self_review_threshold(mut self, threshold: usize) -> Self {
+        self.self_review_threshold = threshold;
+        self
+    }
+
+    /// Set whether tool schemas are adapted to the provider's dialect
+    pub fn with_strict_tools(mut self, enabled: bool) -> Self {
+        self.strict_tools = enabled;
+        self
+    }
+
+    /// Set the language of the text the session shows people
+    pub fn with_locale(mut self, locale: crate::i18n::Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// Set whether tools with side effects are only simulated (see `tools::dry_run`)
+    pub fn with_dry_run(mut self, enabled: bool) -> Self {
+        self.dry_run = enabled;
+        self
+    }
+
+    /// Set the request and session limits
+    pub fn with_limits(mut self, limits: crate::config::LimitsConfig) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Set the shared request limiter
+    pub fn with_request_limiter(mut self, limiter: Arc<super::limits::RequestLimiter>) -> Self {
+        self.request_limiter = Some(limiter);
+        self
+    }
+
+    /// Set the file locks shared with the other sessions in the workspace
+    pub fn with_workspace_locks(mut self, locks: Arc<super::workspace_locks::WorkspaceLocks>) -> Self {
+        self.workspace_locks = Some(locks);
+        self
+    }
+
+    /// Set how the history is compacted, and the provider summaries are
+    /// written with when it isn't the session's
+    pub fn with_summarizer(
+        mut self,
+        summarizer: crate::config::SummarizerConfig,
+        provider: Option<crate::config::ProviderConfig>,
+    ) -> Self {
+        self.summarizer = summarizer;
+        self.summarizer_provider = provider;
+        self
+    }
+
+    /// Set the store LLM usage is recorded to
+    pub fn with_usage_store(mut self, store: Arc<crate::provider::UsageStore>) -> Self {
+        self.usage_store = Some(store);
+        self
+    }
+
+    /// Set whether the session keeps an audit log (default: true)
+    pub fn with_audit(mut self, enabled: bool) -> Self {
+        self.audit = enabled;
+        self
+    }
+
+    /// Append audit records to this log instead of the session's own
+    pub fn with_audit_log(mut self, log: Arc<super::audit::AuditLog>) -> Self {
+        self.audit_log = Some(log);
+        self
+    }
+
+    /// Set the environment policy for shell commands
+    pub fn with_env_policy(mut self, policy: crate::tools::shell::EnvPolicy) -> Self {
+        self.env_policy = Some(policy);
+        self
+    }
+
+    /// Set the organization policy the session is held to
+    pub fn with_policy(mut self, policy: Arc<crate::policy::Policy>) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    /// Set the hosts WebFetch and WebSearch may reach
+    pub fn with_network_policy(mut self, network: crate::tools::web::NetworkPolicy) -> Self {
+        self.network_policy = Some(network);
+        self
+    }
+
+    /// Check the syntax of files Edit and Write change
+    pub fn with_verifier(mut self, verifier: Arc<crate::tools::filesystem::SyntaxVerifier>) -> Self {
+        self.verifier = Some(verifier);
+        self
+    }
+
+    /// Set whether the first message carries a project overview (default: true)
+    pub fn with_project_context(mut self, enabled: bool) -> Self {
+        self.project_context = enabled;
+        self
+    }
+
+    /// Set the project overview's size, in estimated tokens
+    pub fn with_project_context_budget(mut self, tokens: usize) -> Self {
+        self.project_context_budget = tokens;
+        self
+    }
+
+    /// Set whether the session gets a scratch directory (default: true)
+    pub fn with_scratch(mut self, enabled: bool) -> Self {
+        self.scratch = enabled;
+        self
+    }
+
+    /// Set whether a saved session's scratch directory outlives it (default: false)
+    pub fn with_keep_scratch(mut self, keep: bool) -> Self {
+        self.keep_scratch = keep;
+        self
+    }
+
+    /// Autosave the session periodically and after every turn (None = only on exit)
+    pub fn with_autosave_interval(mut self, interval: Option<std::time::Duration>) -> Self {
+        self.autosave_interval = interval;
+        self
+    }
+
+    /// Run the session in a git worktree of the workspace (see `session::Worktree`)
+    pub fn with_isolation(mut self, isolation: super::worktree::Isolation) -> Self {
+        self.isolation = isolation;
+        self
+    }
+
+    /// Operate on a remote workspace over SSH
+    pub fn with_remote(mut self, remote: Arc<crate::tools::remote::RemoteWorkspace>) -> Self {
+        self.remote = Some(remote);
+        self
+    }
+
+    /// Let the file tools use these directories as well as the workspace
+    pub fn with_additional_roots(mut self, roots: Vec<std::path::PathBuf>) -> Self {
+        self.additional_roots = roots;
+        self
+    }
+
+    /// Offer the model only the tools `profile` includes
+    pub fn with_tool_profile(mut self, profile: ToolProfile) -> Self {
+        self.tool_profile = profile;
+        self
+    }
+
+    /// Describe the built-in tools in a few sentences each, to make every
+    /// request smaller
+    pub fn with_compact_tool_descriptions(mut self, enabled: bool) -> Self {
+        self.compact_tool_descriptions = enabled;
+        self
+    }
+
+    /// Let the agent put text on the user's clipboard (see `tools::interaction::ClipboardWrite`)
+    pub fn with_clipboard(mut self, clipboard: Arc<dyn crate::tools::interaction::Clipboard>) -> Self {
+        self.clipboard = Some(clipboard);
+        self
+    }
+
+    /// Preprocess user messages with `pipeline`
+    pub fn with_prompt_pipeline(mut self, pipeline: crate::prompt::PromptPipeline) -> Self {
+        self.prompt_pipeline = pipeline;
+        self
+    }
+
+    /// Set all generation parameters at once
+    pub fn with_generation_params(mut self, params: GenerationParams) -> Self {
+        self.generation = params;
+        self
+    }
+
+    /// Continue a saved session instead of starting empty
+    pub fn with_resume(mut self, saved: super::persistence::SavedSession) -> Self {
+        self.resume = Some(saved);
+        self
+    }
+
+    /// Set the sampling temperature (clamped to 0.0 - 2.0 when the session starts)
+    pub fn with_temperature(mut self, temperature: f32) -> Self {
+        self.generation.temperature = Some(temperature);
+        self
+    }
+
+    /// Set the maximum number of tokens to generate per request
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.generation.max_tokens = Some(max_tokens);
+        self
+    }
+
+    /// Set nucleus sampling (clamped to 0.0 - 1.0 when the session starts)
+    pub fn with_top_p(mut self, top_p: f32) -> Self {
+        self.generation.top_p = Some(top_p);
+        self
+    }
+
+    /// Set sequences that end generation
+    pub fn with_stop_sequences(mut self, stop_sequences: Vec<String>) -> Self {
+        self.generation.stop_sequences = stop_sequences;
+        self
+    }
+
+    /// Drive the session with a scripted provider instead of a real LLM
+    #[cfg(feature = "mock-provider")]
+    pub fn with_mock_provider(mut self, mock: Arc<crate::provider::MockProvider>) -> Self {
+        self.mock_provider = Some(mock);
+        self
+    }
 }
 
 #[cfg(test)]
@@ -740,7 +1841,10 @@ mod tests {
     fn test_session_input_creation() {
         let msg = SessionInput::user_message("Hello");
         match msg {
-            SessionInput::UserMessage { content } => assert_eq!(content, "Hello"),
+            SessionInput::UserMessage { content, overrides } => {
+                assert_eq!(content, "Hello");
+                assert_eq!(overrides, None);
+            }
             _ => panic!("Expected UserMessage"),
         }
 
@@ -775,7 +1879,7 @@ mod tests {
 
         let deserialized: SessionInput = serde_json::from_str(&json).unwrap();
         match deserialized {
-            SessionInput::UserMessage { content } => assert_eq!(content, "test"),
+            SessionInput::UserMessage { content, .. } => assert_eq!(content, "test"),
             _ => panic!("Deserialization failed"),
         }
     }
@@ -793,17 +1897,37 @@ mod tests {
                 id,
                 name,
                 success,
+                status,
                 output,
             } => {
                 assert_eq!(id, "t1");
                 assert_eq!(name, "Read");
                 assert!(success);
+                assert_eq!(status, ToolDoneStatus::Success);
                 assert_eq!(output, "file contents");
             }
             _ => panic!("Deserialization failed"),
         }
     }
 
+    #[test]
+    fn test_queue_serialization() {
+        let json = serde_json::to_string(&SessionInput::clear_queue()).unwrap();
+        assert_eq!(json, r#"{"type":"clear_queue"}"#);
+
+        let json = serde_json::to_string(&SessionOutput::message_queued(2)).unwrap();
+        assert_eq!(json, r#"{"type":"message_queued","position":2}"#);
+    }
+
+    #[test]
+    fn test_queue_policy_parse() {
+        assert_eq!("queue".parse::<QueuePolicy>().unwrap(), QueuePolicy::Queue);
+        assert_eq!("Reject".parse::<QueuePolicy>().unwrap(), QueuePolicy::Reject);
+        assert_eq!("interrupt".parse::<QueuePolicy>().unwrap(), QueuePolicy::Interrupt);
+        assert!("later".parse::<QueuePolicy>().is_err());
+        assert_eq!(SessionConfig::default().queue_policy, QueuePolicy::Queue);
+    }
+
     #[test]
     fn test_session_config_builder() {
         let config = SessionConfig::new("/tmp/workspace")
@@ -0,0 +1,249 @@
+//! Advisory locks on workspace files, shared by all sessions
+//!
+//! Within a session the `ToolScheduler` orders calls on the same file, but
+//! two sessions in one workspace know nothing of each other: both could edit
+//! `src/lib.rs` and the last write would silently win. One `WorkspaceLocks`
+//! is created per `SessionManager` and handed to every agent loop. A call
+//! that writes a file holds a `FileLock` while it runs; a call from another
+//! session on the same file fails right away with `ToolError::FileLocked`,
+//! naming the session holding it, instead of waiting.
+//!
+//! Locks are keyed by canonical path, so an isolated session's worktree
+//! never conflicts with the workspace. A lock is released when its guard is
+//! dropped: when the call completes, is cancelled, or its loop exits. As a
+//! backstop, a lock held longer than `stale_after` may be taken over.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use super::title::SessionTitle;
+use super::types::SessionId;
+use crate::error::ToolError;
+
+/// How long a lock is held before another session may take it over
+pub const DEFAULT_LOCK_STALE_AFTER: Duration = Duration::from_secs(300);
+
+/// The session holding a file
+#[derive(Debug)]
+struct Holder {
+    session_id: SessionId,
+    title: Option<Arc<SessionTitle>>,
+    acquired_at: Instant,
+    /// Calls of the session holding the lock (its own calls may overlap)
+    holds: usize,
+    /// Tells this hold from a later one after a takeover
+    generation: u64,
+}
+
+impl Holder {
+    /// The session's title, or its ID before it has one
+    fn name(&self) -> String {
+        self.title
+            .as_ref()
+            .and_then(|title| title.get())
+            .unwrap_or_else(|| self.session_id.clone())
+    }
+}
+
+/// A lock as reported for debugging
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileLockInfo {
+    pub path: PathBuf,
+    pub session_id: SessionId,
+    /// The session's title, or its ID before it has one
+    pub session_name: String,
+    pub held_secs: f64,
+    /// Held longer than the staleness timeout; another session may take it over
+    pub stale: bool,
+}
+
+#[derive(Debug, Default)]
+struct Table {
+    files: HashMap<PathBuf, Holder>,
+    next_generation: u64,
+}
+
+/// The files being written, by which session
+#[derive(Debug)]
+pub struct WorkspaceLocks {
+    table: Mutex<Table>,
+    stale_after: Duration,
+}
+
+impl Default for WorkspaceLocks {
+    fn default() -> Self {
+        Self::new(DEFAULT_LOCK_STALE_AFTER)
+    }
+}
+
+/// Held while a call writes a file; dropping it releases the lock
+#[must_use = "the lock is released when the guard is dropped"]
+pub struct FileLock {
+    locks: Arc<WorkspaceLocks>,
+    path: PathBuf,
+    generation: u64,
+}
+
+impl WorkspaceLocks {
+    /// Create a table whose locks may be taken over after `stale_after`
+    pub fn new(stale_after: Duration) -> Self {
+        Self {
+            table: Mutex::new(Table::default()),
+            stale_after,
+        }
+    }
+
+    /// Lock `path` for `session_id`, or fail naming the session holding it
+    ///
+    /// `path` should be canonical. The session's own calls share its lock.
+    /// `shown` is the path as the error names it.
+    pub fn try_lock(
+        self: &Arc<Self>,
+        path: &Path,
+        shown: &str,
+        session_id: &str,
+        title: Option<Arc<SessionTitle>>,
+    ) -> Result<FileLock, ToolError> {
+        let mut table = self.table.lock();
+        let generation = match table.files.get_mut(path) {
+            Some(holder) if holder.session_id == session_id => {
+                holder.holds += 1;
+                holder.generation
+            }
+            Some(holder) if holder.acquired_at.elapsed() < self.stale_after => {
+                return Err(ToolError::FileLocked {
+                    path: shown.to_string(),
+                    session: holder.name(),
+                });
+            }
+            stale => {
+                if let Some(holder) = stale {
+                    warn!(
+                        "Taking over the lock on {} from session {}, held for {:?}",
+                        path.display(),
+                        holder.session_id,
+                        holder.acquired_at.elapsed()
+                    );
+                }
+                table.next_generation += 1;
+                let generation = table.next_generation;
+                table.files.insert(
+                    path.to_path_buf(),
+                    Holder {
+                        session_id: session_id.to_string(),
+                        title,
+                        acquired_at: Instant::now(),
+                        holds: 1,
+                        generation,
+                    },
+                );
+                generation
+            }
+        };
+        Ok(FileLock {
+            locks: self.clone(),
+            path: path.to_path_buf(),
+            generation,
+        })
+    }
+
+    /// Drop every lock a session holds (when its loop exits)
+    pub fn release_session(&self, session_id: &str) {
+        self.table.lock().files.retain(|_, holder| holder.session_id != session_id);
+    }
+
+    /// The locks held now, by path
+    pub fn list(&self) -> Vec<FileLockInfo> {
+        let table = self.table.lock();
+        let mut locks: Vec<FileLockInfo> = table
+            .files
+            .iter()
+            .map(|(path, holder)| {
+                let held = holder.acquired_at.elapsed();
+                FileLockInfo {
+                    path: path.clone(),
+                    session_id: holder.session_id.clone(),
+                    session_name: holder.name(),
+                    held_secs: held.as_secs_f64(),
+                    stale: held >= self.stale_after,
+                }
+            })
+            .collect();
+        locks.sort_by(|a, b| a.path.cmp(&b.path));
+        locks
+    }
+
+    fn release(&self, path: &Path, generation: u64) {
+        let mut table = self.table.lock();
+        // A lock taken over since is no longer this guard's to release
+        if let Some(holder) = table.files.get_mut(path)
+            && holder.generation == generation
+        {
+            holder.holds -= 1;
+            if holder.holds == 0 {
+                table.files.remove(path);
+            }
+        }
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        self.locks.release(&self.path, self.generation);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::TitleSource;
+
+    #[test]
+    fn test_other_sessions_are_refused_until_release() {
+        let locks = Arc::new(WorkspaceLocks::default());
+        let path = Path::new("/work/src/lib.rs");
+        let title = Arc::new(SessionTitle::new("Fix auth bug", TitleSource::User));
+
+        let first = locks.try_lock(path, "src/lib.rs", "a", Some(title)).unwrap();
+        // The session's own calls share the lock
+        let again = locks.try_lock(path, "src/lib.rs", "a", None).unwrap();
+        let err = locks.try_lock(path, "src/lib.rs", "b", None).err().unwrap();
+        assert!(
+            err.to_string().contains("src/lib.rs is being modified by session 'Fix auth bug'"),
+            "{}",
+            err
+        );
+        assert!(locks.try_lock(Path::new("/work/src/main.rs"), "src/main.rs", "b", None).is_ok());
+
+        drop(first);
+        assert!(locks.try_lock(path, "src/lib.rs", "b", None).is_err());
+        drop(again);
+        assert!(locks.list().is_empty());
+        let _b = locks.try_lock(path, "src/lib.rs", "b", None).unwrap();
+        assert_eq!(locks.list()[0].session_id, "b");
+
+        locks.release_session("b");
+        assert!(locks.list().is_empty());
+    }
+
+    #[test]
+    fn test_stale_locks_are_taken_over() {
+        let locks = Arc::new(WorkspaceLocks::new(Duration::ZERO));
+        let path = Path::new("/work/a.txt");
+
+        let stale = locks.try_lock(path, "a.txt", "a", None).unwrap();
+        assert!(locks.list()[0].stale);
+        let taken = locks.try_lock(path, "a.txt", "b", None).unwrap();
+        // The old guard no longer releases what b holds
+        drop(stale);
+        assert_eq!(locks.list()[0].session_id, "b");
+        drop(taken);
+        assert!(locks.list().is_empty());
+    }
+}
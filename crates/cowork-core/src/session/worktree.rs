@@ -0,0 +1,246 @@
+//! Git worktree isolation for sessions
+//!
+//! With `isolation = "worktree"` a session works in its own git worktree at
+//! `.cowork/worktrees/<session_id>`, on a new branch `cowork/<session_id>`
+//! made from HEAD, so the branch checked out in the repository is untouched
+//! while the agent works. `/merge` shows what the worktree changed and, once
+//! approved, merges its branch into the branch checked out in the repository
+//! and removes the worktree. A merge that conflicts is aborted, leaving the
+//! worktree intact for resolving by hand.
+//!
+//! A worktree outlives its session (including suspension and restarts): it
+//! is reopened when the session resumes and removed when the session is
+//! merged or deleted.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+/// Worktree directory, relative to the repository root
+pub const WORKTREES_DIR: &str = ".cowork/worktrees";
+
+/// Prefix of the branch a session's worktree is on
+pub const WORKTREE_BRANCH_PREFIX: &str = "cowork/";
+
+/// Where a session's tools operate
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Isolation {
+    /// Directly in the workspace
+    #[default]
+    None,
+    /// In a git worktree of the workspace, merged back on approval
+    Worktree,
+}
+
+/// A session's git worktree
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Worktree {
+    /// Top level of the repository the worktree belongs to
+    pub repo: PathBuf,
+    /// The worktree's directory
+    pub path: PathBuf,
+    /// The branch checked out in the worktree
+    pub branch: String,
+    /// Where the session's workspace is within the repository (empty at its top level)
+    pub prefix: PathBuf,
+}
+
+/// What merging a worktree brings into the repository
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorktreeSummary {
+    /// The worktree's branch
+    pub branch: String,
+    /// The branch checked out in the repository, which the worktree merges into
+    pub target: String,
+    /// Commits on the worktree branch that the target lacks, newest first
+    pub commits: Vec<String>,
+    /// Uncommitted changes in the worktree (`git status --short`); merging commits them first
+    pub uncommitted: Vec<String>,
+    /// `git diff --stat` of the worktree against where it branched off
+    pub diff_stat: String,
+}
+
+impl WorktreeSummary {
+    /// Whether merging would change nothing
+    pub fn is_empty(&self) -> bool {
+        self.commits.is_empty() && self.uncommitted.is_empty()
+    }
+}
+
+impl std::fmt::Display for WorktreeSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return write!(f, "{} has no changes to merge into {}", self.branch, self.target);
+        }
+        writeln!(f, "{} -> {}", self.branch, self.target)?;
+        if !self.commits.is_empty() {
+            writeln!(f, "\nCommits:")?;
+            for commit in &self.commits {
+                writeln!(f, "  {}", commit)?;
+            }
+        }
+        if !self.uncommitted.is_empty() {
+            writeln!(f, "\nUncommitted (committed when merged):")?;
+            for change in &self.uncommitted {
+                writeln!(f, "  {}", change)?;
+            }
+        }
+        if !self.diff_stat.is_empty() {
+            write!(f, "\n{}", self.diff_stat.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+impl Worktree {
+    /// The worktree a session in `workspace` would use, whether or not it exists
+    pub fn for_session(workspace: &Path, session_id: &str) -> Result<Self> {
+        let repo = PathBuf::from(
+            git(workspace, &["rev-parse", "--show-toplevel"])
+                .map_err(|_| Error::Workspace(format!("{} is not a git repository", workspace.display())))?,
+        );
+        Ok(Self {
+            path: repo.join(WORKTREES_DIR).join(session_id),
+            branch: format!("{}{}", WORKTREE_BRANCH_PREFIX, session_id),
+            prefix: PathBuf::from(git(workspace, &["rev-parse", "--show-prefix"])?.trim_end_matches('/')),
+            repo,
+        })
+    }
+
+    /// The session's workspace: the worktree's copy of the original workspace
+    pub fn workspace(&self) -> PathBuf {
+        if self.prefix.as_os_str().is_empty() {
+            self.path.clone()
+        } else {
+            self.path.join(&self.prefix)
+        }
+    }
+
+    /// Open a session's worktree, creating it from HEAD if it doesn't exist
+    ///
+    /// Fails if `workspace` isn't in a git repository or the repository has
+    /// no commits to branch from.
+    pub fn open_or_create(workspace: &Path, session_id: &str) -> Result<Self> {
+        let worktree = Self::for_session(workspace, session_id)?;
+        if worktree.exists() {
+            return Ok(worktree);
+        }
+        if git(&worktree.repo, &["rev-parse", "--verify", "--quiet", "HEAD"]).is_err() {
+            return Err(Error::Workspace(format!(
+                "{} has no commits yet; commit something before starting an isolated session",
+                worktree.repo.display()
+            )));
+        }
+
+        worktree.exclude_worktrees_dir()?;
+        // A worktree directory deleted by hand is still registered until pruned
+        git(&worktree.repo, &["worktree", "prune"])?;
+        let path = worktree.path.to_string_lossy();
+        if worktree.branch_exists() {
+            git(&worktree.repo, &["worktree", "add", &path, &worktree.branch])?;
+        } else {
+            git(&worktree.repo, &["worktree", "add", "-b", &worktree.branch, &path, "HEAD"])?;
+        }
+        Ok(worktree)
+    }
+
+    /// Whether the worktree is checked out
+    pub fn exists(&self) -> bool {
+        self.path.join(".git").exists()
+    }
+
+    fn branch_exists(&self) -> bool {
+        let reference = format!("refs/heads/{}", self.branch);
+        git(&self.repo, &["rev-parse", "--verify", "--quiet", &reference]).is_ok()
+    }
+
+    /// What merging the worktree would bring into the repository's branch
+    pub fn summary(&self) -> Result<WorktreeSummary> {
+        let target = git(&self.repo, &["symbolic-ref", "--short", "-q", "HEAD"]).unwrap_or_else(|_| "HEAD".to_string());
+        let range = format!("HEAD..{}", self.branch);
+        let base = git(&self.repo, &["merge-base", "HEAD", &self.branch])?;
+        Ok(WorktreeSummary {
+            branch: self.branch.clone(),
+            target,
+            commits: lines(&git(&self.repo, &["log", "--oneline", &range])?),
+            uncommitted: lines(&git(&self.path, &["status", "--short"])?),
+            diff_stat: git(&self.path, &["diff", "--stat", &base])?,
+        })
+    }
+
+    /// Merge the worktree's branch into the repository's and remove the worktree
+    ///
+    /// Uncommitted changes in the worktree are committed first. A conflicting
+    /// merge is aborted and reported as `Error::MergeConflict`, leaving the
+    /// repository as it was and the worktree in place. Returns what was merged.
+    pub fn merge(&self) -> Result<WorktreeSummary> {
+        let summary = self.summary()?;
+        if !summary.uncommitted.is_empty() {
+            git(&self.path, &["add", "--all"])?;
+            let message = format!("Uncommitted changes from {}", self.branch);
+            git(&self.path, &["commit", "--quiet", "-m", &message])?;
+        }
+
+        if !summary.is_empty()
+            && let Err(e) = git(&self.repo, &["merge", "--no-ff", "--no-edit", &self.branch])
+        {
+            let conflicts = lines(&git(&self.repo, &["diff", "--name-only", "--diff-filter=U"]).unwrap_or_default());
+            if conflicts.is_empty() {
+                return Err(e);
+            }
+            let _ = git(&self.repo, &["merge", "--abort"]);
+            return Err(Error::MergeConflict(self.branch.clone(), conflicts));
+        }
+
+        self.remove()?;
+        Ok(summary)
+    }
+
+    /// Remove the worktree and delete its branch, discarding unmerged work
+    pub fn remove(&self) -> Result<()> {
+        if self.exists() {
+            git(&self.repo, &["worktree", "remove", "--force", &self.path.to_string_lossy()])?;
+        } else {
+            git(&self.repo, &["worktree", "prune"])?;
+        }
+        if self.branch_exists() {
+            git(&self.repo, &["branch", "-D", &self.branch])?;
+        }
+        Ok(())
+    }
+
+    /// Keep worktrees out of the repository's `git status` without touching .gitignore
+    fn exclude_worktrees_dir(&self) -> Result<()> {
+        let common_dir = PathBuf::from(git(&self.repo, &["rev-parse", "--git-common-dir"])?);
+        let exclude = self.repo.join(common_dir).join("info").join("exclude");
+        let pattern = format!("/{}/", WORKTREES_DIR);
+        let existing = std::fs::read_to_string(&exclude).unwrap_or_default();
+        if existing.lines().any(|line| line.trim() == pattern) {
+            return Ok(());
+        }
+        if let Some(dir) = exclude.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let separator = if existing.is_empty() || existing.ends_with('\n') { "" } else { "\n" };
+        std::fs::write(&exclude, format!("{}{}{}\n", existing, separator, pattern))?;
+        Ok(())
+    }
+}
+
+/// Run git in `dir`, returning its trimmed stdout
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git").arg("-C").arg(dir).args(args).output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::Workspace(format!("git {} failed: {}", args.join(" "), stderr.trim())));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim_end().to_string())
+}
+
+fn lines(output: &str) -> Vec<String> {
+    output.lines().filter(|line| !line.trim().is_empty()).map(str::to_string).collect()
+}
@@ -0,0 +1,220 @@
+//! Built-in `/agents` skill for browsing and trying out agent definitions
+//!
+//! Runs locally like `/memory`. Agents come from the workspace's
+//! `ComponentRegistry` (built-in, plugin, user and project agents), with
+//! agent files that failed to parse listed alongside their errors.
+//!
+//! Subcommands:
+//! - `list` (default) - every agent with its scope, model and allowed tools
+//! - `show <name>` - an agent's details and its assembled system prompt
+//! - `test <name> "<task>"` - run the agent on a task in this workspace. The
+//!   Task tool call is made by the LLM: the instruction is returned in
+//!   `SkillResult::data[FOLLOW_UP_PROMPT_KEY]`, and the subagent's progress
+//!   streams like any other Task call.
+
+use serde_json::json;
+
+use crate::prompt::{AgentDefinition, AgentInfo, ComponentRegistry, RegistrySummary, TemplateVars};
+use crate::skills::{BoxFuture, Skill, SkillContext, SkillInfo, SkillResult, FOLLOW_UP_PROMPT_KEY};
+
+/// Usage text shown for unknown subcommands
+const USAGE: &str = "Usage: /agents [list | show <name> | test <name> \"<task>\"]";
+
+/// Skill that lists, shows and tests agents
+pub struct AgentsSkill;
+
+impl AgentsSkill {
+    pub fn new() -> Self {
+        Self
+    }
+
+    fn list(&self, summary: &RegistrySummary) -> SkillResult {
+        let mut out = String::from("Agents:\n");
+        for agent in &summary.agents {
+            out.push_str(&format!(
+                "  {:<18} {:<8} model: {:<8} tools: {}\n",
+                agent.name,
+                agent.scope,
+                agent.model.as_deref().unwrap_or("inherit"),
+                tools_label(agent)
+            ));
+            out.push_str(&format!("      {}\n", agent.description));
+        }
+
+        let errors: Vec<_> = summary.agent_errors().collect();
+        if !errors.is_empty() {
+            out.push_str("\nFailed to load:\n");
+            for error in &errors {
+                out.push_str(&format!("  {}: {}\n", error.path.display(), error.message));
+            }
+        }
+        SkillResult::success(out.trim_end()).with_data(json!({ "agents": summary.agents, "errors": errors }))
+    }
+
+    fn show(&self, registry: &ComponentRegistry, summary: &RegistrySummary, name: &str) -> SkillResult {
+        let Some(agent) = find_agent(registry, name) else {
+            return unknown_agent(summary, name);
+        };
+        let Some(info) = summary.agents.iter().find(|a| a.name == agent.name()) else {
+            return unknown_agent(summary, name);
+        };
+
+        let mut out = format!("{} ({})\n{}\n\n", info.name, info.scope, info.description);
+        out.push_str(&format!("Model: {}\n", info.model.as_deref().unwrap_or("inherit")));
+        out.push_str(&format!("Tools: {}\n", tools_label(info)));
+        if let Some(max_turns) = info.max_turns {
+            out.push_str(&format!("Max turns: {}\n", max_turns));
+        }
+        if let Some(path) = &info.source_path {
+            out.push_str(&format!("Source: {}\n", path.display()));
+        }
+        out.push_str(&format!("\nSystem prompt:\n{}", info.prompt.as_deref().unwrap_or_default()));
+        SkillResult::success(out.trim_end()).with_data(json!(info))
+    }
+
+    fn test(&self, registry: &ComponentRegistry, summary: &RegistrySummary, args: &str) -> SkillResult {
+        let args = args.trim();
+        let (name, task) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+        let task = unquote(task);
+        if name.is_empty() || task.is_empty() {
+            return SkillResult::error(USAGE);
+        }
+        let Some(agent) = find_agent(registry, name) else {
+            return unknown_agent(summary, name);
+        };
+
+        SkillResult::success(format!("Testing agent '{}' on: {}", agent.name(), task))
+            .with_data(json!({ FOLLOW_UP_PROMPT_KEY: test_prompt(agent.name(), task) }))
+    }
+}
+
+impl Default for AgentsSkill {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The instruction that has the LLM run `agent` on `task` through the Task tool
+pub fn test_prompt(agent: &str, task: &str) -> String {
+    format!(
+        "Test the \"{agent}\" agent on a task in this workspace.\n\n<task>\n{task}\n</task>\n\n\
+         Use the Task tool with subagent_type=\"{agent}\" and the task above as its prompt, \
+         passed unchanged. Then report what the agent did and returned."
+    )
+}
+
+/// Look up an agent by name, ignoring case like the Task tool does
+fn find_agent<'a>(registry: &'a ComponentRegistry, name: &str) -> Option<&'a AgentDefinition> {
+    registry
+        .get_agent(name)
+        .or_else(|| registry.list_agents().find(|a| a.name().eq_ignore_ascii_case(name)))
+}
+
+fn unknown_agent(summary: &RegistrySummary, name: &str) -> SkillResult {
+    let names: Vec<&str> = summary.agents.iter().map(|a| a.name.as_str()).collect();
+    SkillResult::error(format!("Unknown agent '{}'. Available: {}", name, names.join(", ")))
+}
+
+fn tools_label(agent: &AgentInfo) -> String {
+    if agent.tools.is_empty() {
+        "all".to_string()
+    } else {
+        agent.tools.join(", ")
+    }
+}
+
+/// Strip one pair of surrounding quotes
+fn unquote(text: &str) -> &str {
+    let text = text.trim();
+    ['"', '\'']
+        .iter()
+        .find_map(|q| text.strip_prefix(*q).and_then(|t| t.strip_suffix(*q)))
+        .unwrap_or(text)
+        .trim()
+}
+
+impl Skill for AgentsSkill {
+    fn info(&self) -> SkillInfo {
+        SkillInfo {
+            name: "agents".to_string(),
+            display_name: "Agents".to_string(),
+            description: "List agents, show their system prompts, and test them on a task".to_string(),
+            usage: USAGE.to_string(),
+            user_invocable: true,
+        }
+    }
+
+    fn execute(&self, ctx: SkillContext) -> BoxFuture<'_, SkillResult> {
+        Box::pin(async move {
+            let registry = match ComponentRegistry::for_workspace(&ctx.workspace) {
+                Ok(registry) => registry,
+                Err(e) => return SkillResult::error(format!("Failed to load agents: {}", e)),
+            };
+            let summary = registry.summary_with_prompts(&TemplateVars::gather(&ctx.workspace));
+
+            let args = ctx.args.trim();
+            let (command, rest) = args.split_once(' ').unwrap_or((args, ""));
+            match command {
+                "" | "list" => self.list(&summary),
+                "show" => self.show(&registry, &summary, rest.trim()),
+                "test" => self.test(&registry, &summary, rest),
+                other => SkillResult::error(format!("Unknown agents command '{}'. {}", other, USAGE)),
+            }
+        })
+    }
+
+    fn prompt_template(&self) -> &str {
+        USAGE
+    }
+
+    fn runs_locally(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+
+    fn ctx(workspace: &TempDir, args: &str) -> SkillContext {
+        SkillContext {
+            workspace: workspace.path().to_path_buf(),
+            args: args.to_string(),
+            data: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_show_test() {
+        let dir = TempDir::new().unwrap();
+        let agents_dir = dir.path().join(".claude").join("agents");
+        std::fs::create_dir_all(&agents_dir).unwrap();
+        std::fs::write(
+            agents_dir.join("reviewer.md"),
+            "---\nname: reviewer\ndescription: Reviews diffs\nmodel: haiku\ntools: Read, Grep\n---\n\nReview code in ${WORKING_DIRECTORY}.",
+        )
+        .unwrap();
+        std::fs::write(agents_dir.join("broken.md"), "---\nname: broken\nmax_turns: lots\n---\n\nPrompt").unwrap();
+        let skill = AgentsSkill::new();
+
+        let result = skill.execute(ctx(&dir, "")).await;
+        assert!(result.success, "{:?}", result.error);
+        assert!(result.response.contains("Explore"));
+        assert!(result.response.contains("reviewer"));
+        assert!(result.response.contains("tools: Read, Grep"));
+        assert!(result.response.contains("broken.md"));
+
+        let result = skill.execute(ctx(&dir, "show Reviewer")).await;
+        assert!(result.response.contains("Model: haiku"));
+        assert!(result.response.contains(&format!("Review code in {}.", dir.path().display())));
+        assert!(!skill.execute(ctx(&dir, "show nobody")).await.success);
+
+        let result = skill.execute(ctx(&dir, "test reviewer \"check the README\"")).await;
+        let prompt = result.data.unwrap()[FOLLOW_UP_PROMPT_KEY].as_str().unwrap().to_string();
+        assert!(prompt.contains("subagent_type=\"reviewer\""));
+        assert!(prompt.contains("<task>\ncheck the README\n</task>"));
+        assert!(!skill.execute(ctx(&dir, "test reviewer")).await.success);
+    }
+}
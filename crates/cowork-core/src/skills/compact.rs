@@ -0,0 +1,122 @@
+//! Built-in `/compact` skill to compact the history now
+//!
+//! Runs locally like `/recipe`: compacting needs the session, so the skill
+//! returns what to do in `SkillResult::data["compact"]` (`{mode, model,
+//! focus}`, unset ones null) and the frontend sends it as
+//! `SessionInput::Compact`. Unset options come from `[summarizer]`.
+//!
+//! Usage:
+//! - `/compact` - compact the history the configured way
+//! - `/compact --mode heuristic` - condense it without a model call
+//! - `/compact --model <model> [focus]` - summarize with another model,
+//!   paying attention to `focus`
+
+use serde_json::json;
+
+use crate::context::CompactionMode;
+use crate::skills::{BoxFuture, Skill, SkillContext, SkillInfo, SkillResult};
+
+/// Key for the compaction to run (`{mode, model, focus}`) in skill result data
+pub const COMPACT_KEY: &str = "compact";
+
+/// Usage text shown for invalid arguments
+const USAGE: &str = "Usage: /compact [--mode llm|heuristic] [--model <model>] [focus]";
+
+/// Skill that compacts the session's history
+pub struct CompactSkill;
+
+impl CompactSkill {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for CompactSkill {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Skill for CompactSkill {
+    fn info(&self) -> SkillInfo {
+        SkillInfo {
+            name: "compact".to_string(),
+            display_name: "Compact".to_string(),
+            description: "Summarize the conversation so far to free up context".to_string(),
+            usage: USAGE.to_string(),
+            user_invocable: true,
+        }
+    }
+
+    fn execute(&self, ctx: SkillContext) -> BoxFuture<'_, SkillResult> {
+        Box::pin(async move {
+            let mut mode = None;
+            let mut model = None;
+            let mut focus = Vec::new();
+            let mut words = ctx.args.split_whitespace();
+            while let Some(word) = words.next() {
+                match word {
+                    "--mode" => match words.next().map(str::parse::<CompactionMode>) {
+                        Some(Ok(parsed)) => mode = Some(parsed),
+                        Some(Err(e)) => return SkillResult::error(e),
+                        None => return SkillResult::error(USAGE),
+                    },
+                    "--model" => match words.next() {
+                        Some(name) => model = Some(name.to_string()),
+                        None => return SkillResult::error(USAGE),
+                    },
+                    _ => focus.push(word),
+                }
+            }
+            if mode == Some(CompactionMode::Heuristic) && model.is_some() {
+                return SkillResult::error("--model only applies to --mode llm");
+            }
+
+            let focus = (!focus.is_empty()).then(|| focus.join(" "));
+            SkillResult::success("Compacting the conversation").with_data(json!({
+                COMPACT_KEY: { "mode": mode, "model": model, "focus": focus }
+            }))
+        })
+    }
+
+    fn prompt_template(&self) -> &str {
+        USAGE
+    }
+
+    fn runs_locally(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn ctx(args: &str) -> SkillContext {
+        SkillContext {
+            workspace: std::env::temp_dir(),
+            args: args.to_string(),
+            data: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_compact_args() {
+        let skill = CompactSkill::new();
+        let data = skill.execute(ctx("")).await.data.unwrap();
+        assert_eq!(data[COMPACT_KEY], json!({ "mode": null, "model": null, "focus": null }));
+
+        let data = skill.execute(ctx("--model gpt-5-mini the API changes")).await.data.unwrap();
+        assert_eq!(
+            data[COMPACT_KEY],
+            json!({ "mode": null, "model": "gpt-5-mini", "focus": "the API changes" })
+        );
+        let data = skill.execute(ctx("--mode heuristic")).await.data.unwrap();
+        assert_eq!(data[COMPACT_KEY]["mode"], "heuristic");
+
+        assert!(!skill.execute(ctx("--mode summary")).await.success);
+        assert!(!skill.execute(ctx("--model")).await.success);
+        assert!(!skill.execute(ctx("--mode heuristic --model gpt-5-mini")).await.success);
+    }
+}
@@ -0,0 +1,226 @@
+//! Built-in `/context` skill for showing the project overview
+//!
+//! Runs locally like `/debug`. Shows the overview a session gives the model
+//! with its first message (see `context::overview`), as last cached for the
+//! workspace. `/context refresh` gathers it again.
+//!
+//! The session's tool profile is shown first when the frontend passes it,
+//! since its tool definitions are sent with every request too, followed by
+//! the tools the session has called most (see `session::tool_usage`).
+//!
+//! Usage: `/context [refresh]`
+
+use serde_json::Value;
+
+use crate::context::overview::{cached_overview, project_overview, DEFAULT_OVERVIEW_BUDGET, OVERVIEW_CACHE_FILE};
+use crate::session::ToolUsageStats;
+use crate::skills::{BoxFuture, Skill, SkillContext, SkillInfo, SkillResult};
+
+/// Usage text shown for invalid arguments
+const USAGE: &str = "Usage: /context [refresh] - show the project overview given to the model";
+
+/// Key for the session's tool profile in skill context data: `profile`,
+/// and once the session has reported them, `tools` and `definition_tokens`
+/// (as in `SessionOutput::ToolProfileChanged`)
+pub const TOOL_PROFILE_KEY: &str = "tool_profile";
+
+/// Key for the session's tool calls in skill context data (a
+/// `ToolUsageStats`, as in `SessionStats::tool_usage`)
+pub const TOOL_USAGE_KEY: &str = "tool_usage";
+
+/// Tools shown in the table of the session's most called tools
+const TOP_TOOLS: usize = 10;
+
+/// The table of the session's most called tools in `data`, if it called any
+fn tool_usage_table(data: &Value) -> Option<String> {
+    let usage: ToolUsageStats = serde_json::from_value(data.clone()).ok()?;
+    if usage.is_empty() {
+        return None;
+    }
+    let top = usage.top(TOP_TOOLS);
+    let width = top.iter().map(|(tool, _)| tool.chars().count()).max().unwrap_or(0).max(4);
+    let mut lines = vec![
+        format!("Top tools ({} calls):", usage.total().invocations),
+        format!(
+            "  {:<width$}  {:>5}  {:>5}  {:>6}  {:>8}  {:>8}  {:>9}",
+            "tool", "calls", "ok", "failed", "rejected", "time", "to model"
+        ),
+    ];
+    for (tool, counts) in top {
+        lines.push(format!(
+            "  {:<width$}  {:>5}  {:>5}  {:>6}  {:>8}  {:>7.1}s  {:>7} KB",
+            tool,
+            counts.invocations,
+            counts.successes,
+            counts.failures,
+            counts.rejections,
+            counts.duration_ms as f64 / 1000.0,
+            counts.result_bytes.div_ceil(1024),
+        ));
+    }
+    Some(lines.join("\n"))
+}
+
+/// The line describing the tool profile in `data`, if there is one
+fn tool_profile_line(data: &Value) -> Option<String> {
+    let profile = data.get("profile")?.as_str()?;
+    let tools: Vec<&str> = data
+        .get("tools")
+        .and_then(Value::as_array)
+        .map(|tools| tools.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    let tokens = data.get("definition_tokens").and_then(Value::as_u64);
+    Some(match tokens {
+        Some(tokens) if !tools.is_empty() => format!(
+            "Tool profile: {} ({} tools, ~{} tokens per request): {}",
+            profile,
+            tools.len(),
+            tokens,
+            tools.join(", ")
+        ),
+        _ => format!("Tool profile: {}", profile),
+    })
+}
+
+/// Skill that shows the project overview injected on the first turn
+pub struct ContextSkill;
+
+impl ContextSkill {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for ContextSkill {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Skill for ContextSkill {
+    fn info(&self) -> SkillInfo {
+        SkillInfo {
+            name: "context".to_string(),
+            display_name: "Context".to_string(),
+            description: "Show the project overview given to the model at the start of a session".to_string(),
+            usage: USAGE.to_string(),
+            user_invocable: true,
+        }
+    }
+
+    fn execute(&self, ctx: SkillContext) -> BoxFuture<'_, SkillResult> {
+        Box::pin(async move {
+            let overview = match ctx.args.trim() {
+                "" => cached_overview(&ctx.workspace),
+                "refresh" => {
+                    let workspace = ctx.workspace.clone();
+                    tokio::task::spawn_blocking(move || project_overview(&workspace, DEFAULT_OVERVIEW_BUDGET))
+                        .await
+                        .ok()
+                        .flatten()
+                }
+                _ => return SkillResult::error(USAGE),
+            };
+            let overview = match overview {
+                Some(text) if !text.is_empty() => format!("{}\n\n(cached in {})", text, OVERVIEW_CACHE_FILE),
+                _ => "No project overview yet: one is gathered with a session's first message (or run /context refresh)"
+                    .to_string(),
+            };
+            let sections: Vec<String> = [
+                ctx.data.get(TOOL_PROFILE_KEY).and_then(tool_profile_line),
+                ctx.data.get(TOOL_USAGE_KEY).and_then(tool_usage_table),
+                Some(overview),
+            ]
+            .into_iter()
+            .flatten()
+            .collect();
+            SkillResult::success(sections.join("\n\n"))
+        })
+    }
+
+    fn prompt_template(&self) -> &str {
+        USAGE
+    }
+
+    fn runs_locally(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::ToolDoneStatus;
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    fn ctx(workspace: &std::path::Path, args: &str) -> SkillContext {
+        SkillContext {
+            workspace: workspace.to_path_buf(),
+            args: args.to_string(),
+            data: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_context_shows_cached_overview() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("Cargo.toml"), "[package]\nname = \"demo\"\n").unwrap();
+        let skill = ContextSkill::new();
+
+        let empty = skill.execute(ctx(dir.path(), "")).await;
+        assert!(empty.response.contains("No project overview yet"));
+
+        let refreshed = skill.execute(ctx(dir.path(), "refresh")).await;
+        assert!(refreshed.response.contains("`cargo test`"), "{}", refreshed.response);
+        assert_eq!(skill.execute(ctx(dir.path(), "")).await.response, refreshed.response);
+        assert!(!skill.execute(ctx(dir.path(), "bogus")).await.success);
+    }
+
+    #[tokio::test]
+    async fn test_context_shows_tool_profile() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let skill = ContextSkill::new();
+
+        let mut context = ctx(dir.path(), "");
+        context.data.insert(TOOL_PROFILE_KEY.to_string(), serde_json::json!({ "profile": "full" }));
+        let result = skill.execute(context).await;
+        assert!(result.response.starts_with("Tool profile: full\n\nNo project overview yet"), "{}", result.response);
+
+        let mut context = ctx(dir.path(), "");
+        context.data.insert(
+            TOOL_PROFILE_KEY.to_string(),
+            serde_json::json!({ "profile": "minimal", "tools": ["Bash", "Read"], "definition_tokens": 420 }),
+        );
+        let result = skill.execute(context).await;
+        assert!(
+            result.response.starts_with("Tool profile: minimal (2 tools, ~420 tokens per request): Bash, Read\n\n"),
+            "{}",
+            result.response
+        );
+    }
+
+    #[tokio::test]
+    async fn test_context_shows_top_tools() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let skill = ContextSkill::new();
+
+        let mut usage = ToolUsageStats::default();
+        for _ in 0..3 {
+            usage.record("Grep", ToolDoneStatus::Success, Duration::from_millis(400), 2048);
+        }
+        usage.record("Edit", ToolDoneStatus::Rejected, Duration::ZERO, 30);
+        let mut context = ctx(dir.path(), "");
+        context.data.insert(TOOL_USAGE_KEY.to_string(), serde_json::to_value(&usage).unwrap());
+        let result = skill.execute(context).await;
+        let lines: Vec<&str> = result.response.lines().collect();
+        assert_eq!(lines[0], "Top tools (4 calls):");
+        assert_eq!(lines[2], "  Grep      3      3       0         0      1.2s        6 KB");
+        assert_eq!(lines[3], "  Edit      1      0       0         1      0.0s        1 KB");
+
+        // Nothing called yet: no table
+        let mut context = ctx(dir.path(), "");
+        context.data.insert(TOOL_USAGE_KEY.to_string(), serde_json::json!({}));
+        assert!(skill.execute(context).await.response.starts_with("No project overview yet"));
+    }
+}
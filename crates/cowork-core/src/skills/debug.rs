@@ -0,0 +1,125 @@
+//! Built-in `/debug` skill for inspecting provider traffic
+//!
+//! Unlike prompt-based skills, `/debug` runs locally: its output is shown
+//! to the user directly and never sent to the LLM.
+//!
+//! Subcommands:
+//! - `last-request` - pretty-print the most recent record from the LLM request log
+
+use std::path::PathBuf;
+
+use crate::provider::logging::read_last_record;
+use crate::provider::LlmLogger;
+use crate::skills::{BoxFuture, Skill, SkillContext, SkillInfo, SkillResult};
+
+/// Usage text shown for unknown subcommands
+const USAGE: &str = "Usage: /debug last-request";
+
+/// Skill that surfaces debugging information about the session
+pub struct DebugSkill {
+    /// Log path override (None = resolve from config/environment at execution time)
+    log_path: Option<PathBuf>,
+}
+
+impl DebugSkill {
+    pub fn new() -> Self {
+        Self { log_path: None }
+    }
+
+    /// Read from a specific request log instead of the configured one
+    pub fn with_log_path(path: impl Into<PathBuf>) -> Self {
+        Self { log_path: Some(path.into()) }
+    }
+
+    fn last_request(&self) -> SkillResult {
+        let Some(path) = self
+            .log_path
+            .clone()
+            .or_else(|| LlmLogger::load().map(|l| l.path().to_path_buf()))
+        else {
+            return SkillResult::error(
+                "LLM request logging is disabled. Set `[general] llm_log = \"path\"` in config \
+                 or the COWORK_LLM_LOG environment variable.",
+            );
+        };
+
+        match read_last_record(&path) {
+            Some(record) => {
+                let pretty = serde_json::to_string_pretty(&record).unwrap_or_default();
+                SkillResult::success(format!("Last LLM request ({}):\n{}", path.display(), pretty))
+                    .with_data(record)
+            }
+            None => SkillResult::error(format!("No requests logged yet in {}", path.display())),
+        }
+    }
+}
+
+impl Default for DebugSkill {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Skill for DebugSkill {
+    fn info(&self) -> SkillInfo {
+        SkillInfo {
+            name: "debug".to_string(),
+            display_name: "Debug".to_string(),
+            description: "Inspect the most recent LLM request and response".to_string(),
+            usage: USAGE.to_string(),
+            user_invocable: true,
+        }
+    }
+
+    fn execute(&self, ctx: SkillContext) -> BoxFuture<'_, SkillResult> {
+        Box::pin(async move {
+            match ctx.args.trim() {
+                "last-request" => self.last_request(),
+                other => SkillResult::error(format!("Unknown debug command '{}'. {}", other, USAGE)),
+            }
+        })
+    }
+
+    fn prompt_template(&self) -> &str {
+        USAGE
+    }
+
+    fn runs_locally(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::logging::LogConfig;
+    use std::collections::HashMap;
+
+    fn ctx(args: &str) -> SkillContext {
+        SkillContext {
+            workspace: PathBuf::from("."),
+            args: args.to_string(),
+            data: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_last_request_pretty_prints_record() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("llm.jsonl");
+        LlmLogger::new(&path).log(LogConfig { model: "first-model", ..Default::default() });
+        LlmLogger::new(&path).log(LogConfig { model: "last-model", ..Default::default() });
+
+        let result = DebugSkill::with_log_path(&path).execute(ctx("last-request")).await;
+        assert!(result.success);
+        assert!(result.response.contains("\"model\": \"last-model\""));
+        assert!(!result.response.contains("first-model"));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_subcommand() {
+        let result = DebugSkill::new().execute(ctx("bogus")).await;
+        assert!(!result.success);
+        assert!(result.error.unwrap().contains(USAGE));
+    }
+}
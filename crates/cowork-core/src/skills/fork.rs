@@ -0,0 +1,96 @@
+//! Built-in `/fork` skill for branching the conversation
+//!
+//! Runs locally like `/plans`. Forking needs the session manager, so the
+//! skill only validates its argument and returns the number of user
+//! messages to go back in `SkillResult::data["fork_turns_back"]`; the
+//! frontend forks with `SessionManager::fork_session` at the message before
+//! that user message (see `session::fork_point_before_user_turn`) and
+//! switches to the new session.
+//!
+//! Usage: `/fork [n]` - fork before the n-th last user message (default 1,
+//! i.e. retry the last message differently)
+
+use serde_json::json;
+
+use crate::skills::{BoxFuture, Skill, SkillContext, SkillInfo, SkillResult};
+
+/// Key for the number of user messages to go back in skill result data
+pub const FORK_TURNS_BACK_KEY: &str = "fork_turns_back";
+
+/// Usage text shown for invalid arguments
+const USAGE: &str = "Usage: /fork [n] - branch off before your n-th last message (default 1)";
+
+/// Skill that forks the session from an earlier message
+pub struct ForkSkill;
+
+impl ForkSkill {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for ForkSkill {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Skill for ForkSkill {
+    fn info(&self) -> SkillInfo {
+        SkillInfo {
+            name: "fork".to_string(),
+            display_name: "Fork".to_string(),
+            description: "Branch the conversation into a new session from an earlier message".to_string(),
+            usage: USAGE.to_string(),
+            user_invocable: true,
+        }
+    }
+
+    fn execute(&self, ctx: SkillContext) -> BoxFuture<'_, SkillResult> {
+        Box::pin(async move {
+            let args = ctx.args.trim();
+            let turns_back = if args.is_empty() {
+                1
+            } else {
+                match args.parse::<usize>() {
+                    Ok(n) if n > 0 => n,
+                    _ => return SkillResult::error(USAGE),
+                }
+            };
+            let plural = if turns_back == 1 { "" } else { "s" };
+            SkillResult::success(format!("Forking before your last {} message{}", turns_back, plural))
+                .with_data(json!({ FORK_TURNS_BACK_KEY: turns_back }))
+        })
+    }
+
+    fn prompt_template(&self) -> &str {
+        USAGE
+    }
+
+    fn runs_locally(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn ctx(args: &str) -> SkillContext {
+        SkillContext {
+            workspace: std::env::temp_dir(),
+            args: args.to_string(),
+            data: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fork_args() {
+        let skill = ForkSkill::new();
+        assert_eq!(skill.execute(ctx("")).await.data.unwrap()[FORK_TURNS_BACK_KEY], 1);
+        assert_eq!(skill.execute(ctx(" 3 ")).await.data.unwrap()[FORK_TURNS_BACK_KEY], 3);
+        assert!(!skill.execute(ctx("0")).await.success);
+        assert!(!skill.execute(ctx("two")).await.success);
+    }
+}
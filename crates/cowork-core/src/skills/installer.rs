@@ -11,7 +11,6 @@ use std::fs;
 use std::io::{self, Cursor, Read};
 use std::path::{Path, PathBuf};
 
-use reqwest::blocking::Client;
 use tracing::{debug, info, warn};
 use zip::ZipArchive;
 
@@ -78,8 +77,6 @@ pub enum InstallError {
 
 /// Skill package installer
 pub struct SkillInstaller {
-    /// HTTP client for downloads
-    client: Client,
     /// Workspace directory (for project-level installs)
     workspace: PathBuf,
 }
@@ -87,10 +84,7 @@ pub struct SkillInstaller {
 impl SkillInstaller {
     /// Create a new installer
     pub fn new(workspace: PathBuf) -> Self {
-        Self {
-            client: Client::new(),
-            workspace,
-        }
+        Self { workspace }
     }
 
     /// Get the global skills directory
@@ -115,7 +109,8 @@ impl SkillInstaller {
         info!("Downloading skill from {}", url);
 
         // Download the zip file
-        let response = self.client.get(url).send().map_err(|e| {
+        let client = crate::http::blocking_client().map_err(|e| InstallError::DownloadError(e.to_string()))?;
+        let response = client.get(url).send().map_err(|e| {
             InstallError::DownloadError(format!("Failed to fetch {}: {}", url, e))
         })?;
 
@@ -0,0 +1,192 @@
+//! Built-in `/memory` skill for viewing and editing memory
+//!
+//! Runs locally like `/plans`. Entries are managed in the workspace's
+//! `CLAUDE.local.md` (see `crate::context::memory`); the other memory files
+//! are only listed. Changes apply from the next session, when the memory
+//! hierarchy is read into the system prompt.
+//!
+//! Subcommands:
+//! - (none) - the memory files in effect for this workspace
+//! - `list` - the `CLAUDE.local.md` entries, numbered
+//! - `add "<note>"` - append an entry
+//! - `remove <n>` - delete entry `n`
+//! - `edit` - the path of `CLAUDE.local.md` to open (created if missing), also
+//!   returned in `SkillResult::data["edit_path"]` for frontends to open
+
+use serde_json::json;
+
+use crate::context::{ContextGatherer, LocalMemory};
+use crate::skills::{BoxFuture, Skill, SkillContext, SkillInfo, SkillResult};
+
+/// Key for the file to open in skill result data
+pub const EDIT_PATH_KEY: &str = "edit_path";
+
+/// Usage text shown for unknown subcommands
+const USAGE: &str = "Usage: /memory [list | add \"<note>\" | remove <n> | edit]";
+
+/// Skill that shows and edits memory
+pub struct MemorySkill;
+
+impl MemorySkill {
+    pub fn new() -> Self {
+        Self
+    }
+
+    fn files(&self, ctx: &SkillContext) -> SkillResult {
+        let hierarchy = ContextGatherer::new(&ctx.workspace).memory_hierarchy();
+        if hierarchy.is_empty() {
+            return SkillResult::success(format!(
+                "No memory files. Add one with /memory add \"<note>\" (saved to {}).",
+                LocalMemory::for_workspace(&ctx.workspace).path().display()
+            ));
+        }
+
+        let mut out = String::from("Memory files, highest priority first:\n");
+        for file in &hierarchy.files {
+            out.push_str(&format!("  {:<10}  {} ({} bytes)\n", file.tier.to_string(), file.path.display(), file.size));
+        }
+        SkillResult::success(out.trim_end())
+    }
+
+    fn list(&self, memory: &LocalMemory) -> SkillResult {
+        let entries = match memory.entries() {
+            Ok(entries) => entries,
+            Err(e) => return SkillResult::error(format!("Failed to read {}: {}", memory.path().display(), e)),
+        };
+        if entries.is_empty() {
+            return SkillResult::success(format!("No entries in {}", memory.path().display()));
+        }
+
+        let width = entries.len().to_string().len();
+        let mut out = format!("{}:\n", memory.path().display());
+        for (i, entry) in entries.iter().enumerate() {
+            out.push_str(&format!("{:>width$}. {}\n", i + 1, entry));
+        }
+        SkillResult::success(out.trim_end())
+    }
+
+    fn add(&self, memory: &LocalMemory, note: &str) -> SkillResult {
+        let note = unquote(note);
+        if note.is_empty() {
+            return SkillResult::error(USAGE);
+        }
+        match memory.add(note) {
+            Ok(()) => SkillResult::success(format!("Saved to {}: {}", memory.path().display(), note)),
+            Err(e) => SkillResult::error(format!("Failed to save memory: {}", e)),
+        }
+    }
+
+    fn remove(&self, memory: &LocalMemory, index: &str) -> SkillResult {
+        let Ok(index) = index.parse::<usize>() else {
+            return SkillResult::error(USAGE);
+        };
+        match memory.remove(index) {
+            Ok(entry) => SkillResult::success(format!("Removed: {}", entry)),
+            Err(e) => SkillResult::error(e.to_string()),
+        }
+    }
+
+    fn edit(&self, memory: &LocalMemory) -> SkillResult {
+        if let Err(e) = memory.ensure_exists() {
+            return SkillResult::error(format!("Failed to create {}: {}", memory.path().display(), e));
+        }
+        let path = memory.path().display().to_string();
+        SkillResult::success(format!("Edit {} in your editor; changes apply from the next session.", path))
+            .with_data(json!({ EDIT_PATH_KEY: path }))
+    }
+}
+
+impl Default for MemorySkill {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Strip one pair of surrounding quotes
+fn unquote(text: &str) -> &str {
+    let text = text.trim();
+    ['"', '\'']
+        .iter()
+        .find_map(|q| text.strip_prefix(*q).and_then(|t| t.strip_suffix(*q)))
+        .unwrap_or(text)
+        .trim()
+}
+
+impl Skill for MemorySkill {
+    fn info(&self) -> SkillInfo {
+        SkillInfo {
+            name: "memory".to_string(),
+            display_name: "Memory".to_string(),
+            description: "Show memory files and add or remove project memory entries".to_string(),
+            usage: USAGE.to_string(),
+            user_invocable: true,
+        }
+    }
+
+    fn execute(&self, ctx: SkillContext) -> BoxFuture<'_, SkillResult> {
+        Box::pin(async move {
+            let memory = LocalMemory::for_workspace(&ctx.workspace);
+            let args = ctx.args.trim();
+            let (command, rest) = args.split_once(' ').unwrap_or((args, ""));
+            match command {
+                "" => self.files(&ctx),
+                "list" => self.list(&memory),
+                "add" => self.add(&memory, rest),
+                "remove" | "rm" => self.remove(&memory, rest.trim()),
+                "edit" => self.edit(&memory),
+                other => SkillResult::error(format!("Unknown memory command '{}'. {}", other, USAGE)),
+            }
+        })
+    }
+
+    fn prompt_template(&self) -> &str {
+        USAGE
+    }
+
+    fn runs_locally(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+
+    fn ctx(workspace: &TempDir, args: &str) -> SkillContext {
+        SkillContext {
+            workspace: workspace.path().to_path_buf(),
+            args: args.to_string(),
+            data: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_add_list_remove_edit() {
+        let dir = TempDir::new().unwrap();
+        let skill = MemorySkill::new();
+
+        let result = skill.execute(ctx(&dir, "add \"Use tabs in Makefiles\"")).await;
+        assert!(result.success, "{:?}", result.error);
+        skill.execute(ctx(&dir, "add Run cargo fmt first")).await;
+
+        let result = skill.execute(ctx(&dir, "list")).await;
+        assert!(result.response.contains("1. Use tabs in Makefiles"));
+        assert!(result.response.contains("2. Run cargo fmt first"));
+
+        let result = skill.execute(ctx(&dir, "")).await;
+        assert!(result.response.contains("CLAUDE.local.md"));
+
+        let result = skill.execute(ctx(&dir, "remove 1")).await;
+        assert_eq!(result.response, "Removed: Use tabs in Makefiles");
+        assert!(!skill.execute(ctx(&dir, "remove 9")).await.success);
+        assert!(!skill.execute(ctx(&dir, "remove one")).await.success);
+        assert!(!skill.execute(ctx(&dir, "add \"\"")).await.success);
+
+        let result = skill.execute(ctx(&dir, "edit")).await;
+        let path = result.data.unwrap()[EDIT_PATH_KEY].as_str().unwrap().to_string();
+        assert!(path.ends_with("CLAUDE.local.md"));
+        assert!(std::path::Path::new(&path).exists());
+    }
+}
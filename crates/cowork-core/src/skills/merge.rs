@@ -0,0 +1,87 @@
+//! Built-in `/merge` skill for finishing a session isolated in a git worktree
+//!
+//! Runs locally like `/fork`. Merging needs the session manager, so the
+//! skill only parses its argument and returns whether the merge is approved
+//! in `SkillResult::data["merge_worktree"]`: without `confirm` the frontend
+//! shows `SessionManager::worktree_summary` for review; with it, it calls
+//! `SessionManager::merge_worktree`.
+//!
+//! Usage: `/merge [confirm]` - review the worktree's changes, then merge them
+
+use serde_json::json;
+
+use crate::skills::{BoxFuture, Skill, SkillContext, SkillInfo, SkillResult};
+
+/// Key for whether the merge is approved (false = show the summary) in skill result data
+pub const MERGE_WORKTREE_KEY: &str = "merge_worktree";
+
+/// Usage text shown for invalid arguments
+const USAGE: &str = "Usage: /merge [confirm] - review this session's worktree changes, then merge them with /merge confirm";
+
+/// Skill that merges an isolated session's worktree back
+pub struct MergeSkill;
+
+impl MergeSkill {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for MergeSkill {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Skill for MergeSkill {
+    fn info(&self) -> SkillInfo {
+        SkillInfo {
+            name: "merge".to_string(),
+            display_name: "Merge".to_string(),
+            description: "Review and merge the changes of a session running in a git worktree".to_string(),
+            usage: USAGE.to_string(),
+            user_invocable: true,
+        }
+    }
+
+    fn execute(&self, ctx: SkillContext) -> BoxFuture<'_, SkillResult> {
+        Box::pin(async move {
+            match ctx.args.trim() {
+                "" => SkillResult::success("Reviewing the worktree's changes")
+                    .with_data(json!({ MERGE_WORKTREE_KEY: false })),
+                "confirm" => SkillResult::success("Merging the worktree").with_data(json!({ MERGE_WORKTREE_KEY: true })),
+                _ => SkillResult::error(USAGE),
+            }
+        })
+    }
+
+    fn prompt_template(&self) -> &str {
+        USAGE
+    }
+
+    fn runs_locally(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn ctx(args: &str) -> SkillContext {
+        SkillContext {
+            workspace: std::env::temp_dir(),
+            args: args.to_string(),
+            data: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_merge_args() {
+        let skill = MergeSkill::new();
+        assert_eq!(skill.execute(ctx("")).await.data.unwrap()[MERGE_WORKTREE_KEY], false);
+        assert_eq!(skill.execute(ctx(" confirm ")).await.data.unwrap()[MERGE_WORKTREE_KEY], true);
+        assert!(!skill.execute(ctx("now")).await.success);
+    }
+}
@@ -8,9 +8,25 @@
 //! - User level: `~/.claude/skills/`
 //! - Project level: `{workspace}/.cowork/skills/`
 
+pub mod agents;
 pub mod builtins;
+pub mod compact;
+pub mod context;
+pub mod debug;
+pub mod fork;
 pub mod installer;
 pub mod loader;
+pub mod memory;
+pub mod merge;
+pub mod permissions;
+pub mod pin;
+pub mod plans;
+pub mod provider;
+pub mod recipe;
+pub mod retry;
+pub mod settings;
+pub mod template;
+pub mod title;
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -36,6 +52,9 @@ pub struct SkillInfo {
     pub user_invocable: bool,
 }
 
+/// Key for a prompt a local skill hands to the LLM as the next user message
+pub const FOLLOW_UP_PROMPT_KEY: &str = "follow_up_prompt";
+
 /// Result of executing a skill
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SkillResult {
@@ -117,6 +136,12 @@ pub trait Skill: Send + Sync {
     fn model_override(&self) -> Option<&str> {
         None
     }
+
+    /// Whether this skill runs locally, producing output for the user
+    /// directly instead of a prompt for the LLM
+    fn runs_locally(&self) -> bool {
+        false
+    }
 }
 
 /// Registry of available skills
@@ -138,6 +163,23 @@ impl SkillRegistry {
         for skill in builtins::load_builtin_skills() {
             registry.register(skill);
         }
+        registry.register(Arc::new(debug::DebugSkill::new()));
+        registry.register(Arc::new(settings::SettingsSkill::new()));
+        registry.register(Arc::new(plans::PlansSkill::new()));
+        registry.register(Arc::new(memory::MemorySkill::new()));
+        registry.register(Arc::new(agents::AgentsSkill::new()));
+        registry.register(Arc::new(fork::ForkSkill::new()));
+        registry.register(Arc::new(merge::MergeSkill::new()));
+        registry.register(Arc::new(recipe::RecipeSkill::new()));
+        registry.register(Arc::new(retry::RetrySkill::new()));
+        registry.register(Arc::new(retry::EditLastSkill::new()));
+        registry.register(Arc::new(permissions::PermissionsSkill::new()));
+        registry.register(Arc::new(provider::ProviderSkill::new()));
+        registry.register(Arc::new(pin::PinSkill::new()));
+        registry.register(Arc::new(title::TitleSkill::new()));
+        registry.register(Arc::new(context::ContextSkill::new()));
+        registry.register(Arc::new(compact::CompactSkill::new()));
+        registry.register(Arc::new(template::TemplateSkill::new()));
 
         // Load dynamic skills from filesystem
         // Project skills override user skills with the same name
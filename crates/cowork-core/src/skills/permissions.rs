@@ -0,0 +1,143 @@
+//! Built-in `/permissions` skill for showing what tools may do
+//!
+//! Runs locally like `/debug`. Shows the approval level and, when an
+//! organization policy is in effect (see `crate::policy`), the rules it
+//! adds: tools that always ask, disabled tools, blocked hosts and the
+//! required provider. Settings the policy locks are marked, since changing
+//! them in the user's config has no effect.
+//!
+//! Usage: `/permissions`
+
+use crate::approval::ApprovalLevel;
+use crate::config::{Config, ConfigManager};
+use crate::policy::Policy;
+use crate::skills::{BoxFuture, Skill, SkillContext, SkillInfo, SkillResult};
+
+/// Usage text shown for invalid arguments
+const USAGE: &str = "Usage: /permissions - show approval settings and organization policy rules";
+
+/// Skill that shows the approval settings and the policy constraining them
+pub struct PermissionsSkill;
+
+impl PermissionsSkill {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for PermissionsSkill {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Skill for PermissionsSkill {
+    fn info(&self) -> SkillInfo {
+        SkillInfo {
+            name: "permissions".to_string(),
+            display_name: "Permissions".to_string(),
+            description: "Show approval settings and which are locked by organization policy".to_string(),
+            usage: USAGE.to_string(),
+            user_invocable: true,
+        }
+    }
+
+    fn execute(&self, ctx: SkillContext) -> BoxFuture<'_, SkillResult> {
+        Box::pin(async move {
+            if !ctx.args.trim().is_empty() {
+                return SkillResult::error(USAGE);
+            }
+            match ConfigManager::new() {
+                Ok(manager) => SkillResult::success(describe(manager.config(), &manager.policy())),
+                Err(e) => SkillResult::error(e.to_string()),
+            }
+        })
+    }
+
+    fn prompt_template(&self) -> &str {
+        USAGE
+    }
+
+    fn runs_locally(&self) -> bool {
+        true
+    }
+}
+
+/// The permissions summary for `config` under `policy`
+pub fn describe(config: &Config, policy: &Policy) -> String {
+    const LOCKED: &str = " [locked by policy]";
+
+    let configured = config.approval.auto_approve_level.parse().unwrap_or(ApprovalLevel::Low);
+    let effective = policy.clamp_approval_level(configured);
+    let mut lines = vec![match policy.min_approval_level() {
+        Some(min) if effective != configured => format!(
+            "Approval level: {} (configured {}, policy minimum {}){}",
+            effective, configured, min, LOCKED
+        ),
+        Some(min) => format!("Approval level: {} (policy minimum {}){}", effective, min, LOCKED),
+        None => format!("Approval level: {}", effective),
+    }];
+
+    if policy.is_empty() {
+        lines.push("No organization policy is in effect.".to_string());
+        return lines.join("\n");
+    }
+
+    let list = |items: &[String]| if items.is_empty() { "none".to_string() } else { items.join(", ") };
+    lines.push(format!("Always ask (policy): {}", list(&policy.approval.require)));
+    lines.push(format!("Disabled tools (policy): {}", list(&policy.tools.disabled)));
+    lines.push(format!("Blocked hosts (policy): {}", list(policy.blocked_hosts())));
+
+    let provider = &config.default_provider;
+    if !policy.provider.allowed.is_empty() {
+        let status = if policy.allows_provider(provider) { "" } else { ", not allowed" };
+        lines.push(format!(
+            "Provider: {} (allowed: {}{}){}",
+            provider,
+            policy.provider.allowed.join(", "),
+            status,
+            LOCKED
+        ));
+    }
+    if let Some(url) = &policy.provider.base_url {
+        lines.push(format!("Base URL: {}{}", url, LOCKED));
+    }
+    lines.push(format!("Policy file: {}", policy.describe()));
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe_without_policy() {
+        let text = describe(&Config::default(), &Policy::default());
+        assert!(text.contains("No organization policy"));
+        assert!(!text.contains("locked"));
+    }
+
+    #[test]
+    fn test_describe_marks_locked_settings() {
+        let mut config = Config::default();
+        config.approval.auto_approve_level = "none".to_string();
+        config.default_provider = "openai".to_string();
+        let policy = Policy::parse(
+            r#"
+            [approval]
+            min_level = "medium"
+            require = ["Bash"]
+
+            [provider]
+            allowed = ["anthropic"]
+            "#,
+        )
+        .unwrap();
+
+        let text = describe(&config, &policy);
+        assert!(text.contains("Approval level: medium (configured none, policy minimum medium) [locked by policy]"));
+        assert!(text.contains("Always ask (policy): Bash"));
+        assert!(text.contains("Disabled tools (policy): none"));
+        assert!(text.contains("Provider: openai (allowed: anthropic, not allowed) [locked by policy]"));
+    }
+}
@@ -0,0 +1,119 @@
+//! Built-in `/pin` skill for keeping a session when saved sessions are
+//! pruned, or a reply when the history is compacted
+//!
+//! Runs locally like `/fork`. Pinning is a session input, so the skill only
+//! validates its argument and returns the flag in
+//! `SkillResult::data["pin_session"]` or `data["pin_reply"]`; the frontend
+//! sends `SessionInput::SetPinned` (see `session::retention`) or
+//! `SessionInput::PinMessage` (see `session::pins`) to the session.
+//!
+//! Usage:
+//! - `/pin` - pin the current session
+//! - `/pin off` - unpin it
+//! - `/pin reply` - keep the latest reply verbatim through compaction
+//! - `/pin reply off` - unpin it
+
+use serde_json::json;
+
+use crate::skills::{BoxFuture, Skill, SkillContext, SkillInfo, SkillResult};
+
+/// Key for whether to pin the session in skill result data
+pub const PIN_SESSION_KEY: &str = "pin_session";
+
+/// Key for whether to pin the latest reply in skill result data
+pub const PIN_REPLY_KEY: &str = "pin_reply";
+
+/// Usage text shown for invalid arguments
+const USAGE: &str = "Usage: /pin [off] - keep this session when saved sessions are pruned\n       /pin reply [off] - keep the latest reply when the history is compacted";
+
+/// Skill that pins or unpins the session
+pub struct PinSkill;
+
+impl PinSkill {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for PinSkill {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Skill for PinSkill {
+    fn info(&self) -> SkillInfo {
+        SkillInfo {
+            name: "pin".to_string(),
+            display_name: "Pin".to_string(),
+            description: "Pin the session so pruning of saved sessions keeps it".to_string(),
+            usage: USAGE.to_string(),
+            user_invocable: true,
+        }
+    }
+
+    fn execute(&self, ctx: SkillContext) -> BoxFuture<'_, SkillResult> {
+        Box::pin(async move {
+            let args: Vec<&str> = ctx.args.split_whitespace().collect();
+            let (reply, flag) = match args.as_slice() {
+                ["reply", flag @ ..] => (true, flag),
+                flag => (false, flag),
+            };
+            let pinned = match flag {
+                [] | ["on"] => true,
+                ["off"] => false,
+                _ => return SkillResult::error(USAGE),
+            };
+            if reply {
+                let message = if pinned {
+                    "Reply pinned; compaction will keep it"
+                } else {
+                    "Reply unpinned"
+                };
+                return SkillResult::success(message).with_data(json!({ PIN_REPLY_KEY: pinned }));
+            }
+            let message = if pinned {
+                "Session pinned; pruning will keep it"
+            } else {
+                "Session unpinned"
+            };
+            SkillResult::success(message).with_data(json!({ PIN_SESSION_KEY: pinned }))
+        })
+    }
+
+    fn prompt_template(&self) -> &str {
+        USAGE
+    }
+
+    fn runs_locally(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn ctx(args: &str) -> SkillContext {
+        SkillContext {
+            workspace: std::env::temp_dir(),
+            args: args.to_string(),
+            data: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pin_args() {
+        let skill = PinSkill::new();
+        assert_eq!(skill.execute(ctx("")).await.data.unwrap()[PIN_SESSION_KEY], true);
+        assert_eq!(skill.execute(ctx(" off ")).await.data.unwrap()[PIN_SESSION_KEY], false);
+        assert!(!skill.execute(ctx("forever")).await.success);
+
+        let reply = skill.execute(ctx("reply")).await.data.unwrap();
+        assert_eq!(reply[PIN_REPLY_KEY], true);
+        assert!(reply.get(PIN_SESSION_KEY).is_none());
+        assert_eq!(skill.execute(ctx("reply off")).await.data.unwrap()[PIN_REPLY_KEY], false);
+        assert!(!skill.execute(ctx("reply forever")).await.success);
+    }
+}
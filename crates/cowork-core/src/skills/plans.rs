@@ -0,0 +1,177 @@
+//! Built-in `/plans` skill for browsing and approving plans
+//!
+//! Runs locally like `/debug`. Approving does not change the plan itself: the
+//! skill returns the plan name in `SkillResult::data["approve_plan"]` (null
+//! for the session's current plan) for the frontend to send as
+//! `SessionInput::ApprovePlan`, so the session injects it into the next turn.
+//!
+//! Subcommands:
+//! - `list` (default) - all plans, newest first, with their status
+//! - `open <name>` - show a plan and its steps
+//! - `approve [name]` - approve a plan (default: the session's current plan)
+
+use serde_json::json;
+
+use crate::skills::{BoxFuture, Skill, SkillContext, SkillInfo, SkillResult};
+use crate::tools::planning::PlanStore;
+
+/// Key for the plan to approve in skill result data
+pub const APPROVE_PLAN_KEY: &str = "approve_plan";
+
+/// Usage text shown for unknown subcommands
+const USAGE: &str = "Usage: /plans [list | open <name> | approve [name]]";
+
+/// Skill that lists, opens, and approves plans
+pub struct PlansSkill {
+    store: PlanStore,
+}
+
+impl PlansSkill {
+    pub fn new() -> Self {
+        Self { store: PlanStore::default() }
+    }
+
+    /// Use plans from this store instead of the plans directory
+    pub fn with_store(mut self, store: PlanStore) -> Self {
+        self.store = store;
+        self
+    }
+
+    fn list(&self) -> SkillResult {
+        let plans = match self.store.list() {
+            Ok(plans) => plans,
+            Err(e) => return SkillResult::error(format!("Failed to list plans: {}", e)),
+        };
+        if plans.is_empty() {
+            return SkillResult::success(format!("No plans in {}", self.store.dir().display()));
+        }
+
+        let name_width = plans.iter().map(|p| p.name.len()).max().unwrap_or(0).max(4);
+        let mut out = format!("{:<name_width$}  {:<11}  {:<16}  TITLE\n", "NAME", "STATUS", "CREATED");
+        for plan in &plans {
+            out.push_str(&format!(
+                "{:<name_width$}  {:<11}  {:<16}  {}\n",
+                plan.name,
+                plan.status.to_string(),
+                plan.created_at.format("%Y-%m-%d %H:%M"),
+                plan.title
+            ));
+        }
+        SkillResult::success(out.trim_end())
+    }
+
+    fn open(&self, name: &str) -> SkillResult {
+        if name.is_empty() {
+            return SkillResult::error(USAGE);
+        }
+        let plan = match self.store.open(name) {
+            Ok(Some(plan)) => plan,
+            Ok(None) => return SkillResult::error(format!("Plan not found: {}", name)),
+            Err(e) => return SkillResult::error(format!("Failed to read plan {}: {}", name, e)),
+        };
+
+        let mut out = format!("{} [{}] - {}\n", plan.record.name, plan.record.status, plan.path.display());
+        if plan.is_read_only() {
+            out.push_str("(read-only: no checklist to track)\n");
+        }
+        out.push('\n');
+        out.push_str(plan.content.trim_end());
+        SkillResult::success(out)
+    }
+
+    fn approve(&self, name: &str) -> SkillResult {
+        if name.is_empty() {
+            return SkillResult::success("Approving the current plan")
+                .with_data(json!({ APPROVE_PLAN_KEY: null }));
+        }
+        match self.store.open(name) {
+            Ok(Some(plan)) => SkillResult::success(format!("Approving plan {} ({})", plan.record.name, plan.record.title))
+                .with_data(json!({ APPROVE_PLAN_KEY: plan.record.name })),
+            Ok(None) => SkillResult::error(format!("Plan not found: {}", name)),
+            Err(e) => SkillResult::error(format!("Failed to read plan {}: {}", name, e)),
+        }
+    }
+}
+
+impl Default for PlansSkill {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Skill for PlansSkill {
+    fn info(&self) -> SkillInfo {
+        SkillInfo {
+            name: "plans".to_string(),
+            display_name: "Plans".to_string(),
+            description: "List, open, and approve plans".to_string(),
+            usage: USAGE.to_string(),
+            user_invocable: true,
+        }
+    }
+
+    fn execute(&self, ctx: SkillContext) -> BoxFuture<'_, SkillResult> {
+        Box::pin(async move {
+            let args = ctx.args.trim();
+            let (command, rest) = args.split_once(' ').unwrap_or((args, ""));
+            match command {
+                "" | "list" => self.list(),
+                "open" => self.open(rest.trim()),
+                "approve" => self.approve(rest.trim()),
+                other => SkillResult::error(format!("Unknown plans command '{}'. {}", other, USAGE)),
+            }
+        })
+    }
+
+    fn prompt_template(&self) -> &str {
+        USAGE
+    }
+
+    fn runs_locally(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::planning::PlanStatus;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    fn ctx(args: &str) -> SkillContext {
+        SkillContext {
+            workspace: PathBuf::from("."),
+            args: args.to_string(),
+            data: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_open_and_approve() {
+        let dir = TempDir::new().unwrap();
+        let store = PlanStore::new(dir.path());
+        std::fs::write(store.path_for("calm-river"), "# Add caching\n- [ ] Add the cache\n").unwrap();
+        std::fs::write(store.path_for("old-notes"), "Just some prose.\n").unwrap();
+        store.set_status("calm-river", PlanStatus::Approved).unwrap();
+        let skill = PlansSkill::new().with_store(store);
+
+        let result = skill.execute(ctx("")).await;
+        assert!(result.response.contains("calm-river  approved"));
+        assert!(result.response.contains("old-notes   draft"));
+        assert!(result.response.contains("Add caching"));
+
+        let result = skill.execute(ctx("open old-notes")).await;
+        assert!(result.response.contains("read-only"));
+        assert!(result.response.contains("Just some prose."));
+
+        let result = skill.execute(ctx("approve calm-river")).await;
+        assert_eq!(result.data.unwrap()[APPROVE_PLAN_KEY], "calm-river");
+
+        let result = skill.execute(ctx("approve")).await;
+        assert!(result.data.unwrap()[APPROVE_PLAN_KEY].is_null());
+
+        assert!(!skill.execute(ctx("approve missing")).await.success);
+    }
+}
@@ -0,0 +1,137 @@
+//! Built-in `/provider` skill for checking whether providers are reachable
+//!
+//! Runs locally like `/permissions`. Shows each configured provider's circuit
+//! (see `crate::provider::circuit`): whether requests are going through, or
+//! until when it's marked unavailable after repeated timeouts or server
+//! errors, and how to switch to another provider meanwhile.
+//!
+//! Usage:
+//! - `/provider` - show every provider's status
+//! - `/provider reset <id>` - mark a provider available again
+
+use crate::config::{Config, ConfigManager};
+use crate::provider::{provider_circuits, CircuitState, ProviderCircuits, ProviderStatus};
+use crate::skills::{BoxFuture, Skill, SkillContext, SkillInfo, SkillResult};
+
+/// Usage text shown for invalid arguments
+const USAGE: &str = "Usage: /provider [reset <id>]";
+
+/// Skill that shows and resets provider circuits
+pub struct ProviderSkill;
+
+impl ProviderSkill {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for ProviderSkill {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Skill for ProviderSkill {
+    fn info(&self) -> SkillInfo {
+        SkillInfo {
+            name: "provider".to_string(),
+            display_name: "Provider".to_string(),
+            description: "Show which providers are available, or mark one available again".to_string(),
+            usage: USAGE.to_string(),
+            user_invocable: true,
+        }
+    }
+
+    fn execute(&self, ctx: SkillContext) -> BoxFuture<'_, SkillResult> {
+        Box::pin(async move {
+            let args: Vec<&str> = ctx.args.split_whitespace().collect();
+            match args.as_slice() {
+                [] => match ConfigManager::new() {
+                    Ok(manager) => SkillResult::success(describe(manager.config(), provider_circuits())),
+                    Err(e) => SkillResult::error(e.to_string()),
+                },
+                ["reset", provider_id] => {
+                    provider_circuits().reset(provider_id);
+                    SkillResult::success(format!("{} marked available", provider_id))
+                }
+                _ => SkillResult::error(USAGE),
+            }
+        })
+    }
+
+    fn prompt_template(&self) -> &str {
+        USAGE
+    }
+
+    fn runs_locally(&self) -> bool {
+        true
+    }
+}
+
+/// Each configured provider's status, then any other provider with recent failures
+pub fn describe(config: &Config, circuits: &ProviderCircuits) -> String {
+    let mut provider_ids: Vec<String> = config.providers.keys().cloned().collect();
+    if !provider_ids.contains(&config.default_provider) {
+        provider_ids.push(config.default_provider.clone());
+    }
+    provider_ids.sort();
+    for status in circuits.statuses() {
+        if !provider_ids.contains(&status.provider_id) {
+            provider_ids.push(status.provider_id);
+        }
+    }
+
+    let mut lines = vec!["Providers:".to_string()];
+    for provider_id in &provider_ids {
+        let default = if *provider_id == config.default_provider { " (default)" } else { "" };
+        lines.push(format!("  {}{}: {}", provider_id, default, describe_status(&circuits.status(provider_id))));
+    }
+    if provider_ids.iter().any(|id| circuits.status(id).state == CircuitState::Open) {
+        lines.push(String::new());
+        lines.push(
+            "To switch, start a session with --provider <id> or choose another provider in the app's settings. \
+             /provider reset <id> marks a provider available again."
+                .to_string(),
+        );
+    }
+    lines.join("\n")
+}
+
+fn describe_status(status: &ProviderStatus) -> String {
+    let last_error = status
+        .last_error
+        .as_deref()
+        .map(|e| format!(" - last error: {}", e.lines().next().unwrap_or_default()))
+        .unwrap_or_default();
+    match status.state {
+        CircuitState::Closed if status.recent_failures == 0 => "available".to_string(),
+        CircuitState::Closed => format!("available ({} recent failures){}", status.recent_failures, last_error),
+        CircuitState::HalfOpen => format!("recovering, the next request decides{}", last_error),
+        CircuitState::Open => format!(
+            "unavailable until {}{}",
+            status.open_until.map(|until| until.format("%H:%M").to_string()).unwrap_or_default(),
+            last_error
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::CIRCUIT_COOLDOWN;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_describe_open_circuit() {
+        let circuits = ProviderCircuits::new(1, CIRCUIT_COOLDOWN, CIRCUIT_COOLDOWN);
+        let config = Config::default();
+        let text = describe(&config, &circuits);
+        assert!(text.contains(&format!("{} (default): available", config.default_provider)), "{}", text);
+        assert!(!text.contains("--provider"));
+
+        circuits.record_failure("openai", "No response from openai within 120s");
+        let text = describe(&config, &circuits);
+        assert!(text.contains("openai: unavailable until"), "{}", text);
+        assert!(text.contains("last error: No response from openai within 120s"), "{}", text);
+        assert!(text.contains("--provider <id>"), "{}", text);
+    }
+}
@@ -0,0 +1,187 @@
+//! Built-in `/recipe` skill for the workspace's scripted workflows
+//!
+//! Runs locally like `/merge`. Listing, validating and dry runs are answered
+//! here; running a recipe needs the session, so the skill returns what to run
+//! in `SkillResult::data["run_recipe"]` (`{name, args}`) and the frontend
+//! sends it as `SessionInput::RunRecipe`.
+//!
+//! Usage:
+//! - `/recipe` or `/recipe list` - list the workspace's recipes
+//! - `/recipe validate <name>` - check a recipe without running it
+//! - `/recipe run <name> [args] [--dry-run]` - run a recipe, or print its resolved plan
+
+use serde_json::json;
+
+use crate::recipes::{RecipeRunner, builtin_tools_and_skills, list_recipes, load_recipe, recipes_dir};
+use crate::skills::{BoxFuture, Skill, SkillContext, SkillInfo, SkillResult};
+
+/// Key for the recipe to run (`{name, args}`) in skill result data
+pub const RUN_RECIPE_KEY: &str = "run_recipe";
+
+/// Flag that prints a recipe's plan instead of running it
+const DRY_RUN_FLAG: &str = "--dry-run";
+
+/// Usage text shown for invalid arguments
+const USAGE: &str = "Usage: /recipe [list] | /recipe validate <name> | /recipe run <name> [args] [--dry-run]";
+
+/// Skill that lists, checks and starts recipes
+pub struct RecipeSkill;
+
+impl RecipeSkill {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for RecipeSkill {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Skill for RecipeSkill {
+    fn info(&self) -> SkillInfo {
+        SkillInfo {
+            name: "recipe".to_string(),
+            display_name: "Recipe".to_string(),
+            description: "List, validate or run the workflows in .cowork/recipes".to_string(),
+            usage: USAGE.to_string(),
+            user_invocable: true,
+        }
+    }
+
+    fn execute(&self, ctx: SkillContext) -> BoxFuture<'_, SkillResult> {
+        Box::pin(async move {
+            let args = ctx.args.trim();
+            let (command, rest) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+            match command {
+                "" | "list" => list(&ctx),
+                "validate" if !rest.trim().is_empty() => validate(&ctx, rest.trim()),
+                "run" if !rest.trim().is_empty() => run(&ctx, rest.trim()),
+                _ => SkillResult::error(USAGE),
+            }
+        })
+    }
+
+    fn prompt_template(&self) -> &str {
+        USAGE
+    }
+
+    fn runs_locally(&self) -> bool {
+        true
+    }
+}
+
+fn list(ctx: &SkillContext) -> SkillResult {
+    let recipes = list_recipes(&ctx.workspace);
+    if recipes.is_empty() {
+        return SkillResult::success(format!("No recipes in {}", recipes_dir(&ctx.workspace).display()));
+    }
+    let mut lines = vec!["Recipes:".to_string()];
+    for (name, recipe) in recipes {
+        lines.push(match recipe {
+            Ok(recipe) if recipe.description.is_empty() => format!("  {}", recipe.usage()),
+            Ok(recipe) => format!("  {} - {}", recipe.usage(), recipe.description.lines().next().unwrap_or_default()),
+            Err(e) => format!("  {} (invalid: {})", name, e),
+        });
+    }
+    SkillResult::success(lines.join("\n"))
+}
+
+fn validate(ctx: &SkillContext, name: &str) -> SkillResult {
+    let recipe = match load_recipe(&ctx.workspace, name) {
+        Ok(recipe) => recipe,
+        Err(e) => return SkillResult::error(e.to_string()),
+    };
+    let (tools, skills) = builtin_tools_and_skills(&ctx.workspace);
+    let problems = recipe.validate(&tools, &skills);
+    if problems.is_empty() {
+        SkillResult::success(format!("{} is valid ({} steps)", name, recipe.steps.len()))
+    } else {
+        SkillResult::error(format!("{} is not valid:\n- {}", name, problems.join("\n- ")))
+    }
+}
+
+fn run(ctx: &SkillContext, rest: &str) -> SkillResult {
+    let dry_run = rest.split_whitespace().any(|word| word == DRY_RUN_FLAG);
+    let rest = rest
+        .split_whitespace()
+        .filter(|word| *word != DRY_RUN_FLAG)
+        .collect::<Vec<_>>()
+        .join(" ");
+    let (name, args) = rest.split_once(' ').unwrap_or((&rest, ""));
+    if name.is_empty() {
+        return SkillResult::error(USAGE);
+    }
+    if dry_run {
+        return plan(ctx, name, args);
+    }
+    SkillResult::success(format!("Running recipe {}", name)).with_data(json!({
+        RUN_RECIPE_KEY: { "name": name, "args": args }
+    }))
+}
+
+/// The steps a run would take, without running any
+fn plan(ctx: &SkillContext, name: &str, args: &str) -> SkillResult {
+    let (tools, skills) = builtin_tools_and_skills(&ctx.workspace);
+    let plan = load_recipe(&ctx.workspace, name)
+        .and_then(|recipe| RecipeRunner::new(recipe, args, &tools, &skills))
+        .and_then(|runner| runner.format_plan());
+    match plan {
+        Ok(plan) => SkillResult::success(plan),
+        Err(e) => SkillResult::error(e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::path::Path;
+
+    fn ctx(workspace: &Path, args: &str) -> SkillContext {
+        SkillContext {
+            workspace: workspace.to_path_buf(),
+            args: args.to_string(),
+            data: HashMap::new(),
+        }
+    }
+
+    fn workspace() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let recipes = dir.path().join(crate::recipes::RECIPES_DIR);
+        std::fs::create_dir_all(&recipes).unwrap();
+        std::fs::write(
+            recipes.join("release.yaml"),
+            "description: Tag a release\nargs:\n  - name: version\nsteps:\n  - id: tag\n    shell: git tag v${args.version}\n",
+        )
+        .unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_list_recipes() {
+        let dir = workspace();
+        let result = RecipeSkill::new().execute(ctx(dir.path(), "")).await;
+        assert!(result.success);
+        assert!(result.response.contains("/recipe run release <version> - Tag a release"), "{}", result.response);
+    }
+
+    #[tokio::test]
+    async fn test_run_returns_recipe_to_run() {
+        let dir = workspace();
+        let result = RecipeSkill::new().execute(ctx(dir.path(), "run release 1.2.0")).await;
+        assert_eq!(result.data.unwrap()[RUN_RECIPE_KEY], json!({ "name": "release", "args": "1.2.0" }));
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_prints_plan() {
+        let dir = workspace();
+        let result = RecipeSkill::new().execute(ctx(dir.path(), "run release 1.2.0 --dry-run")).await;
+        assert!(result.data.is_none());
+        assert!(result.response.contains("$ git tag v1.2.0"), "{}", result.response);
+
+        let missing = RecipeSkill::new().execute(ctx(dir.path(), "run release --dry-run")).await;
+        assert!(!missing.success);
+    }
+}
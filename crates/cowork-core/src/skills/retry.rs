@@ -0,0 +1,155 @@
+//! Built-in `/retry` and `/edit-last` skills for re-running a turn
+//!
+//! Both run locally like `/fork`, but stay in the same session: the frontend
+//! sends `SessionInput::RegenerateLast` or `SessionInput::EditUserMessage`
+//! (for the last user message it displayed) with the values in
+//! `SkillResult::data`. The session refuses either while a turn is in flight.
+//!
+//! Usage:
+//! - `/retry [temperature]` - discard the last reply and generate it again,
+//!   optionally at a different temperature for that reply only
+//! - `/edit-last <message>` - replace your last message and re-run from it
+
+use serde_json::json;
+
+use crate::provider::generation::TEMPERATURE_RANGE;
+use crate::skills::{BoxFuture, Skill, SkillContext, SkillInfo, SkillResult};
+
+/// Key for the optional temperature of the regenerated reply in skill result data
+pub const RETRY_TEMPERATURE_KEY: &str = "retry_temperature";
+
+/// Key for the replacement text of the last user message in skill result data
+pub const EDIT_LAST_CONTENT_KEY: &str = "edit_last_content";
+
+const RETRY_USAGE: &str = "Usage: /retry [temperature] - discard the last reply and generate it again";
+const EDIT_LAST_USAGE: &str = "Usage: /edit-last <message> - replace your last message and re-run from it";
+
+/// Skill that regenerates the last reply
+pub struct RetrySkill;
+
+impl RetrySkill {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for RetrySkill {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Skill for RetrySkill {
+    fn info(&self) -> SkillInfo {
+        SkillInfo {
+            name: "retry".to_string(),
+            display_name: "Retry".to_string(),
+            description: "Discard the last reply and generate it again".to_string(),
+            usage: RETRY_USAGE.to_string(),
+            user_invocable: true,
+        }
+    }
+
+    fn execute(&self, ctx: SkillContext) -> BoxFuture<'_, SkillResult> {
+        Box::pin(async move {
+            let args = ctx.args.trim();
+            if args.is_empty() {
+                return SkillResult::success("Regenerating the last reply")
+                    .with_data(json!({ RETRY_TEMPERATURE_KEY: null }));
+            }
+            let (min, max) = TEMPERATURE_RANGE;
+            match args.parse::<f32>() {
+                Ok(t) if (min..=max).contains(&t) => {
+                    SkillResult::success(format!("Regenerating the last reply at temperature {}", t))
+                        .with_data(json!({ RETRY_TEMPERATURE_KEY: t }))
+                }
+                _ => SkillResult::error(format!("{} (temperature {} to {})", RETRY_USAGE, min, max)),
+            }
+        })
+    }
+
+    fn prompt_template(&self) -> &str {
+        RETRY_USAGE
+    }
+
+    fn runs_locally(&self) -> bool {
+        true
+    }
+}
+
+/// Skill that rewrites the last user message
+pub struct EditLastSkill;
+
+impl EditLastSkill {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for EditLastSkill {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Skill for EditLastSkill {
+    fn info(&self) -> SkillInfo {
+        SkillInfo {
+            name: "edit-last".to_string(),
+            display_name: "Edit Last".to_string(),
+            description: "Replace your last message and re-run the conversation from it".to_string(),
+            usage: EDIT_LAST_USAGE.to_string(),
+            user_invocable: true,
+        }
+    }
+
+    fn execute(&self, ctx: SkillContext) -> BoxFuture<'_, SkillResult> {
+        Box::pin(async move {
+            let content = ctx.args.trim();
+            if content.is_empty() {
+                return SkillResult::error(EDIT_LAST_USAGE);
+            }
+            SkillResult::success("Re-running from your edited message")
+                .with_data(json!({ EDIT_LAST_CONTENT_KEY: content }))
+        })
+    }
+
+    fn prompt_template(&self) -> &str {
+        EDIT_LAST_USAGE
+    }
+
+    fn runs_locally(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn ctx(args: &str) -> SkillContext {
+        SkillContext {
+            workspace: std::env::temp_dir(),
+            args: args.to_string(),
+            data: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_args() {
+        let skill = RetrySkill::new();
+        assert!(skill.execute(ctx("")).await.data.unwrap()[RETRY_TEMPERATURE_KEY].is_null());
+        assert_eq!(skill.execute(ctx(" 1.5 ")).await.data.unwrap()[RETRY_TEMPERATURE_KEY], 1.5);
+        assert!(!skill.execute(ctx("3")).await.success);
+        assert!(!skill.execute(ctx("hot")).await.success);
+    }
+
+    #[tokio::test]
+    async fn test_edit_last_args() {
+        let skill = EditLastSkill::new();
+        let data = skill.execute(ctx("  list the tests instead ")).await.data.unwrap();
+        assert_eq!(data[EDIT_LAST_CONTENT_KEY], "list the tests instead");
+        assert!(!skill.execute(ctx("   ")).await.success);
+    }
+}
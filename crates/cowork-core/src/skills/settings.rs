@@ -0,0 +1,201 @@
+//! Built-in `/settings` skill for adjusting the current session
+//!
+//! Runs locally like `/debug`. The frontend passes the session's current
+//! generation parameters in `SkillContext::data["generation_params"]`; when
+//! the command changes them, the updated set is returned in
+//! `SkillResult::data["generation_params"]` for the frontend to send as
+//! `SessionInput::SetGenerationParams`.
+//!
+//! Subcommands:
+//! - `model` - show the generation parameters
+//! - `model temperature=0.2 max_tokens=1024 top_p=0.9 stop=###` - change them
+//!   (`stop` may repeat; `key=default` clears one; `reset` clears all)
+
+use serde_json::json;
+
+use crate::provider::GenerationParams;
+use crate::skills::{BoxFuture, Skill, SkillContext, SkillInfo, SkillResult};
+
+/// Key for the current/updated parameters in skill context and result data
+pub const GENERATION_PARAMS_KEY: &str = "generation_params";
+
+/// Usage text shown for unknown subcommands
+const USAGE: &str = "Usage: /settings model [reset] [temperature=<0-2>] [max_tokens=<n>] [top_p=<0-1>] [stop=<seq>]...";
+
+/// Skill that adjusts settings of the current session
+pub struct SettingsSkill;
+
+impl SettingsSkill {
+    pub fn new() -> Self {
+        Self
+    }
+
+    fn model(&self, args: &str, current: GenerationParams) -> SkillResult {
+        if args.is_empty() {
+            return SkillResult::success(format!("Generation parameters: {}", current));
+        }
+
+        let mut params = current;
+        let mut stop_sequences: Option<Vec<String>> = None;
+        for arg in args.split_whitespace() {
+            if arg == "reset" {
+                params = GenerationParams::default();
+                continue;
+            }
+            let Some((key, value)) = arg.split_once('=') else {
+                return SkillResult::error(format!("Expected key=value, got '{}'. {}", arg, USAGE));
+            };
+            let clear = value == "default";
+            let parsed = match key {
+                "temperature" => parse(key, value, clear).map(|v| params.temperature = v),
+                "max_tokens" => parse(key, value, clear).map(|v| params.max_tokens = v),
+                "top_p" => parse(key, value, clear).map(|v| params.top_p = v),
+                "stop" => {
+                    let stops = stop_sequences.get_or_insert_with(Vec::new);
+                    if !clear {
+                        stops.push(value.to_string());
+                    }
+                    Ok(())
+                }
+                _ => Err(format!("Unknown setting '{}'. {}", key, USAGE)),
+            };
+            if let Err(e) = parsed {
+                return SkillResult::error(e);
+            }
+        }
+        if let Some(stops) = stop_sequences {
+            params.stop_sequences = stops;
+        }
+
+        let params = params.clamped();
+        SkillResult::success(format!("Generation parameters: {}", params))
+            .with_data(json!({ GENERATION_PARAMS_KEY: params }))
+    }
+}
+
+impl Default for SettingsSkill {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Parse a setting value; `None` when clearing it back to the provider default
+fn parse<T: std::str::FromStr>(key: &str, value: &str, clear: bool) -> Result<Option<T>, String> {
+    if clear {
+        return Ok(None);
+    }
+    value
+        .parse()
+        .map(Some)
+        .map_err(|_| format!("Invalid value for {}: '{}'", key, value))
+}
+
+impl Skill for SettingsSkill {
+    fn info(&self) -> SkillInfo {
+        SkillInfo {
+            name: "settings".to_string(),
+            display_name: "Settings".to_string(),
+            description: "Adjust generation parameters for the current session".to_string(),
+            usage: USAGE.to_string(),
+            user_invocable: true,
+        }
+    }
+
+    fn execute(&self, ctx: SkillContext) -> BoxFuture<'_, SkillResult> {
+        Box::pin(async move {
+            let current = ctx
+                .data
+                .get(GENERATION_PARAMS_KEY)
+                .and_then(|v| serde_json::from_value(v.clone()).ok())
+                .unwrap_or_default();
+            let args = ctx.args.trim();
+            let (command, rest) = args.split_once(' ').unwrap_or((args, ""));
+            match command {
+                "model" => self.model(rest.trim(), current),
+                other => SkillResult::error(format!("Unknown settings command '{}'. {}", other, USAGE)),
+            }
+        })
+    }
+
+    fn prompt_template(&self) -> &str {
+        USAGE
+    }
+
+    fn runs_locally(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    fn ctx(args: &str, current: Option<GenerationParams>) -> SkillContext {
+        let mut data = HashMap::new();
+        if let Some(current) = current {
+            data.insert(GENERATION_PARAMS_KEY.to_string(), serde_json::to_value(current).unwrap());
+        }
+        SkillContext {
+            workspace: PathBuf::from("."),
+            args: args.to_string(),
+            data,
+        }
+    }
+
+    fn updated(result: &SkillResult) -> GenerationParams {
+        serde_json::from_value(result.data.as_ref().unwrap()[GENERATION_PARAMS_KEY].clone()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_updates_merge_with_current() {
+        let current = GenerationParams { top_p: Some(0.9), ..Default::default() };
+        let result = SettingsSkill::new()
+            .execute(ctx("model temperature=0.2 max_tokens=256 stop=### stop=END", Some(current)))
+            .await;
+
+        assert!(result.success);
+        let params = updated(&result);
+        assert_eq!(params.temperature, Some(0.2));
+        assert_eq!(params.max_tokens, Some(256));
+        assert_eq!(params.top_p, Some(0.9));
+        assert_eq!(params.stop_sequences, vec!["###", "END"]);
+    }
+
+    #[tokio::test]
+    async fn test_out_of_range_is_clamped() {
+        let result = SettingsSkill::new().execute(ctx("model temperature=5", None)).await;
+        assert!(result.success);
+        assert_eq!(updated(&result).temperature, Some(2.0));
+        assert!(result.response.contains("temperature=2"));
+    }
+
+    #[tokio::test]
+    async fn test_reset_and_clear() {
+        let current = GenerationParams {
+            temperature: Some(0.5),
+            stop_sequences: vec!["###".to_string()],
+            ..Default::default()
+        };
+        let result = SettingsSkill::new().execute(ctx("model stop=default", Some(current))).await;
+        assert_eq!(updated(&result).stop_sequences, Vec::<String>::new());
+        assert_eq!(updated(&result).temperature, Some(0.5));
+
+        let result = SettingsSkill::new().execute(ctx("model reset", None)).await;
+        assert!(updated(&result).is_default());
+    }
+
+    #[tokio::test]
+    async fn test_show_and_errors() {
+        let result = SettingsSkill::new().execute(ctx("model", None)).await;
+        assert!(result.success);
+        assert!(result.data.is_none());
+
+        let result = SettingsSkill::new().execute(ctx("model temperature=hot", None)).await;
+        assert!(!result.success);
+
+        let result = SettingsSkill::new().execute(ctx("theme dark", None)).await;
+        assert!(result.error.unwrap().contains(USAGE));
+    }
+}
@@ -0,0 +1,131 @@
+//! Built-in `/template` skill for the session templates
+//!
+//! Runs locally. Templates configure a session as it starts, so a running
+//! session can only list and show them; new sessions are started from one
+//! with `cowork new --template <name>` or the app's new session dialog.
+//!
+//! Usage:
+//! - `/template` or `/template list` - list the templates
+//! - `/template show <name>` - show a template's settings and any problems with them
+
+use crate::config::ConfigManager;
+use crate::recipes::builtin_tools_and_skills;
+use crate::skills::{BoxFuture, Skill, SkillContext, SkillInfo, SkillResult};
+use crate::templates::{find_template, list_templates};
+
+/// Usage text shown for invalid arguments
+const USAGE: &str = "Usage: /template [list] | /template show <name>";
+
+/// Skill that lists and shows session templates
+pub struct TemplateSkill;
+
+impl TemplateSkill {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for TemplateSkill {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Skill for TemplateSkill {
+    fn info(&self) -> SkillInfo {
+        SkillInfo {
+            name: "template".to_string(),
+            display_name: "Template".to_string(),
+            description: "List or show the session templates new sessions can start from".to_string(),
+            usage: USAGE.to_string(),
+            user_invocable: true,
+        }
+    }
+
+    fn execute(&self, ctx: SkillContext) -> BoxFuture<'_, SkillResult> {
+        Box::pin(async move {
+            let args = ctx.args.trim();
+            let (command, rest) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+            match command {
+                "" | "list" => list(&ctx),
+                "show" if !rest.trim().is_empty() => show(&ctx, rest.trim()),
+                _ => SkillResult::error(USAGE),
+            }
+        })
+    }
+
+    fn prompt_template(&self) -> &str {
+        USAGE
+    }
+
+    fn runs_locally(&self) -> bool {
+        true
+    }
+}
+
+fn list(ctx: &SkillContext) -> SkillResult {
+    let mut lines = vec!["Session templates:".to_string()];
+    for (name, template) in list_templates(&ctx.workspace) {
+        lines.push(match template {
+            Ok(template) if template.description.is_empty() => format!("  {}", name),
+            Ok(template) => format!("  {} - {}", name, template.description.lines().next().unwrap_or_default()),
+            Err(e) => format!("  {} (invalid: {})", name, e),
+        });
+    }
+    lines.push("Start a session from one with: cowork new --template <name> [args]".to_string());
+    SkillResult::success(lines.join("\n"))
+}
+
+fn show(ctx: &SkillContext, name: &str) -> SkillResult {
+    let template = match find_template(&ctx.workspace, name) {
+        Ok(template) => template,
+        Err(e) => return SkillResult::error(e.to_string()),
+    };
+    let config = ConfigManager::new().unwrap_or_default();
+    let (tools, _) = builtin_tools_and_skills(&ctx.workspace);
+    let problems = template.validate(config.config(), &tools);
+    let mut response = template.describe();
+    if !problems.is_empty() {
+        response.push_str(&format!("\n\nProblems:\n- {}", problems.join("\n- ")));
+    }
+    SkillResult::success(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::path::Path;
+
+    fn ctx(workspace: &Path, args: &str) -> SkillContext {
+        SkillContext {
+            workspace: workspace.to_path_buf(),
+            args: args.to_string(),
+            data: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_includes_builtins() {
+        let dir = tempfile::tempdir().unwrap();
+        let result = TemplateSkill::new().execute(ctx(dir.path(), "")).await;
+        assert!(result.success);
+        assert!(result.response.contains("  code-review - Review changes"), "{}", result.response);
+        assert!(result.response.contains("  research - "), "{}", result.response);
+    }
+
+    #[tokio::test]
+    async fn test_show_reports_problems() {
+        let dir = tempfile::tempdir().unwrap();
+        let templates = dir.path().join(crate::templates::TEMPLATES_DIR);
+        std::fs::create_dir_all(&templates).unwrap();
+        std::fs::write(templates.join("triage.toml"), "tool_profile = \"tiny\"\nmessage = \"Triage $0\"\n").unwrap();
+
+        let result = TemplateSkill::new().execute(ctx(dir.path(), "show triage")).await;
+        assert!(result.response.contains("Opening message:\nTriage $0"), "{}", result.response);
+        assert!(result.response.contains("Problems:\n- unknown tool profile tiny"), "{}", result.response);
+
+        let missing = TemplateSkill::new().execute(ctx(dir.path(), "show nope")).await;
+        assert!(!missing.success);
+    }
+}
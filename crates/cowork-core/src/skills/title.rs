@@ -0,0 +1,88 @@
+//! Built-in `/title` skill for naming the session
+//!
+//! Runs locally like `/pin`. Naming is a session input, so the skill only
+//! checks that a title was given and returns it in
+//! `SkillResult::data["session_title"]`; the frontend sends
+//! `SessionInput::SetTitle` to the session (see `session::title`). A title
+//! set this way is never replaced by a generated one.
+//!
+//! Usage:
+//! - `/title <text>` - name the current session
+
+use serde_json::json;
+
+use crate::skills::{BoxFuture, Skill, SkillContext, SkillInfo, SkillResult};
+
+/// Key for the session's new title in skill result data
+pub const SESSION_TITLE_KEY: &str = "session_title";
+
+/// Usage text shown for invalid arguments
+const USAGE: &str = "Usage: /title <text> - name this session in the list of saved sessions";
+
+/// Skill that names the session
+pub struct TitleSkill;
+
+impl TitleSkill {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for TitleSkill {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Skill for TitleSkill {
+    fn info(&self) -> SkillInfo {
+        SkillInfo {
+            name: "title".to_string(),
+            display_name: "Title".to_string(),
+            description: "Name the session, replacing its generated title".to_string(),
+            usage: USAGE.to_string(),
+            user_invocable: true,
+        }
+    }
+
+    fn execute(&self, ctx: SkillContext) -> BoxFuture<'_, SkillResult> {
+        Box::pin(async move {
+            let title = ctx.args.split_whitespace().collect::<Vec<_>>().join(" ");
+            if title.is_empty() {
+                return SkillResult::error(USAGE);
+            }
+            SkillResult::success(format!("Session renamed to \"{}\"", title))
+                .with_data(json!({ SESSION_TITLE_KEY: title }))
+        })
+    }
+
+    fn prompt_template(&self) -> &str {
+        USAGE
+    }
+
+    fn runs_locally(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn ctx(args: &str) -> SkillContext {
+        SkillContext {
+            workspace: std::env::temp_dir(),
+            args: args.to_string(),
+            data: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_title_args() {
+        let skill = TitleSkill::new();
+        let result = skill.execute(ctx("  Storage   choice ")).await;
+        assert_eq!(result.data.unwrap()[SESSION_TITLE_KEY], "Storage choice");
+        assert!(!skill.execute(ctx(" ")).await.success);
+    }
+}
@@ -0,0 +1,177 @@
+//! Opt-in metrics for latency and reliability monitoring
+//!
+//! Instrumented code reports counters and histograms through `counter` and
+//! `histogram`; they go to the process's `MetricsSink`, which does nothing
+//! unless `[telemetry]` is enabled and a sink was installed (see
+//! `install_from_config`). With the `prometheus` feature, `PrometheusSink`
+//! keeps the values and exposes them as a Prometheus textfile and,
+//! optionally, an HTTP endpoint.
+//!
+//! Metrics must never carry conversation content or file paths. Only the
+//! labels in `ALLOWED_LABELS` reach the sink, all of them names from a small
+//! set (provider, model, tool, ...); any other label is dropped.
+
+#[cfg(feature = "prometheus")]
+mod prometheus;
+
+use std::sync::{Arc, OnceLock};
+
+use crate::config::TelemetryConfig;
+
+#[cfg(feature = "prometheus")]
+pub use prometheus::{parse_exposition, PrometheusSink, Sample};
+
+/// Labels metrics may carry; any other is dropped before the sink sees it
+pub const ALLOWED_LABELS: &[&str] = &["provider", "model", "tool", "outcome", "kind", "state", "server", "decider"];
+
+/// Duration of an LLM request, by provider, model and outcome
+pub const LLM_CALL_SECONDS: &str = "cowork_llm_call_seconds";
+/// Tokens used, by provider, model and kind (input, output, cached)
+pub const LLM_TOKENS: &str = "cowork_llm_tokens_total";
+/// LLM requests sent again after a timeout, by provider and model
+pub const LLM_RETRIES: &str = "cowork_llm_retries_total";
+/// Duration of a tool call, by tool and outcome
+pub const TOOL_SECONDS: &str = "cowork_tool_seconds";
+/// Time a call waited for someone to approve or reject it, by tool, outcome and decider
+pub const APPROVAL_WAIT_SECONDS: &str = "cowork_approval_wait_seconds";
+/// Context compactions, by kind (auto, emergency)
+pub const COMPACTIONS: &str = "cowork_compactions_total";
+/// How long sessions ran, by provider
+pub const SESSION_SECONDS: &str = "cowork_session_seconds";
+/// Provider circuit breaker changes, by provider and the state entered
+pub const PROVIDER_CIRCUIT_TRANSITIONS: &str = "cowork_provider_circuit_transitions_total";
+/// MCP server starts, by server and outcome
+pub const MCP_SERVER_STARTS: &str = "cowork_mcp_server_starts_total";
+/// Duration of an MCP tool call, by server and outcome
+pub const MCP_CALL_SECONDS: &str = "cowork_mcp_call_seconds";
+
+/// Where metrics go
+///
+/// Label values are bounded names (a provider, a tool), never content.
+pub trait MetricsSink: Send + Sync {
+    /// Add `value` to a counter
+    fn record_counter(&self, name: &str, value: u64, labels: &[(&str, &str)]);
+
+    /// Record one observation in a histogram
+    fn record_histogram(&self, name: &str, value: f64, labels: &[(&str, &str)]);
+
+    /// Write out anything buffered (called when the process exits)
+    fn flush(&self) {}
+}
+
+/// Sink that drops everything, used while telemetry is off
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoopSink;
+
+impl MetricsSink for NoopSink {
+    fn record_counter(&self, _name: &str, _value: u64, _labels: &[(&str, &str)]) {}
+
+    fn record_histogram(&self, _name: &str, _value: f64, _labels: &[(&str, &str)]) {}
+}
+
+/// The process's sink, once installed
+fn installed() -> &'static OnceLock<Arc<dyn MetricsSink>> {
+    static SINK: OnceLock<Arc<dyn MetricsSink>> = OnceLock::new();
+    &SINK
+}
+
+/// Send metrics to `sink` from now on; false if one is already installed
+pub fn install(sink: Arc<dyn MetricsSink>) -> bool {
+    installed().set(sink).is_ok()
+}
+
+/// Keep only the labels in `ALLOWED_LABELS`
+pub fn allowed_labels<'a>(labels: &[(&'a str, &'a str)]) -> Vec<(&'a str, &'a str)> {
+    labels
+        .iter()
+        .filter(|(key, _)| {
+            let allowed = ALLOWED_LABELS.contains(key);
+            if !allowed {
+                tracing::warn!("Dropping metric label {} (not in the allowlist)", key);
+            }
+            allowed
+        })
+        .copied()
+        .collect()
+}
+
+/// Add `value` to a counter, if telemetry is on
+pub fn counter(name: &str, value: u64, labels: &[(&str, &str)]) {
+    if let Some(sink) = installed().get() {
+        sink.record_counter(name, value, &allowed_labels(labels));
+    }
+}
+
+/// Record an observation in a histogram, if telemetry is on
+pub fn histogram(name: &str, value: f64, labels: &[(&str, &str)]) {
+    if let Some(sink) = installed().get() {
+        sink.record_histogram(name, value, &allowed_labels(labels));
+    }
+}
+
+/// Write out the installed sink's buffered metrics
+pub fn flush() {
+    if let Some(sink) = installed().get() {
+        sink.flush();
+    }
+}
+
+/// Flushes the metrics when dropped, at the end of `main`
+#[must_use = "metrics are flushed when the guard is dropped"]
+pub struct TelemetryGuard;
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        flush();
+    }
+}
+
+/// Install the sink `[telemetry]` asks for; nothing is recorded while it's off
+///
+/// The HTTP endpoint, if configured, is served on the current tokio runtime.
+pub fn install_from_config(config: &TelemetryConfig) -> TelemetryGuard {
+    if !config.enabled {
+        return TelemetryGuard;
+    }
+    #[cfg(feature = "prometheus")]
+    {
+        let sink = Arc::new(PrometheusSink::new(Some(config.textfile_path())));
+        if let Some(listen) = &config.listen {
+            sink.clone().serve(listen.clone());
+        }
+        install(sink);
+    }
+    #[cfg(not(feature = "prometheus"))]
+    tracing::warn!("[telemetry] is enabled, but this build has no metrics exporter (feature `prometheus`)");
+    TelemetryGuard
+}
+
+/// Outcome label for a result
+pub fn outcome<T, E>(result: &Result<T, E>) -> &'static str {
+    if result.is_ok() { "ok" } else { "error" }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_labels_outside_the_allowlist_are_dropped() {
+        let labels = [
+            ("tool", "Write"),
+            ("file_path", "/home/me/secret.txt"),
+            ("content", "hello"),
+            ("model", "gpt-4o"),
+        ];
+        assert_eq!(allowed_labels(&labels), [("tool", "Write"), ("model", "gpt-4o")]);
+    }
+
+    #[test]
+    fn test_allowlist_names_no_content() {
+        for label in ALLOWED_LABELS {
+            for forbidden in ["path", "file", "content", "message", "prompt", "command", "argument", "query", "url"] {
+                assert!(!label.contains(forbidden), "{} may carry content", label);
+            }
+        }
+    }
+}
@@ -0,0 +1,259 @@
+//! Prometheus exposition of the metrics, as a textfile and over HTTP
+//!
+//! Values are kept as exposition series (`name{labels}` to value), so the
+//! textfile written by an earlier run is simply read back and added to:
+//! counters keep counting across runs of the CLI.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use super::MetricsSink;
+
+/// Upper bounds of the histogram buckets, in seconds: from a quick tool
+/// call to an approval that waited an hour
+const BUCKETS: &[f64] = &[
+    0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0, 3600.0,
+];
+
+/// Least time between two writes of the textfile
+const WRITE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// One line of an exposition: the series and its value
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+    /// Metric name with its labels, as in `name{label="value"}`
+    pub series: String,
+    pub value: f64,
+}
+
+/// Read the samples of a Prometheus text exposition, skipping comments
+pub fn parse_exposition(text: &str) -> Vec<Sample> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let (series, value) = line.rsplit_once(' ')?;
+            Some(Sample {
+                series: series.to_string(),
+                value: value.parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+#[derive(Default)]
+struct State {
+    series: BTreeMap<String, f64>,
+    /// Names of the metrics that are histograms (the rest are counters)
+    histograms: BTreeSet<String>,
+    last_write: Option<Instant>,
+    /// Changed since the textfile was last written
+    dirty: bool,
+}
+
+/// Keeps the metrics in memory and exposes them in Prometheus' text format
+pub struct PrometheusSink {
+    /// Textfile the metrics are written to (None = only kept in memory)
+    textfile: Option<PathBuf>,
+    state: Mutex<State>,
+}
+
+impl PrometheusSink {
+    /// Start from the metrics already in `textfile`, if it exists
+    pub fn new(textfile: Option<PathBuf>) -> Self {
+        let mut state = State::default();
+        if let Some(text) = textfile.as_ref().and_then(|path| std::fs::read_to_string(path).ok()) {
+            for line in text.lines() {
+                if let Some(name) = line.strip_prefix("# TYPE ").and_then(|rest| rest.strip_suffix(" histogram")) {
+                    state.histograms.insert(name.to_string());
+                }
+            }
+            state.series = parse_exposition(&text).into_iter().map(|s| (s.series, s.value)).collect();
+        }
+        Self {
+            textfile,
+            state: Mutex::new(state),
+        }
+    }
+
+    /// The metrics in Prometheus' text exposition format
+    pub fn render(&self) -> String {
+        let state = self.state.lock();
+        let mut families: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+        for (series, value) in &state.series {
+            let name = series.split('{').next().unwrap_or(series);
+            let family = ["_bucket", "_sum", "_count"]
+                .iter()
+                .find_map(|suffix| name.strip_suffix(suffix).filter(|base| state.histograms.contains(*base)))
+                .unwrap_or(name);
+            families.entry(family).or_default().push(format!("{} {}", series, value));
+        }
+        let mut text = String::new();
+        for (family, lines) in families {
+            let kind = if state.histograms.contains(family) { "histogram" } else { "counter" };
+            text.push_str(&format!("# TYPE {} {}\n", family, kind));
+            for line in lines {
+                text.push_str(&line);
+                text.push('\n');
+            }
+        }
+        text
+    }
+
+    /// Serve the metrics at `listen` (e.g. `127.0.0.1:9464`) on the current runtime
+    pub fn serve(self: Arc<Self>, listen: String) {
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            tracing::warn!("Not serving metrics on {}: no async runtime", listen);
+            return;
+        };
+        runtime.spawn(async move {
+            let listener = match tokio::net::TcpListener::bind(&listen).await {
+                Ok(listener) => listener,
+                Err(e) => {
+                    tracing::warn!("Not serving metrics on {}: {}", listen, e);
+                    return;
+                }
+            };
+            tracing::info!("Serving metrics on http://{}/metrics", listen);
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let sink = self.clone();
+                tokio::spawn(async move {
+                    // Whatever was asked for, the answer is the metrics
+                    let mut request = [0u8; 1024];
+                    let _ = stream.read(&mut request).await;
+                    let body = sink.render();
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    );
+                    let _ = stream.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+    }
+
+    fn add(&self, series: String, value: f64) -> parking_lot::MutexGuard<'_, State> {
+        let mut state = self.state.lock();
+        *state.series.entry(series).or_default() += value;
+        state.dirty = true;
+        state
+    }
+
+    /// Write the textfile, at most once per `WRITE_INTERVAL` unless `force`d
+    fn write(&self, mut state: parking_lot::MutexGuard<'_, State>, force: bool) {
+        let Some(path) = &self.textfile else {
+            return;
+        };
+        let due = state.last_write.is_none_or(|last| last.elapsed() >= WRITE_INTERVAL);
+        if !state.dirty || !(force || due) {
+            return;
+        }
+        state.last_write = Some(Instant::now());
+        state.dirty = false;
+        drop(state);
+        if let Err(e) = write_atomically(path, &self.render()) {
+            tracing::warn!("Failed to write metrics to {}: {}", path.display(), e);
+        }
+    }
+}
+
+impl MetricsSink for PrometheusSink {
+    fn record_counter(&self, name: &str, value: u64, labels: &[(&str, &str)]) {
+        let state = self.add(series(name, labels, None), value as f64);
+        self.write(state, false);
+    }
+
+    fn record_histogram(&self, name: &str, value: f64, labels: &[(&str, &str)]) {
+        self.state.lock().histograms.insert(name.to_string());
+        // Every bucket is written, empty ones as 0, so the series are complete
+        for bound in BUCKETS {
+            let hit = if value <= *bound { 1.0 } else { 0.0 };
+            drop(self.add(series(&format!("{}_bucket", name), labels, Some(&bound.to_string())), hit));
+        }
+        drop(self.add(series(&format!("{}_bucket", name), labels, Some("+Inf")), 1.0));
+        drop(self.add(series(&format!("{}_sum", name), labels, None), value));
+        let state = self.add(series(&format!("{}_count", name), labels, None), 1.0);
+        self.write(state, false);
+    }
+
+    fn flush(&self) {
+        self.write(self.state.lock(), true);
+    }
+}
+
+/// `name{label="value",...}`, labels sorted and `le` last
+fn series(name: &str, labels: &[(&str, &str)], le: Option<&str>) -> String {
+    let mut labels: Vec<(&str, &str)> = labels.to_vec();
+    labels.sort();
+    labels.extend(le.map(|le| ("le", le)));
+    if labels.is_empty() {
+        return name.to_string();
+    }
+    let labels: Vec<String> = labels
+        .iter()
+        .map(|(key, value)| {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n");
+            format!("{}=\"{}\"", key, value)
+        })
+        .collect();
+    format!("{}{{{}}}", name, labels.join(","))
+}
+
+/// Replace `path` so readers never see a half-written file
+fn write_atomically(path: &Path, text: &str) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut temp = path.as_os_str().to_os_string();
+    temp.push(".tmp");
+    std::fs::write(&temp, text)?;
+    std::fs::rename(&temp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_renders_counters_and_histograms() {
+        let sink = PrometheusSink::new(None);
+        sink.record_counter("cowork_llm_retries_total", 2, &[("provider", "openai")]);
+        sink.record_histogram("cowork_tool_seconds", 0.3, &[("tool", "Read"), ("outcome", "ok")]);
+
+        let text = sink.render();
+        assert!(text.contains("# TYPE cowork_llm_retries_total counter\ncowork_llm_retries_total{provider=\"openai\"} 2\n"));
+        assert!(text.contains("# TYPE cowork_tool_seconds histogram\n"));
+        assert!(text.contains("cowork_tool_seconds_bucket{outcome=\"ok\",tool=\"Read\",le=\"0.25\"} 0\n"));
+        assert!(text.contains("cowork_tool_seconds_bucket{outcome=\"ok\",tool=\"Read\",le=\"0.5\"} 1\n"));
+        assert!(text.contains("cowork_tool_seconds_bucket{outcome=\"ok\",tool=\"Read\",le=\"+Inf\"} 1\n"));
+        assert!(text.contains("cowork_tool_seconds_count{outcome=\"ok\",tool=\"Read\"} 1\n"));
+        assert!(!text.contains("le=\"0.25\"} 1"));
+    }
+
+    #[test]
+    fn test_textfile_accumulates_across_runs() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("metrics.prom");
+
+        let first = PrometheusSink::new(Some(path.clone()));
+        first.record_counter("cowork_compactions_total", 1, &[("kind", "auto")]);
+        first.record_histogram("cowork_session_seconds", 42.0, &[]);
+        first.flush();
+
+        let second = PrometheusSink::new(Some(path.clone()));
+        second.record_counter("cowork_compactions_total", 1, &[("kind", "auto")]);
+        second.flush();
+
+        let samples = parse_exposition(&std::fs::read_to_string(&path).unwrap());
+        let value = |series: &str| samples.iter().find(|s| s.series == series).map(|s| s.value);
+        assert_eq!(value("cowork_compactions_total{kind=\"auto\"}"), Some(2.0));
+        assert_eq!(value("cowork_session_seconds_sum"), Some(42.0));
+        assert!(second.render().contains("# TYPE cowork_session_seconds histogram"));
+    }
+}
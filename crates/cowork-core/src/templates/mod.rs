@@ -0,0 +1,480 @@
+//! Session templates: sessions pre-configured for a kind of task
+//!
+//! A template is kept in `.cowork/templates/<name>.toml`, or `.md` with its
+//! settings in YAML frontmatter and its opening message as the body. Every
+//! setting is optional; the ones given override the config's for the new
+//! session:
+//!
+//! ```toml
+//! description = "Triage a bug report"
+//! provider = "anthropic"
+//! model = "claude-opus-4-5-20251101"
+//! tool_profile = "coding"
+//! approval_level = "medium"
+//! system_prompt = "Reproduce the bug before you try to fix it."
+//! argument_hint = "<issue>"
+//! message = "Triage $ARGUMENTS"
+//! ```
+//!
+//! `system_prompt` is added to the session's system prompt rather than
+//! replacing it. The opening message is filled in with the template's
+//! arguments like a skill's prompt (`$ARGUMENTS`, `$0`, `$1`, ...) and sent
+//! as the session's first message. The built-in templates (`code-review`,
+//! `refactor` and `research`) can be replaced by a workspace template of the
+//! same name.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::approval::ApprovalLevel;
+use crate::config::{Config, ProviderConfig};
+use crate::error::{Error, Result};
+use crate::orchestration::tool_profile::PRESETS;
+use crate::provider::catalog;
+use crate::session::SessionConfig;
+use crate::tools::skill::substitute_arguments;
+
+/// Template directory, relative to the workspace
+pub const TEMPLATES_DIR: &str = ".cowork/templates";
+
+/// File extensions templates are read from, in order of precedence
+const TEMPLATE_EXTENSIONS: &[&str] = &["toml", "md"];
+
+/// Templates every workspace has, in the markdown format
+const BUILTIN_TEMPLATES: &[(&str, &str)] = &[
+    ("code-review", include_str!("builtin/code-review.md")),
+    ("refactor", include_str!("builtin/refactor.md")),
+    ("research", include_str!("builtin/research.md")),
+];
+
+/// Settings a session starts with, and the message it opens with
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionTemplate {
+    /// Name the template is used by (its file name without the extension)
+    #[serde(skip_deserializing)]
+    pub name: String,
+    /// File the template was loaded from (None for a built-in)
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    #[serde(default)]
+    pub description: String,
+    /// Provider the session runs on, as named in the config's `[providers]` or the catalog
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Tool profile: a preset or a profile from `[tools.profiles]`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_profile: Option<String>,
+    /// Approval threshold: none, low, medium, high or critical
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approval_level: Option<String>,
+    /// Added to the end of the session's system prompt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+    /// What the arguments are, for usage lines (e.g. `<issue>`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub argument_hint: Option<String>,
+    /// Opening message, filled in with the arguments
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl SessionTemplate {
+    /// Parse a template file's contents; `path` decides the format and the name
+    pub fn parse(path: &Path, content: &str) -> Result<Self> {
+        let invalid = |e: &dyn std::fmt::Display| {
+            Error::Config(format!("{} is not a valid session template: {}", path.display(), e))
+        };
+        let is_markdown = path.extension().is_some_and(|ext| ext == "md");
+        let mut template: SessionTemplate = if is_markdown {
+            let doc = crate::prompt::parse_frontmatter(content).map_err(|e| invalid(&e))?;
+            let mut template: SessionTemplate =
+                serde_json::from_value(Value::Object(doc.metadata.into_iter().collect())).map_err(|e| invalid(&e))?;
+            if template.message.is_none() && !doc.content.trim().is_empty() {
+                template.message = Some(doc.content.trim().to_string());
+            }
+            template
+        } else {
+            toml::from_str(content).map_err(|e| invalid(&e))?
+        };
+        template.name = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        template.path = Some(path.to_path_buf());
+        Ok(template)
+    }
+
+    /// Load a template file
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Self::parse(path, &content)
+    }
+
+    /// The built-in template called `name`, if there is one
+    pub fn builtin(name: &str) -> Option<Self> {
+        let (name, content) = BUILTIN_TEMPLATES.iter().find(|(builtin, _)| *builtin == name)?;
+        let mut template = Self::parse(Path::new(&format!("{}.md", name)), content).expect("built-in templates are valid");
+        template.path = None;
+        Some(template)
+    }
+
+    /// The approval threshold, if the template sets one
+    pub fn approval_level(&self) -> Result<Option<ApprovalLevel>> {
+        self.approval_level
+            .as_deref()
+            .map(|level| level.parse().map_err(Error::Config))
+            .transpose()
+    }
+
+    /// Problems with the settings: an unknown provider, a model the
+    /// provider isn't known to have, an unknown tool profile or one that
+    /// includes unknown tools, and an invalid approval level
+    ///
+    /// `tools` are the names of the tools sessions can have. A model is
+    /// known if the catalog lists it for the provider or the config names it
+    /// as the provider's model or one of its tiers.
+    pub fn validate(&self, config: &Config, tools: &[String]) -> Vec<String> {
+        let mut problems = Vec::new();
+        let provider = self.provider.as_deref().unwrap_or(&config.default_provider);
+        if self.provider.is_some() && config.get_provider(provider).is_none() && catalog::get(provider).is_none() {
+            problems.push(format!("unknown provider {}", provider));
+        } else if let Some(model) = &self.model {
+            let known = known_models(config, provider);
+            if !known.is_empty() && !known.contains(model) {
+                problems.push(format!(
+                    "provider {} has no model {} (known: {})",
+                    provider,
+                    model,
+                    known.join(", ")
+                ));
+            }
+        }
+
+        if let Some(name) = &self.tool_profile {
+            match config.tools.resolve(name) {
+                Err(_) => problems.push(format!(
+                    "unknown tool profile {} (available: {})",
+                    name,
+                    config.tools.profile_names().join(", ")
+                )),
+                // The presets only name built-in tools
+                Ok(_) if PRESETS.contains(&name.as_str()) && !config.tools.profiles.contains_key(name) => {}
+                Ok(profile) => {
+                    for tool in &profile.include {
+                        if !tool.contains('*') && !tool.starts_with("mcp__") && !tools.contains(tool) {
+                            problems.push(format!("tool profile {} includes unknown tool {}", name, tool));
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Err(e) = self.approval_level() {
+            problems.push(e.to_string());
+        }
+        problems
+    }
+
+    /// Fail with the template's problems, if it has any
+    pub fn check(&self, config: &Config, tools: &[String]) -> Result<()> {
+        let problems = self.validate(config, tools);
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Config(format!(
+                "Session template {} is not valid: {}",
+                self.name,
+                problems.join("; ")
+            )))
+        }
+    }
+
+    /// The opening message with `args` filled in (None if the template has
+    /// none, or it comes out empty)
+    pub fn opening_message(&self, args: &str) -> Option<String> {
+        let message = substitute_arguments(self.message.as_deref()?, args.trim());
+        let message = message.trim();
+        (!message.is_empty()).then(|| message.to_string())
+    }
+
+    /// Apply the template's settings to a session's config; settings the
+    /// template leaves out keep their values
+    ///
+    /// A provider is set up from its `[providers]` entry in `config` (API
+    /// key, base URL, model and tiers), or from the catalog without one.
+    /// The session records the template's name in its saved metadata.
+    pub fn apply(&self, session: &mut SessionConfig, config: &Config) -> Result<()> {
+        if let Some(name) = &self.provider {
+            let provider = config
+                .get_provider(name)
+                .cloned()
+                .unwrap_or_else(|| ProviderConfig::for_provider(name));
+            session.provider_id = provider.provider_type.clone();
+            session.model = Some(provider.model.clone()).filter(|model| !model.is_empty());
+            session.api_key = provider.get_api_key();
+            session.base_url = provider.base_url.clone();
+            session.model_tiers = provider.model_tiers.clone();
+            session.request_timeout = Some(provider.request_timeout(session.stream_mode));
+        }
+        if let Some(model) = &self.model {
+            session.model = Some(model.clone());
+        }
+        if let Some(profile) = &self.tool_profile {
+            session.tool_profile = config.tools.resolve(profile)?;
+        }
+        if let Some(level) = self.approval_level()? {
+            session.approval_config.set_level(level);
+        }
+        if let Some(addition) = self.system_prompt.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+            session.system_prompt = Some(match session.system_prompt.take() {
+                Some(prompt) => format!("{}\n\n{}", prompt, addition),
+                None => addition.to_string(),
+            });
+        }
+        session.template = Some(self.name.clone());
+        Ok(())
+    }
+
+    /// `cowork new --template <name> <hint>`
+    pub fn usage(&self) -> String {
+        match &self.argument_hint {
+            Some(hint) => format!("cowork new --template {} {}", self.name, hint),
+            None => format!("cowork new --template {}", self.name),
+        }
+    }
+
+    /// Where the template comes from and what it sets, one setting per line
+    pub fn describe(&self) -> String {
+        let source = match &self.path {
+            Some(path) => path.display().to_string(),
+            None => "built-in".to_string(),
+        };
+        let mut lines = vec![format!("Template {} ({})", self.name, source)];
+        if !self.description.is_empty() {
+            lines.push(self.description.clone());
+        }
+        lines.push(format!("Usage: {}", self.usage()));
+        let settings = [
+            ("provider", &self.provider),
+            ("model", &self.model),
+            ("tool profile", &self.tool_profile),
+            ("approval level", &self.approval_level),
+        ];
+        for (label, value) in settings {
+            if let Some(value) = value {
+                lines.push(format!("{}: {}", label, value));
+            }
+        }
+        if let Some(prompt) = &self.system_prompt {
+            lines.push(format!("System prompt addition:\n{}", prompt.trim_end()));
+        }
+        if let Some(message) = &self.message {
+            lines.push(format!("Opening message:\n{}", message.trim_end()));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Models a provider is known to have: its catalog models, and the model
+/// and tiers its `[providers]` entry names
+fn known_models(config: &Config, provider: &str) -> Vec<String> {
+    let configured = config.get_provider(provider);
+    let provider_type = configured.map_or(provider, |p| p.provider_type.as_str());
+    let mut models: Vec<String> = catalog::get(provider_type)
+        .map(|p| p.models.values().map(|m| m.id.clone()).collect())
+        .unwrap_or_default();
+    if let Some(p) = configured {
+        models.push(p.model.clone());
+        if let Some(tiers) = &p.model_tiers {
+            models.extend([tiers.fast.clone(), tiers.balanced.clone(), tiers.powerful.clone()]);
+        }
+    }
+    models.retain(|m| !m.is_empty());
+    models.sort();
+    models.dedup();
+    models
+}
+
+/// Where a workspace's templates are kept
+pub fn templates_dir(workspace: &Path) -> PathBuf {
+    workspace.join(TEMPLATES_DIR)
+}
+
+/// The workspace's templates and the built-ins it doesn't replace, by
+/// name, each loaded or the reason it couldn't be
+pub fn list_templates(workspace: &Path) -> Vec<(String, Result<SessionTemplate>)> {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(templates_dir(workspace))
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| p.extension().is_some_and(|ext| TEMPLATE_EXTENSIONS.iter().any(|t| ext == *t)))
+                .collect()
+        })
+        .unwrap_or_default();
+    // A name in several formats is read from the first extension only
+    paths.sort_by_key(|p| {
+        let rank = TEMPLATE_EXTENSIONS
+            .iter()
+            .position(|ext| p.extension().is_some_and(|e| e == *ext))
+            .unwrap_or(usize::MAX);
+        (p.file_stem().map(|s| s.to_os_string()), rank)
+    });
+    paths.dedup_by_key(|p| p.file_stem().map(|s| s.to_os_string()));
+
+    let mut templates: Vec<(String, Result<SessionTemplate>)> = paths
+        .into_iter()
+        .map(|path| {
+            let name = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+            (name, SessionTemplate::load(&path))
+        })
+        .collect();
+    for (name, _) in BUILTIN_TEMPLATES {
+        if !templates.iter().any(|(existing, _)| existing == name) {
+            templates.push((name.to_string(), Ok(SessionTemplate::builtin(name).expect("listed built-in"))));
+        }
+    }
+    templates.sort_by(|a, b| a.0.cmp(&b.0));
+    templates
+}
+
+/// The template called `name`: the workspace's, else a built-in
+pub fn find_template(workspace: &Path, name: &str) -> Result<SessionTemplate> {
+    let dir = templates_dir(workspace);
+    if let Some(path) = TEMPLATE_EXTENSIONS
+        .iter()
+        .map(|ext| dir.join(format!("{}.{}", name, ext)))
+        .find(|path| path.is_file())
+    {
+        return SessionTemplate::load(&path);
+    }
+    SessionTemplate::builtin(name).ok_or_else(|| {
+        Error::Config(format!("No session template named {} in {} or the built-ins", name, dir.display()))
+    })
+}
+
+/// The template called `name`, checked against `config` and the built-in
+/// tools so a session doesn't start with settings it can't use
+pub fn load_template(workspace: &Path, name: &str, config: &Config) -> Result<SessionTemplate> {
+    let template = find_template(workspace, name)?;
+    let (tools, _) = crate::recipes::builtin_tools_and_skills(workspace);
+    template.check(config, &tools)?;
+    Ok(template)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRIAGE: &str = r#"
+description = "Triage a bug"
+provider = "anthropic"
+model = "claude-opus-4-5-20251101"
+tool_profile = "coding"
+approval_level = "medium"
+system_prompt = "Reproduce the bug first."
+message = "Triage issue $0 in $1"
+"#;
+
+    fn triage() -> SessionTemplate {
+        SessionTemplate::parse(Path::new(".cowork/templates/triage.toml"), TRIAGE).unwrap()
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_toml() {
+        let template = triage();
+        assert_eq!(template.name, "triage");
+        assert_eq!(template.provider.as_deref(), Some("anthropic"));
+        assert_eq!(template.approval_level().unwrap(), Some(ApprovalLevel::Medium));
+        assert_eq!(template.opening_message("42 parser").as_deref(), Some("Triage issue 42 in parser"));
+    }
+
+    #[test]
+    fn test_parse_markdown_body_is_the_message() {
+        let content = "---\ndescription: Explain\ntool_profile: minimal\n---\n\nExplain $ARGUMENTS\n";
+        let template = SessionTemplate::parse(Path::new("explain.md"), content).unwrap();
+        assert_eq!(template.tool_profile.as_deref(), Some("minimal"));
+        assert_eq!(template.message.as_deref(), Some("Explain $ARGUMENTS"));
+        assert_eq!(template.opening_message("").as_deref(), Some("Explain"));
+    }
+
+    #[test]
+    fn test_builtins_are_valid() {
+        let config = Config::default();
+        for (name, _) in BUILTIN_TEMPLATES {
+            let template = SessionTemplate::builtin(name).unwrap();
+            assert!(template.path.is_none());
+            assert!(template.validate(&config, &[]).is_empty(), "{}", name);
+            assert!(template.opening_message("x").is_some(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        let config = Config::default();
+        assert!(triage().validate(&config, &[]).is_empty());
+
+        let content = "provider = \"anthropic\"\nmodel = \"gpt-5\"\ntool_profile = \"tiny\"\napproval_level = \"sometimes\"\n";
+        let template = SessionTemplate::parse(Path::new("bad.toml"), content).unwrap();
+        let problems = template.validate(&config, &[]);
+        assert_eq!(problems.len(), 3, "{:?}", problems);
+        assert!(problems[0].starts_with("provider anthropic has no model gpt-5"), "{}", problems[0]);
+        assert!(problems[1].starts_with("unknown tool profile tiny"), "{}", problems[1]);
+        assert!(problems[2].contains("Unknown approval level: sometimes"), "{}", problems[2]);
+
+        let template = SessionTemplate::parse(Path::new("x.toml"), "provider = \"nope\"\nmodel = \"m\"\n").unwrap();
+        assert_eq!(template.validate(&config, &[]), vec!["unknown provider nope"]);
+    }
+
+    #[test]
+    fn test_validate_custom_profile_tools() {
+        let mut config = Config::default();
+        config.tools.profiles.insert(
+            "review".to_string(),
+            crate::config::CustomToolProfile {
+                include: names(&["Read", "Gerp", "mcp__github__*"]),
+                exclude: Vec::new(),
+            },
+        );
+        let template = SessionTemplate::parse(Path::new("r.toml"), "tool_profile = \"review\"\n").unwrap();
+        assert_eq!(
+            template.validate(&config, &names(&["Read", "Grep"])),
+            vec!["tool profile review includes unknown tool Gerp"]
+        );
+    }
+
+    #[test]
+    fn test_apply() {
+        let config = Config::default();
+        let mut session = SessionConfig::new("/tmp").with_system_prompt("Base prompt");
+        triage().apply(&mut session, &config).unwrap();
+        assert_eq!(session.provider_id, "anthropic");
+        assert_eq!(session.model.as_deref(), Some("claude-opus-4-5-20251101"));
+        assert_eq!(session.tool_profile.name, "coding");
+        assert_eq!(session.approval_config.level(), ApprovalLevel::Medium);
+        assert_eq!(session.system_prompt.as_deref(), Some("Base prompt\n\nReproduce the bug first."));
+        assert_eq!(session.template.as_deref(), Some("triage"));
+    }
+
+    #[test]
+    fn test_workspace_template_replaces_builtin() {
+        let dir = tempfile::tempdir().unwrap();
+        let templates = templates_dir(dir.path());
+        std::fs::create_dir_all(&templates).unwrap();
+        std::fs::write(templates.join("research.toml"), "description = \"Ours\"\n").unwrap();
+        std::fs::write(templates.join("broken.toml"), "model = [\n").unwrap();
+
+        let listed = list_templates(dir.path());
+        let listed_names: Vec<&str> = listed.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(listed_names, vec!["broken", "code-review", "refactor", "research"]);
+        assert!(listed[0].1.is_err());
+
+        assert_eq!(find_template(dir.path(), "research").unwrap().description, "Ours");
+        assert!(find_template(dir.path(), "refactor").unwrap().path.is_none());
+        assert!(find_template(dir.path(), "missing").is_err());
+    }
+}
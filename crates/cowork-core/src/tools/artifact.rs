@@ -0,0 +1,253 @@
+//! Typed artifacts attached to tool output
+//!
+//! The text a tool returns is what the LLM sees; artifacts are the same
+//! result in a form a frontend can render (an image inline, a table, a link
+//! to a created file). They travel in `SessionOutput::ToolResult` and never
+//! reach the model.
+//!
+//! Artifacts are kept small: `compact` caps table rows and diff length, and
+//! moves images over `MAX_EMBEDDED_BYTES` to a file so only their path is sent.
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Largest image embedded in an artifact, in bytes (decoded)
+pub const MAX_EMBEDDED_BYTES: usize = 64 * 1024;
+
+/// Most table rows kept in an artifact
+pub const MAX_TABLE_ROWS: usize = 200;
+
+/// Longest unified diff kept in an artifact, in bytes
+pub const MAX_DIFF_BYTES: usize = 64 * 1024;
+
+/// Where an artifact's binary content is
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlobRef {
+    /// A file on disk
+    Path(PathBuf),
+    /// Base64-encoded content (at most `MAX_EMBEDDED_BYTES` once compacted)
+    Base64(String),
+}
+
+/// A structured piece of a tool result for frontends to render
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Artifact {
+    /// A file the tool created or changed
+    File {
+        path: PathBuf,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        mime: Option<String>,
+    },
+    /// A PNG image, e.g. a screenshot
+    ImagePng { data: BlobRef },
+    /// Rows of values under named columns
+    Table {
+        columns: Vec<String>,
+        rows: Vec<Vec<String>>,
+        /// Rows dropped by `compact`
+        #[serde(default, skip_serializing_if = "is_zero")]
+        omitted_rows: usize,
+    },
+    /// A web page
+    Url {
+        href: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        title: Option<String>,
+    },
+    /// A change in unified diff format
+    Diff { unified: String },
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+impl Artifact {
+    /// A file, with its MIME type if known
+    pub fn file(path: impl Into<PathBuf>, mime: Option<String>) -> Self {
+        Self::File { path: path.into(), mime }
+    }
+
+    /// A table
+    pub fn table(columns: Vec<String>, rows: Vec<Vec<String>>) -> Self {
+        Self::Table { columns, rows, omitted_rows: 0 }
+    }
+
+    /// A web page, with its title if known
+    pub fn url(href: impl Into<String>, title: Option<String>) -> Self {
+        Self::Url { href: href.into(), title }
+    }
+
+    /// A PNG given as base64 data
+    pub fn png_base64(data: impl Into<String>) -> Self {
+        Self::ImagePng { data: BlobRef::Base64(data.into()) }
+    }
+
+    /// A PNG file
+    pub fn png_file(path: impl Into<PathBuf>) -> Self {
+        Self::ImagePng { data: BlobRef::Path(path.into()) }
+    }
+
+    /// A unified diff
+    pub fn diff(unified: impl Into<String>) -> Self {
+        Self::Diff { unified: unified.into() }
+    }
+
+    /// Bring the artifact within the size limits
+    ///
+    /// Large images are written to the temp directory and referenced by
+    /// path; `None` if that fails, since the image can't be sent either way.
+    pub fn compact(self) -> Option<Self> {
+        match self {
+            Self::ImagePng { data: BlobRef::Base64(data) } if data.len() / 4 * 3 > MAX_EMBEDDED_BYTES => {
+                match spill_png(&data) {
+                    Ok(path) => Some(Self::png_file(path)),
+                    Err(e) => {
+                        tracing::warn!("Dropping image artifact: {}", e);
+                        None
+                    }
+                }
+            }
+            Self::Table { columns, mut rows, omitted_rows } if rows.len() > MAX_TABLE_ROWS => {
+                let dropped = rows.len() - MAX_TABLE_ROWS;
+                rows.truncate(MAX_TABLE_ROWS);
+                Some(Self::Table { columns, rows, omitted_rows: omitted_rows + dropped })
+            }
+            Self::Diff { mut unified } if unified.len() > MAX_DIFF_BYTES => {
+                let mut end = MAX_DIFF_BYTES;
+                while !unified.is_char_boundary(end) {
+                    end -= 1;
+                }
+                unified.truncate(end);
+                unified.push_str("\n... (diff truncated)");
+                Some(Self::Diff { unified })
+            }
+            artifact => Some(artifact),
+        }
+    }
+
+    /// Plain-text rendering for terminal frontends (tables as aligned columns)
+    pub fn render_text(&self) -> Vec<String> {
+        match self {
+            Self::File { path, .. } => vec![format!("file: {}", path.display())],
+            Self::ImagePng { data: BlobRef::Path(path) } => vec![format!("image: {}", path.display())],
+            Self::ImagePng { data: BlobRef::Base64(data) } => {
+                vec![format!("image: {} bytes (inline)", data.len() / 4 * 3)]
+            }
+            Self::Url { href, title } => match title {
+                Some(title) => vec![format!("{} <{}>", title, href)],
+                None => vec![href.clone()],
+            },
+            Self::Diff { unified } => unified.lines().map(String::from).collect(),
+            Self::Table { columns, rows, omitted_rows } => {
+                let mut widths: Vec<usize> = columns.iter().map(|c| c.chars().count()).collect();
+                for row in rows {
+                    for (i, cell) in row.iter().enumerate() {
+                        if let Some(w) = widths.get_mut(i) {
+                            *w = (*w).max(cell.chars().count());
+                        }
+                    }
+                }
+                let line = |cells: &[String]| {
+                    cells
+                        .iter()
+                        .zip(&widths)
+                        .map(|(cell, w)| format!("{:<w$}", cell, w = *w))
+                        .collect::<Vec<_>>()
+                        .join("  ")
+                        .trim_end()
+                        .to_string()
+                };
+                let mut lines = vec![line(columns)];
+                lines.extend(rows.iter().map(|row| line(row)));
+                if *omitted_rows > 0 {
+                    lines.push(format!("... {} more rows", omitted_rows));
+                }
+                lines
+            }
+        }
+    }
+}
+
+/// Write a base64 PNG to the temp directory, named by content hash
+fn spill_png(data: &str) -> Result<PathBuf, String> {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+
+    let bytes = STANDARD.decode(data.trim()).map_err(|e| format!("invalid base64: {}", e))?;
+    let dir = std::env::temp_dir().join("cowork-artifacts");
+    let path = dir.join(format!("{:x}.png", Sha256::digest(&bytes)));
+    if !path.exists() {
+        std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+        std::fs::write(&path, &bytes).map_err(|e| e.to_string())?;
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use serde_json::json;
+
+    #[test]
+    fn test_artifact_serde_round_trip() {
+        let artifacts = vec![
+            Artifact::file("src/main.rs", Some("text/x-rust".to_string())),
+            Artifact::png_base64("iVBORw0KGgo="),
+            Artifact::png_file("/tmp/shot.png"),
+            Artifact::table(vec!["file".into(), "line".into()], vec![vec!["a.rs".into(), "3".into()]]),
+            Artifact::url("https://example.com", None),
+            Artifact::diff("-old\n+new"),
+        ];
+        for artifact in artifacts {
+            let json = serde_json::to_string(&artifact).unwrap();
+            assert_eq!(serde_json::from_str::<Artifact>(&json).unwrap(), artifact);
+        }
+
+        // Compact form: optional and zero fields are left out
+        assert_eq!(
+            serde_json::to_value(Artifact::url("https://example.com", None)).unwrap(),
+            json!({"type": "url", "href": "https://example.com"})
+        );
+        assert_eq!(
+            serde_json::to_value(Artifact::png_file("/tmp/a.png")).unwrap(),
+            json!({"type": "image_png", "data": {"path": "/tmp/a.png"}})
+        );
+    }
+
+    #[test]
+    fn test_compact_limits() {
+        let small = Artifact::png_base64(STANDARD.encode([0u8; 16]));
+        assert_eq!(small.clone().compact(), Some(small));
+
+        let large = Artifact::png_base64(STANDARD.encode(vec![7u8; MAX_EMBEDDED_BYTES + 1]));
+        let Some(Artifact::ImagePng { data: BlobRef::Path(path) }) = large.compact() else {
+            panic!("large image should be moved to a file");
+        };
+        assert_eq!(std::fs::read(&path).unwrap().len(), MAX_EMBEDDED_BYTES + 1);
+
+        let rows = vec![vec!["x".to_string()]; MAX_TABLE_ROWS + 5];
+        let Some(Artifact::Table { rows, omitted_rows, .. }) = Artifact::table(vec!["c".into()], rows).compact() else {
+            panic!("table should stay a table");
+        };
+        assert_eq!((rows.len(), omitted_rows), (MAX_TABLE_ROWS, 5));
+
+        let Some(Artifact::Diff { unified }) = Artifact::diff("é".repeat(MAX_DIFF_BYTES)).compact() else {
+            panic!("diff should stay a diff");
+        };
+        assert!(unified.len() < MAX_DIFF_BYTES + 32 && unified.ends_with("(diff truncated)"));
+    }
+
+    #[test]
+    fn test_render_table() {
+        let table = Artifact::table(
+            vec!["file".into(), "line".into()],
+            vec![vec!["src/lib.rs".into(), "12".into()], vec!["a.rs".into(), "3".into()]],
+        );
+        assert_eq!(table.render_text(), vec!["file        line", "src/lib.rs  12", "a.rs        3"]);
+    }
+}
@@ -0,0 +1,158 @@
+//! Which description each tool offers the model
+//!
+//! Tool definitions go out with every request, so their descriptions are a
+//! steady cost. A workspace can replace a tool's description with
+//! `.cowork/tool-descriptions/<ToolName>.md` (template variables filled in,
+//! see `TemplateVars`), and `[tools] compact_descriptions` has built-in
+//! tools offer their short description (`Tool::compact_description`)
+//! instead of the full one. An override wins over both.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use serde_json::Value;
+use tracing::warn;
+
+use super::{BoxFuture, Tool, ToolExecutionContext, ToolOutput, ToolRegistry};
+use crate::error::ToolError;
+use crate::prompt::TemplateVars;
+
+/// Description override directory, relative to the workspace
+pub const TOOL_DESCRIPTIONS_DIR: &str = ".cowork/tool-descriptions";
+
+/// Description overrides in `workspace`, by tool name, with template
+/// variables substituted
+///
+/// Empty files are skipped, so an override can't leave a tool undescribed.
+pub fn load_overrides(workspace: &Path) -> HashMap<String, String> {
+    let Ok(entries) = std::fs::read_dir(workspace.join(TOOL_DESCRIPTIONS_DIR)) else {
+        return HashMap::new();
+    };
+    let mut overrides = HashMap::new();
+    let mut vars = None;
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if path.extension().is_none_or(|ext| ext != "md") {
+            continue;
+        }
+        let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) if !text.trim().is_empty() => text,
+            Ok(_) => continue,
+            Err(e) => {
+                warn!("Failed to read tool description {}: {}", path.display(), e);
+                continue;
+            }
+        };
+        let vars = vars.get_or_insert_with(|| TemplateVars::gather(workspace));
+        overrides.insert(name.to_string(), vars.substitute(text.trim()));
+    }
+    overrides
+}
+
+/// Have each tool in `registry` offer its override from `workspace`, else
+/// its compact description if `compact`
+pub fn describe_tools(registry: &mut ToolRegistry, workspace: &Path, compact: bool) {
+    let overrides = load_overrides(workspace);
+    if overrides.is_empty() && !compact {
+        return;
+    }
+    registry.map_tools(|tool| {
+        let description = match overrides.get(tool.name()) {
+            Some(text) => text.clone(),
+            None if compact && tool.compact_description() != tool.description() => {
+                tool.compact_description().to_string()
+            }
+            None => return tool,
+        };
+        Arc::new(DescribedTool { inner: tool, description })
+    });
+}
+
+/// Tool wrapper offering another description
+pub struct DescribedTool {
+    inner: Arc<dyn Tool>,
+    description: String,
+}
+
+impl Tool for DescribedTool {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn compact_description(&self) -> &str {
+        &self.description
+    }
+
+    fn parameters_schema(&self) -> Value {
+        self.inner.parameters_schema()
+    }
+
+    fn execute(&self, params: Value, ctx: ToolExecutionContext) -> BoxFuture<'_, Result<ToolOutput, ToolError>> {
+        self.inner.execute(params, ctx)
+    }
+
+    fn is_idempotent(&self) -> bool {
+        self.inner.is_idempotent()
+    }
+
+    fn supports_progress(&self) -> bool {
+        self.inner.supports_progress()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orchestration::ToolRegistryBuilder;
+    use crate::prompt::builtin::claude_code::tools;
+    use tempfile::TempDir;
+
+    fn description(registry: &ToolRegistry, name: &str) -> String {
+        registry.get(name).unwrap().to_definition().description.unwrap()
+    }
+
+    #[test]
+    fn test_every_builtin_tool_has_a_compact_description() {
+        let dir = TempDir::new().unwrap();
+        let registry = ToolRegistryBuilder::new(dir.path().to_path_buf()).build();
+        let compact = ToolRegistryBuilder::new(dir.path().to_path_buf())
+            .with_compact_descriptions(true)
+            .build();
+        let full: usize = registry.list().iter().filter_map(|d| d.description.as_ref()).map(String::len).sum();
+        let short: usize = compact.list().iter().filter_map(|d| d.description.as_ref()).map(String::len).sum();
+        for def in compact.list() {
+            let text = def.description.unwrap_or_default();
+            assert!(!text.trim().is_empty(), "{} has no compact description", def.name);
+            assert!(text.len() <= description(&registry, &def.name).len(), "{}", def.name);
+        }
+        assert!(short * 4 < full, "compact {} vs full {}", short, full);
+    }
+
+    #[test]
+    fn test_override_wins_over_both_descriptions() {
+        let dir = TempDir::new().unwrap();
+        let overrides = dir.path().join(TOOL_DESCRIPTIONS_DIR);
+        std::fs::create_dir_all(&overrides).unwrap();
+        std::fs::write(overrides.join("Bash.md"), "Run a command in ${WORKING_DIRECTORY}.\n").unwrap();
+        std::fs::write(overrides.join("Read.md"), "  \n").unwrap();
+        std::fs::write(overrides.join("notes.txt"), "not an override").unwrap();
+        let expected = format!("Run a command in {}.", dir.path().display());
+
+        for compact in [false, true] {
+            let registry = ToolRegistryBuilder::new(dir.path().to_path_buf())
+                .with_compact_descriptions(compact)
+                .build();
+            assert_eq!(description(&registry, "Bash"), expected);
+            // The empty override is ignored
+            let read = if compact { tools::READ_COMPACT } else { tools::READ };
+            assert_eq!(description(&registry, "Read"), read);
+        }
+    }
+}
@@ -0,0 +1,495 @@
+//! Batch edit tool - one find/replace across many files
+//!
+//! A mechanical refactor such as renaming a symbol would otherwise take one
+//! Edit call, and one approval, per file. BatchEdit takes a single
+//! replacement (literal, or a regex whose capture groups the replacement can
+//! use as `$1` or `${name}`) and the files to apply it to: an include glob
+//! and an optional exclude glob, matched against the path relative to the
+//! workspace, or against the file name for a pattern without a `/`. Files
+//! the workspace's `.gitignore` files exclude are never touched.
+//!
+//! With `dry_run`, it only reports the files that match, how often, and a
+//! few sample diffs. Otherwise it works out the same plan, asks for approval
+//! once with that summary, and then writes each file on its own (to a
+//! temporary file renamed over it), so a file that fails is reported and
+//! the others still go through. Before writing, it locks the planned files
+//! against other sessions and has them snapshotted for the turn
+//! (`ToolExecutionContext::prepare_writes`), as the agent loop does for
+//! Edit's file.
+//!
+//! Binary files and files over `MAX_BATCH_FILE_BYTES` are skipped with a
+//! note, and so are generated files (see `GeneratedFileGuard`) unless the
+//! call sets `allow_generated`, which makes the approval one only a person
+//! can give.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use globset::{GlobBuilder, GlobMatcher};
+use regex::Regex;
+use serde_json::{json, Value};
+
+use crate::context::index::workspace_files;
+use crate::error::ToolError;
+use crate::formatting::diff_lines;
+use crate::tools::{BoxFuture, Tool, ToolExecutionContext, ToolOutput};
+
+use super::generated::{allow_generated_schema, allows_generated, GeneratedFile, GeneratedFileGuard};
+
+/// Name of the tool that replaces text across many files
+pub const BATCH_EDIT_TOOL_NAME: &str = "BatchEdit";
+
+/// Larger files are skipped
+pub const MAX_BATCH_FILE_BYTES: u64 = 1024 * 1024;
+
+/// Files whose diffs are sampled in a preview
+const SAMPLE_FILES: usize = 3;
+
+/// Diff lines shown per sampled file
+const SAMPLE_LINES: usize = 12;
+
+/// Files (and skipped files) listed by name; the rest are only counted
+const LISTED_FILES: usize = 20;
+
+/// What to replace
+enum Pattern {
+    Literal(String),
+    Regex(Regex),
+}
+
+/// A glob over workspace-relative paths
+pub(super) struct PathGlob {
+    matcher: GlobMatcher,
+    /// Matched against the file name rather than the whole path
+    by_name: bool,
+}
+
+impl PathGlob {
+    pub(super) fn parse(pattern: &str, param: &str) -> Result<Self, ToolError> {
+        let matcher = GlobBuilder::new(pattern)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| ToolError::InvalidParams(format!("Invalid {} glob: {}", param, e)))?
+            .compile_matcher();
+        Ok(Self {
+            matcher,
+            by_name: !pattern.contains('/'),
+        })
+    }
+
+    pub(super) fn matches(&self, path: &str) -> bool {
+        if self.by_name {
+            self.matcher.is_match(path.rsplit('/').next().unwrap_or(path))
+        } else {
+            self.matcher.is_match(path)
+        }
+    }
+}
+
+/// The replacement and the files it applies to
+struct BatchSpec {
+    pattern: Pattern,
+    replacement: String,
+    include: PathGlob,
+    exclude: Option<PathGlob>,
+}
+
+impl BatchSpec {
+    fn from_params(params: &Value) -> Result<Self, ToolError> {
+        let find = params["find"]
+            .as_str()
+            .filter(|find| !find.is_empty())
+            .ok_or_else(|| ToolError::InvalidParams("find is required".into()))?;
+        let replacement = params["replace"]
+            .as_str()
+            .ok_or_else(|| ToolError::InvalidParams("replace is required".into()))?;
+        let pattern = if params["regex"].as_bool().unwrap_or(false) {
+            Pattern::Regex(
+                Regex::new(find).map_err(|e| ToolError::InvalidParams(format!("Invalid regex: {}", e)))?,
+            )
+        } else {
+            if find == replacement {
+                return Err(ToolError::InvalidParams("find and replace must be different".into()));
+            }
+            Pattern::Literal(find.to_string())
+        };
+        let include = PathGlob::parse(params["include"].as_str().unwrap_or("*"), "include")?;
+        let exclude = match params["exclude"].as_str().filter(|exclude| !exclude.is_empty()) {
+            Some(pattern) => Some(PathGlob::parse(pattern, "exclude")?),
+            None => None,
+        };
+        Ok(Self {
+            pattern,
+            replacement: replacement.to_string(),
+            include,
+            exclude,
+        })
+    }
+
+    fn selects(&self, path: &str) -> bool {
+        self.include.matches(path) && !self.exclude.as_ref().is_some_and(|exclude| exclude.matches(path))
+    }
+
+    /// Matches in `text`
+    fn count(&self, text: &str) -> usize {
+        match &self.pattern {
+            Pattern::Literal(find) => text.matches(find.as_str()).count(),
+            Pattern::Regex(regex) => regex.find_iter(text).count(),
+        }
+    }
+
+    /// `text` with every match replaced
+    fn apply(&self, text: &str) -> String {
+        match &self.pattern {
+            Pattern::Literal(find) => text.replace(find.as_str(), &self.replacement),
+            Pattern::Regex(regex) => regex.replace_all(text, self.replacement.as_str()).into_owned(),
+        }
+    }
+}
+
+/// A file the replacement changes
+struct PlannedFile {
+    /// Relative to the workspace, with `/` separators
+    path: String,
+    matches: usize,
+    before: String,
+    after: String,
+}
+
+/// What a batch edit would do
+#[derive(Default)]
+struct Plan {
+    files: Vec<PlannedFile>,
+    /// Selected files left alone, and why
+    skipped: Vec<(String, String)>,
+    /// Generated files among `files`, changed because the call allows it
+    generated: Vec<GeneratedFile>,
+}
+
+impl Plan {
+    /// Read every selected file and work out its replacement (blocking);
+    /// generated files are skipped unless `allow_generated`
+    fn make(workspace: &Path, spec: &BatchSpec, guard: &GeneratedFileGuard, allow_generated: bool) -> Self {
+        let mut plan = Self::default();
+        for (path, size) in workspace_files(workspace) {
+            if !spec.selects(&path) {
+                continue;
+            }
+            if size > MAX_BATCH_FILE_BYTES {
+                plan.skipped.push((path, format!("larger than {} KB", MAX_BATCH_FILE_BYTES / 1024)));
+                continue;
+            }
+            let bytes = match std::fs::read(workspace.join(&path)) {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    plan.skipped.push((path, e.to_string()));
+                    continue;
+                }
+            };
+            let before = match String::from_utf8(bytes) {
+                Ok(text) if !text.contains('\0') => text,
+                _ => {
+                    plan.skipped.push((path, "binary".to_string()));
+                    continue;
+                }
+            };
+            let matches = spec.count(&before);
+            if matches == 0 {
+                continue;
+            }
+            let after = spec.apply(&before);
+            if after == before {
+                continue;
+            }
+            if let Some(file) = guard.check(&path, Some(&before)) {
+                if !allow_generated {
+                    plan.skipped.push((path, format!("{}; {}", file.reason, file.action)));
+                    continue;
+                }
+                plan.generated.push(file);
+            }
+            plan.files.push(PlannedFile { path, matches, before, after });
+        }
+        plan
+    }
+
+    fn total_matches(&self) -> usize {
+        self.files.iter().map(|file| file.matches).sum()
+    }
+
+    /// Diffs of the first few files, a few lines each
+    fn samples(&self) -> Vec<String> {
+        self.files
+            .iter()
+            .take(SAMPLE_FILES)
+            .map(|file| {
+                let lines = diff_lines(&file.before, &file.after, 1);
+                let mut sample = vec![format!("--- {}", file.path)];
+                sample.extend(lines.iter().take(SAMPLE_LINES).map(|line| match line.line_type.as_str() {
+                    "added" => format!("+ {}", line.content),
+                    "removed" => format!("- {}", line.content),
+                    _ => format!("  {}", line.content),
+                }));
+                if lines.len() > SAMPLE_LINES {
+                    sample.push(format!("  ... ({} more lines)", lines.len() - SAMPLE_LINES));
+                }
+                sample.join("\n")
+            })
+            .collect()
+    }
+
+    /// What the batch would change, for the model and for the approval prompt
+    fn summary(&self) -> String {
+        let mut lines = vec![format!(
+            "Replace {} matches in {} files:",
+            self.total_matches(),
+            self.files.len()
+        )];
+        lines.extend(
+            self.files
+                .iter()
+                .take(LISTED_FILES)
+                .map(|file| format!("  {} ({})", file.path, file.matches)),
+        );
+        if self.files.len() > LISTED_FILES {
+            lines.push(format!("  ... and {} more files", self.files.len() - LISTED_FILES));
+        }
+        if !self.generated.is_empty() {
+            lines.push(format!("Including {} generated files:", self.generated.len()));
+            lines.extend(self.generated.iter().take(LISTED_FILES).map(|file| format!("  {}", file)));
+        }
+        if !self.skipped.is_empty() {
+            lines.push(format!("Skipped {} files:", self.skipped.len()));
+            lines.extend(
+                self.skipped
+                    .iter()
+                    .take(LISTED_FILES)
+                    .map(|(path, reason)| format!("  {} ({})", path, reason)),
+            );
+        }
+        let samples = self.samples();
+        if !samples.is_empty() {
+            lines.push(String::new());
+            lines.push(samples.join("\n\n"));
+        }
+        lines.join("\n")
+    }
+
+    fn skipped_json(&self) -> Vec<Value> {
+        self.skipped
+            .iter()
+            .take(LISTED_FILES)
+            .map(|(path, reason)| json!({ "path": path, "reason": reason }))
+            .collect()
+    }
+}
+
+/// Replace `path` with `content` through a temporary file next to it, so
+/// the file is never left half written
+fn write_atomic(path: &Path, content: &str) -> std::io::Result<()> {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".batch-edit.tmp");
+    let temp = PathBuf::from(temp);
+    std::fs::write(&temp, content)?;
+    // Keep the file's mode (e.g. an executable script)
+    if let Ok(metadata) = std::fs::metadata(path) {
+        let _ = std::fs::set_permissions(&temp, metadata.permissions());
+    }
+    std::fs::rename(&temp, path).inspect_err(|_| {
+        let _ = std::fs::remove_file(&temp);
+    })
+}
+
+/// Apply the replacement to one planned file as it is now (blocking),
+/// returning the matches replaced
+fn apply_file(workspace: &Path, spec: &BatchSpec, path: &str) -> Result<usize, String> {
+    let path = workspace.join(path);
+    let content = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    let matches = spec.count(&content);
+    if matches == 0 {
+        return Err("no longer matches".to_string());
+    }
+    write_atomic(&path, &spec.apply(&content)).map_err(|e| e.to_string())?;
+    Ok(matches)
+}
+
+/// Tool replacing text across the workspace's files in one call
+pub struct BatchEdit {
+    workspace: PathBuf,
+    /// Refuses lockfiles, generated code and build output
+    generated: Arc<GeneratedFileGuard>,
+}
+
+impl BatchEdit {
+    pub fn new(workspace: PathBuf) -> Self {
+        Self {
+            workspace,
+            generated: Arc::new(GeneratedFileGuard::new()),
+        }
+    }
+
+    /// Decide which files are generated with `guard` (the project's
+    /// patterns) rather than the built-in patterns alone
+    pub fn with_generated_guard(mut self, guard: Arc<GeneratedFileGuard>) -> Self {
+        self.generated = guard;
+        self
+    }
+}
+
+impl Tool for BatchEdit {
+    fn name(&self) -> &str {
+        BATCH_EDIT_TOOL_NAME
+    }
+
+    fn description(&self) -> &str {
+        r#"Replace text across many files at once, for mechanical refactors like renaming a symbol.
+
+Usage:
+- find is a literal string, or a regular expression with regex=true; replace may then use capture groups as $1 or ${name} (write $$ for a literal $)
+- include picks the files (default every file) and exclude leaves some out. A glob without "/" matches file names ("*.rs"), one with "/" matches paths relative to the workspace ("src/**/*.rs")
+- Files ignored by .gitignore, binary files and files over 1 MB are never changed
+- Lockfiles, generated code and build output are skipped; allow_generated=true includes them, but a person must approve it
+- Run with dry_run=true first: it lists the files and match counts with sample diffs, without changing anything
+- The real run asks for approval once for the whole batch, then writes each file on its own and reports which succeeded; a failed file doesn't stop the others
+- For a change to a single file, use Edit"#
+    }
+
+    fn compact_description(&self) -> &str {
+        "Replaces a literal string or regex (with $1 capture groups) across the files matching an include glob, minus an exclude glob. Use dry_run=true first to see the files, match counts and sample diffs; the real run is approved once for the whole batch."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "find": {
+                    "type": "string",
+                    "description": "The text to replace, or a regular expression with regex=true"
+                },
+                "replace": {
+                    "type": "string",
+                    "description": "The replacement; with regex=true it may use capture groups ($1, ${name})"
+                },
+                "regex": {
+                    "type": "boolean",
+                    "description": "Treat find as a regular expression (default false)",
+                    "default": false
+                },
+                "include": {
+                    "type": "string",
+                    "description": "Glob of the files to change, e.g. \"*.rs\" or \"src/**/*.ts\" (default every file)"
+                },
+                "exclude": {
+                    "type": "string",
+                    "description": "Glob of files to leave alone, e.g. \"tests/**\""
+                },
+                "dry_run": {
+                    "type": "boolean",
+                    "description": "Only report what would change (default false)",
+                    "default": false
+                },
+                "allow_generated": allow_generated_schema()
+            },
+            "required": ["find", "replace"]
+        })
+    }
+
+    fn execute(&self, params: Value, ctx: ToolExecutionContext) -> BoxFuture<'_, Result<ToolOutput, ToolError>> {
+        Box::pin(async move {
+            let spec = Arc::new(BatchSpec::from_params(&params)?);
+            let dry_run = params["dry_run"].as_bool().unwrap_or(false);
+            let allow_generated = allows_generated(&params);
+
+            let workspace = self.workspace.clone();
+            let plan = {
+                let spec = spec.clone();
+                let guard = self.generated.clone();
+                tokio::task::spawn_blocking(move || Plan::make(&workspace, &spec, &guard, allow_generated))
+                    .await
+                    .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?
+            };
+
+            if dry_run || plan.files.is_empty() {
+                let files: Vec<Value> = plan
+                    .files
+                    .iter()
+                    .map(|file| json!({ "path": file.path, "matches": file.matches }))
+                    .collect();
+                let mut result = json!({
+                    "dry_run": dry_run,
+                    "files": files,
+                    "total_matches": plan.total_matches(),
+                    "skipped": plan.skipped_json(),
+                    "skipped_count": plan.skipped.len(),
+                    "samples": plan.samples(),
+                    "summary": plan.summary(),
+                });
+                if !plan.generated.is_empty() {
+                    result["generated"] = json!(plan.generated);
+                }
+                return Ok(ToolOutput::success(result));
+            }
+
+            // One approval for the whole batch, showing what it changes; a
+            // person's if it includes generated files
+            if plan.generated.is_empty() {
+                ctx.request_approval(params.clone(), Some(plan.summary())).await
+            } else {
+                ctx.request_generated_approval(params.clone(), Some(plan.summary()), plan.generated.clone()).await
+            }
+            .map_err(ToolError::Rejected)?;
+
+            // Held until every file is written
+            let absolute: Vec<PathBuf> = plan.files.iter().map(|file| self.workspace.join(&file.path)).collect();
+            let _locks = ctx.prepare_writes(&absolute).await?;
+
+            let workspace = self.workspace.clone();
+            let paths: Vec<String> = plan.files.iter().map(|file| file.path.clone()).collect();
+            let results = tokio::task::spawn_blocking(move || {
+                paths
+                    .into_iter()
+                    .map(|path| {
+                        let result = apply_file(&workspace, &spec, &path);
+                        (path, result)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+
+            let changed = results.iter().filter(|(_, result)| result.is_ok()).count();
+            let replaced: usize = results.iter().filter_map(|(_, result)| result.as_ref().ok()).sum();
+            let failed: Vec<String> = results
+                .iter()
+                .filter_map(|(path, result)| result.as_ref().err().map(|e| format!("{}: {}", path, e)))
+                .collect();
+            let files: Vec<Value> = results
+                .into_iter()
+                .map(|(path, result)| match result {
+                    Ok(matches) => json!({ "path": path, "success": true, "matches": matches }),
+                    Err(error) => json!({ "path": path, "success": false, "error": error }),
+                })
+                .collect();
+            let mut summary = format!("Replaced {} matches in {} files", replaced, changed);
+            if !failed.is_empty() {
+                summary.push_str(&format!("; {} failed: {}", failed.len(), failed.join(", ")));
+            }
+            if !plan.skipped.is_empty() {
+                summary.push_str(&format!("; skipped {}", plan.skipped.len()));
+            }
+            let mut result = json!({
+                "dry_run": false,
+                "files": files,
+                "total_matches": replaced,
+                "failed": failed.len(),
+                "skipped": plan.skipped_json(),
+                "skipped_count": plan.skipped.len(),
+                "summary": summary,
+            });
+            if !plan.generated.is_empty() {
+                result["generated"] = json!(plan.generated);
+            }
+            Ok(ToolOutput::success(result))
+        })
+    }
+}
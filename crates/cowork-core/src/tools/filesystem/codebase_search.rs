@@ -0,0 +1,119 @@
+//! CodebaseSearch tool - symbol and file lookups from the workspace index
+//!
+//! Queries the index kept by `context::index` instead of walking and reading
+//! the tree. The index is loaded on first use, refreshed (only changed files
+//! are re-read) before every query, and saved back when it changed.
+
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+use crate::context::{IndexQuery, WorkspaceIndex};
+use crate::error::ToolError;
+use crate::tools::{BoxFuture, Tool, ToolExecutionContext, ToolOutput};
+
+/// Results returned when the call doesn't say
+const DEFAULT_LIMIT: usize = 50;
+
+/// Most results returned
+const MAX_LIMIT: usize = 200;
+
+/// Tool for looking up symbols and files in the workspace index
+pub struct CodebaseSearch {
+    workspace: PathBuf,
+    /// The index as of the last call (None until first use)
+    index: Arc<Mutex<Option<WorkspaceIndex>>>,
+}
+
+impl CodebaseSearch {
+    pub fn new(workspace: PathBuf) -> Self {
+        Self {
+            workspace,
+            index: Arc::new(Mutex::new(None)),
+        }
+    }
+}
+
+impl Tool for CodebaseSearch {
+    fn name(&self) -> &str {
+        "CodebaseSearch"
+    }
+
+    fn description(&self) -> &str {
+        crate::prompt::builtin::claude_code::tools::CODEBASE_SEARCH
+    }
+
+    fn compact_description(&self) -> &str {
+        crate::prompt::builtin::claude_code::tools::CODEBASE_SEARCH_COMPACT
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "query": {
+                    "type": "string",
+                    "description": "Substring of a symbol name to look up (case-insensitive)"
+                },
+                "path": {
+                    "type": "string",
+                    "description": "Only files whose path contains this (case-insensitive)"
+                },
+                "language": {
+                    "type": "string",
+                    "description": "Only files in this language, e.g. \"rust\", \"python\", \"typescript\""
+                },
+                "limit": {
+                    "type": "integer",
+                    "description": "Maximum number of results (default 50, at most 200)"
+                }
+            }
+        })
+    }
+
+    fn execute(&self, params: Value, _ctx: ToolExecutionContext) -> BoxFuture<'_, Result<ToolOutput, ToolError>> {
+        Box::pin(async move {
+            let field = |name: &str| params[name].as_str().map(str::trim).filter(|s| !s.is_empty()).map(str::to_string);
+            let query = IndexQuery {
+                symbol: field("query"),
+                path: field("path"),
+                language: field("language"),
+                limit: params["limit"].as_u64().map_or(DEFAULT_LIMIT, |l| l as usize).clamp(1, MAX_LIMIT),
+            };
+            if query.symbol.is_none() && query.path.is_none() && query.language.is_none() {
+                return Err(ToolError::InvalidParams("Give at least one of query, path or language".into()));
+            }
+
+            let workspace = self.workspace.clone();
+            let cache = self.index.clone();
+            let (hits, indexed_files, truncated) = tokio::task::spawn_blocking(move || {
+                let mut cache = cache.lock();
+                let index = cache.get_or_insert_with(|| WorkspaceIndex::load(&workspace).unwrap_or_default());
+                if index.refresh(&workspace).changed()
+                    && let Err(e) = index.save(&workspace)
+                {
+                    tracing::debug!("Failed to save workspace index: {}", e);
+                }
+                (index.search(&query), index.files.len(), index.truncated)
+            })
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(format!("Index update failed: {}", e)))?;
+
+            let mut output = json!({
+                "results": hits,
+                "count": hits.len(),
+                "indexed_files": indexed_files,
+            });
+            if truncated {
+                output["note"] = json!("The workspace is too large to index fully; use Glob or Grep for files not found here");
+            }
+            Ok(ToolOutput::success(output))
+        })
+    }
+
+    fn is_idempotent(&self) -> bool {
+        true
+    }
+}
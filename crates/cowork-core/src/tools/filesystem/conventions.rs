@@ -0,0 +1,801 @@
+//! Formatting conventions of files, and normalizing written text to them
+//!
+//! Write and Edit bring what the model writes in line with the conventions
+//! of the file (unless called with `raw: true`), so files keep their
+//! indentation and line endings instead of collecting whitespace-only
+//! diffs. Normalizing is conservative: it converts leading tabs and spaces
+//! into each other, and fixes line endings, trailing whitespace, the final
+//! newline and a required BOM. It never reflows code; `max_line_length` is
+//! only reported.
+//!
+//! Each setting comes from the first of these that has it:
+//! 1. `.editorconfig` files, from the file's directory up to one with
+//!    `root = true`; nearer files and later sections take precedence
+//! 2. For Rust files, the nearest `rustfmt.toml` or `.rustfmt.toml`
+//!    (`hard_tabs`, `tab_spaces`, `newline_style`, `max_width`)
+//! 3. The file's current content or, for a new file, up to
+//!    `MAX_SIBLINGS` files with the same extension next to it (indentation,
+//!    line endings and final newline, when they agree)
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use globset::GlobBuilder;
+use serde::Serialize;
+
+/// Siblings read to infer the conventions of a new file
+const MAX_SIBLINGS: usize = 5;
+
+/// Bytes of a file read to infer its conventions
+const MAX_INFER_BYTES: usize = 64 * 1024;
+
+/// Share of indented lines that must agree on tabs or spaces
+const INDENT_AGREEMENT: f64 = 0.8;
+
+/// Fewest indented lines an indentation is inferred from
+const MIN_INDENTED_LINES: usize = 3;
+
+/// Most numbers an .editorconfig `{n..m}` range is spelled out to
+const MAX_RANGE_VALUES: i64 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IndentStyle {
+    Tab,
+    Space,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+}
+
+/// How a file is formatted, as far as whitespace goes (None = no convention)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct FileConventions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub indent_style: Option<IndentStyle>,
+    /// Columns per indentation level (and per tab)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub indent_size: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_of_line: Option<LineEnding>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub insert_final_newline: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trim_trailing_whitespace: Option<bool>,
+    /// As in .editorconfig: `utf-8`, `utf-8-bom`, `latin1`, ...
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub charset: Option<String>,
+    /// A hint, reported but never enforced
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_line_length: Option<usize>,
+    /// Where the settings came from: `.editorconfig`, `rustfmt.toml`, `inferred`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<String>,
+}
+
+impl FileConventions {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Fill the settings this doesn't have from `other`
+    fn or(mut self, other: FileConventions) -> Self {
+        let before = self.clone();
+        self.indent_style = self.indent_style.or(other.indent_style);
+        self.indent_size = self.indent_size.or(other.indent_size);
+        self.end_of_line = self.end_of_line.or(other.end_of_line);
+        self.insert_final_newline = self.insert_final_newline.or(other.insert_final_newline);
+        self.trim_trailing_whitespace = self.trim_trailing_whitespace.or(other.trim_trailing_whitespace);
+        self.charset = self.charset.or(other.charset);
+        self.max_line_length = self.max_line_length.or(other.max_line_length);
+        if self != before {
+            self.sources.extend(other.sources);
+        }
+        self
+    }
+
+    /// A setting from an .editorconfig section (keys and values lowercase)
+    fn apply_editorconfig(&mut self, key: &str, value: &str) {
+        if value == "unset" {
+            match key {
+                "indent_style" => self.indent_style = None,
+                "indent_size" | "tab_width" => self.indent_size = None,
+                "end_of_line" => self.end_of_line = None,
+                "insert_final_newline" => self.insert_final_newline = None,
+                "trim_trailing_whitespace" => self.trim_trailing_whitespace = None,
+                "charset" => self.charset = None,
+                "max_line_length" => self.max_line_length = None,
+                _ => {}
+            }
+            return;
+        }
+        match key {
+            "indent_style" => {
+                self.indent_style = match value {
+                    "tab" => Some(IndentStyle::Tab),
+                    "space" => Some(IndentStyle::Space),
+                    _ => self.indent_style,
+                }
+            }
+            // `indent_size = tab` defers to tab_width
+            "indent_size" | "tab_width" => {
+                if let Ok(size) = value.parse::<usize>()
+                    && size > 0
+                {
+                    self.indent_size = Some(size);
+                }
+            }
+            "end_of_line" => {
+                self.end_of_line = match value {
+                    "lf" => Some(LineEnding::Lf),
+                    "crlf" => Some(LineEnding::Crlf),
+                    // `cr` files are left alone
+                    _ => None,
+                }
+            }
+            "insert_final_newline" => self.insert_final_newline = value.parse().ok(),
+            "trim_trailing_whitespace" => self.trim_trailing_whitespace = value.parse().ok(),
+            "charset" => self.charset = Some(value.to_string()),
+            "max_line_length" => self.max_line_length = value.parse().ok(),
+            _ => {}
+        }
+    }
+
+    /// The conventions in words, e.g. "4-space indentation, LF line endings"
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        match (self.indent_style, self.indent_size) {
+            (Some(IndentStyle::Tab), _) => parts.push("tab indentation".to_string()),
+            (Some(IndentStyle::Space), Some(size)) => parts.push(format!("{}-space indentation", size)),
+            (Some(IndentStyle::Space), None) => parts.push("space indentation".to_string()),
+            (None, _) => {}
+        }
+        match self.end_of_line {
+            Some(LineEnding::Lf) => parts.push("LF line endings".to_string()),
+            Some(LineEnding::Crlf) => parts.push("CRLF line endings".to_string()),
+            None => {}
+        }
+        match self.insert_final_newline {
+            Some(true) => parts.push("final newline".to_string()),
+            Some(false) => parts.push("no final newline required".to_string()),
+            None => {}
+        }
+        if self.trim_trailing_whitespace == Some(true) {
+            parts.push("no trailing whitespace".to_string());
+        }
+        if let Some(charset) = &self.charset {
+            parts.push(format!("charset {}", charset));
+        }
+        if let Some(max) = self.max_line_length {
+            parts.push(format!("lines up to {} characters", max));
+        }
+        parts.join(", ")
+    }
+
+    /// `fragment` (Edit's new_string) brought in line with the conventions,
+    /// with what was changed. Its first line is only reindented when it
+    /// starts a line in the file and its last only trimmed when it ends one;
+    /// the final newline, BOM and line endings are left to the caller.
+    pub fn normalize_fragment(&self, fragment: &str, at_line_start: bool, at_line_end: bool) -> (String, Vec<&'static str>) {
+        let lines: Vec<&str> = fragment.split('\n').collect();
+        let last = lines.len() - 1;
+        let mut indentation_changed = false;
+        let mut trailing_changed = false;
+        let mut normalized = Vec::with_capacity(lines.len());
+        for (i, line) in lines.into_iter().enumerate() {
+            let mut line = line.to_string();
+            if (i > 0 || at_line_start)
+                && let Some(reindented) = self.reindent(&line)
+            {
+                line = reindented;
+                indentation_changed = true;
+            }
+            if self.trim_trailing_whitespace == Some(true) && (i < last || at_line_end) {
+                let trimmed = line.trim_end_matches([' ', '\t']);
+                if trimmed.len() != line.len() {
+                    line.truncate(trimmed.len());
+                    trailing_changed = true;
+                }
+            }
+            normalized.push(line);
+        }
+        let mut changes = Vec::new();
+        if indentation_changed {
+            changes.push("indentation");
+        }
+        if trailing_changed {
+            changes.push("trailing whitespace");
+        }
+        (normalized.join("\n"), changes)
+    }
+
+    /// `content` brought in line with the conventions, with what was changed
+    /// ("indentation", "line endings", ...)
+    pub fn normalize(&self, content: &str) -> (String, Vec<&'static str>) {
+        let mut changes = Vec::new();
+        let had_bom = content.starts_with('\u{feff}');
+        let body = content.trim_start_matches('\u{feff}');
+        let crlf = body.matches("\r\n").count();
+        let lf = body.matches('\n').count() - crlf;
+        let text = body.replace("\r\n", "\n");
+
+        let mut indentation_changed = false;
+        let mut trailing_changed = false;
+        let mut lines: Vec<String> = Vec::new();
+        for line in text.split('\n') {
+            let mut line = line.to_string();
+            if let Some(reindented) = self.reindent(&line) {
+                line = reindented;
+                indentation_changed = true;
+            }
+            if self.trim_trailing_whitespace == Some(true) {
+                let trimmed = line.trim_end_matches([' ', '\t']);
+                if trimmed.len() != line.len() {
+                    line.truncate(trimmed.len());
+                    trailing_changed = true;
+                }
+            }
+            lines.push(line);
+        }
+        let mut text = lines.join("\n");
+        if indentation_changed {
+            changes.push("indentation");
+        }
+        if trailing_changed {
+            changes.push("trailing whitespace");
+        }
+
+        if self.insert_final_newline == Some(true) && !text.is_empty() && !text.ends_with('\n') {
+            text.push('\n');
+            changes.push("final newline");
+        }
+
+        // Without a convention, the content keeps its own (majority) line endings
+        let ending = self.end_of_line.unwrap_or(if crlf > lf { LineEnding::Crlf } else { LineEnding::Lf });
+        let consistent = match ending {
+            LineEnding::Lf => crlf == 0,
+            LineEnding::Crlf => lf == 0,
+        };
+        if ending == LineEnding::Crlf {
+            text = text.replace('\n', "\r\n");
+        }
+        if !consistent {
+            changes.push("line endings");
+        }
+
+        let wants_bom = self.charset.as_deref() == Some("utf-8-bom");
+        if wants_bom || had_bom {
+            text.insert(0, '\u{feff}');
+            if !had_bom {
+                changes.push("byte order mark");
+            }
+        }
+        (text, changes)
+    }
+
+    /// The line with its leading whitespace converted to the indent style,
+    /// or None when that changes nothing
+    fn reindent(&self, line: &str) -> Option<String> {
+        let size = self.indent_size?;
+        let indent_len = line.len() - line.trim_start_matches([' ', '\t']).len();
+        let (indent, rest) = line.split_at(indent_len);
+        // Blank lines are left to trim_trailing_whitespace
+        if indent.is_empty() || rest.is_empty() {
+            return None;
+        }
+        match self.indent_style? {
+            IndentStyle::Space if indent.contains('\t') => {
+                let mut spaces = String::new();
+                for c in indent.chars() {
+                    if c == '\t' {
+                        // To the next tab stop
+                        spaces.push_str(&" ".repeat(size - spaces.len() % size));
+                    } else {
+                        spaces.push(' ');
+                    }
+                }
+                Some(format!("{}{}", spaces, rest))
+            }
+            // Only whole levels of spaces become tabs; the rest is alignment
+            IndentStyle::Tab if indent.bytes().all(|b| b == b' ') && indent.len() >= size => {
+                let tabs = "\t".repeat(indent.len() / size);
+                Some(format!("{}{}{}", tabs, " ".repeat(indent.len() % size), rest))
+            }
+            _ => None,
+        }
+    }
+
+    /// How many lines of `content` are longer than `max_line_length`
+    pub fn long_lines(&self, content: &str) -> usize {
+        let Some(max) = self.max_line_length else {
+            return 0;
+        };
+        content.lines().filter(|line| line.chars().count() > max).count()
+    }
+}
+
+/// The conventions that apply to the file at `path` (which needn't exist)
+pub fn get_file_conventions(path: &Path) -> FileConventions {
+    let mut conventions = editorconfig_conventions(path);
+    if path.extension().is_some_and(|ext| ext == "rs") {
+        conventions = conventions.or(rustfmt_conventions(path));
+    }
+    let mut conventions = conventions.or(inferred_conventions(path));
+    // Make requires tabs, whatever the conventions around it say
+    if is_makefile(path) && conventions.indent_style == Some(IndentStyle::Space) {
+        conventions.indent_style = None;
+    }
+    // Without a size, indentation can't be converted
+    if conventions.indent_style.is_some() && conventions.indent_size.is_none() {
+        conventions.indent_size = Some(match conventions.indent_style {
+            Some(IndentStyle::Tab) => 4,
+            _ => return conventions,
+        });
+    }
+    conventions
+}
+
+/// What the workspace's own .editorconfig and rustfmt.toml ask for, one
+/// line per section, for the project overview (None when it has neither)
+pub fn describe_workspace_conventions(workspace: &Path) -> Option<String> {
+    let mut parts = Vec::new();
+    if let Ok(content) = std::fs::read_to_string(workspace.join(".editorconfig")) {
+        for (pattern, settings) in parse_editorconfig(&content).1 {
+            let mut conventions = FileConventions::default();
+            for (key, value) in &settings {
+                conventions.apply_editorconfig(key, value);
+            }
+            let described = conventions.describe();
+            if !described.is_empty() {
+                parts.push(format!(".editorconfig [{}]: {}", pattern, described));
+            }
+        }
+    }
+    for name in ["rustfmt.toml", ".rustfmt.toml"] {
+        if let Some(conventions) = read_rustfmt(&workspace.join(name)) {
+            let described = conventions.describe();
+            if !described.is_empty() {
+                parts.push(format!("{}: {}", name, described));
+            }
+            break;
+        }
+    }
+    (!parts.is_empty()).then(|| parts.join("; "))
+}
+
+fn is_makefile(path: &Path) -> bool {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    matches!(name, "Makefile" | "makefile" | "GNUmakefile") || name.ends_with(".mk")
+}
+
+/// An .editorconfig section's glob with its settings (keys and values lowercase)
+type Section = (String, Vec<(String, String)>);
+
+/// Sections of an .editorconfig file, and whether it is the root
+fn parse_editorconfig(content: &str) -> (bool, Vec<Section>) {
+    let mut root = false;
+    let mut sections: Vec<Section> = Vec::new();
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(pattern) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            sections.push((pattern.to_string(), Vec::new()));
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim().to_lowercase();
+        let value = value.trim().to_lowercase();
+        match sections.last_mut() {
+            Some((_, settings)) => settings.push((key, value)),
+            None if key == "root" => root = value == "true",
+            None => {}
+        }
+    }
+    (root, sections)
+}
+
+/// Whether an .editorconfig section's glob matches `relative` (the path
+/// from the .editorconfig's directory, with `/` separators)
+fn section_matches(pattern: &str, relative: &str) -> bool {
+    // A glob without a slash matches the file name at any depth
+    let pattern = match pattern.strip_prefix('/') {
+        Some(anchored) => anchored.to_string(),
+        None if pattern.contains('/') => pattern.to_string(),
+        None => format!("**/{}", pattern),
+    };
+    // In .editorconfig `**` matches across directories anywhere (`lib/**.js`),
+    // in globset only as a whole path component
+    let parts: Vec<&str> = pattern.split("**").collect();
+    let mut glob = parts[0].to_string();
+    for pair in parts.windows(2) {
+        let after_separator = pair[0].is_empty() || pair[0].ends_with('/');
+        let before_separator = pair[1].is_empty() || pair[1].starts_with('/');
+        glob.push_str(match (after_separator, before_separator) {
+            (true, true) => "**",
+            (true, false) => "**/*",
+            (false, true) => "*/**",
+            (false, false) => "*/**/*",
+        });
+        glob.push_str(pair[1]);
+    }
+    let Some(glob) = expand_ranges(&glob) else {
+        tracing::warn!("Ignoring .editorconfig section [{}]: a {{n..m}} range is too large", pattern);
+        return false;
+    };
+    match GlobBuilder::new(&glob).literal_separator(true).build() {
+        Ok(glob) => glob.compile_matcher().is_match(relative),
+        Err(e) => {
+            tracing::warn!("Ignoring .editorconfig section [{}]: {}", pattern, e);
+            false
+        }
+    }
+}
+
+/// Spell out the `{n..m}` numeric ranges of an .editorconfig glob, which
+/// globset lacks, as alternations (`{1..3}` becomes `{1,2,3}`); None when a
+/// range holds more than `MAX_RANGE_VALUES` numbers
+fn expand_ranges(glob: &str) -> Option<String> {
+    let mut expanded = String::with_capacity(glob.len());
+    let mut rest = glob;
+    while let Some(idx) = rest.find(['\\', '{']) {
+        expanded.push_str(&rest[..idx]);
+        rest = &rest[idx..];
+        if rest.starts_with('\\') {
+            // An escaped character, copied as it is
+            let len = rest[1..].chars().next().map_or(1, |c| 1 + c.len_utf8());
+            expanded.push_str(&rest[..len]);
+            rest = &rest[len..];
+            continue;
+        }
+        let range = rest[1..].split_once('}').and_then(|(inner, after)| {
+            let (start, end) = inner.split_once("..")?;
+            Some((start.parse::<i64>().ok()?, end.parse::<i64>().ok()?, after))
+        });
+        match range {
+            Some((start, end, after)) => {
+                let (low, high) = (start.min(end), start.max(end));
+                if high - low >= MAX_RANGE_VALUES {
+                    return None;
+                }
+                let values: Vec<String> = (low..=high).map(|n| n.to_string()).collect();
+                expanded.push_str(&format!("{{{}}}", values.join(",")));
+                rest = after;
+            }
+            None => {
+                expanded.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    expanded.push_str(rest);
+    Some(expanded)
+}
+
+fn editorconfig_conventions(path: &Path) -> FileConventions {
+    // Nearest first, up to the root one
+    let mut files = Vec::new();
+    for dir in path.ancestors().skip(1) {
+        let Ok(content) = std::fs::read_to_string(dir.join(".editorconfig")) else {
+            continue;
+        };
+        let (root, sections) = parse_editorconfig(&content);
+        files.push((dir, sections));
+        if root {
+            break;
+        }
+    }
+
+    let mut settings: HashMap<String, String> = HashMap::new();
+    for (dir, sections) in files.into_iter().rev() {
+        let Ok(relative) = path.strip_prefix(dir) else {
+            continue;
+        };
+        let relative = relative.to_string_lossy().replace('\\', "/");
+        for (pattern, section) in sections {
+            if section_matches(&pattern, &relative) {
+                settings.extend(section);
+            }
+        }
+    }
+    if settings.is_empty() {
+        return FileConventions::default();
+    }
+
+    let mut conventions = FileConventions::default();
+    // indent_size after tab_width, so an explicit size wins
+    let mut keys: Vec<&String> = settings.keys().collect();
+    keys.sort_by_key(|key| (key.as_str() == "indent_size", key.as_str()));
+    for key in keys {
+        conventions.apply_editorconfig(key, &settings[key]);
+    }
+    if settings.get("indent_size").map(String::as_str) == Some("tab") && !settings.contains_key("tab_width") {
+        conventions.indent_size = None;
+    }
+    if !conventions.is_empty() {
+        conventions.sources.push(".editorconfig".to_string());
+    }
+    conventions
+}
+
+fn rustfmt_conventions(path: &Path) -> FileConventions {
+    for dir in path.ancestors().skip(1) {
+        for name in ["rustfmt.toml", ".rustfmt.toml"] {
+            if let Some(conventions) = read_rustfmt(&dir.join(name)) {
+                return conventions;
+            }
+        }
+    }
+    FileConventions::default()
+}
+
+fn read_rustfmt(path: &Path) -> Option<FileConventions> {
+    let content = std::fs::read_to_string(path).ok()?;
+    let table: toml::Table = content.parse().ok()?;
+    let mut conventions = FileConventions::default();
+    if let Some(hard_tabs) = table.get("hard_tabs").and_then(|v| v.as_bool()) {
+        conventions.indent_style = Some(if hard_tabs { IndentStyle::Tab } else { IndentStyle::Space });
+    }
+    if let Some(size) = table.get("tab_spaces").and_then(|v| v.as_integer()) {
+        conventions.indent_size = usize::try_from(size).ok().filter(|size| *size > 0);
+    }
+    conventions.end_of_line = match table.get("newline_style").and_then(|v| v.as_str()) {
+        Some("Unix") => Some(LineEnding::Lf),
+        Some("Windows") => Some(LineEnding::Crlf),
+        _ => None,
+    };
+    if let Some(width) = table.get("max_width").and_then(|v| v.as_integer()) {
+        conventions.max_line_length = usize::try_from(width).ok();
+    }
+    conventions.sources.push(path.file_name()?.to_string_lossy().into_owned());
+    Some(conventions)
+}
+
+/// Whitespace habits counted over one or more files
+#[derive(Debug, Default)]
+struct Tally {
+    tab_lines: usize,
+    space_lines: usize,
+    /// How often each increase in space indentation occurs
+    steps: HashMap<usize, usize>,
+    crlf: usize,
+    lf: usize,
+    with_final_newline: usize,
+    without_final_newline: usize,
+}
+
+impl Tally {
+    fn add(&mut self, content: &str) {
+        self.crlf += content.matches("\r\n").count();
+        self.lf += content.matches('\n').count() - content.matches("\r\n").count();
+        if !content.is_empty() {
+            if content.ends_with('\n') {
+                self.with_final_newline += 1;
+            } else {
+                self.without_final_newline += 1;
+            }
+        }
+
+        let mut previous = 0;
+        for line in content.lines() {
+            let rest = line.trim_start_matches([' ', '\t']);
+            if rest.is_empty() {
+                continue;
+            }
+            let indent = &line[..line.len() - rest.len()];
+            if indent.starts_with('\t') {
+                self.tab_lines += 1;
+            } else if !indent.is_empty() && !indent.contains('\t') {
+                self.space_lines += 1;
+                if indent.len() > previous {
+                    *self.steps.entry(indent.len() - previous).or_default() += 1;
+                }
+            }
+            if !indent.contains('\t') {
+                previous = indent.len();
+            }
+        }
+    }
+
+    fn conventions(&self) -> FileConventions {
+        let mut conventions = FileConventions::default();
+        let indented = self.tab_lines + self.space_lines;
+        if indented >= MIN_INDENTED_LINES {
+            if self.tab_lines as f64 >= indented as f64 * INDENT_AGREEMENT {
+                conventions.indent_style = Some(IndentStyle::Tab);
+            } else if self.space_lines as f64 >= indented as f64 * INDENT_AGREEMENT {
+                // The most common step, preferring the smaller one on a tie
+                let step = self
+                    .steps
+                    .iter()
+                    .filter(|(step, _)| (2..=8).contains(*step))
+                    .max_by_key(|(step, count)| (**count, std::cmp::Reverse(**step)))
+                    .map(|(step, _)| *step);
+                if let Some(step) = step {
+                    conventions.indent_style = Some(IndentStyle::Space);
+                    conventions.indent_size = Some(step);
+                }
+            }
+        }
+        if self.crlf > 0 && self.lf == 0 {
+            conventions.end_of_line = Some(LineEnding::Crlf);
+        } else if self.lf > 0 && self.crlf == 0 {
+            conventions.end_of_line = Some(LineEnding::Lf);
+        }
+        if self.with_final_newline > 0 && self.without_final_newline == 0 {
+            conventions.insert_final_newline = Some(true);
+        }
+        if !conventions.is_empty() {
+            conventions.sources.push("inferred".to_string());
+        }
+        conventions
+    }
+}
+
+/// The conventions of the file's current content, or of its siblings with
+/// the same extension when it doesn't exist yet
+fn inferred_conventions(path: &Path) -> FileConventions {
+    let mut tally = Tally::default();
+    if path.exists() {
+        if let Some(content) = read_start(path) {
+            tally.add(&content);
+        }
+        return tally.conventions();
+    }
+
+    let (Some(dir), Some(extension)) = (path.parent(), path.extension()) else {
+        return FileConventions::default();
+    };
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return FileConventions::default();
+    };
+    let mut siblings: Vec<_> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|p| p.extension() == Some(extension) && p.is_file())
+        .collect();
+    siblings.sort();
+    for sibling in siblings.into_iter().take(MAX_SIBLINGS) {
+        if let Some(content) = read_start(&sibling) {
+            tally.add(&content);
+        }
+    }
+    tally.conventions()
+}
+
+/// The start of a text file (None for binary or unreadable files)
+fn read_start(path: &Path) -> Option<String> {
+    use std::io::Read;
+    let mut bytes = Vec::new();
+    std::fs::File::open(path).ok()?.take(MAX_INFER_BYTES as u64).read_to_end(&mut bytes).ok()?;
+    match String::from_utf8(bytes) {
+        Ok(text) => Some(text),
+        // Cut off inside a character
+        Err(e) if e.utf8_error().error_len().is_none() => {
+            let valid = e.utf8_error().valid_up_to();
+            let mut bytes = e.into_bytes();
+            bytes.truncate(valid);
+            String::from_utf8(bytes).ok()
+        }
+        Err(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(path: &Path, content: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_infers_tabs_and_spaces() {
+        let dir = tempfile::tempdir().unwrap();
+        let go = dir.path().join("main.go");
+        write(&go, "func main() {\n\tif x {\n\t\ty()\n\t}\n}\n");
+        let conventions = get_file_conventions(&go);
+        assert_eq!(conventions.indent_style, Some(IndentStyle::Tab));
+        assert_eq!(conventions.insert_final_newline, Some(true));
+
+        let py = dir.path().join("app.py");
+        write(&py, "def f():\n  if x:\n    y()\n  return 1\n\ndef g():\n  pass\n");
+        let conventions = get_file_conventions(&py);
+        assert_eq!(conventions.indent_style, Some(IndentStyle::Space));
+        assert_eq!(conventions.indent_size, Some(2));
+
+        // A new file takes after its siblings
+        let conventions = get_file_conventions(&dir.path().join("other.py"));
+        assert_eq!(conventions.indent_size, Some(2));
+        assert_eq!(conventions.sources, vec!["inferred"]);
+        let (normalized, changes) = conventions.normalize("def h():\n\tif y:\n\t\tz()");
+        assert_eq!(normalized, "def h():\n  if y:\n    z()\n");
+        assert_eq!(changes, vec!["indentation", "final newline"]);
+    }
+
+    #[test]
+    fn test_keeps_crlf() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.txt");
+        write(&path, "one\r\ntwo\r\n");
+        let conventions = get_file_conventions(&path);
+        assert_eq!(conventions.end_of_line, Some(LineEnding::Crlf));
+        let (normalized, changes) = conventions.normalize("one\ntwo\nthree\n");
+        assert_eq!(normalized, "one\r\ntwo\r\nthree\r\n");
+        assert_eq!(changes, vec!["line endings"]);
+
+        // Content already in CRLF is left as is, even without a convention
+        let (normalized, changes) = FileConventions::default().normalize("a\r\nb\r\n");
+        assert_eq!(normalized, "a\r\nb\r\n");
+        assert!(changes.is_empty());
+    }
+
+    #[test]
+    fn test_editorconfig_precedence() {
+        let dir = tempfile::tempdir().unwrap();
+        write(
+            &dir.path().join(".editorconfig"),
+            "root = true\n\n[*]\nindent_style = space\nindent_size = 4\ntrim_trailing_whitespace = true\n\n\
+             [*.md]\ntrim_trailing_whitespace = false\n\n[{Makefile,*.mk}]\nindent_style = tab\n\n\
+             [/lib/**.js]\nindent_size = 2\n",
+        );
+        // A nearer file overrides the root one
+        write(&dir.path().join("web/.editorconfig"), "[*.js]\nindent_size = 3\nend_of_line = crlf\n");
+        // Above the root: ignored
+        write(&dir.path().parent().unwrap().join(".editorconfig"), "");
+
+        let rs = get_file_conventions(&dir.path().join("src/main.rs"));
+        assert_eq!((rs.indent_style, rs.indent_size), (Some(IndentStyle::Space), Some(4)));
+        assert_eq!(rs.trim_trailing_whitespace, Some(true));
+        assert_eq!(rs.sources, vec![".editorconfig"]);
+
+        // Later sections override earlier ones
+        assert_eq!(get_file_conventions(&dir.path().join("docs/a.md")).trim_trailing_whitespace, Some(false));
+        assert_eq!(get_file_conventions(&dir.path().join("Makefile")).indent_style, Some(IndentStyle::Tab));
+        // A glob with a slash is anchored to the .editorconfig's directory
+        assert_eq!(get_file_conventions(&dir.path().join("lib/x/y.js")).indent_size, Some(2));
+        assert_eq!(get_file_conventions(&dir.path().join("src/lib/y.js")).indent_size, Some(4));
+        let js = get_file_conventions(&dir.path().join("web/app.js"));
+        assert_eq!((js.indent_size, js.end_of_line), (Some(3), Some(LineEnding::Crlf)));
+
+        let (normalized, changes) = rs.normalize("fn main() {  \n\tlet x = 1;\n}\n");
+        assert_eq!(normalized, "fn main() {\n    let x = 1;\n}\n");
+        assert_eq!(changes, vec!["indentation", "trailing whitespace"]);
+    }
+
+    #[test]
+    fn test_editorconfig_numeric_ranges() {
+        assert_eq!(expand_ranges("file{1..3}.txt").as_deref(), Some("file{1,2,3}.txt"));
+        assert_eq!(expand_ranges("{-1..1}{a,b}\\{0..2}").as_deref(), Some("{-1,0,1}{a,b}\\{0..2}"));
+        assert_eq!(expand_ranges("{0..100000}"), None);
+
+        assert!(section_matches("v{2..10}/*.rs", "v10/main.rs"));
+        assert!(!section_matches("v{2..10}/*.rs", "v1/main.rs"));
+        assert!(!section_matches("v{2..10}/*.rs", "v02/main.rs"));
+        assert!(section_matches("{a{1..2},b}.py", "a2.py"));
+    }
+
+    #[test]
+    fn test_rustfmt_and_makefiles() {
+        let dir = tempfile::tempdir().unwrap();
+        write(&dir.path().join("rustfmt.toml"), "hard_tabs = true\nmax_width = 80\n");
+        let conventions = get_file_conventions(&dir.path().join("src/lib.rs"));
+        assert_eq!(conventions.indent_style, Some(IndentStyle::Tab));
+        assert_eq!(conventions.max_line_length, Some(80));
+        let (normalized, _) = conventions.normalize("fn f() {\n        g();\n  }\n");
+        assert_eq!(normalized, "fn f() {\n\t\tg();\n  }\n");
+
+        write(&dir.path().join(".editorconfig"), "[*]\nindent_style = space\nindent_size = 2\n");
+        let makefile = get_file_conventions(&dir.path().join("Makefile"));
+        assert_eq!(makefile.indent_style, None);
+        assert_eq!(makefile.normalize("all:\n\tcargo build\n").0, "all:\n\tcargo build\n");
+    }
+}
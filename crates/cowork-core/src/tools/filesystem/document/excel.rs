@@ -0,0 +1,543 @@
+//! Spreadsheet extraction as structured ranges
+//!
+//! Without a `sheet` parameter the extractor returns workbook metadata (sheet
+//! names and dimensions) so the model can orient itself. With a sheet it
+//! returns the selected range as tab-separated text, RFC 4180 CSV, or JSON
+//! row objects keyed by the header row.
+//!
+//! The first row of the selection is treated as the header. Data rows are
+//! paginated with `offset` / `max_rows` and always returned whole; the
+//! response carries `next_offset` when more rows remain.
+
+use std::path::Path;
+
+use calamine::{open_workbook_auto, Data, Range, Reader};
+use serde_json::{json, Map, Value};
+
+use crate::error::ToolError;
+
+use super::MAX_OUTPUT_SIZE;
+
+/// Default number of data rows returned per call
+const DEFAULT_MAX_ROWS: usize = 500;
+
+/// Output format for sheet data
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(super) enum SheetFormat {
+    /// Tab-separated lines
+    #[default]
+    Text,
+    /// RFC 4180 CSV
+    Csv,
+    /// Array of row objects keyed by header
+    Json,
+}
+
+/// Sheet selector: by name or 0-based index
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum SheetSelector {
+    Name(String),
+    Index(usize),
+}
+
+/// Rectangular cell range with 0-based bounds (`None` = open-ended)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct CellRange {
+    start_row: Option<u32>,
+    start_col: Option<u32>,
+    end_row: Option<u32>,
+    end_col: Option<u32>,
+}
+
+/// What to read from a spreadsheet
+#[derive(Debug, Clone)]
+pub(super) struct SheetQuery {
+    sheet: Option<SheetSelector>,
+    range: Option<CellRange>,
+    format: SheetFormat,
+    offset: usize,
+    max_rows: usize,
+}
+
+impl SheetQuery {
+    /// Build a query from Read tool parameters
+    pub(super) fn from_params(params: &Value) -> Result<Self, ToolError> {
+        let sheet = match &params["sheet"] {
+            Value::Null => None,
+            Value::String(name) => Some(SheetSelector::Name(name.clone())),
+            Value::Number(n) => Some(SheetSelector::Index(n.as_u64().ok_or_else(|| {
+                ToolError::InvalidParams("sheet index must be a non-negative integer".into())
+            })? as usize)),
+            _ => return Err(ToolError::InvalidParams("sheet must be a name or index".into())),
+        };
+
+        let range = params["range"].as_str().map(parse_a1_range).transpose()?;
+
+        let format = match params["format"].as_str() {
+            None | Some("text") => SheetFormat::Text,
+            Some("csv") => SheetFormat::Csv,
+            Some("json") => SheetFormat::Json,
+            Some(other) => {
+                return Err(ToolError::InvalidParams(format!(
+                    "Invalid format '{}': expected text, csv, or json",
+                    other
+                )));
+            }
+        };
+
+        let max_rows = params["max_rows"].as_u64().map(|n| n as usize).unwrap_or(DEFAULT_MAX_ROWS);
+        if max_rows == 0 {
+            return Err(ToolError::InvalidParams("max_rows must be at least 1".into()));
+        }
+
+        Ok(Self {
+            sheet,
+            range,
+            format,
+            offset: params["offset"].as_u64().unwrap_or(0) as usize,
+            max_rows,
+        })
+    }
+}
+
+/// Extract workbook metadata or sheet data according to the query
+pub(super) fn extract(path: &Path, query: &SheetQuery) -> Result<Value, ToolError> {
+    let mut workbook = open_workbook_auto(path)
+        .map_err(|e| ToolError::ExecutionFailed(format!("Failed to open Excel file: {}", e)))?;
+    let sheet_names: Vec<String> = workbook.sheet_names().to_vec();
+
+    let Some(selector) = &query.sheet else {
+        let sheets: Vec<Value> = sheet_names
+            .iter()
+            .enumerate()
+            .map(|(index, name)| {
+                let range = workbook.worksheet_range(name).unwrap_or_default();
+                json!({
+                    "index": index,
+                    "name": name,
+                    "dimensions": dimensions(&range),
+                    "rows": range.height(),
+                    "columns": range.width(),
+                })
+            })
+            .collect();
+        return Ok(json!({
+            "sheets": sheets,
+            "hint": "Call Read again with `sheet` (name or index), and optionally `range` (A1 notation) and `format` (text, csv, json), to get cell data.",
+        }));
+    };
+
+    let name = resolve_sheet(selector, &sheet_names)?;
+    let sheet = workbook
+        .worksheet_range(&name)
+        .map_err(|e| ToolError::ExecutionFailed(format!("Failed to read sheet '{}': {}", name, e)))?;
+
+    let rows = select_rows(&sheet, query.range.unwrap_or_default());
+    Ok(render(&name, rows, query))
+}
+
+/// Find the sheet name for a selector
+fn resolve_sheet(selector: &SheetSelector, names: &[String]) -> Result<String, ToolError> {
+    let found = match selector {
+        SheetSelector::Name(name) => names
+            .iter()
+            .find(|n| *n == name)
+            // Accept "2" as an index when no sheet has that name
+            .or_else(|| name.parse::<usize>().ok().and_then(|i| names.get(i))),
+        SheetSelector::Index(index) => names.get(*index),
+    };
+
+    found.cloned().ok_or_else(|| {
+        ToolError::InvalidParams(format!(
+            "Sheet {:?} not found. Available sheets: {}",
+            selector,
+            names.join(", ")
+        ))
+    })
+}
+
+/// A selected block of cells with its absolute origin
+struct SelectedRows<'a> {
+    /// A1 notation of the selection
+    range: Option<String>,
+    /// First column of the selection (for naming blank headers)
+    first_col: u32,
+    rows: Vec<Vec<&'a Data>>,
+}
+
+/// Clip the requested range to the sheet's used area and collect its cells
+fn select_rows(sheet: &Range<Data>, requested: CellRange) -> SelectedRows<'_> {
+    let (Some((sheet_r0, sheet_c0)), Some((sheet_r1, sheet_c1))) = (sheet.start(), sheet.end()) else {
+        return SelectedRows { range: None, first_col: 0, rows: Vec::new() };
+    };
+
+    let r0 = requested.start_row.unwrap_or(sheet_r0).max(sheet_r0);
+    let c0 = requested.start_col.unwrap_or(sheet_c0).max(sheet_c0);
+    let r1 = requested.end_row.unwrap_or(sheet_r1).min(sheet_r1);
+    let c1 = requested.end_col.unwrap_or(sheet_c1).min(sheet_c1);
+    if r0 > r1 || c0 > c1 {
+        return SelectedRows { range: None, first_col: c0, rows: Vec::new() };
+    }
+
+    let rows = (r0..=r1)
+        .map(|r| {
+            (c0..=c1)
+                .map(|c| sheet.get_value((r, c)).unwrap_or(&Data::Empty))
+                .collect()
+        })
+        .collect();
+
+    SelectedRows {
+        range: Some(format!("{}{}:{}{}", column_name(c0), r0 + 1, column_name(c1), r1 + 1)),
+        first_col: c0,
+        rows,
+    }
+}
+
+/// Render the selection as the requested format, one page of data rows at a time
+fn render(sheet_name: &str, selected: SelectedRows<'_>, query: &SheetQuery) -> Value {
+    let mut rows = selected.rows.into_iter();
+    let header: Vec<String> = rows
+        .next()
+        .map(|cells| header_names(&cells, selected.first_col))
+        .unwrap_or_default();
+    let data: Vec<Vec<&Data>> = rows.collect();
+    let total_rows = data.len();
+
+    let mut text = String::new();
+    let mut objects = Vec::new();
+    let mut size = 0;
+    match query.format {
+        SheetFormat::Text => text.push_str(&header.join("\t")),
+        SheetFormat::Csv => text.push_str(&csv_line(header.iter().map(String::as_str))),
+        SheetFormat::Json => {}
+    }
+    size += text.len();
+
+    let mut returned = 0;
+    for row in data.iter().skip(query.offset).take(query.max_rows) {
+        // Only whole rows: stop before a row that would exceed the size cap
+        let (line, object) = match query.format {
+            SheetFormat::Text => {
+                let cells: Vec<String> = row.iter().map(|c| cell_text(c)).collect();
+                (format!("\n{}", cells.join("\t")), None)
+            }
+            SheetFormat::Csv => {
+                let cells: Vec<String> = row.iter().map(|c| cell_text(c)).collect();
+                (format!("\r\n{}", csv_line(cells.iter().map(String::as_str))), None)
+            }
+            SheetFormat::Json => {
+                let object: Map<String, Value> = header
+                    .iter()
+                    .cloned()
+                    .zip(row.iter().map(|c| cell_json(c)))
+                    .collect();
+                (String::new(), Some(Value::Object(object)))
+            }
+        };
+        let row_size = object.as_ref().map(|o| o.to_string().len()).unwrap_or(line.len());
+        if returned > 0 && size + row_size > MAX_OUTPUT_SIZE {
+            break;
+        }
+        size += row_size;
+        text.push_str(&line);
+        objects.extend(object);
+        returned += 1;
+    }
+    if query.format == SheetFormat::Csv && !text.is_empty() {
+        text.push_str("\r\n");
+    }
+
+    let start = query.offset.min(total_rows);
+    let next_offset = (start + returned < total_rows).then_some(start + returned);
+
+    let mut result = json!({
+        "sheet": sheet_name,
+        "range": selected.range,
+        "columns": header,
+        "total_rows": total_rows,
+        "offset": start,
+        "returned_rows": returned,
+        "next_offset": next_offset,
+    });
+    if query.format == SheetFormat::Json {
+        result["rows"] = Value::Array(objects);
+    } else {
+        result["content"] = Value::String(text);
+    }
+    result
+}
+
+/// Header names from the first row; blanks use the column letter, duplicates get a suffix
+fn header_names(cells: &[&Data], first_col: u32) -> Vec<String> {
+    let mut names: Vec<String> = Vec::with_capacity(cells.len());
+    for (i, cell) in cells.iter().enumerate() {
+        let base = match cell_text(cell).trim() {
+            "" => column_name(first_col + i as u32),
+            text => text.to_string(),
+        };
+        let mut name = base.clone();
+        let mut n = 2;
+        while names.contains(&name) {
+            name = format!("{}_{}", base, n);
+            n += 1;
+        }
+        names.push(name);
+    }
+    names
+}
+
+/// Display text for a cell
+fn cell_text(cell: &Data) -> String {
+    match cell {
+        Data::Empty => String::new(),
+        Data::String(s) => s.clone(),
+        Data::Float(f) => f.to_string(),
+        Data::Int(i) => i.to_string(),
+        Data::Bool(b) => b.to_string(),
+        Data::Error(e) => format!("#ERR:{:?}", e),
+        Data::DateTime(dt) => format!("{}", dt),
+        Data::DateTimeIso(s) => s.clone(),
+        Data::DurationIso(s) => s.clone(),
+    }
+}
+
+/// JSON value for a cell, keeping numbers and booleans typed
+fn cell_json(cell: &Data) -> Value {
+    match cell {
+        Data::Empty => Value::Null,
+        Data::Float(f) => serde_json::Number::from_f64(*f).map(Value::Number).unwrap_or(Value::Null),
+        Data::Int(i) => json!(i),
+        Data::Bool(b) => json!(b),
+        other => Value::String(cell_text(other)),
+    }
+}
+
+/// One RFC 4180 record (without the line terminator)
+fn csv_line<'a>(fields: impl Iterator<Item = &'a str>) -> String {
+    fields
+        .map(|field| {
+            if field.contains([',', '"', '\r', '\n']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Column letters for a 0-based index (0 = A, 26 = AA)
+fn column_name(mut col: u32) -> String {
+    let mut name = Vec::new();
+    loop {
+        name.push(b'A' + (col % 26) as u8);
+        if col < 26 {
+            break;
+        }
+        col = col / 26 - 1;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap_or_default()
+}
+
+/// Parse an A1-style reference ("B12", "B", or "12") into 0-based (row, col)
+fn parse_cell_ref(s: &str) -> Option<(Option<u32>, Option<u32>)> {
+    let s = s.trim().replace('$', "");
+    let split = s.find(|c: char| c.is_ascii_digit()).unwrap_or(s.len());
+    let (letters, digits) = s.split_at(split);
+    if letters.is_empty() && digits.is_empty() {
+        return None;
+    }
+    if !letters.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+
+    let col = if letters.is_empty() {
+        None
+    } else {
+        let n = letters
+            .to_ascii_uppercase()
+            .bytes()
+            .try_fold(0u32, |acc, b| acc.checked_mul(26)?.checked_add((b - b'A' + 1) as u32))?;
+        Some(n - 1)
+    };
+    let row = if digits.is_empty() {
+        None
+    } else {
+        Some(digits.parse::<u32>().ok()?.checked_sub(1)?)
+    };
+    Some((row, col))
+}
+
+/// Parse an A1 range: "A1:D20", "B3", "A:C" (whole columns), or "2:10" (whole rows)
+fn parse_a1_range(s: &str) -> Result<CellRange, ToolError> {
+    let invalid = || ToolError::InvalidParams(format!("Invalid range '{}': expected A1 notation like A1:D20", s));
+    // Ignore a sheet prefix like "Sheet1!A1:B2"
+    let s = s.rsplit('!').next().unwrap_or(s);
+    let (start, end) = s.split_once(':').unwrap_or((s, s));
+    let (start_row, start_col) = parse_cell_ref(start).ok_or_else(invalid)?;
+    let (end_row, end_col) = parse_cell_ref(end).ok_or_else(invalid)?;
+
+    let range = CellRange { start_row, start_col, end_row, end_col };
+    if matches!((start_row, end_row), (Some(a), Some(b)) if a > b)
+        || matches!((start_col, end_col), (Some(a), Some(b)) if a > b)
+    {
+        return Err(invalid());
+    }
+    Ok(range)
+}
+
+/// A1 dimensions of a sheet's used range, e.g. "A1:D20"
+fn dimensions(range: &Range<Data>) -> Option<String> {
+    let (r0, c0) = range.start()?;
+    let (r1, c1) = range.end()?;
+    Some(format!("{}{}:{}{}", column_name(c0), r0 + 1, column_name(c1), r1 + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_xlsxwriter::Workbook;
+
+    /// Write a small workbook: "Tasks" (header + 4 rows) and an empty "Notes" sheet
+    fn write_fixture(path: &Path) {
+        let mut workbook = Workbook::new();
+        let tasks = workbook.add_worksheet().set_name("Tasks").unwrap();
+        let rows: [(&str, &str, f64); 4] = [
+            ("Ship \"v2\"", "open", 3.0),
+            ("Fix bug, urgent", "closed", 1.5),
+            ("Write docs", "open", 2.0),
+            ("Line\nbreak", "open", 4.0),
+        ];
+        tasks.write_string(0, 0, "Task").unwrap();
+        tasks.write_string(0, 1, "Status").unwrap();
+        tasks.write_string(0, 2, "Hours").unwrap();
+        for (i, (task, status, hours)) in rows.iter().enumerate() {
+            let r = i as u32 + 1;
+            tasks.write_string(r, 0, *task).unwrap();
+            tasks.write_string(r, 1, *status).unwrap();
+            tasks.write_number(r, 2, *hours).unwrap();
+        }
+        workbook.add_worksheet().set_name("Notes").unwrap();
+        workbook.save(path).unwrap();
+    }
+
+    fn read(params: Value) -> Value {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fixture.xlsx");
+        write_fixture(&path);
+        extract(&path, &SheetQuery::from_params(&params).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_metadata_without_sheet() {
+        let result = read(json!({}));
+        let sheets = result["sheets"].as_array().unwrap();
+        assert_eq!(sheets.len(), 2);
+        assert_eq!(sheets[0]["name"], "Tasks");
+        assert_eq!(sheets[0]["dimensions"], "A1:C5");
+        assert_eq!(sheets[0]["rows"], 5);
+        assert_eq!(sheets[0]["columns"], 3);
+        assert_eq!(sheets[1]["name"], "Notes");
+        assert!(sheets[1]["dimensions"].is_null());
+    }
+
+    #[test]
+    fn test_json_rows_keyed_by_header() {
+        let result = read(json!({"sheet": "Tasks", "format": "json"}));
+        assert_eq!(result["columns"], json!(["Task", "Status", "Hours"]));
+        let rows = result["rows"].as_array().unwrap();
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[1]["Status"], "closed");
+        assert_eq!(rows[1]["Hours"], 1.5);
+
+        let open_hours: f64 = rows
+            .iter()
+            .filter(|r| r["Status"] == "open")
+            .map(|r| r["Hours"].as_f64().unwrap())
+            .sum();
+        assert_eq!(open_hours, 9.0);
+    }
+
+    #[test]
+    fn test_csv_is_rfc4180() {
+        let result = read(json!({"sheet": 0, "format": "csv"}));
+        assert_eq!(
+            result["content"],
+            "Task,Status,Hours\r\n\"Ship \"\"v2\"\"\",open,3\r\n\"Fix bug, urgent\",closed,1.5\r\nWrite docs,open,2\r\n\"Line\nbreak\",open,4\r\n"
+        );
+    }
+
+    #[test]
+    fn test_range_selection() {
+        let result = read(json!({"sheet": "Tasks", "range": "B1:C3"}));
+        assert_eq!(result["range"], "B1:C3");
+        assert_eq!(result["content"], "Status\tHours\nopen\t3\nclosed\t1.5");
+
+        // Whole columns, clipped to the used area
+        let result = read(json!({"sheet": "Tasks", "range": "C:Z", "format": "json"}));
+        assert_eq!(result["range"], "C1:C5");
+        assert_eq!(result["rows"][3], json!({"Hours": 4.0}));
+    }
+
+    #[test]
+    fn test_pagination_returns_whole_rows() {
+        let first = read(json!({"sheet": "Tasks", "format": "json", "max_rows": 3}));
+        assert_eq!(first["returned_rows"], 3);
+        assert_eq!(first["total_rows"], 4);
+        assert_eq!(first["next_offset"], 3);
+
+        let rest = read(json!({"sheet": "Tasks", "format": "json", "max_rows": 3, "offset": 3}));
+        assert_eq!(rest["returned_rows"], 1);
+        assert!(rest["next_offset"].is_null());
+        assert_eq!(rest["rows"][0]["Status"], "open");
+    }
+
+    #[test]
+    fn test_unknown_sheet_lists_available() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fixture.xlsx");
+        write_fixture(&path);
+        let query = SheetQuery::from_params(&json!({"sheet": "Budget"})).unwrap();
+
+        let err = extract(&path, &query).unwrap_err().to_string();
+        assert!(err.contains("Tasks, Notes"), "{}", err);
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        assert!(SheetQuery::from_params(&json!({"format": "xml"})).is_err());
+        assert!(SheetQuery::from_params(&json!({"range": "D5:A1"})).is_err());
+        assert!(SheetQuery::from_params(&json!({"range": "1A"})).is_err());
+        assert!(SheetQuery::from_params(&json!({"max_rows": 0})).is_err());
+    }
+
+    #[test]
+    fn test_parse_a1_range() {
+        assert_eq!(
+            parse_a1_range("Sheet1!$B$2:AA10").unwrap(),
+            CellRange { start_row: Some(1), start_col: Some(1), end_row: Some(9), end_col: Some(26) }
+        );
+        assert_eq!(
+            parse_a1_range("2:4").unwrap(),
+            CellRange { start_row: Some(1), start_col: None, end_row: Some(3), end_col: None }
+        );
+    }
+
+    #[test]
+    fn test_header_names_fill_blanks_and_dedupe() {
+        let name = Data::String("Name".into());
+        let empty = Data::Empty;
+        assert_eq!(header_names(&[&name, &empty, &name], 1), vec!["Name", "C", "Name_2"]);
+    }
+
+    #[test]
+    fn test_column_name() {
+        assert_eq!(column_name(0), "A");
+        assert_eq!(column_name(25), "Z");
+        assert_eq!(column_name(26), "AA");
+        assert_eq!(column_name(701), "ZZ");
+        assert_eq!(column_name(702), "AAA");
+    }
+}
@@ -0,0 +1,106 @@
+//! Document extraction helper for the Read tool
+//!
+//! Supports extracting text from PDF, Word, Excel, and PowerPoint files.
+//! Each format has its own extractor module; spreadsheets are read as
+//! structured ranges (see [`excel`]) rather than flattened text.
+
+mod excel;
+mod pdf;
+mod pptx;
+mod word;
+
+use std::path::Path;
+
+use serde_json::{json, Value};
+
+use crate::error::ToolError;
+use crate::tools::ToolOutput;
+
+use super::path_to_display;
+
+/// Maximum output size in bytes to prevent DoS from very large documents
+const MAX_OUTPUT_SIZE: usize = 512 * 1024; // 512 KB
+
+/// Marker appended when text output is cut at [`MAX_OUTPUT_SIZE`]
+const TRUNCATION_NOTICE: &str = "\n\n... [Content truncated due to size limit]";
+
+/// Check if a file extension corresponds to a supported document format.
+pub fn is_document(ext: &str) -> bool {
+    matches!(
+        ext.to_lowercase().as_str(),
+        "pdf" | "doc" | "docx" | "xls" | "xlsx" | "pptx"
+    )
+}
+
+/// Extract content from a document file, dispatching to the appropriate extractor.
+///
+/// `params` are the Read tool parameters; only spreadsheets use them
+/// (`sheet`, `range`, `format`, `offset`, `max_rows`).
+pub fn extract_document(path: &Path, params: &Value) -> Result<ToolOutput, ToolError> {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    let (content, format) = match ext.as_str() {
+        "pdf" => (pdf::extract_text(path)?, "pdf"),
+        "doc" | "docx" => (word::extract_text(path)?, "word"),
+        "xls" | "xlsx" => {
+            let query = excel::SheetQuery::from_params(params)?;
+            let mut result = excel::extract(path, &query)?;
+            result["path"] = json!(path_to_display(path));
+            result["format"] = json!("excel");
+            return Ok(ToolOutput::success(result));
+        }
+        "pptx" => (pptx::extract_text(path)?, "powerpoint"),
+        _ => {
+            return Err(ToolError::InvalidParams(format!(
+                "Unsupported document format: .{}",
+                ext
+            )));
+        }
+    };
+
+    Ok(ToolOutput::success(json!({
+        "path": path_to_display(path),
+        "format": format,
+        "content": content,
+    })))
+}
+
+/// Cut text to [`MAX_OUTPUT_SIZE`] on a character boundary, noting the truncation
+fn truncate_output(text: &mut String) {
+    if text.len() > MAX_OUTPUT_SIZE {
+        let mut end = MAX_OUTPUT_SIZE;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+        text.push_str(TRUNCATION_NOTICE);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_document() {
+        assert!(is_document("xlsx"));
+        assert!(is_document("PDF"));
+        assert!(!is_document("csv"));
+    }
+
+    #[test]
+    fn test_truncate_output_respects_char_boundaries() {
+        let mut text = "é".repeat(MAX_OUTPUT_SIZE);
+        truncate_output(&mut text);
+        assert!(text.ends_with(TRUNCATION_NOTICE));
+        assert!(text.len() <= MAX_OUTPUT_SIZE + TRUNCATION_NOTICE.len());
+
+        let mut short = "short".to_string();
+        truncate_output(&mut short);
+        assert_eq!(short, "short");
+    }
+}
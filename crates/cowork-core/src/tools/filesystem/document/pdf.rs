@@ -0,0 +1,31 @@
+//! PDF text extraction
+
+use std::path::Path;
+
+use crate::error::ToolError;
+
+use super::truncate_output;
+
+/// Extract text from a PDF file using pdf-extract
+pub(super) fn extract_text(path: &Path) -> Result<String, ToolError> {
+    let mut text = pdf_extract::extract_text(path)
+        .map_err(|e| ToolError::ExecutionFailed(format!("Failed to extract PDF text: {}", e)))?;
+
+    truncate_output(&mut text);
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_pdf_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fake.pdf");
+        std::fs::write(&path, "not a real pdf").unwrap();
+
+        let err = extract_text(&path).unwrap_err();
+        assert!(err.to_string().contains("PDF"));
+    }
+}
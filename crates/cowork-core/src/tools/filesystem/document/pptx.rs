@@ -0,0 +1,167 @@
+//! PowerPoint text extraction
+
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use quick_xml::events::Event;
+use quick_xml::reader::Reader;
+
+use crate::error::ToolError;
+
+use super::MAX_OUTPUT_SIZE;
+
+/// Extract text from a PowerPoint presentation (.pptx)
+pub(super) fn extract_text(path: &Path) -> Result<String, ToolError> {
+    let file = std::fs::File::open(path)
+        .map_err(|e| ToolError::ExecutionFailed(format!("Failed to open file: {}", e)))?;
+
+    let reader = BufReader::new(file);
+    let mut archive = zip::ZipArchive::new(reader)
+        .map_err(|e| ToolError::ExecutionFailed(format!("Failed to open PPTX archive: {}", e)))?;
+
+    let mut output = String::new();
+    let mut slide_num = 1;
+    let mut truncated = false;
+
+    loop {
+        if output.len() >= MAX_OUTPUT_SIZE {
+            truncated = true;
+            break;
+        }
+
+        let slide_path = format!("ppt/slides/slide{}.xml", slide_num);
+
+        match archive.by_name(&slide_path) {
+            Ok(mut slide_file) => {
+                let mut xml_content = String::new();
+                slide_file.read_to_string(&mut xml_content).map_err(|e| {
+                    ToolError::ExecutionFailed(format!("Failed to read slide: {}", e))
+                })?;
+
+                let slide_text = extract_text_from_slide_xml(&xml_content)?;
+                if !slide_text.trim().is_empty() {
+                    output.push_str(&format!("=== Slide {} ===\n", slide_num));
+                    output.push_str(&slide_text);
+                    output.push_str("\n\n");
+                }
+                slide_num += 1;
+            }
+            Err(zip::result::ZipError::FileNotFound) => break,
+            Err(e) => {
+                return Err(ToolError::ExecutionFailed(format!(
+                    "Failed to read slide {}: {}",
+                    slide_num, e
+                )));
+            }
+        }
+    }
+
+    if output.is_empty() {
+        output = "(No text content found in presentation)".to_string();
+    } else if truncated {
+        output.push_str("\n... [Content truncated due to size limit]");
+    }
+
+    Ok(output)
+}
+
+/// Extract text content from PPTX slide XML
+fn extract_text_from_slide_xml(xml: &str) -> Result<String, ToolError> {
+    let reader_error = |e: &dyn std::fmt::Display| ToolError::ExecutionFailed(format!("XML parsing error: {}", e));
+    let mut reader = Reader::from_str(xml);
+
+    let mut output = String::new();
+    let mut in_text_element = false;
+    // Text of the current `a:t`, which arrives split around entity references
+    let mut run = String::new();
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(ref e)) if e.name().as_ref() == b"a:t" => in_text_element = true,
+            Ok(Event::Text(e)) if in_text_element => {
+                run.push_str(&e.decode().map_err(|e| reader_error(&e))?);
+            }
+            Ok(Event::GeneralRef(e)) if in_text_element => {
+                if let Some(c) = e.resolve_char_ref().map_err(|e| reader_error(&e))? {
+                    run.push(c);
+                } else {
+                    let name = e.decode().map_err(|e| reader_error(&e))?;
+                    run.push_str(quick_xml::escape::resolve_predefined_entity(&name).unwrap_or_default());
+                }
+            }
+            Ok(Event::End(ref e)) => {
+                if e.name().as_ref() == b"a:t" {
+                    in_text_element = false;
+                    let text = run.trim();
+                    if !text.is_empty() {
+                        output.push_str(text);
+                        output.push(' ');
+                    }
+                    run.clear();
+                }
+                if e.name().as_ref() == b"a:p" {
+                    output.push('\n');
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(reader_error(&e)),
+            _ => {}
+        }
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn slide_xml(text: &str) -> String {
+        format!(
+            r#"<p:sld xmlns:a="a" xmlns:p="p"><p:cSld><p:spTree><p:sp><p:txBody><a:p><a:r><a:t>{}</a:t></a:r></a:p></p:txBody></p:sp></p:spTree></p:cSld></p:sld>"#,
+            text
+        )
+    }
+
+    fn write_fixture(path: &Path, slides: &[&str]) {
+        let file = std::fs::File::create(path).unwrap();
+        let mut zip = zip::ZipWriter::new(file);
+        for (i, text) in slides.iter().enumerate() {
+            zip.start_file(format!("ppt/slides/slide{}.xml", i + 1), zip::write::SimpleFileOptions::default())
+                .unwrap();
+            zip.write_all(slide_xml(text).as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn test_extracts_slides_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fixture.pptx");
+        write_fixture(&path, &["Welcome", "Roadmap"]);
+
+        let text = extract_text(&path).unwrap();
+        let welcome = text.find("=== Slide 1 ===\nWelcome").unwrap();
+        let roadmap = text.find("=== Slide 2 ===\nRoadmap").unwrap();
+        assert!(welcome < roadmap);
+    }
+
+    #[test]
+    fn test_decodes_entity_references() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fixture.pptx");
+        write_fixture(&path, &["R&amp;D &lt;2024&gt; &#8212; &#x41;"]);
+
+        assert!(extract_text(&path).unwrap().contains("R&D <2024> \u{2014} A"));
+    }
+
+    #[test]
+    fn test_empty_presentation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("empty.pptx");
+        write_fixture(&path, &[]);
+
+        assert_eq!(extract_text(&path).unwrap(), "(No text content found in presentation)");
+    }
+}
@@ -0,0 +1,44 @@
+//! Word document text extraction
+
+use std::io::Read;
+use std::path::Path;
+
+use crate::error::ToolError;
+
+use super::truncate_output;
+
+/// Extract text from a Word document (.docx) using dotext
+pub(super) fn extract_text(path: &Path) -> Result<String, ToolError> {
+    use dotext::*;
+
+    let mut text = String::new();
+    Docx::open(path)
+        .map_err(|e| ToolError::ExecutionFailed(format!("Failed to parse DOCX: {}", e)))?
+        .read_to_string(&mut text)
+        .map_err(|e| ToolError::ExecutionFailed(format!("Failed to read DOCX content: {}", e)))?;
+
+    truncate_output(&mut text);
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extracts_paragraph_text() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fixture.docx");
+        let file = std::fs::File::create(&path).unwrap();
+        docx_rs::Docx::new()
+            .add_paragraph(docx_rs::Paragraph::new().add_run(docx_rs::Run::new().add_text("Quarterly report")))
+            .add_paragraph(docx_rs::Paragraph::new().add_run(docx_rs::Run::new().add_text("Revenue grew")))
+            .build()
+            .pack(file)
+            .unwrap();
+
+        let text = extract_text(&path).unwrap();
+        assert!(text.contains("Quarterly report"));
+        assert!(text.contains("Revenue grew"));
+    }
+}
@@ -2,21 +2,77 @@
 
 use serde_json::{json, Value};
 use std::path::PathBuf;
+use std::sync::Arc;
 
 use crate::error::ToolError;
+use crate::formatting::diff_stats;
+use crate::tools::remote::RemoteWorkspace;
 use crate::tools::{BoxFuture, Tool, ToolExecutionContext, ToolOutput};
 
-use super::validate_path;
+use super::generated::allow_generated_schema;
+use super::{get_file_conventions, validate_path_with_scratch, GeneratedFileGuard, SyntaxVerifier, WorkspaceRoots};
 
 /// Tool for performing exact string replacements in files
 pub struct EditFile {
-    workspace: PathBuf,
+    /// Workspace roots; relative paths resolve against the primary one
+    roots: WorkspaceRoots,
+    /// Remote workspace to edit in instead of `roots`
+    remote: Option<Arc<RemoteWorkspace>>,
+    /// Session scratch directory, allowed as well as the workspace roots
+    scratch: Option<PathBuf>,
+    /// Syntax check of the written file (None = not checked)
+    verifier: Option<Arc<SyntaxVerifier>>,
+    /// Refuses lockfiles, generated code and build output
+    generated: Arc<GeneratedFileGuard>,
 }
 
 impl EditFile {
     pub fn new(workspace: PathBuf) -> Self {
-        Self { workspace }
+        Self {
+            roots: WorkspaceRoots::new(workspace),
+            remote: None,
+            scratch: None,
+            verifier: None,
+            generated: Arc::new(GeneratedFileGuard::new()),
+        }
     }
+
+    /// Edit files in a remote workspace, or the local one with `None`
+    pub fn with_remote(mut self, remote: Option<Arc<RemoteWorkspace>>) -> Self {
+        self.remote = remote;
+        self
+    }
+
+    /// Also allow paths in these roots besides the workspace
+    pub fn with_additional_roots(mut self, roots: &[PathBuf]) -> Self {
+        self.roots = self.roots.with_additional(roots.iter().cloned());
+        self
+    }
+
+    /// Also allow paths in the session's scratch directory
+    pub fn with_scratch_dir(mut self, scratch: Option<PathBuf>) -> Self {
+        self.scratch = scratch;
+        self
+    }
+
+    /// Check the syntax of local files after writing them
+    pub fn with_verifier(mut self, verifier: Option<Arc<SyntaxVerifier>>) -> Self {
+        self.verifier = verifier;
+        self
+    }
+
+    /// Decide which files are generated with `guard` (the project's
+    /// patterns) rather than the built-in patterns alone
+    pub fn with_generated_guard(mut self, guard: Arc<GeneratedFileGuard>) -> Self {
+        self.generated = guard;
+        self
+    }
+}
+
+/// Where the edited file lives
+enum Target {
+    Local(PathBuf),
+    Remote(Arc<RemoteWorkspace>, String),
 }
 
 impl Tool for EditFile {
@@ -28,6 +84,10 @@ impl Tool for EditFile {
         crate::prompt::builtin::claude_code::tools::EDIT
     }
 
+    fn compact_description(&self) -> &str {
+        crate::prompt::builtin::claude_code::tools::EDIT_COMPACT
+    }
+
     fn parameters_schema(&self) -> Value {
         json!({
             "type": "object",
@@ -48,13 +108,18 @@ impl Tool for EditFile {
                     "type": "boolean",
                     "description": "Replace all occurrences of old_string (default false)",
                     "default": false
-                }
+                },
+                "raw": {
+                    "type": "boolean",
+                    "description": "Insert new_string exactly as given, without adapting its indentation and trailing whitespace to the file's conventions (default false)"
+                },
+                "allow_generated": allow_generated_schema()
             },
             "required": ["file_path", "old_string", "new_string"]
         })
     }
 
-    fn execute(&self, params: Value, _ctx: ToolExecutionContext) -> BoxFuture<'_, Result<ToolOutput, ToolError>> {
+    fn execute(&self, params: Value, ctx: ToolExecutionContext) -> BoxFuture<'_, Result<ToolOutput, ToolError>> {
         Box::pin(async move {
             let file_path = params["file_path"]
                 .as_str()
@@ -77,14 +142,27 @@ impl Tool for EditFile {
                 ));
             }
 
-            // Validate path
-            let path = self.workspace.join(file_path);
-            let validated = validate_path(&path, &self.workspace)?;
+            // Validate path and read current content
+            let (target, content) = if let Some(remote) = &self.remote {
+                let path = remote.resolve(file_path)?;
+                let bytes = remote.read(&path).await?;
+                let content = String::from_utf8(bytes)
+                    .map_err(|_| ToolError::InvalidParams(format!("{} is not a UTF-8 text file", path)))?;
+                (Target::Remote(remote.clone(), path), content)
+            } else {
+                let path = self.roots.resolve(file_path);
+                let validated = validate_path_with_scratch(&path, self.roots.paths(), self.scratch.as_deref())?;
+                let content = tokio::fs::read_to_string(&validated)
+                    .await
+                    .map_err(ToolError::Io)?;
+                (Target::Local(validated), content)
+            };
 
-            // Read current content
-            let content = tokio::fs::read_to_string(&validated)
-                .await
-                .map_err(ToolError::Io)?;
+            // Paths in the workspace are reported relative to it
+            let shown = match &target {
+                Target::Local(path) => self.roots.display(path, false),
+                Target::Remote(_, path) => path.clone(),
+            };
 
             // Detect original line ending style (for preserving on write)
             let uses_crlf = content.contains("\r\n");
@@ -93,7 +171,7 @@ impl Tool for EditFile {
             // This handles Windows files (CRLF) when the LLM sends LF
             let content_normalized = content.replace("\r\n", "\n");
             let old_string_normalized = old_string.replace("\r\n", "\n");
-            let new_string_normalized = new_string.replace("\r\n", "\n");
+            let mut new_string_normalized = new_string.replace("\r\n", "\n");
 
             // Count occurrences using normalized strings
             let occurrences = content_normalized.matches(&old_string_normalized).count();
@@ -112,6 +190,27 @@ impl Tool for EditFile {
                 )));
             }
 
+            // Bring new_string in line with the file's conventions, unless
+            // asked to insert it verbatim
+            let mut conventions = None;
+            let mut changes = Vec::new();
+            if let Target::Local(path) = &target
+                && !params["raw"].as_bool().unwrap_or(false)
+            {
+                let file_conventions = get_file_conventions(path);
+                let mut starts = content_normalized.match_indices(&old_string_normalized).map(|(i, _)| i);
+                let at_line_start = starts.all(|i| i == 0 || content_normalized[..i].ends_with('\n'));
+                let at_line_end = content_normalized.match_indices(&old_string_normalized).all(|(i, m)| {
+                    let rest = &content_normalized[i + m.len()..];
+                    rest.is_empty() || rest.starts_with('\n')
+                });
+                let (normalized, fragment_changes) =
+                    file_conventions.normalize_fragment(&new_string_normalized, at_line_start, at_line_end);
+                new_string_normalized = normalized;
+                changes = fragment_changes;
+                conventions = Some(file_conventions);
+            }
+
             // Perform replacement on normalized content
             let new_content_normalized = if replace_all {
                 content_normalized.replace(&old_string_normalized, &new_string_normalized)
@@ -123,6 +222,8 @@ impl Tool for EditFile {
             let old_lines = content_normalized.lines().count();
             let new_lines = new_content_normalized.lines().count();
             let lines_changed = (new_lines as i64 - old_lines as i64).abs();
+            let replaced = if replace_all { occurrences } else { 1 };
+            let (added, removed) = diff_stats(&old_string_normalized, &new_string_normalized);
 
             // Restore original line ending style if the file used CRLF
             let new_content = if uses_crlf {
@@ -131,19 +232,49 @@ impl Tool for EditFile {
                 new_content_normalized
             };
 
+            // Only now that the edit is known to apply, so nobody approves
+            // an override that then fails
+            let generated = self.generated.enforce(&shown, Some(&content), &params, &ctx).await?;
+
             // Write back
-            tokio::fs::write(&validated, &new_content)
-                .await
-                .map_err(ToolError::Io)?;
+            match &target {
+                Target::Local(path) => tokio::fs::write(path, &new_content).await.map_err(ToolError::Io)?,
+                Target::Remote(remote, path) => remote.write(path, new_content.as_bytes(), false).await?,
+            }
 
-            Ok(ToolOutput::success(json!({
+            let mut result = json!({
                 "success": true,
-                "path": file_path,
-                "occurrences_replaced": if replace_all { occurrences } else { 1 },
+                "path": shown,
+                "occurrences_replaced": replaced,
                 "old_line_count": old_lines,
                 "new_line_count": new_lines,
-                "lines_changed": lines_changed
-            })))
+                "lines_changed": lines_changed,
+                "lines_added": added * replaced,
+                "lines_removed": removed * replaced
+            });
+            if let Some(conventions) = &conventions {
+                if !changes.is_empty() {
+                    result["normalized"] = json!(changes);
+                    result["conventions"] = json!(conventions.describe());
+                }
+                let long_lines = conventions.long_lines(&new_string_normalized);
+                if long_lines > 0 {
+                    result["long_lines"] = json!(format!(
+                        "{} inserted line(s) exceed the {} character limit",
+                        long_lines,
+                        conventions.max_line_length.unwrap_or_default()
+                    ));
+                }
+            }
+            if let Some(generated) = generated {
+                result["generated"] = json!(generated);
+            }
+            if let (Some(verifier), Target::Local(path)) = (&self.verifier, &target)
+                && let Some(verification) = verifier.verify(path, &shown, &new_content).await
+            {
+                result["verification"] = json!(verification);
+            }
+            Ok(ToolOutput::success(result))
         })
     }
 }
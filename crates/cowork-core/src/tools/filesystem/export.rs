@@ -12,7 +12,7 @@ use std::path::PathBuf;
 use crate::error::ToolError;
 use crate::tools::{BoxFuture, Tool, ToolExecutionContext, ToolOutput};
 
-use super::{normalize_path, path_to_display, validate_path};
+use super::{path_to_display, validate_path, validate_write_path};
 
 /// Tool for exporting content to document formats (PDF, DOCX, XLSX, HTML slides)
 pub struct ExportDocument {
@@ -85,6 +85,10 @@ HTML Slides:
 }"#
     }
 
+    fn compact_description(&self) -> &str {
+        "Exports content to PDF, Word, Excel or HTML slides; the file_path extension picks the format."
+    }
+
     fn parameters_schema(&self) -> Value {
         json!({
             "type": "object",
@@ -126,15 +130,7 @@ HTML Slides:
             let path = self.workspace.join(path_str);
 
             // Normalize and validate path
-            let normalized_path = normalize_path(&path);
-            let normalized_workspace = normalize_path(&self.workspace);
-
-            if !normalized_path.starts_with(&normalized_workspace) {
-                return Err(ToolError::PermissionDenied(format!(
-                    "Path {} is outside workspace",
-                    path.display()
-                )));
-            }
+            validate_write_path(&path, &self.workspace)?;
 
             // Create parent directories if needed
             if let Some(parent) = path.parent() {
@@ -0,0 +1,200 @@
+//! Finish file tool - closes a chunked write
+
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::error::ToolError;
+use crate::tools::remote::RemoteWorkspace;
+use crate::tools::{BoxFuture, Tool, ToolExecutionContext, ToolOutput};
+
+use super::{get_file_conventions, validate_path_with_scratch, SyntaxVerifier, WorkspaceRoots};
+
+/// Name of the tool that closes a chunked write
+pub const FINISH_FILE_TOOL_NAME: &str = "FinishFile";
+
+/// Tool that checks a file written in appended chunks came out whole
+///
+/// It compares the size (and optionally the SHA-256) the model believes it
+/// wrote with the file on disk, then applies the file's conventions and the
+/// syntax check that Write skips for appended chunks.
+pub struct FinishFile {
+    /// Workspace roots; relative paths resolve against the primary one
+    roots: WorkspaceRoots,
+    /// Remote workspace to check in instead of `roots`
+    remote: Option<Arc<RemoteWorkspace>>,
+    /// Session scratch directory, allowed as well as the workspace roots
+    scratch: Option<PathBuf>,
+    /// Syntax check of the finished file (None = not checked)
+    verifier: Option<Arc<SyntaxVerifier>>,
+}
+
+impl FinishFile {
+    pub fn new(workspace: PathBuf) -> Self {
+        Self {
+            roots: WorkspaceRoots::new(workspace),
+            remote: None,
+            scratch: None,
+            verifier: None,
+        }
+    }
+
+    /// Check files in a remote workspace, or the local one with `None`
+    pub fn with_remote(mut self, remote: Option<Arc<RemoteWorkspace>>) -> Self {
+        self.remote = remote;
+        self
+    }
+
+    /// Also allow paths in these roots besides the workspace
+    pub fn with_additional_roots(mut self, roots: &[PathBuf]) -> Self {
+        self.roots = self.roots.with_additional(roots.iter().cloned());
+        self
+    }
+
+    /// Also allow paths in the session's scratch directory
+    pub fn with_scratch_dir(mut self, scratch: Option<PathBuf>) -> Self {
+        self.scratch = scratch;
+        self
+    }
+
+    /// Check the syntax of local files once finished
+    pub fn with_verifier(mut self, verifier: Option<Arc<SyntaxVerifier>>) -> Self {
+        self.verifier = verifier;
+        self
+    }
+}
+
+/// Compare the file with the size and hash the model stated
+fn check_stated(shown: &str, bytes: &[u8], expected_bytes: u64, sha256: Option<&str>) -> Result<String, ToolError> {
+    if bytes.len() as u64 != expected_bytes {
+        return Err(ToolError::InvalidParams(format!(
+            "{} is {} bytes, not the stated {}: a chunk is missing, repeated or was changed. Read the end of \
+             the file and append or rewrite what is wrong before finishing again",
+            shown,
+            bytes.len(),
+            expected_bytes
+        )));
+    }
+    let actual = format!("{:x}", Sha256::digest(bytes));
+    if let Some(stated) = sha256
+        && !stated.eq_ignore_ascii_case(&actual)
+    {
+        return Err(ToolError::InvalidParams(format!(
+            "{} has SHA-256 {}, not the stated {}: its content differs from what was meant to be written",
+            shown, actual, stated
+        )));
+    }
+    Ok(actual)
+}
+
+impl Tool for FinishFile {
+    fn name(&self) -> &str {
+        FINISH_FILE_TOOL_NAME
+    }
+
+    fn description(&self) -> &str {
+        r#"Finish a file written in chunks with Write in append mode.
+
+Usage:
+- Call it once, after the last chunk, with the total size in bytes you wrote (the total_bytes of the last chunk) and optionally the SHA-256 of the whole content.
+- It fails if the file on disk differs, which means a chunk was lost, repeated or changed; fix the file and call it again.
+- It then adapts the file to the project's conventions (unless raw is set) and checks its syntax, as Write does for a whole file."#
+    }
+
+    fn compact_description(&self) -> &str {
+        "Finishes a file written in chunks with Write in append mode: give the total bytes written (and optionally the SHA-256) to check nothing was lost."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "file_path": {
+                    "type": "string",
+                    "description": "The absolute path to the file written in chunks"
+                },
+                "expected_bytes": {
+                    "type": "integer",
+                    "description": "The total size in bytes the file should have"
+                },
+                "sha256": {
+                    "type": "string",
+                    "description": "Hex SHA-256 of the whole content, if known"
+                },
+                "raw": {
+                    "type": "boolean",
+                    "description": "Leave the file byte for byte as written, without adapting it to the file's conventions (default false)"
+                }
+            },
+            "required": ["file_path", "expected_bytes"]
+        })
+    }
+
+    fn execute(&self, params: Value, _ctx: ToolExecutionContext) -> BoxFuture<'_, Result<ToolOutput, ToolError>> {
+        Box::pin(async move {
+            let path_str = params["file_path"]
+                .as_str()
+                .ok_or_else(|| ToolError::InvalidParams("file_path is required".into()))?;
+            let expected_bytes = params["expected_bytes"]
+                .as_u64()
+                .ok_or_else(|| ToolError::InvalidParams("expected_bytes is required".into()))?;
+            let sha256 = params["sha256"].as_str();
+
+            if let Some(remote) = &self.remote {
+                let path = remote.resolve(path_str)?;
+                let shown = remote.relative(&path);
+                let bytes = remote.read(&path).await?;
+                let actual = check_stated(&shown, &bytes, expected_bytes, sha256)?;
+                return Ok(ToolOutput::success(json!({
+                    "path": shown,
+                    "bytes": bytes.len(),
+                    "sha256": actual
+                })));
+            }
+
+            let path = self.roots.resolve(path_str);
+            let path = validate_path_with_scratch(&path, self.roots.paths(), self.scratch.as_deref())?;
+            // Paths in the workspace are reported relative to it
+            let shown = self.roots.display(&path, false);
+            let bytes = tokio::fs::read(&path).await.map_err(ToolError::Io)?;
+            let actual = check_stated(&shown, &bytes, expected_bytes, sha256)?;
+            let mut result = json!({
+                "path": shown,
+                "bytes": bytes.len(),
+                "sha256": actual
+            });
+
+            // Only text gets conventions and a syntax check
+            let Ok(content) = String::from_utf8(bytes) else {
+                return Ok(ToolOutput::success(result));
+            };
+            let conventions = get_file_conventions(&path);
+            let (content, changes) = if params["raw"].as_bool().unwrap_or(false) {
+                (content, Vec::new())
+            } else {
+                conventions.normalize(&content)
+            };
+            if !changes.is_empty() {
+                tokio::fs::write(&path, &content).await.map_err(ToolError::Io)?;
+                result["normalized"] = json!(changes);
+                result["conventions"] = json!(conventions.describe());
+                result["bytes_after_normalizing"] = json!(content.len());
+            }
+            let long_lines = conventions.long_lines(&content);
+            if long_lines > 0 {
+                result["long_lines"] = json!(format!(
+                    "{} line(s) exceed the {} character limit",
+                    long_lines,
+                    conventions.max_line_length.unwrap_or_default()
+                ));
+            }
+            if let Some(verifier) = &self.verifier
+                && let Some(verification) = verifier.verify(&path, &shown, &content).await
+            {
+                result["verification"] = json!(verification);
+            }
+            Ok(ToolOutput::success(result))
+        })
+    }
+}
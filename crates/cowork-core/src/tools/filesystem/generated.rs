@@ -0,0 +1,319 @@
+//! Generated-file guard for Write, Edit and BatchEdit
+//!
+//! Lockfiles, generated code and build outputs are produced by a tool, so
+//! changing them by hand is always wrong: the next run of the tool undoes it,
+//! or worse, the file no longer matches what produced it (a lockfile whose
+//! checksums don't match, protobuf code out of step with its `.proto`).
+//!
+//! A file counts as generated when its workspace-relative path matches one
+//! of the built-in patterns (lockfiles, `*.generated.*`, protobuf output,
+//! anything under `dist/`, `build/` or `target/`) or a project pattern from
+//! `generated_files` in `.cowork/settings.json`, or when one of its first
+//! `MARKER_LINES` lines carries a generated marker. Changing one fails with
+//! `ToolError::GeneratedFile`, which says what to run instead. A call that
+//! sets `allow_generated: true` goes through only once a person approves
+//! it: approve rules, the turn's approval and auto-approving frontends
+//! don't count.
+
+use std::io::Read;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::config::{GeneratedFilesConfig, ProjectSettings};
+use crate::error::ToolError;
+use crate::tools::ToolExecutionContext;
+
+use super::batch_edit::PathGlob;
+
+/// Parameter that asks to change a generated file anyway
+pub const ALLOW_GENERATED_PARAM: &str = "allow_generated";
+
+/// Lines at the top of a file searched for a marker
+pub const MARKER_LINES: usize = 5;
+
+/// Bytes read from the top of an existing file to look for a marker
+const HEAD_BYTES: u64 = 4096;
+
+/// Text that marks a file as generated when it appears near the top
+const MARKERS: &[&str] = &["@generated", "DO NOT EDIT"];
+
+/// Suggested for generated files when nothing better is known
+const DEFAULT_ACTION: &str = "change the generator's input and rerun it instead";
+
+/// Built-in patterns: glob, why the file is generated, what to do instead
+const BUILTIN_RULES: &[(&str, &str, &str)] = &[
+    ("Cargo.lock", "Cargo's lockfile", "run `cargo update -p <crate>` (or change Cargo.toml) instead of editing Cargo.lock"),
+    ("package-lock.json", "npm's lockfile", "run `npm install <package>` instead of editing package-lock.json"),
+    ("npm-shrinkwrap.json", "npm's lockfile", "run `npm install <package>` instead of editing npm-shrinkwrap.json"),
+    ("pnpm-lock.yaml", "pnpm's lockfile", "run `pnpm add <package>` or `pnpm install` instead of editing pnpm-lock.yaml"),
+    ("yarn.lock", "Yarn's lockfile", "run `yarn add <package>` or `yarn install` instead of editing yarn.lock"),
+    ("poetry.lock", "Poetry's lockfile", "run `poetry lock` or `poetry add <package>` instead of editing poetry.lock"),
+    ("uv.lock", "uv's lockfile", "run `uv lock` or `uv add <package>` instead of editing uv.lock"),
+    ("Pipfile.lock", "Pipenv's lockfile", "run `pipenv lock` or `pipenv install <package>` instead of editing Pipfile.lock"),
+    ("Gemfile.lock", "Bundler's lockfile", "run `bundle update <gem>` instead of editing Gemfile.lock"),
+    ("composer.lock", "Composer's lockfile", "run `composer update <package>` instead of editing composer.lock"),
+    ("go.sum", "Go's module checksums", "run `go mod tidy` or `go get <module>` instead of editing go.sum"),
+    ("flake.lock", "Nix's flake lockfile", "run `nix flake update` instead of editing flake.lock"),
+    ("*.generated.*", "generated code", DEFAULT_ACTION),
+    ("*.pb.go", "protobuf output", "edit the .proto file and rerun protoc instead"),
+    ("*.pb.cc", "protobuf output", "edit the .proto file and rerun protoc instead"),
+    ("*.pb.h", "protobuf output", "edit the .proto file and rerun protoc instead"),
+    ("*_pb2.py", "protobuf output", "edit the .proto file and rerun protoc instead"),
+    ("*_pb2_grpc.py", "protobuf output", "edit the .proto file and rerun protoc instead"),
+    ("**/dist/**", "build output", "change the sources and rebuild instead"),
+    ("**/build/**", "build output", "change the sources and rebuild instead"),
+    ("**/target/**", "build output", "change the sources and rebuild instead"),
+];
+
+/// A file the guard refuses to change
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GeneratedFile {
+    /// As shown to the model, relative to the workspace when inside it
+    pub path: String,
+    /// Why the file counts as generated
+    pub reason: String,
+    /// What to do instead of editing it
+    pub action: String,
+}
+
+impl GeneratedFile {
+    /// The error a call gets for changing the file without `allow_generated`
+    pub fn into_error(self) -> ToolError {
+        ToolError::GeneratedFile {
+            path: self.path,
+            reason: self.reason,
+            action: self.action,
+        }
+    }
+}
+
+impl std::fmt::Display for GeneratedFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} is {}; {}", self.path, self.reason, self.action)
+    }
+}
+
+/// Whether the call asks to change generated files anyway
+pub fn allows_generated(params: &Value) -> bool {
+    params[ALLOW_GENERATED_PARAM].as_bool().unwrap_or(false)
+}
+
+/// Schema of the `allow_generated` parameter
+pub fn allow_generated_schema() -> Value {
+    serde_json::json!({
+        "type": "boolean",
+        "description": "Change the file even though it is a lockfile, generated code or build output (default false). Only when the error says so and running the suggested command is not an option; a person must approve it"
+    })
+}
+
+/// The first few KB of the file at `path`, to look for a marker in
+pub fn read_head(path: &Path) -> Option<String> {
+    let mut head = Vec::new();
+    std::fs::File::open(path).ok()?.take(HEAD_BYTES).read_to_end(&mut head).ok()?;
+    Some(String::from_utf8_lossy(&head).into_owned())
+}
+
+/// A pattern and what it says about the files it matches
+struct Rule {
+    glob: PathGlob,
+    reason: String,
+    action: String,
+}
+
+/// Decides which files are generated
+pub struct GeneratedFileGuard {
+    /// Project rules first, so their actions win over the built-in ones
+    rules: Vec<Rule>,
+    /// Files never treated as generated
+    allow: Vec<PathGlob>,
+}
+
+impl Default for GeneratedFileGuard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GeneratedFileGuard {
+    /// The built-in patterns and markers
+    pub fn new() -> Self {
+        let rules = BUILTIN_RULES
+            .iter()
+            .filter_map(|(pattern, reason, action)| {
+                let glob = PathGlob::parse(pattern, "generated").ok()?;
+                Some(Rule {
+                    glob,
+                    reason: reason.to_string(),
+                    action: action.to_string(),
+                })
+            })
+            .collect();
+        Self { rules, allow: Vec::new() }
+    }
+
+    /// Add a project's patterns and exceptions (invalid globs are skipped
+    /// with a warning)
+    pub fn with_config(mut self, config: &GeneratedFilesConfig) -> Self {
+        let mut rules = Vec::new();
+        for pattern in &config.patterns {
+            match PathGlob::parse(pattern.pattern(), "generated_files") {
+                Ok(glob) => rules.push(Rule {
+                    glob,
+                    reason: "generated (project settings)".to_string(),
+                    action: pattern.action().unwrap_or(DEFAULT_ACTION).to_string(),
+                }),
+                Err(e) => tracing::warn!("Ignoring generated_files pattern: {}", e),
+            }
+        }
+        rules.append(&mut self.rules);
+        self.rules = rules;
+        for pattern in &config.allow {
+            match PathGlob::parse(pattern, "generated_files allow") {
+                Ok(glob) => self.allow.push(glob),
+                Err(e) => tracing::warn!("Ignoring generated_files exception: {}", e),
+            }
+        }
+        self
+    }
+
+    /// The guard for a workspace: built-ins plus its project settings
+    pub fn load(workspace: &Path) -> Self {
+        match ProjectSettings::load(workspace) {
+            Ok(Some(ProjectSettings {
+                generated_files: Some(config),
+                ..
+            })) => Self::new().with_config(&config),
+            Ok(_) => Self::new(),
+            Err(e) => {
+                tracing::warn!("Ignoring project settings: {}", e);
+                Self::new()
+            }
+        }
+    }
+
+    /// Whether the file at `path` (relative to the workspace) is generated,
+    /// judging by its name or by `content`, the start of the file as it is
+    /// now (None for a new file)
+    pub fn check(&self, path: &str, content: Option<&str>) -> Option<GeneratedFile> {
+        let path = path.replace('\\', "/");
+        if self.allow.iter().any(|glob| glob.matches(&path)) {
+            return None;
+        }
+        if let Some(rule) = self.rules.iter().find(|rule| rule.glob.matches(&path)) {
+            return Some(GeneratedFile {
+                path,
+                reason: rule.reason.clone(),
+                action: rule.action.clone(),
+            });
+        }
+        let marker = content.and_then(|content| {
+            content
+                .lines()
+                .take(MARKER_LINES)
+                .find_map(|line| MARKERS.iter().find(|marker| line.contains(*marker)))
+        })?;
+        Some(GeneratedFile {
+            path,
+            reason: format!("marked \"{}\" in its header", marker),
+            action: DEFAULT_ACTION.to_string(),
+        })
+    }
+
+    /// Let a call change the file at `path` or not
+    ///
+    /// A file that isn't generated passes. A generated one fails the call,
+    /// unless it sets `allow_generated` and a person approves; the file is
+    /// then returned, for the result to mention.
+    pub async fn enforce(
+        &self,
+        path: &str,
+        content: Option<&str>,
+        params: &Value,
+        ctx: &ToolExecutionContext,
+    ) -> Result<Option<GeneratedFile>, ToolError> {
+        let Some(file) = self.check(path, content) else {
+            return Ok(None);
+        };
+        if !allows_generated(params) {
+            return Err(file.into_error());
+        }
+        ctx.request_generated_approval(params.clone(), Some(format!("Change a generated file: {}", file)), vec![file.clone()])
+            .await
+            .map_err(ToolError::Rejected)?;
+        Ok(Some(file))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GeneratedPattern;
+
+    #[test]
+    fn test_builtin_patterns() {
+        let guard = GeneratedFileGuard::new();
+        let lock = guard.check("Cargo.lock", None).unwrap();
+        assert!(lock.action.contains("cargo update -p"));
+        assert!(guard.check("frontend/pnpm-lock.yaml", None).is_some());
+        assert!(guard.check("src/schema.generated.ts", None).is_some());
+        assert!(guard.check("api/v1/user.pb.go", None).is_some());
+        assert!(guard.check("dist/app.js", None).is_some());
+        assert!(guard.check("crates/core/target/debug/build.rs", None).is_some());
+        assert!(guard.check(r"web\build\index.html", None).is_some());
+        assert!(guard.check("src/main.rs", None).is_none());
+        assert!(guard.check("src/builder.rs", None).is_none());
+        assert!(guard.check("Cargo.toml", None).is_none());
+    }
+
+    #[test]
+    fn test_marker_in_header() {
+        let guard = GeneratedFileGuard::new();
+        let go = "// Code generated by stringer; DO NOT EDIT.\n\npackage main\n";
+        let file = guard.check("kind_string.go", Some(go)).unwrap();
+        assert!(file.reason.contains("DO NOT EDIT"));
+        let rust = "// This file is @generated by build.rs\nfn main() {}\n";
+        assert!(guard.check("src/gen.rs", Some(rust)).is_some());
+
+        // Only the first few lines count
+        let late = format!("{}// DO NOT EDIT\n", "fn f() {}\n".repeat(MARKER_LINES));
+        assert!(guard.check("src/lib.rs", Some(&late)).is_none());
+        assert!(guard.check("src/lib.rs", Some("fn main() {}\n")).is_none());
+    }
+
+    #[test]
+    fn test_project_patterns_merge_with_builtins() {
+        let config: GeneratedFilesConfig = serde_json::from_value(serde_json::json!({
+            "patterns": [
+                "src/gen/**",
+                { "pattern": "schema.graphql", "action": "run `npm run codegen` instead" },
+                { "pattern": "Cargo.lock", "action": "run `make deps` instead" },
+                "[invalid"
+            ],
+            "allow": ["docs/build/**", "vendor.pb.go"]
+        }))
+        .unwrap();
+        let guard = GeneratedFileGuard::new().with_config(&config);
+
+        assert!(guard.check("src/gen/api.rs", None).unwrap().action.contains("rerun"));
+        assert_eq!(guard.check("schema.graphql", None).unwrap().action, "run `npm run codegen` instead");
+        // A project action wins over the built-in one
+        assert_eq!(guard.check("Cargo.lock", None).unwrap().action, "run `make deps` instead");
+        // Built-ins still apply
+        assert!(guard.check("yarn.lock", None).is_some());
+        // Exceptions beat patterns and markers alike
+        assert!(guard.check("docs/build/index.md", None).is_none());
+        assert!(guard.check("vendor.pb.go", Some("// DO NOT EDIT\n")).is_none());
+        assert!(matches!(config.patterns[0], GeneratedPattern::Glob(_)));
+    }
+
+    #[test]
+    fn test_error_suggests_the_command() {
+        let file = GeneratedFileGuard::new().check("Cargo.lock", None).unwrap();
+        let message = file.into_error().to_string();
+        assert!(message.contains("Cargo.lock"));
+        assert!(message.contains("cargo update -p <crate>"));
+        assert!(message.contains(ALLOW_GENERATED_PARAM));
+    }
+}
@@ -2,20 +2,84 @@
 
 use serde_json::{json, Value};
 use std::path::PathBuf;
+use std::sync::Arc;
 
 use crate::error::ToolError;
+use crate::tools::remote::{split_glob, RemoteWorkspace};
 use crate::tools::{BoxFuture, Tool, ToolExecutionContext, ToolOutput};
 
-use super::{path_to_display, path_to_glob_pattern};
+use super::roots::root_param_schema;
+use super::{path_to_glob_pattern, WorkspaceRoots};
 
 /// Tool for fast file pattern matching using glob patterns
 pub struct GlobFiles {
-    workspace: PathBuf,
+    /// Workspace roots; the primary one is searched unless `root` says otherwise
+    roots: WorkspaceRoots,
+    /// Remote workspace to search instead of `roots`
+    remote: Option<Arc<RemoteWorkspace>>,
 }
 
+/// Maximum number of files returned
+const LIMIT: usize = 100;
+
 impl GlobFiles {
     pub fn new(workspace: PathBuf) -> Self {
-        Self { workspace }
+        Self {
+            roots: WorkspaceRoots::new(workspace),
+            remote: None,
+        }
+    }
+
+    /// Let `root` pick these roots besides the workspace
+    pub fn with_additional_roots(mut self, roots: &[PathBuf]) -> Self {
+        self.roots = self.roots.with_additional(roots.iter().cloned());
+        self
+    }
+
+    /// Search a remote workspace, or the local one with `None`
+    pub fn with_remote(mut self, remote: Option<Arc<RemoteWorkspace>>) -> Self {
+        self.remote = remote;
+        self
+    }
+
+    /// Match files in the remote workspace: one listing of the directory the
+    /// pattern's literal prefix points at, matched here with the same rules
+    /// as the local search
+    async fn glob_remote(
+        &self,
+        remote: &RemoteWorkspace,
+        pattern: &str,
+        path: Option<&str>,
+    ) -> Result<Vec<String>, ToolError> {
+        let base = remote.resolve(path.unwrap_or("."))?;
+        let (prefix, rest) = split_glob(pattern);
+        let dir = if prefix.is_empty() {
+            base
+        } else {
+            remote.resolve(&format!("{}/{}", base, prefix))?
+        };
+
+        let matcher = glob::Pattern::new(&rest)
+            .map_err(|e| ToolError::InvalidParams(format!("Invalid glob pattern: {}", e)))?;
+        let options = glob::MatchOptions {
+            case_sensitive: true,
+            require_literal_separator: true,
+            require_literal_leading_dot: false,
+        };
+
+        let mut entries = match remote.list_files(&dir).await {
+            Ok(entries) => entries,
+            Err(ToolError::ResourceNotFound(_)) => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        entries.retain(|(path, _)| matcher.matches_with(path, options));
+        entries.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        Ok(entries
+            .into_iter()
+            .take(LIMIT)
+            .map(|(path, _)| remote.relative(&format!("{}/{}", dir.trim_end_matches('/'), path)))
+            .collect())
     }
 }
 
@@ -28,8 +92,12 @@ impl Tool for GlobFiles {
         crate::prompt::builtin::claude_code::tools::GLOB
     }
 
+    fn compact_description(&self) -> &str {
+        crate::prompt::builtin::claude_code::tools::GLOB_COMPACT
+    }
+
     fn parameters_schema(&self) -> Value {
-        json!({
+        let mut schema = json!({
             "type": "object",
             "properties": {
                 "pattern": {
@@ -42,7 +110,12 @@ impl Tool for GlobFiles {
                 }
             },
             "required": ["pattern"]
-        })
+        });
+        // Only offered when there is another root to pick
+        if self.roots.is_multi_root() && self.remote.is_none() {
+            schema["properties"]["root"] = root_param_schema();
+        }
+        schema
     }
 
     fn execute(&self, params: Value, _ctx: ToolExecutionContext) -> BoxFuture<'_, Result<ToolOutput, ToolError>> {
@@ -51,31 +124,45 @@ impl Tool for GlobFiles {
                 .as_str()
                 .ok_or_else(|| ToolError::InvalidParams("pattern is required".into()))?;
 
-            let base_path = if let Some(path) = params["path"].as_str() {
-                self.workspace.join(path)
-            } else {
-                self.workspace.clone()
-            };
-
-            let limit = 100;
+            if let Some(remote) = &self.remote {
+                let files = self.glob_remote(remote, pattern, params["path"].as_str()).await?;
+                return Ok(ToolOutput::success(json!({
+                    "files": files,
+                    "count": files.len(),
+                    "pattern": pattern
+                })));
+            }
 
-            // Construct full glob pattern with forward slashes (required by glob crate)
-            let full_pattern = path_to_glob_pattern(&base_path.join(pattern));
+            // Results are prefixed with their root's name unless they all
+            // come from the primary root
+            let selected = self.roots.select(&params["root"])?;
+            let prefixed = selected != [0];
 
             // Collect matching files with metadata
             let mut entries: Vec<(PathBuf, std::time::SystemTime)> = Vec::new();
 
-            for path in glob::glob(&full_pattern)
-                .map_err(|e| ToolError::InvalidParams(format!("Invalid glob pattern: {}", e)))?
-                .flatten()
-            {
-                if path.is_file() {
-                    let mtime = tokio::fs::metadata(&path)
-                        .await
-                        .ok()
-                        .and_then(|m| m.modified().ok())
-                        .unwrap_or(std::time::SystemTime::UNIX_EPOCH);
-                    entries.push((path, mtime));
+            for index in selected {
+                let root = &self.roots.paths()[index];
+                let base_path = match params["path"].as_str() {
+                    Some(path) => root.join(path),
+                    None => root.clone(),
+                };
+
+                // Construct full glob pattern with forward slashes (required by glob crate)
+                let full_pattern = path_to_glob_pattern(&base_path.join(pattern));
+
+                for path in glob::glob(&full_pattern)
+                    .map_err(|e| ToolError::InvalidParams(format!("Invalid glob pattern: {}", e)))?
+                    .flatten()
+                {
+                    if path.is_file() {
+                        let mtime = tokio::fs::metadata(&path)
+                            .await
+                            .ok()
+                            .and_then(|m| m.modified().ok())
+                            .unwrap_or(std::time::SystemTime::UNIX_EPOCH);
+                        entries.push((path, mtime));
+                    }
                 }
             }
 
@@ -83,16 +170,12 @@ impl Tool for GlobFiles {
             entries.sort_by(|a, b| b.1.cmp(&a.1));
 
             // Limit results
-            let entries: Vec<_> = entries.into_iter().take(limit).collect();
+            let entries: Vec<_> = entries.into_iter().take(LIMIT).collect();
 
             // Convert to relative paths with consistent forward slash separators
             let files: Vec<String> = entries
                 .iter()
-                .map(|(path, _)| {
-                    path.strip_prefix(&self.workspace)
-                        .map(path_to_display)
-                        .unwrap_or_else(|_| path_to_display(path))
-                })
+                .map(|(path, _)| self.roots.display(path, prefixed))
                 .collect();
 
             Ok(ToolOutput::success(json!({
@@ -102,4 +185,9 @@ impl Tool for GlobFiles {
             })))
         })
     }
+
+    fn is_idempotent(&self) -> bool {
+        true
+    }
 }
+
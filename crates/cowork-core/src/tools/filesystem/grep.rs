@@ -1,17 +1,26 @@
 //! Grep content search tool - A powerful search tool built on regex
 //!
 //! Supports full regex syntax, file filtering, context lines, and multiple output modes.
+//! Searches with ripgrep when it is available (much faster on large trees) and
+//! falls back to the built-in search otherwise; both return the same results.
+//! In a remote workspace the search runs as one ripgrep command on the remote
+//! host. The backend that ran is recorded in the output's `backend` metadata.
 
 use regex::{Regex, RegexBuilder};
 use serde_json::{json, Value};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+use tracing::warn;
 
 use crate::error::ToolError;
-use crate::tools::{BoxFuture, Tool, ToolExecutionContext, ToolOutput};
+use crate::tools::remote::RemoteWorkspace;
+use crate::tools::{Artifact, BoxFuture, Tool, ToolExecutionContext, ToolOutput};
 
-use super::{path_to_display, path_to_glob_pattern};
+use super::ripgrep::{self, RipgrepFile, RipgrepSearch};
+use super::roots::root_param_schema;
+use super::{path_to_glob_pattern, WorkspaceRoots};
 
 /// File type mappings (similar to ripgrep --type)
 fn get_type_extensions(type_name: &str) -> Option<Vec<&'static str>> {
@@ -45,14 +54,116 @@ fn get_type_extensions(type_name: &str) -> Option<Vec<&'static str>> {
     types.get(type_name).cloned()
 }
 
+/// ripgrep request for a search: the glob, or else the type's extensions
+/// (the same precedence as the built-in search)
+fn ripgrep_search<'a>(search: &GrepSearch<'a>) -> RipgrepSearch<'a> {
+    let glob = match (search.params["glob"].as_str(), search.params["type"].as_str()) {
+        (Some(glob), _) => Some(glob.to_string()),
+        (None, Some(type_name)) => get_type_extensions(type_name).map(|extensions| {
+            if extensions.len() == 1 {
+                format!("**/*.{}", extensions[0])
+            } else {
+                format!("**/*.{{{}}}", extensions.join(","))
+            }
+        }),
+        (None, None) => None,
+    };
+    RipgrepSearch {
+        pattern: search.pattern,
+        base_path: search.base_path,
+        glob,
+        case_insensitive: search.case_insensitive,
+        multiline: search.multiline,
+        first_match_only: search.output_mode == OutputMode::FilesWithMatches,
+    }
+}
+
 /// Tool for searching file contents with regex support
 pub struct GrepFiles {
-    workspace: PathBuf,
+    /// Workspace roots; the primary one is searched unless `root` says otherwise
+    roots: WorkspaceRoots,
+    /// ripgrep binary, if searches should use it
+    ripgrep: Option<PathBuf>,
+    /// Remote workspace to search (with its host's ripgrep) instead of `roots`
+    remote: Option<Arc<RemoteWorkspace>>,
 }
 
 impl GrepFiles {
+    /// Create the tool, using ripgrep if one is configured or on PATH
     pub fn new(workspace: PathBuf) -> Self {
-        Self { workspace }
+        Self {
+            roots: WorkspaceRoots::new(workspace),
+            ripgrep: ripgrep::default_binary(),
+            remote: None,
+        }
+    }
+
+    /// Let `root` pick these roots besides the workspace
+    pub fn with_additional_roots(mut self, roots: &[PathBuf]) -> Self {
+        self.roots = self.roots.with_additional(roots.iter().cloned());
+        self
+    }
+
+    /// Use this ripgrep binary, or only the built-in search with `None`
+    pub fn with_ripgrep(mut self, binary: Option<PathBuf>) -> Self {
+        self.ripgrep = binary;
+        self
+    }
+
+    /// Search a remote workspace, or the local one with `None`
+    pub fn with_remote(mut self, remote: Option<Arc<RemoteWorkspace>>) -> Self {
+        self.remote = remote;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputMode {
+    FilesWithMatches,
+    Count,
+    Content,
+}
+
+/// A parsed search request shared by both backends
+struct GrepSearch<'a> {
+    pattern: &'a str,
+    regex: &'a Regex,
+    base_path: &'a Path,
+    params: &'a Value,
+    case_insensitive: bool,
+    multiline: bool,
+    context_before: usize,
+    context_after: usize,
+    output_mode: OutputMode,
+    /// Prefix every result with its root's name (see `WorkspaceRoots::display`)
+    prefix_roots: bool,
+}
+
+/// Search results before offset/limit are applied, files in path order
+enum GrepResults {
+    Files(Vec<String>),
+    Counts(Vec<(String, usize)>),
+    Matches(Vec<GrepMatch>),
+}
+
+impl GrepResults {
+    /// Append the results of searching another root (same output mode)
+    fn merge(self, other: GrepResults) -> GrepResults {
+        match (self, other) {
+            (GrepResults::Files(mut a), GrepResults::Files(b)) => {
+                a.extend(b);
+                GrepResults::Files(a)
+            }
+            (GrepResults::Counts(mut a), GrepResults::Counts(b)) => {
+                a.extend(b);
+                GrepResults::Counts(a)
+            }
+            (GrepResults::Matches(mut a), GrepResults::Matches(b)) => {
+                a.extend(b);
+                GrepResults::Matches(a)
+            }
+            (a, _) => a,
+        }
     }
 }
 
@@ -74,8 +185,12 @@ impl Tool for GrepFiles {
         crate::prompt::builtin::claude_code::tools::GREP
     }
 
+    fn compact_description(&self) -> &str {
+        crate::prompt::builtin::claude_code::tools::GREP_COMPACT
+    }
+
     fn parameters_schema(&self) -> Value {
-        json!({
+        let mut schema = json!({
             "type": "object",
             "properties": {
                 "pattern": {
@@ -142,7 +257,12 @@ impl Tool for GrepFiles {
                 }
             },
             "required": ["pattern"]
-        })
+        });
+        // Only offered when there is another root to pick
+        if self.roots.is_multi_root() && self.remote.is_none() {
+            schema["properties"]["root"] = root_param_schema();
+        }
+        schema
     }
 
     fn execute(&self, params: Value, _ctx: ToolExecutionContext) -> BoxFuture<'_, Result<ToolOutput, ToolError>> {
@@ -156,7 +276,8 @@ impl Tool for GrepFiles {
             let multiline = params["multiline"].as_bool().unwrap_or(false);
             let show_line_numbers = params["-n"].as_bool().unwrap_or(true);
 
-            // Build regex
+            // Build regex - validated here for both backends, so a malformed
+            // pattern gets the same error whichever one runs
             let regex = RegexBuilder::new(pattern_str)
                 .case_insensitive(case_insensitive)
                 .multi_line(multiline)
@@ -164,11 +285,25 @@ impl Tool for GrepFiles {
                 .build()
                 .map_err(|e| ToolError::InvalidParams(format!("Invalid regex: {}", e)))?;
 
-            // Determine path
-            let base_path = if let Some(path) = params["path"].as_str() {
-                self.workspace.join(path)
+            // Determine the paths to search: one per selected root, whose
+            // results are prefixed with the root's name unless it is only
+            // the primary root
+            let (base_paths, prefix_roots) = if let Some(remote) = &self.remote {
+                (vec![PathBuf::from(remote.resolve(params["path"].as_str().unwrap_or("."))?)], false)
             } else {
-                self.workspace.clone()
+                let selected = self.roots.select(&params["root"])?;
+                let prefix_roots = selected != [0];
+                let base_paths = selected
+                    .into_iter()
+                    .map(|index| {
+                        let root = &self.roots.paths()[index];
+                        match params["path"].as_str() {
+                            Some(path) => root.join(path),
+                            None => root.clone(),
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                (base_paths, prefix_roots)
             };
 
             // Context lines - -C overrides -A and -B
@@ -184,31 +319,51 @@ impl Tool for GrepFiles {
                 params["-A"].as_u64().unwrap_or(0) as usize
             };
 
-            let output_mode = params["output_mode"]
-                .as_str()
-                .unwrap_or("files_with_matches");
+            let output_mode = match params["output_mode"].as_str().unwrap_or("files_with_matches") {
+                "files_with_matches" => OutputMode::FilesWithMatches,
+                "count" => OutputMode::Count,
+                _ => OutputMode::Content,
+            };
             let head_limit = params["head_limit"].as_u64().unwrap_or(0) as usize;
             let offset = params["offset"].as_u64().unwrap_or(0) as usize;
 
-            // Get files to search
-            let files = self.get_files_to_search(&base_path, &params).await?;
-
-            // Process based on output mode
-            match output_mode {
-                "files_with_matches" => {
-                    let mut matching_files = Vec::new();
-
-                    for file_path in files {
-                        if is_binary_file(&file_path).await {
-                            continue;
-                        }
+            let mut results: Option<GrepResults> = None;
+            let mut backend = "native";
+            for base_path in &base_paths {
+                let search = GrepSearch {
+                    pattern: pattern_str,
+                    regex: &regex,
+                    base_path,
+                    params: &params,
+                    case_insensitive,
+                    multiline,
+                    context_before,
+                    context_after,
+                    output_mode,
+                    prefix_roots,
+                };
 
-                        if self.file_has_match(&file_path, &regex, multiline).await {
-                            let relative = self.relative_path(&file_path);
-                            matching_files.push(relative);
+                let (found, used) = match (&self.remote, &self.ripgrep) {
+                    (Some(remote), _) => (self.search_remote(remote, &search).await?, "remote"),
+                    (None, Some(binary)) => match self.search_ripgrep(binary, &search).await {
+                        Ok(results) => (results, "ripgrep"),
+                        Err(e) => {
+                            warn!("{}; falling back to built-in search", e);
+                            (self.search_native(&search).await?, "native")
                         }
-                    }
+                    },
+                    (None, None) => (self.search_native(&search).await?, "native"),
+                };
+                backend = used;
+                results = Some(match results {
+                    Some(results) => results.merge(found),
+                    None => found,
+                });
+            }
+            let results = results.unwrap_or(GrepResults::Files(Vec::new()));
 
+            let output = match results {
+                GrepResults::Files(matching_files) => {
                     // Apply offset and limit
                     let total = matching_files.len();
                     let result: Vec<_> = matching_files
@@ -217,58 +372,36 @@ impl Tool for GrepFiles {
                         .take(if head_limit > 0 { head_limit } else { usize::MAX })
                         .collect();
 
-                    Ok(ToolOutput::success(json!({
+                    ToolOutput::success(json!({
                         "files": result,
                         "count": result.len(),
                         "total_matches": total,
                         "pattern": pattern_str
-                    })))
+                    }))
                 }
-                "count" => {
-                    let mut file_counts: Vec<(String, usize)> = Vec::new();
-                    let mut total_count = 0;
-
-                    for file_path in files {
-                        if is_binary_file(&file_path).await {
-                            continue;
-                        }
-
-                        let count = self.count_matches(&file_path, &regex, multiline).await;
-                        if count > 0 {
-                            let relative = self.relative_path(&file_path);
-                            file_counts.push((relative, count));
-                            total_count += count;
-                        }
-                    }
+                GrepResults::Counts(file_counts) => {
+                    let total_count: usize = file_counts.iter().map(|(_, c)| c).sum();
 
                     // Apply offset and limit
-                    let result: Vec<_> = file_counts
+                    let file_counts: Vec<_> = file_counts
                         .into_iter()
                         .skip(offset)
                         .take(if head_limit > 0 { head_limit } else { usize::MAX })
-                        .map(|(f, c)| json!({ "file": f, "count": c }))
                         .collect();
+                    let result: Vec<_> = file_counts.iter().map(|(f, c)| json!({ "file": f, "count": c })).collect();
+                    let table = Artifact::table(
+                        vec!["file".to_string(), "count".to_string()],
+                        file_counts.into_iter().map(|(f, c)| vec![f, c.to_string()]).collect(),
+                    );
 
-                    Ok(ToolOutput::success(json!({
+                    ToolOutput::success(json!({
                         "counts": result,
                         "total_matches": total_count,
                         "pattern": pattern_str
-                    })))
+                    }))
+                    .with_artifact(table)
                 }
-                _ => {
-                    let mut matches: Vec<GrepMatch> = Vec::new();
-
-                    for file_path in files {
-                        if is_binary_file(&file_path).await {
-                            continue;
-                        }
-
-                        let file_matches = self
-                            .find_matches(&file_path, &regex, context_before, context_after, multiline)
-                            .await;
-                        matches.extend(file_matches);
-                    }
-
+                GrepResults::Matches(matches) => {
                     // Apply offset and limit
                     let total = matches.len();
                     let matches: Vec<_> = matches
@@ -318,23 +451,213 @@ impl Tool for GrepFiles {
                         })
                         .collect();
 
-                    Ok(ToolOutput::success(json!({
+                    let table = Artifact::table(
+                        vec!["file".to_string(), "line".to_string(), "content".to_string()],
+                        matches
+                            .into_iter()
+                            .map(|m| vec![m.file, m.line_number.to_string(), m.content])
+                            .collect(),
+                    );
+
+                    ToolOutput::success(json!({
                         "matches": formatted,
                         "count": formatted.len(),
                         "total_matches": total,
                         "pattern": pattern_str
-                    })))
+                    }))
+                    .with_artifact(table)
                 }
-            }
+            };
+
+            Ok(output.with_metadata("backend", backend))
         })
     }
+
+    fn is_idempotent(&self) -> bool {
+        true
+    }
 }
 
 impl GrepFiles {
-    fn relative_path(&self, path: &Path) -> String {
-        path.strip_prefix(&self.workspace)
-            .map(path_to_display)
-            .unwrap_or_else(|_| path_to_display(path))
+    /// Search with the built-in regex engine
+    async fn search_native(&self, search: &GrepSearch<'_>) -> Result<GrepResults, ToolError> {
+        let regex = search.regex;
+        let multiline = search.multiline;
+
+        // Get files to search
+        let files = self.get_files_to_search(search.base_path, search.params).await?;
+
+        let results = match search.output_mode {
+            OutputMode::FilesWithMatches => {
+                let mut matching_files = Vec::new();
+
+                for file_path in files {
+                    if is_binary_file(&file_path).await {
+                        continue;
+                    }
+
+                    if self.file_has_match(&file_path, regex, multiline).await {
+                        matching_files.push(self.relative_path(&file_path, search));
+                    }
+                }
+                GrepResults::Files(matching_files)
+            }
+            OutputMode::Count => {
+                let mut file_counts: Vec<(String, usize)> = Vec::new();
+
+                for file_path in files {
+                    if is_binary_file(&file_path).await {
+                        continue;
+                    }
+
+                    let count = self.count_matches(&file_path, regex, multiline).await;
+                    if count > 0 {
+                        file_counts.push((self.relative_path(&file_path, search), count));
+                    }
+                }
+                GrepResults::Counts(file_counts)
+            }
+            OutputMode::Content => {
+                let mut matches: Vec<GrepMatch> = Vec::new();
+
+                for file_path in files {
+                    if is_binary_file(&file_path).await {
+                        continue;
+                    }
+
+                    let file_matches = self
+                        .find_matches(&file_path, search)
+                        .await;
+                    matches.extend(file_matches);
+                }
+                GrepResults::Matches(matches)
+            }
+        };
+        Ok(results)
+    }
+
+    /// Search with ripgrep, shaping its matches like the built-in search
+    async fn search_ripgrep(&self, binary: &Path, search: &GrepSearch<'_>) -> Result<GrepResults, String> {
+        let mut files = ripgrep::search(binary, &ripgrep_search(search)).await?;
+        // ripgrep searches in parallel, so its file order varies
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let mut searched = Vec::with_capacity(files.len());
+        for file in files {
+            if !is_binary_file(&file.path).await {
+                searched.push(file);
+            }
+        }
+        Ok(self.ripgrep_results(searched, search).await)
+    }
+
+    /// Search a remote workspace with one ripgrep run on its host
+    async fn search_remote(&self, remote: &RemoteWorkspace, search: &GrepSearch<'_>) -> Result<GrepResults, ToolError> {
+        let mut files = ripgrep::search_remote(remote, &ripgrep_search(search)).await?;
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(self.ripgrep_results(files, search).await)
+    }
+
+    /// Shape ripgrep's matches like the built-in search
+    async fn ripgrep_results(&self, searched: Vec<RipgrepFile>, search: &GrepSearch<'_>) -> GrepResults {
+        match search.output_mode {
+            OutputMode::FilesWithMatches => {
+                GrepResults::Files(searched.iter().map(|f| self.relative_path(&f.path, search)).collect())
+            }
+            OutputMode::Count => GrepResults::Counts(
+                searched
+                    .iter()
+                    .map(|f| (self.relative_path(&f.path, search), f.hits.iter().map(|h| h.submatches.len()).sum()))
+                    .filter(|(_, count)| *count > 0)
+                    .collect(),
+            ),
+            OutputMode::Content => {
+                let mut matches = Vec::new();
+                for file in &searched {
+                    matches.extend(self.ripgrep_matches(file, search).await);
+                }
+                GrepResults::Matches(matches)
+            }
+        }
+    }
+
+    /// Turn ripgrep hits into matches with the same content and context
+    /// lines `find_matches` produces
+    async fn ripgrep_matches(&self, file: &RipgrepFile, search: &GrepSearch<'_>) -> Vec<GrepMatch> {
+        let relative = self.relative_path(&file.path, search);
+        let (context_before, context_after) = (search.context_before, search.context_after);
+
+        // Context lines come from the file itself; only files with matches are read
+        let lines: Vec<String> = if context_before > 0 || context_after > 0 {
+            let content = match &self.remote {
+                Some(remote) => remote
+                    .read(&file.path.to_string_lossy())
+                    .await
+                    .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+                    .ok(),
+                None => tokio::fs::read_to_string(&file.path).await.ok(),
+            };
+            content
+                .map(|content| content.lines().map(String::from).collect())
+                .unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        // 1-based line numbers in `range` that exist in the file
+        let context = |range: std::ops::Range<usize>| -> Vec<(usize, String)> {
+            range
+                .filter_map(|n| lines.get(n.wrapping_sub(1)).map(|line| (n, line.clone())))
+                .collect()
+        };
+
+        let mut matches = Vec::new();
+        if search.multiline {
+            // One match per regex match, reported with its first line
+            for hit in &file.hits {
+                for (start, text) in &hit.submatches {
+                    let line = hit.line_number + hit.text[..*start].matches('\n').count();
+                    matches.push(GrepMatch {
+                        file: relative.clone(),
+                        line_number: line,
+                        content: text.clone(),
+                        context_before: context(line.saturating_sub(context_before).max(1)..line),
+                        context_after: context(line + 1..line + 1 + context_after),
+                    });
+                }
+            }
+        } else {
+            // Like the line-by-line search, lines taken as after-context are
+            // not matched themselves, and before-context never reaches back
+            // past the previous match
+            let mut consumed = 0;
+            for hit in &file.hits {
+                let line = hit.line_number;
+                if line <= consumed {
+                    continue;
+                }
+                let before = context(line.saturating_sub(context_before).max(consumed + 1)..line);
+                let after = context(line + 1..line + 1 + context_after);
+                consumed = line + after.len();
+
+                let content = hit.text.strip_suffix('\n').unwrap_or(&hit.text);
+                let content = content.strip_suffix('\r').unwrap_or(content);
+                matches.push(GrepMatch {
+                    file: relative.clone(),
+                    line_number: line,
+                    content: content.to_string(),
+                    context_before: before,
+                    context_after: after,
+                });
+            }
+        }
+        matches
+    }
+
+    fn relative_path(&self, path: &Path, search: &GrepSearch<'_>) -> String {
+        if let Some(remote) = &self.remote {
+            return remote.relative(&path.to_string_lossy());
+        }
+        self.roots.display(path, search.prefix_roots)
     }
 
     async fn get_files_to_search(
@@ -349,30 +672,29 @@ impl GrepFiles {
         let file_glob = params["glob"].as_str();
         let file_type = params["type"].as_str();
 
-        // Build glob pattern (use forward slashes for glob crate compatibility)
+        // Build glob pattern (use forward slashes for glob crate compatibility).
+        // The glob crate has no `{a,b}` alternation, so types filter by extension.
+        let mut extensions = None;
         let glob_pattern = if let Some(glob) = file_glob {
             path_to_glob_pattern(&base_path.join(glob))
-        } else if let Some(type_name) = file_type {
-            if let Some(extensions) = get_type_extensions(type_name) {
-                let ext_pattern = if extensions.len() == 1 {
-                    extensions[0].to_string()
-                } else {
-                    format!("{{{}}}", extensions.join(","))
-                };
-                path_to_glob_pattern(&base_path.join(format!("**/*.{}", ext_pattern)))
-            } else {
-                // Unknown type, search all files
-                path_to_glob_pattern(&base_path.join("**/*"))
-            }
         } else {
+            // Unknown type, search all files
+            extensions = file_type.and_then(get_type_extensions);
             path_to_glob_pattern(&base_path.join("**/*"))
         };
 
-        let files: Vec<PathBuf> = glob::glob(&glob_pattern)
+        let mut files: Vec<PathBuf> = glob::glob(&glob_pattern)
             .map_err(|e| ToolError::InvalidParams(format!("Invalid glob: {}", e)))?
             .filter_map(|e| e.ok())
             .filter(|p| p.is_file())
+            .filter(|p| match &extensions {
+                Some(extensions) => p
+                    .extension()
+                    .is_some_and(|ext| extensions.contains(&ext.to_string_lossy().as_ref())),
+                None => true,
+            })
             .collect();
+        files.sort();
 
         Ok(files)
     }
@@ -415,15 +737,10 @@ impl GrepFiles {
         0
     }
 
-    async fn find_matches(
-        &self,
-        path: &PathBuf,
-        regex: &Regex,
-        context_before: usize,
-        context_after: usize,
-        multiline: bool,
-    ) -> Vec<GrepMatch> {
-        let relative = self.relative_path(path);
+    async fn find_matches(&self, path: &PathBuf, search: &GrepSearch<'_>) -> Vec<GrepMatch> {
+        let (regex, multiline) = (search.regex, search.multiline);
+        let (context_before, context_after) = (search.context_before, search.context_after);
+        let relative = self.relative_path(path, search);
         let mut matches = Vec::new();
 
         if multiline {
@@ -1,25 +1,47 @@
 //! Filesystem tools for file operations
 
+mod batch_edit;
+mod codebase_search;
+mod conventions;
 mod document;
 mod edit;
 mod export;
+mod finish;
+mod generated;
 mod glob;
 mod grep;
+mod office;
 mod path_utils;
 mod read;
+mod ripgrep;
+mod roots;
+mod search;
+mod symbols;
+mod verify;
 mod write;
 
 // Re-export tools
+pub use batch_edit::{BatchEdit, BATCH_EDIT_TOOL_NAME, MAX_BATCH_FILE_BYTES};
+pub use codebase_search::CodebaseSearch;
+pub use conventions::{describe_workspace_conventions, get_file_conventions, FileConventions, IndentStyle, LineEnding};
 pub use edit::EditFile;
 pub use export::ExportDocument;
+pub use finish::{FinishFile, FINISH_FILE_TOOL_NAME};
+pub use generated::{allows_generated, GeneratedFile, GeneratedFileGuard, ALLOW_GENERATED_PARAM, MARKER_LINES};
 pub use glob::GlobFiles;
 pub use grep::GrepFiles;
+pub use office::{WriteDocx, WritePptx, WriteXlsx};
 pub use read::ReadFile;
+pub use ripgrep::{find_ripgrep, RIPGREP_ENV};
+pub use roots::{WorkspaceRoots, ALL_ROOTS};
+pub use search::SearchFiles;
+pub use verify::{SyntaxProblem, SyntaxVerifier, Verification, VerifyStatus, DEFAULT_VERIFY_TIMEOUT};
 pub use write::WriteFile;
 
 // Re-export path utilities for use by other modules
 pub use path_utils::{
-    normalize_path, path_needs_shell_escape, path_to_display, path_to_glob_pattern, path_to_uri,
-    percent_decode_path, percent_encode_path, shell_escape_path, shell_escape_str, uri_to_path,
-    validate_path, validate_write_path,
+    is_drive_relative, is_reserved_device_name, normalize_path, path_needs_shell_escape, path_starts_with,
+    path_to_display, path_to_glob_pattern, path_to_uri, percent_decode_path, percent_encode_path,
+    shell_escape_path, shell_escape_str, uri_to_path, validate_path, validate_path_in_roots, validate_path_with_scratch,
+    validate_write_path, validate_write_path_in_roots, validate_write_path_with_scratch,
 };
@@ -0,0 +1,273 @@
+//! WriteDocx: markdown-style text to a Word document
+
+use std::path::PathBuf;
+
+use docx_rs::{
+    AbstractNumbering, Docx, IndentLevel, Level, LevelJc, LevelText, NumberFormat, Numbering, NumberingId,
+    Paragraph, Run, Start, Style, StyleType, Table, TableCell, TableRow,
+};
+use serde_json::{json, Value};
+
+use crate::error::ToolError;
+use crate::tools::{BoxFuture, Tool, ToolExecutionContext, ToolOutput};
+
+use super::{created, generation_error, prepare_output};
+
+const DOCX_MIME: &str = "application/vnd.openxmlformats-officedocument.wordprocessingml.document";
+
+/// Numbering definition used for every bullet list
+const BULLETS: usize = 1;
+
+/// Heading styles by level: (style id, display name, half-point size)
+const HEADINGS: [(&str, &str, usize); 3] = [
+    ("Heading1", "heading 1", 32),
+    ("Heading2", "heading 2", 28),
+    ("Heading3", "heading 3", 24),
+];
+
+/// Tool for writing a structured Word document
+pub struct WriteDocx {
+    workspace: PathBuf,
+}
+
+impl WriteDocx {
+    pub fn new(workspace: PathBuf) -> Self {
+        Self { workspace }
+    }
+}
+
+impl Tool for WriteDocx {
+    fn name(&self) -> &str {
+        "WriteDocx"
+    }
+
+    fn description(&self) -> &str {
+        r####"Write a Word document (.docx) from markdown-style content.
+
+Supported in content:
+- Headings: lines starting with "# ", "## " or "### "
+- Paragraphs: consecutive lines of text, separated by blank lines
+- Bullet lists: lines starting with "- " or "* "
+- Tables: consecutive lines starting with "|", the first row being the header
+  (a "|---|---|" separator row is optional and ignored)
+
+Example:
+{
+  "file_path": "report.docx",
+  "title": "Quarterly Report",
+  "content": "# Summary\nRevenue grew 12%.\n\n- New customers: 40\n- Churn: 2%\n\n| Region | Revenue |\n|---|---|\n| EMEA | 1.2M |"
+}"####
+    }
+
+    fn compact_description(&self) -> &str {
+        "Writes a Word document (.docx) from markdown-style content: headings, paragraphs, bullet lists and | tables."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "file_path": {
+                    "type": "string",
+                    "description": "Path for the output file (must end in .docx)"
+                },
+                "title": {
+                    "type": "string",
+                    "description": "Optional document title, shown above the content"
+                },
+                "content": {
+                    "type": "string",
+                    "description": "Markdown-style content: headings, paragraphs, bullet lists and pipe tables"
+                }
+            },
+            "required": ["file_path", "content"]
+        })
+    }
+
+    fn execute(&self, params: Value, _ctx: ToolExecutionContext) -> BoxFuture<'_, Result<ToolOutput, ToolError>> {
+        Box::pin(async move {
+            let content = params["content"]
+                .as_str()
+                .ok_or_else(|| ToolError::InvalidParams("content is required".into()))?;
+            let title = params["title"].as_str().filter(|t| !t.trim().is_empty());
+            let path = prepare_output(&self.workspace, &params, "docx").await?;
+
+            let blocks = parse_blocks(content);
+            let file = std::fs::File::create(&path).map_err(ToolError::Io)?;
+            build_document(title, &blocks)
+                .build()
+                .pack(file)
+                .map_err(|e| generation_error("write DOCX", e))?;
+
+            let count = |f: fn(&Block) -> bool| blocks.iter().filter(|b| f(b)).count();
+            created(
+                &path,
+                DOCX_MIME,
+                json!({
+                    "format": "docx",
+                    "headings": count(|b| matches!(b, Block::Heading(..))),
+                    "paragraphs": count(|b| matches!(b, Block::Paragraph(_))),
+                    "bullets": count(|b| matches!(b, Block::Bullet(_))),
+                    "tables": count(|b| matches!(b, Block::Table(_))),
+                }),
+            )
+        })
+    }
+}
+
+/// A block of the input
+#[derive(Debug, PartialEq)]
+enum Block {
+    /// Heading level (1-3) and text
+    Heading(usize, String),
+    Paragraph(String),
+    Bullet(String),
+    /// Rows of cells, the first row being the header
+    Table(Vec<Vec<String>>),
+}
+
+/// Split markdown-style content into blocks
+fn parse_blocks(content: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut table: Vec<Vec<String>> = Vec::new();
+
+    fn flush(blocks: &mut Vec<Block>, paragraph: &mut Vec<&str>, table: &mut Vec<Vec<String>>) {
+        if !paragraph.is_empty() {
+            blocks.push(Block::Paragraph(paragraph.join(" ")));
+            paragraph.clear();
+        }
+        if !table.is_empty() {
+            blocks.push(Block::Table(std::mem::take(table)));
+        }
+    }
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.starts_with('|') {
+            if !paragraph.is_empty() {
+                flush(&mut blocks, &mut paragraph, &mut table);
+            }
+            let cells = table_cells(line);
+            if !is_separator_row(&cells) {
+                table.push(cells);
+            }
+            continue;
+        }
+
+        let heading = line
+            .split_once(' ')
+            .filter(|(hashes, _)| (1..=HEADINGS.len()).contains(&hashes.len()) && hashes.chars().all(|c| c == '#'));
+        let bullet = line.strip_prefix("- ").or_else(|| line.strip_prefix("* "));
+
+        if line.is_empty() {
+            flush(&mut blocks, &mut paragraph, &mut table);
+        } else if let Some((hashes, text)) = heading {
+            flush(&mut blocks, &mut paragraph, &mut table);
+            blocks.push(Block::Heading(hashes.len(), text.trim().to_string()));
+        } else if let Some(item) = bullet {
+            flush(&mut blocks, &mut paragraph, &mut table);
+            blocks.push(Block::Bullet(item.trim().to_string()));
+        } else {
+            if !table.is_empty() {
+                flush(&mut blocks, &mut paragraph, &mut table);
+            }
+            paragraph.push(line);
+        }
+    }
+    flush(&mut blocks, &mut paragraph, &mut table);
+    blocks
+}
+
+/// The cells of a `| a | b |` row
+fn table_cells(line: &str) -> Vec<String> {
+    let inner = line.trim_start_matches('|');
+    let inner = inner.strip_suffix('|').unwrap_or(inner);
+    inner.split('|').map(|cell| cell.trim().to_string()).collect()
+}
+
+/// Whether a row is the `|---|:---:|` line under a header
+fn is_separator_row(cells: &[String]) -> bool {
+    cells
+        .iter()
+        .all(|c| !c.is_empty() && c.contains('-') && c.chars().all(|ch| matches!(ch, '-' | ':' | ' ')))
+}
+
+fn build_document(title: Option<&str>, blocks: &[Block]) -> Docx {
+    let mut doc = Docx::new()
+        .add_style(Style::new("Title", StyleType::Paragraph).name("Title").size(40).bold())
+        .add_abstract_numbering(AbstractNumbering::new(BULLETS).add_level(Level::new(
+            0,
+            Start::new(1),
+            NumberFormat::new("bullet"),
+            LevelText::new("•"),
+            LevelJc::new("left"),
+        )))
+        .add_numbering(Numbering::new(BULLETS, BULLETS));
+    for (id, name, size) in HEADINGS {
+        doc = doc.add_style(Style::new(id, StyleType::Paragraph).name(name).size(size).bold());
+    }
+
+    if let Some(title) = title {
+        doc = doc.add_paragraph(text_paragraph(title).style("Title"));
+    }
+
+    for block in blocks {
+        doc = match block {
+            Block::Heading(level, text) => doc.add_paragraph(text_paragraph(text).style(HEADINGS[level - 1].0)),
+            Block::Paragraph(text) => doc.add_paragraph(text_paragraph(text)),
+            Block::Bullet(text) => doc.add_paragraph(
+                text_paragraph(text).numbering(NumberingId::new(BULLETS), IndentLevel::new(0)),
+            ),
+            Block::Table(rows) => doc.add_table(build_table(rows)),
+        };
+    }
+    doc
+}
+
+fn text_paragraph(text: &str) -> Paragraph {
+    Paragraph::new().add_run(Run::new().add_text(text))
+}
+
+/// A bordered table with a bold header row; short rows are padded
+fn build_table(rows: &[Vec<String>]) -> Table {
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let rows = rows
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let cells = (0..columns)
+                .map(|c| {
+                    let mut run = Run::new().add_text(row.get(c).map(String::as_str).unwrap_or(""));
+                    if i == 0 {
+                        run = run.bold();
+                    }
+                    TableCell::new().add_paragraph(Paragraph::new().add_run(run))
+                })
+                .collect();
+            TableRow::new(cells)
+        })
+        .collect();
+    Table::new(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_blocks() {
+        let content = "# Intro\nFirst line\nsecond line\n\n- one\n* two\n| A | B |\n|---|:-:|\n| 1 | 2 |\nAfter\n####### not a heading";
+        assert_eq!(
+            parse_blocks(content),
+            vec![
+                Block::Heading(1, "Intro".into()),
+                Block::Paragraph("First line second line".into()),
+                Block::Bullet("one".into()),
+                Block::Bullet("two".into()),
+                Block::Table(vec![vec!["A".into(), "B".into()], vec!["1".into(), "2".into()]]),
+                Block::Paragraph("After ####### not a heading".into()),
+            ]
+        );
+    }
+}
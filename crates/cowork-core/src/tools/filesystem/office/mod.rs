@@ -0,0 +1,75 @@
+//! Office document generation tools
+//!
+//! Unlike `ExportDocument`, which turns one blob of text into whichever
+//! format the extension names, each of these tools takes structured input
+//! for its format:
+//! - `WriteDocx`: markdown-style text (headings, paragraphs, bullet lists,
+//!   pipe tables) → Word document (docx-rs)
+//! - `WriteXlsx`: row objects or CSV → one typed worksheet (rust_xlsxwriter)
+//! - `WritePptx`: slides with a title, bullets and an optional image →
+//!   PowerPoint presentation (OOXML written directly)
+//!
+//! The output path goes through the same workspace validation as Write, and
+//! the created file is returned as a `File` artifact. All three require
+//! approval like Write.
+
+mod docx;
+mod pptx;
+mod xlsx;
+
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+
+use crate::error::ToolError;
+use crate::tools::{Artifact, ToolOutput};
+
+use super::{path_to_display, validate_path, validate_write_path};
+
+pub use docx::WriteDocx;
+pub use pptx::WritePptx;
+pub use xlsx::WriteXlsx;
+
+/// Resolve and validate the output path, creating missing parent directories
+///
+/// The path must end in `.{extension}`.
+async fn prepare_output(workspace: &Path, params: &Value, extension: &str) -> Result<PathBuf, ToolError> {
+    let path_str = params["file_path"]
+        .as_str()
+        .ok_or_else(|| ToolError::InvalidParams("file_path is required".into()))?;
+    let path = workspace.join(path_str);
+
+    let matches = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case(extension));
+    if !matches {
+        return Err(ToolError::InvalidParams(format!("file_path must end in .{}", extension)));
+    }
+
+    validate_write_path(&path, workspace)?;
+    if let Some(parent) = path.parent() {
+        if parent.exists() {
+            validate_path(parent, workspace)?;
+        } else {
+            tokio::fs::create_dir_all(parent).await.map_err(ToolError::Io)?;
+        }
+    }
+    Ok(path)
+}
+
+/// The tool result for a generated file, with `details` merged in
+fn created(path: &Path, mime: &str, details: Value) -> Result<ToolOutput, ToolError> {
+    let bytes = std::fs::metadata(path).map_err(ToolError::Io)?.len();
+    let display = path_to_display(path);
+    let mut content = json!({ "path": display, "bytes_written": bytes });
+    if let (Some(content), Value::Object(details)) = (content.as_object_mut(), details) {
+        content.extend(details);
+    }
+    Ok(ToolOutput::success(content).with_artifact(Artifact::file(display, Some(mime.to_string()))))
+}
+
+/// A failure while building or saving a document
+fn generation_error(what: &str, e: impl std::fmt::Display) -> ToolError {
+    ToolError::ExecutionFailed(format!("Failed to {}: {}", what, e))
+}
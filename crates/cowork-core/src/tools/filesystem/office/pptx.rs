@@ -0,0 +1,455 @@
+//! WritePptx: slides with a title, bullets and an optional image
+//!
+//! There is no PowerPoint writer among our dependencies, so this builds the
+//! smallest package PowerPoint and LibreOffice accept: one blank layout on
+//! one master with a plain theme, and a slide per entry holding a title text
+//! box, a bulleted text box and, if given, a picture on the right half.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use quick_xml::escape::escape;
+use serde_json::{json, Value};
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+use crate::error::ToolError;
+use crate::session::{image_dimensions, sniff_image_type};
+use crate::tools::{BoxFuture, Tool, ToolExecutionContext, ToolOutput};
+
+use super::{created, generation_error, prepare_output, validate_path};
+
+const PPTX_MIME: &str = "application/vnd.openxmlformats-officedocument.presentationml.presentation";
+
+/// 16:9 slide size in EMU (914400 per inch)
+const SLIDE_WIDTH: u64 = 12_192_000;
+const SLIDE_HEIGHT: u64 = 6_858_000;
+const MARGIN: u64 = 457_200;
+const TITLE_HEIGHT: u64 = 1_143_000;
+/// Top of the bullets and image area
+const BODY_TOP: u64 = MARGIN + TITLE_HEIGHT + 182_880;
+
+const NS: &str = r#"xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships" xmlns:p="http://schemas.openxmlformats.org/presentationml/2006/main""#;
+const XML_HEADER: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#;
+const REL_NS: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+
+/// Tool for writing a PowerPoint presentation
+pub struct WritePptx {
+    workspace: PathBuf,
+}
+
+impl WritePptx {
+    pub fn new(workspace: PathBuf) -> Self {
+        Self { workspace }
+    }
+}
+
+impl Tool for WritePptx {
+    fn name(&self) -> &str {
+        "WritePptx"
+    }
+
+    fn description(&self) -> &str {
+        r#"Write a PowerPoint presentation (.pptx) from a list of slides.
+
+Each slide has:
+- title: slide title
+- bullets (optional): array of bullet point strings
+- image (optional): path to a PNG, JPEG or GIF in the workspace, placed on the
+  right half of the slide
+
+Example:
+{
+  "file_path": "deck.pptx",
+  "title": "Q1 Review",
+  "slides": [
+    {"title": "Highlights", "bullets": ["Revenue up 12%", "Two new regions"]},
+    {"title": "Growth", "bullets": ["EMEA led"], "image": "charts/growth.png"}
+  ]
+}"#
+    }
+
+    fn compact_description(&self) -> &str {
+        "Writes a PowerPoint presentation (.pptx) from slides, each with a title and optional bullets and image."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "file_path": {
+                    "type": "string",
+                    "description": "Path for the output file (must end in .pptx)"
+                },
+                "title": {
+                    "type": "string",
+                    "description": "Optional presentation title (document properties)"
+                },
+                "slides": {
+                    "type": "array",
+                    "description": "Slides in order",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "title": { "type": "string" },
+                            "bullets": { "type": "array", "items": { "type": "string" } },
+                            "image": { "type": "string", "description": "Path to a PNG, JPEG or GIF image" }
+                        },
+                        "required": ["title"]
+                    }
+                }
+            },
+            "required": ["file_path", "slides"]
+        })
+    }
+
+    fn execute(&self, params: Value, _ctx: ToolExecutionContext) -> BoxFuture<'_, Result<ToolOutput, ToolError>> {
+        Box::pin(async move {
+            let entries = params["slides"]
+                .as_array()
+                .filter(|s| !s.is_empty())
+                .ok_or_else(|| ToolError::InvalidParams("slides must be a non-empty array".into()))?;
+            let slides = entries
+                .iter()
+                .enumerate()
+                .map(|(i, entry)| Slide::from_param(entry, &self.workspace).map_err(|e| prefix_error(i + 1, e)))
+                .collect::<Result<Vec<_>, _>>()?;
+            let title = params["title"].as_str();
+            let path = prepare_output(&self.workspace, &params, "pptx").await?;
+
+            write_package(&path, title, &slides).map_err(|e| generation_error("write PPTX", e))?;
+
+            created(
+                &path,
+                PPTX_MIME,
+                json!({
+                    "format": "pptx",
+                    "slides": slides.len(),
+                    "images": slides.iter().filter(|s| s.image.is_some()).count(),
+                }),
+            )
+        })
+    }
+}
+
+fn prefix_error(slide: usize, e: ToolError) -> ToolError {
+    match e {
+        ToolError::InvalidParams(msg) => ToolError::InvalidParams(format!("slide {}: {}", slide, msg)),
+        other => other,
+    }
+}
+
+struct Slide {
+    title: String,
+    bullets: Vec<String>,
+    image: Option<Image>,
+}
+
+struct Image {
+    bytes: Vec<u8>,
+    extension: &'static str,
+    width: u32,
+    height: u32,
+}
+
+impl Slide {
+    fn from_param(entry: &Value, workspace: &Path) -> Result<Self, ToolError> {
+        let title = entry["title"]
+            .as_str()
+            .ok_or_else(|| ToolError::InvalidParams("title is required".into()))?
+            .to_string();
+        let bullets = match &entry["bullets"] {
+            Value::Null => Vec::new(),
+            Value::Array(items) => items
+                .iter()
+                .map(|b| b.as_str().map(str::to_string))
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| ToolError::InvalidParams("bullets must be strings".into()))?,
+            _ => return Err(ToolError::InvalidParams("bullets must be an array".into())),
+        };
+        let image = entry["image"].as_str().map(|p| Image::load(p, workspace)).transpose()?;
+        Ok(Self { title, bullets, image })
+    }
+}
+
+impl Image {
+    fn load(path: &str, workspace: &Path) -> Result<Self, ToolError> {
+        let path = validate_path(&workspace.join(path), workspace)?;
+        let bytes = std::fs::read(&path).map_err(ToolError::Io)?;
+        let extension = match sniff_image_type(&bytes) {
+            Some("image/png") => "png",
+            Some("image/jpeg") => "jpeg",
+            Some("image/gif") => "gif",
+            _ => {
+                return Err(ToolError::InvalidParams(format!(
+                    "{} is not a PNG, JPEG or GIF image",
+                    path.display()
+                )));
+            }
+        };
+        let (width, height) = image_dimensions(&bytes)
+            .filter(|&(w, h)| w > 0 && h > 0)
+            .ok_or_else(|| ToolError::InvalidParams(format!("Can't read the size of {}", path.display())))?;
+        Ok(Self { bytes, extension, width, height })
+    }
+
+    /// Offset and size that fit the image into a box, keeping its aspect ratio
+    fn fit(&self, x: u64, y: u64, max_w: u64, max_h: u64) -> (u64, u64, u64, u64) {
+        let scale = (max_w as f64 / self.width as f64).min(max_h as f64 / self.height as f64);
+        let w = (self.width as f64 * scale) as u64;
+        let h = (self.height as f64 * scale) as u64;
+        (x + (max_w - w.min(max_w)) / 2, y + (max_h - h.min(max_h)) / 2, w, h)
+    }
+}
+
+fn write_package(path: &Path, title: Option<&str>, slides: &[Slide]) -> zip::result::ZipResult<()> {
+    let mut zip = ZipWriter::new(std::fs::File::create(path)?);
+    let mut part = |name: &str, content: &[u8]| -> zip::result::ZipResult<()> {
+        zip.start_file(name, SimpleFileOptions::default())?;
+        zip.write_all(content)?;
+        Ok(())
+    };
+
+    part("[Content_Types].xml", content_types(slides.len()).as_bytes())?;
+    part("_rels/.rels", ROOT_RELS.as_bytes())?;
+    part("docProps/core.xml", core_properties(title).as_bytes())?;
+    part("ppt/presentation.xml", presentation(slides.len()).as_bytes())?;
+    part("ppt/_rels/presentation.xml.rels", presentation_rels(slides.len()).as_bytes())?;
+    part("ppt/slideMasters/slideMaster1.xml", SLIDE_MASTER.as_bytes())?;
+    part("ppt/slideMasters/_rels/slideMaster1.xml.rels", SLIDE_MASTER_RELS.as_bytes())?;
+    part("ppt/slideLayouts/slideLayout1.xml", SLIDE_LAYOUT.as_bytes())?;
+    part("ppt/slideLayouts/_rels/slideLayout1.xml.rels", SLIDE_LAYOUT_RELS.as_bytes())?;
+    part("ppt/theme/theme1.xml", THEME.as_bytes())?;
+
+    for (i, slide) in slides.iter().enumerate() {
+        let n = i + 1;
+        let image = slide.image.as_ref().map(|img| format!("image{}.{}", n, img.extension));
+        part(&format!("ppt/slides/slide{}.xml", n), slide_xml(slide).as_bytes())?;
+        part(&format!("ppt/slides/_rels/slide{}.xml.rels", n), slide_rels(image.as_deref()).as_bytes())?;
+        if let (Some(img), Some(name)) = (&slide.image, &image) {
+            part(&format!("ppt/media/{}", name), &img.bytes)?;
+        }
+    }
+
+    zip.finish()?;
+    Ok(())
+}
+
+fn relationships(rels: &[(&str, &str, &str)]) -> String {
+    let items: String = rels
+        .iter()
+        .map(|(id, kind, target)| format!(r#"<Relationship Id="{}" Type="{}/{}" Target="{}"/>"#, id, REL_NS, kind, target))
+        .collect();
+    format!(
+        r#"{}<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">{}</Relationships>"#,
+        XML_HEADER, items
+    )
+}
+
+fn content_types(slides: usize) -> String {
+    let slide_overrides: String = (1..=slides)
+        .map(|n| {
+            format!(
+                r#"<Override PartName="/ppt/slides/slide{}.xml" ContentType="application/vnd.openxmlformats-officedocument.presentationml.slide+xml"/>"#,
+                n
+            )
+        })
+        .collect();
+    format!(
+        concat!(
+            "{}",
+            r#"<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">"#,
+            r#"<Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>"#,
+            r#"<Default Extension="xml" ContentType="application/xml"/>"#,
+            r#"<Default Extension="png" ContentType="image/png"/>"#,
+            r#"<Default Extension="jpeg" ContentType="image/jpeg"/>"#,
+            r#"<Default Extension="gif" ContentType="image/gif"/>"#,
+            r#"<Override PartName="/docProps/core.xml" ContentType="application/vnd.openxmlformats-package.core-properties+xml"/>"#,
+            r#"<Override PartName="/ppt/presentation.xml" ContentType="application/vnd.openxmlformats-officedocument.presentationml.presentation.main+xml"/>"#,
+            r#"<Override PartName="/ppt/slideMasters/slideMaster1.xml" ContentType="application/vnd.openxmlformats-officedocument.presentationml.slideMaster+xml"/>"#,
+            r#"<Override PartName="/ppt/slideLayouts/slideLayout1.xml" ContentType="application/vnd.openxmlformats-officedocument.presentationml.slideLayout+xml"/>"#,
+            r#"<Override PartName="/ppt/theme/theme1.xml" ContentType="application/vnd.openxmlformats-officedocument.theme+xml"/>"#,
+            "{}</Types>"
+        ),
+        XML_HEADER, slide_overrides
+    )
+}
+
+const ROOT_RELS: &str = concat!(
+    r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+    r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
+    r#"<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="ppt/presentation.xml"/>"#,
+    r#"<Relationship Id="rId2" Type="http://schemas.openxmlformats.org/package/2006/relationships/metadata/core-properties" Target="docProps/core.xml"/>"#,
+    "</Relationships>"
+);
+
+fn core_properties(title: Option<&str>) -> String {
+    let title = title.map(|t| format!("<dc:title>{}</dc:title>", escape(t))).unwrap_or_default();
+    format!(
+        r#"{}<cp:coreProperties xmlns:cp="http://schemas.openxmlformats.org/package/2006/metadata/core-properties" xmlns:dc="http://purl.org/dc/elements/1.1/">{}</cp:coreProperties>"#,
+        XML_HEADER, title
+    )
+}
+
+fn presentation(slides: usize) -> String {
+    // rId1 is the master, rId2 the theme, slides follow
+    let ids: String = (0..slides)
+        .map(|i| format!(r#"<p:sldId id="{}" r:id="rId{}"/>"#, 256 + i, i + 3))
+        .collect();
+    format!(
+        r#"{}<p:presentation {}><p:sldMasterIdLst><p:sldMasterId id="2147483648" r:id="rId1"/></p:sldMasterIdLst><p:sldIdLst>{}</p:sldIdLst><p:sldSz cx="{}" cy="{}"/><p:notesSz cx="{}" cy="{}"/></p:presentation>"#,
+        XML_HEADER, NS, ids, SLIDE_WIDTH, SLIDE_HEIGHT, SLIDE_HEIGHT, SLIDE_WIDTH
+    )
+}
+
+fn presentation_rels(slides: usize) -> String {
+    let targets: Vec<String> = (1..=slides).map(|n| format!("slides/slide{}.xml", n)).collect();
+    let ids: Vec<String> = (1..=slides).map(|n| format!("rId{}", n + 2)).collect();
+    let mut rels = vec![
+        ("rId1", "slideMaster", "slideMasters/slideMaster1.xml"),
+        ("rId2", "theme", "theme/theme1.xml"),
+    ];
+    rels.extend(ids.iter().zip(&targets).map(|(id, target)| (id.as_str(), "slide", target.as_str())));
+    relationships(&rels)
+}
+
+fn slide_rels(image: Option<&str>) -> String {
+    let media = image.map(|name| format!("../media/{}", name));
+    let mut rels = vec![("rId1", "slideLayout", "../slideLayouts/slideLayout1.xml")];
+    rels.extend(media.as_deref().map(|target| ("rId2", "image", target)));
+    relationships(&rels)
+}
+
+/// An empty shape tree root, shared by the master, layout and slides
+const GROUP_ROOT: &str = r#"<p:nvGrpSpPr><p:cNvPr id="1" name=""/><p:cNvGrpSpPr/><p:nvPr/></p:nvGrpSpPr><p:grpSpPr><a:xfrm><a:off x="0" y="0"/><a:ext cx="0" cy="0"/><a:chOff x="0" y="0"/><a:chExt cx="0" cy="0"/></a:xfrm></p:grpSpPr>"#;
+
+fn xfrm(x: u64, y: u64, w: u64, h: u64) -> String {
+    format!(r#"<a:xfrm><a:off x="{}" y="{}"/><a:ext cx="{}" cy="{}"/></a:xfrm>"#, x, y, w, h)
+}
+
+fn text_box(id: usize, name: &str, frame: String, paragraphs: String) -> String {
+    format!(
+        r#"<p:sp><p:nvSpPr><p:cNvPr id="{}" name="{}"/><p:cNvSpPr txBox="1"/><p:nvPr/></p:nvSpPr><p:spPr>{}<a:prstGeom prst="rect"><a:avLst/></a:prstGeom></p:spPr><p:txBody><a:bodyPr wrap="square"><a:normAutofit/></a:bodyPr><a:lstStyle/>{}</p:txBody></p:sp>"#,
+        id, name, frame, paragraphs
+    )
+}
+
+fn slide_xml(slide: &Slide) -> String {
+    let width = SLIDE_WIDTH - 2 * MARGIN;
+    let body_height = SLIDE_HEIGHT - BODY_TOP - MARGIN;
+    let mut shapes = text_box(
+        2,
+        "Title",
+        xfrm(MARGIN, MARGIN, width, TITLE_HEIGHT),
+        format!(
+            r#"<a:p><a:r><a:rPr lang="en-US" sz="3600" b="1"/><a:t>{}</a:t></a:r></a:p>"#,
+            escape(&slide.title)
+        ),
+    );
+
+    let body_width = if slide.image.is_some() { (width - MARGIN) / 2 } else { width };
+    if !slide.bullets.is_empty() {
+        let paragraphs: String = slide
+            .bullets
+            .iter()
+            .map(|b| {
+                format!(
+                    r#"<a:p><a:pPr marL="342900" indent="-342900"><a:buFont typeface="Arial"/><a:buChar char="•"/></a:pPr><a:r><a:rPr lang="en-US" sz="2400"/><a:t>{}</a:t></a:r></a:p>"#,
+                    escape(b)
+                )
+            })
+            .collect();
+        shapes.push_str(&text_box(3, "Bullets", xfrm(MARGIN, BODY_TOP, body_width, body_height), paragraphs));
+    }
+
+    if let Some(image) = &slide.image {
+        let (x, y, w, h) = image.fit(MARGIN + body_width + MARGIN, BODY_TOP, body_width, body_height);
+        shapes.push_str(&format!(
+            r#"<p:pic><p:nvPicPr><p:cNvPr id="4" name="Picture"/><p:cNvPicPr><a:picLocks noChangeAspect="1"/></p:cNvPicPr><p:nvPr/></p:nvPicPr><p:blipFill><a:blip r:embed="rId2"/><a:stretch><a:fillRect/></a:stretch></p:blipFill><p:spPr>{}<a:prstGeom prst="rect"><a:avLst/></a:prstGeom></p:spPr></p:pic>"#,
+            xfrm(x, y, w, h)
+        ));
+    }
+
+    format!(
+        r#"{}<p:sld {}><p:cSld><p:spTree>{}{}</p:spTree></p:cSld><p:clrMapOvr><a:masterClrMapping/></p:clrMapOvr></p:sld>"#,
+        XML_HEADER, NS, GROUP_ROOT, shapes
+    )
+}
+
+const SLIDE_MASTER: &str = concat!(
+    r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+    r#"<p:sldMaster xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships" xmlns:p="http://schemas.openxmlformats.org/presentationml/2006/main">"#,
+    r#"<p:cSld><p:bg><p:bgRef idx="1001"><a:schemeClr val="bg1"/></p:bgRef></p:bg><p:spTree>"#,
+    r#"<p:nvGrpSpPr><p:cNvPr id="1" name=""/><p:cNvGrpSpPr/><p:nvPr/></p:nvGrpSpPr><p:grpSpPr><a:xfrm><a:off x="0" y="0"/><a:ext cx="0" cy="0"/><a:chOff x="0" y="0"/><a:chExt cx="0" cy="0"/></a:xfrm></p:grpSpPr>"#,
+    r#"</p:spTree></p:cSld>"#,
+    r#"<p:clrMap bg1="lt1" tx1="dk1" bg2="lt2" tx2="dk2" accent1="accent1" accent2="accent2" accent3="accent3" accent4="accent4" accent5="accent5" accent6="accent6" hlink="hlink" folHlink="folHlink"/>"#,
+    r#"<p:sldLayoutIdLst><p:sldLayoutId id="2147483649" r:id="rId1"/></p:sldLayoutIdLst>"#,
+    "</p:sldMaster>"
+);
+
+const SLIDE_MASTER_RELS: &str = concat!(
+    r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+    r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
+    r#"<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/slideLayout" Target="../slideLayouts/slideLayout1.xml"/>"#,
+    r#"<Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/theme" Target="../theme/theme1.xml"/>"#,
+    "</Relationships>"
+);
+
+const SLIDE_LAYOUT: &str = concat!(
+    r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+    r#"<p:sldLayout xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships" xmlns:p="http://schemas.openxmlformats.org/presentationml/2006/main" type="blank" preserve="1">"#,
+    r#"<p:cSld name="Blank"><p:spTree>"#,
+    r#"<p:nvGrpSpPr><p:cNvPr id="1" name=""/><p:cNvGrpSpPr/><p:nvPr/></p:nvGrpSpPr><p:grpSpPr><a:xfrm><a:off x="0" y="0"/><a:ext cx="0" cy="0"/><a:chOff x="0" y="0"/><a:chExt cx="0" cy="0"/></a:xfrm></p:grpSpPr>"#,
+    r#"</p:spTree></p:cSld><p:clrMapOvr><a:masterClrMapping/></p:clrMapOvr></p:sldLayout>"#
+);
+
+const SLIDE_LAYOUT_RELS: &str = concat!(
+    r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+    r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
+    r#"<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/slideMaster" Target="../slideMasters/slideMaster1.xml"/>"#,
+    "</Relationships>"
+);
+
+/// A plain Office theme: the required color, font and format schemes only
+const THEME: &str = concat!(
+    r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+    r#"<a:theme xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main" name="Office Theme"><a:themeElements>"#,
+    r#"<a:clrScheme name="Office">"#,
+    r#"<a:dk1><a:sysClr val="windowText" lastClr="000000"/></a:dk1><a:lt1><a:sysClr val="window" lastClr="FFFFFF"/></a:lt1>"#,
+    r#"<a:dk2><a:srgbClr val="44546A"/></a:dk2><a:lt2><a:srgbClr val="E7E6E6"/></a:lt2>"#,
+    r#"<a:accent1><a:srgbClr val="4472C4"/></a:accent1><a:accent2><a:srgbClr val="ED7D31"/></a:accent2>"#,
+    r#"<a:accent3><a:srgbClr val="A5A5A5"/></a:accent3><a:accent4><a:srgbClr val="FFC000"/></a:accent4>"#,
+    r#"<a:accent5><a:srgbClr val="5B9BD5"/></a:accent5><a:accent6><a:srgbClr val="70AD47"/></a:accent6>"#,
+    r#"<a:hlink><a:srgbClr val="0563C1"/></a:hlink><a:folHlink><a:srgbClr val="954F72"/></a:folHlink>"#,
+    r#"</a:clrScheme>"#,
+    r#"<a:fontScheme name="Office">"#,
+    r#"<a:majorFont><a:latin typeface="Calibri Light"/><a:ea typeface=""/><a:cs typeface=""/></a:majorFont>"#,
+    r#"<a:minorFont><a:latin typeface="Calibri"/><a:ea typeface=""/><a:cs typeface=""/></a:minorFont>"#,
+    r#"</a:fontScheme>"#,
+    r#"<a:fmtScheme name="Office">"#,
+    r#"<a:fillStyleLst><a:solidFill><a:schemeClr val="phClr"/></a:solidFill><a:solidFill><a:schemeClr val="phClr"/></a:solidFill><a:solidFill><a:schemeClr val="phClr"/></a:solidFill></a:fillStyleLst>"#,
+    r#"<a:lnStyleLst><a:ln w="6350"><a:solidFill><a:schemeClr val="phClr"/></a:solidFill></a:ln><a:ln w="12700"><a:solidFill><a:schemeClr val="phClr"/></a:solidFill></a:ln><a:ln w="19050"><a:solidFill><a:schemeClr val="phClr"/></a:solidFill></a:ln></a:lnStyleLst>"#,
+    r#"<a:effectStyleLst><a:effectStyle><a:effectLst/></a:effectStyle><a:effectStyle><a:effectLst/></a:effectStyle><a:effectStyle><a:effectLst/></a:effectStyle></a:effectStyleLst>"#,
+    r#"<a:bgFillStyleLst><a:solidFill><a:schemeClr val="phClr"/></a:solidFill><a:solidFill><a:schemeClr val="phClr"/></a:solidFill><a:solidFill><a:schemeClr val="phClr"/></a:solidFill></a:bgFillStyleLst>"#,
+    r#"</a:fmtScheme></a:themeElements></a:theme>"#
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_image_fit_keeps_aspect_ratio() {
+        let image = Image { bytes: Vec::new(), extension: "png", width: 200, height: 100 };
+        // A wide image in a square box is centered vertically
+        assert_eq!(image.fit(10, 20, 1000, 1000), (10, 270, 1000, 500));
+    }
+
+    #[test]
+    fn test_slide_text_is_escaped() {
+        let slide = Slide { title: "R&D <2024>".into(), bullets: vec!["a \"quoted\" point".into()], image: None };
+        let xml = slide_xml(&slide);
+        assert!(xml.contains("R&amp;D &lt;2024&gt;"));
+        assert!(!xml.contains("<p:pic>"));
+    }
+}
@@ -0,0 +1,332 @@
+//! WriteXlsx: row objects or CSV to a typed Excel worksheet
+
+use std::path::PathBuf;
+
+use chrono::{Datelike, NaiveDate, NaiveDateTime, Timelike};
+use rust_xlsxwriter::{ExcelDateTime, Format, Workbook, Worksheet, XlsxError};
+use serde_json::{json, Value};
+
+use crate::error::ToolError;
+use crate::tools::{BoxFuture, Tool, ToolExecutionContext, ToolOutput};
+
+use super::{created, generation_error, prepare_output};
+
+const XLSX_MIME: &str = "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet";
+
+const DATE_FORMAT: &str = "yyyy-mm-dd";
+const DATETIME_FORMAT: &str = "yyyy-mm-dd hh:mm:ss";
+
+/// Tool for writing a single-sheet Excel workbook
+pub struct WriteXlsx {
+    workspace: PathBuf,
+}
+
+impl WriteXlsx {
+    pub fn new(workspace: PathBuf) -> Self {
+        Self { workspace }
+    }
+}
+
+impl Tool for WriteXlsx {
+    fn name(&self) -> &str {
+        "WriteXlsx"
+    }
+
+    fn description(&self) -> &str {
+        r#"Write an Excel workbook (.xlsx) with one sheet, from row objects or CSV.
+
+Provide exactly one of:
+- rows: array of objects, one per row; keys become the bold header row.
+  Columns follow `columns` if given, otherwise the keys sorted by name.
+- csv: CSV text (RFC 4180 quoting); the first record is the header.
+
+Cells are typed: numbers, true/false, and ISO dates ("2024-01-15") or
+date-times ("2024-01-15T09:30:00") are written as numbers, booleans and
+formatted dates so they sort and compute in Excel. Everything else is text.
+
+Example:
+{
+  "file_path": "sales.xlsx",
+  "sheet_name": "Q1",
+  "rows": [{"Region": "EMEA", "Revenue": 1200000, "Closed": "2024-03-31"}]
+}"#
+    }
+
+    fn compact_description(&self) -> &str {
+        "Writes an Excel workbook (.xlsx) with one sheet from either rows (objects) or csv; numbers, booleans and ISO dates get typed cells."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "file_path": {
+                    "type": "string",
+                    "description": "Path for the output file (must end in .xlsx)"
+                },
+                "sheet_name": {
+                    "type": "string",
+                    "description": "Worksheet name (default: Sheet1)"
+                },
+                "rows": {
+                    "type": "array",
+                    "items": { "type": "object" },
+                    "description": "Row objects keyed by column name"
+                },
+                "columns": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Column order for rows (default: keys sorted by name)"
+                },
+                "csv": {
+                    "type": "string",
+                    "description": "CSV content with a header record (instead of rows)"
+                }
+            },
+            "required": ["file_path"]
+        })
+    }
+
+    fn execute(&self, params: Value, _ctx: ToolExecutionContext) -> BoxFuture<'_, Result<ToolOutput, ToolError>> {
+        Box::pin(async move {
+            let table = match (&params["rows"], &params["csv"]) {
+                (Value::Array(rows), Value::Null) => rows_table(rows, &params["columns"])?,
+                (Value::Null, Value::String(csv)) => csv_table(csv)?,
+                _ => return Err(ToolError::InvalidParams("Provide exactly one of rows (array) or csv (string)".into())),
+            };
+            let sheet_name = params["sheet_name"].as_str().unwrap_or("Sheet1");
+            let path = prepare_output(&self.workspace, &params, "xlsx").await?;
+
+            let mut workbook = Workbook::new();
+            let sheet = workbook.add_worksheet();
+            sheet
+                .set_name(sheet_name)
+                .map_err(|e| ToolError::InvalidParams(format!("Invalid sheet_name: {}", e)))?;
+            write_table(sheet, &table).map_err(|e| generation_error("build XLSX", e))?;
+            workbook.save(&path).map_err(|e| generation_error("write XLSX", e))?;
+
+            created(
+                &path,
+                XLSX_MIME,
+                json!({
+                    "format": "xlsx",
+                    "sheet": sheet_name,
+                    "columns": table.header,
+                    "rows": table.rows.len(),
+                }),
+            )
+        })
+    }
+}
+
+/// A typed cell value
+#[derive(Debug, Clone, PartialEq)]
+enum Cell {
+    Empty,
+    Text(String),
+    Number(f64),
+    Bool(bool),
+    Date(NaiveDate),
+    DateTime(NaiveDateTime),
+}
+
+/// Header plus typed data rows
+struct Table {
+    header: Vec<String>,
+    rows: Vec<Vec<Cell>>,
+}
+
+fn rows_table(rows: &[Value], columns: &Value) -> Result<Table, ToolError> {
+    let objects = rows
+        .iter()
+        .map(|row| {
+            row.as_object()
+                .ok_or_else(|| ToolError::InvalidParams("Each entry of rows must be an object".into()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let header: Vec<String> = match columns {
+        Value::Array(names) => names.iter().filter_map(|n| n.as_str().map(str::to_string)).collect(),
+        _ => {
+            let mut keys: Vec<String> = objects.iter().flat_map(|o| o.keys().cloned()).collect();
+            keys.sort();
+            keys.dedup();
+            keys
+        }
+    };
+
+    let rows = objects
+        .iter()
+        .map(|object| header.iter().map(|key| json_cell(object.get(key).unwrap_or(&Value::Null))).collect())
+        .collect();
+    Ok(Table { header, rows })
+}
+
+fn csv_table(csv: &str) -> Result<Table, ToolError> {
+    let mut records = parse_csv(csv)?.into_iter();
+    let header = records
+        .next()
+        .ok_or_else(|| ToolError::InvalidParams("csv has no header record".into()))?;
+    let rows = records.map(|record| record.iter().map(|field| text_cell(field)).collect()).collect();
+    Ok(Table { header, rows })
+}
+
+fn json_cell(value: &Value) -> Cell {
+    match value {
+        Value::Null => Cell::Empty,
+        Value::Bool(b) => Cell::Bool(*b),
+        Value::Number(n) => n.as_f64().map(Cell::Number).unwrap_or_else(|| Cell::Text(n.to_string())),
+        Value::String(s) => date_cell(s).unwrap_or_else(|| Cell::Text(s.clone())),
+        other => Cell::Text(other.to_string()),
+    }
+}
+
+/// Type a CSV field: numbers, booleans and ISO dates, otherwise text
+fn text_cell(field: &str) -> Cell {
+    let trimmed = field.trim();
+    if trimmed.is_empty() {
+        return Cell::Empty;
+    }
+    if let Some(date) = date_cell(trimmed) {
+        return date;
+    }
+    match trimmed {
+        "true" | "TRUE" => return Cell::Bool(true),
+        "false" | "FALSE" => return Cell::Bool(false),
+        _ => {}
+    }
+    // Keep identifiers like "00123" as text
+    let digits = trimmed.trim_start_matches(['-', '+']);
+    let leading_zero = digits.len() > 1 && digits.starts_with('0') && !digits.starts_with("0.");
+    match trimmed.parse::<f64>() {
+        Ok(n) if n.is_finite() && !leading_zero => Cell::Number(n),
+        _ => Cell::Text(field.to_string()),
+    }
+}
+
+fn date_cell(s: &str) -> Option<Cell> {
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return Some(Cell::Date(date));
+    }
+    ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M"]
+        .iter()
+        .find_map(|fmt| NaiveDateTime::parse_from_str(s, fmt).ok())
+        .map(Cell::DateTime)
+}
+
+/// Parse RFC 4180 CSV into records; blank lines are skipped
+fn parse_csv(input: &str) -> Result<Vec<Vec<String>>, ToolError> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => in_quotes = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' => in_quotes = true,
+            ',' => record.push(std::mem::take(&mut field)),
+            '\r' | '\n' => {
+                if c == '\r' && chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+                if !record.is_empty() || !field.is_empty() {
+                    record.push(std::mem::take(&mut field));
+                    records.push(std::mem::take(&mut record));
+                }
+            }
+            _ => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(ToolError::InvalidParams("csv has an unterminated quoted field".into()));
+    }
+    if !record.is_empty() || !field.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    Ok(records)
+}
+
+fn write_table(sheet: &mut Worksheet, table: &Table) -> Result<(), XlsxError> {
+    let bold = Format::new().set_bold();
+    let date = Format::new().set_num_format(DATE_FORMAT);
+    let datetime = Format::new().set_num_format(DATETIME_FORMAT);
+
+    for (col, name) in table.header.iter().enumerate() {
+        sheet.write_string_with_format(0, col as u16, name, &bold)?;
+    }
+    for (r, row) in table.rows.iter().enumerate() {
+        let r = r as u32 + 1;
+        for (col, cell) in row.iter().enumerate() {
+            let col = col as u16;
+            match cell {
+                Cell::Empty => continue,
+                Cell::Text(s) => sheet.write_string(r, col, s)?,
+                Cell::Number(n) => sheet.write_number(r, col, *n)?,
+                Cell::Bool(b) => sheet.write_boolean(r, col, *b)?,
+                Cell::Date(d) => sheet.write_datetime_with_format(r, col, excel_date(d)?, &date)?,
+                Cell::DateTime(dt) => {
+                    let time = dt.time();
+                    let seconds = time.second() as f64 + time.nanosecond() as f64 / 1e9;
+                    let value = excel_date(&dt.date())?.and_hms(time.hour() as u16, time.minute() as u8, seconds)?;
+                    sheet.write_datetime_with_format(r, col, value, &datetime)?
+                }
+            };
+        }
+    }
+    if !table.header.is_empty() {
+        sheet.set_freeze_panes(1, 0)?;
+    }
+    sheet.autofit();
+    Ok(())
+}
+
+/// Dates outside Excel's range (1900-9999) fail here
+fn excel_date(date: &NaiveDate) -> Result<ExcelDateTime, XlsxError> {
+    let year = u16::try_from(date.year()).map_err(|_| XlsxError::DateTimeRangeError(date.to_string()))?;
+    ExcelDateTime::from_ymd(year, date.month() as u8, date.day() as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_csv_quoting() {
+        let records = parse_csv("a,b\r\n\"x, y\",\"say \"\"hi\"\"\"\n\n\"multi\nline\",\n").unwrap();
+        assert_eq!(
+            records,
+            vec![
+                vec!["a".to_string(), "b".to_string()],
+                vec!["x, y".to_string(), "say \"hi\"".to_string()],
+                vec!["multi\nline".to_string(), String::new()],
+            ]
+        );
+        assert!(parse_csv("\"open").is_err());
+    }
+
+    #[test]
+    fn test_text_cell_typing() {
+        assert_eq!(text_cell("42.5"), Cell::Number(42.5));
+        assert_eq!(text_cell("-7"), Cell::Number(-7.0));
+        assert_eq!(text_cell("0.5"), Cell::Number(0.5));
+        assert_eq!(text_cell("00123"), Cell::Text("00123".into()));
+        assert_eq!(text_cell("TRUE"), Cell::Bool(true));
+        assert_eq!(text_cell("2024-01-15"), Cell::Date(NaiveDate::from_ymd_opt(2024, 1, 15).unwrap()));
+        assert!(matches!(text_cell("2024-01-15T09:30:00"), Cell::DateTime(_)));
+        assert_eq!(text_cell("NaN"), Cell::Text("NaN".into()));
+        assert_eq!(text_cell(" "), Cell::Empty);
+    }
+}
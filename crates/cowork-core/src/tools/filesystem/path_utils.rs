@@ -235,15 +235,25 @@ pub fn percent_decode_path(encoded: &str) -> String {
 // PATH NORMALIZATION & VALIDATION
 // ============================================================================
 
+/// Device names Windows reserves in every directory, with or without an extension
+const RESERVED_DEVICE_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9",
+    "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
 /// Normalize a path by resolving `.` and `..` components without filesystem access.
 ///
 /// This is used for:
 /// - Security validation of paths that don't exist yet
 /// - Path traversal prevention
+///
+/// Verbatim prefixes (`\\?\C:\`) are simplified with `dunce` where that
+/// doesn't change the meaning. A `..` right after a drive-relative prefix
+/// (`C:..`) is kept, since it climbs from the drive's current directory.
 pub fn normalize_path(path: &Path) -> PathBuf {
     let mut components = Vec::new();
 
-    for component in path.components() {
+    for component in dunce::simplified(path).components() {
         match component {
             Component::Prefix(p) => components.push(Component::Prefix(p)),
             Component::RootDir => {
@@ -256,9 +266,13 @@ pub fn normalize_path(path: &Path) -> PathBuf {
                 // Pop the last component unless we're at root or have no components
                 if let Some(last) = components.last() {
                     match last {
-                        Component::RootDir | Component::Prefix(_) => {
+                        Component::RootDir => {
                             // Can't go above root, ignore the `..`
                         }
+                        Component::Prefix(_) => {
+                            // Drive-relative (`C:..`): relative to the drive's current directory
+                            components.push(Component::ParentDir);
+                        }
                         Component::ParentDir => {
                             // Already have `..`, add another
                             components.push(Component::ParentDir);
@@ -283,6 +297,48 @@ pub fn normalize_path(path: &Path) -> PathBuf {
     }
 }
 
+/// Check whether `path` is `base` or inside it, the way the filesystem compares paths.
+///
+/// Both sides are simplified with `dunce` so a verbatim (`\\?\`) path matches
+/// its plain form, and components compare case-insensitively on Windows
+/// (NTFS is case-insensitive) and exactly elsewhere.
+pub fn path_starts_with(path: &Path, base: &Path) -> bool {
+    starts_with_components(dunce::simplified(path), dunce::simplified(base), cfg!(windows))
+}
+
+/// Component-wise prefix check, optionally ignoring case
+fn starts_with_components(path: &Path, base: &Path, ignore_case: bool) -> bool {
+    if !ignore_case {
+        return path.starts_with(base);
+    }
+    let mut path_components = path.components();
+    base.components().all(|b| {
+        path_components.next().is_some_and(|p| {
+            p.as_os_str().to_string_lossy().to_lowercase() == b.as_os_str().to_string_lossy().to_lowercase()
+        })
+    })
+}
+
+/// Check whether a path is drive-relative (`C:foo`): a drive prefix with no root.
+///
+/// Such paths resolve against the drive's current directory, not the
+/// workspace. Always false outside Windows, where there are no prefixes.
+pub fn is_drive_relative(path: &Path) -> bool {
+    let mut components = path.components();
+    matches!(components.next(), Some(Component::Prefix(_)))
+        && !matches!(components.next(), Some(Component::RootDir))
+}
+
+/// Check whether a file name is a reserved Windows device name (`CON`, `NUL`, `COM1`, ...).
+///
+/// Windows ignores the extension and trailing dots and spaces, so `nul.txt`
+/// and `Con ` are reserved too.
+pub fn is_reserved_device_name(name: &str) -> bool {
+    let name = name.trim_end_matches(['.', ' ']);
+    let stem = name.split('.').next().unwrap_or(name).trim_end();
+    RESERVED_DEVICE_NAMES.iter().any(|reserved| stem.eq_ignore_ascii_case(reserved))
+}
+
 /// Validate that a path is within the workspace boundary.
 ///
 /// Uses `dunce::canonicalize()` to avoid UNC path prefix issues on Windows.
@@ -313,7 +369,7 @@ pub fn validate_path(path: &Path, workspace: &Path) -> Result<PathBuf, ToolError
         ))
     })?;
 
-    if canonical.starts_with(&workspace_canonical) {
+    if path_starts_with(&canonical, &workspace_canonical) {
         Ok(canonical)
     } else {
         Err(ToolError::PermissionDenied(format!(
@@ -327,8 +383,31 @@ pub fn validate_path(path: &Path, workspace: &Path) -> Result<PathBuf, ToolError
 /// Validate a path for writing (path may not exist yet).
 ///
 /// Uses `normalize_path()` instead of `canonicalize()` since the file
-/// may not exist yet.
+/// may not exist yet. On Windows, drive-relative paths and reserved device
+/// names are rejected: neither would write a file in the workspace.
 pub fn validate_write_path(path: &Path, workspace: &Path) -> Result<PathBuf, ToolError> {
+    if is_drive_relative(path) {
+        return Err(ToolError::InvalidParams(format!(
+            "Path {} is drive-relative; use an absolute path like C:\\dir\\file",
+            path.display()
+        )));
+    }
+    if cfg!(windows)
+        && let Some(name) = path
+            .components()
+            .filter_map(|c| match c {
+                Component::Normal(name) => Some(name.to_string_lossy()),
+                _ => None,
+            })
+            .find(|name| is_reserved_device_name(name))
+    {
+        return Err(ToolError::InvalidParams(format!(
+            "Path {} uses the reserved device name '{}', which Windows doesn't allow as a file or directory name",
+            path.display(),
+            name
+        )));
+    }
+
     let normalized = normalize_path(path);
     let workspace_normalized = normalize_path(workspace);
 
@@ -351,7 +430,7 @@ pub fn validate_write_path(path: &Path, workspace: &Path) -> Result<PathBuf, Too
     let path_normalized = normalize_path(&path_abs);
     let workspace_normalized = normalize_path(&workspace_abs);
 
-    if path_normalized.starts_with(&workspace_normalized) {
+    if path_starts_with(&path_normalized, &workspace_normalized) {
         Ok(path_normalized)
     } else {
         Err(ToolError::PermissionDenied(format!(
@@ -362,6 +441,74 @@ pub fn validate_write_path(path: &Path, workspace: &Path) -> Result<PathBuf, Too
     }
 }
 
+/// Validate that a path is within one of the workspace roots (see `WorkspaceRoots`)
+///
+/// With one root this is `validate_path`; with several, the error names them all.
+pub fn validate_path_in_roots(path: &Path, roots: &[PathBuf]) -> Result<PathBuf, ToolError> {
+    in_any_root(path, roots, validate_path)
+}
+
+/// Validate a path for writing within one of the workspace roots (see
+/// `validate_write_path`)
+pub fn validate_write_path_in_roots(path: &Path, roots: &[PathBuf]) -> Result<PathBuf, ToolError> {
+    in_any_root(path, roots, validate_write_path)
+}
+
+/// The first root `validate` accepts `path` in; a path outside every root is
+/// denied, and any other error is returned as is
+fn in_any_root(
+    path: &Path,
+    roots: &[PathBuf],
+    validate: fn(&Path, &Path) -> Result<PathBuf, ToolError>,
+) -> Result<PathBuf, ToolError> {
+    let mut denied = None;
+    for root in roots {
+        match validate(path, root) {
+            Err(ToolError::PermissionDenied(msg)) => {
+                denied.get_or_insert(msg);
+            }
+            result => return result,
+        }
+    }
+    Err(ToolError::PermissionDenied(match roots {
+        [_, _, ..] => format!(
+            "Path {} is outside the workspace roots {}",
+            path.display(),
+            roots.iter().map(|root| root.display().to_string()).collect::<Vec<_>>().join(", ")
+        ),
+        _ => denied.unwrap_or_else(|| format!("Path {} is outside the workspace", path.display())),
+    }))
+}
+
+/// Validate a path within the workspace roots or the session's scratch directory
+///
+/// The scratch directory holds the session's temporary files outside the
+/// workspace; it is always readable and writable. Paths outside all of them
+/// are rejected with the workspace error.
+pub fn validate_path_with_scratch(path: &Path, roots: &[PathBuf], scratch: Option<&Path>) -> Result<PathBuf, ToolError> {
+    match (validate_path_in_roots(path, roots), scratch) {
+        (Err(ToolError::PermissionDenied(msg)), Some(scratch)) => {
+            validate_path(path, scratch).map_err(|_| ToolError::PermissionDenied(msg))
+        }
+        (result, _) => result,
+    }
+}
+
+/// Validate a path for writing within the workspace roots or the session's
+/// scratch directory (see `validate_path_with_scratch`)
+pub fn validate_write_path_with_scratch(
+    path: &Path,
+    roots: &[PathBuf],
+    scratch: Option<&Path>,
+) -> Result<PathBuf, ToolError> {
+    match (validate_write_path_in_roots(path, roots), scratch) {
+        (Err(ToolError::PermissionDenied(msg)), Some(scratch)) => {
+            validate_write_path(path, scratch).map_err(|_| ToolError::PermissionDenied(msg))
+        }
+        (result, _) => result,
+    }
+}
+
 // ============================================================================
 // TESTS
 // ============================================================================
@@ -510,4 +657,151 @@ mod tests {
         let decoded = percent_decode_path(&encoded);
         assert_eq!(decoded, "/path/with space/and#hash");
     }
+
+    #[test]
+    fn test_starts_with_components_case() {
+        let workspace = PathBuf::from("/Users/Dev/Project");
+        assert!(starts_with_components(&PathBuf::from("/users/dev/project/src"), &workspace, true));
+        assert!(!starts_with_components(&PathBuf::from("/users/dev/project/src"), &workspace, false));
+        assert!(starts_with_components(&PathBuf::from("/Users/Dev/Project/src"), &workspace, false));
+        // Whole components only
+        assert!(!starts_with_components(&PathBuf::from("/users/dev/project2"), &workspace, true));
+        assert!(!starts_with_components(&PathBuf::from("/users/dev"), &workspace, true));
+    }
+
+    #[test]
+    fn test_reserved_device_names() {
+        for name in ["CON", "nul", "Com1", "lpt9", "nul.txt", "aux.tar.gz", "CON ", "prn."] {
+            assert!(is_reserved_device_name(name), "{name}");
+        }
+        for name in ["console", "nullable.rs", "COM10", "com", "my-con", ".nul"] {
+            assert!(!is_reserved_device_name(name), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_validate_write_path_stays_in_workspace() {
+        let workspace = PathBuf::from("/work/repo");
+        assert!(validate_write_path(&workspace.join("src/new.rs"), &workspace).is_ok());
+        assert!(validate_write_path(&workspace.join("../other/new.rs"), &workspace).is_err());
+        assert!(validate_write_path(&PathBuf::from("/work/repository/new.rs"), &workspace).is_err());
+    }
+
+    #[test]
+    fn test_scratch_dir_is_the_only_exception() {
+        let workspace = PathBuf::from("/work/repo");
+        let scratch = PathBuf::from("/data/sessions/abc/scratch");
+        let roots = [workspace.clone()];
+        let check = |path: &str| validate_write_path_with_scratch(Path::new(path), &roots, Some(&scratch));
+        assert!(check("/work/repo/src/new.rs").is_ok());
+        assert!(check("/data/sessions/abc/scratch/notes.md").is_ok());
+        assert!(check("/data/sessions/abc/other.md").is_err());
+        assert!(check("/data/sessions/abc/scratch/../../def/scratch/x").is_err());
+        // The error names the workspace, as without a scratch dir
+        match check("/etc/passwd") {
+            Err(ToolError::PermissionDenied(msg)) => assert!(msg.contains("outside workspace /work/repo")),
+            other => panic!("expected PermissionDenied, got {:?}", other),
+        }
+        assert!(validate_write_path_with_scratch(&scratch.join("x"), &roots, None).is_err());
+    }
+
+    #[test]
+    fn test_validate_path_in_roots() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = dir.path().join("backend");
+        let frontend = dir.path().join("frontend");
+        std::fs::create_dir_all(&backend).unwrap();
+        std::fs::create_dir_all(&frontend).unwrap();
+        std::fs::write(frontend.join("app.ts"), "").unwrap();
+        std::fs::write(dir.path().join("secret.txt"), "").unwrap();
+        let roots = [backend.clone(), frontend.clone()];
+
+        // Traversal from one root into another is allowed
+        assert!(validate_path_in_roots(&backend.join("../frontend/app.ts"), &roots).is_ok());
+        assert!(validate_write_path_in_roots(&backend.join("../frontend/new.ts"), &roots).is_ok());
+
+        // Escaping every root is still denied, and the error names them all
+        for result in [
+            validate_path_in_roots(&backend.join("../secret.txt"), &roots),
+            validate_write_path_in_roots(&frontend.join("../secret.txt"), &roots),
+        ] {
+            match result {
+                Err(ToolError::PermissionDenied(msg)) => assert!(msg.contains("outside the workspace roots"), "{}", msg),
+                other => panic!("expected PermissionDenied, got {:?}", other),
+            }
+        }
+        // A missing file is reported as such, not as outside the roots
+        assert!(matches!(
+            validate_path_in_roots(&frontend.join("missing.ts"), &roots),
+            Err(ToolError::ResourceNotFound(_))
+        ));
+    }
+}
+
+/// Windows path forms: verbatim prefixes, case, drive-relative paths and device names
+#[cfg(all(test, windows))]
+mod windows_tests {
+    use super::*;
+    use crate::tools::filesystem::WriteFile;
+    use crate::tools::{Tool, ToolExecutionContext};
+    use serde_json::json;
+
+    #[test]
+    fn test_validate_path_accepts_verbatim_paths() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("a.txt"), "a").unwrap();
+        // std's canonicalize returns \\?\C:\... while the workspace is plain
+        let verbatim = std::fs::canonicalize(dir.path().join("a.txt")).unwrap();
+        assert!(verbatim.to_string_lossy().starts_with(r"\\?\"));
+        assert!(validate_path(&verbatim, dir.path()).is_ok());
+
+        let verbatim_workspace = std::fs::canonicalize(dir.path()).unwrap();
+        assert!(validate_path(&dir.path().join("a.txt"), &verbatim_workspace).is_ok());
+    }
+
+    #[test]
+    fn test_workspace_comparison_ignores_case() {
+        let workspace = Path::new(r"C:\Users\Dev\Project");
+        assert!(path_starts_with(Path::new(r"c:\users\dev\PROJECT\src\main.rs"), workspace));
+        assert!(path_starts_with(Path::new(r"\\?\C:\Users\Dev\Project\src"), workspace));
+        assert!(!path_starts_with(Path::new(r"C:\Users\Dev\Project2"), workspace));
+        assert!(validate_write_path(Path::new(r"c:\USERS\dev\project\new.rs"), workspace).is_ok());
+    }
+
+    #[test]
+    fn test_normalize_path_prefixes() {
+        assert_eq!(normalize_path(Path::new(r"\\?\C:\work\repo")), PathBuf::from(r"C:\work\repo"));
+        assert_eq!(normalize_path(Path::new(r"C:\..\..\repo")), PathBuf::from(r"C:\repo"));
+        // Drive-relative: `..` climbs from the drive's current directory
+        assert_eq!(normalize_path(Path::new(r"C:..\repo")), PathBuf::from(r"C:..\repo"));
+        assert_eq!(normalize_path(Path::new(r"C:foo\.\bar")), PathBuf::from(r"C:foo\bar"));
+    }
+
+    #[test]
+    fn test_drive_relative_paths_rejected() {
+        let workspace = Path::new(r"C:\work\repo");
+        assert!(is_drive_relative(Path::new("C:foo")));
+        assert!(!is_drive_relative(Path::new(r"C:\foo")));
+        assert!(!is_drive_relative(Path::new(r"foo\bar")));
+        let err = validate_write_path(Path::new("C:foo"), workspace).unwrap_err();
+        assert!(err.to_string().contains("drive-relative"));
+    }
+
+    #[tokio::test]
+    async fn test_write_rejects_device_names() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let tool = WriteFile::new(dir.path().to_path_buf());
+        for name in ["NUL", "con.txt", r"sub\COM1\file.rs"] {
+            let err = tool
+                .execute(json!({"file_path": name, "content": "x"}), ToolExecutionContext::standalone("test", "Write"))
+                .await
+                .unwrap_err();
+            assert!(err.to_string().contains("reserved device name"), "{name}: {err}");
+        }
+        assert!(
+            tool.execute(json!({"file_path": "console.txt", "content": "x"}), ToolExecutionContext::standalone("test", "Write"))
+                .await
+                .is_ok()
+        );
+    }
 }
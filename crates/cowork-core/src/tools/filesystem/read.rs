@@ -1,16 +1,26 @@
 //! Read file tool
 
+use regex::Regex;
 use serde_json::{json, Value};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use crate::error::ToolError;
+use crate::tools::remote::RemoteWorkspace;
 use crate::tools::{BoxFuture, Tool, ToolExecutionContext, ToolOutput};
 
-use super::{path_to_display, validate_path};
+use super::symbols::{find_symbol, Language};
+use super::{path_to_display, validate_path_with_scratch, WorkspaceRoots};
 
 /// Maximum tokens allowed in file read output (matches Claude Code's limit)
 const MAX_OUTPUT_TOKENS: usize = 25000;
 
+/// Lines of context shown either side of a symbol's source
+const SYMBOL_CONTEXT_LINES: usize = 2;
+
+/// Default lines of context shown either side of an `around_match` match
+const DEFAULT_MATCH_CONTEXT_LINES: usize = 3;
+
 /// Estimate token count for a string
 /// Uses tiktoken if available, otherwise falls back to char/4 approximation
 #[cfg(feature = "tiktoken")]
@@ -30,14 +40,148 @@ fn estimate_tokens(text: &str) -> usize {
     text.len() / 4
 }
 
+/// Numbered lines of a file gathered into blocks for Read's output, within
+/// MAX_OUTPUT_TOKENS
+struct NumberedBlocks<'a> {
+    lines: &'a [&'a str],
+    output: Vec<String>,
+    /// 1-based, inclusive line ranges of each block returned
+    ranges: Vec<(usize, usize)>,
+    tokens: usize,
+    truncated: bool,
+}
+
+impl<'a> NumberedBlocks<'a> {
+    fn new(lines: &'a [&'a str]) -> Self {
+        Self {
+            lines,
+            output: Vec::new(),
+            ranges: Vec::new(),
+            tokens: 0,
+            truncated: false,
+        }
+    }
+
+    /// Add lines `range` (0-based) as a block, separated from the previous
+    /// one by `--`. Returns false once the token budget runs out.
+    fn push(&mut self, range: std::ops::Range<usize>) -> bool {
+        let start = range.start;
+        let mut returned = 0;
+        for i in range {
+            let line = self.lines[i];
+            // Truncate lines longer than 2000 chars
+            let truncated = if line.len() > 2000 {
+                format!("{}...", &line[..2000])
+            } else {
+                line.to_string()
+            };
+            let formatted_line = format!("{:>6}\t{}", i + 1, truncated);
+
+            // Check token limit before adding
+            let line_tokens = estimate_tokens(&formatted_line);
+            if self.tokens + line_tokens > MAX_OUTPUT_TOKENS {
+                self.truncated = true;
+                break;
+            }
+            if returned == 0 && !self.ranges.is_empty() {
+                self.output.push("--".to_string());
+            }
+            self.tokens += line_tokens;
+            self.output.push(formatted_line);
+            returned += 1;
+        }
+        if returned > 0 {
+            self.ranges.push((start + 1, start + returned));
+        }
+        !self.truncated
+    }
+
+    fn lines_returned(&self) -> usize {
+        self.ranges.iter().map(|(start, end)| end - start + 1).sum()
+    }
+
+    fn content(&self) -> String {
+        self.output.join("\n")
+    }
+}
+
 /// Tool for reading file contents
 pub struct ReadFile {
-    workspace: PathBuf,
+    /// Workspace roots; relative paths resolve against the primary one
+    roots: WorkspaceRoots,
+    /// Remote workspace to read from instead of `roots`
+    remote: Option<Arc<RemoteWorkspace>>,
+    /// Session scratch directory, allowed as well as the workspace roots
+    scratch: Option<PathBuf>,
 }
 
 impl ReadFile {
     pub fn new(workspace: PathBuf) -> Self {
-        Self { workspace }
+        Self {
+            roots: WorkspaceRoots::new(workspace),
+            remote: None,
+            scratch: None,
+        }
+    }
+
+    /// Read from a remote workspace, or the local one with `None`
+    pub fn with_remote(mut self, remote: Option<Arc<RemoteWorkspace>>) -> Self {
+        self.remote = remote;
+        self
+    }
+
+    /// Also allow paths in these roots besides the workspace
+    pub fn with_additional_roots(mut self, roots: &[PathBuf]) -> Self {
+        self.roots = self.roots.with_additional(roots.iter().cloned());
+        self
+    }
+
+    /// Also allow paths in the session's scratch directory
+    pub fn with_scratch_dir(mut self, scratch: Option<PathBuf>) -> Self {
+        self.scratch = scratch;
+        self
+    }
+
+    /// Read a file from the remote workspace: its text and display path, or
+    /// the finished output for documents
+    async fn read_remote(
+        &self,
+        remote: &RemoteWorkspace,
+        path_str: &str,
+        params: &Value,
+    ) -> Result<Result<(String, String), ToolOutput>, ToolError> {
+        let path = remote.resolve(path_str)?;
+        let bytes = remote.read(&path).await.map_err(|e| match e {
+            ToolError::InvalidParams(_) => ToolError::InvalidParams(format!(
+                "{} is a directory, not a file. Use the Bash tool with `ls` to list directory contents.",
+                path
+            )),
+            e => e,
+        })?;
+
+        // Documents are extracted locally from a downloaded copy
+        let file_name = Path::new(&path).file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let ext = Path::new(&file_name)
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+        if super::document::is_document(&ext) {
+            let dir = std::env::temp_dir().join(format!("cowork-remote-{}", uuid::Uuid::new_v4()));
+            tokio::fs::create_dir_all(&dir).await.map_err(ToolError::Io)?;
+            let local = dir.join(&file_name);
+            let extracted = match tokio::fs::write(&local, &bytes).await {
+                Ok(()) => super::document::extract_document(&local, params),
+                Err(e) => Err(ToolError::Io(e)),
+            };
+            let _ = tokio::fs::remove_dir_all(&dir).await;
+            return extracted.map(Err);
+        }
+
+        let content = String::from_utf8(bytes).map_err(|_| {
+            ToolError::InvalidParams(format!("{} is not a UTF-8 text file", path))
+        })?;
+        Ok(Ok((content, remote.relative(&path))))
     }
 }
 
@@ -50,6 +194,10 @@ impl Tool for ReadFile {
         crate::prompt::builtin::claude_code::tools::READ
     }
 
+    fn compact_description(&self) -> &str {
+        crate::prompt::builtin::claude_code::tools::READ_COMPACT
+    }
+
     fn parameters_schema(&self) -> Value {
         json!({
             "type": "object",
@@ -60,11 +208,47 @@ impl Tool for ReadFile {
                 },
                 "offset": {
                     "type": "integer",
-                    "description": "The line number to start reading from. Only provide if the file is too large to read at once"
+                    "description": "The line number to start reading from. Only provide if the file is too large to read at once. For spreadsheets, the data row to start from (use next_offset from the previous call)"
                 },
                 "limit": {
                     "type": "integer",
                     "description": "The number of lines to read. Only provide if the file is too large to read at once"
+                },
+                "sheet": {
+                    "type": ["string", "integer"],
+                    "description": "Spreadsheets only: sheet name or 0-based index. Omit to list sheets with their dimensions"
+                },
+                "range": {
+                    "type": "string",
+                    "description": "Spreadsheets only: cell range in A1 notation (e.g. \"A1:D20\" or \"B:D\"). The first row is treated as the header"
+                },
+                "format": {
+                    "type": "string",
+                    "enum": ["text", "csv", "json"],
+                    "description": "Spreadsheets only: output format. json returns row objects keyed by the header row (default: text)"
+                },
+                "max_rows": {
+                    "type": "integer",
+                    "description": "Spreadsheets only: maximum number of data rows to return (default: 500)"
+                },
+                "symbol": {
+                    "type": "string",
+                    "description": "Return only this item's source, e.g. \"parse_args\", \"MyStruct\" or \"MyStruct::my_method\" (\"MyClass.method\" in Python). Supported for Rust, Python, JS/TS, Go, Java, C-family and similar source files"
+                },
+                "around_match": {
+                    "type": "object",
+                    "description": "Return only the lines matching a regex, with context, as numbered blocks",
+                    "properties": {
+                        "pattern": {
+                            "type": "string",
+                            "description": "Regular expression to search the file for"
+                        },
+                        "context_lines": {
+                            "type": "integer",
+                            "description": "Lines of context before and after each match (default: 3)"
+                        }
+                    },
+                    "required": ["pattern"]
                 }
             },
             "required": ["file_path"]
@@ -77,30 +261,48 @@ impl Tool for ReadFile {
                 .as_str()
                 .ok_or_else(|| ToolError::InvalidParams("file_path is required".into()))?;
 
-            let path = self.workspace.join(path_str);
-            let validated = validate_path(&path, &self.workspace)?;
+            let (content, display_path) = if let Some(remote) = &self.remote {
+                match self.read_remote(remote, path_str, &params).await? {
+                    Ok(read) => read,
+                    Err(document) => return Ok(document),
+                }
+            } else {
+                let path = self.roots.resolve(path_str);
+                let validated = validate_path_with_scratch(&path, self.roots.paths(), self.scratch.as_deref())?;
 
-            // Reject directories with a helpful message
-            if validated.is_dir() {
-                return Err(ToolError::InvalidParams(format!(
-                    "{} is a directory, not a file. Use the Bash tool with `ls` to list directory contents.",
-                    path_to_display(&validated)
-                )));
-            }
+                // Reject directories with a helpful message
+                if validated.is_dir() {
+                    return Err(ToolError::InvalidParams(format!(
+                        "{} is a directory, not a file. Use the Bash tool with `ls` to list directory contents.",
+                        path_to_display(&validated)
+                    )));
+                }
 
-            // Check if this is a document file (PDF, Word, Excel, PowerPoint)
-            let ext = validated
-                .extension()
-                .and_then(|e| e.to_str())
-                .unwrap_or("")
-                .to_lowercase();
-            if super::document::is_document(&ext) {
-                return super::document::extract_document(&validated);
-            }
+                // Check if this is a document file (PDF, Word, Excel, PowerPoint)
+                let ext = validated
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .unwrap_or("")
+                    .to_lowercase();
+                if super::document::is_document(&ext) {
+                    return super::document::extract_document(&validated, &params);
+                }
 
-            let content = tokio::fs::read_to_string(&validated)
-                .await
-                .map_err(ToolError::Io)?;
+                let content = tokio::fs::read_to_string(&validated)
+                    .await
+                    .map_err(ToolError::Io)?;
+                (content, self.roots.display(&validated, false))
+            };
+
+            let lines: Vec<&str> = content.lines().collect();
+            let total_lines = lines.len();
+
+            if let Some(symbol) = params["symbol"].as_str() {
+                return read_symbol(&lines, symbol, &display_path);
+            }
+            if !params["around_match"].is_null() {
+                return read_around_match(&lines, &params["around_match"], &display_path);
+            }
 
             // Handle offset and limit for large files
             // Default to 2000 lines as per Claude Code behavior
@@ -112,47 +314,106 @@ impl Tool for ReadFile {
                 .map(|l| l as usize)
                 .unwrap_or(DEFAULT_LINE_LIMIT);
 
-            let lines: Vec<&str> = content.lines().collect();
-            let total_lines = lines.len();
-
-            // Build output lines with line numbers
-            let mut output_lines: Vec<String> = Vec::new();
-            let mut token_count = 0;
-            let mut truncated_by_tokens = false;
-
-            for (i, line) in lines.into_iter().skip(offset).take(limit).enumerate() {
-                let line_num = offset + i + 1;
-                // Truncate lines longer than 2000 chars
-                let truncated = if line.len() > 2000 {
-                    format!("{}...", &line[..2000])
-                } else {
-                    line.to_string()
-                };
-                let formatted_line = format!("{:>6}\t{}", line_num, truncated);
-
-                // Check token limit before adding
-                let line_tokens = estimate_tokens(&formatted_line);
-                if token_count + line_tokens > MAX_OUTPUT_TOKENS {
-                    truncated_by_tokens = true;
-                    break;
-                }
-
-                token_count += line_tokens;
-                output_lines.push(formatted_line);
-            }
+            let mut blocks = NumberedBlocks::new(&lines);
+            blocks.push(offset.min(total_lines)..offset.saturating_add(limit).min(total_lines));
 
-            let formatted_content = output_lines.join("\n");
-            let lines_returned = output_lines.len();
-            let has_more = offset + lines_returned < total_lines || truncated_by_tokens;
+            let lines_returned = blocks.lines_returned();
+            let has_more = offset + lines_returned < total_lines || blocks.truncated;
 
             Ok(ToolOutput::success(json!({
-                "content": formatted_content,
-                "path": path_to_display(&validated),
+                "content": blocks.content(),
+                "path": display_path,
                 "total_lines": total_lines,
                 "offset": offset,
                 "lines_returned": lines_returned,
+                "ranges": blocks.ranges,
                 "has_more": has_more
             })))
         })
     }
+
+    fn is_idempotent(&self) -> bool {
+        true
+    }
+}
+
+/// Read just `symbol`'s source, with SYMBOL_CONTEXT_LINES either side
+fn read_symbol(lines: &[&str], symbol: &str, display_path: &str) -> Result<ToolOutput, ToolError> {
+    let ext = Path::new(display_path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+    let language = Language::from_extension(&ext).ok_or_else(|| {
+        ToolError::InvalidParams(format!(
+            "symbol lookup isn't supported for {}; use around_match or offset/limit instead",
+            display_path
+        ))
+    })?;
+    let item = find_symbol(lines, language, symbol).ok_or_else(|| {
+        ToolError::ExecutionFailed(format!(
+            "Symbol {} not found in {}. Use around_match to search the file instead.",
+            symbol, display_path
+        ))
+    })?;
+
+    let mut blocks = NumberedBlocks::new(lines);
+    blocks.push(item.start.saturating_sub(SYMBOL_CONTEXT_LINES)..(item.end + SYMBOL_CONTEXT_LINES).min(lines.len()));
+
+    Ok(ToolOutput::success(json!({
+        "content": blocks.content(),
+        "path": display_path,
+        "total_lines": lines.len(),
+        "symbol": symbol,
+        "symbol_range": [item.start + 1, item.end],
+        "ranges": blocks.ranges,
+        "has_more": blocks.truncated
+    })))
+}
+
+/// Read only the lines matching `around_match.pattern`, with context, as
+/// numbered blocks with overlapping context merged
+fn read_around_match(lines: &[&str], around_match: &Value, display_path: &str) -> Result<ToolOutput, ToolError> {
+    let pattern = around_match["pattern"]
+        .as_str()
+        .ok_or_else(|| ToolError::InvalidParams("around_match.pattern is required".into()))?;
+    let regex = Regex::new(pattern).map_err(|e| ToolError::InvalidParams(format!("Invalid regex: {}", e)))?;
+    let context = around_match["context_lines"]
+        .as_u64()
+        .map(|c| c as usize)
+        .unwrap_or(DEFAULT_MATCH_CONTEXT_LINES);
+
+    let matches: Vec<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| regex.is_match(line))
+        .map(|(i, _)| i)
+        .collect();
+
+    // Merge the context windows of nearby matches into contiguous blocks
+    let mut windows: Vec<std::ops::Range<usize>> = Vec::new();
+    for &i in &matches {
+        let window = i.saturating_sub(context)..(i + context + 1).min(lines.len());
+        match windows.last_mut() {
+            Some(last) if window.start <= last.end => last.end = window.end,
+            _ => windows.push(window),
+        }
+    }
+
+    let mut blocks = NumberedBlocks::new(lines);
+    for window in windows {
+        if !blocks.push(window) {
+            break;
+        }
+    }
+
+    Ok(ToolOutput::success(json!({
+        "content": blocks.content(),
+        "path": display_path,
+        "total_lines": lines.len(),
+        "pattern": pattern,
+        "matches": matches.len(),
+        "ranges": blocks.ranges,
+        "has_more": blocks.truncated
+    })))
 }
@@ -0,0 +1,292 @@
+//! ripgrep backend for the Grep tool
+//!
+//! Runs `rg --json` and parses its match events. The arguments keep ripgrep's
+//! results in line with the built-in search: ignore files are not honoured and
+//! hidden files are searched (the built-in search walks every file under the
+//! path), globs are anchored to the search path, and binary files are filtered
+//! by the caller with the same check as the built-in search.
+
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use tokio::process::Command;
+use tracing::{debug, warn};
+
+use crate::config::ConfigManager;
+use crate::error::ToolError;
+use crate::tools::remote::{quote, RemoteWorkspace, FILE_OP_TIMEOUT};
+
+/// Environment variable naming the ripgrep binary (`off` disables ripgrep)
+pub const RIPGREP_ENV: &str = "COWORK_RIPGREP";
+
+/// Binary used by `GrepFiles::new`, resolved once per process
+static DEFAULT_BINARY: LazyLock<Option<PathBuf>> = LazyLock::new(resolve_binary);
+
+/// The ripgrep binary to use by default
+///
+/// `COWORK_RIPGREP` wins, then `[general] ripgrep_path`, then `rg` on PATH.
+pub(super) fn default_binary() -> Option<PathBuf> {
+    DEFAULT_BINARY.clone()
+}
+
+fn resolve_binary() -> Option<PathBuf> {
+    if let Ok(value) = std::env::var(RIPGREP_ENV) {
+        let value = value.trim();
+        if value.eq_ignore_ascii_case("off") || value == "0" || value.eq_ignore_ascii_case("false") {
+            debug!("ripgrep disabled by {}", RIPGREP_ENV);
+            return None;
+        }
+        if !value.is_empty() {
+            return Some(PathBuf::from(value));
+        }
+    }
+    if let Ok(manager) = ConfigManager::new()
+        && let Some(path) = manager.config().general.ripgrep_path.clone()
+    {
+        return Some(path);
+    }
+    find_ripgrep()
+}
+
+/// Find an `rg` executable on PATH
+pub fn find_ripgrep() -> Option<PathBuf> {
+    let name = if cfg!(windows) { "rg.exe" } else { "rg" };
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(name))
+        .find(|candidate| candidate.is_file())
+}
+
+/// What to search for
+pub(super) struct RipgrepSearch<'a> {
+    pub pattern: &'a str,
+    /// File or directory to search
+    pub base_path: &'a Path,
+    /// Glob relative to `base_path` (ignored when searching a single file)
+    pub glob: Option<String>,
+    pub case_insensitive: bool,
+    pub multiline: bool,
+    /// Stop at the first match in each file
+    pub first_match_only: bool,
+}
+
+/// A match event: the line(s) matched and the matches within them
+#[derive(Debug)]
+pub(super) struct RipgrepHit {
+    /// 1-based number of the first line in `text`
+    pub line_number: usize,
+    /// Matched line(s) including line terminators
+    pub text: String,
+    /// Byte offset into `text` and matched text of each match
+    pub submatches: Vec<(usize, String)>,
+}
+
+/// Matches in one file, in line order
+#[derive(Debug)]
+pub(super) struct RipgrepFile {
+    pub path: PathBuf,
+    pub hits: Vec<RipgrepHit>,
+}
+
+/// Run ripgrep and group its matches by file (files in the order reported)
+pub(super) async fn search(binary: &Path, search: &RipgrepSearch<'_>) -> Result<Vec<RipgrepFile>, String> {
+    // Searching from inside the directory lets `/`-anchored globs mean
+    // "relative to the search path", like the built-in glob does
+    let (cwd, target) = if search.base_path.is_file() {
+        (search.base_path.parent().unwrap_or(search.base_path), search.base_path)
+    } else {
+        (search.base_path, Path::new("."))
+    };
+
+    let output = Command::new(binary)
+        .current_dir(cwd)
+        .args(arguments(search, search.base_path.is_file()))
+        .arg(target)
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run {}: {}", binary.display(), e))?;
+
+    let files = parse_json_output(&String::from_utf8_lossy(&output.stdout), cwd);
+    check_status(output.status.code(), &String::from_utf8_lossy(&output.stderr), files)
+}
+
+/// Run ripgrep on a remote workspace's host in a single command
+///
+/// `search.base_path` is the resolved remote path. Unlike the local search,
+/// binary files are left to ripgrep's own detection.
+pub(super) async fn search_remote(
+    remote: &RemoteWorkspace,
+    search: &RipgrepSearch<'_>,
+) -> Result<Vec<RipgrepFile>, ToolError> {
+    let join = |args: Vec<String>| args.iter().map(|arg| quote(arg)).collect::<Vec<_>>().join(" ");
+    let mut dir_args = arguments(search, false);
+    dir_args.retain(|arg| arg != "--text");
+    let mut file_args = arguments(search, true);
+    file_args.retain(|arg| arg != "--text");
+    let body = format!(
+        r#"if [ -d "$p" ]; then cd -- "$p" && rg {} .; elif [ -e "$p" ]; then rg {} "$p"; else exit 98; fi"#,
+        join(dir_args),
+        join(file_args)
+    );
+
+    let base = search.base_path.to_string_lossy();
+    let output = remote.run_guarded(&base, &body, FILE_OP_TIMEOUT).await?;
+    match output.status {
+        Some(98) => return Err(ToolError::ResourceNotFound(format!("{} does not exist", base))),
+        Some(127) => {
+            return Err(ToolError::ExecutionFailed(format!(
+                "ripgrep (rg) is not installed on {}; install it or search with the Bash tool",
+                remote.target().host
+            )));
+        }
+        _ => {}
+    }
+    let files = parse_json_output(&String::from_utf8_lossy(&output.stdout), search.base_path);
+    check_status(output.status, &output.stderr, files).map_err(ToolError::ExecutionFailed)
+}
+
+/// Arguments for a search, up to the path to search
+fn arguments(search: &RipgrepSearch<'_>, is_file: bool) -> Vec<String> {
+    let mut args: Vec<String> = ["--json", "--no-config", "--no-ignore", "--hidden", "--follow", "--text", "--crlf"]
+        .into_iter()
+        .chain(["--encoding", "none"])
+        .map(String::from)
+        .collect();
+    if search.case_insensitive {
+        args.push("--ignore-case".into());
+    }
+    if search.multiline {
+        args.extend(["--multiline".into(), "--multiline-dotall".into()]);
+    }
+    if search.first_match_only {
+        args.extend(["--max-count".into(), "1".into()]);
+    }
+    if let Some(glob) = &search.glob
+        && !is_file
+    {
+        args.extend(["--glob".into(), anchored_glob(glob)]);
+    }
+    args.extend(["--regexp".into(), search.pattern.to_string(), "--".into()]);
+    args
+}
+
+/// Results for ripgrep's exit status: 0 = matches, 1 = no matches,
+/// 2 = error (possibly after partial results)
+fn check_status(status: Option<i32>, stderr: &str, files: Vec<RipgrepFile>) -> Result<Vec<RipgrepFile>, String> {
+    match status {
+        Some(0) | Some(1) => Ok(files),
+        Some(2) if !files.is_empty() => {
+            warn!("ripgrep reported errors: {}", stderr.trim());
+            Ok(files)
+        }
+        _ => Err(format!(
+            "ripgrep failed ({}): {}",
+            status.map_or("signal".to_string(), |code| format!("exit status: {}", code)),
+            stderr.trim()
+        )),
+    }
+}
+
+/// Anchor a glob to the search directory (rg matches unanchored globs at any depth)
+fn anchored_glob(glob: &str) -> String {
+    let glob = glob.strip_prefix("./").unwrap_or(glob);
+    if glob.starts_with('/') {
+        glob.to_string()
+    } else {
+        format!("/{}", glob)
+    }
+}
+
+#[derive(Deserialize)]
+struct Event {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    data: Option<MatchData>,
+}
+
+#[derive(Deserialize)]
+struct MatchData {
+    path: Data,
+    lines: Data,
+    line_number: Option<usize>,
+    #[serde(default)]
+    submatches: Vec<Submatch>,
+}
+
+#[derive(Deserialize)]
+struct Submatch {
+    #[serde(rename = "match")]
+    matched: Data,
+    start: usize,
+}
+
+/// Text ripgrep could decode as UTF-8 (`bytes` otherwise, which is skipped
+/// like the built-in search skips files it can't read as text)
+#[derive(Deserialize)]
+struct Data {
+    text: Option<String>,
+}
+
+/// Parse `rg --json` output, resolving paths against `cwd`
+fn parse_json_output(stdout: &str, cwd: &Path) -> Vec<RipgrepFile> {
+    let mut files: Vec<RipgrepFile> = Vec::new();
+    for line in stdout.lines() {
+        // Only "match" events matter; begin/end/context/summary are skipped
+        let Ok(event) = serde_json::from_str::<Event>(line) else {
+            continue;
+        };
+        if event.kind != "match" {
+            continue;
+        }
+        let Some(data) = event.data else { continue };
+        let (Some(path), Some(text), Some(line_number)) = (data.path.text, data.lines.text, data.line_number) else {
+            continue;
+        };
+        let submatches = data
+            .submatches
+            .into_iter()
+            .filter_map(|s| s.matched.text.map(|text| (s.start, text)))
+            .collect();
+
+        let relative = Path::new(&path);
+        let path = cwd.join(relative.strip_prefix(".").unwrap_or(relative));
+        let hit = RipgrepHit { line_number, text, submatches };
+        match files.last_mut() {
+            Some(file) if file.path == path => file.hits.push(hit),
+            _ => files.push(RipgrepFile { path, hits: vec![hit] }),
+        }
+    }
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_anchored_glob() {
+        assert_eq!(anchored_glob("*.rs"), "/*.rs");
+        assert_eq!(anchored_glob("./src/*.rs"), "/src/*.rs");
+        assert_eq!(anchored_glob("**/*.tsx"), "/**/*.tsx");
+    }
+
+    #[test]
+    fn test_parse_json_output() {
+        let stdout = r#"{"type":"begin","data":{"path":{"text":"./src/a.rs"}}}
+{"type":"match","data":{"path":{"text":"./src/a.rs"},"lines":{"text":"fn a() { fn_b() }\n"},"line_number":3,"absolute_offset":20,"submatches":[{"match":{"text":"fn"},"start":0,"end":2},{"match":{"text":"fn"},"start":9,"end":11}]}}
+{"type":"match","data":{"path":{"text":"./src/a.rs"},"lines":{"bytes":"/w=="},"line_number":4,"absolute_offset":40,"submatches":[]}}
+{"type":"end","data":{"path":{"text":"./src/a.rs"},"binary_offset":null,"stats":{}}}
+{"type":"match","data":{"path":{"text":"./b.rs"},"lines":{"text":"fn main() {}\n"},"line_number":1,"absolute_offset":0,"submatches":[{"match":{"text":"fn"},"start":0,"end":2}]}}
+{"type":"summary","data":{"elapsed_total":{"secs":0,"nanos":1},"stats":{}}}"#;
+        let files = parse_json_output(stdout, Path::new("/ws"));
+
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path, Path::new("/ws/src/a.rs"));
+        assert_eq!(files[0].hits.len(), 1);
+        assert_eq!(files[0].hits[0].line_number, 3);
+        assert_eq!(files[0].hits[0].submatches, vec![(0, "fn".to_string()), (9, "fn".to_string())]);
+        assert_eq!(files[1].path, Path::new("/ws/b.rs"));
+    }
+}
@@ -0,0 +1,254 @@
+//! Workspace roots: the directories a session's file tools may use
+//!
+//! A session has a primary root, its workspace, where relative paths resolve,
+//! and may have additional roots such as a sibling repository. Absolute paths
+//! within any root are allowed; paths outside every root are still rejected.
+//!
+//! Glob and Grep search the primary root unless their `root` parameter picks
+//! another root, or every root with `"all"`. Results from another root, or
+//! from every root at once, are prefixed with the root's name
+//! (`frontend/src/app.ts`), and the file tools resolve a path starting with a
+//! root's name into that root.
+
+use std::path::{Component, Path, PathBuf};
+
+use serde_json::{json, Value};
+
+use crate::error::ToolError;
+
+use super::path_to_display;
+
+/// `root` value that selects every root
+pub const ALL_ROOTS: &str = "all";
+
+/// The roots of a workspace, the primary root first
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkspaceRoots {
+    paths: Vec<PathBuf>,
+    /// Name of each root: its directory name, made unique
+    names: Vec<String>,
+}
+
+impl WorkspaceRoots {
+    /// Roots with only the primary one
+    pub fn new(primary: PathBuf) -> Self {
+        let mut roots = Self {
+            paths: Vec::new(),
+            names: Vec::new(),
+        };
+        roots.push(primary);
+        roots
+    }
+
+    /// Add roots after the existing ones, skipping any already present
+    pub fn with_additional(mut self, roots: impl IntoIterator<Item = PathBuf>) -> Self {
+        for root in roots {
+            if !self.paths.iter().any(|path| dunce::simplified(path) == dunce::simplified(&root)) {
+                self.push(root);
+            }
+        }
+        self
+    }
+
+    fn push(&mut self, path: PathBuf) {
+        let base = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .filter(|name| name != ALL_ROOTS)
+            .unwrap_or_else(|| format!("root{}", self.paths.len()));
+        let mut name = base.clone();
+        let mut suffix = 2;
+        while self.names.contains(&name) {
+            name = format!("{}-{}", base, suffix);
+            suffix += 1;
+        }
+        self.paths.push(path);
+        self.names.push(name);
+    }
+
+    /// The primary root, where relative paths resolve
+    pub fn primary(&self) -> &Path {
+        &self.paths[0]
+    }
+
+    /// Every root, the primary first
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    /// The roots other than the primary one
+    pub fn additional(&self) -> &[PathBuf] {
+        &self.paths[1..]
+    }
+
+    /// Whether there is more than the primary root
+    pub fn is_multi_root(&self) -> bool {
+        self.paths.len() > 1
+    }
+
+    /// Name of the root at `index`
+    pub fn name(&self, index: usize) -> &str {
+        &self.names[index]
+    }
+
+    /// Resolve a path from a tool call
+    ///
+    /// Absolute paths are kept. A relative path resolves against the primary
+    /// root, unless its first component is another root's name and the
+    /// primary root has no entry by that name: then it resolves into that root.
+    pub fn resolve(&self, path: &str) -> PathBuf {
+        let relative = Path::new(path);
+        if self.is_multi_root()
+            && let Some(Component::Normal(first)) = relative.components().next()
+            && !self.primary().join(first).exists()
+            && let Some(index) = self.names.iter().position(|name| first == name.as_str())
+        {
+            return self.paths[index].join(relative.components().skip(1).collect::<PathBuf>());
+        }
+        self.primary().join(path)
+    }
+
+    /// The roots a `root` parameter selects, as indexes: the primary root when
+    /// it's missing, every root for `"all"`, else the root with that name or index
+    pub fn select(&self, root: &Value) -> Result<Vec<usize>, ToolError> {
+        let index = match root {
+            Value::Null => Some(0),
+            Value::String(s) if s == ALL_ROOTS => return Ok((0..self.paths.len()).collect()),
+            Value::String(s) => self
+                .names
+                .iter()
+                .position(|name| name == s)
+                .or_else(|| s.parse().ok()),
+            Value::Number(n) => n.as_u64().map(|n| n as usize),
+            _ => None,
+        };
+        match index {
+            Some(index) if index < self.paths.len() => Ok(vec![index]),
+            _ => Err(ToolError::InvalidParams(format!(
+                "Unknown root {}; the roots are {} or \"{}\"",
+                root,
+                self.names
+                    .iter()
+                    .enumerate()
+                    .map(|(i, name)| format!("{} ({})", name, i))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                ALL_ROOTS
+            ))),
+        }
+    }
+
+    /// Display a path found in the roots: relative to the root holding it,
+    /// prefixed with the root's name if that isn't the primary root or
+    /// `prefixed` is set. Paths outside every root are shown in full.
+    pub fn display(&self, path: &Path, prefixed: bool) -> String {
+        for (index, root) in self.paths.iter().enumerate() {
+            if let Ok(relative) = path.strip_prefix(root) {
+                let relative = path_to_display(relative);
+                return if index == 0 && !prefixed {
+                    relative
+                } else {
+                    format!("{}/{}", self.names[index], relative)
+                };
+            }
+        }
+        path_to_display(path)
+    }
+
+    /// The roots described for the system prompt's `${WORKING_DIRECTORY}`:
+    /// just the primary root's path when it is the only one
+    pub fn describe(&self) -> String {
+        if !self.is_multi_root() {
+            return self.primary().display().to_string();
+        }
+        let mut lines = vec![format!(
+            "{} (root \"{}\", where relative paths resolve)",
+            self.primary().display(),
+            self.names[0]
+        )];
+        lines.push("Other workspace roots (absolute paths in them are allowed; Glob and Grep search them with `root`):".to_string());
+        for (name, path) in self.names.iter().zip(&self.paths).skip(1) {
+            lines.push(format!("- \"{}\": {}", name, path.display()));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Schema of the `root` parameter Glob and Grep take in a multi-root workspace
+pub(super) fn root_param_schema() -> Value {
+    json!({
+        "type": "string",
+        "description": "Workspace root to search: a root's name or index, or \"all\" for every root. Defaults to the primary root. Results from other roots start with the root's name; pass such paths to the file tools as they are."
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roots() -> WorkspaceRoots {
+        WorkspaceRoots::new(PathBuf::from("/work/backend"))
+            .with_additional([PathBuf::from("/work/frontend"), PathBuf::from("/other/frontend"), PathBuf::from("/work/backend")])
+    }
+
+    #[test]
+    fn test_names_are_unique() {
+        let roots = roots();
+        assert_eq!(roots.paths().len(), 3);
+        assert_eq!(roots.name(0), "backend");
+        assert_eq!(roots.name(1), "frontend");
+        assert_eq!(roots.name(2), "frontend-2");
+    }
+
+    #[test]
+    fn test_select() {
+        let roots = roots();
+        assert_eq!(roots.select(&Value::Null).unwrap(), vec![0]);
+        assert_eq!(roots.select(&json!("frontend")).unwrap(), vec![1]);
+        assert_eq!(roots.select(&json!(2)).unwrap(), vec![2]);
+        assert_eq!(roots.select(&json!("1")).unwrap(), vec![1]);
+        assert_eq!(roots.select(&json!("all")).unwrap(), vec![0, 1, 2]);
+        let err = roots.select(&json!("docs")).unwrap_err().to_string();
+        assert!(err.contains("backend (0), frontend (1), frontend-2 (2)"), "{}", err);
+        assert!(roots.select(&json!(3)).is_err());
+    }
+
+    #[test]
+    fn test_display_prefixes_other_roots() {
+        let roots = roots();
+        assert_eq!(roots.display(Path::new("/work/backend/src/main.rs"), false), "src/main.rs");
+        assert_eq!(roots.display(Path::new("/work/backend/src/main.rs"), true), "backend/src/main.rs");
+        assert_eq!(roots.display(Path::new("/work/frontend/src/app.ts"), false), "frontend/src/app.ts");
+        assert_eq!(roots.display(Path::new("/elsewhere/x"), false), "/elsewhere/x");
+    }
+
+    #[test]
+    fn test_resolve_root_prefixed_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = dir.path().join("backend");
+        let frontend = dir.path().join("frontend");
+        std::fs::create_dir_all(backend.join("docs")).unwrap();
+        std::fs::create_dir_all(&frontend).unwrap();
+        let roots = WorkspaceRoots::new(backend.clone()).with_additional([frontend.clone()]);
+
+        assert_eq!(roots.resolve("src/main.rs"), backend.join("src/main.rs"));
+        assert_eq!(roots.resolve("frontend/src/app.ts"), frontend.join("src/app.ts"));
+        assert_eq!(roots.resolve("backend/src/main.rs"), backend.join("src/main.rs"));
+
+        // An entry of the primary root wins over a root's name
+        std::fs::create_dir_all(backend.join("frontend")).unwrap();
+        assert_eq!(roots.resolve("frontend/src/app.ts"), backend.join("frontend/src/app.ts"));
+
+        // Without other roots, paths always resolve against the workspace
+        let single = WorkspaceRoots::new(backend.clone());
+        assert_eq!(single.resolve("backend/x"), backend.join("backend/x"));
+    }
+
+    #[test]
+    fn test_describe() {
+        assert_eq!(WorkspaceRoots::new(PathBuf::from("/work/backend")).describe(), "/work/backend");
+        let described = roots().describe();
+        assert!(described.starts_with("/work/backend (root \"backend\""), "{}", described);
+        assert!(described.contains("- \"frontend\": /work/frontend"), "{}", described);
+    }
+}
@@ -0,0 +1,376 @@
+//! SearchFiles tool - one query over file names, paths and contents
+//!
+//! Walks the workspace (skipping hidden entries and dependency and build
+//! directories, as the project tree does) and scores every file the query
+//! matches. An exact file name beats a name match, which beats a directory
+//! in the path, which beats content, where more matching lines score higher.
+//! Matches of several kinds add up, so a file named after the query that also
+//! mentions it comes before one that is only named after it.
+
+use regex::{Regex, RegexBuilder};
+use serde_json::{json, Value};
+use std::path::{Component, Path, PathBuf};
+
+use crate::context::gather::TREE_SKIP;
+use crate::error::ToolError;
+use crate::tools::{BoxFuture, Tool, ToolExecutionContext, ToolOutput};
+
+use super::roots::root_param_schema;
+use super::WorkspaceRoots;
+
+/// Results per page when the call doesn't say
+const DEFAULT_LIMIT: usize = 20;
+
+/// Most results per page
+const MAX_LIMIT: usize = 100;
+
+/// Most files looked at in one search
+const MAX_FILES: usize = 20_000;
+
+/// Larger files are matched by name and path only
+const MAX_CONTENT_BYTES: u64 = 1024 * 1024;
+
+/// Longest matching line returned, in characters
+const MAX_LINE_CHARS: usize = 200;
+
+/// The file name (or its name without extension) is the query
+const EXACT_NAME_SCORE: u32 = 1000;
+/// The file name contains the query
+const NAME_SCORE: u32 = 600;
+/// The query's characters appear in order in the file name (less per gap)
+const FUZZY_NAME_SCORE: u32 = 300;
+/// A directory in the path is, or contains, the query
+const PATH_SCORE: u32 = 200;
+/// Per matching line, up to `MAX_CONTENT_SCORE`; below a path match
+const CONTENT_SCORE_PER_LINE: u32 = 10;
+const MAX_CONTENT_SCORE: u32 = 150;
+
+/// What a search looks at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scope {
+    /// Names, paths and contents (`query`)
+    All,
+    /// Names and paths only (the older `pattern` parameter)
+    Names,
+    /// Contents only (the older `content` parameter)
+    Contents,
+}
+
+/// The query, as a literal or a regex; both ignore case
+enum Matcher {
+    Literal(String),
+    Regex(Regex),
+}
+
+impl Matcher {
+    fn new(query: &str, regex: bool) -> Result<Self, ToolError> {
+        if !regex {
+            return Ok(Matcher::Literal(query.to_lowercase()));
+        }
+        RegexBuilder::new(query)
+            .case_insensitive(true)
+            .build()
+            .map(Matcher::Regex)
+            .map_err(|e| ToolError::InvalidParams(format!("Invalid regex: {}", e)))
+    }
+
+    fn is_match(&self, text: &str) -> bool {
+        match self {
+            Matcher::Literal(query) => text.to_lowercase().contains(query.as_str()),
+            Matcher::Regex(regex) => regex.is_match(text),
+        }
+    }
+
+    /// Whether `text` is the whole query (a regex has to match all of it)
+    fn is_exact(&self, text: &str) -> bool {
+        match self {
+            Matcher::Literal(query) => text.to_lowercase() == *query,
+            Matcher::Regex(regex) => regex.find(text).is_some_and(|m| m.start() == 0 && m.end() == text.len()),
+        }
+    }
+}
+
+/// Score of the query's characters appearing in order in `name`, fewer and
+/// shorter gaps scoring higher (None if they don't all appear)
+fn fuzzy_score(query: &str, name: &str) -> Option<u32> {
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+    let mut position = 0;
+    let mut first = None;
+    for wanted in query.chars() {
+        let found = position + name[position..].iter().position(|c| *c == wanted)?;
+        first.get_or_insert(found);
+        position = found + 1;
+    }
+    let span = position - first.unwrap_or(0);
+    let gaps = span.saturating_sub(query.chars().count()) as u32;
+    Some(FUZZY_NAME_SCORE.saturating_sub(gaps * 20).max(FUZZY_NAME_SCORE / 3))
+}
+
+/// One scored file
+#[derive(Debug)]
+struct Hit {
+    path: PathBuf,
+    score: u32,
+    matches: Vec<&'static str>,
+    /// First matching line (1-based) and its text, for content matches
+    line: Option<(usize, String)>,
+    content_matches: usize,
+    size: u64,
+    modified: Option<std::time::SystemTime>,
+}
+
+/// Score `path` (relative to its root as `relative`), or None if nothing matched
+fn score_file(path: &Path, relative: &Path, matcher: &Matcher, scope: Scope) -> Option<Hit> {
+    let metadata = std::fs::metadata(path).ok()?;
+    let mut score = 0;
+    let mut matches = Vec::new();
+
+    if scope != Scope::Contents {
+        let name = relative.file_name()?.to_string_lossy();
+        let stem = relative.file_stem().map(|s| s.to_string_lossy()).unwrap_or_default();
+        let name_score = if matcher.is_exact(&name) || matcher.is_exact(&stem) {
+            Some(EXACT_NAME_SCORE)
+        } else if matcher.is_match(&name) {
+            Some(NAME_SCORE)
+        } else if let Matcher::Literal(query) = matcher {
+            fuzzy_score(query, &name)
+        } else {
+            None
+        };
+        if let Some(name_score) = name_score {
+            score += name_score;
+            matches.push("name");
+        }
+
+        let in_directory = relative
+            .parent()
+            .into_iter()
+            .flat_map(Path::components)
+            .any(|c| matches!(c, Component::Normal(dir) if matcher.is_match(&dir.to_string_lossy())));
+        if in_directory {
+            score += PATH_SCORE;
+            matches.push("path");
+        }
+    }
+
+    let mut line = None;
+    let mut content_matches = 0;
+    if scope != Scope::Names
+        && metadata.len() <= MAX_CONTENT_BYTES
+        && let Ok(bytes) = std::fs::read(path)
+        && !bytes[..bytes.len().min(8000)].contains(&0)
+    {
+        let text = String::from_utf8_lossy(&bytes);
+        for (index, text_line) in text.lines().enumerate() {
+            if matcher.is_match(text_line) {
+                content_matches += 1;
+                line.get_or_insert_with(|| (index + 1, text_line.trim().chars().take(MAX_LINE_CHARS).collect()));
+            }
+        }
+        if content_matches > 0 {
+            score += (content_matches as u32 * CONTENT_SCORE_PER_LINE).min(MAX_CONTENT_SCORE);
+            matches.push("content");
+        }
+    }
+
+    (score > 0).then(|| Hit {
+        path: path.to_path_buf(),
+        score,
+        matches,
+        line,
+        content_matches,
+        size: metadata.len(),
+        modified: metadata.modified().ok(),
+    })
+}
+
+/// Score the files under `base`; true if the walk stopped at `MAX_FILES`
+fn search_tree(root: &Path, base: &Path, matcher: &Matcher, scope: Scope, hits: &mut Vec<Hit>, seen: &mut usize) -> bool {
+    let files = walkdir::WalkDir::new(base)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| {
+            if entry.depth() == 0 {
+                return true;
+            }
+            let name = entry.file_name().to_string_lossy();
+            let skip = name.starts_with('.') || (entry.file_type().is_dir() && TREE_SKIP.contains(&name.as_ref()));
+            !skip
+        })
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file());
+    for entry in files {
+        if *seen == MAX_FILES {
+            return true;
+        }
+        *seen += 1;
+        let relative = entry.path().strip_prefix(root).unwrap_or(entry.path());
+        hits.extend(score_file(entry.path(), relative, matcher, scope));
+    }
+    false
+}
+
+/// Tool for finding files by name, path and content at once
+pub struct SearchFiles {
+    /// Workspace roots; the primary one is searched unless `root` says otherwise
+    roots: WorkspaceRoots,
+}
+
+impl SearchFiles {
+    pub fn new(workspace: PathBuf) -> Self {
+        Self {
+            roots: WorkspaceRoots::new(workspace),
+        }
+    }
+
+    /// Let `root` pick these roots besides the workspace
+    pub fn with_additional_roots(mut self, roots: &[PathBuf]) -> Self {
+        self.roots = self.roots.with_additional(roots.iter().cloned());
+        self
+    }
+}
+
+impl Tool for SearchFiles {
+    fn name(&self) -> &str {
+        "SearchFiles"
+    }
+
+    fn description(&self) -> &str {
+        crate::prompt::builtin::claude_code::tools::SEARCH_FILES
+    }
+
+    fn compact_description(&self) -> &str {
+        crate::prompt::builtin::claude_code::tools::SEARCH_FILES_COMPACT
+    }
+
+    fn parameters_schema(&self) -> Value {
+        let mut schema = json!({
+            "type": "object",
+            "properties": {
+                "query": {
+                    "type": "string",
+                    "description": "What to look for in file names, directory names and file contents (case-insensitive)"
+                },
+                "regex": {
+                    "type": "boolean",
+                    "description": "Treat the query as a regular expression instead of literal text (default false)"
+                },
+                "path": {
+                    "type": "string",
+                    "description": "Directory to search, relative to the workspace. Defaults to the whole workspace."
+                },
+                "limit": {
+                    "type": "integer",
+                    "description": "Results per page (default 20, at most 100)"
+                },
+                "cursor": {
+                    "type": "string",
+                    "description": "The next_cursor of an earlier result, to get the following page"
+                }
+            },
+            "required": ["query"]
+        });
+        // Only offered when there is another root to pick
+        if self.roots.is_multi_root() {
+            schema["properties"]["root"] = root_param_schema();
+        }
+        schema
+    }
+
+    fn execute(&self, params: Value, _ctx: ToolExecutionContext) -> BoxFuture<'_, Result<ToolOutput, ToolError>> {
+        Box::pin(async move {
+            // `pattern` (names) and `content` (contents) are older forms of `query`
+            let (query, scope) = match (params["query"].as_str(), params["pattern"].as_str(), params["content"].as_str()) {
+                (Some(query), _, _) => (query, Scope::All),
+                (None, Some(pattern), _) => (pattern, Scope::Names),
+                (None, None, Some(content)) => (content, Scope::Contents),
+                (None, None, None) => return Err(ToolError::InvalidParams("query is required".into())),
+            };
+            if query.is_empty() {
+                return Err(ToolError::InvalidParams("query must not be empty".into()));
+            }
+            let matcher = Matcher::new(query, params["regex"].as_bool().unwrap_or(false))?;
+            let limit = params["limit"].as_u64().map_or(DEFAULT_LIMIT, |n| n as usize).clamp(1, MAX_LIMIT);
+            let offset = match params["cursor"].as_str() {
+                Some(cursor) => cursor.parse::<usize>().map_err(|_| {
+                    ToolError::InvalidParams("cursor must be the next_cursor of an earlier result".into())
+                })?,
+                None => 0,
+            };
+            let subdirectory = params["path"].as_str().map(PathBuf::from);
+            if let Some(dir) = &subdirectory
+                && (dir.is_absolute() || dir.components().any(|c| matches!(c, Component::ParentDir)))
+            {
+                return Err(ToolError::InvalidParams(
+                    "path must be a directory inside the workspace, relative to it".into(),
+                ));
+            }
+
+            // Results are prefixed with their root's name unless they all
+            // come from the primary root
+            let selected = self.roots.select(&params["root"])?;
+            let prefixed = selected != [0];
+            let roots: Vec<PathBuf> = selected.iter().map(|i| self.roots.paths()[*i].clone()).collect();
+
+            let (mut hits, truncated) = tokio::task::spawn_blocking(move || {
+                let mut hits = Vec::new();
+                let mut seen = 0;
+                let mut truncated = false;
+                for root in &roots {
+                    let base = subdirectory.as_ref().map_or_else(|| root.clone(), |dir| root.join(dir));
+                    truncated |= search_tree(root, &base, &matcher, scope, &mut hits, &mut seen);
+                }
+                (hits, truncated)
+            })
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(format!("Search failed: {}", e)))?;
+
+            // Best first; equal scores in path order
+            hits.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.path.cmp(&b.path)));
+            let total = hits.len();
+            let results: Vec<Value> = hits
+                .drain(..)
+                .skip(offset)
+                .take(limit)
+                .map(|hit| {
+                    let mut result = json!({
+                        "path": self.roots.display(&hit.path, prefixed),
+                        "score": hit.score,
+                        "matches": hit.matches,
+                        "size": hit.size,
+                    });
+                    if let Some(modified) = hit.modified {
+                        result["modified"] = json!(chrono::DateTime::<chrono::Utc>::from(modified)
+                            .to_rfc3339_opts(chrono::SecondsFormat::Secs, true));
+                    }
+                    if let Some((line_number, line)) = hit.line {
+                        result["line_number"] = json!(line_number);
+                        result["line"] = json!(line);
+                        result["content_matches"] = json!(hit.content_matches);
+                    }
+                    result
+                })
+                .collect();
+
+            let mut output = json!({
+                "query": query,
+                "results": results,
+                "total": total,
+            });
+            if offset + limit < total {
+                output["next_cursor"] = json!((offset + limit).to_string());
+            }
+            if truncated {
+                output["truncated"] = json!(format!(
+                    "Stopped after {} files; narrow the search with path",
+                    MAX_FILES
+                ));
+            }
+            Ok(ToolOutput::success(output))
+        })
+    }
+
+    fn is_idempotent(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,247 @@
+//! Locating a named item in source code, for Read's `symbol` mode
+//!
+//! A heuristic, line-based parser: a declaration is recognised by a regex on
+//! the line it starts on, and it ends where its braces balance (or at a `;`
+//! before any brace) or, for Python, where the indentation drops back. Good
+//! enough to cut one function or type out of a file without a language server.
+
+use std::ops::Range;
+
+use regex::Regex;
+
+/// How a language's items are declared and delimited
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Language {
+    Rust,
+    Python,
+    /// Other languages whose blocks are delimited by braces (JS/TS, Go, Java, C, ...)
+    Brace,
+}
+
+impl Language {
+    /// The language of a file with extension `ext`, if symbols can be found in it
+    pub(super) fn from_extension(ext: &str) -> Option<Self> {
+        match ext {
+            "rs" => Some(Self::Rust),
+            "py" | "pyi" => Some(Self::Python),
+            "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "go" | "java" | "kt" | "kts" | "scala" | "swift" | "c"
+            | "h" | "cc" | "cpp" | "cxx" | "hpp" | "cs" | "php" => Some(Self::Brace),
+            _ => None,
+        }
+    }
+
+    /// Split `Type::method` (or `Type.method` outside Rust) into names to resolve in turn
+    fn segments(self, symbol: &str) -> Vec<&str> {
+        let segments: Vec<&str> = match self {
+            Self::Rust => symbol.split("::").collect(),
+            _ => symbol.split("::").flat_map(|s| s.split('.')).collect(),
+        };
+        segments.into_iter().map(str::trim).filter(|s| !s.is_empty()).collect()
+    }
+
+    /// Regex for a line declaring `name` itself
+    fn item_pattern(self, name: &str) -> Regex {
+        let name = regex::escape(name);
+        let pattern = match self {
+            Self::Rust => format!(
+                r#"^\s*(?:pub(?:\s*\([^)]*\))?\s+)?(?:(?:default|async|const|unsafe|extern(?:\s+"[^"]*")?)\s+)*(?:fn|struct|enum|trait|union|mod|type|const|static|macro_rules!)\s+{}\b"#,
+                name
+            ),
+            Self::Python => format!(r"^\s*(?:async\s+)?(?:def|class)\s+{}\b", name),
+            Self::Brace => format!(
+                concat!(
+                    r"^\s*(?:[\w@]+\s+)*(?:function\*?|class|interface|struct|enum|type|func|fun|trait|object|namespace|record)\s+(?:\([^)]*\)\s*)?{0}\b",
+                    r"|^\s*(?:export\s+)?(?:const|let|var)\s+{0}\s*=",
+                    r"|^\s*(?:[\w<>\[\],.?*&:@]+\s+)*\*?&?{0}\s*(?:<[^>]*>)?\s*\([^;]*$",
+                ),
+                name
+            ),
+        };
+        Regex::new(&pattern).expect("symbol pattern is valid")
+    }
+
+    /// Regex for a line opening a block whose items belong to `name`, like a
+    /// Rust `impl` or `trait`
+    fn container_pattern(self, name: &str) -> Regex {
+        match self {
+            Self::Rust => Regex::new(&format!(
+                r"^\s*(?:pub(?:\s*\([^)]*\))?\s+)?(?:unsafe\s+)?(?:impl(?:<.*?>)?\s+(?:.*?\s+for\s+)?|trait\s+|mod\s+){}\b",
+                regex::escape(name)
+            ))
+            .expect("symbol pattern is valid"),
+            _ => self.item_pattern(name),
+        }
+    }
+
+    /// Whether `line` matches `pattern` as a declaration rather than, say, a
+    /// call in an `if` that happens to look like a C-style function header
+    fn declares(self, pattern: &Regex, line: &str) -> bool {
+        const STATEMENTS: &[&str] = &["if", "else", "for", "while", "switch", "return", "await", "new", "throw", "}"];
+        pattern.is_match(line)
+            && (self != Self::Brace
+                || !line
+                    .split(|c: char| c.is_whitespace() || c == '(')
+                    .find(|word| !word.is_empty())
+                    .is_some_and(|word| STATEMENTS.contains(&word)))
+    }
+
+    /// Whether `line` is a doc comment, attribute or decorator belonging to the item below it
+    fn is_preamble(self, line: &str) -> bool {
+        let line = line.trim_start();
+        match self {
+            Self::Rust => line.starts_with("///") || line.starts_with("#["),
+            Self::Python => line.starts_with('@'),
+            Self::Brace => {
+                line.starts_with('@')
+                    || line.starts_with("///")
+                    || line.starts_with("/**")
+                    || line.starts_with("* ")
+                    || line == "*"
+                    || line.starts_with("*/")
+            }
+        }
+    }
+
+    /// Lines of the item declared on line `start`, not reaching past `limit`
+    fn extent(self, lines: &[&str], start: usize, limit: usize) -> Range<usize> {
+        match self {
+            Self::Python => indent_extent(lines, start, limit),
+            _ => brace_extent(lines, start, limit),
+        }
+    }
+}
+
+/// Find `symbol` (`name`, or `Type::method` to look inside a type's impls or
+/// class) in `lines`, returning the 0-based lines of its source, including
+/// its doc comments and attributes. The first match in the file wins.
+pub(super) fn find_symbol(lines: &[&str], language: Language, symbol: &str) -> Option<Range<usize>> {
+    let segments = language.segments(symbol);
+    if segments.is_empty() {
+        return None;
+    }
+    let found = resolve(lines, language, 0..lines.len(), &segments)?;
+    let mut start = found.start;
+    while start > 0 && language.is_preamble(lines[start - 1]) {
+        start -= 1;
+    }
+    Some(start..found.end)
+}
+
+fn resolve(lines: &[&str], language: Language, scope: Range<usize>, segments: &[&str]) -> Option<Range<usize>> {
+    let (name, rest) = segments.split_first()?;
+    if rest.is_empty() {
+        // The item itself, or failing that a block for it (say, an impl of a foreign type)
+        let item = language.item_pattern(name);
+        let container = language.container_pattern(name);
+        return [item, container].iter().find_map(|pattern| {
+            scope
+                .clone()
+                .find(|&i| language.declares(pattern, lines[i]))
+                .map(|i| language.extent(lines, i, scope.end))
+        });
+    }
+
+    let container = language.container_pattern(name);
+    scope.clone().filter(|&i| language.declares(&container, lines[i])).find_map(|i| {
+        let block = language.extent(lines, i, scope.end);
+        resolve(lines, language, block.start + 1..block.end, rest)
+    })
+}
+
+/// An item that ends where its braces balance, or at a `;` before any brace
+fn brace_extent(lines: &[&str], start: usize, limit: usize) -> Range<usize> {
+    let mut depth = 0usize;
+    let mut nesting = 0usize;
+    let mut opened = false;
+    let mut in_block_comment = false;
+
+    for (i, line) in lines.iter().enumerate().take(limit).skip(start) {
+        let chars: Vec<char> = line.chars().collect();
+        let mut j = 0;
+        while j < chars.len() {
+            let c = chars[j];
+            let next = chars.get(j + 1).copied();
+            if in_block_comment {
+                if c == '*' && next == Some('/') {
+                    in_block_comment = false;
+                    j += 1;
+                }
+                j += 1;
+                continue;
+            }
+            match c {
+                '/' if next == Some('/') => break,
+                '/' if next == Some('*') => {
+                    in_block_comment = true;
+                    j += 1;
+                }
+                '"' | '`' => {
+                    // Skip the string, honouring escapes; strings spanning lines are rare enough to ignore
+                    j += 1;
+                    while j < chars.len() && chars[j] != c {
+                        if chars[j] == '\\' {
+                            j += 1;
+                        }
+                        j += 1;
+                    }
+                }
+                // A brace or quote as a character literal, not a lifetime
+                '\'' if chars.get(j + 2) == Some(&'\'') => j += 2,
+                '\'' if next == Some('\\') && chars.get(j + 3) == Some(&'\'') => j += 3,
+                '{' => {
+                    depth += 1;
+                    opened = true;
+                }
+                '}' => {
+                    depth = depth.saturating_sub(1);
+                    if opened && depth == 0 {
+                        return start..i + 1;
+                    }
+                }
+                '(' | '[' => nesting += 1,
+                ')' | ']' => nesting = nesting.saturating_sub(1),
+                ';' if !opened && nesting == 0 => return start..i + 1,
+                _ => {}
+            }
+            j += 1;
+        }
+    }
+    start..limit
+}
+
+/// A Python block: its header (however many lines its brackets span), then
+/// every line indented deeper than the header
+fn indent_extent(lines: &[&str], start: usize, limit: usize) -> Range<usize> {
+    let indent = indentation(lines[start]);
+    let mut nesting = 0i32;
+    let mut header_end = start;
+    for (i, line) in lines.iter().enumerate().take(limit).skip(start) {
+        for c in line.split('#').next().unwrap_or("").chars() {
+            match c {
+                '(' | '[' | '{' => nesting += 1,
+                ')' | ']' | '}' => nesting -= 1,
+                _ => {}
+            }
+        }
+        header_end = i;
+        if nesting <= 0 {
+            break;
+        }
+    }
+
+    let mut end = header_end + 1;
+    for (i, line) in lines.iter().enumerate().take(limit).skip(header_end + 1) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if indentation(line) <= indent {
+            break;
+        }
+        end = i + 1;
+    }
+    start..end
+}
+
+fn indentation(line: &str) -> usize {
+    line.chars().take_while(|c| c.is_whitespace()).count()
+}
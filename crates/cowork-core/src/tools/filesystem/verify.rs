@@ -0,0 +1,444 @@
+//! Syntax check of files after Edit and Write
+//!
+//! Most broken edits are plain syntax errors the model fixes at once when it
+//! is told about them. After a write to a file in a language it knows, the
+//! verifier runs a cheap parser over the new content and the problems go
+//! into the tool result's `verification` field, so the model sees them in
+//! the same turn:
+//!
+//! - `rust`: `rustfmt --emit stdout` over the content (the edition is read
+//!   from the nearest Cargo.toml; out-of-line modules aren't followed)
+//! - `python`: `compile()` in `python3`
+//! - `javascript`: `node --check`
+//! - `json`: parsed in-process
+//!
+//! A language's command can be replaced, and commands added for other file
+//! extensions; `{file}` in a command is replaced with the file's path and
+//! `file:line[:column]: message` lines in its output become problems. A
+//! checker that isn't installed, fails to start or times out leaves a note
+//! instead: the write itself always stands.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+
+use crate::tools::process_utils::{direct_command, shell_command};
+
+use super::shell_escape_path;
+
+/// Default time a checker may take
+pub const DEFAULT_VERIFY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Most problems reported for one file
+const MAX_PROBLEMS: usize = 10;
+
+/// Longest problem message kept
+const MAX_MESSAGE_CHARS: usize = 300;
+
+/// Built-in languages and their file extensions
+const LANGUAGES: &[(&str, &[&str])] = &[
+    ("rust", &["rs"]),
+    ("python", &["py", "pyi"]),
+    ("javascript", &["js", "mjs", "cjs"]),
+    ("json", &["json"]),
+];
+
+/// Reports the first syntax error in the content on stdin as `line:column: message`
+const PYTHON_CHECK: &str = "import sys
+try:
+    compile(sys.stdin.buffer.read(), '<file>', 'exec')
+except SyntaxError as e:
+    print(f'{e.lineno or 0}:{e.offset or 0}: {e.msg}')
+    sys.exit(1)
+";
+
+/// `file:line[:column]: message`, as most compilers and linters print problems
+static PROBLEM_LINE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(.+?):(\d+)(?::(\d+))?:\s*(.+)$").expect("valid regex"));
+
+/// rustfmt's ` --> <stdin>:line:column` pointer under an error
+static RUST_LOCATION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*--> .*:(\d+):(\d+)$").expect("valid regex"));
+
+/// node's `file:line` header above the offending source line
+static NODE_LOCATION: LazyLock<Regex> = LazyLock::new(|| Regex::new(r":(\d+)$").expect("valid regex"));
+
+/// The outcome of checking a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VerifyStatus {
+    /// No problems found
+    Ok,
+    /// The checker found problems
+    Problems,
+    /// The check couldn't run (see the note)
+    Unchecked,
+}
+
+/// A problem a checker found
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyntaxProblem {
+    pub file: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<u64>,
+    pub message: String,
+}
+
+/// The result of checking a written file, added to the tool result
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Verification {
+    pub language: String,
+    pub status: VerifyStatus,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub problems: Vec<SyntaxProblem>,
+    /// Why the check didn't run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+impl Verification {
+    fn ok(language: &str) -> Self {
+        Self {
+            language: language.to_string(),
+            status: VerifyStatus::Ok,
+            problems: Vec::new(),
+            note: None,
+        }
+    }
+
+    fn problems(language: &str, mut problems: Vec<SyntaxProblem>) -> Self {
+        if problems.is_empty() {
+            return Self::ok(language);
+        }
+        problems.truncate(MAX_PROBLEMS);
+        Self {
+            language: language.to_string(),
+            status: VerifyStatus::Problems,
+            problems,
+            note: None,
+        }
+    }
+
+    fn unchecked(language: &str, note: impl Into<String>) -> Self {
+        Self {
+            language: language.to_string(),
+            status: VerifyStatus::Unchecked,
+            problems: Vec::new(),
+            note: Some(note.into()),
+        }
+    }
+}
+
+/// Checks the syntax of written files
+#[derive(Debug, Clone)]
+pub struct SyntaxVerifier {
+    /// Languages or extensions not checked
+    disabled: Vec<String>,
+    /// Commands by language or extension, replacing the built-in check
+    commands: HashMap<String, String>,
+    timeout: Duration,
+}
+
+impl Default for SyntaxVerifier {
+    fn default() -> Self {
+        Self {
+            disabled: Vec::new(),
+            commands: HashMap::new(),
+            timeout: DEFAULT_VERIFY_TIMEOUT,
+        }
+    }
+}
+
+impl SyntaxVerifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Don't check these languages or file extensions
+    pub fn with_disabled(mut self, disabled: Vec<String>) -> Self {
+        self.disabled = disabled.into_iter().map(|l| l.to_ascii_lowercase()).collect();
+        self
+    }
+
+    /// Check a language or file extension with `command` instead
+    pub fn with_command(mut self, language: impl Into<String>, command: impl Into<String>) -> Self {
+        self.commands.insert(language.into().to_ascii_lowercase(), command.into());
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// The language `path` is checked as, if any: a built-in language, or
+    /// the extension itself when a command is configured for it
+    pub fn language_for(&self, path: &Path) -> Option<String> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        let language = LANGUAGES
+            .iter()
+            .find(|(_, extensions)| extensions.contains(&ext.as_str()))
+            .map(|(language, _)| language.to_string())
+            .or_else(|| self.commands.contains_key(&ext).then(|| ext.clone()))?;
+        let disabled = self.disabled.iter().any(|d| *d == language || *d == ext);
+        (!disabled).then_some(language)
+    }
+
+    /// Check `content`, just written to `path` (shown as `display`), or
+    /// None if its language isn't checked
+    pub async fn verify(&self, path: &Path, display: &str, content: &str) -> Option<Verification> {
+        let language = self.language_for(path)?;
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_ascii_lowercase();
+        let command = self.commands.get(&language).or_else(|| self.commands.get(&ext));
+        let verification = match (command, language.as_str()) {
+            (Some(command), _) => self.check_with_command(&language, command, path, display).await,
+            (None, "rust") => self.check_rust(path, display, content).await,
+            (None, "python") => self.check_python(display, content).await,
+            (None, "javascript") => self.check_javascript(path, display).await,
+            (None, "json") => check_json(display, content),
+            (None, _) => return None,
+        };
+        Some(verification)
+    }
+
+    async fn check_rust(&self, path: &Path, display: &str, content: &str) -> Verification {
+        let edition = rust_edition(path);
+        let args = ["--emit", "stdout", "--edition", edition.as_str()];
+        let output = match self.run("rust", "rustfmt", &args, Some(content)).await {
+            Ok(output) => output,
+            Err(unchecked) => return unchecked,
+        };
+        if output.status.success() {
+            return Verification::ok("rust");
+        }
+        // `error: message` followed by ` --> <stdin>:line:column`
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let mut problems = Vec::new();
+        let mut message: Option<&str> = None;
+        for line in stderr.lines() {
+            if let Some(error) = line.strip_prefix("error: ") {
+                message = Some(error);
+            } else if let (Some(error), Some(caps)) = (message, RUST_LOCATION.captures(line)) {
+                problems.push(problem(display, caps[1].parse().ok(), caps[2].parse().ok(), error));
+                message = None;
+            }
+        }
+        if problems.is_empty() {
+            problems.push(problem(display, None, None, first_line(&stderr)));
+        }
+        Verification::problems("rust", problems)
+    }
+
+    async fn check_python(&self, display: &str, content: &str) -> Verification {
+        let args = ["-c", PYTHON_CHECK];
+        let output = match self.run("python", "python3", &args, Some(content)).await {
+            Err(unchecked) if cfg!(windows) => match self.run("python", "python", &args, Some(content)).await {
+                Ok(output) => output,
+                Err(_) => return unchecked,
+            },
+            Err(unchecked) => return unchecked,
+            Ok(output) => output,
+        };
+        if output.status.success() {
+            return Verification::ok("python");
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let reported = stdout.lines().find_map(|line| {
+            let (position, message) = line.split_once(": ")?;
+            let (line, column) = position.split_once(':')?;
+            Some(problem(display, line.parse().ok().filter(|l| *l > 0), column.parse().ok().filter(|c| *c > 0), message))
+        });
+        let problem = reported
+            .unwrap_or_else(|| problem(display, None, None, first_line(&String::from_utf8_lossy(&output.stderr))));
+        Verification::problems("python", vec![problem])
+    }
+
+    async fn check_javascript(&self, path: &Path, display: &str) -> Verification {
+        let path = path.to_string_lossy();
+        let output = match self.run("javascript", "node", &["--check", path.as_ref()], None).await {
+            Ok(output) => output,
+            Err(unchecked) => return unchecked,
+        };
+        if output.status.success() {
+            return Verification::ok("javascript");
+        }
+        // `file:line`, the source line, a caret, then `SyntaxError: message`
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let line = stderr
+            .lines()
+            .next()
+            .and_then(|header| NODE_LOCATION.captures(header))
+            .and_then(|caps| caps[1].parse().ok());
+        let message = stderr
+            .lines()
+            .find(|l| l.contains("Error:"))
+            .unwrap_or_else(|| first_line(&stderr));
+        Verification::problems("javascript", vec![problem(display, line, None, message)])
+    }
+
+    async fn check_with_command(&self, language: &str, command: &str, path: &Path, display: &str) -> Verification {
+        let command = command.replace("{file}", &shell_escape_path(path));
+        let mut cmd = shell_command(&command);
+        if let Some(dir) = path.parent() {
+            cmd.current_dir(dir);
+        }
+        let program = command.split_whitespace().next().unwrap_or(language).to_string();
+        let output = match self.run_command(language, &program, cmd, None).await {
+            Ok(output) => output,
+            Err(unchecked) => return unchecked,
+        };
+        if output.status.success() {
+            return Verification::ok(language);
+        }
+        let text = format!("{}\n{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr));
+        let mut problems: Vec<SyntaxProblem> = text
+            .lines()
+            .filter_map(|line| PROBLEM_LINE.captures(line.trim()))
+            .map(|caps| problem(display, caps[2].parse().ok(), caps.get(3).and_then(|c| c.as_str().parse().ok()), &caps[4]))
+            .collect();
+        if problems.is_empty() {
+            problems.push(problem(display, None, None, first_line(&text)));
+        }
+        Verification::problems(language, problems)
+    }
+
+    /// Run a checker program, or say why it couldn't run
+    async fn run(
+        &self,
+        language: &str,
+        program: &str,
+        args: &[&str],
+        stdin: Option<&str>,
+    ) -> Result<std::process::Output, Verification> {
+        let mut cmd = direct_command(program);
+        cmd.args(args);
+        self.run_command(language, program, cmd, stdin).await
+    }
+
+    /// Run a checker command (`program` names it in notes)
+    async fn run_command(
+        &self,
+        language: &str,
+        program: &str,
+        mut cmd: tokio::process::Command,
+        stdin: Option<&str>,
+    ) -> Result<std::process::Output, Verification> {
+        cmd.stdin(if stdin.is_some() { Stdio::piped() } else { Stdio::null() })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        let not_installed = || Verification::unchecked(language, format!("{} is not installed, so the syntax wasn't checked", program));
+        let mut child = cmd.spawn().map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                not_installed()
+            } else {
+                Verification::unchecked(language, format!("The syntax check couldn't start: {}", e))
+            }
+        })?;
+        if let (Some(content), Some(mut pipe)) = (stdin, child.stdin.take()) {
+            // A checker that exits early closes the pipe; its output says why
+            let _ = pipe.write_all(content.as_bytes()).await;
+        }
+        match tokio::time::timeout(self.timeout, child.wait_with_output()).await {
+            // The shell's "command not found"
+            Ok(Ok(output)) if output.status.code() == Some(127) => Err(not_installed()),
+            Ok(Ok(output)) => Ok(output),
+            Ok(Err(e)) => Err(Verification::unchecked(language, format!("The syntax check failed: {}", e))),
+            Err(_) => Err(Verification::unchecked(
+                language,
+                format!("The syntax check timed out after {}s", self.timeout.as_secs()),
+            )),
+        }
+    }
+}
+
+fn check_json(display: &str, content: &str) -> Verification {
+    match serde_json::from_str::<serde::de::IgnoredAny>(content) {
+        Ok(_) => Verification::ok("json"),
+        Err(e) => {
+            let message = e.to_string();
+            // serde_json appends " at line L column C"
+            let message = message.split(" at line ").next().unwrap_or(&message);
+            Verification::problems("json", vec![problem(display, Some(e.line() as u64), Some(e.column() as u64), message)])
+        }
+    }
+}
+
+fn problem(file: &str, line: Option<u64>, column: Option<u64>, message: &str) -> SyntaxProblem {
+    SyntaxProblem {
+        file: file.to_string(),
+        line,
+        column,
+        message: message.trim().chars().take(MAX_MESSAGE_CHARS).collect(),
+    }
+}
+
+fn first_line(text: &str) -> &str {
+    text.lines().map(str::trim).find(|l| !l.is_empty()).unwrap_or("The syntax check failed")
+}
+
+/// Edition of the crate `path` belongs to, from the nearest Cargo.toml
+/// that sets one (2021 if none does)
+fn rust_edition(path: &Path) -> String {
+    let mut dir: Option<PathBuf> = path.parent().map(Path::to_path_buf);
+    while let Some(current) = dir {
+        if let Ok(manifest) = std::fs::read_to_string(current.join("Cargo.toml")) {
+            let edition = manifest.lines().find_map(|line| {
+                let value = line.trim().strip_prefix("edition")?.trim_start().strip_prefix('=')?;
+                Some(value.trim().trim_matches('"').to_string())
+            });
+            // `edition.workspace = true` keeps looking for the workspace root
+            if let Some(edition) = edition.filter(|e| e.chars().all(|c| c.is_ascii_digit()) && !e.is_empty()) {
+                return edition;
+            }
+        }
+        dir = current.parent().map(Path::to_path_buf);
+    }
+    "2021".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_language_for() {
+        let verifier = SyntaxVerifier::new()
+            .with_disabled(vec!["JavaScript".into()])
+            .with_command("go", "gofmt -e {file}");
+        assert_eq!(verifier.language_for(Path::new("src/lib.rs")).as_deref(), Some("rust"));
+        assert_eq!(verifier.language_for(Path::new("tool.PY")).as_deref(), Some("python"));
+        assert_eq!(verifier.language_for(Path::new("main.go")).as_deref(), Some("go"));
+        assert_eq!(verifier.language_for(Path::new("app.js")), None);
+        assert_eq!(verifier.language_for(Path::new("README.md")), None);
+        assert_eq!(verifier.language_for(Path::new("Makefile")), None);
+    }
+
+    #[test]
+    fn test_json_problems() {
+        assert_eq!(check_json("a.json", "{\"a\": [1, 2]}").status, VerifyStatus::Ok);
+        let broken = check_json("a.json", "{\n  \"a\": [1, 2\n}");
+        assert_eq!(broken.status, VerifyStatus::Problems);
+        assert_eq!(broken.problems[0].line, Some(3));
+        assert!(!broken.problems[0].message.contains(" at line "));
+    }
+
+    #[test]
+    fn test_rust_edition() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("Cargo.toml"), "[workspace.package]\nedition = \"2024\"\n").unwrap();
+        let member = dir.path().join("crates/core");
+        std::fs::create_dir_all(member.join("src")).unwrap();
+        std::fs::write(member.join("Cargo.toml"), "[package]\nedition.workspace = true\n").unwrap();
+        assert_eq!(rust_edition(&member.join("src/lib.rs")), "2024");
+        assert_eq!(rust_edition(Path::new("/nonexistent/src/lib.rs")), "2021");
+    }
+}
@@ -2,20 +2,157 @@
 
 use serde_json::{json, Value};
 use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
 
 use crate::error::ToolError;
-use crate::tools::{BoxFuture, Tool, ToolExecutionContext, ToolOutput};
+use crate::formatting::diff_stats;
+use crate::tools::remote::RemoteWorkspace;
+use crate::tools::{Artifact, BoxFuture, Tool, ToolExecutionContext, ToolOutput};
 
-use super::{normalize_path, path_to_display, validate_path};
+use super::generated::{allow_generated_schema, read_head, GeneratedFile};
+use super::{
+    get_file_conventions, path_to_display, validate_path_with_scratch, validate_write_path_with_scratch,
+    GeneratedFileGuard, SyntaxVerifier, WorkspaceRoots,
+};
+
+/// How a Write call treats the file already at the path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WriteMode {
+    /// Replace the file (the default)
+    Overwrite,
+    /// Add the content to the end of the file, creating it if need be
+    Append,
+    /// Create the file, failing if it exists
+    CreateExclusive,
+}
+
+impl WriteMode {
+    fn from_params(params: &Value) -> Result<Self, ToolError> {
+        match params["mode"].as_str() {
+            None | Some("overwrite") => Ok(Self::Overwrite),
+            Some("append") => Ok(Self::Append),
+            Some("create_exclusive") => Ok(Self::CreateExclusive),
+            Some(other) => Err(ToolError::InvalidParams(format!(
+                "Unknown mode '{}'. Valid modes: overwrite, append, create_exclusive",
+                other
+            ))),
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Overwrite => "overwrite",
+            Self::Append => "append",
+            Self::CreateExclusive => "create_exclusive",
+        }
+    }
+}
+
+/// Check the file's size before a write against what the caller expects
+///
+/// A chunked write states the size it left the file at; anything else means
+/// a chunk went missing or someone else changed the file in between.
+fn check_prior_size(shown: &str, mode: WriteMode, existing: Option<u64>, expected: Option<u64>) -> Result<(), ToolError> {
+    if mode == WriteMode::CreateExclusive && existing.is_some() {
+        return Err(ToolError::InvalidParams(format!(
+            "{} already exists; use mode overwrite to replace it or append to add to it",
+            shown
+        )));
+    }
+    match expected {
+        Some(expected) if existing.unwrap_or(0) != expected => Err(ToolError::InvalidParams(format!(
+            "{} is {} bytes, not the expected {}: it changed since the last chunk. Read the end of the file \
+             before writing more",
+            shown,
+            existing.unwrap_or(0),
+            expected
+        ))),
+        _ => Ok(()),
+    }
+}
 
 /// Tool for writing file contents
 pub struct WriteFile {
-    workspace: PathBuf,
+    /// Workspace roots; relative paths resolve against the primary one
+    roots: WorkspaceRoots,
+    /// Remote workspace to write to instead of `roots`
+    remote: Option<Arc<RemoteWorkspace>>,
+    /// Session scratch directory, allowed as well as the workspace roots
+    scratch: Option<PathBuf>,
+    /// Syntax check of the written file (None = not checked)
+    verifier: Option<Arc<SyntaxVerifier>>,
+    /// Refuses lockfiles, generated code and build output
+    generated: Arc<GeneratedFileGuard>,
 }
 
 impl WriteFile {
     pub fn new(workspace: PathBuf) -> Self {
-        Self { workspace }
+        Self {
+            roots: WorkspaceRoots::new(workspace),
+            remote: None,
+            scratch: None,
+            verifier: None,
+            generated: Arc::new(GeneratedFileGuard::new()),
+        }
+    }
+
+    /// Write to a remote workspace, or the local one with `None`
+    pub fn with_remote(mut self, remote: Option<Arc<RemoteWorkspace>>) -> Self {
+        self.remote = remote;
+        self
+    }
+
+    /// Also allow paths in these roots besides the workspace
+    pub fn with_additional_roots(mut self, roots: &[PathBuf]) -> Self {
+        self.roots = self.roots.with_additional(roots.iter().cloned());
+        self
+    }
+
+    /// Also allow paths in the session's scratch directory
+    pub fn with_scratch_dir(mut self, scratch: Option<PathBuf>) -> Self {
+        self.scratch = scratch;
+        self
+    }
+
+    /// Check the syntax of local files after writing them
+    pub fn with_verifier(mut self, verifier: Option<Arc<SyntaxVerifier>>) -> Self {
+        self.verifier = verifier;
+        self
+    }
+
+    /// Decide which files are generated with `guard` (the project's
+    /// patterns) rather than the built-in patterns alone
+    pub fn with_generated_guard(mut self, guard: Arc<GeneratedFileGuard>) -> Self {
+        self.generated = guard;
+        self
+    }
+
+    /// Write a file in the remote workspace, returning its display path,
+    /// previous content (None for a new file), new size and, if the call
+    /// overrides the guard, the generated file
+    async fn write_remote(
+        &self,
+        remote: &RemoteWorkspace,
+        path_str: &str,
+        content: &str,
+        mode: WriteMode,
+        params: &Value,
+        ctx: &ToolExecutionContext,
+    ) -> Result<(String, Option<String>, usize, Option<GeneratedFile>), ToolError> {
+        let path = remote.resolve(path_str)?;
+        let shown = remote.relative(&path);
+        let previous = remote.read_optional(&path).await?;
+        check_prior_size(&shown, mode, previous.as_ref().map(|b| b.len() as u64), params["expected_prior_bytes"].as_u64())?;
+        let head = previous.as_ref().map(|bytes| String::from_utf8_lossy(bytes).into_owned());
+        let generated = self.generated.enforce(&shown, head.as_deref(), params, ctx).await?;
+        let mut bytes = match (mode, &previous) {
+            (WriteMode::Append, Some(previous)) => previous.clone(),
+            _ => Vec::new(),
+        };
+        bytes.extend_from_slice(content.as_bytes());
+        remote.write(&path, &bytes, params["create_dirs"].as_bool().unwrap_or(true)).await?;
+        Ok((shown, head, bytes.len(), generated))
     }
 }
 
@@ -28,6 +165,10 @@ impl Tool for WriteFile {
         crate::prompt::builtin::claude_code::tools::WRITE
     }
 
+    fn compact_description(&self) -> &str {
+        crate::prompt::builtin::claude_code::tools::WRITE_COMPACT
+    }
+
     fn parameters_schema(&self) -> Value {
         json!({
             "type": "object",
@@ -39,13 +180,27 @@ impl Tool for WriteFile {
                 "content": {
                     "type": "string",
                     "description": "The content to write to the file"
-                }
+                },
+                "raw": {
+                    "type": "boolean",
+                    "description": "Write the content exactly as given, without adapting its indentation, line endings and trailing whitespace to the file's conventions (default false)"
+                },
+                "mode": {
+                    "type": "string",
+                    "enum": ["overwrite", "append", "create_exclusive"],
+                    "description": "overwrite replaces the file (default), append adds the content to its end verbatim (creating it if missing), create_exclusive fails if the file exists"
+                },
+                "expected_prior_bytes": {
+                    "type": "integer",
+                    "description": "Size in bytes the file must have before this write (0 for a missing file); the write fails if it differs. Pass the total_bytes of the previous chunk when appending"
+                },
+                "allow_generated": allow_generated_schema()
             },
             "required": ["file_path", "content"]
         })
     }
 
-    fn execute(&self, params: Value, _ctx: ToolExecutionContext) -> BoxFuture<'_, Result<ToolOutput, ToolError>> {
+    fn execute(&self, params: Value, ctx: ToolExecutionContext) -> BoxFuture<'_, Result<ToolOutput, ToolError>> {
         Box::pin(async move {
             let path_str = params["file_path"]
                 .as_str()
@@ -56,40 +211,152 @@ impl Tool for WriteFile {
                 .ok_or_else(|| ToolError::InvalidParams("content is required".into()))?;
 
             let create_dirs = params["create_dirs"].as_bool().unwrap_or(true);
+            let mode = WriteMode::from_params(&params)?;
+            let expected_prior_bytes = params["expected_prior_bytes"].as_u64();
 
-            let path = self.workspace.join(path_str);
+            if let Some(remote) = &self.remote {
+                let (path, previous, total, generated) =
+                    self.write_remote(remote, path_str, content, mode, &params, &ctx).await?;
+                let created = previous.is_none();
+                let mut result = json!({
+                    "path": path,
+                    "bytes_written": content.len(),
+                    "created": created
+                });
+                if mode == WriteMode::Append {
+                    result["mode"] = json!(mode.as_str());
+                    result["total_bytes"] = json!(total);
+                } else {
+                    let (added, removed) = diff_stats(
+                        &previous.unwrap_or_default().replace("\r\n", "\n"),
+                        &content.replace("\r\n", "\n"),
+                    );
+                    result["lines_added"] = json!(added);
+                    result["lines_removed"] = json!(removed);
+                }
+                if let Some(generated) = generated {
+                    result["generated"] = json!(generated);
+                }
+                return Ok(ToolOutput::success(result).with_artifact(Artifact::file(path, None)));
+            }
 
-            // Normalize the path to resolve .. components for security check
-            let normalized_path = normalize_path(&path);
-            let normalized_workspace = normalize_path(&self.workspace);
+            // Joining keeps a drive-relative path (`C:foo`) as is, so
+            // validate_write_path sees and rejects it
+            let path = self.roots.resolve(path_str);
 
-            // Security check: ensure normalized path is within workspace
-            if !normalized_path.starts_with(&normalized_workspace) {
-                return Err(ToolError::PermissionDenied(format!(
-                    "Path {} is outside workspace",
-                    path.display()
-                )));
-            }
+            // Security check: the path (with .. resolved) must be within the
+            // workspace roots or the scratch directory
+            let scratch = self.scratch.as_deref();
+            validate_write_path_with_scratch(&path, self.roots.paths(), scratch)?;
 
             // For new files, validate parent directory
             if !path.exists() {
                 if let Some(parent) = path.parent() {
                     if parent.exists() {
-                        validate_path(parent, &self.workspace)?;
+                        validate_path_with_scratch(parent, self.roots.paths(), scratch)?;
                     } else if create_dirs {
                         tokio::fs::create_dir_all(parent).await.map_err(ToolError::Io)?;
                     }
                 }
             } else {
-                validate_path(&path, &self.workspace)?;
+                validate_path_with_scratch(&path, self.roots.paths(), scratch)?;
+            }
+
+            // Paths in the workspace are reported relative to it
+            let shown = self.roots.display(&path, false);
+            let existing = tokio::fs::metadata(&path).await.ok().map(|m| m.len());
+            check_prior_size(&shown, mode, existing, expected_prior_bytes)?;
+            let created = existing.is_none();
+            let generated = self.generated.enforce(&shown, read_head(&path).as_deref(), &params, &ctx).await?;
+
+            // A chunk of a larger file: added as is, since conventions and
+            // syntax only make sense for the whole file (see FinishFile)
+            if mode == WriteMode::Append {
+                let mut file = tokio::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&path)
+                    .await
+                    .map_err(ToolError::Io)?;
+                file.write_all(content.as_bytes()).await.map_err(ToolError::Io)?;
+                file.flush().await.map_err(ToolError::Io)?;
+                let mut result = json!({
+                    "path": shown,
+                    "mode": mode.as_str(),
+                    "bytes_written": content.len(),
+                    "total_bytes": existing.unwrap_or(0) + content.len() as u64,
+                    "created": created
+                });
+                if let Some(generated) = generated {
+                    result["generated"] = json!(generated);
+                }
+                return Ok(ToolOutput::success(result).with_artifact(Artifact::file(path_to_display(&path), None)));
             }
 
-            tokio::fs::write(&path, content).await.map_err(ToolError::Io)?;
+            // Previous content, for the lines added/removed summary
+            let previous = if created {
+                String::new()
+            } else {
+                tokio::fs::read_to_string(&path).await.unwrap_or_default()
+            };
+
+            // Brought in line with .editorconfig or the file's own habits,
+            // unless asked to write it verbatim
+            let conventions = get_file_conventions(&path);
+            let raw = params["raw"].as_bool().unwrap_or(false);
+            let (normalized, changes) = if raw {
+                (content.to_string(), Vec::new())
+            } else {
+                conventions.normalize(content)
+            };
+            let content = normalized.as_str();
+
+            if mode == WriteMode::CreateExclusive {
+                // Fails if the file appeared since the check above
+                let mut file = tokio::fs::OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .open(&path)
+                    .await
+                    .map_err(ToolError::Io)?;
+                file.write_all(content.as_bytes()).await.map_err(ToolError::Io)?;
+                file.flush().await.map_err(ToolError::Io)?;
+            } else {
+                tokio::fs::write(&path, content).await.map_err(ToolError::Io)?;
+            }
 
-            Ok(ToolOutput::success(json!({
-                "path": path_to_display(&path),
-                "bytes_written": content.len()
-            })))
+            let (added, removed) = diff_stats(
+                &previous.replace("\r\n", "\n"),
+                &content.replace("\r\n", "\n"),
+            );
+            let mut result = json!({
+                "path": shown,
+                "bytes_written": content.len(),
+                "created": created,
+                "lines_added": added,
+                "lines_removed": removed
+            });
+            if !changes.is_empty() {
+                result["normalized"] = json!(changes);
+                result["conventions"] = json!(conventions.describe());
+            }
+            let long_lines = conventions.long_lines(content);
+            if long_lines > 0 {
+                result["long_lines"] = json!(format!(
+                    "{} line(s) exceed the {} character limit",
+                    long_lines,
+                    conventions.max_line_length.unwrap_or_default()
+                ));
+            }
+            if let Some(generated) = generated {
+                result["generated"] = json!(generated);
+            }
+            if let Some(verifier) = &self.verifier
+                && let Some(verification) = verifier.verify(&path, &shown, content).await
+            {
+                result["verification"] = json!(verification);
+            }
+            Ok(ToolOutput::success(result).with_artifact(Artifact::file(path_to_display(&path), None)))
         })
     }
 }
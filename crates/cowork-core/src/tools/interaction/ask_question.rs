@@ -31,7 +31,7 @@ pub struct Question {
     #[serde(default)]
     pub options: Vec<QuestionOption>,
     /// Allow multiple selections
-    #[serde(default)]
+    #[serde(default, alias = "multi_select")]
     pub multi_select: bool,
 }
 
@@ -76,6 +76,10 @@ impl Tool for AskUserQuestion {
         crate::prompt::builtin::claude_code::tools::ASK_USER_QUESTION
     }
 
+    fn compact_description(&self) -> &str {
+        crate::prompt::builtin::claude_code::tools::ASK_USER_QUESTION_COMPACT
+    }
+
     fn parameters_schema(&self) -> Value {
         json!({
             "type": "object",
@@ -158,32 +162,14 @@ impl Tool for AskUserQuestion {
                 })));
             }
 
-        // Parse questions
-        let questions_value = params
-            .get("questions")
-            .ok_or_else(|| ToolError::InvalidParams("questions is required".into()))?;
-
-        let questions: Vec<Question> = serde_json::from_value(questions_value.clone())
-            .map_err(|e| ToolError::InvalidParams(format!("Invalid questions format: {}", e)))?;
-
-        // Validate questions
-        if questions.is_empty() || questions.len() > 4 {
-            return Err(ToolError::InvalidParams(
-                "Must have 1-4 questions".into(),
-            ));
-        }
-
-        for (i, q) in questions.iter().enumerate() {
-            if q.options.len() < 2 || q.options.len() > 4 {
-                return Err(ToolError::InvalidParams(format!(
-                    "Question {} must have 2-4 options",
-                    i + 1
-                )));
-            }
+        // Parse questions, repairing common malformations before giving up
+        let parsed = parse_questions_with_repair(&params).map_err(ToolError::InvalidParams)?;
+        if parsed.rung != ParseRung::Strict || !parsed.repairs.is_empty() {
+            tracing::debug!(rung = parsed.rung.as_str(), repairs = ?parsed.repairs, "Repaired AskUserQuestion arguments");
         }
 
         // Convert to QuestionInfo for the approval channel
-        let question_infos: Vec<crate::session::QuestionInfo> = questions
+        let question_infos: Vec<crate::session::QuestionInfo> = parsed.questions
             .iter()
             .map(|q| crate::session::QuestionInfo {
                 question: q.question.clone(),
@@ -198,10 +184,10 @@ impl Tool for AskUserQuestion {
 
         // Ask questions through the approval channel
         match ctx.ask_question(question_infos).await {
-            Ok(answers) => Ok(ToolOutput::success(json!({
+            Ok(answers) => Ok(parsed.annotate(ToolOutput::success(json!({
                 "answered": true,
                 "answers": answers
-            }))),
+            })))),
             Err(e) => Err(ToolError::ExecutionFailed(e)),
         }
             })
@@ -306,6 +292,224 @@ pub fn parse_questions_lenient(args: &Value) -> Result<Vec<Question>, String> {
     Ok(questions)
 }
 
+/// Most questions the repair ladder keeps; extra questions are dropped
+pub const MAX_QUESTIONS: usize = 5;
+
+/// Most options per question the repair ladder keeps; extra options are dropped
+pub const MAX_OPTIONS: usize = 12;
+
+/// Longest option label, in characters, before it is truncated
+pub const MAX_LABEL_CHARS: usize = 60;
+
+/// Longest question header, in characters, before it is truncated
+pub const MAX_HEADER_CHARS: usize = 12;
+
+/// Tool result metadata key holding the [`ParseRung`] that parsed the questions
+pub const PARSE_RUNG_KEY: &str = "question_parse";
+
+/// Tool result metadata key holding the repairs applied to the questions
+pub const REPAIRS_KEY: &str = "question_repairs";
+
+/// The rung of the repair ladder that produced a set of questions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParseRung {
+    /// The arguments matched the schema
+    Strict,
+    /// Missing fields were defaulted by [`parse_questions_lenient`]
+    Lenient,
+    /// The arguments were coerced by [`repair_question_args`] first
+    Repaired,
+}
+
+impl ParseRung {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ParseRung::Strict => "strict",
+            ParseRung::Lenient => "lenient",
+            ParseRung::Repaired => "repaired",
+        }
+    }
+}
+
+/// Questions parsed by [`parse_questions_with_repair`]
+#[derive(Debug, Clone)]
+pub struct ParsedQuestions {
+    pub questions: Vec<Question>,
+    /// The ladder rung that parsed them
+    pub rung: ParseRung,
+    /// Repairs and truncations applied, in the order they were first made
+    pub repairs: Vec<&'static str>,
+}
+
+impl ParsedQuestions {
+    /// Record the rung and repairs in a tool result's metadata when the
+    /// questions weren't used exactly as the model sent them
+    pub fn annotate(&self, output: ToolOutput) -> ToolOutput {
+        if self.rung == ParseRung::Strict && self.repairs.is_empty() {
+            return output;
+        }
+        output
+            .with_metadata(PARSE_RUNG_KEY, self.rung.as_str())
+            .with_metadata(REPAIRS_KEY, json!(self.repairs))
+    }
+}
+
+/// Parse questions, falling back through a repair ladder
+///
+/// Tries [`parse_questions`] first, then [`parse_questions_lenient`] on the
+/// arguments as sent, then the lenient parser on the arguments coerced by
+/// [`repair_question_args`]. The lenient rungs only succeed when every
+/// question ends up with at least 2 labelled options. Whatever rung succeeds,
+/// questions and options beyond [`MAX_QUESTIONS`] and [`MAX_OPTIONS`] are
+/// dropped and long labels and headers truncated rather than rejected.
+///
+/// # Errors
+/// Returns the strict parser's error when no rung produces usable questions.
+pub fn parse_questions_with_repair(args: &Value) -> Result<ParsedQuestions, String> {
+    let strict_error = match parse_questions(args) {
+        Ok(questions) => return Ok(enforce_limits(questions, ParseRung::Strict, Vec::new())),
+        Err(e) => e,
+    };
+
+    let (repaired, repairs) = repair_question_args(args);
+    let rung = if repairs.is_empty() { ParseRung::Lenient } else { ParseRung::Repaired };
+    match parse_questions_lenient(&repaired) {
+        Ok(questions) if is_usable(&questions) => Ok(enforce_limits(questions, rung, repairs)),
+        _ => Err(strict_error),
+    }
+}
+
+/// Coerce the malformations models commonly make in AskUserQuestion arguments
+///
+/// Returns the repaired arguments and the names of the repairs applied:
+/// - `wrapped_single_question`: a question object where the array belongs
+/// - `parsed_questions_string`: the questions array sent as a JSON string
+/// - `options_from_strings`: options given as bare labels instead of objects
+/// - `coerced_multi_select`: `multiSelect` as a string or number, or spelled `multi_select`
+pub fn repair_question_args(args: &Value) -> (Value, Vec<&'static str>) {
+    let mut repairs = Vec::new();
+    let mut args = args.clone();
+
+    // The whole arguments object is a single question
+    if args.get("questions").is_none() && args.get("question").is_some() {
+        args = json!({ "questions": [args] });
+        note(&mut repairs, "wrapped_single_question");
+    }
+
+    if let Some(text) = args.get("questions").and_then(|q| q.as_str())
+        && let Ok(parsed) = serde_json::from_str::<Value>(text)
+    {
+        args["questions"] = parsed;
+        note(&mut repairs, "parsed_questions_string");
+    }
+
+    if args.get("questions").is_some_and(|q| q.is_object()) {
+        args["questions"] = json!([args["questions"].take()]);
+        note(&mut repairs, "wrapped_single_question");
+    }
+
+    if let Some(questions) = args.get_mut("questions").and_then(|q| q.as_array_mut()) {
+        for question in questions.iter_mut().filter_map(|q| q.as_object_mut()) {
+            if let Some(options) = question.get_mut("options").and_then(|o| o.as_array_mut()) {
+                for option in options.iter_mut() {
+                    let label = match option {
+                        Value::String(label) => label.clone(),
+                        Value::Number(n) => n.to_string(),
+                        Value::Bool(b) => b.to_string(),
+                        _ => continue,
+                    };
+                    *option = json!({ "label": label, "description": "" });
+                    note(&mut repairs, "options_from_strings");
+                }
+            }
+
+            if !question.contains_key("multiSelect")
+                && let Some(value) = question.remove("multi_select")
+            {
+                question.insert("multiSelect".to_string(), value);
+                note(&mut repairs, "coerced_multi_select");
+            }
+            if let Some(value) = question.get_mut("multiSelect")
+                && !value.is_boolean()
+                && let Some(coerced) = coerce_bool(value)
+            {
+                *value = Value::Bool(coerced);
+                note(&mut repairs, "coerced_multi_select");
+            }
+        }
+    }
+
+    (args, repairs)
+}
+
+/// Read a boolean from a string or number, as models sometimes send `"true"` or `1`
+fn coerce_bool(value: &Value) -> Option<bool> {
+    match value {
+        Value::String(s) => match s.trim().to_ascii_lowercase().as_str() {
+            "true" | "yes" | "1" => Some(true),
+            "false" | "no" | "0" | "" => Some(false),
+            _ => None,
+        },
+        Value::Number(n) => n.as_f64().map(|n| n != 0.0),
+        Value::Null => Some(false),
+        _ => None,
+    }
+}
+
+/// Whether every question has at least 2 options with labels
+fn is_usable(questions: &[Question]) -> bool {
+    questions.iter().all(|q| {
+        q.options.iter().filter(|o| !o.label.trim().is_empty()).count() >= 2
+    })
+}
+
+/// Drop questions, options and empty options past the limits and truncate
+/// long labels and headers, noting each kind of change in `repairs`
+fn enforce_limits(mut questions: Vec<Question>, rung: ParseRung, mut repairs: Vec<&'static str>) -> ParsedQuestions {
+    if questions.len() > MAX_QUESTIONS {
+        questions.truncate(MAX_QUESTIONS);
+        note(&mut repairs, "truncated_questions");
+    }
+    for question in &mut questions {
+        let before = question.options.len();
+        question.options.retain(|o| !o.label.trim().is_empty());
+        if question.options.len() < before {
+            note(&mut repairs, "dropped_empty_options");
+        }
+        if question.options.len() > MAX_OPTIONS {
+            question.options.truncate(MAX_OPTIONS);
+            note(&mut repairs, "truncated_options");
+        }
+        for option in &mut question.options {
+            if truncate_chars(&mut option.label, MAX_LABEL_CHARS) {
+                note(&mut repairs, "truncated_labels");
+            }
+        }
+        if truncate_chars(&mut question.header, MAX_HEADER_CHARS) {
+            note(&mut repairs, "truncated_headers");
+        }
+    }
+    ParsedQuestions { questions, rung, repairs }
+}
+
+/// Cut `s` to `max` characters, returning whether it was cut
+fn truncate_chars(s: &mut String, max: usize) -> bool {
+    match s.char_indices().nth(max) {
+        Some((index, _)) => {
+            s.truncate(index);
+            true
+        }
+        None => false,
+    }
+}
+
+fn note(repairs: &mut Vec<&'static str>, repair: &'static str) {
+    if !repairs.contains(&repair) {
+        repairs.push(repair);
+    }
+}
+
 /// Validate a list of questions
 pub fn validate_questions(questions: &[Question]) -> Result<(), String> {
     if questions.is_empty() {
@@ -0,0 +1,129 @@
+//! ClipboardWrite tool - Put text on the user's clipboard
+//!
+//! Only registered when the frontend provides a [`Clipboard`]: the desktop
+//! app writes through the Tauri clipboard API, the CLI through arboard when
+//! built with the `clipboard` feature.
+
+use std::sync::Arc;
+
+use serde_json::{json, Value};
+
+use crate::error::ToolError;
+use crate::tools::{BoxFuture, Tool, ToolExecutionContext, ToolOutput};
+
+/// The canonical tool name, used in approval config and formatting
+pub const NAME: &str = "ClipboardWrite";
+
+/// Most characters the agent may put on the clipboard at once
+pub const MAX_CLIPBOARD_CHARS: usize = 100_000;
+
+/// The system clipboard, as provided by a frontend
+pub trait Clipboard: Send + Sync {
+    /// Replace the clipboard's contents with `text`
+    fn write_text(&self, text: &str) -> Result<(), String>;
+}
+
+/// Tool for placing text on the system clipboard
+pub struct ClipboardWrite {
+    clipboard: Arc<dyn Clipboard>,
+}
+
+impl ClipboardWrite {
+    pub fn new(clipboard: Arc<dyn Clipboard>) -> Self {
+        Self { clipboard }
+    }
+}
+
+impl Tool for ClipboardWrite {
+    fn name(&self) -> &str {
+        NAME
+    }
+
+    fn description(&self) -> &str {
+        "Place text on the user's system clipboard, replacing what was there. \
+         Use it only when the user asks for something to be copied. \
+         Returns the number of characters written."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "text": {
+                    "type": "string",
+                    "description": format!("The text to copy (at most {} characters)", MAX_CLIPBOARD_CHARS)
+                }
+            },
+            "required": ["text"]
+        })
+    }
+
+    fn execute(&self, params: Value, _ctx: ToolExecutionContext) -> BoxFuture<'_, Result<ToolOutput, ToolError>> {
+        Box::pin(async move {
+            let text = params
+                .get("text")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| ToolError::InvalidParams("text is required".into()))?;
+
+            let chars = text.chars().count();
+            if chars > MAX_CLIPBOARD_CHARS {
+                return Err(ToolError::InvalidParams(format!(
+                    "text is {} characters, more than the {} that can be copied at once",
+                    chars, MAX_CLIPBOARD_CHARS
+                )));
+            }
+
+            self.clipboard
+                .write_text(text)
+                .map_err(|e| ToolError::ExecutionFailed(format!("Failed to write to the clipboard: {}", e)))?;
+
+            Ok(ToolOutput::success(json!({ "chars_written": chars })))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parking_lot::Mutex;
+
+    #[derive(Default)]
+    struct MemoryClipboard(Mutex<String>);
+
+    impl Clipboard for MemoryClipboard {
+        fn write_text(&self, text: &str) -> Result<(), String> {
+            *self.0.lock() = text.to_string();
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_writes_and_reports_length() {
+        let clipboard = Arc::new(MemoryClipboard::default());
+        let tool = ClipboardWrite::new(clipboard.clone());
+
+        let output = tool
+            .execute(json!({ "text": "héllo" }), ToolExecutionContext::standalone("t1", NAME))
+            .await
+            .unwrap();
+        assert_eq!(output.content["chars_written"], 5);
+        assert_eq!(*clipboard.0.lock(), "héllo");
+
+        let too_long = "x".repeat(MAX_CLIPBOARD_CHARS + 1);
+        let err = tool
+            .execute(json!({ "text": too_long }), ToolExecutionContext::standalone("t2", NAME))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ToolError::InvalidParams(_)));
+        assert_eq!(*clipboard.0.lock(), "héllo");
+    }
+
+    #[test]
+    fn test_approval_only_at_high() {
+        use crate::approval::{ApprovalLevel, ToolApprovalConfig};
+
+        assert!(ToolApprovalConfig::new(ApprovalLevel::Low).should_auto_approve(NAME));
+        assert!(ToolApprovalConfig::new(ApprovalLevel::Medium).should_auto_approve(NAME));
+        assert!(!ToolApprovalConfig::new(ApprovalLevel::High).should_auto_approve(NAME));
+    }
+}
@@ -1,10 +1,14 @@
 //! User interaction tools
 
 mod ask_question;
+mod clipboard;
 
 pub use ask_question::{
     AskUserQuestion, NAME as ASK_QUESTION_TOOL_NAME,
     Question, QuestionMetadata, QuestionOption,
     QuestionRequest, format_answer_response, format_answer_response_with_id,
-    parse_questions, parse_questions_lenient, validate_questions,
+    parse_questions, parse_questions_lenient, parse_questions_with_repair, repair_question_args,
+    validate_questions, ParseRung, ParsedQuestions, MAX_HEADER_CHARS, MAX_LABEL_CHARS, MAX_OPTIONS,
+    MAX_QUESTIONS, PARSE_RUNG_KEY, REPAIRS_KEY,
 };
+pub use clipboard::{Clipboard, ClipboardWrite, MAX_CLIPBOARD_CHARS, NAME as CLIPBOARD_WRITE_TOOL_NAME};
@@ -24,7 +24,8 @@ use tokio::sync::Mutex;
 use tracing::debug;
 
 use crate::tools::filesystem::{percent_decode_path, percent_encode_path};
-use crate::tools::process_utils::std_direct_command;
+use crate::processes::{ProcessGuard, ProcessKind, ProcessTracker};
+use crate::tools::process_utils::{std_direct_command, std_own_process_group};
 
 /// LSP client for communicating with a language server
 pub struct LspClient {
@@ -33,6 +34,8 @@ pub struct LspClient {
     stdout: Mutex<BufReader<ChildStdout>>,
     request_id: AtomicU64,
     workspace_root: PathBuf,
+    /// Kills the server's process group once the client is gone
+    _tracked: ProcessGuard,
 }
 
 /// Convert a file path to a file:// URI string with proper percent encoding.
@@ -80,7 +83,9 @@ impl LspClient {
     pub async fn new(workspace: &Path, command: &str, args: &[String]) -> Result<Self, String> {
         // Spawn the language server process
         // Uses process_utils which handles hiding console windows on Windows
-        let mut process = std_direct_command(command)
+        let mut cmd = std_direct_command(command);
+        std_own_process_group(&mut cmd);
+        let mut process = cmd
             .args(args)
             .current_dir(workspace)
             .stdin(Stdio::piped())
@@ -88,6 +93,7 @@ impl LspClient {
             .stderr(Stdio::null())
             .spawn()
             .map_err(|e| format!("Failed to spawn {}: {}", command, e))?;
+        let tracked = ProcessTracker::global().track(process.id(), ProcessKind::Lsp, command, cfg!(unix));
 
         let stdin = process
             .stdin
@@ -104,6 +110,7 @@ impl LspClient {
             stdout: Mutex::new(BufReader::new(stdout)),
             request_id: AtomicU64::new(1),
             workspace_root: workspace.to_path_buf(),
+            _tracked: tracked,
         };
 
         // Initialize the server
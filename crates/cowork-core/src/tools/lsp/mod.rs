@@ -114,6 +114,10 @@ impl Tool for LspTool {
         crate::prompt::builtin::claude_code::tools::LSP
     }
 
+    fn compact_description(&self) -> &str {
+        crate::prompt::builtin::claude_code::tools::LSP_COMPACT
+    }
+
     fn parameters_schema(&self) -> Value {
         json!({
             "type": "object",
@@ -150,7 +154,7 @@ impl Tool for LspTool {
         })
     }
 
-    fn execute(&self, params: Value, _ctx: ToolExecutionContext) -> BoxFuture<'_, Result<ToolOutput, ToolError>> {
+    fn execute(&self, params: Value, ctx: ToolExecutionContext) -> BoxFuture<'_, Result<ToolOutput, ToolError>> {
         Box::pin(async move {
             let operation_str = params["operation"]
                 .as_str()
@@ -174,8 +178,8 @@ impl Tool for LspTool {
 
             #[cfg(feature = "lsp")]
             {
-                // Initialize client if needed
-                self.get_or_init_client(file_path).await?;
+                // Initialize client if needed (starting a server can take a while)
+                ctx.cancellable(self.get_or_init_client(file_path)).await??;
 
                 let mut client_guard = self.client.lock().await;
                 let client = client_guard.as_mut().ok_or_else(|| {
@@ -193,36 +197,41 @@ impl Tool for LspTool {
                 let line_0 = line.saturating_sub(1);
                 let char_0 = character.saturating_sub(1);
 
-                let result = match operation {
-                    LspOperation::GoToDefinition => {
-                        client.go_to_definition(&full_path, line_0, char_0).await
-                    }
-                    LspOperation::FindReferences => {
-                        client.find_references(&full_path, line_0, char_0).await
-                    }
-                    LspOperation::Hover => {
-                        client.hover(&full_path, line_0, char_0).await
-                    }
-                    LspOperation::DocumentSymbol => {
-                        client.document_symbols(&full_path).await
-                    }
-                    LspOperation::WorkspaceSymbol => {
-                        let query = params["query"].as_str().unwrap_or("");
-                        client.workspace_symbols(query).await
-                    }
-                    LspOperation::GoToImplementation => {
-                        client.go_to_implementation(&full_path, line_0, char_0).await
-                    }
-                    LspOperation::PrepareCallHierarchy => {
-                        client.prepare_call_hierarchy(&full_path, line_0, char_0).await
-                    }
-                    LspOperation::IncomingCalls => {
-                        client.incoming_calls(&full_path, line_0, char_0).await
-                    }
-                    LspOperation::OutgoingCalls => {
-                        client.outgoing_calls(&full_path, line_0, char_0).await
-                    }
-                };
+                // A stuck server mustn't outlive the call
+                let result = ctx
+                    .cancellable(async {
+                        match operation {
+                            LspOperation::GoToDefinition => {
+                                client.go_to_definition(&full_path, line_0, char_0).await
+                            }
+                            LspOperation::FindReferences => {
+                                client.find_references(&full_path, line_0, char_0).await
+                            }
+                            LspOperation::Hover => {
+                                client.hover(&full_path, line_0, char_0).await
+                            }
+                            LspOperation::DocumentSymbol => {
+                                client.document_symbols(&full_path).await
+                            }
+                            LspOperation::WorkspaceSymbol => {
+                                let query = params["query"].as_str().unwrap_or("");
+                                client.workspace_symbols(query).await
+                            }
+                            LspOperation::GoToImplementation => {
+                                client.go_to_implementation(&full_path, line_0, char_0).await
+                            }
+                            LspOperation::PrepareCallHierarchy => {
+                                client.prepare_call_hierarchy(&full_path, line_0, char_0).await
+                            }
+                            LspOperation::IncomingCalls => {
+                                client.incoming_calls(&full_path, line_0, char_0).await
+                            }
+                            LspOperation::OutgoingCalls => {
+                                client.outgoing_calls(&full_path, line_0, char_0).await
+                            }
+                        }
+                    })
+                    .await?;
 
                 result.map_err(ToolError::ExecutionFailed)
                     .map(ToolOutput::success)
@@ -230,11 +239,15 @@ impl Tool for LspTool {
 
             #[cfg(not(feature = "lsp"))]
             {
-                let _ = (operation, file_path, line, character);
+                let _ = (operation, file_path, line, character, ctx);
                 Err(ToolError::ExecutionFailed(
                     "LSP support not compiled. Rebuild with --features lsp".into()
                 ))
             }
         })
     }
+
+    fn is_idempotent(&self) -> bool {
+        true
+    }
 }
@@ -10,7 +10,10 @@ use serde_json::Value;
 
 use crate::error::ToolError;
 use crate::mcp_manager::{McpServerManager, McpToolInfo};
-use crate::tools::{BoxFuture, Tool, ToolExecutionContext, ToolOutput};
+use crate::tools::{Artifact, BoxFuture, Tool, ToolExecutionContext, ToolOutput};
+
+/// Prefix of every MCP tool name
+pub const MCP_TOOL_PREFIX: &str = "mcp__";
 
 /// Wrapper that exposes an MCP tool as a Cowork Tool
 pub struct McpToolWrapper {
@@ -27,7 +30,8 @@ impl McpToolWrapper {
     pub fn new(tool_info: McpToolInfo, manager: Arc<McpServerManager>) -> Self {
         // Create a prefixed name to avoid collisions with built-in tools
         // Format: mcp__{server}__{tool}
-        let prefixed_name = format!("mcp__{}__{}",
+        let prefixed_name = format!("{}{}__{}",
+            MCP_TOOL_PREFIX,
             tool_info.server.replace('-', "_"),
             tool_info.name.replace('-', "_")
         );
@@ -69,7 +73,7 @@ impl Tool for McpToolWrapper {
             let result = self.manager.call_tool(
                 &self.tool_info.server,
                 &self.tool_info.name,
-                params,
+                params.clone(),
             );
 
             match result {
@@ -110,9 +114,11 @@ impl Tool for McpToolWrapper {
                             .unwrap_or_else(|| "MCP tool execution failed".to_string());
                         Ok(ToolOutput::error(error_msg))
                     } else {
-                        Ok(ToolOutput::success(content)
+                        let mut output = ToolOutput::success(content)
                             .with_metadata("mcp_server", Value::String(self.tool_info.server.clone()))
-                            .with_metadata("mcp_tool", Value::String(self.tool_info.name.clone())))
+                            .with_metadata("mcp_tool", Value::String(self.tool_info.name.clone()));
+                        output.artifacts = response_artifacts(&self.tool_info.name, &params, &response);
+                        Ok(output)
                     }
                 }
                 Err(e) => {
@@ -140,6 +146,28 @@ pub fn create_mcp_tools(manager: Arc<McpServerManager>) -> Vec<Arc<dyn Tool>> {
         .collect()
 }
 
+/// Artifacts for an MCP tool's response
+///
+/// PNG image content (e.g. Playwright's `browser_take_screenshot`) becomes
+/// an image, and `browser_navigate` a link to the page.
+fn response_artifacts(tool_name: &str, params: &Value, response: &Value) -> Vec<Artifact> {
+    let mut artifacts = Vec::new();
+    if tool_name == "browser_navigate"
+        && let Some(url) = params.get("url").and_then(|u| u.as_str())
+    {
+        artifacts.push(Artifact::url(url, None));
+    }
+    let items = response.get("content").and_then(|c| c.as_array()).into_iter().flatten();
+    for item in items {
+        let is_png = item.get("type").and_then(|t| t.as_str()) == Some("image")
+            && item.get("mimeType").and_then(|m| m.as_str()) == Some("image/png");
+        if is_png && let Some(data) = item.get("data").and_then(|d| d.as_str()) {
+            artifacts.push(Artifact::png_base64(data));
+        }
+    }
+    artifacts
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -215,4 +243,27 @@ mod tests {
 
         assert_eq!(wrapper.parameters_schema(), schema);
     }
+
+    #[test]
+    fn test_response_artifacts() {
+        let response = serde_json::json!({
+            "content": [
+                { "type": "text", "text": "Took a screenshot" },
+                { "type": "image", "mimeType": "image/png", "data": "iVBORw0KGgo=" },
+                { "type": "image", "mimeType": "image/jpeg", "data": "/9j/" }
+            ]
+        });
+        assert_eq!(
+            response_artifacts("browser_take_screenshot", &serde_json::json!({}), &response),
+            vec![Artifact::png_base64("iVBORw0KGgo=")]
+        );
+
+        let params = serde_json::json!({ "url": "https://example.com" });
+        let text = serde_json::json!({ "content": [{ "type": "text", "text": "ok" }] });
+        assert_eq!(
+            response_artifacts("browser_navigate", &params, &text),
+            vec![Artifact::url("https://example.com", None)]
+        );
+        assert!(response_artifacts("browser_click", &params, &text).is_empty());
+    }
 }
@@ -23,6 +23,9 @@
 //! }
 //! ```
 
+pub mod artifact;
+pub mod descriptions;
+pub mod dry_run;
 pub mod filesystem;
 pub mod interaction;
 pub mod lsp;
@@ -30,9 +33,11 @@ pub mod mcp;
 pub mod notebook;
 pub mod planning;
 pub mod process_utils;
+pub mod remote;
 pub mod shell;
 pub mod skill;
 pub mod task;
+pub mod validation;
 pub mod web;
 
 use serde::{Deserialize, Serialize};
@@ -42,8 +47,11 @@ use std::pin::Pin;
 use std::future::Future;
 use std::sync::Arc;
 
+use parking_lot::Mutex;
+
 use crate::error::ToolError;
 pub use crate::session::ToolExecutionContext;
+pub use artifact::{Artifact, BlobRef};
 
 // Re-export genai's Tool as ToolDefinition to avoid conflict with our Tool trait
 pub use genai::chat::Tool as ToolDefinition;
@@ -62,6 +70,9 @@ pub struct ToolOutput {
     pub error: Option<String>,
     /// Metadata about the execution
     pub metadata: HashMap<String, Value>,
+    /// The result in structured form for frontends (not sent to the LLM)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub artifacts: Vec<Artifact>,
 }
 
 impl ToolOutput {
@@ -71,6 +82,7 @@ impl ToolOutput {
             content: content.into(),
             error: None,
             metadata: HashMap::new(),
+            artifacts: Vec::new(),
         }
     }
 
@@ -80,6 +92,7 @@ impl ToolOutput {
             content: Value::Null,
             error: Some(message.into()),
             metadata: HashMap::new(),
+            artifacts: Vec::new(),
         }
     }
 
@@ -87,6 +100,11 @@ impl ToolOutput {
         self.metadata.insert(key.into(), value.into());
         self
     }
+
+    pub fn with_artifact(mut self, artifact: Artifact) -> Self {
+        self.artifacts.push(artifact);
+        self
+    }
 }
 
 /// Core trait for all tools
@@ -97,6 +115,15 @@ pub trait Tool: Send + Sync {
     /// Description of what the tool does
     fn description(&self) -> &str;
 
+    /// A short form of the description, a few sentences, offered instead of
+    /// the full one when `[tools] compact_descriptions` is on
+    ///
+    /// Built-in tools return a hand-written summary; the default (used by
+    /// MCP tools) is the full description.
+    fn compact_description(&self) -> &str {
+        self.description()
+    }
+
     /// JSON schema for parameters
     fn parameters_schema(&self) -> Value;
 
@@ -112,6 +139,25 @@ pub trait Tool: Send + Sync {
     /// * `ctx` - Execution context with approval channel
     fn execute(&self, params: Value, ctx: ToolExecutionContext) -> BoxFuture<'_, Result<ToolOutput, ToolError>>;
 
+    /// Whether repeating a call with the same arguments gives the same result
+    ///
+    /// Only idempotent calls are answered from the previous result when the
+    /// agent loop sees the model repeating itself; tools whose output can
+    /// change between calls (shell commands, edits, subagents) keep the
+    /// default and always run.
+    fn is_idempotent(&self) -> bool {
+        false
+    }
+
+    /// Whether the tool reports heartbeats while it runs
+    ///
+    /// Tools that may run for minutes return true and call
+    /// `ctx.report_progress()` periodically, so the UI can show they are
+    /// still alive; the agent loop only gives them a progress sender then.
+    fn supports_progress(&self) -> bool {
+        false
+    }
+
     /// Convert to tool definition for LLM
     fn to_definition(&self) -> ToolDefinition {
         ToolDefinition::new(self.name())
@@ -124,6 +170,10 @@ pub trait Tool: Send + Sync {
 #[derive(Default)]
 pub struct ToolRegistry {
     tools: HashMap<String, Arc<dyn Tool>>,
+    /// Compiled parameter schemas by tool name (None = schema didn't compile)
+    validators: Mutex<HashMap<String, Option<Arc<jsonschema::Validator>>>>,
+    /// Leave MCP tool arguments unchecked (their schemas may be sloppy)
+    skip_mcp_validation: bool,
 }
 
 impl ToolRegistry {
@@ -150,6 +200,78 @@ impl ToolRegistry {
     pub fn all(&self) -> Vec<Arc<dyn Tool>> {
         self.tools.values().cloned().collect()
     }
+
+    /// Keep only the tools whose names satisfy `keep`
+    pub fn retain(&mut self, mut keep: impl FnMut(&str) -> bool) {
+        self.tools.retain(|name, _| keep(name));
+    }
+
+    /// A registry of the tools whose names satisfy `keep`, sharing them with this one
+    pub fn filtered(&self, mut keep: impl FnMut(&str) -> bool) -> Self {
+        Self {
+            tools: self
+                .tools
+                .iter()
+                .filter(|(name, _)| keep(name))
+                .map(|(name, tool)| (name.clone(), tool.clone()))
+                .collect(),
+            validators: Mutex::default(),
+            skip_mcp_validation: self.skip_mcp_validation,
+        }
+    }
+
+    /// Replace each tool with `f(tool)` (e.g. to wrap it)
+    pub fn map_tools(&mut self, mut f: impl FnMut(Arc<dyn Tool>) -> Arc<dyn Tool>) {
+        for tool in self.tools.values_mut() {
+            *tool = f(tool.clone());
+        }
+    }
+
+    /// Swap every tool with side effects for a dry-run stand-in
+    ///
+    /// See [`dry_run`] for which tools still run.
+    pub fn into_dry_run(mut self) -> Self {
+        for tool in self.tools.values_mut() {
+            if !dry_run::runs_in_dry_run(tool.as_ref()) {
+                *tool = Arc::new(dry_run::DryRunTool::new(tool.clone()));
+            }
+        }
+        self
+    }
+
+    /// Set whether MCP tool arguments are validated (default: true)
+    pub fn set_mcp_validation(&mut self, enabled: bool) {
+        self.skip_mcp_validation = !enabled;
+    }
+
+    /// Check a call's arguments against the tool's parameters schema
+    ///
+    /// Unambiguous slips are corrected in place first (see
+    /// [`validation`]). Unknown tools pass; the caller reports them.
+    /// Arguments that arrived as unparseable JSON text are rejected even
+    /// when MCP validation is off.
+    pub fn check_arguments(&self, name: &str, arguments: &mut Value) -> Result<(), validation::InvalidArguments> {
+        let Some(tool) = self.tools.get(name) else {
+            return Ok(());
+        };
+
+        let schema = tool.parameters_schema();
+        validation::decode_json_text(name, &schema, arguments)?;
+        if self.skip_mcp_validation && name.starts_with(mcp::MCP_TOOL_PREFIX) {
+            return Ok(());
+        }
+        let validator = {
+            let mut validators = self.validators.lock();
+            validators
+                .entry(name.to_string())
+                .or_insert_with(|| validation::compile_schema(name, &schema).map(Arc::new))
+                .clone()
+        };
+        match validator {
+            Some(validator) => validation::validate_arguments(name, &schema, &validator, arguments),
+            None => Ok(()),
+        }
+    }
 }
 
 /// Get standard tool definitions
@@ -53,6 +53,10 @@ impl Tool for NotebookEdit {
          - Use edit_mode=delete to delete the cell at the specified position"
     }
 
+    fn compact_description(&self) -> &str {
+        "Replaces, inserts (edit_mode=insert) or deletes (edit_mode=delete) a cell in a Jupyter notebook, given its absolute path."
+    }
+
     fn parameters_schema(&self) -> Value {
         json!({
             "type": "object",
@@ -1,12 +1,15 @@
 //! Config tool - Runtime settings management
 //!
-//! Allows getting and setting configuration values at runtime.
+//! Allows getting and setting configuration values at runtime. Settings the
+//! organization policy constrains are reported as `locked_by_policy`, and
+//! values outside the policy are refused rather than saved.
 
 
 use serde_json::{json, Value};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+use crate::approval::ApprovalLevel;
 use crate::config::ConfigManager;
 use crate::error::ToolError;
 use crate::tools::{BoxFuture, Tool, ToolExecutionContext, ToolOutput};
@@ -65,10 +68,12 @@ impl Tool for ConfigTool {
         // If no value provided, get current value
         if params.get("value").is_none() || params["value"].is_null() {
             let current_value = get_config_value(&config, setting);
+            let locked = config.policy().locked_settings().contains(&setting);
             return Ok(ToolOutput::success(json!({
                 "setting": setting,
                 "value": current_value,
-                "readonly": true
+                "readonly": true,
+                "locked_by_policy": locked
             })));
         }
 
@@ -76,6 +81,7 @@ impl Tool for ConfigTool {
         drop(config);
         let mut config = self.config_manager.write().await;
         let new_value = &params["value"];
+        check_policy(&config, setting, new_value).map_err(ToolError::PermissionDenied)?;
 
         match set_config_value(&mut config, setting, new_value.clone()) {
             Ok(_) => {
@@ -126,6 +132,32 @@ fn get_config_value(config: &ConfigManager, key: &str) -> Value {
     }
 }
 
+/// Refuse values outside what the organization policy allows
+fn check_policy(config: &ConfigManager, key: &str, value: &Value) -> Result<(), String> {
+    let policy = config.policy();
+    match key {
+        "auto_approve_level" => {
+            let Some(level) = value.as_str().and_then(|s| s.parse::<ApprovalLevel>().ok()) else {
+                return Ok(());
+            };
+            if policy.clamp_approval_level(level) != level {
+                return Err(format!(
+                    "auto_approve_level is locked by {}: it can't be less strict than {}",
+                    policy.describe(),
+                    policy.approval.min_level.as_deref().unwrap_or_default()
+                ));
+            }
+        }
+        "default_provider" | "provider" => {
+            if let Some(provider) = value.as_str() {
+                policy.check_provider(provider, None).map_err(|e| e.to_string())?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
 fn set_config_value(config: &mut ConfigManager, key: &str, value: Value) -> Result<(), String> {
     let parts: Vec<&str> = key.split('.').collect();
 
@@ -174,3 +206,31 @@ fn set_config_value(config: &mut ConfigManager, key: &str, value: Value) -> Resu
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::Policy;
+
+    #[tokio::test]
+    async fn test_policy_locked_settings() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let policy = Policy::parse("[approval]\nmin_level = \"medium\"\n\n[provider]\nallowed = [\"anthropic\"]").unwrap();
+        let manager = ConfigManager::with_path(dir.path().join("config.toml")).unwrap().with_policy(policy);
+        let tool = ConfigTool::new(Arc::new(RwLock::new(manager)));
+        let ctx = || ToolExecutionContext::standalone("test", "Config");
+
+        let get = tool.execute(json!({"setting": "auto_approve_level"}), ctx()).await.unwrap();
+        assert_eq!(get.content["locked_by_policy"], true);
+        let get = tool.execute(json!({"setting": "log_level"}), ctx()).await.unwrap();
+        assert_eq!(get.content["locked_by_policy"], false);
+
+        let lax = tool.execute(json!({"setting": "auto_approve_level", "value": "low"}), ctx()).await;
+        assert!(matches!(lax, Err(ToolError::PermissionDenied(_))));
+        let strict = tool.execute(json!({"setting": "auto_approve_level", "value": "high"}), ctx()).await;
+        assert!(strict.is_ok());
+
+        let provider = tool.execute(json!({"setting": "provider", "value": "openai"}), ctx()).await;
+        assert!(matches!(provider, Err(ToolError::PermissionDenied(e)) if e.contains("not allowed")));
+    }
+}
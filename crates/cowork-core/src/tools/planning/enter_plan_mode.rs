@@ -34,7 +34,11 @@ impl Tool for EnterPlanMode {
     }
 
     fn description(&self) -> &str {
-        include_str!("../../prompt/builtin/claude_code/tools/enterplanmode.md")
+        crate::prompt::builtin::claude_code::tools::ENTER_PLAN_MODE
+    }
+
+    fn compact_description(&self) -> &str {
+        crate::prompt::builtin::claude_code::tools::ENTER_PLAN_MODE_COMPACT
     }
 
     fn parameters_schema(&self) -> Value {
@@ -3,7 +3,11 @@
 mod config;
 mod enter_plan_mode;
 mod plan_mode;
+mod plan_status;
+pub mod store;
 
 pub use config::ConfigTool;
 pub use enter_plan_mode::EnterPlanMode;
 pub use plan_mode::{get_plans_dir, ExitPlanMode, PlanModeState};
+pub use plan_status::PlanStatusTool;
+pub use store::{Plan, PlanRecord, PlanStatus, PlanStep, PlanStore, StepProgress};
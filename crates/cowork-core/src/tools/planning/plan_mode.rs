@@ -8,10 +8,13 @@ use serde_json::{json, Value};
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::RwLock;
+use tracing::warn;
 
 use crate::error::ToolError;
 use crate::tools::{BoxFuture, Tool, ToolExecutionContext, ToolOutput};
 
+use super::store::{parse_steps, plan_name, PlanStore};
+
 /// Allowed prompt for bash commands
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AllowedPrompt {
@@ -57,6 +60,12 @@ pub struct PlanModeState {
     /// Full path to the plan file (e.g., ~/.claude/plans/keen-stirring-sunbeam.md)
     pub plan_file: Option<PathBuf>,
     pub allowed_prompts: Vec<AllowedPrompt>,
+    /// Session the plans are written by (recorded in the plan index)
+    pub session_id: Option<String>,
+    /// Name of the plan being executed (set by ExitPlanMode or approval)
+    pub current_plan: Option<String>,
+    /// Plan approved since the last turn, injected into the next one
+    pub approved_plan: Option<String>,
 }
 
 impl PlanModeState {
@@ -73,17 +82,22 @@ impl PlanModeState {
 /// Tool for exiting plan mode
 pub struct ExitPlanMode {
     state: Arc<RwLock<PlanModeState>>,
+    store: PlanStore,
 }
 
 impl ExitPlanMode {
     pub fn new(state: Arc<RwLock<PlanModeState>>) -> Self {
-        Self { state }
+        Self { state, store: PlanStore::default() }
     }
 
     pub fn new_standalone() -> Self {
-        Self {
-            state: Arc::new(RwLock::new(PlanModeState::default())),
-        }
+        Self::new(Arc::new(RwLock::new(PlanModeState::default())))
+    }
+
+    /// Index plans in this store instead of the plans directory
+    pub fn with_store(mut self, store: PlanStore) -> Self {
+        self.store = store;
+        self
     }
 }
 
@@ -97,6 +111,10 @@ impl Tool for ExitPlanMode {
         crate::prompt::builtin::claude_code::tools::EXIT_PLAN_MODE
     }
 
+    fn compact_description(&self) -> &str {
+        crate::prompt::builtin::claude_code::tools::EXIT_PLAN_MODE_COMPACT
+    }
+
     fn parameters_schema(&self) -> Value {
         json!({
             "type": "object",
@@ -171,10 +189,23 @@ impl Tool for ExitPlanMode {
                 }).collect::<Vec<_>>()
             });
 
-            // Include plan contents if available
+            // Include plan contents if available, and index the plan so it
+            // can be listed, approved, and tracked later
             if let Some(contents) = plan_contents {
                 if let Some(ref plan_file) = state.plan_file {
                     result["plan_file"] = json!(plan_file.to_string_lossy());
+                    match self.store.register(plan_file, state.session_id.as_deref()) {
+                        Ok(record) => {
+                            result["plan_name"] = json!(record.name);
+                            result["plan_status"] = json!(record.status);
+                        }
+                        Err(e) => warn!("Failed to index plan {:?}: {}", plan_file, e),
+                    }
+                    state.current_plan = plan_name(plan_file);
+                }
+                let steps = parse_steps(&contents);
+                if !steps.is_empty() {
+                    result["steps"] = json!(steps.iter().map(|s| &s.text).collect::<Vec<_>>());
                 }
                 result["plan_contents"] = json!(contents);
             }
@@ -0,0 +1,162 @@
+//! PlanStatus tool - Report progress through an approved plan
+//!
+//! Reconciles the plan's checklist against the TodoWrite list and moves the
+//! plan to in-progress or done as its steps complete.
+
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::error::ToolError;
+use crate::tools::task::{TodoList, TodoStatus};
+use crate::tools::{BoxFuture, Tool, ToolExecutionContext, ToolOutput};
+
+use super::plan_mode::PlanModeState;
+use super::store::{reconcile_steps, PlanStatus, PlanStore};
+
+/// Tool reporting per-step completion of a plan
+pub struct PlanStatusTool {
+    state: Arc<RwLock<PlanModeState>>,
+    todos: TodoList,
+    store: PlanStore,
+}
+
+impl PlanStatusTool {
+    pub fn new(state: Arc<RwLock<PlanModeState>>, todos: TodoList) -> Self {
+        Self { state, todos, store: PlanStore::default() }
+    }
+
+    /// Read plans from this store instead of the plans directory
+    pub fn with_store(mut self, store: PlanStore) -> Self {
+        self.store = store;
+        self
+    }
+}
+
+impl Tool for PlanStatusTool {
+    fn name(&self) -> &str {
+        "PlanStatus"
+    }
+
+    fn description(&self) -> &str {
+        "Report progress through the approved plan. Each checklist step of the plan is matched \
+         against the TodoWrite list (use the step text as the todo content) and reported as \
+         pending, in_progress, or completed. Defaults to the plan of the current session."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "plan": {
+                    "type": "string",
+                    "description": "Plan name (e.g. 'keen-stirring-sunbeam'). Defaults to the current plan."
+                }
+            }
+        })
+    }
+
+    fn execute(&self, params: Value, _ctx: ToolExecutionContext) -> BoxFuture<'_, Result<ToolOutput, ToolError>> {
+        Box::pin(async move {
+            let name = match params["plan"].as_str() {
+                Some(name) => name.to_string(),
+                None => self.state.read().await.current_plan.clone().ok_or_else(|| {
+                    ToolError::InvalidParams("No current plan; pass the plan name".into())
+                })?,
+            };
+            let plan = self
+                .store
+                .open(&name)
+                .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?
+                .ok_or_else(|| ToolError::ResourceNotFound(format!("Plan not found: {}", name)))?;
+
+            if plan.is_read_only() {
+                return Ok(ToolOutput::success(json!({
+                    "plan": plan.record.name,
+                    "title": plan.record.title,
+                    "status": plan.record.status,
+                    "read_only": true,
+                    "message": "This plan has no checklist, so its steps can't be tracked."
+                })));
+            }
+
+            let steps = reconcile_steps(&plan.steps, &self.todos.read().await);
+            let count = |status: TodoStatus| steps.iter().filter(|s| s.status == status).count();
+            let (completed, in_progress) = (count(TodoStatus::Completed), count(TodoStatus::InProgress));
+
+            // Approved plans move along as their steps are worked through
+            let mut status = plan.record.status;
+            if matches!(status, PlanStatus::Approved | PlanStatus::InProgress) {
+                let progressed = if completed == steps.len() {
+                    PlanStatus::Done
+                } else if completed + in_progress > 0 {
+                    PlanStatus::InProgress
+                } else {
+                    status
+                };
+                if progressed != status {
+                    self.store
+                        .set_status(&name, progressed)
+                        .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+                    status = progressed;
+                }
+            }
+
+            Ok(ToolOutput::success(json!({
+                "plan": plan.record.name,
+                "title": plan.record.title,
+                "status": status,
+                "steps": steps,
+                "summary": {
+                    "total": steps.len(),
+                    "completed": completed,
+                    "in_progress": in_progress,
+                    "pending": count(TodoStatus::Pending)
+                }
+            })))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::task::TodoItem;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_reports_progress_and_completes_plan() {
+        let dir = TempDir::new().unwrap();
+        let store = PlanStore::new(dir.path());
+        std::fs::write(store.path_for("calm-river"), "# Plan\n- [ ] Add the parser\n- [ ] Wire it up\n").unwrap();
+        store.set_status("calm-river", PlanStatus::Approved).unwrap();
+
+        let state = Arc::new(RwLock::new(PlanModeState {
+            current_plan: Some("calm-river".into()),
+            ..Default::default()
+        }));
+        let todos: TodoList = Arc::new(RwLock::new(vec![TodoItem {
+            content: "Add the parser".into(),
+            status: TodoStatus::Completed,
+            active_form: "Adding the parser".into(),
+            ..Default::default()
+        }]));
+        let tool = PlanStatusTool::new(state, todos.clone()).with_store(store.clone());
+        let ctx = || ToolExecutionContext::standalone("t1", "PlanStatus");
+
+        let output = tool.execute(json!({}), ctx()).await.unwrap();
+        assert_eq!(output.content["status"], "in_progress");
+        assert_eq!(output.content["steps"][0]["status"], "completed");
+        assert_eq!(output.content["steps"][1]["status"], "pending");
+
+        todos.write().await.push(TodoItem {
+            content: "Wire it up".into(),
+            status: TodoStatus::Completed,
+            active_form: "Wiring it up".into(),
+            ..Default::default()
+        });
+        let output = tool.execute(json!({ "plan": "calm-river" }), ctx()).await.unwrap();
+        assert_eq!(output.content["status"], "done");
+        assert_eq!(store.open("calm-river").unwrap().unwrap().record.status, PlanStatus::Done);
+    }
+}
@@ -0,0 +1,404 @@
+//! Plan store - index and lifecycle of plan files
+//!
+//! Plans live as markdown files in the plans directory (~/.claude/plans).
+//! `index.json` beside them records each plan's title, creation time,
+//! session, and status. Files without an index entry (e.g. written by older
+//! versions) are listed as drafts. A plan's steps are its markdown checklist
+//! items (`- [ ] step`); free-form plans without a checklist can still be
+//! opened, but have no steps to track.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+use crate::error::{Error, Result};
+use crate::tools::task::{TodoItem, TodoStatus};
+
+use super::plan_mode::get_plans_dir;
+
+/// Index file in the plans directory
+pub const PLAN_INDEX_FILE: &str = "index.json";
+
+/// Lifecycle status of a plan
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanStatus {
+    /// Written in plan mode, not yet approved
+    #[default]
+    Draft,
+    /// Approved by the user, work not started
+    Approved,
+    /// Being executed
+    InProgress,
+    /// Every step completed
+    Done,
+}
+
+impl std::fmt::Display for PlanStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            PlanStatus::Draft => "draft",
+            PlanStatus::Approved => "approved",
+            PlanStatus::InProgress => "in-progress",
+            PlanStatus::Done => "done",
+        };
+        f.write_str(s)
+    }
+}
+
+/// Index entry for a plan file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanRecord {
+    /// File name without `.md` (e.g. "keen-stirring-sunbeam")
+    pub name: String,
+    /// First markdown heading, or the name
+    pub title: String,
+    pub created_at: DateTime<Utc>,
+    /// Session that wrote the plan
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    #[serde(default)]
+    pub status: PlanStatus,
+}
+
+/// A checklist item in a plan
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanStep {
+    pub text: String,
+    /// Checked off in the plan file itself (`- [x]`)
+    pub checked: bool,
+}
+
+/// Progress of one plan step
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StepProgress {
+    pub text: String,
+    pub status: TodoStatus,
+}
+
+/// A plan file with its index entry and parsed steps
+#[derive(Debug, Clone)]
+pub struct Plan {
+    pub record: PlanRecord,
+    pub path: PathBuf,
+    pub content: String,
+    pub steps: Vec<PlanStep>,
+}
+
+impl Plan {
+    /// Free-form plans have no checklist, so there is nothing to track
+    pub fn is_read_only(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Reminder telling the model the plan was approved and is its task list
+    pub fn approval_reminder(&self) -> String {
+        let instructions = if self.is_read_only() {
+            "Carry it out as written."
+        } else {
+            "It is now the authoritative task list for this work: carry out its steps in order, \
+             track each one with TodoWrite using the step text as the todo content, and call \
+             PlanStatus to check which steps are done."
+        };
+        format!(
+            "The user approved the plan \"{}\" ({}). {}\n\n<plan>\n{}\n</plan>",
+            self.record.title,
+            self.path.display(),
+            instructions,
+            self.content.trim_end()
+        )
+    }
+}
+
+/// Indexes the plan files in a directory
+#[derive(Debug, Clone)]
+pub struct PlanStore {
+    dir: PathBuf,
+}
+
+impl Default for PlanStore {
+    fn default() -> Self {
+        Self::new(get_plans_dir())
+    }
+}
+
+impl PlanStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Path of the plan file with this name
+    pub fn path_for(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{}.md", name))
+    }
+
+    /// All plans, newest first
+    pub fn list(&self) -> Result<Vec<PlanRecord>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+        let index = self.load_index();
+        let mut plans = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != "md") {
+                continue;
+            }
+            let Some(name) = plan_name(&path) else { continue };
+            let record = match index.get(&name) {
+                Some(record) => record.clone(),
+                None => self.unindexed_record(&name, &path),
+            };
+            plans.push(record);
+        }
+        plans.sort_by(|a, b| b.created_at.cmp(&a.created_at).then_with(|| a.name.cmp(&b.name)));
+        Ok(plans)
+    }
+
+    /// Read a plan and parse its steps
+    pub fn open(&self, name: &str) -> Result<Option<Plan>> {
+        let path = self.path_for(name);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(&path)?;
+        let record = match self.load_index().remove(name) {
+            Some(record) => record,
+            None => self.unindexed_record(name, &path),
+        };
+        let steps = parse_steps(&content);
+        Ok(Some(Plan { record, path, content, steps }))
+    }
+
+    /// Index a plan file (keeping its status if already indexed)
+    pub fn register(&self, path: &Path, session_id: Option<&str>) -> Result<PlanRecord> {
+        let name = plan_name(path)
+            .ok_or_else(|| Error::Agent(format!("Not a plan file: {}", path.display())))?;
+        let content = std::fs::read_to_string(path).unwrap_or_default();
+        let mut index = self.load_index();
+        let record = index
+            .entry(name.clone())
+            .or_insert_with(|| PlanRecord {
+                name: name.clone(),
+                title: name.clone(),
+                created_at: Utc::now(),
+                session_id: session_id.map(String::from),
+                status: PlanStatus::Draft,
+            });
+        record.title = plan_title(&content).unwrap_or(name);
+        if record.session_id.is_none() {
+            record.session_id = session_id.map(String::from);
+        }
+        let record = record.clone();
+        self.save_index(&index)?;
+        Ok(record)
+    }
+
+    /// Change a plan's status
+    pub fn set_status(&self, name: &str, status: PlanStatus) -> Result<PlanRecord> {
+        let path = self.path_for(name);
+        if !path.is_file() {
+            return Err(Error::Agent(format!("Plan not found: {}", name)));
+        }
+        let mut index = self.load_index();
+        let record = index
+            .entry(name.to_string())
+            .or_insert_with(|| self.unindexed_record(name, &path));
+        record.status = status;
+        let record = record.clone();
+        self.save_index(&index)?;
+        Ok(record)
+    }
+
+    /// Record for a plan file that has no index entry
+    fn unindexed_record(&self, name: &str, path: &Path) -> PlanRecord {
+        let content = std::fs::read_to_string(path).unwrap_or_default();
+        let created_at = std::fs::metadata(path)
+            .and_then(|m| m.created().or_else(|_| m.modified()))
+            .map(DateTime::<Utc>::from)
+            .unwrap_or_else(|_| Utc::now());
+        PlanRecord {
+            name: name.to_string(),
+            title: plan_title(&content).unwrap_or_else(|| name.to_string()),
+            created_at,
+            session_id: None,
+            status: PlanStatus::Draft,
+        }
+    }
+
+    fn load_index(&self) -> BTreeMap<String, PlanRecord> {
+        let path = self.dir.join(PLAN_INDEX_FILE);
+        let Ok(json) = std::fs::read_to_string(&path) else {
+            return BTreeMap::new();
+        };
+        serde_json::from_str(&json).unwrap_or_else(|e| {
+            warn!("Ignoring unreadable plan index {:?}: {}", path, e);
+            BTreeMap::new()
+        })
+    }
+
+    fn save_index(&self, index: &BTreeMap<String, PlanRecord>) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let json = serde_json::to_string_pretty(index)?;
+        std::fs::write(self.dir.join(PLAN_INDEX_FILE), json)?;
+        Ok(())
+    }
+}
+
+/// Plan name from a plan file path
+pub fn plan_name(path: &Path) -> Option<String> {
+    path.file_stem().map(|s| s.to_string_lossy().to_string())
+}
+
+/// First markdown heading of a plan
+pub fn plan_title(content: &str) -> Option<String> {
+    content
+        .lines()
+        .find_map(|line| line.trim_start().strip_prefix('#'))
+        .map(|heading| heading.trim_start_matches('#').trim().to_string())
+        .filter(|title| !title.is_empty())
+}
+
+/// Checklist items (`- [ ] step`, `* [x] step`, `1. [ ] step`) in a plan
+pub fn parse_steps(content: &str) -> Vec<PlanStep> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim_start();
+            let rest = line
+                .strip_prefix(['-', '*', '+'])
+                .or_else(|| {
+                    let digits = line.find(|c: char| !c.is_ascii_digit()).filter(|&n| n > 0)?;
+                    line[digits..].strip_prefix(['.', ')'])
+                })?
+                .trim_start();
+            let (checked, text) = if let Some(text) = rest.strip_prefix("[ ]") {
+                (false, text)
+            } else if let Some(text) = rest.strip_prefix("[x]").or_else(|| rest.strip_prefix("[X]")) {
+                (true, text)
+            } else {
+                return None;
+            };
+            let text = text.trim();
+            (!text.is_empty()).then(|| PlanStep { text: text.to_string(), checked })
+        })
+        .collect()
+}
+
+/// Per-step progress, matching steps to todo items by their text
+///
+/// A step is completed if it is checked in the plan or a matching todo is
+/// completed, and in progress if a matching todo is.
+pub fn reconcile_steps(steps: &[PlanStep], todos: &[TodoItem]) -> Vec<StepProgress> {
+    let todos: Vec<(String, &TodoItem)> = todos.iter().map(|t| (normalize(&t.content), t)).collect();
+    steps
+        .iter()
+        .map(|step| {
+            let status = if step.checked {
+                TodoStatus::Completed
+            } else {
+                let text = normalize(&step.text);
+                todos
+                    .iter()
+                    .find(|(todo, _)| texts_match(&text, todo))
+                    .map(|(_, t)| t.status.clone())
+                    .unwrap_or(TodoStatus::Pending)
+            };
+            StepProgress { text: step.text.clone(), status }
+        })
+        .collect()
+}
+
+/// Shortest text matched by containment rather than equality
+const MIN_CONTAINED_LEN: usize = 8;
+
+fn texts_match(a: &str, b: &str) -> bool {
+    if a.is_empty() || b.is_empty() {
+        return false;
+    }
+    a == b
+        || (b.len() >= MIN_CONTAINED_LEN && a.contains(b))
+        || (a.len() >= MIN_CONTAINED_LEN && b.contains(a))
+}
+
+/// Lowercase words, ignoring punctuation and markdown
+fn normalize(text: &str) -> String {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const CHECKLIST_PLAN: &str = "# Add retries\n\nContext here.\n\n## Steps\n\n- [ ] Add `RetryPolicy` to config\n- [x] Wrap provider calls\n1. [ ] Write tests\n- not a step\n";
+
+    fn todo(content: &str, status: TodoStatus) -> TodoItem {
+        TodoItem { content: content.to_string(), status, active_form: content.to_string(), ..Default::default() }
+    }
+
+    #[test]
+    fn test_parse_steps_and_title() {
+        let steps = parse_steps(CHECKLIST_PLAN);
+        assert_eq!(
+            steps,
+            vec![
+                PlanStep { text: "Add `RetryPolicy` to config".into(), checked: false },
+                PlanStep { text: "Wrap provider calls".into(), checked: true },
+                PlanStep { text: "Write tests".into(), checked: false },
+            ]
+        );
+        assert_eq!(plan_title(CHECKLIST_PLAN).as_deref(), Some("Add retries"));
+        assert!(parse_steps("Just prose.\n\n1. First do this\n").is_empty());
+    }
+
+    #[test]
+    fn test_reconcile_steps() {
+        let steps = parse_steps(CHECKLIST_PLAN);
+        let todos = vec![
+            todo("Add RetryPolicy to config", TodoStatus::Completed),
+            todo("Write tests for retries", TodoStatus::InProgress),
+        ];
+        let statuses: Vec<TodoStatus> = reconcile_steps(&steps, &todos).into_iter().map(|s| s.status).collect();
+        assert_eq!(statuses, vec![TodoStatus::Completed, TodoStatus::Completed, TodoStatus::InProgress]);
+    }
+
+    #[test]
+    fn test_store_lifecycle() {
+        let dir = TempDir::new().unwrap();
+        let store = PlanStore::new(dir.path());
+        std::fs::write(store.path_for("old-free-form"), "Some notes without a checklist").unwrap();
+        std::fs::write(store.path_for("keen-river"), CHECKLIST_PLAN).unwrap();
+
+        let record = store.register(&store.path_for("keen-river"), Some("session-1")).unwrap();
+        assert_eq!(record.title, "Add retries");
+        assert_eq!(record.status, PlanStatus::Draft);
+        store.set_status("keen-river", PlanStatus::Approved).unwrap();
+
+        let plans = store.list().unwrap();
+        assert_eq!(plans.len(), 2);
+        let indexed = plans.iter().find(|p| p.name == "keen-river").unwrap();
+        assert_eq!(indexed.status, PlanStatus::Approved);
+        assert_eq!(indexed.session_id.as_deref(), Some("session-1"));
+
+        // Registering again keeps the status
+        store.register(&store.path_for("keen-river"), None).unwrap();
+        assert_eq!(store.open("keen-river").unwrap().unwrap().record.status, PlanStatus::Approved);
+
+        let old = store.open("old-free-form").unwrap().unwrap();
+        assert!(old.is_read_only());
+        assert_eq!(old.record.title, "old-free-form");
+        assert!(store.open("missing").unwrap().is_none());
+        assert!(store.set_status("missing", PlanStatus::Done).is_err());
+    }
+}
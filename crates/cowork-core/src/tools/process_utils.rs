@@ -51,6 +51,51 @@ pub fn shell_command(command: &str) -> Command {
     }
 }
 
+/// Give a command a process group of its own (Unix only)
+///
+/// Whatever the command starts joins the group, so a `ProcessGroupKiller`
+/// can stop the whole tree rather than just the shell.
+#[cfg(unix)]
+pub fn own_process_group(cmd: &mut Command) {
+    cmd.process_group(0);
+}
+
+#[cfg(not(unix))]
+pub fn own_process_group(_cmd: &mut Command) {
+    // Windows has no process groups to kill; `kill_on_drop` stops the shell
+}
+
+/// Kills a child's process group when dropped, unless disarmed
+///
+/// `kill_on_drop` only stops the shell; the commands it started keep
+/// running. Create one right after spawning a child put in its own group
+/// with `own_process_group`, and disarm it once the child has exited.
+pub struct ProcessGroupKiller {
+    pid: Option<u32>,
+}
+
+impl ProcessGroupKiller {
+    pub fn new(child: &tokio::process::Child) -> Self {
+        Self { pid: child.id() }
+    }
+
+    /// The child exited on its own; leave what it left running alone
+    pub fn disarm(&mut self) {
+        self.pid = None;
+    }
+}
+
+impl Drop for ProcessGroupKiller {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Some(pid) = self.pid.and_then(|pid| i32::try_from(pid).ok()) {
+            use nix::sys::signal::{killpg, Signal};
+            use nix::unistd::Pid;
+            let _ = killpg(Pid::from_raw(pid), Signal::SIGKILL);
+        }
+    }
+}
+
 /// Create a shell command for background execution with output redirection
 ///
 /// The output will be redirected to the specified file.
@@ -140,6 +185,18 @@ pub fn std_direct_command(program: &str) -> std::process::Command {
     cmd
 }
 
+/// `own_process_group` for a std::process::Command
+#[cfg(unix)]
+pub fn std_own_process_group(cmd: &mut std::process::Command) {
+    use std::os::unix::process::CommandExt;
+    cmd.process_group(0);
+}
+
+#[cfg(not(unix))]
+pub fn std_own_process_group(_cmd: &mut std::process::Command) {
+    // No process groups on Windows
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
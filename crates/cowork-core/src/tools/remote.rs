@@ -0,0 +1,438 @@
+//! SSH backend for remote workspaces
+//!
+//! When the workspace is remote (`[remote]` in the config), the filesystem and
+//! shell tools run on the remote host through the system `ssh` client. The
+//! connection is multiplexed (`ControlMaster`): the first call opens it and
+//! later calls reuse it for `ControlPersist` after the last one.
+//!
+//! Every operation is a single remote `sh` script. Paths are checked against
+//! the root twice: lexically here, and after resolving symlinks on the remote
+//! host (`realpath -m`) in the same script that does the work. Glob lists the
+//! tree with one `find` and Grep runs one `rg`, instead of a round trip per
+//! file. The remote host needs a POSIX `sh` and GNU coreutils/findutils; Grep
+//! also needs ripgrep.
+//!
+//! When ssh itself fails (exit 255 with an ssh error) the call returns
+//! `ToolError::ConnectionLost` and the control connection is closed, so the
+//! next call reconnects.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tracing::{debug, warn};
+
+use crate::config::RemoteTarget;
+use crate::error::ToolError;
+
+/// Exit status of ssh when the connection fails
+const SSH_ERROR_STATUS: i32 = 255;
+
+/// Exit status of the path guard for paths outside the root
+const OUTSIDE_ROOT_STATUS: i32 = 97;
+
+/// Exit status for paths that don't exist
+const NOT_FOUND_STATUS: i32 = 98;
+
+/// Exit status for directories where a file was expected
+const IS_DIRECTORY_STATUS: i32 = 99;
+
+/// How long an idle control connection stays open
+const CONTROL_PERSIST: &str = "10m";
+
+/// Timeout for file operations (commands pass their own)
+pub const FILE_OP_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Output of a remote command
+#[derive(Debug)]
+pub struct RemoteOutput {
+    /// Exit status (None if killed by a signal)
+    pub status: Option<i32>,
+    pub stdout: Vec<u8>,
+    pub stderr: String,
+}
+
+impl RemoteOutput {
+    pub fn success(&self) -> bool {
+        self.status == Some(0)
+    }
+}
+
+/// What a remote path points at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemoteFileKind {
+    File,
+    Directory,
+}
+
+/// A workspace directory on a remote host
+pub struct RemoteWorkspace {
+    target: RemoteTarget,
+    /// Normalized absolute root
+    root: String,
+}
+
+impl RemoteWorkspace {
+    pub fn new(target: RemoteTarget) -> Self {
+        let root = normalize("/", &target.root);
+        Self { target, root }
+    }
+
+    pub fn target(&self) -> &RemoteTarget {
+        &self.target
+    }
+
+    /// Absolute workspace root on the remote host
+    pub fn root(&self) -> &str {
+        &self.root
+    }
+
+    /// Resolve a tool path (absolute, or relative to the root) to an absolute
+    /// remote path, rejecting paths that leave the root
+    pub fn resolve(&self, path: &str) -> Result<String, ToolError> {
+        let resolved = normalize(&self.root, path);
+        if !is_within(&resolved, &self.root) {
+            return Err(ToolError::PermissionDenied(format!(
+                "Path {} is outside workspace {}",
+                path, self.root
+            )));
+        }
+        Ok(resolved)
+    }
+
+    /// Path relative to the root, for tool output
+    pub fn relative(&self, path: &str) -> String {
+        if path == self.root {
+            return ".".to_string();
+        }
+        let prefix = if self.root == "/" { "/".to_string() } else { format!("{}/", self.root) };
+        path.strip_prefix(&prefix).unwrap_or(path).to_string()
+    }
+
+    /// Read a file
+    pub async fn read(&self, path: &str) -> Result<Vec<u8>, ToolError> {
+        let script = self.guarded(
+            path,
+            r#"[ -e "$p" ] || exit 98; [ -d "$p" ] && exit 99; cat -- "$p""#,
+        );
+        let output = self.run_checked(&script, None, FILE_OP_TIMEOUT, path).await?;
+        Ok(output.stdout)
+    }
+
+    /// Read a file if it exists
+    pub async fn read_optional(&self, path: &str) -> Result<Option<Vec<u8>>, ToolError> {
+        match self.read(path).await {
+            Ok(content) => Ok(Some(content)),
+            Err(ToolError::ResourceNotFound(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Write a file, creating its parent directories if `create_dirs` is set
+    pub async fn write(&self, path: &str, content: &[u8], create_dirs: bool) -> Result<(), ToolError> {
+        let body = if create_dirs {
+            r#"[ -d "$p" ] && exit 99; mkdir -p -- "$(dirname -- "$p")" && cat > "$p""#
+        } else {
+            r#"[ -d "$p" ] && exit 99; cat > "$p""#
+        };
+        let script = self.guarded(path, body);
+        self.run_checked(&script, Some(content), FILE_OP_TIMEOUT, path).await?;
+        Ok(())
+    }
+
+    /// Whether a path is a file or a directory (None if it doesn't exist)
+    pub async fn kind(&self, path: &str) -> Result<Option<RemoteFileKind>, ToolError> {
+        let script = self.guarded(
+            path,
+            r#"if [ -d "$p" ]; then echo d; elif [ -e "$p" ]; then echo f; else exit 98; fi"#,
+        );
+        match self.run_checked(&script, None, FILE_OP_TIMEOUT, path).await {
+            Ok(output) if output.stdout.starts_with(b"d") => Ok(Some(RemoteFileKind::Directory)),
+            Ok(_) => Ok(Some(RemoteFileKind::File)),
+            Err(ToolError::ResourceNotFound(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// All files under a directory with their modification times (seconds
+    /// since the epoch), paths relative to the directory
+    pub async fn list_files(&self, dir: &str) -> Result<Vec<(String, f64)>, ToolError> {
+        let script = self.guarded(
+            dir,
+            r#"[ -d "$p" ] || exit 98; cd -- "$p" && find . -type f -printf '%T@ %P\0'"#,
+        );
+        let output = self.run_checked(&script, None, FILE_OP_TIMEOUT, dir).await?;
+        Ok(output
+            .stdout
+            .split(|b| *b == 0)
+            .filter(|entry| !entry.is_empty())
+            .filter_map(|entry| {
+                let entry = String::from_utf8_lossy(entry);
+                let (mtime, path) = entry.split_once(' ')?;
+                Some((path.to_string(), mtime.parse().unwrap_or(0.0)))
+            })
+            .collect())
+    }
+
+    /// Run a shell command in `cwd` with extra environment variables
+    ///
+    /// The command's own exit status is returned as is; only ssh failures
+    /// are errors.
+    pub async fn exec(
+        &self,
+        command: &str,
+        cwd: &str,
+        vars: &BTreeMap<String, String>,
+        timeout: Duration,
+    ) -> Result<RemoteOutput, ToolError> {
+        let mut script = String::new();
+        for (name, value) in vars {
+            let _ = write!(script, "export {}={}; ", name, quote(value));
+        }
+        script.push_str("cd -- \"$p\" || exit 1; unset root p; ");
+        script.push_str(command);
+        self.run_guarded(cwd, &script, timeout).await
+    }
+
+    /// Run `body` with `$p` set to `path` (see `guarded`), returning its
+    /// exit status as is unless the path is outside the root
+    pub async fn run_guarded(&self, path: &str, body: &str, timeout: Duration) -> Result<RemoteOutput, ToolError> {
+        let output = self.run(&self.guarded(path, body), None, timeout).await?;
+        if output.status == Some(OUTSIDE_ROOT_STATUS) && output.stderr.contains("is outside workspace") {
+            return Err(ToolError::PermissionDenied(output.stderr.trim().to_string()));
+        }
+        Ok(output)
+    }
+
+    /// Run a script and map the guard's exit statuses to errors
+    async fn run_checked(
+        &self,
+        script: &str,
+        stdin: Option<&[u8]>,
+        timeout: Duration,
+        path: &str,
+    ) -> Result<RemoteOutput, ToolError> {
+        let output = self.run(script, stdin, timeout).await?;
+        match output.status {
+            Some(0) => Ok(output),
+            Some(OUTSIDE_ROOT_STATUS) => Err(ToolError::PermissionDenied(output.stderr.trim().to_string())),
+            Some(NOT_FOUND_STATUS) => Err(ToolError::ResourceNotFound(format!("{} does not exist", path))),
+            Some(IS_DIRECTORY_STATUS) => Err(ToolError::InvalidParams(format!("{} is a directory", path))),
+            status => Err(ToolError::ExecutionFailed(format!(
+                "Remote command failed ({}): {}",
+                status.map_or("signal".to_string(), |s| s.to_string()),
+                output.stderr.trim()
+            ))),
+        }
+    }
+
+    /// Wrap a script so it runs with `$p` set to `path` after resolving
+    /// symlinks, and only if that is still inside the root
+    fn guarded(&self, path: &str, body: &str) -> String {
+        format!(
+            "root=$(realpath -m -- {root}) && p=$(realpath -m -- {path}) || exit 1; \
+             case \"$p/\" in \"${{root%/}}\"/*) ;; *) echo \"{shown} is outside workspace $root\" >&2; exit {status};; esac; \
+             {body}",
+            root = quote(&self.root),
+            path = quote(path),
+            shown = path.replace(['"', '$', '`', '\\'], "_"),
+            status = OUTSIDE_ROOT_STATUS,
+        )
+    }
+
+    /// Run a script with `sh` on the remote host
+    pub async fn run(&self, script: &str, stdin: Option<&[u8]>, timeout: Duration) -> Result<RemoteOutput, ToolError> {
+        let mut cmd = self.ssh_command();
+        cmd.arg("--")
+            .arg(format!("sh -c {}", quote(script)))
+            .stdin(if stdin.is_some() { Stdio::piped() } else { Stdio::null() })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+
+        let mut child = cmd
+            .spawn()
+            .map_err(|e| ToolError::ExecutionFailed(format!("Failed to run ssh: {}", e)))?;
+        if let Some(input) = stdin
+            && let Some(mut pipe) = child.stdin.take()
+        {
+            pipe.write_all(input).await.map_err(ToolError::Io)?;
+            // Closing stdin ends the remote `cat`
+            drop(pipe);
+        }
+
+        let output = tokio::time::timeout(timeout, child.wait_with_output())
+            .await
+            .map_err(|_| ToolError::ExecutionFailed(format!("Remote command timed out after {}s", timeout.as_secs())))?
+            .map_err(ToolError::Io)?;
+
+        let output = RemoteOutput {
+            status: output.status.code(),
+            stdout: output.stdout,
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        };
+        if output.status == Some(SSH_ERROR_STATUS) && is_ssh_error(&output.stderr) {
+            warn!("Lost connection to {}: {}", self.target.destination(), output.stderr.trim());
+            self.disconnect().await;
+            return Err(ToolError::ConnectionLost(format!(
+                "{}: {} (will reconnect on the next call)",
+                self.target.destination(),
+                output.stderr.trim()
+            )));
+        }
+        Ok(output)
+    }
+
+    /// Close the control connection so the next call opens a fresh one
+    pub async fn disconnect(&self) {
+        if !cfg!(unix) {
+            return;
+        }
+        let mut cmd = self.ssh_command();
+        cmd.args(["-O", "exit"]).stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null());
+        if let Err(e) = cmd.status().await {
+            debug!("Failed to close ssh control connection: {}", e);
+        }
+    }
+
+    /// `ssh` with the target's options and connection multiplexing
+    fn ssh_command(&self) -> Command {
+        let mut cmd = Command::new("ssh");
+        // Never prompt: there is no terminal to answer on
+        cmd.args(["-o", "BatchMode=yes", "-o", "ServerAliveInterval=15", "-o", "ServerAliveCountMax=3"]);
+        if cfg!(unix) {
+            cmd.args(["-o", "ControlMaster=auto"])
+                .arg("-o")
+                .arg(format!("ControlPath={}", control_path().display()))
+                .arg("-o")
+                .arg(format!("ControlPersist={}", CONTROL_PERSIST));
+        }
+        if let Some(port) = self.target.port {
+            cmd.arg("-p").arg(port.to_string());
+        }
+        if let Some(identity) = &self.target.identity_file {
+            cmd.arg("-i").arg(identity).args(["-o", "IdentitiesOnly=yes"]);
+        }
+        for option in &self.target.ssh_options {
+            cmd.arg("-o").arg(option);
+        }
+        cmd.arg(self.target.destination());
+        cmd
+    }
+}
+
+/// Control socket path; ssh expands `%C` to a hash of the connection, so
+/// every session to the same host shares one connection
+fn control_path() -> PathBuf {
+    std::env::temp_dir().join("cowork-ssh-%C")
+}
+
+/// Whether ssh's stderr describes a connection failure rather than output
+/// of a remote command that happened to exit with 255
+fn is_ssh_error(stderr: &str) -> bool {
+    const MARKERS: [&str; 9] = [
+        "ssh:",
+        "Connection closed",
+        "Connection reset",
+        "Connection refused",
+        "Connection timed out",
+        "Could not resolve hostname",
+        "client_loop:",
+        "Permission denied (",
+        "Host key verification failed",
+    ];
+    MARKERS.iter().any(|marker| stderr.contains(marker)) || stderr.contains("mux_client")
+}
+
+/// Quote a string for a POSIX shell
+pub fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Join `path` onto `base` and resolve `.` and `..` lexically, POSIX style
+fn normalize(base: &str, path: &str) -> String {
+    let joined = if path.starts_with('/') { path.to_string() } else { format!("{}/{}", base, path) };
+    let mut parts: Vec<&str> = Vec::new();
+    for part in joined.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    format!("/{}", parts.join("/"))
+}
+
+/// Whether `path` is `root` or below it (both normalized)
+fn is_within(path: &str, root: &str) -> bool {
+    root == "/" || path == root || path.strip_prefix(root).is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Split a glob into its literal leading directories and the rest, so a
+/// remote listing only walks the part of the tree the glob can match
+pub fn split_glob(pattern: &str) -> (String, String) {
+    let is_literal = |part: &str| !part.contains(['*', '?', '[', '{']);
+    let parts: Vec<&str> = pattern.split('/').collect();
+    let literal = parts[..parts.len().saturating_sub(1)]
+        .iter()
+        .take_while(|part| is_literal(part))
+        .count();
+    let prefix = parts[..literal].join("/");
+    let prefix = if pattern.starts_with('/') && prefix.is_empty() { "/".to_string() } else { prefix };
+    (prefix, parts[literal..].join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workspace(root: &str) -> RemoteWorkspace {
+        RemoteWorkspace::new(RemoteTarget {
+            host: "build".into(),
+            user: None,
+            port: None,
+            identity_file: None,
+            root: root.into(),
+            ssh_options: Vec::new(),
+        })
+    }
+
+    #[test]
+    fn test_resolve_stays_inside_root() {
+        let ws = workspace("/srv/app/");
+        assert_eq!(ws.root(), "/srv/app");
+        assert_eq!(ws.resolve("src/main.rs").unwrap(), "/srv/app/src/main.rs");
+        assert_eq!(ws.resolve("./src/../Cargo.toml").unwrap(), "/srv/app/Cargo.toml");
+        assert_eq!(ws.resolve("/srv/app/README.md").unwrap(), "/srv/app/README.md");
+        assert_eq!(ws.resolve(".").unwrap(), "/srv/app");
+
+        assert!(matches!(ws.resolve("../other"), Err(ToolError::PermissionDenied(_))));
+        assert!(matches!(ws.resolve("/srv/application"), Err(ToolError::PermissionDenied(_))));
+        assert!(matches!(ws.resolve("/etc/passwd"), Err(ToolError::PermissionDenied(_))));
+
+        assert_eq!(ws.relative("/srv/app/src/main.rs"), "src/main.rs");
+        assert_eq!(ws.relative("/srv/app"), ".");
+    }
+
+    #[test]
+    fn test_split_glob() {
+        assert_eq!(split_glob("**/*.rs"), ("".into(), "**/*.rs".into()));
+        assert_eq!(split_glob("src/**/*.rs"), ("src".into(), "**/*.rs".into()));
+        assert_eq!(split_glob("src/tools/*.rs"), ("src/tools".into(), "*.rs".into()));
+        assert_eq!(split_glob("Cargo.toml"), ("".into(), "Cargo.toml".into()));
+        assert_eq!(split_glob("/srv/app/*.md"), ("/srv/app".into(), "*.md".into()));
+    }
+
+    #[test]
+    fn test_quote_and_ssh_errors() {
+        assert_eq!(quote("it's"), r"'it'\''s'");
+        assert!(is_ssh_error("ssh: connect to host build port 22: Connection refused\n"));
+        assert!(is_ssh_error("client_loop: send disconnect: Broken pipe\n"));
+        assert!(!is_ssh_error("make: *** [all] Error 255\n"));
+    }
+}
@@ -2,20 +2,44 @@
 
 use serde_json::{json, Value};
 use std::path::PathBuf;
-use std::process::Stdio;
+use std::process::{ExitStatus, Stdio};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWriteExt, BufReader};
+use tokio::process::Command;
+use tokio::sync::mpsc;
 
 use crate::error::ToolError;
-use crate::tools::process_utils::{shell_command, shell_command_background};
+use crate::processes::{ProcessKind, ProcessTracker};
+use crate::tools::process_utils::{own_process_group, shell_command, ProcessGroupKiller};
+use crate::tools::remote::RemoteWorkspace;
 use crate::tools::{BoxFuture, Tool, ToolExecutionContext, ToolOutput};
 
-use super::{BackgroundShell, ShellConfig, ShellProcessRegistry, ShellStatus};
+use super::{BackgroundShell, EnvPolicy, SecretMasker, ShellConfig, ShellProcessRegistry, ShellStatus};
+
+/// How often a foreground command reports progress
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Longest output line sent with a progress report
+const MAX_PROGRESS_LINE_CHARS: usize = 200;
+
+/// Output of a foreground command that ran to completion
+struct CapturedOutput {
+    status: ExitStatus,
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+}
 
 /// Tool for executing shell commands
 pub struct ExecuteCommand {
     config: ShellConfig,
     workspace: PathBuf,
     process_registry: Option<Arc<ShellProcessRegistry>>,
+    /// Child environment and secret masking for command output
+    env_policy: EnvPolicy,
+    /// Remote workspace to run commands in instead of `workspace`
+    remote: Option<Arc<RemoteWorkspace>>,
 }
 
 impl ExecuteCommand {
@@ -24,6 +48,8 @@ impl ExecuteCommand {
             config: ShellConfig::default(),
             workspace,
             process_registry: None,
+            env_policy: EnvPolicy::default(),
+            remote: None,
         }
     }
 
@@ -37,6 +63,130 @@ impl ExecuteCommand {
         self
     }
 
+    pub fn with_env_policy(mut self, policy: EnvPolicy) -> Self {
+        self.env_policy = policy;
+        self
+    }
+
+    /// Run commands in a remote workspace, or the local one with `None`
+    pub fn with_remote(mut self, remote: Option<Arc<RemoteWorkspace>>) -> Self {
+        self.remote = remote;
+        self
+    }
+
+    /// Run a command on the remote host
+    ///
+    /// The remote login environment is kept; only the policy's `vars` are
+    /// added. Background commands need a local process to track, so they
+    /// are not supported remotely.
+    async fn execute_remote(
+        &self,
+        remote: &RemoteWorkspace,
+        command: &str,
+        working_dir: Option<&str>,
+        run_in_background: bool,
+        timeout_secs: u64,
+    ) -> Result<ToolOutput, ToolError> {
+        if run_in_background {
+            return Err(ToolError::InvalidParams(
+                "run_in_background is not supported in a remote workspace; use nohup or a terminal multiplexer in the command instead".into(),
+            ));
+        }
+        let cwd = remote.resolve(working_dir.unwrap_or("."))?;
+        let output = remote
+            .exec(
+                command,
+                &cwd,
+                &self.env_policy.vars,
+                Duration::from_secs(timeout_secs),
+            )
+            .await?;
+
+        let masker = self.env_policy.masker();
+        let stdout = masker.mask(&String::from_utf8_lossy(&output.stdout));
+        let stderr = masker.mask(&output.stderr);
+        Ok(ToolOutput::success(json!({
+            "exit_code": output.status,
+            "stdout": stdout,
+            "stderr": stderr,
+            "success": output.success()
+        })))
+    }
+
+    /// Run a command to completion, capturing its output line by line
+    ///
+    /// While it runs, the bytes captured so far and the latest output line
+    /// are reported through `ctx` once a second. If it is cancelled or runs
+    /// past `timeout_secs`, the command and everything it started are killed.
+    async fn run_foreground(
+        &self,
+        mut cmd: Command,
+        ctx: &ToolExecutionContext,
+        timeout_secs: u64,
+    ) -> Result<CapturedOutput, ToolError> {
+        let started = Instant::now();
+        own_process_group(&mut cmd);
+        let mut child = cmd
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+        // Returning early drops it, which kills the command's own children too
+        let mut group = ProcessGroupKiller::new(&child);
+        let timed_out = || ToolError::ExecutionFailed(format!("Command timed out after {}s", timeout_secs));
+        let deadline = tokio::time::sleep(Duration::from_secs(timeout_secs));
+        tokio::pin!(deadline);
+
+        let (line_tx, mut line_rx) = mpsc::unbounded_channel();
+        if let Some(stdout) = child.stdout.take() {
+            tokio::spawn(read_lines(stdout, false, line_tx.clone()));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            tokio::spawn(read_lines(stderr, true, line_tx.clone()));
+        }
+        drop(line_tx);
+
+        let masker = self.env_policy.masker();
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        let mut last_line: Option<String> = None;
+        let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + PROGRESS_INTERVAL, PROGRESS_INTERVAL);
+        loop {
+            tokio::select! {
+                line = line_rx.recv() => {
+                    // None once both streams are closed
+                    let Some((is_stderr, bytes)) = line else { break };
+                    if let Some(line) = progress_line(&bytes) {
+                        last_line = Some(line);
+                    }
+                    if is_stderr {
+                        stderr.extend_from_slice(&bytes);
+                    } else {
+                        stdout.extend_from_slice(&bytes);
+                    }
+                }
+                _ = ticker.tick(), if ctx.wants_progress() => {
+                    ctx.report_progress(
+                        started.elapsed(),
+                        (stdout.len() + stderr.len()) as u64,
+                        last_line.as_deref().map(|line| masker.mask(line)),
+                    );
+                }
+                _ = ctx.cancelled() => return Err(ToolError::Cancelled),
+                _ = &mut deadline => return Err(timed_out()),
+            }
+        }
+
+        let status = tokio::select! {
+            status = child.wait() => status?,
+            _ = ctx.cancelled() => return Err(ToolError::Cancelled),
+            _ = &mut deadline => return Err(timed_out()),
+        };
+        group.disarm();
+        Ok(CapturedOutput { status, stdout, stderr })
+    }
+
     fn is_command_blocked(&self, command: &str) -> bool {
         // Normalize command: collapse whitespace, trim
         let normalized: String = command
@@ -108,6 +258,10 @@ impl Tool for ExecuteCommand {
         crate::prompt::builtin::claude_code::tools::BASH
     }
 
+    fn compact_description(&self) -> &str {
+        crate::prompt::builtin::claude_code::tools::BASH_COMPACT
+    }
+
     fn parameters_schema(&self) -> Value {
         json!({
             "type": "object",
@@ -140,6 +294,10 @@ impl Tool for ExecuteCommand {
         })
     }
 
+    fn supports_progress(&self) -> bool {
+        true
+    }
+
     fn execute(&self, params: Value, ctx: ToolExecutionContext) -> BoxFuture<'_, Result<ToolOutput, ToolError>> {
         Box::pin(async move {
             let command = params["command"]
@@ -164,6 +322,12 @@ impl Tool for ExecuteCommand {
                 .await
                 .map_err(ToolError::Rejected)?;
 
+            if let Some(remote) = &self.remote {
+                return self
+                    .execute_remote(remote, command, params["working_dir"].as_str(), run_in_background, timeout_secs)
+                    .await;
+            }
+
             let working_dir = if let Some(dir) = params["working_dir"].as_str() {
                 self.workspace.join(dir)
             } else {
@@ -182,12 +346,35 @@ impl Tool for ExecuteCommand {
                         .to_string_lossy()
                         .to_string();
 
-                    // Spawn the command in background with output redirection
-                    // Uses process_utils which handles hiding console windows on Windows
-                    let child = shell_command_background(command, &output_file)
+                    let log = tokio::fs::File::create(&output_file)
+                        .await
+                        .map_err(|e| ToolError::ExecutionFailed(format!("Failed to create output file: {}", e)))?;
+
+                    // Spawn the command in background; its output is masked on the
+                    // way to the log file. Uses process_utils which handles hiding
+                    // console windows on Windows
+                    let mut cmd = shell_command(command);
+                    own_process_group(&mut cmd);
+                    let mut child = cmd
                         .current_dir(&working_dir)
+                        .env_clear()
+                        .envs(self.env_policy.environment())
+                        .stdin(Stdio::null())
+                        .stdout(Stdio::piped())
+                        .stderr(Stdio::piped())
                         .spawn()
                         .map_err(|e| ToolError::ExecutionFailed(format!("Failed to spawn: {}", e)))?;
+                    let (line_tx, line_rx) = mpsc::unbounded_channel();
+                    if let Some(stdout) = child.stdout.take() {
+                        tokio::spawn(read_lines(stdout, false, line_tx.clone()));
+                    }
+                    if let Some(stderr) = child.stderr.take() {
+                        tokio::spawn(read_lines(stderr, true, line_tx));
+                    }
+                    tokio::spawn(write_masked_log(line_rx, log, self.env_policy.masker()));
+                    let tracked = child
+                        .id()
+                        .map(|pid| ProcessTracker::global().track(pid, ProcessKind::Shell, command, cfg!(unix)));
 
                     let bg_shell = BackgroundShell {
                         id: shell_id.clone(),
@@ -196,6 +383,7 @@ impl Tool for ExecuteCommand {
                         started_at: chrono::Utc::now(),
                         status: ShellStatus::Running,
                         output: None,
+                        tracked,
                     };
 
                     registry.register(bg_shell).await;
@@ -213,24 +401,18 @@ impl Tool for ExecuteCommand {
                 }
             }
 
-            // Foreground execution with timeout
-            // Uses process_utils which handles hiding console windows on Windows
-            let output = tokio::time::timeout(
-                std::time::Duration::from_secs(timeout_secs),
-                shell_command(command)
-                    .current_dir(&working_dir)
-                    .stdout(Stdio::piped())
-                    .stderr(Stdio::piped())
-                    .output(),
-            )
-            .await
-            .map_err(|_| {
-                ToolError::ExecutionFailed(format!("Command timed out after {}s", timeout_secs))
-            })?
-            .map_err(ToolError::Io)?;
+            // Foreground execution with timeout (the command is killed when it expires
+            // or the call is cancelled). Uses process_utils which handles hiding
+            // console windows on Windows
+            let mut cmd = shell_command(command);
+            cmd.current_dir(&working_dir)
+                .env_clear()
+                .envs(self.env_policy.environment());
+            let output = self.run_foreground(cmd, &ctx, timeout_secs).await?;
 
-            let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            let masker = self.env_policy.masker();
+            let stdout = masker.mask(&String::from_utf8_lossy(&output.stdout));
+            let stderr = masker.mask(&String::from_utf8_lossy(&output.stderr));
 
             Ok(ToolOutput::success(json!({
                 "exit_code": output.status.code(),
@@ -241,3 +423,50 @@ impl Tool for ExecuteCommand {
         })
     }
 }
+
+/// Forward a stream's lines, newline included, until it closes
+async fn read_lines(
+    stream: impl AsyncRead + Unpin,
+    is_stderr: bool,
+    line_tx: mpsc::UnboundedSender<(bool, Vec<u8>)>,
+) {
+    let mut reader = BufReader::new(stream);
+    loop {
+        let mut line = Vec::new();
+        match reader.read_until(b'\n', &mut line).await {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {
+                if line_tx.send((is_stderr, line)).is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Append a background command's lines to its log file, secrets masked
+async fn write_masked_log(
+    mut line_rx: mpsc::UnboundedReceiver<(bool, Vec<u8>)>,
+    mut log: tokio::fs::File,
+    masker: SecretMasker,
+) {
+    while let Some((_, bytes)) = line_rx.recv().await {
+        let line = masker.mask(&String::from_utf8_lossy(&bytes));
+        if log.write_all(line.as_bytes()).await.is_err() {
+            break;
+        }
+    }
+    let _ = log.flush().await;
+}
+
+/// A line of output as shown in progress reports, or None if it is blank
+///
+/// Progress bars redraw with carriage returns, so only the last redraw is kept.
+fn progress_line(bytes: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(bytes);
+    let line = text.trim_end().rsplit('\r').next().unwrap_or_default().trim();
+    if line.is_empty() {
+        return None;
+    }
+    Some(line.chars().take(MAX_PROGRESS_LINE_CHARS).collect())
+}
@@ -9,6 +9,7 @@ use tokio::process::Child;
 use tokio::sync::RwLock;
 
 use crate::error::ToolError;
+use crate::processes::ProcessGuard;
 use crate::tools::{BoxFuture, Tool, ToolExecutionContext, ToolOutput};
 
 /// Registry for tracking background shell processes
@@ -24,6 +25,9 @@ pub struct BackgroundShell {
     pub started_at: chrono::DateTime<chrono::Utc>,
     pub status: ShellStatus,
     pub output: Option<String>,
+    /// Registration with the process tracker (kills the shell's process
+    /// group when dropped)
+    pub tracked: Option<ProcessGuard>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -67,6 +71,7 @@ impl ShellProcessRegistry {
                         .await
                         .map_err(|e| format!("Failed to kill process: {}", e))?;
                 }
+                shell.tracked = None;
                 shell.status = ShellStatus::Killed;
                 Ok(())
             } else {
@@ -87,6 +92,25 @@ impl ShellProcessRegistry {
     }
 }
 
+/// Background shells don't outlive the session that started them
+impl Drop for ShellProcessRegistry {
+    fn drop(&mut self) {
+        let Ok(mut processes) = self.processes.try_write() else {
+            return;
+        };
+        for shell in processes.values_mut() {
+            if shell.status != ShellStatus::Running {
+                continue;
+            }
+            if let Some(child) = shell.child.as_mut() {
+                let _ = child.start_kill();
+                shell.status = ShellStatus::Killed;
+            }
+            shell.tracked = None;
+        }
+    }
+}
+
 /// Tool for killing background shell processes
 pub struct KillShell {
     registry: Arc<ShellProcessRegistry>,
@@ -111,6 +135,10 @@ impl Tool for KillShell {
          - Shell IDs can be found using the /tasks command"
     }
 
+    fn compact_description(&self) -> &str {
+        "Kills a running background shell by its shell_id."
+    }
+
     fn parameters_schema(&self) -> Value {
         json!({
             "type": "object",
@@ -5,6 +5,7 @@ mod kill;
 
 pub use execute::ExecuteCommand;
 pub use kill::{BackgroundShell, KillShell, ShellProcessRegistry, ShellStatus};
+pub use cowork_sandbox::{EnvPolicy, SecretMasker};
 
 use std::collections::HashSet;
 
@@ -6,6 +6,7 @@
 //!
 //! Skills with `context: fork` run in a subagent instead of inline.
 
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
 
@@ -15,7 +16,7 @@ use serde_json::Value;
 use crate::error::ToolError;
 use crate::prompt::builtin::claude_code::tools::SKILL as SKILL_DESCRIPTION;
 use crate::prompt::substitution::substitute_commands;
-use crate::skills::SkillRegistry;
+use crate::skills::{SkillContext, SkillRegistry, FOLLOW_UP_PROMPT_KEY};
 use crate::tools::{BoxFuture, Tool, ToolExecutionContext, ToolOutput};
 
 /// Metadata key signaling the agent loop to inject content as a user message
@@ -55,6 +56,10 @@ impl Tool for SkillTool {
         SKILL_DESCRIPTION
     }
 
+    fn compact_description(&self) -> &str {
+        crate::prompt::builtin::claude_code::tools::SKILL_COMPACT
+    }
+
     fn parameters_schema(&self) -> Value {
         serde_json::json!({
             "type": "object",
@@ -89,6 +94,31 @@ impl Tool for SkillTool {
                     format!("Unknown skill: '{}'. Use /help to see available commands.", skill_name)
                 ))?;
 
+            // Local skills produce their output directly
+            if skill.runs_locally() {
+                let ctx = SkillContext {
+                    workspace: self.workspace.clone(),
+                    args: args.to_string(),
+                    data: HashMap::new(),
+                };
+                let result = skill.execute(ctx).await;
+                // A follow-up prompt (e.g. `/agents test`) is for the LLM to act on
+                let follow_up = result
+                    .data
+                    .as_ref()
+                    .and_then(|d| d.get(FOLLOW_UP_PROMPT_KEY))
+                    .and_then(|v| v.as_str());
+                return if result.success {
+                    let response = match follow_up {
+                        Some(prompt) => format!("{}\n\n{}", result.response, prompt),
+                        None => result.response,
+                    };
+                    Ok(ToolOutput::success(Value::String(response)))
+                } else {
+                    Err(ToolError::ExecutionFailed(result.error.unwrap_or_default()))
+                };
+            }
+
             // Get the prompt template and apply substitutions
             let template = skill.prompt_template();
 
@@ -155,36 +185,26 @@ impl Tool for SkillTool {
 /// - `$ARGUMENTS` / `${ARGUMENTS}` - all arguments
 /// - `$ARGUMENTS[N]` / `${ARGUMENTS[N]}` - positional argument by index
 /// - `$N` - shorthand for positional argument (e.g., `$0`, `$1`)
+///
+/// Placeholders are replaced in one pass, so argument values that look like
+/// placeholders are inserted as they are.
 pub fn substitute_arguments(template: &str, args: &str) -> String {
     // Split arguments by whitespace for positional access
     let arg_parts: Vec<&str> = args.split_whitespace().collect();
 
-    let mut result = template.to_string();
-
-    // Replace $ARGUMENTS[N] and ${ARGUMENTS[N]} with positional args
-    let indexed_re = Regex::new(r"\$\{?ARGUMENTS\[(\d+)\]\}?").unwrap();
-    result = indexed_re
-        .replace_all(&result, |caps: &regex::Captures| {
-            let index: usize = caps[1].parse().unwrap_or(0);
-            arg_parts.get(index).unwrap_or(&"").to_string()
-        })
-        .to_string();
-
-    // Replace $N shorthand (must do after $ARGUMENTS to avoid conflicts)
-    // Match $0, $1, etc. but not $ARGUMENTS
-    let shorthand_re = Regex::new(r"\$(\d+)").unwrap();
-    result = shorthand_re
-        .replace_all(&result, |caps: &regex::Captures| {
-            let index: usize = caps[1].parse().unwrap_or(0);
-            arg_parts.get(index).unwrap_or(&"").to_string()
+    // Indexed forms come first, so `$ARGUMENTS[0]` isn't read as `$ARGUMENTS`
+    let placeholder_re = Regex::new(r"\$\{?ARGUMENTS\[(\d+)\]\}?|\$\{ARGUMENTS\}|\$ARGUMENTS|\$(\d+)").unwrap();
+    placeholder_re
+        .replace_all(template, |caps: &regex::Captures| {
+            match caps.get(1).or_else(|| caps.get(2)) {
+                Some(index) => {
+                    let index: usize = index.as_str().parse().unwrap_or(0);
+                    arg_parts.get(index).unwrap_or(&"").to_string()
+                }
+                None => args.to_string(),
+            }
         })
-        .to_string();
-
-    // Replace full $ARGUMENTS / ${ARGUMENTS}
-    result = result.replace("$ARGUMENTS", args);
-    result = result.replace("${ARGUMENTS}", args);
-
-    result
+        .into_owned()
 }
 
 #[cfg(test)]
@@ -9,11 +9,18 @@ use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use tokio::sync::{mpsc, RwLock};
+use tokio::sync::RwLock;
 
-use crate::session::{SessionOutput, SessionRegistry};
+use crate::policy::Policy;
+use crate::provider::UsageStore;
+use crate::session::{AuditLog, OutputSender, RequestLimiter, SessionRegistry};
 
 use crate::error::ToolError;
+use crate::prompt::{ComponentRegistry, ModelPreference, Scope};
+use crate::tools::filesystem::SyntaxVerifier;
+use crate::tools::remote::RemoteWorkspace;
+use crate::tools::shell::EnvPolicy;
+use crate::tools::web::NetworkPolicy;
 use crate::tools::{BoxFuture, Tool, ToolExecutionContext, ToolOutput};
 
 use super::executor::{self, AgentExecutionConfig};
@@ -30,6 +37,8 @@ pub enum AgentType {
     Explore,
     /// Software architect for designing implementation plans
     Plan,
+    /// User-defined agent from `.claude/agents/` (or a plugin), by name
+    Custom(String),
 }
 
 impl std::fmt::Display for AgentType {
@@ -39,6 +48,7 @@ impl std::fmt::Display for AgentType {
             AgentType::GeneralPurpose => write!(f, "general-purpose"),
             AgentType::Explore => write!(f, "Explore"),
             AgentType::Plan => write!(f, "Plan"),
+            AgentType::Custom(name) => write!(f, "{}", name),
         }
     }
 }
@@ -57,6 +67,47 @@ impl std::str::FromStr for AgentType {
     }
 }
 
+impl AgentType {
+    /// Built-in agent names, as accepted by `subagent_type`
+    pub const BUILTIN_NAMES: &'static [&'static str] = &["Bash", "general-purpose", "Explore", "Plan"];
+
+    /// Resolve a `subagent_type` to a built-in agent or a custom agent in the registry
+    pub fn resolve(name: &str, registry: Option<&ComponentRegistry>) -> Result<Self, String> {
+        if let Ok(agent_type) = name.parse() {
+            return Ok(agent_type);
+        }
+
+        let custom = registry.and_then(|r| {
+            r.get_agent(name)
+                .or_else(|| r.list_agents().find(|a| a.name().eq_ignore_ascii_case(name)))
+        });
+        match custom {
+            Some(agent) => Ok(AgentType::Custom(agent.name().to_string())),
+            None => {
+                let mut available: Vec<String> = Self::BUILTIN_NAMES.iter().map(|n| n.to_string()).collect();
+                if let Some(registry) = registry {
+                    available.extend(custom_agents(registry).into_iter().map(|(name, _)| name));
+                }
+                Err(format!("Unknown agent type: {}. Available: {}", name, available.join(", ")))
+            }
+        }
+    }
+}
+
+/// Custom agents (name, description) offered alongside the built-in types, sorted by name
+///
+/// Agents that override a built-in (e.g. a project `Explore.md`) are not listed
+/// separately since they are reached through the built-in name.
+fn custom_agents(registry: &ComponentRegistry) -> Vec<(String, String)> {
+    let mut agents: Vec<(String, String)> = registry
+        .list_agents()
+        .filter(|a| a.scope != Scope::Builtin && a.name().parse::<AgentType>().is_err())
+        .map(|a| (a.name().to_string(), a.description().to_string()))
+        .collect();
+    agents.sort();
+    agents
+}
+
 /// Model tier selection for subagents (provider-agnostic)
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
 #[serde(rename_all = "lowercase")]
@@ -70,6 +121,20 @@ pub enum ModelTier {
     Powerful,
 }
 
+impl ModelTier {
+    /// Map an agent's model preference onto a tier
+    ///
+    /// `inherit` and explicit model IDs have no tier; callers fall back to a default.
+    pub fn from_preference(preference: &ModelPreference) -> Option<Self> {
+        match preference {
+            ModelPreference::Haiku => Some(ModelTier::Fast),
+            ModelPreference::Sonnet => Some(ModelTier::Balanced),
+            ModelPreference::Opus => Some(ModelTier::Powerful),
+            ModelPreference::Inherit | ModelPreference::Custom(_) => None,
+        }
+    }
+}
+
 impl std::str::FromStr for ModelTier {
     type Err = String;
 
@@ -95,6 +160,8 @@ impl AgentType {
             AgentType::Plan => ModelTier::Balanced,
             // General purpose needs full capabilities - use balanced
             AgentType::GeneralPurpose => ModelTier::Balanced,
+            // Custom agents without a model preference get the balanced default
+            AgentType::Custom(_) => ModelTier::Balanced,
         }
     }
 }
@@ -182,11 +249,29 @@ pub struct TaskTool {
     api_key: Option<String>,
     model_tiers: Option<crate::config::ModelTiers>,
     /// Parent session's output channel for forwarding subagent activity
-    progress_tx: Option<mpsc::Sender<(String, SessionOutput)>>,
-    /// Parent session ID (used when forwarding events)
-    parent_session_id: Option<String>,
+    progress_tx: Option<OutputSender>,
     /// Shared session registry for subagent approval routing
     session_registry: Option<SessionRegistry>,
+    /// Component registry with custom agent definitions
+    component_registry: Option<Arc<ComponentRegistry>>,
+    /// Shared request limiter passed on to subagents
+    request_limiter: Option<Arc<RequestLimiter>>,
+    /// Usage store subagents record their LLM calls to
+    usage_store: Option<Arc<UsageStore>>,
+    /// Audit log subagents append to
+    audit_log: Option<Arc<AuditLog>>,
+    /// Environment policy for subagent shell commands
+    env_policy: Option<EnvPolicy>,
+    /// Organization policy subagents are held to
+    policy: Option<Arc<Policy>>,
+    /// Hosts subagent web tools may reach
+    network_policy: Option<NetworkPolicy>,
+    /// Syntax check after subagent edits
+    verifier: Option<Arc<SyntaxVerifier>>,
+    /// Remote workspace subagents operate on
+    remote: Option<Arc<RemoteWorkspace>>,
+    /// Roots subagent file tools may use besides the workspace
+    additional_roots: Vec<PathBuf>,
 }
 
 impl TaskTool {
@@ -199,9 +284,38 @@ impl TaskTool {
             api_key: None,
             model_tiers: None,
             progress_tx: None,
-            parent_session_id: None,
             session_registry: None,
+            component_registry: None,
+            request_limiter: None,
+            usage_store: None,
+            audit_log: None,
+            env_policy: None,
+            policy: None,
+            network_policy: None,
+            verifier: None,
+            remote: None,
+            additional_roots: Vec::new(),
+        }
+    }
+
+    /// Set the component registry so custom agents can be launched
+    pub fn with_component_registry(mut self, registry: Arc<ComponentRegistry>) -> Self {
+        self.component_registry = Some(registry);
+        self
+    }
+
+    /// Default model tier for an agent: a custom agent's preference, else the type's default
+    fn default_tier(&self, agent_type: &AgentType) -> ModelTier {
+        if let AgentType::Custom(name) = agent_type
+            && let Some(tier) = self
+                .component_registry
+                .as_ref()
+                .and_then(|r| r.get_agent(name))
+                .and_then(|a| ModelTier::from_preference(a.model()))
+        {
+            return tier;
         }
+        agent_type.default_tier()
     }
 
     /// Set the shared session registry for subagent approval routing
@@ -210,14 +324,63 @@ impl TaskTool {
         self
     }
 
+    /// Set the shared request limiter for subagent LLM calls
+    pub fn with_request_limiter(mut self, limiter: Arc<RequestLimiter>) -> Self {
+        self.request_limiter = Some(limiter);
+        self
+    }
+
+    /// Set the usage store for subagent LLM calls
+    pub fn with_usage_store(mut self, store: Arc<UsageStore>) -> Self {
+        self.usage_store = Some(store);
+        self
+    }
+
+    /// Set the audit log subagents append to
+    pub fn with_audit_log(mut self, log: Arc<AuditLog>) -> Self {
+        self.audit_log = Some(log);
+        self
+    }
+
+    /// Set the environment policy for subagent shell commands
+    pub fn with_env_policy(mut self, policy: EnvPolicy) -> Self {
+        self.env_policy = Some(policy);
+        self
+    }
+
+    /// Set the organization policy subagents are held to
+    pub fn with_policy(mut self, policy: Arc<Policy>) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    /// Set the hosts subagent web tools may reach
+    pub fn with_network_policy(mut self, network: NetworkPolicy) -> Self {
+        self.network_policy = Some(network);
+        self
+    }
+
+    /// Check the syntax of files subagents edit
+    pub fn with_verifier(mut self, verifier: Arc<SyntaxVerifier>) -> Self {
+        self.verifier = Some(verifier);
+        self
+    }
+
+    /// Set the remote workspace subagents operate on
+    pub fn with_remote(mut self, remote: Arc<RemoteWorkspace>) -> Self {
+        self.remote = Some(remote);
+        self
+    }
+
+    /// Set the roots subagent file tools may use besides the workspace
+    pub fn with_additional_roots(mut self, roots: Vec<PathBuf>) -> Self {
+        self.additional_roots = roots;
+        self
+    }
+
     /// Set the parent's output channel for forwarding subagent activity
-    pub fn with_progress_channel(
-        mut self,
-        tx: mpsc::Sender<(String, SessionOutput)>,
-        session_id: String,
-    ) -> Self {
+    pub fn with_progress_channel(mut self, tx: OutputSender) -> Self {
         self.progress_tx = Some(tx);
-        self.parent_session_id = Some(session_id);
         self
     }
 
@@ -250,7 +413,29 @@ impl Tool for TaskTool {
         crate::prompt::builtin::claude_code::tools::TASK
     }
 
+    fn compact_description(&self) -> &str {
+        crate::prompt::builtin::claude_code::tools::TASK_COMPACT
+    }
+
     fn parameters_schema(&self) -> Value {
+        let custom = self
+            .component_registry
+            .as_ref()
+            .map(|r| custom_agents(r))
+            .unwrap_or_default();
+
+        let mut agent_names: Vec<&str> = AgentType::BUILTIN_NAMES.to_vec();
+        agent_names.extend(custom.iter().map(|(name, _)| name.as_str()));
+
+        let mut agent_description =
+            "The type of specialized agent: Bash, general-purpose, Explore, or Plan".to_string();
+        if !custom.is_empty() {
+            agent_description.push_str(", or one of these custom agents:");
+            for (name, description) in &custom {
+                agent_description.push_str(&format!("\n- {}: {}", name, description));
+            }
+        }
+
         json!({
             "type": "object",
             "properties": {
@@ -264,8 +449,8 @@ impl Tool for TaskTool {
                 },
                 "subagent_type": {
                     "type": "string",
-                    "description": "The type of specialized agent: Bash, general-purpose, Explore, or Plan",
-                    "enum": ["Bash", "general-purpose", "Explore", "Plan"]
+                    "description": agent_description,
+                    "enum": agent_names
                 },
                 "model": {
                     "type": "string",
@@ -308,15 +493,14 @@ impl Tool for TaskTool {
             .as_str()
             .ok_or_else(|| ToolError::InvalidParams("subagent_type is required".into()))?;
 
-        let agent_type: AgentType = agent_type_str
-            .parse()
-            .map_err(|e: String| ToolError::InvalidParams(e))?;
+        let agent_type = AgentType::resolve(agent_type_str, self.component_registry.as_deref())
+            .map_err(ToolError::InvalidParams)?;
 
-        // Parse model tier, falling back to agent type's recommended default
+        // Parse model tier, falling back to the agent's recommended default
         let model = params["model"]
             .as_str()
             .and_then(|s| s.parse::<ModelTier>().ok())
-            .unwrap_or_else(|| agent_type.default_tier());
+            .unwrap_or_else(|| self.default_tier(&agent_type));
 
         let run_in_background = params["run_in_background"].as_bool().unwrap_or(false);
         let _max_turns = params["max_turns"].as_u64().unwrap_or(50);
@@ -379,10 +563,23 @@ impl Tool for TaskTool {
             config = config.with_model_tiers(tiers.clone());
         }
 
+        // Custom agents (and project overrides of built-ins) come from the component registry
+        if let Some(ref components) = self.component_registry {
+            config = config.with_registry(components.clone());
+        }
+
         // Forward parent's progress channel so subagent activity is visible
         config.progress_tx = self.progress_tx.clone();
-        config.parent_session_id = self.parent_session_id.clone();
         config.session_registry = self.session_registry.clone();
+        config.request_limiter = self.request_limiter.clone();
+        config.usage_store = self.usage_store.clone();
+        config.audit_log = self.audit_log.clone();
+        config.env_policy = self.env_policy.clone();
+        config.policy = self.policy.clone();
+        config.network_policy = self.network_policy.clone();
+        config.verifier = self.verifier.clone();
+        config.remote = self.remote.clone();
+        config.additional_roots = self.additional_roots.clone();
 
         // Share parent's approval channel with subagent
         config.parent_approval_channel = Some(parent_approval_tx);
@@ -406,7 +603,8 @@ impl Tool for TaskTool {
                 "message": format!("Agent '{}' started in background. Use TaskOutput to check progress.", description)
             })))
         } else {
-            // Execute agent synchronously using the shared AgentLoop
+            // Execute agent synchronously using the shared AgentLoop, stopping it with the call
+            config.cancel = Some(ctx.cancellation().clone());
             let result = executor::run_subagent(
                 &agent_type,
                 &model,
@@ -416,7 +614,11 @@ impl Tool for TaskTool {
                 &agent_id,
             )
             .await
-            .map_err(|e| ToolError::ExecutionFailed(format!("Agent execution failed: {}", e)))?;
+            .map_err(|e| if ctx.is_cancelled() {
+                ToolError::Cancelled
+            } else {
+                ToolError::ExecutionFailed(format!("Agent execution failed: {}", e))
+            })?;
 
             Ok(ToolOutput::success(json!({
                 "agent_id": agent_id,
@@ -431,11 +633,22 @@ impl Tool for TaskTool {
 /// Tool for getting output from background tasks
 pub struct TaskOutputTool {
     registry: Arc<AgentInstanceRegistry>,
+    /// Secrets masked in output read back from files
+    env_policy: EnvPolicy,
 }
 
 impl TaskOutputTool {
     pub fn new(registry: Arc<AgentInstanceRegistry>) -> Self {
-        Self { registry }
+        Self {
+            registry,
+            env_policy: EnvPolicy::default(),
+        }
+    }
+
+    /// Set the environment policy whose secrets are masked in file output
+    pub fn with_env_policy(mut self, policy: EnvPolicy) -> Self {
+        self.env_policy = policy;
+        self
     }
 }
 
@@ -455,6 +668,10 @@ impl Tool for TaskOutputTool {
          - Works with all task types: background shells, async agents, and remote sessions"
     }
 
+    fn compact_description(&self) -> &str {
+        "Retrieves the output and status of a background task (shell, agent or remote session) by task_id; block=false checks without waiting."
+    }
+
     fn parameters_schema(&self) -> Value {
         json!({
             "type": "object",
@@ -526,7 +743,7 @@ impl Tool for TaskOutputTool {
                 match tokio::fs::read_to_string(output_file).await {
                     Ok(content) => Ok(ToolOutput::success(json!({
                         "task_id": task_id,
-                        "output": content
+                        "output": self.env_policy.masker().mask(&content)
                     }))),
                     Err(e) => Err(ToolError::ExecutionFailed(format!(
                         "Failed to read output file: {}",
@@ -658,6 +875,49 @@ mod tests {
         assert!("unknown".parse::<AgentType>().is_err());
     }
 
+    fn registry_with_reviewer() -> Arc<ComponentRegistry> {
+        let mut components = ComponentRegistry::with_builtins();
+        components.register_agent(
+            crate::prompt::parse_agent(
+                "---\nname: reviewer\ndescription: Reviews diffs\nmodel: opus\ntools: Read, Grep\n---\n\nYou review code.",
+                None,
+                Scope::Project,
+            )
+            .unwrap(),
+        );
+        Arc::new(components)
+    }
+
+    #[test]
+    fn test_resolve_custom_agent() {
+        let components = registry_with_reviewer();
+
+        assert_eq!(AgentType::resolve("Explore", Some(&components)).unwrap(), AgentType::Explore);
+        assert_eq!(
+            AgentType::resolve("Reviewer", Some(&components)).unwrap(),
+            AgentType::Custom("reviewer".to_string())
+        );
+
+        let err = AgentType::resolve("nobody", Some(&components)).unwrap_err();
+        assert!(err.contains("reviewer"));
+        assert!(AgentType::resolve("reviewer", None).is_err());
+    }
+
+    #[test]
+    fn test_schema_lists_custom_agents() {
+        let tool = TaskTool::new(Arc::new(AgentInstanceRegistry::new()), PathBuf::from("/tmp/test-workspace"))
+            .with_component_registry(registry_with_reviewer());
+
+        let schema = tool.parameters_schema();
+        let subagent = &schema["properties"]["subagent_type"];
+        let names: Vec<&str> = subagent["enum"].as_array().unwrap().iter().filter_map(|v| v.as_str()).collect();
+        assert_eq!(names, vec!["Bash", "general-purpose", "Explore", "Plan", "reviewer"]);
+        assert!(subagent["description"].as_str().unwrap().contains("- reviewer: Reviews diffs"));
+
+        // Model preference maps onto a tier
+        assert_eq!(tool.default_tier(&AgentType::Custom("reviewer".to_string())), ModelTier::Powerful);
+    }
+
     #[tokio::test]
     async fn test_agent_registry() {
         let registry = AgentInstanceRegistry::new();
@@ -13,20 +13,26 @@ use std::sync::Arc;
 
 use tokio::fs::OpenOptions;
 use tokio::io::AsyncWriteExt;
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, info};
 
-use tokio::sync::mpsc;
 
 use crate::approval::ToolApprovalConfig;
 use crate::config::ModelTiers;
 use crate::error::Result;
+use crate::policy::Policy;
 use crate::orchestration::ToolScope;
+use crate::provider::UsageStore;
+use crate::tools::filesystem::SyntaxVerifier;
+use crate::tools::remote::RemoteWorkspace;
+use crate::tools::shell::EnvPolicy;
+use crate::tools::web::NetworkPolicy;
 use crate::prompt::{
-    builtin, parse_frontmatter, AgentDefinition, ComponentRegistry, ModelPreference,
+    builtin, parse_frontmatter, AgentDefinition, ComponentRegistry, ModelPreference, TemplateVars,
 };
 use crate::session::{
-    AgentLoop, ApprovalSender, SessionConfig, SessionInput, SessionOutput,
-    SessionRegistry,
+    AgentLoop, ApprovalSender, AuditLog, OutputSender, RequestLimiter, SessionConfig, SessionInput,
+    SessionOutput, SessionRegistry,
 };
 
 /// Maximum result size for subagent output (to prevent context bloat)
@@ -50,13 +56,31 @@ pub struct AgentExecutionConfig {
     /// Optional component registry for dynamic agent loading
     pub registry: Option<Arc<ComponentRegistry>>,
     /// Parent session's output channel for forwarding subagent activity
-    pub progress_tx: Option<mpsc::Sender<(String, SessionOutput)>>,
-    /// Parent session ID (used when forwarding events)
-    pub parent_session_id: Option<String>,
+    pub progress_tx: Option<OutputSender>,
     /// Shared session registry for subagent approval routing
     pub session_registry: Option<SessionRegistry>,
     /// Parent's approval channel sender (for subagents to share)
     pub parent_approval_channel: Option<ApprovalSender>,
+    /// Shared request limiter (subagent LLM calls count against the parent's limits)
+    pub request_limiter: Option<Arc<RequestLimiter>>,
+    /// Usage store the subagent records its LLM calls to
+    pub usage_store: Option<Arc<UsageStore>>,
+    /// Parent's audit log the subagent appends to (None = not audited)
+    pub audit_log: Option<Arc<AuditLog>>,
+    /// Environment policy for the subagent's shell commands
+    pub env_policy: Option<EnvPolicy>,
+    /// Organization policy the subagent is held to
+    pub policy: Option<Arc<Policy>>,
+    /// Hosts the subagent's web tools may reach
+    pub network_policy: Option<NetworkPolicy>,
+    /// Syntax check after the subagent's edits
+    pub verifier: Option<Arc<SyntaxVerifier>>,
+    /// Remote workspace the subagent's tools operate on
+    pub remote: Option<Arc<RemoteWorkspace>>,
+    /// Roots the subagent's file tools may use besides the workspace
+    pub additional_roots: Vec<PathBuf>,
+    /// Cancelled when the call that started the subagent is (None = runs to the end)
+    pub cancel: Option<CancellationToken>,
 }
 
 impl AgentExecutionConfig {
@@ -69,9 +93,18 @@ impl AgentExecutionConfig {
             model_tiers: ModelTiers::for_provider("anthropic"),
             registry: None,
             progress_tx: None,
-            parent_session_id: None,
             session_registry: None,
             parent_approval_channel: None,
+            request_limiter: None,
+            usage_store: None,
+            audit_log: None,
+            env_policy: None,
+            policy: None,
+            network_policy: None,
+            verifier: None,
+            remote: None,
+            additional_roots: Vec::new(),
+            cancel: None,
         }
     }
 
@@ -113,7 +146,8 @@ fn get_builtin_prompt(agent_type: &AgentType) -> String {
         AgentType::Bash => builtin::agents::BASH,
         AgentType::Explore => builtin::agents::EXPLORE,
         AgentType::Plan => builtin::agents::PLAN,
-        AgentType::GeneralPurpose => builtin::agents::GENERAL,
+        // Custom agents come from the registry; general-purpose is the fallback
+        AgentType::GeneralPurpose | AgentType::Custom(_) => builtin::agents::GENERAL,
     };
 
     parse_frontmatter(source)
@@ -141,14 +175,7 @@ pub fn get_system_prompt_dynamic(
     registry: Option<&ComponentRegistry>,
 ) -> String {
     // First, try to find the agent in the registry by its display name
-    let agent_name = match agent_type {
-        AgentType::Bash => "Bash",
-        AgentType::Explore => "Explore",
-        AgentType::Plan => "Plan",
-        AgentType::GeneralPurpose => "general-purpose",
-    };
-
-    if let Some(agent_def) = get_agent_from_registry(agent_name, registry) {
+    if let Some(agent_def) = get_agent_from_registry(&agent_type.to_string(), registry) {
         return agent_def.system_prompt.clone();
     }
 
@@ -163,14 +190,7 @@ pub fn get_agent_model_preference(
     agent_type: &AgentType,
     registry: Option<&ComponentRegistry>,
 ) -> ModelPreference {
-    let agent_name = match agent_type {
-        AgentType::Bash => "Bash",
-        AgentType::Explore => "Explore",
-        AgentType::Plan => "Plan",
-        AgentType::GeneralPurpose => "general-purpose",
-    };
-
-    if let Some(agent_def) = get_agent_from_registry(agent_name, registry) {
+    if let Some(agent_def) = get_agent_from_registry(&agent_type.to_string(), registry) {
         return agent_def.metadata.model.clone();
     }
 
@@ -278,12 +298,24 @@ fn get_os_version() -> String {
 }
 
 /// Map an AgentType to the corresponding ToolScope
-fn tool_scope_for(agent_type: &AgentType) -> ToolScope {
+///
+/// Custom agents are limited to their `tools` list (all general-purpose tools if empty).
+fn tool_scope_for(agent_type: &AgentType, registry: Option<&ComponentRegistry>) -> ToolScope {
     match agent_type {
         AgentType::Bash => ToolScope::Bash,
         AgentType::Explore => ToolScope::Explore,
         AgentType::Plan => ToolScope::Plan,
         AgentType::GeneralPurpose => ToolScope::GeneralPurpose,
+        AgentType::Custom(name) => {
+            let restrictions = get_agent_from_registry(name, registry)
+                .map(|agent| agent.tool_restrictions())
+                .unwrap_or_default();
+            if restrictions.is_empty() {
+                ToolScope::GeneralPurpose
+            } else {
+                ToolScope::Custom(restrictions)
+            }
+        }
     }
 }
 
@@ -302,11 +334,15 @@ pub async fn run_subagent(
 ) -> Result<String> {
     let model_str = get_model_for_tier(model, &config.model_tiers);
 
-    // Get system prompt (registry-aware) + environment info
-    let base_prompt = get_system_prompt_dynamic(
+    // Get system prompt (registry-aware, as `/agents show` prints it) + environment info
+    let vars = TemplateVars {
+        additional_roots: config.additional_roots.clone(),
+        ..TemplateVars::gather(&config.workspace)
+    };
+    let base_prompt = vars.substitute(&get_system_prompt_dynamic(
         agent_type,
         config.registry.as_ref().map(|r| r.as_ref()),
-    );
+    ));
     let env_info = build_environment_info(&config.workspace);
     let system_prompt = format!("{}{}", base_prompt, env_info);
 
@@ -324,9 +360,12 @@ pub async fn run_subagent(
         .with_model(model_str)
         .with_system_prompt(system_prompt)
         .with_approval_config(ToolApprovalConfig::default())
-        .with_tool_scope(tool_scope_for(agent_type))
+        .with_tool_scope(tool_scope_for(agent_type, config.registry.as_deref()))
         .with_enable_hooks(false)
-        .with_save_session(false);
+        .with_save_session(false)
+        .with_project_context(false)
+        .with_scratch(false)
+        .with_audit(false);
 
     if let Some(ref key) = config.api_key {
         session_config = session_config.with_api_key(key.clone());
@@ -336,6 +375,33 @@ pub async fn run_subagent(
     if let Some(ref tx) = config.parent_approval_channel {
         session_config = session_config.with_parent_approval_channel(tx.clone());
     }
+    if let Some(ref limiter) = config.request_limiter {
+        session_config = session_config.with_request_limiter(limiter.clone());
+    }
+    if let Some(ref store) = config.usage_store {
+        session_config = session_config.with_usage_store(store.clone());
+    }
+    if let Some(ref log) = config.audit_log {
+        session_config = session_config.with_audit_log(log.clone());
+    }
+    if let Some(ref policy) = config.env_policy {
+        session_config = session_config.with_env_policy(policy.clone());
+    }
+    if let Some(ref policy) = config.policy {
+        session_config = session_config.with_policy(policy.clone());
+    }
+    if let Some(ref network) = config.network_policy {
+        session_config = session_config.with_network_policy(network.clone());
+    }
+    if let Some(ref verifier) = config.verifier {
+        session_config = session_config.with_verifier(verifier.clone());
+    }
+    if let Some(ref remote) = config.remote {
+        session_config = session_config.with_remote(remote.clone());
+    }
+    if !config.additional_roots.is_empty() {
+        session_config = session_config.with_additional_roots(config.additional_roots.clone());
+    }
 
     // Create channels
     let (input_tx, input_rx) = tokio::sync::mpsc::channel::<SessionInput>(32);
@@ -367,29 +433,40 @@ pub async fn run_subagent(
 
     // Collect output until Idle, forwarding activity and approval events to parent
     let mut last_content = String::new();
-    while let Some((_sid, output)) = output_rx.recv().await {
+    while let Some((_sid, output)) = next_output(&mut output_rx, config.cancel.as_ref()).await {
         match &output {
-            SessionOutput::Idle => break,
+            SessionOutput::Idle { .. } => break,
             SessionOutput::AssistantMessage { content, .. } => {
                 last_content = content.clone();
             }
             SessionOutput::Error { message } => {
                 info!("Subagent error: {}", message);
             }
+            SessionOutput::Warning { message } => {
+                info!("Subagent warning: {}", message);
+            }
+            SessionOutput::Refusal { reason, message } => {
+                info!("Subagent response refused ({})", reason);
+                last_content = format!("The response was refused by the provider ({})", reason);
+                if let Some(message) = message {
+                    last_content.push_str(&format!(": {}", message));
+                }
+            }
             // Forward activity + approval events to parent UI
             SessionOutput::ToolStart { .. }
+            | SessionOutput::ToolProgress { .. }
             | SessionOutput::ToolCall { .. }
             | SessionOutput::ToolResult { .. }
             | SessionOutput::Thinking { .. } => {
-                if let (Some(tx), Some(parent_id)) = (&config.progress_tx, &config.parent_session_id) {
-                    // Forward to parent session for display
-                    let _ = tx.try_send((parent_id.clone(), output));
+                if let Some(tx) = &config.progress_tx {
+                    // Progress is only for display, so it never holds up the subagent
+                    tx.emit_ephemeral(output);
                     continue;
                 }
             }
             // Approval events need subagent_id for routing responses back
-            SessionOutput::ToolPending { id, name, arguments, description, .. } => {
-                if let (Some(tx), Some(parent_id)) = (&config.progress_tx, &config.parent_session_id) {
+            SessionOutput::ToolPending { id, name, arguments, description, policy_required, security_warning, generated_files, .. } => {
+                if let Some(tx) = &config.progress_tx {
                     // Forward to parent session, but include subagent_id for approval routing
                     let modified = SessionOutput::ToolPending {
                         id: id.clone(),
@@ -397,20 +474,23 @@ pub async fn run_subagent(
                         arguments: arguments.clone(),
                         description: description.clone(),
                         subagent_id: Some(agent_id.to_string()),
+                        policy_required: *policy_required,
+                        security_warning: security_warning.clone(),
+                        generated_files: generated_files.clone(),
                     };
-                    let _ = tx.try_send((parent_id.clone(), modified));
+                    tx.emit_critical(modified).await;
                     continue;
                 }
             }
             SessionOutput::Question { request_id, questions, .. } => {
-                if let (Some(tx), Some(parent_id)) = (&config.progress_tx, &config.parent_session_id) {
+                if let Some(tx) = &config.progress_tx {
                     // Forward to parent session, but include subagent_id for answer routing
                     let modified = SessionOutput::Question {
                         request_id: request_id.clone(),
                         questions: questions.clone(),
                         subagent_id: Some(agent_id.to_string()),
                     };
-                    let _ = tx.try_send((parent_id.clone(), modified));
+                    tx.emit_critical(modified).await;
                     continue;
                 }
             }
@@ -423,6 +503,13 @@ pub async fn run_subagent(
         reg.write().remove(agent_id);
     }
 
+    // A cancelled subagent stops its turn before the channel closes
+    if config.cancel.as_ref().is_some_and(|cancel| cancel.is_cancelled()) {
+        let _ = input_tx.send(SessionInput::Cancel).await;
+        registry.update_status(agent_id, AgentStatus::Cancelled, None).await;
+        return Err(crate::error::Error::Agent("Subagent cancelled".to_string()));
+    }
+
     // Drop input_tx to signal shutdown
     drop(input_tx);
 
@@ -435,6 +522,21 @@ pub async fn run_subagent(
     Ok(truncated)
 }
 
+/// The subagent's next output, or None once it stops or `cancel` fires
+async fn next_output(
+    output_rx: &mut tokio::sync::mpsc::Receiver<(String, SessionOutput)>,
+    cancel: Option<&CancellationToken>,
+) -> Option<(String, SessionOutput)> {
+    match cancel {
+        Some(cancel) => tokio::select! {
+            biased;
+            _ = cancel.cancelled() => None,
+            output = output_rx.recv() => output,
+        },
+        None => output_rx.recv().await,
+    }
+}
+
 /// Execute an agent in the background
 ///
 /// Spawns the agent loop as a tokio task and writes output to a file.
@@ -732,6 +834,31 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_custom_agent_prompt_and_scope() {
+        let mut registry = ComponentRegistry::with_builtins();
+        registry.register_agent(
+            crate::prompt::parse_agent(
+                "---\nname: docs-writer\ntools: Read, Write\n---\n\nYou write documentation.",
+                None,
+                crate::prompt::Scope::User,
+            )
+            .unwrap(),
+        );
+        let agent_type = AgentType::Custom("docs-writer".to_string());
+
+        let prompt = get_system_prompt_dynamic(&agent_type, Some(&registry));
+        assert!(prompt.contains("You write documentation."));
+
+        match tool_scope_for(&agent_type, Some(&registry)) {
+            ToolScope::Custom(restrictions) => {
+                assert!(restrictions.allows_tool("Write"));
+                assert!(!restrictions.allows_tool("Bash"));
+            }
+            other => panic!("expected custom scope, got {:?}", other),
+        }
+    }
+
     #[test]
     fn test_get_agent_model_preference() {
         // Without registry, should return Inherit
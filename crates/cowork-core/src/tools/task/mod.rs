@@ -9,4 +9,4 @@ pub use agent::{
     TaskOutputTool, TaskTool,
 };
 pub use executor::AgentExecutionConfig;
-pub use todo::TodoWrite;
+pub use todo::{TodoItem, TodoList, TodoPriority, TodoStatus, TodoWrite};
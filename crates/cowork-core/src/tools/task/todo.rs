@@ -10,19 +10,35 @@ use crate::error::ToolError;
 use crate::tools::{BoxFuture, Tool, ToolExecutionContext, ToolOutput};
 
 /// Status of a todo item
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum TodoStatus {
+    #[default]
     Pending,
     InProgress,
     Completed,
 }
 
+/// Priority of a todo item
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TodoPriority {
+    High,
+    #[default]
+    Medium,
+    Low,
+}
+
 /// A todo item
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
 pub struct TodoItem {
+    /// Stable identifier across updates (assigned when the model omits it)
+    #[serde(default)]
+    pub id: String,
     pub content: String,
     pub status: TodoStatus,
+    #[serde(default)]
+    pub priority: TodoPriority,
     #[serde(rename = "activeForm")]
     pub active_form: String,
 }
@@ -67,6 +83,10 @@ impl Tool for TodoWrite {
         crate::prompt::builtin::claude_code::tools::TODOWRITE
     }
 
+    fn compact_description(&self) -> &str {
+        crate::prompt::builtin::claude_code::tools::TODOWRITE_COMPACT
+    }
+
     fn parameters_schema(&self) -> Value {
         json!({
             "type": "object",
@@ -77,6 +97,10 @@ impl Tool for TodoWrite {
                     "items": {
                         "type": "object",
                         "properties": {
+                            "id": {
+                                "type": "string",
+                                "description": "Identifier from the previous list; omit for new tasks"
+                            },
                             "content": {
                                 "type": "string",
                                 "minLength": 1,
@@ -87,6 +111,11 @@ impl Tool for TodoWrite {
                                 "enum": ["pending", "in_progress", "completed"],
                                 "description": "Current status of the task"
                             },
+                            "priority": {
+                                "type": "string",
+                                "enum": ["high", "medium", "low"],
+                                "description": "Priority of the task (default: medium)"
+                            },
                             "activeForm": {
                                 "type": "string",
                                 "minLength": 1,
@@ -131,14 +160,29 @@ impl Tool for TodoWrite {
                 }
             };
 
+            let priority = match item["priority"].as_str() {
+                None => TodoPriority::default(),
+                Some("high") => TodoPriority::High,
+                Some("medium") => TodoPriority::Medium,
+                Some("low") => TodoPriority::Low,
+                Some(other) => {
+                    return Err(ToolError::InvalidParams(format!(
+                        "Invalid priority: {}. Must be high, medium, or low",
+                        other
+                    )))
+                }
+            };
+
             let active_form = item["activeForm"]
                 .as_str()
                 .ok_or_else(|| ToolError::InvalidParams("todo.activeForm is required".into()))?
                 .to_string();
 
             new_todos.push(TodoItem {
+                id: item["id"].as_str().unwrap_or_default().to_string(),
                 content,
                 status,
+                priority,
                 active_form,
             });
         }
@@ -155,9 +199,11 @@ impl Tool for TodoWrite {
             ));
         }
 
-        // Update the shared list
+        // Check against the previous list and replace it under one lock
         {
             let mut list = self.todos.write().await;
+            assign_ids(&list, &mut new_todos)?;
+            check_in_progress_kept(&list, &new_todos)?;
             *list = new_todos.clone();
         }
 
@@ -189,3 +235,113 @@ impl Tool for TodoWrite {
             })
     }
 }
+
+/// Give every item an id: one the model passed, else the id of the previous
+/// item with the same content, else the next free number
+fn assign_ids(previous: &[TodoItem], todos: &mut [TodoItem]) -> Result<(), ToolError> {
+    let mut next = previous
+        .iter()
+        .chain(todos.iter())
+        .filter_map(|t| t.id.parse::<u64>().ok())
+        .max()
+        .unwrap_or(0);
+    let mut seen = std::collections::HashSet::new();
+    for i in 0..todos.len() {
+        if todos[i].id.is_empty() {
+            let reused = previous
+                .iter()
+                .find(|p| p.content == todos[i].content && !todos.iter().any(|t| t.id == p.id))
+                .map(|p| p.id.clone());
+            todos[i].id = reused.unwrap_or_else(|| {
+                next += 1;
+                next.to_string()
+            });
+        }
+        if !seen.insert(todos[i].id.clone()) {
+            return Err(ToolError::InvalidParams(format!("Duplicate todo id: {}", todos[i].id)));
+        }
+    }
+    Ok(())
+}
+
+/// Reject an update that drops an in-progress item without finishing it
+fn check_in_progress_kept(previous: &[TodoItem], todos: &[TodoItem]) -> Result<(), ToolError> {
+    let dropped: Vec<&TodoItem> = previous
+        .iter()
+        .filter(|p| p.status == TodoStatus::InProgress && !todos.iter().any(|t| t.id == p.id))
+        .collect();
+    if dropped.is_empty() {
+        return Ok(());
+    }
+    let names: Vec<String> = dropped.iter().map(|t| format!("\"{}\" (id {})", t.content, t.id)).collect();
+    Err(ToolError::InvalidParams(format!(
+        "The update removes in-progress task {} without completing it. Keep it in the list and mark it completed, or set it back to pending.",
+        names.join(", ")
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx() -> ToolExecutionContext {
+        ToolExecutionContext::standalone("t1", "TodoWrite")
+    }
+
+    fn item(content: &str, status: &str) -> Value {
+        json!({ "content": content, "status": status, "activeForm": content })
+    }
+
+    #[tokio::test]
+    async fn test_ids_are_assigned_and_kept() {
+        let tool = TodoWrite::new();
+        tool.execute(json!({ "todos": [item("Parse", "in_progress"), item("Test", "pending")] }), ctx())
+            .await
+            .unwrap();
+        let ids: Vec<String> = tool.get_list().read().await.iter().map(|t| t.id.clone()).collect();
+        assert_eq!(ids, vec!["1", "2"]);
+
+        // Same content keeps its id; new items get the next one
+        let output = tool
+            .execute(
+                json!({ "todos": [
+                    item("Parse", "completed"),
+                    { "content": "Test", "status": "in_progress", "activeForm": "Testing", "priority": "high" },
+                    item("Document", "pending")
+                ] }),
+                ctx(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(output.content["todos"][1]["id"], "2");
+        assert_eq!(output.content["todos"][1]["priority"], "high");
+        assert_eq!(output.content["todos"][2]["id"], "3");
+        assert_eq!(output.content["todos"][2]["priority"], "medium");
+
+        let err = tool
+            .execute(json!({ "todos": [{ "id": "3", "content": "A", "status": "pending", "activeForm": "A" }, { "id": "3", "content": "B", "status": "pending", "activeForm": "B" }] }), ctx())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Duplicate todo id: 3"));
+    }
+
+    #[tokio::test]
+    async fn test_dropping_in_progress_item_is_rejected() {
+        let tool = TodoWrite::new();
+        tool.execute(json!({ "todos": [item("Parse", "in_progress"), item("Test", "pending")] }), ctx())
+            .await
+            .unwrap();
+
+        let err = tool.execute(json!({ "todos": [item("Test", "in_progress")] }), ctx()).await.unwrap_err();
+        assert!(matches!(err, ToolError::InvalidParams(ref m) if m.contains("\"Parse\" (id 1)")), "{:?}", err);
+        assert_eq!(tool.get_list().read().await.len(), 2);
+
+        // Setting it back to pending is fine, and so is completing it
+        tool.execute(json!({ "todos": [item("Parse", "pending"), item("Test", "in_progress")] }), ctx())
+            .await
+            .unwrap();
+        tool.execute(json!({ "todos": [item("Parse", "pending"), item("Test", "completed")] }), ctx())
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,385 @@
+//! Tool argument validation
+//!
+//! Arguments from the model are checked against the tool's
+//! `parameters_schema` before the tool runs. A few slips are corrected
+//! first where the schema leaves no doubt about what was meant: a numeric
+//! string where a number is expected, or a single value where an array is
+//! expected. Anything still invalid is reported back to the model with the
+//! offending fields and the part of the schema they should follow, so it can
+//! fix the call on the next iteration.
+//!
+//! Arguments the provider couldn't parse arrive as the raw JSON text; the
+//! parse error is reported the same way.
+
+use jsonschema::error::ValidationErrorKind;
+use jsonschema::Validator;
+use serde_json::{Map, Value};
+
+/// One problem with a call's arguments
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArgumentIssue {
+    /// Path of the field, e.g. `limit` or `todos/0/status` (empty = the arguments as a whole)
+    pub field: String,
+    /// What is wrong with it
+    pub message: String,
+}
+
+/// Arguments that don't match the tool's schema
+#[derive(Debug, Clone)]
+pub struct InvalidArguments {
+    pub tool_name: String,
+    pub issues: Vec<ArgumentIssue>,
+    /// The parts of the schema the invalid fields should follow
+    pub expected: Value,
+}
+
+impl InvalidArguments {
+    /// Tool result sent back to the model in place of running the tool
+    pub fn to_tool_result(&self) -> String {
+        let mut out = format!("Invalid arguments for {}; the tool was not run.\n", self.tool_name);
+        for issue in &self.issues {
+            if issue.field.is_empty() {
+                out.push_str(&format!("- {}\n", issue.message));
+            } else {
+                out.push_str(&format!("- {}: {}\n", issue.field, issue.message));
+            }
+        }
+        let expected = serde_json::to_string_pretty(&self.expected).unwrap_or_default();
+        out.push_str(&format!("Expected schema:\n{}\n", expected));
+        out.push_str(&format!("Fix the arguments and call {} again.", self.tool_name));
+        out
+    }
+}
+
+impl std::fmt::Display for InvalidArguments {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let fields: Vec<&str> = self.issues.iter().map(|i| i.field.as_str()).collect();
+        write!(f, "invalid arguments for {} ({})", self.tool_name, fields.join(", "))
+    }
+}
+
+/// Compile a tool's parameters schema
+///
+/// Returns `None` (and the call goes unchecked) when the schema itself is
+/// not valid JSON Schema.
+pub fn compile_schema(tool_name: &str, schema: &Value) -> Option<Validator> {
+    match jsonschema::validator_for(schema) {
+        Ok(validator) => Some(validator),
+        Err(e) => {
+            tracing::warn!("Not validating {} arguments, its schema is invalid: {}", tool_name, e);
+            None
+        }
+    }
+}
+
+/// Replace arguments that arrived as JSON text with the value they encode
+///
+/// Fails with the parse error when the text isn't valid JSON (typically a
+/// call cut off by the output token limit).
+pub fn decode_json_text(tool_name: &str, schema: &Value, arguments: &mut Value) -> Result<(), InvalidArguments> {
+    let Value::String(text) = arguments else {
+        return Ok(());
+    };
+    match serde_json::from_str::<Value>(text) {
+        Ok(decoded) => {
+            *arguments = decoded;
+            Ok(())
+        }
+        Err(e) => Err(InvalidArguments {
+            tool_name: tool_name.to_string(),
+            issues: vec![ArgumentIssue {
+                field: String::new(),
+                message: format!("arguments are not valid JSON ({})", e),
+            }],
+            expected: schema.clone(),
+        }),
+    }
+}
+
+/// Coerce and validate arguments against a compiled schema
+///
+/// `arguments` is left coerced even when validation fails.
+pub fn validate_arguments(
+    tool_name: &str,
+    schema: &Value,
+    validator: &Validator,
+    arguments: &mut Value,
+) -> Result<(), InvalidArguments> {
+    coerce(schema, arguments);
+
+    let issues: Vec<ArgumentIssue> = validator.iter_errors(arguments).flat_map(|e| issues_for(&e)).collect();
+    if issues.is_empty() {
+        return Ok(());
+    }
+
+    Err(InvalidArguments {
+        tool_name: tool_name.to_string(),
+        expected: expected_schema(schema, &issues),
+        issues,
+    })
+}
+
+fn issues_for(error: &jsonschema::ValidationError<'_>) -> Vec<ArgumentIssue> {
+    let path = error.instance_path().as_str().trim_start_matches('/').to_string();
+    let child = |name: &str| if path.is_empty() { name.to_string() } else { format!("{}/{}", path, name) };
+
+    match error.kind() {
+        ValidationErrorKind::Required { property } => {
+            let name = property.as_str().map(str::to_string).unwrap_or_else(|| property.to_string());
+            vec![ArgumentIssue {
+                field: child(&name),
+                message: "missing required field".to_string(),
+            }]
+        }
+        ValidationErrorKind::AdditionalProperties { unexpected } => unexpected
+            .iter()
+            .map(|name| ArgumentIssue {
+                field: child(name),
+                message: "unknown field".to_string(),
+            })
+            .collect(),
+        _ => vec![ArgumentIssue {
+            field: path,
+            message: error.to_string(),
+        }],
+    }
+}
+
+/// The schema of each top-level field with an issue, plus the required list
+///
+/// The whole schema is echoed when the arguments as a whole are wrong or an
+/// unknown field was passed (the model needs to see what is allowed).
+fn expected_schema(schema: &Value, issues: &[ArgumentIssue]) -> Value {
+    let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+        return schema.clone();
+    };
+
+    let mut picked = Map::new();
+    for issue in issues {
+        let top = issue.field.split('/').next().unwrap_or_default();
+        match properties.get(top) {
+            Some(property) => {
+                picked.insert(top.to_string(), property.clone());
+            }
+            None => return schema.clone(),
+        }
+    }
+
+    let mut expected = Map::new();
+    expected.insert("properties".to_string(), Value::Object(picked));
+    if let Some(required) = schema.get("required") {
+        expected.insert("required".to_string(), required.clone());
+    }
+    Value::Object(expected)
+}
+
+/// Correct values whose intent the schema makes unambiguous
+///
+/// - a numeric string where only a number or integer is allowed
+/// - a single value where an array is expected (and the value's own type isn't)
+fn coerce(schema: &Value, value: &mut Value) {
+    let types = schema_types(schema);
+    let allows = |t: &str| types.contains(&t);
+
+    if let Value::String(s) = value
+        && !types.is_empty()
+        && !allows("string")
+    {
+        let s = s.trim();
+        if allows("integer")
+            && let Ok(n) = s.parse::<i64>()
+        {
+            *value = Value::from(n);
+        } else if allows("number")
+            && let Ok(n) = s.parse::<f64>()
+            && let Some(n) = serde_json::Number::from_f64(n)
+        {
+            *value = Value::Number(n);
+        }
+    }
+
+    let own_type = type_name(value);
+    let own_allowed = allows(own_type) || (own_type == "integer" && allows("number"));
+    if allows("array") && !value.is_array() && !value.is_null() && !own_allowed {
+        *value = Value::Array(vec![value.take()]);
+    }
+
+    match value {
+        Value::Object(fields) => {
+            if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+                for (name, field) in fields.iter_mut() {
+                    if let Some(property) = properties.get(name) {
+                        coerce(property, field);
+                    }
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items").filter(|s| s.is_object()) {
+                for item in items {
+                    coerce(item_schema, item);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The `type` keyword as a list (empty when absent)
+fn schema_types(schema: &Value) -> Vec<&str> {
+    match schema.get("type") {
+        Some(Value::String(t)) => vec![t.as_str()],
+        Some(Value::Array(ts)) => ts.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "file_path": { "type": "string" },
+                "limit": { "type": "integer" },
+                "ratio": { "type": "number" },
+                "mode": { "type": "string", "enum": ["fast", "slow"] },
+                "paths": { "type": "array", "items": { "type": "string" } },
+                "ids": { "type": "array", "items": { "type": "integer" } }
+            },
+            "required": ["file_path"],
+            "additionalProperties": false
+        })
+    }
+
+    fn check(mut args: Value) -> (Value, Result<(), InvalidArguments>) {
+        let schema = schema();
+        let validator = compile_schema("Read", &schema).unwrap();
+        let result = validate_arguments("Read", &schema, &validator, &mut args);
+        (args, result)
+    }
+
+    fn fields(result: Result<(), InvalidArguments>) -> Vec<(String, String)> {
+        result
+            .unwrap_err()
+            .issues
+            .into_iter()
+            .map(|i| (i.field, i.message))
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_arguments_pass() {
+        let (args, result) = check(json!({"file_path": "a.rs", "limit": 10, "paths": ["x"]}));
+        assert!(result.is_ok());
+        assert_eq!(args, json!({"file_path": "a.rs", "limit": 10, "paths": ["x"]}));
+    }
+
+    #[test]
+    fn test_missing_required_field() {
+        let (_, result) = check(json!({"limit": 10}));
+        let err = result.unwrap_err();
+        assert_eq!(err.issues[0].field, "file_path");
+        assert_eq!(err.issues[0].message, "missing required field");
+
+        let text = err.to_tool_result();
+        assert!(text.contains("Invalid arguments for Read"));
+        assert!(text.contains("- file_path: missing required field"));
+        assert!(text.contains("\"required\""));
+        // Only the field at fault is echoed
+        assert!(!text.contains("\"ratio\""));
+    }
+
+    #[test]
+    fn test_wrong_type() {
+        let (_, result) = check(json!({"file_path": 42}));
+        let issues = fields(result);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].0, "file_path");
+        assert!(issues[0].1.contains("\"string\""), "{}", issues[0].1);
+    }
+
+    #[test]
+    fn test_enum_violation() {
+        let (_, result) = check(json!({"file_path": "a.rs", "mode": "medium"}));
+        let issues = fields(result);
+        assert_eq!(issues[0].0, "mode");
+    }
+
+    #[test]
+    fn test_unknown_field_echoes_whole_schema() {
+        let (_, result) = check(json!({"file_path": "a.rs", "path": "b.rs"}));
+        let err = result.unwrap_err();
+        assert_eq!(err.issues[0].field, "path");
+        assert_eq!(err.issues[0].message, "unknown field");
+        assert_eq!(err.expected, schema());
+    }
+
+    #[test]
+    fn test_nested_field_path() {
+        let (_, result) = check(json!({"file_path": "a.rs", "ids": [1, "two"]}));
+        let issues = fields(result);
+        assert_eq!(issues[0].0, "ids/1");
+    }
+
+    #[test]
+    fn test_json_text_decoded_or_reported() {
+        let mut args = json!(r#"{"file_path": "a.rs"}"#);
+        assert!(decode_json_text("Read", &schema(), &mut args).is_ok());
+        assert_eq!(args, json!({"file_path": "a.rs"}));
+
+        let mut args = json!(r#"{"file_path": "a.r"#);
+        let err = decode_json_text("Read", &schema(), &mut args).unwrap_err();
+        assert!(err.issues[0].message.starts_with("arguments are not valid JSON (EOF"), "{}", err.issues[0].message);
+        assert!(err.to_tool_result().contains("- arguments are not valid JSON"));
+        assert_eq!(err.expected, schema());
+    }
+
+    #[test]
+    fn test_numeric_strings_coerced() {
+        let (args, result) = check(json!({"file_path": "a.rs", "limit": "20", "ratio": " 0.5", "ids": ["3"]}));
+        assert!(result.is_ok());
+        assert_eq!(args, json!({"file_path": "a.rs", "limit": 20, "ratio": 0.5, "ids": [3]}));
+    }
+
+    #[test]
+    fn test_non_numeric_string_not_coerced() {
+        let (args, result) = check(json!({"file_path": "a.rs", "limit": "ten"}));
+        assert_eq!(args["limit"], "ten");
+        assert_eq!(fields(result)[0].0, "limit");
+    }
+
+    #[test]
+    fn test_single_value_wrapped_in_array() {
+        let (args, result) = check(json!({"file_path": "a.rs", "paths": "src", "ids": "7"}));
+        assert!(result.is_ok());
+        assert_eq!(args, json!({"file_path": "a.rs", "paths": ["src"], "ids": [7]}));
+    }
+
+    #[test]
+    fn test_strings_left_alone_where_strings_allowed() {
+        let (args, result) = check(json!({"file_path": "123"}));
+        assert!(result.is_ok());
+        assert_eq!(args["file_path"], "123");
+    }
+
+    #[test]
+    fn test_invalid_schema_skips_validation() {
+        assert!(compile_schema("Broken", &json!({"type": "no-such-type"})).is_none());
+    }
+}
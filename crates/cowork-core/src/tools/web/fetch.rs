@@ -1,17 +1,41 @@
 //! WebFetch tool - fetch URLs and convert to markdown
+//!
+//! Requests, redirects included, are checked against the tool's network
+//! policy (see [`super::guard`]).
 
 
+use cowork_sandbox::NetworkPolicy;
 use serde_json::{json, Value};
 
+use super::guard::GuardedClient;
 use crate::error::ToolError;
-use crate::tools::{BoxFuture, Tool, ToolExecutionContext, ToolOutput};
+use crate::tools::{Artifact, BoxFuture, Tool, ToolExecutionContext, ToolOutput};
 
 /// Tool for fetching and processing web content
-pub struct WebFetch;
+pub struct WebFetch {
+    network: NetworkPolicy,
+}
 
 impl WebFetch {
     pub fn new() -> Self {
-        Self
+        Self { network: NetworkPolicy::allow_all() }
+    }
+
+    /// Fetch only what `network` allows, replacing the default of any
+    /// public host
+    pub fn with_network_policy(mut self, network: NetworkPolicy) -> Self {
+        self.network = network;
+        self
+    }
+
+    /// Refuse URLs on these hosts (and their subdomains), including redirects to them
+    pub fn with_blocked_hosts<I, S>(mut self, hosts: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.network.block_hosts(hosts);
+        self
     }
 }
 
@@ -31,6 +55,10 @@ impl Tool for WebFetch {
         crate::prompt::builtin::claude_code::tools::WEBFETCH
     }
 
+    fn compact_description(&self) -> &str {
+        crate::prompt::builtin::claude_code::tools::WEBFETCH_COMPACT
+    }
+
     fn parameters_schema(&self) -> Value {
         json!({
             "type": "object",
@@ -49,7 +77,7 @@ impl Tool for WebFetch {
         })
     }
 
-    fn execute(&self, params: Value, _ctx: ToolExecutionContext) -> BoxFuture<'_, Result<ToolOutput, ToolError>> {
+    fn execute(&self, params: Value, ctx: ToolExecutionContext) -> BoxFuture<'_, Result<ToolOutput, ToolError>> {
         Box::pin(async move {
         let url = params["url"]
             .as_str()
@@ -74,21 +102,14 @@ impl Tool for WebFetch {
         }
 
         // Fetch the URL
-        let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(30))
-            .user_agent("Cowork/1.0")
-            .build()
-            .map_err(|e| ToolError::ExecutionFailed(format!("Failed to create client: {}", e)))?;
-
-        let response = client
-            .get(url)
-            .send()
-            .await
-            .map_err(|e| ToolError::ExecutionFailed(format!("Failed to fetch URL: {}", e)))?;
+        let client = GuardedClient::new(self.network.clone(), crate::http::settings(), |builder| {
+            builder.user_agent("Cowork/1.0")
+        })?;
+        let response = ctx.cancellable(client.get(parsed_url)).await??;
+        let status = response.status();
 
         // Check for redirect
         let final_url = response.url().to_string();
-        let status = response.status();
 
         if !status.is_success() {
             return Err(ToolError::ExecutionFailed(format!(
@@ -105,11 +126,13 @@ impl Tool for WebFetch {
             .unwrap_or("")
             .to_string();
 
-        let body = response
-            .text()
-            .await
+        let body = ctx
+            .cancellable(response.text())
+            .await?
             .map_err(|e| ToolError::ExecutionFailed(format!("Failed to read response: {}", e)))?;
 
+        let title = if content_type.contains("text/html") { html_title(&body) } else { None };
+
         // Process content
         let processed = if extract_text && content_type.contains("text/html") {
             extract_text_from_html(&body)
@@ -134,9 +157,24 @@ impl Tool for WebFetch {
             "length": final_content.len(),
             "prompt": prompt,
             "note": "Use the content above to answer the prompt"
-        })))
+        }))
+        .with_artifact(Artifact::url(final_url, title)))
             })
     }
+
+    fn is_idempotent(&self) -> bool {
+        true
+    }
+}
+
+/// The text of a page's `<title>` element, if it has a non-empty one
+fn html_title(html: &str) -> Option<String> {
+    let lower = html.to_ascii_lowercase();
+    let open = lower.find("<title")?;
+    let start = open + lower[open..].find('>')? + 1;
+    let end = start + lower[start..].find("</title>")?;
+    let title = html[start..end].split_whitespace().collect::<Vec<_>>().join(" ");
+    (!title.is_empty()).then_some(title)
 }
 
 /// Simple HTML to text extraction
@@ -0,0 +1,348 @@
+//! Network policy enforcement for the web tools
+//!
+//! A URL is checked by name before it is requested, and every address its
+//! host resolves to is checked as the connection is made: the client's
+//! resolver refuses names that point at refused addresses, so a name that
+//! answers differently after the first look (DNS rebinding) is still caught.
+//! Redirects are followed here rather than by reqwest, so each hop gets the
+//! same checks.
+//!
+//! Through a proxy the proxy resolves names, so each hop's host is also
+//! resolved and checked before the request is sent.
+
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+use cowork_sandbox::{NetworkPolicy, NetworkRule};
+use parking_lot::Mutex;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use url::{Host, Url};
+
+use crate::error::ToolError;
+use crate::http::HttpSettings;
+
+/// Redirects followed before giving up (reqwest's default)
+pub(crate) const MAX_REDIRECTS: usize = 10;
+
+/// HTTP client that refuses what the network policy refuses
+pub(crate) struct GuardedClient {
+    client: reqwest::Client,
+    network: NetworkPolicy,
+    /// Whether http and https requests go through a proxy
+    proxied: (bool, bool),
+    /// Set by the resolver when it refuses a name
+    violation: Arc<Mutex<Option<String>>>,
+}
+
+impl GuardedClient {
+    /// Client from `settings`, with `configure` applied before building
+    pub(crate) fn new(
+        network: NetworkPolicy,
+        settings: &HttpSettings,
+        configure: impl FnOnce(reqwest::ClientBuilder) -> reqwest::ClientBuilder,
+    ) -> Result<Self, ToolError> {
+        let violation = Arc::new(Mutex::new(None));
+        let resolver = PolicyResolver {
+            network: network.clone(),
+            proxies: [&settings.http_proxy, &settings.https_proxy]
+                .into_iter()
+                .flatten()
+                .filter_map(|proxy| proxy_host(proxy))
+                .collect(),
+            violation: violation.clone(),
+        };
+        let builder = settings
+            .client_builder()
+            .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?
+            .redirect(reqwest::redirect::Policy::none())
+            .dns_resolver(resolver);
+        let client = configure(builder)
+            .build()
+            .map_err(|e| ToolError::ExecutionFailed(format!("Failed to create client: {}", e)))?;
+        Ok(Self {
+            client,
+            network,
+            proxied: (settings.http_proxy.is_some(), settings.https_proxy.is_some()),
+            violation,
+        })
+    }
+
+    /// Error if the policy refuses `url`'s host by name, or its address
+    /// when the host is an IP address
+    pub(crate) fn check_url(&self, url: &Url) -> Result<(), ToolError> {
+        let Some(host) = url.host() else {
+            return Ok(());
+        };
+        let rule = match host {
+            Host::Domain(name) => self.network.host_rule(name),
+            Host::Ipv4(ip) => self.literal_rule(&host.to_string(), IpAddr::V4(ip)),
+            Host::Ipv6(ip) => self.literal_rule(&host.to_string(), IpAddr::V6(ip)),
+        };
+        match rule {
+            Some(rule) => Err(violation(&host.to_string(), &rule)),
+            None => Ok(()),
+        }
+    }
+
+    /// Rules for a host written as an IP address: by name, then by address
+    fn literal_rule(&self, host: &str, ip: IpAddr) -> Option<NetworkRule> {
+        self.network.host_rule(host).or_else(|| self.network.address_rule(host, ip))
+    }
+
+    /// GET `url`, following redirects that the policy allows
+    ///
+    /// Returns the final response, which may be an error status.
+    pub(crate) async fn get(&self, url: Url) -> Result<reqwest::Response, ToolError> {
+        let mut url = url;
+        let mut from: Option<Url> = None;
+        for _ in 0..=MAX_REDIRECTS {
+            let response = match self.send_hop(&url).await {
+                Ok(response) => response,
+                Err(e) => {
+                    return Err(match &from {
+                        Some(from) => redirect_refused(from, e),
+                        None => e,
+                    });
+                }
+            };
+            let location = response
+                .status()
+                .is_redirection()
+                .then(|| response.headers().get(reqwest::header::LOCATION))
+                .flatten()
+                .and_then(|v| v.to_str().ok())
+                .and_then(|location| url.join(location).ok());
+            let Some(next) = location else {
+                return Ok(response);
+            };
+            if next.scheme() != "http" && next.scheme() != "https" {
+                return Err(ToolError::InvalidParams(format!("Redirect to unsupported URL: {}", next)));
+            }
+            from = Some(std::mem::replace(&mut url, next));
+        }
+        Err(ToolError::ExecutionFailed(format!(
+            "Failed to fetch URL: more than {} redirects",
+            MAX_REDIRECTS
+        )))
+    }
+
+    /// Check and request one hop
+    async fn send_hop(&self, url: &Url) -> Result<reqwest::Response, ToolError> {
+        self.check_hop(url).await?;
+        self.client.get(url.clone()).send().await.map_err(|e| self.send_error(e))
+    }
+
+    /// Check `url` by name and, when a proxy will resolve it, by address
+    async fn check_hop(&self, url: &Url) -> Result<(), ToolError> {
+        self.check_url(url)?;
+        let proxied = if url.scheme() == "https" { self.proxied.1 } else { self.proxied.0 };
+        if let (true, Some(Host::Domain(name))) = (proxied, url.host()) {
+            let port = url.port_or_known_default().unwrap_or(80);
+            let addrs = tokio::net::lookup_host((name, port))
+                .await
+                .map_err(|e| ToolError::ExecutionFailed(format!("Failed to resolve {}: {}", name, e)))?;
+            for addr in addrs {
+                if let Some(rule) = self.network.address_rule(name, addr.ip()) {
+                    return Err(violation(name, &rule));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The error for a failed send: the resolver's refusal if it made one
+    fn send_error(&self, e: reqwest::Error) -> ToolError {
+        match self.violation.lock().take() {
+            Some(message) => ToolError::PolicyViolation(message),
+            None => ToolError::ExecutionFailed(format!("Failed to fetch URL: {}", e)),
+        }
+    }
+}
+
+/// Resolver that refuses names pointing at addresses the policy refuses
+struct PolicyResolver {
+    network: NetworkPolicy,
+    /// Hosts of the configured proxies, which are resolved unchecked
+    proxies: Vec<String>,
+    violation: Arc<Mutex<Option<String>>>,
+}
+
+impl Resolve for PolicyResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_string();
+        let network = self.network.clone();
+        let exempt = self.proxies.iter().any(|proxy| proxy.eq_ignore_ascii_case(&host));
+        let violation_slot = self.violation.clone();
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+            if !exempt
+                && let Some(rule) = addrs.iter().find_map(|addr| network.address_rule(&host, addr.ip()))
+            {
+                let message = violation_message(&host, &rule);
+                *violation_slot.lock() = Some(message.clone());
+                return Err(message.into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Host of a proxy URL, which may leave out the scheme
+fn proxy_host(proxy: &str) -> Option<String> {
+    Url::parse(proxy)
+        .ok()
+        .filter(|url| url.has_host())
+        .or_else(|| Url::parse(&format!("http://{}", proxy)).ok())
+        .and_then(|url| url.host_str().map(|h| h.trim_matches(['[', ']']).to_string()))
+}
+
+/// What a violation says: the host, the rule, and how to allow it if it can be
+fn violation_message(host: &str, rule: &NetworkRule) -> String {
+    match rule {
+        NetworkRule::InternalAddress { .. } => format!(
+            "{} refused by network policy ({}); list the host in [web] allowed_hosts to allow it",
+            host, rule
+        ),
+        _ => format!("{} refused by network policy ({})", host, rule),
+    }
+}
+
+fn violation(host: &str, rule: &NetworkRule) -> ToolError {
+    ToolError::PolicyViolation(violation_message(host, rule))
+}
+
+/// A hop's refusal, worded as the redirect that led to it
+fn redirect_refused(from: &Url, error: ToolError) -> ToolError {
+    match error {
+        ToolError::PolicyViolation(message) => {
+            ToolError::PolicyViolation(format!("redirect from {} refused: {}", from, message))
+        }
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Settings without the environment's proxies
+    fn settings() -> HttpSettings {
+        HttpSettings::resolve(&crate::config::NetworkConfig::default(), |_| None)
+    }
+
+    /// A local HTTP server answering each path with `respond`'s raw response
+    async fn serve(respond: fn(&str, SocketAddr) -> String) -> (SocketAddr, Arc<AtomicUsize>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                let mut request = Vec::new();
+                let mut buf = [0u8; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    match stream.read(&mut buf).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => request.extend_from_slice(&buf[..n]),
+                    }
+                }
+                let request = String::from_utf8_lossy(&request);
+                let path = request.split_whitespace().nth(1).unwrap_or("/").to_string();
+                let _ = stream.write_all(respond(&path, addr).as_bytes()).await;
+            }
+        });
+        (addr, hits)
+    }
+
+    fn routes(path: &str, addr: SocketAddr) -> String {
+        let redirect = |location: String| {
+            format!("HTTP/1.1 302 Found\r\nLocation: {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", location)
+        };
+        match path {
+            "/hop" => redirect("/page".to_string()),
+            "/metadata" => redirect("http://169.254.169.254/latest/meta-data/".to_string()),
+            "/blocked" => redirect("http://evil.test/".to_string()),
+            "/by-address" => redirect(format!("http://{}/page", addr)),
+            "/loop" => redirect("/loop".to_string()),
+            _ => "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok".to_string(),
+        }
+    }
+
+    fn client(allowed: &[&str], blocked: &[&str]) -> GuardedClient {
+        let mut network = NetworkPolicy::allow_all();
+        network.allowed_hosts = allowed.iter().map(|h| h.to_string()).collect();
+        network.block_hosts(blocked);
+        GuardedClient::new(network, &settings(), |builder| builder.timeout(Duration::from_secs(5))).unwrap()
+    }
+
+    fn url(addr: SocketAddr, path: &str) -> Url {
+        Url::parse(&format!("http://{}{}", addr, path)).unwrap()
+    }
+
+    fn violation_text(result: Result<reqwest::Response, ToolError>) -> String {
+        match result {
+            Err(ToolError::PolicyViolation(message)) => message,
+            Err(other) => panic!("expected a policy violation, got {:?}", other),
+            Ok(response) => panic!("expected a policy violation, got {}", response.status()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_loopback_refused_unless_listed() {
+        let (addr, hits) = serve(routes).await;
+
+        // By address: refused before connecting
+        let message = violation_text(client(&[], &[]).get(url(addr, "/page")).await);
+        assert!(message.contains("loopback address: 127.0.0.1"), "{}", message);
+        assert!(message.contains("[web] allowed_hosts"), "{}", message);
+
+        // By name: the name passes, the address it resolves to doesn't
+        let by_name = Url::parse(&format!("http://localhost:{}/page", addr.port())).unwrap();
+        let message = violation_text(client(&["*"], &[]).get(by_name).await);
+        assert!(message.starts_with("localhost refused"), "{}", message);
+        assert!(message.contains("loopback address"), "{}", message);
+        assert_eq!(hits.load(Ordering::SeqCst), 0);
+
+        // Listed explicitly, it is fetched
+        let response = client(&["*", "127.0.0.1"], &[]).get(url(addr, "/page")).await.unwrap();
+        assert_eq!(response.text().await.unwrap(), "ok");
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_redirects_checked_per_hop() {
+        let (addr, hits) = serve(routes).await;
+        let client = client(&["*", "127.0.0.1"], &["evil.test"]);
+
+        let response = client.get(url(addr, "/hop")).await.unwrap();
+        assert_eq!(response.url().path(), "/page");
+
+        let message = violation_text(client.get(url(addr, "/metadata")).await);
+        assert!(message.starts_with(&format!("redirect from {} refused", url(addr, "/metadata"))), "{}", message);
+        assert!(message.contains("link-local address: 169.254.169.254"), "{}", message);
+
+        let message = violation_text(client.get(url(addr, "/blocked")).await);
+        assert!(message.contains("blocked_hosts: evil.test"), "{}", message);
+
+        // Listing a name allows what it resolves to, not the address itself
+        let by_name = Url::parse(&format!("http://localhost:{}/by-address", addr.port())).unwrap();
+        let message = violation_text(self::client(&["*", "localhost"], &[]).get(by_name).await);
+        assert!(message.contains("refused: 127.0.0.1 refused"), "{}", message);
+        assert!(message.contains("loopback address: 127.0.0.1"), "{}", message);
+
+        let before = hits.load(Ordering::SeqCst);
+        let err = client.get(url(addr, "/loop")).await.unwrap_err();
+        assert!(err.to_string().contains("more than 10 redirects"), "{}", err);
+        assert_eq!(hits.load(Ordering::SeqCst) - before, MAX_REDIRECTS + 1);
+    }
+
+    #[test]
+    fn test_proxy_host() {
+        assert_eq!(proxy_host("http://proxy.corp:8080").as_deref(), Some("proxy.corp"));
+        assert_eq!(proxy_host("10.0.0.8:3128").as_deref(), Some("10.0.0.8"));
+    }
+}
@@ -1,7 +1,9 @@
 //! Web tools for fetching and processing web content
 
 mod fetch;
+mod guard;
 mod search;
 
+pub use cowork_sandbox::{NetworkPolicy, NetworkRule};
 pub use fetch::WebFetch;
 pub use search::{supports_native_search, SearchResult, WebSearch, NATIVE_SEARCH_PROVIDERS};
@@ -4,9 +4,11 @@
 //! For providers with native web search (Anthropic, OpenAI, Groq, xAI, Gemini, Cohere),
 //! native search is preferred. For others, this tool uses SerpAPI.
 
+use cowork_sandbox::NetworkPolicy;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 
+use super::guard::GuardedClient;
 use crate::config::WebSearchConfig;
 use crate::error::ToolError;
 use crate::tools::{BoxFuture, Tool, ToolExecutionContext, ToolOutput};
@@ -41,6 +43,7 @@ pub fn supports_native_search(provider_type: &str) -> bool {
 pub struct WebSearch {
     config: WebSearchConfig,
     provider_type: Option<String>,
+    network: NetworkPolicy,
 }
 
 impl WebSearch {
@@ -48,6 +51,7 @@ impl WebSearch {
         Self {
             config: WebSearchConfig::default(),
             provider_type: None,
+            network: NetworkPolicy::allow_all(),
         }
     }
 
@@ -55,6 +59,7 @@ impl WebSearch {
         Self {
             config,
             provider_type: None,
+            network: NetworkPolicy::allow_all(),
         }
     }
 
@@ -63,6 +68,12 @@ impl WebSearch {
         self
     }
 
+    /// Reach SerpAPI only if `network` allows it
+    pub fn with_network_policy(mut self, network: NetworkPolicy) -> Self {
+        self.network = network;
+        self
+    }
+
     /// Check if this instance should use native search
     pub fn should_use_native(&self) -> bool {
         self.provider_type
@@ -77,10 +88,10 @@ impl WebSearch {
         query: &str,
         allowed_domains: &[String],
         blocked_domains: &[String],
-    ) -> Result<Vec<SearchResult>, String> {
+    ) -> Result<Vec<SearchResult>, ToolError> {
         let api_key = self.config.get_api_key()
             .ok_or_else(|| {
-                "SerpAPI key not configured. Set SERPAPI_API_KEY environment variable or api_key in [web_search] config.".to_string()
+                ToolError::ExecutionFailed("SerpAPI key not configured. Set SERPAPI_API_KEY environment variable or api_key in [web_search] config.".to_string())
             })?;
 
         // Build search query with domain filters
@@ -99,29 +110,29 @@ impl WebSearch {
             search_query = format!("{} -site:{}", search_query, domain);
         }
 
-        let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(30))
-            .build()
-            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
-
-        let response = client
-            .get("https://serpapi.com/search")
-            .query(&[
+        let client = GuardedClient::new(self.network.clone(), crate::http::settings(), |builder| builder)?;
+        let url = url::Url::parse_with_params(
+            "https://serpapi.com/search",
+            [
                 ("q", search_query.as_str()),
                 ("api_key", api_key.as_str()),
                 ("engine", "google"),
                 ("num", &self.config.max_results.to_string()),
-            ])
-            .send()
-            .await
-            .map_err(|e| format!("SerpAPI search failed: {}", e))?;
+            ],
+        )
+        .map_err(|e| ToolError::ExecutionFailed(format!("SerpAPI search failed: {}", e)))?;
+
+        let response = client.get(url).await.map_err(|e| match e {
+            ToolError::ExecutionFailed(message) => ToolError::ExecutionFailed(format!("SerpAPI search failed: {}", message)),
+            other => other,
+        })?;
 
         if !response.status().is_success() {
-            return Err(format!("SerpAPI error: {}", response.status()));
+            return Err(ToolError::ExecutionFailed(format!("SerpAPI error: {}", response.status())));
         }
 
         let body: Value = response.json().await
-            .map_err(|e| format!("Failed to parse SerpAPI response: {}", e))?;
+            .map_err(|e| ToolError::ExecutionFailed(format!("Failed to parse SerpAPI response: {}", e)))?;
 
         let mut results = Vec::new();
         if let Some(organic) = body.get("organic_results").and_then(|o| o.as_array()) {
@@ -153,6 +164,10 @@ impl Tool for WebSearch {
         crate::prompt::builtin::claude_code::tools::WEBSEARCH
     }
 
+    fn compact_description(&self) -> &str {
+        crate::prompt::builtin::claude_code::tools::WEBSEARCH_COMPACT
+    }
+
     fn parameters_schema(&self) -> Value {
         json!({
             "type": "object",
@@ -202,20 +217,20 @@ impl Tool for WebSearch {
                 .unwrap_or_default();
 
             // Use SerpAPI for search
-            match self.search(query, &allowed_domains, &blocked_domains).await {
-                Ok(results) => {
-                    let count = results.len();
-                    Ok(ToolOutput::success(json!({
-                        "query": query,
-                        "results": results,
-                        "count": count,
-                        "provider": "serpapi"
-                    })))
-                }
-                Err(e) => Err(ToolError::ExecutionFailed(e)),
-            }
+            let results = self.search(query, &allowed_domains, &blocked_domains).await?;
+            let count = results.len();
+            Ok(ToolOutput::success(json!({
+                "query": query,
+                "results": results,
+                "count": count,
+                "provider": "serpapi"
+            })))
         })
     }
+
+    fn is_idempotent(&self) -> bool {
+        true
+    }
 }
 
 #[cfg(test)]
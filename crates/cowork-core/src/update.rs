@@ -1,6 +1,8 @@
 //! Shared update types and helpers for CLI and Tauri app self-update.
 //!
-//! Provides staging metadata, SHA-256 verification, and the `[auto-update]` marker check.
+//! Provides release lookup and downloads (through the shared HTTP settings in
+//! `crate::http`, so they work behind a proxy), staging metadata, SHA-256
+//! verification, and the `[auto-update]` marker check.
 
 use std::fs;
 use std::io::Read;
@@ -12,6 +14,63 @@ use sha2::{Digest, Sha256};
 /// Marker string in a GitHub release body that enables auto-update.
 pub const AUTO_UPDATE_MARKER: &str = "[auto-update]";
 
+/// A GitHub release.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Release {
+    /// Tag the release was made from, e.g. `v1.2.3`.
+    pub tag_name: String,
+    /// Release notes.
+    pub body: Option<String>,
+    /// Files attached to the release.
+    #[serde(default)]
+    pub assets: Vec<ReleaseAsset>,
+}
+
+impl Release {
+    /// Version without the leading `v`.
+    pub fn version(&self) -> &str {
+        self.tag_name.trim_start_matches('v')
+    }
+}
+
+/// A file attached to a GitHub release.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReleaseAsset {
+    pub name: String,
+    #[serde(rename = "browser_download_url")]
+    pub download_url: String,
+}
+
+/// Fetch a GitHub repository's releases, newest first.
+///
+/// Blocking: call from a blocking thread.
+pub fn fetch_releases(owner: &str, repo: &str) -> anyhow::Result<Vec<Release>> {
+    let url = format!("https://api.github.com/repos/{}/{}/releases", owner, repo);
+    let releases = crate::http::blocking_client()?
+        .get(&url)
+        .header("Accept", "application/vnd.github+json")
+        .send()?
+        .error_for_status()?
+        .json()?;
+    Ok(releases)
+}
+
+/// Download a file to `dest`.
+///
+/// Blocking: call from a blocking thread. Uses the configured timeout
+/// only to connect, since release binaries can take a while to download.
+pub fn download_file(url: &str, dest: &Path) -> anyhow::Result<()> {
+    let settings = crate::http::settings();
+    let client = settings
+        .blocking_client_builder()?
+        .timeout(None)
+        .connect_timeout(settings.timeout)
+        .build()?;
+    let bytes = client.get(url).send()?.error_for_status()?.bytes()?;
+    fs::write(dest, &bytes)?;
+    Ok(())
+}
+
 /// Metadata for a staged update waiting to be applied on next startup.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StagedUpdate {
@@ -149,6 +208,22 @@ mod tests {
         assert_eq!(deserialized.complete, true);
     }
 
+    #[test]
+    fn test_release_deserialization() {
+        let json = r#"[{
+            "tag_name": "v1.4.0",
+            "body": "Fixes\n[auto-update]",
+            "assets": [{
+                "name": "cowork-cli-x86_64-unknown-linux-gnu.tar.gz",
+                "browser_download_url": "https://github.com/szguoxz/cowork/releases/download/v1.4.0/cowork-cli-x86_64-unknown-linux-gnu.tar.gz"
+            }]
+        }]"#;
+        let releases: Vec<Release> = serde_json::from_str(json).unwrap();
+        assert_eq!(releases[0].version(), "1.4.0");
+        assert!(has_auto_update_marker(releases[0].body.as_deref()));
+        assert!(releases[0].assets[0].download_url.ends_with(".tar.gz"));
+    }
+
     #[test]
     fn test_updates_dir() {
         let dir = updates_dir();
@@ -273,6 +273,35 @@ mod approval_level_tests {
             assert_eq!(parsed, level, "Roundtrip failed for {:?}", level);
         }
     }
+
+    #[test]
+    fn test_policy_clamps_approval_config() {
+        use cowork_core::{Policy, ToolApprovalConfig};
+
+        let policy = Policy::parse("[approval]\nmin_level = \"medium\"\nrequire = [\"Bash\", \"mcp__*\"]").unwrap();
+
+        // Trusting everything doesn't cover tools the policy requires approval for
+        let mut config = ToolApprovalConfig::trust_all();
+        config.apply_policy(&policy);
+        assert!(!config.should_auto_approve("Bash"));
+        assert!(!config.should_auto_approve_with_args("Bash", &serde_json::json!({"command": "ls"})));
+        assert!(!config.should_auto_approve("mcp__github__create_issue"));
+        assert!(config.should_auto_approve("Read"));
+
+        // The level can't be set below the policy's minimum
+        let mut config = ToolApprovalConfig::default();
+        config.apply_policy(&policy);
+        assert_eq!(config.level(), ApprovalLevel::Medium);
+        config.set_level(ApprovalLevel::None);
+        assert_eq!(config.level(), ApprovalLevel::Medium);
+        config.set_level(ApprovalLevel::High);
+        assert_eq!(config.level(), ApprovalLevel::High);
+
+        // An empty policy changes nothing
+        let mut config = ToolApprovalConfig::trust_all();
+        config.apply_policy(&Policy::default());
+        assert!(config.should_auto_approve("Bash"));
+    }
 }
 
 mod parallel_tool_execution_tests {
@@ -387,8 +416,9 @@ mod parallel_tool_execution_tests {
 
 mod question_parsing_tests {
     use cowork_core::tools::interaction::{
-        parse_questions, parse_questions_lenient, validate_questions,
-        format_answer_response, format_answer_response_with_id, Question, QuestionOption,
+        parse_questions, parse_questions_lenient, parse_questions_with_repair, validate_questions,
+        format_answer_response, format_answer_response_with_id, ParseRung, Question, QuestionOption,
+        MAX_LABEL_CHARS, MAX_QUESTIONS,
     };
     use serde_json::json;
     use std::collections::HashMap;
@@ -505,6 +535,63 @@ mod question_parsing_tests {
         assert_eq!(questions[0].options[1].description, "Nope");
     }
 
+    /// Malformed AskUserQuestion arguments models have sent, each with the
+    /// ladder rung expected to parse it (null when none should)
+    const MALFORMED_CORPUS: &str = include_str!("fixtures/questions/malformed.json");
+
+    #[test]
+    fn test_repair_ladder_corpus() {
+        let corpus: Vec<serde_json::Value> = serde_json::from_str(MALFORMED_CORPUS).unwrap();
+        for case in corpus {
+            let name = case["name"].as_str().unwrap();
+            let expected: Option<ParseRung> = serde_json::from_value(case["rung"].clone()).unwrap();
+            let result = parse_questions_with_repair(&case["args"]);
+            match expected {
+                None => assert!(result.is_err(), "{}: expected no rung to parse it, got {:?}", name, result),
+                Some(rung) => {
+                    let parsed = result.unwrap_or_else(|e| panic!("{}: {}", name, e));
+                    assert_eq!(parsed.rung, rung, "{}", name);
+                    let repairs: Vec<String> = serde_json::from_value(case["repairs"].clone()).unwrap();
+                    assert_eq!(parsed.repairs, repairs, "{}", name);
+                    assert!(!parsed.questions.is_empty(), "{}", name);
+                    assert!(parsed.questions.iter().all(|q| q.options.len() >= 2), "{}", name);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_repair_ladder_coerces_values() {
+        let args = json!({
+            "question": "Which targets?",
+            "multiSelect": "yes",
+            "options": ["Linux", 42]
+        });
+        let parsed = parse_questions_with_repair(&args).unwrap();
+        assert_eq!(parsed.rung, ParseRung::Repaired);
+        assert!(parsed.questions[0].multi_select);
+        assert_eq!(parsed.questions[0].options[1].label, "42");
+    }
+
+    #[test]
+    fn test_repair_ladder_truncates_instead_of_rejecting() {
+        let question = json!({
+            "question": "Q",
+            "header": "H",
+            "multiSelect": false,
+            "options": [
+                { "label": "é".repeat(MAX_LABEL_CHARS + 10), "description": "" },
+                { "label": "B", "description": "" }
+            ]
+        });
+        let args = json!({ "questions": vec![question; MAX_QUESTIONS + 2] });
+        let parsed = parse_questions_with_repair(&args).unwrap();
+        assert_eq!(parsed.rung, ParseRung::Lenient);
+        assert_eq!(parsed.questions.len(), MAX_QUESTIONS);
+        assert_eq!(parsed.questions[0].options[0].label.chars().count(), MAX_LABEL_CHARS);
+        assert_eq!(parsed.repairs, vec!["truncated_questions", "truncated_labels"]);
+    }
+
     #[test]
     fn test_validate_questions() {
         let valid = vec![Question {
@@ -2,7 +2,7 @@
 //!
 //! Tests for ConfigManager and Config structures.
 
-use cowork_core::config::{Config, ConfigManager, ProviderConfig, ApprovalConfig, GeneralConfig, WebSearchConfig, PromptSystemConfig};
+use cowork_core::config::{ApiKeyStorage, Config, ConfigManager, ProviderConfig, ApprovalConfig, GeneralConfig, WebSearchConfig, PromptSystemConfig, LimitsConfig, NotificationsConfig, EnvConfig, RemoteTarget, NetworkConfig, WebConfig, CliConfig, SessionsConfig, ToolsConfig, CustomToolProfile, VerifyConfig, ScheduleConfig, TelemetryConfig, SummarizerConfig};
 use tempfile::TempDir;
 use std::fs;
 use std::path::PathBuf;
@@ -185,6 +185,7 @@ timeout_secs = 600
             model: "claude-sonnet".to_string(),
             model_tiers: None,
             base_url: Some("https://custom.api.com".to_string()),
+            ..Default::default()
         });
         providers.insert("openai".to_string(), ProviderConfig::for_provider("openai"));
 
@@ -197,15 +198,114 @@ timeout_secs = 600
                 auto_approve_level: "high".to_string(),
                 show_dialogs: true,
                 timeout_secs: 120,
+                delegate: vec!["Bash:kubectl*".to_string()],
+                delegate_channel: "ops".to_string(),
+                delegate_timeout_secs: 600,
+                trusted_hosts: vec!["*.corp.example".to_string()],
             },
             general: GeneralConfig {
                 workspace_dir: Some(PathBuf::from("/home/user/projects")),
                 log_level: "warn".to_string(),
                 telemetry: false,
                 stream_mode: false,
+                llm_log: Some(PathBuf::from("/tmp/cowork-llm.jsonl")),
+                llm_log_max_bytes: 1024,
+                llm_log_redact: vec!["ghp_[A-Za-z0-9]+".to_string()],
+                watch_files: true,
+                ripgrep_path: Some(PathBuf::from("/usr/local/bin/rg")),
+                validate_mcp_tool_args: false,
+                auto_memory: true,
+                self_review: true,
+                self_review_threshold: 2,
+                strict_tools: false,
+                keep_scratch: true,
+                autosave_secs: 15,
+                isolation: cowork_core::session::Isolation::Worktree,
+                locale: Some("ja".to_string()),
+                kill_orphaned_processes: true,
+                editor: Some("code --goto {file}:{line}:{column}".to_string()),
             },
             web_search: WebSearchConfig::default(),
+            limits: LimitsConfig {
+                max_concurrent_requests: 2,
+                requests_per_minute: 30,
+                max_sessions: 4,
+                repeated_tool_calls: 2,
+                repeated_tool_calls_abort: 5,
+                max_concurrent_tools: 3,
+                idle_suspend_mins: 10,
+                transcript_events: 200,
+            },
+            notifications: NotificationsConfig {
+                min_turn_secs: 5,
+                desktop: true,
+                ..Default::default()
+            },
+            env: EnvConfig {
+                vars: [("DATABASE_URL".to_string(), "postgres://localhost/test".to_string())].into(),
+                allowlist: vec!["PATH".to_string()],
+                ..Default::default()
+            },
             prompt: PromptSystemConfig::default(),
+            remote: Some(RemoteTarget {
+                host: "build.example.com".to_string(),
+                user: Some("dev".to_string()),
+                port: Some(2222),
+                identity_file: Some(PathBuf::from("/home/user/.ssh/id_ed25519")),
+                root: "/srv/app".to_string(),
+                ssh_options: vec!["StrictHostKeyChecking=accept-new".to_string()],
+            }),
+            network: NetworkConfig {
+                proxy: Some("http://proxy.corp:8080".to_string()),
+                ca_bundle: Some(PathBuf::from("/etc/ssl/corp.pem")),
+                timeout_secs: 10,
+            },
+            web: WebConfig {
+                allowed_hosts: vec!["*".to_string(), "wiki.corp.example".to_string()],
+                blocked_hosts: vec!["*.pastebin.com".to_string()],
+            },
+            cli: CliConfig {
+                aliases: [("/c".to_string(), "/commit".to_string())].into(),
+                keybindings: [("cancel".to_string(), "ctrl+g".to_string())].into(),
+                vim_mode: true,
+            },
+            sessions: SessionsConfig {
+                max_count: 200,
+                max_age_days: 90,
+            },
+            tools: ToolsConfig {
+                profile: "docs".to_string(),
+                profiles: [(
+                    "docs".to_string(),
+                    CustomToolProfile {
+                        include: vec!["Read".to_string(), "Write*".to_string()],
+                        exclude: vec!["WritePptx".to_string()],
+                    },
+                )]
+                .into(),
+                compact_descriptions: true,
+            },
+            verify: VerifyConfig {
+                enabled: true,
+                disabled: vec!["javascript".to_string()],
+                commands: [("go".to_string(), "gofmt -e {file}".to_string())].into(),
+                timeout_secs: 5,
+            },
+            schedule: ScheduleConfig {
+                timezone: Some("Europe/Berlin".to_string()),
+            },
+            telemetry: TelemetryConfig {
+                enabled: true,
+                textfile: Some(PathBuf::from("/var/lib/node_exporter/cowork.prom")),
+                listen: Some("127.0.0.1:9464".to_string()),
+            },
+            summarizer: SummarizerConfig {
+                mode: cowork_core::context::CompactionMode::Heuristic,
+                provider: Some("openai".to_string()),
+                model: Some("gpt-5-mini".to_string()),
+                prompt_file: Some(PathBuf::from(".cowork/summarize.md")),
+                keep_turns: 3,
+            },
         };
 
         // Serialize
@@ -221,6 +321,32 @@ timeout_secs = 600
         assert_eq!(restored_anthropic.model, original_anthropic.model);
         assert_eq!(restored.approval.timeout_secs, original.approval.timeout_secs);
         assert_eq!(restored.general.log_level, original.general.log_level);
+        assert_eq!(restored.general.llm_log, original.general.llm_log);
+        assert_eq!(restored.general.llm_log_max_bytes, 1024);
+        assert_eq!(restored.general.llm_log_redact, original.general.llm_log_redact);
+        assert!(restored.general.watch_files);
+        assert!(!restored.general.validate_mcp_tool_args);
+        assert!(restored.general.auto_memory);
+        assert!(restored.general.self_review);
+        assert_eq!(restored.general.self_review_threshold, 2);
+        assert!(!restored.general.strict_tools);
+        assert!(restored.general.keep_scratch);
+        assert_eq!(restored.general.autosave_secs, 15);
+        assert_eq!(restored.general.isolation, cowork_core::session::Isolation::Worktree);
+        assert_eq!(restored.limits, original.limits);
+        assert_eq!(restored.notifications, original.notifications);
+        assert_eq!(restored.env, original.env);
+        assert_eq!(restored.remote, original.remote);
+        assert_eq!(restored.network, original.network);
+        assert_eq!(restored.web, original.web);
+        assert_eq!(restored.cli, original.cli);
+        assert_eq!(restored.sessions, original.sessions);
+        assert_eq!(restored.tools, original.tools);
+        assert_eq!(restored.verify, original.verify);
+        assert_eq!(restored.schedule, original.schedule);
+        assert_eq!(restored.telemetry, original.telemetry);
+        assert_eq!(restored.summarizer, original.summarizer);
+        assert_eq!(restored.remote.unwrap().to_string(), "dev@build.example.com:/srv/app");
     }
 
     #[test]
@@ -238,6 +364,97 @@ default_provider = "openai"
         assert!(config.providers.contains_key("openai"));
         // Other sections should use defaults
         assert!(config.approval.show_dialogs);
+        assert_eq!(config.limits, LimitsConfig::default());
+        assert_eq!(config.notifications, NotificationsConfig::default());
+        assert_eq!(config.env, EnvConfig::default());
+
+        // A partial [limits] section keeps defaults for the rest
+        let config: Config = toml::from_str("[limits]\nmax_sessions = 2\n").unwrap();
+        assert_eq!(config.limits.max_sessions, 2);
+        assert_eq!(config.limits.max_concurrent_requests, LimitsConfig::default().max_concurrent_requests);
+    }
+
+    #[test]
+    fn test_env_project_overrides() {
+        let toml_content = r#"
+[env]
+env_allowlist = ["PATH", "HOME"]
+
+[env.vars]
+DATABASE_URL = "postgres://localhost/dev"
+RUST_LOG = "info"
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert_eq!(config.env.allowlist, vec!["PATH", "HOME"]);
+        assert_eq!(config.env.secret_patterns, EnvConfig::default().secret_patterns);
+
+        // Without project settings the global section applies as-is
+        let workspace = TempDir::new().unwrap();
+        let policy = config.env.policy_for(workspace.path());
+        assert_eq!(policy.vars["DATABASE_URL"], "postgres://localhost/dev");
+
+        let settings_dir = workspace.path().join(".cowork");
+        std::fs::create_dir_all(&settings_dir).unwrap();
+        std::fs::write(
+            settings_dir.join("settings.json"),
+            r#"{"env": {"vars": {"DATABASE_URL": "postgres://localhost/test"}, "allowlist": ["PATH"]}}"#,
+        )
+        .unwrap();
+        let policy = config.env.policy_for(workspace.path());
+        assert_eq!(policy.vars["DATABASE_URL"], "postgres://localhost/test");
+        assert_eq!(policy.vars["RUST_LOG"], "info");
+        assert_eq!(policy.allowlist, vec!["PATH"]);
+    }
+
+    #[test]
+    fn test_tool_profiles() {
+        let toml_content = r#"
+[tools]
+profile = "review"
+
+[tools.profiles.review]
+include = ["Read", "Glob", "Grep", "mcp__github__*"]
+
+[tools.profiles.coding]
+exclude = ["WebFetch"]
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        let review = config.tools.default_profile();
+        assert_eq!(review.name, "review");
+        assert!(review.allows("mcp__github__list_issues"));
+        assert!(!review.allows("Bash"));
+
+        // A custom profile replaces the preset of the same name
+        assert!(config.tools.resolve("coding").unwrap().allows("Bash"));
+        assert!(!config.tools.resolve("coding").unwrap().allows("WebFetch"));
+        assert_eq!(config.tools.resolve("minimal").unwrap().include, ["Read", "Glob", "Grep", "Bash"]);
+        assert_eq!(config.tools.profile_names(), ["minimal", "coding", "research", "full", "review"]);
+
+        let err = config.tools.resolve("tiny").unwrap_err().to_string();
+        assert!(err.contains("Unknown tool profile 'tiny'"), "{}", err);
+
+        // Without a [tools] section every tool is offered
+        assert!(Config::default().tools.default_profile().is_full());
+    }
+
+    #[test]
+    fn test_verify_opt_in() {
+        assert!(Config::default().verify.verifier().is_none());
+
+        let toml_content = r#"
+[verify]
+enabled = true
+disabled = ["python"]
+
+[verify.commands]
+go = "gofmt -e {file}"
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert_eq!(config.verify.timeout_secs, 10);
+        let verifier = config.verify.verifier().unwrap();
+        assert_eq!(verifier.language_for(std::path::Path::new("src/main.rs")).as_deref(), Some("rust"));
+        assert_eq!(verifier.language_for(std::path::Path::new("main.go")).as_deref(), Some("go"));
+        assert!(verifier.language_for(std::path::Path::new("setup.py")).is_none());
     }
 }
 
@@ -435,6 +652,19 @@ telemetry = false
         assert_eq!(openai_key, Some("openai-key".to_string()));
         assert_eq!(anthropic_key, Some("anthropic-key".to_string()));
     }
+
+    #[test]
+    fn test_plaintext_api_key_providers() {
+        let dir = setup_config_dir();
+        let config_path = dir.path().join("config.toml");
+
+        let mut manager = ConfigManager::with_path(config_path).unwrap();
+        assert!(manager.plaintext_api_key_providers().is_empty());
+
+        manager.set_api_key_for("openai", "openai-key".to_string());
+        manager.set_api_key_for("gemini", String::new());
+        assert_eq!(manager.plaintext_api_key_providers(), vec!["openai".to_string()]);
+    }
 }
 
 mod api_key_resolution_tests {
@@ -472,6 +702,39 @@ mod api_key_resolution_tests {
         unsafe { std::env::remove_var("ANTHROPIC_API_KEY") };
     }
 
+    #[test]
+    fn test_keyring_storage_falls_back_to_env_var() {
+        // Without a keychain entry, or without a keychain at all (CI), the
+        // provider's environment variable is used
+        let env_var = cowork_core::provider::catalog::api_key_env("fireworks").unwrap();
+        let provider = ProviderConfig {
+            provider_type: "fireworks".to_string(),
+            api_key_storage: ApiKeyStorage::Keyring,
+            ..Default::default()
+        };
+
+        // SAFETY: Test runs in isolation, no concurrent access to this env var
+        unsafe { std::env::set_var(env_var, "env-key-fallback") };
+        assert_eq!(provider.get_api_key(), Some("env-key-fallback".to_string()));
+        // SAFETY: Test runs in isolation, no concurrent access to this env var
+        unsafe { std::env::remove_var(env_var) };
+    }
+
+    #[test]
+    fn test_api_key_storage_serde() {
+        let config: ProviderConfig = toml::from_str(r#"
+provider_type = "anthropic"
+api_key_storage = "keyring"
+"#).unwrap();
+        assert_eq!(config.api_key_storage, ApiKeyStorage::Keyring);
+        assert!(toml::to_string(&config).unwrap().contains(r#"api_key_storage = "keyring""#));
+
+        // The default isn't written out
+        let plain = ProviderConfig::for_provider("anthropic");
+        assert_eq!(plain.api_key_storage, ApiKeyStorage::Config);
+        assert!(!toml::to_string(&plain).unwrap().contains("api_key_storage"));
+    }
+
     #[test]
     fn test_api_key_fallback_to_catalog_env() {
         let provider = ProviderConfig {
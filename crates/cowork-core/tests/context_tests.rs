@@ -1,9 +1,10 @@
 //! Context management tests
 
 use cowork_core::context::{
-    context_limit, should_compact, usage_stats,
-    ContextGatherer, MemoryTier,
+    cached_overview, context_limit, project_overview, project_overview_within, should_compact, usage_stats,
+    ContextGatherer, IndexQuery, IndexStats, MemoryTier, WorkspaceIndex,
 };
+use std::time::Duration;
 use tempfile::TempDir;
 use std::fs;
 
@@ -213,3 +214,184 @@ mod memory_hierarchy_tests {
         assert!(hierarchy.combined_content.contains("Coding Style"));
     }
 }
+
+mod project_overview_tests {
+    use super::*;
+
+    #[test]
+    fn test_overview_describes_project() {
+        let dir = setup_project_workspace();
+        fs::write(dir.path().join("README.md"), "[![ci](badge.svg)](ci)\n# Demo\n\nA tool that does things.\n").unwrap();
+        fs::create_dir_all(dir.path().join("target/debug")).unwrap();
+
+        let context = ContextGatherer::new(dir.path()).project_overview();
+        assert_eq!(context.test_command.as_deref(), Some("cargo test"));
+        assert_eq!(context.readme_summary.as_deref(), Some("# Demo\n\nA tool that does things."));
+        assert_eq!(context.tree, vec!["src/", "  main.rs", "CLAUDE.md", "Cargo.toml", "README.md"]);
+        // Memory and git are in the system prompt already
+        assert!(context.claude_md.is_none() && context.git_branch.is_none());
+
+        let text = context.render(1000);
+        assert!(text.contains("- Project: Rust workspace (Rust)"));
+        assert!(text.contains("- Tests (guessed): `cargo test`"));
+        assert!(text.contains("A tool that does things."));
+    }
+
+    #[test]
+    fn test_overview_within_budget() {
+        let dir = TempDir::new().unwrap();
+        for i in 0..30 {
+            fs::write(dir.path().join(format!("file{:02}.txt", i)), "").unwrap();
+        }
+        fs::write(dir.path().join("README.md"), "word ".repeat(2000)).unwrap();
+
+        let text = ContextGatherer::new(dir.path()).project_overview().render(100);
+        assert!(text.chars().count() <= 400, "{} chars", text.chars().count());
+        assert!(text.contains("more entries"));
+    }
+
+    #[test]
+    fn test_overview_cached_until_project_changes() {
+        let dir = setup_project_workspace();
+        assert!(cached_overview(dir.path()).is_none());
+
+        let first = project_overview(dir.path(), 1000).unwrap();
+        assert_eq!(cached_overview(dir.path()).as_deref(), Some(first.as_str()));
+
+        // An unchanged project is answered from the cache
+        let cache = dir.path().join(".cowork/cache/project-context.json");
+        let tampered = fs::read_to_string(&cache).unwrap().replace("Rust workspace", "Cached workspace");
+        fs::write(&cache, tampered).unwrap();
+        assert!(project_overview(dir.path(), 1000).unwrap().contains("Cached workspace"));
+
+        // A new top-level entry (or a different budget) regenerates it
+        std::thread::sleep(Duration::from_millis(20));
+        fs::create_dir(dir.path().join("docs")).unwrap();
+        let regenerated = project_overview(dir.path(), 1000).unwrap();
+        assert!(regenerated.contains("Rust workspace") && regenerated.contains("docs/"));
+    }
+
+    #[tokio::test]
+    async fn test_empty_workspace_has_no_overview() {
+        let dir = TempDir::new().unwrap();
+        let overview = project_overview_within(dir.path().to_path_buf(), 1000, Duration::from_secs(2)).await;
+        assert!(overview.is_none());
+    }
+}
+
+// ============================================================================
+// Workspace Index Tests
+// ============================================================================
+
+/// Set a file's modification time, so a refresh sees it as changed
+fn touch(path: &std::path::Path, secs_ago: u64) {
+    let file = fs::File::options().write(true).open(path).unwrap();
+    file.set_modified(std::time::SystemTime::now() - Duration::from_secs(secs_ago)).unwrap();
+}
+
+#[test]
+fn test_index_refreshes_only_changed_files() {
+    let dir = setup_project_workspace();
+    let base = dir.path();
+    fs::write(base.join("src/lib.rs"), "pub struct Parser;\n").unwrap();
+
+    let (index, stats) = WorkspaceIndex::update(base);
+    assert_eq!(stats.added, 4, "{:?}", index.files.keys().collect::<Vec<_>>());
+    assert_eq!(index.files["src/main.rs"].language.as_deref(), Some("rust"));
+    assert_eq!(index.files["src/lib.rs"].symbols[0].name, "Parser");
+
+    // Nothing changed: nothing re-read
+    let (_, stats) = WorkspaceIndex::update(base);
+    assert_eq!(stats, IndexStats { unchanged: 4, ..Default::default() });
+
+    // Only the edited file is read again, and its symbols follow the edit
+    fs::write(base.join("src/lib.rs"), "pub struct Parser;\npub fn parse() {}\n").unwrap();
+    touch(&base.join("src/lib.rs"), 60);
+    let (index, stats) = WorkspaceIndex::update(base);
+    assert_eq!(stats, IndexStats { updated: 1, unchanged: 3, ..Default::default() });
+    assert_eq!(index.files["src/lib.rs"].lines, 2);
+    assert_eq!(index.files["src/lib.rs"].symbols.len(), 2);
+}
+
+#[test]
+fn test_index_evicts_stale_entries() {
+    let dir = setup_project_workspace();
+    let base = dir.path();
+    fs::write(base.join("src/old.rs"), "fn legacy() {}\n").unwrap();
+    let (index, _) = WorkspaceIndex::update(base);
+    assert!(index.files.contains_key("src/old.rs"));
+
+    fs::remove_file(base.join("src/old.rs")).unwrap();
+    // A newly ignored file goes too
+    fs::write(base.join(".gitignore"), "CLAUDE.md\n").unwrap();
+    let (index, stats) = WorkspaceIndex::update(base);
+    assert_eq!(stats.removed, 2);
+    assert_eq!(stats.added, 1); // the .gitignore itself
+    assert!(!index.files.contains_key("src/old.rs"));
+    assert!(!index.files.contains_key("CLAUDE.md"));
+    let query = IndexQuery { symbol: Some("legacy".to_string()), limit: 10, ..Default::default() };
+    assert!(index.search(&query).is_empty());
+
+    // The saved index matches what was returned
+    let saved = WorkspaceIndex::load(base).unwrap();
+    assert_eq!(saved.files, index.files);
+}
+
+#[test]
+fn test_index_respects_gitignore_and_skips_git() {
+    let dir = setup_project_workspace();
+    let base = dir.path();
+    fs::write(base.join(".gitignore"), "/generated\n*.log\n").unwrap();
+    fs::create_dir_all(base.join("generated")).unwrap();
+    fs::write(base.join("generated/schema.rs"), "pub struct Generated;\n").unwrap();
+    fs::write(base.join("server.log"), "started\n").unwrap();
+    fs::create_dir_all(base.join("web")).unwrap();
+    fs::write(base.join("web/.gitignore"), "*.tmp.ts\n").unwrap();
+    fs::write(base.join("web/app.ts"), "export class App {}\n").unwrap();
+    fs::write(base.join("web/scratch.tmp.ts"), "export class Scratch {}\n").unwrap();
+
+    let (index, _) = WorkspaceIndex::update(base);
+    let paths: Vec<&str> = index.files.keys().map(String::as_str).collect();
+    assert!(paths.contains(&"web/app.ts"));
+    assert!(!paths.iter().any(|p| p.starts_with("generated/") || p.starts_with(".git/") || p.starts_with(".cowork/")));
+    assert!(!paths.contains(&"server.log"));
+    assert!(!paths.contains(&"web/scratch.tmp.ts"));
+}
+
+#[test]
+fn test_index_search_ranking() {
+    let dir = setup_project_workspace();
+    let base = dir.path();
+    fs::write(base.join("src/config.rs"), "pub struct ConfigLoader;\npub struct Config;\npub fn load_config() {}\n").unwrap();
+    fs::write(base.join("src/app.py"), "class Config:\n    pass\n").unwrap();
+    let (index, _) = WorkspaceIndex::update(base);
+
+    let search = |symbol: Option<&str>, path: Option<&str>, language: Option<&str>| {
+        index.search(&IndexQuery {
+            symbol: symbol.map(str::to_string),
+            path: path.map(str::to_string),
+            language: language.map(str::to_string),
+            limit: 10,
+        })
+    };
+
+    let names: Vec<(String, String)> = search(Some("Config"), None, None)
+        .into_iter()
+        .map(|h| (h.path, h.symbol.unwrap().name))
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            ("src/app.py".to_string(), "Config".to_string()),
+            ("src/config.rs".to_string(), "Config".to_string()),
+            ("src/config.rs".to_string(), "ConfigLoader".to_string()),
+            ("src/config.rs".to_string(), "load_config".to_string()),
+        ]
+    );
+
+    let rust_only = search(Some("config"), None, Some("Rust"));
+    assert!(rust_only.iter().all(|h| h.path == "src/config.rs"));
+
+    let files: Vec<String> = search(None, Some("config"), None).into_iter().map(|h| h.path).collect();
+    assert_eq!(files, vec!["src/config.rs"]);
+}
@@ -119,6 +119,35 @@ mod read_office_tests {
         assert!(content.contains("Line 2"));
     }
 
+    #[tokio::test]
+    async fn test_xlsx_sheet_params_return_structured_rows() {
+        let dir = setup_test_dir();
+        let tool = ReadFile::new(dir.path().to_path_buf());
+
+        let mut workbook = rust_xlsxwriter::Workbook::new();
+        let sheet = workbook.add_worksheet().set_name("Orders").unwrap();
+        sheet.write_string(0, 0, "Item").unwrap();
+        sheet.write_string(0, 1, "Qty").unwrap();
+        sheet.write_string(1, 0, "Widget").unwrap();
+        sheet.write_number(1, 1, 4).unwrap();
+        workbook.save(dir.path().join("orders.xlsx")).unwrap();
+
+        // Without a sheet: metadata only
+        let output = tool
+            .execute(json!({"file_path": "orders.xlsx"}), test_ctx())
+            .await
+            .unwrap();
+        assert_eq!(output.content["format"], "excel");
+        assert_eq!(output.content["sheets"][0]["name"], "Orders");
+        assert_eq!(output.content["sheets"][0]["dimensions"], "A1:B2");
+
+        let output = tool
+            .execute(json!({"file_path": "orders.xlsx", "sheet": "Orders", "format": "json"}), test_ctx())
+            .await
+            .unwrap();
+        assert_eq!(output.content["rows"], json!([{"Item": "Widget", "Qty": 4.0}]));
+    }
+
     #[tokio::test]
     async fn test_document_extension_dispatches_to_extractor() {
         let dir = setup_test_dir();
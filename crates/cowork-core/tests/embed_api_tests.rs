@@ -0,0 +1,189 @@
+//! Tests for the embedding API (`cowork_core::embed`)
+//!
+//! - A session driven through `Agent`: calls settled by the approver, a
+//!   deferred call approved by the host
+//! - Start-up errors surfaced by `AgentBuilder::start`
+//! - The module's public items and the variants and fields of
+//!   `SessionOutput`, snapshotted so a change to the stable surface is made
+//!   on purpose
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use cowork_core::embed::{AgentBuilder, Approver, BoxFuture, Decision, SessionOutput, ToolRequest};
+use cowork_core::provider::MockProvider;
+use cowork_core::Error;
+use serde_json::json;
+use tempfile::TempDir;
+use tokio::time::timeout;
+
+/// Approves `echo first`, rejects `echo second`, leaves the rest to the host
+struct ByCommand;
+
+impl Approver for ByCommand {
+    fn decide<'a>(&'a self, request: &'a ToolRequest) -> BoxFuture<'a, Decision> {
+        Box::pin(async move {
+            match request.arguments["command"].as_str() {
+                Some("echo first") => Decision::Approve,
+                Some("echo second") => Decision::Reject { reason: Some("not this one".to_string()) },
+                _ => Decision::Defer,
+            }
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_agent_round_trip() {
+    let workspace = TempDir::new().unwrap();
+    let mock = MockProvider::builder()
+        .tool_call("call_1", "Bash", json!({"command": "echo first"}))
+        .tool_call("call_2", "Bash", json!({"command": "echo second"}))
+        .tool_call("call_3", "Bash", json!({"command": "echo third"}))
+        .text("Done")
+        .build();
+    let mut agent = AgentBuilder::new(workspace.path())
+        .with_mock_provider(mock.clone())
+        .with_approver(ByCommand)
+        .with_hooks(false)
+        .start()
+        .await
+        .unwrap();
+    assert!(!agent.settings().hooks());
+
+    agent.send_message("Run the commands").await.unwrap();
+    let mut pending = Vec::new();
+    loop {
+        let output = timeout(Duration::from_secs(10), agent.next_output())
+            .await
+            .expect("timed out waiting for output")
+            .expect("session stopped");
+        match output {
+            SessionOutput::ToolPending { id, .. } => {
+                pending.push(id.clone());
+                agent.approve(&id).await.unwrap();
+            }
+            SessionOutput::Idle { .. } if !pending.is_empty() => break,
+            _ => {}
+        }
+    }
+    // Only the deferred call reached the host
+    assert_eq!(pending, vec!["call_3".to_string()]);
+
+    let results: Vec<String> = mock.requests().last().unwrap().tool_results();
+    assert_eq!(results.len(), 3);
+    assert!(results[0].contains("first"), "{}", results[0]);
+    assert!(results[1].contains("not this one"), "{}", results[1]);
+    assert!(results[2].contains("third"), "{}", results[2]);
+    agent.stop().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_start_rejects_missing_workspace() {
+    let workspace = TempDir::new().unwrap();
+    let result = AgentBuilder::new(workspace.path().join("missing"))
+        .with_mock_provider(MockProvider::builder().text("unused").build())
+        .start()
+        .await;
+    assert!(matches!(result, Err(Error::Workspace(_))));
+}
+
+/// The public items of `src/embed.rs`, one signature per line: public items
+/// and impl blocks, with the variants of public enums and the methods of
+/// public traits (and the derives and `#[non_exhaustive]`, which are part of
+/// the surface too); items behind the `test-util` feature are left out
+fn public_items(source: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut listed_block = false;
+    let mut test_util = false;
+    let mut lines = source.lines().take_while(|line| *line != "#[cfg(test)]");
+    while let Some(raw) = lines.next() {
+        let line = raw.trim();
+        if std::mem::replace(&mut test_util, line == "#[cfg(feature = \"test-util\")]") {
+            continue;
+        }
+        let top_level = !raw.starts_with(' ');
+        let member = listed_block && !top_level && (line.starts_with("fn ") || !line.starts_with(['/', '#']));
+        let attribute = line.starts_with("#[derive") || line == "#[non_exhaustive]";
+        if line.starts_with("pub ") || (top_level && (line.starts_with("impl") || attribute)) || (member && !line.is_empty()) {
+            let mut signature = line.to_string();
+            while !signature.ends_with(['{', ';', ',']) && !signature.ends_with(['}', ']']) {
+                match lines.next() {
+                    Some(next) => signature = format!("{} {}", signature, next.trim()),
+                    None => break,
+                }
+            }
+            let signature = signature.trim_end_matches(['{', ';', ',']).trim_end().to_string();
+            if top_level {
+                listed_block = line.starts_with("pub enum ") || line.starts_with("pub trait ");
+                items.push(signature);
+            } else {
+                items.push(format!("    {}", signature));
+            }
+        } else if top_level && line == "}" {
+            listed_block = false;
+        }
+    }
+    items
+}
+
+/// The variants of `SessionOutput` with their fields and types, one per
+/// line, marked where they are `#[non_exhaustive]`
+fn session_output_variants(source: &str) -> Vec<String> {
+    let start = source.find("pub enum SessionOutput {").expect("SessionOutput is defined in session/types.rs");
+    let mut items = Vec::new();
+    if source[..start].ends_with("#[non_exhaustive]\n") {
+        items.push("#[non_exhaustive]".to_string());
+    }
+    items.push("pub enum SessionOutput".to_string());
+    let mut non_exhaustive = false;
+    let mut variant: Option<String> = None;
+    for raw in source[start..].lines().skip(1).take_while(|line| *line != "}") {
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with("//") || line.starts_with("#[serde") {
+            continue;
+        }
+        if line == "#[non_exhaustive]" {
+            non_exhaustive = true;
+            continue;
+        }
+        if raw.starts_with("        ") {
+            // A field of a variant spread over several lines
+            if let Some(variant) = variant.as_mut().filter(|_| line != "},") {
+                variant.push_str(&format!(" {}", line));
+            }
+            continue;
+        }
+        let marker = if std::mem::take(&mut non_exhaustive) { "#[non_exhaustive] " } else { "" };
+        let declared = format!("    {}{}", marker, line.trim_end_matches(','));
+        if line.ends_with('{') {
+            variant = Some(declared);
+        } else if line == "}," {
+            let done = variant.take().expect("variant closed after it opened");
+            items.push(format!("{} }}", done.trim_end_matches(',')));
+        } else {
+            items.push(declared);
+        }
+    }
+    items
+}
+
+#[test]
+fn test_public_api_snapshot() {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let source = std::fs::read_to_string(root.join("src/embed.rs")).unwrap();
+    let types = std::fs::read_to_string(root.join("src/session/types.rs")).unwrap();
+    let mut items = public_items(&source);
+    items.extend(session_output_variants(&types));
+    let rendered = format!("{}\n", items.join("\n"));
+    let path = root.join("tests/snapshots/embed_api.txt");
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        std::fs::write(&path, rendered).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("missing snapshot {} ({}); run with UPDATE_SNAPSHOTS=1", path.display(), e));
+    assert!(
+        expected == rendered,
+        "the embedding API changed, which breaks semver unless it only adds items; review the difference and run with UPDATE_SNAPSHOTS=1 to accept it"
+    );
+}
@@ -1,9 +1,10 @@
 //! Filesystem tool tests
 //!
-//! Tests for Read, Write, Edit, Glob, and Grep tools.
+//! Tests for Read, Write, Edit, BatchEdit, Glob, Grep, CodebaseSearch and SearchFiles tools,
+//! and the generated-file guard of the editing tools.
 
 use cowork_core::tools::{Tool, ToolExecutionContext};
-use cowork_core::tools::filesystem::{ReadFile, WriteFile, EditFile, GlobFiles, GrepFiles};
+use cowork_core::tools::filesystem::{ReadFile, WriteFile, EditFile, BatchEdit, FinishFile, GlobFiles, GrepFiles, CodebaseSearch, SearchFiles, find_ripgrep};
 use serde_json::json;
 use tempfile::TempDir;
 use std::fs;
@@ -83,6 +84,168 @@ mod read_file_tests {
     }
 }
 
+mod read_symbol_tests {
+    use super::*;
+
+    const RUST_FIXTURE: &str = r#"use std::fmt;
+
+/// A parsed configuration
+#[derive(Debug)]
+pub struct Config {
+    name: String,
+}
+
+impl Config {
+    pub fn new(name: &str) -> Self {
+        Self { name: name.to_string() }
+    }
+
+    /// The name, with braces in a string: "{"
+    pub fn name(&self) -> &str {
+        let _brace = '{';
+        &self.name
+    }
+}
+
+impl fmt::Display for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+pub fn name() -> &'static str {
+    "free function"
+}
+"#;
+
+    const PYTHON_FIXTURE: &str = r#"import os
+
+
+class Loader:
+    """Loads things."""
+
+    def __init__(self, root):
+        self.root = root
+
+    @property
+    def files(self):
+        return [
+            f for f in os.listdir(self.root)
+        ]
+
+
+def load(path,
+         strict=False):
+    if strict:
+        return Loader(path)
+
+    return None
+"#;
+
+    fn setup_fixture() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("config.rs"), RUST_FIXTURE).unwrap();
+        fs::write(dir.path().join("loader.py"), PYTHON_FIXTURE).unwrap();
+        dir
+    }
+
+    async fn read(dir: &TempDir, params: serde_json::Value) -> serde_json::Value {
+        let tool = ReadFile::new(dir.path().to_path_buf());
+        tool.execute(params, test_ctx()).await.unwrap().content
+    }
+
+    /// The source lines returned, without their numbers
+    fn source(output: &serde_json::Value) -> Vec<String> {
+        output["content"]
+            .as_str()
+            .unwrap()
+            .lines()
+            .map(|line| line.split_once('\t').map_or(line, |(_, text)| text).to_string())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_rust_method_symbol() {
+        let dir = setup_fixture();
+        let output = read(&dir, json!({"file_path": "config.rs", "symbol": "Config::name"})).await;
+
+        // The doc comment is part of the item; a couple of lines either side are context
+        assert_eq!(output["symbol_range"], json!([14, 18]));
+        assert_eq!(output["ranges"], json!([[12, 20]]));
+        assert_eq!(output["total_lines"], 29);
+        let lines = source(&output);
+        assert_eq!(lines[2], "    /// The name, with braces in a string: \"{\"");
+        assert_eq!(lines[6], "    }");
+        assert!(!output["content"].as_str().unwrap().contains("free function"));
+    }
+
+    #[tokio::test]
+    async fn test_rust_type_and_free_function_symbols() {
+        let dir = setup_fixture();
+
+        // The struct, not its impls, with its doc comment and attribute
+        let output = read(&dir, json!({"file_path": "config.rs", "symbol": "Config"})).await;
+        assert_eq!(output["symbol_range"], json!([3, 7]));
+
+        let output = read(&dir, json!({"file_path": "config.rs", "symbol": "name"})).await;
+        assert_eq!(output["symbol_range"], json!([14, 18]), "first match in the file wins");
+
+        let output = read(&dir, json!({"file_path": "config.rs", "symbol": "Config::fmt"})).await;
+        assert_eq!(output["symbol_range"], json!([22, 24]));
+    }
+
+    #[tokio::test]
+    async fn test_python_symbols() {
+        let dir = setup_fixture();
+
+        let output = read(&dir, json!({"file_path": "loader.py", "symbol": "Loader.files"})).await;
+        assert_eq!(output["symbol_range"], json!([10, 14]), "includes the decorator and the whole body");
+
+        let output = read(&dir, json!({"file_path": "loader.py", "symbol": "Loader"})).await;
+        assert_eq!(output["symbol_range"], json!([4, 14]));
+
+        // A signature spanning lines, and a blank line inside the body
+        let output = read(&dir, json!({"file_path": "loader.py", "symbol": "load"})).await;
+        assert_eq!(output["symbol_range"], json!([17, 22]));
+        assert_eq!(output["ranges"], json!([[15, 22]]));
+        assert_eq!(source(&output).last().unwrap(), "    return None");
+    }
+
+    #[tokio::test]
+    async fn test_symbol_not_found() {
+        let dir = setup_fixture();
+        let tool = ReadFile::new(dir.path().to_path_buf());
+        let result = tool.execute(json!({"file_path": "config.rs", "symbol": "Config::missing"}), test_ctx()).await;
+        assert!(result.unwrap_err().to_string().contains("Symbol Config::missing not found"));
+    }
+
+    #[tokio::test]
+    async fn test_around_match_merges_blocks() {
+        let dir = setup_fixture();
+        let output = read(
+            &dir,
+            json!({"file_path": "config.rs", "around_match": {"pattern": "pub fn", "context_lines": 2}}),
+        )
+        .await;
+
+        assert_eq!(output["matches"], 3);
+        // The context of the matches on lines 10 and 15 overlaps, so they share a block
+        assert_eq!(output["ranges"], json!([[8, 17], [25, 29]]));
+        assert_eq!(output["total_lines"], 29);
+        let content = output["content"].as_str().unwrap();
+        assert_eq!(content.matches("\n--\n").count(), 1);
+        assert!(content.contains("    10\t    pub fn new(name: &str) -> Self {"));
+    }
+
+    #[tokio::test]
+    async fn test_plain_read_reports_ranges() {
+        let dir = setup_fixture();
+        let output = read(&dir, json!({"file_path": "loader.py", "offset": 3, "limit": 4})).await;
+        assert_eq!(output["ranges"], json!([[4, 7]]));
+        assert_eq!(output["total_lines"], 22);
+    }
+}
+
 mod write_file_tests {
     use super::*;
 
@@ -117,6 +280,123 @@ mod write_file_tests {
         assert!(result.is_ok(), "Should create parent directories");
         assert!(dir.path().join("deep/nested/dir/file.txt").exists());
     }
+
+    #[tokio::test]
+    async fn test_write_keeps_crlf_and_raw_skips_it() {
+        let dir = setup_test_dir();
+        let tool = WriteFile::new(dir.path().to_path_buf());
+        fs::write(dir.path().join("windows.txt"), "one\r\ntwo\r\n").unwrap();
+
+        let output = tool.execute(json!({
+            "file_path": "windows.txt",
+            "content": "one\ntwo\nthree"
+        }), test_ctx()).await.unwrap();
+        assert_eq!(fs::read_to_string(dir.path().join("windows.txt")).unwrap(), "one\r\ntwo\r\nthree\r\n");
+        assert_eq!(output.content["normalized"], json!(["final newline", "line endings"]));
+
+        let output = tool.execute(json!({
+            "file_path": "windows.txt",
+            "content": "one\ntwo",
+            "raw": true
+        }), test_ctx()).await.unwrap();
+        assert_eq!(fs::read_to_string(dir.path().join("windows.txt")).unwrap(), "one\ntwo");
+        assert!(output.content.get("normalized").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_write_follows_editorconfig() {
+        let dir = setup_test_dir();
+        let tool = WriteFile::new(dir.path().to_path_buf());
+        fs::write(
+            dir.path().join(".editorconfig"),
+            "root = true\n[*]\nindent_style = space\nindent_size = 4\n[*.go]\nindent_style = tab\n",
+        ).unwrap();
+
+        tool.execute(json!({
+            "file_path": "main.go",
+            "content": "func main() {\n    if x {\n        y()\n    }\n}\n"
+        }), test_ctx()).await.unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("main.go")).unwrap(),
+            "func main() {\n\tif x {\n\t\ty()\n\t}\n}\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_write_in_appended_chunks() {
+        let dir = setup_test_dir();
+        let tool = WriteFile::new(dir.path().to_path_buf());
+        let finish = FinishFile::new(dir.path().to_path_buf());
+        fs::write(dir.path().join(".editorconfig"), "root = true\n[*]\ninsert_final_newline = true\n").unwrap();
+
+        let first = tool.execute(json!({
+            "file_path": "data.json",
+            "content": "[1,\n  2,",
+            "mode": "append",
+            "expected_prior_bytes": 0
+        }), test_ctx()).await.unwrap();
+        assert_eq!(first.content["total_bytes"], 8);
+        assert_eq!(first.content["created"], true);
+        let second = tool.execute(json!({
+            "file_path": "data.json",
+            "content": " 3]",
+            "mode": "append",
+            "expected_prior_bytes": 8
+        }), test_ctx()).await.unwrap();
+        assert_eq!(second.content["total_bytes"], 11);
+        assert_eq!(fs::read_to_string(dir.path().join("data.json")).unwrap(), "[1,\n  2, 3]");
+
+        let short = finish.execute(json!({"file_path": "data.json", "expected_bytes": 8}), test_ctx()).await;
+        assert!(short.unwrap_err().to_string().contains("is 11 bytes, not the stated 8"));
+        let wrong_hash = finish.execute(json!({
+            "file_path": "data.json",
+            "expected_bytes": 11,
+            "sha256": "00"
+        }), test_ctx()).await;
+        assert!(wrong_hash.unwrap_err().to_string().contains("not the stated 00"));
+
+        let done = finish.execute(json!({"file_path": "data.json", "expected_bytes": 11}), test_ctx()).await.unwrap();
+        assert_eq!(done.content["normalized"], json!(["final newline"]));
+        assert_eq!(fs::read_to_string(dir.path().join("data.json")).unwrap(), "[1,\n  2, 3]\n");
+    }
+
+    #[tokio::test]
+    async fn test_append_fails_if_file_changed_since_last_chunk() {
+        let dir = setup_test_dir();
+        let tool = WriteFile::new(dir.path().to_path_buf());
+        tool.execute(json!({
+            "file_path": "big.txt",
+            "content": "chunk one\n",
+            "mode": "append",
+            "expected_prior_bytes": 0
+        }), test_ctx()).await.unwrap();
+        // The sequence was interrupted and the file rewritten in between
+        fs::write(dir.path().join("big.txt"), "other\n").unwrap();
+
+        let result = tool.execute(json!({
+            "file_path": "big.txt",
+            "content": "chunk two\n",
+            "mode": "append",
+            "expected_prior_bytes": 10
+        }), test_ctx()).await;
+        assert!(result.unwrap_err().to_string().contains("is 6 bytes, not the expected 10"));
+        assert_eq!(fs::read_to_string(dir.path().join("big.txt")).unwrap(), "other\n");
+    }
+
+    #[tokio::test]
+    async fn test_create_exclusive_keeps_existing_file() {
+        let dir = setup_test_dir();
+        let tool = WriteFile::new(dir.path().to_path_buf());
+        fs::write(dir.path().join("keep.txt"), "mine\n").unwrap();
+
+        let result = tool.execute(json!({
+            "file_path": "keep.txt",
+            "content": "theirs\n",
+            "mode": "create_exclusive"
+        }), test_ctx()).await;
+        assert!(result.unwrap_err().to_string().contains("already exists"));
+        assert_eq!(fs::read_to_string(dir.path().join("keep.txt")).unwrap(), "mine\n");
+    }
 }
 
 mod edit_file_tests {
@@ -160,6 +440,395 @@ mod edit_file_tests {
         assert!(!content.contains("println!"), "Should have replaced all println!");
         assert!(content.contains("debug_print!"), "Should contain debug_print!");
     }
+
+    #[tokio::test]
+    async fn test_edit_matches_tab_indentation() {
+        let dir = setup_test_dir();
+        let tool = EditFile::new(dir.path().to_path_buf());
+        fs::write(dir.path().join("main.go"), "func main() {\n\tif x {\n\t\ty()\n\t}\n}\n").unwrap();
+
+        let output = tool.execute(json!({
+            "file_path": "main.go",
+            "old_string": "\t\ty()\n",
+            "new_string": "        y()\n        z()  \n"
+        }), test_ctx()).await.unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("main.go")).unwrap(),
+            "func main() {\n\tif x {\n\t\ty()\n\t\tz()  \n\t}\n}\n"
+        );
+        assert_eq!(output.content["normalized"], json!(["indentation"]));
+    }
+}
+
+mod batch_edit_tests {
+    use super::*;
+    use cowork_core::session::{approval_channel, ApprovalRequest, ApprovalResponse};
+    use cowork_core::tools::filesystem::MAX_BATCH_FILE_BYTES;
+
+    fn auto_approve() -> ToolExecutionContext {
+        ToolExecutionContext::test_auto_approve("batch", "BatchEdit")
+    }
+
+    fn paths(output: &serde_json::Value) -> Vec<&str> {
+        let mut paths: Vec<&str> = output["files"].as_array().unwrap().iter().map(|f| f["path"].as_str().unwrap()).collect();
+        paths.sort();
+        paths
+    }
+
+    #[tokio::test]
+    async fn test_regex_capture_replacement() {
+        let dir = setup_test_dir();
+        let tool = BatchEdit::new(dir.path().to_path_buf());
+
+        let output = tool.execute(json!({
+            "find": r"fn (\w+)\(value: i32\)",
+            "replace": "fn ${1}_i64(value: i64)",
+            "regex": true,
+            "include": "*.rs"
+        }), auto_approve()).await.unwrap();
+
+        assert_eq!(paths(&output.content), ["src/main.rs"]);
+        assert_eq!(output.content["total_matches"], 1);
+        let content = fs::read_to_string(dir.path().join("src/main.rs")).unwrap();
+        assert!(content.contains("fn do_something_i64(value: i64) {"));
+
+        let invalid = tool.execute(json!({ "find": "(", "replace": "", "regex": true }), auto_approve()).await;
+        assert!(invalid.unwrap_err().to_string().contains("Invalid regex"));
+    }
+
+    #[tokio::test]
+    async fn test_exclusions_and_skipped_files() {
+        let dir = setup_test_dir();
+        let base = dir.path();
+        fs::write(base.join("tests/main_test.rs"), "fn test() { do_something(1); }\n").unwrap();
+        fs::create_dir_all(base.join("generated")).unwrap();
+        fs::write(base.join("generated/out.rs"), "do_something(2);\n").unwrap();
+        fs::write(base.join(".gitignore"), "generated/\n").unwrap();
+        fs::write(base.join("src/blob.rs"), b"do_something\0\x01\x02").unwrap();
+        fs::write(base.join("src/huge.rs"), "do_something();\n".repeat(MAX_BATCH_FILE_BYTES as usize / 10)).unwrap();
+
+        let tool = BatchEdit::new(base.to_path_buf());
+        let output = tool.execute(json!({
+            "find": "do_something",
+            "replace": "do_thing",
+            "include": "**/*.rs",
+            "exclude": "tests/**",
+            "dry_run": true
+        }), test_ctx()).await.unwrap();
+
+        assert_eq!(paths(&output.content), ["src/main.rs"]);
+        assert_eq!(output.content["total_matches"], 2);
+        let skipped: Vec<(&str, &str)> = output.content["skipped"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|s| (s["path"].as_str().unwrap(), s["reason"].as_str().unwrap()))
+            .collect();
+        assert_eq!(skipped, [("src/blob.rs", "binary"), ("src/huge.rs", "larger than 1024 KB")]);
+        // A dry run changes nothing
+        assert!(fs::read_to_string(base.join("src/main.rs")).unwrap().contains("do_something"));
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_matches_real_run() {
+        let dir = setup_test_dir();
+        let tool = BatchEdit::new(dir.path().to_path_buf());
+        let spec = json!({ "find": "Hello", "replace": "Goodbye" });
+
+        let mut dry = spec.clone();
+        dry["dry_run"] = json!(true);
+        let preview = tool.execute(dry, test_ctx()).await.unwrap().content;
+        assert_eq!(preview["dry_run"], true);
+        assert!(preview["samples"][0].as_str().unwrap().contains("+ "));
+        assert!(preview["summary"].as_str().unwrap().starts_with("Replace 2 matches in 2 files:"));
+
+        let applied = tool.execute(spec, auto_approve()).await.unwrap().content;
+        assert_eq!(paths(&applied), paths(&preview));
+        assert_eq!(applied["total_matches"], preview["total_matches"]);
+        assert_eq!(applied["failed"], 0);
+        assert_eq!(fs::read_to_string(dir.path().join("README.md")).unwrap(), "# My Project\n\nThis is a test project.\n");
+        assert!(fs::read_to_string(dir.path().join("src/lib.rs")).unwrap().contains("Goodbye, {}!"));
+    }
+
+    #[tokio::test]
+    async fn test_failed_file_does_not_stop_the_batch() {
+        let dir = setup_test_dir();
+        let base = dir.path().to_path_buf();
+        let (tx, mut rx) = approval_channel();
+        let ctx = ToolExecutionContext::new(tx, "batch".to_string(), "BatchEdit".to_string());
+
+        // The batch is approved once, with its summary; meanwhile one file goes away
+        let lib = base.join("src/lib.rs");
+        let approver = tokio::spawn(async move {
+            let Some(ApprovalRequest::ToolApproval { description, response_tx, .. }) = rx.recv().await else {
+                panic!("expected an approval request");
+            };
+            fs::remove_file(&lib).unwrap();
+            let _ = response_tx.send(ApprovalResponse::Approved);
+            assert!(rx.recv().await.is_none(), "asked more than once");
+            description.unwrap()
+        });
+
+        let tool = BatchEdit::new(base.clone());
+        let output = tool.execute(json!({ "find": "Hello", "replace": "Goodbye" }), ctx).await.unwrap().content;
+        drop(tool);
+        let description = approver.await.unwrap();
+        assert!(description.contains("src/lib.rs (1)"), "{}", description);
+
+        assert_eq!(output["failed"], 1);
+        let failed: Vec<&str> = output["files"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|f| f["success"] == false)
+            .map(|f| f["path"].as_str().unwrap())
+            .collect();
+        assert_eq!(failed, ["src/lib.rs"]);
+        assert!(fs::read_to_string(base.join("src/main.rs")).unwrap().contains("Goodbye, world!"));
+    }
+}
+
+mod verify_tests {
+    use super::*;
+    use cowork_core::tools::filesystem::{SyntaxVerifier, VerifyStatus};
+    use std::sync::Arc;
+
+    const UNCLOSED_BRACE_RS: &str = include_str!("fixtures/verify/unclosed_brace.rs");
+    const BAD_INDENT_PY: &str = include_str!("fixtures/verify/bad_indent.py");
+
+    fn verifier() -> Option<Arc<SyntaxVerifier>> {
+        Some(Arc::new(SyntaxVerifier::new()))
+    }
+
+    /// The result's verification, or None (and a note) if the checker isn't installed here
+    fn verification(content: &serde_json::Value) -> Option<serde_json::Value> {
+        let verification = content["verification"].clone();
+        if verification["status"] == json!(VerifyStatus::Unchecked) {
+            eprintln!("Skipping: {}", verification["note"]);
+            return None;
+        }
+        Some(verification)
+    }
+
+    #[tokio::test]
+    async fn test_write_reports_rust_syntax_error() {
+        let dir = setup_test_dir();
+        let tool = WriteFile::new(dir.path().to_path_buf()).with_verifier(verifier());
+
+        let result = tool
+            .execute(json!({"file_path": "src/broken.rs", "content": UNCLOSED_BRACE_RS}), test_ctx())
+            .await
+            .unwrap();
+
+        // The write stands; the problem is reported alongside
+        assert_eq!(fs::read_to_string(dir.path().join("src/broken.rs")).unwrap(), UNCLOSED_BRACE_RS);
+        let Some(verification) = verification(&result.content) else { return };
+        assert_eq!(verification["language"], "rust");
+        assert_eq!(verification["status"], "problems");
+        let problem = &verification["problems"][0];
+        assert!(problem["file"].as_str().unwrap().ends_with("broken.rs"));
+        assert_eq!(problem["line"], 7);
+        assert!(problem["message"].as_str().unwrap().contains("unclosed delimiter"), "{}", problem);
+    }
+
+    #[tokio::test]
+    async fn test_edit_reports_python_syntax_error_and_fix() {
+        let dir = setup_test_dir();
+        fs::write(dir.path().join("greet.py"), BAD_INDENT_PY.replace("  return", "    return")).unwrap();
+        let tool = EditFile::new(dir.path().to_path_buf()).with_verifier(verifier());
+
+        let broken = tool
+            .execute(
+                json!({"file_path": "greet.py", "old_string": "    return message", "new_string": "  return message"}),
+                test_ctx(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(fs::read_to_string(dir.path().join("greet.py")).unwrap(), BAD_INDENT_PY);
+        let Some(verification) = verification(&broken.content) else { return };
+        assert_eq!(verification["language"], "python");
+        assert_eq!(verification["status"], "problems");
+        assert_eq!(verification["problems"][0]["line"], 3);
+        assert!(verification["problems"][0]["message"].as_str().unwrap().contains("indent"));
+
+        let fixed = tool
+            .execute(
+                json!({"file_path": "greet.py", "old_string": "  return message", "new_string": "    return message"}),
+                test_ctx(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(fixed.content["verification"], json!({"language": "python", "status": "ok"}));
+    }
+
+    #[tokio::test]
+    async fn test_missing_checker_leaves_note() {
+        let dir = setup_test_dir();
+        let verifier = SyntaxVerifier::new().with_command("rust", "cowork-no-such-checker {file}");
+        let tool = WriteFile::new(dir.path().to_path_buf()).with_verifier(Some(Arc::new(verifier)));
+
+        let result = tool
+            .execute(json!({"file_path": "src/lib.rs", "content": UNCLOSED_BRACE_RS}), test_ctx())
+            .await
+            .unwrap();
+
+        assert!(dir.path().join("src/lib.rs").exists());
+        let verification = &result.content["verification"];
+        assert_eq!(verification["status"], "unchecked");
+        assert!(verification["note"].as_str().unwrap().contains("cowork-no-such-checker is not installed"));
+    }
+
+    #[tokio::test]
+    async fn test_unchecked_without_verifier_or_language() {
+        let dir = setup_test_dir();
+        let plain = WriteFile::new(dir.path().to_path_buf());
+        let result = plain
+            .execute(json!({"file_path": "broken.rs", "content": UNCLOSED_BRACE_RS}), test_ctx())
+            .await
+            .unwrap();
+        assert!(result.content.get("verification").is_none());
+
+        let verified = WriteFile::new(dir.path().to_path_buf()).with_verifier(verifier());
+        let result = verified
+            .execute(json!({"file_path": "notes.txt", "content": "fn main() {"}), test_ctx())
+            .await
+            .unwrap();
+        assert!(result.content.get("verification").is_none());
+    }
+}
+
+mod generated_file_tests {
+    use super::*;
+    use cowork_core::error::ToolError;
+    use cowork_core::session::{approval_channel, ApprovalRequest, ApprovalResponse};
+    use cowork_core::tools::filesystem::{GeneratedFile, GeneratedFileGuard};
+    use std::sync::Arc;
+
+    const LOCKFILE: &str = "# This file is automatically @generated by Cargo.\nversion = 4\n";
+
+    /// A context for a call the rules already approved, whose only person
+    /// answers with `response` and reports the generated files it was shown
+    fn person(response: ApprovalResponse) -> (ToolExecutionContext, tokio::task::JoinHandle<Vec<GeneratedFile>>) {
+        let (tx, mut rx) = approval_channel();
+        let ctx = ToolExecutionContext::new(tx, "call".to_string(), "Edit".to_string()).approved();
+        let person = tokio::spawn(async move {
+            let Some(ApprovalRequest::ToolApproval { generated, response_tx, .. }) = rx.recv().await else {
+                return Vec::new();
+            };
+            let _ = response_tx.send(response);
+            generated
+        });
+        (ctx, person)
+    }
+
+    #[tokio::test]
+    async fn test_edit_of_lockfile_is_refused() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("Cargo.lock"), LOCKFILE).unwrap();
+        let tool = EditFile::new(dir.path().to_path_buf());
+
+        let err = tool
+            .execute(json!({"file_path": "Cargo.lock", "old_string": "4", "new_string": "3"}), test_ctx())
+            .await
+            .unwrap_err();
+        assert!(matches!(&err, ToolError::GeneratedFile { path, .. } if path == "Cargo.lock"));
+        assert!(err.to_string().contains("cargo update -p <crate>"), "{}", err);
+        assert_eq!(fs::read_to_string(dir.path().join("Cargo.lock")).unwrap(), LOCKFILE);
+    }
+
+    #[tokio::test]
+    async fn test_write_over_marked_file_is_refused() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("kind_string.go"), "// Code generated by stringer; DO NOT EDIT.\npackage main\n").unwrap();
+        let tool = WriteFile::new(dir.path().to_path_buf());
+
+        let err = tool
+            .execute(json!({"file_path": "kind_string.go", "content": "package main\n"}), test_ctx())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ToolError::GeneratedFile { .. }), "{}", err);
+
+        // A new file has no marker to go by
+        tool.execute(json!({"file_path": "fresh.go", "content": "package main\n"}), test_ctx()).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_override_asks_a_person_even_when_approved() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("Cargo.lock"), LOCKFILE).unwrap();
+        let tool = EditFile::new(dir.path().to_path_buf());
+        let params = json!({"file_path": "Cargo.lock", "old_string": "4", "new_string": "3", "allow_generated": true});
+
+        let (ctx, person) = person(ApprovalResponse::Approved);
+        let output = tool.execute(params, ctx).await.unwrap().content;
+        let shown = person.await.unwrap();
+        assert_eq!(shown.len(), 1);
+        assert_eq!(shown[0].path, "Cargo.lock");
+        assert_eq!(output["generated"]["path"], "Cargo.lock");
+        assert!(fs::read_to_string(dir.path().join("Cargo.lock")).unwrap().contains("version = 3"));
+    }
+
+    #[tokio::test]
+    async fn test_rejected_override_leaves_the_file() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("dist")).unwrap();
+        fs::write(dir.path().join("dist/app.js"), "run();\n").unwrap();
+        let tool = WriteFile::new(dir.path().to_path_buf());
+        let params = json!({"file_path": "dist/app.js", "content": "patched();\n", "allow_generated": true});
+
+        let (ctx, person) = person(ApprovalResponse::Rejected { reason: Some("rebuild it".to_string()) });
+        let err = tool.execute(params, ctx).await.unwrap_err();
+        assert!(!person.await.unwrap().is_empty());
+        assert!(matches!(err, ToolError::Rejected(reason) if reason == "rebuild it"));
+        assert_eq!(fs::read_to_string(dir.path().join("dist/app.js")).unwrap(), "run();\n");
+    }
+
+    #[tokio::test]
+    async fn test_batch_edit_skips_generated_files_unless_allowed() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("Cargo.lock"), "name = \"old\"\n").unwrap();
+        fs::write(dir.path().join("Cargo.toml"), "name = \"old\"\n").unwrap();
+        let tool = BatchEdit::new(dir.path().to_path_buf());
+
+        let dry = tool
+            .execute(json!({"find": "old", "replace": "new", "dry_run": true}), test_ctx())
+            .await
+            .unwrap()
+            .content;
+        assert_eq!(dry["files"][0]["path"], "Cargo.toml");
+        assert_eq!(dry["skipped"][0]["path"], "Cargo.lock");
+        assert!(dry["skipped"][0]["reason"].as_str().unwrap().contains("cargo update"));
+
+        let (ctx, person) = person(ApprovalResponse::Approved);
+        let output = tool
+            .execute(json!({"find": "old", "replace": "new", "allow_generated": true}), ctx)
+            .await
+            .unwrap()
+            .content;
+        assert_eq!(person.await.unwrap()[0].path, "Cargo.lock");
+        assert_eq!(output["generated"][0]["path"], "Cargo.lock");
+        assert_eq!(fs::read_to_string(dir.path().join("Cargo.lock")).unwrap(), "name = \"new\"\n");
+    }
+
+    #[tokio::test]
+    async fn test_project_settings_add_patterns_and_exceptions() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join(".cowork")).unwrap();
+        fs::write(
+            dir.path().join(".cowork/settings.json"),
+            r#"{"generated_files": {"patterns": [{"pattern": "schema.ts", "action": "run `npm run codegen` instead"}], "allow": ["yarn.lock"]}}"#,
+        )
+        .unwrap();
+        let guard = Arc::new(GeneratedFileGuard::load(dir.path()));
+        let tool = WriteFile::new(dir.path().to_path_buf()).with_generated_guard(guard);
+
+        let err = tool
+            .execute(json!({"file_path": "schema.ts", "content": "export {}\n"}), test_ctx())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("npm run codegen"), "{}", err);
+        tool.execute(json!({"file_path": "yarn.lock", "content": "# yarn\n"}), test_ctx()).await.unwrap();
+    }
 }
 
 mod glob_tests {
@@ -237,4 +906,419 @@ mod grep_tests {
 
         assert!(result.is_ok(), "Regex grep failed: {:?}", result.err());
     }
+
+    /// A tree large enough to exercise ripgrep's parallel walk, with the
+    /// cases where the backends could disagree: ignored, hidden, binary,
+    /// CRLF, and adjacent matches within context
+    fn setup_large_fixture() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        let base = dir.path();
+        for module in 0..20 {
+            let module_dir = base.join(format!("crates/mod{:02}/src", module));
+            fs::create_dir_all(&module_dir).unwrap();
+            for file in 0..10 {
+                let mut content = String::new();
+                for line in 0..100 {
+                    match (line + file + module) % 17 {
+                        0 => content.push_str(&format!("fn handler_{}() {{ handle(TODO); handle(todo) }}\n", line)),
+                        1 => content.push_str("    // TODO: tidy up\n"),
+                        _ => content.push_str(&format!("    let value_{} = {};\n", line, line * 3)),
+                    }
+                }
+                fs::write(module_dir.join(format!("file{}.rs", file)), content).unwrap();
+            }
+            fs::write(base.join(format!("crates/mod{:02}/README.md", module)), "# TODO list\nnothing here\n").unwrap();
+        }
+        fs::write(base.join(".gitignore"), "target/\n").unwrap();
+        fs::create_dir_all(base.join("target")).unwrap();
+        fs::write(base.join("target/gen.rs"), "fn generated() { TODO }\n").unwrap();
+        fs::create_dir_all(base.join(".config")).unwrap();
+        fs::write(base.join(".config/hidden.rs"), "// TODO hidden\n").unwrap();
+        fs::write(base.join("blob.dat"), b"TODO\0\x01\x02 binary").unwrap();
+        fs::write(base.join("windows.rs"), "fn a() {}\r\n// TODO crlf\r\nfn b() {}\r\n").unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_grep_ripgrep_parity() {
+        let Some(rg) = find_ripgrep() else {
+            eprintln!("rg not on PATH, skipping ripgrep parity test");
+            return;
+        };
+        let dir = setup_large_fixture();
+        let native = GrepFiles::new(dir.path().to_path_buf()).with_ripgrep(None);
+        let ripgrep = GrepFiles::new(dir.path().to_path_buf()).with_ripgrep(Some(rg));
+
+        let queries = [
+            json!({ "pattern": "TODO" }),
+            json!({ "pattern": "todo", "-i": true, "output_mode": "count" }),
+            json!({ "pattern": "handle\\(\\w+\\)", "output_mode": "count" }),
+            json!({ "pattern": "TODO", "output_mode": "content", "head_limit": 50, "offset": 10 }),
+            json!({ "pattern": "^fn handler_\\d+", "output_mode": "content", "-C": 2 }),
+            json!({ "pattern": "TODO", "output_mode": "content", "-A": 20, "-B": 3 }),
+            json!({ "pattern": "crlf$", "output_mode": "content" }),
+            json!({ "pattern": "TODO", "glob": "*.md" }),
+            json!({ "pattern": "TODO", "glob": "**/*.md" }),
+            json!({ "pattern": "TODO", "type": "rust", "path": "crates/mod03" }),
+            json!({ "pattern": "TODO", "path": "crates/mod05/src/file3.rs", "output_mode": "content" }),
+            json!({ "pattern": "tidy up\\n.*let", "multiline": true, "output_mode": "content", "-B": 1 }),
+            json!({ "pattern": "tidy up\\n.*let", "multiline": true, "output_mode": "count" }),
+        ];
+        for query in queries {
+            let expected = native.execute(query.clone(), test_ctx()).await.unwrap();
+            let actual = ripgrep.execute(query.clone(), test_ctx()).await.unwrap();
+            assert_eq!(expected.metadata["backend"], "native");
+            assert_eq!(actual.metadata["backend"], "ripgrep", "{} fell back", query);
+            assert_eq!(actual.content, expected.content, "results differ for {}", query);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_grep_falls_back_without_ripgrep() {
+        let dir = setup_test_dir();
+        let tool = GrepFiles::new(dir.path().to_path_buf())
+            .with_ripgrep(Some(dir.path().join("missing-rg")));
+
+        let output = tool.execute(json!({ "pattern": "fn main" }), test_ctx()).await.unwrap();
+        assert_eq!(output.metadata["backend"], "native");
+        assert_eq!(output.content["files"], json!(["src/main.rs"]));
+    }
+
+    #[tokio::test]
+    async fn test_grep_invalid_regex_same_error() {
+        let dir = setup_test_dir();
+        for binary in [None, find_ripgrep()] {
+            let tool = GrepFiles::new(dir.path().to_path_buf()).with_ripgrep(binary);
+            let err = tool.execute(json!({ "pattern": "fn (" }), test_ctx()).await.unwrap_err();
+            assert!(
+                matches!(&err, cowork_core::error::ToolError::InvalidParams(msg) if msg.starts_with("Invalid regex")),
+                "unexpected error: {:?}",
+                err
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_grep_type_filter() {
+        let dir = setup_test_dir();
+        fs::write(dir.path().join("src/app.tsx"), "const main = 1;\n").unwrap();
+        let tool = GrepFiles::new(dir.path().to_path_buf()).with_ripgrep(None);
+
+        let output = tool.execute(json!({ "pattern": "main", "type": "ts" }), test_ctx()).await.unwrap();
+        assert_eq!(output.content["files"], json!(["src/app.tsx"]));
+    }
+}
+
+mod multi_root_tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Sibling `backend` and `frontend` roots, with a file outside both
+    fn setup_roots() -> (TempDir, PathBuf, PathBuf) {
+        let dir = TempDir::new().unwrap();
+        let backend = dir.path().join("backend");
+        let frontend = dir.path().join("frontend");
+        fs::create_dir_all(backend.join("src")).unwrap();
+        fs::create_dir_all(frontend.join("src")).unwrap();
+        fs::write(backend.join("src/api.rs"), "pub fn get_user() {}\n").unwrap();
+        fs::write(frontend.join("src/app.ts"), "fetch('/api/get_user')\n").unwrap();
+        fs::write(dir.path().join("secret.txt"), "get_user\n").unwrap();
+        let backend = dunce::canonicalize(backend).unwrap();
+        let frontend = dunce::canonicalize(frontend).unwrap();
+        (dir, backend, frontend)
+    }
+
+    #[tokio::test]
+    async fn test_glob_selects_roots() {
+        let (_dir, backend, frontend) = setup_roots();
+        let tool = GlobFiles::new(backend).with_additional_roots(&[frontend]);
+
+        let output = tool.execute(json!({ "pattern": "**/*" }), test_ctx()).await.unwrap();
+        assert_eq!(output.content["files"], json!(["src/api.rs"]));
+        let output = tool.execute(json!({ "pattern": "**/*", "root": "frontend" }), test_ctx()).await.unwrap();
+        assert_eq!(output.content["files"], json!(["frontend/src/app.ts"]));
+        let output = tool.execute(json!({ "pattern": "**/*.*", "root": "all" }), test_ctx()).await.unwrap();
+        let mut files: Vec<String> = serde_json::from_value(output.content["files"].clone()).unwrap();
+        files.sort();
+        assert_eq!(files, vec!["backend/src/api.rs", "frontend/src/app.ts"]);
+
+        let err = tool.execute(json!({ "pattern": "**/*", "root": "docs" }), test_ctx()).await.unwrap_err();
+        assert!(err.to_string().contains("backend (0), frontend (1)"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_grep_searches_every_root() {
+        let (_dir, backend, frontend) = setup_roots();
+        let tool = GrepFiles::new(backend).with_additional_roots(&[frontend]).with_ripgrep(None);
+
+        let output = tool.execute(json!({ "pattern": "get_user", "root": "all" }), test_ctx()).await.unwrap();
+        let mut files: Vec<String> = serde_json::from_value(output.content["files"].clone()).unwrap();
+        files.sort();
+        assert_eq!(files, vec!["backend/src/api.rs", "frontend/src/app.ts"]);
+        let output = tool.execute(json!({ "pattern": "get_user", "root": 1 }), test_ctx()).await.unwrap();
+        assert_eq!(output.content["files"], json!(["frontend/src/app.ts"]));
+    }
+
+    #[tokio::test]
+    async fn test_traversal_between_roots() {
+        let (dir, backend, frontend) = setup_roots();
+        let read = ReadFile::new(backend.clone()).with_additional_roots(std::slice::from_ref(&frontend));
+
+        // A result path from another root, an absolute path in it, and `..` into it all read
+        for path in [
+            "frontend/src/app.ts".to_string(),
+            frontend.join("src/app.ts").display().to_string(),
+            "../frontend/src/app.ts".to_string(),
+        ] {
+            let output = read.execute(json!({ "file_path": path }), test_ctx()).await;
+            assert!(output.is_ok(), "{}: {:?}", path, output.err());
+        }
+
+        let write = WriteFile::new(backend.clone()).with_additional_roots(std::slice::from_ref(&frontend));
+        write
+            .execute(json!({ "file_path": "frontend/src/new.ts", "content": "export {}\n" }), test_ctx())
+            .await
+            .unwrap();
+        assert!(frontend.join("src/new.ts").exists());
+
+        // Outside every root is still refused
+        for path in ["../secret.txt".to_string(), dir.path().join("secret.txt").display().to_string()] {
+            let err = read.execute(json!({ "file_path": path }), test_ctx()).await.unwrap_err();
+            assert!(matches!(err, cowork_core::error::ToolError::PermissionDenied(_)), "{}: {:?}", path, err);
+        }
+        let err = write
+            .execute(json!({ "file_path": "../evil.txt", "content": "x" }), test_ctx())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, cowork_core::error::ToolError::PermissionDenied(_)), "{:?}", err);
+
+        // Without the extra root, the sibling is outside the workspace
+        let single = ReadFile::new(backend);
+        assert!(single.execute(json!({ "file_path": "../frontend/src/app.ts" }), test_ctx()).await.is_err());
+    }
+}
+
+mod codebase_search_tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_finds_symbols_and_sees_new_files() {
+        let dir = setup_test_dir();
+        let tool = CodebaseSearch::new(dir.path().to_path_buf());
+
+        let output = tool.execute(json!({ "query": "do_something" }), test_ctx()).await.unwrap();
+        assert_eq!(output.content["count"], 1);
+        assert_eq!(output.content["results"][0]["path"], "src/main.rs");
+        assert_eq!(output.content["results"][0]["symbol"]["line"], 7);
+        assert!(dir.path().join(".cowork/cache/index.json").exists());
+
+        // Files added after the first call are picked up on the next one
+        fs::write(dir.path().join("src/extra.rs"), "pub fn do_something_else() {}\n").unwrap();
+        let output = tool.execute(json!({ "query": "do_something" }), test_ctx()).await.unwrap();
+        assert_eq!(output.content["count"], 2);
+        assert_eq!(output.content["results"][0]["path"], "src/main.rs");
+        assert_eq!(output.content["results"][1]["symbol"]["name"], "do_something_else");
+    }
+
+    #[tokio::test]
+    async fn test_requires_a_filter() {
+        let dir = setup_test_dir();
+        let tool = CodebaseSearch::new(dir.path().to_path_buf());
+
+        let err = tool.execute(json!({ "limit": 5 }), test_ctx()).await.unwrap_err();
+        assert!(matches!(err, cowork_core::error::ToolError::InvalidParams(_)));
+    }
+}
+
+mod search_files_tests {
+    use super::*;
+
+    /// A tree where "parser" appears as an exact name, in a name, as a
+    /// directory and in contents, plus a unicode name and skipped directories
+    fn setup_search_tree() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        let base = dir.path();
+        fs::create_dir_all(base.join("src/parser")).unwrap();
+        fs::create_dir_all(base.join("docs")).unwrap();
+        fs::create_dir_all(base.join("node_modules/parser")).unwrap();
+        fs::create_dir_all(base.join(".git")).unwrap();
+        fs::write(base.join("src/parser.rs"), "pub struct Parser;\nimpl Parser {}\n").unwrap();
+        fs::write(base.join("src/json_parser.rs"), "fn parse() {}\n").unwrap();
+        fs::write(base.join("src/parser/mod.rs"), "mod lexer;\n").unwrap();
+        fs::write(base.join("src/main.rs"), "use parser::Parser;\n// the parser runs first\n").unwrap();
+        fs::write(base.join("docs/notes.md"), "The parser is documented here.\n").unwrap();
+        fs::write(base.join("docs/Überblick.md"), "Überblick über den Parser\n").unwrap();
+        fs::write(base.join("node_modules/parser/index.js"), "parser").unwrap();
+        fs::write(base.join(".git/parser"), "parser").unwrap();
+        dir
+    }
+
+    fn paths(output: &serde_json::Value) -> Vec<&str> {
+        output["results"].as_array().unwrap().iter().map(|r| r["path"].as_str().unwrap()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_scores_names_then_paths_then_content() {
+        let dir = setup_search_tree();
+        let tool = SearchFiles::new(dir.path().to_path_buf());
+
+        let output = tool.execute(json!({ "query": "parser" }), test_ctx()).await.unwrap().content;
+        assert_eq!(
+            paths(&output),
+            ["src/parser.rs", "src/json_parser.rs", "src/parser/mod.rs", "src/main.rs", "docs/notes.md", "docs/Überblick.md"]
+        );
+        assert_eq!(output["total"], 6);
+        assert!(output.get("next_cursor").is_none());
+
+        let exact = &output["results"][0];
+        assert_eq!(exact["matches"], json!(["name", "content"]));
+        assert_eq!(exact["line_number"], 1);
+        assert_eq!(exact["line"], "pub struct Parser;");
+        assert_eq!(exact["content_matches"], 2);
+        assert_eq!(exact["size"], 34);
+        assert!(exact["modified"].as_str().unwrap().ends_with('Z'));
+        assert_eq!(output["results"][2]["matches"], json!(["path"]));
+        // Two matching lines outscore one
+        assert_eq!(output["results"][3]["content_matches"], 2);
+        assert!(output["results"][3]["score"].as_u64() > output["results"][4]["score"].as_u64());
+    }
+
+    #[tokio::test]
+    async fn test_ties_are_in_path_order() {
+        let dir = TempDir::new().unwrap();
+        for name in ["b.txt", "a.txt", "c.txt"] {
+            fs::write(dir.path().join(name), "needle\n").unwrap();
+        }
+        let tool = SearchFiles::new(dir.path().to_path_buf());
+
+        let output = tool.execute(json!({ "query": "needle" }), test_ctx()).await.unwrap().content;
+        assert_eq!(paths(&output), ["a.txt", "b.txt", "c.txt"]);
+        let scores: Vec<_> = output["results"].as_array().unwrap().iter().map(|r| r["score"].clone()).collect();
+        assert!(scores.iter().all(|score| *score == scores[0]));
+    }
+
+    #[tokio::test]
+    async fn test_unicode_names_and_fuzzy_matches() {
+        let dir = setup_search_tree();
+        let tool = SearchFiles::new(dir.path().to_path_buf());
+
+        let output = tool.execute(json!({ "query": "überblick" }), test_ctx()).await.unwrap().content;
+        assert_eq!(paths(&output)[0], "docs/Überblick.md");
+        assert_eq!(output["results"][0]["matches"], json!(["name", "content"]));
+
+        let output = tool.execute(json!({ "query": "jsnprs" }), test_ctx()).await.unwrap().content;
+        assert_eq!(paths(&output), ["src/json_parser.rs"]);
+        assert_eq!(output["results"][0]["matches"], json!(["name"]));
+    }
+
+    #[tokio::test]
+    async fn test_cursor_pages_through_results() {
+        let dir = setup_search_tree();
+        let tool = SearchFiles::new(dir.path().to_path_buf());
+
+        let first = tool.execute(json!({ "query": "parser", "limit": 4 }), test_ctx()).await.unwrap().content;
+        assert_eq!(paths(&first).len(), 4);
+        assert_eq!(first["next_cursor"], "4");
+
+        let second = tool
+            .execute(json!({ "query": "parser", "limit": 4, "cursor": first["next_cursor"] }), test_ctx())
+            .await
+            .unwrap()
+            .content;
+        assert_eq!(paths(&second), ["docs/notes.md", "docs/Überblick.md"]);
+        assert_eq!(second["total"], 6);
+        assert!(second.get("next_cursor").is_none());
+
+        let err = tool.execute(json!({ "query": "parser", "cursor": "page two" }), test_ctx()).await.unwrap_err();
+        assert!(matches!(err, cowork_core::error::ToolError::InvalidParams(_)));
+    }
+
+    #[tokio::test]
+    async fn test_older_params_and_regex() {
+        let dir = setup_search_tree();
+        let tool = SearchFiles::new(dir.path().to_path_buf());
+
+        // `pattern` looks at names and paths only, `content` at contents only
+        let output = tool.execute(json!({ "pattern": "parser" }), test_ctx()).await.unwrap().content;
+        assert_eq!(paths(&output), ["src/parser.rs", "src/json_parser.rs", "src/parser/mod.rs"]);
+        let output = tool.execute(json!({ "content": "documented" }), test_ctx()).await.unwrap().content;
+        assert_eq!(paths(&output), ["docs/notes.md"]);
+
+        let output = tool
+            .execute(json!({ "query": "struct \\w+;", "regex": true, "path": "src" }), test_ctx())
+            .await
+            .unwrap()
+            .content;
+        assert_eq!(paths(&output), ["src/parser.rs"]);
+        assert_eq!(output["results"][0]["matches"], json!(["content"]));
+
+        let err = tool.execute(json!({ "query": "x", "path": "../elsewhere" }), test_ctx()).await.unwrap_err();
+        assert!(matches!(err, cowork_core::error::ToolError::InvalidParams(_)));
+    }
+}
+
+mod normalize_path_properties {
+    use cowork_core::tools::filesystem::{normalize_path, path_starts_with, validate_write_path};
+    use proptest::prelude::*;
+    use std::path::{Component, Path, PathBuf};
+
+    /// Relative paths built from ordinary names, `.` and `..`
+    fn relative_path() -> impl Strategy<Value = Vec<&'static str>> {
+        prop::collection::vec(prop::sample::select(vec!["src", "a b", "ünï", "文件", ".", ".."]), 0..12)
+    }
+
+    /// Whether following `parts` from the root ever climbs above it
+    fn climbs_above_root(parts: &[&str]) -> bool {
+        let mut depth = 0i32;
+        for part in parts {
+            match *part {
+                "." => {}
+                ".." => {
+                    depth -= 1;
+                    if depth < 0 {
+                        return true;
+                    }
+                }
+                _ => depth += 1,
+            }
+        }
+        false
+    }
+
+    fn joined(root: &Path, parts: &[&str]) -> PathBuf {
+        parts.iter().fold(root.to_path_buf(), |path, part| path.join(part))
+    }
+
+    proptest! {
+        #[test]
+        fn normalized_paths_have_no_dot_components(parts in relative_path()) {
+            let root = tempfile::TempDir::new().unwrap();
+            let normalized = normalize_path(&joined(root.path(), &parts));
+            prop_assert!(normalized.is_absolute());
+            prop_assert!(normalized
+                .components()
+                .all(|c| !matches!(c, Component::CurDir | Component::ParentDir)));
+        }
+
+        #[test]
+        fn relative_paths_that_stay_below_the_root_never_escape(parts in relative_path()) {
+            prop_assume!(!climbs_above_root(&parts));
+            let root = tempfile::TempDir::new().unwrap();
+            let normalized = normalize_path(&joined(root.path(), &parts));
+            prop_assert!(path_starts_with(&normalized, root.path()));
+        }
+
+        #[test]
+        fn validate_write_path_only_accepts_paths_in_the_root(parts in relative_path()) {
+            let root = tempfile::TempDir::new().unwrap();
+            let path = joined(root.path(), &parts);
+            match validate_write_path(&path, root.path()) {
+                Ok(validated) => {
+                    prop_assert!(path_starts_with(&validated, root.path()));
+                    prop_assert_eq!(validated, normalize_path(&path));
+                }
+                Err(_) => prop_assert!(!path_starts_with(&normalize_path(&path), root.path())),
+            }
+        }
+    }
 }
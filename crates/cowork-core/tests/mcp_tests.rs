@@ -250,3 +250,144 @@ fn test_mcp_tools_multiple_servers() {
     assert_eq!(wrapped_tools[1].name(), "mcp__postgres__query");
     assert_eq!(wrapped_tools[2].name(), "mcp__github__create_issue");
 }
+
+#[tokio::test]
+async fn test_tool_server_refuses_policy_required_tools_even_when_trusted() {
+    use cowork_core::mcp_server::ToolServer;
+    use cowork_core::{Policy, ToolApprovalConfig};
+    use cowork_mcp::server::McpHandler;
+
+    let dir = tempdir().unwrap();
+    let workspace = dunce::canonicalize(dir.path()).unwrap();
+    let policy = Policy::parse("[approval]\nrequire = [\"Write\"]").unwrap();
+    let mut approval = ToolApprovalConfig::trust_all();
+    approval.apply_policy(&policy);
+    let registry = ToolRegistryBuilder::new(workspace.clone()).with_task(false).build();
+    let server = ToolServer::new(&workspace, registry, approval);
+
+    let file = workspace.join("a.txt");
+    let error = server
+        .call_tool("Write", serde_json::json!({"file_path": file, "content": "x"}))
+        .await
+        .unwrap_err();
+    assert!(error.contains("needs approval"), "{}", error);
+    assert!(!file.exists());
+
+    // Everything else is approved
+    let result = server
+        .call_tool("Bash", serde_json::json!({"command": "touch b.txt"}))
+        .await
+        .unwrap();
+    assert!(!result.is_error);
+    assert!(workspace.join("b.txt").exists());
+}
+
+#[test]
+fn test_mcp_sandbox_section_from_toml() {
+    use cowork_core::config::McpServerConfig;
+    use cowork_sandbox::policy::SecurityLevel;
+
+    let config: McpServerConfig = toml::from_str(
+        r#"
+        command = "node"
+        args = ["server.js"]
+        env = { API_URL = "https://api.example.com" }
+
+        [sandbox]
+        required = false
+        level = "Strict"
+
+        [sandbox.filesystem]
+        read_paths = ["/opt/server"]
+
+        [sandbox.limits]
+        max_cpu_time = 120
+        "#,
+    )
+    .unwrap();
+    let sandbox = config.sandbox.as_ref().unwrap();
+    assert!(!sandbox.required);
+    assert_eq!(sandbox.level, SecurityLevel::Strict);
+
+    let sandbox_config = sandbox.to_sandbox_config("docs", &config.env);
+    assert_eq!(sandbox_config.root, std::env::temp_dir().join("cowork-mcp").join("docs"));
+    // Sections replace the preset's; fields left out take the defaults
+    assert!(!sandbox_config.network.enabled);
+    assert_eq!(sandbox_config.filesystem.read_paths.len(), 1);
+    assert!(!sandbox_config.filesystem.blocked_paths.is_empty());
+    assert_eq!(sandbox_config.limits.max_cpu_time, 120);
+    assert_eq!(sandbox_config.limits.max_fds, cowork_sandbox::ResourceLimits::default().max_fds);
+    assert_eq!(sandbox_config.env.vars.get("API_URL").map(String::as_str), Some("https://api.example.com"));
+
+    // An empty section sandboxes at the standard level, without resource limits
+    let config: McpServerConfig = toml::from_str("command = \"node\"\n[sandbox]\n").unwrap();
+    let sandbox = config.sandbox.unwrap();
+    assert!(sandbox.required);
+    assert_eq!(sandbox.level, SecurityLevel::Standard);
+    assert_eq!(sandbox.to_sandbox_config("docs", &config.env).limits.max_cpu_time, 0);
+
+    let plain = toml::to_string(&McpServerConfig::new("node")).unwrap();
+    assert!(!plain.contains("sandbox"));
+}
+
+/// `cat` echoes each request back, which is enough of a server to start
+#[cfg(unix)]
+#[test]
+fn test_mcp_server_refuses_to_start_unsandboxed() {
+    use cowork_core::config::{McpSandboxConfig, McpServerConfig};
+    use cowork_core::McpServerStatus;
+
+    // Blocked paths can't be enforced while reads are unrestricted
+    let mut sandbox = McpSandboxConfig {
+        filesystem: Some(cowork_sandbox::FilesystemPolicy::default()),
+        ..Default::default()
+    };
+    let manager = McpServerManager::new();
+    manager.add_server("echo".to_string(), McpServerConfig::new("cat").with_sandbox(sandbox.clone()));
+
+    let error = manager.start_server("echo").unwrap_err().to_string();
+    assert!(error.contains("Refusing to start MCP server 'echo' unsandboxed"), "{}", error);
+    assert!(error.contains("sandbox.required = false"), "{}", error);
+    assert!(matches!(manager.list_servers()[0].status, McpServerStatus::Failed(_)));
+
+    sandbox.required = false;
+    manager.add_server("echo".to_string(), McpServerConfig::new("cat").with_sandbox(sandbox));
+    manager.start_server("echo").unwrap();
+    let info = &manager.list_servers()[0];
+    assert_eq!(info.status, McpServerStatus::Running);
+    assert!(!info.sandboxed);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_mcp_server_stopped_by_sandbox_reports_violation() {
+    use cowork_core::config::{McpSandboxConfig, McpServerConfig};
+    use cowork_core::McpServerStatus;
+
+    let manager = McpServerManager::new();
+    manager.add_server("echo".to_string(), McpServerConfig::new("cat").with_sandbox(McpSandboxConfig::default()));
+    if let Err(e) = manager.start_server("echo") {
+        assert!(e.to_string().contains("Landlock is unavailable"), "{}", e);
+        eprintln!("skipping: {}", e);
+        return;
+    }
+    // The handshake works through the confined child's pipes
+    assert!(manager.list_servers()[0].sandboxed);
+    manager.remove_server("echo").unwrap();
+
+    let config = McpServerConfig::new("sh")
+        .with_args(vec!["-c".to_string(), "cat /etc/passwd".to_string()])
+        .with_sandbox(McpSandboxConfig::default());
+    manager.add_server("leaky".to_string(), config);
+
+    let error = manager.start_server("leaky").unwrap_err().to_string();
+    assert!(error.contains("closed connection during init"), "{}", error);
+
+    let info = &manager.list_servers()[0];
+    assert_eq!(
+        info.status,
+        McpServerStatus::Failed("Stopped by the sandbox: /etc/passwd (blocked_paths: /etc/passwd)".to_string())
+    );
+    assert!(info.sandboxed);
+    assert_eq!(info.violations.len(), 1);
+}
@@ -0,0 +1,214 @@
+//! Round-trip tests for the Office writer tools
+//!
+//! Each document is written with WriteDocx, WriteXlsx or WritePptx and read
+//! back through the Read tool's document extraction.
+
+use cowork_core::tools::filesystem::{ReadFile, WriteDocx, WritePptx, WriteXlsx};
+use cowork_core::tools::{Artifact, Tool, ToolExecutionContext, ToolOutput};
+use serde_json::{json, Value};
+use tempfile::TempDir;
+
+fn ctx(tool: &str) -> ToolExecutionContext {
+    ToolExecutionContext::test_auto_approve("test-call-id", tool)
+}
+
+async fn read_back(dir: &TempDir, params: Value) -> ToolOutput {
+    ReadFile::new(dir.path().to_path_buf())
+        .execute(params, ctx("Read"))
+        .await
+        .expect("Read failed")
+}
+
+fn assert_file_artifact(output: &ToolOutput, name: &str, mime: &str) {
+    match output.artifacts.as_slice() {
+        [Artifact::File { path, mime: Some(m) }] => {
+            assert!(path.ends_with(name), "artifact path {:?}", path);
+            assert_eq!(m, mime);
+        }
+        other => panic!("expected one File artifact, got {:?}", other),
+    }
+}
+
+/// PNG signature and IHDR chunk: enough to sniff the type and size
+fn png(width: u32, height: u32) -> Vec<u8> {
+    let mut bytes = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+    bytes.extend_from_slice(&width.to_be_bytes());
+    bytes.extend_from_slice(&height.to_be_bytes());
+    bytes.extend_from_slice(&[8, 6, 0, 0, 0]);
+    bytes
+}
+
+#[tokio::test]
+async fn test_docx_round_trip() {
+    let dir = TempDir::new().unwrap();
+    let tool = WriteDocx::new(dir.path().to_path_buf());
+    let content = "# Summary\nRevenue grew in every region.\n\n- New customers: 40\n- Churn & returns: 2%\n\n## Regions\n| Region | Revenue |\n|---|---|\n| EMEA | 1.2M |\n| APAC | 0.8M |";
+
+    let output = tool
+        .execute(
+            json!({ "file_path": "reports/q1.docx", "title": "Quarterly Report", "content": content }),
+            ctx("WriteDocx"),
+        )
+        .await
+        .unwrap();
+    assert_eq!(output.content["format"], "docx");
+    assert_eq!(output.content["headings"], 2);
+    assert_eq!(output.content["bullets"], 2);
+    assert_eq!(output.content["tables"], 1);
+    assert_file_artifact(
+        &output,
+        "reports/q1.docx",
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+    );
+
+    let read = read_back(&dir, json!({ "file_path": "reports/q1.docx" })).await;
+    let text = read.content["content"].as_str().unwrap();
+    for expected in [
+        "Quarterly Report",
+        "Summary",
+        "Revenue grew in every region.",
+        "New customers: 40",
+        "Churn & returns: 2%",
+        "Regions",
+        "EMEA",
+        "1.2M",
+        "APAC",
+    ] {
+        assert!(text.contains(expected), "{:?} missing from {:?}", expected, text);
+    }
+    assert!(!text.contains("---"), "separator row was written: {:?}", text);
+}
+
+#[tokio::test]
+async fn test_xlsx_rows_round_trip() {
+    let dir = TempDir::new().unwrap();
+    let tool = WriteXlsx::new(dir.path().to_path_buf());
+
+    let output = tool
+        .execute(
+            json!({
+                "file_path": "sales.xlsx",
+                "sheet_name": "Q1",
+                "columns": ["Region", "Revenue", "Closed", "Won"],
+                "rows": [
+                    { "Region": "EMEA", "Revenue": 1200, "Closed": "2024-01-15", "Won": true },
+                    { "Region": "APAC", "Revenue": 800.5, "Won": false },
+                ]
+            }),
+            ctx("WriteXlsx"),
+        )
+        .await
+        .unwrap();
+    assert_eq!(output.content["rows"], 2);
+    assert_file_artifact(
+        &output,
+        "sales.xlsx",
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+    );
+
+    let read = read_back(&dir, json!({ "file_path": "sales.xlsx", "sheet": "Q1", "format": "json" })).await;
+    assert_eq!(read.content["columns"], json!(["Region", "Revenue", "Closed", "Won"]));
+    // Dates come back as Excel serial numbers, showing they were typed as dates
+    assert_eq!(
+        read.content["rows"],
+        json!([
+            { "Region": "EMEA", "Revenue": 1200.0, "Closed": "45306", "Won": true },
+            { "Region": "APAC", "Revenue": 800.5, "Closed": null, "Won": false },
+        ])
+    );
+}
+
+#[tokio::test]
+async fn test_xlsx_csv_round_trip() {
+    let dir = TempDir::new().unwrap();
+    let tool = WriteXlsx::new(dir.path().to_path_buf());
+
+    tool.execute(
+        json!({
+            "file_path": "inventory.xlsx",
+            "csv": "Sku,Name,Qty\r\n00123,\"Widget, large\",4\r\n00124,\"Say \"\"hi\"\"\",12\r\n"
+        }),
+        ctx("WriteXlsx"),
+    )
+    .await
+    .unwrap();
+
+    let read = read_back(&dir, json!({ "file_path": "inventory.xlsx", "sheet": 0, "format": "json" })).await;
+    assert_eq!(read.content["sheet"], "Sheet1");
+    assert_eq!(
+        read.content["rows"],
+        json!([
+            { "Sku": "00123", "Name": "Widget, large", "Qty": 4.0 },
+            { "Sku": "00124", "Name": "Say \"hi\"", "Qty": 12.0 },
+        ])
+    );
+}
+
+#[tokio::test]
+async fn test_pptx_round_trip() {
+    let dir = TempDir::new().unwrap();
+    std::fs::write(dir.path().join("chart.png"), png(400, 300)).unwrap();
+    let tool = WritePptx::new(dir.path().to_path_buf());
+
+    let output = tool
+        .execute(
+            json!({
+                "file_path": "deck.pptx",
+                "title": "Q1 Review",
+                "slides": [
+                    { "title": "Highlights", "bullets": ["Revenue up 12%", "R&D <on track>"] },
+                    { "title": "Growth", "bullets": ["EMEA led"], "image": "chart.png" },
+                ]
+            }),
+            ctx("WritePptx"),
+        )
+        .await
+        .unwrap();
+    assert_eq!(output.content["slides"], 2);
+    assert_eq!(output.content["images"], 1);
+    assert_file_artifact(
+        &output,
+        "deck.pptx",
+        "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+    );
+
+    let read = read_back(&dir, json!({ "file_path": "deck.pptx" })).await;
+    let text = read.content["content"].as_str().unwrap();
+    let first = text.find("=== Slide 1 ===").expect("slide 1 missing");
+    let second = text.find("=== Slide 2 ===").expect("slide 2 missing");
+    let (slide1, slide2) = (&text[first..second], &text[second..]);
+    for expected in ["Highlights", "Revenue up 12%", "R&D <on track>"] {
+        assert!(slide1.contains(expected), "{:?} missing from {:?}", expected, slide1);
+    }
+    assert!(slide2.contains("Growth") && slide2.contains("EMEA led"), "{:?}", slide2);
+
+    let archive = zip::ZipArchive::new(std::fs::File::open(dir.path().join("deck.pptx")).unwrap()).unwrap();
+    assert!(archive.file_names().any(|name| name == "ppt/media/image2.png"));
+}
+
+#[tokio::test]
+async fn test_output_path_is_validated() {
+    let dir = TempDir::new().unwrap();
+    let docx = WriteDocx::new(dir.path().to_path_buf());
+
+    let outside = docx
+        .execute(json!({ "file_path": "../escape.docx", "content": "x" }), ctx("WriteDocx"))
+        .await;
+    assert!(outside.is_err());
+    assert!(!dir.path().parent().unwrap().join("escape.docx").exists());
+
+    let wrong_extension = docx
+        .execute(json!({ "file_path": "notes.txt", "content": "x" }), ctx("WriteDocx"))
+        .await;
+    assert!(wrong_extension.is_err());
+
+    let pptx = WritePptx::new(dir.path().to_path_buf());
+    let outside_image = pptx
+        .execute(
+            json!({ "file_path": "deck.pptx", "slides": [{ "title": "x", "image": "/etc/hostname" }] }),
+            ctx("WritePptx"),
+        )
+        .await;
+    assert!(outside_image.is_err());
+    assert!(!dir.path().join("deck.pptx").exists());
+}
@@ -0,0 +1,72 @@
+//! Process tracker tests
+//!
+//! A helper process (this test binary run again) spawns a child, registers
+//! it and is killed with SIGKILL, as a crash would; the next scan must find
+//! the child it left behind.
+
+#![cfg(unix)]
+
+use std::io::{BufRead, BufReader};
+use std::os::unix::process::CommandExt;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use cowork_core::processes::{scan_orphans, ProcessKind, ProcessTracker};
+
+/// Set (to the tracker's directory) when the binary runs as the helper
+const HELPER_DIR: &str = "COWORK_TRACKER_HELPER_DIR";
+
+/// The helper: only does something when run by the test below
+#[test]
+#[allow(
+    clippy::zombie_processes,
+    reason = "the child must outlive the helper, which is killed before it could wait"
+)]
+fn tracker_helper() {
+    let Ok(dir) = std::env::var(HELPER_DIR) else {
+        return;
+    };
+    let tracker = ProcessTracker::new(dir);
+    let child = Command::new("sleep").arg("30").process_group(0).spawn().unwrap();
+    let _guard = tracker.track(child.id(), ProcessKind::Shell, "sleep 30", true);
+    println!("ready");
+    std::thread::sleep(Duration::from_secs(30));
+}
+
+#[test]
+fn test_killed_instance_leaves_detectable_orphans() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut helper = Command::new(std::env::current_exe().unwrap())
+        .args(["--exact", "tracker_helper", "--nocapture", "--test-threads=1"])
+        .env(HELPER_DIR, dir.path())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    let stdout = BufReader::new(helper.stdout.take().unwrap());
+    let ready = stdout.lines().map_while(Result::ok).any(|line| line.contains("ready"));
+    assert!(ready, "the helper didn't start its child");
+
+    helper.kill().unwrap();
+    helper.wait().unwrap();
+
+    let scan = scan_orphans(dir.path());
+    assert_eq!(scan.orphans.len(), 1);
+    let orphan = scan.orphans[0].clone();
+    assert_eq!(orphan.label, "sleep 30");
+    assert!(orphan.is_running());
+
+    let report = scan.kill();
+    assert_eq!(report.killed, vec![orphan.clone()]);
+    // Reparented to init, which reaps it
+    for _ in 0..50 {
+        if !orphan.is_running() {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    assert!(!orphan.is_running());
+    // Nothing left to report next time
+    assert!(scan_orphans(dir.path()).is_empty());
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+}
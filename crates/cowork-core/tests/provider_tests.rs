@@ -407,3 +407,66 @@ mod subagent_tests {
         println!("Subagent output:\n{}", output);
     }
 }
+
+mod tool_schema_tests {
+    use std::collections::BTreeMap;
+    use std::path::PathBuf;
+
+    use cowork_core::orchestration::ToolRegistryBuilder;
+    use cowork_core::provider::tool_schema::{adapt_definitions, SchemaDialect};
+    use serde_json::{json, Value};
+
+    /// Every built-in tool's definition, adapted to `dialect`, keyed by name
+    fn adapted_builtins(dialect: SchemaDialect) -> Value {
+        let workspace = tempfile::TempDir::new().unwrap();
+        let registry = ToolRegistryBuilder::new(workspace.path().to_path_buf())
+            .with_provider("anthropic")
+            .build();
+        let tools: BTreeMap<String, Value> = adapt_definitions(registry.list(), dialect)
+            .into_iter()
+            .map(|def| (def.name, json!({ "config": def.config, "schema": def.schema })))
+            .collect();
+        json!(tools)
+    }
+
+    /// Compare with `tests/snapshots/<name>`; `UPDATE_SNAPSHOTS=1` rewrites it
+    fn assert_snapshot(name: &str, actual: &Value) {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots").join(name);
+        let rendered = format!("{}\n", serde_json::to_string_pretty(actual).unwrap());
+        if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, rendered).unwrap();
+            return;
+        }
+        let expected = std::fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("missing snapshot {} ({}); run with UPDATE_SNAPSHOTS=1", path.display(), e));
+        assert!(
+            expected == rendered,
+            "{} changed; review the difference and run with UPDATE_SNAPSHOTS=1 to accept it",
+            name
+        );
+    }
+
+    #[test]
+    fn test_openai_strict_builtin_schemas() {
+        let tools = adapted_builtins(SchemaDialect::OpenAiStrict);
+        assert_snapshot("tool_schemas_openai_strict.json", &tools);
+    }
+
+    #[test]
+    fn test_gemini_builtin_schemas() {
+        let tools = adapted_builtins(SchemaDialect::Gemini);
+        assert_snapshot("tool_schemas_gemini.json", &tools);
+    }
+
+    #[test]
+    fn test_standard_leaves_schemas_unchanged() {
+        let workspace = tempfile::TempDir::new().unwrap();
+        let registry = ToolRegistryBuilder::new(workspace.path().to_path_buf()).build();
+        let original = registry.list();
+        let adapted = adapt_definitions(original.clone(), SchemaDialect::Standard);
+        for (before, after) in original.iter().zip(&adapted) {
+            assert_eq!(before.schema, after.schema);
+        }
+    }
+}
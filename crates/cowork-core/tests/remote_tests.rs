@@ -0,0 +1,196 @@
+//! Remote workspace tests
+//!
+//! Runs the file and shell tools against a real sshd. Enabled with the
+//! `ssh-tests` feature and configured from the environment:
+//!
+//! ```text
+//! docker build -t cowork-sshd crates/cowork-core/tests/sshd
+//! docker run -d --rm --name cowork-sshd -p 2222:22 \
+//!     -e PUBLIC_KEY="$(cat ~/.ssh/id_ed25519.pub)" cowork-sshd
+//! COWORK_SSH_TEST_HOST=127.0.0.1 COWORK_SSH_TEST_PORT=2222 \
+//!     COWORK_SSH_TEST_USER=cowork COWORK_SSH_TEST_KEY=~/.ssh/id_ed25519 \
+//!     cargo test -p cowork-core --features ssh-tests --test remote_tests
+//! ```
+//!
+//! `COWORK_SSH_TEST_ROOT` defaults to `/home/cowork/workspace`; each test
+//! works in its own directory below it.
+
+#![cfg(feature = "ssh-tests")]
+
+use cowork_core::config::RemoteTarget;
+use cowork_core::error::ToolError;
+use cowork_core::tools::filesystem::{EditFile, GlobFiles, GrepFiles, ReadFile, WriteFile};
+use cowork_core::tools::remote::RemoteWorkspace;
+use cowork_core::tools::shell::ExecuteCommand;
+use cowork_core::tools::{Tool, ToolExecutionContext};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+fn test_ctx() -> ToolExecutionContext {
+    ToolExecutionContext::test_auto_approve("test", "test")
+}
+
+fn env(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+/// The test sshd, rooted at `root`
+fn target(root: &str) -> RemoteTarget {
+    RemoteTarget {
+        host: env("COWORK_SSH_TEST_HOST")
+            .expect("COWORK_SSH_TEST_HOST is not set; see tests/remote_tests.rs for running the sshd container"),
+        user: env("COWORK_SSH_TEST_USER"),
+        port: env("COWORK_SSH_TEST_PORT").map(|port| port.parse().expect("invalid COWORK_SSH_TEST_PORT")),
+        identity_file: env("COWORK_SSH_TEST_KEY").map(PathBuf::from),
+        root: root.to_string(),
+        // The container's host key changes on every run
+        ssh_options: vec![
+            "StrictHostKeyChecking=no".to_string(),
+            "UserKnownHostsFile=/dev/null".to_string(),
+            "LogLevel=ERROR".to_string(),
+        ],
+    }
+}
+
+/// A fresh, empty workspace directory on the test host
+async fn workspace() -> Arc<RemoteWorkspace> {
+    let base = env("COWORK_SSH_TEST_ROOT").unwrap_or_else(|| "/home/cowork/workspace".to_string());
+    let name = format!("test-{}", uuid::Uuid::new_v4());
+    let parent = RemoteWorkspace::new(target(&base));
+    let output = parent
+        .exec(&format!("mkdir {}", name), parent.root(), &BTreeMap::new(), Duration::from_secs(30))
+        .await
+        .expect("failed to reach the test sshd");
+    assert!(output.success(), "mkdir failed: {}", output.stderr);
+    Arc::new(RemoteWorkspace::new(target(&format!("{}/{}", base, name))))
+}
+
+/// Local path the tools are given; never touched in remote mode
+fn local() -> PathBuf {
+    PathBuf::from("/nonexistent-local-workspace")
+}
+
+#[tokio::test]
+async fn test_write_read_and_edit() {
+    let remote = workspace().await;
+    let write = WriteFile::new(local()).with_remote(Some(remote.clone()));
+    let read = ReadFile::new(local()).with_remote(Some(remote.clone()));
+    let edit = EditFile::new(local()).with_remote(Some(remote.clone()));
+
+    let output = write
+        .execute(json!({ "file_path": "src/lib.rs", "content": "fn one() {}\nfn two() {}\n" }), test_ctx())
+        .await
+        .unwrap();
+    assert_eq!(output.content["created"], true);
+    assert_eq!(output.content["path"], "src/lib.rs");
+
+    edit.execute(
+        json!({ "file_path": "src/lib.rs", "old_string": "fn two() {}", "new_string": "fn three() {}" }),
+        test_ctx(),
+    )
+    .await
+    .unwrap();
+
+    let output = read.execute(json!({ "file_path": "src/lib.rs" }), test_ctx()).await.unwrap();
+    assert_eq!(output.content["total_lines"], 2);
+    assert!(output.content["content"].as_str().unwrap().contains("fn three() {}"));
+
+    let err = read.execute(json!({ "file_path": "missing.rs" }), test_ctx()).await.unwrap_err();
+    assert!(matches!(err, ToolError::ResourceNotFound(_)), "{:?}", err);
+    let err = read.execute(json!({ "file_path": "src" }), test_ctx()).await.unwrap_err();
+    assert!(matches!(err, ToolError::InvalidParams(_)), "{:?}", err);
+}
+
+#[tokio::test]
+async fn test_glob_and_grep() {
+    let remote = workspace().await;
+    let write = WriteFile::new(local()).with_remote(Some(remote.clone()));
+    for (path, content) in [
+        ("src/main.rs", "fn main() {\n    run();\n}\n"),
+        ("src/tools/run.rs", "pub fn run() {}\n"),
+        ("README.md", "Call run() to start\n"),
+    ] {
+        write.execute(json!({ "file_path": path, "content": content }), test_ctx()).await.unwrap();
+    }
+
+    let glob = GlobFiles::new(local()).with_remote(Some(remote.clone()));
+    let output = glob.execute(json!({ "pattern": "**/*.rs" }), test_ctx()).await.unwrap();
+    let mut files: Vec<_> = output.content["files"].as_array().unwrap().iter().map(|f| f.as_str().unwrap().to_string()).collect();
+    files.sort();
+    assert_eq!(files, vec!["src/main.rs", "src/tools/run.rs"]);
+
+    let output = glob.execute(json!({ "pattern": "src/*.rs" }), test_ctx()).await.unwrap();
+    assert_eq!(output.content["files"], json!(["src/main.rs"]));
+
+    let grep = GrepFiles::new(local()).with_remote(Some(remote.clone()));
+    let output = grep
+        .execute(json!({ "pattern": "run\\(", "type": "rust" }), test_ctx())
+        .await
+        .unwrap();
+    assert_eq!(output.metadata["backend"], "remote");
+    assert_eq!(output.content["files"], json!(["src/main.rs", "src/tools/run.rs"]));
+
+    let output = grep
+        .execute(json!({ "pattern": "run\\(\\);", "output_mode": "content", "-B": 1 }), test_ctx())
+        .await
+        .unwrap();
+    assert_eq!(output.content["matches"][0]["file"], "src/main.rs");
+    assert_eq!(output.content["matches"][0]["line"], 2);
+    assert_eq!(output.content["matches"][0]["context_before"][0]["content"], "fn main() {");
+}
+
+#[tokio::test]
+async fn test_bash_runs_in_remote_root() {
+    let remote = workspace().await;
+    let bash = ExecuteCommand::new(local()).with_remote(Some(remote.clone()));
+
+    let output = bash.execute(json!({ "command": "pwd" }), test_ctx()).await.unwrap();
+    assert_eq!(output.content["stdout"].as_str().unwrap().trim(), remote.root());
+
+    let output = bash.execute(json!({ "command": "echo oops >&2; exit 3" }), test_ctx()).await.unwrap();
+    assert_eq!(output.content["exit_code"], 3);
+    assert_eq!(output.content["stderr"].as_str().unwrap().trim(), "oops");
+
+    let err = bash
+        .execute(json!({ "command": "true", "run_in_background": true }), test_ctx())
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ToolError::InvalidParams(_)));
+}
+
+#[tokio::test]
+async fn test_paths_outside_root_are_rejected() {
+    let remote = workspace().await;
+    let read = ReadFile::new(local()).with_remote(Some(remote.clone()));
+
+    let err = read.execute(json!({ "file_path": "../../../etc/passwd" }), test_ctx()).await.unwrap_err();
+    assert!(matches!(err, ToolError::PermissionDenied(_)), "{:?}", err);
+
+    // A symlink inside the root pointing out of it is caught on the remote side
+    let bash = ExecuteCommand::new(local()).with_remote(Some(remote.clone()));
+    bash.execute(json!({ "command": "ln -s /etc escape" }), test_ctx()).await.unwrap();
+    let err = read.execute(json!({ "file_path": "escape/passwd" }), test_ctx()).await.unwrap_err();
+    assert!(matches!(err, ToolError::PermissionDenied(_)), "{:?}", err);
+}
+
+#[tokio::test]
+async fn test_connection_loss_and_reconnect() {
+    let remote = workspace().await;
+    let bash = ExecuteCommand::new(local()).with_remote(Some(remote.clone()));
+    bash.execute(json!({ "command": "true" }), test_ctx()).await.unwrap();
+
+    // Dropping the shared connection: the next call opens a new one
+    remote.disconnect().await;
+    let output = bash.execute(json!({ "command": "echo back" }), test_ctx()).await.unwrap();
+    assert_eq!(output.content["stdout"].as_str().unwrap().trim(), "back");
+
+    // Nothing listening: a distinct error rather than a failed command
+    let mut unreachable = target(remote.root());
+    unreachable.port = Some(1);
+    let bash = ExecuteCommand::new(local()).with_remote(Some(Arc::new(RemoteWorkspace::new(unreachable))));
+    let err = bash.execute(json!({ "command": "true" }), test_ctx()).await.unwrap_err();
+    assert!(matches!(err, ToolError::ConnectionLost(_)), "{:?}", err);
+}
@@ -5,9 +5,10 @@
 //! - Session input/output handling
 //! - Multi-session management
 //! - Session configuration
+//! - Importing Claude Code sessions
 
 use cowork_core::approval::ToolApprovalConfig;
-use cowork_core::session::{SessionConfig, SessionInput, SessionManager, SessionOutput};
+use cowork_core::session::{SessionConfig, SessionInput, SessionManager, SessionOutput, ToolDoneStatus};
 use std::collections::HashMap;
 use std::time::Duration;
 use tokio::time::timeout;
@@ -21,16 +22,58 @@ fn test_config() -> SessionConfig {
         model: None,
         api_key: None,
         base_url: None,
+        model_tiers: None,
         web_search_config: None,
         prompt_config: Default::default(),
         component_registry: None,
         tool_scope: None,
+        tool_profile: Default::default(),
+        compact_tool_descriptions: false,
         enable_hooks: None,
         save_session: true,
         session_registry: None,
         mcp_manager: None,
         parent_approval_channel: None,
         stream_mode: false,
+        request_timeout: None,
+        queue_policy: Default::default(),
+        watch_files: false,
+        validate_mcp_tool_args: true,
+        auto_memory: false,
+        self_review: false,
+        self_review_threshold: 0,
+        dry_run: false,
+        strict_tools: true,
+        limits: cowork_core::LimitsConfig::default(),
+        request_limiter: None,
+        workspace_locks: None,
+        usage_store: None,
+        audit: false,
+        audit_log: None,
+        env_policy: None,
+        policy: None,
+        network_policy: None,
+        verifier: None,
+        remote: None,
+        additional_roots: Vec::new(),
+        clipboard: None,
+        output_counters: None,
+        title: None,
+        snapshot: None,
+        project_context: false,
+        project_context_budget: 0,
+        scratch: false,
+        keep_scratch: false,
+        generation: Default::default(),
+        prompt_pipeline: Default::default(),
+        resume: None,
+        template: None,
+        locale: Default::default(),
+        summarizer: Default::default(),
+        summarizer_provider: None,
+        mock_provider: None,
+        autosave_interval: None,
+        isolation: Default::default(),
     }
 }
 
@@ -71,6 +114,64 @@ mod session_manager_tests {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_create_session_over_limit() {
+        let mut config = test_config();
+        config.save_session = false;
+        config.limits.max_sessions = 1;
+        let (manager, _output_rx) = SessionManager::with_config(config);
+
+        manager.create_session("first").await.unwrap();
+        // Re-creating a running session is not a new session
+        manager.create_session("first").await.unwrap();
+
+        let result = manager.create_session("second").await;
+        assert!(matches!(result, Err(cowork_core::Error::SessionLimit(1))));
+        assert!(!manager.has_session("second"));
+
+        // Stopping a session frees its slot
+        manager.stop_session("first").unwrap();
+        manager.create_session("second").await.unwrap();
+        manager.stop_all().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_capabilities() {
+        let mut config = test_config();
+        config.limits.max_sessions = 3;
+        let (manager, _output_rx) = SessionManager::with_config(config);
+        let capabilities = manager.capabilities();
+        assert_eq!(capabilities.protocol_version, cowork_core::session::PROTOCOL_VERSION);
+        assert!(capabilities.inputs.iter().any(|t| t == "user_message"));
+        assert!(capabilities.outputs.iter().any(|t| t == "unsupported_input"));
+        assert!(capabilities.tools.iter().any(|t| t == "Read"));
+        assert_eq!(capabilities.limits.max_sessions, 3);
+        assert_eq!(capabilities.limits.max_attachment_bytes, cowork_core::session::MAX_IMAGE_BYTES);
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_input_is_answered() {
+        let mut config = test_config();
+        config.save_session = false;
+        let (manager, mut output_rx) = SessionManager::with_config(config);
+
+        let input = SessionInput::from_json(serde_json::json!({ "type": "set_mood", "mood": "calm" })).unwrap();
+        manager.push_message("s1", input).await.unwrap();
+
+        let answer = timeout(Duration::from_secs(5), async {
+            while let Some((_, output)) = output_rx.recv().await {
+                if let SessionOutput::UnsupportedInput { input_type, .. } = output {
+                    return input_type;
+                }
+            }
+            panic!("Output channel closed");
+        })
+        .await
+        .expect("No unsupported_input output");
+        assert_eq!(answer, "set_mood");
+        manager.stop_all().unwrap();
+    }
+
     #[tokio::test]
     async fn test_output_sender_clone() {
         let (manager, _output_rx) = SessionManager::with_config(test_config());
@@ -91,7 +192,7 @@ mod session_input_tests {
     fn test_user_message_creation() {
         let input = SessionInput::user_message("Hello, world!");
         match input {
-            SessionInput::UserMessage { content } => {
+            SessionInput::UserMessage { content, .. } => {
                 assert_eq!(content, "Hello, world!");
             }
             _ => panic!("Expected UserMessage"),
@@ -191,14 +292,14 @@ mod session_output_tests {
     #[test]
     fn test_idle_creation() {
         let output = SessionOutput::idle();
-        assert!(matches!(output, SessionOutput::Idle));
+        assert!(matches!(output, SessionOutput::Idle { .. }));
     }
 
     #[test]
     fn test_user_message_echo() {
         let output = SessionOutput::user_message("msg-1", "Hello");
         match output {
-            SessionOutput::UserMessage { id, content } => {
+            SessionOutput::UserMessage { id, content, .. } => {
                 assert_eq!(id, "msg-1");
                 assert_eq!(content, "Hello");
             }
@@ -210,7 +311,7 @@ mod session_output_tests {
     fn test_thinking_creation() {
         let output = SessionOutput::thinking("Processing...");
         match output {
-            SessionOutput::Thinking { content } => {
+            SessionOutput::Thinking { content, .. } => {
                 assert_eq!(content, "Processing...");
             }
             _ => panic!("Expected Thinking"),
@@ -221,7 +322,7 @@ mod session_output_tests {
     fn test_assistant_message_creation() {
         let output = SessionOutput::assistant_message("msg-2", "Here's my response");
         match output {
-            SessionOutput::AssistantMessage { id, content } => {
+            SessionOutput::AssistantMessage { id, content, .. } => {
                 assert_eq!(id, "msg-2");
                 assert_eq!(content, "Here's my response");
             }
@@ -238,6 +339,7 @@ mod session_output_tests {
                 id,
                 name,
                 arguments,
+                ..
             } => {
                 assert_eq!(id, "t1");
                 assert_eq!(name, "read_file");
@@ -277,11 +379,14 @@ mod session_output_tests {
                 id,
                 name,
                 success,
+                status,
                 output: out,
+                ..
             } => {
                 assert_eq!(id, "t3");
                 assert_eq!(name, "read_file");
                 assert!(success);
+                assert_eq!(status, ToolDoneStatus::Success);
                 assert_eq!(out, "file contents");
             }
             _ => panic!("Expected ToolDone"),
@@ -296,22 +401,34 @@ mod session_output_tests {
                 id,
                 name,
                 success,
+                status,
                 output: out,
+                ..
             } => {
                 assert_eq!(id, "t4");
                 assert_eq!(name, "write_file");
                 assert!(!success);
+                assert_eq!(status, ToolDoneStatus::Failed);
                 assert_eq!(out, "Permission denied");
             }
             _ => panic!("Expected ToolDone"),
         }
     }
 
+    #[test]
+    fn test_tool_cancelled_serialization() {
+        let output = SessionOutput::tool_cancelled("t5", "WebFetch", "Cancelled by user");
+        let json = serde_json::to_value(&output).unwrap();
+        assert_eq!(json["type"], "tool_done");
+        assert_eq!(json["success"], false);
+        assert_eq!(json["status"], "cancelled");
+    }
+
     #[test]
     fn test_error_creation() {
         let output = SessionOutput::error("Something went wrong");
         match output {
-            SessionOutput::Error { message } => {
+            SessionOutput::Error { message, .. } => {
                 assert_eq!(message, "Something went wrong");
             }
             _ => panic!("Expected Error"),
@@ -340,11 +457,7 @@ mod session_output_tests {
             multi_select: false,
         };
 
-        let output = SessionOutput::Question {
-            request_id: "q-123".to_string(),
-            questions: vec![question],
-            subagent_id: None,
-        };
+        let output = SessionOutput::question("q-123", vec![question]);
 
         match output {
             SessionOutput::Question { request_id, questions, .. } => {
@@ -383,11 +496,7 @@ mod session_output_tests {
             multi_select: true,
         };
 
-        let output = SessionOutput::Question {
-            request_id: "req-1".to_string(),
-            questions: vec![question],
-            subagent_id: None,
-        };
+        let output = SessionOutput::question("req-1", vec![question]);
 
         let json = serde_json::to_string(&output).expect("Serialization failed");
         assert!(json.contains("question"));
@@ -867,3 +976,218 @@ mod session_integration_tests {
         }
     }
 }
+
+mod history_repair_tests {
+    use cowork_core::provider::{ChatRole, ToolCall, message_text_content};
+    use cowork_core::session::{
+        ChatSession, INTERRUPTED_TOOL_RESULT, SavedSession, SESSION_FORMAT_VERSION, TitleSource, ToolCallStatus,
+    };
+
+    fn tool_call(id: &str, name: &str) -> ToolCall {
+        ToolCall {
+            call_id: id.to_string(),
+            fn_name: name.to_string(),
+            fn_arguments: serde_json::json!({"file_path": "src/main.rs"}),
+            thought_signatures: None,
+        }
+    }
+
+    #[test]
+    fn test_crash_mid_approval_roundtrip() {
+        // The app died while a Write call waited for approval; the Read
+        // call running beside it had already finished
+        let mut session = ChatSession::new();
+        session.add_user_message("Fix the bug");
+        session.add_assistant_message(
+            "Reading and patching",
+            vec![tool_call("call_read", "Read"), tool_call("call_write", "Write")],
+        );
+        session.add_tool_result("call_read", "fn main() {}", false);
+
+        let saved = SavedSession {
+            id: "crashed".to_string(),
+            name: "Crashed".to_string(),
+            title_source: TitleSource::Fallback,
+            format_version: SESSION_FORMAT_VERSION,
+            provider: None,
+            model: None,
+            messages: session.messages.clone(),
+            todos: Vec::new(),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+            parent_id: None,
+            fork_point: None,
+            recovered: false,
+            pinned: false,
+            pinned_messages: Vec::new(),
+            template: None,
+            tool_usage: Default::default(),
+        };
+        let json = serde_json::to_string(&saved).unwrap();
+        let mut loaded: SavedSession = serde_json::from_str(&json).unwrap();
+
+        let repair = loaded.repair_history();
+        assert_eq!(repair.synthesized, vec!["call_write"]);
+        assert!(repair.dropped.is_empty());
+
+        let last = loaded.messages.last().unwrap();
+        assert_eq!(last.role, ChatRole::Tool);
+        assert_eq!(message_text_content(last), INTERRUPTED_TOOL_RESULT);
+        assert!(loaded.repair_history().is_empty(), "repair is idempotent");
+
+        // The live session gets the same repair and marks the call failed
+        let repair = session.repair_history();
+        assert_eq!(repair.synthesized, vec!["call_write"]);
+        assert_eq!(session.get_tool_status("call_write"), Some(ToolCallStatus::Failed));
+        assert_eq!(session.messages.len(), loaded.messages.len());
+    }
+}
+
+mod import_tests {
+    use cowork_core::provider::{message_text_content, ChatRole, ContentPart};
+    use cowork_core::session::{
+        convert_claude_code_session, import_sessions_to, ImportOutcome, ImportSource, SavedSession, TitleSource,
+        INTERRUPTED_TOOL_RESULT,
+    };
+
+    /// Claude Code 0.2: whole assistant messages per line, no summary
+    const V0_2: &str = include_str!("fixtures/claude_code/v0_2.jsonl");
+    /// Claude Code 1.0: one content block per line, summaries, meta and sidechain entries
+    const V1_0: &str = include_str!("fixtures/claude_code/v1_0.jsonl");
+
+    fn known_tools() -> Vec<String> {
+        ["Bash", "Edit", "Glob", "Grep", "KillShell", "Read", "TodoWrite", "Write"]
+            .iter()
+            .map(|name| name.to_string())
+            .collect()
+    }
+
+    fn tool_calls(session: &SavedSession) -> Vec<(String, serde_json::Value)> {
+        session
+            .messages
+            .iter()
+            .flat_map(|message| message.content.tool_calls())
+            .map(|call| (call.fn_name.clone(), call.fn_arguments.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_convert_v0_2() {
+        let converted = convert_claude_code_session(V0_2, &known_tools()).unwrap();
+        let session = &converted.session;
+
+        assert_eq!(session.id, "claude-code-4a7c2e10-9b1d-4f3e-8c55-0d2f6a9e1b01");
+        assert_eq!(session.name, "Why does the checkout test fail after the tax chan…");
+        assert_eq!(session.title_source, TitleSource::Fallback);
+        assert_eq!(session.model.as_deref(), Some("claude-3-7-sonnet-20250219"));
+        assert_eq!(session.created_at.to_rfc3339(), "2025-03-04T09:15:02.114+00:00");
+        assert_eq!(session.updated_at.to_rfc3339(), "2025-03-04T09:15:16.640+00:00");
+        assert_eq!(converted.skipped, vec!["malformed JSON (line 4)"]);
+
+        // Grep keeps its name, NotebookRead becomes Read
+        assert_eq!(
+            tool_calls(session),
+            vec![
+                ("Grep".to_string(), serde_json::json!({"pattern": "TAX_RATE", "include": "*.py"})),
+                ("Read".to_string(), serde_json::json!({"file_path": "/home/dev/shop/report.ipynb"})),
+            ]
+        );
+
+        // LS and MultiEdit have no equivalent: kept as text, with their results
+        let text: Vec<String> = session.messages.iter().map(message_text_content).collect();
+        assert!(text[1].contains("[Called the Claude Code tool LS with {\"path\":\"/home/dev/shop\"}]"), "{}", text[1]);
+        assert_eq!(session.messages[2].role, ChatRole::User);
+        assert!(text[2].starts_with("[Result of the Claude Code tool LS]\n- /home/dev/shop/"), "{}", text[2]);
+        assert!(text.iter().any(|t| t.starts_with("[Result of the Claude Code tool MultiEdit]\nApplied 2 edits")));
+        assert_eq!(text.last().unwrap(), "Done: the test now expects the 20% rate.");
+    }
+
+    #[test]
+    fn test_convert_v1_0() {
+        let converted = convert_claude_code_session(V1_0, &known_tools()).unwrap();
+        let session = &converted.session;
+
+        assert_eq!(session.id, "claude-code-b93e5d44-2c7a-4e19-a0f1-7e6c3d2b8a90");
+        assert_eq!(session.name, "Fix flaky websocket reconnect");
+        assert_eq!(session.title_source, TitleSource::Generated);
+        assert_eq!(session.provider.as_deref(), Some("anthropic"));
+        assert_eq!(
+            converted.skipped,
+            vec![
+                "file-history-snapshot entries (line 14)",
+                "local command messages (line 3)",
+                "meta messages (line 2)",
+                "subagent messages (line 9)",
+                "system entries (line 10)",
+                "thinking blocks (line 5)",
+                "1 tool calls without a result, marked interrupted",
+            ]
+        );
+
+        // The first message keeps its screenshot
+        let first = &session.messages[0];
+        assert_eq!(first.role, ChatRole::User);
+        assert!(first.content.parts().iter().any(|part| matches!(part, ContentPart::Binary(_))));
+
+        // The three lines of msg_01HfXq9 are one assistant message
+        let reply = &session.messages[1];
+        assert_eq!(reply.role, ChatRole::Assistant);
+        assert_eq!(message_text_content(reply), "I'll run the test in a loop to reproduce it.");
+        assert_eq!(reply.content.tool_calls().len(), 1);
+
+        let calls: Vec<String> = tool_calls(session).into_iter().map(|(name, _)| name).collect();
+        assert_eq!(calls, vec!["Bash", "KillShell", "Edit"]);
+
+        // The error result is marked, the MCP tool's call and result are text
+        let results: Vec<String> = session
+            .messages
+            .iter()
+            .flat_map(|message| message.content.tool_responses())
+            .map(|response| response.content.clone())
+            .collect();
+        assert_eq!(
+            results,
+            vec![
+                "Command running in background with ID: bash_1",
+                "Error: Shell bash_1 is not running",
+                INTERRUPTED_TOOL_RESULT,
+            ]
+        );
+        let text: Vec<String> = session.messages.iter().map(message_text_content).collect();
+        assert!(text.iter().any(|t| t.contains("[Called the Claude Code tool mcp__github__get_issue with")));
+        assert!(text.iter().any(|t| t == "[Result of the Claude Code tool mcp__github__get_issue]\n#88 Reconnect races with close (open)"));
+    }
+
+    #[test]
+    fn test_import_reports_each_file() {
+        let source = tempfile::tempdir().unwrap();
+        let sessions = tempfile::tempdir().unwrap();
+        let project = source.path().join("-home-dev-shop");
+        std::fs::create_dir_all(&project).unwrap();
+        std::fs::write(project.join("4a7c2e10.jsonl"), V0_2).unwrap();
+        std::fs::write(source.path().join("b93e5d44.jsonl"), V1_0).unwrap();
+        std::fs::write(source.path().join("broken.jsonl"), "not json\n{\"type\":\"system\"}\n").unwrap();
+        std::fs::write(source.path().join("notes.txt"), "ignored").unwrap();
+        let paths = vec![source.path().to_path_buf()];
+
+        // A dry run reports the sessions without writing them
+        let report = import_sessions_to(ImportSource::ClaudeCode, &paths, &known_tools(), true, sessions.path()).unwrap();
+        assert_eq!(report.files.len(), 3);
+        assert_eq!(report.count(ImportOutcome::Imported), 2);
+        assert_eq!(report.count(ImportOutcome::Failed), 1);
+        assert!(report.to_string().starts_with("Would import 2 Claude Code sessions (0 already imported, 1 failed)"));
+        assert_eq!(std::fs::read_dir(sessions.path()).unwrap().count(), 0);
+
+        let report = import_sessions_to(ImportSource::ClaudeCode, &paths, &known_tools(), false, sessions.path()).unwrap();
+        let broken = report.files.iter().find(|file| file.outcome == ImportOutcome::Failed).unwrap();
+        assert!(broken.path.ends_with("broken.jsonl"));
+        assert!(broken.error.as_deref().unwrap().contains("No messages"));
+        let saved_path = sessions.path().join("claude-code-b93e5d44-2c7a-4e19-a0f1-7e6c3d2b8a90.json");
+        let saved: SavedSession = serde_json::from_str(&std::fs::read_to_string(saved_path).unwrap()).unwrap();
+        assert_eq!(saved.name, "Fix flaky websocket reconnect");
+
+        // Importing again leaves the saved sessions alone
+        let report = import_sessions_to(ImportSource::ClaudeCode, &paths, &known_tools(), false, sessions.path()).unwrap();
+        assert_eq!(report.count(ImportOutcome::AlreadyImported), 2);
+    }
+}
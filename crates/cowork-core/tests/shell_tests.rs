@@ -2,11 +2,15 @@
 //!
 //! Tests for ExecuteCommand and KillShell tools.
 
+use cowork_core::error::ToolError;
+use cowork_core::session::progress_channel;
 use cowork_core::tools::{Tool, ToolExecutionContext};
-use cowork_core::tools::shell::{ExecuteCommand, KillShell, ShellProcessRegistry, ShellConfig, BackgroundShell, ShellStatus};
+use cowork_core::tools::task::{AgentInstanceRegistry, TaskOutputTool};
+use cowork_core::tools::shell::{ExecuteCommand, KillShell, ShellProcessRegistry, ShellConfig, BackgroundShell, ShellStatus, EnvPolicy};
 use serde_json::json;
 use std::sync::Arc;
 use tempfile::TempDir;
+use tokio_util::sync::CancellationToken;
 
 /// Create a test context that auto-approves Bash commands
 fn test_ctx() -> ToolExecutionContext {
@@ -120,6 +124,158 @@ mod execute_command_tests {
 
         assert!(result.is_err(), "sudo should be blocked");
     }
+
+    #[tokio::test]
+    async fn test_progress_reported_while_running() {
+        let dir = setup_workspace();
+        let tool = ExecuteCommand::new(dir.path().to_path_buf());
+        assert!(tool.supports_progress());
+        let (progress_tx, mut progress_rx) = progress_channel();
+
+        let result = tool
+            .execute(
+                json!({"command": "echo 'building'; sleep 0.3; echo 'warming up' >&2; sleep 2; echo 'finished'"}),
+                test_ctx().with_progress(progress_tx),
+            )
+            .await
+            .unwrap();
+
+        // Output is still collected per stream
+        assert_eq!(result.content["stdout"], "building\nfinished\n");
+        assert_eq!(result.content["stderr"], "warming up\n");
+
+        let progress = progress_rx.try_recv().expect("a heartbeat while the command slept");
+        assert_eq!(progress.tool_call_id, "test");
+        assert!(progress.elapsed_ms >= 1000);
+        assert_eq!(progress.output_bytes, "building\nwarming up\n".len() as u64);
+        assert_eq!(progress.last_line.as_deref(), Some("warming up"));
+    }
+
+    #[tokio::test]
+    async fn test_timeout_stops_command() {
+        let dir = setup_workspace();
+        let tool = ExecuteCommand::new(dir.path().to_path_buf());
+        let started = std::time::Instant::now();
+
+        let result = tool.execute(json!({"command": "sleep 30", "timeout": 1000}), test_ctx()).await;
+
+        assert!(matches!(result, Err(ToolError::ExecutionFailed(msg)) if msg.contains("timed out")));
+        assert!(started.elapsed() < std::time::Duration::from_secs(10));
+    }
+
+    /// Whether `pid` is still running (exited processes left unreaped count as gone)
+    #[cfg(unix)]
+    fn is_running(pid: &str) -> bool {
+        let output = std::process::Command::new("ps").args(["-o", "stat=", "-p", pid]).output().unwrap();
+        let stat = String::from_utf8_lossy(&output.stdout);
+        !stat.trim().is_empty() && !stat.trim().starts_with('Z')
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_cancel_kills_command_and_its_children() {
+        let dir = setup_workspace();
+        let tool = ExecuteCommand::new(dir.path().to_path_buf());
+        let cancel = CancellationToken::new();
+        let canceller = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+            canceller.cancel();
+        });
+        let started = std::time::Instant::now();
+
+        let result = tool
+            .execute(
+                json!({"command": "sleep 30 & echo $! > child.pid; wait"}),
+                test_ctx().with_cancellation(cancel),
+            )
+            .await;
+
+        assert!(matches!(result, Err(ToolError::Cancelled)), "{:?}", result.err());
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+        // The sleep the shell started is killed too, not just the shell
+        let pid = std::fs::read_to_string(dir.path().join("child.pid")).unwrap();
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(2);
+        while is_running(pid.trim()) {
+            assert!(std::time::Instant::now() < deadline, "sleep {} outlived the cancelled command", pid.trim());
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+    }
+}
+
+#[cfg(unix)]
+mod env_policy_tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_configured_vars_set_and_secrets_masked() {
+        let dir = setup_workspace();
+        let policy = EnvPolicy::new()
+            .with_var("DATABASE_URL", "postgres://localhost/test")
+            .with_var("DEPLOY_TOKEN", "tok-5f2a9c")
+            .with_var("SERVICE_SECRET", "hunter2-secret");
+        let tool = ExecuteCommand::new(dir.path().to_path_buf()).with_env_policy(policy);
+
+        let output = tool.execute(json!({
+            "command": "echo \"db=$DATABASE_URL token=$DEPLOY_TOKEN\"; echo \"secret=$SERVICE_SECRET\" >&2"
+        }), test_ctx()).await.unwrap();
+
+        assert_eq!(output.content["stdout"], "db=postgres://localhost/test token=***\n");
+        assert_eq!(output.content["stderr"], "secret=***\n");
+    }
+
+    #[tokio::test]
+    async fn test_non_allowlisted_vars_absent() {
+        let dir = setup_workspace();
+        let policy = EnvPolicy::new().with_allowlist(vec!["PATH".to_string()]);
+        let tool = ExecuteCommand::new(dir.path().to_path_buf()).with_env_policy(policy);
+
+        // HOME is always set for the test process but not allowlisted here
+        let output = tool.execute(json!({
+            "command": "echo \"home=${HOME:-unset}\"; env | cut -d= -f1 | grep -v '^PWD$\\|^SHLVL$\\|^_$' | sort"
+        }), test_ctx()).await.unwrap();
+
+        assert_eq!(output.content["stdout"], "home=unset\nPATH\n");
+    }
+
+    #[tokio::test]
+    async fn test_background_output_masked() {
+        let dir = setup_workspace();
+        let policy = EnvPolicy::new().with_var("DEPLOY_TOKEN", "tok-5f2a9c");
+        let tool = ExecuteCommand::new(dir.path().to_path_buf())
+            .with_registry(Arc::new(ShellProcessRegistry::new()))
+            .with_env_policy(policy.clone());
+
+        let output = tool.execute(json!({
+            "command": "echo \"token=$DEPLOY_TOKEN\"; echo \"again=$DEPLOY_TOKEN\" >&2; echo done",
+            "run_in_background": true
+        }), test_ctx()).await.unwrap();
+        let output_file = output.content["output_file"].as_str().unwrap().to_string();
+
+        let mut log = String::new();
+        for _ in 0..50 {
+            log = std::fs::read_to_string(&output_file).unwrap_or_default();
+            if log.contains("done") {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+        assert!(log.contains("token=***"), "{}", log);
+        assert!(log.contains("again=***"), "{}", log);
+        assert!(!log.contains("tok-5f2a9c"), "{}", log);
+
+        // Read back through TaskOutput, which masks too
+        let task_output = TaskOutputTool::new(Arc::new(AgentInstanceRegistry::new())).with_env_policy(policy);
+        let result = task_output.execute(json!({
+            "task_id": output.content["shell_id"],
+            "output_file": output_file,
+            "block": false,
+            "timeout": 0
+        }), test_ctx()).await.unwrap();
+        assert_eq!(result.content["output"], log);
+        let _ = std::fs::remove_file(&output_file);
+    }
+
 }
 
 mod background_execution_tests {
@@ -213,6 +369,7 @@ mod process_registry_tests {
             started_at: chrono::Utc::now(),
             status: ShellStatus::Running,
             output: None,
+            tracked: None,
         };
 
         registry.register(shell).await;
@@ -235,6 +392,7 @@ mod process_registry_tests {
                 started_at: chrono::Utc::now(),
                 status: ShellStatus::Running,
                 output: None,
+                tracked: None,
             };
             registry.register(shell).await;
         }
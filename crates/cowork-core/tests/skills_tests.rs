@@ -64,6 +64,21 @@ mod skill_registry_tests {
         assert!(registry.get("code-review").is_some());
         assert!(registry.get("feature-dev").is_some());
         assert!(registry.get("review-pr").is_some());
+        assert!(registry.get("debug").is_some());
+        assert!(registry.get("settings").is_some());
+        assert!(registry.get("memory").is_some());
+        assert!(registry.get("agents").is_some());
+        assert!(registry.get("fork").is_some());
+        assert!(registry.get("merge").is_some());
+        assert!(registry.get("recipe").is_some());
+        assert!(registry.get("retry").is_some());
+        assert!(registry.get("edit-last").is_some());
+        assert!(registry.get("permissions").is_some());
+        assert!(registry.get("provider").is_some());
+        assert!(registry.get("pin").is_some());
+        assert!(registry.get("context").is_some());
+        assert!(registry.get("compact").is_some());
+        assert!(registry.get("template").is_some());
     }
 
     #[test]
@@ -72,7 +87,7 @@ mod skill_registry_tests {
         let registry = SkillRegistry::with_builtins(dir.path().to_path_buf());
 
         let skills = registry.list();
-        assert_eq!(skills.len(), 6, "Should have exactly 6 built-in skills");
+        assert_eq!(skills.len(), 23, "Should have 6 prompt skills plus /debug, /settings, /plans, /memory, /agents, /fork, /merge, /recipe, /retry, /edit-last, /permissions, /provider, /pin, /title, /context, /compact and /template");
 
         // All skills should have names and descriptions
         for skill in &skills {
@@ -645,4 +660,46 @@ mod argument_substitution_tests {
         let result = substitute_arguments(template, "");
         assert_eq!(result, "Args: , First: ");
     }
+
+    mod properties {
+        use cowork_core::tools::skill::substitute_arguments;
+        use proptest::prelude::*;
+
+        /// Argument words, including ones that look like placeholders
+        fn words() -> impl Strategy<Value = Vec<String>> {
+            prop::collection::vec(
+                prop_oneof![
+                    "[a-z0-9._-]{1,8}",
+                    Just("$ARGUMENTS".to_string()),
+                    Just("${ARGUMENTS}".to_string()),
+                    Just("$1".to_string()),
+                    Just("$ARGUMENTS[0]".to_string()),
+                ],
+                1..5,
+            )
+        }
+
+        proptest! {
+            #[test]
+            fn text_without_placeholders_is_unchanged(template in "[^$]*", args in "[ -~]*") {
+                prop_assert_eq!(substitute_arguments(&template, &args), template);
+            }
+
+            #[test]
+            fn arguments_are_inserted_verbatim(words in words()) {
+                let args = words.join(" ");
+                prop_assert_eq!(substitute_arguments("$ARGUMENTS", &args), args.clone());
+                prop_assert_eq!(substitute_arguments("${ARGUMENTS}", &args), args.clone());
+                prop_assert_eq!(substitute_arguments("[$0]", &args), format!("[{}]", words[0]));
+                prop_assert_eq!(substitute_arguments("[${ARGUMENTS[0]}]", &args), format!("[{}]", words[0]));
+            }
+
+            #[test]
+            fn missing_positions_are_empty(words in words(), extra in 0usize..4) {
+                let index = words.len() + extra;
+                let template = format!("<$ARGUMENTS[{}]><${}>", index, index);
+                prop_assert_eq!(substitute_arguments(&template, &words.join(" ")), "<><>");
+            }
+        }
+    }
 }
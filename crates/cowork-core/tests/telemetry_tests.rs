@@ -0,0 +1,97 @@
+//! Telemetry tests
+//!
+//! A session driven by a mock provider reports its metrics to a capturing
+//! sink; the labels must stay within the allowlist and never carry the
+//! conversation or the workspace's paths.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use cowork_core::provider::MockProvider;
+use cowork_core::session::{SessionConfig, SessionInput, SessionManager, SessionOutput};
+use cowork_core::telemetry::{self, MetricsSink, ALLOWED_LABELS, LLM_CALL_SECONDS, LLM_TOKENS, TOOL_SECONDS};
+use serde_json::json;
+use tempfile::TempDir;
+use tokio::time::timeout;
+
+/// One recorded metric: its name and labels
+type Recorded = (String, Vec<(String, String)>);
+
+/// Sink that keeps every metric it is given
+#[derive(Default)]
+struct CapturingSink {
+    recorded: Mutex<Vec<Recorded>>,
+}
+
+impl CapturingSink {
+    fn record(&self, name: &str, labels: &[(&str, &str)]) {
+        let labels = labels.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        self.recorded.lock().unwrap().push((name.to_string(), labels));
+    }
+}
+
+impl MetricsSink for CapturingSink {
+    fn record_counter(&self, name: &str, _value: u64, labels: &[(&str, &str)]) {
+        self.record(name, labels);
+    }
+
+    fn record_histogram(&self, name: &str, _value: f64, labels: &[(&str, &str)]) {
+        self.record(name, labels);
+    }
+}
+
+#[tokio::test]
+async fn test_session_metrics_carry_no_content_or_paths() {
+    let sink = Arc::new(CapturingSink::default());
+    assert!(telemetry::install(sink.clone()));
+
+    let workspace = TempDir::new().unwrap();
+    let notes = workspace.path().join("secret-notes.md");
+    std::fs::write(&notes, "the launch code is 0000\n").unwrap();
+
+    let mock = MockProvider::builder()
+        .tool_call("call_1", "Read", json!({"file_path": notes.to_str().unwrap()}))
+        .with_usage(120, 8)
+        .text("The notes mention a launch code")
+        .with_usage(150, 6)
+        .build();
+    let mut config = SessionConfig::new(workspace.path())
+        .with_mock_provider(mock.clone())
+        .with_project_context(false)
+        .with_scratch(false)
+        .with_audit(false);
+    config.save_session = false;
+    let (manager, mut rx) = SessionManager::with_config(config);
+
+    manager
+        .push_message("telemetry", SessionInput::user_message("What do my private notes say?"))
+        .await
+        .unwrap();
+    loop {
+        let (_, output) = timeout(Duration::from_secs(10), rx.recv())
+            .await
+            .expect("timed out waiting for session output")
+            .expect("output channel closed");
+        if matches!(output, SessionOutput::Idle { .. }) {
+            break;
+        }
+    }
+
+    let recorded = sink.recorded.lock().unwrap().clone();
+    for name in [LLM_CALL_SECONDS, LLM_TOKENS, TOOL_SECONDS] {
+        assert!(recorded.iter().any(|(n, _)| n == name), "{} was not recorded", name);
+    }
+    assert!(recorded
+        .iter()
+        .any(|(n, labels)| n == TOOL_SECONDS && labels.contains(&("tool".into(), "Read".into()))));
+
+    let workspace_path = workspace.path().to_string_lossy();
+    for (name, labels) in &recorded {
+        for (key, value) in labels {
+            assert!(ALLOWED_LABELS.contains(&key.as_str()), "{} has label {}", name, key);
+            for leaked in [workspace_path.as_ref(), "secret-notes", "launch code", "private notes"] {
+                assert!(!value.contains(leaked), "{} label {}={} leaks {}", name, key, value, leaked);
+            }
+        }
+    }
+}
@@ -0,0 +1,317 @@
+//! Snapshot tests of what the model sees from each tool
+//!
+//! Each case runs a tool against a fixture workspace and snapshots the JSON
+//! the agent loop sends back: the output content on success, or the
+//! `Error: ...` text on failure. A change to a tool's output shape, or to an
+//! error message the model relies on, shows up as a snapshot diff.
+//!
+//! When the change is deliberate, accept the new snapshots and commit them
+//! with the change:
+//!
+//! ```text
+//! cargo insta test -p cowork-core --test tool_snapshot_tests --review
+//! ```
+//!
+//! Without cargo-insta, run `INSTA_UPDATE=always cargo test -p cowork-core
+//! --test tool_snapshot_tests` and review the changed `.snap` files in
+//! `tests/snapshots/`. Paths are redacted to `[ROOT]` (the fixture
+//! workspace) and `[CWD]`, so snapshots are the same on every machine.
+//! Symlink and shell cases only run on Unix.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use cowork_core::error::ToolError;
+use cowork_core::tools::filesystem::{EditFile, GlobFiles, GrepFiles, ReadFile, WriteFile};
+use cowork_core::tools::shell::ExecuteCommand;
+use cowork_core::tools::{Tool, ToolExecutionContext, ToolOutput};
+use serde_json::{json, Value};
+use tempfile::TempDir;
+
+/// Lines in `big.txt`, more than Read returns by default
+const BIG_FILE_LINES: usize = 2500;
+
+/// A workspace with nested directories, a unicode filename, a large file, a
+/// `.gitignore` and (on Unix) symlinks, next to a directory outside it
+struct Fixture {
+    dir: TempDir,
+}
+
+impl Fixture {
+    fn new() -> Self {
+        let dir = TempDir::new().expect("Failed to create temp dir");
+        let fixture = Self { dir };
+        let root = fixture.root();
+        fs::create_dir_all(root.join("src/nested/deep")).unwrap();
+        fs::create_dir_all(root.join("docs")).unwrap();
+        fs::create_dir_all(root.join("target")).unwrap();
+        fs::create_dir_all(fixture.outside()).unwrap();
+
+        // Written oldest first, with fixed mtimes, so Glob's newest-first
+        // order is stable
+        let files: &[(&str, String)] = &[
+            (".gitignore", "target/\n*.log\n".to_string()),
+            ("README.md", "# Fixture\n\nA workspace for tool tests.\n".to_string()),
+            ("src/main.rs", "fn main() {\n    // TODO: parse arguments\n    println!(\"Hello, world!\");\n}\n".to_string()),
+            ("src/lib.rs", "pub fn greet(name: &str) -> String {\n    format!(\"Hello, {}!\", name)\n}\n".to_string()),
+            ("src/nested/deep/mod.rs", "// TODO: fill in\npub const DEPTH: usize = 3;\n".to_string()),
+            ("docs/ünïcødé 文件.md", "# Ünïcødé\n\nGrüße aus 文件.\n".to_string()),
+            ("notes.txt", "TODO one\nTODO two\ndone\n".to_string()),
+            ("target/build.log", "TODO: ignored by .gitignore\n".to_string()),
+            ("big.txt", (1..=BIG_FILE_LINES).map(|n| format!("line {}\n", n)).collect()),
+        ];
+        for (i, (path, content)) in files.iter().enumerate() {
+            let path = root.join(path);
+            fs::write(&path, content).unwrap();
+            let mtime = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000 + i as u64 * 60);
+            fs::File::options().write(true).open(&path).unwrap().set_modified(mtime).unwrap();
+        }
+        fs::write(fixture.outside().join("secret.txt"), "outside the workspace\n").unwrap();
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink("src/lib.rs", root.join("lib_link.rs")).unwrap();
+            std::os::unix::fs::symlink("../outside/secret.txt", root.join("escape.txt")).unwrap();
+        }
+
+        fixture
+    }
+
+    /// The workspace the tools run in
+    fn root(&self) -> PathBuf {
+        self.dir.path().join("workspace")
+    }
+
+    /// A directory next to the workspace, which tools must not reach
+    fn outside(&self) -> PathBuf {
+        self.dir.path().join("outside")
+    }
+
+    /// What the model sees for `result`, with machine-specific paths redacted
+    fn seen(&self, result: Result<ToolOutput, ToolError>) -> Value {
+        let value = match result {
+            Ok(output) => output.content,
+            Err(e) => Value::String(format!("Error: {}", e)),
+        };
+        let root = self.root();
+        let mut replacements = vec![(root.display().to_string(), "[ROOT]")];
+        if let Ok(canonical) = dunce::canonicalize(&root) {
+            replacements.insert(0, (canonical.display().to_string(), "[ROOT]"));
+        }
+        if let Ok(cwd) = std::env::current_dir() {
+            replacements.push((cwd.display().to_string(), "[CWD]"));
+        }
+        let value = redact(value, &replacements);
+
+        // Glob errors give a position in the pattern joined to the root
+        let position = regex::Regex::new(r"position \d+").unwrap();
+        match value {
+            Value::String(s) => Value::String(position.replace_all(&s, "position [N]").into_owned()),
+            other => other,
+        }
+    }
+}
+
+fn redact(value: Value, replacements: &[(String, &str)]) -> Value {
+    match value {
+        Value::String(s) => Value::String(
+            replacements
+                .iter()
+                .fold(s, |s, (from, to)| s.replace(from.as_str(), to)),
+        ),
+        Value::Array(items) => Value::Array(items.into_iter().map(|v| redact(v, replacements)).collect()),
+        Value::Object(map) => Value::Object(map.into_iter().map(|(k, v)| (k, redact(v, replacements))).collect()),
+        other => other,
+    }
+}
+
+fn test_ctx() -> ToolExecutionContext {
+    ToolExecutionContext::test_auto_approve("test", "test")
+}
+
+/// Run each `(snapshot name, params)` case against a fresh fixture
+async fn check_cases<T: Tool>(make_tool: impl Fn(&Path) -> T, cases: Vec<(&str, Value)>) {
+    for (name, params) in cases {
+        let fixture = Fixture::new();
+        let tool = make_tool(&fixture.root());
+        let seen = fixture.seen(tool.execute(params, test_ctx()).await);
+        insta::assert_json_snapshot!(name, seen);
+    }
+}
+
+#[tokio::test]
+async fn test_read_file_output() {
+    check_cases(
+        |root| ReadFile::new(root.to_path_buf()),
+        vec![
+            ("read_file", json!({"file_path": "src/main.rs"})),
+            ("read_file_offset_limit", json!({"file_path": "notes.txt", "offset": 1, "limit": 1})),
+            ("read_file_unicode_name", json!({"file_path": "docs/ünïcødé 文件.md"})),
+            ("read_file_missing_param", json!({})),
+            ("read_file_not_found", json!({"file_path": "missing.txt"})),
+            ("read_file_directory", json!({"file_path": "src"})),
+            ("read_file_outside_workspace", json!({"file_path": "../outside/secret.txt"})),
+        ],
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_read_file_large_file() {
+    let fixture = Fixture::new();
+    let tool = ReadFile::new(fixture.root());
+    let mut seen = fixture.seen(tool.execute(json!({"file_path": "big.txt"}), test_ctx()).await);
+
+    // Keep the snapshot small: the first and last lines returned stand in
+    // for the content
+    let content = seen["content"].as_str().unwrap().to_string();
+    let lines: Vec<&str> = content.lines().collect();
+    seen["content"] = json!(format!("{}\n...\n{}", lines[0], lines[lines.len() - 1]));
+    insta::assert_json_snapshot!("read_file_large", seen);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_read_file_symlinks() {
+    check_cases(
+        |root| ReadFile::new(root.to_path_buf()),
+        vec![
+            ("read_file_symlink_inside", json!({"file_path": "lib_link.rs"})),
+            ("read_file_symlink_escape", json!({"file_path": "escape.txt"})),
+        ],
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_write_file_output() {
+    check_cases(
+        |root| WriteFile::new(root.to_path_buf()),
+        vec![
+            ("write_file_create", json!({"file_path": "src/new.rs", "content": "pub fn new() {}\n"})),
+            ("write_file_create_dirs", json!({"file_path": "a/b/c.txt", "content": "deep\n"})),
+            ("write_file_overwrite", json!({"file_path": "notes.txt", "content": "TODO one\nrewritten\n"})),
+            ("write_file_unicode_name", json!({"file_path": "docs/ünïcødé 文件.md", "content": "# Neu\n"})),
+            ("write_file_missing_path", json!({"content": "x"})),
+            ("write_file_missing_content", json!({"file_path": "x.txt"})),
+            ("write_file_outside_workspace", json!({"file_path": "../outside/new.txt", "content": "x"})),
+        ],
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_edit_file_output() {
+    check_cases(
+        |root| EditFile::new(root.to_path_buf()),
+        vec![
+            (
+                "edit_file",
+                json!({"file_path": "src/lib.rs", "old_string": "Hello", "new_string": "Hi\nthere"}),
+            ),
+            (
+                "edit_file_replace_all",
+                json!({"file_path": "notes.txt", "old_string": "TODO", "new_string": "DONE", "replace_all": true}),
+            ),
+            (
+                "edit_file_unicode_name",
+                json!({"file_path": "docs/ünïcødé 文件.md", "old_string": "Grüße", "new_string": "Hallo"}),
+            ),
+            ("edit_file_missing_params", json!({"file_path": "notes.txt"})),
+            (
+                "edit_file_same_strings",
+                json!({"file_path": "notes.txt", "old_string": "done", "new_string": "done"}),
+            ),
+            (
+                "edit_file_not_found_string",
+                json!({"file_path": "notes.txt", "old_string": "absent", "new_string": "x"}),
+            ),
+            (
+                "edit_file_ambiguous",
+                json!({"file_path": "notes.txt", "old_string": "TODO", "new_string": "DONE"}),
+            ),
+            (
+                "edit_file_missing_file",
+                json!({"file_path": "missing.txt", "old_string": "a", "new_string": "b"}),
+            ),
+            (
+                "edit_file_outside_workspace",
+                json!({"file_path": "../outside/secret.txt", "old_string": "outside", "new_string": "x"}),
+            ),
+        ],
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_glob_files_output() {
+    check_cases(
+        |root| GlobFiles::new(root.to_path_buf()),
+        vec![
+            ("glob_files_recursive", json!({"pattern": "src/**/*.rs"})),
+            ("glob_files_unicode", json!({"pattern": "docs/*.md"})),
+            ("glob_files_in_path", json!({"pattern": "*.rs", "path": "src/nested/deep"})),
+            ("glob_files_no_matches", json!({"pattern": "**/*.py"})),
+            ("glob_files_missing_pattern", json!({})),
+            ("glob_files_invalid_pattern", json!({"pattern": "src/***"})),
+        ],
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_grep_files_output() {
+    // The built-in search, so the result doesn't depend on ripgrep being installed
+    check_cases(
+        |root| GrepFiles::new(root.to_path_buf()).with_ripgrep(None),
+        vec![
+            ("grep_files_with_matches", json!({"pattern": "TODO"})),
+            ("grep_files_count", json!({"pattern": "TODO", "output_mode": "count"})),
+            (
+                "grep_files_content_context",
+                json!({"pattern": "println", "output_mode": "content", "-C": 1}),
+            ),
+            (
+                "grep_files_case_insensitive_glob",
+                json!({"pattern": "hello, WORLD", "-i": true, "glob": "src/**/*.rs", "output_mode": "content"}),
+            ),
+            ("grep_files_unicode", json!({"pattern": "Grüße", "output_mode": "content"})),
+            (
+                "grep_files_head_limit_offset",
+                json!({"pattern": "^line \\d+0$", "output_mode": "content", "head_limit": 2, "offset": 1}),
+            ),
+            ("grep_files_no_matches", json!({"pattern": "no such text"})),
+            ("grep_files_missing_pattern", json!({})),
+            ("grep_files_invalid_regex", json!({"pattern": "(unclosed"})),
+        ],
+    )
+    .await;
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_execute_command_output() {
+    check_cases(
+        |root| ExecuteCommand::new(root.to_path_buf()),
+        vec![
+            ("execute_command", json!({"command": "echo hello"})),
+            ("execute_command_working_dir", json!({"command": "ls", "working_dir": "src/nested"})),
+            ("execute_command_failure", json!({"command": "echo oops >&2; exit 3"})),
+            ("execute_command_missing_param", json!({})),
+            ("execute_command_blocked", json!({"command": "sudo ls"})),
+            ("execute_command_timeout", json!({"command": "sleep 5", "timeout": 1000})),
+        ],
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_execute_command_rejected() {
+    let fixture = Fixture::new();
+    let tool = ExecuteCommand::new(fixture.root());
+    // Nobody answers the approval request
+    let ctx = ToolExecutionContext::standalone("test", "test");
+    let seen = fixture.seen(tool.execute(json!({"command": "echo hello"}), ctx).await);
+    insta::assert_json_snapshot!("execute_command_rejected", seen);
+}
@@ -54,6 +54,35 @@ mod web_fetch_tests {
         assert!(result.is_err(), "Should fail for invalid URL");
     }
 
+    #[tokio::test]
+    async fn test_fetch_blocked_host() {
+        let tool = WebFetch::new().with_blocked_hosts(["pastebin.com"]);
+
+        // Refused before any request is made
+        let result = tool.execute(json!({
+            "url": "https://raw.PASTEBIN.com/abc",
+            "prompt": "Read it"
+        }), test_ctx()).await;
+
+        let err = result.unwrap_err().to_string();
+        assert!(err.starts_with("Policy violation: "), "{}", err);
+        assert!(err.contains("blocked_hosts: pastebin.com"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_metadata_address_refused() {
+        let tool = WebFetch::new();
+
+        let result = tool.execute(json!({
+            "url": "http://169.254.169.254/latest/meta-data/",
+            "prompt": "Read it"
+        }), test_ctx()).await;
+
+        let err = result.unwrap_err();
+        assert!(matches!(err, cowork_core::error::ToolError::PolicyViolation(_)), "{:?}", err);
+        assert!(err.to_string().contains("link-local address: 169.254.169.254"), "{}", err);
+    }
+
     #[tokio::test]
     async fn test_fetch_non_http_scheme() {
         let tool = WebFetch::new();
@@ -0,0 +1,123 @@
+//! Tests for git worktree isolation
+//!
+//! Worktrees are created in throwaway repositories: branching from HEAD,
+//! reopening, summarizing, merging back, conflicts and refusals.
+
+use std::path::Path;
+use std::process::Command;
+
+use cowork_core::session::{Worktree, WORKTREES_DIR};
+use cowork_core::Error;
+use tempfile::TempDir;
+
+fn git(dir: &Path, args: &[&str]) -> String {
+    let output = Command::new("git").arg("-C").arg(dir).args(args).output().expect("git failed to run");
+    assert!(output.status.success(), "git {:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+/// A repository with one commit of `notes.txt`, on branch main
+fn repo() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    git(dir.path(), &["init", "--quiet", "--initial-branch", "main"]);
+    git(dir.path(), &["config", "user.email", "test@test.com"]);
+    git(dir.path(), &["config", "user.name", "Test User"]);
+    std::fs::write(dir.path().join("notes.txt"), "one\ntwo\n").unwrap();
+    git(dir.path(), &["add", "notes.txt"]);
+    git(dir.path(), &["commit", "--quiet", "-m", "Initial commit"]);
+    dir
+}
+
+fn commit_all(dir: &Path, message: &str) {
+    git(dir, &["add", "--all"]);
+    git(dir, &["commit", "--quiet", "-m", message]);
+}
+
+#[test]
+fn test_worktree_is_isolated_and_merged_back() {
+    let repo = repo();
+    let worktree = Worktree::open_or_create(repo.path(), "session-1").unwrap();
+    assert_eq!(worktree.branch, "cowork/session-1");
+    assert!(worktree.path.ends_with(format!("{}/session-1", WORKTREES_DIR)));
+    assert_eq!(worktree.workspace(), worktree.path);
+    assert!(worktree.workspace().join("notes.txt").exists());
+    // Reopening finds the same worktree
+    assert_eq!(Worktree::open_or_create(repo.path(), "session-1").unwrap(), worktree);
+
+    // One committed change, one left uncommitted
+    std::fs::write(worktree.path.join("notes.txt"), "one\ntwo\nthree\n").unwrap();
+    commit_all(&worktree.path, "Add three");
+    std::fs::write(worktree.path.join("todo.txt"), "merge me\n").unwrap();
+
+    // The repository's branch and status are untouched
+    assert_eq!(std::fs::read_to_string(repo.path().join("notes.txt")).unwrap(), "one\ntwo\n");
+    assert_eq!(git(repo.path(), &["status", "--short"]), "");
+
+    let summary = worktree.summary().unwrap();
+    assert_eq!(summary.target, "main");
+    assert_eq!(summary.commits.len(), 1);
+    assert!(summary.commits[0].ends_with("Add three"));
+    assert_eq!(summary.uncommitted, vec!["?? todo.txt"]);
+    assert!(summary.diff_stat.contains("notes.txt"), "{}", summary.diff_stat);
+    assert!(summary.to_string().contains("cowork/session-1 -> main"));
+
+    let merged = worktree.merge().unwrap();
+    assert_eq!(merged, summary);
+    assert_eq!(std::fs::read_to_string(repo.path().join("notes.txt")).unwrap(), "one\ntwo\nthree\n");
+    assert_eq!(std::fs::read_to_string(repo.path().join("todo.txt")).unwrap(), "merge me\n");
+    assert!(!worktree.path.exists());
+    assert_eq!(git(repo.path(), &["branch", "--list", "cowork/*"]), "");
+    assert_eq!(git(repo.path(), &["status", "--short"]), "");
+}
+
+#[test]
+fn test_merge_conflict_keeps_worktree() {
+    let repo = repo();
+    let worktree = Worktree::open_or_create(repo.path(), "session-2").unwrap();
+    std::fs::write(worktree.path.join("notes.txt"), "one\nTWO from the agent\n").unwrap();
+    commit_all(&worktree.path, "Agent edit");
+    std::fs::write(repo.path().join("notes.txt"), "one\nTWO from the user\n").unwrap();
+    commit_all(repo.path(), "User edit");
+
+    match worktree.merge() {
+        Err(Error::MergeConflict(branch, files)) => {
+            assert_eq!(branch, "cowork/session-2");
+            assert_eq!(files, vec!["notes.txt"]);
+        }
+        other => panic!("expected a merge conflict, got {:?}", other),
+    }
+    // The merge was aborted and the worktree kept for resolving by hand
+    assert_eq!(git(repo.path(), &["status", "--short"]), "");
+    assert_eq!(std::fs::read_to_string(repo.path().join("notes.txt")).unwrap(), "one\nTWO from the user\n");
+    assert_eq!(std::fs::read_to_string(worktree.path.join("notes.txt")).unwrap(), "one\nTWO from the agent\n");
+
+    worktree.remove().unwrap();
+    assert!(!worktree.path.exists());
+    assert_eq!(git(repo.path(), &["branch", "--list", "cowork/*"]), "");
+}
+
+#[test]
+fn test_worktree_refused_without_a_commit() {
+    let plain = TempDir::new().unwrap();
+    let err = Worktree::open_or_create(plain.path(), "session-3").unwrap_err();
+    assert!(err.to_string().contains("is not a git repository"), "{}", err);
+
+    let empty = TempDir::new().unwrap();
+    git(empty.path(), &["init", "--quiet"]);
+    let err = Worktree::open_or_create(empty.path(), "session-3").unwrap_err();
+    assert!(err.to_string().contains("has no commits yet"), "{}", err);
+    assert!(!empty.path().join(WORKTREES_DIR).exists());
+}
+
+#[test]
+fn test_subdirectory_workspace_maps_into_worktree() {
+    let repo = repo();
+    std::fs::create_dir(repo.path().join("app")).unwrap();
+    std::fs::write(repo.path().join("app/main.rs"), "fn main() {}\n").unwrap();
+    commit_all(repo.path(), "Add app");
+
+    let worktree = Worktree::open_or_create(&repo.path().join("app"), "session-4").unwrap();
+    assert_eq!(worktree.workspace(), worktree.path.join("app"));
+    assert!(worktree.workspace().join("main.rs").exists());
+    worktree.remove().unwrap();
+}
@@ -71,6 +71,24 @@ impl<T: Transport> McpClient<T> {
         }
     }
 
+    /// Send any request and return its result
+    pub async fn request(
+        &self,
+        method: &str,
+        params: Option<serde_json::Value>,
+    ) -> Result<serde_json::Value, McpError> {
+        let mut request = JsonRpcRequest::new(self.next_id(), method);
+        request.params = params;
+
+        let response = self.send_request(request).await?;
+
+        if let Some(error) = response.error {
+            Err(McpError::Server(error.message))
+        } else {
+            Ok(response.result.unwrap_or(serde_json::Value::Null))
+        }
+    }
+
     /// List available tools
     pub async fn list_tools(&self) -> Result<Vec<McpTool>, McpError> {
         let request = JsonRpcRequest::new(self.next_id(), methods::TOOLS_LIST);
@@ -125,6 +143,23 @@ impl<T: Transport> McpClient<T> {
         }
     }
 
+    /// Read a resource
+    pub async fn read_resource(&self, uri: &str) -> Result<Vec<ResourceContents>, McpError> {
+        let request = JsonRpcRequest::new(self.next_id(), methods::RESOURCES_READ)
+            .with_params(serde_json::json!({ "uri": uri }));
+        let response = self.send_request(request).await?;
+
+        if let Some(result) = response.result {
+            let read_result: ResourceReadResult = serde_json::from_value(result)
+                .map_err(|e| McpError::Protocol(e.to_string()))?;
+            Ok(read_result.contents)
+        } else if let Some(error) = response.error {
+            Err(McpError::Server(error.message))
+        } else {
+            Err(McpError::Protocol("Empty response".to_string()))
+        }
+    }
+
     async fn send_request(&self, request: JsonRpcRequest) -> Result<JsonRpcResponse, McpError> {
         let mut transport = self.transport.lock().await;
 
@@ -134,12 +169,20 @@ impl<T: Transport> McpClient<T> {
         transport.send(request_value).await
             .map_err(|e| McpError::Transport(e.to_string()))?;
 
-        let response_value = transport.receive().await
-            .map_err(|e| McpError::Transport(e.to_string()))?
-            .ok_or_else(|| McpError::Transport("Connection closed".to_string()))?;
-
-        serde_json::from_value(response_value)
-            .map_err(|e| McpError::Protocol(e.to_string()))
+        // Skip notifications and server requests until our response arrives
+        let id = serde_json::to_value(&request.id)
+            .map_err(|e| McpError::Protocol(e.to_string()))?;
+        loop {
+            let message = transport.receive().await
+                .map_err(|e| McpError::Transport(e.to_string()))?
+                .ok_or_else(|| McpError::Transport("Connection closed".to_string()))?;
+
+            if message.get("method").is_none() && message.get("id") == Some(&id) {
+                return serde_json::from_value(message)
+                    .map_err(|e| McpError::Protocol(e.to_string()));
+            }
+            tracing::debug!("Skipping MCP message while waiting for {}: {}", id, message);
+        }
     }
 }
 
@@ -178,6 +221,20 @@ struct ResourcesListResult {
     resources: Vec<McpResource>,
 }
 
+#[derive(Debug, serde::Deserialize)]
+struct ResourceReadResult {
+    contents: Vec<ResourceContents>,
+}
+
+/// One entry of a `resources/read` result
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct ResourceContents {
+    pub uri: String,
+    #[serde(rename = "mimeType")]
+    pub mime_type: Option<String>,
+    pub text: Option<String>,
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct ToolCallResult {
     pub content: Vec<ContentItem>,
@@ -185,6 +242,16 @@ pub struct ToolCallResult {
     pub is_error: bool,
 }
 
+impl ToolCallResult {
+    /// A result with a single text block
+    pub fn text(text: impl Into<String>, is_error: bool) -> Self {
+        Self {
+            content: vec![ContentItem::text(text)],
+            is_error,
+        }
+    }
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct ContentItem {
     #[serde(rename = "type")]
@@ -192,6 +259,15 @@ pub struct ContentItem {
     pub text: Option<String>,
 }
 
+impl ContentItem {
+    pub fn text(text: impl Into<String>) -> Self {
+        Self {
+            content_type: "text".to_string(),
+            text: Some(text.into()),
+        }
+    }
+}
+
 /// MCP errors
 #[derive(Debug, thiserror::Error)]
 pub enum McpError {
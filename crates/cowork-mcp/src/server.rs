@@ -1,8 +1,16 @@
 //! MCP Server implementation
+//!
+//! `McpServer` answers JSON-RPC requests through an `McpHandler`;
+//! `serve` runs it over a newline-delimited stream such as stdio.
 
 use std::collections::HashMap;
+use std::io;
 use std::sync::Arc;
 
+use serde_json::Value;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+
+use crate::client::ToolCallResult;
 use crate::protocol::{methods, JsonRpcError, JsonRpcRequest, JsonRpcResponse, RequestId};
 use crate::{McpPrompt, McpResource, McpTool, ServerCapabilities, PROTOCOL_VERSION};
 
@@ -13,11 +21,13 @@ pub trait McpHandler: Send + Sync {
     async fn list_tools(&self) -> Vec<McpTool>;
 
     /// Call a tool
+    ///
+    /// An `Err` is reported to the client as a tool result with `isError` set.
     async fn call_tool(
         &self,
         name: &str,
         arguments: serde_json::Value,
-    ) -> Result<serde_json::Value, String>;
+    ) -> Result<ToolCallResult, String>;
 
     /// List available resources
     async fn list_resources(&self) -> Vec<McpResource>;
@@ -88,10 +98,62 @@ impl<H: McpHandler> McpServer<H> {
         self
     }
 
+    pub fn with_version(mut self, version: impl Into<String>) -> Self {
+        self.server_version = version.into();
+        self
+    }
+
+    /// Set the capabilities advertised in the `initialize` response
+    pub fn with_capabilities(mut self, capabilities: ServerCapabilities) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// Serve newline-delimited JSON-RPC messages from `reader` until it closes
+    ///
+    /// Each request gets one response line on `writer`; notifications
+    /// (messages without an `id`) get none.
+    pub async fn serve<R, W>(&self, reader: R, mut writer: W) -> io::Result<()>
+    where
+        R: AsyncBufRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        let mut lines = reader.lines();
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let response = match serde_json::from_str::<Value>(&line) {
+                Err(_) => error_response(Value::Null, JsonRpcError::parse_error()),
+                Ok(message) => match message.get("id").cloned() {
+                    None => {
+                        tracing::debug!(method = ?message.get("method"), "Ignoring notification");
+                        continue;
+                    }
+                    Some(id) => match serde_json::from_value::<JsonRpcRequest>(message) {
+                        Ok(request) => serde_json::to_value(self.handle_request(request).await)?,
+                        Err(_) => error_response(id, JsonRpcError::invalid_request()),
+                    },
+                },
+            };
+            let mut out = serde_json::to_vec(&response)?;
+            out.push(b'\n');
+            writer.write_all(&out).await?;
+            writer.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// Serve on this process's stdin and stdout
+    pub async fn serve_stdio(&self) -> io::Result<()> {
+        self.serve(BufReader::new(tokio::io::stdin()), tokio::io::stdout()).await
+    }
+
     /// Handle a JSON-RPC request
     pub async fn handle_request(&self, request: JsonRpcRequest) -> JsonRpcResponse {
         match request.method.as_str() {
             methods::INITIALIZE => self.handle_initialize(request.id).await,
+            methods::PING => JsonRpcResponse::success(request.id, serde_json::json!({})),
             methods::TOOLS_LIST => self.handle_tools_list(request.id).await,
             methods::TOOLS_CALL => self.handle_tools_call(request.id, request.params).await,
             methods::RESOURCES_LIST => self.handle_resources_list(request.id).await,
@@ -140,33 +202,14 @@ impl<H: McpHandler> McpServer<H> {
             .cloned()
             .unwrap_or(serde_json::Value::Object(Default::default()));
 
-        match self.handler.call_tool(name, arguments).await {
-            Ok(result) => {
-                let content = vec![serde_json::json!({
-                    "type": "text",
-                    "text": result.to_string()
-                })];
-                JsonRpcResponse::success(
-                    id,
-                    serde_json::json!({
-                        "content": content,
-                        "isError": false
-                    }),
-                )
-            }
-            Err(e) => {
-                let content = vec![serde_json::json!({
-                    "type": "text",
-                    "text": e
-                })];
-                JsonRpcResponse::success(
-                    id,
-                    serde_json::json!({
-                        "content": content,
-                        "isError": true
-                    }),
-                )
-            }
+        let result = self
+            .handler
+            .call_tool(name, arguments)
+            .await
+            .unwrap_or_else(|e| ToolCallResult::text(e, true));
+        match serde_json::to_value(result) {
+            Ok(result) => JsonRpcResponse::success(id, result),
+            Err(_) => JsonRpcResponse::error(id, JsonRpcError::internal_error()),
         }
     }
 
@@ -259,3 +302,12 @@ impl<H: McpHandler> McpServer<H> {
         }
     }
 }
+
+/// An error response to a message whose id couldn't be read as a `RequestId`
+fn error_response(id: Value, error: JsonRpcError) -> Value {
+    serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": error
+    })
+}
@@ -1,9 +1,17 @@
 //! MCP Transport layer implementations
 
-use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
 use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use eventsource_stream::Eventsource;
+use futures::StreamExt;
+use serde_json::Value;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::process::{Child, Command};
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
 
 /// Transport trait for MCP communication
 #[allow(async_fn_in_trait)]
@@ -75,40 +83,401 @@ impl Transport for StdioTransport {
     }
 }
 
-/// SSE transport for HTTP-based communication
-pub struct SseTransport {
-    base_url: String,
+/// Default time to wait for the endpoint, a POST, or the next message
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Longest wait between attempts to reopen a dropped event stream
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(10);
+
+/// HTTP transport with Server-Sent Events (MCP 2024-11-05)
+///
+/// A GET to the server URL opens an event stream whose `endpoint` event
+/// names the URL to POST JSON-RPC messages to. Responses and
+/// server-initiated messages arrive as `message` events. A server that
+/// doesn't offer a stream (404/405, or no `text/event-stream`) is used in
+/// POST-only mode, with each response taken from the POST's JSON body.
+///
+/// A dropped stream is reopened with exponential backoff. Requests still
+/// waiting for a response are posted again to the new endpoint, and only
+/// the first response for each request id is passed on.
+pub struct HttpSseTransport {
+    url: String,
     client: reqwest::Client,
+    headers: HashMap<String, String>,
+    timeout: Duration,
+    reconnect_attempts: u32,
+    reconnect_delay: Duration,
+    connection: Option<Connection>,
+    /// Responses returned in POST bodies, ahead of the event stream
+    buffered: VecDeque<Value>,
+}
+
+/// An open connection: the endpoint to POST to and the incoming messages
+struct Connection {
+    endpoint: watch::Receiver<Option<String>>,
+    incoming: mpsc::UnboundedReceiver<Value>,
+    pending: Pending,
+    reader: Option<JoinHandle<()>>,
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        if let Some(reader) = self.reader.take() {
+            reader.abort();
+        }
+    }
+}
+
+/// Requests sent and not yet answered, keyed by their id as JSON text
+#[derive(Clone, Default)]
+struct Pending(Arc<Mutex<HashMap<String, Value>>>);
+
+impl Pending {
+    fn insert(&self, message: &Value) -> Option<String> {
+        let key = request_key(message)?;
+        self.0.lock().unwrap().insert(key.clone(), message.clone());
+        Some(key)
+    }
+
+    fn remove(&self, key: &str) {
+        self.0.lock().unwrap().remove(key);
+    }
+
+    fn requests(&self) -> Vec<Value> {
+        self.0.lock().unwrap().values().cloned().collect()
+    }
+
+    /// Whether to pass `message` on: false for a second response to a request
+    fn accept(&self, message: &Value) -> bool {
+        match response_key(message) {
+            Some(key) => self.0.lock().unwrap().remove(&key).is_some(),
+            None => true,
+        }
+    }
 }
 
-impl SseTransport {
-    pub fn new(base_url: impl Into<String>) -> Self {
+/// Id of a request (has both `id` and `method`)
+fn request_key(message: &Value) -> Option<String> {
+    message.get("method")?;
+    Some(message.get("id")?.to_string())
+}
+
+/// Id of a response (has `id` but no `method`)
+fn response_key(message: &Value) -> Option<String> {
+    if message.get("method").is_some() {
+        return None;
+    }
+    Some(message.get("id")?.to_string())
+}
+
+impl HttpSseTransport {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self::with_client(url, reqwest::Client::new())
+    }
+
+    /// Use a preconfigured client (proxy, CA certificates)
+    ///
+    /// The client's own timeout also ends the event stream, which is then
+    /// reopened; leave it unset or long.
+    pub fn with_client(url: impl Into<String>, client: reqwest::Client) -> Self {
         Self {
-            base_url: base_url.into(),
-            client: reqwest::Client::new(),
+            url: url.into(),
+            client,
+            headers: HashMap::new(),
+            timeout: DEFAULT_TIMEOUT,
+            reconnect_attempts: 5,
+            reconnect_delay: Duration::from_millis(250),
+            connection: None,
+            buffered: VecDeque::new(),
+        }
+    }
+
+    /// Headers sent with every request (e.g. `Authorization`)
+    pub fn with_headers(mut self, headers: HashMap<String, String>) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    /// Time to wait for the endpoint, a POST, or the next message
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// How often to try reopening a dropped stream, and the first delay
+    /// (doubled on each failed attempt)
+    pub fn with_reconnect(mut self, attempts: u32, initial_delay: Duration) -> Self {
+        self.reconnect_attempts = attempts;
+        self.reconnect_delay = initial_delay;
+        self
+    }
+
+    /// Open the event stream
+    ///
+    /// Called by the first `send` if not called before.
+    pub async fn connect(&mut self) -> io::Result<()> {
+        let response = get_stream(&self.client, &self.url, &self.headers, self.timeout).await?;
+        let status = response.status();
+        let pending = Pending::default();
+        let (incoming_tx, incoming) = mpsc::unbounded_channel();
+
+        let connection = if is_event_stream(&response) {
+            let (endpoint_tx, endpoint) = watch::channel(None);
+            let stream = EventStream {
+                url: self.url.clone(),
+                client: self.client.clone(),
+                headers: self.headers.clone(),
+                timeout: self.timeout,
+                reconnect_attempts: self.reconnect_attempts,
+                reconnect_delay: self.reconnect_delay,
+                pending: pending.clone(),
+                endpoint: endpoint_tx,
+                incoming: incoming_tx,
+            };
+            Connection {
+                endpoint,
+                incoming,
+                pending,
+                reader: Some(tokio::spawn(stream.run(response))),
+            }
+        } else if status.is_success() || matches!(status.as_u16(), 404 | 405) {
+            tracing::debug!("MCP server at {} has no event stream; using POST responses", self.url);
+            let (_, endpoint) = watch::channel(Some(self.url.clone()));
+            drop(incoming_tx);
+            Connection { endpoint, incoming, pending, reader: None }
+        } else {
+            return Err(io::Error::other(format!("MCP server returned HTTP {}", status)));
+        };
+
+        self.connection = Some(connection);
+        Ok(())
+    }
+
+    /// The endpoint to POST to, waiting while the stream is (re)opened
+    async fn endpoint(&mut self) -> io::Result<String> {
+        let connection = self.connection.as_mut().ok_or_else(not_connected)?;
+        match tokio::time::timeout(self.timeout, connection.endpoint.wait_for(Option::is_some)).await {
+            Ok(Ok(endpoint)) => Ok(endpoint.clone().unwrap_or_default()),
+            Ok(Err(_)) => Err(stream_closed()),
+            Err(_) => Err(io::Error::new(io::ErrorKind::TimedOut, "Timed out waiting for the MCP endpoint")),
         }
     }
 }
 
-impl Transport for SseTransport {
+impl Transport for HttpSseTransport {
     async fn send(&mut self, message: Value) -> io::Result<()> {
-        self.client
-            .post(&self.base_url)
-            .json(&message)
-            .send()
-            .await
-            .map_err(io::Error::other)?;
+        if self.connection.is_none() {
+            self.connect().await?;
+        }
+        let endpoint = self.endpoint().await?;
+        let pending = self.connection.as_ref().ok_or_else(not_connected)?.pending.clone();
+        let key = pending.insert(&message);
 
-        Ok(())
+        match post(&self.client, &endpoint, &self.headers, self.timeout, &message).await {
+            Ok(replies) => {
+                self.buffered.extend(replies.into_iter().filter(|reply| pending.accept(reply)));
+                Ok(())
+            }
+            Err(e) => {
+                if let Some(key) = key {
+                    pending.remove(&key);
+                }
+                Err(e)
+            }
+        }
     }
 
     async fn receive(&mut self) -> io::Result<Option<Value>> {
-        // SSE receive would need event stream handling
-        // Placeholder for now
-        Ok(None)
+        if let Some(message) = self.buffered.pop_front() {
+            return Ok(Some(message));
+        }
+        let connection = self.connection.as_mut().ok_or_else(not_connected)?;
+        tokio::time::timeout(self.timeout, connection.incoming.recv())
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "Timed out waiting for the MCP server"))
     }
 
     async fn close(&mut self) -> io::Result<()> {
+        self.connection = None;
+        self.buffered.clear();
         Ok(())
     }
 }
+
+/// Background reader for the event stream, reopening it when it drops
+struct EventStream {
+    url: String,
+    client: reqwest::Client,
+    headers: HashMap<String, String>,
+    timeout: Duration,
+    reconnect_attempts: u32,
+    reconnect_delay: Duration,
+    pending: Pending,
+    /// None while the stream is being reopened
+    endpoint: watch::Sender<Option<String>>,
+    incoming: mpsc::UnboundedSender<Value>,
+}
+
+impl EventStream {
+    async fn run(self, first: reqwest::Response) {
+        let mut response = Some(first);
+        let mut failures = 0;
+        let mut reopened = false;
+
+        loop {
+            if let Some(stream) = response.take() {
+                match self.read(stream, reopened).await {
+                    Ok(true) => failures = 0,
+                    Ok(false) => {}
+                    Err(()) => return,
+                }
+            }
+            self.endpoint.send_replace(None);
+
+            failures += 1;
+            if failures > self.reconnect_attempts || self.incoming.is_closed() {
+                tracing::warn!("Giving up on the MCP event stream at {}", self.url);
+                return;
+            }
+            let delay = self.reconnect_delay.saturating_mul(1 << (failures - 1).min(16)).min(MAX_RECONNECT_DELAY);
+            tokio::time::sleep(delay).await;
+
+            match get_stream(&self.client, &self.url, &self.headers, self.timeout).await {
+                Ok(stream) if stream.status().is_success() && is_event_stream(&stream) => {
+                    response = Some(stream);
+                    reopened = true;
+                }
+                Ok(stream) => tracing::warn!("Reopening MCP event stream at {}: HTTP {}", self.url, stream.status()),
+                Err(e) => tracing::warn!("Reopening MCP event stream at {}: {}", self.url, e),
+            }
+        }
+    }
+
+    /// Read events until the stream ends; returns whether an endpoint was
+    /// received, or Err when the transport was dropped
+    async fn read(&self, response: reqwest::Response, reopened: bool) -> Result<bool, ()> {
+        let mut got_endpoint = false;
+        let mut events = response.bytes_stream().eventsource();
+        while let Some(event) = events.next().await {
+            let event = match event {
+                Ok(event) => event,
+                Err(e) => {
+                    tracing::warn!("MCP event stream at {} dropped: {}", self.url, e);
+                    break;
+                }
+            };
+            match event.event.as_str() {
+                "endpoint" => {
+                    let endpoint = resolve_endpoint(&self.url, event.data.trim());
+                    got_endpoint = true;
+                    if reopened {
+                        self.repost(&endpoint).await?;
+                    }
+                    self.endpoint.send_replace(Some(endpoint));
+                }
+                "message" => match serde_json::from_str::<Value>(&event.data) {
+                    Ok(message) => self.deliver(message)?,
+                    Err(e) => tracing::warn!("Ignoring malformed MCP message: {}", e),
+                },
+                _ => {}
+            }
+        }
+        Ok(got_endpoint)
+    }
+
+    /// Post requests still waiting for a response to a new endpoint
+    async fn repost(&self, endpoint: &str) -> Result<(), ()> {
+        for request in self.pending.requests() {
+            tracing::debug!("Re-sending MCP request {} after reconnect", request["id"]);
+            match post(&self.client, endpoint, &self.headers, self.timeout, &request).await {
+                Ok(replies) => {
+                    for reply in replies {
+                        self.deliver(reply)?;
+                    }
+                }
+                Err(e) => tracing::warn!("Re-sending MCP request {}: {}", request["id"], e),
+            }
+        }
+        Ok(())
+    }
+
+    fn deliver(&self, message: Value) -> Result<(), ()> {
+        if !self.pending.accept(&message) {
+            return Ok(());
+        }
+        self.incoming.send(message).map_err(|_| ())
+    }
+}
+
+async fn get_stream(
+    client: &reqwest::Client,
+    url: &str,
+    headers: &HashMap<String, String>,
+    timeout: Duration,
+) -> io::Result<reqwest::Response> {
+    let mut request = client.get(url).header("Accept", "text/event-stream");
+    for (key, value) in headers {
+        request = request.header(key, value);
+    }
+    // The timeout covers the response headers only; the body is the stream
+    tokio::time::timeout(timeout, request.send())
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "Timed out connecting to the MCP server"))?
+        .map_err(io::Error::other)
+}
+
+/// POST a message; returns any JSON-RPC messages in the response body
+async fn post(
+    client: &reqwest::Client,
+    endpoint: &str,
+    headers: &HashMap<String, String>,
+    timeout: Duration,
+    message: &Value,
+) -> io::Result<Vec<Value>> {
+    let mut request = client.post(endpoint).timeout(timeout).json(message);
+    for (key, value) in headers {
+        request = request.header(key, value);
+    }
+    let response = request.send().await.map_err(io::Error::other)?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(io::Error::other(format!("MCP server returned HTTP {}", status)));
+    }
+    let is_json = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("json"));
+    if !is_json {
+        return Ok(Vec::new());
+    }
+    match response.json::<Value>().await.map_err(io::Error::other)? {
+        Value::Array(messages) => Ok(messages),
+        Value::Null => Ok(Vec::new()),
+        message => Ok(vec![message]),
+    }
+}
+
+fn is_event_stream(response: &reqwest::Response) -> bool {
+    response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/event-stream"))
+}
+
+/// The endpoint event's URL, which may be relative to the stream's
+fn resolve_endpoint(base: &str, endpoint: &str) -> String {
+    reqwest::Url::parse(base)
+        .and_then(|base| base.join(endpoint))
+        .map(String::from)
+        .unwrap_or_else(|_| endpoint.to_string())
+}
+
+fn not_connected() -> io::Error {
+    io::Error::new(io::ErrorKind::NotConnected, "MCP transport is not connected")
+}
+
+fn stream_closed() -> io::Error {
+    io::Error::new(io::ErrorKind::ConnectionAborted, "MCP event stream closed")
+}
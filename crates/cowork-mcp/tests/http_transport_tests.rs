@@ -0,0 +1,187 @@
+//! HttpSseTransport against a small in-process MCP server
+//!
+//! The server speaks the 2024-11-05 HTTP+SSE protocol: GET /sse opens the
+//! event stream and announces the POST endpoint, and responses come back as
+//! `message` events. POST /mcp answers in the response body instead.
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::sse::{Event, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use cowork_mcp::client::{ClientInfo, McpClient};
+use cowork_mcp::transport::HttpSseTransport;
+use futures::Stream;
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+
+const TOKEN: &str = "Bearer secret";
+
+#[derive(Default)]
+struct ServerState {
+    /// Sender for the open event stream
+    stream: Mutex<Option<mpsc::UnboundedSender<Event>>>,
+    /// Streams opened so far
+    connections: AtomicUsize,
+    /// Drop the stream instead of answering the next tools/list
+    drop_next_tools_list: AtomicBool,
+}
+
+fn authorized(headers: &HeaderMap) -> bool {
+    headers.get("authorization").and_then(|v| v.to_str().ok()) == Some(TOKEN)
+}
+
+/// The response to a request, None for notifications and unanswered methods
+fn respond(message: &Value) -> Option<Value> {
+    let result = match message["method"].as_str()? {
+        "initialize" => json!({
+            "protocolVersion": "2024-11-05",
+            "capabilities": { "tools": { "listChanged": false } },
+            "serverInfo": { "name": "test-server", "version": "1.2.3" }
+        }),
+        "tools/list" => json!({
+            "tools": [{
+                "name": "echo",
+                "description": "Echo the input",
+                "inputSchema": { "type": "object" }
+            }]
+        }),
+        _ => return None,
+    };
+    Some(json!({ "jsonrpc": "2.0", "id": message.get("id")?, "result": result }))
+}
+
+async fn open_stream(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, StatusCode> {
+    if !authorized(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    let connection = state.connections.fetch_add(1, Ordering::SeqCst);
+    let (tx, rx) = mpsc::unbounded_channel();
+    tx.send(Event::default().event("endpoint").data(format!("/message?session={}", connection)))
+        .unwrap();
+    *state.stream.lock().unwrap() = Some(tx);
+
+    let events = futures::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|event| (Ok(event), rx))
+    });
+    Ok(Sse::new(events))
+}
+
+async fn post_message(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    Json(message): Json<Value>,
+) -> StatusCode {
+    if !authorized(&headers) {
+        return StatusCode::UNAUTHORIZED;
+    }
+    if message["method"] == "tools/list" && state.drop_next_tools_list.swap(false, Ordering::SeqCst) {
+        // Close the stream with the request unanswered
+        state.stream.lock().unwrap().take();
+        return StatusCode::ACCEPTED;
+    }
+    if let Some(response) = respond(&message) {
+        let stream = state.stream.lock().unwrap();
+        let tx = stream.as_ref().expect("no event stream");
+        let log = json!({ "jsonrpc": "2.0", "method": "notifications/message", "params": { "data": "working" } });
+        tx.send(Event::default().event("message").data(log.to_string())).unwrap();
+        tx.send(Event::default().event("message").data(response.to_string())).unwrap();
+    }
+    StatusCode::ACCEPTED
+}
+
+/// Answers in the POST body; GET is not allowed
+async fn post_only(Json(message): Json<Value>) -> Response {
+    match respond(&message) {
+        Some(response) => Json(response).into_response(),
+        None => StatusCode::ACCEPTED.into_response(),
+    }
+}
+
+async fn serve() -> (String, Arc<ServerState>) {
+    let state = Arc::new(ServerState::default());
+    let app = Router::new()
+        .route("/sse", get(open_stream))
+        .route("/message", post(post_message))
+        .route("/mcp", post(post_only))
+        .with_state(state.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    (format!("http://{}", addr), state)
+}
+
+fn transport(url: String) -> HttpSseTransport {
+    HttpSseTransport::new(url)
+        .with_headers(HashMap::from([("Authorization".to_string(), TOKEN.to_string())]))
+        .with_timeout(Duration::from_secs(5))
+        .with_reconnect(3, Duration::from_millis(20))
+}
+
+fn client_info() -> ClientInfo {
+    ClientInfo {
+        name: "cowork-test".to_string(),
+        version: "0.0.0".to_string(),
+    }
+}
+
+#[tokio::test]
+async fn test_initialize_and_list_tools_over_sse() {
+    let (base, _state) = serve().await;
+    let mut client = McpClient::new(transport(format!("{}/sse", base)));
+
+    let server = client.initialize(client_info()).await.unwrap();
+    assert_eq!(server.name, "test-server");
+    assert_eq!(server.version, "1.2.3");
+
+    let tools = client.list_tools().await.unwrap();
+    assert_eq!(tools.len(), 1);
+    assert_eq!(tools[0].name, "echo");
+}
+
+#[tokio::test]
+async fn test_pending_request_survives_reconnect() {
+    let (base, state) = serve().await;
+    let mut client = McpClient::new(transport(format!("{}/sse", base)));
+    client.initialize(client_info()).await.unwrap();
+
+    state.drop_next_tools_list.store(true, Ordering::SeqCst);
+    let tools = client.list_tools().await.unwrap();
+
+    assert_eq!(tools[0].name, "echo");
+    assert_eq!(state.connections.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_post_only_server() {
+    let (base, _state) = serve().await;
+    let mut client = McpClient::new(transport(format!("{}/mcp", base)));
+
+    let server = client.initialize(client_info()).await.unwrap();
+    assert_eq!(server.name, "test-server");
+    assert_eq!(client.list_tools().await.unwrap()[0].name, "echo");
+}
+
+#[tokio::test]
+async fn test_headers_and_timeout() {
+    let (base, _state) = serve().await;
+
+    let mut unauthorized = McpClient::new(HttpSseTransport::new(format!("{}/sse", base)));
+    let err = unauthorized.initialize(client_info()).await.unwrap_err();
+    assert!(err.to_string().contains("401"), "{}", err);
+
+    let mut client = McpClient::new(transport(format!("{}/sse", base)).with_timeout(Duration::from_millis(200)));
+    client.initialize(client_info()).await.unwrap();
+    let err = client.request("tools/call", Some(json!({ "name": "never-answered" }))).await.unwrap_err();
+    assert!(err.to_string().contains("Timed out"), "{}", err);
+}
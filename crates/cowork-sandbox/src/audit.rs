@@ -0,0 +1,385 @@
+//! Audit trail of policy denials and near-misses
+//!
+//! Commands run through `Sandbox::execute` are checked before they start and
+//! their stderr is scanned after they exit. The records found are attached
+//! to the `SandboxResult` (commands refused up front only get the error) and,
+//! when `SandboxConfig::audit_log_path` is set, appended to that file as
+//! JSONL.
+//!
+//! The process backend doesn't trace syscalls, so denials reported by the OS
+//! are recovered from "Permission denied"-style messages on stderr and
+//! attributed to the policy rule that covers the path or host.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::env::wildcard_match;
+use crate::{FilesystemPolicy, NetworkPolicy, NetworkRule, SandboxConfig};
+
+/// What a record is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ViolationKind {
+    Filesystem,
+    Network,
+    Command,
+    /// A resource limit the process ran into
+    Resource,
+}
+
+/// Whether the operation was stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ViolationOutcome {
+    /// Refused by the sandbox or the OS
+    Denied,
+    /// Allowed, but matched a watch pattern
+    NearMiss,
+}
+
+/// One denied (or watched) operation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PolicyViolationRecord {
+    pub kind: ViolationKind,
+    pub outcome: ViolationOutcome,
+    /// Path, host, or command that was attempted
+    pub target: String,
+    /// The rule responsible, e.g. `blocked_paths: /etc` or `watch: *.pem`
+    pub rule: String,
+    /// The command line that made the attempt
+    pub command: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl PolicyViolationRecord {
+    pub fn new(
+        kind: ViolationKind,
+        outcome: ViolationOutcome,
+        target: impl Into<String>,
+        rule: impl Into<String>,
+        command: impl Into<String>,
+    ) -> Self {
+        Self {
+            kind,
+            outcome,
+            target: target.into(),
+            rule: rule.into(),
+            command: command.into(),
+            timestamp: Utc::now(),
+        }
+    }
+}
+
+/// Whether `path` is `base` or inside it
+///
+/// Verbatim (`\\?\`) prefixes are dropped on both sides, and components
+/// compare case-insensitively on Windows, whose filesystems ignore case.
+pub(crate) fn path_within(path: &Path, base: &Path) -> bool {
+    components_within(dunce::simplified(path), dunce::simplified(base), cfg!(windows))
+}
+
+fn components_within(path: &Path, base: &Path, ignore_case: bool) -> bool {
+    if !ignore_case {
+        return path.starts_with(base);
+    }
+    let mut path_components = path.components();
+    base.components().all(|b| {
+        path_components.next().is_some_and(|p| {
+            p.as_os_str().to_string_lossy().to_lowercase() == b.as_os_str().to_string_lossy().to_lowercase()
+        })
+    })
+}
+
+/// The most specific `blocked_paths` rule covering `path`
+pub fn blocked_rule(policy: &FilesystemPolicy, path: &Path) -> Option<String> {
+    policy
+        .blocked_paths
+        .iter()
+        .filter(|b| path_within(path, b))
+        .max_by_key(|b| b.components().count())
+        .map(|b| format!("blocked_paths: {}", b.display()))
+}
+
+/// The filesystem rule that denies `path`
+///
+/// Falls back to the OS when no sandbox rule covers the path.
+pub fn filesystem_rule(policy: &FilesystemPolicy, path: &Path) -> String {
+    if let Some(rule) = blocked_rule(policy, path) {
+        return rule;
+    }
+    let listed = |paths: &std::collections::HashSet<PathBuf>| paths.iter().any(|p| path_within(path, p));
+    if !policy.read_paths.is_empty() && !listed(&policy.read_paths) {
+        return "read_paths: not listed".to_string();
+    }
+    if !policy.write_paths.is_empty() && !listed(&policy.write_paths) {
+        return "write_paths: not listed".to_string();
+    }
+    "none (OS permissions)".to_string()
+}
+
+/// The network rule that denies `host`, if any
+pub fn network_rule(policy: &NetworkPolicy, host: Option<&str>) -> Option<String> {
+    if !policy.enabled {
+        return Some(NetworkRule::Disabled.to_string());
+    }
+    policy.host_rule(host?).map(|rule| rule.to_string())
+}
+
+/// Commands and arguments matching one of the config's watch patterns
+pub fn near_misses(config: &SandboxConfig, command: &str, args: &[&str]) -> Vec<PolicyViolationRecord> {
+    let command_line = command_line(command, args);
+    std::iter::once(command)
+        .chain(args.iter().copied())
+        .enumerate()
+        .filter_map(|(i, target)| {
+            let pattern = config.watch_patterns.iter().find(|p| wildcard_match(p, target))?;
+            let kind = if i > 0 && looks_like_path(target) {
+                ViolationKind::Filesystem
+            } else {
+                ViolationKind::Command
+            };
+            Some(PolicyViolationRecord::new(
+                kind,
+                ViolationOutcome::NearMiss,
+                target,
+                format!("watch: {}", pattern),
+                &command_line,
+            ))
+        })
+        .collect()
+}
+
+/// Denials reported on a command's stderr
+pub fn scan_stderr(config: &SandboxConfig, command_line: &str, stderr: &str) -> Vec<PolicyViolationRecord> {
+    let mut records = Vec::new();
+    for line in stderr.lines() {
+        if let Some(path) = denied_path(line) {
+            let rule = filesystem_rule(&config.filesystem, Path::new(&path));
+            records.push(PolicyViolationRecord::new(
+                ViolationKind::Filesystem,
+                ViolationOutcome::Denied,
+                path,
+                rule,
+                command_line,
+            ));
+        } else if let Some(host) = unreachable_host(line)
+            && let Some(rule) = network_rule(&config.network, host.as_deref())
+        {
+            records.push(PolicyViolationRecord::new(
+                ViolationKind::Network,
+                ViolationOutcome::Denied,
+                host.unwrap_or_else(|| "unknown host".to_string()),
+                rule,
+                command_line,
+            ));
+        }
+    }
+    records
+}
+
+/// The resource limit that killed a process, from its exit status
+///
+/// Only limits the kernel enforces with a signal can be told apart:
+/// `max_cpu_time` (SIGXCPU) and `max_file_size` (SIGXFSZ).
+pub fn exit_violation(
+    config: &SandboxConfig,
+    command_line: &str,
+    status: &std::process::ExitStatus,
+) -> Option<PolicyViolationRecord> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+
+        // Same numbers on Linux and macOS
+        const SIGXCPU: i32 = 24;
+        const SIGXFSZ: i32 = 25;
+
+        let (target, rule) = match status.signal()? {
+            SIGXCPU => ("cpu time", format!("max_cpu_time: {}s", config.limits.max_cpu_time)),
+            SIGXFSZ => ("file size", format!("max_file_size: {} bytes", config.limits.max_file_size)),
+            _ => return None,
+        };
+        Some(PolicyViolationRecord::new(
+            ViolationKind::Resource,
+            ViolationOutcome::Denied,
+            target,
+            rule,
+            command_line,
+        ))
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = (config, command_line, status);
+        None
+    }
+}
+
+/// Append records to a JSONL audit log, creating it if needed
+pub fn append_audit_log(path: &Path, records: &[PolicyViolationRecord]) -> std::io::Result<()> {
+    if records.is_empty() {
+        return Ok(());
+    }
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+    for record in records {
+        writeln!(file, "{}", serde_json::to_string(record).map_err(std::io::Error::other)?)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn command_line(command: &str, args: &[&str]) -> String {
+    std::iter::once(command).chain(args.iter().copied()).collect::<Vec<_>>().join(" ")
+}
+
+/// The path in a "Permission denied" / "Operation not permitted" message
+///
+/// Handles `cat: /x: Permission denied`, `ls: cannot open directory '/x':
+/// Permission denied` and `PermissionError: [Errno 13] Permission denied: '/x'`.
+fn denied_path(line: &str) -> Option<String> {
+    let segments: Vec<&str> = line.split(": ").collect();
+    let at = segments
+        .iter()
+        .position(|s| s.contains("Permission denied") || s.contains("Operation not permitted"))?;
+    // Path after the message (Python), else before it (coreutils, shells)
+    [segments.get(at + 1), at.checked_sub(1).and_then(|i| segments.get(i))]
+        .into_iter()
+        .flatten()
+        .find_map(|segment| {
+            segment
+                .split_whitespace()
+                .map(|token| token.trim_matches(|c| matches!(c, '\'' | '"' | '`' | '‘' | '’')))
+                .rfind(|token| looks_like_path(token))
+                .map(str::to_string)
+        })
+}
+
+/// The host in a failed name lookup or connection (None = host not named)
+fn unreachable_host(line: &str) -> Option<Option<String>> {
+    let host_after = |marker: &str| {
+        line.split_once(marker)
+            .and_then(|(_, rest)| rest.split([' ', ':']).next())
+            .filter(|host| !host.is_empty())
+            .map(str::to_string)
+    };
+    if line.contains("Could not resolve host") {
+        return Some(host_after("resolve host: "));
+    }
+    if line.contains("Network is unreachable") || line.contains("Temporary failure in name resolution") {
+        return Some(host_after("connect to "));
+    }
+    None
+}
+
+fn looks_like_path(token: &str) -> bool {
+    token.starts_with('/') || token.starts_with("./") || token.starts_with("../") || token.starts_with('~')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> SandboxConfig {
+        let mut config = SandboxConfig::default();
+        config.filesystem.blocked_paths.insert(PathBuf::from("/data/secret"));
+        config.filesystem.read_paths.insert(PathBuf::from("/workspace"));
+        config.watch_patterns = vec!["*.pem".to_string(), "/usr/bin/ssh".to_string()];
+        config
+    }
+
+    #[test]
+    fn test_denied_reads_attributed_to_rules() {
+        let stderr = "cat: /data/secret/key.pem: Permission denied\n\
+                      ls: cannot open directory '/opt/private': Permission denied\n\
+                      PermissionError: [Errno 13] Permission denied: '/workspace/locked.txt'\n\
+                      some other error";
+        let records = scan_stderr(&config(), "sh run.sh", stderr);
+        let found: Vec<(&str, &str)> = records.iter().map(|r| (r.target.as_str(), r.rule.as_str())).collect();
+        assert_eq!(
+            found,
+            vec![
+                ("/data/secret/key.pem", "blocked_paths: /data/secret"),
+                ("/opt/private", "read_paths: not listed"),
+                ("/workspace/locked.txt", "none (OS permissions)"),
+            ]
+        );
+        assert!(records.iter().all(|r| r.kind == ViolationKind::Filesystem && r.outcome == ViolationOutcome::Denied));
+        assert_eq!(records[0].command, "sh run.sh");
+    }
+
+    #[test]
+    fn test_network_denials() {
+        let mut config = config();
+        let stderr = "curl: (6) Could not resolve host: example.com";
+        let records = scan_stderr(&config, "curl https://example.com", stderr);
+        assert_eq!(records[0].kind, ViolationKind::Network);
+        assert_eq!(records[0].target, "example.com");
+        assert_eq!(records[0].rule, "network.enabled: false");
+
+        // With the network on, only blocked hosts are the sandbox's doing
+        config.network = NetworkPolicy::allow_all();
+        assert!(scan_stderr(&config, "curl", stderr).is_empty());
+        config.network.blocked_hosts.insert("example.com".to_string());
+        assert_eq!(scan_stderr(&config, "curl", stderr)[0].rule, "blocked_hosts: example.com");
+    }
+
+    #[test]
+    fn test_near_misses() {
+        let records = near_misses(&config(), "/usr/bin/ssh", &["-i", "/home/me/id.pem", "host"]);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].kind, ViolationKind::Command);
+        assert_eq!(records[0].rule, "watch: /usr/bin/ssh");
+        assert_eq!(records[1].kind, ViolationKind::Filesystem);
+        assert_eq!(records[1].target, "/home/me/id.pem");
+        assert_eq!(records[1].outcome, ViolationOutcome::NearMiss);
+        assert!(near_misses(&config(), "/bin/ls", &["/workspace"]).is_empty());
+    }
+
+    #[test]
+    fn test_most_specific_blocked_rule() {
+        let mut policy = FilesystemPolicy::default();
+        policy.blocked_paths.insert(PathBuf::from("/srv"));
+        policy.blocked_paths.insert(PathBuf::from("/srv/keys"));
+        assert_eq!(filesystem_rule(&policy, Path::new("/srv/keys/a")), "blocked_paths: /srv/keys");
+    }
+
+    #[test]
+    fn test_components_within_ignoring_case() {
+        assert!(components_within(Path::new("/Srv/Keys/a"), Path::new("/srv/keys"), true));
+        assert!(!components_within(Path::new("/Srv/Keys/a"), Path::new("/srv/keys"), false));
+        assert!(!components_within(Path::new("/srv/keysmith"), Path::new("/srv/keys"), true));
+        assert!(!components_within(Path::new("/srv"), Path::new("/srv/keys"), true));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_exit_violation() {
+        use std::os::unix::process::ExitStatusExt;
+
+        let config = config();
+        let record = exit_violation(&config, "node server.js", &std::process::ExitStatus::from_raw(24)).unwrap();
+        assert_eq!(record.kind, ViolationKind::Resource);
+        assert_eq!(record.rule, format!("max_cpu_time: {}s", config.limits.max_cpu_time));
+        assert_eq!(record.command, "node server.js");
+        // Killed (SIGKILL) or exited normally: nothing to attribute
+        assert!(exit_violation(&config, "node", &std::process::ExitStatus::from_raw(9)).is_none());
+        assert!(exit_violation(&config, "node", &std::process::ExitStatus::from_raw(1 << 8)).is_none());
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_blocked_rule_matches_any_case_and_unc() {
+        let mut policy = FilesystemPolicy::default();
+        policy.blocked_paths.insert(PathBuf::from(r"C:\Secrets"));
+        assert!(blocked_rule(&policy, Path::new(r"c:\secrets\key.pem")).is_some());
+        assert!(blocked_rule(&policy, Path::new(r"\\?\C:\SECRETS\key.pem")).is_some());
+        assert!(blocked_rule(&policy, Path::new(r"C:\Public\key.pem")).is_none());
+    }
+}
@@ -106,6 +106,7 @@ impl ContainerSandbox {
                 memory_used: 0,
                 killed: !output.status.success() && output.status.code().is_none(),
                 kill_reason: None,
+                violations: Vec::new(),
             }),
             Ok(Err(e)) => Err(SandboxError::Execution(e.to_string())),
             Err(_) => Ok(SandboxResult {
@@ -116,6 +117,7 @@ impl ContainerSandbox {
                 memory_used: 0,
                 killed: true,
                 kill_reason: Some("Timeout".to_string()),
+                violations: Vec::new(),
             }),
         }
     }
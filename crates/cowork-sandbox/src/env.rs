@@ -0,0 +1,246 @@
+//! Environment policy for spawned commands
+//!
+//! Decides which variables a child process sees — parent variables on an
+//! allowlist plus explicitly configured ones — and masks the values of
+//! secret-looking variables in captured output. Shared by the sandbox
+//! executor and the agent's shell tool.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Replacement text for masked secret values
+pub const MASK: &str = "***";
+
+/// Secret values shorter than this are not masked (too likely to match
+/// unrelated output)
+const MIN_SECRET_LEN: usize = 4;
+
+/// Parent variables passed through by default
+pub const DEFAULT_ENV_ALLOWLIST: &[&str] = &[
+    // Basics
+    "PATH", "HOME", "USER", "LOGNAME", "SHELL", "TERM", "LANG", "LANGUAGE", "LC_*", "TZ",
+    "TMPDIR", "TMP", "TEMP",
+    // Windows essentials
+    "SYSTEMROOT", "SYSTEMDRIVE", "WINDIR", "COMSPEC", "PATHEXT", "USERPROFILE", "APPDATA",
+    "LOCALAPPDATA", "PROGRAMFILES", "PROGRAMFILES(X86)", "PROGRAMDATA",
+    // Toolchains
+    "CARGO_HOME", "RUSTUP_HOME", "RUSTUP_TOOLCHAIN", "GOPATH", "GOROOT", "GOCACHE",
+    "JAVA_HOME", "NVM_DIR", "NODE_PATH", "PYENV_ROOT", "VIRTUAL_ENV", "CONDA_PREFIX",
+    "GEM_HOME", "GEM_PATH", "PKG_CONFIG_PATH", "CC", "CXX",
+];
+
+/// Variable name patterns whose values are masked in output by default
+pub const DEFAULT_SECRET_PATTERNS: &[&str] = &["*KEY*", "*TOKEN*", "*SECRET*", "*PASSWORD*"];
+
+/// Which environment variables a child process gets, and which are secret
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EnvPolicy {
+    /// Variables set for every command, overriding inherited values
+    pub vars: BTreeMap<String, String>,
+    /// Parent variable names (or `*` globs) passed through to the child
+    pub allowlist: Vec<String>,
+    /// Variable name globs whose values are masked in output
+    pub secret_patterns: Vec<String>,
+}
+
+impl Default for EnvPolicy {
+    fn default() -> Self {
+        Self {
+            vars: BTreeMap::new(),
+            allowlist: DEFAULT_ENV_ALLOWLIST.iter().map(|s| s.to_string()).collect(),
+            secret_patterns: DEFAULT_SECRET_PATTERNS.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+}
+
+impl EnvPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set a variable for every command
+    pub fn with_var(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.vars.insert(name.into(), value.into());
+        self
+    }
+
+    /// Replace the parent variable allowlist
+    pub fn with_allowlist(mut self, allowlist: Vec<String>) -> Self {
+        self.allowlist = allowlist;
+        self
+    }
+
+    /// Replace the secret name patterns
+    pub fn with_secret_patterns(mut self, patterns: Vec<String>) -> Self {
+        self.secret_patterns = patterns;
+        self
+    }
+
+    /// Whether a parent variable may be passed through
+    pub fn is_allowed(&self, name: &str) -> bool {
+        self.allowlist.iter().any(|pattern| glob_match(pattern, name))
+    }
+
+    /// Whether a variable's value should be masked
+    pub fn is_secret(&self, name: &str) -> bool {
+        self.secret_patterns.iter().any(|pattern| glob_match(pattern, name))
+    }
+
+    /// The child environment built from the current process environment
+    pub fn environment(&self) -> Vec<(String, String)> {
+        self.environment_from(std::env::vars())
+    }
+
+    /// The child environment built from the given parent variables
+    pub fn environment_from(
+        &self,
+        parent: impl IntoIterator<Item = (String, String)>,
+    ) -> Vec<(String, String)> {
+        let mut env: BTreeMap<String, String> = parent
+            .into_iter()
+            .filter(|(name, _)| self.is_allowed(name))
+            .collect();
+        env.extend(self.vars.clone());
+        env.into_iter().collect()
+    }
+
+    /// Masker for the current process environment and configured vars
+    pub fn masker(&self) -> SecretMasker {
+        self.masker_from(std::env::vars())
+    }
+
+    /// Masker for the given parent variables and configured vars
+    ///
+    /// Secrets are collected from the whole parent environment, not just the
+    /// allowlisted part, so values read back from files or config still get
+    /// masked.
+    pub fn masker_from(&self, parent: impl IntoIterator<Item = (String, String)>) -> SecretMasker {
+        let secrets = parent
+            .into_iter()
+            .chain(self.vars.clone())
+            .filter(|(name, _)| self.is_secret(name))
+            .map(|(_, value)| value);
+        SecretMasker::new(secrets)
+    }
+}
+
+/// Replaces known secret values in text with `***`
+#[derive(Debug, Clone, Default)]
+pub struct SecretMasker {
+    /// Longest first, so a secret containing another is masked whole
+    secrets: Vec<String>,
+}
+
+impl SecretMasker {
+    pub fn new(secrets: impl IntoIterator<Item = String>) -> Self {
+        let mut secrets: Vec<String> = secrets
+            .into_iter()
+            .filter(|s| s.chars().count() >= MIN_SECRET_LEN)
+            .collect();
+        secrets.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        secrets.dedup();
+        Self { secrets }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.secrets.is_empty()
+    }
+
+    /// Mask every secret value in `text`
+    pub fn mask(&self, text: &str) -> String {
+        let mut masked = text.to_string();
+        for secret in &self.secrets {
+            if masked.contains(secret.as_str()) {
+                masked = masked.replace(secret.as_str(), MASK);
+            }
+        }
+        masked
+    }
+}
+
+/// Case-insensitive glob match supporting `*` wildcards
+fn glob_match(pattern: &str, name: &str) -> bool {
+    wildcard_match(&pattern.to_ascii_uppercase(), &name.to_ascii_uppercase())
+}
+
+/// Case-sensitive match supporting `*` wildcards (`*` also matches `/`)
+pub(crate) fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No wildcard: exact match
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(idx) => rest = &rest[idx + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("PATH", "PATH"));
+        assert!(glob_match("path", "PATH"));
+        assert!(!glob_match("PATH", "PATHEXT"));
+        assert!(glob_match("LC_*", "LC_ALL"));
+        assert!(glob_match("*KEY*", "OPENAI_API_KEY"));
+        assert!(glob_match("*KEY*", "KEYRING"));
+        assert!(glob_match("A*B*C", "AxxBxxC"));
+        assert!(!glob_match("A*B*C", "AxxCxxB"));
+        assert!(!glob_match("AB*BA", "ABA"));
+    }
+
+    #[test]
+    fn test_environment_filters_parent() {
+        let policy = EnvPolicy::new().with_var("DATABASE_URL", "postgres://localhost/test");
+        let env = policy.environment_from(vars(&[
+            ("PATH", "/usr/bin"),
+            ("LC_ALL", "C"),
+            ("AWS_SECRET_ACCESS_KEY", "abcd1234"),
+            ("RANDOM_THING", "x"),
+        ]));
+        let names: Vec<&str> = env.iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(names, vec!["DATABASE_URL", "LC_ALL", "PATH"]);
+    }
+
+    #[test]
+    fn test_configured_vars_override_parent() {
+        let policy = EnvPolicy::new().with_var("PATH", "/opt/bin");
+        let env = policy.environment_from(vars(&[("PATH", "/usr/bin")]));
+        assert_eq!(env, vars(&[("PATH", "/opt/bin")]));
+    }
+
+    #[test]
+    fn test_masks_secret_values() {
+        let policy = EnvPolicy::new().with_var("DEPLOY_TOKEN", "tok-123456");
+        let masker = policy.masker_from(vars(&[
+            ("OPENAI_API_KEY", "sk-abcdef"),
+            ("HOME", "/home/me"),
+            ("SHORT_KEY", "ab"),
+        ]));
+        let text = "key=sk-abcdef token=tok-123456 home=/home/me short=ab";
+        assert_eq!(masker.mask(text), "key=*** token=*** home=/home/me short=ab");
+    }
+
+    #[test]
+    fn test_longer_secret_masked_first() {
+        let masker = SecretMasker::new(vec!["abcd".to_string(), "abcdefgh".to_string()]);
+        assert_eq!(masker.mask("abcdefgh abcd"), "*** ***");
+    }
+}
@@ -3,10 +3,17 @@
 //! This crate provides sandboxing capabilities for running untrusted code
 //! and commands in a secure, isolated environment.
 
+pub mod audit;
 pub mod container;
+pub mod env;
+pub mod network;
 pub mod policy;
 pub mod process;
 
+pub use audit::{PolicyViolationRecord, ViolationKind, ViolationOutcome};
+pub use env::{EnvPolicy, SecretMasker};
+pub use network::{AddressClass, NetworkRule, address_class};
+
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::path::PathBuf;
@@ -22,6 +29,16 @@ pub struct SandboxConfig {
     pub filesystem: FilesystemPolicy,
     /// Resource limits
     pub limits: ResourceLimits,
+    /// Environment passed to commands and secrets masked in their output
+    #[serde(default)]
+    pub env: EnvPolicy,
+    /// File policy violations are appended to as JSONL (None = not logged)
+    #[serde(default)]
+    pub audit_log_path: Option<PathBuf>,
+    /// Globs for commands and arguments that are allowed but recorded as
+    /// near-misses, e.g. `*.pem` or `/usr/bin/ssh`
+    #[serde(default)]
+    pub watch_patterns: Vec<String>,
 }
 
 impl Default for SandboxConfig {
@@ -31,6 +48,9 @@ impl Default for SandboxConfig {
             network: NetworkPolicy::default(),
             filesystem: FilesystemPolicy::default(),
             limits: ResourceLimits::default(),
+            env: EnvPolicy::default(),
+            audit_log_path: None,
+            watch_patterns: Vec::new(),
         }
     }
 }
@@ -38,12 +58,14 @@ impl Default for SandboxConfig {
 /// Network access policy
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[derive(Default)]
+#[serde(default)]
 pub struct NetworkPolicy {
     /// Allow network access
     pub enabled: bool,
-    /// Allowed hosts (if empty, all allowed when enabled)
+    /// Allowed hosts (if empty, all allowed when enabled); `*` wildcards
+    /// allowed, and see [`network`] for internal addresses
     pub allowed_hosts: HashSet<String>,
-    /// Blocked hosts
+    /// Blocked hosts and addresses; `*` wildcards allowed
     pub blocked_hosts: HashSet<String>,
 }
 
@@ -59,10 +81,26 @@ impl NetworkPolicy {
     pub fn deny_all() -> Self {
         Self::default()
     }
+
+    /// Add hosts to the blocklist, keeping those already blocked
+    pub fn block_hosts<I, S>(&mut self, hosts: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.blocked_hosts
+            .extend(hosts.into_iter().map(|h| h.as_ref().trim().trim_end_matches('.').to_ascii_lowercase()));
+    }
+
+    /// Whether `host` is blocked, directly or as a subdomain of a blocked host
+    pub fn is_host_blocked(&self, host: &str) -> bool {
+        matches!(self.host_rule(host), Some(NetworkRule::BlockedHost(_)))
+    }
 }
 
 /// Filesystem access policy
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct FilesystemPolicy {
     /// Readable paths
     pub read_paths: HashSet<PathBuf>,
@@ -104,6 +142,7 @@ impl Default for FilesystemPolicy {
 
 /// Resource limits for sandboxed processes
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ResourceLimits {
     /// Maximum memory in bytes
     pub max_memory: u64,
@@ -130,6 +169,8 @@ impl Default for ResourceLimits {
 }
 
 /// Sandbox execution result
+///
+/// `stdout` and `stderr` have secret environment values masked.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SandboxResult {
     pub exit_code: i32,
@@ -139,6 +180,9 @@ pub struct SandboxResult {
     pub memory_used: u64,
     pub killed: bool,
     pub kill_reason: Option<String>,
+    /// Denials and near-misses seen while running the command
+    #[serde(default)]
+    pub violations: Vec<PolicyViolationRecord>,
 }
 
 /// Sandbox errors
@@ -167,31 +211,170 @@ impl Sandbox {
     }
 
     /// Execute a command in the sandbox
+    ///
+    /// Denials and near-misses are returned in `SandboxResult::violations`
+    /// and written to the audit log, if one is configured.
     pub async fn execute(
         &self,
         command: &str,
         args: &[&str],
     ) -> Result<SandboxResult, SandboxError> {
+        let mut violations = audit::near_misses(&self.config, command, args);
+
         // Validate command against policy
-        self.validate_command(command)?;
+        if let Err(denied) = self.validate_command(command, args) {
+            let message = format!("Command {} is in blocked path ({})", command, denied.rule);
+            violations.push(denied);
+            self.audit(&violations);
+            return Err(SandboxError::PolicyViolation(message));
+        }
 
         // Execute using process sandboxing
-        process::execute_sandboxed(&self.config, command, args).await
+        let mut result = process::execute_sandboxed(&self.config, command, args).await?;
+        violations.append(&mut result.violations);
+        result.violations = violations;
+        self.audit(&result.violations);
+        Ok(result)
     }
 
-    fn validate_command(&self, command: &str) -> Result<(), SandboxError> {
+    fn validate_command(&self, command: &str, args: &[&str]) -> Result<(), PolicyViolationRecord> {
         // Check if command path is allowed
         let path = PathBuf::from(command);
 
-        for blocked in &self.config.filesystem.blocked_paths {
-            if path.starts_with(blocked) {
-                return Err(SandboxError::PolicyViolation(format!(
-                    "Command {} is in blocked path",
-                    command
-                )));
-            }
+        if let Some(rule) = audit::blocked_rule(&self.config.filesystem, &path) {
+            return Err(PolicyViolationRecord::new(
+                ViolationKind::Command,
+                ViolationOutcome::Denied,
+                command,
+                rule,
+                audit::command_line(command, args),
+            ));
         }
 
         Ok(())
     }
+
+    /// Append records to the audit log (failures are logged, not returned)
+    fn audit(&self, records: &[PolicyViolationRecord]) {
+        if let Some(path) = &self.config.audit_log_path
+            && let Err(e) = audit::append_audit_log(path, records)
+        {
+            tracing::warn!("Failed to write sandbox audit log {}: {}", path.display(), e);
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn sandbox_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("cowork-sandbox-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn read_log(path: &std::path::Path) -> Vec<PolicyViolationRecord> {
+        std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_blocked_hosts() {
+        let mut network = NetworkPolicy::allow_all();
+        network.blocked_hosts.insert("tracker.io".to_string());
+        network.block_hosts(["Paste.Example.COM", "tracker.io"]);
+        assert_eq!(network.blocked_hosts.len(), 2);
+
+        assert!(network.is_host_blocked("paste.example.com"));
+        assert!(network.is_host_blocked("raw.PASTE.example.com."));
+        assert!(network.is_host_blocked("tracker.io"));
+        assert!(!network.is_host_blocked("example.com"));
+        assert!(!network.is_host_blocked("notpaste.example.com"));
+        assert!(!NetworkPolicy::allow_all().is_host_blocked("tracker.io"));
+    }
+
+    #[tokio::test]
+    async fn test_blocked_command_is_audited() {
+        let dir = sandbox_dir("blocked");
+        let log = dir.join("audit.jsonl");
+        let mut config = SandboxConfig {
+            root: dir.clone(),
+            audit_log_path: Some(log.clone()),
+            ..Default::default()
+        };
+        config.filesystem.blocked_paths.insert(dir.join("tools"));
+        let sandbox = Sandbox::new(config);
+
+        let command = dir.join("tools/deploy");
+        let err = sandbox.execute(command.to_str().unwrap(), &["--prod"]).await.unwrap_err();
+        assert!(matches!(err, SandboxError::PolicyViolation(_)));
+
+        let records = read_log(&log);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].kind, ViolationKind::Command);
+        assert_eq!(records[0].outcome, ViolationOutcome::Denied);
+        assert_eq!(records[0].rule, format!("blocked_paths: {}", dir.join("tools").display()));
+        assert!(records[0].command.ends_with("deploy --prod"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_watched_argument_is_a_near_miss() {
+        let dir = sandbox_dir("watched");
+        let log = dir.join("audit.jsonl");
+        let key = dir.join("deploy.pem");
+        std::fs::write(&key, "key").unwrap();
+        let config = SandboxConfig {
+            root: dir.clone(),
+            audit_log_path: Some(log.clone()),
+            watch_patterns: vec!["*.pem".to_string()],
+            ..Default::default()
+        };
+
+        let result = Sandbox::new(config).execute("cat", &[key.to_str().unwrap()]).await.unwrap();
+        assert_eq!(result.stdout, "key");
+        assert_eq!(result.violations.len(), 1);
+        assert_eq!(result.violations[0].kind, ViolationKind::Filesystem);
+        assert_eq!(result.violations[0].outcome, ViolationOutcome::NearMiss);
+        assert_eq!(result.violations[0].rule, "watch: *.pem");
+        assert_eq!(read_log(&log), result.violations);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_spawned_process_is_confined() {
+        use std::io::{Read, Write};
+
+        let dir = sandbox_dir("spawn");
+        let config = policy::SecurityLevel::Standard.to_config(dir.clone());
+
+        // Kernels without Landlock refuse rather than run unconfined
+        let mut child = match process::spawn_sandboxed(&config, "cat", &[]) {
+            Err(SandboxError::Creation(e)) => {
+                eprintln!("skipping: {}", e);
+                return;
+            }
+            other => other.unwrap(),
+        };
+        child.stdin.take().unwrap().write_all(b"ping\n").unwrap();
+        let mut out = String::new();
+        child.stdout.take().unwrap().read_to_string(&mut out).unwrap();
+        assert_eq!(out, "ping\n");
+        assert!(child.wait().unwrap().success());
+
+        // /etc isn't readable under the standard preset
+        let child = process::spawn_sandboxed(&config, "cat", &["/etc/hostname"]).unwrap();
+        let output = child.wait_with_output().unwrap();
+        assert!(!output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let records = audit::scan_stderr(&config, "cat /etc/hostname", &stderr);
+        assert_eq!(records[0].rule, "read_paths: not listed");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -0,0 +1,274 @@
+//! Network policy evaluation
+//!
+//! One place decides whether a host or address may be reached, so the
+//! sandbox's audit log and the web tools report the same rules.
+//!
+//! Host patterns in `allowed_hosts` and `blocked_hosts` are matched without
+//! regard to case. A pattern with `*` is a wildcard (`*.internal`,
+//! `10.0.*`); one without matches the host and its subdomains.
+//!
+//! Loopback, private and link-local addresses are refused even when the
+//! network is enabled, unless the host (or the address itself) matches an
+//! `allowed_hosts` entry. A bare `*` does not count: internal addresses
+//! must be listed on purpose.
+
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use crate::NetworkPolicy;
+use crate::env::wildcard_match;
+
+/// Kind of network an address belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressClass {
+    /// 127.0.0.0/8 and ::1
+    Loopback,
+    /// RFC 1918, shared (100.64.0.0/10) and unique local (fc00::/7) ranges
+    Private,
+    /// 169.254.0.0/16 and fe80::/10, where cloud metadata services live
+    LinkLocal,
+    /// 0.0.0.0/8 and ::, which reach the local machine
+    Unspecified,
+    /// Everything else
+    Public,
+}
+
+impl AddressClass {
+    /// Whether the address is on the local machine or network
+    pub fn is_internal(self) -> bool {
+        self != AddressClass::Public
+    }
+}
+
+impl fmt::Display for AddressClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            AddressClass::Loopback => "loopback",
+            AddressClass::Private => "private",
+            AddressClass::LinkLocal => "link-local",
+            AddressClass::Unspecified => "unspecified",
+            AddressClass::Public => "public",
+        })
+    }
+}
+
+/// Classify an address; IPv4-mapped IPv6 addresses are classified as IPv4
+pub fn address_class(ip: IpAddr) -> AddressClass {
+    match ip {
+        IpAddr::V4(v4) => ipv4_class(v4),
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => ipv4_class(v4),
+            None => ipv6_class(v6),
+        },
+    }
+}
+
+fn ipv4_class(ip: Ipv4Addr) -> AddressClass {
+    let [a, b, ..] = ip.octets();
+    if ip.is_loopback() {
+        AddressClass::Loopback
+    } else if ip.is_private() || (a == 100 && (64..128).contains(&b)) {
+        AddressClass::Private
+    } else if ip.is_link_local() {
+        AddressClass::LinkLocal
+    } else if a == 0 {
+        AddressClass::Unspecified
+    } else {
+        AddressClass::Public
+    }
+}
+
+fn ipv6_class(ip: Ipv6Addr) -> AddressClass {
+    let first = ip.segments()[0];
+    if ip.is_loopback() {
+        AddressClass::Loopback
+    } else if ip.is_unspecified() {
+        AddressClass::Unspecified
+    } else if first & 0xfe00 == 0xfc00 {
+        AddressClass::Private
+    } else if first & 0xffc0 == 0xfe80 {
+        AddressClass::LinkLocal
+    } else {
+        AddressClass::Public
+    }
+}
+
+/// The rule that refuses a host or address
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NetworkRule {
+    /// Network access is off
+    Disabled,
+    /// The host matches this `blocked_hosts` pattern
+    BlockedHost(String),
+    /// `allowed_hosts` is set and the host isn't in it
+    NotAllowed,
+    /// The host resolved to an internal address that isn't allow-listed
+    InternalAddress { ip: IpAddr, class: AddressClass },
+}
+
+impl fmt::Display for NetworkRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetworkRule::Disabled => f.write_str("network.enabled: false"),
+            NetworkRule::BlockedHost(pattern) => write!(f, "blocked_hosts: {}", pattern),
+            NetworkRule::NotAllowed => f.write_str("allowed_hosts: not listed"),
+            NetworkRule::InternalAddress { ip, class } => write!(f, "{} address: {}", class, ip),
+        }
+    }
+}
+
+/// Whether `host` matches `pattern` (both already lowercase)
+fn host_matches(pattern: &str, host: &str) -> bool {
+    if pattern.contains('*') {
+        return wildcard_match(pattern, host);
+    }
+    host == pattern || host.strip_suffix(pattern).is_some_and(|rest| rest.ends_with('.'))
+}
+
+/// A host as patterns see it: lowercase, no trailing dot, IPv6 unbracketed
+fn normalize_host(host: &str) -> String {
+    host.trim()
+        .trim_end_matches('.')
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_ascii_lowercase()
+}
+
+impl NetworkPolicy {
+    /// The `blocked_hosts` pattern matching `host`, if any
+    fn blocked_pattern(&self, host: &str) -> Option<&String> {
+        self.blocked_hosts
+            .iter()
+            .find(|pattern| host_matches(&normalize_host(pattern), host))
+    }
+
+    /// Whether an `allowed_hosts` entry other than a bare `*` matches `host`
+    fn explicitly_allowed(&self, host: &str) -> bool {
+        self.allowed_hosts
+            .iter()
+            .map(|pattern| normalize_host(pattern))
+            .any(|pattern| pattern != "*" && host_matches(&pattern, host))
+    }
+
+    /// The rule refusing `host` by name, if any
+    ///
+    /// Addresses aren't looked at; callers that connect also check each
+    /// address the host resolves to with [`address_rule`](Self::address_rule).
+    pub fn host_rule(&self, host: &str) -> Option<NetworkRule> {
+        if !self.enabled {
+            return Some(NetworkRule::Disabled);
+        }
+        let host = normalize_host(host);
+        if let Some(pattern) = self.blocked_pattern(&host) {
+            return Some(NetworkRule::BlockedHost(pattern.clone()));
+        }
+        let allowed = self.allowed_hosts.is_empty()
+            || self
+                .allowed_hosts
+                .iter()
+                .any(|pattern| host_matches(&normalize_host(pattern), &host));
+        (!allowed).then_some(NetworkRule::NotAllowed)
+    }
+
+    /// The rule refusing a connection to `ip`, which `host` resolved to
+    ///
+    /// Checked for every address a name resolves to, since the name alone
+    /// says nothing about where it points.
+    pub fn address_rule(&self, host: &str, ip: IpAddr) -> Option<NetworkRule> {
+        if !self.enabled {
+            return Some(NetworkRule::Disabled);
+        }
+        let host = normalize_host(host);
+        let address = ip.to_string();
+        if let Some(pattern) = self.blocked_pattern(&address) {
+            return Some(NetworkRule::BlockedHost(pattern.clone()));
+        }
+        let class = address_class(ip);
+        if class.is_internal() && !self.explicitly_allowed(&host) && !self.explicitly_allowed(&address) {
+            return Some(NetworkRule::InternalAddress { ip, class });
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_address_classes() {
+        for (addr, class) in [
+            ("127.0.0.1", AddressClass::Loopback),
+            ("127.8.9.10", AddressClass::Loopback),
+            ("::1", AddressClass::Loopback),
+            ("10.1.2.3", AddressClass::Private),
+            ("172.16.0.1", AddressClass::Private),
+            ("172.31.255.255", AddressClass::Private),
+            ("192.168.1.1", AddressClass::Private),
+            ("100.64.0.1", AddressClass::Private),
+            ("fd12:3456::1", AddressClass::Private),
+            ("169.254.169.254", AddressClass::LinkLocal),
+            ("fe80::1", AddressClass::LinkLocal),
+            ("0.0.0.0", AddressClass::Unspecified),
+            ("::", AddressClass::Unspecified),
+            ("::ffff:10.0.0.1", AddressClass::Private),
+            ("::ffff:169.254.169.254", AddressClass::LinkLocal),
+            ("172.32.0.1", AddressClass::Public),
+            ("100.128.0.1", AddressClass::Public),
+            ("93.184.216.34", AddressClass::Public),
+            ("2606:2800:220:1::1", AddressClass::Public),
+        ] {
+            assert_eq!(address_class(ip(addr)), class, "{}", addr);
+        }
+    }
+
+    #[test]
+    fn test_internal_addresses_need_listing() {
+        let mut policy = NetworkPolicy::allow_all();
+        assert_eq!(
+            policy.address_rule("metadata.local", ip("169.254.169.254")).unwrap().to_string(),
+            "link-local address: 169.254.169.254"
+        );
+        assert_eq!(policy.address_rule("[::1]", ip("::1")).unwrap().to_string(), "loopback address: ::1");
+        assert_eq!(policy.host_rule("localhost"), None);
+        assert_eq!(policy.address_rule("example.com", ip("93.184.216.34")), None);
+
+        // A bare `*` doesn't let internal addresses through
+        policy.allowed_hosts.insert("*".to_string());
+        assert!(policy.address_rule("intranet", ip("10.0.0.5")).is_some());
+
+        // Listing the host or the address does
+        policy.allowed_hosts.insert("intranet".to_string());
+        policy.allowed_hosts.insert("192.168.1.*".to_string());
+        assert_eq!(policy.address_rule("intranet", ip("10.0.0.5")), None);
+        assert_eq!(policy.address_rule("printer", ip("192.168.1.20")), None);
+        assert!(policy.address_rule("printer", ip("192.168.2.20")).is_some());
+    }
+
+    #[test]
+    fn test_host_patterns() {
+        let mut policy = NetworkPolicy::allow_all();
+        policy.block_hosts(["Example.com", "*.corp.*", "10.0.*"]);
+
+        assert_eq!(policy.host_rule("example.com"), Some(NetworkRule::BlockedHost("example.com".into())));
+        assert!(policy.host_rule("www.EXAMPLE.com.").is_some());
+        assert!(policy.host_rule("notexample.com").is_none());
+        assert_eq!(policy.host_rule("git.corp.net"), Some(NetworkRule::BlockedHost("*.corp.*".into())));
+        assert!(policy.host_rule("corp.net").is_none());
+
+        // Blocked address patterns apply to what a name resolves to
+        assert_eq!(
+            policy.address_rule("sneaky.example.org", ip("10.0.3.4")),
+            Some(NetworkRule::BlockedHost("10.0.*".into()))
+        );
+
+        policy.allowed_hosts.insert("*.github.com".to_string());
+        assert_eq!(policy.host_rule("api.github.com"), None);
+        assert_eq!(policy.host_rule("github.com"), Some(NetworkRule::NotAllowed));
+        assert_eq!(NetworkPolicy::deny_all().host_rule("github.com"), Some(NetworkRule::Disabled));
+    }
+}
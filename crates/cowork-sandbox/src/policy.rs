@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::path::PathBuf;
 
-use crate::{FilesystemPolicy, NetworkPolicy, ResourceLimits, SandboxConfig};
+use crate::{EnvPolicy, FilesystemPolicy, NetworkPolicy, ResourceLimits, SandboxConfig};
 
 /// Preset security levels
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -51,6 +51,9 @@ fn paranoid_config(root: PathBuf) -> SandboxConfig {
             max_fds: 20,
             max_file_size: 10 * 1024 * 1024, // 10 MB
         },
+        env: EnvPolicy::default(),
+        audit_log_path: None,
+        watch_patterns: Vec::new(),
     }
 }
 
@@ -87,6 +90,9 @@ fn strict_config(root: PathBuf) -> SandboxConfig {
             max_fds: 50,
             max_file_size: 50 * 1024 * 1024, // 50 MB
         },
+        env: EnvPolicy::default(),
+        audit_log_path: None,
+        watch_patterns: Vec::new(),
     }
 }
 
@@ -128,6 +134,9 @@ fn standard_config(root: PathBuf) -> SandboxConfig {
             blocked_paths: default_blocked_paths(),
         },
         limits: ResourceLimits::default(),
+        env: EnvPolicy::default(),
+        audit_log_path: None,
+        watch_patterns: Vec::new(),
     }
 }
 
@@ -148,6 +157,9 @@ fn relaxed_config(root: PathBuf) -> SandboxConfig {
             max_fds: 500,
             max_file_size: 500 * 1024 * 1024, // 500 MB
         },
+        env: EnvPolicy::default(),
+        audit_log_path: None,
+        watch_patterns: Vec::new(),
     }
 }
 
@@ -168,6 +180,9 @@ fn permissive_config(root: PathBuf) -> SandboxConfig {
             max_fds: 1000,
             max_file_size: 1024 * 1024 * 1024, // 1 GB
         },
+        env: EnvPolicy::default(),
+        audit_log_path: None,
+        watch_patterns: Vec::new(),
     }
 }
 
@@ -232,6 +247,7 @@ pub struct PolicyBuilder {
     network_overrides: Option<NetworkPolicy>,
     filesystem_overrides: Option<FilesystemPolicy>,
     limit_overrides: Option<ResourceLimits>,
+    env_overrides: Option<EnvPolicy>,
 }
 
 impl PolicyBuilder {
@@ -242,6 +258,7 @@ impl PolicyBuilder {
             network_overrides: None,
             filesystem_overrides: None,
             limit_overrides: None,
+            env_overrides: None,
         }
     }
 
@@ -265,6 +282,11 @@ impl PolicyBuilder {
         self
     }
 
+    pub fn env(mut self, env: EnvPolicy) -> Self {
+        self.env_overrides = Some(env);
+        self
+    }
+
     pub fn build(self) -> SandboxConfig {
         let mut config = self.base.to_config(self.root);
 
@@ -280,6 +302,10 @@ impl PolicyBuilder {
             config.limits = limits;
         }
 
+        if let Some(env) = self.env_overrides {
+            config.env = env;
+        }
+
         config
     }
 }
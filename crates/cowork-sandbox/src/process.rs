@@ -4,27 +4,19 @@ use std::process::Stdio;
 use std::time::Instant;
 use tokio::process::Command;
 
+use crate::audit;
 use crate::{SandboxConfig, SandboxError, SandboxResult};
 
-/// Execute a command with process-level sandboxing
-pub async fn execute_sandboxed(
-    config: &SandboxConfig,
-    command: &str,
-    args: &[&str],
-) -> Result<SandboxResult, SandboxError> {
-    let start = Instant::now();
-
-    // Build the command with resource limits
-    let mut cmd = Command::new(command);
-    cmd.args(args)
-        .current_dir(&config.root)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped());
+/// A command with the sandbox's environment, working directory and home
+fn base_command(config: &SandboxConfig, command: &str, args: &[&str]) -> std::process::Command {
+    let mut cmd = std::process::Command::new(command);
+    cmd.args(args).current_dir(&config.root);
 
-    // Set environment restrictions
+    // Set environment restrictions: only allowlisted and configured variables
     cmd.env_clear();
+    cmd.envs(config.env.environment());
 
-    // Set platform-appropriate PATH
+    // Set platform-appropriate PATH and home, confined to the sandbox
     #[cfg(windows)]
     {
         cmd.env("PATH", r"C:\Windows\System32;C:\Windows;C:\Windows\System32\Wbem");
@@ -37,21 +29,42 @@ pub async fn execute_sandboxed(
         cmd.env("HOME", config.root.display().to_string());
     }
 
+    cmd
+}
+
+/// Execute a command with process-level sandboxing
+pub async fn execute_sandboxed(
+    config: &SandboxConfig,
+    command: &str,
+    args: &[&str],
+) -> Result<SandboxResult, SandboxError> {
+    let start = Instant::now();
+
+    let mut cmd = Command::from(base_command(config, command, args));
+    cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+
     // Execute with timeout
     let timeout = std::time::Duration::from_secs(config.limits.max_cpu_time);
     let result: Result<Result<std::process::Output, std::io::Error>, _> =
         tokio::time::timeout(timeout, cmd.output()).await;
 
+    let masker = config.env.masker();
     match result {
-        Ok(Ok(output)) => Ok(SandboxResult {
-            exit_code: output.status.code().unwrap_or(-1),
-            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
-            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
-            duration_ms: start.elapsed().as_millis() as u64,
-            memory_used: 0, // Would need platform-specific tracking
-            killed: !output.status.success() && output.status.code().is_none(),
-            kill_reason: None,
-        }),
+        Ok(Ok(output)) => {
+            let stderr = masker.mask(&String::from_utf8_lossy(&output.stderr));
+            // Denials only show up as error messages; recover them after the fact
+            let violations = audit::scan_stderr(config, &audit::command_line(command, args), &stderr);
+            Ok(SandboxResult {
+                exit_code: output.status.code().unwrap_or(-1),
+                stdout: masker.mask(&String::from_utf8_lossy(&output.stdout)),
+                stderr,
+                duration_ms: start.elapsed().as_millis() as u64,
+                memory_used: 0, // Would need platform-specific tracking
+                killed: !output.status.success() && output.status.code().is_none(),
+                kill_reason: None,
+                violations,
+            })
+        }
         Ok(Err(e)) => Err(SandboxError::Execution(e.to_string())),
         Err(_) => Ok(SandboxResult {
             exit_code: -1,
@@ -61,15 +74,184 @@ pub async fn execute_sandboxed(
             memory_used: 0,
             killed: true,
             kill_reason: Some("Timeout".to_string()),
+            violations: Vec::new(),
         }),
     }
 }
 
+/// Spawn a long-running command in the sandbox, with stdin, stdout and
+/// stderr piped
+///
+/// The child gets the environment, working directory and home of
+/// `execute_sandboxed`, and is confined before it starts (see
+/// `linux::confine`). There is no timeout; `max_cpu_time` applies as an
+/// rlimit instead.
+///
+/// Fails with `SandboxError::Creation` when the child can't be confined as
+/// configured, including on platforms other than Linux, so the caller can
+/// decide whether to run it unconfined.
+pub fn spawn_sandboxed(
+    config: &SandboxConfig,
+    command: &str,
+    args: &[&str],
+) -> Result<std::process::Child, SandboxError> {
+    let mut cmd = base_command(config, command, args);
+    cmd.stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped());
+
+    #[cfg(target_os = "linux")]
+    {
+        linux::confine(config, &mut cmd)?;
+        cmd.spawn().map_err(|e| linux::spawn_error(command, e))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        drop(cmd);
+        Err(SandboxError::Creation(
+            "confining long-running processes is only supported on Linux".to_string(),
+        ))
+    }
+}
+
 #[cfg(target_os = "linux")]
 pub mod linux {
     //! Linux-specific sandboxing using namespaces and seccomp
 
     use super::*;
+    use std::os::unix::process::CommandExt;
+    use std::path::Path;
+
+    use landlock::{
+        path_beneath_rules, Access, AccessFs, AccessNet, BitFlags, CompatLevel, Compatible, Ruleset, RulesetAttr,
+        RulesetCreatedAttr, RulesetStatus, ABI,
+    };
+    use nix::sys::resource::{getrlimit, setrlimit, Resource};
+
+    /// What the child reports when it couldn't be confined
+    const CONFINE_FAILED: nix::errno::Errno = nix::errno::Errno::EOPNOTSUPP;
+
+    /// Confine the child `cmd` spawns before it starts
+    ///
+    /// - Landlock (ABI 1) allows reading beneath `read_paths`, reading and
+    ///   executing beneath `exec_paths`, and everything beneath `write_paths`
+    ///   and the sandbox root; the rest of the filesystem is denied. As in the
+    ///   presets, an empty `read_paths` or `exec_paths` leaves reading or
+    ///   executing unrestricted.
+    /// - With the network disabled, Landlock (ABI 4, Linux 6.7) denies TCP
+    ///   binds and connects
+    /// - `max_memory` (address space), `max_cpu_time`, `max_fds` and
+    ///   `max_file_size` become rlimits (0 = not limited). `max_processes`
+    ///   isn't applied: RLIMIT_NPROC counts every process of the user.
+    ///
+    /// Landlock can only allow, so blocked paths it can't keep closed are
+    /// refused rather than left open, and allowed paths beneath a blocked
+    /// one are left out.
+    pub fn confine(config: &SandboxConfig, cmd: &mut std::process::Command) -> Result<(), SandboxError> {
+        let fs = &config.filesystem;
+        let read = AccessFs::from_read(ABI::V1);
+        let mut handled = AccessFs::from_all(ABI::V1);
+        for (paths, access, name) in [(&fs.read_paths, read, "read_paths"), (&fs.exec_paths, AccessFs::Execute.into(), "exec_paths")] {
+            if paths.is_empty() {
+                if let Some(blocked) = fs.blocked_paths.iter().next() {
+                    return Err(SandboxError::Creation(format!(
+                        "blocked path {} can't be enforced while {} is empty",
+                        blocked.display(),
+                        name
+                    )));
+                }
+                handled &= !access;
+            }
+        }
+
+        let grants: Vec<(&Path, BitFlags<AccessFs>)> = fs
+            .read_paths
+            .iter()
+            .map(|p| (p.as_path(), read))
+            .chain(fs.exec_paths.iter().map(|p| (p.as_path(), read | AccessFs::Execute)))
+            .chain(fs.write_paths.iter().map(|p| (p.as_path(), handled)))
+            .chain(std::iter::once((config.root.as_path(), handled)))
+            .map(|(path, access)| (path, access & handled))
+            .filter(|(_, access)| !access.is_empty())
+            .collect();
+
+        let mut allowed = Vec::with_capacity(grants.len());
+        for (path, access) in grants {
+            if let Some(blocked) = fs.blocked_paths.iter().find(|b| audit::path_within(b, path) && !audit::path_within(path, b)) {
+                return Err(SandboxError::Creation(format!(
+                    "blocked path {} is inside the allowed path {}, which Landlock can't exclude",
+                    blocked.display(),
+                    path.display()
+                )));
+            }
+            if let Some(rule) = audit::blocked_rule(fs, path) {
+                tracing::warn!("Not allowing {} in the sandbox ({})", path.display(), rule);
+                continue;
+            }
+            allowed.push((path, access));
+        }
+
+        let mut ruleset = Ruleset::default()
+            .set_compatibility(CompatLevel::HardRequirement)
+            .handle_access(handled)
+            .map_err(landlock_error)?;
+        if !config.network.enabled {
+            ruleset = ruleset.handle_access(AccessNet::from_all(ABI::V4)).map_err(landlock_error)?;
+        }
+        let mut created = ruleset.create().map_err(landlock_error)?;
+        for (path, access) in allowed {
+            // Paths that don't exist are skipped
+            created = created.add_rules(path_beneath_rules([path], access)).map_err(landlock_error)?;
+        }
+
+        let limits = &config.limits;
+        let mut rlimits = Vec::new();
+        for (resource, value) in [
+            (Resource::RLIMIT_AS, limits.max_memory),
+            (Resource::RLIMIT_CPU, limits.max_cpu_time),
+            (Resource::RLIMIT_NOFILE, u64::from(limits.max_fds)),
+            (Resource::RLIMIT_FSIZE, limits.max_file_size),
+        ] {
+            if value == 0 {
+                continue;
+            }
+            let (_, hard) = getrlimit(resource)
+                .map_err(|e| SandboxError::Creation(format!("Failed to read {:?}: {}", resource, e)))?;
+            let soft = value.min(hard);
+            // The soft CPU limit sends SIGXCPU, so the exit names the limit;
+            // the hard limit a second later kills it regardless
+            let hard = if resource == Resource::RLIMIT_CPU { value.saturating_add(1).min(hard) } else { soft };
+            rlimits.push((resource, soft, hard));
+        }
+
+        // Only syscalls between fork and exec: no allocation, no locks
+        let mut ruleset = Some(created);
+        unsafe {
+            cmd.pre_exec(move || {
+                for &(resource, soft, hard) in &rlimits {
+                    setrlimit(resource, soft, hard).map_err(|_| std::io::Error::from(CONFINE_FAILED))?;
+                }
+                let enforced = ruleset
+                    .take()
+                    .and_then(|r| r.restrict_self().ok())
+                    .is_some_and(|status| status.ruleset == RulesetStatus::FullyEnforced);
+                if enforced { Ok(()) } else { Err(std::io::Error::from(CONFINE_FAILED)) }
+            });
+        }
+        Ok(())
+    }
+
+    /// Tell a failure to confine the child from a failure to run it
+    pub fn spawn_error(command: &str, error: std::io::Error) -> SandboxError {
+        if error.raw_os_error() == Some(CONFINE_FAILED as i32) {
+            SandboxError::Creation(format!("{} couldn't be confined before it started", command))
+        } else {
+            SandboxError::Execution(format!("Failed to start {}: {}", command, error))
+        }
+    }
+
+    fn landlock_error(error: landlock::RulesetError) -> SandboxError {
+        SandboxError::Creation(format!("Landlock is unavailable or incomplete on this kernel: {}", error))
+    }
 
     /// Create a sandboxed process using Linux namespaces
     pub async fn execute_namespaced(
@@ -9,6 +9,21 @@ export interface DiffLine {
   content: string
 }
 
+/**
+ * Line diff of the change an Edit or Write call makes to a file
+ */
+export interface FileDiff {
+  /** Relative to the workspace when inside it */
+  path: string
+  /** The file on disk, for opening it */
+  absolute_path: string
+  /** The file doesn't exist yet, so `lines` is a preview of its content */
+  new_file: boolean
+  lines: DiffLine[]
+  added: number
+  removed: number
+}
+
 /**
  * Context usage breakdown by category
  */
@@ -33,22 +48,141 @@ export interface ContextUsage {
   breakdown: ContextBreakdown
 }
 
+/**
+ * Per-session generation parameters; unset fields use the provider default
+ */
+export interface GenerationParams {
+  temperature?: number
+  max_tokens?: number
+  top_p?: number
+  stop_sequences?: string[]
+}
+
+/**
+ * Lifecycle status of a plan
+ */
+export type PlanStatus = "draft" | "approved" | "in_progress" | "done"
+
+/**
+ * How a tool call ended
+ */
+export type ToolDoneStatus = "success" | "failed" | "cancelled" | "rejected"
+
+/**
+ * Where a recipe step ended up
+ */
+export type StepStatus = "succeeded" | "failed" | "skipped"
+
+/**
+ * A recipe step as listed when the recipe starts
+ */
+export interface RecipeStepInfo {
+  id: string
+  /** The step's id with what it runs, e.g. "test (shell cargo test)" */
+  label: string
+}
+
+/**
+ * A todo item
+ */
+export interface TodoItem {
+  /** Stable identifier across updates (assigned when the model omits it) */
+  id: string
+  content: string
+  status: "pending" | "in_progress" | "completed"
+  priority: "high" | "medium" | "low"
+  activeForm: string
+}
+
+/**
+ * What merging a session's worktree brings into the repository
+ */
+export interface WorktreeSummary {
+  branch: string
+  /** The branch checked out in the repository, which the worktree merges into */
+  target: string
+  /** Commits the target lacks, newest first */
+  commits: string[]
+  /** Uncommitted changes (`git status --short`), committed when merged */
+  uncommitted: string[]
+  diff_stat: string
+}
+
 /**
  * Messages sent from loop to frontend
- * Now includes session_id for multi-session support
+ * Now includes session_id for multi-session support, and `seq`: the
+ * output's number within its session, one more than the last
  */
-export type LoopOutput =
+export type LoopOutput = { seq?: number } & (
   | { type: "ready"; session_id: string }
   | { type: "user_message"; session_id: string; id: string; content: string }
   | { type: "thinking"; session_id: string; content: string }
   | { type: "assistant_message"; session_id: string; id: string; content: string }
   | { type: "tool_start"; session_id: string; id: string; name: string; arguments: Record<string, unknown> }
-  | { type: "tool_pending"; session_id: string; id: string; name: string; arguments: Record<string, unknown>; description?: string; subagent_id?: string }
-  | { type: "tool_done"; session_id: string; id: string; name: string; success: boolean; output: string }
-  | { type: "tool_call"; session_id: string; id: string; name: string; arguments: Record<string, unknown>; formatted: string }
-  | { type: "tool_result"; session_id: string; id: string; name: string; success: boolean; output: string; summary: string; diff_preview: DiffLine[] | null }
+  | { type: "tool_pending"; session_id: string; id: string; name: string; arguments: Record<string, unknown>; description?: string; subagent_id?: string; security_warning?: string; generated_files?: { path: string; reason: string; action: string }[] }
+  | { type: "approval_delegated"; session_id: string; id: string; tool: string; args: Record<string, unknown>; reason: string; channel: string; timeout_secs: number }
+  | { type: "tool_explanation"; session_id: string; id: string; text: string }
+  | { type: "tool_progress"; session_id: string; id: string; elapsed_ms: number; output_bytes: number; last_line: string | null }
+  | { type: "tool_done"; session_id: string; id: string; name: string; success: boolean; status: ToolDoneStatus; output: string }
+  | { type: "tool_call"; session_id: string; id: string; name: string; arguments: Record<string, unknown>; formatted: string; diff: FileDiff | null; absolute_path?: string }
+  | { type: "tool_result"; session_id: string; id: string; name: string; success: boolean; output: string; summary: string; diff_preview: DiffLine[] | null; absolute_path?: string; line?: number }
   | { type: "question"; session_id: string; request_id: string; questions: Array<{ question: string; header: string | null; options: Array<{ label: string; description: string | null }>; multi_select: boolean }>; subagent_id?: string }
-  | { type: "idle"; session_id: string }
+  | { type: "idle"; session_id: string; review_warning?: boolean }
   | { type: "error"; session_id: string; message: string }
+  | { type: "warning"; session_id: string; message: string }
+  | { type: "refusal"; session_id: string; reason: string; message: string | null }
   | { type: "stopped"; session_id: string }
-  | { type: "cancelled"; session_id: string };
+  | { type: "cancelled"; session_id: string }
+  | { type: "message_queued"; session_id: string; position: number }
+  | { type: "history_truncated"; session_id: string; from_message_id: string }
+  | { type: "suspended"; session_id: string }
+  | { type: "resumed"; session_id: string; suspended_secs: number }
+  | { type: "worktree"; session_id: string; path: string; branch: string }
+  | { type: "files_changed_externally"; session_id: string; paths: string[] }
+  | { type: "generation_params_changed"; session_id: string; params: GenerationParams }
+  | { type: "plan_status_changed"; session_id: string; name: string; status: PlanStatus }
+  | { type: "todos_updated"; session_id: string; items: TodoItem[] }
+  | { type: "title_changed"; session_id: string; title: string }
+  | { type: "tool_profile_changed"; session_id: string; profile: string; tools: string[]; definition_tokens: number }
+  | { type: "turn_overridden"; session_id: string; model?: string; approval?: string; tool_profile?: string }
+  | { type: "recipe_started"; session_id: string; name: string; steps: RecipeStepInfo[] }
+  | { type: "recipe_step_started"; session_id: string; name: string; step_id: string }
+  | { type: "recipe_step_finished"; session_id: string; name: string; step_id: string; status: StepStatus; output: string }
+  | { type: "recipe_finished"; session_id: string; name: string; success: boolean; summary: string }
+  | { type: "unsupported_input"; session_id: string; input_type: string; protocol_version: number }
+  | { type: "compacted"; session_id: string; mode: CompactionMode; model?: string; messages_summarized: number; tokens_before: number; tokens_after: number }
+  | { type: "self_review"; session_id: string; findings: string[] });
+
+/** How a history is compacted (`[summarizer] mode`) */
+export type CompactionMode = "llm" | "heuristic";
+
+/** An output as `get_session_transcript` returns it: numbered, without `session_id` */
+export type SequencedOutput = LoopOutput extends infer O ? (O extends unknown ? Omit<O, "session_id"> & { seq: number } : never) : never;
+
+/** A session's outputs since a `seq` (from `get_session_transcript`) */
+export interface TranscriptSync {
+  events: SequencedOutput[];
+  last_seq: number;
+  /** False when outputs after the requested one have already been dropped */
+  complete: boolean;
+  /** Approvals and questions the session still waits on, even if older than `events` */
+  pending: SequencedOutput[];
+}
+
+/** Settings for a single turn in place of the session's (`send_message`'s `overrides`) */
+export interface TurnOverrides {
+  model?: string;
+  approval?: string;
+  tool_profile?: string;
+}
+
+/** What the core supports (from `get_capabilities`), for gating UI */
+export interface Capabilities {
+  protocol_version: number;
+  core_version: string;
+  inputs: string[];
+  outputs: string[];
+  tools: string[];
+  features: { streaming: boolean; attachments: boolean; plan_mode: boolean; subagents: boolean; turn_overrides: boolean; self_review: boolean };
+  limits: { max_attachment_bytes: number; max_sessions: number };
+}
@@ -0,0 +1,31 @@
+// Command palette items, as returned by the get_palette_items command
+
+export type PaletteKind = "skill" | "command" | "tool" | "agent" | "session"
+
+/** What picking an item does */
+export type PaletteInvocation =
+  /** Send the text as a message (put it in the message box first when the item takes arguments) */
+  | { type: "slash_command"; text: string }
+  /** Put the text in the message box for the user to finish */
+  | { type: "prefill"; text: string }
+  | { type: "tauri_command"; command: string; args: Record<string, unknown> }
+  | { type: "open_session"; session_id: string }
+
+export interface PaletteItem {
+  /** `<kind>:<name>`, unique across the list */
+  id: string
+  kind: PaletteKind
+  title: string
+  description: string
+  /** Lowercase words to fuzzy-match on besides the title */
+  keywords: string[]
+  /** Argument hints, e.g. `<file>` */
+  args?: string[]
+  invocation: PaletteInvocation
+  /** Why the item can't be used now (shown grayed out) */
+  unavailable?: string
+  /** Tools: whether calling it asks for approval */
+  needs_approval?: boolean
+  /** Tools: the tool profiles offering it */
+  profiles?: string[]
+}
@@ -3,7 +3,7 @@
  * Simplified: tools are ephemeral, questions/approvals are modals
  */
 
-import type { DiffLine } from './LoopOutput'
+import type { DiffLine, GenerationParams, PlanStatus, RecipeStepInfo, StepStatus, TodoItem, TurnOverrides } from './LoopOutput'
 
 export interface SessionProvider {
   type: string  // 'anthropic', 'openai', 'deepseek', etc.
@@ -12,16 +12,22 @@ export interface SessionProvider {
 
 export interface Message {
   id: string
-  type: 'user' | 'assistant' | 'tool_call' | 'tool_result'
+  type: 'user' | 'assistant' | 'tool_call' | 'tool_result' | 'self_review'
   content: string
   // Tool call specific
   toolName?: string
   formatted?: string
+  // The file the tool works on, for opening it
+  absolutePath?: string
+  // Line of the file the result is about (the first changed line of an edit)
+  line?: number
   // Tool result specific
   summary?: string
   success?: boolean
   diffPreview?: DiffLine[]
   expanded?: boolean
+  // Self-review specific: gaps found between the turn's changes and the request
+  findings?: string[]
   // Timing
   elapsedSecs?: number
 }
@@ -34,9 +40,43 @@ export interface QuestionData {
 }
 
 export type Modal =
-  | { type: 'approval'; id: string; name: string; arguments: Record<string, unknown>; description?: string; targetSessionId?: string }
+  | { type: 'approval'; id: string; name: string; arguments: Record<string, unknown>; description?: string; targetSessionId?: string; explainRequested?: boolean; explanation?: string; securityWarning?: string }
   | { type: 'question'; request_id: string; questions: QuestionData[]; targetSessionId?: string }
 
+/**
+ * Checklist of the recipe the session is running (or ran last)
+ */
+export interface RecipeProgress {
+  name: string
+  steps: Array<RecipeStepInfo & { status: StepStatus | 'pending' | 'running'; output?: string }>
+  finished: boolean
+  success?: boolean
+}
+
+/**
+ * Settings a session starts with, and the message it opens with
+ * (see cowork_core::templates)
+ */
+export interface SessionTemplate {
+  name: string
+  path?: string            // Absent for a built-in
+  description: string
+  provider?: string
+  model?: string
+  tool_profile?: string
+  approval_level?: string
+  system_prompt?: string
+  argument_hint?: string
+  message?: string
+}
+
+/** A template for the new session dialog, or why it can't be used */
+export interface TemplateEntry {
+  name: string
+  template: SessionTemplate | null
+  error: string | null
+}
+
 export interface Session {
   id: string
   name: string
@@ -50,6 +90,19 @@ export interface Session {
   createdAt: Date
   updatedAt: Date
   turnStart?: number          // Timestamp when user submitted message (ms since epoch)
+  generationParams?: GenerationParams  // Overrides confirmed by the loop
+  planStatus?: { name: string; status: PlanStatus }  // Latest plan status change
+  warning?: string            // Latest warning from the loop (e.g. repeated tool calls suppressed)
+  todos?: TodoItem[]          // TodoWrite checklist (restored when the session is resumed)
+  recipe?: RecipeProgress     // Progress of the latest recipe run
+  suspended?: boolean         // Saved and shut down while idle; the next message resumes it
+  keepAlive?: boolean         // Never suspended while idle
+  worktree?: { path: string; branch: string }  // Isolated git worktree the session works in
+  toolProgress?: { id: string; elapsedMs: number; outputBytes: number; tail: string[] }  // Live tail of a long-running tool
+  delegatedApproval?: { id: string; tool: string; reason: string; channel: string; deadline: number }  // Tool call only a reviewer can approve
+  toolProfile?: { name: string; tools: string[]; definitionTokens: number }  // Tools offered to the model
+  turnOverrides?: TurnOverrides  // Settings the running turn uses in place of the session's
+  template?: string           // Session template the session was started from
 }
 
 export function createSession(id: string, name?: string, provider?: SessionProvider): Session {
@@ -1,4 +1,5 @@
 // TypeScript types for loop communication
-export type { LoopOutput, DiffLine } from "./LoopOutput";
-export type { Session, Message, Modal, QuestionData, SessionProvider } from "./Session";
+export type { LoopOutput, Capabilities, CompactionMode, DiffLine, FileDiff, GenerationParams, PlanStatus, RecipeStepInfo, SequencedOutput, StepStatus, TodoItem, ToolDoneStatus, TranscriptSync, TurnOverrides, WorktreeSummary } from "./LoopOutput";
+export type { Session, Message, Modal, QuestionData, RecipeProgress, SessionProvider, SessionTemplate, TemplateEntry } from "./Session";
+export type { PaletteInvocation, PaletteItem, PaletteKind } from "./Palette";
 export { createSession, generateSessionId } from "./Session";
@@ -1,15 +1,21 @@
 import { useEffect } from 'react'
-import { Terminal } from 'lucide-react'
+import { AlertTriangle, HelpCircle, Loader2, Terminal } from 'lucide-react'
 
 interface ApprovalModalProps {
   id: string
   name: string
   arguments: Record<string, unknown>
   description?: string
+  // An explanation was asked for (only one is given per call)
+  explainRequested?: boolean
+  explanation?: string
+  // Why the call looks like secret exfiltration, if it does
+  securityWarning?: string
   onApprove: (id: string) => void
   onReject: (id: string) => void
   onApproveForSession: (id: string, name: string) => void
   onApproveAll: (id: string) => void
+  onExplain: (id: string) => void
 }
 
 function formatArgs(args: Record<string, unknown>): string {
@@ -23,10 +29,11 @@ function formatArgs(args: Record<string, unknown>): string {
     .join('\n')
 }
 
-export default function ApprovalModal({ id, name, arguments: args, description, onApprove, onReject, onApproveForSession, onApproveAll }: ApprovalModalProps) {
+export default function ApprovalModal({ id, name, arguments: args, description, explainRequested, explanation, securityWarning, onApprove, onReject, onApproveForSession, onApproveAll, onExplain }: ApprovalModalProps) {
   useEffect(() => {
     const handleKeyDown = (e: KeyboardEvent) => {
-      if (e.key === 'y' || e.key === 'Y' || e.key === 'Enter') {
+      // A flagged call needs a deliberate Y, not a reflexive Enter
+      if (e.key === 'y' || e.key === 'Y' || (e.key === 'Enter' && !securityWarning)) {
         e.preventDefault()
         onApprove(id)
       }
@@ -38,11 +45,15 @@ export default function ApprovalModal({ id, name, arguments: args, description,
         e.preventDefault()
         onApproveForSession(id, name)
       }
+      if ((e.key === 'e' || e.key === 'E') && !explainRequested) {
+        e.preventDefault()
+        onExplain(id)
+      }
     }
 
     window.addEventListener('keydown', handleKeyDown)
     return () => window.removeEventListener('keydown', handleKeyDown)
-  }, [id, name, onApprove, onReject, onApproveForSession])
+  }, [id, name, explainRequested, securityWarning, onApprove, onReject, onApproveForSession, onExplain])
 
   return (
     <div className="fixed inset-0 z-50 flex items-center justify-center">
@@ -60,6 +71,12 @@ export default function ApprovalModal({ id, name, arguments: args, description,
 
         {/* Content */}
         <div className="px-4 py-3 flex-1 overflow-auto min-h-0 space-y-3">
+          {securityWarning && (
+            <div className="flex gap-2 text-sm font-medium text-error bg-error/10 border border-error/40 rounded-lg p-3">
+              <AlertTriangle className="w-4 h-4 mt-0.5 shrink-0" />
+              <p className="whitespace-pre-wrap">{securityWarning}</p>
+            </div>
+          )}
           {description && (
             <p className="text-sm text-foreground">{description}</p>
           )}
@@ -68,6 +85,26 @@ export default function ApprovalModal({ id, name, arguments: args, description,
               {formatArgs(args)}
             </pre>
           )}
+          {explanation ? (
+            <div className="flex gap-2 text-sm text-foreground bg-primary/10 rounded-lg p-3">
+              <HelpCircle className="w-4 h-4 mt-0.5 shrink-0 text-primary" />
+              <p className="whitespace-pre-wrap">{explanation}</p>
+            </div>
+          ) : explainRequested ? (
+            <div className="flex items-center gap-2 text-xs text-muted-foreground">
+              <Loader2 className="w-3.5 h-3.5 animate-spin" />
+              Asking the model to explain this call...
+            </div>
+          ) : (
+            <button
+              onClick={() => onExplain(id)}
+              className="flex items-center gap-1.5 text-xs text-primary hover:underline"
+              title="Ask the model why it wants to run this call and what it could change"
+            >
+              <HelpCircle className="w-3.5 h-3.5" />
+              Explain (E)
+            </button>
+          )}
         </div>
 
         {/* Actions */}
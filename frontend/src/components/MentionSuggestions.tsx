@@ -0,0 +1,116 @@
+import { useEffect, useState } from 'react'
+import { invoke } from '@tauri-apps/api/core'
+import { FileText } from 'lucide-react'
+
+/** How many completions the list shows */
+const LIMIT = 8
+
+/** The `@path` being typed before the cursor: where its `@` is and what follows it */
+function mentionBeforeCursor(text: string, cursor: number): { start: number; prefix: string } | null {
+  const match = /(^|[\s([{"'])@([^\s"'`@]*)$/.exec(text.slice(0, cursor))
+  if (!match) return null
+  return { start: match.index + match[1].length, prefix: match[2] }
+}
+
+/**
+ * Completes `@` mentions of workspace files in a text input: the suggestions
+ * for the mention at the cursor, and the key handling to move through and
+ * pick them (Up/Down, Tab or Enter, Escape)
+ */
+export function useMentionCompletion(
+  input: string,
+  setInput: (value: string) => void,
+  inputRef: React.RefObject<HTMLInputElement | null>,
+) {
+  const [cursor, setCursor] = useState(0)
+  const [suggestions, setSuggestions] = useState<string[]>([])
+  const [selected, setSelected] = useState(0)
+  const [dismissed, setDismissed] = useState<string | null>(null)
+
+  const mention = mentionBeforeCursor(input, cursor)
+  const prefix = mention && dismissed !== input ? mention.prefix : null
+
+  useEffect(() => {
+    if (prefix === null) {
+      setSuggestions([])
+      return
+    }
+    let current = true
+    invoke<string[]>('complete_workspace_paths', { prefix, limit: LIMIT })
+      .then((paths) => {
+        if (current) {
+          setSuggestions(paths)
+          setSelected(0)
+        }
+      })
+      .catch((err) => console.error('Failed to complete workspace paths:', err))
+    return () => {
+      current = false
+    }
+  }, [prefix])
+
+  const trackCursor = (e: React.SyntheticEvent<HTMLInputElement>) => setCursor(e.currentTarget.selectionStart ?? 0)
+
+  const pick = (path: string) => {
+    if (!mention) return
+    const quoted = /\s/.test(path) ? `"${path}"` : path
+    const before = input.slice(0, mention.start) + '@' + quoted + ' '
+    setInput(before + input.slice(cursor))
+    setCursor(before.length)
+    setSuggestions([])
+    requestAnimationFrame(() => {
+      inputRef.current?.focus()
+      inputRef.current?.setSelectionRange(before.length, before.length)
+    })
+  }
+
+  const onKeyDown = (e: React.KeyboardEvent<HTMLInputElement>) => {
+    if (suggestions.length === 0) return
+    if (e.key === 'ArrowDown' || e.key === 'ArrowUp') {
+      e.preventDefault()
+      const step = e.key === 'ArrowDown' ? 1 : suggestions.length - 1
+      setSelected((i) => (i + step) % suggestions.length)
+    } else if (e.key === 'Tab' || e.key === 'Enter') {
+      e.preventDefault()
+      pick(suggestions[selected])
+    } else if (e.key === 'Escape') {
+      e.preventDefault()
+      setDismissed(input)
+      setSuggestions([])
+    }
+  }
+
+  return { suggestions, selected, pick, onKeyDown, trackCursor }
+}
+
+interface MentionSuggestionsProps {
+  suggestions: string[]
+  selected: number
+  onPick: (path: string) => void
+}
+
+/** Workspace files completing the `@` mention being typed, shown above the input */
+export default function MentionSuggestions({ suggestions, selected, onPick }: MentionSuggestionsProps) {
+  if (suggestions.length === 0) {
+    return null
+  }
+
+  return (
+    <ul className="absolute bottom-full left-0 mb-2 w-full max-w-lg rounded-lg border border-border bg-card shadow-lg py-1 text-sm z-10">
+      {suggestions.map((path, i) => (
+        <li key={path}>
+          <button
+            type="button"
+            // Keep focus in the input, so picking doesn't blur it
+            onMouseDown={(e) => e.preventDefault()}
+            onClick={() => onPick(path)}
+            className={`w-full px-3 py-1.5 flex items-center gap-2 text-left font-mono truncate ${i === selected ? 'bg-primary/10 text-foreground' : 'text-muted-foreground hover:bg-muted/50'}`}
+          >
+            <FileText className="w-3.5 h-3.5 shrink-0" />
+            {path}
+          </button>
+        </li>
+      ))}
+    </ul>
+  )
+}
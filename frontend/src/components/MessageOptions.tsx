@@ -0,0 +1,69 @@
+import { useState } from 'react'
+import { SlidersHorizontal } from 'lucide-react'
+import type { TurnOverrides } from '../bindings'
+
+interface MessageOptionsProps {
+  value: TurnOverrides
+  onChange: (overrides: TurnOverrides) => void
+  disabled?: boolean
+}
+
+const APPROVALS = ['', 'all', 'none', 'low', 'medium', 'high', 'critical']
+
+/** Settings for the next message only: model, approval and tool profile */
+export default function MessageOptions({ value, onChange, disabled }: MessageOptionsProps) {
+  const [open, setOpen] = useState(false)
+  const active = Object.values(value).some(Boolean)
+
+  // Empty fields fall back to the session's settings
+  const set = (key: keyof TurnOverrides, v: string) => onChange({ ...value, [key]: v.trim() || undefined })
+
+  return (
+    <div className="relative">
+      <button
+        type="button"
+        onClick={() => setOpen(!open)}
+        disabled={disabled}
+        className={`h-full px-3 rounded-lg hover:bg-muted/50 disabled:opacity-50 ${active ? 'text-primary' : 'text-muted-foreground'}`}
+        title="Options for this message"
+      >
+        <SlidersHorizontal className="w-5 h-5" />
+      </button>
+      {open && (
+        <div className="absolute bottom-full mb-2 left-0 w-64 p-3 rounded-lg border border-border bg-card shadow-lg space-y-2 text-sm z-10">
+          <p className="text-xs text-muted-foreground">For this message only; the next one uses the session's settings</p>
+          <label className="block">
+            <span className="text-xs text-muted-foreground">Model</span>
+            <input
+              value={value.model ?? ''}
+              onChange={(e) => set('model', e.target.value)}
+              placeholder="haiku, sonnet, opus or a model ID"
+              className="w-full rounded border border-border bg-background px-2 py-1"
+            />
+          </label>
+          <label className="block">
+            <span className="text-xs text-muted-foreground">Auto-approve</span>
+            <select
+              value={value.approval ?? ''}
+              onChange={(e) => set('approval', e.target.value)}
+              className="w-full rounded border border-border bg-background px-2 py-1"
+            >
+              {APPROVALS.map((a) => (
+                <option key={a} value={a}>{a || "Session's setting"}</option>
+              ))}
+            </select>
+          </label>
+          <label className="block">
+            <span className="text-xs text-muted-foreground">Tool profile</span>
+            <input
+              value={value.tool_profile ?? ''}
+              onChange={(e) => set('tool_profile', e.target.value)}
+              placeholder="minimal, coding, research or a config profile"
+              className="w-full rounded border border-border bg-background px-2 py-1"
+            />
+          </label>
+        </div>
+      )}
+    </div>
+  )
+}
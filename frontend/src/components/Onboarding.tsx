@@ -40,9 +40,15 @@ const PROVIDERS: ProviderOption[] = [
 // Providers with native web search capability
 const NATIVE_SEARCH_PROVIDERS = ['anthropic', 'openai', 'gemini', 'groq', 'xai', 'perplexity', 'cohere']
 
-interface ApiTestResult {
-  success: boolean
-  message: string
+// Result of the connection probe (see provider::probe::TestReport)
+interface TestReport {
+  auth_ok: boolean
+  tools_ok: boolean
+  streaming_ok: boolean
+  context_limit: number | null
+  latency_ms: number
+  warnings: string[]
+  error: string | null
 }
 
 export default function Onboarding({ onComplete }: OnboardingProps) {
@@ -52,22 +58,23 @@ export default function Onboarding({ onComplete }: OnboardingProps) {
   const [serpApiKey, setSerpApiKey] = useState('')
   const [error, setError] = useState<string | null>(null)
   const [isLoading, setIsLoading] = useState(false)
-  const [testResult, setTestResult] = useState<ApiTestResult | null>(null)
+  const [testResult, setTestResult] = useState<TestReport | null>(null)
+  const [saved, setSaved] = useState(false)
 
   const needsSerpApi = !NATIVE_SEARCH_PROVIDERS.includes(selectedProvider)
 
   const selectedProviderInfo = PROVIDERS.find((p) => p.id === selectedProvider)
 
-  // Auto-complete after successful test
+  // Auto-complete once the settings are saved
   useEffect(() => {
-    if (testResult?.success) {
+    if (saved) {
       const timer = setTimeout(() => {
         localStorage.setItem('onboarding_complete', 'true')
         onComplete()
       }, 1500)
       return () => clearTimeout(timer)
     }
-  }, [testResult, onComplete])
+  }, [saved, onComplete])
 
   const handleProviderSelect = (providerId: string) => {
     setSelectedProvider(providerId)
@@ -113,38 +120,63 @@ export default function Onboarding({ onComplete }: OnboardingProps) {
     setTestResult(null)
 
     try {
-      const result = await invoke<ApiTestResult>('test_api_connection', {
+      const result = await invoke<TestReport>('test_api_connection', {
         providerType: selectedProvider,
         apiKey: key || null,
         model: null, // Use provider default
       })
       setTestResult(result)
 
-      if (result.success) {
-        // Build settings with optional serpapi config
-        const settings: Record<string, unknown> = {
-          provider: {
-            provider_type: selectedProvider,
-            api_key: key || null,
-            model: null, // Use provider default
-            base_url: null,
-          },
-          approval: { auto_approve_level: 'low', show_confirmation_dialogs: true },
-          ui: { theme: 'system', font_size: 14, show_tool_calls: true },
-        }
+      // A model that can't call tools is only saved once the user confirms
+      if (result.auth_ok && result.tools_ok) {
+        await saveSettings(key)
+      }
+    } catch (err) {
+      setTestResult({
+        auth_ok: false,
+        tools_ok: false,
+        streaming_ok: false,
+        context_limit: null,
+        latency_ms: 0,
+        warnings: [],
+        error: String(err),
+      })
+    } finally {
+      setIsLoading(false)
+    }
+  }
 
-        // Add web_search config if serpapi key provided
-        if (serpApiKey.trim()) {
-          settings.web_search = {
-            api_key: serpApiKey,
-          }
-        }
+  const saveSettings = async (key: string) => {
+    // Build settings with optional serpapi config
+    const settings: Record<string, unknown> = {
+      provider: {
+        provider_type: selectedProvider,
+        api_key: key || null,
+        model: null, // Use provider default
+        base_url: null,
+      },
+      approval: { auto_approve_level: 'low', show_confirmation_dialogs: true },
+      ui: { theme: 'system', font_size: 14, show_tool_calls: true },
+    }
 
-        await invoke('update_settings', { settings })
-        await invoke('save_settings')
+    // Add web_search config if serpapi key provided
+    if (serpApiKey.trim()) {
+      settings.web_search = {
+        api_key: serpApiKey,
       }
+    }
+
+    await invoke('update_settings', { settings })
+    await invoke('save_settings')
+    setSaved(true)
+  }
+
+  const handleUseAnyway = async () => {
+    setIsLoading(true)
+    try {
+      await saveSettings(apiKey)
     } catch (err) {
-      setTestResult({ success: false, message: String(err) })
+      setError(String(err))
     } finally {
       setIsLoading(false)
     }
@@ -153,6 +185,7 @@ export default function Onboarding({ onComplete }: OnboardingProps) {
   const handleBack = () => {
     setError(null)
     setTestResult(null)
+    setSaved(false)
     if (step === 'apikey') setStep('provider')
     else if (step === 'serpapi') setStep('apikey')
     else if (step === 'testing') {
@@ -309,17 +342,43 @@ export default function Onboarding({ onComplete }: OnboardingProps) {
                   <Loader2 className="w-8 h-8 text-blue-500 animate-spin mx-auto" />
                   <p className="mt-2 text-sm text-gray-500">Testing...</p>
                 </>
-              ) : testResult?.success ? (
+              ) : saved && testResult ? (
                 <>
                   <CheckCircle2 className="w-10 h-10 text-green-500 mx-auto" />
                   <p className="mt-2 font-medium text-gray-900 dark:text-white">Connected!</p>
+                  <CapabilityBadges report={testResult} />
                   <p className="text-xs text-gray-500 mt-0.5">Starting...</p>
                 </>
+              ) : testResult?.auth_ok ? (
+                <>
+                  <AlertCircle className="w-10 h-10 text-amber-500 mx-auto" />
+                  <p className="mt-2 font-medium text-sm text-gray-900 dark:text-white">Connected, with problems</p>
+                  <CapabilityBadges report={testResult} />
+                  <ul className="text-xs text-amber-600 mt-2 max-w-[280px] mx-auto space-y-1 text-left">
+                    {testResult.warnings.map((warning) => (
+                      <li key={warning}>{warning}</li>
+                    ))}
+                  </ul>
+                  <div className="mt-3 flex justify-center gap-2">
+                    <button
+                      onClick={handleBack}
+                      className="px-3 py-1.5 bg-gray-100 dark:bg-gray-700 hover:bg-gray-200 dark:hover:bg-gray-600 rounded text-sm transition-colors"
+                    >
+                      Try Again
+                    </button>
+                    <button
+                      onClick={handleUseAnyway}
+                      className="px-3 py-1.5 bg-amber-500 hover:bg-amber-600 text-white rounded text-sm transition-colors"
+                    >
+                      Use Anyway
+                    </button>
+                  </div>
+                </>
               ) : (
                 <>
                   <AlertCircle className="w-10 h-10 text-red-500 mx-auto" />
                   <p className="mt-2 font-medium text-sm text-gray-900 dark:text-white">Failed</p>
-                  <p className="text-xs text-red-500 mt-1 max-w-[250px] mx-auto">{testResult?.message}</p>
+                  <p className="text-xs text-red-500 mt-1 max-w-[250px] mx-auto">{testResult?.error}</p>
                   <button
                     onClick={handleBack}
                     className="mt-3 px-3 py-1.5 bg-gray-100 dark:bg-gray-700 hover:bg-gray-200 dark:hover:bg-gray-600 rounded text-sm transition-colors"
@@ -335,3 +394,29 @@ export default function Onboarding({ onComplete }: OnboardingProps) {
     </div>
   )
 }
+
+function CapabilityBadges({ report }: { report: TestReport }) {
+  const badge = (ok: boolean, label: string) => (
+    <span
+      className={`px-1.5 py-0.5 rounded text-[10px] font-medium ${
+        ok ? 'bg-green-100 text-green-700 dark:bg-green-900/40 dark:text-green-300' : 'bg-red-100 text-red-700 dark:bg-red-900/40 dark:text-red-300'
+      }`}
+    >
+      {ok ? '✓' : '✗'} {label}
+    </span>
+  )
+  return (
+    <div className="mt-2 flex flex-wrap justify-center gap-1">
+      {badge(report.tools_ok, 'Tools')}
+      {badge(report.streaming_ok, 'Streaming')}
+      {report.context_limit !== null && (
+        <span className="px-1.5 py-0.5 rounded text-[10px] font-medium bg-gray-100 text-gray-600 dark:bg-gray-700 dark:text-gray-300">
+          {Math.round(report.context_limit / 1000)}k context
+        </span>
+      )}
+      <span className="px-1.5 py-0.5 rounded text-[10px] font-medium bg-gray-100 text-gray-600 dark:bg-gray-700 dark:text-gray-300">
+        {report.latency_ms} ms
+      </span>
+    </div>
+  )
+}
@@ -0,0 +1,85 @@
+import { CheckCircle2, Circle, Loader2, MinusCircle, ScrollText, X, XCircle } from 'lucide-react'
+import type { RecipeProgress } from '../bindings'
+
+interface RecipePanelProps {
+  recipe?: RecipeProgress
+  onDismiss?: () => void
+}
+
+/** Checklist of a running recipe's steps, kept until dismissed once it finishes */
+export default function RecipePanel({ recipe, onDismiss }: RecipePanelProps) {
+  if (!recipe) {
+    return null
+  }
+
+  const done = recipe.steps.filter((s) => s.status === 'succeeded').length
+
+  return (
+    <div
+      className="
+        fixed left-4 bottom-28
+        w-72
+        bg-white dark:bg-gray-800
+        border border-gray-300 dark:border-gray-600
+        rounded-lg shadow-lg
+        z-40
+      "
+    >
+      {/* Header */}
+      <div className="flex items-center justify-between px-4 py-3 border-b border-gray-200 dark:border-gray-700">
+        <div className="flex items-center gap-2 min-w-0">
+          <ScrollText className="w-5 h-5 text-primary-600" />
+          <span className="font-medium text-gray-900 dark:text-white truncate">{recipe.name}</span>
+        </div>
+        <div className="flex items-center gap-2">
+          <span className="text-xs text-gray-500">
+            {done}/{recipe.steps.length}
+          </span>
+          {recipe.finished && (
+            <button onClick={onDismiss} className="text-gray-400 hover:text-gray-600" title="Dismiss">
+              <X className="w-4 h-4" />
+            </button>
+          )}
+        </div>
+      </div>
+
+      {/* Steps */}
+      <div className="max-h-80 overflow-y-auto">
+        {recipe.steps.map((step) => (
+          <div
+            key={step.id}
+            className={`
+              flex items-start gap-3 px-4 py-2.5
+              border-b border-gray-100 dark:border-gray-700 last:border-b-0
+              ${step.status === 'running' ? 'bg-primary-50 dark:bg-primary-900/20' : ''}
+            `}
+          >
+            <div className="mt-0.5">
+              {step.status === 'succeeded' ? (
+                <CheckCircle2 className="w-4 h-4 text-green-500" />
+              ) : step.status === 'failed' ? (
+                <XCircle className="w-4 h-4 text-red-500" />
+              ) : step.status === 'skipped' ? (
+                <MinusCircle className="w-4 h-4 text-gray-400" />
+              ) : step.status === 'running' ? (
+                <Loader2 className="w-4 h-4 text-primary-500 animate-spin" />
+              ) : (
+                <Circle className="w-4 h-4 text-gray-300 dark:text-gray-600" />
+              )}
+            </div>
+            <div className="flex-1 min-w-0">
+              <p className="text-sm text-gray-700 dark:text-gray-300 truncate" title={step.label}>
+                {step.label}
+              </p>
+              {step.status === 'failed' && step.output && (
+                <p className="text-xs text-red-600 dark:text-red-400 truncate" title={step.output}>
+                  {step.output.split('\n')[0]}
+                </p>
+              )}
+            </div>
+          </div>
+        ))}
+      </div>
+    </div>
+  )
+}
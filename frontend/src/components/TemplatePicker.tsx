@@ -0,0 +1,88 @@
+import { useEffect, useState } from 'react'
+import { invoke } from '@tauri-apps/api/core'
+import { LayoutTemplate, Loader2 } from 'lucide-react'
+import type { TemplateEntry } from '../bindings'
+
+interface TemplatePickerProps {
+  onStart: (template: string, args: string) => Promise<void>
+}
+
+/** Session templates offered on an empty chat; picking one starts a new session from it */
+export default function TemplatePicker({ onStart }: TemplatePickerProps) {
+  const [entries, setEntries] = useState<TemplateEntry[]>([])
+  const [selected, setSelected] = useState<string | null>(null)
+  const [args, setArgs] = useState('')
+  const [starting, setStarting] = useState(false)
+
+  useEffect(() => {
+    invoke<TemplateEntry[]>('list_session_templates')
+      .then(setEntries)
+      .catch((err) => console.error('Failed to list session templates:', err))
+  }, [])
+
+  if (entries.length === 0) {
+    return null
+  }
+
+  const template = entries.find((e) => e.name === selected)?.template
+
+  const handleStart = async () => {
+    if (!selected) return
+    setStarting(true)
+    try {
+      await onStart(selected, args)
+      setSelected(null)
+      setArgs('')
+    } finally {
+      setStarting(false)
+    }
+  }
+
+  return (
+    <div className="mt-8 max-w-lg mx-auto text-left">
+      <p className="text-sm font-medium text-muted-foreground mb-2 flex items-center gap-2">
+        <LayoutTemplate className="w-4 h-4" />
+        Start from a template
+      </p>
+      <div className="flex flex-wrap gap-2">
+        {entries.map((entry) => (
+          <button
+            key={entry.name}
+            onClick={() => setSelected(entry.name === selected ? null : entry.name)}
+            disabled={!entry.template}
+            title={entry.error ?? entry.template?.description}
+            className={`
+              px-3 py-1.5 rounded-lg border text-sm transition-colors
+              ${entry.name === selected ? 'border-primary bg-primary/10 text-foreground' : 'border-border text-muted-foreground hover:text-foreground'}
+              ${entry.template ? '' : 'opacity-50 line-through cursor-not-allowed'}
+            `}
+          >
+            {entry.name}
+          </button>
+        ))}
+      </div>
+      {template && (
+        <div className="mt-3 space-y-2">
+          {template.description && <p className="text-xs text-muted-foreground">{template.description}</p>}
+          <div className="flex gap-2">
+            <input
+              value={args}
+              onChange={(e) => setArgs(e.target.value)}
+              onKeyDown={(e) => e.key === 'Enter' && handleStart()}
+              placeholder={template.argument_hint ?? 'Arguments (optional)'}
+              className="flex-1 px-3 py-1.5 rounded-lg border border-border bg-background text-sm"
+            />
+            <button
+              onClick={handleStart}
+              disabled={starting}
+              className="px-3 py-1.5 rounded-lg bg-primary text-primary-foreground text-sm flex items-center gap-1"
+            >
+              {starting && <Loader2 className="w-3.5 h-3.5 animate-spin" />}
+              Start
+            </button>
+          </div>
+        </div>
+      )}
+    </div>
+  )
+}
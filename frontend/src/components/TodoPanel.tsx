@@ -1,10 +1,5 @@
 import { CheckCircle2, Circle, Loader2, ListTodo } from 'lucide-react'
-
-interface TodoItem {
-  content: string
-  status: 'pending' | 'in_progress' | 'completed'
-  activeForm: string
-}
+import type { TodoItem } from '../bindings'
 
 interface TodoPanelProps {
   todos: TodoItem[]
@@ -90,9 +85,9 @@ export default function TodoPanel({ todos, collapsed = false, onToggleCollapse }
 
       {/* Todo list */}
       <div className="max-h-80 overflow-y-auto">
-        {todos.map((todo, idx) => (
+        {todos.map((todo) => (
           <div
-            key={idx}
+            key={todo.id}
             className={`
               flex items-start gap-3 px-4 py-2.5
               border-b border-gray-100 dark:border-gray-700 last:border-b-0
@@ -1,6 +1,13 @@
+import { X } from 'lucide-react'
+import { openPath } from '@tauri-apps/plugin-opener'
+
 interface ToolCallMessageProps {
   formatted: string
+  /** The file the tool works on; clicking the call opens it */
+  absolutePath?: string
   elapsedSecs?: number
+  /** Set while the tool is still running; shows a cancel button */
+  onCancel?: () => void
 }
 
 function formatElapsed(secs: number): string {
@@ -14,18 +21,37 @@ function formatElapsed(secs: number): string {
 /**
  * Renders a tool call in Claude Code style: ● ToolName(args...) [Xs]
  */
-export default function ToolCallMessage({ formatted, elapsedSecs }: ToolCallMessageProps) {
+export default function ToolCallMessage({ formatted, absolutePath, elapsedSecs, onCancel }: ToolCallMessageProps) {
   const elapsed = elapsedSecs ? formatElapsed(elapsedSecs) : ''
 
   return (
     <div className="flex items-start gap-2 py-1">
       <span className="text-foreground font-medium select-none">●</span>
-      <span className="font-mono text-sm text-cyan-600 dark:text-cyan-400 break-all">
-        {formatted}
-      </span>
+      {absolutePath ? (
+        <button
+          onClick={() => openPath(absolutePath).catch((err) => console.error('Failed to open:', err))}
+          title={absolutePath}
+          className="font-mono text-sm text-cyan-600 dark:text-cyan-400 break-all text-left hover:underline"
+        >
+          {formatted}
+        </button>
+      ) : (
+        <span className="font-mono text-sm text-cyan-600 dark:text-cyan-400 break-all">
+          {formatted}
+        </span>
+      )}
       {elapsed && (
         <span className="font-mono text-xs text-muted-foreground">{elapsed}</span>
       )}
+      {onCancel && (
+        <button
+          onClick={onCancel}
+          title="Cancel this tool"
+          className="p-0.5 rounded text-muted-foreground hover:text-red-500 hover:bg-red-50 dark:hover:bg-red-900/20"
+        >
+          <X className="w-3.5 h-3.5" />
+        </button>
+      )}
     </div>
   )
 }
@@ -1,5 +1,5 @@
 import { useState } from 'react'
-import { ChevronDown, ChevronRight } from 'lucide-react'
+import { ChevronDown, ChevronRight, ExternalLink } from 'lucide-react'
 import type { DiffLine } from '../bindings'
 import DiffPreview from './DiffPreview'
 
@@ -9,6 +9,10 @@ interface ToolResultMessageProps {
   output?: string
   success?: boolean
   elapsedSecs?: number
+  /** The file the tool worked on and its line; shows an open-in-editor button */
+  absolutePath?: string
+  line?: number
+  onOpenInEditor?: (path: string, line?: number) => void
 }
 
 function formatElapsed(secs: number): string {
@@ -29,6 +33,9 @@ export default function ToolResultMessage({
   output,
   success = true,
   elapsedSecs,
+  absolutePath,
+  line,
+  onOpenInEditor,
 }: ToolResultMessageProps) {
   const [expanded, setExpanded] = useState(false)
   const hasExpandableContent = (diffPreview && diffPreview.length > 0) || (output && output.length > 100)
@@ -57,6 +64,18 @@ export default function ToolResultMessage({
         {elapsed && (
           <span className="font-mono text-xs text-muted-foreground">{elapsed}</span>
         )}
+        {absolutePath && onOpenInEditor && (
+          <button
+            onClick={(e) => {
+              e.stopPropagation()
+              onOpenInEditor(absolutePath, line)
+            }}
+            title={`Open in editor: ${absolutePath}${line ? `:${line}` : ''}`}
+            className="p-0.5 rounded text-muted-foreground hover:text-foreground hover:bg-muted"
+          >
+            <ExternalLink className="w-3.5 h-3.5" />
+          </button>
+        )}
       </div>
 
       {expanded && diffPreview && diffPreview.length > 0 && (
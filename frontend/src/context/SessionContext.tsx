@@ -1,7 +1,7 @@
 import { createContext, useContext, useState, useCallback, useEffect, useRef, ReactNode } from 'react'
 import { invoke } from '@tauri-apps/api/core'
 import { listen } from '@tauri-apps/api/event'
-import type { LoopOutput, Session, SessionProvider as SessionProviderType } from '../bindings'
+import type { Capabilities, CompactionMode, GenerationParams, LoopOutput, Session, SessionProvider as SessionProviderType, TranscriptSync, TurnOverrides, WorktreeSummary } from '../bindings'
 import { createSession, generateSessionId } from '../bindings'
 
 /** Image data for sending with messages */
@@ -15,15 +15,20 @@ interface SessionContextType {
   activeSessionId: string | null
   isInitialized: boolean
   hasApiKey: boolean | null
+  // What the core supports; null until loaded (or from a core without get_capabilities)
+  capabilities: Capabilities | null
 
   // Session management
   setActiveSession: (id: string) => void
   createNewSession: (name?: string, provider?: SessionProviderType) => string
+  // Start a session from a template; its opening message (with args filled in) is sent right away
+  createSessionFromTemplate: (template: string, args?: string) => Promise<string>
   closeSession: (id: string) => Promise<void>
   updateSessionProvider: (id: string, provider: SessionProviderType) => void
 
   // Message sending
-  sendMessage: (content: string, sessionId?: string) => Promise<void>
+  // `overrides` apply to this message's turn only
+  sendMessage: (content: string, sessionId?: string, overrides?: TurnOverrides) => Promise<void>
   sendMessageWithImages: (content: string, images: ImageData[], sessionId?: string) => Promise<void>
 
   // Tool approval
@@ -31,12 +36,41 @@ interface SessionContextType {
   rejectTool: (toolId: string, sessionId?: string) => Promise<void>
   approveToolForSession: (toolId: string, toolName: string, sessionId?: string) => Promise<void>
   approveAllForSession: (toolId: string, sessionId?: string) => Promise<void>
+  // A reviewer settling a call delegated to them; `approver` goes to the audit log
+  approveDelegated: (toolId: string, approver: string, sessionId: string) => Promise<void>
+  rejectDelegated: (toolId: string, approver: string, reason: string | undefined, sessionId: string) => Promise<void>
 
   // Question answering
   answerQuestion: (requestId: string, answers: Record<string, string>, sessionId?: string) => Promise<void>
 
   // Cancel current turn
   cancelSession: (sessionId?: string) => Promise<void>
+  // Cancel one running or pending tool; the rest of the turn continues
+  cancelTool: (toolCallId: string, sessionId?: string) => Promise<void>
+  // Ask why a pending tool is wanted; the answer fills the approval dialog (once per call)
+  explainTool: (toolCallId: string, sessionId?: string) => Promise<void>
+
+  // Generation parameters (temperature, max_tokens, top_p, stop sequences)
+  setGenerationParams: (params: GenerationParams, sessionId?: string) => Promise<void>
+  regenerateLast: (temperature?: number, sessionId?: string) => Promise<void>
+  editUserMessage: (messageId: string, newContent: string, sessionId?: string) => Promise<void>
+  approvePlan: (name?: string, sessionId?: string) => Promise<void>
+  runRecipe: (name: string, args?: string, sessionId?: string) => Promise<void>
+  compactSession: (options?: { mode?: CompactionMode; model?: string; focus?: string }, sessionId?: string) => Promise<void>
+  setKeepAlive: (keepAlive: boolean, sessionId?: string) => Promise<void>
+  // Text selected in the chat, sent with the session's next message ('' clears it)
+  setContextSelection: (text: string, sessionId?: string) => Promise<void>
+  // The user's own title; a generated one never replaces it
+  renameSession: (title: string, sessionId?: string) => Promise<void>
+  // Tools offered to the model from the next turn (a preset or a custom profile)
+  setToolProfile: (profile: string, sessionId?: string) => Promise<void>
+
+  // Worktree isolation: review, then merge back (which ends the session)
+  getWorktreeSummary: (sessionId?: string) => Promise<WorktreeSummary>
+  mergeWorktree: (sessionId?: string) => Promise<WorktreeSummary>
+
+  // Open a file of the workspace in the user's editor, at a line if given
+  openInEditor: (path: string, line?: number, sessionId?: string) => Promise<void>
 
   // Get active session
   getActiveSession: () => Session | undefined
@@ -61,6 +95,9 @@ function truncateStr(s: string, max: number): string {
   return s.slice(0, max - 3) + '...'
 }
 
+// Output lines kept in a running tool's live tail
+const TOOL_TAIL_LINES = 5
+
 /** Format ephemeral display for tool execution (up to 3 lines) */
 function formatEphemeral(toolName: string, args: Record<string, unknown>): string {
   const lines: string[] = []
@@ -144,10 +181,18 @@ export function SessionProvider({ children }: SessionProviderProps) {
   const [activeSessionId, setActiveSessionId] = useState<string | null>(null)
   const [isInitialized, setIsInitialized] = useState(false)
   const [hasApiKey, setHasApiKey] = useState<boolean | null>(null)
+  const [capabilities, setCapabilities] = useState<Capabilities | null>(null)
 
   // Per-session auto-approve state (doesn't need re-renders)
   const sessionApprovals = useRef<Map<string, { tools: Set<string>; all: boolean }>>(new Map())
 
+  // Last output seq handled per session, to notice missed outputs
+  const lastSeqs = useRef<Map<string, number>>(new Map())
+
+  // Latest sessions, for event listeners registered once
+  const sessionsRef = useRef(sessions)
+  sessionsRef.current = sessions
+
   // Update a specific session
   const updateSession = useCallback((sessionId: string, updater: (session: Session) => Session) => {
     setSessions(prev => {
@@ -182,7 +227,16 @@ export function SessionProvider({ children }: SessionProviderProps) {
         break
 
       case 'idle':
-        updateSession(sessionId, s => ({ ...s, status: '', ephemeral: null, updatedAt: new Date() }))
+        updateSession(sessionId, s => ({
+          ...s,
+          status: '',
+          ephemeral: null,
+          toolProgress: undefined,
+          delegatedApproval: undefined,
+          turnOverrides: undefined,
+          warning: output.review_warning ? 'The self-review flagged gaps in this turn' : s.warning,
+          updatedAt: new Date(),
+        }))
         break
 
       case 'thinking':
@@ -240,6 +294,7 @@ export function SessionProvider({ children }: SessionProviderProps) {
             name: output.name,
             arguments: output.arguments,
             description: output.description,
+            securityWarning: output.security_warning,
             // If from subagent, route approvals there
             targetSessionId: output.subagent_id,
           },
@@ -247,10 +302,51 @@ export function SessionProvider({ children }: SessionProviderProps) {
         }))
         break
 
+      case 'approval_delegated':
+        // Only a reviewer can approve it; the owner may still cancel it
+        updateSession(sessionId, s => ({
+          ...s,
+          delegatedApproval: {
+            id: output.id,
+            tool: output.tool,
+            reason: output.reason,
+            channel: output.channel,
+            deadline: Date.now() + output.timeout_secs * 1000,
+          },
+          updatedAt: new Date(),
+        }))
+        break
+
+      case 'tool_explanation':
+        // Only while its approval dialog is still open
+        updateSession(sessionId, s => s.modal?.type === 'approval' && s.modal.id === output.id
+          ? { ...s, modal: { ...s.modal, explanation: output.text }, updatedAt: new Date() }
+          : s)
+        break
+
+      case 'tool_progress':
+        // Heartbeat from a long-running tool: keep the last few output lines
+        updateSession(sessionId, s => {
+          const tail = s.toolProgress?.id === output.id ? s.toolProgress.tail : []
+          const line = output.last_line
+          return {
+            ...s,
+            toolProgress: {
+              id: output.id,
+              elapsedMs: output.elapsed_ms,
+              outputBytes: output.output_bytes,
+              tail: line && tail[tail.length - 1] !== line ? [...tail, line].slice(-TOOL_TAIL_LINES) : tail,
+            },
+            updatedAt: new Date(),
+          }
+        })
+        break
+
       case 'tool_done':
         updateSession(sessionId, s => ({
           ...s,
-          ephemeral: `${output.name}: ${output.success ? 'done' : 'error'}`,
+          ephemeral: `${output.name}: ${output.status === 'cancelled' ? 'cancelled' : output.success ? 'done' : 'error'}`,
+          delegatedApproval: s.delegatedApproval?.id === output.id ? undefined : s.delegatedApproval,
           updatedAt: new Date(),
         }))
         break
@@ -267,6 +363,7 @@ export function SessionProvider({ children }: SessionProviderProps) {
               content: '',
               toolName: output.name,
               formatted: output.formatted,
+              absolutePath: output.absolute_path,
               elapsedSecs,
             }],
             updatedAt: new Date(),
@@ -281,6 +378,8 @@ export function SessionProvider({ children }: SessionProviderProps) {
           return {
             ...s,
             ephemeral: null,  // Clear ephemeral since we have the result
+            toolProgress: s.toolProgress?.id === output.id ? undefined : s.toolProgress,
+            delegatedApproval: s.delegatedApproval?.id === output.id ? undefined : s.delegatedApproval,
             messages: [...s.messages, {
               id: `${output.id}-result`,
               type: 'tool_result' as const,
@@ -289,6 +388,8 @@ export function SessionProvider({ children }: SessionProviderProps) {
               summary: output.summary,
               success: output.success,
               diffPreview: output.diff_preview || undefined,
+              absolutePath: output.absolute_path,
+              line: output.line,
               expanded: false,
               elapsedSecs,
             }],
@@ -320,6 +421,58 @@ export function SessionProvider({ children }: SessionProviderProps) {
         }))
         break
 
+      case 'warning':
+        updateSession(sessionId, s => ({
+          ...s,
+          warning: output.message,
+          updatedAt: new Date(),
+        }))
+        break
+
+      case 'refusal':
+        updateSession(sessionId, s => ({
+          ...s,
+          error: output.message
+            ? `Response refused (${output.reason}): ${output.message}`
+            : `Response refused (${output.reason})`,
+          status: '',
+          updatedAt: new Date(),
+        }))
+        break
+
+      case 'history_truncated':
+        // The message and everything after it are re-run; the new turn echoes its own user_message
+        updateSession(sessionId, s => {
+          const index = s.messages.findIndex(m => m.id === output.from_message_id)
+          return index < 0 ? s : { ...s, messages: s.messages.slice(0, index), error: null, updatedAt: new Date() }
+        })
+        break
+
+      case 'suspended':
+        updateSession(sessionId, s => ({ ...s, suspended: true, isReady: false, updatedAt: new Date() }))
+        break
+
+      case 'resumed': {
+        // Follows ready; the history is already on screen
+        const minutes = Math.round(output.suspended_secs / 60)
+        updateSession(sessionId, s => ({
+          ...s,
+          suspended: false,
+          warning: `Resumed after ${minutes} min suspended while idle`,
+          updatedAt: new Date(),
+        }))
+        break
+      }
+
+      case 'worktree':
+        // Follows ready for a session isolated in a git worktree
+        updateSession(sessionId, s => ({
+          ...s,
+          worktree: { path: output.path, branch: output.branch },
+          updatedAt: new Date(),
+        }))
+        break
+
       case 'stopped':
         updateSession(sessionId, s => ({
           ...s,
@@ -335,9 +488,123 @@ export function SessionProvider({ children }: SessionProviderProps) {
           status: '',
           ephemeral: null,
           modal: null,
+          delegatedApproval: undefined,
+          updatedAt: new Date(),
+        }))
+        break
+
+      case 'generation_params_changed':
+        updateSession(sessionId, s => ({
+          ...s,
+          generationParams: output.params,
+          updatedAt: new Date(),
+        }))
+        break
+
+      case 'plan_status_changed':
+        updateSession(sessionId, s => ({
+          ...s,
+          planStatus: { name: output.name, status: output.status },
+          updatedAt: new Date(),
+        }))
+        break
+
+      case 'todos_updated':
+        updateSession(sessionId, s => ({
+          ...s,
+          todos: output.items,
+          updatedAt: new Date(),
+        }))
+        break
+
+      case 'title_changed':
+        updateSession(sessionId, s => ({ ...s, name: output.title }))
+        break
+
+      case 'tool_profile_changed':
+        updateSession(sessionId, s => ({
+          ...s,
+          toolProfile: { name: output.profile, tools: output.tools, definitionTokens: output.definition_tokens },
+        }))
+        break
+
+      case 'turn_overridden':
+        updateSession(sessionId, s => ({
+          ...s,
+          turnOverrides: { model: output.model, approval: output.approval, tool_profile: output.tool_profile },
+        }))
+        break
+
+      case 'recipe_started':
+        updateSession(sessionId, s => ({
+          ...s,
+          recipe: {
+            name: output.name,
+            steps: output.steps.map(step => ({ ...step, status: 'pending' as const })),
+            finished: false,
+          },
           updatedAt: new Date(),
         }))
         break
+
+      case 'recipe_step_started':
+      case 'recipe_step_finished':
+        updateSession(sessionId, s => {
+          if (!s.recipe || s.recipe.name !== output.name) return s
+          const update = output.type === 'recipe_step_started'
+            ? { status: 'running' as const }
+            : { status: output.status, output: output.output }
+          return {
+            ...s,
+            recipe: {
+              ...s.recipe,
+              steps: s.recipe.steps.map(step => step.id === output.step_id ? { ...step, ...update } : step),
+            },
+            updatedAt: new Date(),
+          }
+        })
+        break
+
+      case 'recipe_finished':
+        updateSession(sessionId, s => ({
+          ...s,
+          recipe: s.recipe && s.recipe.name === output.name
+            ? { ...s.recipe, finished: true, success: output.success }
+            : s.recipe,
+          warning: output.success ? s.warning : output.summary.split('\n')[0],
+          updatedAt: new Date(),
+        }))
+        break
+
+      case 'compacted': {
+        const by = output.model ? ` by ${output.model}` : ''
+        updateSession(sessionId, s => ({
+          ...s,
+          warning: `Compacted ${output.messages_summarized} messages (${output.mode}${by}): ~${output.tokens_before} -> ~${output.tokens_after} tokens`,
+          updatedAt: new Date(),
+        }))
+        break
+      }
+
+      case 'self_review':
+        updateSession(sessionId, s => ({
+          ...s,
+          messages: [...s.messages, {
+            id: `self-review-${Date.now()}`,
+            type: 'self_review' as const,
+            content: '',
+            findings: output.findings,
+          }],
+          updatedAt: new Date(),
+        }))
+        break
+
+      case 'unsupported_input':
+        updateSession(sessionId, s => ({
+          ...s,
+          warning: `This version of Cowork doesn't support ${output.input_type} (protocol ${output.protocol_version})`,
+        }))
+        break
     }
   }, [updateSession])
 
@@ -346,12 +613,10 @@ export function SessionProvider({ children }: SessionProviderProps) {
     let unlistenFn: (() => void) | null = null
 
     const init = async () => {
-      // 1. Set up event listener FIRST
-      unlistenFn = await listen<LoopOutput>('loop_output', (event) => {
-        const output = event.payload
-
-        // Auto-approve tools if session has approved them
-        if (output.type === 'tool_pending') {
+      const deliver = (output: LoopOutput) => {
+        // Auto-approve tools if session has approved them (never a call
+        // flagged as possible secret exfiltration or changing generated files)
+        if (output.type === 'tool_pending' && !output.security_warning && !output.generated_files?.length) {
           const approvals = sessionApprovals.current.get(output.session_id)
           if (approvals && (approvals.all || approvals.tools.has(output.name))) {
             invoke('approve_tool', { toolId: output.id, sessionId: output.session_id })
@@ -360,6 +625,43 @@ export function SessionProvider({ children }: SessionProviderProps) {
         }
 
         handleOutput(output)
+      }
+
+      // Fetch the outputs missed after `since`, including approvals and
+      // questions still waiting that are older than the outputs kept
+      const catchUp = async (sessionId: string, since: number) => {
+        try {
+          const sync = await invoke<TranscriptSync>('get_session_transcript', { sessionId, sinceSeq: since })
+          const missed = [...sync.events]
+          for (const pending of sync.pending) {
+            if (pending.seq > since && !missed.some(e => e.seq === pending.seq)) missed.push(pending)
+          }
+          missed.sort((a, b) => a.seq - b.seq)
+          for (const output of missed) {
+            deliver({ ...output, session_id: sessionId } as LoopOutput)
+          }
+          lastSeqs.current.set(sessionId, Math.max(since, sync.last_seq))
+        } catch (err) {
+          console.error('Failed to catch up on session outputs:', err)
+        }
+      }
+
+      // Outputs are handled one at a time, so a catch-up finishes before
+      // the outputs after it
+      let received = Promise.resolve()
+      const receive = async (output: LoopOutput) => {
+        if (output.seq !== undefined) {
+          const last = lastSeqs.current.get(output.session_id) ?? 0
+          if (output.seq > last + 1) await catchUp(output.session_id, last)
+          if (output.seq <= (lastSeqs.current.get(output.session_id) ?? 0)) return
+          lastSeqs.current.set(output.session_id, output.seq)
+        }
+        deliver(output)
+      }
+
+      // 1. Set up event listener FIRST
+      unlistenFn = await listen<LoopOutput>('loop_output', (event) => {
+        received = received.then(() => receive(event.payload))
       })
 
       // 2. Check API key
@@ -371,11 +673,19 @@ export function SessionProvider({ children }: SessionProviderProps) {
         if (hasKey) {
           await invoke('start_loop')
 
+          invoke<Capabilities>('get_capabilities')
+            .then(setCapabilities)
+            .catch((err) => console.error('Failed to get capabilities:', err))
+
           // Create default session
           const defaultSession = createSession('default', 'Main Session')
           defaultSession.isReady = true
           setSessions(new Map([['default', defaultSession]]))
           setActiveSessionId('default')
+
+          // After a reload, recover what the default session already said
+          // and anything it is waiting on
+          received = received.then(() => catchUp('default', lastSeqs.current.get('default') ?? 0))
         }
 
         setIsInitialized(true)
@@ -394,6 +704,18 @@ export function SessionProvider({ children }: SessionProviderProps) {
     }
   }, [handleOutput])
 
+  // Focus the session a clicked notification was about
+  useEffect(() => {
+    const unlisten = listen<string>('focus_session', (event) => {
+      if (sessionsRef.current.has(event.payload)) {
+        setActiveSessionId(event.payload)
+      }
+    })
+    return () => {
+      unlisten.then(fn => fn())
+    }
+  }, [])
+
   // Session management
   const setActiveSession = useCallback((id: string) => {
     if (sessions.has(id)) {
@@ -410,6 +732,29 @@ export function SessionProvider({ children }: SessionProviderProps) {
     return id
   }, [])
 
+  const createSessionFromTemplate = useCallback(async (template: string, args?: string): Promise<string> => {
+    const id = generateSessionId()
+    const session = createSession(id, template)
+    session.isReady = true
+    session.template = template
+    session.turnStart = Date.now()
+    const previousId = activeSessionId
+    setSessions(prev => new Map(prev).set(id, session))
+    setActiveSessionId(id)
+    try {
+      await invoke('create_session_from_template', { sessionId: id, template, args: args || null })
+    } catch (err) {
+      setSessions(prev => {
+        const next = new Map(prev)
+        next.delete(id)
+        return next
+      })
+      setActiveSessionId(previousId)
+      throw err
+    }
+    return id
+  }, [activeSessionId])
+
   const updateSessionProvider = useCallback((id: string, provider: SessionProviderType) => {
     updateSession(id, s => ({ ...s, provider }))
   }, [updateSession])
@@ -434,13 +779,16 @@ export function SessionProvider({ children }: SessionProviderProps) {
   }, [activeSessionId, sessions])
 
   // Message sending
-  const sendMessage = useCallback(async (content: string, sessionId?: string) => {
+  const sendMessage = useCallback(async (content: string, sessionId?: string, overrides?: TurnOverrides) => {
     const targetId = sessionId || activeSessionId
     if (!targetId) throw new Error('No active session')
 
     // Set turnStart for elapsed time tracking
     updateSession(targetId, s => ({ ...s, error: null, turnStart: Date.now() }))
-    await invoke('send_message', { content, sessionId: targetId })
+    const position = await invoke<number | null>('send_message', { content, sessionId: targetId, overrides: overrides ?? null })
+    if (position) {
+      updateSession(targetId, s => ({ ...s, status: `Queued (${position})` }))
+    }
   }, [activeSessionId, updateSession])
 
   // Message sending with images
@@ -450,7 +798,10 @@ export function SessionProvider({ children }: SessionProviderProps) {
 
     // Set turnStart for elapsed time tracking
     updateSession(targetId, s => ({ ...s, error: null, turnStart: Date.now() }))
-    await invoke('send_message_with_images', { content, images, sessionId: targetId })
+    const position = await invoke<number | null>('send_message_with_images', { content, images, sessionId: targetId })
+    if (position) {
+      updateSession(targetId, s => ({ ...s, status: `Queued (${position})` }))
+    }
   }, [activeSessionId, updateSession])
 
   // Tool approval
@@ -501,6 +852,15 @@ export function SessionProvider({ children }: SessionProviderProps) {
     updateSession(targetId, s => ({ ...s, modal: null }))
   }, [activeSessionId, updateSession])
 
+  // Delegated approvals: settled by a reviewer, possibly from another window
+  const approveDelegated = useCallback(async (toolId: string, approver: string, sessionId: string) => {
+    await invoke('approve_delegated', { toolId, approver, sessionId })
+  }, [])
+
+  const rejectDelegated = useCallback(async (toolId: string, approver: string, reason: string | undefined, sessionId: string) => {
+    await invoke('reject_delegated', { toolId, approver, reason, sessionId })
+  }, [])
+
   // Question answering
   const answerQuestion = useCallback(async (requestId: string, answers: Record<string, string>, sessionId?: string) => {
     const targetId = sessionId || activeSessionId
@@ -519,6 +879,143 @@ export function SessionProvider({ children }: SessionProviderProps) {
     updateSession(targetId, s => ({ ...s, modal: null, status: '', ephemeral: null }))
   }, [activeSessionId, updateSession])
 
+  // Cancel a single tool call; the session reports it with a cancelled tool_done
+  const cancelTool = useCallback(async (toolCallId: string, sessionId?: string) => {
+    const targetId = sessionId || activeSessionId
+    if (!targetId) throw new Error('No active session')
+
+    await invoke('cancel_tool', { sessionId: targetId, toolCallId })
+  }, [activeSessionId])
+
+  // Explain a pending tool call; the session answers with tool_explanation
+  const explainTool = useCallback(async (toolCallId: string, sessionId?: string) => {
+    const targetId = sessionId || activeSessionId
+    if (!targetId) throw new Error('No active session')
+
+    updateSession(targetId, s => s.modal?.type === 'approval' && s.modal.id === toolCallId
+      ? { ...s, modal: { ...s.modal, explainRequested: true } }
+      : s)
+    await invoke('explain_tool', { sessionId: targetId, toolCallId })
+  }, [activeSessionId, updateSession])
+
+  // Generation parameters; the session confirms with generation_params_changed
+  const setGenerationParams = useCallback(async (params: GenerationParams, sessionId?: string) => {
+    const targetId = sessionId || activeSessionId
+    if (!targetId) throw new Error('No active session')
+
+    await invoke('set_generation_params', { sessionId: targetId, params })
+  }, [activeSessionId])
+
+  // Re-runs of an earlier turn; the session reports the discarded messages with history_truncated
+  const regenerateLast = useCallback(async (temperature?: number, sessionId?: string) => {
+    const targetId = sessionId || activeSessionId
+    if (!targetId) throw new Error('No active session')
+
+    await invoke('regenerate_last', { sessionId: targetId, temperature: temperature ?? null })
+  }, [activeSessionId])
+
+  const editUserMessage = useCallback(async (messageId: string, newContent: string, sessionId?: string) => {
+    const targetId = sessionId || activeSessionId
+    if (!targetId) throw new Error('No active session')
+
+    await invoke('edit_user_message', { sessionId: targetId, messageId, newContent })
+  }, [activeSessionId])
+
+  // Approve a plan (default: the session's current plan); it is injected into the next turn
+  const approvePlan = useCallback(async (name?: string, sessionId?: string) => {
+    const targetId = sessionId || activeSessionId
+    if (!targetId) throw new Error('No active session')
+
+    await invoke('approve_plan', { sessionId: targetId, name: name ?? null })
+  }, [activeSessionId])
+
+  // Run a workspace recipe as a turn; progress arrives as recipe_* events
+  const runRecipe = useCallback(async (name: string, args?: string, sessionId?: string) => {
+    const targetId = sessionId || activeSessionId
+    if (!targetId) throw new Error('No active session')
+
+    updateSession(targetId, s => ({ ...s, status: `Running recipe ${name}...`, turnStart: Date.now() }))
+    await invoke('run_recipe', { sessionId: targetId, name, args: args ?? null })
+  }, [activeSessionId, updateSession])
+
+  // Compact the history between turns; the result arrives as compacted
+  const compactSession = useCallback(async (
+    options?: { mode?: CompactionMode; model?: string; focus?: string },
+    sessionId?: string,
+  ) => {
+    const targetId = sessionId || activeSessionId
+    if (!targetId) throw new Error('No active session')
+
+    updateSession(targetId, s => ({ ...s, status: 'Compacting...', turnStart: Date.now() }))
+    await invoke('compact_session', {
+      sessionId: targetId,
+      mode: options?.mode ?? null,
+      model: options?.model ?? null,
+      focus: options?.focus ?? null,
+    })
+  }, [activeSessionId, updateSession])
+
+  // Exempt a session from idle suspension (or allow it again)
+  const setKeepAlive = useCallback(async (keepAlive: boolean, sessionId?: string) => {
+    const targetId = sessionId || activeSessionId
+    if (!targetId) throw new Error('No active session')
+
+    await invoke('set_session_keep_alive', { sessionId: targetId, keepAlive })
+    updateSession(targetId, s => ({ ...s, keepAlive }))
+  }, [activeSessionId, updateSession])
+
+  const setContextSelection = useCallback(async (text: string, sessionId?: string) => {
+    const targetId = sessionId || activeSessionId
+    if (!targetId) return
+
+    await invoke('set_context_selection', { sessionId: targetId, text })
+  }, [activeSessionId])
+
+  // The open session reports title_changed, which updates the name
+  const renameSession = useCallback(async (title: string, sessionId?: string) => {
+    const targetId = sessionId || activeSessionId
+    if (!targetId) return
+
+    await invoke('rename_session', { sessionId: targetId, title })
+  }, [activeSessionId])
+
+  // The session reports tool_profile_changed when its next turn starts
+  const setToolProfile = useCallback(async (profile: string, sessionId?: string) => {
+    const targetId = sessionId || activeSessionId
+    if (!targetId) return
+
+    await invoke('set_tool_profile', { sessionId: targetId, profile })
+  }, [activeSessionId])
+
+  const getWorktreeSummary = useCallback(async (sessionId?: string) => {
+    const targetId = sessionId || activeSessionId
+    if (!targetId) throw new Error('No active session')
+
+    return await invoke<WorktreeSummary>('get_worktree_summary', { sessionId: targetId })
+  }, [activeSessionId])
+
+  // An isolated session's files are opened from its worktree
+  const openInEditor = useCallback(async (path: string, line?: number, sessionId?: string) => {
+    const targetId = sessionId || activeSessionId
+    await invoke('open_in_editor', { path, line: line ?? null, column: null, sessionId: targetId ?? null })
+  }, [activeSessionId])
+
+  // Merge the worktree into the repository's branch; the session ends and its worktree is removed
+  const mergeWorktree = useCallback(async (sessionId?: string) => {
+    const targetId = sessionId || activeSessionId
+    if (!targetId) throw new Error('No active session')
+
+    const summary = await invoke<WorktreeSummary>('merge_worktree', { sessionId: targetId })
+    updateSession(targetId, s => ({
+      ...s,
+      worktree: undefined,
+      isReady: false,
+      warning: `Merged ${summary.branch} into ${summary.target}; the next message starts a new session`,
+      updatedAt: new Date(),
+    }))
+    return summary
+  }, [activeSessionId, updateSession])
+
   const getActiveSession = useCallback(() => {
     return activeSessionId ? sessions.get(activeSessionId) : undefined
   }, [activeSessionId, sessions])
@@ -528,8 +1025,10 @@ export function SessionProvider({ children }: SessionProviderProps) {
     activeSessionId,
     isInitialized,
     hasApiKey,
+    capabilities,
     setActiveSession,
     createNewSession,
+    createSessionFromTemplate,
     closeSession,
     updateSessionProvider,
     sendMessage,
@@ -538,8 +1037,25 @@ export function SessionProvider({ children }: SessionProviderProps) {
     rejectTool,
     approveToolForSession,
     approveAllForSession,
+    approveDelegated,
+    rejectDelegated,
     answerQuestion,
     cancelSession,
+    cancelTool,
+    explainTool,
+    setGenerationParams,
+    regenerateLast,
+    editUserMessage,
+    approvePlan,
+    runRecipe,
+    compactSession,
+    setKeepAlive,
+    setContextSelection,
+    renameSession,
+    setToolProfile,
+    getWorktreeSummary,
+    mergeWorktree,
+    openInEditor,
     getActiveSession,
   }
 
@@ -1,5 +1,5 @@
 import { useState, useRef, useEffect, useCallback } from 'react'
-import { Send, Loader2, X, AlertCircle, Sparkles, Square, Paperclip } from 'lucide-react'
+import { Send, Loader2, X, AlertCircle, Sparkles, Square, Paperclip, Pencil, RotateCcw, Pin, GitBranch, GitMerge, ClipboardCheck } from 'lucide-react'
 import { Button } from '../components/ui/button'
 import SessionTabs from '../components/SessionTabs'
 import ApprovalModal from '../components/ApprovalModal'
@@ -7,7 +7,13 @@ import QuestionModal from '../components/QuestionModal'
 import ToolCallMessage from '../components/ToolCallMessage'
 import ToolResultMessage from '../components/ToolResultMessage'
 import ClickablePaths from '../components/ClickablePaths'
+import TodoPanel from '../components/TodoPanel'
+import RecipePanel from '../components/RecipePanel'
+import TemplatePicker from '../components/TemplatePicker'
+import MessageOptions from '../components/MessageOptions'
+import MentionSuggestions, { useMentionCompletion } from '../components/MentionSuggestions'
 import { useSession } from '../context/SessionContext'
+import type { CompactionMode, RecipeProgress, TurnOverrides } from '../bindings'
 
 /** Pending image with both preview URL and data for sending */
 interface PendingImage {
@@ -16,14 +22,26 @@ interface PendingImage {
   media_type: string
 }
 
+/** Heartbeat line for a long-running tool, e.g. "Running 1m 5s · 2.0 KB output" */
+function formatToolProgress(elapsedMs: number, outputBytes: number): string {
+  const secs = Math.floor(elapsedMs / 1000)
+  const elapsed = secs >= 60 ? `${Math.floor(secs / 60)}m ${secs % 60}s` : `${secs}s`
+  if (outputBytes === 0) return `Running ${elapsed} · no output yet`
+  const units = ['B', 'KB', 'MB', 'GB']
+  const i = Math.min(Math.floor(Math.log(outputBytes) / Math.log(1024)), units.length - 1)
+  return `Running ${elapsed} · ${(outputBytes / Math.pow(1024, i)).toFixed(i > 0 ? 1 : 0)} ${units[i]} output`
+}
+
 export default function Chat() {
   const {
     sessions,
     activeSessionId,
     isInitialized,
     hasApiKey,
+    capabilities,
     setActiveSession,
     createNewSession,
+    createSessionFromTemplate,
     closeSession,
     sendMessage,
     sendMessageWithImages,
@@ -33,6 +51,18 @@ export default function Chat() {
     approveAllForSession,
     answerQuestion,
     cancelSession,
+    cancelTool,
+    explainTool,
+    regenerateLast,
+    editUserMessage,
+    approvePlan,
+    runRecipe,
+    compactSession,
+    setKeepAlive,
+    setContextSelection,
+    getWorktreeSummary,
+    openInEditor,
+    mergeWorktree,
     getActiveSession,
   } = useSession()
 
@@ -40,16 +70,70 @@ export default function Chat() {
   const [error, setError] = useState<string | null>(null)
   const [pendingImages, setPendingImages] = useState<PendingImage[]>([])
   const [isDragging, setIsDragging] = useState(false)
+  const [todosCollapsed, setTodosCollapsed] = useState(false)
+  // User message being rewritten in the input box (submitting re-runs from it)
+  const [editingId, setEditingId] = useState<string | null>(null)
+  const [messageOverrides, setMessageOverrides] = useState<TurnOverrides>({})
   const messagesEndRef = useRef<HTMLDivElement>(null)
+  const messagesRef = useRef<HTMLDivElement>(null)
   const fileInputRef = useRef<HTMLInputElement>(null)
+  // Older cores don't report capabilities; they take images of any size
+  const attachmentsSupported = capabilities?.features.attachments ?? true
+  const maxAttachmentBytes = capabilities?.limits.max_attachment_bytes
+  const turnOverridesSupported = capabilities?.features.turn_overrides ?? false
   const textInputRef = useRef<HTMLInputElement>(null)
+  const mentions = useMentionCompletion(input, setInput, textInputRef)
 
   const session = getActiveSession()
   const messages = session?.messages || []
   const ephemeral = session?.ephemeral
+  const toolProgress = session?.toolProgress
+  const delegatedApproval = session?.delegatedApproval
+  const turnOverrides = session?.turnOverrides
   const status = session?.status || ''
   const modal = session?.modal || null
   const isReady = session?.isReady ?? false
+  const suspended = session?.suspended ?? false
+  const keepAlive = session?.keepAlive ?? false
+  const worktree = session?.worktree
+  const planStatus = session?.planStatus
+  const todos = session?.todos || []
+  const [dismissedRecipe, setDismissedRecipe] = useState<RecipeProgress | undefined>()
+  const recipe = session?.recipe !== dismissedRecipe ? session?.recipe : undefined
+  // Tool calls without a result yet can be cancelled one by one
+  const finishedToolIds = new Set(messages.filter(m => m.type === 'tool_result').map(m => m.id))
+  const isToolRunning = (id: string) => status !== '' && !finishedToolIds.has(`${id}-result`)
+
+  const handleCancelTool = async (id: string) => {
+    try {
+      await cancelTool(id)
+    } catch (err) {
+      setError(String(err))
+    }
+  }
+
+  // Name of the plan an ExitPlanMode result registered, if any
+  // (matched rather than parsed since long results are truncated)
+  const planNameFromResult = (output: string): string | null => {
+    const match = output.match(/"plan_name"\s*:\s*"([^"]+)"/)
+    return match ? match[1] : null
+  }
+
+  const handleStartTemplate = async (template: string, args: string) => {
+    try {
+      await createSessionFromTemplate(template, args)
+    } catch (err) {
+      setError(String(err))
+    }
+  }
+
+  const handleApprovePlan = async (name: string) => {
+    try {
+      await approvePlan(name)
+    } catch (err) {
+      setError(String(err))
+    }
+  }
 
   // Scroll to bottom on new messages or ephemeral changes
   useEffect(() => {
@@ -63,6 +147,31 @@ export default function Chat() {
     }
   }, [isInitialized, hasApiKey, activeSessionId, modal])
 
+  // Text selected in the transcript goes with the next message (as ${SELECTION}
+  // or a reminder); the session is told once the selection settles. Focusing
+  // the input moves the selection out of the transcript, which keeps it;
+  // clicking elsewhere in the transcript clears it.
+  useEffect(() => {
+    let timer: ReturnType<typeof setTimeout> | undefined
+    let lastSent = ''
+    const handleSelectionChange = () => {
+      clearTimeout(timer)
+      timer = setTimeout(() => {
+        const selection = document.getSelection()
+        if (!selection?.anchorNode || !messagesRef.current?.contains(selection.anchorNode)) return
+        const text = selection.toString()
+        if (text === lastSent) return
+        lastSent = text
+        setContextSelection(text).catch(err => console.error('Selection error:', err))
+      }, 300)
+    }
+    document.addEventListener('selectionchange', handleSelectionChange)
+    return () => {
+      clearTimeout(timer)
+      document.removeEventListener('selectionchange', handleSelectionChange)
+    }
+  }, [setContextSelection])
+
   // Sync session error to local error state
   useEffect(() => {
     if (session?.error) {
@@ -89,12 +198,22 @@ export default function Chat() {
     })
   }
 
+  // Images the core accepts; too large ones are reported and left out
+  const acceptedImages = (files: File[]): File[] => {
+    if (!maxAttachmentBytes) return files
+    const tooLarge = files.filter(f => f.size > maxAttachmentBytes)
+    if (tooLarge.length > 0) {
+      setError(`${tooLarge.map(f => f.name).join(', ')} over the ${Math.floor(maxAttachmentBytes / (1024 * 1024))} MB image limit`)
+    }
+    return files.filter(f => f.size <= maxAttachmentBytes)
+  }
+
   // Handle file selection
   const handleFileSelect = async (e: React.ChangeEvent<HTMLInputElement>) => {
     const files = e.target.files
     if (!files) return
 
-    const imageFiles = Array.from(files).filter(f => f.type.startsWith('image/'))
+    const imageFiles = acceptedImages(Array.from(files).filter(f => f.type.startsWith('image/')))
     if (imageFiles.length === 0) return
 
     try {
@@ -140,6 +259,7 @@ export default function Chat() {
     e.preventDefault()
     e.stopPropagation()
     setIsDragging(false)
+    if (!attachmentsSupported) return
 
     console.log('Drop event - files:', e.dataTransfer.files.length)
     const files = Array.from(e.dataTransfer.files)
@@ -154,7 +274,7 @@ export default function Chat() {
     }
 
     try {
-      const newImages = await Promise.all(imageFiles.map(fileToPendingImage))
+      const newImages = await Promise.all(acceptedImages(imageFiles).map(fileToPendingImage))
       console.log('Processed images:', newImages.map(img => ({
         media_type: img.media_type,
         base64_length: img.base64.length,
@@ -174,12 +294,32 @@ export default function Chat() {
 
     const userMessage = input.trim() || 'What is in this image?'
     const images = [...pendingImages]
+    const editing = editingId
     setInput('')
     setPendingImages([])
+    setEditingId(null)
     setError(null)
 
     try {
-      if (images.length > 0) {
+      // `/recipe run <name> [args]` runs a workspace recipe instead of sending a message
+      const recipeCommand = images.length === 0 && !editing ? userMessage.match(/^\/recipe\s+run\s+(\S+)\s*(.*)$/s) : null
+      // `/compact [--mode llm|heuristic] [--model <model>] [focus]` compacts the history
+      const compactCommand = images.length === 0 && !editing
+        ? userMessage.match(/^\/compact(?:\s+--mode\s+(llm|heuristic))?(?:\s+--model\s+(\S+))?(?:\s+(.*))?$/s)
+        : null
+      if (recipeCommand) {
+        await runRecipe(recipeCommand[1], recipeCommand[2])
+      } else if (compactCommand) {
+        const [, mode, model, focus] = compactCommand
+        await compactSession({
+          mode: mode as CompactionMode | undefined,
+          model,
+          focus: focus?.trim() || undefined,
+        })
+      } else if (editing) {
+        // The edited message keeps its original images
+        await editUserMessage(editing, userMessage)
+      } else if (images.length > 0) {
         // Convert PendingImage to ImageData for backend
         const imagesForBackend = images.map(img => ({
           data: img.base64,
@@ -187,7 +327,9 @@ export default function Chat() {
         }))
         await sendMessageWithImages(userMessage, imagesForBackend)
       } else {
-        await sendMessage(userMessage)
+        const overrides = Object.values(messageOverrides).some(Boolean) ? messageOverrides : undefined
+        setMessageOverrides({})
+        await sendMessage(userMessage, undefined, overrides)
       }
     } catch (err) {
       console.error('Send error:', err)
@@ -222,6 +364,14 @@ export default function Chat() {
     }
   }
 
+  const handleExplain = async (toolId: string) => {
+    try {
+      await explainTool(toolId, targetSessionId)
+    } catch (err) {
+      setError(String(err))
+    }
+  }
+
   const handleApproveAll = async (toolId: string) => {
     try {
       await approveAllForSession(toolId, targetSessionId)
@@ -238,6 +388,63 @@ export default function Chat() {
     }
   }
 
+  // Regenerate and edit are refused while a turn is in flight, so they're only offered when idle
+  const lastUserMessageId = [...messages].reverse().find(m => m.type === 'user')?.id
+
+  const handleToggleKeepAlive = async () => {
+    try {
+      await setKeepAlive(!keepAlive)
+    } catch (err) {
+      setError(String(err))
+    }
+  }
+
+  const handleOpenInEditor = async (path: string, line?: number) => {
+    try {
+      await openInEditor(path, line)
+    } catch (err) {
+      setError(String(err))
+    }
+  }
+
+  // Show what the worktree changed and merge it back once confirmed
+  const handleMergeWorktree = async () => {
+    setError(null)
+    try {
+      const summary = await getWorktreeSummary()
+      const sections = [`${summary.branch} -> ${summary.target}`]
+      if (summary.commits.length > 0) sections.push(`Commits:\n${summary.commits.join('\n')}`)
+      if (summary.uncommitted.length > 0) sections.push(`Uncommitted (committed when merged):\n${summary.uncommitted.join('\n')}`)
+      if (summary.diff_stat) sections.push(summary.diff_stat)
+      const empty = summary.commits.length === 0 && summary.uncommitted.length === 0
+      const question = empty
+        ? 'There are no changes to merge. Remove the worktree and end the session?'
+        : 'Merge these changes and end the session?'
+      if (window.confirm(`${sections.join('\n\n')}\n\n${question}`)) {
+        await mergeWorktree()
+      }
+    } catch (err) {
+      // A conflicting merge is aborted and the worktree kept for resolving by hand
+      setError(String(err))
+    }
+  }
+
+  const handleRegenerate = async () => {
+    setError(null)
+    try {
+      await regenerateLast()
+    } catch (err) {
+      setError(String(err))
+    }
+  }
+
+  const handleStartEdit = (id: string, content: string) => {
+    setEditingId(id)
+    // Drop the "[N image(s)]" suffix the echo adds; the images are kept
+    setInput(content.replace(/ \[\d+ image\(s\)\]$/, ''))
+    textInputRef.current?.focus()
+  }
+
   const handleCancel = useCallback(async () => {
     try {
       await cancelSession()
@@ -290,7 +497,7 @@ export default function Chat() {
       onDrop={handleDrop}
     >
       {/* Drop Overlay */}
-      {isDragging && (
+      {isDragging && attachmentsSupported && (
         <div className="absolute inset-0 z-50 bg-primary/20 border-4 border-dashed border-primary flex items-center justify-center pointer-events-none">
           <div className="bg-card p-6 rounded-xl shadow-lg text-center">
             <Paperclip className="w-12 h-12 text-primary mx-auto mb-2" />
@@ -320,7 +527,7 @@ export default function Chat() {
       )}
 
       {/* Messages */}
-      <div className="flex-1 overflow-y-auto p-4 space-y-4">
+      <div ref={messagesRef} className="flex-1 overflow-y-auto p-4 space-y-4">
         {messages.length === 0 && !ephemeral && (
           <div className="text-center mt-16">
             <Sparkles className="w-12 h-12 text-primary mx-auto mb-4 opacity-50" />
@@ -328,13 +535,23 @@ export default function Chat() {
             <p className="text-sm text-muted-foreground mt-2">
               Ask me anything to get started.
             </p>
+            <TemplatePicker onStart={handleStartTemplate} />
           </div>
         )}
 
         {messages.map((msg) => (
           <div key={msg.id}>
             {msg.type === 'user' && (
-              <div className="flex justify-end">
+              <div className="flex justify-end items-start gap-2 group">
+                {!status && !modal && (
+                  <button
+                    onClick={() => handleStartEdit(msg.id, msg.content)}
+                    className="mt-2 p-1 rounded text-muted-foreground opacity-0 group-hover:opacity-100 hover:text-foreground transition-opacity"
+                    title="Edit and re-run from here"
+                  >
+                    <Pencil className="w-3.5 h-3.5" />
+                  </button>
+                )}
                 <div className="max-w-[80%] rounded-xl px-4 py-3 bg-primary text-primary-foreground">
                   <pre className="whitespace-pre-wrap font-sans text-sm">{msg.content}</pre>
                 </div>
@@ -358,7 +575,31 @@ export default function Chat() {
             {msg.type === 'tool_call' && msg.formatted && (
               <div className="flex justify-start">
                 <div className="max-w-[80%]">
-                  <ToolCallMessage formatted={msg.formatted} elapsedSecs={msg.elapsedSecs} />
+                  <ToolCallMessage
+                    formatted={msg.formatted}
+                    absolutePath={msg.absolutePath}
+                    elapsedSecs={msg.elapsedSecs}
+                    onCancel={isToolRunning(msg.id) ? () => handleCancelTool(msg.id) : undefined}
+                  />
+                </div>
+              </div>
+            )}
+
+            {/* Only reported: nothing acts on the findings */}
+            {msg.type === 'self_review' && (
+              <div className="flex justify-start">
+                <div className="max-w-[80%] rounded-xl px-4 py-3 border border-warning/40 bg-warning/5 text-sm">
+                  <div className="flex items-center gap-2 font-medium">
+                    <ClipboardCheck className="w-4 h-4 text-warning" />
+                    {msg.findings?.length ? `Self-review found ${msg.findings.length} gap(s)` : 'Self-review: no gaps found'}
+                  </div>
+                  {!!msg.findings?.length && (
+                    <ul className="mt-2 list-disc pl-5 space-y-1">
+                      {msg.findings.map((finding, i) => (
+                        <li key={i}><ClickablePaths text={finding} /></li>
+                      ))}
+                    </ul>
+                  )}
                 </div>
               </div>
             )}
@@ -372,7 +613,24 @@ export default function Chat() {
                     output={msg.content}
                     success={msg.success ?? true}
                     elapsedSecs={msg.elapsedSecs}
+                    absolutePath={msg.absolutePath}
+                    line={msg.line}
+                    onOpenInEditor={handleOpenInEditor}
                   />
+                  {msg.toolName === 'ExitPlanMode' && (() => {
+                    const planName = planNameFromResult(msg.content)
+                    if (!planName) return null
+                    const approved = planStatus?.name === planName && planStatus.status !== 'draft'
+                    return (
+                      <button
+                        onClick={() => handleApprovePlan(planName)}
+                        disabled={approved}
+                        className="mt-2 px-3 py-1 text-xs rounded-lg bg-primary text-primary-foreground hover:opacity-90 disabled:opacity-50"
+                      >
+                        {approved ? `Plan ${planStatus?.status.replace('_', ' ')}` : 'Approve plan'}
+                      </button>
+                    )
+                  })()}
                 </div>
               </div>
             )}
@@ -388,11 +646,50 @@ export default function Chat() {
           </div>
         )}
 
+        {/* Live tail of a long-running tool */}
+        {toolProgress && (
+          <div className="text-xs text-muted-foreground/70 font-mono pl-4 space-y-0.5">
+            <div>{formatToolProgress(toolProgress.elapsedMs, toolProgress.outputBytes)}</div>
+            {toolProgress.tail.map((line, i) => (
+              <div key={i} className="truncate opacity-80">{line}</div>
+            ))}
+          </div>
+        )}
+
+        {/* The running turn uses settings picked for its message */}
+        {turnOverrides && (
+          <div className="text-xs text-muted-foreground pl-2">
+            This turn runs with{' '}
+            {[
+              turnOverrides.model && `model ${turnOverrides.model}`,
+              turnOverrides.approval && `approval ${turnOverrides.approval}`,
+              turnOverrides.tool_profile && `tool profile ${turnOverrides.tool_profile}`,
+            ].filter(Boolean).join(', ')}
+          </div>
+        )}
+
+        {/* A call waiting for a reviewer: the owner can only cancel it */}
+        {delegatedApproval && (
+          <div className="flex items-center gap-2 text-xs text-warning pl-2">
+            <AlertCircle className="w-3.5 h-3.5" />
+            <span title={delegatedApproval.reason}>
+              {delegatedApproval.tool} waits for a reviewer on {delegatedApproval.channel}
+              {' '}(refused at {new Date(delegatedApproval.deadline).toLocaleTimeString()} without an answer)
+            </span>
+            <button
+              onClick={() => handleCancelTool(delegatedApproval.id)}
+              className="ml-auto text-muted-foreground hover:text-foreground"
+            >
+              Cancel
+            </button>
+          </div>
+        )}
+
         <div ref={messagesEndRef} />
       </div>
 
       {/* Status Bar */}
-      {(status || isReady) && (
+      {(status || isReady || suspended) && (
         <div className="h-8 border-t border-border flex items-center px-4 text-xs text-muted-foreground bg-card/30">
           {status ? (
             <div className="flex items-center gap-2">
@@ -407,9 +704,47 @@ export default function Chat() {
               </button>
             </div>
           ) : (
-            <span>Ready</span>
+            <div className="flex items-center gap-2">
+              <span>{suspended ? 'Suspended while idle' : 'Ready'}</span>
+              {lastUserMessageId && !modal && (
+                <button
+                  onClick={handleRegenerate}
+                  className="flex items-center gap-1 px-1.5 py-0.5 rounded hover:bg-muted/50 hover:text-foreground transition-colors"
+                  title="Discard the last reply and generate it again"
+                >
+                  <RotateCcw className="w-3 h-3" />
+                  <span>Regenerate</span>
+                </button>
+              )}
+            </div>
           )}
           <div className="ml-auto flex items-center gap-3">
+            {worktree && (
+              <>
+                <span className="flex items-center gap-1 text-warning" title={`Isolated in the git worktree ${worktree.path}`}>
+                  <GitBranch className="w-3 h-3" />
+                  <span>{worktree.branch}</span>
+                </span>
+                {!status && (
+                  <button
+                    onClick={handleMergeWorktree}
+                    className="flex items-center gap-1 px-1.5 py-0.5 rounded hover:bg-muted/50 hover:text-foreground transition-colors"
+                    title="Review the worktree's changes and merge them into your branch"
+                  >
+                    <GitMerge className="w-3 h-3" />
+                    <span>Merge</span>
+                  </button>
+                )}
+              </>
+            )}
+            <button
+              onClick={handleToggleKeepAlive}
+              className={`flex items-center gap-1 px-1.5 py-0.5 rounded hover:bg-muted/50 transition-colors ${keepAlive ? 'text-foreground' : ''}`}
+              title={keepAlive ? 'Allow this session to be suspended when idle' : 'Never suspend this session when idle'}
+            >
+              <Pin className="w-3 h-3" />
+              <span>{keepAlive ? 'Kept alive' : 'Keep alive'}</span>
+            </button>
             {/* Context usage is now appended to message content by core */}
             {session?.provider && (
               <span>{session.provider.type}</span>
@@ -423,6 +758,21 @@ export default function Chat() {
         onSubmit={handleSubmit}
         className="p-4 border-t border-border bg-card/50"
       >
+        {editingId && (
+          <div className="flex items-center gap-2 mb-2 text-xs text-muted-foreground">
+            <Pencil className="w-3 h-3" />
+            <span>Editing a message: sending discards everything after it</span>
+            <button
+              type="button"
+              onClick={() => { setEditingId(null); setInput('') }}
+              className="ml-1 p-0.5 rounded hover:bg-muted/50 hover:text-foreground"
+              title="Stop editing"
+            >
+              <X className="w-3 h-3" />
+            </button>
+          </div>
+        )}
+
         {/* Image Previews */}
         {pendingImages.length > 0 && (
           <div className="flex gap-2 mb-3 flex-wrap">
@@ -457,7 +807,7 @@ export default function Chat() {
           />
 
           {/* Attachment button */}
-          <Button
+          {attachmentsSupported && <Button
             type="button"
             variant="ghost"
             size="lg"
@@ -467,17 +817,26 @@ export default function Chat() {
             title="Attach images"
           >
             <Paperclip className="w-5 h-5" />
-          </Button>
+          </Button>}
 
-          <input
-            ref={textInputRef}
-            type="text"
-            value={input}
-            onChange={(e) => setInput(e.target.value)}
-            placeholder={modal ? "Waiting for response..." : isDragging ? "Drop images here..." : pendingImages.length > 0 ? "Add a message about these images..." : "Type a message..."}
-            disabled={!!modal}
-            className="flex-1 rounded-xl border border-border bg-background px-4 py-3 text-foreground placeholder-muted-foreground focus:outline-none focus:ring-2 focus:ring-primary/50 disabled:opacity-50 disabled:cursor-not-allowed"
-          />
+          {turnOverridesSupported && !editingId && pendingImages.length === 0 && (
+            <MessageOptions value={messageOverrides} onChange={setMessageOverrides} disabled={!!modal} />
+          )}
+
+          <div className="relative flex-1 flex">
+            <MentionSuggestions suggestions={mentions.suggestions} selected={mentions.selected} onPick={mentions.pick} />
+            <input
+              ref={textInputRef}
+              type="text"
+              value={input}
+              onChange={(e) => { setInput(e.target.value); mentions.trackCursor(e) }}
+              onSelect={mentions.trackCursor}
+              onKeyDown={mentions.onKeyDown}
+              placeholder={modal ? "Waiting for response..." : isDragging ? "Drop images here..." : pendingImages.length > 0 ? "Add a message about these images..." : "Type a message or @ to mention a file..."}
+              disabled={!!modal}
+              className="flex-1 rounded-xl border border-border bg-background px-4 py-3 text-foreground placeholder-muted-foreground focus:outline-none focus:ring-2 focus:ring-primary/50 disabled:opacity-50 disabled:cursor-not-allowed"
+            />
+          </div>
           <Button
             type="submit"
             disabled={(!input.trim() && pendingImages.length === 0) || !!modal}
@@ -489,6 +848,14 @@ export default function Chat() {
         </div>
       </form>
 
+      <RecipePanel recipe={recipe} onDismiss={() => setDismissedRecipe(session?.recipe)} />
+
+      <TodoPanel
+        todos={todos}
+        collapsed={todosCollapsed}
+        onToggleCollapse={() => setTodosCollapsed(c => !c)}
+      />
+
       {/* Modal Overlay */}
       {modal?.type === 'approval' && (
         <ApprovalModal
@@ -496,10 +863,14 @@ export default function Chat() {
           name={modal.name}
           arguments={modal.arguments}
           description={modal.description}
+          explainRequested={modal.explainRequested}
+          explanation={modal.explanation}
+          securityWarning={modal.securityWarning}
           onApprove={handleApprove}
           onReject={handleReject}
           onApproveForSession={handleApproveForSession}
           onApproveAll={handleApproveAll}
+          onExplain={handleExplain}
         />
       )}
 
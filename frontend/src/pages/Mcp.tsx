@@ -1,6 +1,6 @@
 import { useState, useEffect } from 'react'
 import { invoke } from '@tauri-apps/api/core'
-import { Server, Plus, Trash2, RefreshCw, Wrench, Globe, Terminal } from 'lucide-react'
+import { Server, Plus, Trash2, RefreshCw, Wrench, Globe, Terminal, Shield } from 'lucide-react'
 import { Card, CardContent, CardDescription, CardHeader, CardTitle } from '../components/ui/card'
 import { Button } from '../components/ui/button'
 import { Input } from '../components/ui/input'
@@ -12,6 +12,7 @@ interface McpServer {
   enabled: boolean
   status: 'stopped' | 'starting' | 'running' | 'failed'
   tool_count: number
+  sandboxed: boolean
   error?: string
 }
 
@@ -225,6 +226,12 @@ export default function McpPage() {
                                 {server.tool_count} tools
                               </Badge>
                             )}
+                            {server.sandboxed && (
+                              <Badge variant="secondary">
+                                <Shield className="w-3 h-3 mr-1" />
+                                Sandboxed
+                              </Badge>
+                            )}
                           </div>
                           <p className="text-sm text-muted-foreground mt-0.5 font-mono">
                             {server.command.length > 60
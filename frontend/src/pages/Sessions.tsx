@@ -1,5 +1,6 @@
 import { useState, useEffect, useCallback } from 'react'
 import { invoke } from '@tauri-apps/api/core'
+import { open } from '@tauri-apps/plugin-dialog'
 import {
   History,
   Trash2,
@@ -11,6 +12,10 @@ import {
   AlertTriangle,
   Play,
   Clock,
+  Pin,
+  PinOff,
+  Scissors,
+  Import,
 } from 'lucide-react'
 import { Button } from '../components/ui/button'
 
@@ -22,6 +27,46 @@ interface SavedSession {
   created_at: string
   updated_at: string
   file_size: number
+  /** Restored from an autosave: the session ended without being saved */
+  recovered?: boolean
+  /** Kept when saved sessions are pruned */
+  pinned?: boolean
+  /** Session template the session was started from */
+  template?: string | null
+}
+
+interface PrunedSession {
+  id: string
+  name: string
+  message_count: number
+  updated_at: string
+  reason: 'age' | 'count'
+}
+
+interface PruneReport {
+  dry_run: boolean
+  pruned: PrunedSession[]
+  kept: number
+  pinned: number
+  errors: string[]
+}
+
+interface FileImport {
+  path: string
+  outcome: 'imported' | 'already_imported' | 'failed'
+  session_id: string | null
+  name: string
+  message_count: number
+  updated_at: string | null
+  /** Entries left out, by kind, with their line numbers */
+  skipped: string[]
+  error: string | null
+}
+
+interface ImportReport {
+  source: 'claude-code'
+  dry_run: boolean
+  files: FileImport[]
 }
 
 interface SessionsDirectoryInfo {
@@ -37,6 +82,9 @@ export default function Sessions() {
   const [error, setError] = useState<string | null>(null)
   const [deleteConfirm, setDeleteConfirm] = useState<string | null>(null)
   const [showDeleteAllConfirm, setShowDeleteAllConfirm] = useState(false)
+  const [pruneReport, setPruneReport] = useState<PruneReport | null>(null)
+  const [importPaths, setImportPaths] = useState<string[]>([])
+  const [importReport, setImportReport] = useState<ImportReport | null>(null)
 
   const loadSessions = useCallback(async () => {
     setIsLoading(true)
@@ -108,6 +156,44 @@ export default function Sessions() {
     }
   }
 
+  const handleTogglePin = async (session: SavedSession) => {
+    try {
+      await invoke('set_session_pinned', { sessionId: session.id, pinned: !session.pinned })
+      loadSessions()
+    } catch (err) {
+      setError(String(err))
+    }
+  }
+
+  // Applies the [sessions] retention limits from the config; a dry run only reports
+  const handlePrune = async (dryRun: boolean) => {
+    try {
+      const report = await invoke<PruneReport>('prune_saved_sessions', { dryRun })
+      setPruneReport(report)
+      if (!dryRun) loadSessions()
+    } catch (err) {
+      setError(String(err))
+    }
+  }
+
+  // Imports Claude Code sessions from a folder (such as ~/.claude/projects); a dry run only reports
+  const handleImport = async (dryRun: boolean) => {
+    try {
+      let paths = importPaths
+      if (dryRun) {
+        const folder = await open({ directory: true, title: 'Import Claude Code sessions' })
+        if (typeof folder !== 'string') return
+        paths = [folder]
+        setImportPaths(paths)
+      }
+      const report = await invoke<ImportReport>('import_sessions', { source: 'claude-code', paths, dryRun })
+      setImportReport(report)
+      if (!dryRun) loadSessions()
+    } catch (err) {
+      setError(String(err))
+    }
+  }
+
   const handleOpenFolder = async () => {
     try {
       await invoke('open_sessions_folder')
@@ -161,6 +247,22 @@ export default function Sessions() {
           >
             <RefreshCw className={`w-4 h-4 ${isLoading ? 'animate-spin' : ''}`} />
           </Button>
+          <Button
+            onClick={() => handlePrune(true)}
+            variant="ghost"
+            size="icon"
+            title="Prune sessions past the retention limits"
+          >
+            <Scissors className="w-4 h-4" />
+          </Button>
+          <Button
+            onClick={() => handleImport(true)}
+            variant="ghost"
+            size="icon"
+            title="Import Claude Code sessions"
+          >
+            <Import className="w-4 h-4" />
+          </Button>
           <Button
             onClick={handleOpenFolder}
             variant="ghost"
@@ -180,6 +282,77 @@ export default function Sessions() {
         </div>
       )}
 
+      {/* Prune Report */}
+      {pruneReport && (
+        <div className="bg-card/50 border-b border-border px-4 py-3 text-sm">
+          <div className="flex items-center justify-between">
+            <span className="text-foreground">
+              {pruneReport.dry_run ? 'Would delete' : 'Deleted'} {pruneReport.pruned.length} session(s),
+              keeping {pruneReport.kept} ({pruneReport.pinned} pinned)
+            </span>
+            <div className="flex items-center gap-2">
+              {pruneReport.dry_run && pruneReport.pruned.length > 0 && (
+                <Button onClick={() => handlePrune(false)} variant="destructive" size="sm">
+                  Delete
+                </Button>
+              )}
+              <Button onClick={() => setPruneReport(null)} variant="outline" size="sm">
+                Close
+              </Button>
+            </div>
+          </div>
+          {pruneReport.pruned.length > 0 && (
+            <ul className="mt-2 space-y-0.5 text-xs text-muted-foreground max-h-40 overflow-y-auto">
+              {pruneReport.pruned.map((pruned) => (
+                <li key={pruned.id}>
+                  {pruned.name || pruned.id} - {pruned.message_count} messages, {formatRelativeTime(pruned.updated_at)}
+                  {' '}({pruned.reason === 'age' ? 'too old' : 'over the count limit'})
+                </li>
+              ))}
+            </ul>
+          )}
+          {pruneReport.errors.map((error) => (
+            <div key={error} className="text-xs text-error mt-1">{error}</div>
+          ))}
+        </div>
+      )}
+
+      {/* Import Report */}
+      {importReport && (
+        <div className="bg-card/50 border-b border-border px-4 py-3 text-sm">
+          <div className="flex items-center justify-between">
+            <span className="text-foreground">
+              {importReport.dry_run ? 'Would import' : 'Imported'}{' '}
+              {importReport.files.filter((f) => f.outcome === 'imported').length} Claude Code session(s)
+              ({importReport.files.filter((f) => f.outcome === 'already_imported').length} already imported,{' '}
+              {importReport.files.filter((f) => f.outcome === 'failed').length} failed)
+            </span>
+            <div className="flex items-center gap-2">
+              {importReport.dry_run && importReport.files.some((f) => f.outcome === 'imported') && (
+                <Button onClick={() => handleImport(false)} size="sm">
+                  Import
+                </Button>
+              )}
+              <Button onClick={() => setImportReport(null)} variant="outline" size="sm">
+                Close
+              </Button>
+            </div>
+          </div>
+          {importReport.files.length > 0 && (
+            <ul className="mt-2 space-y-0.5 text-xs text-muted-foreground max-h-40 overflow-y-auto">
+              {importReport.files.map((file) => (
+                <li key={file.path} className={file.outcome === 'failed' ? 'text-error' : undefined}>
+                  {file.outcome === 'failed'
+                    ? `${file.path}: ${file.error}`
+                    : `${file.name} - ${file.message_count} messages${file.outcome === 'already_imported' ? ' (already imported)' : ''}`}
+                  {file.skipped.length > 0 && <span> · skipped {file.skipped.join('; ')}</span>}
+                </li>
+              ))}
+            </ul>
+          )}
+        </div>
+      )}
+
       {/* Directory Info */}
       {directoryInfo && (
         <div className="bg-card/50 border-b border-border px-4 py-3">
@@ -292,6 +465,30 @@ export default function Sessions() {
                         <span className="px-2 py-0.5 rounded-full bg-secondary text-xs font-medium">
                           {session.provider_type}
                         </span>
+                        {session.template && (
+                          <span
+                            className="px-2 py-0.5 rounded-full bg-secondary text-xs font-medium"
+                            title="Session template the session was started from"
+                          >
+                            {session.template}
+                          </span>
+                        )}
+                        {session.pinned && (
+                          <span
+                            className="px-2 py-0.5 rounded-full bg-primary/10 text-primary text-xs font-medium"
+                            title="Pinned sessions are kept when saved sessions are pruned"
+                          >
+                            Pinned
+                          </span>
+                        )}
+                        {session.recovered && (
+                          <span
+                            className="px-2 py-0.5 rounded-full bg-warning/10 text-warning text-xs font-medium"
+                            title="This session ended without being saved; it was restored from its last autosave"
+                          >
+                            Recovered
+                          </span>
+                        )}
                       </div>
                       <div className="text-xs text-muted-foreground/70 mt-1.5 flex items-center gap-1">
                         <Calendar className="w-3 h-3" />
@@ -308,6 +505,14 @@ export default function Sessions() {
                         <Play className="w-4 h-4" />
                         Load
                       </Button>
+                      <Button
+                        onClick={() => handleTogglePin(session)}
+                        variant="ghost"
+                        size="icon"
+                        title={session.pinned ? 'Unpin' : 'Pin to keep when pruning'}
+                      >
+                        {session.pinned ? <PinOff className="w-4 h-4" /> : <Pin className="w-4 h-4" />}
+                      </Button>
                       {deleteConfirm === session.id ? (
                         <div className="flex items-center gap-1">
                           <Button
@@ -1,11 +1,14 @@
 import { useState, useEffect, useCallback } from 'react'
 import { invoke } from '@tauri-apps/api/core'
-import { Settings as SettingsIcon, Save, RefreshCw, Sparkles, ArrowUpCircle, FolderOpen, FileCode } from 'lucide-react'
+import { open } from '@tauri-apps/plugin-dialog'
+import { Settings as SettingsIcon, Save, RefreshCw, Sparkles, ArrowUpCircle, FolderOpen, FileCode, Folders, Plus, X } from 'lucide-react'
 import { Card, CardContent, CardDescription, CardHeader, CardTitle } from '../components/ui/card'
 import { Button } from '../components/ui/button'
 import { Input } from '../components/ui/input'
 import { Select } from '../components/ui/select'
 import UpdateChecker from '../components/UpdateChecker'
+import { useSession } from '../context/SessionContext'
+import type { GenerationParams } from '../bindings'
 
 interface Settings {
   provider: {
@@ -13,6 +16,8 @@ interface Settings {
     api_key: string | null
     model: string | null
     base_url: string | null
+    /** Where the API key is saved: 'keyring' is the OS keychain */
+    api_key_storage?: 'config' | 'keyring'
   }
   approval: {
     auto_approve_level: string
@@ -23,6 +28,11 @@ interface Settings {
     font_size: number
     show_tool_calls: boolean
   }
+  /** Tool profile new sessions start with, and the profiles to choose from */
+  tools?: {
+    profile: string
+    available: string[]
+  }
 }
 
 interface ModelInfo {
@@ -31,6 +41,15 @@ interface ModelInfo {
   description: string
 }
 
+/** A provider's circuit breaker, from get_provider_status */
+interface ProviderStatus {
+  provider_id: string
+  state: 'closed' | 'open' | 'half_open'
+  recent_failures: number
+  open_until: string | null
+  last_error: string | null
+}
+
 export default function SettingsPage() {
   const [settings, setSettings] = useState<Settings | null>(null)
   const [loading, setLoading] = useState(false)
@@ -38,6 +57,8 @@ export default function SettingsPage() {
   const [message, setMessage] = useState<{ type: 'success' | 'error'; text: string } | null>(null)
   const [configPath, setConfigPath] = useState<string | null>(null)
   const [availableModels, setAvailableModels] = useState<ModelInfo[]>([])
+  const [providerStatus, setProviderStatus] = useState<ProviderStatus[]>([])
+  const [keyringAvailable, setKeyringAvailable] = useState(false)
 
   const fetchModels = useCallback(async (providerType: string) => {
     try {
@@ -79,6 +100,14 @@ export default function SettingsPage() {
     }
   }
 
+  const loadKeyringAvailable = async () => {
+    try {
+      setKeyringAvailable(await invoke<boolean>('keyring_available'))
+    } catch (err) {
+      console.error('Failed to check for an OS keychain:', err)
+    }
+  }
+
   const loadConfigPath = async () => {
     try {
       const path = await invoke<string>('get_config_path')
@@ -96,9 +125,19 @@ export default function SettingsPage() {
     }
   }
 
+  const loadProviderStatus = async () => {
+    try {
+      setProviderStatus(await invoke<ProviderStatus[]>('get_provider_status'))
+    } catch (err) {
+      console.error('Failed to load provider status:', err)
+    }
+  }
+
   useEffect(() => {
     loadSettings()
     loadConfigPath()
+    loadKeyringAvailable()
+    loadProviderStatus()
   }, [])
 
   // Fetch models when settings load or provider changes
@@ -172,7 +211,7 @@ export default function SettingsPage() {
                     const newProvider = e.target.value
                     // Fetch saved config and models for the new provider
                     const [savedConfig, models] = await Promise.all([
-                      invoke<{ api_key: string | null; model: string | null; base_url: string | null }>('get_provider_config', { providerType: newProvider }),
+                      invoke<Settings['provider']>('get_provider_config', { providerType: newProvider }),
                       invoke<ModelInfo[]>('fetch_provider_models', { providerType: newProvider }),
                     ])
                     setAvailableModels(models)
@@ -186,6 +225,7 @@ export default function SettingsPage() {
                         model: savedConfig.model || (models.length > 0 ? models[0].id : null),
                         // Use saved base_url if available
                         base_url: savedConfig.base_url,
+                        api_key_storage: savedConfig.api_key_storage,
                       },
                     })
                   }}
@@ -240,6 +280,25 @@ export default function SettingsPage() {
                   }
                   placeholder="Enter your API key"
                 />
+                <label className="mt-2 flex items-center gap-2 text-sm text-foreground">
+                  <input
+                    type="checkbox"
+                    checked={settings.provider.api_key_storage === 'keyring'}
+                    disabled={!keyringAvailable}
+                    onChange={(e) =>
+                      setSettings({
+                        ...settings,
+                        provider: { ...settings.provider, api_key_storage: e.target.checked ? 'keyring' : 'config' },
+                      })
+                    }
+                  />
+                  Keep the key in the OS keychain instead of the config file
+                </label>
+                {!keyringAvailable && (
+                  <p className="mt-1.5 text-xs text-muted-foreground">
+                    No OS keychain is available; the key is saved in the config file.
+                  </p>
+                )}
               </div>
 
               <div>
@@ -259,6 +318,23 @@ export default function SettingsPage() {
                   Override the default API endpoint with a proxy URL.
                 </p>
               </div>
+
+              {providerStatus.some(p => p.state !== 'closed') && (
+                <div className="rounded-lg border border-warning/30 bg-warning/10 p-3 text-sm space-y-1">
+                  {providerStatus.filter(p => p.state !== 'closed').map(p => (
+                    <p key={p.provider_id} className="text-foreground">
+                      <strong>{p.provider_id}</strong>{' '}
+                      {p.state === 'open' && p.open_until
+                        ? `unavailable until ${new Date(p.open_until).toLocaleTimeString([], { hour: '2-digit', minute: '2-digit' })}`
+                        : 'recovering'}
+                      {p.last_error && <span className="text-muted-foreground"> - {p.last_error}</span>}
+                    </p>
+                  ))}
+                  <p className="text-xs text-muted-foreground">
+                    Switch to another provider above until then.
+                  </p>
+                </div>
+              )}
             </CardContent>
           </Card>
 
@@ -313,6 +389,42 @@ export default function SettingsPage() {
             </CardContent>
           </Card>
 
+          {/* Tool profile for new sessions */}
+          {settings.tools && (
+            <Card>
+              <CardHeader>
+                <CardTitle>Tools</CardTitle>
+                <CardDescription>
+                  Tools offered to the model in new sessions; fewer tools means fewer tokens on every request
+                </CardDescription>
+              </CardHeader>
+              <CardContent className="space-y-4">
+                <div>
+                  <label className="text-sm font-medium mb-1.5 block text-foreground">Tool Profile</label>
+                  <Select
+                    value={settings.tools.profile}
+                    onChange={(e) =>
+                      setSettings({
+                        ...settings,
+                        tools: { ...settings.tools!, profile: e.target.value },
+                      })
+                    }
+                  >
+                    {settings.tools.available.map((name) => (
+                      <option key={name} value={name}>{name}</option>
+                    ))}
+                  </Select>
+                </div>
+              </CardContent>
+            </Card>
+          )}
+
+          {/* Generation parameters for the active session */}
+          <GenerationSettingsCard />
+
+          {/* Workspace roots for new sessions */}
+          <WorkspaceRootsCard />
+
           {/* UI Settings */}
           <Card>
             <CardHeader>
@@ -419,3 +531,151 @@ export default function SettingsPage() {
     </div>
   )
 }
+
+/** Parse an optional number input; empty means provider default */
+function optionalNumber(value: string): number | undefined {
+  const n = parseFloat(value)
+  return value.trim() === '' || Number.isNaN(n) ? undefined : n
+}
+
+function GenerationSettingsCard() {
+  const { activeSessionId, getActiveSession, setGenerationParams } = useSession()
+  const current = getActiveSession()?.generationParams
+  const [temperature, setTemperature] = useState('')
+  const [maxTokens, setMaxTokens] = useState('')
+  const [topP, setTopP] = useState('')
+  const [stop, setStop] = useState('')
+  const [error, setError] = useState<string | null>(null)
+
+  // Show the values the session last confirmed
+  useEffect(() => {
+    setTemperature(current?.temperature?.toString() ?? '')
+    setMaxTokens(current?.max_tokens?.toString() ?? '')
+    setTopP(current?.top_p?.toString() ?? '')
+    setStop(current?.stop_sequences?.join(', ') ?? '')
+  }, [current])
+
+  const apply = async () => {
+    const maxTokensValue = optionalNumber(maxTokens)
+    const params: GenerationParams = {
+      temperature: optionalNumber(temperature),
+      max_tokens: maxTokensValue === undefined ? undefined : Math.round(maxTokensValue),
+      top_p: optionalNumber(topP),
+      stop_sequences: stop.split(',').map(s => s.trim()).filter(Boolean),
+    }
+    try {
+      setError(null)
+      await setGenerationParams(params)
+    } catch (err) {
+      setError(String(err))
+    }
+  }
+
+  return (
+    <Card>
+      <CardHeader>
+        <CardTitle>Generation</CardTitle>
+        <CardDescription>
+          Sampling parameters for the current session. Leave a field empty to use the provider default.
+        </CardDescription>
+      </CardHeader>
+      <CardContent className="space-y-4">
+        <div className="grid grid-cols-3 gap-4">
+          <div>
+            <label className="text-sm font-medium mb-1.5 block text-foreground">Temperature (0-2)</label>
+            <Input type="number" step="0.1" min="0" max="2" value={temperature} onChange={(e) => setTemperature(e.target.value)} />
+          </div>
+          <div>
+            <label className="text-sm font-medium mb-1.5 block text-foreground">Max tokens</label>
+            <Input type="number" min="1" value={maxTokens} onChange={(e) => setMaxTokens(e.target.value)} />
+          </div>
+          <div>
+            <label className="text-sm font-medium mb-1.5 block text-foreground">Top P (0-1)</label>
+            <Input type="number" step="0.05" min="0" max="1" value={topP} onChange={(e) => setTopP(e.target.value)} />
+          </div>
+        </div>
+        <div>
+          <label className="text-sm font-medium mb-1.5 block text-foreground">Stop sequences</label>
+          <Input
+            placeholder="Comma-separated, e.g. ###, END"
+            value={stop}
+            onChange={(e) => setStop(e.target.value)}
+          />
+        </div>
+        {error && <p className="text-sm text-error">{error}</p>}
+        <Button onClick={apply} disabled={!activeSessionId}>
+          Apply to session
+        </Button>
+      </CardContent>
+    </Card>
+  )
+}
+
+/** The workspace and any secondary folders new sessions' file tools may use */
+function WorkspaceRootsCard() {
+  const [roots, setRoots] = useState<string[]>([])
+  const [error, setError] = useState<string | null>(null)
+
+  useEffect(() => {
+    invoke<string[]>('get_workspace_roots')
+      .then(setRoots)
+      .catch((err) => setError(String(err)))
+  }, [])
+
+  const saveAdditional = async (additional: string[]) => {
+    try {
+      setError(null)
+      setRoots(await invoke<string[]>('set_additional_roots', { roots: additional }))
+    } catch (err) {
+      setError(String(err))
+    }
+  }
+
+  const addFolder = async () => {
+    const folder = await open({ directory: true, title: 'Add a workspace folder' })
+    if (typeof folder === 'string') {
+      await saveAdditional([...roots.slice(1), folder])
+    }
+  }
+
+  return (
+    <Card>
+      <CardHeader>
+        <CardTitle className="flex items-center gap-2">
+          <Folders className="w-5 h-5 text-primary" />
+          Workspace
+        </CardTitle>
+        <CardDescription>
+          Folders the file tools of new sessions can use. Relative paths resolve in the workspace;
+          Glob and Grep search another folder by its name, or every folder with "all".
+        </CardDescription>
+      </CardHeader>
+      <CardContent className="space-y-2">
+        {roots.map((root, i) => (
+          <div key={root} className="flex items-center gap-2">
+            <code className="flex-1 px-3 py-2 text-sm bg-secondary rounded-lg text-muted-foreground overflow-x-auto">
+              {root}
+            </code>
+            {i === 0 ? (
+              <span className="text-xs text-muted-foreground w-9 text-center">main</span>
+            ) : (
+              <Button
+                variant="outline"
+                size="sm"
+                title="Remove folder"
+                onClick={() => saveAdditional(roots.slice(1).filter((r) => r !== root))}
+              >
+                <X className="w-4 h-4" />
+              </Button>
+            )}
+          </div>
+        ))}
+        {error && <p className="text-sm text-error">{error}</p>}
+        <Button variant="outline" onClick={addFolder}>
+          <Plus className="w-4 h-4" />
+          Add folder
+        </Button>
+      </CardContent>
+    </Card>
+  )
+}